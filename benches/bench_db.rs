@@ -2,6 +2,7 @@
 
 use criterion::{criterion_group, criterion_main, Criterion};
 use my_dex::storage::dex_db::DexDB; // passe den Pfad an, falls n�tig
+use my_dex::storage::db_layer::DexDB as StructDB;
 
 fn bench_db_put(c: &mut Criterion) {
     let db = DexDB::open_with_retries("tmp_db", 3, 1).expect("DB sollte ge�ffnet werden");
@@ -12,5 +13,27 @@ fn bench_db_put(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, bench_db_put);
+/// Vergleicht 100 einzelne `load_struct`-Aufrufe mit einem `load_structs_batch`.
+fn bench_wallet_loads(c: &mut Criterion) {
+    let db = StructDB::open("tmp_db_wallets").expect("DB sollte geöffnet werden");
+    let keys: Vec<String> = (0..100).map(|i| format!("wallets/bench_{}", i)).collect();
+    for (i, key) in keys.iter().enumerate() {
+        db.store_struct(key, &(i as f64, i as f64 * 2.0)).expect("Store sollte funktionieren");
+    }
+
+    c.bench_function("100 wallets sequential", |b| {
+        b.iter(|| {
+            for key in &keys {
+                let _: Option<(f64, f64)> = db.load_struct(key).expect("Load sollte funktionieren");
+            }
+        })
+    });
+    c.bench_function("100 wallets batched", |b| {
+        b.iter(|| {
+            let _: Vec<Option<(f64, f64)>> = db.load_structs_batch(&keys).expect("Batch sollte funktionieren");
+        })
+    });
+}
+
+criterion_group!(benches, bench_db_put, bench_wallet_loads);
 criterion_main!(benches);
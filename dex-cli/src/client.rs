@@ -0,0 +1,302 @@
+// dex-cli/src/client.rs
+//
+// Dünner HTTP-Client für die REST-API eines laufenden Nodes
+// (siehe my_dex/src/rest_api.rs). Alle Antworten kommen als
+// `ApiResponse { success, message, data }` zurück.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, Result};
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub message: Option<String>,
+    pub data: Option<T>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrderRequest {
+    pub user_id: String,
+    pub coin_to_sell: String,
+    pub coin_to_buy: String,
+    pub amount: f64,
+    pub price: f64,
+    pub side: Side,
+    /// Streng monoton je User; der Node lehnt Wiederholungen ab.
+    pub nonce: u64,
+}
+
+impl OrderRequest {
+    /// Baut den Request aus Markt "BASE/QUOTE" und Seite.
+    pub fn for_market(user_id: &str, market: &str, side: Side, amount: f64, price: f64) -> Result<Self> {
+        let (base, quote) = market
+            .split_once('/')
+            .ok_or_else(|| anyhow!("Markt muss das Format BASE/QUOTE haben, z.B. BTC/USDT"))?;
+        let (coin_to_sell, coin_to_buy) = match side {
+            Side::Sell => (base, quote),
+            Side::Buy => (quote, base),
+        };
+        Ok(Self {
+            user_id: user_id.to_string(),
+            coin_to_sell: coin_to_sell.to_string(),
+            coin_to_buy: coin_to_buy.to_string(),
+            amount,
+            price,
+            side,
+            nonce: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_micros() as u64)
+                .unwrap_or(0),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct PlacedOrder {
+    pub order_id: String,
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderStatusInfo {
+    pub order_id: String,
+    pub user_id: String,
+    pub market: String,
+    pub side: Side,
+    pub amount: f64,
+    pub filled: f64,
+    pub price: f64,
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderBookSnapshot {
+    pub market: String,
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+}
+
+/// Antwort von `/api/state_hash`: hex-Merkle-Root je Shard-ID, die der
+/// ShardManager des Nodes hält.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct StateDigest {
+    pub node_id: String,
+    pub shards: BTreeMap<u32, String>,
+    pub root: String,
+}
+
+/// Shards, deren Root zwischen `a` und `b` abweicht oder die nur einer der
+/// beiden hält, aufsteigend.
+pub fn diverging_shards(a: &StateDigest, b: &StateDigest) -> Vec<u32> {
+    let ids: BTreeSet<u32> = a.shards.keys().chain(b.shards.keys()).copied().collect();
+    ids.into_iter().filter(|id| a.shards.get(id) != b.shards.get(id)).collect()
+}
+
+/// Holt die Digests zweier Nodes und vergleicht sie.
+pub async fn compare_state(a: &NodeClient, b: &NodeClient) -> Result<(StateDigest, StateDigest, Vec<u32>)> {
+    let (da, db) = (a.state_hash().await?, b.state_hash().await?);
+    let diverging = diverging_shards(&da, &db);
+    Ok((da, db, diverging))
+}
+
+pub struct NodeClient {
+    http: Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl NodeClient {
+    pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            http: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn authed(&self, req: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(key) => req.bearer_auth(key),
+            None => req,
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(&self, req: RequestBuilder) -> Result<T> {
+        let resp = req.send().await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        let parsed: ApiResponse<T> = serde_json::from_str(&body)
+            .map_err(|_| anyhow!("Unerwartete Antwort vom Node ({}): {}", status, body))?;
+        match (parsed.success, parsed.data) {
+            (true, Some(data)) => Ok(data),
+            _ => Err(anyhow!(
+                "Node meldet Fehler ({}): {}",
+                status,
+                parsed.message.unwrap_or_else(|| "ohne Meldung".into())
+            )),
+        }
+    }
+
+    pub async fn add_order(&self, order: &OrderRequest) -> Result<PlacedOrder> {
+        let req = self.authed(self.http.post(self.url("/api/place_order")).json(order));
+        self.send(req).await
+    }
+
+    pub async fn cancel_order(&self, user_id: &str, order_id: &str) -> Result<PlacedOrder> {
+        let body = serde_json::json!({ "user_id": user_id, "order_id": order_id });
+        let req = self.authed(self.http.post(self.url("/api/cancel_order")).json(&body));
+        self.send(req).await
+    }
+
+    pub async fn order_status(&self, order_id: &str) -> Result<OrderStatusInfo> {
+        let req = self.authed(self.http.get(self.url(&format!("/api/order/{}", order_id))));
+        self.send(req).await
+    }
+
+    pub async fn order_book(&self, market: &str) -> Result<OrderBookSnapshot> {
+        let req = self.authed(self.http.get(self.url("/api/book")).query(&[("market", market)]));
+        self.send(req).await
+    }
+
+    pub async fn state_hash(&self) -> Result<StateDigest> {
+        let req = self.authed(self.http.get(self.url("/api/state_hash")));
+        self.send(req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::{Path, Query},
+        http::{HeaderMap, StatusCode},
+        routing::{get, post},
+        Json, Router,
+    };
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::net::SocketAddr;
+
+    const KEY: &str = "test-key";
+
+    fn authorized(headers: &HeaderMap) -> bool {
+        headers.get("authorization").and_then(|v| v.to_str().ok()) == Some(&format!("Bearer {}", KEY))
+    }
+
+    /// Nachbau der Node-Routen mit festen Antworten im ApiResponse-Format.
+    async fn spawn_node() -> String {
+        let app = Router::new()
+            .route(
+                "/api/place_order",
+                post(|headers: HeaderMap, Json(body): Json<Value>| async move {
+                    if !authorized(&headers) {
+                        return (StatusCode::UNAUTHORIZED, Json(json!({"success": false, "message": "unauthorized", "data": null})));
+                    }
+                    assert_eq!(body["coin_to_sell"], "USDT");
+                    assert_eq!(body["side"], "buy");
+                    (StatusCode::OK, Json(json!({"success": true, "message": null, "data": {"order_id": "USDT_BTC_abc", "status": "open"}})))
+                }),
+            )
+            .route(
+                "/api/cancel_order",
+                post(|Json(body): Json<Value>| async move {
+                    Json(json!({"success": true, "message": null, "data": {"order_id": body["order_id"], "status": "cancelled"}}))
+                }),
+            )
+            .route(
+                "/api/order/:id",
+                get(|Path(id): Path<String>| async move {
+                    Json(json!({"success": true, "message": null, "data": {
+                        "order_id": id, "user_id": "alice", "market": "BTC/USDT", "side": "buy",
+                        "amount": 1.0, "filled": 0.25, "price": 100.0, "status": "open"
+                    }}))
+                }),
+            )
+            .route(
+                "/api/book",
+                get(|Query(q): Query<HashMap<String, String>>| async move {
+                    Json(json!({"success": true, "message": null, "data": {
+                        "market": q["market"], "bids": [[100.0, 1.0]], "asks": [[101.0, 2.0]]
+                    }}))
+                }),
+            );
+        serve(app)
+    }
+
+    /// Node, der unter `/api/state_hash` den Digest `digest` liefert.
+    fn spawn_state_node(digest: Value) -> String {
+        let app = Router::new().route(
+            "/api/state_hash",
+            get(move || async move { Json(json!({"success": true, "message": null, "data": digest})) }),
+        );
+        serve(app)
+    }
+
+    fn serve(app: Router) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr: SocketAddr = listener.local_addr().unwrap();
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_cli_client_against_node() {
+        let url = spawn_node().await;
+        let client = NodeClient::new(&url, Some(KEY.into()));
+
+        let order = OrderRequest::for_market("alice", "BTC/USDT", Side::Buy, 1.0, 100.0).unwrap();
+        let placed = client.add_order(&order).await.unwrap();
+        assert_eq!(placed, PlacedOrder { order_id: "USDT_BTC_abc".into(), status: "open".into() });
+
+        let status = client.order_status(&placed.order_id).await.unwrap();
+        assert_eq!(status.filled, 0.25);
+        assert_eq!(status.side, Side::Buy);
+
+        let book = client.order_book("BTC/USDT").await.unwrap();
+        assert_eq!(book.market, "BTC/USDT");
+        assert_eq!(book.asks, vec![(101.0, 2.0)]);
+
+        let cancelled = client.cancel_order("alice", &placed.order_id).await.unwrap();
+        assert_eq!(cancelled.status, "cancelled");
+    }
+
+    #[tokio::test]
+    async fn test_missing_api_key_surfaces_error() {
+        let url = spawn_node().await;
+        let client = NodeClient::new(&url, None);
+        let order = OrderRequest::for_market("alice", "BTC/USDT", Side::Buy, 1.0, 100.0).unwrap();
+        let err = client.add_order(&order).await.unwrap_err();
+        assert!(err.to_string().contains("401"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_compare_state_reports_first_differing_shard() {
+        let node_a = spawn_state_node(json!({"node_id": "a", "shards": {"0": "aa", "3": "bb", "7": "cc"}, "root": "ff"}));
+        let node_b = spawn_state_node(json!({"node_id": "b", "shards": {"0": "aa", "3": "00", "9": "dd"}, "root": "ee"}));
+        let node_c = spawn_state_node(json!({"node_id": "c", "shards": {"0": "aa", "3": "bb", "7": "cc"}, "root": "ff"}));
+        let client = |url: &str| NodeClient::new(url, None);
+
+        let (a, b, diverging) = compare_state(&client(&node_a), &client(&node_b)).await.unwrap();
+        assert_eq!((a.node_id.as_str(), b.node_id.as_str()), ("a", "b"));
+        // Shard-IDs sind die des ShardManagers, nicht fortlaufend
+        assert_eq!(diverging, vec![3, 7, 9]);
+
+        let (_, _, diverging) = compare_state(&client(&node_a), &client(&node_c)).await.unwrap();
+        assert!(diverging.is_empty());
+    }
+}
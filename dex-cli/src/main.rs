@@ -1,40 +1,108 @@
 // dex-cli/src/main.rs
 use clap::{Parser, Subcommand};
 use anyhow::Result;
-use dex_core::{Order, Asset};
+
+mod client;
+use client::{compare_state, NodeClient, OrderRequest, Side};
 
 #[derive(Parser)]
 #[command(name="dex-cli",version="0.1")]
 struct Cli {
+    /// REST-Adresse des Nodes
+    #[arg(long, global = true, env = "DEX_NODE_URL", default_value = "http://127.0.0.1:8080")]
+    node_url: String,
+
+    /// API-Token für zustandsändernde Aufrufe (Bearer)
+    #[arg(long, global = true, env = "DEX_API_KEY")]
+    api_key: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Neue Limit-Order platzieren, z.B. `add alice BTC/USDT buy 0.5 30000`
     Add {
-        order_id: String,
         user_id: String,
+        market: String,
+        #[arg(value_enum)]
+        side: Side,
         amount: f64,
         price: f64,
     },
+    /// Offene Order stornieren
     Remove {
         order_id: String,
+        #[arg(long)]
+        user_id: String,
+    },
+    /// Status einer Order abfragen
+    Status {
+        order_id: String,
+    },
+    /// Orderbuch eines Marktes anzeigen, z.B. `book BTC/USDT`
+    Book {
+        market: String,
+    },
+    /// State-Roots zweier Nodes vergleichen, z.B.
+    /// `compare-state http://10.0.0.1:8080 http://10.0.0.2:8080`; Exit-Code 1 bei Abweichung
+    CompareState {
+        node_a: String,
+        node_b: String,
     },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    let node = NodeClient::new(&cli.node_url, cli.api_key.clone());
 
     match &cli.command {
-        Commands::Add { order_id, user_id, amount, price } => {
-            // Sende an Node => z.B. RPC / REST / Socket
-            println!("Would add order {} user={} amt={} price={}", order_id, user_id, amount, price);
+        Commands::Add { user_id, market, side, amount, price } => {
+            let req = OrderRequest::for_market(user_id, market, *side, *amount, *price)?;
+            let placed = node.add_order(&req).await?;
+            println!("order_id={} status={}", placed.order_id, placed.status);
+        },
+        Commands::Remove { order_id, user_id } => {
+            let res = node.cancel_order(user_id, order_id).await?;
+            println!("order_id={} status={}", res.order_id, res.status);
+        },
+        Commands::Status { order_id } => {
+            let s = node.order_status(order_id).await?;
+            println!(
+                "order_id={} user={} market={} side={:?} amount={} filled={} price={} status={}",
+                s.order_id, s.user_id, s.market, s.side, s.amount, s.filled, s.price, s.status
+            );
+        },
+        Commands::Book { market } => {
+            let book = node.order_book(market).await?;
+            println!("{}", book.market);
+            println!("{:>14} {:>14}", "ASK PRICE", "QTY");
+            for (px, qty) in book.asks.iter().rev() {
+                println!("{:>14} {:>14}", px, qty);
+            }
+            println!("{:>14} {:>14}", "BID PRICE", "QTY");
+            for (px, qty) in &book.bids {
+                println!("{:>14} {:>14}", px, qty);
+            }
         },
-        Commands::Remove { order_id } => {
-            // Sende Remove an Node
-            println!("Would remove order {}", order_id);
+        Commands::CompareState { node_a, node_b } => {
+            let (a, b, diverging) = compare_state(
+                &NodeClient::new(node_a, cli.api_key.clone()),
+                &NodeClient::new(node_b, cli.api_key.clone()),
+            )
+            .await?;
+            match diverging.first() {
+                None => println!("in sync: {} == {} root={}", a.node_id, b.node_id, a.root),
+                Some(first) => {
+                    println!(
+                        "DIVERGED: {} vs {} => erster abweichender Shard {} ({} von {} Shards: {:?})",
+                        a.node_id, b.node_id, first, diverging.len(), a.shards.len().max(b.shards.len()), diverging
+                    );
+                    std::process::exit(1);
+                }
+            }
         },
     }
     Ok(())
@@ -18,9 +18,9 @@ use crate::node_logic::{DexNode, NodeConfig};
 // Wenn Node Logic in einer separaten Datei liegt
 mod node_logic;
 
-// Damit wir "verify_config_signature" aufrufen können, 
-// brauchst du ggf. so eine Funktion in crate::crypto::fallback_config o.ä.:
-use crate::crypto::fallback_config::verify_config_signature;
+// Signaturprüfung der Config aus der my_dex-Bibliothek (dex-node hat kein eigenes crypto-Modul)
+use my_dex::crypto::fallback_config::verify_config_signature;
+use my_dex::crypto::key_loader::{load_config_verifying_key, CONFIG_SIGNING_KEY_PATH};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -42,10 +42,14 @@ async fn main() -> Result<()> {
     };
 
     // 2) Signatur-Prüfung
-    //    Du brauchst eine signierte Config + passendes PublicKey. 
-    //    Das hier ist nur ein DEMO-Aufruf (public_key_str = "my_public_key")
-    let public_key_str = "my_public_key";
-    if !verify_config_signature(&raw_cfg, public_key_str) {
+    //    Signatur (hex) liegt neben der Datei (<cfg>.sig), der Public Key
+    //    lokal unter CONFIG_SIGNING_KEY_PATH.
+    let public_key_hex = load_config_verifying_key(CONFIG_SIGNING_KEY_PATH)
+        .map_err(|e| anyhow!("Config signing key unavailable: {}", e))?;
+    let sig_path = format!("{}.sig", cfg_path);
+    let signature_hex = std::fs::read_to_string(&sig_path)
+        .with_context(|| format!("Config-Signatur {} fehlt", sig_path))?;
+    if !verify_config_signature(&raw_cfg, signature_hex.trim(), &public_key_hex) {
         error!("Signatur der Config-Datei ist ungültig!");
         return Err(anyhow!("Config signature invalid"));
     }
@@ -55,6 +59,7 @@ async fn main() -> Result<()> {
     //    nehmen wir confy::from_str => wir haben den YAML-String ja schon.
     let cfg: NodeConfig = confy::from_str("dex_node_app", &raw_cfg)
         .context("Fehler beim Konvertieren von YAML in NodeConfig")?;
+    cfg.validate().context("Config ungültig")?;
     info!("Loaded config: {:?}", cfg);
 
     // => DexNode erstellen und starten
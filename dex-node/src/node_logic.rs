@@ -22,6 +22,30 @@ pub struct NodeConfig {
     // weitere Parameter
 }
 
+impl NodeConfig {
+    /// Plausibilitätsprüfung mit Feldnamen im Fehler.
+    pub fn validate(&self) -> Result<()> {
+        if !self.fees.is_finite() || self.fees <= 0.0 {
+            return Err(anyhow::anyhow!("Invalid config field `fees`: {} must be > 0", self.fees));
+        }
+        if self.fees > 1.0 {
+            return Err(anyhow::anyhow!("Invalid config field `fees`: {} exceeds 100%", self.fees));
+        }
+        if self.node_address.parse::<std::net::SocketAddr>().is_err() {
+            return Err(anyhow::anyhow!(
+                "Invalid config field `node_address`: `{}` is not a socket address", self.node_address
+            ));
+        }
+        if self.db_path.trim().is_empty() {
+            return Err(anyhow::anyhow!("Invalid config field `db_path`: must not be empty"));
+        }
+        if self.match_interval_sec == 0 {
+            return Err(anyhow::anyhow!("Invalid config field `match_interval_sec`: must be > 0"));
+        }
+        Ok(())
+    }
+}
+
 impl Default for NodeConfig {
     fn default() -> Self {
         Self {
@@ -41,13 +65,8 @@ pub struct DexNode {
 
 impl DexNode {
     pub fn new(cfg: NodeConfig) -> Result<Self> {
-        // 1) Plausibilitätscheck => fees > 0, match_interval_sec >= 1?
-        if cfg.fees <= 0.0 {
-            return Err(anyhow::anyhow!("Fees <= 0 => invalid config"));
-        }
-        if cfg.match_interval_sec == 0 {
-            return Err(anyhow::anyhow!("match_interval_sec=0 => invalid config"));
-        }
+        // 1) Plausibilitätscheck
+        cfg.validate()?;
 
         // 2) DB öffnen
         let db = sled::open(&cfg.db_path)?;
@@ -0,0 +1,113 @@
+// Folder: fuzz/fuzz_targets
+// File: fuzz_crdt_merge.rs
+//
+// Mehrere Ursprungs-Nodes führen zufällige Add/Remove/Partial-Fill-Operationen
+// auf ihrem CrdtState aus. Zwei Replikas übernehmen anschließend dieselben
+// Ursprungs-States in unterschiedlicher (vom Fuzzer gewählter) Reihenfolge.
+// Erwartung: identische sichtbare Orders und Fill-Summen (Konvergenz), und
+// keine Order ist über ihre Menge hinaus gefüllt.
+//
+// Start: cargo fuzz run fuzz_crdt_merge fuzz/corpus/fuzz_crdt_merge
+
+#![no_main]
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+use my_dex::crdt_logic::{CrdtSnapshot, CrdtState, Order};
+
+#[derive(Arbitrary, Debug)]
+enum CrdtOp {
+    Add { id: u8, qty: u8, price: u16 },
+    Remove { id: u8 },
+    Fill { id: u8, amount: u8 },
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    /// Operationen je Ursprungs-Node (max. 4 Nodes)
+    origins: Vec<Vec<CrdtOp>>,
+    /// Merge-Reihenfolge für Replika B (Indizes, werden normalisiert)
+    order_b: Vec<u8>,
+}
+
+/// Fill-Summe über die öffentliche Snapshot-Form (G-Counter je Node).
+fn filled(snap: &CrdtSnapshot, o: &Order) -> f64 {
+    snap.fill_counters
+        .iter()
+        .filter(|(ord, _)| ord == o)
+        .flat_map(|(_, gc)| gc.iter())
+        .map(|(_, v)| *v as f64)
+        .sum()
+}
+
+fn snapshot(st: &CrdtState) -> Vec<(String, u64, u64, u64)> {
+    let snap = st.snapshot();
+    st.visible_orders()
+        .into_iter()
+        .map(|o| {
+            let filled = filled(&snap, &o);
+            (o.id.clone(), o.quantity.to_bits(), o.price.to_bits(), filled.to_bits())
+        })
+        .collect()
+}
+
+fuzz_target!(|input: Input| {
+    let mut origins = Vec::new();
+    for (i, ops) in input.origins.into_iter().take(4).enumerate() {
+        let node = format!("N{}", i);
+        let mut st = CrdtState::new(&node);
+        for op in ops.into_iter().take(64) {
+            match op {
+                CrdtOp::Add { id, qty, price } => {
+                    let oid = format!("o{}", id % 16);
+                    // Pro Node höchstens eine sichtbare Version je ID
+                    if st.visible_orders().iter().any(|o| o.id == oid) {
+                        continue;
+                    }
+                    let _ = st.add_local_order(&node, &oid, "fuzz", 1.0 + qty as f64, 1.0 + (price % 1000) as f64);
+                }
+                CrdtOp::Remove { id } => {
+                    let _ = st.remove_local_order(&node, &format!("o{}", id % 16));
+                }
+                CrdtOp::Fill { id, amount } => {
+                    let _ = st.partial_fill(&node, &format!("o{}", id % 16), amount as f64, 0.0);
+                }
+            }
+        }
+        origins.push(st);
+    }
+    if origins.is_empty() {
+        return;
+    }
+
+    // Reihenfolge B: Permutation aus den Fuzzer-Bytes, fehlende Indizes hinten an
+    let mut order_b: Vec<usize> = Vec::new();
+    for b in input.order_b {
+        let idx = b as usize % origins.len();
+        if !order_b.contains(&idx) {
+            order_b.push(idx);
+        }
+    }
+    for idx in 0..origins.len() {
+        if !order_b.contains(&idx) {
+            order_b.push(idx);
+        }
+    }
+
+    let mut a = CrdtState::new("A");
+    for st in &origins {
+        a.merge_remote("A", st).unwrap();
+    }
+    let mut b = CrdtState::new("B");
+    for idx in &order_b {
+        b.merge_remote("B", &origins[*idx]).unwrap();
+    }
+
+    let (sa, sb) = (snapshot(&a), snapshot(&b));
+    assert_eq!(sa, sb, "replicas diverged for merge order {:?}", order_b);
+
+    let snap_a = a.snapshot();
+    for o in a.visible_orders() {
+        assert!(filled(&snap_a, &o) <= o.quantity, "order {} overfilled", o.id);
+    }
+});
@@ -10,6 +10,6 @@ use my_dex::consensus::nakamoto::validate_block;
 
 fuzz_target!(|data: &[u8]| {
     // Die Funktion validate_block wird mit beliebigen Eingabedaten aufgerufen.
-    // Dadurch werden unerwartete Eingaben getestet, um potentielle Abst�rze oder Sicherheitsl�cken aufzudecken.
+    // Dadurch werden unerwartete Eingaben getestet, um potentielle Abstürze oder Sicherheitslücken aufzudecken.
     let _ = validate_block(data);
 });
@@ -6,7 +6,8 @@ use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 use serde_json::Value;
 use anyhow::Result;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use rand::Rng;
 
 use ed25519_dalek::{Keypair, Signer};
 
@@ -28,24 +29,48 @@ async fn main() -> Result<()> {
     // 1) Preis-Feed via WebSocket (optional)
     let global_prices = Arc::new(Mutex::new(HashMap::<String, f64>::new()));
     let gp_clone = global_prices.clone();
+    //    Bei Abbruch: Reconnect mit gejittertem exponentiellem Backoff (max. 60s);
+    //    ohne Update innerhalb von 30s gilt der Preis als stale (wird entfernt).
     let handle_ws = tokio::spawn(async move {
         let url = "wss://stream.binance.com:9443/ws/btcusdt@trade";
-        let (ws_stream, _) = connect_async(url).await.expect("Fehler Connect");
-        let (_write, mut read) = ws_stream.split();
-
-        while let Some(msg) = read.next().await {
-            if let Ok(m) = msg {
-                if let Message::Text(txt) = m {
-                    if let Ok(js) = serde_json::from_str::<Value>(&txt) {
-                        if let Some(price_str) = js.get("p").and_then(|v| v.as_str()) {
-                            if let Ok(px) = price_str.parse::<f64>() {
-                                let mut map = gp_clone.lock().unwrap();
-                                map.insert("BTC/USDT".to_string(), px);
+        let stale_after = Duration::from_secs(30);
+        let max_backoff = Duration::from_secs(60);
+        let mut backoff = Duration::from_millis(500);
+        loop {
+            match connect_async(url).await {
+                Ok((ws_stream, _)) => {
+                    println!("Preis-Feed verbunden.");
+                    let (_write, mut read) = ws_stream.split();
+                    loop {
+                        match tokio::time::timeout(stale_after, read.next()).await {
+                            Err(_) => {
+                                eprintln!("Preis-Feed: kein Update seit {:?} => stale", stale_after);
+                                gp_clone.lock().unwrap().remove("BTC/USDT");
+                                break;
+                            }
+                            Ok(None) | Ok(Some(Err(_))) => break,
+                            Ok(Some(Ok(Message::Text(txt)))) => {
+                                if let Ok(js) = serde_json::from_str::<Value>(&txt) {
+                                    if let Some(price_str) = js.get("p").and_then(|v| v.as_str()) {
+                                        if let Ok(px) = price_str.parse::<f64>() {
+                                            let mut map = gp_clone.lock().unwrap();
+                                            map.insert("BTC/USDT".to_string(), px);
+                                            backoff = Duration::from_millis(500);
+                                        }
+                                    }
+                                }
                             }
+                            Ok(Some(Ok(_))) => {}
                         }
                     }
                 }
+                Err(e) => eprintln!("Preis-Feed: Connect fehlgeschlagen: {:?}", e),
             }
+            let jitter = rand::thread_rng().gen_range(0.8..1.2);
+            let wait = backoff.mul_f64(jitter).min(max_backoff);
+            eprintln!("Preis-Feed getrennt => Reconnect in {:?}", wait);
+            tokio::time::sleep(wait).await;
+            backoff = (backoff * 2).min(max_backoff);
         }
     });
 
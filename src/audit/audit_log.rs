@@ -7,15 +7,33 @@
 // eindeutige Event-ID, Timestamp, Asset-ID, Menge, K�ufer und Verk�ufer
 // protokolliert. Die Daten werden in einer JSON-Logdatei abgespeichert,
 // sodass sie sp�ter zur Pr�fung (Audit) verwendet werden k�nnen.
+//
+// Manipulationsschutz: Jeder Eintrag enthält den Hash des vorherigen
+// Eintrags (Hash-Kette) sowie eine Ed25519-Signatur des Nodes über den
+// eigenen Hash. `verify_audit_chain` erkennt damit jedes Einfügen, Löschen
+// oder Ändern innerhalb der Datei. Das Abschneiden am Ende ist nur gegen
+// einen extern verankerten Head-Hash (z.B. per IPFS-Upload) erkennbar.
+//
+// Export: `export_audit_log` bündelt die Datei mit einem signierten Manifest
+// (SHA-256 der Datei, Anzahl Einträge, Head-Hash, Export-Zeitpunkt).
+// `verify_audit_export` prüft Signatur, Digest und die Kette selbst und
+// verlangt, dass Kette und Manifest auf denselben Head enden. Ein
+// abgeschnittener oder veränderter Export fällt damit auf, auch ohne
+// externen Anker.
 ///////////////////////////////////////////////////////////
 
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
 use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
 use std::fs::OpenOptions;
-use std::io::Write;
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
 
 /// Enum zur Darstellung des Typs eines Handelsereignisses.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum TradeEventType {
     Buy,
     Sell,
@@ -30,7 +48,7 @@ pub enum TradeEventType {
 /// - `quantity`: Die gehandelten Menge.
 /// - `buyer`: Optional der K�ufer (bei Kauf/Transfer).
 /// - `seller`: Optional der Verk�ufer (bei Verkauf/Transfer).
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct TradeAuditEvent {
     pub event_id: String,
     pub event_type: TradeEventType,
@@ -71,25 +89,411 @@ impl TradeAuditEvent {
     }
 }
 
-/// Schreibt ein einzelnes Audit-Event in die angegebene Log-Datei.
+/// Prev-Hash des ersten Eintrags einer Kette.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Eine Zeile im Audit-Log: Event plus Kettenglied und Signatur.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub prev_hash: String,
+    pub event: TradeAuditEvent,
+    /// sha256(seq || prev_hash || event-JSON), hex.
+    pub hash: String,
+    /// Öffentlicher Schlüssel des Nodes (hex).
+    pub signer: String,
+    /// Ed25519-Signatur über `hash` (hex).
+    pub signature: String,
+}
+
+impl AuditRecord {
+    fn compute_hash(seq: u64, prev_hash: &str, event: &TradeAuditEvent) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(seq.to_be_bytes());
+        hasher.update(prev_hash.as_bytes());
+        hasher.update(serde_json::to_vec(event).expect("Fehler beim Serialisieren des Audit-Events"));
+        hex::encode(hasher.finalize())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AuditChainError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Line {line}: malformed audit record")]
+    Malformed { line: usize },
+
+    #[error("Entry {seq}: expected sequence number {expected}")]
+    SequenceGap { seq: u64, expected: u64 },
+
+    #[error("Entry {seq}: prev_hash does not match previous entry")]
+    BrokenLink { seq: u64 },
+
+    #[error("Entry {seq}: content does not match its hash")]
+    HashMismatch { seq: u64 },
+
+    #[error("Entry {seq}: invalid signature")]
+    BadSignature { seq: u64 },
+
+    #[error("Entry {seq}: signed by unexpected key {signer}")]
+    UnexpectedSigner { seq: u64, signer: String },
+
+    #[error("Export: invalid manifest signature")]
+    ExportSignature,
+
+    #[error("Export: log digest does not match manifest")]
+    ExportDigestMismatch,
+
+    #[error("Export: chain ends at {actual_entries} entries / {actual_head}, manifest says {entries} / {head_hash}")]
+    ExportHeadMismatch { entries: u64, head_hash: String, actual_entries: u64, actual_head: String },
+
+    #[error("Export: {0}")]
+    ExportEncoding(String),
+}
+
+/// Ergebnis einer erfolgreichen Prüfung.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditChainSummary {
+    pub entries: u64,
+    /// Hash des letzten Eintrags; zum Verankern außerhalb der Datei.
+    pub head_hash: String,
+}
+
+/// Hängt signierte, verkettete Einträge an eine Audit-Datei an.
+/// Beim Öffnen wird die bestehende Kette geprüft und fortgesetzt, damit sie
+/// über Neustarts hinweg durchgehend ist.
+///
+/// Eine unvollständige letzte Zeile (Absturz mitten im Schreiben) wird
+/// abgeschnitten, die Kette davor bleibt erhalten.
+/// Ist die bestehende Datei ein Log im alten Format (ohne Kette) oder von einem
+/// anderen Schlüssel signiert (Schlüsselwechsel), wird sie nach
+/// `<datei>.rotated-<unix-sekunden>` verschoben und eine neue Kette begonnen.
+/// Eine beschädigte Kette desselben Schlüssels bleibt ein Fehler.
+pub struct AuditLogger {
+    path: PathBuf,
+    keypair: Keypair,
+    human_readable: Option<PathBuf>,
+    head: Mutex<(u64, String)>,
+}
+
+impl std::fmt::Debug for AuditLogger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLogger").field("path", &self.path).finish_non_exhaustive()
+    }
+}
+
+impl AuditLogger {
+    pub fn open<P: Into<PathBuf>>(path: P, keypair: Keypair) -> Result<Self, AuditChainError> {
+        let path = path.into();
+        let signer = hex::encode(keypair.public.as_bytes());
+        if let Some(cut) = truncate_partial_tail(&path)? {
+            tracing::warn!("Audit-Log {:?}: unvollständige letzte Zeile ({} Bytes) abgeschnitten", path, cut);
+        }
+        let head = match verify_records(read_records(&path)?, Some(signer)) {
+            Ok(summary) => (summary.entries, summary.head_hash),
+            Err(e @ AuditChainError::Malformed { .. }) | Err(e @ AuditChainError::UnexpectedSigner { .. }) => {
+                let rotated = rotate_log(&path)?;
+                tracing::warn!("Audit-Log {:?} nicht fortsetzbar ({}) => verschoben nach {:?}", path, e, rotated);
+                (0, GENESIS_HASH.to_string())
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            path,
+            keypair,
+            human_readable: None,
+            head: Mutex::new(head),
+        })
+    }
+
+    /// Schreibt zusätzlich eine lesbare Zeile pro Event in `path` (ohne Integritätsschutz).
+    pub fn with_human_readable<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.human_readable = Some(path.into());
+        self
+    }
+
+    pub fn log(&self, event: &TradeAuditEvent) -> Result<AuditRecord, AuditChainError> {
+        let mut head = self.head.lock().unwrap();
+        let (seq, prev_hash) = (head.0, head.1.clone());
+        let hash = AuditRecord::compute_hash(seq, &prev_hash, event);
+        let signature = self.keypair.sign(hash.as_bytes());
+        let record = AuditRecord {
+            seq,
+            prev_hash,
+            event: event.clone(),
+            hash: hash.clone(),
+            signer: hex::encode(self.keypair.public.as_bytes()),
+            signature: hex::encode(signature.to_bytes()),
+        };
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let serialized = serde_json::to_string(&record)
+            .expect("Fehler beim Serialisieren des Audit-Events");
+        writeln!(file, "{}", serialized)?;
+        file.sync_data()?;
+        *head = (seq + 1, hash);
+
+        if let Some(hr) = &self.human_readable {
+            let mut f = OpenOptions::new().create(true).append(true).open(hr)?;
+            writeln!(
+                f,
+                "#{} {} {:?} {} {} buyer={} seller={}",
+                record.seq,
+                event.timestamp,
+                event.event_type,
+                event.quantity,
+                event.asset_id,
+                event.buyer.as_deref().unwrap_or("-"),
+                event.seller.as_deref().unwrap_or("-"),
+            )?;
+        }
+        Ok(record)
+    }
+}
+
+/// Schreibt ein einzelnes Audit-Event signiert und verkettet in die angegebene Log-Datei.
 /// Die Events werden im JSON-Format gespeichert, jeweils in einer neuen Zeile.
-pub fn log_trade_event(event: &TradeAuditEvent, log_file_path: &str) -> std::io::Result<()> {
-    // �ffne oder erstelle die Log-Datei im Append-Modus.
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_file_path)?;
-    // Serialisiere das Event in eine JSON-Zeichenkette.
-    let serialized = serde_json::to_string(event)
-        .expect("Fehler beim Serialisieren des Audit-Events");
-    // Schreibe die JSON-Zeichenkette in die Datei, gefolgt von einem Zeilenumbruch.
-    writeln!(file, "{}", serialized)?;
+/// Für viele Events ist ein langlebiger `AuditLogger` günstiger.
+pub fn log_trade_event(event: &TradeAuditEvent, log_file_path: &str, keypair: Keypair) -> Result<(), AuditChainError> {
+    AuditLogger::open(log_file_path, keypair)?.log(event)?;
     Ok(())
 }
 
+/// Prüft Hash-Kette, Sequenznummern und Signaturen der gesamten Datei.
+/// Alle Einträge müssen vom selben Schlüssel stammen wie der erste.
+pub fn verify_audit_chain<P: AsRef<Path>>(path: P) -> Result<AuditChainSummary, AuditChainError> {
+    verify_chain(path.as_ref(), None)
+}
+
+/// Wie `verify_audit_chain`, verlangt aber einen bestimmten Node-Schlüssel.
+pub fn verify_audit_chain_for<P: AsRef<Path>>(path: P, signer: &PublicKey) -> Result<AuditChainSummary, AuditChainError> {
+    verify_chain(path.as_ref(), Some(hex::encode(signer.as_bytes())))
+}
+
+fn verify_chain(path: &Path, expected_signer: Option<String>) -> Result<AuditChainSummary, AuditChainError> {
+    verify_records(read_records(path)?, expected_signer)
+}
+
+fn verify_records(
+    records: Vec<Result<AuditRecord, AuditChainError>>,
+    mut expected_signer: Option<String>,
+) -> Result<AuditChainSummary, AuditChainError> {
+    let mut expected_seq = 0u64;
+    let mut prev_hash = GENESIS_HASH.to_string();
+    for record in records {
+        let record = record?;
+        if record.seq != expected_seq {
+            return Err(AuditChainError::SequenceGap { seq: record.seq, expected: expected_seq });
+        }
+        if record.prev_hash != prev_hash {
+            return Err(AuditChainError::BrokenLink { seq: record.seq });
+        }
+        if AuditRecord::compute_hash(record.seq, &record.prev_hash, &record.event) != record.hash {
+            return Err(AuditChainError::HashMismatch { seq: record.seq });
+        }
+        match &expected_signer {
+            Some(s) if *s != record.signer => {
+                return Err(AuditChainError::UnexpectedSigner { seq: record.seq, signer: record.signer });
+            }
+            None => expected_signer = Some(record.signer.clone()),
+            _ => {}
+        }
+        verify_record_signature(&record)?;
+        prev_hash = record.hash;
+        expected_seq += 1;
+    }
+    Ok(AuditChainSummary { entries: expected_seq, head_hash: prev_hash })
+}
+
+fn verify_record_signature(record: &AuditRecord) -> Result<(), AuditChainError> {
+    let bad = || AuditChainError::BadSignature { seq: record.seq };
+    let pk_bytes = hex::decode(&record.signer).map_err(|_| bad())?;
+    let pk = PublicKey::from_bytes(&pk_bytes).map_err(|_| bad())?;
+    let sig_bytes = hex::decode(&record.signature).map_err(|_| bad())?;
+    let sig = Signature::from_bytes(&sig_bytes).map_err(|_| bad())?;
+    pk.verify(record.hash.as_bytes(), &sig).map_err(|_| bad())
+}
+
+fn read_records(path: &Path) -> Result<Vec<Result<AuditRecord, AuditChainError>>, AuditChainError> {
+    let file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    parse_records(BufReader::new(file))
+}
+
+fn parse_records<R: BufRead>(reader: R) -> Result<Vec<Result<AuditRecord, AuditChainError>>, AuditChainError> {
+    let mut out = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        out.push(serde_json::from_str::<AuditRecord>(&line).map_err(|_| AuditChainError::Malformed { line: i + 1 }));
+    }
+    Ok(out)
+}
+
+/// Domain-Tag des Export-Manifests.
+pub const AUDIT_EXPORT_DOMAIN: &str = "my_dex/audit_export/v1";
+
+/// Vom Node signierte Angaben über einen Export.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditExportManifest {
+    /// sha256 über die exportierten Bytes, hex.
+    pub log_sha256: String,
+    pub entries: u64,
+    /// Hash des letzten Eintrags (Kettenkopf) zum Exportzeitpunkt.
+    pub head_hash: String,
+    /// Millisekunden seit UNIX_EPOCH; vom Aufrufer gesetzt.
+    pub exported_at: u64,
+    /// Öffentlicher Schlüssel des Nodes (hex).
+    pub signer: String,
+}
+
+/// Log plus abgesetzte Node-Signatur über das Manifest.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditExport {
+    pub manifest: AuditExportManifest,
+    /// Ed25519-Signatur über `signing_bytes(AUDIT_EXPORT_DOMAIN, manifest)`, hex.
+    pub signature: String,
+    /// Inhalt der Audit-Datei, unverändert.
+    pub log: String,
+}
+
+fn manifest_bytes(manifest: &AuditExportManifest) -> Result<Vec<u8>, AuditChainError> {
+    crate::utils::canonical::signing_bytes(AUDIT_EXPORT_DOMAIN, manifest)
+        .map_err(|e| AuditChainError::ExportEncoding(e.to_string()))
+}
+
+/// Exportiert die Audit-Datei signiert. Die Kette wird vorher geprüft und muss
+/// vollständig von `keypair` stammen; ein bereits beschädigtes Log wird nicht
+/// signiert.
+pub fn export_audit_log<P: AsRef<Path>>(path: P, keypair: &Keypair, exported_at: u64) -> Result<AuditExport, AuditChainError> {
+    let log = match std::fs::read_to_string(path.as_ref()) {
+        Ok(l) => l,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let signer = hex::encode(keypair.public.as_bytes());
+    let summary = verify_records(parse_records(log.as_bytes())?, Some(signer.clone()))?;
+    let manifest = AuditExportManifest {
+        log_sha256: hex::encode(Sha256::digest(log.as_bytes())),
+        entries: summary.entries,
+        head_hash: summary.head_hash,
+        exported_at,
+        signer,
+    };
+    let signature = hex::encode(keypair.sign(&manifest_bytes(&manifest)?).to_bytes());
+    Ok(AuditExport { manifest, signature, log })
+}
+
+/// Prüft einen Export: Manifest-Signatur (optional gegen einen erwarteten
+/// Node-Schlüssel), Digest des Logs, die Kette und dass sie genau auf dem
+/// signierten Head endet.
+pub fn verify_audit_export(export: &AuditExport, expected_signer: Option<&PublicKey>) -> Result<AuditChainSummary, AuditChainError> {
+    let m = &export.manifest;
+    if let Some(expected) = expected_signer {
+        let expected = hex::encode(expected.as_bytes());
+        if expected != m.signer {
+            return Err(AuditChainError::UnexpectedSigner { seq: m.entries, signer: m.signer.clone() });
+        }
+    }
+    let pk = hex::decode(&m.signer)
+        .ok()
+        .and_then(|b| PublicKey::from_bytes(&b).ok())
+        .ok_or(AuditChainError::ExportSignature)?;
+    let sig = hex::decode(&export.signature)
+        .ok()
+        .and_then(|b| Signature::from_bytes(&b).ok())
+        .ok_or(AuditChainError::ExportSignature)?;
+    pk.verify(&manifest_bytes(m)?, &sig).map_err(|_| AuditChainError::ExportSignature)?;
+
+    if hex::encode(Sha256::digest(export.log.as_bytes())) != m.log_sha256 {
+        return Err(AuditChainError::ExportDigestMismatch);
+    }
+    let summary = verify_records(parse_records(export.log.as_bytes())?, Some(m.signer.clone()))?;
+    if summary.entries != m.entries || summary.head_hash != m.head_hash {
+        return Err(AuditChainError::ExportHeadMismatch {
+            entries: m.entries,
+            head_hash: m.head_hash.clone(),
+            actual_entries: summary.entries,
+            actual_head: summary.head_hash,
+        });
+    }
+    Ok(summary)
+}
+
+/// Verschiebt eine nicht fortsetzbare Audit-Datei, statt sie zu überschreiben.
+/// Schneidet eine letzte Zeile ohne Zeilenende ab, die kein vollständiger
+/// Eintrag ist (`log` schreibt jeden Eintrag mit `\n`). Liefert die Anzahl
+/// entfernter Bytes; ein vollständiger Eintrag ohne `\n` wird nur abgeschlossen.
+fn truncate_partial_tail(path: &Path) -> Result<Option<u64>, AuditChainError> {
+    let content = match std::fs::read(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if content.is_empty() || content.ends_with(b"\n") {
+        return Ok(None);
+    }
+    let start = content.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+    let tail = &content[start..];
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    if serde_json::from_slice::<AuditRecord>(tail).is_ok() {
+        file.seek(SeekFrom::End(0))?;
+        file.write_all(b"\n")?;
+        file.sync_data()?;
+        return Ok(None);
+    }
+    file.set_len(start as u64)?;
+    file.sync_data()?;
+    Ok(Some(tail.len() as u64))
+}
+
+fn rotate_log(path: &Path) -> Result<PathBuf, AuditChainError> {
+    let ts = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let base = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    let mut n = 0;
+    let target = loop {
+        let mut name = base.clone();
+        name.push(format!(".rotated-{}", ts));
+        if n > 0 {
+            name.push(format!("-{}", n));
+        }
+        let candidate = path.with_file_name(name);
+        if !candidate.exists() {
+            break candidate;
+        }
+        n += 1;
+    };
+    std::fs::rename(path, &target)?;
+    Ok(target)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ed25519_dalek::SecretKey;
+
+    fn node_keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    fn sample_event(qty: f64) -> TradeAuditEvent {
+        TradeAuditEvent::new(TradeEventType::Buy, "BTC", qty, Some("Alice".into()), Some("Bob".into()))
+    }
+
+    fn write_chain(path: &Path, n: usize) {
+        let logger = AuditLogger::open(path, node_keypair(1)).unwrap();
+        for i in 0..n {
+            logger.log(&sample_event(i as f64 + 1.0)).unwrap();
+        }
+    }
 
     #[test]
     fn test_audit_event_creation_and_logging() {
@@ -104,7 +508,165 @@ mod tests {
         // Pr�fe, ob die Felder korrekt gesetzt wurden.
         println!("{:?}", event);
         // Versuche das Event in eine tempor�re Log-Datei zu schreiben.
-        let result = log_trade_event(&event, "trade_audit_test.log");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trade_audit_test.log");
+        let result = log_trade_event(&event, path.to_str().unwrap(), node_keypair(1));
         assert!(result.is_ok());
+        // Zweites Event setzt die Kette fort
+        log_trade_event(&event, path.to_str().unwrap(), node_keypair(1)).unwrap();
+        assert_eq!(verify_audit_chain(&path).unwrap().entries, 2);
+    }
+
+    #[test]
+    fn test_modified_middle_entry_fails_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        write_chain(&path, 5);
+        assert!(verify_audit_chain_for(&path, &node_keypair(1).public).is_ok());
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+        lines[2] = lines[2].replace("\"quantity\":3.0", "\"quantity\":300.0");
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+        match verify_audit_chain(&path) {
+            Err(AuditChainError::HashMismatch { seq }) => assert_eq!(seq, 2),
+            other => panic!("expected HashMismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_signed_export_verifies_and_truncation_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        write_chain(&path, 3);
+        let key = node_keypair(1);
+
+        let export = export_audit_log(&path, &key, 1_700_000_000_000).unwrap();
+        let summary = verify_audit_export(&export, Some(&key.public)).unwrap();
+        assert_eq!(summary.entries, 3);
+        assert_eq!(export.manifest.head_hash, summary.head_hash);
+        // Gleiches Log, gleicher Zeitpunkt => identisches Bündel
+        assert_eq!(export_audit_log(&path, &key, 1_700_000_000_000).unwrap().signature, export.signature);
+        assert!(matches!(
+            verify_audit_export(&export, Some(&node_keypair(2).public)),
+            Err(AuditChainError::UnexpectedSigner { .. })
+        ));
+
+        // Letzten Eintrag abschneiden => Digest passt nicht mehr
+        let mut truncated = export.clone();
+        let keep: Vec<&str> = export.log.lines().take(2).collect();
+        truncated.log = keep.join("\n") + "\n";
+        assert!(matches!(verify_audit_export(&truncated, None), Err(AuditChainError::ExportDigestMismatch)));
+
+        // Auch mit angepasstem Digest fehlt der signierte Head
+        truncated.manifest.log_sha256 = hex::encode(Sha256::digest(truncated.log.as_bytes()));
+        assert!(matches!(verify_audit_export(&truncated, None), Err(AuditChainError::ExportSignature)));
+
+        // Ein älterer, korrekt signierter Export passt nicht auf den neuen Head
+        AuditLogger::open(&path, node_keypair(1)).unwrap().log(&sample_event(9.0)).unwrap();
+        let newer = export_audit_log(&path, &key, 1_700_000_000_001).unwrap();
+        let mut spliced = newer.clone();
+        spliced.log = export.log.clone();
+        assert!(verify_audit_export(&spliced, None).is_err());
+        assert_eq!(verify_audit_export(&newer, None).unwrap().entries, 4);
+    }
+
+    #[test]
+    fn test_deleted_and_inserted_entries_fail_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        write_chain(&path, 4);
+        let original = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = original.lines().collect();
+
+        // Löschen
+        let deleted = [lines[0], lines[1], lines[3]].join("\n") + "\n";
+        std::fs::write(&path, deleted).unwrap();
+        assert!(matches!(verify_audit_chain(&path), Err(AuditChainError::SequenceGap { .. })));
+
+        // Einfügen eines fremd signierten Eintrags, der sich korrekt an lines[1] hängt
+        let forged_path = dir.path().join("forged.log");
+        let second: AuditRecord = serde_json::from_str(lines[1]).unwrap();
+        let forger = node_keypair(2);
+        let event = sample_event(99.0);
+        let hash = AuditRecord::compute_hash(2, &second.hash, &event);
+        let forged = AuditRecord {
+            seq: 2,
+            prev_hash: second.hash.clone(),
+            event,
+            signature: hex::encode(forger.sign(hash.as_bytes()).to_bytes()),
+            hash,
+            signer: hex::encode(forger.public.as_bytes()),
+        };
+        let content = [lines[0].to_string(), lines[1].to_string(), serde_json::to_string(&forged).unwrap()].join("\n") + "\n";
+        std::fs::write(&forged_path, content).unwrap();
+        assert!(matches!(
+            verify_audit_chain(&forged_path),
+            Err(AuditChainError::UnexpectedSigner { seq: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_reopen_continues_chain_and_rotates_legacy_or_foreign_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+
+        // Neustart mit demselben Node-Schlüssel setzt die Kette fort.
+        write_chain(&path, 2);
+        write_chain(&path, 2);
+        assert_eq!(verify_audit_chain_for(&path, &node_keypair(1).public).unwrap().entries, 4);
+
+        // Anderer Schlüssel => altes Log wird verschoben, neue Kette beginnt.
+        AuditLogger::open(&path, node_keypair(2)).unwrap().log(&sample_event(1.0)).unwrap();
+        assert_eq!(verify_audit_chain_for(&path, &node_keypair(2).public).unwrap().entries, 1);
+
+        // Altes Format (eine JSON-Zeile pro Event ohne Kette) => ebenfalls verschoben.
+        std::fs::write(&path, serde_json::to_string(&sample_event(3.0)).unwrap() + "\n").unwrap();
+        AuditLogger::open(&path, node_keypair(2)).unwrap().log(&sample_event(4.0)).unwrap();
+        assert_eq!(verify_audit_chain(&path).unwrap().entries, 1);
+
+        let rotated = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().contains(".rotated-"))
+            .count();
+        assert_eq!(rotated, 2);
+
+        // Manipulation unter demselben Schlüssel wird nicht wegrotiert.
+        let tampered = std::fs::read_to_string(&path).unwrap().replace("\"quantity\":4.0", "\"quantity\":5.0");
+        std::fs::write(&path, tampered).unwrap();
+        assert!(matches!(
+            AuditLogger::open(&path, node_keypair(2)),
+            Err(AuditChainError::HashMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_partial_last_line_truncated_not_rotated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        write_chain(&path, 3);
+
+        // Absturz mitten im Schreiben: halbe Zeile ohne Zeilenende
+        let intact = std::fs::read(&path).unwrap();
+        let mut content = intact.clone();
+        content.extend_from_slice(br#"{"seq":3,"prev_hash":"ab"#);
+        std::fs::write(&path, &content).unwrap();
+
+        let logger = AuditLogger::open(&path, node_keypair(1)).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), intact);
+        logger.log(&sample_event(4.0)).unwrap();
+        assert_eq!(verify_audit_chain(&path).unwrap().entries, 4);
+        let rotated = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().contains(".rotated-"))
+            .count();
+        assert_eq!(rotated, 0);
+
+        // Vollständiger letzter Eintrag ohne Zeilenende bleibt erhalten
+        let content = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &content[..content.len() - 1]).unwrap();
+        AuditLogger::open(&path, node_keypair(1)).unwrap().log(&sample_event(5.0)).unwrap();
+        assert_eq!(verify_audit_chain(&path).unwrap().entries, 5);
     }
 }
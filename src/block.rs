@@ -6,6 +6,43 @@ use anyhow::Result;
 use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+use crate::error::DexError;
+use crate::identity::keystore::Keystore;
+use crate::utils::canonical;
+use crate::utils::geoip_and_ntp::ClockSkewGuard;
+
+/// Obergrenzen für einen einzelnen Block.
+#[derive(Debug, Clone)]
+pub struct BlockLimits {
+    pub max_transactions: usize,
+    /// Summe der serialisierten Transaktionen (JSON, Bytes)
+    pub max_bytes: usize,
+    pub max_amount: u64,
+}
+
+impl Default for BlockLimits {
+    fn default() -> Self {
+        Self {
+            max_transactions: 4_096,
+            max_bytes: 1_000_000,
+            max_amount: 1_000_000_000_000_000,
+        }
+    }
+}
+
+/// Ledger-Sicht, gegen die ein Block geprüft wird.
+pub trait LedgerState {
+    /// Verfügbares Guthaben von `account` vor Anwendung des Blocks.
+    fn balance(&self, account: &str) -> u64;
+    /// Registrierter Signaturschlüssel von `account`.
+    fn public_key(&self, account: &str) -> Option<PublicKey>;
+    /// Kennung der Chain; Transaktionen anderer Chains sind ungültig.
+    fn chain_id(&self) -> &str;
+    /// Kleinste noch unbenutzte Nonce von `account`.
+    fn next_nonce(&self, account: &str) -> u64;
+}
 
 /// Eine Transaktion im DEX-System.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -14,9 +51,115 @@ pub struct Transaction {
     pub from: String,
     pub to: String,
     pub amount: u64,
+    /// Gebühr an den Block-Produzenten, zusätzlich zu `amount`
+    #[serde(default)]
+    pub fee: u64,
+    /// Fortlaufend je Sender; eine Nonce ist nur einmal gültig (Replay-Schutz)
+    #[serde(default)]
+    pub nonce: u64,
+    /// Chain, für die signiert wurde (kein Replay auf anderen Chains)
+    #[serde(default)]
+    pub chain_id: String,
+    /// Signatur von `from` über `signing_bytes()`
+    #[serde(default)]
+    pub signature: Option<Signature>,
     // Weitere Felder nach Bedarf …
 }
 
+impl Transaction {
+    pub fn new(id: u32, from: &str, to: &str, amount: u64) -> Self {
+        Self {
+            id,
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            fee: 0,
+            nonce: 0,
+            chain_id: String::new(),
+            signature: None,
+        }
+    }
+
+    pub fn with_fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    pub fn with_chain_id(mut self, chain_id: &str) -> Self {
+        self.chain_id = chain_id.to_string();
+        self
+    }
+
+    /// Gesamtbelastung des Senders.
+    pub fn total_spend(&self) -> u64 {
+        self.amount.saturating_add(self.fee)
+    }
+
+    /// Signierter Inhalt: alles außer der Signatur selbst (kanonisches CBOR).
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct TxSigningView<'a> {
+            chain_id: &'a str,
+            id: u32,
+            from: &'a str,
+            to: &'a str,
+            amount: u64,
+            fee: u64,
+            nonce: u64,
+        }
+        canonical::signing_bytes("my_dex/block/tx/v1", &TxSigningView {
+            chain_id: &self.chain_id,
+            id: self.id,
+            from: &self.from,
+            to: &self.to,
+            amount: self.amount,
+            fee: self.fee,
+            nonce: self.nonce,
+        })
+    }
+
+    /// Zustandslose Prüfungen + Signatur gegen den registrierten Schlüssel.
+    /// Deckung und Nonce-Reihenfolge prüft der Aufrufer (Block bzw. Mempool),
+    /// hier nur, dass die Nonce noch nicht verbraucht ist.
+    pub fn check(&self, state: &dyn LedgerState, limits: &BlockLimits) -> Result<(), DexError> {
+        let invalid = |reason: String| DexError::InvalidTransaction { tx_id: self.id, reason };
+        if self.amount == 0 || self.amount > limits.max_amount {
+            return Err(invalid(format!("amount {} out of range 1..={}", self.amount, limits.max_amount)));
+        }
+        if self.chain_id != state.chain_id() {
+            return Err(invalid(format!("chain id `{}` != `{}`", self.chain_id, state.chain_id())));
+        }
+        let next = state.next_nonce(&self.from);
+        if self.nonce < next {
+            return Err(invalid(format!("nonce {} already used (next {})", self.nonce, next)));
+        }
+        let pk = state
+            .public_key(&self.from)
+            .ok_or_else(|| invalid(format!("unknown sender {}", self.from)))?;
+        if !self.verify_signature(&pk) {
+            return Err(invalid("bad signature".into()));
+        }
+        Ok(())
+    }
+
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<(), DexError> {
+        self.signature = Some(keypair.sign(&self.signing_bytes()?));
+        Ok(())
+    }
+
+    pub fn verify_signature(&self, public_key: &PublicKey) -> bool {
+        match (&self.signature, self.signing_bytes()) {
+            (Some(sig), Ok(bytes)) => public_key.verify(&bytes, sig).is_ok(),
+            _ => false,
+        }
+    }
+}
+
 /// Ein Block, der Transaktionen, Metadaten und die digitale Signatur enthält.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Block {
@@ -53,6 +196,113 @@ impl Block {
         })
     }
 
+    /// Erzeugt einen Block mit NTP-korrigiertem Zeitstempel. Liegt die lokale
+    /// Uhr außerhalb der Toleranz (oder wurde nie gemessen), wird kein Block produziert.
+    pub fn produce(
+        guard: &ClockSkewGuard,
+        index: u64,
+        previous_hash: String,
+        nonce: u64,
+        transactions: Vec<Transaction>,
+    ) -> Result<Self> {
+        guard.ensure_local_clock_ok()?;
+        Self::new(index, previous_hash, guard.corrected_now_ms() / 1000, nonce, transactions)
+    }
+
+    /// Zeitstempel eines empfangenen Blocks nicht zu weit in der Zukunft?
+    /// Alte Blöcke (Nachsynchronisieren) sind zulässig.
+    pub fn check_timestamp(&self, guard: &ClockSkewGuard) -> Result<(), DexError> {
+        guard.check_not_future_ms(self.timestamp.saturating_mul(1000))
+    }
+
+    /// `accept` plus Zeitstempel-Prüfung gegen die NTP-korrigierte Uhr.
+    pub fn accept_checked(
+        &self,
+        public_key: &PublicKey,
+        state: &dyn LedgerState,
+        guard: &ClockSkewGuard,
+    ) -> Result<(), DexError> {
+        self.check_timestamp(guard)?;
+        self.accept(public_key, state)
+    }
+
+    /// Prüft Limits, Konsistenz (Merkle-Root, Hash) und jede Transaktion gegen
+    /// `state`: Betrag im erlaubten Bereich, gültige Signatur von `from`,
+    /// passende Chain, je Sender streng steigende, unverbrauchte Nonces und
+    /// ausreichende Deckung. Transaktionen werden in Blockreihenfolge
+    /// verrechnet, d. h. eingehende Beträge dürfen später im Block ausgegeben werden.
+    pub fn validate(&self, state: &dyn LedgerState) -> Result<(), DexError> {
+        self.validate_with_limits(state, &BlockLimits::default())
+    }
+
+    pub fn validate_with_limits(&self, state: &dyn LedgerState, limits: &BlockLimits) -> Result<(), DexError> {
+        if self.transactions.len() > limits.max_transactions {
+            return Err(DexError::InvalidBlock(format!(
+                "{} transactions exceed limit {}",
+                self.transactions.len(),
+                limits.max_transactions
+            )));
+        }
+        let mut size = 0usize;
+        for tx in &self.transactions {
+            size += serde_json::to_vec(tx).map_err(|e| DexError::InvalidBlock(e.to_string()))?.len();
+        }
+        if size > limits.max_bytes {
+            return Err(DexError::InvalidBlock(format!(
+                "block size {} bytes exceeds limit {}",
+                size, limits.max_bytes
+            )));
+        }
+
+        let merkle_root = compute_merkle_root(&self.transactions).map_err(|e| DexError::InvalidBlock(e.to_string()))?;
+        if merkle_root != self.merkle_root {
+            return Err(DexError::InvalidBlock("merkle root mismatch".into()));
+        }
+        let hash = compute_block_hash(self.index, &self.previous_hash, self.timestamp, self.nonce, &merkle_root);
+        if hash != self.block_hash {
+            return Err(DexError::InvalidBlock("block hash mismatch".into()));
+        }
+
+        let mut seen_ids = HashSet::new();
+        let mut balances: HashMap<&str, u64> = HashMap::new();
+        // Nächste zulässige Nonce je Sender innerhalb des Blocks
+        let mut nonces: HashMap<&str, u64> = HashMap::new();
+        for tx in &self.transactions {
+            let invalid = |reason: String| DexError::InvalidTransaction { tx_id: tx.id, reason };
+            if !seen_ids.insert(tx.id) {
+                return Err(invalid("duplicate transaction id".into()));
+            }
+            tx.check(state, limits)?;
+            let min_nonce = *nonces.entry(tx.from.as_str()).or_insert_with(|| state.next_nonce(&tx.from));
+            if tx.nonce < min_nonce {
+                return Err(invalid(format!("nonce {} replayed or out of order (min {})", tx.nonce, min_nonce)));
+            }
+            nonces.insert(tx.from.as_str(), tx.nonce + 1);
+            let from_bal = *balances.entry(tx.from.as_str()).or_insert_with(|| state.balance(&tx.from));
+            if from_bal < tx.total_spend() {
+                return Err(invalid(format!(
+                    "{} has {} but spends {}",
+                    tx.from,
+                    from_bal,
+                    tx.total_spend()
+                )));
+            }
+            balances.insert(tx.from.as_str(), from_bal - tx.total_spend());
+            let to_bal = balances.entry(tx.to.as_str()).or_insert_with(|| state.balance(&tx.to));
+            *to_bal = to_bal.saturating_add(tx.amount);
+        }
+        Ok(())
+    }
+
+    /// Vollständige Annahmeprüfung: erst `validate`, dann die Block-Signatur.
+    pub fn accept(&self, public_key: &PublicKey, state: &dyn LedgerState) -> Result<(), DexError> {
+        self.validate(state)?;
+        if !self.verify_block(public_key) {
+            return Err(DexError::InvalidBlock("bad block signature".into()));
+        }
+        Ok(())
+    }
+
     /// Signiert den Block mit dem übergebenen Keypair.
     pub fn sign_block(&mut self, keypair: &Keypair) {
         // Sicherheitsaspekt: Wir signieren `block_hash`, 
@@ -63,6 +313,16 @@ impl Block {
         self.signature = Some(signature);
     }
 
+    /// Wie `sign_block`, aber mit dem Schlüssel `label` aus dem (entsperrten)
+    /// Keystore; das Secret verlässt den Keystore dabei nicht.
+    pub fn sign_block_with_keystore(&mut self, keystore: &Keystore, label: &str) -> Result<(), DexError> {
+        let signature = keystore
+            .sign(label, self.block_hash.as_bytes())
+            .map_err(|e| DexError::Other(format!("Block signing failed: {}", e)))?;
+        self.signature = Some(signature);
+        Ok(())
+    }
+
     /// Überprüft die Signatur des Blocks mit dem angegebenen öffentlichen Schlüssel.
     pub fn verify_block(&self, public_key: &PublicKey) -> bool {
         if let Some(sig) = &self.signature {
@@ -127,3 +387,143 @@ fn compute_merkle_root(transactions: &[Transaction]) -> Result<String> {
 
     Ok(tx_hashes[0].clone())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    const CHAIN: &str = "my_dex-test";
+
+    #[derive(Default)]
+    struct MemLedger {
+        balances: HashMap<String, u64>,
+        keys: HashMap<String, PublicKey>,
+        nonces: HashMap<String, u64>,
+    }
+
+    impl LedgerState for MemLedger {
+        fn balance(&self, account: &str) -> u64 {
+            *self.balances.get(account).unwrap_or(&0)
+        }
+        fn public_key(&self, account: &str) -> Option<PublicKey> {
+            self.keys.get(account).copied()
+        }
+        fn chain_id(&self) -> &str {
+            CHAIN
+        }
+        fn next_nonce(&self, account: &str) -> u64 {
+            *self.nonces.get(account).unwrap_or(&0)
+        }
+    }
+
+    fn setup() -> (MemLedger, Keypair) {
+        let kp = Keypair::generate(&mut OsRng {});
+        let mut ledger = MemLedger::default();
+        ledger.balances.insert("alice".into(), 100);
+        ledger.keys.insert("alice".into(), kp.public);
+        (ledger, kp)
+    }
+
+    fn signed(id: u32, amount: u64, kp: &Keypair) -> Transaction {
+        let mut tx = Transaction::new(id, "alice", "bob", amount).with_nonce(id as u64).with_chain_id(CHAIN);
+        tx.sign(kp).unwrap();
+        tx
+    }
+
+    #[test]
+    fn test_valid_block_accepted() {
+        let (ledger, kp) = setup();
+        let mut block = Block::new(1, "0".into(), 0, 0, vec![signed(1, 60, &kp), signed(2, 40, &kp)]).unwrap();
+        block.sign_block(&kp);
+        block.accept(&kp.public, &ledger).unwrap();
+    }
+
+    #[test]
+    fn test_production_needs_verified_clock_and_future_blocks_rejected() {
+        let (ledger, kp) = setup();
+        let guard = ClockSkewGuard::new(5_000);
+        assert!(Block::produce(&guard, 1, "0".into(), 0, vec![signed(1, 60, &kp)]).is_err());
+
+        guard.record_offset(0);
+        let mut block = Block::produce(&guard, 1, "0".into(), 0, vec![signed(1, 60, &kp)]).unwrap();
+        block.sign_block(&kp);
+        block.accept_checked(&kp.public, &ledger, &guard).unwrap();
+
+        let mut future = Block::new(2, "0".into(), block.timestamp + 3600, 0, vec![signed(1, 60, &kp)]).unwrap();
+        future.sign_block(&kp);
+        assert!(matches!(
+            future.accept_checked(&kp.public, &ledger, &guard),
+            Err(DexError::ClockSkew { .. })
+        ));
+    }
+
+    #[test]
+    fn test_oversized_block_rejected() {
+        let (ledger, kp) = setup();
+        let txs: Vec<_> = (0..10).map(|i| signed(i, 1, &kp)).collect();
+        let block = Block::new(1, "0".into(), 0, 0, txs).unwrap();
+        let limits = BlockLimits { max_transactions: 5, ..Default::default() };
+        let err = block.validate_with_limits(&ledger, &limits).unwrap_err();
+        assert!(matches!(err, DexError::InvalidBlock(_)));
+
+        let limits = BlockLimits { max_bytes: 200, ..Default::default() };
+        assert!(matches!(block.validate_with_limits(&ledger, &limits), Err(DexError::InvalidBlock(_))));
+    }
+
+    #[test]
+    fn test_overspending_transaction_rejected() {
+        let (ledger, kp) = setup();
+        // Einzeln gedeckt, zusammen 120 > 100
+        let block = Block::new(1, "0".into(), 0, 0, vec![signed(1, 70, &kp), signed(2, 50, &kp)]).unwrap();
+        match block.validate(&ledger) {
+            Err(DexError::InvalidTransaction { tx_id, .. }) => assert_eq!(tx_id, 2),
+            other => panic!("expected overspend rejection, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unsigned_or_tampered_transaction_rejected() {
+        let (ledger, kp) = setup();
+        let unsigned = Block::new(1, "0".into(), 0, 0, vec![Transaction::new(1, "alice", "bob", 1)]).unwrap();
+        assert!(unsigned.validate(&ledger).is_err());
+
+        let mut tx = signed(1, 10, &kp);
+        tx.amount = 99;
+        let tampered = Block::new(1, "0".into(), 0, 0, vec![tx]).unwrap();
+        assert!(tampered.validate(&ledger).is_err());
+    }
+
+    #[test]
+    fn test_replayed_transaction_rejected() {
+        let (mut ledger, kp) = setup();
+        let tx = signed(1, 10, &kp);
+        Block::new(1, "0".into(), 0, 0, vec![tx.clone()]).unwrap().validate(&ledger).unwrap();
+
+        // Nach Anwendung ist die Nonce verbraucht => derselbe Transfer erneut ungültig
+        ledger.nonces.insert("alice".into(), 2);
+        assert!(Block::new(2, "0".into(), 0, 0, vec![tx.clone()]).unwrap().validate(&ledger).is_err());
+
+        // Gleiche Nonce zweimal in einem Block (andere ID)
+        let (ledger, kp) = setup();
+        let mut twin = Transaction::new(2, "alice", "bob", 10).with_nonce(1).with_chain_id(CHAIN);
+        twin.sign(&kp).unwrap();
+        let block = Block::new(1, "0".into(), 0, 0, vec![signed(1, 10, &kp), twin]).unwrap();
+        match block.validate(&ledger) {
+            Err(DexError::InvalidTransaction { tx_id, .. }) => assert_eq!(tx_id, 2),
+            other => panic!("expected nonce rejection, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_transaction_for_other_chain_rejected() {
+        let (ledger, kp) = setup();
+        let mut tx = Transaction::new(1, "alice", "bob", 10).with_chain_id("other-chain");
+        tx.sign(&kp).unwrap();
+        assert!(Block::new(1, "0".into(), 0, 0, vec![tx.clone()]).unwrap().validate(&ledger).is_err());
+
+        // Chain-ID umschreiben bricht die Signatur
+        tx.chain_id = CHAIN.into();
+        assert!(!tx.verify_signature(&kp.public));
+    }
+}
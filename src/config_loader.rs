@@ -4,12 +4,26 @@
 // Enthält Felder für DB-Retries, Merge-Retries, HSM/TPM (PKCS#11), 
 // NTP, STUN/TURN, etc.
 //
+// Hot-Reload: `ConfigReloader` liest die Datei bei Änderung (mtime-Polling)
+// oder SIGHUP neu, prüft die Signatur (`<datei>.sig`, Ed25519 hex über den
+// Dateiinhalt; ohne `config_signing_key` ist Hot-Reload aus) und übernimmt
+// nur die live änderbaren Felder (`LIVE_FIELDS`). Änderungen an allen übrigen
+// Feldern (z. B. `node_id`) werden abgelehnt; der laufende Stand bleibt dann
+// unverändert. Jedes Feld in `LIVE_FIELDS` hat in main einen Abnehmer am
+// `watch`-Kanal (Log-Level direkt im Reloader).
+//
 
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
 use std::fs;
-use tracing::{info, instrument};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn, instrument};
+use crate::crypto::fallback_config::verify_config_signature;
 use crate::error::DexError;
+use crate::logging::enhanced_logging::write_audit_log;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NodeConfig {
@@ -34,13 +48,47 @@ pub struct NodeConfig {
     // Noise/TLS
     pub use_noise: bool,
 
+    /// Wiederholungen beim ausgehenden Noise-Handshake (Versuche, Backoff, Timeout)
+    #[serde(default)]
+    pub noise_handshake: crate::network::p2p_adapter::HandshakeRetryPolicy,
+
+    /// Rotation der Noise-Transportschlüssel (Nachrichten/Bytes je Richtung);
+    /// alle Nodes eines Netzes gleich setzen
+    #[serde(default)]
+    pub noise_rekey: crate::network::noise::RekeyPolicy,
+
+    /// Client-Puzzle vor dem Noise-Handshake (gegen Handshake-Floods)
+    #[serde(default)]
+    pub handshake_pow: crate::sybil::pow::PowConfig,
+
+    /// Fullnode-Zulassung nur mit Stake-Bond (sybil::stake_admission)
+    #[serde(default)]
+    pub stake_admission: crate::sybil::stake_admission::StakeAdmissionConfig,
+
+    /// Per-Epoch VRF-Sequencer vor dem MatchingEngine (consensus::sequencer)
+    #[serde(default)]
+    pub sequencer: crate::consensus::sequencer::SequencerConfig,
+
     // Identity / KeyStore
     pub keystore_path: String,
+    /// Klartext-Passphrase, nur für Demos; `keystore_pass_env`/`keystore_pass_file` haben Vorrang
+    #[serde(default)]
     pub keystore_pass: String,
+    /// Umgebungsvariable mit der Passphrase (z. B. aus einem k8s-Secret)
+    #[serde(default)]
+    pub keystore_pass_env: String,
+    /// Datei mit der Passphrase (z. B. gemountetes Secret); Zeilenumbruch am Ende zählt nicht
+    #[serde(default)]
+    pub keystore_pass_file: String,
 
     // Access Control
     pub allowed_node_pubkeys: Vec<String>,
 
+    /// Signaturen aus `allowed_node_pubkeys`, die ein Markt-Halt/-Resume braucht.
+    /// 0 => einfache Mehrheit des Committees.
+    #[serde(default)]
+    pub market_halt_threshold: usize,
+
     // Timeouts
     pub order_timeout_sec: u64,
     pub swap_timeout_sec: u64,
@@ -72,6 +120,449 @@ pub struct NodeConfig {
 
     #[serde(default)]
     pub turn_password: String,
+
+    /// Portweiterleitung für `listen_addr` am Router (UPnP, Fallback NAT-PMP).
+    /// Aus (Standard) => keine Anfragen ans Gateway, der Port bleibt zu.
+    #[serde(default)]
+    pub upnp_port_mapping: bool,
+
+    /// Kademlia-Bootstrap-Nodes als `hex-node-id@ip:port`; ergänzen das Adressbuch.
+    #[serde(default)]
+    pub kademlia_bootstrap: Vec<String>,
+
+    /// Tor: Onion-Peers anwählen und optional einen eigenen Onion-Service anbieten
+    #[serde(default)]
+    pub tor: crate::network::tor::TorSettings,
+
+    /// GeoIP/ASN-Limits für neue Kademlia-Peers (leere Country-DB => nur IP-Filter)
+    #[serde(default)]
+    pub peer_diversity: crate::network::peer_management::PeerDiversitySettings,
+
+    /// Maximale Abweichung (ms) eingehender Zeitstempel von der NTP-Zeit.
+    #[serde(default = "default_max_clock_skew_ms")]
+    pub max_clock_skew_ms: u64,
+
+    /// Höchstalter (ms) eingehender Zeitstempel; verzögerte Orders/Broadcasts
+    /// bis zu diesem Alter werden noch angenommen.
+    #[serde(default = "default_max_message_age_ms")]
+    pub max_message_age_ms: u64,
+
+    // REST-API: TLS + Auth
+    /// PEM-Zertifikat; leer => REST-Server laufen ohne TLS (nur für lokale Tests).
+    #[serde(default)]
+    pub tls_cert_path: String,
+
+    #[serde(default)]
+    pub tls_key_path: String,
+
+    /// Gültige API-Tokens für zustandsändernde Routen (Bearer oder X-API-Key).
+    #[serde(default)]
+    pub api_tokens: Vec<String>,
+
+    /// Tokens für Admin-Routen (z.B. `GET /accounts`).
+    #[serde(default)]
+    pub admin_api_tokens: Vec<String>,
+
+    /// Tokens für andere Fullnodes (Shard-Replikation, Lesezugriffe).
+    #[serde(default)]
+    pub fullnode_api_tokens: Vec<String>,
+
+    /// Tokens mit reinem Lesezugriff (Monitoring, Dashboards).
+    #[serde(default)]
+    pub readonly_api_tokens: Vec<String>,
+
+    /// An einen Account gebundene Trader-Tokens (`user_id => Token`). Nur diese
+    /// dürfen Orders des Accounts stornieren; ungebundene `api_tokens` nicht.
+    #[serde(default)]
+    pub user_api_tokens: std::collections::BTreeMap<String, String>,
+
+    /// Ed25519-Schlüssel (hex) der Publisher signierter Sanktionslisten-Updates.
+    #[serde(default)]
+    pub sanctions_publishers: Vec<String>,
+
+    /// Konflikt-Policy beim CRDT-Merge: hlc_lww | preserve_higher_priority | reject_both
+    #[serde(default = "default_crdt_conflict_policy")]
+    pub crdt_conflict_policy: String,
+
+    /// Invarianten des Order-Books nach jedem Matching prüfen (Fault-Gossip bei Verletzung).
+    #[serde(default)]
+    pub check_book_invariants: bool,
+
+    /// Paper-Trading: Matching, Fees und Audit laufen normal, das Settlement
+    /// bucht aber nur in ein simuliertes Ledger (keine Wallet-/Chain-Aufrufe).
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// Rolle des Nodes; `follower` synchronisiert nur und bedient Lese-APIs.
+    #[serde(default)]
+    pub role: NodeRole,
+
+    /// Nebenläufigkeit des Matchings: `per_market` (jeder Markt eigener Lock
+    /// und eigener Task) oder `serial` (alle Märkte nacheinander, ein Lock).
+    #[serde(default)]
+    pub matching_concurrency: MatchingConcurrency,
+
+    /// Settlement-Fees (live änderbar)
+    #[serde(default)]
+    pub settlement_fees: FeeScheduleConfig,
+
+    /// Maker/Taker-Raten je Markt fürs Matching (negative Maker-Rate => Rebate)
+    #[serde(default)]
+    pub trading_fees: crate::matching_engine::FeeSchedule,
+
+    /// Überschuss des Fee-Pools über der Hot-Schwelle an eine Multisig-Cold-Adresse
+    #[serde(default)]
+    pub fee_cold_sweep: Option<crate::fees::fee_pool::ColdSweepPolicy>,
+
+    /// Circuit-Breaker je Markt: Matching-Pause bei großen Preissprüngen
+    #[serde(default)]
+    pub circuit_breakers: crate::dex_logic::circuit_breaker::CircuitBreakerConfig,
+
+    /// Mindestmenge und Höchst-Gegenwert je Order und Markt
+    #[serde(default)]
+    pub order_limits: crate::matching_engine::OrderLimits,
+
+    /// Obergrenzen ruhender Orders je User/Markt samt Verdrängungs-Policy
+    #[serde(default)]
+    pub book_caps: crate::matching_engine::BookCaps,
+
+    /// Grenzwerte für /health/detailed und /readyz
+    #[serde(default)]
+    pub health: crate::health_report::HealthThresholds,
+
+    /// Preisquellen und Quorum des PriceFeeds
+    #[serde(default)]
+    pub price_feed: crate::crypto_scraper::price_feed::PriceFeedConfig,
+
+    /// DKG-Komitee für den Onboarding-Schlüssel (leer => keine Zeremonie)
+    #[serde(default)]
+    pub onboarding_dkg: crate::onboarding::dkg::DkgCommitteeConfig,
+
+    /// SWIM-Membership (UDP, signiert)
+    #[serde(default)]
+    pub swim: crate::network::cluster_management::SwimSettings,
+
+    /// Onboarding-Modus (Admin-Keys, Schwelle für den Wechsel zu Auto)
+    #[serde(default)]
+    pub onboarding: crate::onboarding::auto_committee::OnboardingSettings,
+
+    /// Subnetz-Rate-Limits (live änderbar)
+    #[serde(default)]
+    pub rate_limits: RateLimitConfig,
+
+    /// Prüfintervall des Self-Healing-Watchdogs (live änderbar);
+    /// 0 => `interval_sec` je Dienst aus watchdog.toml
+    #[serde(default)]
+    pub watchdog_interval_sec: u64,
+
+    /// Ed25519-Key (hex), der die Config signiert (`<datei>.sig`). Leer => kein Hot-Reload.
+    #[serde(default)]
+    pub config_signing_key: String,
+}
+
+/// `Full` matcht, settled und schlägt Blöcke vor. `Follower` tritt dem DHT bei,
+/// synchronisiert CRDT-State und Trades und beantwortet Lese-Anfragen,
+/// lehnt aber jede zustandsändernde Trading-Operation ab (Monitoring/Analytics).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeRole {
+    #[default]
+    Full,
+    Follower,
+}
+
+impl NodeRole {
+    pub fn is_follower(self) -> bool {
+        self == NodeRole::Follower
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchingConcurrency {
+    Serial,
+    #[default]
+    PerMarket,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FeeScheduleConfig {
+    pub standard: f64,
+    pub atomic_swap: f64,
+}
+
+impl Default for FeeScheduleConfig {
+    fn default() -> Self {
+        Self { standard: 0.001, atomic_swap: 0.002 }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RateLimitConfig {
+    pub subnet_capacity: u64,
+    pub subnet_refill_per_sec: u64,
+    pub spike_threshold_per_sec: u64,
+    pub tightened_cost: u64,
+    #[serde(default = "default_subnet_idle_ttl_sec")]
+    pub subnet_idle_ttl_sec: u64,
+    #[serde(default = "default_max_subnets")]
+    pub max_subnets: usize,
+}
+
+fn default_subnet_idle_ttl_sec() -> u64 {
+    crate::rate_limiting::subnet_limiter::SubnetLimiterConfig::default().idle_ttl.as_secs()
+}
+
+fn default_max_subnets() -> usize {
+    crate::rate_limiting::subnet_limiter::SubnetLimiterConfig::default().max_subnets
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        let d = crate::rate_limiting::subnet_limiter::SubnetLimiterConfig::default();
+        Self {
+            subnet_capacity: d.subnet_capacity,
+            subnet_refill_per_sec: d.subnet_refill_per_sec,
+            spike_threshold_per_sec: d.spike_threshold_per_sec,
+            tightened_cost: d.tightened_cost,
+            subnet_idle_ttl_sec: d.idle_ttl.as_secs(),
+            max_subnets: d.max_subnets,
+        }
+    }
+}
+
+impl From<&RateLimitConfig> for crate::rate_limiting::subnet_limiter::SubnetLimiterConfig {
+    fn from(c: &RateLimitConfig) -> Self {
+        Self {
+            subnet_capacity: c.subnet_capacity,
+            subnet_refill_per_sec: c.subnet_refill_per_sec,
+            spike_threshold_per_sec: c.spike_threshold_per_sec,
+            tightened_cost: c.tightened_cost,
+            idle_ttl: std::time::Duration::from_secs(c.subnet_idle_ttl_sec),
+            max_subnets: c.max_subnets,
+        }
+    }
+}
+
+/// Felder, die ohne Neustart übernommen werden.
+/// Abnehmer: Log-Level (Reloader), `trading_fees` (MatchingEngine),
+/// `rate_limits` (SubnetRateLimiter der P2P-Security), `watchdog_interval_sec`.
+pub const LIVE_FIELDS: &[&str] = &["log_level", "trading_fees", "rate_limits", "watchdog_interval_sec"];
+
+fn default_crdt_conflict_policy() -> String {
+    "hlc_lww".to_string()
+}
+
+fn default_max_clock_skew_ms() -> u64 {
+    crate::utils::geoip_and_ntp::DEFAULT_MAX_CLOCK_SKEW_MS
+}
+
+fn default_max_message_age_ms() -> u64 {
+    crate::utils::geoip_and_ntp::DEFAULT_MAX_MESSAGE_AGE_MS
+}
+
+const CONFLICT_POLICIES: &[&str] = &["hlc_lww", "preserve_higher_priority", "reject_both"];
+
+/// Präfix der Demo-Tokens in `config/node_config.yaml`.
+const PLACEHOLDER_TOKEN_PREFIX: &str = "CHANGE_ME";
+
+/// Leere Tokens und unveränderte Demo-Tokens dürfen nie authentifizieren.
+pub fn is_placeholder_token(token: &str) -> bool {
+    let t = token.trim();
+    t.is_empty() || t.starts_with(PLACEHOLDER_TOKEN_PREFIX)
+}
+
+fn is_ed25519_hex(key: &str) -> bool {
+    hex::decode(key)
+        .ok()
+        .and_then(|b| ed25519_dalek::PublicKey::from_bytes(&b).ok())
+        .is_some()
+}
+
+fn invalid(field: &str, reason: impl Into<String>) -> DexError {
+    DexError::InvalidConfig { field: field.to_string(), reason: reason.into() }
+}
+
+fn check_rate(field: &str, v: f64) -> Result<(), DexError> {
+    if !v.is_finite() || v < 0.0 {
+        return Err(invalid(field, format!("fee rate {} must be >= 0", v)));
+    }
+    if v > 1.0 {
+        return Err(invalid(field, format!("fee rate {} exceeds 100%", v)));
+    }
+    Ok(())
+}
+
+impl NodeConfig {
+    /// Prüft alle Werte auf Plausibilität; liefert den ersten Verstoß mit Feldnamen.
+    pub fn validate(&self) -> Result<(), DexError> {
+        if self.node_id.trim().is_empty() {
+            return Err(invalid("node_id", "must not be empty"));
+        }
+        for (field, addr) in [("listen_addr", &self.listen_addr), ("metrics_addr", &self.metrics_addr)] {
+            if addr.parse::<std::net::SocketAddr>().is_err() {
+                return Err(invalid(field, format!("`{}` is not a socket address", addr)));
+            }
+        }
+        if self.db_path.trim().is_empty() {
+            return Err(invalid("db_path", "must not be empty"));
+        }
+        if self.db_max_retries == 0 {
+            return Err(invalid("db_max_retries", "must be >= 1, 0 would fall back to the in-memory DB without trying"));
+        }
+        if self.max_message_age_ms < self.max_clock_skew_ms {
+            return Err(invalid("max_message_age_ms", "must be >= max_clock_skew_ms"));
+        }
+        for (field, v) in [
+            ("atomic_swap_timeout_sec", self.atomic_swap_timeout_sec),
+            ("crdt_merge_interval_sec", self.crdt_merge_interval_sec),
+            ("order_timeout_sec", self.order_timeout_sec),
+            ("swap_timeout_sec", self.swap_timeout_sec),
+            ("num_shards", self.num_shards as u64),
+        ] {
+            if v == 0 {
+                return Err(invalid(field, "must be > 0"));
+            }
+        }
+        check_rate("settlement_fees.standard", self.settlement_fees.standard)?;
+        check_rate("settlement_fees.atomic_swap", self.settlement_fees.atomic_swap)?;
+        self.trading_fees.validate()?;
+        self.order_limits.validate()?;
+        self.circuit_breakers.default.validate()
+            .map_err(|e| invalid("circuit_breakers.default", e))?;
+        for (market, t) in &self.circuit_breakers.markets {
+            t.validate().map_err(|e| invalid(&format!("circuit_breakers.markets.{}", market), e))?;
+        }
+        self.price_feed.validate().map_err(|e| invalid("price_feed", e))?;
+        self.onboarding_dkg.validate().map_err(|e| invalid("onboarding_dkg", e))?;
+        self.onboarding.validate().map_err(|e| invalid("onboarding", e))?;
+        self.swim.validate().map_err(|e| invalid("swim", e))?;
+        self.tor.validate().map_err(|e| invalid("tor", e))?;
+        self.peer_diversity.validate().map_err(|e| invalid("peer_diversity", e))?;
+        self.handshake_pow.validate().map_err(|e| invalid("handshake_pow", e))?;
+        self.stake_admission.validate().map_err(|e| invalid("stake_admission", e))?;
+        self.sequencer.validate().map_err(|e| invalid("sequencer", e))?;
+        if let Some(sweep) = &self.fee_cold_sweep {
+            sweep.validate().map_err(|e| invalid("fee_cold_sweep", e))?;
+        }
+        if !self.partial_fill_min_amount.is_finite() || self.partial_fill_min_amount < 0.0 {
+            return Err(invalid("partial_fill_min_amount", "must be >= 0"));
+        }
+        if self.rate_limits.subnet_capacity == 0 || self.rate_limits.subnet_refill_per_sec == 0 {
+            return Err(invalid("rate_limits", "capacity and refill must be > 0"));
+        }
+        if self.rate_limits.subnet_idle_ttl_sec == 0 || self.rate_limits.max_subnets == 0 {
+            return Err(invalid("rate_limits", "subnet_idle_ttl_sec and max_subnets must be > 0"));
+        }
+        if self.noise_rekey.max_messages == 0 && self.noise_rekey.max_bytes == 0 {
+            return Err(invalid("noise_rekey", "max_messages or max_bytes must be > 0"));
+        }
+        if !CONFLICT_POLICIES.contains(&self.crdt_conflict_policy.as_str()) {
+            return Err(invalid("crdt_conflict_policy", format!("unknown policy `{}`", self.crdt_conflict_policy)));
+        }
+
+        // Widersprüchliche Sicherheits-Flags
+        if self.use_hardware && self.pkcs11_lib_path.trim().is_empty() {
+            return Err(invalid("pkcs11_lib_path", "required when use_hardware = true"));
+        }
+        if self.tls_cert_path.is_empty() != self.tls_key_path.is_empty() {
+            return Err(invalid("tls_key_path", "tls_cert_path and tls_key_path must be set together"));
+        }
+        if self.keystore_pass.is_empty() && self.keystore_pass_env.trim().is_empty() && self.keystore_pass_file.trim().is_empty() {
+            return Err(invalid("keystore_pass", "set keystore_pass, keystore_pass_env or keystore_pass_file"));
+        }
+        if self.market_halt_threshold > self.allowed_node_pubkeys.len() {
+            return Err(invalid("market_halt_threshold", "exceeds the number of allowed_node_pubkeys"));
+        }
+        if !self.use_noise && !self.allowed_node_pubkeys.is_empty() {
+            return Err(invalid("use_noise", "allowed_node_pubkeys needs the Noise handshake to authenticate peers"));
+        }
+        for entry in &self.kademlia_bootstrap {
+            crate::network::address_book::parse_bootstrap_peer(entry)
+                .map_err(|e| invalid("kademlia_bootstrap", e))?;
+        }
+        for (i, key) in self.sanctions_publishers.iter().enumerate() {
+            if !is_ed25519_hex(key) {
+                return Err(invalid("sanctions_publishers", format!("entry {} is not a hex Ed25519 public key", i)));
+            }
+        }
+        if !self.config_signing_key.is_empty() {
+            if !is_ed25519_hex(&self.config_signing_key) {
+                return Err(invalid("config_signing_key", "not a hex Ed25519 public key"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Prüfungen vor dem Start der REST-Server: keine Platzhalter-Tokens aus der
+    /// Beispiel-Config und, falls TLS konfiguriert ist, lesbare Zertifikat-Dateien.
+    /// Getrennt von `validate`, damit Tests die Beispiel-Config weiter laden können.
+    pub fn check_rest_security(&self) -> Result<(), DexError> {
+        for (field, tokens) in [
+            ("api_tokens", &self.api_tokens),
+            ("admin_api_tokens", &self.admin_api_tokens),
+            ("fullnode_api_tokens", &self.fullnode_api_tokens),
+            ("readonly_api_tokens", &self.readonly_api_tokens),
+        ] {
+            if tokens.iter().any(|t| is_placeholder_token(t)) {
+                return Err(invalid(field, "contains an empty or placeholder (CHANGE_ME) token"));
+            }
+        }
+        if self.user_api_tokens.values().any(|t| is_placeholder_token(t)) {
+            return Err(invalid("user_api_tokens", "contains an empty or placeholder (CHANGE_ME) token"));
+        }
+        if let Some((cert, key)) = self.tls_paths() {
+            for (field, path) in [("tls_cert_path", &cert), ("tls_key_path", &key)] {
+                if !Path::new(path).is_file() {
+                    return Err(invalid(field, format!("file `{}` not found", path)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Keystore-Passphrase: erst `keystore_pass_env` (falls gesetzt und belegt),
+    /// dann `keystore_pass_file`, zuletzt der Klartext `keystore_pass`.
+    pub fn keystore_passphrase(&self) -> Result<String, DexError> {
+        let env_name = self.keystore_pass_env.trim();
+        if !env_name.is_empty() {
+            match std::env::var(env_name) {
+                Ok(pass) if !pass.is_empty() => return Ok(pass),
+                _ => warn!("keystore_pass_env: Variable {} nicht gesetzt", env_name),
+            }
+        }
+        let file = self.keystore_pass_file.trim();
+        if !file.is_empty() {
+            let content = fs::read_to_string(file)
+                .map_err(|e| invalid("keystore_pass_file", format!("`{}` not readable: {}", file, e)))?;
+            let pass = content.trim_end_matches(&['\r', '\n'][..]);
+            if pass.is_empty() {
+                return Err(invalid("keystore_pass_file", format!("`{}` is empty", file)));
+            }
+            return Ok(pass.to_string());
+        }
+        if self.keystore_pass.is_empty() {
+            return Err(invalid("keystore_pass", "no passphrase from keystore_pass_env, keystore_pass_file or keystore_pass"));
+        }
+        Ok(self.keystore_pass.clone())
+    }
+
+    /// Effektiver Threshold für `MarketHaltControl`.
+    pub fn market_halt_threshold(&self) -> usize {
+        match self.market_halt_threshold {
+            0 => self.allowed_node_pubkeys.len() / 2 + 1,
+            t => t,
+        }
+    }
+
+    /// Zertifikat- und Key-Pfad, falls beide gesetzt sind.
+    pub fn tls_paths(&self) -> Option<(String, String)> {
+        if self.tls_cert_path.is_empty() || self.tls_key_path.is_empty() {
+            None
+        } else {
+            Some((self.tls_cert_path.clone(), self.tls_key_path.clone()))
+        }
+    }
 }
 
 /// Lädt die Config aus einer YAML-Datei.
@@ -98,3 +589,391 @@ pub fn load_config(path: &str) -> Result<NodeConfig> {
 
     Ok(cfg)
 }
+
+/// Felder, in denen sich `new` von `old` unterscheidet (Top-Level-Namen).
+fn changed_fields(old: &NodeConfig, new: &NodeConfig) -> Result<Vec<String>, DexError> {
+    let to_map = |c: &NodeConfig| match serde_json::to_value(c) {
+        Ok(serde_json::Value::Object(m)) => Ok(m),
+        _ => Err(DexError::Other("NodeConfig not serialisable".into())),
+    };
+    let (a, b) = (to_map(old)?, to_map(new)?);
+    let mut changed: Vec<String> = a
+        .keys()
+        .chain(b.keys())
+        .filter(|k| a.get(*k) != b.get(*k))
+        .cloned()
+        .collect();
+    changed.sort();
+    changed.dedup();
+    Ok(changed)
+}
+
+/// Ergebnis eines Reload-Versuchs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReloadOutcome {
+    Unchanged,
+    Applied(Vec<String>),
+}
+
+/// Wird mit dem neuen Log-Level aufgerufen (Standard: `set_log_level`).
+pub type LogLevelHook = Box<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// Beobachtet die Config-Datei und verteilt neue Stände per `watch`-Kanal.
+pub struct ConfigReloader {
+    path: PathBuf,
+    tx: watch::Sender<NodeConfig>,
+    apply_log_level: LogLevelHook,
+    last_mtime: Option<SystemTime>,
+}
+
+impl ConfigReloader {
+    pub fn new(path: impl Into<PathBuf>, initial: NodeConfig) -> Self {
+        let path = path.into();
+        let last_mtime = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let (tx, _rx) = watch::channel(initial);
+        Self {
+            path,
+            tx,
+            apply_log_level: Box::new(crate::logging::enhanced_logging::set_log_level),
+            last_mtime,
+        }
+    }
+
+    pub fn with_log_level_hook(mut self, hook: LogLevelHook) -> Self {
+        self.apply_log_level = hook;
+        self
+    }
+
+    /// Empfänger für Fee-/Rate-Limit-/Watchdog-Komponenten.
+    pub fn subscribe(&self) -> watch::Receiver<NodeConfig> {
+        self.tx.subscribe()
+    }
+
+    pub fn current(&self) -> NodeConfig {
+        self.tx.borrow().clone()
+    }
+
+    fn verify_signature(&self, content: &str, key_hex: &str) -> Result<(), DexError> {
+        if key_hex.is_empty() {
+            return Err(DexError::Other("Config-Reload ohne config_signing_key deaktiviert".into()));
+        }
+        let sig_path = format!("{}.sig", self.path.display());
+        let sig = fs::read_to_string(&sig_path)
+            .map_err(|e| DexError::Other(format!("Config-Signatur {} fehlt: {:?}", sig_path, e)))?;
+        if !verify_config_signature(content, sig.trim(), key_hex) {
+            return Err(DexError::Other("Config-Signatur ungültig".into()));
+        }
+        Ok(())
+    }
+
+    /// Liest die Datei neu und übernimmt die live änderbaren Felder.
+    pub fn reload_now(&mut self) -> Result<ReloadOutcome, DexError> {
+        self.last_mtime = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| DexError::Other(format!("Fehler beim Lesen der Config-Datei {}: {:?}", self.path.display(), e)))?;
+        let current = self.current();
+        // Signatur gegen den *laufenden* Schlüssel prüfen – ein neuer Key in der Datei zählt nicht
+        self.verify_signature(&content, &current.config_signing_key)?;
+        let new: NodeConfig = serde_yaml::from_str(&content)
+            .map_err(|e| DexError::Other(format!("YAML-Deserialization error: {:?}", e)))?;
+        new.validate()?;
+
+        let changed = changed_fields(&current, &new)?;
+        if changed.is_empty() {
+            return Ok(ReloadOutcome::Unchanged);
+        }
+        let immutable: Vec<&String> = changed.iter().filter(|f| !LIVE_FIELDS.contains(&f.as_str())).collect();
+        if !immutable.is_empty() {
+            write_audit_log(&format!("Config-Reload abgelehnt: unveränderliche Felder geändert {:?}", immutable));
+            return Err(DexError::Other(format!("Config-Reload: Felder {:?} erfordern einen Neustart", immutable)));
+        }
+
+        if new.log_level != current.log_level {
+            (self.apply_log_level)(&new.log_level).map_err(DexError::Other)?;
+        }
+        self.tx.send_replace(new);
+        write_audit_log(&format!("Config neu geladen: {:?}", changed));
+        info!("Config-Reload => übernommen: {:?}", changed);
+        Ok(ReloadOutcome::Applied(changed))
+    }
+
+    fn file_changed(&self) -> bool {
+        let mtime = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        mtime.is_some() && mtime != self.last_mtime
+    }
+
+    /// Pollt die Datei alle `poll` und reagiert (Unix) zusätzlich auf SIGHUP.
+    /// Ohne `config_signing_key` bleibt nur der Kanal (Startstand) bestehen.
+    pub async fn run(mut self, poll: Duration, token: CancellationToken) {
+        if self.current().config_signing_key.is_empty() {
+            warn!("Config-Hot-Reload deaktiviert: config_signing_key ist leer");
+            token.cancelled().await;
+            return;
+        }
+        #[cfg(unix)]
+        let mut hup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                warn!("SIGHUP-Handler nicht verfügbar: {:?}", e);
+                None
+            }
+        };
+        let mut iv = tokio::time::interval(poll);
+        loop {
+            #[cfg(unix)]
+            let sighup = async {
+                match hup.as_mut() {
+                    Some(s) => {
+                        s.recv().await;
+                    }
+                    None => std::future::pending::<()>().await,
+                }
+            };
+            #[cfg(not(unix))]
+            let sighup = std::future::pending::<()>();
+
+            let forced = tokio::select! {
+                _ = token.cancelled() => break,
+                _ = iv.tick() => false,
+                _ = sighup => true,
+            };
+            if forced || self.file_changed() {
+                if let Err(e) = self.reload_now() {
+                    warn!("Config-Reload fehlgeschlagen, bisheriger Stand bleibt aktiv: {}", e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    const BASE: &str = r#"
+node_id: "NodeA"
+listen_addr: "127.0.0.1:9000"
+metrics_addr: "127.0.0.1:9100"
+jaeger_addr: "127.0.0.1:14268/api/traces"
+atomic_swap_timeout_sec: 30
+crdt_merge_interval_sec: 10
+log_level: "info"
+db_path: "dex_data"
+db_max_retries: 3
+db_backoff_sec: 2
+merge_max_retries: 3
+merge_backoff_sec: 1
+use_noise: true
+keystore_path: "keystore.json"
+keystore_pass: "x"
+allowed_node_pubkeys: []
+order_timeout_sec: 86400
+swap_timeout_sec: 7200
+num_shards: 8
+partial_fill_min_amount: 0.0001
+use_hardware: false
+pkcs11_lib_path: ""
+slot_id: 0
+hsm_pin: ""
+"#;
+
+    fn signing_key() -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[9; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        ed25519_dalek::Keypair { secret, public }
+    }
+
+    /// BASE mit eingetragenem `config_signing_key`.
+    fn signed_base() -> String {
+        format!("{}config_signing_key: \"{}\"\n", BASE, hex::encode(signing_key().public.to_bytes()))
+    }
+
+    /// Schreibt die Config samt `<datei>.sig`.
+    fn write_signed(path: &Path, content: &str) {
+        use ed25519_dalek::Signer;
+        fs::write(path, content).unwrap();
+        let sig = signing_key().sign(content.as_bytes());
+        fs::write(format!("{}.sig", path.display()), hex::encode(sig.to_bytes())).unwrap();
+    }
+
+    fn setup() -> (tempfile::TempDir, PathBuf, ConfigReloader, Arc<Mutex<Vec<String>>>) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node_config.yaml");
+        write_signed(&path, &signed_base());
+        let cfg = load_config(path.to_str().unwrap()).unwrap();
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let sink = applied.clone();
+        let reloader = ConfigReloader::new(&path, cfg).with_log_level_hook(Box::new(move |lvl| {
+            sink.lock().unwrap().push(lvl.to_string());
+            Ok(())
+        }));
+        (dir, path, reloader, applied)
+    }
+
+    fn base() -> NodeConfig {
+        serde_yaml::from_str(BASE).unwrap()
+    }
+
+    #[test]
+    fn test_validate_accepts_base() {
+        assert!(base().validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_each_invalid_field() {
+        let cases: Vec<(&str, Box<dyn Fn(&mut NodeConfig)>)> = vec![
+            ("node_id", Box::new(|c| c.node_id = " ".into())),
+            ("listen_addr", Box::new(|c| c.listen_addr = "localhost".into())),
+            ("metrics_addr", Box::new(|c| c.metrics_addr = "127.0.0.1".into())),
+            ("db_path", Box::new(|c| c.db_path = String::new())),
+            ("db_max_retries", Box::new(|c| c.db_max_retries = 0)),
+            ("max_message_age_ms", Box::new(|c| c.max_message_age_ms = c.max_clock_skew_ms - 1)),
+            ("atomic_swap_timeout_sec", Box::new(|c| c.atomic_swap_timeout_sec = 0)),
+            ("crdt_merge_interval_sec", Box::new(|c| c.crdt_merge_interval_sec = 0)),
+            ("order_timeout_sec", Box::new(|c| c.order_timeout_sec = 0)),
+            ("swap_timeout_sec", Box::new(|c| c.swap_timeout_sec = 0)),
+            ("num_shards", Box::new(|c| c.num_shards = 0)),
+            ("settlement_fees.standard", Box::new(|c| c.settlement_fees.standard = -0.01)),
+            ("settlement_fees.atomic_swap", Box::new(|c| c.settlement_fees.atomic_swap = 1.5)),
+            ("trading_fees.default", Box::new(|c| c.trading_fees.default.maker_rate = -0.01)),
+            ("order_limits.default", Box::new(|c| c.order_limits.default.min_quantity = f64::NAN)),
+            ("circuit_breakers.default", Box::new(|c| c.circuit_breakers.default.cooldown_secs = 0)),
+            ("fee_cold_sweep", Box::new(|c| c.fee_cold_sweep = Some(crate::fees::fee_pool::ColdSweepPolicy {
+                hot_threshold: 100.0,
+                cold_address: "bc1q-cold".into(),
+                hot_wallet_id: "w_fee_hot".into(),
+                multisig_signers: vec!["a".into()],
+                multisig_required: 2,
+            }))),
+            ("price_feed", Box::new(|c| c.price_feed.max_deviation = 0.0)),
+            ("onboarding_dkg", Box::new(|c| c.onboarding_dkg.participants = vec![Default::default()])),
+            ("onboarding", Box::new(|c| c.onboarding.required_count_for_auto = 0)),
+            ("swim", Box::new(|c| c.swim.seeds = vec!["not-an-addr".into()])),
+            ("tor", Box::new(|c| c.tor.onion_service = "mydex".into())),
+            ("peer_diversity", Box::new(|c| c.peer_diversity.geoip_country_db = "/nonexistent/country.mmdb".into())),
+            ("handshake_pow", Box::new(|c| c.handshake_pow.max_difficulty = 30)),
+            ("stake_admission", Box::new(|c| {
+                c.stake_admission.enabled = true;
+                c.stake_admission.min_bond = 0;
+            })),
+            ("sequencer", Box::new(|c| {
+                c.sequencer.enabled = true;
+                c.sequencer.claim_window_ms = c.sequencer.epoch_secs * 1_000;
+            })),
+            ("partial_fill_min_amount", Box::new(|c| c.partial_fill_min_amount = f64::NAN)),
+            ("rate_limits", Box::new(|c| c.rate_limits.subnet_capacity = 0)),
+            ("rate_limits", Box::new(|c| c.rate_limits.max_subnets = 0)),
+            ("noise_rekey", Box::new(|c| c.noise_rekey = crate::network::noise::RekeyPolicy { max_messages: 0, max_bytes: 0 })),
+            ("crdt_conflict_policy", Box::new(|c| c.crdt_conflict_policy = "newest".into())),
+            ("pkcs11_lib_path", Box::new(|c| c.use_hardware = true)),
+            ("tls_key_path", Box::new(|c| c.tls_cert_path = "cert.pem".into())),
+            ("use_noise", Box::new(|c| {
+                c.use_noise = false;
+                c.allowed_node_pubkeys = vec!["ab".into()];
+            })),
+            ("config_signing_key", Box::new(|c| c.config_signing_key = "zz".into())),
+            ("market_halt_threshold", Box::new(|c| c.market_halt_threshold = 1)),
+            ("keystore_pass", Box::new(|c| c.keystore_pass = String::new())),
+            ("sanctions_publishers", Box::new(|c| c.sanctions_publishers = vec!["abcd".into()])),
+            ("kademlia_bootstrap", Box::new(|c| c.kademlia_bootstrap = vec!["127.0.0.1:9000".into()])),
+        ];
+        for (expected, mutate) in cases {
+            let mut cfg = base();
+            mutate(&mut cfg);
+            match cfg.validate() {
+                Err(DexError::InvalidConfig { field, .. }) => assert_eq!(field, expected),
+                other => panic!("{}: expected InvalidConfig, got {:?}", expected, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_keystore_passphrase_sources() {
+        let mut cfg = base();
+        assert_eq!(cfg.keystore_passphrase().unwrap(), "x");
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("keystore_pass");
+        fs::write(&file, "from-file\n").unwrap();
+        cfg.keystore_pass_file = file.to_string_lossy().into_owned();
+        assert_eq!(cfg.keystore_passphrase().unwrap(), "from-file");
+
+        // Env-Variable hat Vorrang; nicht gesetzt => nächste Quelle
+        cfg.keystore_pass_env = "MY_DEX_TEST_KEYSTORE_PASS_1624".into();
+        assert_eq!(cfg.keystore_passphrase().unwrap(), "from-file");
+        std::env::set_var("MY_DEX_TEST_KEYSTORE_PASS_1624", "from-env");
+        assert_eq!(cfg.keystore_passphrase().unwrap(), "from-env");
+        std::env::remove_var("MY_DEX_TEST_KEYSTORE_PASS_1624");
+
+        cfg.keystore_pass_file = dir.path().join("missing").to_string_lossy().into_owned();
+        assert!(matches!(cfg.keystore_passphrase(), Err(DexError::InvalidConfig { field, .. }) if field == "keystore_pass_file"));
+    }
+
+    #[test]
+    fn test_rest_security_rejects_placeholders_and_missing_certs() {
+        let field_of = |cfg: &NodeConfig| match cfg.check_rest_security() {
+            Err(DexError::InvalidConfig { field, .. }) => field,
+            other => panic!("expected InvalidConfig, got {:?}", other),
+        };
+        let mut cfg = base();
+        cfg.api_tokens = vec!["real-token".into()];
+        assert!(cfg.check_rest_security().is_ok());
+
+        cfg.admin_api_tokens = vec!["CHANGE_ME_ADMIN_TOKEN".into()];
+        assert_eq!(field_of(&cfg), "admin_api_tokens");
+        cfg.admin_api_tokens = vec![" ".into()];
+        assert_eq!(field_of(&cfg), "admin_api_tokens");
+        cfg.admin_api_tokens.clear();
+
+        let dir = tempfile::tempdir().unwrap();
+        let cert = dir.path().join("cert.pem");
+        cfg.tls_cert_path = cert.to_string_lossy().into_owned();
+        cfg.tls_key_path = dir.path().join("key.pem").to_string_lossy().into_owned();
+        assert_eq!(field_of(&cfg), "tls_cert_path");
+        fs::write(&cert, "pem").unwrap();
+        assert_eq!(field_of(&cfg), "tls_key_path");
+    }
+
+    #[test]
+    fn test_log_level_change_applies_live() {
+        let (_dir, path, mut reloader, applied) = setup();
+        let rx = reloader.subscribe();
+        assert_eq!(reloader.reload_now().unwrap(), ReloadOutcome::Unchanged);
+
+        write_signed(&path, &signed_base().replace("log_level: \"info\"", "log_level: \"debug\""));
+        let outcome = reloader.reload_now().unwrap();
+        assert_eq!(outcome, ReloadOutcome::Applied(vec!["log_level".into()]));
+        assert_eq!(applied.lock().unwrap().as_slice(), ["debug".to_string()]);
+        assert_eq!(rx.borrow().log_level, "debug");
+    }
+
+    #[test]
+    fn test_immutable_field_change_rejected() {
+        let (_dir, path, mut reloader, applied) = setup();
+        let changed = signed_base()
+            .replace("node_id: \"NodeA\"", "node_id: \"NodeB\"")
+            .replace("log_level: \"info\"", "log_level: \"debug\"");
+        write_signed(&path, &changed);
+        assert!(reloader.reload_now().is_err());
+        assert_eq!(reloader.current().node_id, "NodeA");
+        assert_eq!(reloader.current().log_level, "info");
+        assert!(applied.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unsigned_reload_rejected() {
+        let (_dir, path, mut reloader, applied) = setup();
+        // Inhalt geändert, Signatur passt nicht mehr
+        fs::write(&path, signed_base().replace("log_level: \"info\"", "log_level: \"debug\"")).unwrap();
+        assert!(reloader.reload_now().is_err());
+        assert_eq!(reloader.current().log_level, "info");
+
+        // Ohne config_signing_key gibt es gar keinen Hot-Reload
+        fs::write(&path, BASE.replace("log_level: \"info\"", "log_level: \"debug\"")).unwrap();
+        let mut unsigned = ConfigReloader::new(&path, base());
+        assert!(unsigned.reload_now().is_err());
+        assert_eq!(unsigned.current().log_level, "info");
+        assert!(applied.lock().unwrap().is_empty());
+    }
+}
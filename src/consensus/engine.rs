@@ -1,53 +1,309 @@
 ///////////////////////////////////////////////////////
 // my_dex/src/consensus/engine.rs
-/////////////////////////////////////////////////////// 
+///////////////////////////////////////////////////////
+//
+// Blockproduktion auf der NakamotoChain:
+//  - eigener Block: Mempool-Auswahl + zurückgestellte Transaktionen, PoW mit
+//    `min_difficulty`, Signatur mit dem Keystore-Schlüssel des Nodes, danach
+//    werden die Shard-Roots an den Block verankert und die Gebühren der
+//    aufgenommenen Transaktionen dem Fee-Pool gutgeschrieben
+//  - fremder Block (Kademlia): PoW-/Signaturprüfung + Fork-Choice über die Chain
+//  - Reorg: Checkpoints verwaister Blöcke werden an der neuen Spitze neu verankert
+
+use super::{
+    nakamoto::{validate_block, ChainUpdate, NakamotoBlock, NakamotoChain},
+    pbft::PBFTNode,
+    vrf::VRFValidatorSelection,
+};
+use crate::block::BlockLimits;
+use crate::error::DexError;
+use crate::fees::fee_pool::FeePool;
+use crate::mempool::Mempool;
+use crate::shard_logic::ShardManager;
+use ed25519_dalek::Keypair;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::time::{interval, Duration};
+use tracing::{debug, info, warn};
 
-use super::{vrf::VRFValidatorSelection, pbft::PBFTNode, nakamoto::NakamotoBlock};
-use std::collections::HashMap;
-use tokio::sync::mpsc;
-use tokio::time::{sleep, Duration};
+/// Führende Hex-Nullen für eigene Blöcke (und Mindestwert für fremde).
+pub const DEFAULT_BLOCK_DIFFICULTY: usize = 2;
+/// Blöcke unterhalb von Spitze minus dieser Tiefe gelten als final.
+pub const DEFAULT_FINALITY_DEPTH: u64 = 6;
 
 pub struct ConsensusEngine {
     pub validators: Vec<String>,
     pub current_validator: String,
     pub pbft_node: PBFTNode,
-    pub blockchain: Vec<NakamotoBlock>,
-    pub network_sender: mpsc::Sender<String>,
+    pub chain: NakamotoChain,
+    /// Quelle der Transaktionen für neue Blöcke (höchste Gebühr zuerst)
+    pub mempool: Arc<Mutex<Mempool>>,
+    pub block_limits: BlockLimits,
+    /// Shard-Roots werden an eigene Blöcke verankert (None => keine Checkpoints)
+    pub shard_manager: Option<Arc<ShardManager>>,
+    /// Schlüssel für eigene Blöcke (`BLOCK_SIGNING_LABEL` im Keystore; None => unsigniert)
+    pub signing_key: Option<Arc<Keypair>>,
+    /// Empfänger der Transaktionsgebühren eigener Blöcke (None => nicht gutgeschrieben)
+    pub fee_pool: Option<FeePool>,
 }
 
 impl ConsensusEngine {
-    pub fn new(peers: Vec<String>, network_sender: mpsc::Sender<String>) -> Self {
+    pub fn new(peers: Vec<String>) -> Self {
         let vrf = VRFValidatorSelection::new(peers.clone());
         let selected_validator = vrf.select_validator();
-        println!("?? Neuer Validator: {}", selected_validator);
+        info!("Konsens => Validator {}", selected_validator);
 
         Self {
             validators: peers.clone(),
             current_validator: selected_validator.clone(),
             pbft_node: PBFTNode::new(selected_validator.clone()),
-            blockchain: vec![NakamotoBlock::new(0, "genesis".to_string(), vec![])],
-            network_sender,
+            chain: NakamotoChain::new(NakamotoBlock::genesis(), DEFAULT_BLOCK_DIFFICULTY, DEFAULT_FINALITY_DEPTH),
+            mempool: Arc::new(Mutex::new(Mempool::default())),
+            block_limits: BlockLimits::default(),
+            shard_manager: None,
+            signing_key: None,
+            fee_pool: None,
+        }
+    }
+
+    /// Teilt den Mempool mit RPC/Gossip, die Transaktionen einliefern.
+    pub fn with_mempool(mut self, mempool: Arc<Mutex<Mempool>>) -> Self {
+        self.mempool = mempool;
+        self
+    }
+
+    /// Verankert nach jedem eigenen Block die Merkle-Roots der lokalen Shards.
+    pub fn with_shard_manager(mut self, shard_manager: Arc<ShardManager>) -> Self {
+        self.shard_manager = Some(shard_manager);
+        self
+    }
+
+    /// Signiert eigene Blöcke mit dem Block-Schlüssel des Nodes.
+    pub fn with_signing_key(mut self, key: Arc<Keypair>) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    /// Schreibt die Gebühren der Mempool-Transaktionen eigener Blöcke `fee_pool` gut.
+    pub fn with_fee_pool(mut self, fee_pool: FeePool) -> Self {
+        self.fee_pool = Some(fee_pool);
+        self
+    }
+
+    /// Top-Fee-Auswahl für den nächsten Block (serialisiert wie im Block gespeichert)
+    /// samt Summe der Gebühren.
+    fn take_block_transactions(&self) -> (Vec<String>, u64) {
+        let mut pool = match self.mempool.lock() {
+            Ok(p) => p,
+            Err(_) => return (Vec::new(), 0),
+        };
+        let selected = pool.select_for_block(&self.block_limits);
+        pool.remove_included(&selected);
+        let mut fees = 0u64;
+        let txs = selected
+            .iter()
+            .filter_map(|tx| {
+                let json = serde_json::to_string(tx).ok()?;
+                fees = fees.saturating_add(tx.fee);
+                Some(json)
+            })
+            .collect();
+        (txs, fees)
+    }
+
+    fn credit_fees(&self, fees: u64) {
+        let Some(pool) = &self.fee_pool else { return };
+        if fees == 0 {
+            return;
+        }
+        if let Err(e) = pool.add_fees(fees as f64) {
+            warn!("Konsens => Gebühren ({}) nicht gutgeschrieben: {:?}", fees, e);
+        }
+    }
+
+    /// Verankert die aktuellen Shard-Roots an der Spitze; `only` schränkt auf Shards ein.
+    fn anchor_shards(&mut self, only: Option<&HashSet<u32>>) {
+        let Some(sm) = self.shard_manager.clone() else { return };
+        for (shard_id, root) in sm.shard_roots() {
+            if only.map_or(false, |set| !set.contains(&shard_id)) {
+                continue;
+            }
+            let root = match hex::decode(&root) {
+                Ok(r) => r,
+                Err(e) => {
+                    warn!("Konsens => Root von Shard {} ungültig: {:?}", shard_id, e);
+                    continue;
+                }
+            };
+            let cp = self.chain.anchor_checkpoint(shard_id, root);
+            if let Err(e) = sm.checkpoint_and_store(shard_id, cp.block_height, Some(cp.block_hash.clone())) {
+                warn!("Konsens => Checkpoint für Shard {} fehlgeschlagen: {:?}", shard_id, e);
+            }
+        }
+    }
+
+    fn on_chain_update(&mut self, update: &ChainUpdate) {
+        if let ChainUpdate::Reorg { reverted_checkpoints, .. } = update {
+            if !reverted_checkpoints.is_empty() {
+                let shards: HashSet<u32> = reverted_checkpoints.iter().map(|cp| cp.shard_id).collect();
+                self.anchor_shards(Some(&shards));
+            }
+        }
+    }
+
+    /// Block eines Peers (bincode): PoW prüfen, Fork-Choice anwenden.
+    pub fn receive_block(&mut self, data: &[u8]) -> Result<ChainUpdate, DexError> {
+        let block = validate_block(data)?;
+        let update = self.chain.add_block(block)?;
+        self.on_chain_update(&update);
+        Ok(update)
+    }
+
+    /// Erzeugt, mined und übernimmt einen Block auf der aktuellen Spitze; liefert ihn kodiert.
+    pub fn produce_block(&mut self) -> Result<Vec<u8>, DexError> {
+        let mut txs = self.chain.pending_txs.clone();
+        let (mempool_txs, fees) = self.take_block_transactions();
+        txs.extend(mempool_txs);
+        let tip = self.chain.tip();
+        let mut block = NakamotoBlock::new(tip.index + 1, self.chain.tip_hash().to_string(), txs);
+        block.mine_block(self.chain.min_difficulty);
+        if let Some(key) = &self.signing_key {
+            block.sign(key);
         }
+        let bytes = bincode::serialize(&block).map_err(|e| DexError::Other(format!("Block encode failed: {:?}", e)))?;
+        let update = self.chain.add_block(block)?;
+        self.on_chain_update(&update);
+        self.anchor_shards(None);
+        // Erst nach Übernahme in die Chain: verworfene Blöcke bringen keine Gebühr
+        self.credit_fees(fees);
+        Ok(bytes)
     }
 
-    pub async fn run(&mut self) {
+    /// Produziert Blöcke im Takt und verarbeitet Blöcke der Peers; `broadcast` verteilt eigene.
+    pub async fn run<B>(&mut self, mut inbox: UnboundedReceiver<Vec<u8>>, broadcast: B)
+    where
+        B: Fn(Vec<u8>),
+    {
+        let mut ticker = interval(Duration::from_secs(5));
         loop {
-            println!("?? Konsens-Engine l�uft...");
-            
-            if self.current_validator == self.pbft_node.node_id {
-                let new_block_hash = format!("block_{}", self.blockchain.len());
-                println!("?? Erzeuge neuen Block: {}", new_block_hash);
-
-                if self.pbft_node.handle_message(super::pbft::PBFTMessage::PrePrepare {
-                    block_hash: new_block_hash.clone(),
-                }) {
-                    println!("? PBFT-Konsens erreicht f�r Block {}", new_block_hash);
-
-                    let new_block = NakamotoBlock::new(
-                        self.blockchain.len() as u64,
-                        self.blockchain.last().unwrap().calculate_hash(),
-                        vec![new_block_hash.clone()],
-                    );
-                    self.blockchain.push(new_block);
-
-                    let _ = self.network_sender.send(format!("finalized:{}", new_block_hash)).await;
+            tokio::select! {
+                Some(data) = inbox.recv() => match self.receive_block(&data) {
+                    Ok(update) => debug!("Konsens => Peer-Block: {:?}", update),
+                    Err(e) => debug!("Konsens => Peer-Block verworfen: {:?}", e),
+                },
+                _ = ticker.tick() => {
+                    if self.current_validator != self.pbft_node.node_id {
+                        continue;
+                    }
+                    let block_hash = format!("block_{}", self.chain.height() + 1);
+                    if !self.pbft_node.handle_message(super::pbft::PBFTMessage::PrePrepare { block_hash }) {
+                        continue;
+                    }
+                    match self.produce_block() {
+                        Ok(bytes) => {
+                            info!("Konsens => Block {} erzeugt", self.chain.height());
+                            broadcast(bytes);
+                        }
+                        Err(e) => warn!("Konsens => Block konnte nicht erzeugt werden: {:?}", e),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_block_extends_chain() {
+        let mut producer = ConsensusEngine::new(vec!["a".into()]);
+        let mut follower = ConsensusEngine::new(vec!["b".into()]);
+        producer.chain.submit_tx("tx1".into());
+
+        let bytes = producer.produce_block().unwrap();
+        assert_eq!(producer.chain.height(), 1);
+        assert!(producer.chain.is_confirmed("tx1"));
+
+        let update = follower.receive_block(&bytes).unwrap();
+        assert!(matches!(update, ChainUpdate::Extended { .. }));
+        assert_eq!(follower.chain.tip_hash(), producer.chain.tip_hash());
+        assert!(follower.receive_block(&[0u8; 4]).is_err());
+    }
+
+    #[test]
+    fn test_signed_blocks_verified_on_receipt() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[5u8; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        let mut producer = ConsensusEngine::new(vec!["a".into()]).with_signing_key(Arc::new(Keypair { secret, public }));
+        let mut follower = ConsensusEngine::new(vec!["b".into()]);
+
+        let bytes = producer.produce_block().unwrap();
+        let mut block: NakamotoBlock = bincode::deserialize(&bytes).unwrap();
+        assert!(block.verify_signature());
+
+        // Manipulierte Signatur => abgelehnt, Original wird übernommen
+        block.signature.as_mut().unwrap()[0] ^= 1;
+        assert!(matches!(
+            follower.receive_block(&bincode::serialize(&block).unwrap()),
+            Err(DexError::InvalidSignature(_))
+        ));
+        follower.receive_block(&bytes).unwrap();
+        assert_eq!(follower.chain.tip_hash(), producer.chain.tip_hash());
+    }
+
+    #[test]
+    fn test_mempool_fees_credited_to_fee_pool() {
+        use crate::block::{LedgerState, Transaction};
+        use crate::mempool::MempoolConfig;
+        use crate::storage::db_layer::{DexDB, InMemoryDb};
+        use ed25519_dalek::PublicKey;
+
+        struct Ledger(PublicKey);
+        impl LedgerState for Ledger {
+            fn balance(&self, _account: &str) -> u64 {
+                1_000
+            }
+            fn public_key(&self, _account: &str) -> Option<PublicKey> {
+                Some(self.0)
+            }
+            fn chain_id(&self) -> &str {
+                "test"
+            }
+            fn next_nonce(&self, _account: &str) -> u64 {
+                0
+            }
+        }
+
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[9u8; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        let kp = Keypair { secret, public };
+        let ledger = Ledger(kp.public);
+        let mempool = Arc::new(Mutex::new(Mempool::new(MempoolConfig::default())));
+        for (from, fee) in [("a", 5), ("b", 7)] {
+            let mut tx = Transaction::new(1, from, "c", 10).with_fee(fee).with_chain_id("test");
+            tx.sign(&kp).unwrap();
+            mempool.lock().unwrap().insert(tx, &ledger).unwrap();
+        }
+        let db = Arc::new(Mutex::new(DexDB {
+            rocks: None,
+            fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))),
+        }));
+        let fee_pool = FeePool::new(db, "test/fee_pool");
+        let mut engine = ConsensusEngine::new(vec!["a".into()])
+            .with_mempool(mempool.clone())
+            .with_fee_pool(fee_pool.clone());
+
+        engine.produce_block().unwrap();
+        assert!(mempool.lock().unwrap().is_empty());
+        let credited = fee_pool.current_dev_pool().unwrap() + fee_pool.current_nodes_pool().unwrap();
+        assert!((credited - 12.0).abs() < 1e-9);
+
+        // Leerer Mempool => keine weitere Gutschrift
+        engine.produce_block().unwrap();
+        let again = fee_pool.current_dev_pool().unwrap() + fee_pool.current_nodes_pool().unwrap();
+        assert!((again - 12.0).abs() < 1e-9);
+    }
+}
@@ -6,6 +6,7 @@ pub mod nakamoto;
 pub mod pbft;
 pub mod proof_of_stake;
 pub mod secured_consensus;
+pub mod sequencer;
 pub mod vrf;
 pub mod vrf_committee_async;
 pub mod auto_onboarding;
@@ -1,17 +1,53 @@
 ///////////////////////////////////////////////////
 /// my_dex/src/consensus/nakamoto.rs
 /////////////////////////////////////////////////// 
+//
+// Nakamoto-Blöcke + Kettenverwaltung mit Fork-Choice nach kumulierter Arbeit.
+//
+// NakamotoChain hält alle bekannten Blöcke (auch Seitenketten). Trifft ein
+// Block ein, dessen Kette mehr Arbeit hat als die aktuelle, wird umgestellt:
+//   1) Rückbau bis zum gemeinsamen Vorfahren: Shard-Checkpoints, die an
+//      verwaiste Blöcke verankert waren, werden zurückgenommen, deren
+//      Transaktionen wandern zurück in `pending_txs`
+//   2) Anwenden der neuen Kette: Transaktionen gelten als bestätigt und
+//      verschwinden aus `pending_txs`
+// Blöcke, die tiefer als `finality_depth` unter der Spitze liegen, gelten als
+// final; ein Reorg, der sie zurückrollen würde, wird abgelehnt.
+//
+// Seitenketten sind begrenzt: Forks unterhalb der finalen Höhe werden gar
+// nicht erst gespeichert, und ab `max_side_blocks` werden Seitenblöcke, die
+// nicht mehr übernommen werden können, verworfen. Ist danach noch kein Platz,
+// wird der neue Seitenblock abgelehnt.
 
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
 use sha2::{Sha256, Digest};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
 
-#[derive(Debug)]
+use crate::error::DexError;
+use crate::metrics::{CONSENSUS_HEIGHT, CONSENSUS_PEER_HEIGHT};
+
+/// Obergrenze für gespeicherte Blöcke abseits der Hauptkette.
+pub const DEFAULT_MAX_SIDE_BLOCKS: usize = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NakamotoBlock {
     pub index: u64,
     pub previous_hash: String,
     pub timestamp: u64,
     pub transactions: Vec<String>,
     pub nonce: u64,
+    /// Anzahl führender Hex-Nullen, mit der der Block gemined wurde
+    #[serde(default)]
+    pub difficulty: usize,
+    /// Ed25519-Schlüssel des Produzenten (None => unsigniert)
+    #[serde(default)]
+    pub producer: Option<Vec<u8>>,
+    /// Signatur des Produzenten über `calculate_hash()`
+    #[serde(default)]
+    pub signature: Option<Vec<u8>>,
 }
 
 impl NakamotoBlock {
@@ -23,10 +59,28 @@ impl NakamotoBlock {
             timestamp,
             transactions,
             nonce: 0,
+            difficulty: 0,
+            producer: None,
+            signature: None,
+        }
+    }
+
+    /// Gemeinsamer Genesis-Block aller Nodes (fester Zeitstempel, damit der Hash übereinstimmt).
+    pub fn genesis() -> Self {
+        Self {
+            index: 0,
+            previous_hash: "genesis".to_string(),
+            timestamp: 0,
+            transactions: Vec::new(),
+            nonce: 0,
+            difficulty: 0,
+            producer: None,
+            signature: None,
         }
     }
 
     pub fn mine_block(&mut self, difficulty: usize) {
+        self.difficulty = difficulty;
         loop {
             let hash = self.calculate_hash();
             if hash.starts_with(&"0".repeat(difficulty)) {
@@ -42,6 +96,479 @@ impl NakamotoBlock {
         hasher.update(self.previous_hash.as_bytes());
         hasher.update(self.timestamp.to_le_bytes());
         hasher.update(self.nonce.to_le_bytes());
+        for tx in &self.transactions {
+            hasher.update((tx.len() as u64).to_le_bytes());
+            hasher.update(tx.as_bytes());
+        }
         format!("{:x}", hasher.finalize())
     }
+
+    /// Hash erfüllt die angegebene Difficulty?
+    pub fn meets_difficulty(&self) -> bool {
+        self.calculate_hash().starts_with(&"0".repeat(self.difficulty))
+    }
+
+    /// Signiert den Hash (nach dem Mining, Signatur fließt nicht in den Hash ein).
+    pub fn sign(&mut self, keypair: &Keypair) {
+        let signature = keypair.sign(self.calculate_hash().as_bytes());
+        self.producer = Some(keypair.public.to_bytes().to_vec());
+        self.signature = Some(signature.to_bytes().to_vec());
+    }
+
+    /// Signatur des Produzenten gültig? Unsignierte Blöcke => false.
+    pub fn verify_signature(&self) -> bool {
+        let (Some(producer), Some(signature)) = (&self.producer, &self.signature) else {
+            return false;
+        };
+        match (PublicKey::from_bytes(producer), Signature::from_bytes(signature)) {
+            (Ok(pk), Ok(sig)) => pk.verify(self.calculate_hash().as_bytes(), &sig).is_ok(),
+            _ => false,
+        }
+    }
+
+    /// Erwartete Arbeit: 16^difficulty Hash-Versuche.
+    pub fn work(&self) -> u128 {
+        1u128 << (4 * self.difficulty).min(120)
+    }
+}
+
+/// Dekodiert einen Block aus Bytes (bincode) und prüft den Proof-of-Work
+/// sowie, falls vorhanden, die Signatur des Produzenten.
+pub fn validate_block(data: &[u8]) -> Result<NakamotoBlock, DexError> {
+    let block: NakamotoBlock = bincode::deserialize(data)
+        .map_err(|e| DexError::Other(format!("Block decode failed: {:?}", e)))?;
+    if !block.meets_difficulty() {
+        return Err(DexError::Other("Block hash does not meet difficulty".into()));
+    }
+    if block.signature.is_some() && !block.verify_signature() {
+        return Err(DexError::InvalidSignature(format!("block {}", block.index)));
+    }
+    Ok(block)
+}
+
+/// Shard-Checkpoint, der an einen bestimmten Block verankert ist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnchoredCheckpoint {
+    pub shard_id: u32,
+    pub merkle_root: Vec<u8>,
+    pub block_hash: String,
+    pub block_height: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainUpdate {
+    /// Block verlängert die aktuelle Kette
+    Extended { tip: String },
+    /// Block liegt auf einer Seitenkette mit (noch) weniger Arbeit
+    SideChain { hash: String },
+    /// Umstellung auf eine Kette mit mehr Arbeit
+    Reorg {
+        old_tip: String,
+        new_tip: String,
+        /// verwaiste Blöcke, jüngster zuerst
+        orphaned: Vec<String>,
+        /// übernommene Blöcke, ältester zuerst
+        adopted: Vec<String>,
+        reverted_checkpoints: Vec<AnchoredCheckpoint>,
+        /// Transaktionen verwaister Blöcke, die die neue Kette nicht enthält
+        requeued_txs: Vec<String>,
+    },
+}
+
+pub struct NakamotoChain {
+    blocks: HashMap<String, NakamotoBlock>,
+    total_work: HashMap<String, u128>,
+    tip: String,
+    pub min_difficulty: usize,
+    pub finality_depth: u64,
+    /// Maximal gespeicherte Blöcke außerhalb der Hauptkette
+    pub max_side_blocks: usize,
+    /// Bestätigte Transaktionen der aktuellen Kette
+    confirmed_txs: HashSet<String>,
+    /// Noch nicht (oder nicht mehr) bestätigte Transaktionen
+    pub pending_txs: Vec<String>,
+    checkpoints: Vec<AnchoredCheckpoint>,
+}
+
+impl NakamotoChain {
+    pub fn new(genesis: NakamotoBlock, min_difficulty: usize, finality_depth: u64) -> Self {
+        let hash = genesis.calculate_hash();
+        let mut blocks = HashMap::new();
+        let mut total_work = HashMap::new();
+        total_work.insert(hash.clone(), genesis.work());
+        blocks.insert(hash.clone(), genesis);
+        Self {
+            blocks,
+            total_work,
+            tip: hash,
+            min_difficulty,
+            finality_depth,
+            max_side_blocks: DEFAULT_MAX_SIDE_BLOCKS,
+            confirmed_txs: HashSet::new(),
+            pending_txs: Vec::new(),
+            checkpoints: Vec::new(),
+        }
+    }
+
+    pub fn with_max_side_blocks(mut self, max_side_blocks: usize) -> Self {
+        self.max_side_blocks = max_side_blocks;
+        self
+    }
+
+    pub fn tip(&self) -> &NakamotoBlock {
+        &self.blocks[&self.tip]
+    }
+
+    pub fn tip_hash(&self) -> &str {
+        &self.tip
+    }
+
+    pub fn height(&self) -> u64 {
+        self.tip().index
+    }
+
+    pub fn is_confirmed(&self, tx: &str) -> bool {
+        self.confirmed_txs.contains(tx)
+    }
+
+    /// Anzahl gespeicherter Blöcke außerhalb der Hauptkette.
+    pub fn side_block_count(&self) -> usize {
+        self.blocks.len().saturating_sub(self.height() as usize + 1)
+    }
+
+    /// Höhe, bis zu der Blöcke als final gelten.
+    fn final_height(&self) -> u64 {
+        self.height().saturating_sub(self.finality_depth)
+    }
+
+    pub fn checkpoints(&self) -> &[AnchoredCheckpoint] {
+        &self.checkpoints
+    }
+
+    /// Hashes der Hauptkette von Genesis bis zur Spitze.
+    pub fn main_chain(&self) -> Vec<String> {
+        let mut out = self.path_to_genesis(&self.tip);
+        out.reverse();
+        out
+    }
+
+    fn path_to_genesis(&self, from: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut cur = from.to_string();
+        while let Some(b) = self.blocks.get(&cur) {
+            out.push(cur.clone());
+            if b.index == 0 {
+                break;
+            }
+            cur = b.previous_hash.clone();
+        }
+        out
+    }
+
+    /// Verankert einen Shard-Checkpoint an der aktuellen Spitze.
+    pub fn anchor_checkpoint(&mut self, shard_id: u32, merkle_root: Vec<u8>) -> AnchoredCheckpoint {
+        let cp = AnchoredCheckpoint {
+            shard_id,
+            merkle_root,
+            block_hash: self.tip.clone(),
+            block_height: self.height(),
+        };
+        self.checkpoints.push(cp.clone());
+        cp
+    }
+
+    /// Transaktion für einen künftigen Block vormerken.
+    pub fn submit_tx(&mut self, tx: String) {
+        if !self.confirmed_txs.contains(&tx) && !self.pending_txs.contains(&tx) {
+            self.pending_txs.push(tx);
+        }
+    }
+
+    /// Nimmt einen Block an (eigener oder von Peers) und wendet Fork-Choice an.
+    pub fn add_block(&mut self, block: NakamotoBlock) -> Result<ChainUpdate, DexError> {
+        let update = self.insert_block(block);
+        CONSENSUS_HEIGHT.set(self.height() as i64);
+        update
+    }
+
+    fn insert_block(&mut self, block: NakamotoBlock) -> Result<ChainUpdate, DexError> {
+        // Auch Blöcke mit unbekanntem Parent zeigen, wie weit die Peers sind;
+        // ohne gültige Arbeit zählt der Index nicht (sonst billig fälschbar)
+        let has_work = block.difficulty >= self.min_difficulty && block.meets_difficulty();
+        if has_work && block.index as i64 > CONSENSUS_PEER_HEIGHT.get() {
+            CONSENSUS_PEER_HEIGHT.set(block.index as i64);
+        }
+        let hash = block.calculate_hash();
+        if self.blocks.contains_key(&hash) {
+            return Ok(ChainUpdate::SideChain { hash });
+        }
+        let parent = self
+            .blocks
+            .get(&block.previous_hash)
+            .ok_or_else(|| DexError::Other(format!("Unknown parent {}", block.previous_hash)))?;
+        if block.index != parent.index + 1 {
+            return Err(DexError::Other(format!(
+                "Block index {} does not follow parent index {}",
+                block.index, parent.index
+            )));
+        }
+        if block.difficulty < self.min_difficulty || !block.meets_difficulty() {
+            return Err(DexError::Other("Block does not meet difficulty".into()));
+        }
+
+        let work = self.total_work[&block.previous_hash] + block.work();
+        let extends_tip = block.previous_hash == self.tip;
+        if !extends_tip {
+            // Fork unterhalb der finalen Höhe kann nie übernommen werden
+            if parent.index < self.final_height() {
+                return Err(DexError::Other(format!(
+                    "Fork at height {} is below final height {}",
+                    parent.index,
+                    self.final_height()
+                )));
+            }
+            if self.side_block_count() >= self.max_side_blocks {
+                self.prune_side_chains();
+                if self.side_block_count() >= self.max_side_blocks {
+                    warn!("Seitenketten voll ({} Blöcke) => Block {} verworfen", self.max_side_blocks, hash);
+                    return Err(DexError::Other("Side chain capacity exhausted".into()));
+                }
+            }
+        }
+        self.total_work.insert(hash.clone(), work);
+        self.blocks.insert(hash.clone(), block);
+
+        if extends_tip {
+            self.tip = hash.clone();
+            self.apply_block(&hash);
+            return Ok(ChainUpdate::Extended { tip: hash });
+        }
+        if work <= self.total_work[&self.tip] {
+            return Ok(ChainUpdate::SideChain { hash });
+        }
+        self.reorg_to(&hash)
+    }
+
+    /// Verwirft Seitenblöcke, die nicht mehr übernommen werden können: ihr
+    /// Abzweig von der Hauptkette liegt unterhalb der finalen Höhe.
+    fn prune_side_chains(&mut self) {
+        let main: HashSet<String> = self.main_chain().into_iter().collect();
+        let final_height = self.final_height();
+        let stale: Vec<String> = self
+            .blocks
+            .keys()
+            .filter(|h| !main.contains(*h) && self.fork_height(h, &main) < final_height)
+            .cloned()
+            .collect();
+        for h in &stale {
+            self.blocks.remove(h);
+            self.total_work.remove(h);
+        }
+        if !stale.is_empty() {
+            info!("Seitenketten => {} nicht mehr übernehmbare Blöcke verworfen", stale.len());
+        }
+    }
+
+    /// Höhe des jüngsten Vorfahren von `hash`, der auf der Hauptkette liegt.
+    fn fork_height(&self, hash: &str, main: &HashSet<String>) -> u64 {
+        let mut cur = hash.to_string();
+        while let Some(b) = self.blocks.get(&cur) {
+            if main.contains(&cur) || b.index == 0 {
+                return b.index;
+            }
+            cur = b.previous_hash.clone();
+        }
+        0
+    }
+
+    fn apply_block(&mut self, hash: &str) {
+        let txs = self.blocks[hash].transactions.clone();
+        for tx in &txs {
+            self.confirmed_txs.insert(tx.clone());
+        }
+        self.pending_txs.retain(|t| !txs.contains(t));
+    }
+
+    fn reorg_to(&mut self, new_tip: &str) -> Result<ChainUpdate, DexError> {
+        let old_path = self.path_to_genesis(&self.tip);
+        let new_path = self.path_to_genesis(new_tip);
+        let new_set: HashSet<&String> = new_path.iter().collect();
+
+        // Verwaist: alte Blöcke bis (exkl.) zum gemeinsamen Vorfahren
+        let orphaned: Vec<String> = old_path.iter().take_while(|h| !new_set.contains(h)).cloned().collect();
+        let ancestor = old_path
+            .get(orphaned.len())
+            .cloned()
+            .ok_or_else(|| DexError::Other("Fork has no common ancestor".into()))?;
+        let ancestor_height = self.blocks[&ancestor].index;
+        let final_height = self.height().saturating_sub(self.finality_depth);
+        if ancestor_height < final_height {
+            warn!(
+                "Reorg abgelehnt: gemeinsamer Vorfahr {} unter finaler Höhe {}",
+                ancestor_height, final_height
+            );
+            return Err(DexError::Other(format!(
+                "Reorg would revert final blocks (ancestor height {}, final height {})",
+                ancestor_height, final_height
+            )));
+        }
+        let mut adopted: Vec<String> = new_path.iter().take_while(|h| **h != ancestor).cloned().collect();
+        adopted.reverse();
+
+        // 1) Rückbau
+        let orphan_set: HashSet<&String> = orphaned.iter().collect();
+        let (reverted, kept): (Vec<_>, Vec<_>) =
+            self.checkpoints.drain(..).partition(|cp| orphan_set.contains(&cp.block_hash));
+        self.checkpoints = kept;
+        let mut orphan_txs = Vec::new();
+        for h in &orphaned {
+            for tx in &self.blocks[h].transactions {
+                self.confirmed_txs.remove(tx);
+                orphan_txs.push(tx.clone());
+            }
+        }
+
+        // 2) Neue Kette anwenden
+        let old_tip = std::mem::replace(&mut self.tip, new_tip.to_string());
+        for h in &adopted {
+            self.apply_block(h);
+        }
+
+        // Nicht wieder enthaltene Transaktionen zurück in den Pool
+        let mut requeued = Vec::new();
+        for tx in orphan_txs {
+            if !self.confirmed_txs.contains(&tx) && !self.pending_txs.contains(&tx) {
+                self.pending_txs.push(tx.clone());
+                requeued.push(tx);
+            }
+        }
+
+        info!(
+            "Reorg => {} -> {} ({} verwaist, {} übernommen, {} Checkpoints zurückgenommen)",
+            old_tip,
+            new_tip,
+            orphaned.len(),
+            adopted.len(),
+            reverted.len()
+        );
+        Ok(ChainUpdate::Reorg {
+            old_tip,
+            new_tip: new_tip.to_string(),
+            orphaned,
+            adopted,
+            reverted_checkpoints: reverted,
+            requeued_txs: requeued,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mined(parent: &NakamotoBlock, txs: &[&str]) -> NakamotoBlock {
+        let mut b = NakamotoBlock::new(
+            parent.index + 1,
+            parent.calculate_hash(),
+            txs.iter().map(|s| s.to_string()).collect(),
+        );
+        b.mine_block(1);
+        b
+    }
+
+    fn genesis() -> NakamotoBlock {
+        let mut g = NakamotoBlock::new(0, "genesis".into(), vec![]);
+        g.timestamp = 0;
+        g
+    }
+
+    #[test]
+    fn test_longer_fork_triggers_reorg_and_reverts_checkpoint() {
+        let g = genesis();
+        let mut chain = NakamotoChain::new(g.clone(), 1, 10);
+
+        let a1 = mined(&g, &["tx_a", "tx_shared"]);
+        let a2 = mined(&a1, &["tx_only_a"]);
+        chain.add_block(a1.clone()).unwrap();
+        chain.add_block(a2.clone()).unwrap();
+        let cp = chain.anchor_checkpoint(7, vec![1, 2, 3]);
+        assert_eq!(cp.block_hash, a2.calculate_hash());
+        assert!(chain.is_confirmed("tx_only_a"));
+
+        // Konkurrierende Kette ab Genesis, eine Blocklänge mehr Arbeit
+        let b1 = mined(&g, &["tx_shared"]);
+        let b2 = mined(&b1, &["tx_b"]);
+        let b3 = mined(&b2, &["tx_a"]);
+        assert!(matches!(chain.add_block(b1.clone()).unwrap(), ChainUpdate::SideChain { .. }));
+        assert!(matches!(chain.add_block(b2.clone()).unwrap(), ChainUpdate::SideChain { .. }));
+        let update = chain.add_block(b3.clone()).unwrap();
+
+        match update {
+            ChainUpdate::Reorg { orphaned, adopted, reverted_checkpoints, requeued_txs, .. } => {
+                assert_eq!(orphaned, vec![a2.calculate_hash(), a1.calculate_hash()]);
+                assert_eq!(adopted, vec![b1.calculate_hash(), b2.calculate_hash(), b3.calculate_hash()]);
+                assert_eq!(reverted_checkpoints, vec![cp]);
+                assert_eq!(requeued_txs, vec!["tx_only_a".to_string()]);
+            }
+            other => panic!("expected reorg, got {:?}", other),
+        }
+        assert_eq!(chain.tip_hash(), b3.calculate_hash());
+        assert!(chain.checkpoints().is_empty());
+        assert!(chain.is_confirmed("tx_a") && chain.is_confirmed("tx_b") && chain.is_confirmed("tx_shared"));
+        assert!(!chain.is_confirmed("tx_only_a"));
+        assert_eq!(chain.pending_txs, vec!["tx_only_a".to_string()]);
+    }
+
+    #[test]
+    fn test_reorg_beyond_finality_rejected() {
+        let g = genesis();
+        let mut chain = NakamotoChain::new(g.clone(), 1, 1);
+        let a1 = mined(&g, &["a1"]);
+        let a2 = mined(&a1, &["a2"]);
+        chain.add_block(a1).unwrap();
+        chain.add_block(a2.clone()).unwrap();
+
+        let b1 = mined(&g, &["b1"]);
+        let b2 = mined(&b1, &["b2"]);
+        let b3 = mined(&b2, &["b3"]);
+        chain.add_block(b1).unwrap();
+        chain.add_block(b2).unwrap();
+        assert!(chain.add_block(b3).is_err());
+        assert_eq!(chain.tip_hash(), a2.calculate_hash());
+    }
+
+    #[test]
+    fn test_side_chains_are_bounded() {
+        let g = genesis();
+        let mut chain = NakamotoChain::new(g.clone(), 1, 2).with_max_side_blocks(1);
+        let a1 = mined(&g, &["a1"]);
+        chain.add_block(a1.clone()).unwrap();
+        let b1 = mined(&g, &["b1"]);
+        assert!(matches!(chain.add_block(b1.clone()).unwrap(), ChainUpdate::SideChain { .. }));
+        let a2 = mined(&a1, &["a2"]);
+        let a3 = mined(&a2, &["a3"]);
+        chain.add_block(a2).unwrap();
+        chain.add_block(a3).unwrap();
+
+        // Fork unterhalb der finalen Höhe wird gar nicht gespeichert
+        assert!(chain.add_block(mined(&g, &["late"])).is_err());
+
+        // Voll => b1 (Abzweig unter finaler Höhe) wird verworfen, c2 passt hinein
+        let c2 = mined(&a1, &["c2"]);
+        assert!(matches!(chain.add_block(c2).unwrap(), ChainUpdate::SideChain { .. }));
+        assert_eq!(chain.side_block_count(), 1);
+        assert!(chain.add_block(mined(&b1, &["b2"])).is_err());
+
+        // c2 ist noch übernehmbar => kein Platz für einen weiteren Seitenblock
+        assert!(chain.add_block(mined(&a1, &["d2"])).is_err());
+        assert_eq!(chain.side_block_count(), 1);
+    }
+
+    #[test]
+    fn test_validate_block_roundtrip() {
+        let b = mined(&genesis(), &["x"]);
+        let bytes = bincode::serialize(&b).unwrap();
+        assert_eq!(validate_block(&bytes).unwrap().calculate_hash(), b.calculate_hash());
+        assert!(validate_block(&[1, 2, 3]).is_err());
+    }
 }
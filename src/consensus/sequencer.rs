@@ -0,0 +1,689 @@
+/////////////////////////////////////////////////////////
+// my_dex/src/consensus/sequencer.rs
+/////////////////////////////////////////////////////////
+//
+// Per-Epoch Sequencer für den MatchingEngine:
+//  - Jeder Kandidat (Fullnode) erzeugt mit seinem VRF-Key einen Claim über
+//    `epoch_message(seed, epoch)`.
+//  - Gewählt ist der gültige Claim mit dem kleinsten VRF-Wert (Tiebreak node_id).
+//  - Nur der gewählte Sequencer vergibt kanonische Sequenznummern und signiert
+//    jeden `SequencedBatch` mit seinem ed25519-Key über (epoch, seq, Order-Hash).
+//    Die anderen Nodes prüfen VRF-Proof und Batch-Signatur, bevor sie annehmen.
+//  - `MatchingEngine` verarbeitet Orders dann strikt in Sequenz-Reihenfolge,
+//    unabhängig davon, in welcher Reihenfolge sie über das Netz ankommen.
+//  - Vergibt der Sequencer dieselbe (epoch, seq) an zwei verschiedene Orders,
+//    ist das eine Equivocation: beide signierten Batches zusammen sind der
+//    Beweis (`SequencerEquivocation`), der Sequencer ist für den Rest der
+//    Epoche abgesetzt.
+//  - Orders, die beim Epochenwechsel noch hinter einer Lücke hängen, gehen
+//    nicht verloren, sondern werden beim neuen Sequencer erneut eingereicht
+//    (`SequencingState::take_orphaned`).
+//  - In main über `sequencer` in der Node-Config; Claims, Batches und an den
+//    Sequencer weitergeleitete Orders laufen als `SequencerWire` über Kademlia.
+
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tracing::{debug, warn};
+
+use crate::consensus::vrf_committee_async::{vrf_sign, vrf_verify, VrfKeypair, VrfProof};
+use crate::error::DexError;
+use crate::matching_engine::{MatchingEngine, OrderData};
+use crate::utils::canonical;
+use crate::utils::lock::LockRecover;
+
+/// Bekannter Kandidat mit VRF-Public-Key und ed25519-Key für Batch-Signaturen.
+#[derive(Clone, Debug)]
+pub struct SequencerCandidate {
+    pub node_id: String,
+    pub vrf_pk: [u8; 32],
+    pub sign_pk: [u8; 32],
+}
+
+/// Config-Eintrag eines Kandidaten (Keys hex-kodiert).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequencerCandidateConfig {
+    pub node_id: String,
+    pub vrf_pk: String,
+    pub sign_pk: String,
+    /// Kademlia-Adresse für Claims, Batches und weitergeleitete Orders
+    pub addr: String,
+}
+
+/// Config-Abschnitt `sequencer`. Alle Kandidaten brauchen denselben Seed
+/// und dieselbe Kandidatenliste.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SequencerConfig {
+    pub enabled: bool,
+    pub seed: u64,
+    /// Länge einer Epoche; die Epoche ist `unix_zeit / epoch_secs`
+    pub epoch_secs: u64,
+    /// So lange werden nach Epochenbeginn Claims gesammelt
+    pub claim_window_ms: u64,
+    /// Abstand, in dem der Sequencer gesammelte Orders als Batch signiert
+    pub batch_interval_ms: u64,
+    pub candidates: Vec<SequencerCandidateConfig>,
+}
+
+impl Default for SequencerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            seed: 0,
+            epoch_secs: 60,
+            claim_window_ms: 2_000,
+            batch_interval_ms: 200,
+            candidates: Vec::new(),
+        }
+    }
+}
+
+fn parse_key(hex_key: &str) -> Result<[u8; 32], String> {
+    hex::decode(hex_key)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| format!("`{}` is not a 32-byte hex key", hex_key))
+}
+
+impl SequencerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.epoch_secs == 0 || self.batch_interval_ms == 0 {
+            return Err("epoch_secs and batch_interval_ms must be > 0".into());
+        }
+        if self.claim_window_ms >= self.epoch_secs * 1_000 {
+            return Err("claim_window_ms must be shorter than the epoch".into());
+        }
+        if self.candidates.is_empty() {
+            return Err("needs at least one candidate".into());
+        }
+        self.election().map(|_| ())?;
+        self.peer_addrs_checked().map(|_| ())
+    }
+
+    /// Wahl aus der Kandidatenliste.
+    pub fn election(&self) -> Result<SequencerElection, String> {
+        let candidates = self
+            .candidates
+            .iter()
+            .map(|c| {
+                Ok(SequencerCandidate { node_id: c.node_id.clone(), vrf_pk: parse_key(&c.vrf_pk)?, sign_pk: parse_key(&c.sign_pk)? })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(SequencerElection::new(self.seed, candidates))
+    }
+
+    fn peer_addrs_checked(&self) -> Result<Vec<(String, SocketAddr)>, String> {
+        self.candidates
+            .iter()
+            .map(|c| {
+                c.addr
+                    .parse()
+                    .map(|a| (c.node_id.clone(), a))
+                    .map_err(|_| format!("candidate {}: `{}` is not ip:port", c.node_id, c.addr))
+            })
+            .collect()
+    }
+
+    /// Adressen aller anderen Kandidaten (node_id => Adresse).
+    pub fn peer_addrs(&self, own_node_id: &str) -> HashMap<String, SocketAddr> {
+        self.peer_addrs_checked()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(id, _)| id != own_node_id)
+            .collect()
+    }
+
+    /// Epoche zum Zeitpunkt `unix_secs`.
+    pub fn epoch_at(&self, unix_secs: u64) -> u64 {
+        unix_secs / self.epoch_secs.max(1)
+    }
+}
+
+/// Nachrichten zwischen den Kandidaten (über `KademliaMessage::Sequencer`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum SequencerWire {
+    Claim(SequencerClaim),
+    Batch(SequencedBatch),
+    /// Order zur Sequenzierung an den gewählten Sequencer
+    Order(OrderData),
+}
+
+/// VRF-Ausgabe eines Kandidaten für eine Epoche.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SequencerClaim {
+    pub epoch: u64,
+    pub node_id: String,
+    pub value: u64,
+    pub proof: Vec<u8>,
+}
+
+/// Nachricht, über die pro Epoche die VRF ausgewertet wird.
+pub fn epoch_message(seed: u64, epoch: u64) -> Vec<u8> {
+    let mut msg = b"dex_sequencer".to_vec();
+    msg.extend_from_slice(&seed.to_be_bytes());
+    msg.extend_from_slice(&epoch.to_be_bytes());
+    msg
+}
+
+pub fn make_claim(kp: &VrfKeypair, node_id: &str, seed: u64, epoch: u64) -> SequencerClaim {
+    let (value, proof) = vrf_sign(kp, &epoch_message(seed, epoch));
+    SequencerClaim { epoch, node_id: node_id.to_string(), value, proof: proof.bytes }
+}
+
+/// Deterministische Wahl: alle Nodes mit gleichem Seed und gleicher
+/// Kandidatenliste kommen zum selben Ergebnis.
+#[derive(Clone, Debug)]
+pub struct SequencerElection {
+    pub seed: u64,
+    pub candidates: Vec<SequencerCandidate>,
+}
+
+impl SequencerElection {
+    pub fn new(seed: u64, candidates: Vec<SequencerCandidate>) -> Self {
+        Self { seed, candidates }
+    }
+
+    pub fn candidate(&self, node_id: &str) -> Option<&SequencerCandidate> {
+        self.candidates.iter().find(|c| c.node_id == node_id)
+    }
+
+    /// Claim stammt von einem bekannten Kandidaten und der Proof passt zu
+    /// `epoch_message(seed, epoch)`.
+    pub fn verify_claim(&self, claim: &SequencerClaim) -> bool {
+        let candidate = match self.candidate(&claim.node_id) {
+            Some(c) => c,
+            None => return false,
+        };
+        let proof = VrfProof { bytes: claim.proof.clone() };
+        vrf_verify(&candidate.vrf_pk, &epoch_message(self.seed, claim.epoch), claim.value, &proof)
+    }
+
+    /// Wählt aus den gesammelten Claims den Sequencer für `epoch`.
+    pub fn elect(&self, claims: &[SequencerClaim], epoch: u64) -> Option<SequencerClaim> {
+        claims
+            .iter()
+            .filter(|c| c.epoch == epoch)
+            .filter(|c| {
+                let ok = self.verify_claim(c);
+                if !ok {
+                    warn!("Sequencer => ungültiger VRF-Claim von {} (epoch={})", c.node_id, c.epoch);
+                }
+                ok
+            })
+            .min_by(|a, b| a.value.cmp(&b.value).then_with(|| a.node_id.cmp(&b.node_id)))
+            .cloned()
+    }
+}
+
+/// Order mit kanonischer Position (epoch, seq).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SequencedOrder {
+    pub epoch: u64,
+    pub seq: u64,
+    pub order: OrderData,
+}
+
+/// Vom Sequencer veröffentlichte Sequenzierung, inkl. seines Claims und
+/// seiner Signatur über `signing_bytes`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SequencedBatch {
+    pub claim: SequencerClaim,
+    pub orders: Vec<SequencedOrder>,
+    pub signature: Vec<u8>,
+}
+
+impl SequencedBatch {
+    /// Kanonische Bytes über Sequencer, Epoche und (epoch, seq, Order-Hash)
+    /// jeder Order. Der Hash deckt auch die Order-Signatur ab.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct BatchSigningView<'a> {
+            node_id: &'a str,
+            epoch: u64,
+            orders: Vec<(u64, u64, String)>,
+        }
+        let orders = self
+            .orders
+            .iter()
+            .map(|so| Ok((so.epoch, so.seq, order_hash(&so.order)?)))
+            .collect::<Result<Vec<_>, DexError>>()?;
+        canonical::signing_bytes("my_dex/sequenced_batch/v1", &BatchSigningView {
+            node_id: &self.claim.node_id,
+            epoch: self.claim.epoch,
+            orders,
+        })
+    }
+
+    /// Ed25519-Prüfung gegen den Batch-Key des Kandidaten.
+    pub fn verify_signature(&self, candidate: &SequencerCandidate) -> bool {
+        let (pk, sig) = match (
+            PublicKey::from_bytes(&candidate.sign_pk),
+            Signature::from_bytes(&self.signature),
+        ) {
+            (Ok(pk), Ok(sig)) => (pk, sig),
+            _ => return false,
+        };
+        match self.signing_bytes() {
+            Ok(bytes) => pk.verify(&bytes, &sig).is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Zwei vom selben Sequencer signierte Batches, die dieselbe (epoch, seq)
+/// an verschiedene Orders vergeben. Jeder Node kann das mit der Wahl prüfen.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SequencerEquivocation {
+    pub first: SequencedBatch,
+    pub second: SequencedBatch,
+}
+
+impl SequencerEquivocation {
+    pub fn node_id(&self) -> &str {
+        &self.first.claim.node_id
+    }
+
+    /// Beide Batches stammen gültig signiert vom selben gewählten Kandidaten
+    /// und belegen eine Position doppelt.
+    pub fn verify(&self, election: &SequencerElection) -> bool {
+        if self.first.claim != self.second.claim || !election.verify_claim(&self.first.claim) {
+            return false;
+        }
+        let candidate = match election.candidate(&self.first.claim.node_id) {
+            Some(c) => c,
+            None => return false,
+        };
+        if !self.first.verify_signature(candidate) || !self.second.verify_signature(candidate) {
+            return false;
+        }
+        let positions: HashMap<(u64, u64), String> = self
+            .first
+            .orders
+            .iter()
+            .filter_map(|so| Some(((so.epoch, so.seq), order_hash(&so.order).ok()?)))
+            .collect();
+        self.second.orders.iter().any(|so| {
+            positions
+                .get(&(so.epoch, so.seq))
+                .is_some_and(|h| order_hash(&so.order).map_or(false, |other| &other != h))
+        })
+    }
+}
+
+fn order_hash(order: &OrderData) -> Result<String, DexError> {
+    let mut hasher = Sha256::new();
+    hasher.update(order.signing_bytes()?);
+    hasher.update(order.signature.as_deref().unwrap_or_default());
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Läuft nur auf dem gewählten Sequencer.
+pub struct OrderSequencer {
+    claim: SequencerClaim,
+    keypair: Keypair,
+    next_seq: u64,
+}
+
+impl OrderSequencer {
+    /// `keypair` muss zum `sign_pk` des Kandidaten passen.
+    pub fn new(claim: SequencerClaim, keypair: Keypair) -> Self {
+        Self { claim, keypair, next_seq: 0 }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.claim.epoch
+    }
+
+    /// Vergibt die nächste Sequenznummer (Commit vor dem Matching).
+    pub fn assign(&mut self, order: OrderData) -> SequencedOrder {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        debug!("Sequencer {} => order {} -> ({}, {})", self.claim.node_id, order.id, self.claim.epoch, seq);
+        SequencedOrder { epoch: self.claim.epoch, seq, order }
+    }
+
+    pub fn batch(&mut self, orders: Vec<OrderData>) -> Result<SequencedBatch, DexError> {
+        let orders = orders.into_iter().map(|o| self.assign(o)).collect();
+        let mut batch = SequencedBatch { claim: self.claim.clone(), orders, signature: Vec::new() };
+        batch.signature = self.keypair.sign(&batch.signing_bytes()?).to_bytes().to_vec();
+        Ok(batch)
+    }
+}
+
+/// Empfangsseite: hält Sequencer der aktuellen Epoche und puffert Orders,
+/// bis alle Vorgänger da sind.
+#[derive(Debug)]
+pub struct SequencingState {
+    pub election: SequencerElection,
+    pub leader: Option<SequencerClaim>,
+    next: (u64, u64),
+    pending: std::collections::BTreeMap<(u64, u64), OrderData>,
+    /// Angenommene Batches der Epoche und je Position (Order-Hash, Batch-Index)
+    batches: Vec<SequencedBatch>,
+    positions: HashMap<(u64, u64), (String, usize)>,
+    /// Beweis, falls der Sequencer dieser Epoche equivociert hat
+    equivocation: Option<SequencerEquivocation>,
+    /// Beim Epochenwechsel nicht mehr lückenlos gewordene Orders
+    orphaned: Vec<OrderData>,
+}
+
+impl SequencingState {
+    pub fn new(election: SequencerElection) -> Self {
+        Self {
+            election,
+            leader: None,
+            next: (0, 0),
+            pending: Default::default(),
+            batches: Vec::new(),
+            positions: HashMap::new(),
+            equivocation: None,
+            orphaned: Vec::new(),
+        }
+    }
+
+    /// Orders, die in einer alten Epoche hinter einer Lücke hängen blieben;
+    /// sie müssen beim aktuellen Sequencer neu eingereicht werden.
+    pub fn take_orphaned(&mut self) -> Vec<OrderData> {
+        std::mem::take(&mut self.orphaned)
+    }
+
+    /// Equivocation-Beweis gegen den Sequencer der laufenden Epoche (einmalig).
+    pub fn take_equivocation(&mut self) -> Option<SequencerEquivocation> {
+        self.equivocation.take()
+    }
+
+    /// Setzt den Sequencer für `epoch` anhand der gesammelten Claims.
+    pub fn start_epoch(&mut self, claims: &[SequencerClaim], epoch: u64) -> Result<SequencerClaim, DexError> {
+        if self.leader.as_ref().map_or(false, |l| epoch <= l.epoch) {
+            return Err(DexError::Other(format!("Sequencer epoch {} is not newer than current", epoch)));
+        }
+        let leader = self
+            .election
+            .elect(claims, epoch)
+            .ok_or_else(|| DexError::Other(format!("No valid sequencer claim for epoch {}", epoch)))?;
+        // Reste der alten Epoche, die nie lückenlos wurden => neu einreichen
+        let newer = self.pending.split_off(&(epoch, 0));
+        let stale = std::mem::replace(&mut self.pending, newer);
+        if !stale.is_empty() {
+            warn!("Sequencer => {} Orders der alten Epoche hinter einer Lücke, werden neu eingereicht", stale.len());
+            self.orphaned.extend(stale.into_values());
+        }
+        self.batches.clear();
+        self.positions.clear();
+        self.next = (epoch, 0);
+        self.leader = Some(leader.clone());
+        Ok(leader)
+    }
+
+    /// Nimmt eine Sequenzierung nur vom aktuellen, per VRF verifizierten
+    /// Sequencer an, und nur mit dessen gültiger Batch-Signatur.
+    pub fn accept(&mut self, batch: SequencedBatch) -> Result<(), DexError> {
+        let leader = self.leader.clone()
+            .ok_or_else(|| DexError::Other("No sequencer elected".into()))?;
+        if batch.claim != leader || !self.election.verify_claim(&batch.claim) {
+            return Err(DexError::Other(format!(
+                "Batch from {} is not from the elected sequencer", batch.claim.node_id
+            )));
+        }
+        let signed = self
+            .election
+            .candidate(&leader.node_id)
+            .map_or(false, |c| batch.verify_signature(c));
+        if !signed {
+            warn!("Sequencer => Batch von {} mit ungültiger Signatur verworfen", leader.node_id);
+            return Err(DexError::InvalidSignature(format!(
+                "sequenced batch from {} (epoch={})", leader.node_id, leader.epoch
+            )));
+        }
+        // Erst alle Positionen prüfen: eine doppelt vergebene Position ist
+        // Equivocation, der ganze Batch wird verworfen
+        let hashes = batch
+            .orders
+            .iter()
+            .map(|so| order_hash(&so.order))
+            .collect::<Result<Vec<_>, DexError>>()?;
+        let conflict = batch.orders.iter().zip(&hashes).find_map(|(so, hash)| {
+            let pos = (so.epoch, so.seq);
+            self.positions.get(&pos).filter(|(known, _)| known != hash).map(|(_, idx)| (pos, *idx))
+        });
+        if let Some(((epoch, seq), idx)) = conflict {
+            warn!("Sequencer => {} vergibt ({}, {}) doppelt => abgesetzt", leader.node_id, epoch, seq);
+            self.equivocation = Some(SequencerEquivocation { first: self.batches[idx].clone(), second: batch });
+            self.leader = None;
+            return Err(DexError::Other(format!("Sequencer {} equivocated at ({}, {})", leader.node_id, epoch, seq)));
+        }
+        let idx = self.batches.len();
+        for (so, hash) in batch.orders.iter().zip(hashes) {
+            if so.epoch != leader.epoch {
+                continue;
+            }
+            self.positions.entry((so.epoch, so.seq)).or_insert((hash, idx));
+            if (so.epoch, so.seq) >= self.next {
+                self.pending.entry((so.epoch, so.seq)).or_insert_with(|| so.order.clone());
+            }
+        }
+        self.batches.push(batch);
+        Ok(())
+    }
+
+    /// Liefert alle Orders, die lückenlos an die letzte Position anschließen,
+    /// jeweils mit ihrer (epoch, seq).
+    pub fn drain_ready(&mut self) -> Vec<((u64, u64), OrderData)> {
+        let mut ready = Vec::new();
+        while let Some(order) = self.pending.remove(&self.next) {
+            ready.push((self.next, order));
+            self.next.1 += 1;
+        }
+        ready
+    }
+}
+
+/// Sequencer-Ablauf eines Kandidaten am Netz: je Epoche eigenen Claim senden,
+/// Claims sammeln und den Sequencer wählen. Der Gewählte signiert die
+/// eingehenden Orders als Batches, alle anderen nehmen Batches an und leiten
+/// ihre verwaisten Orders an ihn weiter.
+pub struct SequencerNode {
+    config: SequencerConfig,
+    node_id: String,
+    vrf: VrfKeypair,
+    keypair: Keypair,
+    engine: Arc<Mutex<MatchingEngine>>,
+}
+
+impl SequencerNode {
+    /// `engine` muss mit `with_sequencing(config.election())` gebaut sein.
+    pub fn new(
+        config: SequencerConfig,
+        node_id: &str,
+        vrf: VrfKeypair,
+        keypair: Keypair,
+        engine: Arc<Mutex<MatchingEngine>>,
+    ) -> Self {
+        Self { config, node_id: node_id.to_string(), vrf, keypair, engine }
+    }
+
+    fn sequencer_for(&self, claim: &SequencerClaim) -> Option<OrderSequencer> {
+        if claim.node_id != self.node_id {
+            return None;
+        }
+        let keypair = Keypair::from_bytes(&self.keypair.to_bytes()).ok()?;
+        Some(OrderSequencer::new(claim.clone(), keypair))
+    }
+
+    /// Hauptschleife: Nachrichten aus `inbox`, Versand über `send`,
+    /// Equivocation-Beweise gehen an `on_equivocation`.
+    pub async fn run<S, F>(self, mut inbox: UnboundedReceiver<SequencerWire>, send: S, on_equivocation: F)
+    where
+        S: Fn(SocketAddr, SequencerWire),
+        F: Fn(SequencerEquivocation),
+    {
+        let peers = self.config.peer_addrs(&self.node_id);
+        let broadcast = |wire: &SequencerWire| {
+            for addr in peers.values() {
+                send(*addr, wire.clone());
+            }
+        };
+        let claim_window = Duration::from_millis(self.config.claim_window_ms);
+        let mut ticker = tokio::time::interval(Duration::from_millis(self.config.batch_interval_ms));
+        let mut current_epoch = 0;
+        // Laufende Claim-Runde (Epoche, Ende des Sammelfensters)
+        let mut round: Option<(u64, Instant)> = None;
+        let mut claims: Vec<SequencerClaim> = Vec::new();
+        let mut leader: Option<SequencerClaim> = None;
+        let mut sequencer: Option<OrderSequencer> = None;
+        let mut queue: Vec<OrderData> = Vec::new();
+        // Jeder Node reicht dieselben verwaisten Orders ein => nur einmal sequenzieren
+        let mut sequenced: HashSet<String> = HashSet::new();
+        loop {
+            tokio::select! {
+                Some(wire) = inbox.recv() => match wire {
+                    SequencerWire::Claim(c) if c.epoch > current_epoch => {
+                        if !claims.iter().any(|k| k.epoch == c.epoch && k.node_id == c.node_id) {
+                            claims.push(c);
+                        }
+                    }
+                    SequencerWire::Claim(c) => debug!("Sequencer => veralteter Claim von {} verworfen", c.node_id),
+                    SequencerWire::Batch(b) => {
+                        let from = b.claim.node_id.clone();
+                        if let Err(e) = self.engine.lock_recover().submit_sequenced(b) {
+                            warn!("Sequencer => Batch von {} abgelehnt: {}", from, e);
+                        }
+                    }
+                    SequencerWire::Order(o) if o.verify_signature() => queue.push(o),
+                    SequencerWire::Order(o) => warn!("Sequencer => Order {} mit ungültiger Signatur verworfen", o.id),
+                },
+                _ = ticker.tick() => {
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+                    let epoch = self.config.epoch_at(now);
+                    if epoch > current_epoch && round.map_or(true, |(e, _)| e != epoch) {
+                        let own = make_claim(&self.vrf, &self.node_id, self.config.seed, epoch);
+                        claims.retain(|c| c.epoch >= epoch);
+                        claims.push(own.clone());
+                        broadcast(&SequencerWire::Claim(own));
+                        round = Some((epoch, Instant::now() + claim_window));
+                    }
+                    if let Some((epoch, _)) = round.filter(|(_, deadline)| Instant::now() >= *deadline) {
+                        round = None;
+                        let epoch_claims: Vec<_> = claims.iter().filter(|c| c.epoch == epoch).cloned().collect();
+                        let mut engine = self.engine.lock_recover();
+                        match engine.start_sequencer_epoch(&epoch_claims, epoch) {
+                            Ok(elected) => {
+                                current_epoch = epoch;
+                                sequencer = self.sequencer_for(&elected);
+                                leader = Some(elected);
+                                sequenced.clear();
+                                queue.extend(engine.take_sequencer_orphans());
+                            }
+                            Err(e) => warn!("Sequencer => Epoche {} ohne Sequencer: {}", epoch, e),
+                        }
+                    }
+                    if let Some(proof) = self.engine.lock_recover().take_sequencer_equivocation() {
+                        warn!("Sequencer => {} hat equivociert, bis zur nächsten Epoche abgesetzt", proof.node_id());
+                        on_equivocation(proof);
+                        leader = None;
+                        sequencer = None;
+                    }
+                    if queue.is_empty() {
+                        continue;
+                    }
+                    match (&mut sequencer, &leader) {
+                        (Some(seq), _) => {
+                            let orders: Vec<_> = std::mem::take(&mut queue)
+                                .into_iter()
+                                .filter(|o| sequenced.insert(o.id.clone()))
+                                .collect();
+                            if orders.is_empty() {
+                                continue;
+                            }
+                            match seq.batch(orders) {
+                                Ok(batch) => {
+                                    broadcast(&SequencerWire::Batch(batch.clone()));
+                                    if let Err(e) = self.engine.lock_recover().submit_sequenced(batch) {
+                                        warn!("Sequencer => eigener Batch abgelehnt: {}", e);
+                                    }
+                                }
+                                Err(e) => warn!("Sequencer => Batch konnte nicht signiert werden: {}", e),
+                            }
+                        }
+                        (None, Some(l)) => match peers.get(&l.node_id) {
+                            Some(addr) => {
+                                for o in std::mem::take(&mut queue) {
+                                    send(*addr, SequencerWire::Order(o));
+                                }
+                            }
+                            None => warn!("Sequencer => keine Adresse für {}, {} Orders warten", l.node_id, queue.len()),
+                        },
+                        // Kein Sequencer => Orders warten auf die nächste Epoche
+                        (None, None) => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching_engine::{OrderSide, OrderType};
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    #[tokio::test]
+    async fn test_single_candidate_sequences_incoming_orders() {
+        let vrf = VrfKeypair::from_seed(&[7; 32]);
+        let config = SequencerConfig {
+            enabled: true,
+            seed: 1,
+            epoch_secs: 1,
+            claim_window_ms: 50,
+            batch_interval_ms: 20,
+            candidates: vec![SequencerCandidateConfig {
+                node_id: "node0".into(),
+                vrf_pk: hex::encode(vrf.pk),
+                sign_pk: hex::encode(keypair(3).public.to_bytes()),
+                addr: "127.0.0.1:1".into(),
+            }],
+        };
+        config.validate().unwrap();
+        let engine = Arc::new(Mutex::new(MatchingEngine::new().with_sequencing(config.election().unwrap())));
+        let node = SequencerNode::new(config, "node0", vrf, keypair(3), engine.clone());
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let sent_by_node = sent.clone();
+        let task = tokio::spawn(node.run(rx, move |_, w| sent_by_node.lock_recover().push(w), |_| {}));
+
+        let order = OrderData::new("b1", "user", OrderSide::Buy, OrderType::Limit(100.0), 1.0, 0).signed_for_tests();
+        tx.send(SequencerWire::Order(order.clone())).unwrap();
+        // Gleiche Order doppelt (von mehreren Nodes weitergeleitet) => nur einmal sequenziert
+        tx.send(SequencerWire::Order(order)).unwrap();
+        let mut booked = Vec::new();
+        for _ in 0..100 {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            let mut e = engine.lock_recover();
+            e.match_orders().unwrap();
+            booked = e.order_book.buy_orders.iter().map(|lo| lo.order.id.clone()).collect();
+            if !booked.is_empty() {
+                break;
+            }
+        }
+        task.abort();
+        assert_eq!(booked, vec!["b1".to_string()]);
+        // Ohne andere Kandidaten wird nichts versendet
+        assert!(sent.lock_recover().is_empty());
+    }
+}
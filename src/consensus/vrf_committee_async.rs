@@ -6,60 +6,128 @@ use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
 use rand::Rng;
+use sha2::{Digest, Sha512};
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
 use tracing::{info, debug, warn, error};
 
-// --- Fiktive VRF-Funktionen (curve25519-dalek-VRF) DEMO ---
+// --- VRF (ECVRF-Konstruktion auf Ristretto255, curve25519-dalek) ---
+//
+//  - pk = x·G, H = hash_to_group(pk, msg), Gamma = x·H
+//  - Proof = (Gamma, c, s) mit c = H(pk, H, Gamma, k·G, k·H), s = k + c·x
+//  - Wert = H(Gamma) => eindeutig pro (Key, msg), ohne sk nicht vorhersagbar
+//    und vom Ersteller nicht wählbar (anders als eine bloße Signatur).
+
+const VRF_PROOF_LEN: usize = 96;
+
 #[derive(Clone)]
 pub struct VrfKeypair {
-    pub sk: [u8; 64],
+    sk: Scalar,
     pub pk: [u8; 32],
 }
+
+impl VrfKeypair {
+    /// Deterministisch aus einem 32-Byte-Seed (z. B. aus dem Keystore).
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        let sk = Scalar::from_bytes_mod_order_wide(&hash_wide(b"my_dex/vrf/sk", &[&seed[..]]));
+        let pk = (sk * RISTRETTO_BASEPOINT_POINT).compress().to_bytes();
+        VrfKeypair { sk, pk }
+    }
+}
+
 #[derive(Clone)]
 pub struct VrfProof {
     pub bytes: Vec<u8>,
 }
 
+fn hash_wide(domain: &[u8], parts: &[&[u8]]) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    hasher.update(domain);
+    for part in parts {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    let mut out = [0u8; 64];
+    out.copy_from_slice(&hasher.finalize());
+    out
+}
+
+fn hash_to_point(pk: &[u8; 32], msg: &[u8]) -> RistrettoPoint {
+    RistrettoPoint::from_uniform_bytes(&hash_wide(b"my_dex/vrf/h2g", &[&pk[..], msg]))
+}
+
+fn challenge(pk: &[u8; 32], h: &RistrettoPoint, gamma: &RistrettoPoint, u: &RistrettoPoint, v: &RistrettoPoint) -> Scalar {
+    Scalar::from_bytes_mod_order_wide(&hash_wide(b"my_dex/vrf/challenge", &[
+        &pk[..],
+        &h.compress().as_bytes()[..],
+        &gamma.compress().as_bytes()[..],
+        &u.compress().as_bytes()[..],
+        &v.compress().as_bytes()[..],
+    ]))
+}
+
+fn output_value(gamma: &RistrettoPoint) -> u64 {
+    let out = hash_wide(b"my_dex/vrf/output", &[&gamma.compress().as_bytes()[..]]);
+    u64::from_be_bytes(out[..8].try_into().unwrap())
+}
+
 pub fn generate_keypair() -> VrfKeypair {
     let mut rng = rand::thread_rng();
-    let mut sk = [0u8; 64];
-    rng.fill(&mut sk);
-    let mut pk = [0u8; 32];
-    rng.fill(&mut pk);
-    VrfKeypair { sk, pk }
+    let mut seed = [0u8; 32];
+    rng.fill(&mut seed);
+    VrfKeypair::from_seed(&seed)
 }
 
-/// Scheinfunktion: VRF-Sign => (value, proof)
+/// VRF-Sign => (value, proof)
 pub fn vrf_sign(kp: &VrfKeypair, msg: &[u8]) -> (u64, VrfProof) {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-    let mut hasher = DefaultHasher::new();
-    kp.sk.hash(&mut hasher);
-    msg.hash(&mut hasher);
-    let val = hasher.finish() % 1_000_000;
-    let mut proofbytes = Vec::new();
-    proofbytes.extend_from_slice(&kp.pk);
-    proofbytes.extend_from_slice(&val.to_le_bytes());
-    (val, VrfProof { bytes: proofbytes })
+    let h = hash_to_point(&kp.pk, msg);
+    let gamma = kp.sk * h;
+    // Deterministischer Nonce (wie RFC 9381, Abschnitt 5.4.2.2)
+    let k = Scalar::from_bytes_mod_order_wide(&hash_wide(b"my_dex/vrf/nonce", &[
+        &kp.sk.as_bytes()[..],
+        &h.compress().as_bytes()[..],
+    ]));
+    let c = challenge(&kp.pk, &h, &gamma, &(k * RISTRETTO_BASEPOINT_POINT), &(k * h));
+    let s = k + c * kp.sk;
+    let mut bytes = Vec::with_capacity(VRF_PROOF_LEN);
+    bytes.extend_from_slice(gamma.compress().as_bytes());
+    bytes.extend_from_slice(c.as_bytes());
+    bytes.extend_from_slice(s.as_bytes());
+    (output_value(&gamma), VrfProof { bytes })
 }
 
-/// VRF-Verify => prüft pseudo
+/// VRF-Verify => Proof muss zu `pk` und `msg` passen und `val` ergeben.
 pub fn vrf_verify(pk: &[u8], msg: &[u8], val: u64, proof: &VrfProof) -> bool {
-    if proof.bytes.len() < 40 {
+    if proof.bytes.len() != VRF_PROOF_LEN {
         return false;
     }
-    let stored_pk = &proof.bytes[0..32];
-    let stored_val = &proof.bytes[32..40];
-    if stored_pk != pk { 
-        return false; 
-    }
-    let val_proof = u64::from_le_bytes(stored_val.try_into().unwrap());
-    // Wir ignorieren msg in diesem Mock
-    val_proof == val
+    let pk: [u8; 32] = match pk.try_into() {
+        Ok(b) => b,
+        Err(_) => return false,
+    };
+    let decode_point = |b: &[u8]| CompressedRistretto::from_slice(b).ok().and_then(|c| c.decompress());
+    let decode_scalar = |b: &[u8]| -> Option<Scalar> {
+        Option::from(Scalar::from_canonical_bytes(b.try_into().ok()?))
+    };
+    let (y, gamma, c, s) = match (
+        decode_point(&pk),
+        decode_point(&proof.bytes[0..32]),
+        decode_scalar(&proof.bytes[32..64]),
+        decode_scalar(&proof.bytes[64..96]),
+    ) {
+        (Some(y), Some(gamma), Some(c), Some(s)) => (y, gamma, c, s),
+        _ => return false,
+    };
+    let h = hash_to_point(&pk, msg);
+    let u = s * RISTRETTO_BASEPOINT_POINT - c * y;
+    let v = s * h - c * gamma;
+    challenge(&pk, &h, &gamma, &u, &v) == c && output_value(&gamma) == val
 }
-// --- ENDE VRF-Stub ---
+// --- ENDE VRF ---
 
 /////////////////////////////////////////////////////////
 // Block-Struktur => wir keepen: 
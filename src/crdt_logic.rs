@@ -10,27 +10,56 @@
 // - (Kleine Warnungen / Logs)
 //
 // NEU: Signaturfelder in Order + verify_signature() + Optionale Methode
-//      add_local_order_with_signature(...)
+//      add_local_order_with_signature(...). Signiert wird über kanonisches
+//      CBOR (utils::canonical, Domain "my_dex/crdt/order/v1").
+//
+// NEU: Jede Order trägt einen HLC-Zeitstempel (utils::hlc), vergeben bei
+//      Erzeugung. Empfangene Orders ziehen die lokale Uhr nach. Bei mehreren
+//      sichtbaren Versionen derselben Order-ID gewinnt der größte HLC,
+//      visible_orders() liefert in HLC-Reihenfolge => auf allen Nodes gleich.
+//
+// NEU: Weichen zwei Replikas bei einer Order-ID voneinander ab, entscheidet
+//      beim Merge eine ConflictPolicy (decentralized_order_book::conflict_resolution).
+//      Verlierer werden per Tombstone entfernt, die letzten Entscheidungen
+//      (höchstens `MAX_CONFLICT_LOG`) landen in `conflict_log`.
+//
+// NEU: state_root() => kanonischer Hash über die sichtbaren Orders (nach id
+//      sortiert), unabhängig von der HashMap-Reihenfolge. Zwei Nodes mit
+//      gleichem Buch liefern denselben Root.
+//
+// NEU: merge_snapshot() prüft die Signaturen aller empfangenen Orders;
+//      with_required_signatures() lehnt zusätzlich unsignierte ab.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{info, warn, debug, instrument};
 
+use crate::decentralized_order_book::conflict_resolution::{ConflictOutcome, ConflictPolicy, HlcLastWriterWins};
 use crate::error::DexError;
 use crate::metrics::{CRDT_MERGE_COUNT, PARTIAL_FILL_COUNT};
+use crate::node_logic::OrderSide;
+use crate::storage::replicated_db_layer::{SnapshotCheckFn, SnapshotMergeFn};
+use crate::utils::canonical;
+use crate::utils::geoip_and_ntp::ClockSkewGuard;
+use crate::utils::hlc::{HlcTimestamp, HybridLogicalClock};
 
-// Beispiel: Damit du Signaturen validieren kannst, brauchst du evtl. 
-// eine Krypto-Lib wie ed25519_dalek. Hier minimal:
-use ed25519_dalek::{PublicKey, Signature, Verifier}; 
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
 use sha2::{Sha256, Digest};
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Order {
     pub id: String,
     pub user_id: String,
     pub timestamp: u64,
     pub quantity: f64,
     pub price: f64,
+    /// Kauf/Verkauf; None => unbekannt (Preis zählt dann bei Konflikten nicht)
+    #[serde(default)]
+    pub side: Option<OrderSide>,
+    /// Kausaler Zeitstempel, vergeben vom erzeugenden Node
+    pub hlc: HlcTimestamp,
     
     // NEU: Optionale Signaturfelder
     pub signature: Option<Vec<u8>>,
@@ -38,9 +67,38 @@ pub struct Order {
 }
 
 impl Order {
-    /// Beispiel-Implementierung einer Signaturprüfung.
-    /// In echter Produktion müsstest du definieren,
-    /// wie genau du signierst (Nachricht, Hashtyp, etc.).
+    /// Kanonische Bytes der signierten Felder (ohne `hlc`, den vergibt der Node).
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct CrdtOrderSigningView<'a> {
+            id: &'a str,
+            user_id: &'a str,
+            timestamp: u64,
+            quantity: f64,
+            price: f64,
+            side: &'a Option<OrderSide>,
+            public_key: &'a Option<Vec<u8>>,
+        }
+        canonical::signing_bytes("my_dex/crdt/order/v1", &CrdtOrderSigningView {
+            id: &self.id,
+            user_id: &self.user_id,
+            timestamp: self.timestamp,
+            quantity: self.quantity,
+            price: self.price,
+            side: &self.side,
+            public_key: &self.public_key,
+        })
+    }
+
+    /// Setzt `public_key` und signiert `signing_bytes`.
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<(), DexError> {
+        self.public_key = Some(keypair.public.to_bytes().to_vec());
+        let bytes = self.signing_bytes()?;
+        self.signature = Some(keypair.sign(&bytes).to_bytes().to_vec());
+        Ok(())
+    }
+
+    /// Ed25519-Prüfung über `signing_bytes`.
     pub fn verify_signature(&self) -> bool {
         // Falls wir gar keine Signatur haben, return false
         let (Some(sig_bytes), Some(pk_bytes)) = (self.signature.as_ref(), self.public_key.as_ref()) else {
@@ -55,26 +113,14 @@ impl Order {
             return false;
         };
 
-        // Definiere die zu signierende Nachricht – z. B. "id + user_id + quantity + price + timestamp"
-        let msg = format!("{}:{}:{}:{}:{}",
-            self.id, 
-            self.user_id, 
-            self.quantity, 
-            self.price, 
-            self.timestamp
-        );
-        // Digest optional, wir könnten die Bytes direkt signieren.
-        // Hier als Bsp: Sha256
-        let mut hasher = Sha256::new();
-        hasher.update(msg.as_bytes());
-        let hashed = hasher.finalize();
-
-        // Prüfe Signatur
-        pubkey.verify(&hashed, &signature).is_ok()
+        match self.signing_bytes() {
+            Ok(bytes) => pubkey.verify(&bytes, &signature).is_ok(),
+            Err(_) => false,
+        }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CrdtDot {
     pub node_id: String,
     pub counter: u64,
@@ -83,6 +129,19 @@ pub struct CrdtDot {
 // GCounter => node => val
 pub type GCounter = HashMap<String, u64>;
 
+/// Obergrenze für `conflict_log`; ältere Einträge fallen heraus.
+pub const MAX_CONFLICT_LOG: usize = 1_000;
+
+/// Protokoll einer Konfliktentscheidung beim Merge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConflictRecord {
+    pub order_id: String,
+    pub policy: &'static str,
+    pub local_hlc: HlcTimestamp,
+    pub remote_hlc: HlcTimestamp,
+    pub outcome: ConflictOutcome,
+}
+
 #[derive(Clone, Debug)]
 pub struct CrdtORSet {
     pub adds: HashMap<Order, HashSet<CrdtDot>>,
@@ -97,6 +156,19 @@ pub struct CrdtState {
 
     // fill_counters => Key=Order => GCounter
     pub fill_counters: HashMap<Order, GCounter>,
+
+    // HLC dieses Nodes => stempelt lokale Orders
+    pub clock: HybridLogicalClock,
+
+    // Policy für abweichende Versionen derselben Order-ID + Audit-Log
+    pub conflict_policy: Arc<dyn ConflictPolicy>,
+    pub conflict_log: VecDeque<ConflictRecord>,
+
+    // Remote-HLCs aus der Zukunft ablehnen, damit sie die Uhr nicht vorziehen (None => keine Prüfung)
+    pub clock_guard: Option<Arc<ClockSkewGuard>>,
+
+    // Snapshots nur mit signierten Orders annehmen (ungültige Signaturen werden immer abgelehnt)
+    pub require_signatures: bool,
 }
 
 impl Default for CrdtState {
@@ -109,11 +181,94 @@ impl Default for CrdtState {
             counters: HashMap::new(),
             offline: false,
             fill_counters: HashMap::new(),
+            clock: HybridLogicalClock::default(),
+            conflict_policy: Arc::new(HlcLastWriterWins),
+            conflict_log: VecDeque::new(),
+            clock_guard: None,
+            require_signatures: false,
         }
     }
 }
 
 impl CrdtState {
+    /// State mit HLC für `node_id` (Default nutzt einen generischen Node-Tag).
+    pub fn new(node_id: &str) -> Self {
+        Self { clock: HybridLogicalClock::new(node_id), ..Self::default() }
+    }
+
+    pub fn with_conflict_policy(mut self, policy: Arc<dyn ConflictPolicy>) -> Self {
+        self.conflict_policy = policy;
+        self
+    }
+
+    pub fn with_clock_guard(mut self, guard: Arc<ClockSkewGuard>) -> Self {
+        self.clock_guard = Some(guard);
+        self
+    }
+
+    pub fn with_required_signatures(mut self) -> Self {
+        self.require_signatures = true;
+        self
+    }
+
+    /// Remote-HLC höchstens `max_skew_ms` vor der NTP-korrigierten Zeit?
+    /// Alte Zeitstempel sind beim Merge normal und bleiben erlaubt.
+    fn check_remote_hlc(&self, hlc: HlcTimestamp) -> Result<(), DexError> {
+        match &self.clock_guard {
+            Some(guard) => guard.check_not_future_ms(hlc.physical_ms),
+            None => Ok(()),
+        }
+    }
+
+    /// Entfernt eine Version, indem alle ihre Add-Dots als Remove-Dots gesetzt werden.
+    fn tombstone(&mut self, ord: &Order) {
+        let dots = self.orset.adds.get(ord).cloned().unwrap_or_default();
+        self.orset.removes.entry(ord.clone()).or_insert_with(HashSet::new).extend(dots);
+    }
+
+    /// Löst Konflikte zwischen lokal sichtbaren Versionen und den sichtbaren
+    /// Versionen des Remote-States (gleiche ID, unterschiedliche Felder).
+    /// Lokale Versionen werden vorab nach ID gruppiert => O(n + m).
+    fn resolve_conflicts(&mut self, remote_visible: &[Order], local_visible: &[Order]) {
+        let mut local_by_id: HashMap<&str, Vec<&Order>> = HashMap::new();
+        for l in local_visible {
+            local_by_id.entry(l.id.as_str()).or_default().push(l);
+        }
+        for r in remote_visible {
+            let Some(candidates) = local_by_id.get(r.id.as_str()) else { continue };
+            for l in candidates.iter().copied().filter(|l| *l != r) {
+                if !self.is_visible(l) || !self.is_visible(r) {
+                    continue;
+                }
+                let outcome = self.conflict_policy.resolve(l, r);
+                match outcome {
+                    ConflictOutcome::KeepLocal => self.tombstone(r),
+                    ConflictOutcome::TakeRemote => self.tombstone(l),
+                    ConflictOutcome::RejectBoth => {
+                        self.tombstone(l);
+                        self.tombstone(r);
+                    }
+                }
+                info!(
+                    "CRDT-Konflikt order_id={} policy={} outcome={:?}",
+                    r.id,
+                    self.conflict_policy.name(),
+                    outcome
+                );
+                if self.conflict_log.len() >= MAX_CONFLICT_LOG {
+                    self.conflict_log.pop_front();
+                }
+                self.conflict_log.push_back(ConflictRecord {
+                    order_id: r.id.clone(),
+                    policy: self.conflict_policy.name(),
+                    local_hlc: l.hlc,
+                    remote_hlc: r.hlc,
+                    outcome,
+                });
+            }
+        }
+    }
+
     fn next_dot(&mut self, node_id: &str) -> CrdtDot {
         let ctr = self.counters.entry(node_id.to_string()).or_insert(0);
         *ctr += 1;
@@ -142,16 +297,18 @@ impl CrdtState {
         false
     }
 
+    /// Mehrere sichtbare Versionen derselben ID => größter HLC gewinnt.
     fn find_visible_order(&self, order_id: &str) -> Result<Order, DexError> {
-        for (ord, _) in &self.orset.adds {
-            if ord.id == order_id && self.is_visible(ord) {
-                return Ok(ord.clone());
-            }
-        }
-        Err(DexError::OrderNotFound { order_id: order_id.to_string() })
+        self.orset
+            .adds
+            .keys()
+            .filter(|ord| ord.id == order_id && self.is_visible(ord))
+            .max_by_key(|ord| ord.hlc)
+            .cloned()
+            .ok_or_else(|| DexError::OrderNotFound { order_id: order_id.to_string() })
     }
 
-    fn partial_filled_sum(&self, ord: &Order) -> f64 {
+    pub(crate) fn partial_filled_sum(&self, ord: &Order) -> f64 {
         match self.fill_counters.get(ord) {
             Some(gc) => {
                 let mut s = 0.0;
@@ -172,6 +329,31 @@ impl CrdtState {
         user_id: &str,
         quantity: f64,
         price: f64,
+    ) -> Result<(), DexError> {
+        self.insert_local_order(node_id, order_id, user_id, None, quantity, price)
+    }
+
+    /// Wie `add_local_order`, mit Seite (für Preis-Priorität bei Konflikten).
+    pub fn add_local_order_with_side(
+        &mut self,
+        node_id: &str,
+        order_id: &str,
+        user_id: &str,
+        side: OrderSide,
+        quantity: f64,
+        price: f64,
+    ) -> Result<(), DexError> {
+        self.insert_local_order(node_id, order_id, user_id, Some(side), quantity, price)
+    }
+
+    fn insert_local_order(
+        &mut self,
+        node_id: &str,
+        order_id: &str,
+        user_id: &str,
+        side: Option<OrderSide>,
+        quantity: f64,
+        price: f64,
     ) -> Result<(), DexError> {
         let dot = self.next_dot(node_id);
         let now = SystemTime::now()
@@ -192,6 +374,8 @@ impl CrdtState {
             timestamp: now,
             quantity,
             price,
+            side,
+            hlc: self.clock.tick(),
             signature: None,
             public_key: None,
         };
@@ -236,6 +420,8 @@ impl CrdtState {
             timestamp: now,
             quantity,
             price,
+            side: None,
+            hlc: self.clock.tick(),
             signature: Some(signature),
             public_key: Some(public_key),
         };
@@ -255,6 +441,21 @@ impl CrdtState {
         Ok(())
     }
 
+    /// Übernimmt eine empfangene Order samt ihrem HLC (z. B. aus einem Delta)
+    /// und zieht die lokale Uhr nach.
+    #[instrument(name="crdt_add_remote_order", skip(self, order), fields(order_id = %order.id))]
+    pub fn add_remote_order(&mut self, node_id: &str, order: Order) -> Result<(), DexError> {
+        if order.quantity <= 0.0 {
+            return Err(DexError::Other("Quantity must be >0".into()));
+        }
+        self.check_remote_hlc(order.hlc)?;
+        self.clock.update(order.hlc);
+        let dot = self.next_dot(node_id);
+        self.orset.adds.entry(order.clone()).or_insert_with(HashSet::new).insert(dot);
+        self.fill_counters.entry(order).or_insert_with(HashMap::new);
+        Ok(())
+    }
+
     #[instrument(name="crdt_remove_local_order", skip(self))]
     pub fn remove_local_order(&mut self, node_id: &str, order_id: &str) -> Result<(), DexError> {
         let dot = self.next_dot(node_id);
@@ -343,8 +544,16 @@ impl CrdtState {
         if self.offline {
             return Err(DexError::NetworkPartition);
         }
+        // Empfang => HLC auf den größten gesehenen Zeitstempel ziehen
+        if let Some(max_hlc) = remote.orset.adds.keys().map(|o| o.hlc).max() {
+            self.check_remote_hlc(max_hlc)?;
+            self.clock.update(max_hlc);
+        }
         CRDT_MERGE_COUNT.inc();
 
+        let local_visible = self.visible_orders();
+        let remote_visible = remote.visible_orders();
+
         // union => orset adds, removes
         for (o, adddots) in &remote.orset.adds {
             let local = self.orset.adds.entry(o.clone()).or_insert_with(HashSet::new);
@@ -378,10 +587,13 @@ impl CrdtState {
             }
         }
 
+        self.resolve_conflicts(&remote_visible, &local_visible);
+
         debug!("merge_remote => done for node_id={}", node_id);
         Ok(())
     }
 
+    /// Sichtbare Orders in HLC-Reihenfolge (Gleichstand => Order-ID).
     #[instrument(name="crdt_visible_orders", skip(self))]
     pub fn visible_orders(&self) -> Vec<Order> {
         let mut out = Vec::new();
@@ -390,16 +602,316 @@ impl CrdtState {
                 out.push(ord.clone());
             }
         }
+        out.sort_by(|a, b| a.hlc.cmp(&b.hlc).then_with(|| a.id.cmp(&b.id)));
         debug!("crdt_visible_orders => found {} orders", out.len());
         out
     }
 }
 
+/// Serialisierbare Form des replizierten Zustands (OR-Set + Fill-Counter)
+/// für den Versand über das Netz. Lokale Felder (Uhr, Policy, Log) fehlen.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CrdtSnapshot {
+    pub adds: Vec<(Order, Vec<CrdtDot>)>,
+    pub removes: Vec<(Order, Vec<CrdtDot>)>,
+    pub fill_counters: Vec<(Order, Vec<(String, u64)>)>,
+}
+
+impl CrdtState {
+    pub fn snapshot(&self) -> CrdtSnapshot {
+        let dots = |m: &HashMap<Order, HashSet<CrdtDot>>| {
+            m.iter().map(|(o, d)| (o.clone(), d.iter().cloned().collect())).collect()
+        };
+        CrdtSnapshot {
+            adds: dots(&self.orset.adds),
+            removes: dots(&self.orset.removes),
+            fill_counters: self
+                .fill_counters
+                .iter()
+                .map(|(o, gc)| (o.clone(), gc.iter().map(|(n, v)| (n.clone(), *v)).collect()))
+                .collect(),
+        }
+    }
+
+    /// Jede Order im Snapshot mit Signatur oder Public Key muss gültig
+    /// signiert sein; mit `require_signatures` auch jede unsignierte.
+    fn check_snapshot_signatures(&self, snap: &CrdtSnapshot) -> Result<(), DexError> {
+        let orders = snap.adds.iter().chain(&snap.removes).map(|(o, _)| o).chain(snap.fill_counters.iter().map(|(o, _)| o));
+        for o in orders {
+            let signed = o.signature.is_some() || o.public_key.is_some();
+            if (signed || self.require_signatures) && !o.verify_signature() {
+                return Err(DexError::InvalidSignature(format!("CRDT-Order {} im Snapshot", o.id)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Merged einen empfangenen Snapshot wie einen Remote-State, nachdem
+    /// alle Order-Signaturen geprüft wurden (ein ungültiger Eintrag verwirft
+    /// den ganzen Snapshot).
+    pub fn merge_snapshot(&mut self, node_id: &str, snap: &CrdtSnapshot) -> Result<(), DexError> {
+        self.check_snapshot_signatures(snap)?;
+        let mut remote = CrdtState::default();
+        for (o, d) in &snap.adds {
+            remote.orset.adds.entry(o.clone()).or_insert_with(HashSet::new).extend(d.iter().cloned());
+        }
+        for (o, d) in &snap.removes {
+            remote.orset.removes.entry(o.clone()).or_insert_with(HashSet::new).extend(d.iter().cloned());
+        }
+        for (o, gc) in &snap.fill_counters {
+            remote.fill_counters.entry(o.clone()).or_insert_with(HashMap::new).extend(gc.iter().cloned());
+        }
+        // Zähler aus den Dots ableiten, sonst vergibt next_dot bereits benutzte Tags
+        for dot in snap.adds.iter().chain(&snap.removes).flat_map(|(_, d)| d) {
+            let ctr = remote.counters.entry(dot.node_id.clone()).or_insert(0);
+            *ctr = (*ctr).max(dot.counter);
+        }
+        self.merge_remote(node_id, &remote)
+    }
+
+    /// Kanonischer Root über alle sichtbaren Orders.
+    pub fn state_root(&self) -> [u8; 32] {
+        orders_root(&self.visible_orders())
+    }
+}
+
+/// Merge-Funktion für `DexDB::with_snapshot_merge`: beide Heads sind
+/// bincode-kodierte `CrdtSnapshot`s und werden über `merge_snapshot` vereinigt.
+pub fn snapshot_merge_fn(node_id: &str) -> SnapshotMergeFn {
+    let node_id = node_id.to_string();
+    Arc::new(move |a: &[u8], b: &[u8]| {
+        let mut state = CrdtState::default();
+        for side in [a, b] {
+            let snap: CrdtSnapshot = bincode::deserialize(side)?;
+            state.merge_snapshot(&node_id, &snap)?;
+        }
+        Ok(bincode::serialize(&state.snapshot())?)
+    })
+}
+
+/// Prüfung für `DexDB::with_snapshot_check`: verwirft empfangene Snapshots,
+/// deren Orders HLC-Zeitstempel aus der Zukunft tragen.
+pub fn snapshot_skew_check(guard: Arc<ClockSkewGuard>) -> SnapshotCheckFn {
+    Arc::new(move |data: &[u8]| {
+        let snap: CrdtSnapshot = bincode::deserialize(data)?;
+        if let Some(max_hlc) = snap.adds.iter().chain(&snap.removes).map(|(o, _)| o.hlc).max() {
+            guard.check_not_future_ms(max_hlc.physical_ms)?;
+        }
+        Ok(())
+    })
+}
+
+/// Hash über `orders`, sortiert nach id; Felder mit Längenpräfix, damit
+/// verschobene Grenzen zwischen id und user_id nicht kollidieren.
+pub fn orders_root(orders: &[Order]) -> [u8; 32] {
+    let mut sorted: Vec<&Order> = orders.iter().collect();
+    sorted.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut hasher = Sha256::new();
+    for o in sorted {
+        hasher.update((o.id.len() as u32).to_be_bytes());
+        hasher.update(o.id.as_bytes());
+        hasher.update((o.user_id.len() as u32).to_be_bytes());
+        hasher.update(o.user_id.as_bytes());
+        hasher.update(o.timestamp.to_be_bytes());
+        hasher.update(o.quantity.to_bits().to_be_bytes());
+        hasher.update(o.price.to_bits().to_be_bytes());
+    }
+    hasher.finalize().into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::error::DexError;
 
+    #[test]
+    fn test_snapshot_merge_fn_unites_concurrent_heads() {
+        let mut a = CrdtState::new("NodeA");
+        let mut b = CrdtState::new("NodeB");
+        a.add_local_order("NodeA", "o1", "alice", 1.0, 100.0).unwrap();
+        b.add_local_order("NodeB", "o2", "bob", 2.0, 101.0).unwrap();
+        let bytes_a = bincode::serialize(&a.snapshot()).unwrap();
+        let bytes_b = bincode::serialize(&b.snapshot()).unwrap();
+
+        let merge = snapshot_merge_fn("NodeA");
+        let merged: CrdtSnapshot = bincode::deserialize(&merge(&bytes_a, &bytes_b).unwrap()).unwrap();
+        let mut st = CrdtState::new("NodeA");
+        st.merge_snapshot("NodeA", &merged).unwrap();
+        let mut ids: Vec<_> = st.visible_orders().iter().map(|o| o.id.clone()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["o1", "o2"]);
+
+        assert!(merge(&bytes_a, &[0xff]).is_err());
+    }
+
+    #[test]
+    fn test_future_hlc_rejected_with_clock_guard() {
+        let guard = Arc::new(ClockSkewGuard::new(5_000));
+        let mut a = CrdtState::new("NodeA");
+        a.clock.state.last_physical_ms = crate::utils::hlc::local_physical_ms() + 60_000;
+        a.add_local_order("NodeA", "o1", "alice", 1.0, 100.0).unwrap();
+
+        let mut b = CrdtState::new("NodeB").with_clock_guard(guard.clone());
+        let before = b.clock.current();
+        assert!(matches!(b.merge_remote("NodeB", &a), Err(DexError::ClockSkew { .. })));
+        assert_eq!(b.clock.current(), before, "Uhr darf nicht vorgezogen werden");
+        assert!(b.visible_orders().is_empty());
+
+        let check = snapshot_skew_check(guard);
+        assert!(check(&bincode::serialize(&a.snapshot()).unwrap()).is_err());
+        b.add_local_order("NodeB", "o2", "bob", 1.0, 100.0).unwrap();
+        check(&bincode::serialize(&b.snapshot()).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_causal_orders_order_consistently_on_two_nodes() {
+        let mut a = CrdtState::new("NodeA");
+        let mut b = CrdtState::new("NodeB");
+        // A's Wanduhr geht eine Minute vor
+        a.clock.state.last_physical_ms = crate::utils::hlc::local_physical_ms() + 60_000;
+
+        a.add_local_order("NodeA", "o1", "alice", 1.0, 100.0).unwrap();
+        b.merge_remote("NodeB", &a).unwrap();
+        // o2 entsteht auf B kausal nach o1, obwohl B's Uhr "früher" ist
+        b.add_local_order("NodeB", "o2", "bob", 1.0, 100.0).unwrap();
+        a.merge_remote("NodeA", &b).unwrap();
+
+        let ids = |st: &CrdtState| st.visible_orders().iter().map(|o| o.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&a), vec!["o1", "o2"]);
+        assert_eq!(ids(&b), ids(&a));
+        let o1 = a.find_visible_order("o1").unwrap();
+        let o2 = a.find_visible_order("o2").unwrap();
+        assert!(o2.hlc > o1.hlc);
+    }
+
+    #[test]
+    fn test_concurrent_versions_resolved_by_hlc() {
+        let mut a = CrdtState::new("NodeA");
+        let mut b = CrdtState::new("NodeB");
+        a.add_local_order("NodeA", "o1", "alice", 1.0, 100.0).unwrap();
+        b.merge_remote("NodeB", &a).unwrap();
+        b.add_local_order("NodeB", "o1", "alice", 2.0, 101.0).unwrap();
+        a.merge_remote("NodeA", &b).unwrap();
+        assert_eq!(a.find_visible_order("o1").unwrap().quantity, 2.0);
+        assert_eq!(b.find_visible_order("o1").unwrap().quantity, 2.0);
+    }
+
+    #[test]
+    fn test_merge_applies_configured_conflict_policy() {
+        use crate::decentralized_order_book::conflict_resolution::{PreserveHigherPriority, RejectBoth};
+
+        let setup = |policy: Arc<dyn ConflictPolicy>| {
+            let mut a = CrdtState::new("NodeA").with_conflict_policy(policy.clone());
+            let mut b = CrdtState::new("NodeB").with_conflict_policy(policy);
+            a.add_local_order_with_side("NodeA", "o1", "alice", OrderSide::Buy, 1.0, 105.0).unwrap();
+            b.add_local_order_with_side("NodeB", "o1", "alice", OrderSide::Buy, 2.0, 100.0).unwrap();
+            a.merge_remote("NodeA", &b).unwrap();
+            b.merge_remote("NodeB", &a).unwrap();
+            (a, b)
+        };
+
+        // Höherer Preis bleibt, auf beiden Replikas
+        let (a, b) = setup(Arc::new(PreserveHigherPriority));
+        assert_eq!(a.find_visible_order("o1").unwrap().price, 105.0);
+        assert_eq!(b.find_visible_order("o1").unwrap().price, 105.0);
+        assert_eq!(a.conflict_log.len(), 1);
+        assert_eq!(a.conflict_log[0].outcome, ConflictOutcome::KeepLocal);
+
+        // RejectBoth => Order ist überall weg
+        let (a, b) = setup(Arc::new(RejectBoth));
+        assert!(a.find_visible_order("o1").is_err());
+        assert!(b.find_visible_order("o1").is_err());
+        assert_eq!(a.conflict_log[0].policy, "reject_both");
+    }
+
+    #[test]
+    fn test_conflict_log_is_bounded() {
+        let mut a = CrdtState::new("NodeA");
+        let mut b = CrdtState::new("NodeB");
+        for i in 0..MAX_CONFLICT_LOG + 5 {
+            let id = format!("o{}", i);
+            a.add_local_order("NodeA", &id, "alice", 1.0, 100.0).unwrap();
+            b.add_local_order("NodeB", &id, "alice", 2.0, 100.0).unwrap();
+        }
+        a.merge_remote("NodeA", &b).unwrap();
+        assert_eq!(a.conflict_log.len(), MAX_CONFLICT_LOG);
+    }
+
+    #[test]
+    fn test_order_signature_over_canonical_bytes() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[5; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        let kp = Keypair { secret, public };
+        let mut o = Order {
+            id: "o1".into(),
+            user_id: "alice".into(),
+            timestamp: 1,
+            quantity: 0.1 + 0.2,
+            price: 100.0,
+            side: None,
+            hlc: HybridLogicalClock::new("n").tick(),
+            signature: None,
+            public_key: None,
+        };
+        o.sign(&kp).unwrap();
+        assert!(o.verify_signature());
+
+        // Gleicher Wert, anders berechnet => gleiche Bytes; HLC ist nicht signiert
+        let mut same = o.clone();
+        same.quantity = "0.30000000000000004".parse().unwrap();
+        same.hlc = HybridLogicalClock::new("m").tick();
+        assert_eq!(same.signing_bytes().unwrap(), o.signing_bytes().unwrap());
+        assert!(same.verify_signature());
+
+        let mut tampered = o.clone();
+        tampered.price = 99.0;
+        assert!(!tampered.verify_signature());
+    }
+
+    #[test]
+    fn test_merge_snapshot_verifies_signatures() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[6; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        let kp = Keypair { secret, public };
+        let mut a = CrdtState::new("NodeA");
+        a.add_local_order("NodeA", "o1", "alice", 1.0, 100.0).unwrap();
+        let unsigned = a.snapshot();
+
+        let mut signed = Order {
+            id: "o2".into(),
+            user_id: "bob".into(),
+            timestamp: 1,
+            quantity: 2.0,
+            price: 101.0,
+            side: None,
+            hlc: HybridLogicalClock::new("NodeA").tick(),
+            signature: None,
+            public_key: None,
+        };
+        signed.sign(&kp).unwrap();
+        let mut b = CrdtState::new("NodeB");
+        b.add_remote_order("NodeB", signed.clone()).unwrap();
+        let valid = b.snapshot();
+
+        // Manipulierter Preis => ganzer Snapshot verworfen
+        let mut tampered = valid.clone();
+        tampered.adds[0].0.price = 1.0;
+        let mut st = CrdtState::new("NodeC");
+        assert!(matches!(st.merge_snapshot("NodeC", &tampered), Err(DexError::InvalidSignature(_))));
+        assert!(st.visible_orders().is_empty());
+
+        // Standard: unsignierte erlaubt, gültig signierte sowieso
+        st.merge_snapshot("NodeC", &unsigned).unwrap();
+        st.merge_snapshot("NodeC", &valid).unwrap();
+        assert_eq!(st.visible_orders().len(), 2);
+
+        // Mit Pflicht-Signatur nur noch der signierte Snapshot
+        let mut strict = CrdtState::new("NodeD").with_required_signatures();
+        assert!(strict.merge_snapshot("NodeD", &unsigned).is_err());
+        strict.merge_snapshot("NodeD", &valid).unwrap();
+        assert_eq!(strict.visible_orders(), vec![signed]);
+    }
+
     #[test]
     fn test_gcounter_partial_fill_edgecases() {
         let mut st = CrdtState::default();
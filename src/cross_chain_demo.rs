@@ -9,6 +9,12 @@ use crate::dex_logic::htlc::{HTLC, AtomicSwap, SwapState};
 use sha2::{Sha256, Digest};
 
 use anyhow::{Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::settlement::advanced_settlement::{Asset as SettlementAsset, ChainSettlement, LedgerBalances, LedgerSettlement, SettlementRegistry};
+use crate::settlement::swap_coordinator::{CrossChainSwapCoordinator, SwapLeg, SwapTerms};
+use crate::storage::db_layer::DexDB;
 
 pub fn cross_chain_example() -> Result<()> {
     // Buyer: 0.10 BTC => will LTC
@@ -67,3 +73,31 @@ pub fn cross_chain_example() -> Result<()> {
 
     Ok(())
 }
+
+/// Derselbe Ablauf ueber den persistenten `CrossChainSwapCoordinator`
+/// (In-Memory-DB, Off-Chain-Ledger als Backend fuer beide Chains).
+pub fn coordinated_swap_example() -> Result<()> {
+    let balances: LedgerBalances = Arc::new(Mutex::new(HashMap::new()));
+    {
+        let mut b = balances.lock().unwrap();
+        b.entry("buyerA".into()).or_insert_with(HashMap::new).insert(SettlementAsset::BTC, (0.10, 0.0));
+        b.entry("sellerB".into()).or_insert_with(HashMap::new).insert(SettlementAsset::LTC, (10.0, 0.0));
+    }
+    let ledger: Arc<dyn ChainSettlement> = Arc::new(LedgerSettlement::new(balances.clone()));
+    let mut registry = SettlementRegistry::default();
+    registry.register(SettlementAsset::BTC, ledger.clone());
+    registry.register(SettlementAsset::LTC, ledger);
+
+    let db = Arc::new(Mutex::new(DexDB::in_memory()));
+    // Demo: einmaliges Preimage-Geheimnis statt Keystore
+    let coordinator = CrossChainSwapCoordinator::new(db, registry, rand::random());
+    let terms = SwapTerms {
+        initiator: SwapLeg { owner: "buyerA".into(), asset: SettlementAsset::BTC, amount: 0.10 },
+        counterparty: SwapLeg { owner: "sellerB".into(), asset: SettlementAsset::LTC, amount: 10.0 },
+        timelock_secs: 500,
+    };
+    let id = coordinator.negotiate(terms, 0)?;
+    let state = coordinator.drive(&id, 1)?;
+    println!("Koordinierter Swap {} => {:?}, Balances: {:?}", id, state, balances.lock().unwrap());
+    Ok(())
+}
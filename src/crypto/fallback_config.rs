@@ -2,9 +2,11 @@
 // my_dex/src/crypto/fallback_config.rs
 ///////////////////////////////////////////////////////
 
-use anyhow::{Result, Context};
+use anyhow::{anyhow, Result, Context};
+use serde::{Deserialize, Serialize};
 use tokio::time::{sleep, Duration};
 use tracing::{info, warn, error};
+use crate::storage::ipfs_fallback::IpfsFallbackStore;
 use crate::storage::ipfs_storage::cat_file_from_ipfs;
 use ed25519_dalek::{PublicKey, Signature, Verifier};
 use hex;
@@ -19,6 +21,11 @@ use hex;
 /// # R�ckgabe
 /// Gibt die geladene Konfiguration als String zur�ck oder einen Fehler, falls alle Versuche fehlschlagen.
 pub async fn load_backup_config_with_retry(backup_identifier: &str, max_retries: usize, base_delay: Duration) -> Result<String> {
+    // Lokale Upload-Queue zuerst: ist IPFS down, liegt der neueste Stand dort
+    if let Some(data) = IpfsFallbackStore::default_store().get_queued(backup_identifier) {
+        info!("Backup configuration {} aus lokaler IPFS-Queue geladen", backup_identifier);
+        return String::from_utf8(data).context("Failed to convert backup config to UTF-8");
+    }
     let mut attempt = 0;
     loop {
         match cat_file_from_ipfs(backup_identifier).await {
@@ -76,10 +83,69 @@ pub fn verify_config_signature(config: &str, signature_hex: &str, public_key_hex
     public_key.verify(config.as_bytes(), &signature).is_ok()
 }
 
+/// Format eines Config-Backups: YAML plus Ed25519-Signatur (hex) darüber.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedConfig {
+    pub config: String,
+    pub signature: String,
+}
+
+/// Prüft ein signiertes Backup gegen den lokal hinterlegten Schlüssel und
+/// liefert nur dann den Config-Inhalt zurück.
+pub fn open_signed_config(raw: &str, public_key_hex: &str) -> Result<String> {
+    let signed: SignedConfig = serde_json::from_str(raw)
+        .context("Backup config is not a signed config envelope")?;
+    if !verify_config_signature(&signed.config, &signed.signature, public_key_hex) {
+        return Err(anyhow!("Fallback configuration signature is invalid"));
+    }
+    Ok(signed.config)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::time::Duration;
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    fn envelope(kp: &Keypair, config: &str) -> String {
+        serde_json::to_string(&SignedConfig {
+            config: config.to_string(),
+            signature: hex::encode(kp.sign(config.as_bytes()).to_bytes()),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_signed_config_accepted() {
+        let kp = keypair(1);
+        let raw = envelope(&kp, "node_id: \"NodeA\"\n");
+        let pk = hex::encode(kp.public.to_bytes());
+        assert_eq!(open_signed_config(&raw, &pk).unwrap(), "node_id: \"NodeA\"\n");
+    }
+
+    #[test]
+    fn test_signed_config_rejected() {
+        let kp = keypair(1);
+        let pk = hex::encode(kp.public.to_bytes());
+
+        // anderer Signierer
+        let raw = envelope(&keypair(2), "node_id: \"NodeA\"\n");
+        assert!(open_signed_config(&raw, &pk).is_err());
+
+        // Inhalt nach dem Signieren verändert
+        let mut signed: SignedConfig = serde_json::from_str(&envelope(&kp, "log_level: info")).unwrap();
+        signed.config = "log_level: trace".into();
+        assert!(open_signed_config(&serde_json::to_string(&signed).unwrap(), &pk).is_err());
+
+        // unsigniertes YAML
+        assert!(open_signed_config("node_id: \"NodeA\"", &pk).is_err());
+    }
     
     #[tokio::test]
     async fn test_load_backup_config_with_retry_failure() {
@@ -12,6 +12,22 @@ use std::io::Write;
 /// Pfad zur Key-Datei (z. B. im Home-Verzeichnis)
 const DEFAULT_KEY_PATH: &str = ".my_dex/keys/node_key.hex";
 
+/// Lokal hinterlegter Ed25519-Schlüssel (hex), der Config-Backups signiert.
+/// Liegt bewusst NICHT im Backup selbst – sonst könnte ein Angreifer Key und
+/// Signatur gleich mitliefern.
+pub const CONFIG_SIGNING_KEY_PATH: &str = "config/config_signing_key.hex";
+
+/// Lädt den öffentlichen Config-Signaturschlüssel (hex) und prüft, dass er
+/// ein gültiger Ed25519-Key ist.
+pub fn load_config_verifying_key<P: AsRef<Path>>(path: P) -> Result<String, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path.as_ref())
+        .map_err(|e| format!("Config-Signaturschlüssel {} nicht lesbar: {}", path.as_ref().display(), e))?;
+    let hex_str = content.trim().to_string();
+    let bytes = hex::decode(&hex_str)?;
+    ed25519_dalek::PublicKey::from_bytes(&bytes)?;
+    Ok(hex_str)
+}
+
 /// Erstellt oder lädt ein KeyPair aus Datei
 pub fn get_or_create_keypair() -> Result<KeyPair, Box<dyn std::error::Error>> {
     let home = dirs::home_dir().ok_or("Kein Home-Verzeichnis gefunden")?;
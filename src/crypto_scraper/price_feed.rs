@@ -1,28 +1,508 @@
 ////////////////////////////////////////
 // my_dex/src/crypto_scraper/price_feed.rs
 ////////////////////////////////////////
+//
+// Preise werden aus mehreren unabhängigen Quellen (`FeedSource`) gesammelt.
+// Pro Symbol wird der Median gebildet; Quotes, die mehr als
+// `max_deviation` vom Median abweichen, werden verworfen und der Median neu
+// berechnet. Stimmen weniger als `min_sources` Quellen überein, gilt der Preis
+// als `stale`; unabhängig von der Konfiguration sind mindestens
+// `MIN_INDEPENDENT_SOURCES` Quellen nötig. Quellen mit hinterlegtem Schlüssel
+// müssen jeden Quote mit Ed25519 über eine kanonische Festkomma-Kodierung
+// signieren, sonst wird er ignoriert.
+//
+// Gegen Replays: Quotes älter als `max_quote_age_secs` (oder zu weit in der
+// Zukunft) werden verworfen, ebenso Quotes, die älter sind als der zuletzt
+// übernommene Quote derselben Quelle für dasselbe Symbol (gleich alt zählt
+// als Replay).
+//
+// Neben dem Scraper liefern öffentliche Börsen-Ticker (`ExchangeApiSource`,
+// konfiguriert über `PriceFeedConfig::exchange_apis`) die unabhängigen
+// Quellen für das Quorum.
+//
+// Streaming-Quellen (WebSocket, z. B. Binance-Trades) laufen über
+// `run_ws_price_stream`: bei Abbruch wird mit gejittertem exponentiellem
+// Backoff neu verbunden; kommt innerhalb von `stale_after` kein Update,
+// wird der Preis als stale markiert und die Verbindung neu aufgebaut.
+// Stream-Preise sind unsignierte Quotes wie alle anderen: sie gehen als
+// jüngster Quote ihrer Quelle in dieselbe Median-/Quorum-Aggregation ein und
+// können den Preis allein nie festlegen.
 
 use anyhow::Result;
+use async_trait::async_trait;
+use futures::StreamExt;
+use rand::Rng;
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::Utc;
 use tokio::time::{sleep, Duration};
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use crate::metrics::{PRICE_FEED_CONNECTED, PRICE_FEED_RECONNECTS, PRICE_FEED_STALE};
 use crate::crypto_scraper::stealth_browser;
+use crate::error::DexError;
 use fantoccini::Client;
 use scraper::{Html, Selector};
 
-#[derive(Debug, Clone)]
+/// Mindestanzahl unabhängiger Quellen, unabhängig von `AggregationConfig::min_sources`.
+pub const MIN_INDEPENDENT_SOURCES: usize = 2;
+
+/// Toleranz für Quote-Zeitstempel in der Zukunft (Uhrenabweichung der Quelle).
+pub const MAX_QUOTE_FUTURE_SKEW_SECS: i64 = 30;
+
+/// Festkomma-Skalierung der signierten Preise (8 Nachkommastellen).
+pub const PRICE_SCALE: f64 = 100_000_000.0;
+
+const PRICE_QUOTE_DOMAIN: &str = "my_dex/price_quote/v1";
+
+#[derive(Debug, Clone, Serialize)]
 pub struct PriceFeed {
+    /// Aggregierter Preis pro Symbol (als String für die UI / REST-Ausgabe).
     pub prices: HashMap<String, String>,
+    /// Details der Aggregation pro Symbol, inkl. `stale`-Flag.
+    pub aggregated: HashMap<String, AggregatedPrice>,
     pub last_updated: i64,
+    /// Zeitstempel des zuletzt übernommenen Quotes je (Quelle, Symbol).
+    #[serde(skip)]
+    last_quote_ts: HashMap<(String, String), i64>,
+    /// Zeitpunkt des letzten nicht-stale Aggregats je Symbol.
+    #[serde(skip)]
+    fresh_at: HashMap<String, i64>,
+    /// Übernommene Quotes der letzten Abfragerunde je Symbol.
+    #[serde(skip)]
+    polled: HashMap<String, Vec<PriceQuote>>,
+    /// Jüngster Quote je (Streaming-Quelle, Symbol).
+    #[serde(skip)]
+    stream_quotes: HashMap<(String, String), PriceQuote>,
+    /// Aggregationsparameter der letzten Abfragerunde, auch für Stream-Updates.
+    #[serde(skip)]
+    config: AggregationConfig,
 }
 
 impl PriceFeed {
     pub fn new() -> Self {
         Self {
             prices: HashMap::new(),
+            aggregated: HashMap::new(),
             last_updated: Utc::now().timestamp(),
+            last_quote_ts: HashMap::new(),
+            fresh_at: HashMap::new(),
+            polled: HashMap::new(),
+            stream_quotes: HashMap::new(),
+            config: AggregationConfig::default(),
+        }
+    }
+
+    /// Aggregiert die Quotes aller Quellen und übernimmt das Ergebnis.
+    /// Stale Preise werden in `aggregated` markiert, aber nicht in `prices` geschrieben,
+    /// damit der zuletzt gültige Wert nicht durch einen unsicheren ersetzt wird.
+    pub fn apply_quotes(&mut self, quotes: &[PriceQuote], config: &AggregationConfig) {
+        self.apply_quotes_at(quotes, config, Utc::now().timestamp());
+    }
+
+    /// Wie `apply_quotes`, mit expliziter aktueller Zeit (Unix-Sekunden).
+    pub fn apply_quotes_at(&mut self, quotes: &[PriceQuote], config: &AggregationConfig, now: i64) {
+        let mut by_symbol: HashMap<&str, Vec<&PriceQuote>> = HashMap::new();
+        for q in quotes {
+            if now - q.timestamp > config.max_quote_age_secs || q.timestamp - now > MAX_QUOTE_FUTURE_SKEW_SECS {
+                warn!("Verwerfe Quote von {} für {}: Zeitstempel {} nicht aktuell", q.source, q.symbol, q.timestamp);
+                continue;
+            }
+            let key = (q.source.clone(), q.symbol.clone());
+            // Gleicher Zeitstempel zählt als Replay: eine Quelle liefert je Sekunde einen Quote
+            if self.last_quote_ts.get(&key).map_or(false, |last| q.timestamp <= *last) {
+                warn!("Verwerfe Quote von {} für {}: nicht neuer als der zuletzt übernommene", q.source, q.symbol);
+                continue;
+            }
+            self.last_quote_ts.insert(key, q.timestamp);
+            by_symbol.entry(q.symbol.as_str()).or_default().push(q);
+        }
+        self.config = config.clone();
+        for (symbol, qs) in by_symbol {
+            self.polled.insert(symbol.to_string(), qs.into_iter().cloned().collect());
+            let agg = self.reaggregate(symbol, now);
+            if agg.stale {
+                warn!("Preis für {} ist stale ({} von {} Quellen übereinstimmend)",
+                    symbol, agg.sources_used.len(), config.min_sources);
+            }
+        }
+        self.last_updated = Utc::now().timestamp();
+    }
+
+    /// Aggregiert `symbol` neu aus der letzten Abfragerunde und den
+    /// Stream-Quotes, soweit beide nicht älter als `max_quote_age_secs` sind.
+    /// Stream-Quotes stehen vorn: meldet eine Börse per Stream und per Ticker
+    /// unter demselben Namen, zählt der jüngere Stream-Wert einmal.
+    fn reaggregate(&mut self, symbol: &str, now: i64) -> &AggregatedPrice {
+        let max_age = self.config.max_quote_age_secs;
+        let fresh = |q: &&PriceQuote| now - q.timestamp <= max_age;
+        let mut qs: Vec<&PriceQuote> = self
+            .stream_quotes
+            .values()
+            .filter(|q| q.symbol == symbol)
+            .filter(fresh)
+            .collect();
+        qs.extend(self.polled.get(symbol).into_iter().flatten().filter(fresh));
+        let agg = aggregate(symbol, &qs, &self.config);
+        if !agg.stale {
+            self.prices.insert(symbol.to_string(), agg.median.to_string());
+            self.fresh_at.insert(symbol.to_string(), now);
+        }
+        self.aggregated.insert(symbol.to_string(), agg);
+        &self.aggregated[symbol]
+    }
+
+    /// Zeitpunkt des jüngsten Aggregats, das aktuell nicht stale ist.
+    /// `last_updated` läuft auch bei reinen stale-Runden weiter und taugt
+    /// daher nicht als Gesundheitssignal. `None` => kein verlässlicher Preis.
+    pub fn last_fresh_update(&self) -> Option<i64> {
+        self.aggregated
+            .iter()
+            .filter(|(_, agg)| !agg.stale)
+            .filter_map(|(symbol, _)| self.fresh_at.get(symbol).copied())
+            .max()
+    }
+
+    /// true, wenn für `symbol` kein verlässlicher Preis vorliegt.
+    pub fn is_stale(&self, symbol: &str) -> bool {
+        self.aggregated.get(symbol).map(|a| a.stale).unwrap_or(true)
+    }
+
+    /// Übernimmt einen Preis einer Streaming-Quelle als deren jüngsten Quote
+    /// und aggregiert `symbol` neu; ohne Quorum bleibt der Preis stale.
+    pub fn apply_stream_price(&mut self, source: &str, symbol: &str, price: f64) {
+        self.apply_stream_price_at(source, symbol, price, Utc::now().timestamp());
+    }
+
+    /// Wie `apply_stream_price`, mit expliziter aktueller Zeit (Unix-Sekunden).
+    pub fn apply_stream_price_at(&mut self, source: &str, symbol: &str, price: f64, now: i64) {
+        let quote = PriceQuote {
+            source: source.to_string(),
+            symbol: symbol.to_string(),
+            price,
+            timestamp: now,
+            signature: None,
+        };
+        self.stream_quotes.insert((source.to_string(), symbol.to_string()), quote);
+        self.reaggregate(symbol, now);
+        self.last_updated = Utc::now().timestamp();
+    }
+
+    /// Streaming-Quelle liefert nicht mehr: ihr Quote fällt aus dem Aggregat.
+    pub fn drop_stream_source(&mut self, source: &str, symbol: &str) {
+        if self.stream_quotes.remove(&(source.to_string(), symbol.to_string())).is_some() {
+            self.reaggregate(symbol, Utc::now().timestamp());
+        }
+    }
+}
+
+/// Ein einzelner Kurs einer Quelle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceQuote {
+    pub source: String,
+    pub symbol: String,
+    pub price: f64,
+    pub timestamp: i64,
+    /// Hex-kodierte Ed25519-Signatur über `signing_bytes()`, falls die Quelle signiert.
+    pub signature: Option<String>,
+}
+
+/// Signierter Inhalt eines Quotes; der Preis als Festkomma-Integer in 1e-8.
+#[derive(Serialize)]
+struct QuoteSigningPayload<'a> {
+    symbol: &'a str,
+    price_e8: i64,
+    timestamp: i64,
+}
+
+impl PriceQuote {
+    /// Kanonische Bytes, die eine Quelle signiert (Domain-Tag + CBOR aus Symbol,
+    /// Festkomma-Preis und Zeitstempel). Nicht darstellbare Preise => Fehler.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        let scaled = (self.price * PRICE_SCALE).round();
+        if !scaled.is_finite() || scaled.abs() >= i64::MAX as f64 {
+            return Err(DexError::Other(format!("Price {} not representable as fixed-point", self.price)));
+        }
+        let payload = QuoteSigningPayload { symbol: &self.symbol, price_e8: scaled as i64, timestamp: self.timestamp };
+        crate::utils::canonical::signing_bytes(PRICE_QUOTE_DOMAIN, &payload)
+    }
+
+    fn verify(&self, key: &PublicKey) -> bool {
+        let bytes = match self.signing_bytes() {
+            Ok(b) => b,
+            Err(_) => return false,
+        };
+        self.signature
+            .as_ref()
+            .and_then(|h| hex::decode(h).ok())
+            .and_then(|b| Signature::from_bytes(&b).ok())
+            .map(|sig| key.verify(&bytes, &sig).is_ok())
+            .unwrap_or(false)
+    }
+}
+
+/// Eine Preisquelle (Scraper, Börsen-API, Oracle-Node, ...).
+#[async_trait]
+pub trait FeedSource: Send + Sync {
+    fn name(&self) -> &str;
+    /// Schlüssel, mit dem die Quotes dieser Quelle signiert sein müssen.
+    /// `None` => Quelle liefert unsignierte Quotes.
+    fn verifying_key(&self) -> Option<PublicKey> {
+        None
+    }
+    async fn fetch(&self) -> Result<Vec<PriceQuote>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct AggregationConfig {
+    /// Maximale relative Abweichung vom Median (0.02 = 2%).
+    pub max_deviation: f64,
+    /// Mindestanzahl übereinstimmender Quellen für einen gültigen Preis
+    /// (wird auf mindestens `MIN_INDEPENDENT_SOURCES` angehoben).
+    pub min_sources: usize,
+    /// Maximales Alter eines Quotes in Sekunden.
+    pub max_quote_age_secs: i64,
+}
+
+impl Default for AggregationConfig {
+    fn default() -> Self {
+        Self { max_deviation: 0.02, min_sources: MIN_INDEPENDENT_SOURCES, max_quote_age_secs: 120 }
+    }
+}
+
+/// `price_feed` in der Node-Config: Quorum/Toleranzen der Aggregation und
+/// die Börsen-Ticker, die neben dem Scraper abgefragt werden.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriceFeedConfig {
+    /// Übereinstimmende Quellen für einen gültigen Preis (mind. `MIN_INDEPENDENT_SOURCES`)
+    pub min_sources: usize,
+    pub max_deviation: f64,
+    pub max_quote_age_secs: i64,
+    pub exchange_apis: Vec<ExchangeApiSource>,
+}
+
+impl Default for PriceFeedConfig {
+    fn default() -> Self {
+        let agg = AggregationConfig::default();
+        Self {
+            min_sources: agg.min_sources,
+            max_deviation: agg.max_deviation,
+            max_quote_age_secs: agg.max_quote_age_secs,
+            exchange_apis: vec![
+                ExchangeApiSource {
+                    name: "binance".into(),
+                    url: "https://api.binance.com/api/v3/ticker/price?symbol=BTCUSDT".into(),
+                    symbol: "BTC".into(),
+                    price_pointer: "/price".into(),
+                },
+                ExchangeApiSource {
+                    name: "coinbase".into(),
+                    url: "https://api.coinbase.com/v2/prices/BTC-USD/spot".into(),
+                    symbol: "BTC".into(),
+                    price_pointer: "/data/amount".into(),
+                },
+            ],
+        }
+    }
+}
+
+impl PriceFeedConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.max_deviation.is_finite() || self.max_deviation <= 0.0 {
+            return Err(format!("max_deviation {} must be > 0", self.max_deviation));
+        }
+        if self.max_quote_age_secs <= 0 {
+            return Err("max_quote_age_secs must be > 0".into());
+        }
+        let mut names = std::collections::HashSet::new();
+        for api in &self.exchange_apis {
+            if api.name.trim().is_empty() || !names.insert(api.name.as_str()) {
+                return Err(format!("exchange_apis: name `{}` empty or duplicate", api.name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn aggregation(&self) -> AggregationConfig {
+        AggregationConfig {
+            max_deviation: self.max_deviation,
+            min_sources: self.min_sources.max(MIN_INDEPENDENT_SOURCES),
+            max_quote_age_secs: self.max_quote_age_secs,
+        }
+    }
+
+    pub fn sources(&self) -> Vec<Box<dyn FeedSource>> {
+        self.exchange_apis.iter().cloned().map(|s| Box::new(s) as Box<dyn FeedSource>).collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregatedPrice {
+    pub symbol: String,
+    pub median: f64,
+    pub sources_used: Vec<String>,
+    pub rejected: Vec<String>,
+    pub stale: bool,
+}
+
+/// Median einer Werteliste; `None` bei leerer Liste.
+pub fn median(values: &[f64]) -> Option<f64> {
+    let mut v: Vec<f64> = values.iter().copied().filter(|x| x.is_finite()).collect();
+    if v.is_empty() {
+        return None;
+    }
+    v.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mid = v.len() / 2;
+    if v.len() % 2 == 0 {
+        Some((v[mid - 1] + v[mid]) / 2.0)
+    } else {
+        Some(v[mid])
+    }
+}
+
+/// Bildet den Median über alle Quotes eines Symbols und verwirft Ausreißer.
+/// Pro Quelle zählt nur der erste Quote, damit eine Quelle den Median nicht
+/// durch Mehrfachmeldungen verschieben kann.
+pub fn aggregate(symbol: &str, quotes: &[&PriceQuote], config: &AggregationConfig) -> AggregatedPrice {
+    let mut seen = std::collections::HashSet::new();
+    let quotes: Vec<&PriceQuote> = quotes
+        .iter()
+        .copied()
+        .filter(|q| q.price.is_finite() && q.price > 0.0 && seen.insert(q.source.clone()))
+        .collect();
+
+    let initial = match median(&quotes.iter().map(|q| q.price).collect::<Vec<_>>()) {
+        Some(m) => m,
+        None => {
+            return AggregatedPrice {
+                symbol: symbol.to_string(),
+                median: 0.0,
+                sources_used: Vec::new(),
+                rejected: Vec::new(),
+                stale: true,
+            }
+        }
+    };
+
+    let (accepted, rejected): (Vec<&PriceQuote>, Vec<&PriceQuote>) = quotes
+        .into_iter()
+        .partition(|q| ((q.price - initial) / initial).abs() <= config.max_deviation);
+    let final_median = median(&accepted.iter().map(|q| q.price).collect::<Vec<_>>()).unwrap_or(initial);
+
+    AggregatedPrice {
+        symbol: symbol.to_string(),
+        median: final_median,
+        stale: accepted.len() < config.min_sources.max(MIN_INDEPENDENT_SOURCES),
+        sources_used: accepted.iter().map(|q| q.source.clone()).collect(),
+        rejected: rejected.iter().map(|q| q.source.clone()).collect(),
+    }
+}
+
+/// Holt die Quotes aller Quellen; fehlerhafte Quellen und ungültig signierte
+/// Quotes werden protokolliert und übersprungen.
+pub async fn collect_quotes(sources: &[Box<dyn FeedSource>]) -> Vec<PriceQuote> {
+    let mut out = Vec::new();
+    for src in sources {
+        match src.fetch().await {
+            Ok(quotes) => {
+                for mut q in quotes {
+                    q.source = src.name().to_string();
+                    if let Some(key) = src.verifying_key() {
+                        if !q.verify(&key) {
+                            warn!("Verwerfe Quote von {} für {}: ungültige Signatur", src.name(), q.symbol);
+                            continue;
+                        }
+                    }
+                    out.push(q);
+                }
+            }
+            Err(e) => warn!("Preisquelle {} nicht erreichbar: {:?}", src.name(), e),
         }
     }
+    out
+}
+
+/// Öffentlicher Ticker-Endpunkt einer Börse (JSON, ohne API-Key).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeApiSource {
+    /// Quellname im Aggregat, je Börse eindeutig
+    pub name: String,
+    pub url: String,
+    /// Symbol, unter dem der Preis aggregiert wird (wie beim Scraper, z. B. "BTC")
+    pub symbol: String,
+    /// JSON-Pointer auf den Preis (Zahl oder String), z. B. "/price"
+    pub price_pointer: String,
+}
+
+impl ExchangeApiSource {
+    /// Liest den Preis aus der Ticker-Antwort.
+    pub fn parse_price(&self, body: &serde_json::Value) -> Option<f64> {
+        match body.pointer(&self.price_pointer)? {
+            serde_json::Value::String(s) => s.parse().ok(),
+            v => v.as_f64(),
+        }
+    }
+}
+
+#[async_trait]
+impl FeedSource for ExchangeApiSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch(&self) -> Result<Vec<PriceQuote>> {
+        let body: serde_json::Value = reqwest::Client::new()
+            .get(&self.url)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let price = self
+            .parse_price(&body)
+            .ok_or_else(|| anyhow::anyhow!("{}: kein Preis unter {}", self.name, self.price_pointer))?;
+        Ok(vec![PriceQuote {
+            source: String::new(),
+            symbol: self.symbol.clone(),
+            price,
+            timestamp: Utc::now().timestamp(),
+            signature: None,
+        }])
+    }
+}
+
+/// Bestehender Stealth-Browser-Scraper als `FeedSource`.
+pub struct ScraperSource {
+    client: Client,
+}
+
+impl ScraperSource {
+    pub async fn new() -> Result<Self> {
+        Ok(Self { client: stealth_browser::setup_stealth_browser().await? })
+    }
+}
+
+#[async_trait]
+impl FeedSource for ScraperSource {
+    fn name(&self) -> &str {
+        "stealth_scraper"
+    }
+
+    async fn fetch(&self) -> Result<Vec<PriceQuote>> {
+        let now = Utc::now().timestamp();
+        let prices = extract_prices(&self.client).await?;
+        Ok(prices
+            .into_iter()
+            .filter_map(|(symbol, raw)| {
+                let price = raw.replace(',', "").trim_start_matches('$').parse::<f64>().ok()?;
+                Some(PriceQuote { source: String::new(), symbol, price, timestamp: now, signature: None })
+            })
+            .collect())
+    }
 }
 
 /// Extrahiert Kursdaten von der Ziel-Webseite mithilfe des �bergebenen Clients.
@@ -53,22 +533,427 @@ async fn extract_prices(client: &Client) -> Result<HashMap<String, String>> {
 
 /// F�hrt das PriceFeed-Update-System aus, das periodisch den Stealth-Browser verwendet,
 /// um die aktuellen Kursdaten abzurufen und den PriceFeed zu aktualisieren.
-pub async fn run_price_feed_system(price_feed: std::sync::Arc<tokio::sync::Mutex<PriceFeed>>) -> Result<()> {
+/// Eine einzelne Quelle reicht nie für einen gültigen Preis (`MIN_INDEPENDENT_SOURCES`);
+/// die weiteren, unabhängigen Quellen kommen aus `config` (Börsen-Ticker).
+/// Fällt der Scraper beim Start aus, laufen die übrigen Quellen weiter.
+pub async fn run_price_feed_system(
+    price_feed: std::sync::Arc<tokio::sync::Mutex<PriceFeed>>,
+    config: &PriceFeedConfig,
+) -> Result<()> {
+    let mut sources: Vec<Box<dyn FeedSource>> = Vec::new();
     // Initialisiere den Stealth-Browser
-    let client = stealth_browser::setup_stealth_browser().await?;
-    
+    match ScraperSource::new().await {
+        Ok(scraper) => sources.push(Box::new(scraper)),
+        Err(e) => warn!("Stealth-Scraper nicht verfügbar: {:?}", e),
+    }
+    sources.extend(config.sources());
+    let aggregation = config.aggregation();
+    if sources.len() < aggregation.min_sources {
+        warn!(
+            "Nur {} Preisquelle(n) konfiguriert => alle Preise bleiben stale (mindestens {} nötig)",
+            sources.len(),
+            aggregation.min_sources
+        );
+    }
+    run_multi_source_price_feed(price_feed, sources, aggregation).await
+}
+
+/// Fragt alle Quellen periodisch ab und aktualisiert den PriceFeed mit dem
+/// aggregierten Ergebnis.
+pub async fn run_multi_source_price_feed(
+    price_feed: std::sync::Arc<tokio::sync::Mutex<PriceFeed>>,
+    sources: Vec<Box<dyn FeedSource>>,
+    config: AggregationConfig,
+) -> Result<()> {
     loop {
-        // Extrahiere die Kursdaten
-        let prices = extract_prices(&client).await?;
-        
+        let quotes = collect_quotes(&sources).await;
+
         // Aktualisiere den PriceFeed-Zustand
         {
             let mut pf = price_feed.lock().await;
-            pf.prices = prices;
-            pf.last_updated = Utc::now().timestamp();
+            pf.apply_quotes(&quotes, &config);
         }
-        
+
         // Warte eine Minute, bevor die Kurse erneut abgefragt werden
         sleep(Duration::from_secs(60)).await;
     }
 }
+
+// ----------------------------------------------------------------
+// WebSocket-Streams mit Reconnect + Staleness-Watchdog
+// ----------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct ReconnectConfig {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Relativer Jitter (0.2 = ±20 %)
+    pub jitter: f64,
+    /// Ohne Update innerhalb dieses Fensters => stale + Reconnect
+    pub stale_after: Duration,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(60),
+            multiplier: 2.0,
+            jitter: 0.2,
+            stale_after: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectConfig {
+    /// Wartezeit vor Versuch `attempt` (0-basiert), gedeckelt auf `max_backoff`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.powi(attempt.min(32) as i32);
+        let capped = base.min(self.max_backoff.as_secs_f64());
+        let j = if self.jitter > 0.0 { rand::thread_rng().gen_range(-self.jitter..=self.jitter) } else { 0.0 };
+        Duration::from_secs_f64((capped * (1.0 + j)).clamp(0.0, self.max_backoff.as_secs_f64()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WsPriceStream {
+    /// Name für Logs/Metriken, z. B. "binance_btcusdt"
+    pub name: String,
+    pub url: String,
+    pub symbol: String,
+    /// Extrahiert den Preis aus einer Text-Nachricht
+    pub parse: fn(&str) -> Option<f64>,
+}
+
+impl WsPriceStream {
+    /// Quellname "binance" wie der Ticker in `PriceFeedConfig`, damit Stream
+    /// und Ticker derselben Börse im Quorum nur einmal zählen.
+    pub fn binance_trades(pair: &str, symbol: &str) -> Self {
+        Self {
+            name: "binance".to_string(),
+            url: format!("wss://stream.binance.com:9443/ws/{}@trade", pair),
+            symbol: symbol.to_string(),
+            parse: parse_binance_trade,
+        }
+    }
+}
+
+/// Binance-Trade-Event: Preis steht als String in "p".
+pub fn parse_binance_trade(txt: &str) -> Option<f64> {
+    let js: serde_json::Value = serde_json::from_str(txt).ok()?;
+    js.get("p")?.as_str()?.parse().ok()
+}
+
+fn set_connected(feed: &str, connected: bool) {
+    PRICE_FEED_CONNECTED.with_label_values(&[feed]).set(connected as i64);
+}
+
+/// Hält den Stream bis `token` abbricht am Leben und reicht jeden Preis als
+/// Quote der Quelle `stream.name` an die Aggregation in `price_feed` weiter.
+pub async fn run_ws_price_stream(
+    stream: WsPriceStream,
+    price_feed: std::sync::Arc<tokio::sync::Mutex<PriceFeed>>,
+    config: ReconnectConfig,
+    token: CancellationToken,
+) {
+    let feed = stream.name.as_str();
+    let mut attempt: u32 = 0;
+    while !token.is_cancelled() {
+        if attempt > 0 {
+            PRICE_FEED_RECONNECTS.with_label_values(&[feed]).inc();
+            let wait = config.backoff(attempt - 1);
+            debug!("{} => reconnect in {:?} (attempt {})", feed, wait, attempt);
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = sleep(wait) => {}
+            }
+        }
+        attempt += 1;
+
+        let ws = tokio::select! {
+            _ = token.cancelled() => break,
+            r = connect_async(stream.url.as_str()) => r,
+        };
+        let (_write, mut read) = match ws {
+            Ok((ws, _)) => ws.split(),
+            Err(e) => {
+                warn!("{} => connect failed: {:?}", feed, e);
+                continue;
+            }
+        };
+        info!("{} => verbunden", feed);
+        set_connected(feed, true);
+
+        loop {
+            let next = tokio::select! {
+                _ = token.cancelled() => break,
+                n = tokio::time::timeout(config.stale_after, read.next()) => n,
+            };
+            match next {
+                Err(_) => {
+                    warn!("{} => kein Update seit {:?} => stale, reconnect", feed, config.stale_after);
+                    PRICE_FEED_STALE.with_label_values(&[feed]).set(1);
+                    price_feed.lock().await.drop_stream_source(feed, &stream.symbol);
+                    break;
+                }
+                Ok(None) | Ok(Some(Err(_))) => {
+                    warn!("{} => Verbindung getrennt", feed);
+                    price_feed.lock().await.drop_stream_source(feed, &stream.symbol);
+                    break;
+                }
+                Ok(Some(Ok(Message::Text(txt)))) => {
+                    if let Some(px) = (stream.parse)(&txt) {
+                        price_feed.lock().await.apply_stream_price(feed, &stream.symbol, px);
+                        PRICE_FEED_STALE.with_label_values(&[feed]).set(0);
+                        // erst ein echtes Update setzt den Backoff zurück
+                        attempt = 1;
+                    }
+                }
+                Ok(Some(Ok(_))) => {}
+            }
+        }
+        set_connected(feed, false);
+    }
+    set_connected(feed, false);
+    info!("{} => beendet", feed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn quote(source: &str, price: f64) -> PriceQuote {
+        PriceQuote { source: source.into(), symbol: "BTC".into(), price, timestamp: NOW, signature: None }
+    }
+
+    #[test]
+    fn test_median_odd_and_even() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), Some(2.5));
+        assert_eq!(median(&[]), None);
+        assert_eq!(median(&[f64::NAN, 5.0]), Some(5.0));
+    }
+
+    #[test]
+    fn test_outlier_rejected() {
+        let qs = vec![quote("a", 100.0), quote("b", 101.0), quote("c", 99.5), quote("evil", 150.0)];
+        let refs: Vec<&PriceQuote> = qs.iter().collect();
+        let agg = aggregate("BTC", &refs, &AggregationConfig::default());
+        assert!(!agg.stale);
+        assert_eq!(agg.rejected, vec!["evil".to_string()]);
+        assert_eq!(agg.sources_used.len(), 3);
+        assert_eq!(agg.median, 100.0);
+    }
+
+    #[test]
+    fn test_stale_when_sources_disagree() {
+        let qs = vec![quote("a", 100.0), quote("b", 200.0)];
+        let refs: Vec<&PriceQuote> = qs.iter().collect();
+        let cfg = AggregationConfig { max_deviation: 0.01, ..AggregationConfig::default() };
+        let agg = aggregate("BTC", &refs, &cfg);
+        assert!(agg.stale);
+
+        let mut pf = PriceFeed::new();
+        pf.apply_quotes_at(&qs, &cfg, NOW);
+        assert!(pf.is_stale("BTC"));
+        assert!(pf.prices.get("BTC").is_none());
+    }
+
+    #[test]
+    fn test_duplicate_source_counts_once() {
+        let qs = vec![quote("a", 100.0), quote("a", 100.0), quote("b", 300.0)];
+        let refs: Vec<&PriceQuote> = qs.iter().collect();
+        let agg = aggregate("BTC", &refs, &AggregationConfig::default());
+        assert!(agg.stale);
+    }
+
+    #[test]
+    fn test_single_source_never_enough() {
+        let qs = vec![quote("a", 100.0)];
+        let refs: Vec<&PriceQuote> = qs.iter().collect();
+        let agg = aggregate("BTC", &refs, &AggregationConfig { min_sources: 1, ..AggregationConfig::default() });
+        assert!(agg.stale);
+    }
+
+    #[test]
+    fn test_old_and_rolled_back_quotes_rejected() {
+        let cfg = AggregationConfig::default();
+        let mut pf = PriceFeed::new();
+
+        // Zu alter Quote (Replay) zählt nicht => nur eine frische Quelle => stale
+        let mut old = quote("a", 100.0);
+        old.timestamp = NOW - cfg.max_quote_age_secs - 1;
+        pf.apply_quotes_at(&[old, quote("b", 100.0)], &cfg, NOW);
+        assert!(pf.is_stale("BTC"));
+
+        let fresh = |source: &str, price: f64, ts: i64| PriceQuote { timestamp: ts, ..quote(source, price) };
+        pf.apply_quotes_at(&[fresh("a", 100.0, NOW + 1), fresh("b", 100.0, NOW + 1)], &cfg, NOW + 1);
+        assert!(!pf.is_stale("BTC"));
+
+        // Innerhalb des Alters, aber älter als der letzte Quote von "a" => verworfen
+        pf.apply_quotes_at(&[fresh("a", 50.0, NOW - 10), fresh("b", 50.0, NOW + 2)], &cfg, NOW + 2);
+        assert!(pf.is_stale("BTC"));
+        assert_eq!(pf.prices.get("BTC").map(String::as_str), Some("100"));
+
+        // Gleicher Zeitstempel wie der letzte übernommene Quote => Replay
+        pf.apply_quotes_at(&[fresh("a", 50.0, NOW + 1), fresh("b", 50.0, NOW + 3)], &cfg, NOW + 3);
+        assert!(pf.is_stale("BTC"));
+        assert_eq!(pf.prices.get("BTC").map(String::as_str), Some("100.5"));
+    }
+
+    #[test]
+    fn test_exchange_api_price_parsing_and_default_quorum() {
+        let cfg = PriceFeedConfig::default();
+        assert!(cfg.sources().len() >= MIN_INDEPENDENT_SOURCES);
+        assert_eq!(PriceFeedConfig { min_sources: 1, ..cfg.clone() }.aggregation().min_sources, MIN_INDEPENDENT_SOURCES);
+        let binance = &cfg.exchange_apis[0];
+        let coinbase = &cfg.exchange_apis[1];
+        assert_eq!(binance.parse_price(&serde_json::json!({"symbol": "BTCUSDT", "price": "64000.5"})), Some(64000.5));
+        assert_eq!(coinbase.parse_price(&serde_json::json!({"data": {"amount": "63990.1"}})), Some(63990.1));
+        assert_eq!(binance.parse_price(&serde_json::json!({"error": 1})), None);
+        assert!(cfg.validate().is_ok());
+        let mut dup = cfg.clone();
+        dup.exchange_apis[1].name = "binance".into();
+        assert!(dup.validate().is_err());
+    }
+
+    #[test]
+    fn test_fresh_update_ignores_stale_rounds() {
+        let cfg = AggregationConfig::default();
+        let mut pf = PriceFeed::new();
+        assert_eq!(pf.last_fresh_update(), None);
+
+        pf.apply_quotes_at(&[quote("a", 100.0), quote("b", 100.0)], &cfg, NOW);
+        assert_eq!(pf.last_fresh_update(), Some(NOW));
+
+        // Nur noch eine Quelle => stale; last_updated läuft weiter, der Health-Zeitpunkt nicht
+        let mut a = quote("a", 100.0);
+        a.timestamp = NOW + 60;
+        pf.apply_quotes_at(&[a], &cfg, NOW + 60);
+        assert!(pf.is_stale("BTC"));
+        assert_eq!(pf.last_fresh_update(), None);
+    }
+
+    #[test]
+    fn test_quote_signature() {
+        let secret = SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        let kp = Keypair { secret, public };
+        let mut q = quote("oracle", 100.0);
+        q.signature = Some(hex::encode(kp.sign(&q.signing_bytes().unwrap()).to_bytes()));
+        assert!(q.verify(&public));
+        // Gleicher Festkomma-Wert => gleiche Bytes, unabhängig von der f64-Darstellung
+        q.price = 100.000000001;
+        assert!(q.verify(&public));
+        q.price = 120.0;
+        assert!(!q.verify(&public));
+        q.price = 100.0;
+        q.timestamp += 1;
+        assert!(!q.verify(&public));
+    }
+
+    #[test]
+    fn test_backoff_is_capped_and_jittered() {
+        let cfg = ReconnectConfig {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            ..ReconnectConfig::default()
+        };
+        let first = cfg.backoff(0);
+        assert!(first >= Duration::from_millis(80) && first <= Duration::from_millis(120));
+        for attempt in [5, 20, 1000] {
+            assert!(cfg.backoff(attempt) <= Duration::from_secs(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_triggers_reconnect() {
+        use futures::SinkExt;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        // Server: sendet einen Trade und trennt dann jede Verbindung
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let acc = accepted.clone();
+        tokio::spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                let n = acc.fetch_add(1, Ordering::SeqCst) + 1;
+                if let Ok(mut ws) = tokio_tungstenite::accept_async(tcp).await {
+                    let msg = format!(r#"{{"e":"trade","p":"{}.0"}}"#, 100 + n);
+                    let _ = ws.send(Message::Text(msg)).await;
+                    let _ = ws.close(None).await;
+                }
+            }
+        });
+
+        let stream = WsPriceStream {
+            name: "test_feed".into(),
+            url: format!("ws://{}", addr),
+            symbol: "BTC/USDT".into(),
+            parse: parse_binance_trade,
+        };
+        let cfg = ReconnectConfig {
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(50),
+            ..ReconnectConfig::default()
+        };
+        let pf = Arc::new(tokio::sync::Mutex::new(PriceFeed::new()));
+        let token = CancellationToken::new();
+        let task = tokio::spawn(run_ws_price_stream(stream, pf.clone(), cfg, token.clone()));
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while accepted.load(Ordering::SeqCst) < 2 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("feed did not reconnect after disconnect");
+
+        token.cancel();
+        task.await.unwrap();
+        assert!(PRICE_FEED_RECONNECTS.with_label_values(&["test_feed"]).get() >= 1);
+        // Einzelne Stream-Quelle => im Aggregat, aber ohne Quorum kein Preis
+        let pf = pf.lock().await;
+        assert!(pf.is_stale("BTC/USDT"));
+        assert!(pf.prices.get("BTC/USDT").is_none());
+    }
+
+    #[test]
+    fn test_stream_price_joins_quorum_instead_of_overriding() {
+        let cfg = AggregationConfig::default();
+        let mut pf = PriceFeed::new();
+
+        // Eine einzelne Stream-Quelle setzt keinen Preis
+        pf.apply_stream_price_at("stream", "BTC", 500.0, NOW);
+        assert!(pf.is_stale("BTC"));
+        assert!(pf.prices.get("BTC").is_none());
+
+        // Abfragerunde mit zwei übereinstimmenden Quellen => Ausreißer-Stream verworfen
+        pf.apply_quotes_at(&[quote("a", 100.0), quote("b", 101.0)], &cfg, NOW);
+        let agg = &pf.aggregated["BTC"];
+        assert!(!agg.stale);
+        assert_eq!(agg.rejected, vec!["stream".to_string()]);
+        assert_eq!(agg.median, 100.5);
+
+        // Übereinstimmender Stream-Preis zählt als weitere Quelle im Median
+        pf.apply_stream_price_at("stream", "BTC", 102.0, NOW + 1);
+        assert_eq!(pf.aggregated["BTC"].median, 101.0);
+        assert_eq!(pf.aggregated["BTC"].sources_used.len(), 3);
+
+        // Gleichnamige Quelle per Stream und Ticker zählt nur einmal
+        pf.drop_stream_source("stream", "BTC");
+        pf.apply_stream_price_at("a", "BTC", 100.0, NOW + 2);
+        assert_eq!(pf.aggregated["BTC"].sources_used.len(), 2);
+
+        // Abgelaufene Abfragerunde => der Stream allein reicht nicht
+        pf.apply_stream_price_at("a", "BTC", 100.0, NOW + cfg.max_quote_age_secs + 1);
+        assert!(pf.is_stale("BTC"));
+        assert_eq!(pf.prices.get("BTC").map(String::as_str), Some("100"));
+    }
+}
@@ -6,8 +6,8 @@
 use serde::{Serialize, Deserialize};
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
-/// Assets mit Untereinheiten
-#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+/// Assets mit Untereinheiten (Ordnung = Deklarationsreihenfolge)
+#[derive(Clone, Debug, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Asset {
     BTC,
     ETH,
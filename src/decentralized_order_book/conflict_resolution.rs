@@ -2,8 +2,147 @@
 // my_dex/src/decentralized_order_book/conflict_resolution.rs
 //////////////////////////////////////////////////////////////////////////
 
+use crate::crdt_logic::Order as CrdtOrder;
 use crate::decentralized_order_book::order::{Order, OrderType, OrderSide, OrderStatus};
+use crate::node_logic::OrderSide as CrdtSide;
 use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use tracing::warn;
+
+// ---------------------------------------------------------------------
+// Konflikt-Policies für den CRDT-Merge
+// ---------------------------------------------------------------------
+//
+// Zwei Replikas können für dieselbe Order-ID unterschiedliche Versionen
+// sichtbar haben (z. B. nebenläufige Änderungen von Menge/Preis). Beim
+// Merge (crdt_logic::CrdtState::merge_remote) entscheidet eine Policy,
+// welche Version überlebt. Verlierer werden per Tombstone entfernt.
+//
+// Wichtig: `resolve(a, b)` muss symmetrisch und deterministisch sein
+// (resolve(a,b) == resolve(b,a) gespiegelt), sonst konvergieren die
+// Replikas nicht. Alle eingebauten Policies brechen Gleichstände über den
+// HLC (inkl. Node-Tag) => totale Ordnung.
+
+/// Ergebnis einer Konfliktauflösung aus Sicht des lokalen Nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictOutcome {
+    KeepLocal,
+    TakeRemote,
+    RejectBoth,
+}
+
+pub trait ConflictPolicy: Send + Sync + Debug {
+    /// Name, unter dem die Policy in der Config ausgewählt wird.
+    fn name(&self) -> &'static str;
+    fn resolve(&self, local: &CrdtOrder, remote: &CrdtOrder) -> ConflictOutcome;
+}
+
+fn newer_wins(local: &CrdtOrder, remote: &CrdtOrder) -> ConflictOutcome {
+    if remote.hlc > local.hlc {
+        ConflictOutcome::TakeRemote
+    } else {
+        ConflictOutcome::KeepLocal
+    }
+}
+
+/// Last-Writer-Wins nach HLC.
+#[derive(Debug, Default)]
+pub struct HlcLastWriterWins;
+
+impl ConflictPolicy for HlcLastWriterWins {
+    fn name(&self) -> &'static str {
+        "hlc_lww"
+    }
+    fn resolve(&self, local: &CrdtOrder, remote: &CrdtOrder) -> ConflictOutcome {
+        newer_wins(local, remote)
+    }
+}
+
+/// Die Version mit höherer Priorität bleibt: besserer Preis (Kauf höher,
+/// Verkauf niedriger; ohne gemeinsame Seite zählt der Preis nicht), dann
+/// größere Menge, dann neuerer HLC.
+#[derive(Debug, Default)]
+pub struct PreserveHigherPriority;
+
+impl ConflictPolicy for PreserveHigherPriority {
+    fn name(&self) -> &'static str {
+        "preserve_higher_priority"
+    }
+    fn resolve(&self, local: &CrdtOrder, remote: &CrdtOrder) -> ConflictOutcome {
+        use std::cmp::Ordering::*;
+        let by_price = match (&local.side, &remote.side) {
+            (Some(CrdtSide::Buy), Some(CrdtSide::Buy)) => remote.price.partial_cmp(&local.price).unwrap_or(Equal),
+            (Some(CrdtSide::Sell), Some(CrdtSide::Sell)) => local.price.partial_cmp(&remote.price).unwrap_or(Equal),
+            _ => Equal,
+        };
+        let by_qty = remote.quantity.partial_cmp(&local.quantity).unwrap_or(Equal);
+        match by_price.then(by_qty) {
+            Greater => ConflictOutcome::TakeRemote,
+            Less => ConflictOutcome::KeepLocal,
+            Equal => newer_wins(local, remote),
+        }
+    }
+}
+
+/// Beide Versionen verwerfen => der Nutzer muss die Order neu einstellen.
+#[derive(Debug, Default)]
+pub struct RejectBoth;
+
+impl ConflictPolicy for RejectBoth {
+    fn name(&self) -> &'static str {
+        "reject_both"
+    }
+    fn resolve(&self, _local: &CrdtOrder, _remote: &CrdtOrder) -> ConflictOutcome {
+        ConflictOutcome::RejectBoth
+    }
+}
+
+/// Registry der verfügbaren Policies (eingebaute + eigene).
+pub struct ConflictPolicyRegistry {
+    policies: HashMap<String, Arc<dyn ConflictPolicy>>,
+}
+
+impl ConflictPolicyRegistry {
+    pub const DEFAULT_POLICY: &'static str = "hlc_lww";
+
+    /// Registry mit den eingebauten Policies.
+    pub fn new() -> Self {
+        let mut reg = Self { policies: HashMap::new() };
+        reg.register(Arc::new(HlcLastWriterWins));
+        reg.register(Arc::new(PreserveHigherPriority));
+        reg.register(Arc::new(RejectBoth));
+        reg
+    }
+
+    pub fn register(&mut self, policy: Arc<dyn ConflictPolicy>) {
+        self.policies.insert(policy.name().to_string(), policy);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn ConflictPolicy>> {
+        self.policies.get(name).cloned()
+    }
+
+    /// Policy laut Config; unbekannte Namen fallen auf HLC-LWW zurück.
+    pub fn from_config(&self, name: &str) -> Arc<dyn ConflictPolicy> {
+        self.get(name).unwrap_or_else(|| {
+            warn!("Unbekannte Konflikt-Policy '{}' => {}", name, Self::DEFAULT_POLICY);
+            Arc::new(HlcLastWriterWins)
+        })
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut v: Vec<String> = self.policies.keys().cloned().collect();
+        v.sort();
+        v
+    }
+}
+
+impl Default for ConflictPolicyRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// Einfache Struktur zur Manipulationsüberwachung
 pub struct ConflictResolution {
@@ -103,3 +242,78 @@ impl ConflictResolution {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hlc::HlcTimestamp;
+
+    fn version(qty: f64, price: f64, physical_ms: u64, node: u64) -> CrdtOrder {
+        sided(CrdtSide::Buy, qty, price, physical_ms, node)
+    }
+
+    fn sided(side: CrdtSide, qty: f64, price: f64, physical_ms: u64, node: u64) -> CrdtOrder {
+        CrdtOrder {
+            id: "o1".into(),
+            user_id: "alice".into(),
+            timestamp: 1,
+            quantity: qty,
+            price,
+            side: Some(side),
+            hlc: HlcTimestamp { physical_ms, logical: 0, node },
+            signature: None,
+            public_key: None,
+        }
+    }
+
+    #[test]
+    fn test_hlc_lww_picks_newer_version() {
+        let p = HlcLastWriterWins;
+        let old = version(1.0, 100.0, 1_000, 1);
+        let new = version(2.0, 99.0, 2_000, 2);
+        assert_eq!(p.resolve(&old, &new), ConflictOutcome::TakeRemote);
+        assert_eq!(p.resolve(&new, &old), ConflictOutcome::KeepLocal);
+        // Gleicher physischer Teil => Node-Tag entscheidet, auf beiden Seiten gleich
+        let a = version(1.0, 100.0, 1_000, 1);
+        let b = version(3.0, 100.0, 1_000, 2);
+        assert_eq!(p.resolve(&a, &b), ConflictOutcome::TakeRemote);
+        assert_eq!(p.resolve(&b, &a), ConflictOutcome::KeepLocal);
+    }
+
+    #[test]
+    fn test_preserve_higher_priority_ignores_recency() {
+        let p = PreserveHigherPriority;
+        let high = version(1.0, 105.0, 1_000, 1);
+        let low_newer = version(5.0, 100.0, 9_000, 2);
+        assert_eq!(p.resolve(&high, &low_newer), ConflictOutcome::KeepLocal);
+        assert_eq!(p.resolve(&low_newer, &high), ConflictOutcome::TakeRemote);
+        // Gleicher Preis => größere Menge
+        let big = version(3.0, 100.0, 1_000, 1);
+        assert_eq!(p.resolve(&low_newer, &big), ConflictOutcome::KeepLocal);
+    }
+
+    #[test]
+    fn test_preserve_higher_priority_respects_side() {
+        let p = PreserveHigherPriority;
+        // Verkauf: niedrigerer Preis ist aggressiver
+        let cheap = sided(CrdtSide::Sell, 1.0, 99.0, 1_000, 1);
+        let dear = sided(CrdtSide::Sell, 5.0, 101.0, 2_000, 2);
+        assert_eq!(p.resolve(&cheap, &dear), ConflictOutcome::KeepLocal);
+        assert_eq!(p.resolve(&dear, &cheap), ConflictOutcome::TakeRemote);
+        // Seite unbekannt => Menge entscheidet
+        let mut unknown = dear.clone();
+        unknown.side = None;
+        assert_eq!(p.resolve(&cheap, &unknown), ConflictOutcome::TakeRemote);
+    }
+
+    #[test]
+    fn test_reject_both_and_registry_lookup() {
+        let reg = ConflictPolicyRegistry::new();
+        assert_eq!(reg.names(), vec!["hlc_lww", "preserve_higher_priority", "reject_both"]);
+        let p = reg.from_config("reject_both");
+        let a = version(1.0, 100.0, 1_000, 1);
+        let b = version(2.0, 100.0, 2_000, 2);
+        assert_eq!(p.resolve(&a, &b), ConflictOutcome::RejectBoth);
+        assert_eq!(reg.from_config("does_not_exist").name(), "hlc_lww");
+    }
+}
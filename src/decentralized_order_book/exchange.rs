@@ -3,16 +3,59 @@
 // my_dex/src/decentralized_order_book/exchange.rs
 //////////////////////////////////////////////////////////////////////////
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
 use crate::decentralized_order_book::settlement::SettlementEngine;
 use crate::decentralized_order_book::assets::Asset;
-use crate::decentralized_order_book::order::{Order, OrderSide, OrderType, OrderStatus};
+use crate::decentralized_order_book::nonce_registry::NonceRegistry;
+use crate::decentralized_order_book::order::{order_id_for, Order, OrderSide, OrderType, OrderStatus};
+use crate::storage::db_layer::DexDB;
+use crate::utils::lock::LockRecover;
 use crate::decentralized_order_book::order_book::OrderBook;
 
+/// Maximale Anzahl Märkte in einer Route bzw. einem Arbitrage-Zyklus.
+pub const MAX_ROUTE_HOPS: usize = 3;
+
+/// Eine Stufe einer Route: Tausch über genau einen Markt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteHop {
+    pub market: (Asset, Asset),
+    /// Buy => from=quote, to=base; Sell => from=base, to=quote
+    pub side: OrderSide,
+    pub amount_in: f64,
+    pub amount_out: f64,
+    /// Buchtiefe reichte nicht: `amount_in` ist kleiner als die angebotene Menge
+    pub partial: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    /// Assets in Reihenfolge, inkl. Start und Ziel
+    pub path: Vec<Asset>,
+    pub hops: Vec<RouteHop>,
+    pub amount_out: f64,
+    /// Mindestens eine Stufe nur teilweise gefüllt => nicht die ganze Menge tauschbar
+    pub partial: bool,
+}
+
+/// Gewinnbringender Zyklus auf Basis der Top-of-Book-Preise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArbitrageCycle {
+    /// Start == Ende, z. B. [BTC, ETH, USDT, BTC]
+    pub path: Vec<Asset>,
+    /// Produkt der Kurse; > 1.0 => Gewinn
+    pub profit_ratio: f64,
+}
+
 /// Ein "Exchange" verwaltet mehrere OrderBooks (z. B. BTC/USDT, ETH/USDT usw.)
 /// und hält eine gemeinsame SettlementEngine für Escrow- und Finalisierungs-Operationen.
 pub struct Exchange {
     pub settlement: SettlementEngine,
     pub orderbooks: HashMap<(Asset, Asset), OrderBook>,
+    /// Replay-Schutz: (user_id, nonce) wird nur einmal akzeptiert
+    pub nonces: NonceRegistry,
 }
 
 impl Exchange {
@@ -20,9 +63,16 @@ impl Exchange {
         Self {
             settlement: SettlementEngine::new(),
             orderbooks: HashMap::new(),
+            nonces: NonceRegistry::new(),
         }
     }
 
+    /// Nonces werden in `db` persistiert und überstehen Neustarts.
+    pub fn with_nonce_db(mut self, db: Arc<Mutex<DexDB>>) -> Self {
+        self.nonces = NonceRegistry::with_db(db);
+        self
+    }
+
     /// Einen neuen Markt (base vs. quote) anlegen:
     /// z. B. (BTC, USDT) => OrderBook
     pub fn create_market(&mut self, base: Asset, quote: Asset) {
@@ -56,20 +106,31 @@ impl Exchange {
     ) -> bool {
         // Sicherheitscheck
         if !order.verify_signature() {
-            println!("Warn: place_order() => ungültige Signatur bei Order {}, abgelehnt.", order.id);
+            warn!("place_order() => ungültige Signatur bei Order {}, abgelehnt.", order.id);
+            return false;
+        }
+        // Die ID muss aus (user_id, nonce) folgen, sonst wäre die Dedup umgehbar
+        if order.id != order_id_for(&order.user_id, order.nonce) {
+            warn!("place_order() => Order-ID {} passt nicht zu Nonce {}, abgelehnt.", order.id, order.nonce);
             return false;
         }
 
         if let Some(ob) = self.orderbooks.get_mut(&(base_asset.clone(), quote_asset.clone())) {
+            // Replay/veraltete Nonce => ablehnen, bevor etwas gesperrt wird
+            if let Err(e) = self.nonces.check(&order.user_id, order.nonce) {
+                warn!("place_order() => Order {} abgelehnt: {}", order.id, e);
+                return false;
+            }
             // Sperre Guthaben
-            match order.side {
+            let locked = match order.side {
                 OrderSide::Sell => {
                     let needed = order.remaining_quantity();
-                    let ok = self.settlement.lock_funds(&order.user_id, base_asset, needed);
+                    let ok = self.settlement.lock_funds(&order.user_id, base_asset.clone(), needed);
                     if !ok {
                         println!("Nicht genug {:?}-Guthaben bei {}!", base_asset, order.user_id);
                         return false;
                     }
+                    Some((base_asset, needed))
                 },
                 OrderSide::Buy => {
                     if let Some(px) = self.extract_price(&order.order_type) {
@@ -79,10 +140,21 @@ impl Exchange {
                             println!("Nicht genug {:?}-Guthaben bei {}!", quote_asset, order.user_id);
                             return false;
                         }
+                        Some((quote_asset, needed))
                     } else {
                         println!("(Warn) Market-Buy => kein definierter Preis => unklare Obergrenze!");
+                        None
                     }
                 }
+            };
+            // Nonce erst verbrauchen, wenn die Order angenommen ist; scheitert
+            // das (Replay im Wettlauf, DB), wird die Sperre zurückgegeben
+            if let Err(e) = self.nonces.check_and_record(&order.user_id, order.nonce) {
+                warn!("place_order() => Order {} abgelehnt: {}", order.id, e);
+                if let Some((asset, amount)) = locked {
+                    let _ = self.settlement.release_funds(&order.user_id, asset, amount);
+                }
+                return false;
             }
 
             // Danach fügen wir die Order ins OrderBook ein
@@ -169,6 +241,201 @@ impl Exchange {
         }
     }
 
+    // -----------------------------------------------------------------
+    // Routing über mehrere Märkte + Arbitrage-Erkennung
+    // -----------------------------------------------------------------
+
+    /// Offene Limit-Orders einer Seite als (Preis, Restmenge), in Ausführungs-
+    /// reihenfolge sortiert (Asks aufsteigend, Bids absteigend).
+    fn levels(&self, market: &(Asset, Asset), side: OrderSide) -> Vec<(f64, f64)> {
+        let Some(ob) = self.orderbooks.get(market) else {
+            return Vec::new();
+        };
+        let mut lv: Vec<(f64, f64)> = ob
+            .book
+            .all_visible_orders()
+            .into_iter()
+            .filter(|o| o.side == side && matches!(o.status, OrderStatus::Open | OrderStatus::PartiallyFilled))
+            .filter_map(|o| match o.order_type {
+                OrderType::Limit(px) if px > 0.0 && o.remaining_quantity() > 0.0 => Some((px, o.remaining_quantity())),
+                _ => None,
+            })
+            .collect();
+        match side {
+            OrderSide::Sell => lv.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal)),
+            OrderSide::Buy => lv.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal)),
+        }
+        lv
+    }
+
+    /// Simuliert einen Tausch von `amount` `from` über `market` gegen die Buchtiefe.
+    fn simulate_hop(&self, from: &Asset, market: &(Asset, Asset), amount: f64) -> Option<(Asset, RouteHop)> {
+        let (base, quote) = market;
+        let mut remaining = amount;
+        let mut out = 0.0;
+        let (to, side) = if from == quote {
+            // Quote => Base: gegen Asks kaufen
+            for (px, qty) in self.levels(market, OrderSide::Sell) {
+                if remaining <= f64::EPSILON {
+                    break;
+                }
+                let take = (remaining / px).min(qty);
+                out += take;
+                remaining -= take * px;
+            }
+            (base.clone(), OrderSide::Buy)
+        } else if from == base {
+            // Base => Quote: an Bids verkaufen
+            for (px, qty) in self.levels(market, OrderSide::Buy) {
+                if remaining <= f64::EPSILON {
+                    break;
+                }
+                let take = remaining.min(qty);
+                out += take * px;
+                remaining -= take;
+            }
+            (quote.clone(), OrderSide::Sell)
+        } else {
+            return None;
+        };
+        if out <= 0.0 {
+            return None;
+        }
+        let remaining = remaining.max(0.0);
+        let hop = RouteHop {
+            market: market.clone(),
+            side,
+            amount_in: amount - remaining,
+            amount_out: out,
+            partial: remaining > amount * 1e-9,
+        };
+        Some((to, hop))
+    }
+
+    /// Beste Route (höchster Ertrag) von `from_asset` nach `to_asset` über
+    /// höchstens `MAX_ROUTE_HOPS` Märkte, unter Berücksichtigung der Buchtiefe.
+    /// Vollständig gefüllte Routen gehen vor; bleibt nur eine Teilfüllung,
+    /// ist `Route::partial` gesetzt.
+    pub fn best_route(&self, from_asset: Asset, to_asset: Asset, amount: f64) -> Option<Route> {
+        if amount <= 0.0 || from_asset == to_asset {
+            return None;
+        }
+        let mut best = None;
+        let mut path = vec![from_asset.clone()];
+        let mut hops = Vec::new();
+        self.route_dfs(&from_asset, &to_asset, amount, &mut path, &mut hops, &mut best);
+        best
+    }
+
+    fn route_dfs(
+        &self,
+        current: &Asset,
+        target: &Asset,
+        amount: f64,
+        path: &mut Vec<Asset>,
+        hops: &mut Vec<RouteHop>,
+        best: &mut Option<Route>,
+    ) {
+        if hops.len() >= MAX_ROUTE_HOPS {
+            return;
+        }
+        for market in self.orderbooks.keys() {
+            let Some((next, hop)) = self.simulate_hop(current, market, amount) else {
+                continue;
+            };
+            if path.contains(&next) {
+                continue;
+            }
+            let out = hop.amount_out;
+            path.push(next.clone());
+            hops.push(hop);
+            if &next == target {
+                let partial = hops.iter().any(|h| h.partial);
+                let better = best.as_ref().map_or(true, |b| match (b.partial, partial) {
+                    (true, false) => true,
+                    (false, true) => false,
+                    _ => out > b.amount_out,
+                });
+                if better {
+                    *best = Some(Route { path: path.clone(), hops: hops.clone(), amount_out: out, partial });
+                }
+            } else {
+                self.route_dfs(&next, target, out, path, hops, best);
+            }
+            hops.pop();
+            path.pop();
+        }
+    }
+
+    /// Kurs für 1 Einheit `from` über `market` zum besten Preis.
+    fn top_rate(&self, from: &Asset, market: &(Asset, Asset)) -> Option<(Asset, f64)> {
+        let (base, quote) = market;
+        if from == quote {
+            let (ask, _) = *self.levels(market, OrderSide::Sell).first()?;
+            Some((base.clone(), 1.0 / ask))
+        } else if from == base {
+            let (bid, _) = *self.levels(market, OrderSide::Buy).first()?;
+            Some((quote.clone(), bid))
+        } else {
+            None
+        }
+    }
+
+    /// Sucht Zyklen (bis `MAX_ROUTE_HOPS` Märkte), deren Kursprodukt
+    /// `1.0 + min_profit` übersteigt. Jeder Zyklus wird nur einmal gemeldet
+    /// (Start = kleinstes Asset nach `Ord`).
+    pub fn detect_arbitrage(&self, min_profit: f64) -> Vec<ArbitrageCycle> {
+        let mut assets: Vec<Asset> = Vec::new();
+        for (b, q) in self.orderbooks.keys() {
+            for a in [b, q] {
+                if !assets.contains(a) {
+                    assets.push(a.clone());
+                }
+            }
+        }
+        let mut found = Vec::new();
+        for start in &assets {
+            let mut path = vec![start.clone()];
+            self.cycle_dfs(start, start, 1.0, min_profit, &mut path, &mut found);
+        }
+        found
+    }
+
+    fn cycle_dfs(
+        &self,
+        start: &Asset,
+        current: &Asset,
+        ratio: f64,
+        min_profit: f64,
+        path: &mut Vec<Asset>,
+        found: &mut Vec<ArbitrageCycle>,
+    ) {
+        if path.len() > MAX_ROUTE_HOPS {
+            return;
+        }
+        for market in self.orderbooks.keys() {
+            let Some((next, rate)) = self.top_rate(current, market) else {
+                continue;
+            };
+            let r = ratio * rate;
+            if &next == start {
+                let canonical = path.iter().all(|a| a >= start);
+                if path.len() >= 2 && canonical && r > 1.0 + min_profit {
+                    let mut p = path.clone();
+                    p.push(next);
+                    found.push(ArbitrageCycle { path: p, profit_ratio: r });
+                }
+                continue;
+            }
+            if path.contains(&next) {
+                continue;
+            }
+            path.push(next.clone());
+            self.cycle_dfs(start, &next, r, min_profit, path, found);
+            path.pop();
+        }
+    }
+
     fn extract_price(&self, ot: &OrderType) -> Option<f64> {
         match ot {
             OrderType::Limit(px) | OrderType::Stop(px) => Some(*px),
@@ -191,3 +458,143 @@ impl Exchange {
         (b_px + s_px)/2.0
     }
 }
+
+/// Prüft periodisch alle Märkte auf Arbitrage-Zyklen und meldet sie im Log.
+pub async fn run_arbitrage_monitor(
+    exchange: Arc<Mutex<Exchange>>,
+    interval: Duration,
+    min_profit: f64,
+    token: CancellationToken,
+) {
+    let mut tick = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = tick.tick() => {
+                let cycles = exchange.lock_recover().detect_arbitrage(min_profit);
+                for c in cycles {
+                    warn!("Arbitrage-Zyklus {:?} => ratio={:.4}", c.path, c.profit_ratio);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_keypair() -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        ed25519_dalek::Keypair { secret, public }
+    }
+
+    fn limit(id: &str, side: OrderSide, px: f64, qty: f64) -> Order {
+        let mut o = Order::new("mm", OrderType::Limit(px), side, qty);
+        o.id = id.to_string();
+        o.sign(&test_keypair()).unwrap();
+        o
+    }
+
+    fn signed(user: &str, nonce: u64, side: OrderSide, px: f64, qty: f64) -> Order {
+        let mut o = Order::with_nonce(user, nonce, OrderType::Limit(px), side, qty);
+        o.sign(&test_keypair()).unwrap();
+        o
+    }
+
+    #[test]
+    fn test_replayed_order_rejected_fresh_nonce_accepted() {
+        let mut ex = Exchange::new();
+        ex.create_market(Asset::BTC, Asset::USDT);
+        ex.create_account("alice");
+        ex.deposit("alice", Asset::BTC, 10.0);
+
+        let order = signed("alice", 100, OrderSide::Sell, 30000.0, 1.0);
+        assert_eq!(order.id, "alice_100");
+        assert!(ex.place_order(Asset::BTC, Asset::USDT, order.clone()));
+        // Identische signierte Order erneut => Replay
+        assert!(!ex.place_order(Asset::BTC, Asset::USDT, order));
+        // Ältere Nonce => veraltet
+        assert!(!ex.place_order(Asset::BTC, Asset::USDT, signed("alice", 99, OrderSide::Sell, 30000.0, 1.0)));
+        // Manipulierte ID bei gleicher Nonce
+        let mut forged = signed("alice", 100, OrderSide::Sell, 30000.0, 1.0);
+        forged.id = "alice_other".into();
+        assert!(!ex.place_order(Asset::BTC, Asset::USDT, forged));
+
+        // Ungedeckte Order verbraucht keine Nonce: nach der Einzahlung geht dieselbe durch
+        let big = signed("alice", 200, OrderSide::Sell, 30000.0, 50.0);
+        assert!(!ex.place_order(Asset::BTC, Asset::USDT, big.clone()));
+        ex.deposit("alice", Asset::BTC, 50.0);
+        assert!(ex.place_order(Asset::BTC, Asset::USDT, big));
+
+        assert!(ex.place_order(Asset::BTC, Asset::USDT, signed("alice", 101, OrderSide::Sell, 30000.0, 1.0)));
+        // Zwei lokal erzeugte Orders in derselben Sekunde kollidieren nicht
+        let a = Order::new("bob", OrderType::Market, OrderSide::Buy, 1.0);
+        let b = Order::new("bob", OrderType::Market, OrderSide::Buy, 1.0);
+        assert!(b.nonce > a.nonce && a.id != b.id);
+        assert!(a.signing_bytes().unwrap() != b.signing_bytes().unwrap());
+    }
+
+    fn book(ex: &mut Exchange, base: Asset, quote: Asset, orders: Vec<Order>) {
+        ex.create_market(base.clone(), quote.clone());
+        let ob = ex.orderbooks.get_mut(&(base, quote)).unwrap();
+        for o in orders {
+            ob.add_order(o);
+        }
+    }
+
+    #[test]
+    fn test_two_hop_route_beats_thin_direct_market() {
+        let mut ex = Exchange::new();
+        book(&mut ex, Asset::ETH, Asset::USDT, vec![limit("a1", OrderSide::Sell, 2000.0, 20.0)]);
+        book(&mut ex, Asset::BTC, Asset::ETH, vec![limit("a2", OrderSide::Sell, 15.0, 1.0)]);
+        book(&mut ex, Asset::BTC, Asset::USDT, vec![limit("a3", OrderSide::Sell, 31000.0, 5.0)]);
+
+        let route = ex.best_route(Asset::USDT, Asset::BTC, 30000.0).unwrap();
+        assert_eq!(route.path, vec![Asset::USDT, Asset::ETH, Asset::BTC]);
+        assert_eq!(route.hops.len(), 2);
+        assert!((route.hops[0].amount_out - 15.0).abs() < 1e-9);
+        assert!((route.amount_out - 1.0).abs() < 1e-9);
+        assert!(!route.partial);
+
+        // Kein Markt für SOL => keine Route
+        assert!(ex.best_route(Asset::USDT, Asset::SOL, 100.0).is_none());
+    }
+
+    #[test]
+    fn test_partial_fill_signalled_and_full_route_preferred() {
+        let mut ex = Exchange::new();
+        book(&mut ex, Asset::BTC, Asset::USDT, vec![limit("a1", OrderSide::Sell, 30000.0, 1.0)]);
+
+        // Nur 1 BTC im Buch => 30000 von 60000 USDT getauscht
+        let route = ex.best_route(Asset::USDT, Asset::BTC, 60000.0).unwrap();
+        assert!(route.partial && route.hops[0].partial);
+        assert!((route.hops[0].amount_in - 30000.0).abs() < 1e-6);
+        assert!((route.amount_out - 1.0).abs() < 1e-9);
+
+        // Eine vollständige Route schlägt die Teilfüllung trotz geringerem Ertrag
+        book(&mut ex, Asset::ETH, Asset::USDT, vec![limit("a2", OrderSide::Sell, 2000.0, 40.0)]);
+        book(&mut ex, Asset::BTC, Asset::ETH, vec![limit("a3", OrderSide::Sell, 40.0, 5.0)]);
+        let route = ex.best_route(Asset::USDT, Asset::BTC, 60000.0).unwrap();
+        assert!(!route.partial);
+        assert_eq!(route.path, vec![Asset::USDT, Asset::ETH, Asset::BTC]);
+        assert!((route.amount_out - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_triangular_arbitrage_detected() {
+        let mut ex = Exchange::new();
+        book(&mut ex, Asset::ETH, Asset::USDT, vec![limit("b1", OrderSide::Buy, 2000.0, 10.0)]);
+        book(&mut ex, Asset::BTC, Asset::USDT, vec![limit("a1", OrderSide::Sell, 30000.0, 1.0)]);
+        book(&mut ex, Asset::BTC, Asset::ETH, vec![limit("b2", OrderSide::Buy, 16.0, 1.0)]);
+
+        let cycles = ex.detect_arbitrage(0.01);
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].path, vec![Asset::BTC, Asset::ETH, Asset::USDT, Asset::BTC]);
+        assert!((cycles[0].profit_ratio - 16.0 * 2000.0 / 30000.0).abs() < 1e-9);
+
+        // Schwelle über dem Gewinn => nichts
+        assert!(ex.detect_arbitrage(0.10).is_empty());
+    }
+}
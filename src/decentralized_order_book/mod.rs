@@ -2,6 +2,7 @@
 
 pub mod matcher;              // Hauptlogik der Matching-Engine
 pub mod order;                // Order-Datenstruktur
+pub mod nonce_registry;       // Replay-Schutz über (user_id, nonce)
 pub mod order_book;           // Verwaltung des Orderbuchs
 pub mod conflict_resolution;  // Konfliktlösungen
 pub mod assets;               // Assets, subunits
@@ -0,0 +1,131 @@
+//////////////////////////////////////////////////////////////////////////
+// my_dex/src/decentralized_order_book/nonce_registry.rs
+//////////////////////////////////////////////////////////////////////////
+//
+// Serverseitige Deduplizierung über (user_id, nonce).
+//
+// Jeder User muss streng monoton steigende Nonces verwenden. Eine bereits
+// gesehene Nonce ist ein Replay (`DuplicateOrder`), eine kleinere als die
+// höchste akzeptierte ist veraltet (`StaleNonce`). Die letzten Nonces je
+// User werden in DexDB gehalten, damit ein Neustart keine Replays öffnet.
+//
+// Layout:
+//   order_nonce/<user_id> => UserNonces
+//////////////////////////////////////////////////////////////////////////
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::DexError;
+use crate::storage::db_layer::DexDB;
+use crate::utils::lock::LockRecover;
+
+const NONCE_PREFIX: &str = "order_nonce/";
+
+/// Wie viele zuletzt akzeptierte Nonces je User gemerkt werden (nur für die
+/// genauere Fehlermeldung; abgewiesen wird alles <= `highest`).
+pub const RECENT_NONCES_PER_USER: usize = 64;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserNonces {
+    pub highest: u64,
+    pub recent: VecDeque<u64>,
+}
+
+pub struct NonceRegistry {
+    db: Option<Arc<Mutex<DexDB>>>,
+    users: HashMap<String, UserNonces>,
+}
+
+impl NonceRegistry {
+    /// Nur im Speicher (Tests, Simulation).
+    pub fn new() -> Self {
+        Self { db: None, users: HashMap::new() }
+    }
+
+    pub fn with_db(db: Arc<Mutex<DexDB>>) -> Self {
+        Self { db: Some(db), users: HashMap::new() }
+    }
+
+    fn key(user_id: &str) -> String {
+        format!("{}{}", NONCE_PREFIX, user_id)
+    }
+
+    fn load(&mut self, user_id: &str) -> Result<&mut UserNonces, DexError> {
+        if !self.users.contains_key(user_id) {
+            let stored = match &self.db {
+                Some(db) => db.lock_recover().load_struct::<UserNonces>(&Self::key(user_id))?,
+                None => None,
+            };
+            self.users.insert(user_id.to_string(), stored.unwrap_or_default());
+        }
+        Ok(self.users.get_mut(user_id).expect("just inserted"))
+    }
+
+    pub fn highest(&mut self, user_id: &str) -> Result<u64, DexError> {
+        Ok(self.load(user_id)?.highest)
+    }
+
+    /// Prüft die Nonce, ohne sie zu verbrauchen (vor dem Sperren des Guthabens).
+    pub fn check(&mut self, user_id: &str, nonce: u64) -> Result<(), DexError> {
+        let seen = self.load(user_id)?;
+        if seen.recent.contains(&nonce) {
+            return Err(DexError::DuplicateOrder { user_id: user_id.to_string(), nonce });
+        }
+        if nonce <= seen.highest {
+            return Err(DexError::StaleNonce { user_id: user_id.to_string(), nonce, highest: seen.highest });
+        }
+        Ok(())
+    }
+
+    /// Prüft die Nonce erneut und merkt sie sich bei Erfolg (inkl. Persistenz).
+    /// Erst aufrufen, wenn die Order angenommen ist.
+    pub fn check_and_record(&mut self, user_id: &str, nonce: u64) -> Result<(), DexError> {
+        self.check(user_id, nonce)?;
+        let mut updated = self.load(user_id)?.clone();
+        updated.highest = nonce;
+        updated.recent.push_back(nonce);
+        while updated.recent.len() > RECENT_NONCES_PER_USER {
+            updated.recent.pop_front();
+        }
+        // Erst persistieren, dann im Speicher übernehmen: schlägt die DB fehl,
+        // bleibt die Nonce unverbraucht und der Client kann es erneut versuchen.
+        if let Some(db) = &self.db {
+            db.lock_recover().store_struct(&Self::key(user_id), &updated)?;
+        }
+        self.users.insert(user_id.to_string(), updated);
+        Ok(())
+    }
+}
+
+impl Default for NonceRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replayed_nonce_rejected_fresh_accepted_across_restart() {
+        let db = Arc::new(Mutex::new(DexDB::in_memory()));
+        let mut reg = NonceRegistry::with_db(db.clone());
+        reg.check_and_record("alice", 10).unwrap();
+        reg.check_and_record("alice", 11).unwrap();
+        // Andere User haben eigene Nonce-Räume
+        reg.check_and_record("bob", 1).unwrap();
+
+        assert!(matches!(reg.check_and_record("alice", 11), Err(DexError::DuplicateOrder { nonce: 11, .. })));
+        assert!(matches!(reg.check_and_record("alice", 5), Err(DexError::StaleNonce { highest: 11, .. })));
+
+        // Neustart: Nonces kommen aus der DB
+        let mut reg = NonceRegistry::with_db(db);
+        assert!(matches!(reg.check_and_record("alice", 10), Err(DexError::DuplicateOrder { .. })));
+        reg.check_and_record("alice", 12).unwrap();
+        assert_eq!(reg.highest("alice").unwrap(), 12);
+    }
+}
@@ -1,9 +1,36 @@
 // my_dex/src/decentralized_order_book/order.rs
 
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
 use serde::{Serialize, Deserialize};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::fmt;
 
+use crate::error::DexError;
+use crate::utils::canonical;
+
+/// Letzte lokal vergebene Nonce (siehe `next_client_nonce`).
+static LAST_CLIENT_NONCE: AtomicU64 = AtomicU64::new(0);
+
+/// Streng monoton steigende Nonce auf Basis der Mikrosekunden-Uhr. Zwei
+/// Orders in derselben Sekunde (oder Mikrosekunde) erhalten verschiedene Werte.
+pub fn next_client_nonce() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_micros() as u64;
+    let mut last = LAST_CLIENT_NONCE.load(Ordering::Relaxed);
+    loop {
+        let next = now.max(last + 1);
+        match LAST_CLIENT_NONCE.compare_exchange_weak(last, next, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => return next,
+            Err(actual) => last = actual,
+        }
+    }
+}
+
+/// Deterministische Order-ID aus (user_id, nonce).
+pub fn order_id_for(user_id: &str, nonce: u64) -> String {
+    format!("{}_{}", user_id, nonce)
+}
+
 /// Art der Order (Market, Limit, Stop)
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OrderType {
@@ -37,6 +64,7 @@ pub enum OrderStatus {
 /// - `quantity`: Gewünschte Gesamtmenge
 /// - `filled_quantity`: Bereits ausgeführte Menge
 /// - `status`: Open, PartiallyFilled, Filled, Cancelled
+/// - `nonce`: Client-Nonce, pro User streng monoton; `id` = `order_id_for(user_id, nonce)`
 ///
 /// Neu: Felder `signature` und `pub_key` für die Authentizität.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -49,18 +77,24 @@ pub struct Order {
     pub quantity: f64,
     pub filled_quantity: f64,
     pub status: OrderStatus,
+    #[serde(default)]
+    pub nonce: u64,
     // Neu: Signatur
     pub signature: Option<Vec<u8>>,
     pub pub_key: Option<Vec<u8>>,
 }
 
 impl Order {
-    /// Erstellt eine neue Order mit automatisch generierter ID und Zeitstempel
+    /// Erstellt eine neue Order mit lokal vergebener Nonce (siehe `next_client_nonce`)
     pub fn new(user_id: &str, order_type: OrderType, side: OrderSide, quantity: f64) -> Self {
+        Self::with_nonce(user_id, next_client_nonce(), order_type, side, quantity)
+    }
+
+    /// Erstellt eine Order mit vom Client gewählter Nonce; die ID folgt daraus.
+    pub fn with_nonce(user_id: &str, nonce: u64, order_type: OrderType, side: OrderSide, quantity: f64) -> Self {
         let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-        let unique_id = format!("{}_{}", user_id, now);
         Self {
-            id: unique_id,
+            id: order_id_for(user_id, nonce),
             user_id: user_id.to_string(),
             timestamp: now,
             order_type,
@@ -68,6 +102,7 @@ impl Order {
             quantity,
             filled_quantity: 0.0,
             status: OrderStatus::Open,
+            nonce,
             signature: None,
             pub_key: None,
         }
@@ -95,21 +130,53 @@ impl Order {
         }
     }
 
-    /// Minimalbeispiel für Signaturprüfung.
-    /// Du müsstest in einer produktiven Umgebung
-    /// - pub_key => ed25519_dalek::PublicKey
-    /// - signature => ed25519_dalek::Signature
-    /// - message = "id+user_id+timestamp+quantity" (Hash)
-    /// verarbeiten und verifizieren.
+    /// Kanonische Bytes, die der Client signiert (Domain "my_dex/book/order/v1").
+    /// Die Nonce ist enthalten, damit eine abgefangene Order nicht mit neuer
+    /// Nonce erneut eingereicht werden kann.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct BookOrderSigningView<'a> {
+            id: &'a str,
+            user_id: &'a str,
+            nonce: u64,
+            timestamp: u64,
+            order_type: &'a OrderType,
+            side: &'a OrderSide,
+            quantity: f64,
+            pub_key: &'a Option<Vec<u8>>,
+        }
+        canonical::signing_bytes("my_dex/book/order/v1", &BookOrderSigningView {
+            id: &self.id,
+            user_id: &self.user_id,
+            nonce: self.nonce,
+            timestamp: self.timestamp,
+            order_type: &self.order_type,
+            side: &self.side,
+            quantity: self.quantity,
+            pub_key: &self.pub_key,
+        })
+    }
+
+    /// Hinterlegt den Public Key und signiert `signing_bytes()`.
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<(), DexError> {
+        self.pub_key = Some(keypair.public.to_bytes().to_vec());
+        let bytes = self.signing_bytes()?;
+        self.signature = Some(keypair.sign(&bytes).to_bytes().to_vec());
+        Ok(())
+    }
+
+    /// Ed25519-Prüfung über `signing_bytes()` (inkl. Nonce).
     pub fn verify_signature(&self) -> bool {
-        if let (Some(sig_bytes), Some(pk_bytes)) = (self.signature.as_ref(), self.pub_key.as_ref()) {
-            // Beispiel: Wir checken nur, dass sign. + pub_key existieren.
-            // In einer echten Implementation => ed25519_dalek::PublicKey::from_bytes(pk_bytes)
-            // => pk.verify(msg, &Signature::from_bytes(sig_bytes)) etc.
-            // Hier nur Dummy:
-            !sig_bytes.is_empty() && !pk_bytes.is_empty()
-        } else {
-            false
+        let (sig, pk) = match (&self.signature, &self.pub_key) {
+            (Some(sig), Some(pk)) => (sig, pk),
+            _ => return false,
+        };
+        match (PublicKey::from_bytes(pk), Signature::from_bytes(sig)) {
+            (Ok(pk), Ok(sig)) => match self.signing_bytes() {
+                Ok(bytes) => pk.verify(&bytes, &sig).is_ok(),
+                Err(_) => false,
+            },
+            _ => false,
         }
     }
 }
@@ -125,3 +192,41 @@ impl fmt::Display for Order {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    #[test]
+    fn test_signature_is_checked_not_just_present() {
+        let mut o = Order::with_nonce("alice", 7, OrderType::Limit(100.0), OrderSide::Buy, 1.0);
+        assert!(!o.verify_signature());
+        o.sign(&keypair(1)).unwrap();
+        assert!(o.verify_signature());
+
+        // Beliebige Bytes reichen nicht mehr
+        let mut dummy = o.clone();
+        dummy.signature = Some(vec![1]);
+        dummy.pub_key = Some(vec![1]);
+        assert!(!dummy.verify_signature());
+
+        // Geänderte Nonce bzw. fremder Schlüssel => ungültig
+        let mut replayed = o.clone();
+        replayed.nonce = 8;
+        assert!(!replayed.verify_signature());
+        let mut swapped = o.clone();
+        swapped.pub_key = Some(keypair(2).public.to_bytes().to_vec());
+        assert!(!swapped.verify_signature());
+
+        // Limit-Preis ist Teil der signierten Bytes
+        let mut repriced = o;
+        repriced.order_type = OrderType::Limit(101.0);
+        assert!(!repriced.verify_signature());
+    }
+}
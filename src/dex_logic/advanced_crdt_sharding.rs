@@ -8,6 +8,14 @@
 //  3) Watchtower-Integration (um on-chain Settlement / Betrugsfälle im CRDT zu erkennen)
 //  4) RocksDB-Optimierungen mit Column Families
 //  5) Merkle-basierten Checkpoint-Mechanismus, optional on-chain verankerbar.
+//  6) Signierte, versionierte Delta-Umschläge: der Ursprungs-Node signiert
+//     (Version, Ursprung, shard_id, Delta, Zeitstempel). Die Order-Signatur
+//     allein deckt weder Removals noch die Shard-Zuordnung ab; ein Relay
+//     könnte sonst Orders einschleusen, entfernen oder umleiten.
+//     Schlüssel kommen nur aus Membership/Onboarding (`trust_peer`,
+//     `with_key_directory`), nie vom ersten Delta. Je (Ursprung, Shard) muss
+//     der Zeitstempel steigen (Replay), zu alte Deltas werden verworfen, und
+//     ein Ursprung darf nur Orders entfernen, die er selbst eingestellt hat.
 //
 // Hinweis: Dieser Code bindet an vorhandene Strukturen an:
 //  - crate::error::DexError (my_dex/src/error.rs)
@@ -19,7 +27,11 @@
 
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
 
 use tracing::{info, debug, warn, error};
 use anyhow::{Result, anyhow};
@@ -33,26 +45,110 @@ use crate::crdt_logic::{CrdtState, Order};
 // ### CHANGED: Manchmal heißt der Ordner "shard_logic", manchmal "shard_manager". 
 // Bleiben wir bei shard_logic::ShardManager:
 use crate::shard_logic::ShardManager;
+use crate::utils::hlc::{HlcTimestamp, HybridLogicalClock};
 
 // --- RocksDB: Column Families ---
-use rocksdb::{DB, Options, ColumnFamilyDescriptor, ColumnFamily};
+use rocksdb::{DB, Options, ColumnFamilyDescriptor, ColumnFamily, Direction, IteratorMode};
+
+use crate::storage::migrations::{shard_db_migrations, MigrationRunner, MigrationStore, META_CF};
 
 ////////////////////////////////////////////////////////
 // Delta-basiertes CRDT-Update (vermeidet Full-Sync)
 ////////////////////////////////////////////////////////
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CrdtDelta {
     // Minimaler "diff" (z. B. neue Orders, geänderte Fills etc.)
     pub updated_orders: Vec<Order>,  
     pub removed_orders: Vec<String>, // order_ids
+    // HLC des Senders beim Erzeugen des Deltas
+    pub hlc: HlcTimestamp,
 }
 
-#[derive(Clone, Debug)]
+/// Aktuelle Version des Delta-Umschlags; andere Versionen werden verworfen.
+pub const DELTA_ENVELOPE_VERSION: u16 = 1;
+
+/// Deltas, die älter sind (Unix-Millis), werden nicht mehr angenommen.
+pub const DELTA_MAX_AGE_MS: u64 = 5 * 60 * 1000;
+/// Toleranz für Zeitstempel in der Zukunft (Uhrenabweichung des Ursprungs).
+pub const DELTA_MAX_FUTURE_SKEW_MS: u64 = 30 * 1000;
+
+/// Liefert den in Membership/Onboarding hinterlegten Schlüssel eines Nodes.
+pub type KeyDirectory = Arc<dyn Fn(&str) -> Option<PublicKey> + Send + Sync>;
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GossipMessage {
+    pub version: u16,
+    /// Node, der das Delta erzeugt und signiert hat (nicht der Relay)
+    pub origin_node: String,
     pub shard_id: u32,
     pub delta: CrdtDelta,
-    pub timestamp: Instant,
+    /// Erzeugungszeit (Unix-Millis), Teil der Signatur
+    pub timestamp: u64,
+    pub signature: Option<Vec<u8>>,
+    pub public_key: Option<Vec<u8>>,
+}
+
+impl GossipMessage {
+    pub fn new(origin_node: &str, shard_id: u32, delta: CrdtDelta) -> Self {
+        let timestamp = now_millis();
+        Self {
+            version: DELTA_ENVELOPE_VERSION,
+            origin_node: origin_node.to_string(),
+            shard_id,
+            delta,
+            timestamp,
+            signature: None,
+            public_key: None,
+        }
+    }
+
+    /// SHA-256 über alle Felder außer der Signatur selbst.
+    fn signing_hash(&self) -> Result<Vec<u8>> {
+        let data = bincode::serialize(&(
+            self.version,
+            &self.origin_node,
+            self.shard_id,
+            &self.delta,
+            self.timestamp,
+        ))?;
+        Ok(Sha256::digest(&data).to_vec())
+    }
+
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<()> {
+        let hash = self.signing_hash()?;
+        self.signature = Some(keypair.sign(&hash).to_bytes().to_vec());
+        self.public_key = Some(keypair.public.to_bytes().to_vec());
+        Ok(())
+    }
+
+    /// Prüft Version und Signatur; liefert den Schlüssel des Signierers.
+    pub fn verify(&self) -> Result<PublicKey, DexError> {
+        if self.version != DELTA_ENVELOPE_VERSION {
+            return Err(DexError::InvalidSignature(format!(
+                "unsupported delta envelope version {}", self.version
+            )));
+        }
+        let (Some(sig_bytes), Some(pk_bytes)) = (self.signature.as_ref(), self.public_key.as_ref()) else {
+            return Err(DexError::InvalidSignature(format!("unsigned delta from {}", self.origin_node)));
+        };
+        let pubkey = PublicKey::from_bytes(pk_bytes)
+            .map_err(|e| DexError::InvalidSignature(format!("delta public key: {:?}", e)))?;
+        let signature = Signature::from_bytes(sig_bytes)
+            .map_err(|e| DexError::InvalidSignature(format!("delta signature: {:?}", e)))?;
+        let hash = self.signing_hash().map_err(|e| DexError::Other(e.to_string()))?;
+        pubkey
+            .verify(&hash, &signature)
+            .map_err(|_| DexError::InvalidSignature(format!("tampered delta from {}", self.origin_node)))?;
+        Ok(pubkey)
+    }
 }
 
 ////////////////////////////////////////////////////////
@@ -83,6 +179,9 @@ pub const ORDERS_CF: &str = "orders_cf";
 pub const SNAPSHOTS_CF: &str = "snapshots_cf";
 pub const CHECKPOINTS_CF: &str = "checkpoints_cf";
 
+/// Alle Column Families der AdvancedShardDB (inkl. "default" für die Schema-Version).
+pub const SHARD_DB_CFS: &[&str] = &[META_CF, ORDERS_CF, SNAPSHOTS_CF, CHECKPOINTS_CF];
+
 ////////////////////////////////////////////////////////
 // AdvancedShardDB => CFs pro Shard
 ////////////////////////////////////////////////////////
@@ -101,23 +200,29 @@ impl AdvancedShardDB {
         opts.create_if_missing(true);
         opts.create_missing_column_families(true);
 
-        let cfs = vec![
-            ColumnFamilyDescriptor::new(ORDERS_CF, Options::default()),
-            ColumnFamilyDescriptor::new(SNAPSHOTS_CF, Options::default()),
-            ColumnFamilyDescriptor::new(CHECKPOINTS_CF, Options::default()),
-        ];
+        let cfs = SHARD_DB_CFS
+            .iter()
+            .map(|name| ColumnFamilyDescriptor::new(*name, Options::default()))
+            .collect::<Vec<_>>();
 
         let db = DB::open_cf_descriptors(&opts, path, cfs)?;
         let orders_cf = db.cf_handle(ORDERS_CF).ok_or_else(|| anyhow!("orders_cf missing"))?;
         let snapshots_cf = db.cf_handle(SNAPSHOTS_CF).ok_or_else(|| anyhow!("snapshots_cf missing"))?;
         let checkpoints_cf = db.cf_handle(CHECKPOINTS_CF).ok_or_else(|| anyhow!("checkpoints_cf missing"))?;
 
-        Ok(Self {
+        let shard_db = Self {
             db: Arc::new(db),
             orders_cf,
             snapshots_cf,
             checkpoints_cf
-        })
+        };
+        let version = MigrationRunner::new(shard_db_migrations())?.run(&shard_db)?;
+        debug!("AdvancedShardDB schema version {}", version);
+        Ok(shard_db)
+    }
+
+    fn cf(&self, name: &str) -> Result<&ColumnFamily> {
+        self.db.cf_handle(name).ok_or_else(|| anyhow!("ColumnFamily not found: {}", name))
     }
 
     pub fn store_order(&self, shard_id: u32, order: &Order) -> Result<()> {
@@ -158,6 +263,35 @@ impl AdvancedShardDB {
     }
 }
 
+impl MigrationStore for AdvancedShardDB {
+    fn read(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.db.get_cf(self.cf(cf)?, key)?.map(|v| v.to_vec()))
+    }
+
+    fn write(&self, cf: &str, key: &[u8], val: &[u8]) -> Result<()> {
+        self.db.put_cf(self.cf(cf)?, key, val)?;
+        Ok(())
+    }
+
+    fn remove(&self, cf: &str, key: &[u8]) -> Result<()> {
+        self.db.delete_cf(self.cf(cf)?, key)?;
+        Ok(())
+    }
+
+    fn scan_prefix(&self, cf: &str, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut out = Vec::new();
+        let mode = IteratorMode::From(prefix, Direction::Forward);
+        for item in self.db.iterator_cf(self.cf(cf)?, mode) {
+            let (k, v) = item?;
+            if !k.starts_with(prefix) {
+                break;
+            }
+            out.push((k.to_vec(), v.to_vec()));
+        }
+        Ok(out)
+    }
+}
+
 ////////////////////////////////////////////////////////
 // Watchtower-Integration => wir binden watchtower
 ////////////////////////////////////////////////////////
@@ -194,6 +328,8 @@ pub struct AdvancedShardState {
     pub crdt_state: CrdtState,
     pub db: AdvancedShardDB,
     pub watchtower: AdvancedWatchtower,
+    /// Order-ID => Ursprungs-Node, der sie eingestellt hat ("" = lokal)
+    order_origins: HashMap<String, String>,
 }
 
 impl AdvancedShardState {
@@ -206,6 +342,7 @@ impl AdvancedShardState {
             crdt_state: st,
             db,
             watchtower: advwt,
+            order_origins: HashMap::new(),
         })
     }
 
@@ -215,33 +352,57 @@ impl AdvancedShardState {
     ///  - Prüfe, ob Order eine gültige Signatur hat (falls `Order` das unterstützt).
     ///  - Nur dann CRDT-state updaten + store_order.
     pub fn apply_delta(&mut self, delta: &CrdtDelta) -> Result<()> {
+        self.apply_delta_from(None, delta)
+    }
+
+    /// Wie `apply_delta`, für ein Delta des (bereits verifizierten) Ursprungs
+    /// `origin`: Updates und Removals nur für Orders, die `origin` selbst
+    /// eingestellt hat; fremde werden übersprungen.
+    pub fn apply_remote_delta(&mut self, origin: &str, delta: &CrdtDelta) -> Result<()> {
+        self.apply_delta_from(Some(origin), delta)
+    }
+
+    fn apply_delta_from(&mut self, origin: Option<&str>, delta: &CrdtDelta) -> Result<()> {
+        // Empfang => HLC nachziehen, Orders behalten ihren Ursprungs-HLC
+        self.crdt_state.clock.update(delta.hlc);
+        let owner = origin.unwrap_or("");
         for o in &delta.updated_orders {
             // Beispiel: Falls du in `crdt_logic::Order` => verify_signature() hast
             if !o.verify_signature() {
                 warn!("Order {} hat ungültige Signatur => Delta-Anwendung übersprungen", o.id);
                 continue;
             }
-            self.crdt_state.add_local_order("NodeX", &o.id, &o.user_id, o.quantity, o.price)?;
+            if !self.may_modify(origin, &o.id) {
+                warn!("Order {} gehört nicht zu {} => Update übersprungen", o.id, owner);
+                continue;
+            }
+            self.crdt_state.add_remote_order("NodeX", o.clone())?;
             self.db.store_order(self.shard_id, o)?;
+            self.order_origins.entry(o.id.clone()).or_insert_with(|| owner.to_string());
         }
         for rid in &delta.removed_orders {
+            if !self.may_modify(origin, rid) {
+                warn!("Removal von {} durch {} nicht autorisiert => übersprungen", rid, owner);
+                continue;
+            }
             self.crdt_state.remove_local_order("NodeX", rid)?;
             self.db.remove_order(self.shard_id, rid)?;
         }
         Ok(())
     }
 
-    /// Bilde Merkle-Root => naive Variante
-    pub fn compute_merkle_root(&self) -> Vec<u8> {
-        use sha2::{Sha256, Digest};
-        let mut hasher = Sha256::new();
-        // iteriere alle visible Orders
-        for (ord, _) in &self.crdt_state.orset.adds {
-            hasher.update(ord.id.as_bytes());
-            // optional: auch user_id, quantity, price etc. 
-            // => so stellst du sicher, dass jede Änderung am Order erfasst wird.
+    /// Lokal darf alles; ein Ursprung nur eigene oder noch unbekannte Orders.
+    fn may_modify(&self, origin: Option<&str>, order_id: &str) -> bool {
+        match (origin, self.order_origins.get(order_id)) {
+            (None, _) | (Some(_), None) => true,
+            (Some(origin), Some(owner)) => owner == origin,
         }
-        hasher.finalize().to_vec()
+    }
+
+    /// Merkle-Root des Shards = kanonischer State-Root der sichtbaren Orders
+    /// (auf allen Replikaten gleich, solange der Zustand gleich ist).
+    pub fn compute_merkle_root(&self) -> Vec<u8> {
+        self.crdt_state.state_root().to_vec()
     }
 
     /// Erzeugt einen Checkpoint => z. B. on-chain anchor
@@ -309,6 +470,15 @@ impl AdvancedShardState {
 pub struct AdvancedGossipNode {
     pub shard_states: Arc<Mutex<HashMap<u32, AdvancedShardState>>>,
     pub node_id: String,
+    /// Signiert ausgehende Deltas
+    pub keypair: Option<Arc<Keypair>>,
+    /// Ursprungs-Node => gepinnter Schlüssel (aus Membership/Onboarding).
+    /// Deltas unbekannter Ursprünge werden verworfen.
+    pub trusted_keys: Arc<Mutex<HashMap<String, PublicKey>>>,
+    /// Fallback für Nodes, die erst nach dem Start onboarded wurden
+    key_directory: Option<KeyDirectory>,
+    /// Zeitstempel des letzten angenommenen Deltas je (Ursprung, Shard)
+    last_seen: Arc<Mutex<HashMap<(String, u32), u64>>>,
 }
 
 impl AdvancedGossipNode {
@@ -316,7 +486,75 @@ impl AdvancedGossipNode {
         Self {
             shard_states: Arc::new(Mutex::new(HashMap::new())),
             node_id: node_id.to_string(),
+            keypair: None,
+            trusted_keys: Arc::new(Mutex::new(HashMap::new())),
+            key_directory: None,
+            last_seen: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn with_keypair(mut self, keypair: Keypair) -> Self {
+        self.keypair = Some(Arc::new(keypair));
+        self
+    }
+
+    /// Hinterlegt den Schlüssel eines Peers explizit (z. B. beim Onboarding).
+    pub fn trust_peer(&self, node_id: &str, key: PublicKey) {
+        self.trusted_keys.lock().unwrap().insert(node_id.to_string(), key);
+    }
+
+    /// Schlüssel-Quelle für Ursprünge ohne gepinnten Schlüssel, z. B. die
+    /// `NodeInfo.public_key` aus dem Onboarding. Gefundene Schlüssel werden gepinnt.
+    pub fn with_key_directory(mut self, directory: KeyDirectory) -> Self {
+        self.key_directory = Some(directory);
+        self
+    }
+
+    /// Verpackt ein Delta als signierte GossipMessage dieses Nodes.
+    pub fn sign_delta(&self, shard_id: u32, delta: CrdtDelta) -> Result<GossipMessage> {
+        let kp = self
+            .keypair
+            .as_ref()
+            .ok_or_else(|| anyhow!("Node {} has no keypair to sign deltas", self.node_id))?;
+        let mut msg = GossipMessage::new(&self.node_id, shard_id, delta);
+        msg.sign(kp)?;
+        Ok(msg)
+    }
+
+    /// Signatur, Ursprungs-Schlüssel und Frische prüfen.
+    fn verify_origin(&self, msg: &GossipMessage) -> Result<(), DexError> {
+        let key = msg.verify()?;
+        let known = self.origin_key(&msg.origin_node).ok_or_else(|| {
+            DexError::InvalidSignature(format!("delta from unknown origin {}", msg.origin_node))
+        })?;
+        if known.as_bytes() != key.as_bytes() {
+            return Err(DexError::InvalidSignature(format!(
+                "delta for origin {} signed with unknown key", msg.origin_node
+            )));
+        }
+        let now = now_millis();
+        if msg.timestamp + DELTA_MAX_AGE_MS < now || msg.timestamp > now + DELTA_MAX_FUTURE_SKEW_MS {
+            return Err(DexError::Other(format!(
+                "delta from {} with timestamp {} outside the accepted window", msg.origin_node, msg.timestamp
+            )));
         }
+        let seen = self.last_seen.lock().unwrap();
+        if seen.get(&(msg.origin_node.clone(), msg.shard_id)).is_some_and(|last| msg.timestamp <= *last) {
+            return Err(DexError::Other(format!(
+                "replayed delta from {} for shard {}", msg.origin_node, msg.shard_id
+            )));
+        }
+        Ok(())
+    }
+
+    fn origin_key(&self, origin: &str) -> Option<PublicKey> {
+        if let Some(key) = self.trusted_keys.lock().unwrap().get(origin) {
+            return Some(*key);
+        }
+        let key = self.key_directory.as_ref().and_then(|dir| dir(origin))?;
+        debug!("Pinning onboarded key of origin {}", origin);
+        self.trust_peer(origin, key);
+        Some(key)
     }
 
     /// Füge einen ShardState hinzu (z. B. wenn wir gerade 
@@ -326,11 +564,17 @@ impl AdvancedGossipNode {
         lock.insert(shard.shard_id, shard);
     }
 
-    /// Empfängt Delta => nur, wenn wir shard_id haben
+    /// Empfängt Delta => nur mit gültiger Ursprungs-Signatur und nur, wenn wir shard_id haben.
+    /// Unsignierte oder manipulierte Deltas werden verworfen (Err).
     pub fn handle_delta_gossip(&mut self, msg: &GossipMessage) -> Result<()> {
+        if let Err(e) = self.verify_origin(msg) {
+            warn!("Node {} drops delta for shard={}: {}", self.node_id, msg.shard_id, e);
+            return Err(e.into());
+        }
         let mut lock = self.shard_states.lock().unwrap();
         if let Some(state) = lock.get_mut(&msg.shard_id) {
-            state.apply_delta(&msg.delta)?;
+            state.apply_remote_delta(&msg.origin_node, &msg.delta)?;
+            self.last_seen.lock().unwrap().insert((msg.origin_node.clone(), msg.shard_id), msg.timestamp);
             debug!("Node {} applied delta on shard={}", self.node_id, msg.shard_id);
         } else {
             warn!("Shard {} not found on Node {}", msg.shard_id, self.node_id);
@@ -348,6 +592,7 @@ impl AdvancedGossipNode {
                 crdt_state: CrdtState::default(),
                 db: AdvancedShardDB::open(&format!("db_shard_{}.db", snap.shard_id)).unwrap(),
                 watchtower: AdvancedWatchtower::new(Watchtower::new()),
+                order_origins: HashMap::new(),
             }
         });
         entry.crdt_state = CrdtState::default();
@@ -371,11 +616,7 @@ pub fn send_delta_message(
     shard_id: u32,
     delta: CrdtDelta
 ) -> Result<()> {
-    let msg = GossipMessage {
-        shard_id,
-        delta,
-        timestamp: Instant::now()
-    };
+    let msg = sender.sign_delta(shard_id, delta)?;
     receiver.handle_delta_gossip(&msg)
 }
 
@@ -396,6 +637,7 @@ pub fn demo_advanced_crdt_sharding() -> Result<()> {
     )?;
 
     // 3) Delta => fügen wir eine Order ein
+    let mut clock = HybridLogicalClock::new("Node1");
     let deltaA = CrdtDelta {
         updated_orders: vec![
             // ACHTUNG: In einer produktiven Implementierung bräuchte
@@ -407,12 +649,15 @@ pub fn demo_advanced_crdt_sharding() -> Result<()> {
                 timestamp: 0,
                 quantity: 5.0,
                 price: 100.0,
+                side: None,
+                hlc: clock.tick(),
                 // Falls Signatur-Felder existieren:
                 signature: None,
                 public_key: None,
             }
         ],
-        removed_orders: vec![]
+        removed_orders: vec![],
+        hlc: clock.tick(),
     };
     shardA.apply_delta(&deltaA)?;
 
@@ -423,10 +668,15 @@ pub fn demo_advanced_crdt_sharding() -> Result<()> {
     shardA.store_shard_snapshot()?;
 
     // 6) Node1 + Node2 => Gossip
-    let mut node1 = AdvancedGossipNode::new("Node1");
+    let mut node1 = AdvancedGossipNode::new("Node1")
+        .with_keypair(Keypair::generate(&mut rand_07::rngs::OsRng));
     node1.add_shard_state(shardA);
 
     let mut node2 = AdvancedGossipNode::new("Node2");
+    // Schlüssel von Node1 kommt aus dem Onboarding, nicht aus dem ersten Delta
+    if let Some(kp) = &node1.keypair {
+        node2.trust_peer("Node1", kp.public);
+    }
 
     // Snapshot -> node2 => Full-Bootstrap
     {
@@ -445,11 +695,14 @@ pub fn demo_advanced_crdt_sharding() -> Result<()> {
                 timestamp: 0,
                 quantity: 2.5,
                 price: 101.0,
+                side: None,
+                hlc: clock.tick(),
                 signature: None,
                 public_key: None,
             }
         ],
-        removed_orders: vec![]
+        removed_orders: vec![],
+        hlc: clock.tick(),
     };
     // Sende => Node2
     send_delta_message(&mut node1, &mut node2, 0, deltaB)?;
@@ -457,3 +710,142 @@ pub fn demo_advanced_crdt_sharding() -> Result<()> {
     info!("Demo finished => Node2 should have o1 & o2 in shard=0");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair() -> Keypair {
+        Keypair::generate(&mut rand_07::rngs::OsRng)
+    }
+
+    fn signed_order(id: &str, kp: &Keypair, clock: &mut HybridLogicalClock) -> Order {
+        let mut o = Order {
+            id: id.to_string(),
+            user_id: "alice".to_string(),
+            timestamp: 1,
+            quantity: 1.0,
+            price: 100.0,
+            side: None,
+            hlc: clock.tick(),
+            signature: None,
+            public_key: None,
+        };
+        o.sign(kp).unwrap();
+        o
+    }
+
+    fn receiver(dir: &tempfile::TempDir) -> AdvancedGossipNode {
+        let path = dir.path().join("shard0");
+        let shard = AdvancedShardState::new(0, path.to_str().unwrap(), Watchtower::new("Node2")).unwrap();
+        let mut node = AdvancedGossipNode::new("Node2");
+        node.add_shard_state(shard);
+        node
+    }
+
+    fn sender(rx: &AdvancedGossipNode) -> AdvancedGossipNode {
+        let node = AdvancedGossipNode::new("Node1").with_keypair(keypair());
+        rx.trust_peer("Node1", node.keypair.as_ref().unwrap().public);
+        node
+    }
+
+    fn delta_of(orders: Vec<Order>, removed: Vec<String>, clock: &mut HybridLogicalClock) -> CrdtDelta {
+        CrdtDelta { updated_orders: orders, removed_orders: removed, hlc: clock.tick() }
+    }
+
+    #[test]
+    fn test_valid_signed_delta_is_applied() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut rx = receiver(&dir);
+        let mut sender = sender(&rx);
+        let mut clock = HybridLogicalClock::new("Node1");
+        let user_kp = keypair();
+        let delta = CrdtDelta {
+            updated_orders: vec![signed_order("o1", &user_kp, &mut clock)],
+            removed_orders: vec![],
+            hlc: clock.tick(),
+        };
+
+        send_delta_message(&mut sender, &mut rx, 0, delta).unwrap();
+        let states = rx.shard_states.lock().unwrap();
+        let visible = states[&0].crdt_state.visible_orders();
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].id, "o1");
+    }
+
+    #[test]
+    fn test_tampered_or_unsigned_delta_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut rx = receiver(&dir);
+        let sender = sender(&rx);
+        let mut clock = HybridLogicalClock::new("Node1");
+        let user_kp = keypair();
+        let delta = CrdtDelta {
+            updated_orders: vec![signed_order("o1", &user_kp, &mut clock)],
+            removed_orders: vec![],
+            hlc: clock.tick(),
+        };
+        let good = sender.sign_delta(0, delta).unwrap();
+
+        // Relay hängt eine Removal an bzw. leitet auf einen anderen Shard um
+        let mut tampered = good.clone();
+        tampered.delta.removed_orders.push("victim".into());
+        assert!(rx.handle_delta_gossip(&tampered).is_err());
+        let mut rerouted = good.clone();
+        rerouted.shard_id = 7;
+        assert!(rx.handle_delta_gossip(&rerouted).is_err());
+
+        // Unsigniert
+        let mut unsigned = good.clone();
+        unsigned.signature = None;
+        unsigned.public_key = None;
+        assert!(rx.handle_delta_gossip(&unsigned).is_err());
+
+        // Neu signiert von einem fremden Schlüssel, Node1 ist gepinnt
+        rx.handle_delta_gossip(&good).unwrap();
+        let mut forged = good.clone();
+        forged.delta.removed_orders.push("o1".into());
+        forged.timestamp += 1;
+        forged.sign(&keypair()).unwrap();
+        assert!(rx.handle_delta_gossip(&forged).is_err());
+
+        let states = rx.shard_states.lock().unwrap();
+        assert_eq!(states[&0].crdt_state.visible_orders().len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_origin_replay_and_foreign_removal_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut rx = receiver(&dir);
+        let node1 = sender(&rx);
+        let mut clock = HybridLogicalClock::new("Node1");
+        let user_kp = keypair();
+
+        // Gültig signiert, aber Schlüssel nie über Onboarding hinterlegt => kein TOFU
+        let stranger = AdvancedGossipNode::new("Node3").with_keypair(keypair());
+        let msg = stranger.sign_delta(0, delta_of(vec![signed_order("x1", &user_kp, &mut clock)], vec![], &mut clock)).unwrap();
+        assert!(rx.handle_delta_gossip(&msg).is_err());
+
+        // Über das Key-Directory (Onboarding) bekannt => angenommen
+        let node3_key = stranger.keypair.as_ref().unwrap().public;
+        let mut rx = rx.with_key_directory(Arc::new(move |id: &str| (id == "Node3").then_some(node3_key)));
+        rx.handle_delta_gossip(&msg).unwrap();
+
+        // Replay desselben Umschlags und zu alte Deltas
+        assert!(rx.handle_delta_gossip(&msg).is_err());
+        let mut old = node1.sign_delta(0, delta_of(vec![signed_order("o9", &user_kp, &mut clock)], vec![], &mut clock)).unwrap();
+        old.timestamp -= DELTA_MAX_AGE_MS + 1_000;
+        old.sign(node1.keypair.as_ref().unwrap()).unwrap();
+        assert!(rx.handle_delta_gossip(&old).is_err());
+
+        // Node1 darf die Order von Node3 nicht entfernen, seine eigene schon
+        let own = node1.sign_delta(0, delta_of(vec![signed_order("o1", &user_kp, &mut clock)], vec![], &mut clock)).unwrap();
+        rx.handle_delta_gossip(&own).unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        let removal = node1.sign_delta(0, delta_of(vec![], vec!["x1".into(), "o1".into()], &mut clock)).unwrap();
+        rx.handle_delta_gossip(&removal).unwrap();
+        let states = rx.shard_states.lock().unwrap();
+        let ids: Vec<String> = states[&0].crdt_state.visible_orders().into_iter().map(|o| o.id).collect();
+        assert_eq!(ids, vec!["x1".to_string()]);
+    }
+}
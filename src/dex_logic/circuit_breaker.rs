@@ -0,0 +1,239 @@
+////////////////////////////////////////////////////
+/// my_dex/src/dex_logic/circuit_breaker.rs
+////////////////////////////////////////////////////
+//
+// Circuit-Breaker je Markt gegen Flash-Crashes und manipulierte Feeds.
+//
+// Jeder Referenzpreis aus dem PriceFeed (ohne Quorum: der letzte lokale
+// Trade-Preis) geht über `observe` in ein gleitendes Fenster (`window_secs`). Liegt die Spanne (max - min) / min im
+// Fenster über `max_move_pct`, löst der Breaker aus: die MatchingEngine
+// matcht den Markt nicht mehr (Orders dürfen weiter ins Buch), bis
+// `cooldown_secs` verstrichen sind. Danach setzt `check` den Markt
+// automatisch zurück; das Fenster startet dann mit dem letzten Preis neu,
+// damit der alte Sprung nicht sofort erneut auslöst.
+//
+// Märkte werden wie in `FeeSchedule` normalisiert ("btc/usdt" == "BTC/USDT").
+//
+// Auslösen und Wiederaufnahme landen im Audit-Log und optional als
+// `BreakerEvent` in einem Kanal (main.rs leitet sie als FaultMessage an
+// die Peers weiter).
+////////////////////////////////////////////////////
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use tracing::warn;
+
+use crate::logging::enhanced_logging::write_audit_log;
+use crate::matching_engine::FeeSchedule;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BreakerThresholds {
+    /// Maximal erlaubte Spanne im Fenster, z. B. 0.1 = 10 %
+    pub max_move_pct: f64,
+    pub window_secs: u64,
+    pub cooldown_secs: u64,
+}
+
+impl Default for BreakerThresholds {
+    fn default() -> Self {
+        Self { max_move_pct: 0.10, window_secs: 60, cooldown_secs: 300 }
+    }
+}
+
+impl BreakerThresholds {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.max_move_pct.is_finite() || self.max_move_pct <= 0.0 {
+            return Err(format!("max_move_pct {} must be > 0", self.max_move_pct));
+        }
+        if self.window_secs == 0 || self.cooldown_secs == 0 {
+            return Err("window_secs and cooldown_secs must be > 0".into());
+        }
+        Ok(())
+    }
+}
+
+/// Teil der NodeConfig (`circuit_breakers`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub default: BreakerThresholds,
+    /// Abweichende Schwellen je Markt ("BASE/QUOTE")
+    #[serde(default)]
+    pub markets: BTreeMap<String, BreakerThresholds>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self { enabled: true, default: BreakerThresholds::default(), markets: BTreeMap::new() }
+    }
+}
+
+impl CircuitBreakerConfig {
+    pub fn thresholds(&self, market: &str) -> BreakerThresholds {
+        self.markets.get(&FeeSchedule::market_key(market)).copied().unwrap_or(self.default)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BreakerEvent {
+    Tripped { market: String, from: f64, to: f64, move_pct: f64, resume_at: u64 },
+    Resumed { market: String },
+}
+
+impl BreakerEvent {
+    pub fn market(&self) -> &str {
+        match self {
+            BreakerEvent::Tripped { market, .. } | BreakerEvent::Resumed { market } => market,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            BreakerEvent::Tripped { market, from, to, move_pct, resume_at } => format!(
+                "Circuit-Breaker {}: {} => {} ({:.2}%), Matching pausiert bis {}",
+                market, from, to, move_pct * 100.0, resume_at
+            ),
+            BreakerEvent::Resumed { market } => format!("Circuit-Breaker {}: Matching wieder aufgenommen", market),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MarketState {
+    window: VecDeque<(u64, f64)>,
+    resume_at: Option<u64>,
+}
+
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    markets: HashMap<String, MarketState>,
+    events: Option<tokio::sync::mpsc::UnboundedSender<BreakerEvent>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self { config, markets: HashMap::new(), events: None }
+    }
+
+    pub fn with_events(mut self, tx: tokio::sync::mpsc::UnboundedSender<BreakerEvent>) -> Self {
+        self.events = Some(tx);
+        self
+    }
+
+    fn emit(&self, event: BreakerEvent) -> BreakerEvent {
+        write_audit_log(&event.describe());
+        if let Some(tx) = &self.events {
+            let _ = tx.send(event.clone());
+        }
+        event
+    }
+
+    /// Neuer Referenzpreis für `market` zum Zeitpunkt `now` (Sekunden).
+    pub fn observe(&mut self, market: &str, price: f64, now: u64) -> Option<BreakerEvent> {
+        if !self.config.enabled || !price.is_finite() || price <= 0.0 {
+            return None;
+        }
+        let market = FeeSchedule::market_key(market);
+        let t = self.config.thresholds(&market);
+        let state = self.markets.entry(market.clone()).or_default();
+        state.window.push_back((now, price));
+        while state.window.front().map_or(false, |(ts, _)| ts + t.window_secs < now) {
+            state.window.pop_front();
+        }
+        if state.resume_at.is_some() {
+            return None;
+        }
+        let (lo, hi) = state.window.iter().fold((f64::MAX, f64::MIN), |(lo, hi), (_, p)| (lo.min(*p), hi.max(*p)));
+        let move_pct = (hi - lo) / lo;
+        if move_pct <= t.max_move_pct {
+            return None;
+        }
+        let from = state.window.front().map(|(_, p)| *p).unwrap_or(price);
+        let resume_at = now + t.cooldown_secs;
+        state.resume_at = Some(resume_at);
+        warn!("Circuit-Breaker {} ausgelöst: {:.2}% in {}s", market, move_pct * 100.0, t.window_secs);
+        Some(self.emit(BreakerEvent::Tripped { market, from, to: price, move_pct, resume_at }))
+    }
+
+    /// true, solange `market` pausiert ist. Nach dem Cooldown wird der Markt
+    /// hier automatisch wieder freigegeben.
+    pub fn check(&mut self, market: &str, now: u64) -> bool {
+        let market = FeeSchedule::market_key(market);
+        let state = match self.markets.get_mut(&market) {
+            Some(s) => s,
+            None => return false,
+        };
+        match state.resume_at {
+            Some(at) if now < at => true,
+            Some(_) => {
+                state.resume_at = None;
+                // Neustart des Fensters ab dem letzten Preis
+                let last = state.window.back().copied();
+                state.window.clear();
+                state.window.extend(last);
+                self.emit(BreakerEvent::Resumed { market });
+                false
+            }
+            None => false,
+        }
+    }
+
+    pub fn tripped_markets(&self) -> Vec<String> {
+        self.markets.iter().filter(|(_, s)| s.resume_at.is_some()).map(|(m, _)| m.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> CircuitBreaker {
+        let t = BreakerThresholds { max_move_pct: 0.05, window_secs: 60, cooldown_secs: 120 };
+        CircuitBreaker::new(CircuitBreakerConfig { enabled: true, default: t, markets: BTreeMap::new() })
+    }
+
+    #[test]
+    fn test_spike_trips_and_cooldown_resumes() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut cb = breaker().with_events(tx);
+        assert!(cb.observe("BTC/USDT", 30_000.0, 1_000).is_none());
+        assert!(cb.observe("BTC/USDT", 30_900.0, 1_010).is_none()); // 3 %
+        // Langsame Bewegung außerhalb des Fensters löst nicht aus
+        assert!(cb.observe("BTC/USDT", 31_800.0, 1_080).is_none());
+        assert!(!cb.check("BTC/USDT", 1_080));
+
+        let ev = cb.observe("BTC/USDT", 26_000.0, 1_090).expect("crash trips");
+        assert!(matches!(ev, BreakerEvent::Tripped { resume_at: 1_210, .. }));
+        assert!(cb.check("BTC/USDT", 1_100));
+        assert!(!cb.check("ETH/USDT", 1_100));
+        assert_eq!(cb.tripped_markets(), vec!["BTC/USDT".to_string()]);
+
+        assert!(!cb.check("BTC/USDT", 1_210));
+        assert!(cb.tripped_markets().is_empty());
+        // Das alte Fenster zählt nach der Wiederaufnahme nicht mehr
+        assert!(cb.observe("BTC/USDT", 26_100.0, 1_215).is_none());
+
+        assert!(matches!(rx.try_recv(), Ok(BreakerEvent::Tripped { .. })));
+        assert_eq!(rx.try_recv().unwrap(), BreakerEvent::Resumed { market: "BTC/USDT".into() });
+    }
+
+    #[test]
+    fn test_market_keys_normalized() {
+        let tight = BreakerThresholds { max_move_pct: 0.01, window_secs: 60, cooldown_secs: 120 };
+        let mut markets = BTreeMap::new();
+        markets.insert("BTC/USDT".to_string(), tight);
+        let mut cb = CircuitBreaker::new(CircuitBreakerConfig { enabled: true, default: BreakerThresholds::default(), markets });
+        assert_eq!(cb.config.thresholds("btc/usdt"), tight);
+
+        cb.observe("btc/usdt", 30_000.0, 1_000);
+        assert!(cb.observe("BTC/usdt", 30_600.0, 1_010).is_some());
+        assert!(cb.check("BTC/USDT", 1_020));
+        assert_eq!(cb.tripped_markets(), vec!["BTC/USDT".to_string()]);
+    }
+}
@@ -0,0 +1,260 @@
+//-------------------------------------
+// my_dex/src/dex_logic/commit_reveal.rs
+//-------------------------------------
+//
+// Commit-Reveal für Order-Einreichung (MEV-Schutz):
+//  1) Commit-Phase: der Nutzer schickt nur `order_commitment_hash(order, nonce)`.
+//     Beobachtende Nodes sehen weder Seite noch Preis noch Menge.
+//  2) Nach `commit_deadline` (Reveal-Phase bis `reveal_deadline`) wird die
+//     Order samt Nonce offengelegt. Der Hash muss zu einer Commitment der
+//     aktuellen Epoche passen, erst dann geht die Order ins Buch.
+//  3) Zu späte, zu frühe oder nicht passende Reveals werden abgelehnt.
+//     Mit `start_epoch` verfallen alle offenen Commitments der alten Epoche.
+//  4) Jede Commitment gehört einem user_id; offengelegt werden kann sie nur
+//     mit einer Order desselben Nutzers. Offene Commitments sind pro Nutzer
+//     und insgesamt begrenzt (`CommitRevealLimits`).
+//
+
+use serde::Serialize;
+use std::collections::HashMap;
+use sha2::{Sha256, Digest};
+use tracing::{debug, warn};
+
+use crate::error::DexError;
+use crate::matching_engine::{OrderData, OrderSide, OrderType};
+use crate::utils::canonical;
+
+/// Domain-Tag der Commitment-Hashes (eigener Namensraum neben `my_dex/order/v1`).
+pub const ORDER_COMMITMENT_DOMAIN: &str = "my_dex/order_commitment/v1";
+
+/// Zeitfenster einer Epoche (Unix-Sekunden).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitRevealWindow {
+    pub epoch: u64,
+    pub commit_deadline: u64,
+    pub reveal_deadline: u64,
+}
+
+/// Obergrenzen für offene Commitments einer Epoche.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitRevealLimits {
+    pub max_per_user: usize,
+    pub max_total: usize,
+}
+
+impl Default for CommitRevealLimits {
+    fn default() -> Self {
+        Self { max_per_user: 64, max_total: 100_000 }
+    }
+}
+
+#[derive(Serialize)]
+struct OrderCommitmentView<'a> {
+    id: &'a str,
+    user_id: &'a str,
+    side: &'a OrderSide,
+    order_type: &'a OrderType,
+    quantity: f64,
+    timestamp: u64,
+    nonce: &'a [u8],
+}
+
+/// Hash, auf den sich der Nutzer vor dem Reveal festlegt: SHA-256 über die
+/// kanonischen CBOR-Bytes der Order-Felder samt Nonce.
+pub fn order_commitment_hash(order: &OrderData, nonce: &[u8]) -> Result<[u8; 32], DexError> {
+    let bytes = canonical::signing_bytes(ORDER_COMMITMENT_DOMAIN, &OrderCommitmentView {
+        id: &order.id,
+        user_id: &order.user_id,
+        side: &order.side,
+        order_type: &order.order_type,
+        quantity: order.quantity,
+        timestamp: order.timestamp,
+        nonce,
+    })?;
+    Ok(Sha256::digest(&bytes).into())
+}
+
+pub struct CommitRevealBook {
+    window: CommitRevealWindow,
+    limits: CommitRevealLimits,
+    /// (user_id, Hash) => Epoche, in der committet wurde. Eine kopierte
+    /// fremde Commitment blockiert den eigentlichen Eigentümer so nicht.
+    commitments: HashMap<(String, [u8; 32]), u64>,
+    /// user_id => Anzahl offener Commitments
+    per_user: HashMap<String, usize>,
+}
+
+impl CommitRevealBook {
+    pub fn new(window: CommitRevealWindow) -> Result<Self, DexError> {
+        Self::check_window(&window)?;
+        Ok(Self {
+            window,
+            limits: CommitRevealLimits::default(),
+            commitments: HashMap::new(),
+            per_user: HashMap::new(),
+        })
+    }
+
+    pub fn with_limits(mut self, limits: CommitRevealLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn open_commitments(&self) -> usize {
+        self.commitments.len()
+    }
+
+    fn check_window(window: &CommitRevealWindow) -> Result<(), DexError> {
+        if window.commit_deadline >= window.reveal_deadline {
+            return Err(DexError::Other("commit_deadline must be before reveal_deadline".into()));
+        }
+        Ok(())
+    }
+
+    pub fn window(&self) -> CommitRevealWindow {
+        self.window
+    }
+
+    /// Wechselt zur nächsten Epoche; offene Commitments verfallen.
+    pub fn start_epoch(&mut self, window: CommitRevealWindow) -> Result<(), DexError> {
+        Self::check_window(&window)?;
+        if window.epoch <= self.window.epoch {
+            return Err(DexError::Other(format!("Epoch {} is not newer than {}", window.epoch, self.window.epoch)));
+        }
+        let dropped = self.commitments.len();
+        if dropped > 0 {
+            debug!("CommitReveal => {} nicht offengelegte Commitments aus Epoche {} verfallen", dropped, self.window.epoch);
+        }
+        self.commitments.clear();
+        self.per_user.clear();
+        self.window = window;
+        Ok(())
+    }
+
+    pub fn submit_commitment_at(&mut self, user_id: &str, hash: [u8; 32], now: u64) -> Result<(), DexError> {
+        if now >= self.window.commit_deadline {
+            return Err(DexError::Other(format!("Commit phase of epoch {} is over", self.window.epoch)));
+        }
+        let key = (user_id.to_string(), hash);
+        if self.commitments.contains_key(&key) {
+            return Err(DexError::Other("Duplicate commitment".into()));
+        }
+        let open = self.per_user.get(user_id).copied().unwrap_or(0);
+        if open >= self.limits.max_per_user {
+            warn!("CommitReveal => {} hat bereits {} offene Commitments", user_id, open);
+            return Err(DexError::Other(format!("Too many open commitments for {}", user_id)));
+        }
+        if self.commitments.len() >= self.limits.max_total {
+            warn!("CommitReveal => globales Limit ({}) erreicht", self.limits.max_total);
+            return Err(DexError::Other("Commitment capacity of this epoch exhausted".into()));
+        }
+        self.commitments.insert(key, self.window.epoch);
+        *self.per_user.entry(user_id.to_string()).or_insert(0) += 1;
+        Ok(())
+    }
+
+    /// Prüft den Reveal und verbraucht die Commitment. Liefert die Order zurück,
+    /// die dann ins Buch darf. Die Order muss dem Nutzer gehören, der die
+    /// Commitment eingereicht hat, und ihr Zeitstempel darf nicht nach der
+    /// Commit-Deadline liegen (sie wurde ja in der Commit-Phase erstellt).
+    pub fn reveal_order_at(&mut self, order: OrderData, nonce: &[u8], now: u64) -> Result<OrderData, DexError> {
+        if now < self.window.commit_deadline {
+            return Err(DexError::Other("Reveal before commit deadline".into()));
+        }
+        if now > self.window.reveal_deadline {
+            warn!("CommitReveal => verspäteter Reveal für Order {}", order.id);
+            return Err(DexError::Other(format!("Reveal phase of epoch {} is over", self.window.epoch)));
+        }
+        if order.timestamp > self.window.commit_deadline {
+            return Err(DexError::Other(format!("Order {} was created after the commit deadline", order.id)));
+        }
+        let key = (order.user_id.clone(), order_commitment_hash(&order, nonce)?);
+        match self.commitments.get(&key) {
+            Some(epoch) if *epoch == self.window.epoch => {
+                self.commitments.remove(&key);
+                if let Some(open) = self.per_user.get_mut(&order.user_id) {
+                    *open -= 1;
+                    if *open == 0 {
+                        self.per_user.remove(&order.user_id);
+                    }
+                }
+                Ok(order)
+            }
+            _ => {
+                warn!("CommitReveal => Reveal für Order {} passt zu keiner Commitment", order.id);
+                Err(DexError::Other(format!("Order {} does not match any commitment", order.id)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window() -> CommitRevealWindow {
+        CommitRevealWindow { epoch: 1, commit_deadline: 100, reveal_deadline: 160 }
+    }
+
+    fn order() -> OrderData {
+        OrderData::new("o1", "alice", OrderSide::Buy, OrderType::Limit(100.0), 2.0, 10).signed_for_tests()
+    }
+
+    #[test]
+    fn test_commit_then_reveal() {
+        let mut book = CommitRevealBook::new(window()).unwrap();
+        let o = order();
+        book.submit_commitment_at("alice", order_commitment_hash(&o, b"salt").unwrap(), 50).unwrap();
+
+        // Zu früh
+        assert!(book.reveal_order_at(o.clone(), b"salt", 99).is_err());
+        let admitted = book.reveal_order_at(o.clone(), b"salt", 120).unwrap();
+        assert_eq!(admitted.id, "o1");
+        // Commitment ist verbraucht
+        assert!(book.reveal_order_at(o, b"salt", 121).is_err());
+    }
+
+    #[test]
+    fn test_tampered_or_late_reveal_rejected() {
+        let mut book = CommitRevealBook::new(window()).unwrap();
+        let o = order();
+        book.submit_commitment_at("alice", order_commitment_hash(&o, b"salt").unwrap(), 50).unwrap();
+
+        let mut tampered = o.clone();
+        tampered.order_type = OrderType::Limit(101.0);
+        assert!(book.reveal_order_at(tampered, b"salt", 120).is_err());
+        assert!(book.reveal_order_at(o.clone(), b"other", 120).is_err());
+        assert!(book.reveal_order_at(o.clone(), b"salt", 161).is_err());
+        // Order nach der Commit-Deadline erstellt => kein gültiger Reveal
+        let mut late = o.clone();
+        late.timestamp = 101;
+        assert!(book.reveal_order_at(late, b"salt", 120).is_err());
+
+        // Nach Epochenwechsel gilt die alte Commitment nicht mehr
+        book.start_epoch(CommitRevealWindow { epoch: 2, commit_deadline: 200, reveal_deadline: 260 }).unwrap();
+        assert!(book.reveal_order_at(o, b"salt", 220).is_err());
+        assert!(book.submit_commitment_at("alice", [0u8; 32], 200).is_err());
+    }
+
+    #[test]
+    fn test_commitments_bound_to_user_and_capped() {
+        let limits = CommitRevealLimits { max_per_user: 2, max_total: 3 };
+        let mut book = CommitRevealBook::new(window()).unwrap().with_limits(limits);
+        let o = order();
+
+        // Fremde Commitment auf Alices Order => zählt nicht für Alice
+        book.submit_commitment_at("mallory", order_commitment_hash(&o, b"salt").unwrap(), 50).unwrap();
+        assert!(book.reveal_order_at(o.clone(), b"salt", 120).is_err());
+
+        book.submit_commitment_at("mallory", [1u8; 32], 50).unwrap();
+        assert!(book.submit_commitment_at("mallory", [2u8; 32], 50).is_err());
+        // Kopierter Hash blockiert Alice nicht
+        book.submit_commitment_at("alice", order_commitment_hash(&o, b"salt").unwrap(), 50).unwrap();
+        // Global voll
+        assert!(book.submit_commitment_at("bob", [3u8; 32], 50).is_err());
+        assert_eq!(book.open_commitments(), 3);
+
+        book.reveal_order_at(o, b"salt", 120).unwrap();
+        assert_eq!(book.open_commitments(), 2);
+    }
+}
@@ -9,17 +9,14 @@
 // => und ITCOrderBook checkt signierte Orders.
 
 use crate::dex_logic::itc_crdt_orderbook::{ITCOrderBook, Order, Asset};
-use crate::error::DexError;
 
 use rand::{thread_rng, Rng};
 use std::collections::VecDeque;
 
-// Ed25519 – in echter Code ggf. error handling / crates
-use ed25519_dalek::{Keypair, Signer};
-use rand::rngs::OsRng;
+// Ed25519 (baut auf rand 0.7 auf)
+use ed25519_dalek::Keypair;
 
 // Diese Struktur repräsentiert einen Node inkl. Keypair
-#[derive(Clone)]
 struct FuzzNode {
     book: ITCOrderBook,
     keypair: Keypair,
@@ -27,36 +24,29 @@ struct FuzzNode {
 }
 
 impl FuzzNode {
-    fn new(id: &str) -> Self {
-        let mut csprng = OsRng {};
-        let kp = Keypair::generate(&mut csprng);
+    /// `book` ist ein Fork einer bestehenden Replik (eigene ITC-Identität).
+    fn new(id: &str, book: ITCOrderBook) -> Self {
+        let kp = Keypair::generate(&mut rand_07::rngs::OsRng);
         Self {
-            book: ITCOrderBook::new(),
+            book,
             keypair: kp,
             node_id: id.to_string(),
         }
     }
 
     fn sign_order(&self, order: &mut Order) {
-        // define message to sign => e.g. "id + user + assets + quantity + price + timestamp"
-        let msg = format!(
-            "{}:{}:{:?}:{:?}:{}:{}:{}",
-            order.order_id, order.user_id, order.base, order.quote,
-            order.quantity, order.price, order.timestamp
-        );
-        let sig_bytes = self.keypair.sign(msg.as_bytes()).to_bytes().to_vec();
-        order.signature = Some(sig_bytes);
-        // public_key
-        order.public_key = Some(self.keypair.public.to_bytes().to_vec());
+        // Gleiches Format, das Order::verify_signature prüft
+        order.sign(&self.keypair).expect("order signing");
     }
 }
 
 pub fn fuzz_simulation(num_nodes: usize, steps: usize) {
     println!("Fuzz simulation: {} nodes, {} steps", num_nodes, steps);
 
-    // Erzeuge Node-Objekte, jedes mit eigenem Keypair
+    // Erzeuge Node-Objekte, jedes mit eigenem Keypair und geforkter ITC-Identität
+    let mut root = ITCOrderBook::new();
     let mut nodes: Vec<FuzzNode> = (0..num_nodes)
-        .map(|i| FuzzNode::new(&format!("Node{}", i)))
+        .map(|i| FuzzNode::new(&format!("Node{}", i), root.fork()))
         .collect();
 
     let mut rng = thread_rng();
@@ -77,24 +67,21 @@ pub fn fuzz_simulation(num_nodes: usize, steps: usize) {
             rng.gen_range(0.01..1.0),
             rng.gen_range(50.0..150.0)
         );
-        // Setze timestamp random
-        order.timestamp = rng.gen_range(1_600_000_000..1_700_000_000);
-
         // Node signiert die Order => in real usage: "owner" sign
         // => im Fuzz Test signiert einfach der Node, der die Order "ausführt"
         nodes[node_i].sign_order(&mut order);
 
         if op_type == 0 {
             // add
-            let res = nodes[node_i].book.add_order(order.clone(), &nodes[node_i].node_id);
+            let res = nodes[node_i].book.add_order(order.clone());
             match res {
                 Ok(_) => {
-                    ops_log.push_back(format!("step {}: Node{} ADD {}", step, node_i, order_id));
+                    ops_log.push_back(format!("step {}: {} ADD {}", step, nodes[node_i].node_id, order_id));
                 },
                 Err(e) => {
                     // z.B. Signatur invalid, negative quantity => in diesem Fuzz
                     // normal unwahrscheinlich
-                    ops_log.push_back(format!("step {}: Node{} ADD {} => ERR={:?}", step, node_i, order_id, e));
+                    ops_log.push_back(format!("step {}: {} ADD {} => ERR={:?}", step, nodes[node_i].node_id, order_id, e));
                 }
             }
         } else {
@@ -103,8 +90,8 @@ pub fn fuzz_simulation(num_nodes: usize, steps: usize) {
             if !all.is_empty() {
                 let pick = rng.gen_range(0..all.len());
                 let orem = &all[pick];
-                nodes[node_i].book.remove_order(orem, &nodes[node_i].node_id);
-                ops_log.push_back(format!("step {}: Node{} REMOVE {}", step, node_i, orem.order_id));
+                let _ = nodes[node_i].book.remove_order(orem);
+                ops_log.push_back(format!("step {}: {} REMOVE {}", step, nodes[node_i].node_id, orem.order_id));
             }
         }
 
@@ -115,8 +102,8 @@ pub fn fuzz_simulation(num_nodes: usize, steps: usize) {
             let b = rng.gen_range(0..num_nodes);
             if a != b {
                 // clone A => merge into B
-                let cloneA = nodes[a].book.clone();
-                nodes[b].book.merge(&cloneA);
+                let clone_a = nodes[a].book.clone();
+                nodes[b].book.merge(&clone_a);
             }
         }
     }
@@ -124,8 +111,8 @@ pub fn fuzz_simulation(num_nodes: usize, steps: usize) {
     for i in 1..num_nodes {
         let clone0 = nodes[0].book.clone();
         nodes[i].book.merge(&clone0);
-        let cloneI = nodes[i].book.clone();
-        nodes[0].book.merge(&cloneI);
+        let clone_i = nodes[i].book.clone();
+        nodes[0].book.merge(&clone_i);
     }
 
     let final0 = nodes[0].book.all_orders();
@@ -9,15 +9,18 @@
 // Schritte:
 //  1) ITCBookDelta bekommt (signature, public_key) + sign_delta(...) & verify_signature(...)
 //  2) Node kann optional ein Keypair halten und in create_delta() das Delta signieren.
+//  Signiert wird über kanonisches CBOR (utils::canonical, Domain "my_dex/itc/delta/v1").
 //  3) merge_delta() prüft Signatur => bei ungültig => Abbruch.
 
-use crate::dex_logic::itc_crdt_orderbook::ITCOrderBook;
+use crate::dex_logic::itc_crdt_orderbook::{ITCOrderBook, ItcId};
+use crate::error::DexError;
+use crate::utils::canonical;
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 // Für die Signatur => ed25519_dalek
 use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
-use sha2::{Sha256, Digest};
 
 /// Ein Gossip 'Delta'
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -31,22 +34,27 @@ pub struct ITCBookDelta {
 }
 
 impl ITCBookDelta {
-    /// Signiert das Delta auf Basis von (version + orset).
-    /// In echter Production könntest du auch Timestamps, Node-IDs etc. mit reinhashen.
-    pub fn sign_delta(&mut self, keypair: &Keypair) {
-        // 1) Serialize => Bytes
-        let data = serde_json::to_vec(&( &self.version, &self.orset ))
-            .expect("Failed to serialize delta data");
-
-        // 2) Hash
-        let mut hasher = Sha256::new();
-        hasher.update(data);
-        let hash = hasher.finalize();
-
-        // 3) Sign
-        let sig = keypair.sign(&hash);
-        self.signature = Some(sig.to_bytes().to_vec());
+    /// Kanonische Bytes von (version, orset, public_key).
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct ItcDeltaSigningView<'a> {
+            version: &'a serde_json::Value,
+            orset: &'a serde_json::Value,
+            public_key: &'a Option<Vec<u8>>,
+        }
+        canonical::signing_bytes("my_dex/itc/delta/v1", &ItcDeltaSigningView {
+            version: &self.version,
+            orset: &self.orset,
+            public_key: &self.public_key,
+        })
+    }
+
+    /// Setzt `public_key` und signiert `signing_bytes`.
+    pub fn sign_delta(&mut self, keypair: &Keypair) -> Result<(), DexError> {
         self.public_key = Some(keypair.public.to_bytes().to_vec());
+        let bytes = self.signing_bytes()?;
+        self.signature = Some(keypair.sign(&bytes).to_bytes().to_vec());
+        Ok(())
     }
 
     /// Prüft, ob signature + public_key valide sind.
@@ -63,14 +71,10 @@ impl ITCBookDelta {
             return false;
         };
 
-        // Rebuild hash
-        let data = serde_json::to_vec(&( &self.version, &self.orset ))
-            .expect("Failed to serialize delta data");
-        let mut hasher = Sha256::new();
-        hasher.update(data);
-        let hash = hasher.finalize();
-
-        pubkey.verify(&hash, &signature).is_ok()
+        match self.signing_bytes() {
+            Ok(bytes) => pubkey.verify(&bytes, &signature).is_ok(),
+            Err(_) => false,
+        }
     }
 }
 
@@ -81,11 +85,13 @@ pub struct Node {
     pub itc_book: ITCOrderBook,
 
     // NEU: optionales Keypair => Falls ein Node signieren möchte
-    pub keypair: Option<Keypair>,
+    pub keypair: Option<Arc<Keypair>>,
 }
 
 impl Node {
-    pub fn new(node_id: &str) -> Self {
+    /// Erste Replik eines Netzes: besitzt die ganze ITC-Identität. Darf es
+    /// pro Netz nur einmal geben, alle weiteren Nodes treten über `join_via` bei.
+    pub fn seed(node_id: &str) -> Self {
         Self {
             node_id: node_id.to_string(),
             itc_book: ITCOrderBook::new(),
@@ -93,6 +99,27 @@ impl Node {
         }
     }
 
+    /// Deltas dieses Nodes werden signiert.
+    pub fn with_keypair(mut self, keypair: Arc<Keypair>) -> Self {
+        self.keypair = Some(keypair);
+        self
+    }
+
+    /// Tritt dem Netz über `sponsor` bei: übernimmt dessen halbe ITC-Identität
+    /// und den aktuellen Buch-Zustand.
+    pub fn join_via(node_id: &str, sponsor: &mut Node) -> Self {
+        Self {
+            node_id: node_id.to_string(),
+            itc_book: sponsor.itc_book.fork(),
+            keypair: None,
+        }
+    }
+
+    /// Verlässt das Netz: Identität und Zustand gehen an `heir` zurück.
+    pub fn leave(self, heir: &mut Node) {
+        self.itc_book.retire(&mut heir.itc_book);
+    }
+
     /// Erzeugt das Delta (version + orset) und signiert es optional,
     /// falls wir ein Keypair besitzen.
     pub fn create_delta(&self) -> ITCBookDelta {
@@ -106,8 +133,11 @@ impl Node {
         };
 
         // Falls wir signieren wollen => Keypair anlegen in Node
-        if let Some(ref kp) = self.keypair {
-            delta.sign_delta(kp);
+        // Schlägt das fehl, bleibt das Delta unsigniert und Peers ignorieren es
+        if let Some(kp) = &self.keypair {
+            if let Err(e) = delta.sign_delta(kp) {
+                println!("Warn: Delta nicht signiert: {:?}", e);
+            }
         }
 
         delta
@@ -133,26 +163,110 @@ impl Node {
 }
 
 /// GossipNet => simuliert ein kleines Netzwerk
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct GossipNet {
-    pub nodes: HashMap<String, bool>, // node_id -> dummy
+    /// node_id => zuletzt gemeldete ITC-Identität
+    pub nodes: HashMap<String, ItcId>,
 }
 
 impl GossipNet {
     pub fn new() -> Self {
-        Self {
-            nodes: HashMap::new(),
-        }
+        Self::default()
     }
 
-    pub fn add_node(&mut self, node: &Node) {
-        self.nodes.insert(node.node_id.clone(), true);
+    /// Registriert (oder aktualisiert) einen Node. Überschneidet sich seine
+    /// ITC-Identität mit der eines anderen Nodes (z. B. zwei `seed`s), würden
+    /// beide dieselben Events erzeugen => abgelehnt.
+    pub fn add_node(&mut self, node: &Node) -> Result<(), DexError> {
+        let id = &node.itc_book.version.id;
+        if let Some((holder, _)) = self
+            .nodes
+            .iter()
+            .find(|(nid, other)| *nid != &node.node_id && other.overlaps(id))
+        {
+            return Err(DexError::Other(format!(
+                "ITC-Identität von {} überschneidet sich mit {}",
+                node.node_id, holder
+            )));
+        }
+        self.nodes.insert(node.node_id.clone(), id.clone());
+        Ok(())
     }
 
-    pub fn tick(&self, node: &mut Node) {
-        // Sende Delta an alle => in echt würdest du peers pingen
+    /// Schickt das Delta von `node` an alle registrierten `peers`.
+    pub fn tick(&self, node: &Node, peers: &mut [&mut Node]) {
         let delta = node.create_delta();
-        // normal: net würde "peer_node.merge_delta(delta)" aufrufen
-        // hier nur demonstration => do nothing
+        for peer in peers.iter_mut() {
+            if peer.node_id != node.node_id && self.nodes.contains_key(&peer.node_id) {
+                peer.merge_delta(delta.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex_logic::itc_crdt_orderbook::{Asset, Order};
+
+    fn key() -> Arc<Keypair> {
+        Arc::new(Keypair::generate(&mut rand_07::rngs::OsRng))
+    }
+
+    #[test]
+    fn test_second_seed_is_rejected() {
+        let mut net = GossipNet::new();
+        net.add_node(&Node::seed("A")).unwrap();
+        assert!(net.add_node(&Node::seed("B")).is_err());
+    }
+
+    #[test]
+    fn test_joined_nodes_converge_via_signed_deltas() {
+        let mut a = Node::seed("A").with_keypair(key());
+        let mut b = Node::join_via("B", &mut a).with_keypair(key());
+        let mut c = Node::join_via("C", &mut b).with_keypair(key());
+        let mut net = GossipNet::new();
+        for n in [&a, &b, &c] {
+            net.add_node(n).unwrap();
+        }
+
+        let owner = key();
+        let mut o = Order::new("o1", "carol", Asset::BTC, Asset::LTC, 0.3, 99.0);
+        o.sign(&owner).unwrap();
+        a.itc_book.add_order(o).unwrap();
+        let mut o = Order::new("o2", "dave", Asset::LTC, Asset::BTC, 2.0, 0.01);
+        o.sign(&owner).unwrap();
+        c.itc_book.add_order(o).unwrap();
+
+        net.tick(&a, &mut [&mut b, &mut c]);
+        net.tick(&c, &mut [&mut a, &mut b]);
+        assert_eq!(a.itc_book.all_orders().len(), 2);
+        assert_eq!(a.itc_book.all_orders(), b.itc_book.all_orders());
+        assert_eq!(b.itc_book.all_orders(), c.itc_book.all_orders());
+
+        // Unsignierte Deltas werden ignoriert
+        let mut d = Node::join_via("D", &mut c);
+        net.add_node(&c).unwrap();
+        net.add_node(&d).unwrap();
+        d.itc_book.remove_order(&a.itc_book.all_orders()[0]).unwrap();
+        net.tick(&d, &mut [&mut a]);
+        assert_eq!(a.itc_book.all_orders().len(), 2);
+    }
+
+    #[test]
+    fn test_delta_signature_survives_json_key_order() {
+        let a = Node::seed("A").with_keypair(key());
+        let delta = a.create_delta();
+        assert!(delta.verify_signature());
+
+        // Gleicher Inhalt über einen anderen JSON-Umweg => gleiche kanonische Bytes
+        let text = serde_json::to_string(&delta).unwrap();
+        let back: ITCBookDelta = serde_json::from_str(&text).unwrap();
+        assert_eq!(back.signing_bytes().unwrap(), delta.signing_bytes().unwrap());
+        assert!(back.verify_signature());
+
+        let mut tampered = delta.clone();
+        tampered.version = serde_json::Value::Null;
+        assert!(!tampered.verify_signature());
     }
 }
@@ -7,14 +7,17 @@
 // 1) Wir verhindern negative/Null-Beträge beim Erstellen eines HTLC/AtomicSwap.
 // 2) Wir prüfen, ob der timelock (HTLC) bzw. max_sign_time (Swap) in der Vergangenheit liegt.
 //    So kann kein Angreifer immediate-expired HTLC anlegen.
+//
+// Die Zeit kommt aus einer injizierten Clock (Default: Systemuhr), damit
+// Timelock-Ablauf in Tests per MockClock ohne Warten geprüft werden kann.
 
 use serde::{Serialize, Deserialize};
 use sha2::{Sha256, Digest};
 use anyhow::{Result, anyhow};
-use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{info, debug, instrument};
 
 use crate::dex_logic::orders::Asset;
+use crate::utils::clock::{system_clock, SharedClock};
 use crate::metrics::{
     HTLC_REDEEM_COUNT, HTLC_REFUND_COUNT,
     SWAP_SELLER_REDEEM_COUNT, SWAP_BUYER_REDEEM_COUNT, SWAP_REFUND_COUNT
@@ -29,15 +32,28 @@ pub struct HTLC {
     pub timelock: u64, // Unix-Zeit => ab hier refund
     pub redeemed: bool,
     pub refunded: bool,
+    #[serde(skip, default = "system_clock")]
+    clock: SharedClock,
 }
 
 impl HTLC {
     /// Erzeugt eine neue HTLC, prüft aber, ob amount>0 und timelock>JETZT.
     pub fn new(chain: Asset, amount: f64, preimage_hash: [u8; 32], timelock: u64) -> Result<Self> {
+        Self::new_with_clock(chain, amount, preimage_hash, timelock, system_clock())
+    }
+
+    /// Wie `new`, aber "JETZT" und alle späteren Ablaufprüfungen kommen aus `clock`.
+    pub fn new_with_clock(
+        chain: Asset,
+        amount: f64,
+        preimage_hash: [u8; 32],
+        timelock: u64,
+        clock: SharedClock,
+    ) -> Result<Self> {
         if amount <= 0.0 {
             return Err(anyhow!("HTLC: amount must be positive"));
         }
-        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let now = clock.unix_secs();
         if timelock <= now {
             return Err(anyhow!("HTLC: timelock is already in the past => can't create"));
         }
@@ -49,6 +65,7 @@ impl HTLC {
             timelock,
             redeemed: false,
             refunded: false,
+            clock,
         })
     }
 
@@ -56,9 +73,14 @@ impl HTLC {
         hex::encode(self.hashlock)
     }
 
+    /// Tauscht die Zeitquelle, z.B. nach dem Deserialisieren.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     fn is_expired(&self) -> Result<bool> {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-        Ok(now >= self.timelock)
+        Ok(self.clock.unix_secs() >= self.timelock)
     }
 
     #[instrument(name="htlc_redeem", skip(self, preimage))]
@@ -133,8 +155,8 @@ impl AtomicSwap {
         seller_htlc: HTLC,
         max_sign_time: u64
     ) -> Result<Self> {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)?.as_secs();
+        // Swap-Zeit folgt der Uhr der Käufer-HTLC
+        let now = buyer_htlc.clock.unix_secs();
         if max_sign_time <= now {
             return Err(anyhow!("AtomicSwap: max_sign_time is already in the past => can't create swap"));
         }
@@ -204,8 +226,7 @@ impl AtomicSwap {
     /// check_timeout => Falls wir in (Init/SellerRedeemed) und Zeit abgelaufen => Cancel
     #[instrument(name="swap_check_timeout", skip(self))]
     pub fn check_timeout(&mut self) {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let now = self.buyer_htlc.clock.unix_secs();
         if (self.state == SwapState::Init || self.state == SwapState::SellerRedeemed)
             && now > self.max_sign_time
         {
@@ -214,3 +235,24 @@ impl AtomicSwap {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::clock::MockClock;
+    use std::time::Duration;
+
+    #[test]
+    fn test_timelock_expiry_follows_mock_clock() {
+        let clock = MockClock::new(1_700_000_000);
+        let preimage = b"secret";
+        let hashlock: [u8; 32] = Sha256::digest(preimage).into();
+        let mut htlc = HTLC::new_with_clock(Asset::BTC, 1.0, hashlock, 1_700_000_600, clock.shared()).unwrap();
+
+        assert!(htlc.refund().is_err(), "timelock not reached yet");
+        clock.advance(Duration::from_secs(600));
+        assert!(htlc.redeem(preimage).is_err(), "expired => no redeem");
+        htlc.refund().unwrap();
+        assert!(htlc.refunded);
+    }
+}
@@ -12,14 +12,27 @@
 // Falls du in remove_order() ebenfalls signierte Prüfungen möchtest
 // (z. B. "nur der Besitzer kann entfernen"), könntest du analog
 // checken. Hier zeigen wir es optional in auskommentierter Form.
+//
+// Kausalität über Interval Tree Clocks (Almeida, Baquero, Fonte 2008):
+// Jede Replik hält einen `Stamp` aus Identitäts-Baum (welcher Teil des
+// Intervalls [0,1) ihr gehört) und Event-Baum. Neue Repliken entstehen per
+// `fork` aus einer bestehenden, ausscheidende geben ihre Identität per
+// `join` zurück. Anders als Versionsvektoren wächst der Zustand damit nicht
+// mit jeder Node-ID, die das Netz je gesehen hat.
+//
+// OR-Set: jedes Add ist mit dem Event-Baum nach dem Add-Event getaggt, jedes
+// Remove mit dem Event-Baum des Entfernenden. Ein Add gilt als entfernt, wenn
+// sein Tag kausal vor (leq) einem Remove liegt; nebenläufige Adds gewinnen.
 
 use serde::{Serialize, Deserialize};
-use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::HashMap;
 
 // == Sicherheits-Importe ==
 use crate::error::DexError; 
-use ed25519_dalek::{PublicKey, Signature, Verifier};
-use sha2::{Sha256, Digest};
+use crate::utils::canonical;
+use crate::utils::lock::LockRecover;
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
 
 // NEU: concurrency (grober globaler Mutex)
 use std::sync::Mutex;
@@ -31,7 +44,8 @@ pub enum Asset {
     LTC,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+/// Orders werden im OR-Set über `order_id` adressiert (f64-Felder sind nicht hashbar).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Order {
     pub order_id: String,
     pub user_id: String,
@@ -66,9 +80,38 @@ impl Order {
         }
     }
 
-    /// Minimaler Signaturcheck:
-    /// Wir hashen (order_id + user_id + amount_sell + price),
-    /// optional weitere Felder, und prüfen mit ed25519_dalek.
+    /// Kanonische Bytes aller Felder außer `signature` (Domain "my_dex/itc/order/v1").
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct ItcOrderSigningView<'a> {
+            order_id: &'a str,
+            user_id: &'a str,
+            asset_sell: &'a Asset,
+            asset_buy: &'a Asset,
+            amount_sell: f64,
+            price: f64,
+            public_key: &'a Option<Vec<u8>>,
+        }
+        canonical::signing_bytes("my_dex/itc/order/v1", &ItcOrderSigningView {
+            order_id: &self.order_id,
+            user_id: &self.user_id,
+            asset_sell: &self.asset_sell,
+            asset_buy: &self.asset_buy,
+            amount_sell: self.amount_sell,
+            price: self.price,
+            public_key: &self.public_key,
+        })
+    }
+
+    /// Signiert die Order mit dem Schlüssel des Besitzers (setzt `public_key`).
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<(), DexError> {
+        self.public_key = Some(keypair.public.to_bytes().to_vec());
+        let bytes = self.signing_bytes()?;
+        self.signature = Some(keypair.sign(&bytes).to_bytes().to_vec());
+        Ok(())
+    }
+
+    /// Ed25519-Prüfung über `signing_bytes`.
     pub fn verify_signature(&self) -> bool {
         let (Some(sig_bytes), Some(pk_bytes)) = (self.signature.as_ref(), self.public_key.as_ref()) else {
             return false;
@@ -80,130 +123,278 @@ impl Order {
             return false;
         };
 
-        let data = format!("{}:{}:{}:{}",
-            self.order_id,
-            self.user_id,
-            self.amount_sell,
-            self.price
-        );
-        let mut hasher = Sha256::new();
-        hasher.update(data.as_bytes());
-        let hashed = hasher.finalize();
-
-        pubkey.verify(&hashed, &signature).is_ok()
+        match self.signing_bytes() {
+            Ok(bytes) => pubkey.verify(&bytes, &signature).is_ok(),
+            Err(_) => false,
+        }
     }
 }
 
 /******************************************************************************
- * EXTENDED INTERVAL TREE CLOCK
+ * INTERVAL TREE CLOCK
  ******************************************************************************/
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ITCInterval {
-    pub node: String,
-    pub start: u64,
-    pub end: u64,
+
+/// Identitäts-Baum: 0 = nichts, 1 = ganzes Intervall, (l, r) = linke/rechte Hälfte.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ItcId {
+    Zero,
+    One,
+    Node(Box<ItcId>, Box<ItcId>),
 }
 
-impl ITCInterval {
-    pub fn overlaps(&self, other: &ITCInterval) -> bool {
-        self.node == other.node &&
-        !(self.end < other.start || other.end < self.start)
+impl ItcId {
+    fn node(l: ItcId, r: ItcId) -> Self {
+        match (&l, &r) {
+            (ItcId::Zero, ItcId::Zero) => ItcId::Zero,
+            (ItcId::One, ItcId::One) => ItcId::One,
+            _ => ItcId::Node(Box::new(l), Box::new(r)),
+        }
+    }
+
+    /// Teilen sich zwei Identitäten einen Teil des Intervalls? Dann würden
+    /// ihre Events kollidieren.
+    pub fn overlaps(&self, other: &ItcId) -> bool {
+        match (self, other) {
+            (ItcId::Zero, _) | (_, ItcId::Zero) => false,
+            (ItcId::One, _) | (_, ItcId::One) => true,
+            (ItcId::Node(l1, r1), ItcId::Node(l2, r2)) => l1.overlaps(l2) || r1.overlaps(r2),
+        }
+    }
+
+    /// Teilt die Identität in zwei disjunkte Hälften.
+    pub fn split(&self) -> (ItcId, ItcId) {
+        match self {
+            ItcId::Zero => (ItcId::Zero, ItcId::Zero),
+            ItcId::One => (
+                ItcId::Node(Box::new(ItcId::One), Box::new(ItcId::Zero)),
+                ItcId::Node(Box::new(ItcId::Zero), Box::new(ItcId::One)),
+            ),
+            ItcId::Node(l, r) => match (l.as_ref(), r.as_ref()) {
+                (ItcId::Zero, r) => {
+                    let (r1, r2) = r.split();
+                    (ItcId::node(ItcId::Zero, r1), ItcId::node(ItcId::Zero, r2))
+                }
+                (l, ItcId::Zero) => {
+                    let (l1, l2) = l.split();
+                    (ItcId::node(l1, ItcId::Zero), ItcId::node(l2, ItcId::Zero))
+                }
+                (l, r) => (ItcId::node(l.clone(), ItcId::Zero), ItcId::node(ItcId::Zero, r.clone())),
+            },
+        }
     }
 
-    pub fn merge_with(&mut self, other: &ITCInterval) -> bool {
-        if self.node == other.node && self.overlaps(other)
-            || self.end+1 == other.start
-            || other.end+1 == self.start
-        {
-            self.start = self.start.min(other.start);
-            self.end   = self.end.max(other.end);
-            return true;
+    /// Vereinigt zwei (disjunkte) Identitäten.
+    pub fn sum(&self, other: &ItcId) -> ItcId {
+        match (self, other) {
+            (ItcId::Zero, i) | (i, ItcId::Zero) => i.clone(),
+            (ItcId::Node(l1, r1), ItcId::Node(l2, r2)) => ItcId::node(l1.sum(l2), r1.sum(r2)),
+            // Überlappende Identitäten sind ein Programmierfehler; 1 absorbiert
+            _ => ItcId::One,
         }
-        false
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ITCVersion {
-    pub intervals: Vec<ITCInterval>,
+/// Event-Baum: Basiswert plus optionale Zuwächse für linke/rechte Hälfte.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ItcEvent {
+    Leaf(u64),
+    Node(u64, Box<ItcEvent>, Box<ItcEvent>),
 }
 
-impl ITCVersion {
-    pub fn new() -> Self {
-        Self { intervals: Vec::new() }
+/// Kosten-Offset, damit `grow` das Aufspalten eines Blatts nur wählt, wenn
+/// kein vorhandener Teilbaum wachsen kann.
+const GROW_SPLIT_COST: u64 = 1 << 20;
+
+impl ItcEvent {
+    fn base(&self) -> u64 {
+        match self {
+            ItcEvent::Leaf(n) | ItcEvent::Node(n, _, _) => *n,
+        }
     }
 
-    /// Suche maxEnd für node => +1
-    pub fn increment(&mut self, node_id: &str) -> ITCInterval {
-        let mut maxend = 0;
-        for iv in &self.intervals {
-            if iv.node == node_id && iv.end > maxend {
-                maxend = iv.end;
+    fn min(&self) -> u64 {
+        match self {
+            ItcEvent::Leaf(n) => *n,
+            ItcEvent::Node(n, l, r) => n + l.min().min(r.min()),
+        }
+    }
+
+    fn max(&self) -> u64 {
+        match self {
+            ItcEvent::Leaf(n) => *n,
+            ItcEvent::Node(n, l, r) => n + l.max().max(r.max()),
+        }
+    }
+
+    fn lift(&self, m: u64) -> ItcEvent {
+        match self {
+            ItcEvent::Leaf(n) => ItcEvent::Leaf(n + m),
+            ItcEvent::Node(n, l, r) => ItcEvent::Node(n + m, l.clone(), r.clone()),
+        }
+    }
+
+    fn sink(&self, m: u64) -> ItcEvent {
+        match self {
+            ItcEvent::Leaf(n) => ItcEvent::Leaf(n - m),
+            ItcEvent::Node(n, l, r) => ItcEvent::Node(n - m, l.clone(), r.clone()),
+        }
+    }
+
+    /// Normalform: gleiche Blätter zusammenfassen, gemeinsames Minimum hochziehen.
+    fn norm(n: u64, l: ItcEvent, r: ItcEvent) -> ItcEvent {
+        if let (ItcEvent::Leaf(a), ItcEvent::Leaf(b)) = (&l, &r) {
+            if a == b {
+                return ItcEvent::Leaf(n + a);
             }
         }
-        let newstart = maxend + 1;
-        let newend   = maxend + 1;
-        let mut newiv = ITCInterval {
-            node: node_id.to_string(),
-            start: newstart,
-            end: newend,
-        };
-        self.merge_interval(newiv.clone());
-        for iv in &self.intervals {
-            if iv.node == node_id && newstart >= iv.start && newend <= iv.end {
-                newiv.start = iv.start;
-                newiv.end   = iv.end;
-                break;
+        let m = l.min().min(r.min());
+        ItcEvent::Node(n + m, Box::new(l.sink(m)), Box::new(r.sink(m)))
+    }
+
+    fn children(&self) -> (u64, ItcEvent, ItcEvent) {
+        match self {
+            ItcEvent::Leaf(n) => (*n, ItcEvent::Leaf(0), ItcEvent::Leaf(0)),
+            ItcEvent::Node(n, l, r) => (*n, (**l).clone(), (**r).clone()),
+        }
+    }
+
+    /// Kleinste gemeinsame obere Schranke.
+    pub fn join(&self, other: &ItcEvent) -> ItcEvent {
+        match (self, other) {
+            (ItcEvent::Leaf(a), ItcEvent::Leaf(b)) => ItcEvent::Leaf(*a.max(b)),
+            _ => {
+                if self.base() > other.base() {
+                    return other.join(self);
+                }
+                let (n1, l1, r1) = self.children();
+                let (n2, l2, r2) = other.children();
+                let d = n2 - n1;
+                ItcEvent::norm(n1, l1.join(&l2.lift(d)), r1.join(&r2.lift(d)))
             }
         }
-        newiv
     }
 
-    fn merge_interval(&mut self, newiv: ITCInterval) {
-        let mut merged = false;
-        for iv in &mut self.intervals {
-            if iv.node == newiv.node && iv.overlaps(&newiv) {
-                iv.merge_with(&newiv);
-                merged = true;
-                self.merge_all_for_node(&iv.node);
-                break;
+    /// self <= other (kausal vor oder gleich).
+    pub fn leq(&self, other: &ItcEvent) -> bool {
+        match (self, other) {
+            (ItcEvent::Leaf(a), _) => *a <= other.base(),
+            (ItcEvent::Node(n1, l1, r1), ItcEvent::Leaf(n2)) => {
+                n1 <= n2 && l1.lift(*n1).leq(other) && r1.lift(*n1).leq(other)
+            }
+            (ItcEvent::Node(n1, l1, r1), ItcEvent::Node(n2, l2, r2)) => {
+                n1 <= n2 && l1.lift(*n1).leq(&l2.lift(*n2)) && r1.lift(*n1).leq(&r2.lift(*n2))
             }
         }
-        if !merged {
-            self.intervals.push(newiv);
+    }
+
+    /// Füllt Teilbäume im eigenen Identitätsbereich bis zum Maximum auf.
+    fn fill(&self, id: &ItcId) -> ItcEvent {
+        match (id, self) {
+            (ItcId::Zero, e) => e.clone(),
+            (ItcId::One, e) => ItcEvent::Leaf(e.max()),
+            (_, ItcEvent::Leaf(n)) => ItcEvent::Leaf(*n),
+            (ItcId::Node(il, ir), ItcEvent::Node(n, el, er)) => match (il.as_ref(), ir.as_ref()) {
+                (ItcId::One, ir) => {
+                    let er2 = er.fill(ir);
+                    let left = ItcEvent::Leaf(el.max().max(er2.min()));
+                    ItcEvent::norm(*n, left, er2)
+                }
+                (il, ItcId::One) => {
+                    let el2 = el.fill(il);
+                    let right = ItcEvent::Leaf(er.max().max(el2.min()));
+                    ItcEvent::norm(*n, el2, right)
+                }
+                (il, ir) => ItcEvent::norm(*n, el.fill(il), er.fill(ir)),
+            },
         }
-        self.merge_all_for_node("");
-    }
-
-    fn merge_all_for_node(&mut self, node_id: &str) {
-        let mut changed = true;
-        while changed {
-            changed = false;
-            'outer: for i in 0..self.intervals.len() {
-                for j in (i+1)..self.intervals.len() {
-                    if self.intervals[i].node == self.intervals[j].node || node_id == "" {
-                        if self.intervals[i].overlaps(&self.intervals[j])
-                            || self.intervals[i].end+1 == self.intervals[j].start
-                            || self.intervals[j].end+1 == self.intervals[i].start
-                        {
-                            let merged_start = self.intervals[i].start.min(self.intervals[j].start);
-                            let merged_end   = self.intervals[i].end.max(self.intervals[j].end);
-                            let node_ = self.intervals[i].node.clone();
-                            self.intervals[i].start = merged_start;
-                            self.intervals[i].end   = merged_end;
-                            self.intervals.remove(j);
-                            changed = true;
-                            break 'outer;
+    }
+
+    /// Minimaler Zuwachs im eigenen Identitätsbereich; liefert (Baum, Kosten).
+    fn grow(&self, id: &ItcId) -> Option<(ItcEvent, u64)> {
+        match (id, self) {
+            (ItcId::Zero, _) => None,
+            (ItcId::One, ItcEvent::Leaf(n)) => Some((ItcEvent::Leaf(n + 1), 0)),
+            (_, ItcEvent::Leaf(n)) => {
+                let (e, c) = ItcEvent::Node(*n, Box::new(ItcEvent::Leaf(0)), Box::new(ItcEvent::Leaf(0))).grow(id)?;
+                Some((e, c + GROW_SPLIT_COST))
+            }
+            (ItcId::One, ItcEvent::Node(..)) => None,
+            (ItcId::Node(il, ir), ItcEvent::Node(n, el, er)) => {
+                let left = el.grow(il);
+                let right = er.grow(ir);
+                match (left, right) {
+                    (Some((el2, cl)), Some((er2, cr))) => {
+                        if cl < cr {
+                            Some((ItcEvent::Node(*n, Box::new(el2), er.clone()), cl + 1))
+                        } else {
+                            Some((ItcEvent::Node(*n, el.clone(), Box::new(er2)), cr + 1))
                         }
                     }
+                    (Some((el2, cl)), None) => Some((ItcEvent::Node(*n, Box::new(el2), er.clone()), cl + 1)),
+                    (None, Some((er2, cr))) => Some((ItcEvent::Node(*n, el.clone(), Box::new(er2)), cr + 1)),
+                    (None, None) => None,
                 }
             }
         }
     }
+}
+
+/// ITC-Stamp einer Replik.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Stamp {
+    pub id: ItcId,
+    pub event: ItcEvent,
+}
+
+impl Stamp {
+    /// Erste Replik eines Netzes: besitzt das ganze Identitätsintervall.
+    pub fn seed() -> Self {
+        Self { id: ItcId::One, event: ItcEvent::Leaf(0) }
+    }
+
+    /// Teilt die Identität; beide Hälften behalten die bisherige Historie.
+    pub fn fork(&self) -> (Stamp, Stamp) {
+        let (a, b) = self.id.split();
+        (Stamp { id: a, event: self.event.clone() }, Stamp { id: b, event: self.event.clone() })
+    }
+
+    /// Vereinigt Identität und Historie (z. B. wenn eine Replik ausscheidet).
+    pub fn join(&self, other: &Stamp) -> Stamp {
+        Stamp { id: self.id.sum(&other.id), event: self.event.join(&other.event) }
+    }
+
+    /// Registriert ein lokales Event. Anonyme Stamps (id 0) können das nicht.
+    pub fn event(&mut self) -> Result<(), DexError> {
+        let filled = self.event.fill(&self.id);
+        if filled != self.event {
+            self.event = filled;
+            return Ok(());
+        }
+        match self.event.grow(&self.id) {
+            Some((e, _)) => {
+                self.event = e;
+                Ok(())
+            }
+            None => Err(DexError::Other("ITC event on anonymous stamp (no identity)".into())),
+        }
+    }
+
+    /// Übernimmt fremde Historie, ohne Identität abzugeben.
+    pub fn observe(&mut self, other: &ItcEvent) {
+        self.event = self.event.join(other);
+    }
 
-    pub fn merge(&mut self, other: &ITCVersion) {
-        for iv in &other.intervals {
-            self.merge_interval(iv.clone());
+    pub fn leq(&self, other: &Stamp) -> bool {
+        self.event.leq(&other.event)
+    }
+
+    /// Kausale Ordnung; None => nebenläufig.
+    pub fn compare(&self, other: &Stamp) -> Option<Ordering> {
+        match (self.leq(other), other.leq(self)) {
+            (true, true) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (false, false) => None,
         }
     }
 }
@@ -211,67 +402,83 @@ impl ITCVersion {
 /******************************************************************************
  * ITC-BASED OR-SET
  ******************************************************************************/
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ITCORDSet {
-    pub adds: HashMap<Order, HashSet<ITCInterval>>,
-    pub removes: HashMap<Order, HashSet<ITCInterval>>,
+    /// order_id => Adds (Tag, Inhalt); jeder Add trägt seinen eigenen Inhalt
+    pub adds: HashMap<String, Vec<(ItcEvent, Order)>>,
+    /// order_id => Remove-Tags (Historie des Entfernenden)
+    pub removes: HashMap<String, Vec<ItcEvent>>,
+}
+
+fn push_tag(tags: &mut Vec<ItcEvent>, tag: ItcEvent) {
+    if !tags.contains(&tag) {
+        tags.push(tag);
+    }
 }
 
 impl ITCORDSet {
     pub fn new() -> Self {
-        Self {
-            adds: HashMap::new(),
-            removes: HashMap::new(),
-        }
+        Self::default()
     }
 
-    pub fn add(&mut self, elem: Order, dot: ITCInterval) {
-        self.adds.entry(elem).or_insert_with(HashSet::new).insert(dot);
+    pub fn add(&mut self, elem: Order, tag: ItcEvent) {
+        let entry = self.adds.entry(elem.order_id.clone()).or_default();
+        if !entry.iter().any(|(t, _)| t == &tag) {
+            entry.push((tag, elem));
+        }
     }
 
-    pub fn remove(&mut self, elem: &Order, dot: ITCInterval) {
-        if let Some(_) = self.adds.get(elem) {
-            self.removes.entry(elem.clone()).or_insert_with(HashSet::new).insert(dot);
+    pub fn remove(&mut self, order_id: &str, tag: ItcEvent) {
+        if self.adds.contains_key(order_id) {
+            push_tag(self.removes.entry(order_id.to_string()).or_default(), tag);
         }
     }
 
-    pub fn lookup(&self, elem: &Order) -> bool {
-        if let Some(adset) = self.adds.get(elem) {
-            let rmset = self.removes.get(elem).unwrap_or(&HashSet::new());
-            for aiv in adset {
-                let covered = rmset.iter().any(|riv| {
-                    riv.node == aiv.node && riv.start <= aiv.start && riv.end >= aiv.end
-                });
-                if !covered {
-                    return true;
-                }
-            }
-            return false;
-        }
-        false
+    /// Adds, die von keinem Remove kausal überdeckt sind.
+    fn live_adds(&self, order_id: &str) -> Vec<&(ItcEvent, Order)> {
+        let Some(adds) = self.adds.get(order_id) else { return Vec::new() };
+        let rm = self.removes.get(order_id);
+        adds.iter()
+            .filter(|(a, _)| !rm.map(|r| r.iter().any(|t| a.leq(t))).unwrap_or(false))
+            .collect()
+    }
+
+    /// Sichtbar, solange ein Add-Tag von keinem Remove kausal überdeckt ist.
+    pub fn lookup(&self, order_id: &str) -> bool {
+        !self.live_adds(order_id).is_empty()
+    }
+
+    /// Inhalt der sichtbaren Order: das kausal jüngste überlebende Add. Bei
+    /// nebenläufigen Adds entscheidet die kleinste Signatur, damit alle
+    /// Repliken unabhängig von der Merge-Reihenfolge dasselbe zeigen.
+    pub fn visible(&self, order_id: &str) -> Option<&Order> {
+        let live = self.live_adds(order_id);
+        live.iter()
+            .filter(|(t, _)| !live.iter().any(|(u, _)| t != u && t.leq(u)))
+            .map(|(_, o)| o)
+            .min_by(|a, b| a.signature.cmp(&b.signature).then_with(|| a.public_key.cmp(&b.public_key)))
     }
 
     pub fn all_visible(&self) -> Vec<Order> {
-        let mut out = Vec::new();
-        for (ord, _) in &self.adds {
-            if self.lookup(ord) {
-                out.push(ord.clone());
-            }
-        }
+        let mut out: Vec<Order> = self
+            .adds
+            .keys()
+            .filter_map(|id| self.visible(id).cloned())
+            .collect();
+        out.sort_by(|a, b| a.order_id.cmp(&b.order_id));
         out
     }
 
     pub fn merge(&mut self, other: &ITCORDSet) {
-        for (ord, setiv) in &other.adds {
-            let localset = self.adds.entry(ord.clone()).or_insert_with(HashSet::new);
-            for iv in setiv {
-                localset.insert(iv.clone());
+        for adds in other.adds.values() {
+            for (t, ord) in adds {
+                self.add(ord.clone(), t.clone());
             }
         }
-        for (ord, setiv) in &other.removes {
-            let localset = self.removes.entry(ord.clone()).or_insert_with(HashSet::new);
-            for iv in setiv {
-                localset.insert(iv.clone());
+        for (id, tags) in &other.removes {
+            let local = self.removes.entry(id.clone()).or_default();
+            for t in tags {
+                push_tag(local, t.clone());
             }
         }
     }
@@ -280,8 +487,6 @@ impl ITCORDSet {
 /******************************************************************************
  * Das finale ITCOrderBook
  ******************************************************************************/
-use std::sync::Mutex; // NEU: concurrency
-use lazy_static::lazy_static; // NEU
 
 // Globaler Mutex => in add_order() & remove_order() => wir sperren
 lazy_static! {
@@ -290,23 +495,38 @@ lazy_static! {
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ITCOrderBook {
-    pub version: ITCVersion,
+    /// ITC-Stamp dieser Replik
+    pub version: Stamp,
     pub orset: ITCORDSet,
 }
 
 impl ITCOrderBook {
+    /// Erste Replik (Seed). Weitere Repliken entstehen über `fork`.
     pub fn new() -> Self {
         Self {
-            version: ITCVersion::new(),
+            version: Stamp::seed(),
             orset: ITCORDSet::new(),
         }
     }
 
+    /// Neue Replik: erhält die Hälfte der Identität und den aktuellen Zustand.
+    pub fn fork(&mut self) -> ITCOrderBook {
+        let (mine, theirs) = self.version.fork();
+        self.version = mine;
+        ITCOrderBook { version: theirs, orset: self.orset.clone() }
+    }
+
+    /// Replik scheidet aus: Identität und Zustand gehen an `heir`.
+    pub fn retire(self, heir: &mut ITCOrderBook) {
+        heir.orset.merge(&self.orset);
+        heir.version = heir.version.join(&self.version);
+    }
+
     /// Neu: Wir prüfen, ob die Order signiert ist. Falls nicht, -> DexError
     /// => wir prüfen negative Werte (z. B. amount_sell, price) => DexError
     /// => concurrency => globaler Mutex
-    pub fn add_order(&mut self, mut order: Order, node_id: &str) -> Result<(), DexError> {
-        let _guard = CRDT_ORDERBOOK_MUTEX.lock().map_err(|_| DexError::Other("CRDT Orderbook mutex poisoned".into()))?;
+    pub fn add_order(&mut self, order: Order) -> Result<(), DexError> {
+        let _guard = CRDT_ORDERBOOK_MUTEX.lock_or_err("CRDT orderbook")?;
 
         // 1) Negative checks
         if order.amount_sell <= 0.0 {
@@ -321,23 +541,22 @@ impl ITCOrderBook {
             return Err(DexError::Other("Ungültige Order-Signatur".into()));
         }
 
-        let iv = self.version.increment(node_id);
-        self.orset.add(order, iv);
+        self.version.event()?;
+        self.orset.add(order, self.version.event.clone());
         Ok(())
     }
 
-    /// Optional: remove_order => concurrency & evtl. Signaturcheck
-    /// => wir sperren => fallback
-    pub fn remove_order(&mut self, order: &Order, node_id: &str) {
-        let _guard = CRDT_ORDERBOOK_MUTEX.lock().expect("CRDT Orderbook mutex poisoned");
-        let iv = self.version.increment(node_id);
-        self.orset.remove(order, iv);
+    /// Entfernt alle bisher beobachteten Adds von `order`.
+    pub fn remove_order(&mut self, order: &Order) -> Result<(), DexError> {
+        let _guard = CRDT_ORDERBOOK_MUTEX.lock_or_err("CRDT orderbook")?;
+        self.version.event()?;
+        self.orset.remove(&order.order_id, self.version.event.clone());
+        Ok(())
     }
 
+    /// Übernimmt Zustand und Historie der anderen Replik; die eigene Identität bleibt.
     pub fn merge(&mut self, other: &ITCOrderBook) {
-        // Lock => optional, in real code => 
-        // CRDT merges sich selbst => 
-        self.version.merge(&other.version);
+        self.version.observe(&other.version.event);
         self.orset.merge(&other.orset);
     }
 
@@ -345,3 +564,134 @@ impl ITCOrderBook {
         self.orset.all_visible()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Keypair;
+
+    fn signed(id: &str, kp: &Keypair) -> Order {
+        signed_at(id, 100.0, kp)
+    }
+
+    fn signed_at(id: &str, price: f64, kp: &Keypair) -> Order {
+        let mut o = Order::new(id, "alice", Asset::BTC, Asset::LTC, 1.0, price);
+        o.sign(kp).unwrap();
+        o
+    }
+
+    #[test]
+    fn test_fork_join_roundtrip() {
+        let seed = Stamp::seed();
+        let (mut a, b) = seed.fork();
+        let (mut b1, mut b2) = b.fork();
+        a.event().unwrap();
+        b1.event().unwrap();
+        b2.event().unwrap();
+        b2.event().unwrap();
+
+        // Alle wieder zusammenführen => volle Identität, Historie dominiert alle
+        let joined = a.join(&b1).join(&b2);
+        assert_eq!(joined.id, ItcId::One);
+        for s in [&a, &b1, &b2] {
+            assert!(s.leq(&joined));
+        }
+        // Normalform: nach fill auf der vollen Identität Blatt mit Maximum
+        let mut j = joined.clone();
+        j.event().unwrap();
+        assert!(matches!(j.event, ItcEvent::Leaf(_)));
+        assert!(joined.leq(&j) && !j.leq(&joined));
+    }
+
+    #[test]
+    fn test_concurrent_events_across_dynamic_replicas() {
+        let mut a = Stamp::seed();
+        a.event().unwrap();
+        let (mut a, mut b) = a.fork();
+        assert_eq!(a.compare(&b), Some(Ordering::Equal));
+
+        a.event().unwrap();
+        b.event().unwrap();
+        assert_eq!(a.compare(&b), None);
+
+        // Neue Replik c aus b; c sieht b's Event, a nicht
+        let (b2, mut c) = b.fork();
+        c.event().unwrap();
+        assert_eq!(b2.compare(&c), Some(Ordering::Less));
+        assert_eq!(a.compare(&c), None);
+
+        // a übernimmt c's Historie => kausal danach
+        a.observe(&c.event);
+        a.event().unwrap();
+        assert_eq!(c.compare(&a), Some(Ordering::Less));
+
+        // c scheidet aus => Identität geht zurück an b2
+        let b3 = b2.join(&c);
+        assert_eq!(b3.id, b.id);
+        assert!(Stamp::seed().fork().0.id != b3.id);
+        let mut anon = Stamp { id: ItcId::Zero, event: b3.event.clone() };
+        assert!(anon.event().is_err());
+    }
+
+    #[test]
+    fn test_orderbook_add_wins_and_membership_churn() {
+        let kp = Keypair::generate(&mut rand_07::rngs::OsRng);
+        let mut a = ITCOrderBook::new();
+        a.add_order(signed("o1", &kp)).unwrap();
+        let mut b = a.fork();
+        let mut c = b.fork();
+
+        // a entfernt o1, c fügt o1 nebenläufig erneut hinzu => Add gewinnt
+        a.remove_order(&signed("o1", &kp)).unwrap();
+        c.add_order(signed("o1", &kp)).unwrap();
+        b.add_order(signed("o2", &kp)).unwrap();
+        a.merge(&c);
+        a.merge(&b);
+        let ids: Vec<String> = a.all_orders().into_iter().map(|o| o.order_id).collect();
+        assert_eq!(ids, vec!["o1", "o2"]);
+
+        // Nach dem Merge gesehenes Remove überdeckt beide Adds
+        a.remove_order(&signed("o1", &kp)).unwrap();
+        assert_eq!(a.all_orders().len(), 1);
+
+        // c scheidet aus, b übernimmt => Identitätsraum wieder zusammen
+        c.retire(&mut b);
+        b.merge(&a);
+        let b_id = b.version.id.clone();
+        a.version = a.version.join(&b.version);
+        assert_eq!(a.version.id, ItcId::One);
+        assert_ne!(b_id, ItcId::One);
+        assert_eq!(b.all_orders(), a.all_orders());
+    }
+
+    #[test]
+    fn test_readd_contents_are_causal_and_merge_order_independent() {
+        let kp = Keypair::generate(&mut rand_07::rngs::OsRng);
+        let mut a = ITCOrderBook::new();
+        let mut b = a.fork();
+
+        // Nebenläufige Adds mit verschiedenem Inhalt => beide Repliken einigen sich
+        a.add_order(signed_at("o1", 100.0, &kp)).unwrap();
+        b.add_order(signed_at("o1", 200.0, &kp)).unwrap();
+        let (a0, b0) = (a.clone(), b.clone());
+        a.merge(&b0);
+        b.merge(&a0);
+        assert_eq!(a.all_orders(), b.all_orders());
+        assert_eq!(a.all_orders().len(), 1);
+
+        // Kausal späteres Add ersetzt den zuerst gesehenen Inhalt
+        a.add_order(signed_at("o1", 300.0, &kp)).unwrap();
+        b.merge(&a);
+        assert_eq!(b.all_orders()[0].price, 300.0);
+        assert_eq!(a.all_orders(), b.all_orders());
+    }
+
+    #[test]
+    fn test_identity_overlap() {
+        let (l, r) = ItcId::One.split();
+        let (l1, l2) = l.split();
+        assert!(!l.overlaps(&r) && !l1.overlaps(&l2) && !l2.overlaps(&r));
+        assert!(ItcId::One.overlaps(&l1) && l.overlaps(&l2));
+        assert!(!ItcId::Zero.overlaps(&ItcId::One));
+    }
+}
@@ -4,6 +4,10 @@ use std::collections::{BTreeMap, VecDeque};
 use crate::dex_logic::orders::{Order, Asset};
 // NEU: Für Sicherheitsfehler
 use crate::error::DexError;
+use crate::utils::canonical;
+use crate::utils::lock::LockRecover;
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use serde::Serialize;
 
 // Für den Mutex-Lock (Concurrency):
 use std::sync::Mutex;
@@ -32,11 +36,41 @@ pub struct LimitOrder {
 }
 
 impl LimitOrder {
-    /// Prüft, ob signiert + Hash korrekt.
-    /// Minimalbeispiel (ähnlich wie in anderen Dateien).
+    /// Kanonische Bytes der signierten Felder (Domain "my_dex/limit/order/v1").
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct LimitOrderSigningView<'a> {
+            order_id: &'a str,
+            user_id: &'a str,
+            side: &'a str,
+            price: f64,
+            quantity: f64,
+            public_key: &'a Option<Vec<u8>>,
+        }
+        let side = match self.side {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        };
+        canonical::signing_bytes("my_dex/limit/order/v1", &LimitOrderSigningView {
+            order_id: &self.order_id,
+            user_id: &self.user_id,
+            side,
+            price: self.price,
+            quantity: self.quantity,
+            public_key: &self.public_key,
+        })
+    }
+
+    /// Setzt `public_key` und signiert `signing_bytes`.
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<(), DexError> {
+        self.public_key = Some(keypair.public.to_bytes().to_vec());
+        let bytes = self.signing_bytes()?;
+        self.signature = Some(keypair.sign(&bytes).to_bytes().to_vec());
+        Ok(())
+    }
+
+    /// Prüft die Ed25519-Signatur über `signing_bytes`.
     pub fn verify_signature(&self) -> bool {
-        use ed25519_dalek::{PublicKey, Signature, Verifier};
-        use sha2::{Sha256, Digest};
 
         // Falls keins von beiden existiert => false
         let (Some(sig_bytes), Some(pk_bytes)) = (self.signature.as_ref(), self.public_key.as_ref()) else {
@@ -49,20 +83,10 @@ impl LimitOrder {
         let Ok(signature) = Signature::from_bytes(sig_bytes) else {
             return false;
         };
-
-        // Baue Hash => z.B. user_id + side + price + quantity + order_id
-        let data = format!("{}:{:?}:{}:{}:{}",
-            self.user_id, 
-            self.side,
-            self.price,
-            self.quantity,
-            self.order_id
-        );
-        let mut hasher = Sha256::new();
-        hasher.update(data.as_bytes());
-        let hashed = hasher.finalize();
-
-        pubkey.verify(&hashed, &signature).is_ok()
+        match self.signing_bytes() {
+            Ok(bytes) => pubkey.verify(&bytes, &signature).is_ok(),
+            Err(_) => false,
+        }
     }
 }
 
@@ -106,7 +130,7 @@ impl LimitOrderBook {
     /// => Zusätzlich globaler Lock
     pub fn insert_limit_order(&mut self, ord: LimitOrder) -> Result<(), DexError> {
         // (2) Lock => verhindert paralleles Einfügen
-        let _guard = ORDERBOOK_MUTEX.lock().map_err(|_| DexError::Other("Orderbook mutex poisoned".into()))?;
+        let _guard = ORDERBOOK_MUTEX.lock_or_err("orderbook")?;
 
         // 1) Negative/Nullwerte abfangen
         if ord.price <= 0.0 || ord.quantity <= 0.0 {
@@ -195,3 +219,30 @@ impl LimitOrderBook {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_covers_side() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[4; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        let kp = Keypair { secret, public };
+        let mut o = LimitOrder {
+            order_id: "l1".into(),
+            side: Side::Buy,
+            price: 100.0,
+            quantity: 0.5,
+            user_id: "alice".into(),
+            signature: None,
+            public_key: None,
+        };
+        o.sign(&kp).unwrap();
+        assert!(o.verify_signature());
+
+        let mut flipped = o.clone();
+        flipped.side = Side::Sell;
+        assert!(!flipped.verify_signature());
+    }
+}
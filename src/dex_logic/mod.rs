@@ -14,3 +14,4 @@ pub mod fuzz_test;
 pub mod gossip; 
 pub mod advanced_crdt_sharding; 
 pub mod itc_crdt_orderbook;
+pub mod circuit_breaker;
@@ -6,16 +6,19 @@
 //
 // NEU (Sicherheitsupdate):
 //  - Signatur-Felder (signature, public_key) in Order
-//  - verify_signature() für kryptographische Authentifizierung
+//  - verify_signature() für kryptographische Authentifizierung, über
+//    kanonisches CBOR (utils::canonical, Domain "my_dex/dex/order/v1")
 //  - is_valid_at(...) für Ablaufprüfung
 //
 // Weiter NEU: Wir fangen negative/Null-Werte ab und geben Err(...) zurück.
 
 use serde::{Serialize, Deserialize};
-use sha2::{Sha256, Digest};
-use ed25519_dalek::{PublicKey, Signature, Verifier};
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
 use anyhow::{Result, anyhow}; // Für die fehlerhafte Konstruktor-Rückgabe
 
+use crate::error::DexError;
+use crate::utils::canonical;
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Asset {
     BTC,
@@ -87,9 +90,40 @@ impl Order {
         })
     }
 
-    /// Prüft, ob die Signatur (falls vorhanden) gültig ist.
-    /// Wir hashen z. B. (order_id + user_id + amount_sell + price + valid_until).
-    /// In echter Anwendung kannst du beliebige oder alle Felder reinnehmen.
+    /// Kanonische Bytes aller Order-Felder außer `signature`.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct DexOrderSigningView<'a> {
+            order_id: &'a str,
+            user_id: &'a str,
+            asset_sell: &'a Asset,
+            asset_buy: &'a Asset,
+            amount_sell: f64,
+            price: f64,
+            valid_until: u64,
+            public_key: &'a Option<Vec<u8>>,
+        }
+        canonical::signing_bytes("my_dex/dex/order/v1", &DexOrderSigningView {
+            order_id: &self.order_id,
+            user_id: &self.user_id,
+            asset_sell: &self.asset_sell,
+            asset_buy: &self.asset_buy,
+            amount_sell: self.amount_sell,
+            price: self.price,
+            valid_until: self.valid_until,
+            public_key: &self.public_key,
+        })
+    }
+
+    /// Setzt `public_key` und signiert `signing_bytes`.
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<(), DexError> {
+        self.public_key = Some(keypair.public.to_bytes().to_vec());
+        let bytes = self.signing_bytes()?;
+        self.signature = Some(keypair.sign(&bytes).to_bytes().to_vec());
+        Ok(())
+    }
+
+    /// Prüft, ob die Signatur (falls vorhanden) über `signing_bytes` gültig ist.
     pub fn verify_signature(&self) -> bool {
         let (Some(sig_bytes), Some(pk_bytes)) = (self.signature.as_ref(), self.public_key.as_ref()) else {
             return false;
@@ -100,20 +134,10 @@ impl Order {
         let Ok(signature) = Signature::from_bytes(sig_bytes) else {
             return false;
         };
-
-        // Erzeuge Hash => Minimale Felder
-        let data = format!("{}:{}:{}:{}:{}",
-            self.order_id,
-            self.user_id,
-            self.amount_sell,
-            self.price,
-            self.valid_until
-        );
-        let mut hasher = Sha256::new();
-        hasher.update(data.as_bytes());
-        let hashed = hasher.finalize();
-
-        pubkey.verify(&hashed, &signature).is_ok()
+        match self.signing_bytes() {
+            Ok(bytes) => pubkey.verify(&bytes, &signature).is_ok(),
+            Err(_) => false,
+        }
     }
 
     /// Gibt zurück, ob die Order zum angegebenen Zeitpunkt noch gültig ist.
@@ -122,3 +146,23 @@ impl Order {
         now < self.valid_until
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_covers_assets() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[3; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        let kp = Keypair { secret, public };
+        let mut o = Order::new("o1", "alice", Asset::BTC, Asset::LTC, 0.1, 100.0, 1_000).unwrap();
+        o.sign(&kp).unwrap();
+        assert!(o.verify_signature());
+
+        let mut swapped = o.clone();
+        swapped.asset_sell = Asset::LTC;
+        swapped.asset_buy = Asset::BTC;
+        assert!(!swapped.verify_signature());
+    }
+}
@@ -8,6 +8,10 @@
 //  3) Automatisches Wieder-Einstellen (Re-Listing) bis zu N (1…3) Mal, falls
 //     am Ende der Zeit noch nicht 100 % verkauft/gekauft wurden.
 //  4) Vorzeitiges Abbrechen (Cancel) durch Käufer oder Verkäufer.
+//  5) Good-After-Time (`good_after`): Order bleibt bis zum Zeitpunkt inaktiv
+//     und liegt erst danach im Buch. Good-Till-Date (`good_till_date`):
+//     absolutes Ablaufdatum zusätzlich zur relativen Laufzeit, auch
+//     Re-Listings laufen nie darüber hinaus.
 //
 // Wir verwenden ein HashMap<order_id, TimeLimitedOrder> in einem Mutex zur Demonstration.
 //
@@ -17,16 +21,41 @@
 //  1) Ein globaler lazy_static! Mutex => schützt alle Methoden (add_order, cancel_order, partial_fill, poll_expirations).
 //  2) Im partial_fill => wir checken is_expired => darf nicht mehr gefüllt werden.
 //
+// Der TimeLimitedOrderManager ist die einzige Stelle, die Ablauf prüft:
+// `check_and_handle_expired` entfernt endgültig abgelaufene Orders aus dem
+// LimitOrderBook der MatchingEngine (vor jedem Matching-Durchlauf).
+//
+// Abgeschlossene Orders (gefüllt, abgebrochen, endgültig abgelaufen) werden
+// sofort aus `orders` entfernt, die Map wächst also nur mit offenen Orders.
+// Ihr Endstatus bleibt in einem begrenzten Status-Log (FIFO, höchstens
+// FINISHED_STATUS_CAPACITY Einträge) abfragbar; dieselbe ID kann solange
+// nicht erneut eingestellt werden.
+// Die MatchingEngine meldet Fills über `record_fill`.
+//
+// Ablauf-Index: ein BTreeSet<(expiry, order_id)> neben der HashMap. Ein
+// Durchlauf nimmt nur die fälligen Einträge vorne aus dem Set (O(log n) je
+// Order) statt alle Orders zu prüfen. Cancel, Fill, Re-Listing und Replace
+// halten den Index konsistent.
+//
+// "Jetzt" liefert die Clock des Managers (`with_clock`, Default Systemuhr);
+// mit einer MockClock lässt sich Ablauf ohne Warten testen.
+//
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, Duration, UNIX_EPOCH};
 use anyhow::{Result, anyhow};
 use tracing::{info, warn, debug, error};
 
+use crate::error::DexError;
+use crate::matching_engine::{LimitOrderBook, OrderData};
+use crate::utils::clock::{system_clock, SharedClock};
+
 // NEU: Für Signaturchecks
-use ed25519_dalek::{PublicKey, Signature, Verifier};
-use sha2::{Sha256, Digest};
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use serde::Serialize;
+
+use crate::utils::canonical;
 
 // NEU: Globaler Mutex
 use lazy_static::lazy_static;
@@ -41,6 +70,17 @@ pub enum OrderSide {
     Sell,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeLimitedStatus {
+    /// Wartet auf `good_after`, noch nicht im Buch
+    Scheduled,
+    Active,
+    Filled,
+    Cancelled,
+    /// Endzeit erreicht und alle Re-Listings aufgebraucht
+    Expired,
+}
+
 #[derive(Debug, Clone)]
 pub struct TimeLimitedOrder {
     pub order_id: String,
@@ -68,6 +108,12 @@ pub struct TimeLimitedOrder {
     /// => Dann kein Re-Listing mehr
     pub cancelled: bool,
     pub fully_filled: bool,
+    pub status: TimeLimitedStatus,
+
+    /// Frühester Aktivierungszeitpunkt (UNIX), None => sofort aktiv
+    pub good_after: Option<u64>,
+    /// Absolutes Ablaufdatum (UNIX), None => nur end_time
+    pub good_till_date: Option<u64>,
 
     // NEU: Signaturfelder
     pub signature: Option<Vec<u8>>,
@@ -84,6 +130,22 @@ impl TimeLimitedOrder {
         price_per_unit: f64,
         duration_secs: u64,
         max_relist: u32,
+    ) -> Result<Self> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        Self::new_at(order_id, user_id, side, quantity, price_per_unit, duration_secs, max_relist, now)
+    }
+
+    /// Wie `new`, mit Startzeit `now` (UNIX) statt der Systemuhr.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_at(
+        order_id: &str,
+        user_id: &str,
+        side: OrderSide,
+        quantity: f64,
+        price_per_unit: f64,
+        duration_secs: u64,
+        max_relist: u32,
+        now: u64,
     ) -> Result<Self> {
         if quantity <= 0.0 {
             return Err(anyhow!("Quantity must be positive"));
@@ -100,7 +162,6 @@ impl TimeLimitedOrder {
         if max_relist < 1 || max_relist > 3 {
             return Err(anyhow!("max_relist must be between 1 and 3"));
         }
-        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
         Ok(TimeLimitedOrder {
             order_id: order_id.to_string(),
             user_id: user_id.to_string(),
@@ -114,6 +175,9 @@ impl TimeLimitedOrder {
             max_relist,
             cancelled: false,
             fully_filled: false,
+            status: TimeLimitedStatus::Active,
+            good_after: None,
+            good_till_date: None,
             signature: None,
             public_key: None,
         })
@@ -148,21 +212,96 @@ impl TimeLimitedOrder {
         Ok(tmp)
     }
 
+    /// Setzt Aktivierungszeit und/oder absolutes Ablaufdatum.
+    /// Mit `good_after` in der Zukunft beginnt die Laufzeit erst bei Aktivierung.
+    pub fn with_schedule(mut self, good_after: Option<u64>, good_till_date: Option<u64>) -> Result<Self> {
+        if let Some(gtd) = good_till_date {
+            if gtd <= self.start_time {
+                return Err(anyhow!("good_till_date {} is not in the future", gtd));
+            }
+            if let Some(ga) = good_after {
+                if gtd <= ga {
+                    return Err(anyhow!("good_till_date {} must be after good_after {}", gtd, ga));
+                }
+            }
+        }
+        if let Some(ga) = good_after {
+            if ga > self.start_time {
+                let dur = self.end_time - self.start_time;
+                self.start_time = ga;
+                self.end_time = ga + dur;
+                self.status = TimeLimitedStatus::Scheduled;
+            }
+        }
+        self.good_after = good_after;
+        self.good_till_date = good_till_date;
+        Ok(self)
+    }
+
+    /// Effektiver Ablaufzeitpunkt: min(end_time, good_till_date)
+    pub fn expiry(&self) -> u64 {
+        match self.good_till_date {
+            Some(gtd) => gtd.min(self.end_time),
+            None => self.end_time,
+        }
+    }
+
+    pub fn is_pending_at(&self, now: u64) -> bool {
+        self.good_after.map(|ga| now < ga).unwrap_or(false)
+    }
+
+    pub fn is_expired_at(&self, now: u64) -> bool {
+        now >= self.expiry()
+    }
+
     pub fn remaining_amount(&self) -> f64 {
         self.quantity - self.filled_amount
     }
 
     pub fn is_expired(&self) -> bool {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH)
-            .unwrap_or_default().as_secs();
-        now >= self.end_time
+        self.is_expired_at(now_secs())
     }
 
     pub fn is_active(&self) -> bool {
         !self.cancelled && !self.fully_filled
     }
 
-    /// Prüft Signatur
+    /// Kanonische Bytes der signierten Felder (Domain "my_dex/time_limited/order/v1").
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct TimeLimitedSigningView<'a> {
+            order_id: &'a str,
+            user_id: &'a str,
+            side: &'a str,
+            quantity: f64,
+            price_per_unit: f64,
+            end_time: u64,
+            public_key: &'a Option<Vec<u8>>,
+        }
+        let side = match self.side {
+            OrderSide::Buy => "buy",
+            OrderSide::Sell => "sell",
+        };
+        canonical::signing_bytes("my_dex/time_limited/order/v1", &TimeLimitedSigningView {
+            order_id: &self.order_id,
+            user_id: &self.user_id,
+            side,
+            quantity: self.quantity,
+            price_per_unit: self.price_per_unit,
+            end_time: self.end_time,
+            public_key: &self.public_key,
+        })
+    }
+
+    /// Setzt `public_key` und signiert `signing_bytes`.
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<(), DexError> {
+        self.public_key = Some(keypair.public.to_bytes().to_vec());
+        let bytes = self.signing_bytes()?;
+        self.signature = Some(keypair.sign(&bytes).to_bytes().to_vec());
+        Ok(())
+    }
+
+    /// Prüft die Ed25519-Signatur über `signing_bytes`.
     pub fn verify_signature(&self) -> bool {
         let (Some(sig_bytes), Some(pk_bytes)) = (self.signature.as_ref(), self.public_key.as_ref()) else {
             return false;
@@ -173,22 +312,16 @@ impl TimeLimitedOrder {
         let Ok(signature) = Signature::from_bytes(sig_bytes) else {
             return false;
         };
-
-        let data = format!(
-            "{}:{}:{}:{}:{}",
-            self.order_id,
-            self.user_id,
-            self.quantity,
-            self.price_per_unit,
-            self.end_time
-        );
-        let mut hasher = Sha256::new();
-        hasher.update(data.as_bytes());
-        let hashed = hasher.finalize();
-        pubkey.verify(&hashed, &signature).is_ok()
+        match self.signing_bytes() {
+            Ok(bytes) => pubkey.verify(&bytes, &signature).is_ok(),
+            Err(_) => false,
+        }
     }
 }
 
+/// So viele abgeschlossene Orders behalten ihren Endstatus (älteste zuerst verdrängt).
+pub const FINISHED_STATUS_CAPACITY: usize = 10_000;
+
 // ------------------------------------------------
 // Eine Manager-Struktur, die die Zeit-limit. Orders verwaltet
 // ------------------------------------------------
@@ -196,42 +329,119 @@ impl TimeLimitedOrder {
 pub struct TimeLimitedOrderBook {
     /// Order-ID -> TimeLimitedOrder
     pub orders: HashMap<String, TimeLimitedOrder>,
+    /// Order-ID -> Buch-Order, die bis `good_after` zurückgehalten wird
+    pub held: HashMap<String, OrderData>,
+    /// (Ablaufzeitpunkt, Order-ID) aller aktiven Orders, früheste zuerst
+    expiry_index: BTreeSet<(u64, String)>,
+    /// Order-ID -> im Index eingetragener Ablaufzeitpunkt
+    indexed_at: HashMap<String, u64>,
+    /// Endstatus abgeschlossener Orders (begrenzt, siehe FINISHED_STATUS_CAPACITY)
+    finished: HashMap<String, TimeLimitedStatus>,
+    /// Reihenfolge der Einträge in `finished`, älteste vorne
+    finished_order: VecDeque<String>,
 }
 
 impl TimeLimitedOrderBook {
     pub fn new() -> Self {
-        Self {
-            orders: HashMap::new(),
+        Self::default()
+    }
+
+    fn index(&mut self, order_id: &str, expiry: u64) {
+        self.unindex(order_id);
+        self.expiry_index.insert((expiry, order_id.to_string()));
+        self.indexed_at.insert(order_id.to_string(), expiry);
+    }
+
+    fn unindex(&mut self, order_id: &str) {
+        if let Some(at) = self.indexed_at.remove(order_id) {
+            self.expiry_index.remove(&(at, order_id.to_string()));
+        }
+    }
+
+    /// Trägt eine Order nach direkter Änderung in `orders` neu in den
+    /// Ablauf-Index ein (inaktive Orders fallen heraus).
+    pub fn reindex(&mut self, order_id: &str) {
+        match self.orders.get(order_id).filter(|o| o.is_active()).map(|o| o.expiry()) {
+            Some(expiry) => self.index(order_id, expiry),
+            None => self.unindex(order_id),
         }
     }
 
+    /// Anzahl der Orders, deren Ablauf noch aussteht.
+    pub fn pending_expiries(&self) -> usize {
+        self.expiry_index.len()
+    }
+
+    /// Entfernt eine abgeschlossene Order samt Index und zurückgehaltener
+    /// Buch-Order und merkt sich ihren Endstatus.
+    fn remove(&mut self, order_id: &str, status: TimeLimitedStatus) -> Option<TimeLimitedOrder> {
+        self.unindex(order_id);
+        self.held.remove(order_id);
+        let removed = self.orders.remove(order_id);
+        if removed.is_some() && self.finished.insert(order_id.to_string(), status).is_none() {
+            self.finished_order.push_back(order_id.to_string());
+            while self.finished_order.len() > FINISHED_STATUS_CAPACITY {
+                if let Some(old) = self.finished_order.pop_front() {
+                    self.finished.remove(&old);
+                }
+            }
+        }
+        removed
+    }
+
+    /// Status einer offenen oder (noch im Status-Log) abgeschlossenen Order.
+    pub fn status(&self, order_id: &str) -> Option<TimeLimitedStatus> {
+        self.orders
+            .get(order_id)
+            .map(|o| o.status)
+            .or_else(|| self.finished.get(order_id).copied())
+    }
+
     /// Fügt eine neue Order ein.
     pub fn add_order(&mut self, order: TimeLimitedOrder) -> Result<()> {
-        if self.orders.contains_key(&order.order_id) {
+        if self.orders.contains_key(&order.order_id) || self.finished.contains_key(&order.order_id) {
             return Err(anyhow!("OrderID '{}' already exists", order.order_id));
         }
-        self.orders.insert(order.order_id.clone(), order);
+        let id = order.order_id.clone();
+        self.orders.insert(id.clone(), order);
+        self.reindex(&id);
+        Ok(())
+    }
+
+    /// Cancel/Replace: ersetzt eine aktive Order gleicher ID (neue Menge,
+    /// neuer Preis, neue Laufzeit). Der Ablauf-Index folgt der neuen Order.
+    pub fn replace_order(&mut self, order: TimeLimitedOrder) -> Result<()> {
+        let old = self.orders.get(&order.order_id)
+            .ok_or_else(|| anyhow!("OrderID '{}' not found", order.order_id))?;
+        if !old.is_active() {
+            return Err(anyhow!("OrderID '{}' is no longer active", order.order_id));
+        }
+        let id = order.order_id.clone();
+        self.orders.insert(id.clone(), order);
+        self.reindex(&id);
         Ok(())
     }
 
-    /// Vorzeitiges Cancel
+    /// Vorzeitiges Cancel; die Order wird entfernt.
     pub fn cancel_order(&mut self, order_id: &str) -> Result<()> {
-        let ord = self.orders.get_mut(order_id)
-            .ok_or_else(|| anyhow!("OrderID '{}' not found", order_id))?;
-        if ord.cancelled || ord.fully_filled {
-            return Ok(());
+        if !self.orders.contains_key(order_id) {
+            return Err(anyhow!("OrderID '{}' not found", order_id));
         }
-        ord.cancelled = true;
+        self.remove(order_id, TimeLimitedStatus::Cancelled);
         Ok(())
     }
 
     /// Partial Fill
     pub fn partial_fill_order(&mut self, order_id: &str, fill_amt: f64) -> Result<f64> {
+        self.partial_fill_order_at(order_id, fill_amt, now_secs())
+    }
+
+    pub fn partial_fill_order_at(&mut self, order_id: &str, fill_amt: f64, now: u64) -> Result<f64> {
         let ord = self.orders.get_mut(order_id)
             .ok_or_else(|| anyhow!("OrderID '{}' not found", order_id))?;
 
-        // NEU: Wir prüfen, ob Order abgelaufen oder inaktiv
-        if ord.is_expired() || !ord.is_active() {
+        // NEU: Wir prüfen, ob Order abgelaufen, noch nicht aktiv oder inaktiv
+        if ord.is_expired_at(now) || ord.is_pending_at(now) || !ord.is_active() {
             return Err(anyhow!("Order not active or expired"));
         }
         if fill_amt <= 0.0 {
@@ -239,46 +449,106 @@ impl TimeLimitedOrderBook {
         }
         let remain = ord.remaining_amount();
         if remain <= 0.0 {
-            ord.fully_filled = true;
+            self.remove(order_id, TimeLimitedStatus::Filled);
             return Err(anyhow!("Already fully filled or no remain"));
         }
         let actual_fill = if fill_amt > remain { remain } else { fill_amt };
         ord.filled_amount += actual_fill;
         if ord.filled_amount >= ord.quantity {
-            ord.fully_filled = true;
+            // Vollständig gefüllt => nichts mehr zu verwalten
+            self.remove(order_id, TimeLimitedStatus::Filled);
         }
         Ok(actual_fill)
     }
 
-    /// Check + neu einstellen
-    pub fn check_and_handle_expirations(&mut self) {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH)
-            .unwrap_or_default().as_secs();
+    /// Check + neu einstellen.
+    /// Rückgabe: IDs der Orders, die in diesem Durchlauf endgültig abgelaufen sind.
+    pub fn check_and_handle_expirations(&mut self) -> Vec<String> {
+        self.check_and_handle_expirations_at(now_secs())
+    }
 
-        for (_oid, ord) in &mut self.orders {
-            if ord.is_active() && ord.is_expired() {
-                let remain = ord.remaining_amount();
-                if remain <= 0.0 {
-                    ord.fully_filled = true;
-                    continue;
-                }
-                if ord.auto_relist_count < ord.max_relist {
-                    let old_dur = ord.end_time - ord.start_time;
-                    ord.auto_relist_count += 1;
-                    ord.start_time = now;
-                    ord.end_time = now + old_dur;
-                } else {
-                    ord.cancelled = true;
+    /// Nimmt alle Index-Einträge mit Ablauf <= `now` heraus.
+    fn pop_due(&mut self, now: u64) -> Vec<String> {
+        let mut due = Vec::new();
+        while let Some((at, _)) = self.expiry_index.first() {
+            if *at > now {
+                break;
+            }
+            let (_, oid) = self.expiry_index.pop_first().unwrap();
+            self.indexed_at.remove(&oid);
+            due.push(oid);
+        }
+        due
+    }
+
+    pub fn check_and_handle_expirations_at(&mut self, now: u64) -> Vec<String> {
+        let mut expired = Vec::new();
+        let mut relisted = Vec::new();
+        let mut filled = Vec::new();
+        for oid in self.pop_due(now) {
+            let Some(ord) = self.orders.get_mut(&oid) else { continue };
+            if !ord.is_active() || !ord.is_expired_at(now) {
+                continue;
+            }
+            let remain = ord.remaining_amount();
+            if remain <= 0.0 {
+                filled.push(oid);
+                continue;
+            }
+            let gtd_reached = ord.good_till_date.map(|gtd| now >= gtd).unwrap_or(false);
+            if ord.auto_relist_count < ord.max_relist && !gtd_reached {
+                let old_dur = ord.end_time - ord.start_time;
+                ord.auto_relist_count += 1;
+                ord.start_time = now;
+                ord.end_time = now + old_dur;
+                relisted.push((oid, ord.expiry()));
+            } else {
+                expired.push(oid);
+            }
+        }
+        for (oid, expiry) in relisted {
+            self.index(&oid, expiry);
+        }
+        for oid in &filled {
+            self.remove(oid, TimeLimitedStatus::Filled);
+        }
+        for oid in &expired {
+            self.remove(oid, TimeLimitedStatus::Expired);
+        }
+        expired.sort();
+        expired
+    }
+
+    /// Gibt zurückgehaltene Buch-Orders frei, deren `good_after` erreicht ist.
+    /// Abgebrochene Scheduled-Orders werden dabei verworfen.
+    pub fn activate_due_at(&mut self, now: u64) -> Vec<OrderData> {
+        let mut due: Vec<String> = Vec::new();
+        let mut dropped: Vec<String> = Vec::new();
+        for id in self.held.keys() {
+            match self.orders.get_mut(id) {
+                Some(ord) if ord.status == TimeLimitedStatus::Scheduled => {
+                    if !ord.is_pending_at(now) {
+                        ord.status = TimeLimitedStatus::Active;
+                        due.push(id.clone());
+                    }
                 }
+                _ => dropped.push(id.clone()),
             }
         }
+        for id in dropped {
+            self.held.remove(&id);
+        }
+        due.sort();
+        due.into_iter().filter_map(|id| self.held.remove(&id)).collect()
     }
 
     pub fn list_active_orders(&self) -> Vec<TimeLimitedOrder> {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH)
-            .unwrap_or_default().as_secs();
+        self.list_active_orders_at(now_secs())
+    }
+
+    pub fn list_active_orders_at(&self, now: u64) -> Vec<TimeLimitedOrder> {
         self.orders.values()
-            .filter(|o| o.is_active() && o.end_time > now)
+            .filter(|o| o.is_active() && !o.is_pending_at(now) && o.expiry() > now)
             .cloned()
             .collect()
     }
@@ -286,8 +556,6 @@ impl TimeLimitedOrderBook {
 
 // ---------------------------------------------------------------
 // Globaler Mutex => einfache concurrency
-use lazy_static::lazy_static;
-
 lazy_static! {
     static ref TIMELIMITED_MUTEX: Mutex<()> = Mutex::new(());
 }
@@ -295,15 +563,27 @@ lazy_static! {
 #[derive(Clone)]
 pub struct TimeLimitedOrderManager {
     pub orderbook: Arc<Mutex<TimeLimitedOrderBook>>,
+    clock: SharedClock,
 }
 
 impl TimeLimitedOrderManager {
     pub fn new() -> Self {
         Self {
             orderbook: Arc::new(Mutex::new(TimeLimitedOrderBook::new())),
+            clock: system_clock(),
         }
     }
 
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Aktuelle Zeit (UNIX-Sekunden) laut Clock des Managers.
+    pub fn now(&self) -> u64 {
+        self.clock.unix_secs()
+    }
+
     pub fn place_time_limited_order(
         &self,
         order_id: &str,
@@ -315,20 +595,41 @@ impl TimeLimitedOrderManager {
         max_relist: u32,
     ) -> Result<()> {
         let _guard = TIMELIMITED_MUTEX.lock().unwrap();
-        let order = TimeLimitedOrder::new(
+        let order = TimeLimitedOrder::new_at(
             order_id, 
             user_id, 
             side, 
             quantity, 
             price_per_unit,
             duration_secs,
-            max_relist
+            max_relist,
+            self.now(),
         )?;
         let mut ob = self.orderbook.lock().unwrap();
         ob.add_order(order)?;
         Ok(())
     }
 
+    /// Fügt eine fertige Order ein; `held` ist die Buch-Order, die bis
+    /// `good_after` zurückgehalten wird.
+    pub fn add_order(&self, order: TimeLimitedOrder, held: Option<OrderData>) -> Result<()> {
+        let _guard = TIMELIMITED_MUTEX.lock().unwrap();
+        let mut ob = self.orderbook.lock().unwrap();
+        let id = order.order_id.clone();
+        ob.add_order(order)?;
+        if let Some(h) = held {
+            ob.held.insert(id, h);
+        }
+        Ok(())
+    }
+
+    /// Cancel/Replace einer aktiven Order (gleiche Order-ID).
+    pub fn replace(&self, order: TimeLimitedOrder) -> Result<()> {
+        let _guard = TIMELIMITED_MUTEX.lock().unwrap();
+        let mut ob = self.orderbook.lock().unwrap();
+        ob.replace_order(order)
+    }
+
     pub fn cancel(&self, order_id: &str) -> Result<()> {
         let _guard = TIMELIMITED_MUTEX.lock().unwrap();
         let mut ob = self.orderbook.lock().unwrap();
@@ -339,20 +640,284 @@ impl TimeLimitedOrderManager {
     pub fn partial_fill(&self, order_id: &str, fill_amt: f64) -> Result<f64> {
         let _guard = TIMELIMITED_MUTEX.lock().unwrap();
         let mut ob = self.orderbook.lock().unwrap();
-        let actual_fill = ob.partial_fill_order(order_id, fill_amt)?;
+        let actual_fill = ob.partial_fill_order_at(order_id, fill_amt, self.now())?;
         Ok(actual_fill)
     }
 
-    pub fn poll_expirations(&self) {
+    /// Fill aus dem Matching. Orders, die nicht time-limited sind, werden
+    /// ignoriert; vollständig gefüllte fallen aus der Verwaltung.
+    pub fn record_fill(&self, order_id: &str, fill_amt: f64) {
+        let _guard = TIMELIMITED_MUTEX.lock().unwrap();
+        let mut ob = self.orderbook.lock().unwrap();
+        if !ob.orders.contains_key(order_id) {
+            return;
+        }
+        if let Err(e) = ob.partial_fill_order_at(order_id, fill_amt, self.now()) {
+            debug!("Time-Limited Order {} => Fill nicht verbucht: {}", order_id, e);
+        }
+    }
+
+    /// Anzahl verwalteter (offener) Orders.
+    pub fn tracked_orders(&self) -> usize {
+        self.orderbook.lock().unwrap().orders.len()
+    }
+
+    pub fn poll_expirations(&self) -> Vec<String> {
         let _guard = TIMELIMITED_MUTEX.lock().unwrap();
         let mut ob = self.orderbook.lock().unwrap();
-        ob.check_and_handle_expirations();
+        ob.check_and_handle_expirations_at(self.now())
+    }
+
+    /// Buch-Orders, deren Aktivierungszeit erreicht ist (Status => Active).
+    pub fn activate_due_orders(&self) -> Vec<OrderData> {
+        let _guard = TIMELIMITED_MUTEX.lock().unwrap();
+        let mut ob = self.orderbook.lock().unwrap();
+        ob.activate_due_at(self.now())
+    }
+
+    /// Markiert abgelaufene Orders als `Expired` und nimmt sie aus `book`.
+    /// Re-gelistete Orders bleiben im Buch. Rückgabe: die entfernten Orders.
+    pub fn check_and_handle_expired(&self, book: &mut LimitOrderBook) -> Result<Vec<OrderData>, DexError> {
+        let expired = self.poll_expirations();
+        if expired.is_empty() {
+            return Ok(Vec::new());
+        }
+        let removed = book.remove_orders(&expired);
+        for o in &removed {
+            info!("Time-Limited Order {} abgelaufen => aus dem Buch entfernt (offen={})", o.id, o.remaining());
+        }
+        Ok(removed)
+    }
+
+    pub fn status(&self, order_id: &str) -> Option<TimeLimitedStatus> {
+        let _guard = TIMELIMITED_MUTEX.lock().unwrap();
+        let ob = self.orderbook.lock().unwrap();
+        ob.status(order_id)
     }
 
     pub fn get_active_orders(&self) -> Vec<TimeLimitedOrder> {
         // read => lock
         let _guard = TIMELIMITED_MUTEX.lock().unwrap();
         let ob = self.orderbook.lock().unwrap();
-        ob.list_active_orders()
+        ob.list_active_orders_at(self.now())
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(id: &str) -> TimeLimitedOrder {
+        TimeLimitedOrder::new(id, "alice", OrderSide::Sell, 1.0, 100.0, 3600, 1).unwrap()
+    }
+
+    #[test]
+    fn test_signature_over_canonical_bytes() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        let kp = Keypair { secret, public };
+        let mut o = order("signed");
+        assert!(!o.verify_signature());
+        o.sign(&kp).unwrap();
+        assert!(o.verify_signature());
+
+        let mut flipped = o.clone();
+        flipped.side = OrderSide::Buy;
+        assert!(!flipped.verify_signature());
+        let mut later = o.clone();
+        later.end_time += 1;
+        assert!(!later.verify_signature());
+    }
+
+    #[test]
+    fn test_expires_exactly_at_gtd() {
+        let o = order("gtd");
+        let gtd = o.start_time + 600;
+        let o = o.with_schedule(None, Some(gtd)).unwrap();
+        assert_eq!(o.expiry(), gtd);
+
+        let mut book = TimeLimitedOrderBook::new();
+        book.add_order(o).unwrap();
+        assert!(book.check_and_handle_expirations_at(gtd - 1).is_empty());
+        // Re-Listing ist noch frei, aber das GTD gewinnt
+        assert_eq!(book.check_and_handle_expirations_at(gtd), vec!["gtd".to_string()]);
+        assert!(!book.orders.contains_key("gtd"));
+        assert_eq!(book.pending_expiries(), 0);
+    }
+
+    #[test]
+    fn test_good_after_holds_order_until_activation() {
+        let o = order("gat");
+        let ga = o.start_time + 300;
+        let o = o.with_schedule(Some(ga), None).unwrap();
+        assert_eq!(o.status, TimeLimitedStatus::Scheduled);
+        assert_eq!(o.end_time, ga + 3600);
+
+        let held = crate::matching_engine::OrderData::new(
+            "gat", "alice", crate::matching_engine::OrderSide::Sell,
+            crate::matching_engine::OrderType::Limit(100.0), 1.0, 0,
+        );
+        let mut book = TimeLimitedOrderBook::new();
+        book.add_order(o).unwrap();
+        book.held.insert("gat".into(), held);
+
+        assert!(book.activate_due_at(ga - 1).is_empty());
+        assert!(book.check_and_handle_expirations_at(ga - 1).is_empty());
+        let active = book.activate_due_at(ga);
+        assert_eq!(active.len(), 1);
+        assert_eq!(book.orders["gat"].status, TimeLimitedStatus::Active);
+        assert!(book.held.is_empty());
+    }
+
+    #[test]
+    fn test_scheduled_order_pruned_with_held_order() {
+        let held = |id: &str| crate::matching_engine::OrderData::new(
+            id, "alice", crate::matching_engine::OrderSide::Sell,
+            crate::matching_engine::OrderType::Limit(100.0), 1.0, 0,
+        );
+        let o = order("gtd_first");
+        let now = o.start_time;
+        let mut book = TimeLimitedOrderBook::new();
+        // GTD vor der Aktivierungszeit erreicht => nie aktiv geworden
+        book.add_order(o.with_schedule(Some(now + 300), Some(now + 600)).unwrap()).unwrap();
+        book.held.insert("gtd_first".into(), held("gtd_first"));
+        book.add_order(order("cancel_me").with_schedule(Some(now + 300), None).unwrap()).unwrap();
+        book.held.insert("cancel_me".into(), held("cancel_me"));
+
+        book.cancel_order("cancel_me").unwrap();
+        assert!(!book.held.contains_key("cancel_me"));
+        assert_eq!(book.check_and_handle_expirations_at(now + 600), vec!["gtd_first".to_string()]);
+        assert!(book.orders.is_empty());
+        assert!(book.held.is_empty());
+        assert!(book.activate_due_at(now + 600).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_schedule_rejected() {
+        let o = order("bad");
+        let now = o.start_time;
+        assert!(order("bad").with_schedule(None, Some(now)).is_err());
+        assert!(o.with_schedule(Some(now + 100), Some(now + 50)).is_err());
+    }
+
+    #[test]
+    fn test_expiry_index_touches_only_due_orders() {
+        let mut book = TimeLimitedOrderBook::new();
+        let base = order("x").start_time;
+        for i in 0..10_000 {
+            let mut o = order(&format!("o{:05}", i));
+            if i % 2_000 == 0 {
+                o.end_time = base + 120; // 5 Orders laufen früh ab
+            }
+            book.add_order(o).unwrap();
+        }
+        assert_eq!(book.pending_expiries(), 10_000);
+        assert!(book.pop_due(base + 119).is_empty());
+
+        let due = book.pop_due(base + 120);
+        assert_eq!(due, vec!["o00000", "o02000", "o04000", "o06000", "o08000"]);
+        assert_eq!(book.pending_expiries(), 9_995);
+        for id in &due {
+            book.reindex(id);
+        }
+
+        // Erster Ablauf => Re-Listing (wieder im Index), zweiter => Expired
+        assert!(book.check_and_handle_expirations_at(base + 120).is_empty());
+        assert_eq!(book.orders["o02000"].auto_relist_count, 1);
+        assert_eq!(book.orders["o00001"].auto_relist_count, 0);
+        assert_eq!(book.pending_expiries(), 10_000);
+        let expired = book.check_and_handle_expirations_at(base + 240);
+        assert_eq!(expired.len(), 5);
+        assert_eq!(book.pending_expiries(), 9_995);
+    }
+
+    #[test]
+    fn test_expiry_index_follows_cancel_and_replace() {
+        let mut book = TimeLimitedOrderBook::new();
+        let a = order("a");
+        let t = a.end_time;
+        book.add_order(a).unwrap();
+        book.add_order(order("b")).unwrap();
+
+        book.cancel_order("a").unwrap();
+        assert_eq!(book.pending_expiries(), 1);
+        assert!(!book.orders.contains_key("a"));
+        assert!(book.replace_order(order("a")).is_err(), "cancelled order can't be replaced");
+
+        // Replace mit längerer Laufzeit => alter Ablaufzeitpunkt greift nicht mehr
+        let longer = TimeLimitedOrder::new("b", "alice", OrderSide::Sell, 2.0, 101.0, 7200, 1).unwrap();
+        book.replace_order(longer).unwrap();
+        assert_eq!(book.pending_expiries(), 1);
+        assert!(book.pop_due(t).is_empty());
+        assert_eq!(book.pop_due(t + 3600), vec!["b".to_string()]);
+    }
+
+    #[test]
+    fn test_mock_clock_expires_order_without_waiting() {
+        use crate::utils::clock::MockClock;
+
+        let clock = MockClock::new(1_700_000_000);
+        let manager = TimeLimitedOrderManager::new().with_clock(clock.shared());
+        manager.place_time_limited_order("mc", "alice", OrderSide::Sell, 1.0, 100.0, 3600, 1).unwrap();
+        assert_eq!(manager.get_active_orders().len(), 1);
+
+        clock.advance(Duration::from_secs(3599));
+        assert!(manager.poll_expirations().is_empty());
+        // Ablauf => Re-Listing, zweiter Ablauf => endgültig Expired
+        clock.advance(Duration::from_secs(1));
+        assert!(manager.poll_expirations().is_empty());
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(manager.poll_expirations(), vec!["mc".to_string()]);
+        assert_eq!(manager.status("mc"), Some(TimeLimitedStatus::Expired));
+        assert!(manager.get_active_orders().is_empty());
+        assert!(manager.partial_fill("mc", 0.5).is_err());
+    }
+
+    #[test]
+    fn test_filled_and_expired_orders_are_pruned() {
+        let mut book = TimeLimitedOrderBook::new();
+        let filled = order("filled");
+        let now = filled.start_time;
+        book.add_order(filled).unwrap();
+        book.add_order(order("part")).unwrap();
+        book.add_order(order("gone")).unwrap();
+
+        assert_eq!(book.partial_fill_order_at("filled", 0.4, now).unwrap(), 0.4);
+        assert!(book.orders.contains_key("filled"));
+        assert_eq!(book.partial_fill_order_at("filled", 5.0, now).unwrap(), 0.6);
+        assert!(!book.orders.contains_key("filled"));
+        book.partial_fill_order_at("part", 0.5, now).unwrap();
+
+        // Zwei Abläufe (ein Re-Listing) => "part" und "gone" verschwinden
+        book.check_and_handle_expirations_at(now + 3600);
+        assert_eq!(book.orders.len(), 2);
+        let expired = book.check_and_handle_expirations_at(now + 7200);
+        assert_eq!(expired, vec!["gone".to_string(), "part".to_string()]);
+        assert!(book.orders.is_empty());
+        assert_eq!(book.pending_expiries(), 0);
+
+        // Endstatus bleibt abfragbar, die ID ist verbraucht
+        assert_eq!(book.status("filled"), Some(TimeLimitedStatus::Filled));
+        assert_eq!(book.status("gone"), Some(TimeLimitedStatus::Expired));
+        assert!(book.add_order(order("gone")).is_err());
+    }
+
+    #[test]
+    fn test_finished_status_log_is_bounded() {
+        let mut book = TimeLimitedOrderBook::new();
+        for i in 0..FINISHED_STATUS_CAPACITY + 5 {
+            let id = format!("o{}", i);
+            book.add_order(order(&id)).unwrap();
+            book.cancel_order(&id).unwrap();
+        }
+        assert_eq!(book.finished.len(), FINISHED_STATUS_CAPACITY);
+        assert_eq!(book.status("o0"), None);
+        assert_eq!(book.status("o5"), Some(TimeLimitedStatus::Cancelled));
+        let last = format!("o{}", FINISHED_STATUS_CAPACITY + 4);
+        assert_eq!(book.status(&last), Some(TimeLimitedStatus::Cancelled));
     }
 }
@@ -54,9 +54,212 @@ pub enum DexError {
     CannotDeleteNonEmptyAccount(String),
 
     #[error("Account {0} is paused and cannot perform new trades")]
-    AccountIsPaused(String),
+    AccountPaused(String),
+
+    // Signatur (Order, Nachricht, Zertifikat) fehlt oder ist ungültig
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    // Falsches Passwort oder ungültiger 2FA-/Recovery-Code
+    #[error("Invalid credentials: {0}")]
+    InvalidCredentials(String),
+
+    // Freies (bzw. gesperrtes) Guthaben reicht nicht
+    #[error("Insufficient {asset} balance for {user}")]
+    InsufficientBalance { user: String, asset: String },
+
+    // Rate-Limit (IP, User, Subnetz) überschritten
+    #[error("Rate limit exceeded for {0}")]
+    RateLimited(String),
+
+    // Settlement nach einem Match fehlgeschlagen (Validierung, Chain, RPC)
+    #[error("Settlement failed: {0}")]
+    SettlementFailed(String),
+
+    // Fatal: DB wurde von einem neueren Binary geschrieben
+    #[error("On-disk schema version {on_disk} is newer than supported version {supported}; upgrade the node binary")]
+    SchemaVersionTooNew { on_disk: u32, supported: u32 },
+
+    // Compliance: Adresse oder Jurisdiktion steht auf der Sanktionsliste
+    #[error("Sanctioned party: {0}")]
+    SanctionedParty(String),
+
+    // Kill-Switch: Markt wurde per Committee-Beschluss angehalten
+    #[error("Market halted: {0}")]
+    MarketHalted(String),
+
+    // Zeitstempel (oder lokale Uhr) weicht zu weit von der NTP-Zeit ab
+    #[error("Clock skew of {skew_ms}ms exceeds allowed {max_ms}ms")]
+    ClockSkew { skew_ms: i64, max_ms: u64 },
+
+    // Order-Book nach dem Matching inkonsistent (gekreuzt, negative Restmenge, ...)
+    #[error("Order book invariant violated: {0}")]
+    InvariantViolation(String),
+
+    // Block verletzt Limits (Größe, Anzahl Tx) oder ist inkonsistent
+    #[error("Invalid block: {0}")]
+    InvalidBlock(String),
+
+    // Einzelne Transaktion ungültig (Betrag, Deckung, Signatur)
+    #[error("Invalid transaction {tx_id}: {reason}")]
+    InvalidTransaction { tx_id: u32, reason: String },
+
+    // Follower-Node: Operation verändert Trading-State und ist nicht erlaubt
+    #[error("Node is a read-only follower, {0} is not allowed")]
+    ReadOnlyNode(String),
+
+    // Order mit bereits gesehenem (user_id, nonce) => Replay
+    #[error("Duplicate order nonce {nonce} for {user_id}")]
+    DuplicateOrder { user_id: String, nonce: u64 },
+
+    // Nonce nicht größer als die höchste bisher akzeptierte
+    #[error("Stale order nonce {nonce} for {user_id}, must be > {highest}")]
+    StaleNonce { user_id: String, nonce: u64, highest: u64 },
+
+    // Buchungsjournal ergibt nicht die gespeicherte Wallet-Balance
+    #[error("Ledger mismatch for wallet {wallet_id} ({kind}): ledger {ledger}, stored {stored}")]
+    LedgerMismatch { wallet_id: String, kind: String, ledger: f64, stored: f64 },
+
+    // Übertragener Shard ergibt nicht den Checkpoint der Quelle
+    #[error("Checkpoint mismatch for shard {shard_id}: expected {expected}, got {actual}")]
+    CheckpointMismatch { shard_id: u32, expected: String, actual: String },
+
+    // Obergrenze ruhender Orders (je User oder Markt) erreicht
+    #[error("Order book cap reached in {market} ({scope}): limit {limit}")]
+    BookCapExceeded { market: String, scope: String, limit: usize },
+
+    // Order unter der Mindestmenge des Marktes (Dust)
+    #[error("Order too small in {market}: quantity {quantity} < min {min_quantity}")]
+    OrderTooSmall { market: String, quantity: f64, min_quantity: f64 },
+
+    // Gegenwert der Order über dem Markt-Limit
+    #[error("Order too large in {market}: notional {notional} > max {max_notional}")]
+    OrderTooLarge { market: String, notional: f64, max_notional: f64 },
+
+    // Mutex nach Panic eines anderen Tasks poisoned
+    #[error("Lock poisoned: {0}")]
+    LockPoisoned(String),
+
+    // NodeConfig-Feld mit unzulässigem Wert
+    #[error("Invalid config field `{field}`: {reason}")]
+    InvalidConfig { field: String, reason: String },
+
+    // Authentifiziert, aber nicht Eigentümer der Ressource (fremde Order, fremder Account)
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    // Nutzer vom Watchtower/Netzwerk gesperrt
+    #[error("User {0} is banned")]
+    UserBanned(String),
 
     // Sammel-Fehler
     #[error("Other error: {0}")]
     Other(String),
 }
+
+impl DexError {
+    /// Stabiler, maschinenlesbarer Code für REST-Clients.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DexError::DatabaseError(_) => "database_error",
+            DexError::PeerNotFound { .. } => "peer_not_found",
+            DexError::OrderNotFound { .. } => "order_not_found",
+            DexError::NetworkPartition => "network_partition",
+            DexError::SwapTimeout => "swap_timeout",
+            DexError::PartialFillError { .. } => "partial_fill_error",
+            DexError::AccountAlreadyExists(_) => "account_already_exists",
+            DexError::AccountNotFound(_) => "account_not_found",
+            DexError::WalletAlreadyExists(_) => "wallet_already_exists",
+            DexError::WalletNotFound(_) => "wallet_not_found",
+            DexError::CannotDeleteNonEmptyAccount(_) => "account_not_empty",
+            DexError::AccountPaused(_) => "account_paused",
+            DexError::InvalidSignature(_) => "invalid_signature",
+            DexError::InvalidCredentials(_) => "invalid_credentials",
+            DexError::InsufficientBalance { .. } => "insufficient_balance",
+            DexError::RateLimited(_) => "rate_limited",
+            DexError::SettlementFailed(_) => "settlement_failed",
+            DexError::SchemaVersionTooNew { .. } => "schema_version_too_new",
+            DexError::SanctionedParty(_) => "sanctioned_party",
+            DexError::MarketHalted(_) => "market_halted",
+            DexError::ClockSkew { .. } => "clock_skew",
+            DexError::InvariantViolation(_) => "invariant_violation",
+            DexError::InvalidBlock(_) => "invalid_block",
+            DexError::InvalidTransaction { .. } => "invalid_transaction",
+            DexError::ReadOnlyNode(_) => "read_only_node",
+            DexError::DuplicateOrder { .. } => "duplicate_order",
+            DexError::StaleNonce { .. } => "stale_nonce",
+            DexError::LedgerMismatch { .. } => "ledger_mismatch",
+            DexError::CheckpointMismatch { .. } => "checkpoint_mismatch",
+            DexError::BookCapExceeded { .. } => "book_cap_exceeded",
+            DexError::OrderTooSmall { .. } => "order_too_small",
+            DexError::OrderTooLarge { .. } => "order_too_large",
+            DexError::LockPoisoned(_) => "lock_poisoned",
+            DexError::InvalidConfig { .. } => "invalid_config",
+            DexError::Forbidden(_) => "forbidden",
+            DexError::UserBanned(_) => "user_banned",
+            DexError::Other(_) => "internal",
+        }
+    }
+
+    /// Passender HTTP-Status für die REST-API.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            DexError::OrderNotFound { .. }
+            | DexError::AccountNotFound(_)
+            | DexError::WalletNotFound(_)
+            | DexError::PeerNotFound { .. } => 404,
+            DexError::AccountAlreadyExists(_)
+            | DexError::WalletAlreadyExists(_)
+            | DexError::DuplicateOrder { .. }
+            | DexError::StaleNonce { .. } => 409,
+            DexError::InvalidSignature(_) | DexError::InvalidCredentials(_) => 401,
+            DexError::AccountPaused(_)
+            | DexError::SanctionedParty(_)
+            | DexError::ReadOnlyNode(_)
+            | DexError::Forbidden(_)
+            | DexError::UserBanned(_) => 403,
+            DexError::RateLimited(_) | DexError::BookCapExceeded { .. } => 429,
+            DexError::MarketHalted(_) | DexError::NetworkPartition => 503,
+            DexError::DatabaseError(_)
+            | DexError::SchemaVersionTooNew { .. }
+            | DexError::InvariantViolation(_)
+            | DexError::SettlementFailed(_)
+            | DexError::LedgerMismatch { .. }
+            | DexError::CheckpointMismatch { .. }
+            | DexError::LockPoisoned(_)
+            | DexError::Other(_) => 500,
+            _ => 400,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes_and_status() {
+        let cases = [
+            (DexError::InvalidSignature("order o1".into()), "invalid_signature", 401),
+            (DexError::InsufficientBalance { user: "alice".into(), asset: "BTC".into() }, "insufficient_balance", 400),
+            (DexError::MarketHalted("BTC/USDT".into()), "market_halted", 503),
+            (DexError::AccountPaused("alice".into()), "account_paused", 403),
+            (DexError::SanctionedParty("bc1q...".into()), "sanctioned_party", 403),
+            (DexError::RateLimited("10.0.0.1".into()), "rate_limited", 429),
+            (DexError::InvalidCredentials("2FA code".into()), "invalid_credentials", 401),
+            (DexError::SettlementFailed("rpc down".into()), "settlement_failed", 500),
+            (DexError::OrderNotFound { order_id: "o1".into() }, "order_not_found", 404),
+            (DexError::ReadOnlyNode("place_order".into()), "read_only_node", 403),
+            (DexError::Forbidden("order o1".into()), "forbidden", 403),
+            (DexError::UserBanned("alice".into()), "user_banned", 403),
+            (DexError::DuplicateOrder { user_id: "alice".into(), nonce: 7 }, "duplicate_order", 409),
+            (DexError::StaleNonce { user_id: "alice".into(), nonce: 3, highest: 7 }, "stale_nonce", 409),
+            (DexError::LedgerMismatch { wallet_id: "w1".into(), kind: "Dex".into(), ledger: 1.0, stored: 2.0 }, "ledger_mismatch", 500),
+            (DexError::Other("x".into()), "internal", 500),
+        ];
+        for (err, code, status) in cases {
+            assert_eq!(err.code(), code, "{}", err);
+            assert_eq!(err.http_status(), status, "{}", err);
+        }
+    }
+}
@@ -18,7 +18,15 @@
 // "distribute_dev_pool" bzw. "distribute_nodes_pool".
 // Ein periodischer Task ("run_fee_distributor_task") ruft 
 // z. B. "distribute_all" (dev + nodes) in einem definierten Intervall auf.
-
+//
+// Cold-Sweep: Die Pools liegen in einer Hot-Wallet des Nodes. Mit einer
+// `ColdSweepPolicy` wird nach jedem `add_fees` geprüft, ob der heiße Bestand
+// (dev + nodes + sync) die Schwelle übersteigt; der Überschuss wird im
+// Audit-Log (Hash-Kette, siehe audit::audit_log) festgehalten, über einen
+// `ColdTransfer` aus der Hot-Wallet an die Multisig-Cold-Adresse gesendet und
+// erst danach anteilig aus allen Pools abgezogen.
+
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use serde::{Serialize, Deserialize};
 use tracing::{info, warn, debug};
@@ -29,6 +37,11 @@ use tokio::time::{sleep, Duration};
 use crate::error::DexError;
 use crate::storage::db_layer::DexDB;
 use crate::identity::accounts::{Account, AccountType};
+use crate::identity::wallet::WalletManager;
+use crate::metrics::FEE_PAYOUTS_TOTAL;
+use crate::audit::audit_log::{AuditLogger, TradeAuditEvent, TradeEventType};
+use crate::network::cluster_management::Membership;
+use crate::utils::lock::LockRecover;
 
 /// Beschreibt einen Empfänger, der vom FeePool bedacht wird.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -50,9 +63,96 @@ pub struct FeePoolData {
     /// Pool für Fullnodes (oder \"Nodes\").
     pub nodes_pool: f64,
 
+    /// Sync-Fee-Pool: wird pro Epoche nach geleisteter Sync-Arbeit verteilt.
+    #[serde(default)]
+    pub sync_pool: f64,
+
     /// Liste statischer Empfänger (Founder, Dev-Team, Partner).
     /// Fullnodes werden über \"auto_sync_fullnodes\" zugewiesen.
     pub recipients: Vec<FeeRecipient>,
+
+    /// Summe aller bisher in den Cold-Storage abgeführten Beträge.
+    #[serde(default)]
+    pub cold_swept: f64,
+}
+
+impl FeePoolData {
+    /// Bestand in der Hot-Wallet (alle noch nicht verteilten Pools).
+    pub fn hot_balance(&self) -> f64 {
+        self.dev_pool + self.nodes_pool + self.sync_pool
+    }
+}
+
+/// Übersteigt der heiße Bestand `hot_threshold`, wird der Überschuss an
+/// `cold_address` abgeführt. Die Adresse ist multisig-kontrolliert
+/// (`multisig_required` aus `multisig_signers`); der Node selbst kann sie
+/// nicht ausgeben.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColdSweepPolicy {
+    pub hot_threshold: f64,
+    pub cold_address: String,
+    /// Hot-Wallet des Nodes, aus der die Fees an `cold_address` gehen
+    #[serde(default)]
+    pub hot_wallet_id: String,
+    #[serde(default)]
+    pub multisig_signers: Vec<String>,
+    #[serde(default)]
+    pub multisig_required: u32,
+}
+
+impl ColdSweepPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.hot_threshold.is_finite() || self.hot_threshold < 0.0 {
+            return Err(format!("hot_threshold must be >= 0, got {}", self.hot_threshold));
+        }
+        if self.cold_address.trim().is_empty() {
+            return Err("cold_address must not be empty".into());
+        }
+        if self.hot_wallet_id.trim().is_empty() {
+            return Err("hot_wallet_id must not be empty".into());
+        }
+        if self.multisig_required == 0 || self.multisig_required as usize > self.multisig_signers.len() {
+            return Err(format!(
+                "multisig_required must be 1..={}, got {}",
+                self.multisig_signers.len(), self.multisig_required
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Ein durchgeführter Sweep inkl. txid der Hot→Cold-Transaktion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColdSweep {
+    pub amount: f64,
+    pub cold_address: String,
+    pub hot_after: f64,
+    pub txid: String,
+}
+
+/// Baut und sendet die Transaktion eines Sweeps aus der Hot-Wallet; liefert die txid.
+pub trait ColdTransfer: Send + Sync + std::fmt::Debug {
+    fn submit(&self, hot_wallet_id: &str, cold_address: &str, amount: f64) -> Result<String, DexError>;
+}
+
+/// Produktiv-Backend: On-Chain-Auszahlung über `WalletManager::send_onchain`.
+#[derive(Debug, Clone)]
+pub struct WalletColdTransfer {
+    wallets: WalletManager,
+}
+
+impl WalletColdTransfer {
+    pub fn new(wallets: WalletManager) -> Self {
+        Self { wallets }
+    }
+}
+
+impl ColdTransfer for WalletColdTransfer {
+    fn submit(&self, hot_wallet_id: &str, cold_address: &str, amount: f64) -> Result<String, DexError> {
+        let mut w = self.wallets.load_wallet(hot_wallet_id)?
+            .ok_or(DexError::WalletNotFound(hot_wallet_id.to_string()))?;
+        self.wallets.send_onchain(&mut w, cold_address, amount)
+    }
 }
 
 /// Ein fester prozentualer Anteil, den alle Fullnodes zusammen 
@@ -69,6 +169,15 @@ const NODE_PERCENT: f64 = 0.70;
 pub struct FeePool {
     db: Arc<Mutex<DexDB>>,
     pool_key: String,
+    /// Optional: nur Fullnodes, die laut SWIM leben, erhalten Node-Fees.
+    /// Fullnode-Accounts tragen dafür ihre Membership-ID als user_id.
+    membership: Option<Arc<Mutex<Membership>>>,
+    /// Anteil jeder Fee, der in den sync_pool fließt (0.0 = aus)
+    sync_fee_rate: f64,
+    cold_sweep: Option<ColdSweepPolicy>,
+    /// Ziel für Sweep-Einträge; ohne Audit-Log wird nie gesweept
+    audit: Option<Arc<AuditLogger>>,
+    cold_transfer: Option<Arc<dyn ColdTransfer>>,
 }
 
 impl FeePool {
@@ -78,12 +187,51 @@ impl FeePool {
         Self {
             db,
             pool_key: pool_key.to_string(),
+            membership: None,
+            sync_fee_rate: 0.0,
+            cold_sweep: None,
+            audit: None,
+            cold_transfer: None,
+        }
+    }
+
+    /// Aktiviert den Cold-Sweep; jeder Sweep wird in `audit` protokolliert
+    /// und über `transfer` aus der Hot-Wallet gesendet.
+    pub fn with_cold_sweep(mut self, policy: ColdSweepPolicy, audit: Arc<AuditLogger>, transfer: Arc<dyn ColdTransfer>) -> Self {
+        self.cold_sweep = Some(policy);
+        self.audit = Some(audit);
+        self.cold_transfer = Some(transfer);
+        self
+    }
+
+    /// z. B. 0.01 => 1 % jeder Fee geht an Nodes, die Sync-Traffic bedient haben.
+    pub fn with_sync_fee(mut self, rate: f64) -> Self {
+        self.sync_fee_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_membership(mut self, membership: Arc<Mutex<Membership>>) -> Self {
+        self.membership = Some(membership);
+        self
+    }
+
+    /// Teil-Pool für Fees in `symbol` (gleiche Einstellungen, eigener
+    /// Schlüssel `<pool_key>/<symbol>`), damit Fees verschiedener Assets
+    /// nicht addiert werden.
+    pub fn for_asset(&self, symbol: &str) -> FeePool {
+        FeePool { pool_key: format!("{}/{}", self.pool_key, symbol), ..self.clone() }
+    }
+
+        fn is_member_alive(&self, user_id: &str) -> bool {
+        match &self.membership {
+            Some(m) => m.lock().map(|m| m.is_alive(user_id)).unwrap_or(false),
+            None => true,
         }
     }
 
     /// Lädt den FeePool-Zustand oder erzeugt leeren, falls noch keiner existiert.
     fn load_fee_pool_data(&self) -> Result<FeePoolData, DexError> {
-        let lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock = self.db.lock_recover();
         if let Some(fp) = lock.load_struct::<FeePoolData>(&self.pool_key)? {
             Ok(fp)
         } else {
@@ -91,19 +239,22 @@ impl FeePool {
                 total_fees: 0.0,
                 dev_pool: 0.0,
                 nodes_pool: 0.0,
+                sync_pool: 0.0,
                 recipients: Vec::new(),
+                cold_swept: 0.0,
             })
         }
     }
 
     /// Speichert FeePoolData in DB.
     fn store_fee_pool_data(&self, data: &FeePoolData) -> Result<(), DexError> {
-        let lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock = self.db.lock_recover();
         lock.store_struct(&self.pool_key, data)?;
         Ok(())
     }
 
-    /// Addiert amount an Fees und splittet sie: 30% => dev_pool, 70% => nodes_pool.
+    /// Addiert amount an Fees: zuerst sync_fee_rate => sync_pool,
+    /// der Rest splittet 30% => dev_pool, 70% => nodes_pool.
     pub fn add_fees(&self, amount: f64) -> Result<(), DexError> {
         if amount <= 0.0 {
             return Err(DexError::Other(format!("fee amount <=0 => {amount}")));
@@ -111,15 +262,103 @@ impl FeePool {
         let mut fp = self.load_fee_pool_data()?;
 
         // Optional: fp.total_fees += amount; (kann man belassen oder weglassen.)
-        let dev_amt = amount * DEV_PERCENT;
-        let node_amt = amount * NODE_PERCENT;
+        let sync_amt = amount * self.sync_fee_rate;
+        let rest = amount - sync_amt;
+        let dev_amt = rest * DEV_PERCENT;
+        let node_amt = rest * NODE_PERCENT;
 
+        fp.sync_pool += sync_amt;
         fp.dev_pool += dev_amt;
         fp.nodes_pool += node_amt;
 
         self.store_fee_pool_data(&fp)?;
-        debug!("add_fees({:.8}) => dev_pool += {:.8}, nodes_pool += {:.8}",
-               amount, dev_amt, node_amt);
+        debug!("add_fees({:.8}) => sync_pool += {:.8}, dev_pool += {:.8}, nodes_pool += {:.8}",
+               amount, sync_amt, dev_amt, node_amt);
+        if let Err(e) = self.sweep_to_cold() {
+            warn!("Cold-Sweep fehlgeschlagen: {:?}", e);
+        }
+        Ok(())
+    }
+
+    /// Führt den Überschuss über `hot_threshold` an die Cold-Adresse ab.
+    /// Reihenfolge: Audit-Eintrag, Transaktion, dann erst die Pools kürzen
+    /// (alle im gleichen Verhältnis, damit dev/nodes/sync erhalten bleibt).
+    /// Scheitert Audit oder Transaktion, bleiben die Pools unverändert.
+    /// Unterhalb der Schwelle (oder ohne Policy) passiert nichts.
+    pub fn sweep_to_cold(&self) -> Result<Option<ColdSweep>, DexError> {
+        let policy = match &self.cold_sweep {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+        let (audit, transfer) = match (&self.audit, &self.cold_transfer) {
+            (Some(a), Some(t)) => (a, t),
+            _ => return Err(DexError::Other("cold sweep without audit log or transfer backend".into())),
+        };
+        let mut fp = self.load_fee_pool_data()?;
+        let hot = fp.hot_balance();
+        if hot <= policy.hot_threshold {
+            return Ok(None);
+        }
+        let excess = hot - policy.hot_threshold;
+
+        // Erst protokollieren: ohne Audit-Eintrag kein Sweep
+        let event = TradeAuditEvent::new(
+            TradeEventType::Transfer,
+            "fee_pool",
+            excess,
+            Some(policy.cold_address.clone()),
+            Some(format!("{}:{}", self.pool_key, policy.hot_wallet_id)),
+        );
+        audit.log(&event).map_err(|e| DexError::Other(format!("cold sweep audit failed: {}", e)))?;
+        let txid = transfer.submit(&policy.hot_wallet_id, &policy.cold_address, excess)?;
+
+        let keep = policy.hot_threshold.max(0.0) / hot;
+        fp.dev_pool *= keep;
+        fp.nodes_pool *= keep;
+        fp.sync_pool *= keep;
+        fp.cold_swept += excess;
+        self.store_fee_pool_data(&fp)?;
+        let sweep = ColdSweep { amount: excess, cold_address: policy.cold_address.clone(), hot_after: fp.hot_balance(), txid };
+        info!("Cold-Sweep => {:.8} an {} ({}-von-{} Multisig), tx={}, hot={:.8}",
+              excess, policy.cold_address, policy.multisig_required, policy.multisig_signers.len(), sweep.txid, sweep.hot_after);
+        Ok(Some(sweep))
+    }
+
+    /// Summe aller bisherigen Cold-Sweeps
+    pub fn total_cold_swept(&self) -> Result<f64, DexError> {
+        Ok(self.load_fee_pool_data()?.cold_swept)
+    }
+
+    /// Aktueller sync_pool-Betrag
+    pub fn current_sync_pool(&self) -> Result<f64, DexError> {
+        let fp = self.load_fee_pool_data()?;
+        Ok(fp.sync_pool)
+    }
+
+    /// Verteilt sync_pool proportional zur Sync-Arbeit (Gewicht je Node,
+    /// z. B. bediente Bytes einer Epoche). Node-Schlüssel sind Member-IDs,
+    /// ausgezahlt wird an den Fullnode-Account gleicher user_id. Anteile von
+    /// Nodes ohne auszahlbaren Account bleiben wie ein Pool ohne Arbeit für
+    /// die nächste Epoche stehen.
+    pub fn distribute_sync_pool(&self, work: &HashMap<String, u64>) -> Result<(), DexError> {
+        let mut fp = self.load_fee_pool_data()?;
+        let sync_total = fp.sync_pool;
+        let total_work: u64 = work.values().sum();
+        if sync_total <= 0.0 || total_work == 0 {
+            debug!("distribute_sync_pool => pool={:.8}, work={} => skip", sync_total, total_work);
+            return Ok(());
+        }
+        let mut paid = 0.0;
+        for (node, w) in work {
+            let portion = sync_total * (*w as f64 / total_work as f64);
+            if self.credit_user_dex_balance(node, portion)? {
+                paid += portion;
+                info!("SYNC node={} => +{:.8} => work={}/{}", node, portion, w, total_work);
+            }
+        }
+        fp.sync_pool = (sync_total - paid).max(0.0);
+        self.store_fee_pool_data(&fp)?;
+        info!("sync_pool => {:.8} left after distributing {:.8} of {:.8}", fp.sync_pool, paid, sync_total);
         Ok(())
     }
 
@@ -180,24 +419,30 @@ impl FeePool {
     /// D. h. wir entfernen alle Fullnodes aus recipients und setzen sie neu 
     /// mit fee_share_percent = (FULLNODES_POOL_PERCENT / #found).
     pub fn auto_sync_fullnodes(&self) -> Result<(), DexError> {
-        let lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock = self.db.lock_recover();
         let all_keys = lock.list_prefix("accounts/");
         drop(lock);
 
         let mut fullnode_ids = Vec::new();
+        let mut all_fullnodes = Vec::new();
         for (k, _) in all_keys {
-            let maybe_acc = self.db.lock().unwrap().load_struct::<Account>(&k)?;
+            let maybe_acc = self.db.lock_recover().load_struct::<Account>(&k)?;
             if let Some(acc) = maybe_acc {
                 if acc.account_type == AccountType::Fullnode && acc.is_fee_pool_recipient {
-                    fullnode_ids.push(acc.user_id.clone());
+                    all_fullnodes.push(acc.user_id.clone());
+                    if self.is_member_alive(&acc.user_id) {
+                        fullnode_ids.push(acc.user_id.clone());
+                    } else {
+                        debug!("Fullnode {} not alive => no node fees", acc.user_id);
+                    }
                 }
             }
         }
 
         let mut fp = self.load_fee_pool_data()?;
-        // Entferne vorhandene Fullnodes
+        // Entferne vorhandene Fullnodes (auch tote)
         let old_len = fp.recipients.len();
-        fp.recipients.retain(|r| !fullnode_ids.contains(&r.user_id));
+        fp.recipients.retain(|r| !all_fullnodes.contains(&r.user_id));
         let removed = old_len - fp.recipients.len();
         if removed > 0 {
             debug!("Removed {removed} old Fullnode recipients");
@@ -301,34 +546,37 @@ impl FeePool {
     }
 
     /// Bucht portion auf das Dex-Balance des erstbesten Wallets dieses Users.
-    fn credit_user_dex_balance(&self, user_id: &str, portion: f64) -> Result<(), DexError> {
-        if portion <= 0.0 { return Ok(()); }
+    /// true => gutgeschrieben; false => kein Account/Wallet, Betrag bleibt beim Aufrufer.
+    fn credit_user_dex_balance(&self, user_id: &str, portion: f64) -> Result<bool, DexError> {
+        if portion <= 0.0 { return Ok(false); }
 
-        let lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock = self.db.lock_recover();
         let key = format!("accounts/{}", user_id);
         let maybe_acc = lock.load_struct::<Account>(&key)?;
         let acc = match maybe_acc {
             Some(a) => a,
             None => {
                 warn!("credit_user_dex_balance => user={} not found => skip portion={}", user_id, portion);
-                return Ok(());
+                return Ok(false);
             }
         };
         if acc.wallet_ids.is_empty() {
             warn!("User={} has no wallet => ignoring portion={:.8}", user_id, portion);
-            return Ok(());
+            return Ok(false);
         }
         let w_id = &acc.wallet_ids[0];
         let wkey = format!("wallets/{}", w_id);
         let mut maybe_w = lock.load_struct::<crate::identity::wallet::WalletInfo>(&wkey)?;
         if let Some(mut w) = maybe_w {
-            w.dex_balance += portion;
-            lock.store_struct(&wkey, &w)?;
+            let posting = crate::identity::balance_ledger::Posting::new("fee_pool", "fee_payout", user_id);
+            crate::identity::balance_ledger::post(&lock, &mut w, crate::identity::balance_ledger::BalanceKind::Dex, portion, posting)?;
+            FEE_PAYOUTS_TOTAL.inc_by(portion);
             info!("User={} => credited +{:.8} => wallet={}", user_id, portion, w.wallet_id);
+            Ok(true)
         } else {
             warn!("Wallet={} for user={} not found => skipping portion", w_id, user_id);
+            Ok(false)
         }
-        Ok(())
     }
 }
 
@@ -350,3 +598,187 @@ pub fn start_fee_distribution_task(fee_pool: FeePool, interval: Duration) -> Joi
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::wallet::{BlockchainType, WalletInfo};
+    use crate::network::cluster_management::SyncWorkLedger;
+    use crate::storage::db_layer::InMemoryDb;
+
+    fn add_fullnode(db: &Arc<Mutex<DexDB>>, user_id: &str) {
+        let lock = db.lock_recover();
+        let wallet_id = format!("w_{}", user_id);
+        lock.store_struct(&format!("accounts/{}", user_id), &Account {
+            user_id: user_id.to_string(),
+            account_type: AccountType::Fullnode,
+            is_fee_pool_recipient: true,
+            fee_share_percent: 0.0,
+            wallet_ids: vec![wallet_id.clone()],
+            paused: false,
+            country: None,
+            two_fa_secret: None,
+            hashed_password: None,
+            recovery_code_hashes: vec![],
+            last_totp_step: None,
+            pending_two_fa_secret: None,
+            active: true,
+        }).unwrap();
+        lock.store_struct(&format!("wallets/{}", wallet_id), &WalletInfo {
+            wallet_id,
+            blockchain: BlockchainType::Bitcoin,
+            public_info: String::new(),
+            address: String::new(),
+            onchain_balance: 0.0,
+            dex_balance: 0.0,
+        }).unwrap();
+    }
+
+    fn mem_db() -> Arc<Mutex<DexDB>> {
+        Arc::new(Mutex::new(DexDB {
+            rocks: None,
+            fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))),
+        }))
+    }
+
+    fn sweep_policy() -> ColdSweepPolicy {
+        ColdSweepPolicy {
+            hot_threshold: 100.0,
+            cold_address: "bc1q-cold-multisig".into(),
+            hot_wallet_id: "w_fee_hot".into(),
+            multisig_signers: vec!["a".into(), "b".into(), "c".into()],
+            multisig_required: 2,
+        }
+    }
+
+    /// Zeichnet gesendete Sweeps auf; `fail` simuliert einen Sendefehler.
+    #[derive(Debug, Default)]
+    struct RecordingTransfer {
+        sent: Mutex<Vec<(String, String, f64)>>,
+        fail: std::sync::atomic::AtomicBool,
+    }
+
+    impl ColdTransfer for RecordingTransfer {
+        fn submit(&self, hot_wallet_id: &str, cold_address: &str, amount: f64) -> Result<String, DexError> {
+            if self.fail.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(DexError::Other("rpc down".into()));
+            }
+            let mut sent = self.sent.lock().unwrap();
+            sent.push((hot_wallet_id.to_string(), cold_address.to_string(), amount));
+            Ok(format!("tx{}", sent.len()))
+        }
+    }
+
+    fn test_audit(path: &std::path::Path) -> Arc<AuditLogger> {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        Arc::new(AuditLogger::open(path, ed25519_dalek::Keypair { secret, public }).unwrap())
+    }
+
+    fn dex_balance(db: &Arc<Mutex<DexDB>>, user_id: &str) -> f64 {
+        let key = format!("wallets/w_{}", user_id);
+        db.lock_recover().load_struct::<WalletInfo>(&key).unwrap().unwrap().dex_balance
+    }
+
+    #[test]
+    fn test_sync_pool_follows_served_traffic() {
+        let db = Arc::new(Mutex::new(DexDB {
+            rocks: None,
+            fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))),
+        }));
+        add_fullnode(&db, "node_a");
+        add_fullnode(&db, "node_b");
+        let pool = FeePool::new(db.clone(), "system_accounts/fee_pool").with_sync_fee(0.01);
+        pool.add_fees(1000.0).unwrap();
+        assert!((pool.current_sync_pool().unwrap() - 10.0).abs() < 1e-9);
+
+        // node_a bedient 90 % des Sync-Traffics
+        let mut ledger = SyncWorkLedger::default();
+        for _ in 0..9 {
+            ledger.record("node_a", 1_000, 1);
+        }
+        ledger.record("node_b", 1_000, 1);
+        pool.distribute_sync_pool(&ledger.weights()).unwrap();
+
+        assert!((dex_balance(&db, "node_a") - 9.0).abs() < 1e-6);
+        assert!((dex_balance(&db, "node_b") - 1.0).abs() < 1e-6);
+        assert_eq!(pool.current_sync_pool().unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_sync_share_without_account_is_carried_over() {
+        let db = mem_db();
+        add_fullnode(&db, "node_a");
+        let pool = FeePool::new(db.clone(), "system_accounts/fee_pool").with_sync_fee(0.01);
+        pool.add_fees(1000.0).unwrap();
+
+        // Member ohne Fullnode-Account: sein Anteil wird nicht verbrannt
+        let mut ledger = SyncWorkLedger::default();
+        ledger.record("node_a", 1_000, 1);
+        ledger.record("unregistered_member", 3_000, 3);
+        pool.distribute_sync_pool(&ledger.weights()).unwrap();
+        assert!((dex_balance(&db, "node_a") - 2.5).abs() < 1e-6);
+        assert!((pool.current_sync_pool().unwrap() - 7.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cold_sweep_moves_only_excess_and_is_audited() {
+        use crate::audit::audit_log::verify_audit_chain;
+
+        let dir = tempfile::tempdir().unwrap();
+        let audit_path = dir.path().join("fee_audit.log");
+        let transfer = Arc::new(RecordingTransfer::default());
+
+        let pool = FeePool::new(mem_db(), "system_accounts/fee_pool")
+            .with_cold_sweep(sweep_policy(), test_audit(&audit_path), transfer.clone());
+        pool.add_fees(80.0).unwrap();
+        assert_eq!(pool.total_cold_swept().unwrap(), 0.0);
+        assert!(!audit_path.exists(), "unter der Schwelle kein Sweep");
+
+        // 80 + 70 = 150 => 50 gehen kalt, 100 bleiben heiß (30/70 erhalten)
+        pool.add_fees(70.0).unwrap();
+        assert!((pool.total_cold_swept().unwrap() - 50.0).abs() < 1e-9);
+        assert!((pool.current_dev_pool().unwrap() - 30.0).abs() < 1e-9);
+        assert!((pool.current_nodes_pool().unwrap() - 70.0).abs() < 1e-9);
+        assert_eq!(verify_audit_chain(&audit_path).unwrap().entries, 1);
+        // Die Transaktion ging tatsächlich aus der Hot-Wallet an die Cold-Adresse
+        let sent = transfer.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert_eq!((sent[0].0.as_str(), sent[0].1.as_str()), ("w_fee_hot", "bc1q-cold-multisig"));
+        assert!((sent[0].2 - 50.0).abs() < 1e-9);
+
+        // Genau auf der Schwelle => nichts zu tun
+        assert_eq!(pool.sweep_to_cold().unwrap(), None);
+    }
+
+    #[test]
+    fn test_failed_transfer_keeps_pools() {
+        let dir = tempfile::tempdir().unwrap();
+        let transfer = Arc::new(RecordingTransfer::default());
+        transfer.fail.store(true, std::sync::atomic::Ordering::SeqCst);
+        let pool = FeePool::new(mem_db(), "system_accounts/fee_pool")
+            .with_cold_sweep(sweep_policy(), test_audit(&dir.path().join("a.log")), transfer.clone());
+        pool.add_fees(150.0).unwrap();
+        assert!(pool.sweep_to_cold().is_err());
+        assert_eq!(pool.total_cold_swept().unwrap(), 0.0);
+        assert!((pool.current_dev_pool().unwrap() - 45.0).abs() < 1e-9);
+
+        // Erneuter Versuch nach Recovery sweept den vollen Überschuss
+        transfer.fail.store(false, std::sync::atomic::Ordering::SeqCst);
+        let sweep = pool.sweep_to_cold().unwrap().unwrap();
+        assert!((sweep.amount - 50.0).abs() < 1e-9);
+        assert_eq!(sweep.txid, "tx1");
+    }
+
+    #[test]
+    fn test_below_threshold_balances_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = FeePool::new(mem_db(), "system_accounts/fee_pool")
+            .with_cold_sweep(sweep_policy(), test_audit(&dir.path().join("a.log")), Arc::new(RecordingTransfer::default()));
+        pool.add_fees(99.0).unwrap();
+        assert_eq!(pool.sweep_to_cold().unwrap(), None);
+        assert!((pool.current_dev_pool().unwrap() - 29.7).abs() < 1e-9);
+        assert!((pool.current_nodes_pool().unwrap() - 69.3).abs() < 1e-9);
+        assert_eq!(pool.total_cold_swept().unwrap(), 0.0);
+    }
+}
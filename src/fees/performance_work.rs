@@ -12,6 +12,7 @@ use crate::error::DexError;
 use crate::storage::db_layer::DexDB;
 use crate::identity::accounts::{Account, AccountType};
 use crate::fees::fee_pool::FeePool;
+use crate::utils::lock::LockRecover;
 
 /// Definiert einen einzelnen Work-Nachweis: Node (user_id), Arbeitseinheiten, Zeitstempel etc.
 #[derive(Debug, Clone)]
@@ -69,7 +70,7 @@ impl PerformanceWorkManager {
     /// L�dt den aggregierten Score einer Node aus der DB.
     pub fn load_node_score(&self, node_id: &str) -> Result<Option<NodeWorkScore>, DexError> {
         let key = format!("performance_work/score_{}", node_id);
-        let lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock = self.db.lock_recover();
         let res = lock.load_struct::<NodeWorkScore>(&key)?;
         Ok(res)
     }
@@ -77,14 +78,14 @@ impl PerformanceWorkManager {
     /// Speichert einen NodeWorkScore in der DB.
     pub fn store_node_score(&self, score: NodeWorkScore) -> Result<(), DexError> {
         let key = format!("performance_work/score_{}", score.node_id);
-        let lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock = self.db.lock_recover();
         lock.store_struct(&key, &score)?;
         Ok(())
     }
 
     /// Summiert alle Node-Scores (z. B. f�r die Fee-Verteilung).
     pub fn sum_all_scores(&self) -> Result<u64, DexError> {
-        let lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock = self.db.lock_recover();
         let prefix = "performance_work/score_";
 
         let list = lock.list_structs_with_prefix::<NodeWorkScore>(prefix)?;
@@ -117,7 +118,7 @@ impl PerformanceWorkManager {
         // 3) Verteile an alle Nodes (nur Fullnode?), proportional
         // => Man k�nnte AccountsManager hier heranziehen, 
         // => wir holen (node_id => account)
-        let lock_db = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock_db = self.db.lock_recover();
         let prefix = "performance_work/score_";
         let scores = lock_db.list_structs_with_prefix::<NodeWorkScore>(prefix)?;
 
@@ -135,7 +136,10 @@ impl PerformanceWorkManager {
                     let first_wallet = acc.wallet_ids.first().cloned();
                     if let Some(wid) = first_wallet {
                         // Add Dex-Balance
-                        self.wallet_manager.add_dex_balance(&wid, node_reward)?;
+                        let posting = crate::identity::balance_ledger::Posting::new(
+                            "fee_pool:daily", "performance_reward", sc.node_id.clone(),
+                        );
+                        self.wallet_manager.add_dex_balance(&wid, node_reward, posting)?;
                         info!("Leistungsbasiert: Node={} (acc={}) kriegt {} aus daily_fees ({} total, share={:.2}%)",
                             sc.node_id, acc.user_id, node_reward, daily_amount, share_percent*100.0
                         );
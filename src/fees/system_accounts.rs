@@ -27,6 +27,7 @@ use tracing::{info, debug, warn};
 
 use crate::error::DexError;
 use crate::storage::replicated_db_layer::DexDB;
+use crate::utils::lock::LockRecover;
 
 /// Rolle eines System-Accounts, z. B. Inventor, Developer, Founder etc.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -81,7 +82,7 @@ impl SystemAccountsManager {
             )));
         }
         let key = format!("system_account/{}", account_id);
-        let mut locked_db = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let mut locked_db = self.db.lock_recover();
 
         if locked_db.exists(&key)? {
             return Err(DexError::Other(format!(
@@ -108,7 +109,7 @@ impl SystemAccountsManager {
     /// dass sein Fee-Anteil auf 0.0 gesetzt wird.
     pub fn set_account_inactive(&self, account_id: &str) -> Result<(), DexError> {
         let key = format!("system_account/{}", account_id);
-        let mut locked_db = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let mut locked_db = self.db.lock_recover();
 
         let raw = locked_db.get(&key)?;
         let val = match raw {
@@ -134,7 +135,7 @@ impl SystemAccountsManager {
     /// Gibt eine Liste aller SystemAccounts (aktiv und inaktiv) zurück.
     pub fn list_system_accounts(&self) -> Result<Vec<SystemAccount>, DexError> {
         let prefix = "system_account/";
-        let locked_db = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let locked_db = self.db.lock_recover();
         let kv_pairs = locked_db.list_prefix(prefix)?;
         let mut out = Vec::new();
 
@@ -189,7 +190,7 @@ impl SystemAccountsManager {
         let system_fee_pool = total_fee * sum_share;
         debug!("Verteile {:.6} {asset} an {} System-Accounts", system_fee_pool, active.len());
 
-        let mut locked_db = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let mut locked_db = self.db.lock_recover();
         for acc in &active {
             let ratio = acc.fee_share_percent / sum_share;
             let portion = system_fee_pool * ratio;
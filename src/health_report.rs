@@ -0,0 +1,376 @@
+//////////////////////////////////////////
+// my_DEX/src/health_report.rs
+//////////////////////////////////////////
+//
+// Strukturierter Health-Report statt nur "ready/not ready".
+//
+// Jedes Subsystem (DB, DHT, Konsens, IPFS, Price-Feed, Settlement-Queue)
+// meldet über eine Probe Status + Detailtext an die HealthRegistry. Probes
+// werden registriert, sobald das Subsystem in main existiert; bis dahin
+// fehlt es einfach im Report.
+//
+//  - GET /health/detailed => kompletter HealthReport als JSON
+//  - GET /readyz          => 503, sobald ein *kritisches* Subsystem
+//                            Unhealthy ist; Degradierung nicht-kritischer
+//                            Subsysteme (IPFS, Settlement-Backlog) lässt den
+//                            Node ready, steht aber im Body.
+//
+// Gesamtstatus: kritisch + Unhealthy => Unhealthy, sonst schlechtester
+// Einzelstatus, höchstens Degraded.
+//////////////////////////////////////////
+
+use std::sync::{Arc, Mutex};
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::clock::{system_clock, SharedClock};
+use crate::utils::lock::LockRecover;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    Degraded,
+    Unhealthy,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubsystemHealth {
+    pub name: String,
+    pub status: HealthStatus,
+    /// Kritisch => Unhealthy macht den Node not-ready
+    pub critical: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub ready: bool,
+    /// UNIX-Sekunden
+    pub checked_at: u64,
+    pub subsystems: Vec<SubsystemHealth>,
+}
+
+impl HealthReport {
+    pub fn from_subsystems(checked_at: u64, subsystems: Vec<SubsystemHealth>) -> Self {
+        let ready = !subsystems.iter().any(|s| s.critical && s.status == HealthStatus::Unhealthy);
+        let worst = subsystems.iter().map(|s| s.status).max().unwrap_or(HealthStatus::Healthy);
+        let status = if ready { worst.min(HealthStatus::Degraded) } else { HealthStatus::Unhealthy };
+        Self { status, ready, checked_at, subsystems }
+    }
+
+    pub fn subsystem(&self, name: &str) -> Option<&SubsystemHealth> {
+        self.subsystems.iter().find(|s| s.name == name)
+    }
+
+    /// Namen aller Subsysteme mit mindestens `status`.
+    pub fn names_with(&self, status: HealthStatus) -> Vec<&str> {
+        self.subsystems.iter().filter(|s| s.status >= status).map(|s| s.name.as_str()).collect()
+    }
+}
+
+/// Grenzwerte für die Standard-Checks (`health` in der Node-Config).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthThresholds {
+    /// Weniger DHT-Peers => Degraded, keiner => Unhealthy
+    pub min_dht_peers: usize,
+    /// Rückstand in Blöcken; über der Hälfte Degraded, darüber Unhealthy
+    pub max_height_lag: u64,
+    /// Alter des letzten Preis-Updates; über der Hälfte Degraded, darüber Unhealthy
+    pub max_price_age_secs: u64,
+    /// Offene Settlement-Jobs; über der Hälfte Degraded, darüber Unhealthy
+    pub max_settlement_queue: usize,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            min_dht_peers: 3,
+            max_height_lag: 10,
+            max_price_age_secs: 120,
+            max_settlement_queue: 1_000,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Standard-Checks: reine Funktionen über die Rohwerte, damit main sie nur
+// noch mit den jeweiligen Quellen verdrahten muss.
+// ---------------------------------------------------------------------------
+
+pub type ProbeResult = (HealthStatus, String);
+
+/// Unter `limit / 2` Healthy, bis `limit` Degraded, darüber Unhealthy.
+fn against_limit(value: u64, limit: u64) -> HealthStatus {
+    if value > limit {
+        HealthStatus::Unhealthy
+    } else if value > limit / 2 {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Healthy
+    }
+}
+
+pub fn check_db(ping: Result<(), String>) -> ProbeResult {
+    match ping {
+        Ok(()) => (HealthStatus::Healthy, "ok".into()),
+        Err(e) => (HealthStatus::Unhealthy, e),
+    }
+}
+
+pub fn check_dht_peers(peers: usize, t: &HealthThresholds) -> ProbeResult {
+    let status = match peers {
+        0 => HealthStatus::Unhealthy,
+        n if n < t.min_dht_peers => HealthStatus::Degraded,
+        _ => HealthStatus::Healthy,
+    };
+    (status, format!("{} Peers (min {})", peers, t.min_dht_peers))
+}
+
+pub fn check_height_lag(local: u64, best_known: u64, t: &HealthThresholds) -> ProbeResult {
+    let lag = best_known.saturating_sub(local);
+    (against_limit(lag, t.max_height_lag), format!("Höhe {}, Peers {}, Rückstand {}", local, best_known, lag))
+}
+
+pub fn check_ipfs(available: bool, pending_uploads: i64) -> ProbeResult {
+    if available {
+        (HealthStatus::Healthy, format!("{} pending uploads", pending_uploads))
+    } else {
+        (HealthStatus::Degraded, format!("lokaler Fallback, {} pending uploads", pending_uploads))
+    }
+}
+
+/// `last_fresh` = `PriceFeed::last_fresh_update` (UNIX-Sekunden des jüngsten
+/// nicht-stale Aggregats); ohne verlässlichen Preis Unhealthy.
+pub fn check_price_feed(last_fresh: Option<i64>, now: u64, t: &HealthThresholds) -> ProbeResult {
+    let Some(last_fresh) = last_fresh else {
+        return (HealthStatus::Unhealthy, "kein nicht-stale Preis".into());
+    };
+    let age = now.saturating_sub(last_fresh.max(0) as u64);
+    (against_limit(age, t.max_price_age_secs), format!("letzter verlässlicher Preis vor {}s", age))
+}
+
+/// Registriert die Konsens-Probe; erst aufrufen, wenn die Kette läuft und
+/// `CONSENSUS_HEIGHT` pflegt (sonst meldet 0 vs. 0 fälschlich Healthy).
+pub fn register_consensus_probe(registry: &HealthRegistry, t: HealthThresholds) {
+    registry.register("consensus", true, move |_| {
+        check_height_lag(
+            crate::metrics::CONSENSUS_HEIGHT.get().max(0) as u64,
+            crate::metrics::CONSENSUS_PEER_HEIGHT.get().max(0) as u64,
+            &t,
+        )
+    });
+}
+
+pub fn check_settlement_queue(depth: usize, t: &HealthThresholds) -> ProbeResult {
+    (
+        against_limit(depth as u64, t.max_settlement_queue as u64),
+        format!("{} offene Jobs (max {})", depth, t.max_settlement_queue),
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Registry
+// ---------------------------------------------------------------------------
+
+/// Probe bekommt die aktuelle Zeit (UNIX-Sekunden) der Registry-Uhr.
+type Probe = Arc<dyn Fn(u64) -> ProbeResult + Send + Sync>;
+
+struct RegisteredProbe {
+    name: String,
+    critical: bool,
+    probe: Probe,
+}
+
+/// Sammelt die Probes aller Subsysteme. Klone teilen dieselbe Liste.
+#[derive(Clone)]
+pub struct HealthRegistry {
+    probes: Arc<Mutex<Vec<RegisteredProbe>>>,
+    clock: SharedClock,
+}
+
+impl Default for HealthRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthRegistry {
+    pub fn new() -> Self {
+        Self { probes: Arc::new(Mutex::new(Vec::new())), clock: system_clock() }
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Registriert (bzw. ersetzt) die Probe für `name`.
+    pub fn register<F>(&self, name: &str, critical: bool, probe: F)
+    where
+        F: Fn(u64) -> ProbeResult + Send + Sync + 'static,
+    {
+        let mut probes = self.probes.lock_recover();
+        probes.retain(|p| p.name != name);
+        probes.push(RegisteredProbe { name: name.to_string(), critical, probe: Arc::new(probe) });
+    }
+
+    pub fn report(&self) -> HealthReport {
+        let now = self.clock.unix_secs();
+        // Probes außerhalb des Locks ausführen, sie dürfen selbst locken
+        let probes: Vec<(String, bool, Probe)> = self
+            .probes
+            .lock_recover()
+            .iter()
+            .map(|p| (p.name.clone(), p.critical, p.probe.clone()))
+            .collect();
+        let subsystems = probes
+            .into_iter()
+            .map(|(name, critical, probe)| {
+                let (status, detail) = probe(now);
+                SubsystemHealth { name, status, critical, detail }
+            })
+            .collect();
+        HealthReport::from_subsystems(now, subsystems)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// HTTP
+// ---------------------------------------------------------------------------
+
+/// `GET /health/detailed` (200 wenn ready, sonst 503) und `GET /readyz`.
+pub fn health_routes<S>(registry: HealthRegistry) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/health/detailed", get(detailed_health))
+        .route("/readyz", get(readiness))
+        .with_state(registry)
+}
+
+fn ready_status(report: &HealthReport) -> StatusCode {
+    if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+async fn detailed_health(State(registry): State<HealthRegistry>) -> impl IntoResponse {
+    let report = registry.report();
+    (ready_status(&report), Json(report))
+}
+
+async fn readiness(State(registry): State<HealthRegistry>) -> impl IntoResponse {
+    let report = registry.report();
+    let body = if !report.ready {
+        let down: Vec<&str> = report
+            .subsystems
+            .iter()
+            .filter(|s| s.critical && s.status == HealthStatus::Unhealthy)
+            .map(|s| s.name.as_str())
+            .collect();
+        format!("not ready: {}", down.join(", "))
+    } else if report.status == HealthStatus::Healthy {
+        "ready".to_string()
+    } else {
+        format!("ready; degraded: {}", report.names_with(HealthStatus::Degraded).join(", "))
+    };
+    (ready_status(&report), body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::clock::MockClock;
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    const START: u64 = 1_700_000_000;
+
+    /// Alle Standard-Subsysteme; Preis-Feed zuletzt bei START aktualisiert.
+    fn registry(clock: &MockClock, ipfs_up: bool) -> HealthRegistry {
+        let t = HealthThresholds::default();
+        let reg = HealthRegistry::new().with_clock(clock.shared());
+        reg.register("db", true, |_| check_db(Ok(())));
+        let t2 = t.clone();
+        reg.register("dht", true, move |_| check_dht_peers(5, &t2));
+        let t2 = t.clone();
+        reg.register("consensus", true, move |_| check_height_lag(100, 101, &t2));
+        reg.register("ipfs", false, move |_| check_ipfs(ipfs_up, 3));
+        let t2 = t.clone();
+        reg.register("price_feed", true, move |now| check_price_feed(Some(START as i64), now, &t2));
+        reg.register("settlement_queue", false, move |_| check_settlement_queue(2, &t));
+        reg
+    }
+
+    async fn get(reg: &HealthRegistry, path: &str) -> (StatusCode, String) {
+        let app: Router = health_routes(reg.clone());
+        let resp = app.oneshot(Request::get(path).body(Body::empty()).unwrap()).await.unwrap();
+        let status = resp.status();
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_stale_price_feed_is_unhealthy_and_fails_readiness() {
+        let clock = MockClock::new(START);
+        let reg = registry(&clock, true);
+        assert_eq!(get(&reg, "/readyz").await, (StatusCode::OK, "ready".to_string()));
+
+        clock.advance(Duration::from_secs(HealthThresholds::default().max_price_age_secs + 1));
+        let report = reg.report();
+        assert_eq!(report.subsystem("price_feed").unwrap().status, HealthStatus::Unhealthy);
+        assert_eq!(report.subsystem("db").unwrap().status, HealthStatus::Healthy);
+        assert_eq!(report.status, HealthStatus::Unhealthy);
+        assert!(!report.ready);
+
+        let (status, body) = get(&reg, "/health/detailed").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        let feed = json["subsystems"].as_array().unwrap().iter().find(|s| s["name"] == "price_feed").unwrap();
+        assert_eq!(feed["status"], "unhealthy");
+        assert_eq!(get(&reg, "/readyz").await, (StatusCode::SERVICE_UNAVAILABLE, "not ready: price_feed".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_non_critical_degradation_keeps_readiness_green() {
+        let clock = MockClock::new(START);
+        let reg = registry(&clock, false);
+        // Settlement-Backlog über dem Limit ist ebenfalls nicht kritisch
+        reg.register("settlement_queue", false, |_| check_settlement_queue(5_000, &HealthThresholds::default()));
+
+        let report = reg.report();
+        assert_eq!(report.subsystem("ipfs").unwrap().status, HealthStatus::Degraded);
+        assert_eq!(report.subsystem("settlement_queue").unwrap().status, HealthStatus::Unhealthy);
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert!(report.ready);
+        assert_eq!(report.subsystems.len(), 6, "register ersetzt gleichnamige Probe");
+
+        assert_eq!(
+            get(&reg, "/readyz").await,
+            (StatusCode::OK, "ready; degraded: ipfs, settlement_queue".to_string())
+        );
+        assert_eq!(get(&reg, "/health/detailed").await.0, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let t = HealthThresholds::default();
+        assert_eq!(check_dht_peers(0, &t).0, HealthStatus::Unhealthy);
+        assert_eq!(check_dht_peers(1, &t).0, HealthStatus::Degraded);
+        assert_eq!(check_height_lag(100, 90, &t).0, HealthStatus::Healthy);
+        assert_eq!(check_height_lag(100, 106, &t).0, HealthStatus::Degraded);
+        assert_eq!(check_height_lag(100, 111, &t).0, HealthStatus::Unhealthy);
+        assert_eq!(check_db(Err("io".into())).0, HealthStatus::Unhealthy);
+        assert_eq!(check_price_feed(None, START, &t).0, HealthStatus::Unhealthy);
+        assert_eq!(check_price_feed(Some(START as i64 - 10), START, &t).0, HealthStatus::Healthy);
+    }
+}
@@ -2,8 +2,50 @@
 
 use anyhow::Result;
 use ed25519_dalek::{Signature, PublicKey, Verifier};
+use serde::{Deserialize, Serialize};
 use tracing::{warn, instrument};
 
+/// Rolle eines authentifizierten API-Nutzers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Role {
+    Admin,
+    Fullnode,
+    Trader,
+    ReadOnly,
+}
+
+/// Was eine Route verlangt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Permission {
+    /// Shards, Order-Status, Salden lesen
+    ReadState,
+    /// Orders platzieren/stornieren
+    Trade,
+    /// Eigenen Account abfragen (Saldo, Info, Wallet-Journal); Admin jeden
+    ReadAccount,
+    /// Alle Accounts auflisten
+    ListAccounts,
+    /// 2FA des eigenen Accounts einrichten/ändern (zusätzlich Passwort + TOTP)
+    ManageTwoFactor,
+    /// Shard-Replikation anstoßen
+    ReplicateShards,
+    /// Markt anhalten/fortsetzen (`POST /api/market/halt`)
+    HaltMarket,
+}
+
+impl Role {
+    /// Rechte-Matrix. Admin darf alles, ReadOnly nur lesen.
+    pub fn allows(self, permission: Permission) -> bool {
+        use Permission::*;
+        match self {
+            Role::Admin => true,
+            Role::Fullnode => matches!(permission, ReadState | ReplicateShards),
+            Role::Trader => matches!(permission, ReadState | Trade | ReadAccount | ManageTwoFactor),
+            Role::ReadOnly => matches!(permission, ReadState),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct AccessPolicy {
     pub allowed_pubkeys: Vec<Vec<u8>>, // Byte-Arrays
@@ -20,3 +62,26 @@ pub fn verify_message(pubkey: &[u8], message: &[u8], signature: &[u8]) -> Result
     let sig = Signature::from_bytes(signature)?;
     Ok(pk.verify(message, &sig).is_ok())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_matrix() {
+        use Permission::*;
+        for p in [ReadState, Trade, ReadAccount, ListAccounts, ManageTwoFactor, ReplicateShards, HaltMarket] {
+            assert!(Role::Admin.allows(p));
+        }
+        assert!(Role::Trader.allows(Trade));
+        assert!(!Role::Trader.allows(ListAccounts));
+        assert!(!Role::Trader.allows(HaltMarket));
+        assert!(Role::Trader.allows(ManageTwoFactor));
+        assert!(!Role::ReadOnly.allows(ManageTwoFactor));
+        assert!(Role::Fullnode.allows(ReplicateShards));
+        assert!(!Role::Fullnode.allows(Trade));
+        assert!(Role::ReadOnly.allows(ReadState));
+        assert!(!Role::ReadOnly.allows(Trade));
+        assert!(!Role::ReadOnly.allows(ReadAccount));
+    }
+}
@@ -12,6 +12,9 @@ use crate::storage::db_layer::DexDB;
 use crate::identity::wallet::{
     WalletInfo, WalletManager, BlockchainType
 };
+use crate::sanctions::sanctions_list::{global_sanctions, ScreeningSubject};
+use crate::utils::clock::{system_clock, SharedClock};
+use crate::utils::lock::LockRecover;
 
 use totp_rs::{TOTP, Algorithm};  // Für echte 2FA-Unterstützung (OTP)
 
@@ -31,6 +34,11 @@ pub enum AccountType {
 /// - country: Land, wichtig für Spenden (dort soll eine real existierende Institution spendenfähig sein).
 /// - two_fa_secret: Ein geheimer Key für TOTP (2FA). Wird beim NormalUser oder Dev erzeugt, falls 2FA aktiv.
 /// - hashed_password: Das (stark gehashte!) Passwort.
+/// - recovery_code_hashes: SHA-256 der noch unbenutzten Recovery-Codes (Klartext nie gespeichert).
+/// - last_totp_step: zuletzt akzeptierter TOTP-Zeitschritt; Codes bis einschließlich
+///   dieses Schritts werden abgelehnt (kein Replay innerhalb des Gültigkeitsfensters).
+/// - pending_two_fa_secret: per `enroll_2fa` ausgegebenes Secret, das erst mit dem
+///   ersten gültigen Code (`confirm_2fa`) zu `two_fa_secret` wird.
 ///
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Account {
@@ -46,24 +54,95 @@ pub struct Account {
 
     pub two_fa_secret: Option<String>,
     pub hashed_password: Option<String>,
+    #[serde(default)]
+    pub recovery_code_hashes: Vec<String>,
+    #[serde(default)]
+    pub last_totp_step: Option<u64>,
+    #[serde(default)]
+    pub pending_two_fa_secret: Option<String>,
 
     // (NEU) => Hilfsfeld, falls wir die Accounts nicht physisch löschen,
     // sondern nur active = false setzen möchten.
     pub active: bool,
 }
 
+/// Öffentliche Sicht auf einen Account für die REST-API.
+/// Enthält bewusst KEINE Passwort-Hashes, 2FA-Secrets oder Wallet-IDs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AccountSummary {
+    pub user_id: String,
+    pub account_type: AccountType,
+    pub paused: bool,
+    pub active: bool,
+    pub is_fee_pool_recipient: bool,
+    pub fee_share_percent: f64,
+}
+
+impl From<&Account> for AccountSummary {
+    fn from(acc: &Account) -> Self {
+        Self {
+            user_id: acc.user_id.clone(),
+            account_type: acc.account_type.clone(),
+            paused: acc.paused,
+            active: acc.active,
+            is_fee_pool_recipient: acc.is_fee_pool_recipient,
+            fee_share_percent: acc.fee_share_percent,
+        }
+    }
+}
+
+/// Eine Seite aus `list_accounts`. `next_cursor` ist `None` auf der letzten Seite.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountPage {
+    pub accounts: Vec<AccountSummary>,
+    pub next_cursor: Option<String>,
+}
+
+/// Obergrenze für `limit` in `list_accounts`.
+pub const MAX_ACCOUNTS_PAGE: usize = 100;
+
+const ACCOUNTS_PREFIX: &str = "accounts/";
+
+/// Fehlgeschlagene 2FA-Versuche (Code oder Passwort) je User, bevor gesperrt wird.
+pub const MAX_2FA_FAILURES: u32 = 5;
+/// Fehlgeschlagene 2FA-Versuche je Client (IP bzw. Token), über alle Accounts.
+pub const MAX_2FA_FAILURES_PER_CLIENT: u32 = 20;
+/// Fenster, in dem die Fehlversuche gezählt werden.
+pub const TWO_FA_FAILURE_WINDOW_SECS: u64 = 300;
+/// Länge eines TOTP-Zeitschritts.
+pub const TOTP_STEP_SECS: u64 = 30;
+/// Anzahl Recovery-Codes pro `generate_recovery_codes`.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
 /// Der zentrale Manager für Accounts.
 /// Er verwaltet das Anlegen/Pflegen von Accounts und nutzt den WalletManager
 /// für das Handling der zugehörigen Wallets.
 pub struct AccountsManager {
     pub db: Arc<Mutex<DexDB>>,
     pub wallet_manager: WalletManager,
+    /// user_id => (Beginn des Fensters, Fehlversuche darin)
+    two_fa_failures: Mutex<HashMap<String, (u64, u32)>>,
+    /// Client => (Beginn des Fensters, Fehlversuche darin)
+    two_fa_client_failures: Mutex<HashMap<String, (u64, u32)>>,
+    clock: SharedClock,
 }
 
 impl AccountsManager {
     /// Erzeugt einen neuen AccountsManager.
     pub fn new(db: Arc<Mutex<DexDB>>, wallet_manager: WalletManager) -> Self {
-        Self { db, wallet_manager }
+        Self {
+            db,
+            wallet_manager,
+            two_fa_failures: Mutex::new(HashMap::new()),
+            two_fa_client_failures: Mutex::new(HashMap::new()),
+            clock: system_clock(),
+        }
+    }
+
+    /// Uhr für das Fehlversuch-Fenster und die TOTP-Prüfung (Tests: MockClock).
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
     }
 
     // -----------------------------------------------------------------------------------
@@ -91,14 +170,14 @@ impl AccountsManager {
     /// Lädt einen Account aus der DB.
     fn db_load_account(&self, user_id: &str) -> Result<Option<Account>, DexError> {
         let key = format!("accounts/{}", user_id);
-        let lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock = self.db.lock_recover();
         lock.load_struct::<Account>(&key)
     }
 
     /// Speichert/aktualisiert einen Account in der DB.
     fn db_store_account(&self, acc: &Account) -> Result<(), DexError> {
         let key = format!("accounts/{}", acc.user_id);
-        let lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock = self.db.lock_recover();
         lock.store_struct(&key, acc)?;
         Ok(())
     }
@@ -106,11 +185,9 @@ impl AccountsManager {
     /// Bestimmt die Summe aller OnChain- + Dex-Balances des Accounts.
     fn compute_total_balance(&self, acc: &Account) -> Result<f64, DexError> {
         let mut sum = 0.0;
-        for w_id in &acc.wallet_ids {
-            if let Some(w) = self.wallet_manager.load_wallet(w_id)? {
-                sum += w.dex_balance;
-                sum += w.onchain_balance;
-            }
+        for w in self.wallet_manager.load_wallets(&acc.wallet_ids)?.into_iter().flatten() {
+            sum += w.dex_balance;
+            sum += w.onchain_balance;
         }
         Ok(sum)
     }
@@ -175,7 +252,7 @@ impl AccountsManager {
         country: Option<String>,
     ) -> Result<(), DexError> {
         let key = format!("accounts/{}", user_id);
-        let mut lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let mut lock = self.db.lock_recover();
         if let Some(_) = lock.load_struct::<Account>(&key)? {
             return Err(DexError::AccountAlreadyExists(user_id.into()));
         }
@@ -191,6 +268,9 @@ impl AccountsManager {
             country,
             two_fa_secret: None,
             hashed_password: Some(self.hash_password(password)),
+            recovery_code_hashes: Vec::new(),
+            last_totp_step: None,
+            pending_two_fa_secret: None,
             active: true,
         };
         self.db_store_account(&acc)?;
@@ -223,7 +303,7 @@ impl AccountsManager {
         country: Option<String>,
     ) -> Result<(), DexError> {
         let key = format!("accounts/{}", user_id);
-        let mut lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let mut lock = self.db.lock_recover();
         if let Some(_) = lock.load_struct::<Account>(&key)? {
             return Err(DexError::AccountAlreadyExists(user_id.into()));
         }
@@ -247,6 +327,9 @@ impl AccountsManager {
             country,
             two_fa_secret: totp_secret,
             hashed_password: Some(self.hash_password(password)),
+            recovery_code_hashes: Vec::new(),
+            last_totp_step: None,
+            pending_two_fa_secret: None,
             active: true,
         };
         self.db_store_account(&acc)?;
@@ -284,7 +367,7 @@ impl AccountsManager {
             return Err(DexError::Other("fee_share muss in (0,1] liegen".into()));
         }
         let key = format!("accounts/{}", user_id);
-        let mut lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let mut lock = self.db.lock_recover();
         if let Some(_) = lock.load_struct::<Account>(&key)? {
             return Err(DexError::AccountAlreadyExists(user_id.into()));
         }
@@ -307,6 +390,9 @@ impl AccountsManager {
             country,
             two_fa_secret: totp_secret,
             hashed_password: Some(self.hash_password(password)),
+            recovery_code_hashes: Vec::new(),
+            last_totp_step: None,
+            pending_two_fa_secret: None,
             active: true,
         };
         self.db_store_account(&acc)?;
@@ -345,71 +431,43 @@ impl AccountsManager {
         Ok(acc)
     }
 
-    /// NormalUser => user+pass => optional TOTP => verify
+    /// NormalUser => user+pass => optional TOTP (oder Recovery-Code) => verify
     pub fn login_normal_user(
         &self,
         user_id: &str,
         pass: &str,
         twofa_code: Option<&str>,
     ) -> Result<Account, DexError> {
-        let acc = self.load_account_checked(user_id, AccountType::NormalUser)?;
+        let mut acc = self.load_account_checked(user_id, AccountType::NormalUser)?;
         self.check_password(&acc, pass)?;
         if !acc.active {
             return Err(DexError::Other("Dieser Account ist nicht aktiv.".into()));
         }
 
-        if let Some(sec) = &acc.two_fa_secret {
-            if twofa_code.is_none() {
-                return Err(DexError::Other("2FA code required".into()));
-            }
-            let user_supplied_code = twofa_code.unwrap();
-            let totp = TOTP::new(
-                Algorithm::SHA1,
-                6,              
-                1,              
-                30,             
-                sec.as_bytes()
-            ).map_err(|e| DexError::Other(format!("TOTP error: {:?}", e)))?;
-            let is_ok = totp.check_current(user_supplied_code)
-                .map_err(|e| DexError::Other(format!("TOTP check error: {:?}", e)))?;
-            if !is_ok {
-                return Err(DexError::Other("Invalid 2FA code".into()));
-            }
+        if acc.two_fa_secret.is_some() {
+            let code = twofa_code.ok_or_else(|| DexError::Other("2FA code required".into()))?;
+            self.verify_login_code(&mut acc, code)?;
         }
         info!("Login NormalUser => user_id={}", user_id);
         Ok(acc)
     }
 
-    /// (NEU) Dev => user+pass => optional TOTP => verify
+    /// (NEU) Dev => user+pass => optional TOTP (oder Recovery-Code) => verify
     pub fn login_dev_account(
         &self,
         user_id: &str,
         pass: &str,
         twofa_code: Option<&str>,
     ) -> Result<Account, DexError> {
-        let acc = self.load_account_checked(user_id, AccountType::Dev)?;
+        let mut acc = self.load_account_checked(user_id, AccountType::Dev)?;
         self.check_password(&acc, pass)?;
         if !acc.active {
             return Err(DexError::Other("Dev-Account ist inaktiv.".into()));
         }
 
-        if let Some(sec) = &acc.two_fa_secret {
-            if twofa_code.is_none() {
-                return Err(DexError::Other("2FA code required for dev".into()));
-            }
-            let user_supplied_code = twofa_code.unwrap();
-            let totp = TOTP::new(
-                Algorithm::SHA1,
-                6,              
-                1,              
-                30,             
-                sec.as_bytes()
-            ).map_err(|e| DexError::Other(format!("TOTP error: {:?}", e)))?;
-            let is_ok = totp.check_current(user_supplied_code)
-                .map_err(|e| DexError::Other(format!("TOTP check error: {:?}", e)))?;
-            if !is_ok {
-                return Err(DexError::Other("Invalid 2FA code for dev".into()));
-            }
+        if acc.two_fa_secret.is_some() {
+            let code = twofa_code.ok_or_else(|| DexError::Other("2FA code required for dev".into()))?;
+            self.verify_login_code(&mut acc, code)?;
         }
         info!("Login Dev => user_id={}", user_id);
         Ok(acc)
@@ -434,15 +492,225 @@ impl AccountsManager {
     fn check_password(&self, acc: &Account, pass: &str) -> Result<(), DexError> {
         let hashed = self.hash_password(pass);
         if acc.hashed_password.as_deref() != Some(&hashed) {
-            return Err(DexError::Other("Invalid password".into()));
+            return Err(DexError::InvalidCredentials("password".into()));
         }
         Ok(())
     }
 
+    // -----------------------------------------------------------------------------------
+    // 2FA: Einrichten, Abschalten, Rotieren, Recovery-Codes
+    //
+    // Jede Änderung verlangt das Passwort; sobald 2FA aktiv ist, zusätzlich den
+    // aktuellen TOTP-Code (Recovery-Codes gelten nur beim Login). Fehlversuche
+    // zählen je User (MAX_2FA_FAILURES) und je `client` (MAX_2FA_FAILURES_PER_CLIENT,
+    // z.B. die IP des Aufrufers); ist eines davon erreicht => RateLimited.
+    // -----------------------------------------------------------------------------------
+
+    /// Beginnt die 2FA-Einrichtung und gibt das neue TOTP-Secret (base32) zurück.
+    /// Aktiv wird es erst mit `confirm_2fa`; bis dahin bleibt der Login ohne Code
+    /// möglich, und ein erneutes Enroll ersetzt das offene Secret.
+    pub fn enroll_2fa(&self, user_id: &str, pass: &str, client: &str) -> Result<String, DexError> {
+        let mut acc = self.load_for_2fa(user_id, pass, client)?;
+        if acc.two_fa_secret.is_some() {
+            return Err(DexError::Other(format!("2FA für {} ist bereits aktiv", user_id)));
+        }
+        let secret = totp_generate_secret_20_bytes()?;
+        acc.pending_two_fa_secret = Some(secret.clone());
+        self.db_store_account(&acc)?;
+        info!("2FA-Einrichtung begonnen => user_id={}", user_id);
+        Ok(secret)
+    }
+
+    /// Aktiviert das per `enroll_2fa` ausgegebene Secret, sobald ein gültiger
+    /// Code dazu vorliegt (die Authenticator-App ist also eingerichtet).
+    pub fn confirm_2fa(&self, user_id: &str, pass: &str, totp_code: &str, client: &str) -> Result<(), DexError> {
+        let mut acc = self.load_for_2fa(user_id, pass, client)?;
+        let secret = acc
+            .pending_two_fa_secret
+            .clone()
+            .ok_or_else(|| DexError::Other(format!("Keine offene 2FA-Einrichtung für {}", user_id)))?;
+        let Some(step) = totp_step(&secret, totp_code, self.clock.unix_secs())? else {
+            self.record_client_failure(client);
+            self.record_2fa_failure(user_id);
+            return Err(DexError::InvalidCredentials("2FA code".into()));
+        };
+        self.clear_2fa_failures(user_id);
+        acc.two_fa_secret = Some(secret);
+        acc.pending_two_fa_secret = None;
+        acc.recovery_code_hashes.clear();
+        acc.last_totp_step = Some(step);
+        self.db_store_account(&acc)?;
+        info!("2FA aktiviert => user_id={}", user_id);
+        Ok(())
+    }
+
+    /// Schaltet 2FA ab; verwirft Secret und alle Recovery-Codes.
+    pub fn disable_2fa(&self, user_id: &str, pass: &str, totp_code: &str, client: &str) -> Result<(), DexError> {
+        let mut acc = self.load_for_2fa(user_id, pass, client)?;
+        self.verify_current_totp(&mut acc, totp_code, client)?;
+        acc.two_fa_secret = None;
+        acc.recovery_code_hashes.clear();
+        acc.last_totp_step = None;
+        self.db_store_account(&acc)?;
+        info!("2FA deaktiviert => user_id={}", user_id);
+        Ok(())
+    }
+
+    /// Ersetzt das TOTP-Secret; das alte ist danach ungültig.
+    /// Recovery-Codes bleiben bestehen.
+    pub fn rotate_2fa_secret(&self, user_id: &str, pass: &str, totp_code: &str, client: &str) -> Result<String, DexError> {
+        let mut acc = self.load_for_2fa(user_id, pass, client)?;
+        self.verify_current_totp(&mut acc, totp_code, client)?;
+        let secret = totp_generate_secret_20_bytes()?;
+        acc.two_fa_secret = Some(secret.clone());
+        // Neues Secret => verbrauchte Schritte des alten zählen nicht mehr
+        acc.last_totp_step = None;
+        self.db_store_account(&acc)?;
+        info!("2FA-Secret rotiert => user_id={}", user_id);
+        Ok(secret)
+    }
+
+    /// Erzeugt RECOVERY_CODE_COUNT neue Einmal-Codes und ersetzt die alten.
+    /// Gespeichert werden nur die Hashes, der Klartext geht einmalig an den Nutzer.
+    pub fn generate_recovery_codes(&self, user_id: &str, pass: &str, totp_code: &str, client: &str) -> Result<Vec<String>, DexError> {
+        let mut acc = self.load_for_2fa(user_id, pass, client)?;
+        self.verify_current_totp(&mut acc, totp_code, client)?;
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+        acc.recovery_code_hashes = codes.iter().map(|c| hash_recovery_code(c)).collect();
+        self.db_store_account(&acc)?;
+        info!("{} Recovery-Codes erzeugt => user_id={}", codes.len(), user_id);
+        Ok(codes)
+    }
+
+    /// Lädt den Account für eine 2FA-Änderung: Limits prüfen, dann Passwort.
+    fn load_for_2fa(&self, user_id: &str, pass: &str, client: &str) -> Result<Account, DexError> {
+        self.ensure_client_attempts_left(client)?;
+        self.ensure_2fa_attempts_left(user_id)?;
+        let acc = self.db_load_account(user_id)?
+            .ok_or(DexError::AccountNotFound(user_id.into()))?;
+        if let Err(e) = self.check_password(&acc, pass) {
+            self.record_client_failure(client);
+            self.record_2fa_failure(user_id);
+            return Err(e);
+        }
+        if !acc.active {
+            return Err(DexError::Other("Dieser Account ist nicht aktiv.".into()));
+        }
+        Ok(acc)
+    }
+
+    /// Nur der aktuelle TOTP-Code zählt, kein Recovery-Code. Der akzeptierte
+    /// Zeitschritt landet in `acc.last_totp_step` (speichert der Aufrufer).
+    fn verify_current_totp(&self, acc: &mut Account, code: &str, client: &str) -> Result<(), DexError> {
+        if !self.accept_totp(acc, code)? {
+            self.record_client_failure(client);
+            self.record_2fa_failure(&acc.user_id);
+            return Err(DexError::InvalidCredentials("2FA code".into()));
+        }
+        self.clear_2fa_failures(&acc.user_id);
+        Ok(())
+    }
+
+    /// Prüft `code` gegen das Secret und verbraucht den Zeitschritt. Ein Code,
+    /// dessen Schritt nicht nach `last_totp_step` liegt, gilt als Replay.
+    fn accept_totp(&self, acc: &mut Account, code: &str) -> Result<bool, DexError> {
+        let secret = acc.two_fa_secret.as_deref()
+            .ok_or_else(|| DexError::Other(format!("2FA für {} ist nicht aktiv", acc.user_id)))?;
+        let Some(step) = totp_step(secret, code, self.clock.unix_secs())? else {
+            return Ok(false);
+        };
+        if acc.last_totp_step.is_some_and(|last| step <= last) {
+            warn!("TOTP-Code für {} bereits verwendet (Schritt {})", acc.user_id, step);
+            return Ok(false);
+        }
+        acc.last_totp_step = Some(step);
+        Ok(true)
+    }
+
+    /// Login: TOTP-Code oder ein unbenutzter Recovery-Code. Ein passender
+    /// Recovery-Code wird sofort aus dem Account entfernt.
+    fn verify_login_code(&self, acc: &mut Account, code: &str) -> Result<(), DexError> {
+        self.ensure_2fa_attempts_left(&acc.user_id)?;
+        let totp_ok = acc.two_fa_secret.is_some() && self.accept_totp(acc, code)?;
+        if totp_ok {
+            self.db_store_account(acc)?;
+            self.clear_2fa_failures(&acc.user_id);
+            return Ok(());
+        }
+        let hashed = hash_recovery_code(code);
+        if let Some(pos) = acc.recovery_code_hashes.iter().position(|h| *h == hashed) {
+            acc.recovery_code_hashes.remove(pos);
+            self.db_store_account(acc)?;
+            self.clear_2fa_failures(&acc.user_id);
+            warn!("Login per Recovery-Code => user_id={}, {} Codes übrig", acc.user_id, acc.recovery_code_hashes.len());
+            return Ok(());
+        }
+        self.record_2fa_failure(&acc.user_id);
+        Err(DexError::InvalidCredentials("2FA code".into()))
+    }
+
+    fn ensure_2fa_attempts_left(&self, user_id: &str) -> Result<(), DexError> {
+        let now = self.clock.unix_secs();
+        match self.two_fa_failures.lock_recover().get(user_id) {
+            Some(&(since, count)) if now < since + TWO_FA_FAILURE_WINDOW_SECS && count >= MAX_2FA_FAILURES => {
+                warn!("2FA für {} gesperrt: {} Fehlversuche", user_id, count);
+                Err(DexError::RateLimited(user_id.into()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn record_2fa_failure(&self, user_id: &str) {
+        let now = self.clock.unix_secs();
+        let mut failures = self.two_fa_failures.lock_recover();
+        let entry = failures.entry(user_id.to_string()).or_insert((now, 0));
+        if now >= entry.0 + TWO_FA_FAILURE_WINDOW_SECS {
+            *entry = (now, 0);
+        }
+        entry.1 += 1;
+    }
+
+    fn clear_2fa_failures(&self, user_id: &str) {
+        self.two_fa_failures.lock_recover().remove(user_id);
+    }
+
+    /// Client-Limit: wird nicht durch Erfolge zurückgesetzt, nur durch das Fenster.
+    fn ensure_client_attempts_left(&self, client: &str) -> Result<(), DexError> {
+        let now = self.clock.unix_secs();
+        match self.two_fa_client_failures.lock_recover().get(client) {
+            Some(&(since, count)) if now < since + TWO_FA_FAILURE_WINDOW_SECS && count >= MAX_2FA_FAILURES_PER_CLIENT => {
+                warn!("2FA-Routen für Client {} gesperrt: {} Fehlversuche", client, count);
+                Err(DexError::RateLimited(client.into()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn record_client_failure(&self, client: &str) {
+        let now = self.clock.unix_secs();
+        let mut failures = self.two_fa_client_failures.lock_recover();
+        failures.retain(|_, (since, _)| now < *since + TWO_FA_FAILURE_WINDOW_SECS);
+        let entry = failures.entry(client.to_string()).or_insert((now, 0));
+        entry.1 += 1;
+    }
+
     // -----------------------------------------------------------------------------------
     // Kontoverwaltung: Pausieren, Löschen, Spenden
     // -----------------------------------------------------------------------------------
 
+    /// Darf der Account neue Orders platzieren? (existiert, aktiv, nicht pausiert)
+    pub fn ensure_can_trade(&self, user_id: &str) -> Result<(), DexError> {
+        let acc = self.db_load_account(user_id)?
+            .ok_or(DexError::AccountNotFound(user_id.to_string()))?;
+        if acc.paused {
+            return Err(DexError::AccountPaused(user_id.to_string()));
+        }
+        if !acc.active {
+            return Err(DexError::Other("Dieser Account ist nicht aktiv.".into()));
+        }
+        Ok(())
+    }
+
     /// Pause => Account kann nicht mehr handeln
     pub fn pause_account(&self, user_id: &str) -> Result<(), DexError> {
         let mut acc = self.db_load_account(user_id)?
@@ -472,7 +740,7 @@ impl AccountsManager {
         }
 
         let key = format!("accounts/{}", user_id);
-        let lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock = self.db.lock_recover();
         if let Some(rdb) = &lock.rocks {
             rdb.delete(key.as_bytes())
                 .map_err(|e| DexError::Other(format!("rocksdb delete: {:?}", e)))?;
@@ -508,8 +776,9 @@ impl AccountsManager {
         let mut acc = self.db_load_account(user_id)?
             .ok_or(DexError::AccountNotFound(user_id.to_string()))?;
 
-        for w_id in &acc.wallet_ids {
-            let mut wallet = match self.wallet_manager.load_wallet(w_id)? {
+        let wallets = self.wallet_manager.load_wallets(&acc.wallet_ids)?;
+        for (w_id, loaded) in acc.wallet_ids.iter().zip(wallets) {
+            let mut wallet = match loaded {
                 Some(x) => x,
                 None => {
                     error!("Wallet {} not found => skipping donation for user {}", w_id, user_id);
@@ -532,7 +801,10 @@ impl AccountsManager {
             // Dex-Balance => spende
             if wallet.dex_balance > 0.0 {
                 let dex_amt = wallet.dex_balance;
-                self.wallet_manager.sub_dex_balance(w_id, dex_amt)?;
+                let posting = crate::identity::balance_ledger::Posting::new(
+                    "donation:DEX_SPEND_ADDR", "donation", user_id,
+                );
+                self.wallet_manager.sub_dex_balance(w_id, dex_amt, posting)?;
                 info!("Dex-Spende => wallet={} amount={} an {} (chain={:?})",
                     w_id, dex_amt, "DEX_SPEND_ADDR", wallet.blockchain);
             }
@@ -540,6 +812,70 @@ impl AccountsManager {
         Ok(())
     }
 
+    /// On-Chain-Adressen aller Wallets eines Accounts.
+    pub fn wallet_addresses(&self, user_id: &str) -> Result<Vec<String>, DexError> {
+        let acc = self.db_load_account(user_id)?
+            .ok_or(DexError::AccountNotFound(user_id.to_string()))?;
+        Ok(self.wallet_manager
+            .load_wallets(&acc.wallet_ids)?
+            .into_iter()
+            .flatten()
+            .map(|w: WalletInfo| w.address)
+            .collect())
+    }
+
+    /// Gehört `wallet_id` zu den Wallets von `user_id`? Unbekannter Account => false.
+    pub fn owns_wallet(&self, user_id: &str, wallet_id: &str) -> Result<bool, DexError> {
+        Ok(self.db_load_account(user_id)?
+            .map(|acc| acc.wallet_ids.iter().any(|w| w == wallet_id))
+            .unwrap_or(false))
+    }
+
+    /// Wallet-Adressen und Land eines Accounts für das Sanktions-Screening.
+    pub fn screening_subject(&self, user_id: &str) -> Result<ScreeningSubject, DexError> {
+        let acc = self.db_load_account(user_id)?
+            .ok_or(DexError::AccountNotFound(user_id.to_string()))?;
+        Ok(ScreeningSubject {
+            addresses: self.wallet_addresses(user_id)?,
+            country: acc.country,
+        })
+    }
+
+    /// Sanktions-Screening eines Accounts: Land (Jurisdiktion) und alle Wallet-Adressen.
+    /// Liefert `DexError::SanctionedParty` beim ersten Treffer.
+    pub fn screen_sanctions(&self, user_id: &str) -> Result<(), DexError> {
+        let subject = self.screening_subject(user_id)?;
+        let result = global_sanctions().screen_subject(&subject);
+        if let Err(ref e) = result {
+            warn!("Sanktions-Treffer für user_id={} => {}", user_id, e);
+        }
+        result
+    }
+
+    /// Blättert sortiert nach user_id durch alle Accounts.
+    /// `cursor` ist die user_id des letzten Eintrags der vorherigen Seite.
+    pub fn list_accounts(&self, cursor: Option<&str>, limit: usize) -> Result<AccountPage, DexError> {
+        let limit = limit.clamp(1, MAX_ACCOUNTS_PAGE);
+        let start_after = cursor.map(|c| format!("{}{}", ACCOUNTS_PREFIX, c));
+        let lock = self.db.lock_recover();
+        let (entries, next_key) = lock.list_entries_page(ACCOUNTS_PREFIX, start_after.as_deref(), limit)?;
+        drop(lock);
+
+        let mut accounts = Vec::with_capacity(entries.len());
+        for (key, bytes) in entries {
+            let acc: Account = bincode::deserialize(&bytes)
+                .map_err(|e| DexError::Other(format!("deserialize error for {}: {:?}", key, e)))?;
+            accounts.push(AccountSummary::from(&acc));
+        }
+        let next_cursor = next_key.map(|k| k.trim_start_matches(ACCOUNTS_PREFIX).to_string());
+        Ok(AccountPage { accounts, next_cursor })
+    }
+
+    /// Nicht-sensitive Daten eines einzelnen Accounts.
+    pub fn account_info(&self, user_id: &str) -> Result<Option<AccountSummary>, DexError> {
+        Ok(self.db_load_account(user_id)?.as_ref().map(AccountSummary::from))
+    }
+
     // (NEU) => Fee-Share anpassen (z.B. bei Dev-Account).
     // Nur Accounts, die is_fee_pool_recipient=true haben => wir updaten fee_share_percent.
     pub fn set_fee_share_percent(&self, user_id: &str, new_share: f64) -> Result<(), DexError> {
@@ -595,3 +931,292 @@ fn totp_generate_secret_20_bytes() -> Result<String, DexError> {
     let base32_secret = base32::encode(base32::Alphabet::RFC4648 { padding: false }, &buf);
     Ok(base32_secret)
 }
+
+// TOTP: SHA1, 6 Stellen, 1 Schritt Toleranz, 30s. Der Schlüssel ist das
+// base32-dekodierte Secret – genau das, was Authenticator-Apps aus dem
+// ausgegebenen String machen.
+fn totp_for(secret: &str) -> Result<TOTP, DexError> {
+    let key = base32::decode(base32::Alphabet::RFC4648 { padding: false }, secret.trim_end_matches('='))
+        .ok_or_else(|| DexError::Other("TOTP-Secret ist kein gültiges base32".into()))?;
+    TOTP::new(Algorithm::SHA1, 6, 1, TOTP_STEP_SECS, key).map_err(|e| DexError::Other(format!("TOTP error: {:?}", e)))
+}
+
+// Liefert den Zeitschritt, zu dem `code` passt (für den Replay-Schutz).
+fn totp_step(secret: &str, code: &str, now: u64) -> Result<Option<u64>, DexError> {
+    let totp = totp_for(secret)?;
+    let current = now / TOTP_STEP_SECS;
+    Ok((current.saturating_sub(1)..=current + 1).find(|step| {
+        let expected = totp.generate(step * TOTP_STEP_SECS);
+        // Vergleich ohne frühen Abbruch
+        expected.len() == code.len()
+            && expected.bytes().zip(code.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+    }))
+}
+
+// Recovery-Code => 10 Bytes random => base32 => "XXXX-XXXX-XXXX-XXXX"
+fn generate_recovery_code() -> String {
+    let mut buf = [0u8; 10];
+    OsRng.fill_bytes(&mut buf);
+    let raw = base32::encode(base32::Alphabet::RFC4648 { padding: false }, &buf);
+    raw.as_bytes()
+        .chunks(4)
+        .map(|c| String::from_utf8_lossy(c).into_owned())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// Bindestriche/Leerzeichen und Groß-/Kleinschreibung spielen keine Rolle
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    format!("sha256:{}", hex::encode(Sha256::digest(normalized.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::db_layer::InMemoryDb;
+
+    fn mem_db() -> DexDB {
+        DexDB { rocks: None, fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))) }
+    }
+
+    fn account(user_id: &str) -> Account {
+        Account {
+            user_id: user_id.to_string(),
+            account_type: AccountType::NormalUser,
+            is_fee_pool_recipient: false,
+            fee_share_percent: 0.0,
+            wallet_ids: vec!["w1".into()],
+            paused: false,
+            country: None,
+            two_fa_secret: Some("JBSWY3DPEHPK3PXP".into()),
+            hashed_password: Some("sha256:deadbeef".into()),
+            recovery_code_hashes: Vec::new(),
+            last_totp_step: None,
+            pending_two_fa_secret: None,
+            active: true,
+        }
+    }
+
+    fn manager_with(db: DexDB, users: &[&str]) -> AccountsManager {
+        let mgr = AccountsManager::new(Arc::new(Mutex::new(db)), WalletManager::new(mem_db(), None, None, None));
+        for u in users {
+            mgr.db_store_account(&account(u)).unwrap();
+        }
+        // Fremder Prefix darf nicht in der Liste auftauchen
+        mgr.db.lock_recover().store_struct("accountsX/zzz", &1u8).unwrap();
+        mgr
+    }
+
+    fn collect_all(mgr: &AccountsManager, limit: usize) -> Vec<Vec<String>> {
+        let mut pages = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = mgr.list_accounts(cursor.as_deref(), limit).unwrap();
+            pages.push(page.accounts.iter().map(|a| a.user_id.clone()).collect());
+            match page.next_cursor {
+                Some(c) => cursor = Some(c),
+                None => break,
+            }
+        }
+        pages
+    }
+
+    #[test]
+    fn test_list_accounts_cursor_pagination() {
+        let users = ["alice", "bob", "carol", "dave", "erin"];
+        let dir = tempfile::tempdir().unwrap();
+        let rocks = DexDB::open(dir.path().to_str().unwrap()).unwrap();
+        for db in [rocks, mem_db()] {
+            let mgr = manager_with(db, &users);
+            let pages = collect_all(&mgr, 2);
+            assert_eq!(
+                pages,
+                vec![vec!["alice", "bob"], vec!["carol", "dave"], vec!["erin"]]
+            );
+            // Genau passende Seitengröße => keine leere Folgeseite
+            assert_eq!(collect_all(&mgr, 5).len(), 1);
+            // Cursor hinter dem letzten Account => leere Seite
+            let tail = mgr.list_accounts(Some("erin"), 10).unwrap();
+            assert!(tail.accounts.is_empty() && tail.next_cursor.is_none());
+        }
+    }
+
+    #[test]
+    fn test_account_summary_omits_sensitive_fields() {
+        let mgr = manager_with(mem_db(), &["alice"]);
+        let info = mgr.account_info("alice").unwrap().unwrap();
+        let json = serde_json::to_value(&info).unwrap();
+        let obj = json.as_object().unwrap();
+        assert!(!obj.contains_key("hashed_password"));
+        assert!(!obj.contains_key("two_fa_secret"));
+        assert!(!obj.contains_key("wallet_ids"));
+        assert_eq!(obj["active"], true);
+        assert!(mgr.account_info("nobody").unwrap().is_none());
+    }
+
+    /// TOTP-Code zur Uhr des Managers, wie ihn eine Authenticator-App aus dem
+    /// base32-Secret berechnet.
+    fn current_code(mgr: &AccountsManager, secret: &str) -> String {
+        code_at(secret, mgr.clock.unix_secs())
+    }
+
+    fn code_at(secret: &str, unix_secs: u64) -> String {
+        let key = base32::decode(base32::Alphabet::RFC4648 { padding: false }, secret).unwrap();
+        TOTP::new(Algorithm::SHA1, 6, 1, TOTP_STEP_SECS, key).unwrap().generate(unix_secs)
+    }
+
+    /// Enroll + Bestätigung mit dem Code des vorigen Schritts, damit der
+    /// aktuelle Code danach noch frei ist.
+    fn enroll_confirmed(mgr: &AccountsManager, user_id: &str) -> String {
+        let secret = mgr.enroll_2fa(user_id, "pw", "local").unwrap();
+        let previous = code_at(&secret, mgr.clock.unix_secs() - TOTP_STEP_SECS);
+        mgr.confirm_2fa(user_id, "pw", &previous, "local").unwrap();
+        secret
+    }
+
+    fn user_with_password(mgr: &AccountsManager, user_id: &str, pass: &str) {
+        let mut acc = account(user_id);
+        acc.hashed_password = Some(mgr.hash_password(pass));
+        acc.two_fa_secret = None;
+        mgr.db_store_account(&acc).unwrap();
+    }
+
+    #[test]
+    fn test_recovery_code_logs_in_once() {
+        let mgr = manager_with(mem_db(), &[]);
+        user_with_password(&mgr, "alice", "pw");
+        let secret = enroll_confirmed(&mgr, "alice");
+        let codes = mgr.generate_recovery_codes("alice", "pw", &current_code(&mgr, &secret), "local").unwrap();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        // Nur Hashes in der DB
+        let stored = mgr.db_load_account("alice").unwrap().unwrap();
+        assert!(stored.recovery_code_hashes.iter().all(|h| h.starts_with("sha256:")));
+        assert!(!stored.recovery_code_hashes.contains(&codes[0]));
+
+        mgr.login_normal_user("alice", "pw", Some(&codes[0])).unwrap();
+        assert!(mgr.login_normal_user("alice", "pw", Some(&codes[0])).is_err());
+        // Andere Codes bleiben gültig, Schreibweise egal
+        mgr.login_normal_user("alice", "pw", Some(&codes[1].to_lowercase().replace('-', ""))).unwrap();
+        assert_eq!(mgr.db_load_account("alice").unwrap().unwrap().recovery_code_hashes.len(), RECOVERY_CODE_COUNT - 2);
+    }
+
+    #[test]
+    fn test_disable_2fa_requires_current_code_and_failures_are_limited() {
+        use crate::utils::clock::MockClock;
+        use std::time::Duration;
+
+        let clock = MockClock::new(1_700_000_000);
+        let mgr = manager_with(mem_db(), &[]).with_clock(clock.shared());
+        user_with_password(&mgr, "bob", "pw");
+        let secret = enroll_confirmed(&mgr, "bob");
+        assert!(mgr.enroll_2fa("bob", "pw", "local").is_err());
+        let codes = mgr.generate_recovery_codes("bob", "pw", &current_code(&mgr, &secret), "local").unwrap();
+
+        // Recovery-Code reicht zum Abschalten nicht
+        assert!(matches!(mgr.disable_2fa("bob", "pw", &codes[0], "local"), Err(DexError::InvalidCredentials(_))));
+        assert!(mgr.disable_2fa("bob", "pw", "abcdef", "local").is_err());
+        assert!(mgr.db_load_account("bob").unwrap().unwrap().two_fa_secret.is_some());
+
+        // Nach MAX_2FA_FAILURES Fehlversuchen ist auch der richtige Code gesperrt
+        for _ in 0..MAX_2FA_FAILURES {
+            let _ = mgr.disable_2fa("bob", "wrong", &current_code(&mgr, &secret), "local");
+        }
+        assert!(matches!(
+            mgr.disable_2fa("bob", "pw", &current_code(&mgr, &secret), "local"),
+            Err(DexError::RateLimited(u)) if u == "bob"
+        ));
+
+        clock.advance(Duration::from_secs(TWO_FA_FAILURE_WINDOW_SECS));
+        mgr.disable_2fa("bob", "pw", &current_code(&mgr, &secret), "local").unwrap();
+        let acc = mgr.db_load_account("bob").unwrap().unwrap();
+        assert!(acc.two_fa_secret.is_none() && acc.recovery_code_hashes.is_empty());
+        // Ohne 2FA kein Code mehr nötig
+        mgr.login_normal_user("bob", "pw", None).unwrap();
+    }
+
+    #[test]
+    fn test_totp_code_cannot_be_replayed() {
+        use crate::utils::clock::MockClock;
+        use std::time::Duration;
+
+        let clock = MockClock::new(1_700_000_000);
+        let mgr = manager_with(mem_db(), &[]).with_clock(clock.shared());
+        user_with_password(&mgr, "carol", "pw");
+        let secret = enroll_confirmed(&mgr, "carol");
+        let code = current_code(&mgr, &secret);
+        mgr.generate_recovery_codes("carol", "pw", &code, "local").unwrap();
+
+        // Derselbe Code im selben Fenster => abgelehnt, auch beim Login
+        assert!(matches!(
+            mgr.generate_recovery_codes("carol", "pw", &code, "local"),
+            Err(DexError::InvalidCredentials(_))
+        ));
+        assert!(mgr.login_normal_user("carol", "pw", Some(&code)).is_err());
+
+        // Nächster Schritt gilt einmal, danach auch der vorherige nicht mehr
+        clock.advance(Duration::from_secs(TOTP_STEP_SECS));
+        let next = current_code(&mgr, &secret);
+        mgr.login_normal_user("carol", "pw", Some(&next)).unwrap();
+        assert!(mgr.login_normal_user("carol", "pw", Some(&next)).is_err());
+        assert_eq!(
+            mgr.db_load_account("carol").unwrap().unwrap().last_totp_step,
+            Some(clock.shared().unix_secs() / TOTP_STEP_SECS)
+        );
+    }
+
+    #[test]
+    fn test_2fa_failures_limited_per_client() {
+        let mgr = manager_with(mem_db(), &[]);
+        let users = ["u1", "u2", "u3", "u4", "u5"];
+        for u in users {
+            user_with_password(&mgr, u, "pw");
+        }
+        // Je User unter dem Account-Limit, in Summe über dem Client-Limit
+        for u in &users[..4] {
+            for _ in 0..MAX_2FA_FAILURES_PER_CLIENT / 4 {
+                let _ = mgr.enroll_2fa(u, "wrong", "10.0.0.1");
+            }
+        }
+        assert!(matches!(
+            mgr.enroll_2fa("u5", "pw", "10.0.0.1"),
+            Err(DexError::RateLimited(c)) if c == "10.0.0.1"
+        ));
+        // Andere Clients und der Account selbst sind nicht betroffen
+        mgr.enroll_2fa("u5", "pw", "10.0.0.2").unwrap();
+    }
+
+    #[test]
+    fn test_enrolled_secret_stays_pending_until_confirmed() {
+        use crate::utils::clock::MockClock;
+
+        let clock = MockClock::new(1_700_000_000);
+        let mgr = manager_with(mem_db(), &[]).with_clock(clock.shared());
+        user_with_password(&mgr, "dave", "pw");
+        let first = mgr.enroll_2fa("dave", "pw", "local").unwrap();
+        // Noch nicht aktiv: Login ohne Code klappt, erneutes Enroll ersetzt das Secret
+        mgr.login_normal_user("dave", "pw", None).unwrap();
+        let secret = mgr.enroll_2fa("dave", "pw", "local").unwrap();
+        assert_ne!(first, secret);
+        assert!(matches!(
+            mgr.confirm_2fa("dave", "pw", &current_code(&mgr, &first), "local"),
+            Err(DexError::InvalidCredentials(_))
+        ));
+        // Der Code einer Authenticator-App (base32-dekodierter Schlüssel) bestätigt
+        mgr.confirm_2fa("dave", "pw", &current_code(&mgr, &secret), "local").unwrap();
+        let acc = mgr.db_load_account("dave").unwrap().unwrap();
+        assert_eq!(acc.two_fa_secret.as_deref(), Some(secret.as_str()));
+        assert!(acc.pending_two_fa_secret.is_none());
+        assert!(mgr.login_normal_user("dave", "pw", None).is_err());
+        // Mit den rohen ASCII-Bytes des Secrets berechnete Codes gelten nicht
+        let ascii = TOTP::new(Algorithm::SHA1, 6, 1, TOTP_STEP_SECS, secret.as_bytes().to_vec()).unwrap();
+        clock.advance(std::time::Duration::from_secs(TOTP_STEP_SECS));
+        let wrong = ascii.generate(clock.shared().unix_secs());
+        assert!(mgr.login_normal_user("dave", "pw", Some(&wrong)).is_err());
+        mgr.login_normal_user("dave", "pw", Some(&current_code(&mgr, &secret))).unwrap();
+    }
+}
@@ -0,0 +1,350 @@
+//////////////////////////////////////
+/// my_DEX/src/identity/balance_ledger.rs
+//////////////////////////////////////
+//
+// Append-only Buchungsjournal für Wallet-Balances (doppelte Buchführung).
+//
+// Jede Änderung an `dex_balance` / `onchain_balance` läuft über `post`:
+// Buchung (Soll-Konto, Haben-Konto, Betrag, Grund, Referenz) und neues Wallet
+// werden in einem atomaren Batch geschrieben. Das Wallet-Konto heißt
+// `wallet:<id>:dex` bzw. `wallet:<id>:onchain`, die Gegenseite z. B.
+// `fee_pool`, `donation:<addr>`, `chain:<addr>`.
+//
+// Wallets mit Guthaben aus der Zeit vor dem Journal bekommen bei der ersten
+// Buchung eine `opening_balance`-Eröffnungsbuchung.
+//
+// `post` hält je Wallet eine Sperre und liest Sequenz und Wallet darunter
+// frisch aus der DB: parallele Buchungen auf dasselbe Wallet bekommen
+// fortlaufende Sequenznummern und überschreiben keine Balances.
+//
+// `reconcile` summiert die Buchungen je Konto in Sequenz-Reihenfolge und
+// vergleicht exakt mit dem gespeicherten Wallet (gleiche Rechenschritte =>
+// gleiches f64-Ergebnis).
+//
+// Layout:
+//   balance_ledger/<wallet_id>/<seq:020> => LedgerEntry
+//   balance_ledger_head/<wallet_id>      => nächste Sequenznummer (u64)
+//////////////////////////////////////
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::DexError;
+use crate::identity::wallet::WalletInfo;
+use crate::storage::db_layer::DexDB;
+use crate::utils::lock::LockRecover;
+
+const LEDGER_PREFIX: &str = "balance_ledger/";
+const HEAD_PREFIX: &str = "balance_ledger_head/";
+
+/// Gegenkonto für Eröffnungsbuchungen.
+pub const OPENING_ACCOUNT: &str = "opening_balance";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BalanceKind {
+    Dex,
+    Onchain,
+}
+
+impl BalanceKind {
+    pub fn account(self, wallet_id: &str) -> String {
+        match self {
+            BalanceKind::Dex => format!("wallet:{}:dex", wallet_id),
+            BalanceKind::Onchain => format!("wallet:{}:onchain", wallet_id),
+        }
+    }
+
+    fn balance(self, w: &WalletInfo) -> f64 {
+        match self {
+            BalanceKind::Dex => w.dex_balance,
+            BalanceKind::Onchain => w.onchain_balance,
+        }
+    }
+
+    fn balance_mut(self, w: &mut WalletInfo) -> &mut f64 {
+        match self {
+            BalanceKind::Dex => &mut w.dex_balance,
+            BalanceKind::Onchain => &mut w.onchain_balance,
+        }
+    }
+}
+
+/// Gegenkonto, Grund und Referenz (Trade-ID, TxID, ...) einer Buchung.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Posting {
+    pub counter_account: String,
+    pub reason: String,
+    pub reference: String,
+}
+
+impl Posting {
+    pub fn new(counter_account: impl Into<String>, reason: &str, reference: impl Into<String>) -> Self {
+        Self { counter_account: counter_account.into(), reason: reason.to_string(), reference: reference.into() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LedgerEntry {
+    pub seq: u64,
+    pub wallet_id: String,
+    pub kind: BalanceKind,
+    /// Soll-Konto (gibt ab)
+    pub debit: String,
+    /// Haben-Konto (erhält)
+    pub credit: String,
+    /// Immer > 0
+    pub amount: f64,
+    /// Wallet-Balance dieses Kontos nach der Buchung
+    pub balance_after: f64,
+    pub reason: String,
+    pub reference: String,
+    pub timestamp: u64,
+}
+
+impl LedgerEntry {
+    /// Vorzeichenbehaftete Änderung aus Sicht des Wallet-Kontos.
+    pub fn delta(&self) -> f64 {
+        if self.credit == self.kind.account(&self.wallet_id) {
+            self.amount
+        } else {
+            -self.amount
+        }
+    }
+}
+
+/// Ergebnis eines erfolgreichen Abgleichs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Reconciliation {
+    pub wallet_id: String,
+    pub entries: usize,
+    pub dex_balance: f64,
+    pub onchain_balance: f64,
+}
+
+fn entry_key(wallet_id: &str, seq: u64) -> String {
+    format!("{}{}/{:020}", LEDGER_PREFIX, wallet_id, seq)
+}
+
+fn head_key(wallet_id: &str) -> String {
+    format!("{}{}", HEAD_PREFIX, wallet_id)
+}
+
+// Eine Sperre je Wallet-ID (head lesen => Batch schreiben)
+static WALLET_LOCKS: Lazy<Mutex<HashMap<String, Arc<Mutex<()>>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn wallet_lock(wallet_id: &str) -> Arc<Mutex<()>> {
+    WALLET_LOCKS.lock_recover().entry(wallet_id.to_string()).or_default().clone()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn encode<T: Serialize>(val: &T) -> Result<Vec<u8>, DexError> {
+    bincode::serialize(val).map_err(|e| DexError::Other(format!("serialize: {:?}", e)))
+}
+
+fn make_entry(seq: u64, w: &WalletInfo, kind: BalanceKind, delta: f64, posting: &Posting) -> LedgerEntry {
+    let wallet_account = kind.account(&w.wallet_id);
+    let (debit, credit) = if delta >= 0.0 {
+        (posting.counter_account.clone(), wallet_account)
+    } else {
+        (wallet_account, posting.counter_account.clone())
+    };
+    LedgerEntry {
+        seq,
+        wallet_id: w.wallet_id.clone(),
+        kind,
+        debit,
+        credit,
+        amount: delta.abs(),
+        balance_after: kind.balance(w),
+        reason: posting.reason.clone(),
+        reference: posting.reference.clone(),
+        timestamp: now_secs(),
+    }
+}
+
+/// Bucht `delta` auf `w` und schreibt Wallet + Buchung atomar.
+/// `delta == 0` ist ein No-op. `w` wird auf den gespeicherten Stand nach der
+/// Buchung gesetzt.
+pub fn post(db: &DexDB, w: &mut WalletInfo, kind: BalanceKind, delta: f64, posting: Posting) -> Result<Option<LedgerEntry>, DexError> {
+    post_with(db, w, kind, posting, |_| Ok(delta))
+}
+
+/// Wie `post`, aber `compute` bestimmt den Betrag aus dem unter der
+/// Wallet-Sperre frisch geladenen Wallet (z. B. Deckungsprüfung vor Abbuchung).
+pub fn post_with<F>(db: &DexDB, w: &mut WalletInfo, kind: BalanceKind, posting: Posting, compute: F) -> Result<Option<LedgerEntry>, DexError>
+where
+    F: FnOnce(&WalletInfo) -> Result<f64, DexError>,
+{
+    let lock = wallet_lock(&w.wallet_id);
+    let _guard = lock.lock_recover();
+    if let Some(stored) = db.load_struct::<WalletInfo>(&format!("wallets/{}", w.wallet_id))? {
+        *w = stored;
+    }
+    let delta = compute(w)?;
+    if !delta.is_finite() {
+        return Err(DexError::Other(format!("non-finite ledger delta for wallet {}", w.wallet_id)));
+    }
+    if delta == 0.0 {
+        return Ok(None);
+    }
+    let mut seq = db.load_struct::<u64>(&head_key(&w.wallet_id))?.unwrap_or(0);
+    let mut writes = Vec::new();
+
+    // Erste Buchung: vorhandene Guthaben als Eröffnungsbilanz übernehmen
+    if seq == 0 {
+        for k in [BalanceKind::Dex, BalanceKind::Onchain] {
+            let opening = k.balance(w);
+            if opening != 0.0 {
+                let e = make_entry(seq, w, k, opening, &Posting::new(OPENING_ACCOUNT, "opening_balance", ""));
+                writes.push((entry_key(&w.wallet_id, seq), encode(&e)?));
+                seq += 1;
+            }
+        }
+    }
+
+    *kind.balance_mut(w) += delta;
+    let entry = make_entry(seq, w, kind, delta, &posting);
+    writes.push((entry_key(&w.wallet_id, seq), encode(&entry)?));
+    writes.push((head_key(&w.wallet_id), encode(&(seq + 1))?));
+    writes.push((format!("wallets/{}", w.wallet_id), encode(w)?));
+    db.put_raw_batch(writes)?;
+    Ok(Some(entry))
+}
+
+/// Setzt die Balance auf `target` (z. B. nach RPC-Abfrage) und bucht die Differenz.
+pub fn post_set(db: &DexDB, w: &mut WalletInfo, kind: BalanceKind, target: f64, posting: Posting) -> Result<Option<LedgerEntry>, DexError> {
+    post_with(db, w, kind, posting, |cur| Ok(target - kind.balance(cur)))
+}
+
+/// Alle Buchungen eines Wallets in Sequenz-Reihenfolge.
+pub fn entries(db: &DexDB, wallet_id: &str) -> Result<Vec<LedgerEntry>, DexError> {
+    let prefix = format!("{}{}/", LEDGER_PREFIX, wallet_id);
+    db.list_entries_with_prefix(&prefix)?
+        .into_iter()
+        .map(|(k, v)| {
+            bincode::deserialize(&v).map_err(|e| DexError::DatabaseError(format!("ledger entry {}: {:?}", k, e)))
+        })
+        .collect()
+}
+
+/// Prüft, dass die Buchungen exakt die gespeicherten Balances ergeben.
+pub fn reconcile(db: &DexDB, wallet_id: &str) -> Result<Reconciliation, DexError> {
+    let w = db
+        .load_struct::<WalletInfo>(&format!("wallets/{}", wallet_id))?
+        .ok_or_else(|| DexError::WalletNotFound(wallet_id.to_string()))?;
+    let all = entries(db, wallet_id)?;
+    for kind in [BalanceKind::Dex, BalanceKind::Onchain] {
+        let mut sum = 0.0;
+        for e in all.iter().filter(|e| e.kind == kind) {
+            sum += e.delta();
+            if sum != e.balance_after {
+                return Err(DexError::LedgerMismatch {
+                    wallet_id: wallet_id.to_string(),
+                    kind: format!("{:?} (seq {})", kind, e.seq),
+                    ledger: sum,
+                    stored: e.balance_after,
+                });
+            }
+        }
+        if sum != kind.balance(&w) {
+            return Err(DexError::LedgerMismatch {
+                wallet_id: wallet_id.to_string(),
+                kind: format!("{:?}", kind),
+                ledger: sum,
+                stored: kind.balance(&w),
+            });
+        }
+    }
+    Ok(Reconciliation {
+        wallet_id: wallet_id.to_string(),
+        entries: all.len(),
+        dex_balance: w.dex_balance,
+        onchain_balance: w.onchain_balance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::wallet::{BlockchainType, WalletManager};
+
+    fn wallet(id: &str, dex: f64) -> WalletInfo {
+        WalletInfo {
+            wallet_id: id.into(),
+            blockchain: BlockchainType::Bitcoin,
+            public_info: "xpub".into(),
+            address: "bc1q".into(),
+            onchain_balance: 0.0,
+            dex_balance: dex,
+        }
+    }
+
+    #[test]
+    fn test_ledger_reconstructs_balance_exactly() {
+        let db = DexDB::in_memory();
+        let mgr = WalletManager::new(db.clone(), None, None, None);
+        // Altbestand vor Einführung des Journals
+        mgr.store_wallet(&wallet("w1", 1.25)).unwrap();
+
+        mgr.add_dex_balance("w1", 0.1, Posting::new("fee_pool", "fee_payout", "day-1")).unwrap();
+        mgr.add_dex_balance("w1", 0.2, Posting::new("fee_pool", "fee_payout", "day-2")).unwrap();
+        mgr.sub_dex_balance("w1", 0.7, Posting::new("donation:charity", "donation", "d-1")).unwrap();
+        let mut w = mgr.load_wallet("w1").unwrap().unwrap();
+        post_set(&db, &mut w, BalanceKind::Onchain, 0.3, Posting::new("chain:sync", "onchain_sync", "bc1q")).unwrap();
+
+        let log = entries(&db, "w1").unwrap();
+        assert_eq!(log.iter().map(|e| e.reason.as_str()).collect::<Vec<_>>(),
+                   ["opening_balance", "fee_payout", "fee_payout", "donation", "onchain_sync"]);
+        assert_eq!((log[3].debit.as_str(), log[3].credit.as_str()), ("wallet:w1:dex", "donation:charity"));
+
+        let stored = mgr.load_wallet("w1").unwrap().unwrap();
+        let rec = reconcile(&db, "w1").unwrap();
+        assert_eq!(rec.entries, 5);
+        assert_eq!(rec.dex_balance, stored.dex_balance);
+        assert_eq!(rec.dex_balance, 1.25 + 0.1 + 0.2 - 0.7);
+        assert_eq!(rec.onchain_balance, 0.3);
+
+        // Änderung am Journal vorbei fällt auf
+        let mut tampered = stored.clone();
+        tampered.dex_balance += 1.0;
+        mgr.store_wallet(&tampered).unwrap();
+        assert!(matches!(reconcile(&db, "w1"), Err(DexError::LedgerMismatch { .. })));
+    }
+
+    #[test]
+    fn test_concurrent_posts_keep_sequence_and_balance() {
+        let db = DexDB::in_memory();
+        let mgr = WalletManager::new(db.clone(), None, None, None);
+        mgr.store_wallet(&wallet("w2", 0.0)).unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    // Jeder Thread mit eigenem (veraltetem) Wallet-Stand
+                    let mut w = wallet("w2", 0.0);
+                    for i in 0..25 {
+                        post(&db, &mut w, BalanceKind::Dex, 1.0, Posting::new("fee_pool", "fee_payout", format!("{}-{}", t, i)))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        let log = entries(&db, "w2").unwrap();
+        assert_eq!(log.len(), 200);
+        assert!(log.iter().enumerate().all(|(i, e)| e.seq == i as u64));
+        let rec = reconcile(&db, "w2").unwrap();
+        assert_eq!(rec.dex_balance, 200.0);
+    }
+}
@@ -1,96 +1,357 @@
 // my_dex/src/identity/keystore.rs
 //
-// Speichert PrivateKey verschl�sselt via AES-GCM.
+// Verschlüsselter Keystore für Node-/Operator-Signaturschlüssel.
+//
+//  - Master-Key per Argon2id aus der Passphrase (Salt + Parameter liegen in
+//    der Datei), Einträge per AES-256-GCM versiegelt; das Label ist AAD, ein
+//    Eintrag lässt sich also nicht unter anderem Namen unterschieben.
+//  - Auf Platte liegt nie ein Klartext-Secret, nur Salt, ein verschlüsselter
+//    Prüfwert (für die Passphrase) und die versiegelten Einträge.
+//  - Im Speicher hält nur ein entsperrter Keystore den Master-Key
+//    (`Zeroizing`, wird bei `lock`/Drop genullt). Entschlüsselte Secrets
+//    leben nur für die Dauer eines `sign`-Aufrufs – außer bei `keypair`,
+//    dessen Ergebnis der Aufrufer im Speicher hält.
 
-use serde::{Serialize, Deserialize};
-use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
 use std::fs;
-use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature, Signer};
 use rand::rngs::OsRng;
-use tracing::{info, warn, instrument};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument, warn};
+use zeroize::Zeroizing;
+
+const KEYSTORE_VERSION: u32 = 1;
+/// Klartext des Prüfwerts; entschlüsselt er sich, stimmt die Passphrase.
+const CHECK_PLAINTEXT: &[u8] = b"my_dex keystore v1";
+const CHECK_AAD: &[u8] = b"__check__";
+
+/// Standard-Label für das Block-Signing des Nodes.
+pub const BLOCK_SIGNING_LABEL: &str = "node_block_signing";
+
+/// Label des Node-Schlüssels für Audit-Logs, Audit-Exporte und Fee-Pool-Audit.
+pub const AUDIT_SIGNING_LABEL: &str = "node_audit_signing";
+
+/// Label des Geheimnisses, aus dem der Swap-Koordinator HTLC-Preimages ableitet.
+pub const SWAP_PREIMAGE_LABEL: &str = "node_swap_preimage";
+
+/// Label des Node-Schlüssels für die DKG-Zeremonie (signiert die Nachrichten,
+/// daraus wird auch der BLS-Schlüssel für die Deal-Werte abgeleitet).
+pub const DKG_SIGNING_LABEL: &str = "node_dkg_signing";
+
+/// Label des Node-Schlüssels für SWIM; die Member-ID ist daraus abgeleitet.
+pub const SWIM_SIGNING_LABEL: &str = "node_swim_signing";
+
+/// Label des Node-Schlüssels, der die beim Peering ausgetauschte Gossip-Config signiert.
+pub const GOSSIP_SIGNING_LABEL: &str = "node_gossip_signing";
+
+/// Label des Node-Schlüssels, aus dem der Sequencer-VRF-Key abgeleitet wird.
+pub const SEQUENCER_VRF_LABEL: &str = "node_sequencer_vrf";
+
+/// Label des Node-Schlüssels, mit dem der gewählte Sequencer seine Batches signiert.
+pub const SEQUENCER_SIGNING_LABEL: &str = "node_sequencer_signing";
+
+/// Argon2id-Parameter (Speicher in KiB, Iterationen, Parallelität).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KdfParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub salt: Vec<u8>,
+}
+
+impl KdfParams {
+    /// OWASP-Empfehlung für Argon2id: 19 MiB, 2 Iterationen.
+    pub fn recommended() -> Self {
+        Self::with_cost(19 * 1024, 2, 1)
+    }
+
+    pub fn with_cost(m_cost: u32, t_cost: u32, p_cost: u32) -> Self {
+        let mut salt = vec![0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        Self { m_cost, t_cost, p_cost, salt }
+    }
+
+    pub(crate) fn derive(&self, passphrase: &str) -> Result<Zeroizing<[u8; 32]>> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| anyhow!("Argon2 params: {:?}", e))?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &self.salt, key.as_mut())
+            .map_err(|e| anyhow!("Argon2: {:?}", e))?;
+        Ok(key)
+    }
+}
+
+/// AES-GCM-Chiffrat samt Nonce.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SealedBlob {
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
 
-use crate::utils::aesgcm_utils::{aes_gcm_encrypt, aes_gcm_decrypt};
+pub(crate) fn seal(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<SealedBlob> {
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| anyhow!("AES key: {:?}", e))?;
+    let mut nonce = vec![0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| anyhow!("AES-GCM encrypt failed"))?;
+    Ok(SealedBlob { nonce, ciphertext })
+}
+
+pub(crate) fn open(key: &[u8; 32], blob: &SealedBlob, aad: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    if blob.nonce.len() != 12 {
+        return Err(anyhow!("invalid nonce length"));
+    }
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| anyhow!("AES key: {:?}", e))?;
+    cipher
+        .decrypt(Nonce::from_slice(&blob.nonce), Payload { msg: &blob.ciphertext, aad })
+        .map(Zeroizing::new)
+        .map_err(|_| anyhow!("AES-GCM decrypt failed"))
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct NodeIdentity {
+pub struct StoredKey {
     pub public_key: Vec<u8>,
-    pub cipher_secret: Vec<u8>, // AES-GCM-ciphered secret
-    pub nonce: Vec<u8>,
+    pub sealed_secret: SealedBlob,
 }
 
-/// Keystore => kann mehrere Keys, hier nur 1
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+/// Das, was auf Platte liegt.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KeystoreFile {
+    pub version: u32,
+    pub kdf: KdfParams,
+    pub check: SealedBlob,
+    pub keys: BTreeMap<String, StoredKey>,
+}
+
+/// Keystore mit mehreren benannten Ed25519-Schlüsseln.
 pub struct Keystore {
-    pub identity: NodeIdentity,
+    file: KeystoreFile,
+    master: Option<Zeroizing<[u8; 32]>>,
+}
+
+impl std::fmt::Debug for Keystore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Keystore")
+            .field("labels", &self.file.keys.keys().collect::<Vec<_>>())
+            .field("unlocked", &self.is_unlocked())
+            .finish()
+    }
 }
 
 impl Keystore {
-    #[instrument(name="keystore_generate")]
-    pub fn generate(master_pass: &str) -> Result<Self> {
-        let mut csprng = OsRng{};
-        let keypair = Keypair::generate(&mut csprng);
-
-        let secret_bytes = keypair.secret.to_bytes();
-        let pub_bytes = keypair.public.to_bytes();
-
-        // AES-GCM => keyable from master_pass (z. B. using a KDF, hier quick 'n dirty)
-        let derived_key = crate::utils::aesgcm_utils::derive_key_from_pass(master_pass)?;
-        let (cipher_secret, nonce) = aes_gcm_encrypt(&derived_key, &secret_bytes)?;
-
-        let node_id = NodeIdentity {
-            public_key: pub_bytes.to_vec(),
-            cipher_secret,
-            nonce,
-        };
-        Ok(Self { identity: node_id })
-    }
-
-    #[instrument(name="keystore_save", skip(self, path))]
-    pub fn save_to_file(&self, path: &str) -> Result<()> {
-        let data = serde_json::to_string_pretty(self)
-            .map_err(|e| anyhow!("Ser. error: {:?}", e))?;
-        fs::write(path, data)?;
-        info!("Keystore saved to {}", path);
-        Ok(())
+    /// Neuer, leerer und bereits entsperrter Keystore.
+    #[instrument(name = "keystore_create", skip(passphrase))]
+    pub fn create(passphrase: &str) -> Result<Self> {
+        Self::create_with_params(passphrase, KdfParams::recommended())
+    }
+
+    pub fn create_with_params(passphrase: &str, kdf: KdfParams) -> Result<Self> {
+        if passphrase.is_empty() {
+            return Err(anyhow!("Keystore passphrase must not be empty"));
+        }
+        let master = kdf.derive(passphrase)?;
+        let check = seal(&master, CHECK_PLAINTEXT, CHECK_AAD)?;
+        Ok(Self {
+            file: KeystoreFile { version: KEYSTORE_VERSION, kdf, check, keys: BTreeMap::new() },
+            master: Some(master),
+        })
     }
 
-    #[instrument(name="keystore_load", skip(path, master_pass))]
-    pub fn load_from_file(path: &str, master_pass: &str) -> Result<Self> {
-        let data = fs::read_to_string(path)?;
-        let ks: Keystore = serde_json::from_str(&data)
-            .map_err(|e| anyhow!("DeSer. error: {:?}", e))?;
-        // test decrypt:
-        let _ = ks.get_secretkey(master_pass)?; 
-        info!("Keystore loaded from {}", path);
-        Ok(ks)
+    /// Lädt einen Keystore (gesperrt) aus `path`.
+    #[instrument(name = "keystore_open", skip(path))]
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let data = fs::read_to_string(path.as_ref())?;
+        let file: KeystoreFile = serde_json::from_str(&data)
+            .map_err(|e| anyhow!("Keystore decode error: {:?}", e))?;
+        if file.version != KEYSTORE_VERSION {
+            return Err(anyhow!("Unsupported keystore version {}", file.version));
+        }
+        Ok(Self { file, master: None })
     }
 
-    #[instrument(name="keystore_rotate")]
-    pub fn rotate_key(&mut self, master_pass: &str) -> Result<()> {
-        let new = Keystore::generate(master_pass)?;
-        self.identity = new.identity;
+    #[instrument(name = "keystore_save", skip(self, path))]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let data = serde_json::to_string_pretty(&self.file)
+            .map_err(|e| anyhow!("Keystore encode error: {:?}", e))?;
+        fs::write(path.as_ref(), data)?;
+        info!("Keystore gespeichert => {}", path.as_ref().display());
         Ok(())
     }
 
-    #[instrument(name="keystore_sign", skip(self, message, master_pass))]
-    pub fn sign(&self, message: &[u8], master_pass: &str) -> Result<Vec<u8>> {
-        let secret = self.get_secretkey(master_pass)?;
+    /// Leitet den Master-Key ab und prüft ihn am verschlüsselten Prüfwert.
+    #[instrument(name = "keystore_unlock", skip(self, passphrase))]
+    pub fn unlock(&mut self, passphrase: &str) -> Result<()> {
+        let master = self.file.kdf.derive(passphrase)?;
+        match open(&master, &self.file.check, CHECK_AAD) {
+            Ok(plain) if plain.as_slice() == CHECK_PLAINTEXT => {
+                self.master = Some(master);
+                Ok(())
+            }
+            _ => {
+                warn!("Keystore => falsche Passphrase");
+                Err(anyhow!("Wrong keystore passphrase"))
+            }
+        }
+    }
+
+    /// Verwirft den Master-Key (wird dabei genullt).
+    pub fn lock(&mut self) {
+        self.master = None;
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.master.is_some()
+    }
+
+    fn master(&self) -> Result<&[u8; 32]> {
+        self.master.as_deref().ok_or_else(|| anyhow!("Keystore is locked"))
+    }
+
+    /// Versiegelt ein Ed25519-Secret (32 Byte) unter `label`.
+    #[instrument(name = "keystore_store_key", skip(self, secret))]
+    pub fn store_key(&mut self, label: &str, secret: &[u8]) -> Result<PublicKey> {
+        let master = self.master()?;
+        let secret_key = SecretKey::from_bytes(secret)
+            .map_err(|e| anyhow!("SecretKey invalid: {:?}", e))?;
+        let public = PublicKey::from(&secret_key);
+        let sealed_secret = seal(master, secret, label.as_bytes())?;
+        self.file.keys.insert(
+            label.to_string(),
+            StoredKey { public_key: public.to_bytes().to_vec(), sealed_secret },
+        );
+        Ok(public)
+    }
+
+    /// Erzeugt einen neuen Schlüssel unter `label` (überschreibt einen alten).
+    pub fn generate_key(&mut self, label: &str) -> Result<PublicKey> {
+        let mut secret = Zeroizing::new([0u8; 32]);
+        OsRng.fill_bytes(secret.as_mut());
+        self.store_key(label, secret.as_ref())
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.file.keys.keys().map(String::as_str)
+    }
+
+    /// Öffentlicher Schlüssel – dafür muss der Keystore nicht entsperrt sein.
+    pub fn public_key(&self, label: &str) -> Result<PublicKey> {
+        let stored = self.file.keys.get(label).ok_or_else(|| anyhow!("No key `{}` in keystore", label))?;
+        PublicKey::from_bytes(&stored.public_key).map_err(|e| anyhow!("PublicKey invalid: {:?}", e))
+    }
+
+    /// Signiert `message` mit dem Schlüssel `label`.
+    #[instrument(name = "keystore_sign", skip(self, message))]
+    pub fn sign(&self, label: &str, message: &[u8]) -> Result<Signature> {
+        let master = self.master()?;
+        let stored = self.file.keys.get(label).ok_or_else(|| anyhow!("No key `{}` in keystore", label))?;
+        let plain = open(master, &stored.sealed_secret, label.as_bytes())?;
+        // SecretKey nullt sich beim Drop selbst
+        let secret = SecretKey::from_bytes(&plain).map_err(|e| anyhow!("SecretKey invalid: {:?}", e))?;
         let public = PublicKey::from(&secret);
         let keypair = Keypair { secret, public };
-        let sig = keypair.sign(message).to_bytes().to_vec();
-        Ok(sig)
-    }
-
-    /// Intern => decrypt & build ed25519::SecretKey
-    fn get_secretkey(&self, master_pass: &str) -> Result<SecretKey> {
-        let derived_key = crate::utils::aesgcm_utils::derive_key_from_pass(master_pass)?;
-        let plain = aes_gcm_decrypt(
-            &derived_key, 
-            &self.identity.cipher_secret, 
-            &self.identity.nonce
-        )?;
-        let sec = SecretKey::from_bytes(&plain)
-            .map_err(|e| anyhow!("SecretKey invalid: {:?}", e))?;
-        Ok(sec)
+        Ok(keypair.sign(message))
+    }
+
+    /// Entschlüsselt den Schlüssel `label` als `Keypair` für Komponenten, die
+    /// dauerhaft signieren (z. B. `AuditLogger`). Das Secret bleibt nur im Speicher.
+    pub fn keypair(&self, label: &str) -> Result<Keypair> {
+        let master = self.master()?;
+        let stored = self.file.keys.get(label).ok_or_else(|| anyhow!("No key `{}` in keystore", label))?;
+        let plain = open(master, &stored.sealed_secret, label.as_bytes())?;
+        let secret = SecretKey::from_bytes(&plain).map_err(|e| anyhow!("SecretKey invalid: {:?}", e))?;
+        let public = PublicKey::from(&secret);
+        Ok(Keypair { secret, public })
+    }
+}
+
+/// Öffnet den Keystore unter `path` (legt ihn bei Bedarf an), erzeugt den
+/// Schlüssel `label` falls er fehlt und gibt ihn als `Keypair` zurück. So bleibt
+/// der Node-Schlüssel über Neustarts hinweg derselbe.
+pub fn load_or_create_keypair(path: &str, passphrase: &str, label: &str) -> Result<Keypair> {
+    let mut keystore = if Path::new(path).exists() {
+        let mut ks = Keystore::open(path)?;
+        ks.unlock(passphrase)?;
+        ks
+    } else {
+        Keystore::create(passphrase)?
+    };
+    if keystore.public_key(label).is_err() {
+        keystore.generate_key(label)?;
+        keystore.save(path)?;
+        info!("Keystore => neuer Schlüssel `{}` erzeugt", label);
+    }
+    let keypair = keystore.keypair(label);
+    keystore.lock();
+    keypair
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Verifier;
+
+    /// Geringe Kosten, damit die Tests schnell bleiben.
+    fn fast_kdf() -> KdfParams {
+        KdfParams::with_cost(256, 1, 1)
+    }
+
+    #[test]
+    fn test_wrong_passphrase_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        let mut ks = Keystore::create_with_params("correct horse", fast_kdf()).unwrap();
+        ks.generate_key(BLOCK_SIGNING_LABEL).unwrap();
+        ks.save(&path).unwrap();
+
+        let mut reopened = Keystore::open(&path).unwrap();
+        assert!(reopened.unlock("battery staple").is_err());
+        assert!(!reopened.is_unlocked());
+        assert!(reopened.sign(BLOCK_SIGNING_LABEL, b"block").is_err());
+    }
+
+    #[test]
+    fn test_sign_roundtrip_after_unlock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        let secret = [7u8; 32];
+        let mut ks = Keystore::create_with_params("correct horse", fast_kdf()).unwrap();
+        let public = ks.store_key("operator", &secret).unwrap();
+        ks.save(&path).unwrap();
+        drop(ks);
+
+        // kein Klartext-Secret auf Platte
+        let on_disk = fs::read(&path).unwrap();
+        assert!(!on_disk.windows(secret.len()).any(|w| w == secret));
+        assert!(!String::from_utf8_lossy(&on_disk).contains(&hex::encode(secret)));
+
+        let mut ks = Keystore::open(&path).unwrap();
+        assert_eq!(ks.public_key("operator").unwrap(), public);
+        assert!(ks.sign("operator", b"msg").is_err());
+        ks.unlock("correct horse").unwrap();
+        let sig = ks.sign("operator", b"msg").unwrap();
+        assert!(public.verify(b"msg", &sig).is_ok());
+
+        ks.lock();
+        assert!(ks.sign("operator", b"msg").is_err());
+    }
+
+    #[test]
+    fn test_load_or_create_keypair_is_stable_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        let path = path.to_str().unwrap();
+        let first = load_or_create_keypair(path, "pass", AUDIT_SIGNING_LABEL).unwrap();
+        let second = load_or_create_keypair(path, "pass", AUDIT_SIGNING_LABEL).unwrap();
+        assert_eq!(first.public, second.public);
+        assert!(load_or_create_keypair(path, "wrong", AUDIT_SIGNING_LABEL).is_err());
     }
 }
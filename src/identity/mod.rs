@@ -4,6 +4,7 @@
 
 pub mod access_control;
 pub mod accounts;
+pub mod balance_ledger;
 pub mod extended_access_control;
 pub mod hsm_provider;
 pub mod identity;
@@ -7,6 +7,7 @@ use std::str::FromStr;
 use tracing::{info, warn, error};
 use anyhow::{Result, anyhow};
 use crate::error::DexError;
+use crate::identity::balance_ledger::{self, BalanceKind, Posting};
 use crate::storage::db_layer::DexDB;
 
 use bitcoincore_rpc::{Auth, Client, RpcApi};
@@ -332,6 +333,12 @@ impl WalletManager {
         self.db.load_struct::<WalletInfo>(&key)
     }
 
+    /// Lädt mehrere Wallets mit einem einzigen DB-Roundtrip (Reihenfolge wie `wallet_ids`).
+    pub fn load_wallets(&self, wallet_ids: &[String]) -> Result<Vec<Option<WalletInfo>>, DexError> {
+        let keys = wallet_ids.iter().map(|id| format!("wallets/{}", id)).collect::<Vec<_>>();
+        self.db.load_structs_batch::<WalletInfo>(&keys)
+    }
+
     // ----------------------------------------------------------------------------
     // OnChain-Balance + Senden
     // ----------------------------------------------------------------------------
@@ -348,8 +355,8 @@ impl WalletManager {
                         .map_err(|_| DexError::Other("BTC address parse err".into()))?;
                     let recv = client.get_received_by_address(parsed, Some(0))
                         .map_err(|e| DexError::Other(format!("BTC get_received_by_address: {:?}", e)))?;
-                    w.onchain_balance = recv;
-                    self.store_wallet(w)?;
+                    let sync = Posting::new(format!("chain:{}", w.address), "onchain_sync", "btc_rpc");
+                    balance_ledger::post_set(&self.db, w, BalanceKind::Onchain, recv.to_btc(), sync)?;
                 } else {
                     return Err(DexError::Other("No BTC config found".into()));
                 }
@@ -363,8 +370,8 @@ impl WalletManager {
                         .map_err(|_| DexError::Other("LTC address parse err".into()))?;
                     let recv = client.get_received_by_address(parsed, Some(0))
                         .map_err(|e| DexError::Other(format!("LTC get_received_by_address: {:?}", e)))?;
-                    w.onchain_balance = recv;
-                    self.store_wallet(w)?;
+                    let sync = Posting::new(format!("chain:{}", w.address), "onchain_sync", "ltc_rpc");
+                    balance_ledger::post_set(&self.db, w, BalanceKind::Onchain, recv.to_btc(), sync)?;
                 } else {
                     return Err(DexError::Other("No LTC config found".into()));
                 }
@@ -378,8 +385,9 @@ impl WalletManager {
                     let balance_res = futures::executor::block_on(provider.get_balance(addr, None))
                         .map_err(|e| DexError::Other(format!("ETH get_balance error: {:?}", e)))?;
                     let bal_eth = ethers::utils::from_wei(balance_res, 18u32);
-                    w.onchain_balance = bal_eth.to_string().parse().unwrap_or(0.0);
-                    self.store_wallet(w)?;
+                    let target = bal_eth.to_string().parse().unwrap_or(0.0);
+                    let sync = Posting::new(format!("chain:{}", w.address), "onchain_sync", "eth_rpc");
+                    balance_ledger::post_set(&self.db, w, BalanceKind::Onchain, target, sync)?;
                 } else {
                     return Err(DexError::Other("No ETH config found".into()));
                 }
@@ -388,16 +396,20 @@ impl WalletManager {
         Ok(())
     }
 
-    /// Sendet amount OnChain, abgezogen von w.onchain_balance.
+    /// Sendet amount OnChain, abgezogen von w.onchain_balance; liefert die txid.
     /// BTC/LTC => sendtoaddress (RPC).
     /// ETH => local Key sign? => Minimales Stub => TODO
-    pub fn send_onchain(&self, w: &mut WalletInfo, to_addr: &str, amount: f64) -> Result<(), DexError> {
+    pub fn send_onchain(&self, w: &mut WalletInfo, to_addr: &str, amount: f64) -> Result<String, DexError> {
+        // Sanktions-Screening vor jeder Auszahlung (Ziel und Quelle)
+        crate::sanctions::sanctions_list::global_sanctions()
+            .screen([to_addr, w.address.as_str()], None)?;
         if w.onchain_balance < amount {
-            return Err(DexError::Other(format!(
-                "Not enough onchain balance in wallet '{}'", w.wallet_id
-            )));
+            return Err(DexError::InsufficientBalance {
+                user: w.wallet_id.clone(),
+                asset: format!("{:?} (onchain)", w.blockchain),
+            });
         }
-        match w.blockchain {
+        let txid = match w.blockchain {
             BlockchainType::Bitcoin => {
                 if let Some(cfg) = &self.btc_cfg {
                     let auth = Auth::UserPass(cfg.rpc_user.clone(), cfg.rpc_pass.clone());
@@ -405,12 +417,13 @@ impl WalletManager {
                         .map_err(|e| DexError::Other(format!("BTC client init err: {:?}", e)))?;
                     let parsed_addr = to_addr.parse()
                         .map_err(|_| DexError::Other("Bad BTC address to send".into()))?;
-                    let _txid = client.send_to_address(
+                    let txid = client.send_to_address(
                         parsed_addr, amount,
                         None, None, None, None, None, None
                     ).map_err(|e| DexError::Other(format!("BTC send_to_address: {:?}", e)))?;
-                    w.onchain_balance -= amount;
-                    self.store_wallet(w)?;
+                    let out = Posting::new(format!("chain:{}", to_addr), "withdrawal", txid.to_string());
+                    balance_ledger::post(&self.db, w, BalanceKind::Onchain, -amount, out)?;
+                    txid.to_string()
                 } else {
                     return Err(DexError::Other("No BTC config found".into()));
                 }
@@ -422,12 +435,13 @@ impl WalletManager {
                         .map_err(|e| DexError::Other(format!("LTC client init err: {:?}", e)))?;
                     let parsed_addr = to_addr.parse()
                         .map_err(|_| DexError::Other("Bad LTC address to send".into()))?;
-                    let _txid = client.send_to_address(
+                    let txid = client.send_to_address(
                         parsed_addr, amount,
                         None, None, None, None, None, None
                     ).map_err(|e| DexError::Other(format!("LTC send_to_address: {:?}", e)))?;
-                    w.onchain_balance -= amount;
-                    self.store_wallet(w)?;
+                    let out = Posting::new(format!("chain:{}", to_addr), "withdrawal", txid.to_string());
+                    balance_ledger::post(&self.db, w, BalanceKind::Onchain, -amount, out)?;
+                    txid.to_string()
                 } else {
                     return Err(DexError::Other("No LTC config found".into()));
                 }
@@ -443,28 +457,42 @@ impl WalletManager {
                     return Err(DexError::Other("No ETH config found".into()));
                 }
             }
-        }
-        Ok(())
+        };
+        Ok(txid)
     }
 
-    /// Erhöht Dex-Guthaben
-    pub fn add_dex_balance(&self, wallet_id: &str, amount: f64) -> Result<(), DexError> {
+    /// Erhöht Dex-Guthaben (mit Journal-Buchung, siehe balance_ledger)
+    pub fn add_dex_balance(&self, wallet_id: &str, amount: f64, posting: Posting) -> Result<(), DexError> {
         let mut w = self.load_wallet(wallet_id)?
             .ok_or(DexError::WalletNotFound(wallet_id.to_string()))?;
-        w.dex_balance += amount;
-        self.store_wallet(&w)?;
+        balance_ledger::post(&self.db, &mut w, BalanceKind::Dex, amount, posting)?;
         Ok(())
     }
 
-    /// Verringert Dex-Guthaben
-    pub fn sub_dex_balance(&self, wallet_id: &str, amount: f64) -> Result<(), DexError> {
+    /// Verringert Dex-Guthaben (mit Journal-Buchung)
+    pub fn sub_dex_balance(&self, wallet_id: &str, amount: f64, posting: Posting) -> Result<(), DexError> {
         let mut w = self.load_wallet(wallet_id)?
             .ok_or(DexError::WalletNotFound(wallet_id.to_string()))?;
-        if w.dex_balance < amount {
-            return Err(DexError::Other("Not enough dex_balance".into()));
-        }
-        w.dex_balance -= amount;
-        self.store_wallet(&w)?;
+        // Deckung unter der Wallet-Sperre prüfen, sonst überziehen parallele Abbuchungen
+        balance_ledger::post_with(&self.db, &mut w, BalanceKind::Dex, posting, |cur| {
+            if cur.dex_balance < amount {
+                return Err(DexError::InsufficientBalance {
+                    user: wallet_id.to_string(),
+                    asset: format!("{:?}", cur.blockchain),
+                });
+            }
+            Ok(-amount)
+        })?;
         Ok(())
     }
+
+    /// Buchungsjournal eines Wallets.
+    pub fn ledger(&self, wallet_id: &str) -> Result<Vec<balance_ledger::LedgerEntry>, DexError> {
+        balance_ledger::entries(&self.db, wallet_id)
+    }
+
+    /// Prüft, dass das Journal die gespeicherten Balances exakt ergibt.
+    pub fn reconcile(&self, wallet_id: &str) -> Result<balance_ledger::Reconciliation, DexError> {
+        balance_ledger::reconcile(&self.db, wallet_id)
+    }
 }
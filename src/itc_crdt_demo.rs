@@ -5,20 +5,30 @@
 use crate::dex_logic::itc_crdt_orderbook::{ITCOrderBook, Order, Asset};
 use crate::dex_logic::gossip::{Node, GossipNet};
 use crate::dex_logic::fuzz_test::fuzz_simulation;
+use ed25519_dalek::Keypair;
+use std::sync::Arc;
 
 /// Demo: Einfach ITC-basiertes CRDT-Orderbuch -> Add/Remove
 pub fn demo_itc_crdt() {
     println!("--- Demo: ITC-CRDT Add/Remove ---");
 
     let mut book = ITCOrderBook::new();
-    book.add_order(Order::new("O1", "Alice", Asset::BTC, Asset::LTC, 0.1, 100.0), "NodeA");
-    book.add_order(Order::new("O2", "Bob", Asset::LTC, Asset::BTC, 5.0, 0.02), "NodeA");
+    // Unsignierte Orders werden abgelehnt => Fehler nur anzeigen
+    for o in [
+        Order::new("O1", "Alice", Asset::BTC, Asset::LTC, 0.1, 100.0),
+        Order::new("O2", "Bob", Asset::LTC, Asset::BTC, 5.0, 0.02),
+    ] {
+        if let Err(e) = book.add_order(o) {
+            println!("add_order: {:?}", e);
+        }
+    }
 
     println!("Book after additions: {:?}", book.all_orders());
 
     // remove
     if let Some(o2) = book.all_orders().iter().find(|o| o.order_id == "O2") {
-        book.remove_order(o2, "NodeA");
+        let o2 = o2.clone();
+        let _ = book.remove_order(&o2);
     }
 
     println!("Book after remove O2: {:?}", book.all_orders());
@@ -28,31 +38,35 @@ pub fn demo_itc_crdt() {
 pub fn demo_gossip() {
     println!("--- Demo: Gossip ---");
 
-    // Erstelle 3 Knoten
-    let mut net = GossipNet::new();
-    let mut nodeA = Node::new("NodeA");
-    let mut nodeB = Node::new("NodeB");
-    let mut nodeC = Node::new("NodeC");
+    let key = || Arc::new(Keypair::generate(&mut rand_07::rngs::OsRng));
+    // Nur NodeA ist Seed; B und C erhalten ihre ITC-Identität per Fork
+    let mut node_a = Node::seed("NodeA").with_keypair(key());
+    let mut node_b = Node::join_via("NodeB", &mut node_a).with_keypair(key());
+    let mut node_c = Node::join_via("NodeC", &mut node_b).with_keypair(key());
 
-    net.add_node(&mut nodeA);
-    net.add_node(&mut nodeB);
-    net.add_node(&mut nodeC);
+    let mut net = GossipNet::new();
+    for node in [&node_a, &node_b, &node_c] {
+        if let Err(e) = net.add_node(node) {
+            println!("add_node: {:?}", e);
+        }
+    }
 
-    // NodeA f�gt was hinzu
-    nodeA.itc_book.add_order(
-        Order::new("O99", "Carol", Asset::BTC, Asset::LTC, 0.3, 99.0),
-        "NodeA"
-    );
+    // NodeA fügt eine vom Besitzer signierte Order hinzu
+    let mut order = Order::new("O99", "Carol", Asset::BTC, Asset::LTC, 0.3, 99.0);
+    if let Err(e) = order.sign(&key()) {
+        println!("sign: {:?}", e);
+    }
+    if let Err(e) = node_a.itc_book.add_order(order) {
+        println!("add_order: {:?}", e);
+    }
 
-    // Gossip => wir lassen net "tick()" 2-3 mal
-    net.tick(&mut nodeA);
-    net.tick(&mut nodeB);
-    net.tick(&mut nodeC);
+    // Gossip => NodeA schickt sein Delta an B und C
+    net.tick(&node_a, &mut [&mut node_b, &mut node_c]);
 
     // NodeB, NodeC => sollen Book haben
-    println!("NodeA: {:?}", nodeA.itc_book.all_orders());
-    println!("NodeB: {:?}", nodeB.itc_book.all_orders());
-    println!("NodeC: {:?}", nodeC.itc_book.all_orders());
+    println!("NodeA: {:?}", node_a.itc_book.all_orders());
+    println!("NodeB: {:?}", node_b.itc_book.all_orders());
+    println!("NodeC: {:?}", node_c.itc_book.all_orders());
 }
 
 /// Demo: Fuzzing-Simulation
@@ -29,11 +29,11 @@ use ed25519_dalek::{
     PublicKey, Signature, Verifier, SIGNATURE_LENGTH, KEYPAIR_LENGTH
 };
 use sha2::{Sha256, Digest};
-use crate::error::DexError;
 use crate::storage::db_layer::DexDB;
 use crate::kademlia::kademlia_service::KademliaService;
 use crate::dex_logic::crdt_orderbook::CrdtStorage; // Oder dein ITC-CRDT, wie du es nennst
 use crate::identity::accounts::{Account, AccountType};
+use crate::utils::lock::LockRecover;
 
 
 /// Struktur, die Metadaten zum Node-Build speichert:
@@ -131,7 +131,7 @@ impl OnboardingFlow {
     // --------------------------------------------------
     pub fn verify_crdt_hash_against_network(&self, local_hash: [u8; 32]) -> Result<()> {
         // (a) Sammle z. B. 8 Peers aus Kademlia
-        let mut kad_l = self.kad.lock().unwrap();
+        let mut kad_l = self.kad.lock_recover();
         let peers = kad_l.table.find_closest(&kad_l.local_id, 8);
         drop(kad_l); 
 
@@ -213,13 +213,13 @@ impl OnboardingFlow {
     {
         // (a) Hole Fullnodes => db => 
         //    wir filtern (account_type=Fullnode && is_fee_pool_recipient=true)
-        let lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock = self.db.lock_recover();
         let all_keys = lock.list_prefix("accounts/");
         drop(lock);
 
         let mut potential_signers = Vec::new();
         for (k, _) in all_keys {
-            let maybe_acc = self.db.lock().unwrap().load_struct::<Account>(&k)?;
+            let maybe_acc = self.db.lock_recover().load_struct::<Account>(&k)?;
             if let Some(acc) = maybe_acc {
                 if acc.account_type == AccountType::Fullnode && acc.is_fee_pool_recipient {
                     // => hole in memory => wir tun so, als kennen wir pubkey
@@ -315,7 +315,7 @@ impl OnboardingFlow {
         }
 
         // (d) set in DB => �account_type=Fullnode, is_fee_pool_recipient=true�
-        let lock = self.db.lock().map_err(|_| DexError::Other("DB lock poisoned".into()))?;
+        let lock = self.db.lock_recover();
         let acc_key = format!("accounts/{}", join_req.node_id);
         let maybe_acc = lock.load_struct::<Account>(&acc_key)?;
         let mut acc = if let Some(a) = maybe_acc {
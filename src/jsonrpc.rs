@@ -0,0 +1,585 @@
+///////////////////////////////////////////////////////////
+// my_dex/src/jsonrpc.rs
+///////////////////////////////////////////////////////////
+//
+// JSON-RPC 2.0 neben der REST-API, gemountet unter `/rpc`:
+//  - POST /rpc => Einzel- oder Batch-Request, Antwort als JSON
+//  - GET  /rpc => WebSocket, dieselben Methoden plus `subscribe_trades`
+//
+// Methoden: place_order, cancel_order, get_book, get_balance, subscribe_trades.
+// Sie laufen auf demselben `AppState` wie die REST-Handler und verlangen
+// dieselben Permissions wie die entsprechenden REST-Routen (siehe
+// `RpcMethod::permission`). Der Token wird einmal pro HTTP-Request bzw. pro
+// WebSocket-Handshake aus den Headern gelesen.
+//
+// Fehler folgen der Spezifikation (-32700 .. -32603). Fachliche Fehler aus
+// der Node kommen als -32000 mit `data.code` = `DexError::code`.
+///////////////////////////////////////////////////////////
+
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+use crate::error::DexError;
+use crate::identity::access_control::{Permission, Role};
+use crate::market_data::{MarketDataEvent, SubscriptionFilter, CLIENT_SEND_TIMEOUT};
+use crate::node_logic::OrderRequest;
+use crate::rest_api::{
+    token_from_headers, AppState, BalanceQuery, BookQuery, CancelOrderRequest, PlacedOrder, Principal, RoleAuth,
+};
+
+pub const JSONRPC_VERSION: &str = "2.0";
+
+// Standard-Fehlercodes laut JSON-RPC 2.0
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+// Server-Fehler (reservierter Bereich -32000 .. -32099)
+/// Fachlicher Fehler der Node, `data.code` = `DexError::code`.
+pub const APPLICATION_ERROR: i64 = -32000;
+/// Fehlender oder ungültiger Token (REST: 401).
+pub const UNAUTHORIZED: i64 = -32001;
+/// Rolle ohne Permission oder gesperrter Nutzer (REST: 403).
+pub const FORBIDDEN: i64 = -32003;
+
+/// Name der Notification, mit der abonnierte Trades gepusht werden.
+pub const TRADES_NOTIFICATION: &str = "trades";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl JsonRpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), data: None }
+    }
+
+    pub fn invalid_params(detail: impl std::fmt::Display) -> Self {
+        Self::new(INVALID_PARAMS, format!("Ungültige Parameter: {}", detail))
+    }
+}
+
+impl From<&DexError> for JsonRpcError {
+    fn from(err: &DexError) -> Self {
+        Self {
+            code: APPLICATION_ERROR,
+            message: err.to_string(),
+            data: Some(json!({ "code": err.code() })),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JsonRpcRequest {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+    #[serde(default)]
+    pub id: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JsonRpcResponse {
+    pub jsonrpc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+    pub id: Value,
+}
+
+impl JsonRpcResponse {
+    pub fn result(id: Value, result: Value) -> Self {
+        Self { jsonrpc: JSONRPC_VERSION.into(), result: Some(result), error: None, id }
+    }
+
+    pub fn error(id: Value, error: JsonRpcError) -> Self {
+        Self { jsonrpc: JSONRPC_VERSION.into(), result: None, error: Some(error), id }
+    }
+}
+
+/// Unterstützte Methoden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcMethod {
+    PlaceOrder,
+    CancelOrder,
+    GetBook,
+    GetBalance,
+    SubscribeTrades,
+}
+
+impl RpcMethod {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "place_order" => Some(RpcMethod::PlaceOrder),
+            "cancel_order" => Some(RpcMethod::CancelOrder),
+            "get_book" => Some(RpcMethod::GetBook),
+            "get_balance" => Some(RpcMethod::GetBalance),
+            "subscribe_trades" => Some(RpcMethod::SubscribeTrades),
+            _ => None,
+        }
+    }
+
+    /// Wie in `build_rest_api_with_auth`: Buch und Trades sind öffentlich.
+    pub fn permission(self) -> Option<Permission> {
+        match self {
+            RpcMethod::PlaceOrder | RpcMethod::CancelOrder => Some(Permission::Trade),
+            RpcMethod::GetBalance => Some(Permission::ReadAccount),
+            RpcMethod::GetBook | RpcMethod::SubscribeTrades => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SubscribeTradesParams {
+    #[serde(default)]
+    markets: Vec<String>,
+}
+
+/// Trade-Abo einer WebSocket-Verbindung; ein neues Abo ersetzt das alte.
+#[derive(Debug, Clone)]
+pub struct TradeSubscription {
+    pub id: String,
+    filter: SubscriptionFilter,
+}
+
+/// Zustand einer WebSocket-Session; über HTTP gibt es keinen.
+#[derive(Debug, Default)]
+pub struct RpcSession {
+    pub subscription: Option<TradeSubscription>,
+    next_subscription: u64,
+}
+
+/// Dispatcher für `/rpc`: Node-Zugriff über `AppState`, Auth wie die REST-Routen.
+#[derive(Clone)]
+pub struct JsonRpcService {
+    state: AppState,
+    auth: Option<RoleAuth>,
+}
+
+impl JsonRpcService {
+    pub fn new(state: AppState, auth: Option<RoleAuth>) -> Self {
+        Self { state, auth }
+    }
+
+    /// Principal zum Token aus den Headern; `None`, wenn keiner oder ein unbekannter vorliegt.
+    pub fn principal_from_headers(&self, headers: &HeaderMap) -> Option<Principal> {
+        let auth = self.auth.as_ref()?;
+        token_from_headers(headers).and_then(|t| auth.principal_of(t))
+    }
+
+    /// Verarbeitet einen kompletten Payload (Einzel-Request oder Batch).
+    /// `None` => nichts zu antworten (nur Notifications).
+    pub fn handle_payload(&self, caller: Option<&Principal>, payload: &str, mut session: Option<&mut RpcSession>) -> Option<Value> {
+        let value: Value = match serde_json::from_str(payload) {
+            Ok(v) => v,
+            Err(e) => {
+                let err = JsonRpcError::new(PARSE_ERROR, format!("Parse-Fehler: {}", e));
+                return Some(JsonRpcResponse::error(Value::Null, err).into());
+            }
+        };
+        match value {
+            Value::Array(calls) if calls.is_empty() => {
+                let err = JsonRpcError::new(INVALID_REQUEST, "Leerer Batch");
+                Some(JsonRpcResponse::error(Value::Null, err).into())
+            }
+            Value::Array(calls) => {
+                let responses: Vec<Value> = calls
+                    .into_iter()
+                    .filter_map(|call| self.handle_call(caller, call, session.as_deref_mut()))
+                    .map(Value::from)
+                    .collect();
+                if responses.is_empty() {
+                    None
+                } else {
+                    Some(Value::Array(responses))
+                }
+            }
+            call => self.handle_call(caller, call, session).map(Value::from),
+        }
+    }
+
+    /// Ein einzelner Aufruf. Notifications (ohne `id`) werden ausgeführt, aber nicht beantwortet.
+    pub fn handle_call(&self, caller: Option<&Principal>, call: Value, session: Option<&mut RpcSession>) -> Option<JsonRpcResponse> {
+        let is_notification = call.as_object().map_or(false, |o| !o.contains_key("id"));
+        let req: JsonRpcRequest = match serde_json::from_value(call) {
+            Ok(req) => req,
+            Err(e) => {
+                let err = JsonRpcError::new(INVALID_REQUEST, format!("Ungültiger Request: {}", e));
+                return Some(JsonRpcResponse::error(Value::Null, err));
+            }
+        };
+        if req.jsonrpc != JSONRPC_VERSION {
+            let err = JsonRpcError::new(INVALID_REQUEST, "jsonrpc muss \"2.0\" sein");
+            return Some(JsonRpcResponse::error(req.id, err));
+        }
+
+        let outcome = self.dispatch(caller, &req.method, req.params, session);
+        if is_notification {
+            if let Err(e) = outcome {
+                debug!("JSON-RPC => Notification {} fehlgeschlagen: {}", req.method, e.message);
+            }
+            return None;
+        }
+        Some(match outcome {
+            Ok(result) => JsonRpcResponse::result(req.id, result),
+            Err(err) => JsonRpcResponse::error(req.id, err),
+        })
+    }
+
+    fn dispatch(
+        &self,
+        caller: Option<&Principal>,
+        method: &str,
+        params: Value,
+        session: Option<&mut RpcSession>,
+    ) -> Result<Value, JsonRpcError> {
+        let m = RpcMethod::parse(method)
+            .ok_or_else(|| JsonRpcError::new(METHOD_NOT_FOUND, format!("Unbekannte Methode: {}", method)))?;
+        self.authorize(caller.map(|p| p.role), m)?;
+
+        match m {
+            RpcMethod::PlaceOrder => {
+                let req: OrderRequest = parse_params(params)?;
+                // Wie REST: mit Auth nur im Namen des eigenen Accounts
+                if let (Some(p), Some(_)) = (caller, &self.auth) {
+                    if !p.acts_for(&req.user_id) {
+                        return Err(JsonRpcError::from(&DexError::Forbidden(format!("Account {}", req.user_id))));
+                    }
+                }
+                self.reject_banned(&req.user_id)?;
+                let order_id = self.state.node.place_order(req).map_err(|e| JsonRpcError::from(&e))?;
+                to_result(&PlacedOrder { order_id, status: "open".into() })
+            }
+            RpcMethod::CancelOrder => {
+                let req: CancelOrderRequest = parse_params(params)?;
+                // Wie REST: mit Auth zählt der gespeicherte Eigentümer, nicht die user_id im Request
+                let acting_user = match (caller, &self.auth) {
+                    (Some(p), Some(_)) => match self.state.node.order_owner(&req.order_id) {
+                        Some(owner) if p.acts_for(&owner) => owner,
+                        Some(_) => return Err(JsonRpcError::from(&DexError::Forbidden(format!("Order {}", req.order_id)))),
+                        None => return Err(JsonRpcError::from(&DexError::OrderNotFound { order_id: req.order_id })),
+                    },
+                    _ => req.user_id.clone(),
+                };
+                self.state.node.cancel_order(&acting_user, &req.order_id).map_err(|e| JsonRpcError::from(&e))?;
+                to_result(&PlacedOrder { order_id: req.order_id, status: "cancelled".into() })
+            }
+            RpcMethod::GetBook => {
+                let q: BookQuery = parse_params(params)?;
+                to_result(&self.state.node.order_book(&q.market))
+            }
+            RpcMethod::GetBalance => {
+                let q: BalanceQuery = parse_params(params)?;
+                // Wie REST: mit Auth nur der eigene Account
+                if let (Some(p), Some(_)) = (caller, &self.auth) {
+                    if !p.acts_for(&q.user_id) {
+                        return Err(JsonRpcError::from(&DexError::Forbidden(format!("Account {}", q.user_id))));
+                    }
+                }
+                self.reject_banned(&q.user_id)?;
+                Ok(json!(self.state.node.user_get_free_balance(&q.user_id, &q.coin)))
+            }
+            RpcMethod::SubscribeTrades => {
+                let session = session.ok_or_else(|| {
+                    JsonRpcError::new(INVALID_REQUEST, "subscribe_trades nur über WebSocket (GET /rpc)")
+                })?;
+                let p: SubscribeTradesParams = if params.is_null() { SubscribeTradesParams::default() } else { parse_params(params)? };
+                session.next_subscription += 1;
+                let id = format!("trades-{}", session.next_subscription);
+                let filter = SubscriptionFilter::from_query(Some(&p.markets.join(",")));
+                session.subscription = Some(TradeSubscription { id: id.clone(), filter });
+                Ok(Value::String(id))
+            }
+        }
+    }
+
+    /// Ohne Auth offen (nur lokale Tests), sonst wie `require_permission`.
+    fn authorize(&self, role: Option<Role>, method: RpcMethod) -> Result<(), JsonRpcError> {
+        let (Some(_), Some(permission)) = (&self.auth, method.permission()) else {
+            return Ok(());
+        };
+        match role {
+            None => Err(JsonRpcError::new(UNAUTHORIZED, "Fehlender oder ungültiger API-Token")),
+            Some(role) if !role.allows(permission) => {
+                warn!("JSON-RPC => {:?} ohne {:?} => {:?} verweigert", role, permission, method);
+                Err(JsonRpcError::new(FORBIDDEN, "Keine Berechtigung für diese Methode"))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    fn reject_banned(&self, user_id: &str) -> Result<(), JsonRpcError> {
+        if self.state.node.watchtower.is_banned(user_id) {
+            warn!("JSON-RPC => gebannter Nutzer {}", user_id);
+            // FORBIDDEN wie bisher, Nachricht/Code aus dem typisierten Fehler
+            return Err(JsonRpcError { code: FORBIDDEN, ..JsonRpcError::from(&DexError::UserBanned(user_id.to_string())) });
+        }
+        Ok(())
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, JsonRpcError> {
+    serde_json::from_value(params).map_err(JsonRpcError::invalid_params)
+}
+
+fn to_result<T: Serialize>(v: &T) -> Result<Value, JsonRpcError> {
+    serde_json::to_value(v)
+        .map_err(|e| JsonRpcError::new(INTERNAL_ERROR, format!("Serialisierung fehlgeschlagen: {}", e)))
+}
+
+impl From<JsonRpcResponse> for Value {
+    fn from(resp: JsonRpcResponse) -> Self {
+        // Enthält nur Strings und `Value`s => Serialisierung kann nicht scheitern
+        serde_json::to_value(resp).unwrap_or(Value::Null)
+    }
+}
+
+/// Notification für einen abonnierten Trade, oder `None`, wenn das Event nicht passt.
+pub fn trade_notification(session: &RpcSession, event: &MarketDataEvent) -> Option<Value> {
+    let sub = session.subscription.as_ref()?;
+    match event {
+        MarketDataEvent::Trade(trade) if sub.filter.matches(event) => Some(json!({
+            "jsonrpc": JSONRPC_VERSION,
+            "method": TRADES_NOTIFICATION,
+            "params": { "subscription": sub.id, "result": trade },
+        })),
+        _ => None,
+    }
+}
+
+/// Router mit `/rpc` (POST und WebSocket); wird in `build_rest_api_with_auth` gemergt.
+pub fn jsonrpc_routes<S>(state: AppState, auth: Option<RoleAuth>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/rpc", post(rpc_http).get(rpc_ws))
+        .with_state(JsonRpcService::new(state, auth))
+}
+
+async fn rpc_http(State(rpc): State<JsonRpcService>, headers: HeaderMap, body: String) -> Response {
+    let caller = rpc.principal_from_headers(&headers);
+    match rpc.handle_payload(caller.as_ref(), &body, None) {
+        Some(resp) => (StatusCode::OK, Json(resp)).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    }
+}
+
+async fn rpc_ws(ws: WebSocketUpgrade, State(rpc): State<JsonRpcService>, headers: HeaderMap) -> impl IntoResponse {
+    let caller = rpc.principal_from_headers(&headers);
+    // Wie /ws/marketdata: vor dem Upgrade abonnieren
+    let rx = rpc.state.market_data.subscribe();
+    ws.on_upgrade(move |socket| rpc_ws_loop(socket, rpc, caller, rx))
+}
+
+async fn rpc_ws_loop(
+    mut socket: WebSocket,
+    rpc: JsonRpcService,
+    caller: Option<Principal>,
+    mut rx: broadcast::Receiver<MarketDataEvent>,
+) {
+    let mut session = RpcSession::default();
+    loop {
+        let outgoing = tokio::select! {
+            event = rx.recv() => match event {
+                Ok(event) => match trade_notification(&session, &event) {
+                    Some(note) => note,
+                    None => continue,
+                },
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    warn!("JSON-RPC => Client {} Events im Rückstand, Verbindung wird getrennt", n);
+                    break;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => match rpc.handle_payload(caller.as_ref(), &text, Some(&mut session)) {
+                    Some(resp) => resp,
+                    None => continue,
+                },
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        match tokio::time::timeout(CLIENT_SEND_TIMEOUT, socket.send(Message::Text(outgoing.to_string()))).await {
+            Ok(Ok(())) => {}
+            Ok(Err(_)) => break,
+            Err(_) => {
+                warn!("JSON-RPC => Client zu langsam (Send-Timeout), Verbindung wird getrennt");
+                break;
+            }
+        }
+    }
+    let _ = socket.close().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_loader::load_config;
+    use crate::market_data::{MarketDataHub, TradeEvent};
+    use crate::node_logic::DexNode;
+    use crate::shard_logic::ShardManager;
+    use crate::trade_history::TradeHistory;
+    use futures::StreamExt;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+    fn app_state() -> AppState {
+        let cfg = load_config("config/node_config.yaml").unwrap();
+        AppState {
+            node: Arc::new(DexNode::new(cfg, None)),
+            shard_manager: ShardManager::new(3, None),
+            market_data: MarketDataHub::new(),
+            trade_history: TradeHistory::new(16),
+        }
+    }
+
+    #[test]
+    fn test_batch_request_mixes_results_errors_and_notifications() {
+        let state = app_state();
+        state.node.user_deposit("alice", "BTC", 2.0);
+        let rpc = JsonRpcService::new(state, None);
+
+        let batch = r#"[
+            {"jsonrpc":"2.0","id":1,"method":"place_order","params":{"user_id":"alice","coin_to_sell":"BTC","coin_to_buy":"USDT","amount":1.0,"price":30000.0,"side":"sell"}},
+            {"jsonrpc":"2.0","id":2,"method":"get_balance","params":{"user_id":"alice","coin":"BTC"}},
+            {"jsonrpc":"2.0","method":"get_book","params":{"market":"BTC/USDT"}},
+            {"jsonrpc":"2.0","id":3,"method":"get_book","params":{"market":"BTC/USDT"}}
+        ]"#;
+        let resp = rpc.handle_payload(None, batch, None).unwrap();
+        let arr = resp.as_array().unwrap();
+        // Notification ohne id => keine Antwort
+        assert_eq!(arr.len(), 3);
+        assert_eq!(arr[0]["id"], 1);
+        assert_eq!(arr[0]["result"]["status"], "open");
+        assert_eq!(arr[1]["id"], 2);
+        assert!(arr[1]["result"].is_number());
+        assert_eq!(arr[2]["result"]["asks"][0][1], 1.0);
+
+        assert!(rpc.handle_payload(None, r#"[{"jsonrpc":"2.0","method":"get_book","params":{"market":"x"}}]"#, None).is_none());
+        let empty = rpc.handle_payload(None, "[]", None).unwrap();
+        assert_eq!(empty["error"]["code"], INVALID_REQUEST);
+    }
+
+    #[test]
+    fn test_error_objects() {
+        let auth = RoleAuth::new().with_tokens(Role::ReadOnly, vec!["readonly".to_string()]);
+        let rpc = JsonRpcService::new(app_state(), Some(auth));
+        let call = |role: Option<Role>, body: &str| {
+            let caller = role.map(|role| Principal { role, user_id: None });
+            rpc.handle_payload(caller.as_ref(), body, None).unwrap()
+        };
+
+        let unknown = call(None, r#"{"jsonrpc":"2.0","id":"a","method":"nope"}"#);
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(unknown["id"], "a");
+        assert!(unknown.get("result").is_none());
+
+        let bad_params = call(None, r#"{"jsonrpc":"2.0","id":1,"method":"get_book","params":{"wrong":1}}"#);
+        assert_eq!(bad_params["error"]["code"], INVALID_PARAMS);
+
+        assert_eq!(call(None, "{not json")["error"]["code"], PARSE_ERROR);
+        assert_eq!(call(None, r#"{"jsonrpc":"1.0","id":1,"method":"get_book"}"#)["error"]["code"], INVALID_REQUEST);
+
+        let order = r#"{"jsonrpc":"2.0","id":7,"method":"place_order","params":{"user_id":"bob","coin_to_sell":"BTC","coin_to_buy":"USDT","amount":1.0,"price":1.0,"side":"sell"}}"#;
+        assert_eq!(call(None, order)["error"]["code"], UNAUTHORIZED);
+        assert_eq!(call(Some(Role::ReadOnly), order)["error"]["code"], FORBIDDEN);
+
+        // Fachlicher Fehler der Node => -32000 mit DexError-Code in data
+        let app_err = call(Some(Role::Admin), order);
+        assert_eq!(app_err["error"]["code"], APPLICATION_ERROR);
+        assert!(app_err["error"]["data"]["code"].is_string());
+
+        let sub = call(None, r#"{"jsonrpc":"2.0","id":9,"method":"subscribe_trades"}"#);
+        assert_eq!(sub["error"]["code"], INVALID_REQUEST);
+    }
+
+    #[test]
+    fn test_place_order_only_for_own_account() {
+        let state = app_state();
+        state.node.user_deposit("alice", "BTC", 1.0);
+        let auth = RoleAuth::new().with_user_tokens(vec![("alice".to_string(), "alice-token".to_string())]);
+        let rpc = JsonRpcService::new(state.clone(), Some(auth));
+        let order = r#"{"jsonrpc":"2.0","id":1,"method":"place_order","params":{"user_id":"alice","coin_to_sell":"BTC","coin_to_buy":"USDT","amount":1.0,"price":100.0,"side":"sell"}}"#;
+
+        let bob = Principal { role: Role::Trader, user_id: Some("bob".into()) };
+        let denied = rpc.handle_payload(Some(&bob), order, None).unwrap();
+        assert_eq!(denied["error"]["data"]["code"], "forbidden");
+        let shared = Principal { role: Role::Trader, user_id: None };
+        assert_eq!(rpc.handle_payload(Some(&shared), order, None).unwrap()["error"]["data"]["code"], "forbidden");
+        assert_eq!(state.node.user_get_free_balance("alice", "BTC"), 1.0);
+
+        let alice = Principal { role: Role::Trader, user_id: Some("alice".into()) };
+        assert_eq!(rpc.handle_payload(Some(&alice), order, None).unwrap()["result"]["status"], "open");
+        assert_eq!(state.node.user_get_free_balance("alice", "BTC"), 0.0);
+    }
+
+    async fn next_json<S>(client: &mut S) -> Value
+    where
+        S: futures::Stream<Item = Result<WsMessage, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        match tokio::time::timeout(Duration::from_secs(5), client.next()).await.unwrap() {
+            Some(Ok(WsMessage::Text(t))) => serde_json::from_str(&t).unwrap(),
+            other => panic!("unerwartete Nachricht: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ws_subscribe_trades_receives_notification() {
+        use futures::SinkExt;
+        use std::net::SocketAddr;
+
+        let state = app_state();
+        let hub = state.market_data.clone();
+        let app: Router = jsonrpc_routes(state, None);
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr: SocketAddr = listener.local_addr().unwrap();
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+
+        let (mut client, _) = tokio_tungstenite::connect_async(format!("ws://{}/rpc", addr)).await.unwrap();
+        client
+            .send(WsMessage::Text(r#"{"jsonrpc":"2.0","id":1,"method":"subscribe_trades","params":{"markets":["BTC/USDT"]}}"#.into()))
+            .await
+            .unwrap();
+        let ack = next_json(&mut client).await;
+        let sub_id = ack["result"].as_str().unwrap().to_string();
+
+        let trade = |market: &str| MarketDataEvent::Trade(TradeEvent {
+            market: market.into(),
+            buy_order_id: "b1".into(),
+            sell_order_id: "s1".into(),
+            quantity: 0.5,
+            price: 100.0,
+            timestamp: 1,
+        });
+        hub.publish(trade("ETH/USDT"));
+        hub.publish(trade("BTC/USDT"));
+
+        let note = next_json(&mut client).await;
+        assert_eq!(note["method"], TRADES_NOTIFICATION);
+        assert!(note.get("id").is_none());
+        assert_eq!(note["params"]["subscription"], sub_id);
+        assert_eq!(note["params"]["result"]["market"], "BTC/USDT");
+    }
+}
@@ -18,6 +18,7 @@ use std::time::{Duration, Instant};
 
 use rand::Rng;
 use serde::{Serialize, Deserialize};
+use tokio::sync::mpsc::UnboundedSender;
 use tokio::time::sleep;
 use tracing::{info, warn, debug, error};
 
@@ -26,6 +27,15 @@ use crate::storage::replicated_db_layer::{DexDB, CrdtSnapshot};
 
 // Optionales ShardManager, falls du Self-Healing willst:
 use crate::shard_logic::ShardManager;
+use crate::shard_logic::rebalance::ShardTransfer;
+use crate::network::address_book::SharedAddressBook;
+use crate::network::peer_management::PeerManager;
+use crate::network::reliable_gossip::GossipWire;
+use crate::utils::lock::LockRecover;
+use crate::metrics::{ACTIVE_PEERS, DHT_BUCKET_OCCUPANCY, DHT_LOOKUP_DURATION};
+use crate::onboarding::auto_committee::ModeTransition;
+use crate::onboarding::dkg::SignedDkgMessage;
+use crate::consensus::sequencer::SequencerWire;
 
 // -----------------------------------------
 // NodeId: 256-Bit, Distanzberechnungen, Hilfsmethoden
@@ -44,6 +54,12 @@ impl NodeId {
         NodeId(id)
     }
 
+    /// Stabile NodeId aus einem Ed25519-Key (SHA-256), z. B. für SWIM-Member-IDs
+    pub fn from_public_key(pk: &ed25519_dalek::PublicKey) -> Self {
+        use sha2::{Digest, Sha256};
+        NodeId(Sha256::digest(pk.as_bytes()).into())
+    }
+
     /// XOR mit anderem NodeId, Ergebnis als neue NodeId
     pub fn xor(&self, other: &NodeId) -> NodeId {
         let mut result = [0u8; ID_LENGTH];
@@ -104,6 +120,24 @@ pub enum KademliaMessage {
 
     // NEU => Für CRDT-Sync
     CrdtSnapshots(Vec<CrdtSnapshot>),
+
+    // DKG-Zeremonie des Onboarding-Komitees (signiert, siehe onboarding::dkg)
+    Dkg(SignedDkgMessage),
+
+    // Onboarding-Moduswechsel (von Fullnodes signiert, siehe auto_committee)
+    ModeTransition(ModeTransition),
+
+    // Rebalancing: Shard-Transfer-Log an das neue Replikat (siehe shard_logic::rebalance)
+    ShardTransfer(ShardTransfer),
+
+    // Reliable Gossip: signierte Broadcasts + Anti-Entropy (siehe network::reliable_gossip)
+    ReliableGossip(GossipWire),
+
+    // VRF-Sequencer: Claims, signierte Batches, Orders an den Sequencer (siehe consensus::sequencer)
+    Sequencer(SequencerWire),
+
+    // Nakamoto-Blöcke (bincode; PoW und Fork-Choice siehe consensus::engine)
+    Block(Vec<u8>),
 }
 
 // -----------------------------------------
@@ -130,23 +164,27 @@ impl KBucket {
         }
     }
 
-    /// upsert => nach vorn
-    pub fn upsert(&mut self, node_id: NodeId, address: SocketAddr) {
+    /// upsert => nach vorn; liefert den dafür verdrängten Eintrag
+    pub fn upsert(&mut self, node_id: NodeId, address: SocketAddr) -> Option<(NodeId, SocketAddr)> {
         if let Some(pos) = self.entries.iter().position(|e| e.node_id == node_id) {
             let mut entry = self.entries.remove(pos).unwrap();
             entry.last_seen = Instant::now();
             entry.address = address;
             self.entries.push_front(entry);
+            None
         } else {
-            if self.entries.len() >= self.capacity {
-                self.entries.pop_back();
-            }
+            let evicted = if self.entries.len() >= self.capacity {
+                self.entries.pop_back().map(|e| (e.node_id, e.address))
+            } else {
+                None
+            };
             let entry = BucketEntry {
                 node_id,
                 address,
                 last_seen: Instant::now(),
             };
             self.entries.push_front(entry);
+            evicted
         }
     }
 
@@ -195,17 +233,40 @@ impl RoutingTable {
         ID_LENGTH * 8 - 1
     }
 
-    pub fn update_node(&mut self, node_id: NodeId, address: SocketAddr) {
+    /// Trägt den Node ein; liefert den verdrängten Eintrag (volle Bucket).
+    pub fn update_node(&mut self, node_id: NodeId, address: SocketAddr) -> Option<(NodeId, SocketAddr)> {
         if node_id == self.local_id {
-            return;
+            return None;
         }
         let idx = self.bucket_index(&node_id);
-        self.buckets[idx].upsert(node_id, address);
+        let evicted = self.buckets[idx].upsert(node_id, address);
+        self.publish_metrics(idx);
+        evicted
+    }
+
+    /// Aktuelle Adresse eines Nodes in der Tabelle
+    pub fn address_of(&self, node_id: &NodeId) -> Option<SocketAddr> {
+        let idx = self.bucket_index(node_id);
+        self.buckets[idx]
+            .entries
+            .iter()
+            .find(|e| &e.node_id == node_id)
+            .map(|e| e.address)
     }
 
     pub fn remove_node(&mut self, node_id: &NodeId) {
         let idx = self.bucket_index(node_id);
         self.buckets[idx].remove(node_id);
+        self.publish_metrics(idx);
+    }
+
+    /// Aktualisiert Bucket-Belegung und Peer-Anzahl in den Prometheus-Metriken.
+    fn publish_metrics(&self, idx: usize) {
+        DHT_BUCKET_OCCUPANCY
+            .with_label_values(&[&idx.to_string()])
+            .set(self.buckets[idx].entries.len() as i64);
+        let total: usize = self.buckets.iter().map(|b| b.entries.len()).sum();
+        ACTIVE_PEERS.set(total as i64);
     }
 
     pub fn find_closest(&self, target: &NodeId, k: usize) -> Vec<(NodeId, SocketAddr)> {
@@ -292,6 +353,27 @@ pub struct KademliaService {
     // NEU => optionaler ShardManager (für on_node_failed)
     pub shard_manager: Option<Arc<ShardManager>>,
 
+    // Persistentes Adressbuch => direkt gesehene Peers (kein Hörensagen)
+    pub address_book: Option<SharedAddressBook>,
+
+    // IP-Filter und GeoIP/ASN-Diversität für neue Tabelleneinträge (None => alle)
+    pub peer_manager: Option<Arc<PeerManager>>,
+
+    // Empfänger für DKG-Nachrichten (None => verwerfen)
+    pub dkg_inbox: Option<UnboundedSender<SignedDkgMessage>>,
+
+    // Empfänger für ModeTransitions (None => verwerfen)
+    pub transition_inbox: Option<UnboundedSender<ModeTransition>>,
+
+    // Empfänger für Reliable-Gossip samt Absenderadresse (None => verwerfen)
+    pub gossip_inbox: Option<UnboundedSender<(SocketAddr, GossipWire)>>,
+
+    // Empfänger für Sequencer-Nachrichten (None => verwerfen)
+    pub sequencer_inbox: Option<UnboundedSender<SequencerWire>>,
+
+    // Empfänger für Blöcke der Peers (None => verwerfen)
+    pub block_inbox: Option<UnboundedSender<Vec<u8>>>,
+
     // Timeout => wie lange "last_seen" in BucketEntry akzeptabel
     // z.B. 300 Sek => danach Node veraltet => wir checken => if unresponsive => remove
     pub node_fail_timeout: Duration,
@@ -313,6 +395,13 @@ impl KademliaService {
             stop_flag: Arc::new(Mutex::new(false)),
             db: None,
             shard_manager: None,
+            address_book: None,
+            peer_manager: None,
+            dkg_inbox: None,
+            transition_inbox: None,
+            gossip_inbox: None,
+            sequencer_inbox: None,
+            block_inbox: None,
             node_fail_timeout: Duration::from_secs(300),
         }
     }
@@ -322,11 +411,112 @@ impl KademliaService {
         self.db = Some(db);
     }
 
+    /// Leitet empfangene DKG-Nachrichten an die laufende Zeremonie weiter.
+    pub fn set_dkg_inbox(&mut self, tx: UnboundedSender<SignedDkgMessage>) {
+        self.dkg_inbox = Some(tx);
+    }
+
+    /// Leitet gegossipte ModeTransitions an den Onboarding-State weiter.
+    pub fn set_transition_inbox(&mut self, tx: UnboundedSender<ModeTransition>) {
+        self.transition_inbox = Some(tx);
+    }
+
+    /// Leitet Reliable-Gossip an den GossipNode weiter; Antworten gehen an die Absenderadresse.
+    pub fn set_gossip_inbox(&mut self, tx: UnboundedSender<(SocketAddr, GossipWire)>) {
+        self.gossip_inbox = Some(tx);
+    }
+
+    /// Leitet Sequencer-Claims, -Batches und -Orders an den Sequencer-Task weiter.
+    pub fn set_sequencer_inbox(&mut self, tx: UnboundedSender<SequencerWire>) {
+        self.sequencer_inbox = Some(tx);
+    }
+
+    /// Leitet Blöcke der Peers an die ConsensusEngine weiter.
+    pub fn set_block_inbox(&mut self, tx: UnboundedSender<Vec<u8>>) {
+        self.block_inbox = Some(tx);
+    }
+
     /// Falls du Self-Healing via shard_manager.on_node_failed => setze ihn
     pub fn set_shard_manager(&mut self, sm: Arc<ShardManager>) {
         self.shard_manager = Some(sm);
     }
 
+    /// Direkt gesehene Peers landen zusätzlich im Adressbuch
+    pub fn set_address_book(&mut self, book: SharedAddressBook) {
+        self.address_book = Some(book);
+    }
+
+    /// Neue Tabelleneinträge laufen über den PeerManager; bereits eingetragene
+    /// Peers (Adressbuch, Bootstrap) zählen ohne Prüfung mit.
+    pub fn set_peer_manager(&mut self, pm: Arc<PeerManager>) {
+        for (_, _, addr) in self.table.all_entries() {
+            pm.track_peer(addr);
+        }
+        self.peer_manager = Some(pm);
+    }
+
+    /// Darf `node_id` unter `addr` in die Tabelle? Bei Adresswechsel wird die
+    /// alte Adresse freigegeben und bei Ablehnung wiederhergestellt.
+    fn admit(&self, node_id: &NodeId, addr: SocketAddr) -> bool {
+        let Some(pm) = &self.peer_manager else {
+            return true;
+        };
+        match self.table.address_of(node_id) {
+            Some(old) if old == addr => true,
+            Some(old) => {
+                pm.remove_peer(&old);
+                if pm.admit_peer(addr) {
+                    true
+                } else {
+                    pm.track_peer(old);
+                    false
+                }
+            }
+            None => pm.admit_peer(addr),
+        }
+    }
+
+    /// RoutingTable-Eintrag nach PeerManager-Prüfung; verdrängte Einträge
+    /// geben ihren Platz im PeerManager frei.
+    fn upsert_peer(&mut self, node_id: NodeId, addr: SocketAddr) -> bool {
+        if node_id == self.local_id {
+            return false;
+        }
+        if !self.admit(&node_id, addr) {
+            debug!("Node {} ({}) vom PeerManager abgelehnt", node_id_to_hex(&node_id), addr);
+            return false;
+        }
+        if let Some((_, old_addr)) = self.table.update_node(node_id, addr) {
+            if let Some(pm) = &self.peer_manager {
+                pm.remove_peer(&old_addr);
+            }
+        }
+        true
+    }
+
+    /// Übernimmt Nodes vom Hörensagen (FindNodeResult): neue Nodes in der
+    /// Reihenfolge `refresh_priority`, unterrepräsentierte Regionen zuerst.
+    fn insert_peers(&mut self, nodes: Vec<(NodeId, SocketAddr)>) {
+        let Some(pm) = self.peer_manager.clone() else {
+            for (nid, addr) in nodes {
+                self.table.update_node(nid, addr);
+            }
+            return;
+        };
+        let (known, new): (Vec<_>, Vec<_>) = nodes
+            .into_iter()
+            .partition(|(nid, _)| self.table.address_of(nid).is_some());
+        for (nid, addr) in known {
+            self.upsert_peer(nid, addr);
+        }
+        let addrs: Vec<SocketAddr> = new.iter().map(|(_, a)| *a).collect();
+        for addr in pm.refresh_priority(&addrs) {
+            if let Some((nid, _)) = new.iter().find(|(_, a)| *a == addr) {
+                self.upsert_peer(nid.clone(), addr);
+            }
+        }
+    }
+
     /// Startet die Hintergrundprozesse => bucket refresh + node-failure-detection
     pub async fn run_service(&self) {
         info!("KademliaService {} => starting main loop", hex::encode(&self.local_id.0));
@@ -413,6 +603,9 @@ impl KademliaService {
 
     /// Node entfernen => optional shard_manager.on_node_failed
     pub fn remove_node(&self, node_id: &NodeId) {
+        if let (Some(pm), Some(addr)) = (&self.peer_manager, self.table.address_of(node_id)) {
+            pm.remove_peer(&addr);
+        }
         self.table.remove_node(node_id);
         if let Some(sm) = &self.shard_manager {
             info!("Kademlia => Node {:?} removed => call shard_manager.on_node_failed", hex::encode(&node_id.0[..4]));
@@ -422,6 +615,7 @@ impl KademliaService {
 
     /// find_node => parallel alpha, wie gehabt
     pub async fn find_node(&self, target: NodeId) -> Vec<(NodeId, SocketAddr)> {
+        let _lookup_timer = DHT_LOOKUP_DURATION.start_timer();
         let alpha = 3;
         let k = self.table.bucket_size;
         let mut closest = self.table.find_closest(&target, k);
@@ -460,27 +654,56 @@ impl KademliaService {
         closest
     }
 
+    /// Sendet an einen Node aus der Routing-Tabelle; false, wenn er unbekannt ist.
+    pub fn send_to_node(&self, node: &NodeId, msg: &KademliaMessage) -> bool {
+        let addr = self
+            .table
+            .all_entries()
+            .into_iter()
+            .find(|(nid, _, _)| nid == node)
+            .map(|(_, _, addr)| addr);
+        match addr {
+            Some(addr) => {
+                self.send_msg(addr, msg);
+                true
+            }
+            None => false,
+        }
+    }
+
     fn send_msg(&self, addr: SocketAddr, msg: &KademliaMessage) {
         let locked = self.p2p.lock().unwrap();
         locked.send_kademlia_msg(addr, msg);
     }
 
+    /// Peer hat uns selbst eine Nachricht geschickt => RoutingTable + Adressbuch
+    fn saw_peer(&mut self, node_id: NodeId, addr: SocketAddr) {
+        if let Some(book) = &self.address_book {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            book.lock_recover().observe(node_id.clone(), addr, now);
+        }
+        self.upsert_peer(node_id, addr);
+    }
+
     /// handle_message => P2P-Callback
     pub fn handle_message(&mut self, sender_addr: SocketAddr, msg: KademliaMessage) {
         match msg {
             KademliaMessage::Ping(node_id) => {
                 debug!("Received PING from {}", node_id_to_hex(&node_id));
-                self.table.update_node(node_id.clone(), sender_addr);
+                self.saw_peer(node_id.clone(), sender_addr);
                 let pong = KademliaMessage::Pong(self.local_id.clone());
                 self.send_msg(sender_addr, &pong);
             }
             KademliaMessage::Pong(node_id) => {
                 debug!("Received PONG from {}", node_id_to_hex(&node_id));
-                self.table.update_node(node_id, sender_addr);
+                self.saw_peer(node_id, sender_addr);
             }
             KademliaMessage::FindNode { source, target } => {
                 debug!("Received FIND_NODE from {}, target={}", node_id_to_hex(&source), node_id_to_hex(&target));
-                self.table.update_node(source.clone(), sender_addr);
+                self.saw_peer(source.clone(), sender_addr);
                 let k = self.table.bucket_size;
                 let closer = self.table.find_closest(&target, k);
                 let result = KademliaMessage::FindNodeResult {
@@ -491,14 +714,12 @@ impl KademliaService {
             }
             KademliaMessage::FindNodeResult { source, closer_nodes } => {
                 debug!("Received FindNodeResult from {}, {} nodes", node_id_to_hex(&source), closer_nodes.len());
-                self.table.update_node(source.clone(), sender_addr);
-                for (nid, addr) in closer_nodes {
-                    self.table.update_node(nid, addr);
-                }
+                self.saw_peer(source.clone(), sender_addr);
+                self.insert_peers(closer_nodes);
             }
             KademliaMessage::Store { source, key, data } => {
                 debug!("Received STORE from {}, key={:?}, data.len={}", node_id_to_hex(&source), key, data.len());
-                self.table.update_node(source, sender_addr);
+                self.saw_peer(source, sender_addr);
                 self.storage.store(key.clone(), data.clone());
                 let ack = KademliaMessage::StoreResult {
                     source: self.local_id.clone(),
@@ -508,11 +729,11 @@ impl KademliaService {
             }
             KademliaMessage::StoreResult { source, stored } => {
                 debug!("Received StoreResult => stored={}, from {}", stored, node_id_to_hex(&source));
-                self.table.update_node(source, sender_addr);
+                self.saw_peer(source, sender_addr);
             }
             KademliaMessage::FindValue { source, key } => {
                 debug!("Received FIND_VALUE from {}, key={:?}", node_id_to_hex(&source), key);
-                self.table.update_node(source.clone(), sender_addr);
+                self.saw_peer(source.clone(), sender_addr);
                 let data_opt = self.storage.lookup(&key).map(|v| v.to_vec());
                 let mut closer_nodes = vec![];
                 if data_opt.is_none() {
@@ -533,7 +754,7 @@ impl KademliaService {
                     data.as_ref().map(|d| d.len()),
                     closer_nodes.len()
                 );
-                self.table.update_node(source, sender_addr);
+                self.saw_peer(source, sender_addr);
                 // optional: hier local cachen
             }
 
@@ -548,6 +769,55 @@ impl KademliaService {
                     warn!("Received CRDT-Snapshots, but no db is set in KademliaService!");
                 }
             }
+
+            // Signatur prüft die DKG-Session selbst
+            KademliaMessage::Dkg(env) => {
+                debug!("Received DKG message from {}", env.msg.sender());
+                match &self.dkg_inbox {
+                    Some(tx) if tx.send(env).is_ok() => {}
+                    _ => debug!("Keine laufende DKG-Zeremonie => Nachricht verworfen"),
+                }
+            }
+
+            // Signaturen und Schwelle prüft OnboardingGlobalState
+            KademliaMessage::ModeTransition(t) => {
+                debug!("Received ModeTransition epoch {} ({} approvals)", t.epoch, t.approvals.len());
+                match &self.transition_inbox {
+                    Some(tx) if tx.send(t).is_ok() => {}
+                    _ => debug!("Kein Onboarding-State => ModeTransition verworfen"),
+                }
+            }
+
+            // Claims und Batch-Signaturen prüft SequencingState
+            KademliaMessage::Sequencer(w) => match &self.sequencer_inbox {
+                Some(tx) if tx.send(w).is_ok() => {}
+                _ => debug!("Kein Sequencer-Task => Nachricht verworfen"),
+            },
+
+            // PoW und Fork-Choice prüft ConsensusEngine::receive_block
+            KademliaMessage::Block(b) => match &self.block_inbox {
+                Some(tx) if tx.send(b).is_ok() => {}
+                _ => debug!("Keine ConsensusEngine => Block verworfen"),
+            },
+
+            // Signaturen und angefragte Hashes prüft GossipNode::on_wire
+            KademliaMessage::ReliableGossip(w) => match &self.gossip_inbox {
+                Some(tx) if tx.send((sender_addr, w)).is_ok() => {}
+                _ => debug!("Kein GossipNode => Reliable-Gossip verworfen"),
+            },
+
+            // Signaturen und Anker prüft ShardManager::install_transfer
+            KademliaMessage::ShardTransfer(t) => {
+                debug!("Received ShardTransfer shard {} ({} deltas)", t.shard_id, t.deltas.len());
+                match &self.shard_manager {
+                    Some(sm) => {
+                        if let Err(e) = sm.receive_transfer(&t) {
+                            warn!("ShardTransfer für Shard {} abgelehnt: {:?}", t.shard_id, e);
+                        }
+                    }
+                    None => debug!("Kein ShardManager => ShardTransfer verworfen"),
+                }
+            }
         }
     }
 }
@@ -602,3 +872,90 @@ pub async fn run_kademlia() -> Result<(), Box<dyn std::error::Error>> {
     info!("Kademlia-Demo => ende");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::peer_management::{DiversityPolicy, PeerDiscoveryConfig};
+    use crate::utils::geoip_and_ntp::StaticGeoResolver;
+
+    struct NullAdapter;
+
+    impl KademliaP2PAdapter for NullAdapter {
+        fn send_kademlia_msg(&self, _addr: SocketAddr, _msg: &KademliaMessage) {}
+        fn local_address(&self) -> SocketAddr {
+            SocketAddr::from(([127, 0, 0, 1], 9000))
+        }
+    }
+
+    fn service(pm: Arc<PeerManager>) -> KademliaService {
+        let mut svc = KademliaService::new(NodeId([0; 32]), 20, Arc::new(Mutex::new(NullAdapter)));
+        svc.set_peer_manager(pm);
+        svc
+    }
+
+    fn manager() -> Arc<PeerManager> {
+        let mut res = StaticGeoResolver::default();
+        for i in 1..=4u8 {
+            res.insert(std::net::IpAddr::from([10, 0, 0, i]), "DE", 3320);
+        }
+        res.insert(std::net::IpAddr::from([10, 1, 0, 1]), "FR", 5410);
+        let policy = DiversityPolicy { max_per_country: 8, max_per_asn: 2, max_onion: 8 };
+        Arc::new(PeerManager::new(PeerDiscoveryConfig::new()).with_geo_diversity(Arc::new(res), policy))
+    }
+
+    fn addr(ip: [u8; 4]) -> SocketAddr {
+        SocketAddr::from((ip, 9000))
+    }
+
+    #[test]
+    fn test_peer_manager_gates_routing_table() {
+        let pm = manager();
+        let mut svc = service(pm.clone());
+        for i in 1..=3u8 {
+            svc.handle_message(addr([10, 0, 0, i]), KademliaMessage::Ping(NodeId([i; 32])));
+        }
+        // AS3320 ist nach zwei Peers voll
+        assert_eq!(svc.table.all_entries().len(), 2);
+        assert!(svc.table.address_of(&NodeId([3; 32])).is_none());
+
+        // Entfernen gibt den Platz frei
+        svc.remove_node(&NodeId([1; 32]));
+        svc.handle_message(addr([10, 0, 0, 3]), KademliaMessage::Ping(NodeId([3; 32])));
+        assert_eq!(svc.table.address_of(&NodeId([3; 32])), Some(addr([10, 0, 0, 3])));
+        assert_eq!(pm.diversity_distribution().0.get("DE"), Some(&2));
+    }
+
+    #[test]
+    fn test_find_node_result_prefers_underrepresented_regions() {
+        let pm = manager();
+        let mut svc = service(pm.clone());
+        svc.handle_message(addr([10, 0, 0, 1]), KademliaMessage::Ping(NodeId([1; 32])));
+        let result = KademliaMessage::FindNodeResult {
+            source: NodeId([1; 32]),
+            closer_nodes: vec![
+                (NodeId([2; 32]), addr([10, 0, 0, 2])),
+                (NodeId([3; 32]), addr([10, 0, 0, 3])),
+                (NodeId([5; 32]), addr([10, 1, 0, 1])),
+            ],
+        };
+        svc.handle_message(addr([10, 0, 0, 1]), result);
+        assert!(svc.table.address_of(&NodeId([5; 32])).is_some());
+        // nur noch ein Platz in AS3320
+        let de = [2u8, 3].iter().filter(|b| svc.table.address_of(&NodeId([**b; 32])).is_some()).count();
+        assert_eq!(de, 1);
+    }
+
+    #[test]
+    fn test_address_change_keeps_old_slot_when_rejected() {
+        let pm = manager();
+        let mut svc = service(pm.clone());
+        svc.handle_message(addr([10, 0, 0, 1]), KademliaMessage::Ping(NodeId([1; 32])));
+        svc.handle_message(addr([10, 0, 0, 2]), KademliaMessage::Ping(NodeId([2; 32])));
+        svc.handle_message(addr([10, 1, 0, 1]), KademliaMessage::Ping(NodeId([5; 32])));
+        // Node 5 meldet sich aus dem vollen AS3320 => bleibt bei alter Adresse
+        svc.handle_message(addr([10, 0, 0, 3]), KademliaMessage::Ping(NodeId([5; 32])));
+        assert_eq!(svc.table.address_of(&NodeId([5; 32])), Some(addr([10, 1, 0, 1])));
+        assert_eq!(pm.diversity_distribution().0.get("FR"), Some(&1));
+    }
+}
@@ -18,6 +18,7 @@ use anyhow::Result;
 
 // Hier importieren wir unser KademliaService + NodeId:
 use crate::kademlia::kademlia_service::{KademliaService, NodeId};
+use crate::utils::lock::LockRecover;
 
 /// Einfache Konfiguration für mDNS:
 /// - `service_name` ist typischerweise "_mydex._udp" oder "_mydex._udp.local"
@@ -100,7 +101,7 @@ pub async fn start_mdns_discovery(
                                 let node_id = NodeId::random();
 
                                 // In KademliaService eintragen
-                                let mut kad = kademlia.lock().unwrap();
+                                let mut kad = kademlia.lock_recover();
                                 kad.table.update_node(node_id, sock);
 
                                 debug!(
@@ -21,6 +21,16 @@ pub mod crypto;
 pub mod identity {
     pub mod wallet;
     pub mod accounts;
+    pub mod balance_ledger;
+    pub mod keystore;
+}
+
+// Onboarding: Admin-/Komitee-Modus, DKG für den Komitee-Schlüssel.
+// (auto_onboarding, committee_phase_b, gatekeeper_phase_a und
+// onboarding_manager sind ältere Entwürfe und bleiben außen vor.)
+pub mod onboarding {
+    pub mod auto_committee;
+    pub mod dkg;
 }
 
 // Sybil-Schutz, Protokoll, etc.
@@ -30,14 +40,27 @@ pub mod protocol;
 // Network => hier fügen wir das p2p_adapter hinzu:
 pub mod network {
     pub mod tcp;
+    pub mod handler;
     pub mod noise;
     pub mod secure_channel;
     pub mod p2p_adapter; // NEU: echter P2P-TCP-Adapter
+    pub mod peer_management;
+    pub mod address_book;
+    pub mod tor;
+    pub mod stun;
+    pub mod turn;
+    pub mod port_mapping;
+    pub mod gossip_config;
+    pub mod reliable_gossip;
 }
 
 // Rate Limiting, Konsens, Noise, Secure Channel ...
 pub mod rate_limiting;
 pub mod consensus;
+
+// Blöcke + Mempool (ausstehende Transaktionen)
+pub mod block;
+pub mod mempool;
 pub mod noise;
 pub mod secure_channel;
 pub mod p2p_order_matcher;
@@ -55,25 +78,46 @@ pub mod dex_logic {
     pub mod htlc;
     pub mod sign_utils;
     pub mod time_limited_orders; // <== Hier einbinden
-    // optional: gossip, fuzz_test, etc.
+    pub mod commit_reveal;
+    pub mod itc_crdt_orderbook;
+    pub mod circuit_breaker;
+    pub mod gossip;
+    pub mod fuzz_test;
 }
 
 // Zusätzliche Demos (falls benötigt)
 pub mod cross_chain_demo;
 pub mod node_simulation;
 pub mod limit_orderbook_demo;
+pub mod itc_crdt_demo;
 
 // Logging, Metrik, Tracing
 pub mod logging;
 pub mod metrics;
+pub mod health_report;
+pub mod market_data;
+pub mod trade_history;
+pub mod shutdown;
 pub mod tracing_setup;
 pub mod config_loader;
 pub mod node_logic;
 
+// CRDT-State + Matching-Engine (von node_logic und den fuzz-Targets genutzt)
+pub mod crdt_logic;
+pub mod matching_engine;
+pub mod settlement;
+pub mod security {
+    pub mod security_validator;
+    pub mod global_security_facade;
+    pub mod advanced_security;
+    pub mod privacy;
+}
+
 // Storage + Error
 pub mod error;
 pub mod storage {
     pub mod db_layer;
+    pub mod migrations;
     pub mod replicated_db_layer;
 }
 
@@ -83,8 +127,18 @@ pub mod fees {
     // ggf. weitere Fees-Module
 }
 
+// Sanktionslisten + Screening
+pub mod sanctions {
+    pub mod sanctions_list;
+    pub mod internal_analysis;
+    pub mod update_manager;
+}
+
 // Utils => HLC / GeoIP etc.
 pub mod utils {
     pub mod hlc;
     pub mod geoip_and_ntp;
+    pub mod canonical;
+    pub mod clock;
+    pub mod lock;
 }
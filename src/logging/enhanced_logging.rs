@@ -14,11 +14,24 @@
 // diese JSON-Logs sammeln und weiterverarbeiten.
 ///////////////////////////////////////////////////////////
 
-use tracing_subscriber::{fmt, EnvFilter, layer::SubscriberExt, Registry};
+use tracing_subscriber::{fmt, reload, EnvFilter, layer::SubscriberExt, Registry};
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing::{info, error};
+use once_cell::sync::OnceCell;
 use std::io;
 
+/// Handle auf den EnvFilter => Log-Level zur Laufzeit änderbar (Config-Hot-Reload).
+static LOG_FILTER: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+
+/// Setzt das Log-Level des laufenden Subscribers neu.
+pub fn set_log_level(log_level: &str) -> Result<(), String> {
+    let filter = EnvFilter::try_new(log_level).map_err(|e| format!("invalid log level {}: {}", log_level, e))?;
+    let handle = LOG_FILTER.get().ok_or_else(|| "logging not initialised".to_string())?;
+    handle.reload(filter).map_err(|e| e.to_string())?;
+    info!("Log-Level => {}", log_level);
+    Ok(())
+}
+
 /// Initialisiert das erweiterte, strukturierte Logging-System.
 ///
 /// - `log_level`: Gewünschtes Log-Level (z. B. "info", "debug", "trace").
@@ -29,8 +42,10 @@ use std::io;
 /// in eine tagesrotierte Datei geschrieben. Dadurch kannst du sie
 /// in einem zentralen Log-System analysieren.
 pub fn init_enhanced_logging(log_level: &str, log_dir: &str, log_file: &str) {
-    // 1) Definiere einen EnvFilter auf Basis des angegebenen Log-Levels.
-    let filter = EnvFilter::new(log_level);
+    // 1) Definiere einen EnvFilter auf Basis des angegebenen Log-Levels
+    //    (hinter einem reload-Layer, siehe set_log_level).
+    let (filter, filter_handle) = reload::Layer::new(EnvFilter::new(log_level));
+    let _ = LOG_FILTER.set(filter_handle);
 
     // 2) Erstelle einen RollingFileAppender, der täglich rotiert.
     let file_appender = RollingFileAppender::new(Rotation::Daily, log_dir, log_file);
@@ -37,7 +37,7 @@
 // 24) Warten auf Ctrl+C => geordneter Shutdown.
 //
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use tokio::signal;
 use tracing::{info, warn, error, instrument, debug};
 use std::net::SocketAddr;
@@ -49,8 +49,10 @@ use chrono::{Utc};
 
 use crate::config_loader::{load_config, NodeConfig};
 use crate::node_logic::DexNode;
-use crate::storage::db_layer::{DexDB, CrdtSnapshot};
+use crate::storage::db_layer::DexDB;
 use crate::tracing_setup::shutdown_tracing;
+use crate::shutdown::ShutdownCoordinator;
+use crate::utils::lock::LockRecover;
 use crate::monitoring::global_monitoring::start_global_monitoring_server;
 use crate::monitoring::node_monitoring::start_node_monitoring_server;
 use crate::security::async_security_tasks::run_security_tasks;
@@ -66,7 +68,6 @@ use crate::identity::wallet::{
     BitcoinRPCConfig, ETHConfig, LTCConfig,
 };
 use crate::fees::fee_pool::FeePool;
-use crate::dex_logic::time_limited_orders::check_expired_time_limited_orders;
 use crate::network::p2p_adapter::TcpP2PAdapter;
 
 use axum::{
@@ -84,11 +85,12 @@ use crate::dex_logic::time_limited_orders::TimeLimitedOrderManager;
 use crate::self_healing::custom_checks::inject_orderbook;
 use crate::dex_logic::crdt_orderbook::OrderBookCRDT;
 use crate::security::global_security_facade::GlobalSecuritySystem;
-use crate::matching_engine::MatchingEngine;
+use crate::matching_engine::{MarketPair, MarketShards, MatchingEngine};
 use crate::crypto_scraper::PriceFeed;
 
 // Zusätzliche Imports für IPFS Storage
-use crate::storage::ipfs_storage::{add_file_to_ipfs, cat_file_from_ipfs};
+use crate::storage::ipfs_storage::cat_file_from_ipfs;
+use crate::storage::ipfs_fallback::{IpfsFallbackStore, StoredRef};
 
 // Importiere den IPFS-Manager (aus src/ipfs_manager.rs)
 mod ipfs_manager;
@@ -98,12 +100,16 @@ use ipfs_manager::start_ipfs_daemon;
 // Integration des neuen Monitoring-Moduls inklusive metrics_server
 // ─────────────────────────────────────────────────────────────
 mod monitoring {
+    // Prozessweiter Event-Logger (Singleton), siehe src/monitoring/logging.rs
+    pub mod logging;
     // Hier wird das neue Modul "metrics_server" eingeführt.
     pub mod metrics_server {
-        use axum::{Router, routing::get, http::StatusCode, Server};
-        /// Diese Funktion startet einen einfachen Metrics-Server, der unter `/metrics` immer den Status OK zurückgibt.
+        use axum::Server;
+        /// Startet den Metrics-Server; `/metrics` liefert die Prometheus-Registry
+        /// aus `crate::metrics` im Text-Exposition-Format.
         pub async fn run_metrics_server() {
-            let app = Router::new().route("/metrics", get(|| async { StatusCode::OK }));
+            crate::metrics::register_metrics();
+            let app = crate::metrics::metrics_router();
             let addr = "127.0.0.1:9300".parse().unwrap();
             println!("Metrics server started on {}", addr);
             if let Err(e) = Server::bind(&addr)
@@ -121,10 +127,12 @@ mod monitoring {
 mod gossip {
     use chrono::{DateTime, Utc};
     use serde::{Serialize, Deserialize};
-    use std::collections::HashMap;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::Mutex;
     use std::time::{Duration, Instant};
-    use tokio::sync::{mpsc, RwLock};
+    use tokio::sync::{Notify, RwLock};
     use tokio::time::sleep;
+    use crate::metrics::GOSSIP_DROPPED;
     use tracing::{info, warn};
     use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
     use hex;
@@ -167,92 +175,221 @@ mod gossip {
             ttl: u64,
             keypair: &Keypair,
         ) -> Self {
-            let timestamp = Utc::now();
-            let message_str = format!("{}{}{}{}{}", node_id, fault_type, timestamp, log_excerpt, severity);
-            let signature = keypair.sign(message_str.as_bytes());
-            FaultMessage {
-                node_id,
-                fault_type,
-                timestamp,
-                log_excerpt,
-                severity,
-                ttl,
-                signature: Some(hex::encode(signature.to_bytes())),
+            let mut msg = FaultMessage::new(node_id, fault_type, log_excerpt, severity, ttl);
+            let bytes = msg.signing_bytes().expect("FaultMessage enthält nur Strings/Zeitstempel");
+            msg.signature = Some(hex::encode(keypair.sign(&bytes).to_bytes()));
+            msg
+        }
+
+        /// Kanonische Bytes ohne `ttl` (wird beim Weiterleiten verringert) und Signatur.
+        pub fn signing_bytes(&self) -> Result<Vec<u8>, crate::error::DexError> {
+            #[derive(Serialize)]
+            struct FaultSigningView<'a> {
+                node_id: &'a str,
+                fault_type: &'a str,
+                timestamp: &'a DateTime<Utc>,
+                log_excerpt: &'a str,
+                severity: &'a str,
             }
+            crate::utils::canonical::signing_bytes("my_dex/fault/v1", &FaultSigningView {
+                node_id: &self.node_id,
+                fault_type: &self.fault_type,
+                timestamp: &self.timestamp,
+                log_excerpt: &self.log_excerpt,
+                severity: &self.severity,
+            })
         }
 
         pub fn verify(&self, public_key: &PublicKey) -> bool {
-            if let Some(sig_hex) = &self.signature {
-                if let Ok(sig_bytes) = hex::decode(sig_hex) {
-                    if let Ok(signature) = Signature::from_bytes(&sig_bytes) {
-                        let message_str = format!("{}{}{}{}{}", self.node_id, self.fault_type, self.timestamp, self.log_excerpt, self.severity);
-                        return public_key.verify(message_str.as_bytes(), &signature).is_ok();
+            let signature = match self.signature.as_ref()
+                .and_then(|h| hex::decode(h).ok())
+                .and_then(|b| Signature::from_bytes(&b).ok())
+            {
+                Some(sig) => sig,
+                None => return false,
+            };
+            match self.signing_bytes() {
+                Ok(bytes) => public_key.verify(&bytes, &signature).is_ok(),
+                Err(_) => false,
+            }
+        }
+    }
+
+    /// Verhalten bei voller Queue.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum OverflowPolicy {
+        /// Älteste wartende Nachricht verwerfen, neue annehmen (Default:
+        /// bei einem Fault-Sturm sind die neuesten Meldungen die relevanten)
+        DropOldest,
+        /// Neue Nachricht ablehnen, `broadcast` liefert Err
+        RejectNew,
+    }
+
+    /// Beschränkte Queue; `push` blockiert nie.
+    struct BoundedQueue {
+        items: Mutex<VecDeque<FaultMessage>>,
+        notify: Notify,
+        capacity: usize,
+        policy: OverflowPolicy,
+    }
+
+    impl BoundedQueue {
+        fn push(&self, msg: FaultMessage) -> Result<(), String> {
+            {
+                let mut items = self.items.lock().unwrap();
+                if items.len() >= self.capacity {
+                    match self.policy {
+                        OverflowPolicy::DropOldest => {
+                            items.pop_front();
+                            GOSSIP_DROPPED.with_label_values(&["queue_drop_oldest"]).inc();
+                        }
+                        OverflowPolicy::RejectNew => {
+                            GOSSIP_DROPPED.with_label_values(&["queue_reject"]).inc();
+                            return Err(format!("gossip queue full ({})", self.capacity));
+                        }
                     }
                 }
+                items.push_back(msg);
+            }
+            self.notify.notify_one();
+            Ok(())
+        }
+
+        fn try_pop(&self) -> Option<FaultMessage> {
+            self.items.lock().unwrap().pop_front()
+        }
+
+        async fn pop(&self) -> FaultMessage {
+            loop {
+                if let Some(msg) = self.try_pop() {
+                    return msg;
+                }
+                self.notify.notified().await;
             }
-            false
+        }
+    }
+
+    /// Dedup-Cache mit TTL und Obergrenze; bei voller Kapazität fliegt der
+    /// älteste Eintrag (Einfügereihenfolge) raus.
+    pub struct GossipCache {
+        entries: HashMap<String, (FaultMessage, Instant)>,
+        order: VecDeque<String>,
+        capacity: usize,
+    }
+
+    impl GossipCache {
+        fn new(capacity: usize) -> Self {
+            Self { entries: HashMap::new(), order: VecDeque::new(), capacity }
+        }
+
+        /// false, wenn die Nachricht schon bekannt war.
+        fn insert(&mut self, key: String, msg: FaultMessage, expiration: Instant) -> bool {
+            if self.entries.contains_key(&key) {
+                return false;
+            }
+            while self.entries.len() >= self.capacity {
+                let Some(oldest) = self.order.pop_front() else { break };
+                if self.entries.remove(&oldest).is_some() {
+                    GOSSIP_DROPPED.with_label_values(&["cache_evicted"]).inc();
+                }
+            }
+            self.order.push_back(key.clone());
+            self.entries.insert(key, (msg, expiration));
+            true
+        }
+
+        fn retain_unexpired(&mut self, now: Instant) -> usize {
+            let before = self.entries.len();
+            self.entries.retain(|_, &mut (_, exp)| exp > now);
+            let entries = &self.entries;
+            self.order.retain(|k| entries.contains_key(k));
+            before - self.entries.len()
+        }
+
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        pub fn messages(&self) -> impl Iterator<Item = &FaultMessage> {
+            self.order.iter().filter_map(|k| self.entries.get(k).map(|(m, _)| m))
         }
     }
 
     pub struct GossipManager {
-        pub sender: mpsc::Sender<FaultMessage>,
-        pub receiver: mpsc::Receiver<FaultMessage>,
-        pub cache: RwLock<HashMap<String, (FaultMessage, Instant)>>,
+        queue: BoundedQueue,
+        pub cache: RwLock<GossipCache>,
         pub ttl: Duration,
     }
 
     impl GossipManager {
+        /// Cache-Obergrenze ohne explizite Angabe: 16x Queue-Kapazität.
         pub fn new(ttl: Duration, channel_capacity: usize) -> Self {
-            let (sender, receiver) = mpsc::channel(channel_capacity);
+            Self::with_limits(ttl, channel_capacity, channel_capacity.saturating_mul(16), OverflowPolicy::DropOldest)
+        }
+
+        pub fn with_limits(ttl: Duration, channel_capacity: usize, max_cache_entries: usize, policy: OverflowPolicy) -> Self {
             GossipManager {
-                sender,
-                receiver,
-                cache: RwLock::new(HashMap::new()),
+                queue: BoundedQueue {
+                    items: Mutex::new(VecDeque::with_capacity(channel_capacity)),
+                    notify: Notify::new(),
+                    capacity: channel_capacity.max(1),
+                    policy,
+                },
+                cache: RwLock::new(GossipCache::new(max_cache_entries.max(1))),
                 ttl,
             }
         }
 
+        /// Nimmt die Nachricht in Cache und Queue auf, ohne je zu blockieren.
+        /// Mit `RejectNew` und voller Queue => Err.
         pub async fn broadcast(&self, msg: FaultMessage) -> Result<(), String> {
             let serialized = serde_json::to_string(&msg).map_err(|e| e.to_string())?;
             let expiration = Instant::now() + self.ttl;
-            {
-                let mut cache = self.cache.write().await;
-                cache.insert(serialized.clone(), (msg.clone(), expiration));
-            }
-            self.sender.send(msg).await.map_err(|e| e.to_string())?;
+            self.queue.push(msg.clone())?;
+            self.cache.write().await.insert(serialized, msg, expiration);
             Ok(())
         }
 
-        pub async fn process_incoming(&self) {
-            while let Some(msg) = self.receiver.recv().await {
-                info!("Received gossip message: {:?}", msg);
-                let serialized = match serde_json::to_string(&msg) {
-                    Ok(s) => s,
-                    Err(e) => {
-                        warn!("Error serializing message: {}", e);
-                        continue;
-                    }
-                };
-                {
-                    let mut cache = self.cache.write().await;
-                    if !cache.contains_key(&serialized) {
-                        let expiration = Instant::now() + self.ttl;
-                        cache.insert(serialized, (msg, expiration));
-                    }
+        pub fn queued(&self) -> usize {
+            self.queue.items.lock().unwrap().len()
+        }
+
+        async fn ingest(&self, msg: FaultMessage) {
+            info!("Received gossip message: {:?}", msg);
+            let serialized = match serde_json::to_string(&msg) {
+                Ok(s) => s,
+                Err(e) => {
+                    warn!("Error serializing message: {}", e);
+                    return;
                 }
+            };
+            let expiration = Instant::now() + self.ttl;
+            self.cache.write().await.insert(serialized, msg, expiration);
+        }
+
+        pub async fn process_incoming(&self) {
+            loop {
+                let msg = self.queue.pop().await;
+                self.ingest(msg).await;
+            }
+        }
+
+        /// Verarbeitet alles, was gerade in der Queue liegt; liefert die Anzahl.
+        pub async fn drain_pending(&self) -> usize {
+            let mut n = 0;
+            while let Some(msg) = self.queue.try_pop() {
+                self.ingest(msg).await;
+                n += 1;
             }
+            n
         }
 
         pub async fn cleanup_cache(&self) {
             loop {
                 sleep(Duration::from_secs(10)).await;
-                let now = Instant::now();
-                let mut cache = self.cache.write().await;
-                let before = cache.len();
-                cache.retain(|_, &mut (_, exp)| exp > now);
-                let after = cache.len();
-                if before != after {
-                    info!("Cache cleanup: {} messages removed", before - after);
+                let removed = self.cache.write().await.retain_unexpired(Instant::now());
+                if removed > 0 {
+                    info!("Cache cleanup: {} messages removed", removed);
                 }
             }
         }
@@ -261,6 +398,47 @@ mod gossip {
     pub async fn broadcast_gossip_message(msg: FaultMessage) {
         println!("Broadcasting Fault Message: {:?}", msg);
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn fault(i: usize) -> FaultMessage {
+            FaultMessage::new("node".into(), "invariant".into(), format!("fault #{}", i), "critical".into(), 3)
+        }
+
+        #[tokio::test]
+        async fn test_fault_flood_stays_bounded_and_keeps_newest() {
+            let gm = GossipManager::with_limits(Duration::from_secs(60), 16, 64, OverflowPolicy::DropOldest);
+            for i in 0..10_000 {
+                gm.broadcast(fault(i)).await.unwrap();
+                assert!(gm.queued() <= 16);
+                if i % 100 == 99 {
+                    gm.drain_pending().await;
+                }
+            }
+            assert_eq!(gm.queued(), 16);
+            gm.drain_pending().await;
+            assert_eq!(gm.queued(), 0);
+
+            let cache = gm.cache.read().await;
+            assert_eq!(cache.len(), 64);
+            assert_eq!(cache.order.len(), 64);
+            let newest: Vec<String> = cache.messages().map(|m| m.log_excerpt.clone()).collect();
+            let expected: Vec<String> = (10_000 - 64..10_000).map(|i| format!("fault #{}", i)).collect();
+            assert_eq!(newest, expected);
+        }
+
+        #[tokio::test]
+        async fn test_reject_new_policy_refuses_when_full() {
+            let gm = GossipManager::with_limits(Duration::from_secs(60), 2, 8, OverflowPolicy::RejectNew);
+            gm.broadcast(fault(0)).await.unwrap();
+            gm.broadcast(fault(1)).await.unwrap();
+            assert!(gm.broadcast(fault(2)).await.is_err());
+            assert_eq!(gm.drain_pending().await, 2);
+            gm.broadcast(fault(3)).await.unwrap();
+        }
+    }
 }
 
 mod self_healing;
@@ -295,11 +473,13 @@ use crate::self_healing::{
         Err(format!("Failed to restart service '{}' after {} attempts", service_name, max_attempts))
     }
 
+    /// Lokale Upload-Queue zuerst (IPFS evtl. down), dann IPFS.
     pub async fn load_backup_config() -> Option<String> {
-        match crate::storage::ipfs_storage::cat_file_from_ipfs("backup_config_hash").await {
+        let store = crate::storage::ipfs_fallback::IpfsFallbackStore::default_store();
+        match store.get("backup_config_hash").await {
             Ok(data) => Some(String::from_utf8_lossy(&data).to_string()),
             Err(e) => {
-                warn!("Failed to load backup config from IPFS: {:?}", e);
+                warn!("Failed to load backup config (local queue / IPFS): {:?}", e);
                 None
             }
         }
@@ -339,8 +519,7 @@ use crate::self_healing::{
 // ─────────────────────────────────────────────────────────────
 // Integration des Reliable Gossip Moduls
 // ─────────────────────────────────────────────────────────────
-mod reliable_gossip;
-use reliable_gossip::{GossipMessage, GossipNode as ReliableGossipNode};
+use crate::network::reliable_gossip::GossipNode as ReliableGossipNode;
 
 use crate::crypto::fallback_config::{load_backup_config_with_retry, verify_config_signature};
 
@@ -352,59 +531,20 @@ mod sanctions {
     pub mod update_manager;
 }
 
-mod monitoring_logging {
-    use chrono::{DateTime, Utc};
-    use std::sync::{Mutex, Arc};
-
-    #[derive(Debug, Clone)]
-    pub struct LogEntry {
-        pub timestamp: DateTime<Utc>,
-        pub user_type: String,
-        pub event: String,
-    }
-
-    pub struct Logger {
-        logs: Mutex<Vec<LogEntry>>,
-    }
-
-    impl Logger {
-        pub fn new() -> Self {
-            Logger { logs: Mutex::new(Vec::new()) }
-        }
-
-        pub fn log_event(&self, user_type: &str, event: &str) {
-            let entry = LogEntry {
-                timestamp: Utc::now(),
-                user_type: user_type.to_string(),
-                event: event.to_string(),
-            };
-            let mut logs = self.logs.lock().unwrap();
-            logs.push(entry);
-        }
-
-        pub fn get_logs_for_user(&self, user_type: &str) -> Vec<LogEntry> {
-            let logs = self.logs.lock().unwrap();
-            logs.iter().filter(|e| e.user_type == user_type).cloned().collect()
-        }
-
-        pub fn get_all_logs(&self) -> Vec<LogEntry> {
-            let logs = self.logs.lock().unwrap();
-            logs.clone()
-        }
-    }
-
-    pub fn get_global_logger() -> Arc<Logger> {
-        Arc::new(Logger::new())
-    }
-}
-
-use monitoring_logging::{get_global_logger, Logger, LogEntry};
+use monitoring::logging::{get_global_logger, Logger};
 
 // ─────────────────────────────────────────────────────────────
 // REST API Modul Integration
 // ─────────────────────────────────────────────────────────────
 mod rest_api;
-use rest_api::{build_rest_api, AppState};
+mod health_report;
+mod jsonrpc;
+mod market_data;
+use market_data::MarketDataHub;
+mod trade_history;
+use trade_history::{TradeHistory, DEFAULT_HISTORY_CAPACITY};
+use rest_api::{build_rest_api_with_auth, serve_router, AppState, RoleAuth};
+use health_report::{HealthRegistry, HealthStatus};
 
 ///////////////////////////////////////////////////////////
 // Integration des neuen asynchronen Sicherheits-Tasks-Moduls
@@ -500,6 +640,9 @@ async fn main() -> Result<()> {
     let logger: Arc<Logger> = get_global_logger();
     logger.log_event("system", "Global Logger initialisiert.");
 
+    // Shutdown-Koordinator: Token für alle langlaufenden Subsysteme
+    let mut shutdown = ShutdownCoordinator::new(Duration::from_secs(30));
+
     // IPFS-Daemon starten (lokal, aus "my_dex/.ipfs/bin/")
     match start_ipfs_daemon() {
         Ok(()) => {
@@ -531,16 +674,25 @@ async fn main() -> Result<()> {
     info!("HSM-/TPM-Key-Management: HSM-Session erfolgreich geöffnet.");
 
     // (2) Health-Probes und Download-Endpunkt
-    start_health_server().await;
-    logger.log_event("system", "Health Server gestartet.");
+    // Der Server startet nach dem Laden der Config (Audit-Schlüssel aus dem Keystore), siehe (4.0a)
+    // Subsysteme registrieren ihre Probes, sobald sie existieren (siehe unten)
+    let health = HealthRegistry::new();
+    health.register("startup", true, |_| {
+        if IS_READY.load(Ordering::Relaxed) {
+            (HealthStatus::Healthy, "DexNode gestartet".into())
+        } else {
+            (HealthStatus::Unhealthy, "Start läuft".into())
+        }
+    });
+    health.register("ipfs", false, |_| {
+        health_report::check_ipfs(
+            crate::metrics::IPFS_AVAILABLE.get() != 0,
+            crate::metrics::IPFS_PENDING_UPLOADS.get(),
+        )
+    });
 
     // (3) Integration der regulatorischen Sanktionslisten
-    println!("Starte Integration der regulatorischen Sanktionslisten...");
-    match sanctions::update_manager::update_sanctions_list() {
-        Ok(_) => println!("Update der Sanktionsliste erfolgreich."),
-        Err(e) => println!("Update-Prozess fehlgeschlagen: {}", e),
-    }
-    logger.log_event("system", "Sanktionslisten aktualisiert.");
+    // => erst nach dem Laden der Config (Publisher-Keys), siehe (4.1)
 
     // (4) Node-Konfiguration laden
     let cfg_path = "config/node_config.yaml";
@@ -548,25 +700,89 @@ async fn main() -> Result<()> {
         Ok(cfg) => cfg,
         Err(e) => {
             log_error(e);
-            use crate::crypto::fallback_config::{load_backup_config_with_retry, verify_config_signature};
+            use crate::crypto::fallback_config::{load_backup_config_with_retry, open_signed_config};
+            use crate::crypto::key_loader::{load_config_verifying_key, CONFIG_SIGNING_KEY_PATH};
+            // Schlüssel zuerst: ohne lokalen Trust-Anchor kein Fallback
+            let public_key_hex = load_config_verifying_key(CONFIG_SIGNING_KEY_PATH)
+                .map_err(|e| anyhow::anyhow!("No config signing key for fallback: {}", e))?;
             let backup_config = load_backup_config_with_retry("config_backup_hash", 5, Duration::from_secs(1)).await?;
-            // config-Signatur wird hier verifiziert => gut
-            if !verify_config_signature(&backup_config, "signature_placeholder", "public_key_placeholder") {
-                return Err(anyhow::anyhow!("Fallback configuration signature is invalid"));
-            }
-            serde_yaml::from_str(&backup_config).context("Failed to parse fallback configuration")?
+            let verified = open_signed_config(&backup_config, &public_key_hex)?;
+            serde_yaml::from_str(&verified).context("Failed to parse fallback configuration")?
         }
     };
-    // TIPP: Du könntest hier optional negative Fee-Werte, etc. abfangen,
-    // falls es in config misst. 
+    // Unsichere / widersprüchliche Werte => Start abbrechen
+    config.validate().context("Node-Konfiguration ungültig")?;
+    // Demo-Tokens oder fehlende TLS-Zertifikate => nicht starten statt Panic im REST-Task
+    config.check_rest_security().context("REST-Konfiguration unsicher oder unvollständig")?;
+    // Passphrase aus Env-Variable, Secret-Datei oder (nur Demo) Klartext
+    let keystore_pass = config.keystore_passphrase().context("Keystore-Passphrase nicht verfügbar")?;
     logger.log_event("system", "Node-Konfiguration geladen.");
 
+    // (4.0a) Node-Schlüssel für Audit-Log, signierte Audit-Exporte und Fee-Pool-Audit.
+    // Bleibt über Neustarts gleich, damit die Audit-Kette verifizierbar bleibt.
+    let audit_keypair = Arc::new(
+        crate::identity::keystore::load_or_create_keypair(
+            &config.keystore_path,
+            &keystore_pass,
+            crate::identity::keystore::AUDIT_SIGNING_LABEL,
+        )
+        .context("Audit-Schlüssel konnte nicht aus dem Keystore geladen werden")?,
+    );
+    start_health_server(audit_keypair.clone(), health.clone()).await;
+    logger.log_event("system", "Health Server gestartet.");
+
+    // (4.0) Clock-Skew: NTP-Offset periodisch messen
+    let clock_guard = Arc::new(
+        crate::utils::geoip_and_ntp::ClockSkewGuard::new(config.max_clock_skew_ms)
+            .with_max_age_ms(config.max_message_age_ms),
+    );
+    {
+        let guard = clock_guard.clone();
+        let servers = config.ntp_servers.clone();
+        shutdown.spawn("ntp_skew", move |token| {
+            crate::utils::geoip_and_ntp::run_skew_monitor(guard, servers, Duration::from_secs(300), token)
+        });
+    }
+
+    // (4.1) Sanktionsliste: nur signierte, versionierte Updates werden aktiv
+    {
+        use crate::sanctions::update_manager::SanctionsUpdater;
+        // Von validate() geprüft; ein unlesbarer Key bricht den Start ab statt
+        // still Updates dieses Publishers zu verwerfen
+        let publishers: Vec<ed25519_dalek::PublicKey> = config
+            .sanctions_publishers
+            .iter()
+            .map(|h| {
+                hex::decode(h)
+                    .ok()
+                    .and_then(|b| ed25519_dalek::PublicKey::from_bytes(&b).ok())
+                    .with_context(|| format!("sanctions_publishers: ungültiger Ed25519-Key {}", h))
+            })
+            .collect::<Result<_>>()?;
+        match SanctionsUpdater::load("sanctions_state.json", publishers) {
+            Ok(mut updater) => {
+                if let Err(e) = updater.apply_updates_from_dir("sanctions_updates") {
+                    warn!("Sanktions-Updates konnten nicht gelesen werden: {}", e);
+                }
+                info!("Sanktionsliste aktiv => v{}", updater.version());
+                updater.publish_global();
+            }
+            Err(e) => error!("Sanktionszustand unlesbar, Standardliste bleibt aktiv: {}", e),
+        }
+        logger.log_event("system", "Sanktionslisten aktualisiert.");
+    }
+
     // (5) Logging & Audit einrichten
     init_enhanced_logging(&config.log_level, "./logs", "audit.log");
     info!("Node startet => node_id={}, log_level={}", config.node_id, config.log_level);
     write_audit_log("Node-Start: Konfiguration und Logging initialisiert.");
     logger.log_event("system", "Enhanced Logging initialisiert.");
 
+    // (5.1) Config-Hot-Reload (Datei-Änderung oder SIGHUP)
+    let config_reloader = crate::config_loader::ConfigReloader::new(cfg_path, config.clone());
+    let live_config = config_reloader.subscribe();
+    shutdown.spawn("config_reload", move |token| config_reloader.run(Duration::from_secs(5), token));
+
     // (6) DB initialisieren
     let db = match DexDB::open_with_retries(
         &config.db_path,
@@ -583,15 +799,26 @@ async fn main() -> Result<()> {
     write_audit_log("DB initialisiert.");
     logger.log_event("system", "Datenbank initialisiert.");
 
-    // CRDT-Snapshot-Test
-    let snap = CrdtSnapshot { version: 1, data: vec![1, 2, 3, 4] };
-    if let Err(e) = db.store_crdt_snapshot(&snap) {
-        log_error(e);
+    // CRDT-Snapshot-Test: Heads liegen in einer replizierten DB, nebenläufige
+    // Heads werden über den OR-Set-Merge aus crdt_logic vereinigt.
+    // Empfangene Snapshots mit HLC aus der Zukunft werden verworfen.
+    let crdt_db = Arc::new(
+        crate::storage::replicated_db_layer::DexDB::open_with_retries(
+            &format!("{}/crdt", config.db_path),
+            config.db_max_retries,
+            config.db_backoff_sec,
+        )?
+        .with_snapshot_merge(crate::crdt_logic::snapshot_merge_fn(&config.node_id))
+        .with_snapshot_check(crate::crdt_logic::snapshot_skew_check(clock_guard.clone())),
+    );
+    let demo_state = bincode::serialize(&crate::crdt_logic::CrdtState::new(&config.node_id).snapshot())?;
+    if let Err(e) = crdt_db.commit_local(&config.node_id, demo_state) {
+        error!("CRDT-Snapshot nicht gespeichert: {:?}", e);
     }
-    match db.load_crdt_snapshot(1) {
-        Ok(Some(loaded)) => info!("CRDT-Snapshot loaded => version={}, data={:?}", loaded.version, loaded.data),
+    match crdt_db.load_crdt_snapshot(&config.node_id, 1) {
+        Ok(Some(loaded)) => info!("CRDT-Snapshot loaded => version={}, vector={:?}, {} Bytes", loaded.version, loaded.vector, loaded.data.len()),
         Ok(None) => info!("No snapshot found for v=1"),
-        Err(e) => log_error(e),
+        Err(e) => error!("CRDT-Snapshot nicht lesbar: {:?}", e),
     }
     write_audit_log("CRDT-Snapshot-Demo abgeschlossen.");
     logger.log_event("system", "CRDT-Snapshot-Test durchgeführt.");
@@ -602,36 +829,62 @@ async fn main() -> Result<()> {
         let db_listen_addr: SocketAddr = "127.0.0.1:5002".parse()?;
         let local_db_instance = RocksDBInstance::new(&config.db_path)?;
         let distributed_db = DistributedDexDB::new(Box::new(local_db_instance), vec![], db_listen_addr);
-        distributed_db.start_replication_server().await?;
+        let replication = distributed_db.start_replication_server_until(shutdown.token()).await?;
+        shutdown.track("replication", replication);
         info!("Distributed DB replication server gestartet auf {}", db_listen_addr);
     }
 
-    // (6.2) ClusterManager => Node-Sync-Fee
-    {
-        let mut cluster_mgr = ClusterManager::new(&config, &db);
+    // (6.2) ClusterManager => SWIM-Membership (signiert) + Node-Sync-Fee.
+    // Die Membership-Sicht geht an ShardManager (6.3) und FeePool (16).
+    let (cluster_mgr, swim_membership) = {
+        use crate::network::cluster_management::ClusterManagerConfig;
+        let swim_key = Arc::new(
+            crate::identity::keystore::load_or_create_keypair(
+                &config.keystore_path,
+                &keystore_pass,
+                crate::identity::keystore::SWIM_SIGNING_LABEL,
+            )
+            .context("SWIM-Schlüssel konnte nicht aus dem Keystore geladen werden")?,
+        );
+        let local_id = NodeId::from_public_key(&swim_key.public);
+        // Fullnode-Accounts tragen diese ID als user_id (FeePool::is_member_alive)
+        info!("SWIM => member id {}", crate::network::cluster_management::member_key(&local_id));
+        let cluster_cfg = ClusterManagerConfig {
+            mdns_service_name: "_mydex._udp.local.".to_string(),
+            kademlia_bootstrap_nodes: Vec::new(),
+            local_id,
+            kademlia_bucket_size: 20,
+            snapshot_sync_interval: Duration::from_secs(60),
+            swim: config.swim.to_config().map_err(|e| anyhow::anyhow!("swim: {}", e))?,
+        };
+        let cluster_db = crate::storage::replicated_db_layer::DexDB::open(&format!("{}/cluster", config.db_path))?;
+        let mut cluster_mgr = ClusterManager::new(cluster_cfg, Arc::new(cluster_db)).with_swim_key(swim_key);
         cluster_mgr.enable_sync_fee(0.01);
         cluster_mgr.start().await?;
-        info!("ClusterManager => Neuer Node-Sync-Fee-Ansatz aktiv => 1% Belohnung für Sync-Knoten");
+        info!("ClusterManager => SWIM aktiv, Sync-Fee 1% für Sync-Knoten");
         logger.log_event("system", "ClusterManager mit Extra Sync-Fee integriert.");
-    }
+        let membership = cluster_mgr.membership();
+        (cluster_mgr, membership)
+    };
 
     // (6.3) ShardManager mit CRDT initialisieren
-use crate::shard_logic::shard_manager::ShardManager;
+use crate::shard_logic::ShardManager;
 use crate::watchtower::Watchtower;
 use crate::crdt_logic::{CrdtDelta, Order};
 
 let shard_manager = {
-    let shard_manager = ShardManager::new(3, Some(kad_arc.clone()));
+    let shard_manager = ShardManager::new(3, Some(kad_arc.clone())).with_membership(swim_membership.clone());
 
     // 1) Shard erstellen
     let watchtower = Watchtower::new();
     shard_manager.create_shard(0, "db_shard_0.db", watchtower)?;
 
     // 2) Lokalen Node abonnieren
-    let local_id = kad_arc.lock().unwrap().local_id.clone();
+    let local_id = kad_arc.lock_recover().local_id.clone();
     shard_manager.subscribe_node_to_shard(&local_id.to_string(), 0);
 
     // 3) Delta anwenden
+    let mut clock = crate::utils::hlc::HybridLogicalClock::new(&local_id.to_string());
     let delta = CrdtDelta {
         updated_orders: vec![
             Order {
@@ -640,9 +893,14 @@ let shard_manager = {
                 timestamp: 0,
                 quantity: 1.5,
                 price: 99.0,
+                side: None,
+                hlc: clock.tick(),
+                signature: None,
+                public_key: None,
             }
         ],
         removed_orders: vec![],
+        hlc: clock.tick(),
     };
     shard_manager.apply_delta(0, &delta)?;
 
@@ -659,6 +917,23 @@ if let Some(orderbook) = shard_manager.get_orderbook_crdt(0) {
 }
 logger.log_event("system", "ShardManager mit CRDT initialisiert.");
 
+    // (6.4) Rebalancing: Kademlia meldet Ausfälle und liefert eingehende
+    // Shard-Transfers, periodisch wird die Platzierung angeglichen.
+    let shard_manager = Arc::new(shard_manager);
+    kad_arc.lock_recover().set_shard_manager(shard_manager.clone());
+    {
+        let sm = shard_manager.clone();
+        shutdown.spawn("shard_maintenance", move |token| async move {
+            let mut tick = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    _ = tick.tick() => sm.maintain_shards(),
+                }
+            }
+        });
+    }
+
 
    // (7) P2P-Security initialisieren (STUN/TURN)
     let p2p_sec_cfg = P2PSecurityConfig {
@@ -672,8 +947,27 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
         turn_username: config.turn_username.clone(),
         turn_password: config.turn_password.clone(),
     };
-    let p2p_sec = AdvancedP2PSecurity::new(p2p_sec_cfg).await?;
+    let p2p_sec = AdvancedP2PSecurity::new(p2p_sec_cfg).await?.with_subnet_limits((&config.rate_limits).into());
     info!("P2PSecurity-System initialisiert.");
+    {
+        // Config-Reload: neue rate_limits gelten sofort für den Subnetz-Limiter
+        let limiter = p2p_sec.subnet_limiter.clone();
+        let mut live = live_config.clone();
+        shutdown.spawn("config_live/rate_limits", move |token| async move {
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    r = live.changed() => {
+                        if r.is_err() {
+                            break;
+                        }
+                        let limits = live.borrow_and_update().rate_limits.clone();
+                        limiter.lock_recover().reconfigure((&limits).into());
+                    }
+                }
+            }
+        });
+    }
     logger.log_event("system", "P2PSecurity-System initialisiert.");
 
     if !config.stun_server.is_empty() {
@@ -684,59 +978,139 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
     }
     if !config.turn_server.is_empty() {
         match p2p_sec.turn_allocate_channel().await {
-            Ok(_) => info!("TURN => Channel allocated."),
+            Ok(relayed) => {
+                info!("TURN => Relay allocated at {}", relayed);
+                if let Some(turn) = p2p_sec.turn_client() {
+                    shutdown.spawn("turn_refresh", move |token| turn.run_refresh(token));
+                }
+            }
             Err(e) => warn!("TURN => allocate channel fehlgeschlagen: {:?}", e),
         }
     }
 
     // (8) DexNode anlegen & starten
+    let arc_db = Arc::new(Mutex::new(db));
     let mut node = DexNode::new(config.clone(), Some(global_sec_arc.clone()));
+    node.set_clock_guard(clock_guard.clone());
+    // Replay-Schutz: jede Order braucht eine frische Nonce, gemerkt in DexDB
+    node.set_nonce_registry(Arc::new(Mutex::new(
+        crate::decentralized_order_book::nonce_registry::NonceRegistry::with_db(arc_db.clone()),
+    )));
+    // Kill-Switch je Markt: Committee = allowed_node_pubkeys, Zustand überlebt Neustarts.
+    // Node (REST) und MatchingEngines teilen sich dieselbe Instanz.
+    let halt_control = match crate::matching_engine::MarketHaltControl::from_hex_keys(
+        &config.allowed_node_pubkeys,
+        config.market_halt_threshold(),
+    )
+    .and_then(|c| c.with_state_file("market_halt_state.json"))
+    {
+        Ok(control) => {
+            let control = Arc::new(Mutex::new(control));
+            node.set_halt_control(control.clone());
+            Some(control)
+        }
+        Err(e) => {
+            warn!("MarketHaltControl nicht aktiv (kein Committee?): {:?}", e);
+            None
+        }
+    };
     node.start().await?;
     IS_READY.store(true, Ordering::Relaxed);
     write_audit_log("DexNode erfolgreich gestartet.");
     logger.log_event("system", "DexNode gestartet.");
 
+    // Ausstehende Transaktionen (höchste Gebühr zuerst) für die Blockproduktion
+    let mempool = Arc::new(Mutex::new(crate::mempool::Mempool::default()));
+
     // (8.1) Sicherheits-Demo: Block erstellen, signieren und verifizieren
     {
-        use crate::block::{Block, Transaction};
-        use ed25519_dalek::Keypair;
+        use crate::block::{Block, LedgerState, Transaction};
+        use ed25519_dalek::{Keypair, PublicKey};
         use rand::rngs::OsRng;
-        use std::time::{SystemTime, UNIX_EPOCH};
+
+        // Demo-Ledger: alle Konten teilen sich den Demo-Schlüssel
+        struct DemoLedger {
+            balances: std::collections::HashMap<&'static str, u64>,
+            key: PublicKey,
+        }
+        impl LedgerState for DemoLedger {
+            fn balance(&self, account: &str) -> u64 {
+                *self.balances.get(account).unwrap_or(&0)
+            }
+            fn public_key(&self, _account: &str) -> Option<PublicKey> {
+                Some(self.key)
+            }
+            fn chain_id(&self) -> &str {
+                "my_dex-demo"
+            }
+            fn next_nonce(&self, _account: &str) -> u64 {
+                0
+            }
+        }
 
         let mut csprng = OsRng {};
         let demo_keypair: Keypair = Keypair::generate(&mut csprng);
+        let demo_ledger = DemoLedger {
+            balances: [("DemoAlice", 100), ("DemoBob", 50)].into_iter().collect(),
+            key: demo_keypair.public,
+        };
 
-        let demo_transactions = vec![
-            Transaction {
-                id: 101,
-                from: "DemoAlice".to_string(),
-                to: "DemoBob".to_string(),
-                amount: 42,
-            },
-            Transaction {
-                id: 102,
-                from: "DemoBob".to_string(),
-                to: "DemoCharlie".to_string(),
-                amount: 84,
-            },
+        let mut demo_transactions = vec![
+            Transaction::new(101, "DemoAlice", "DemoBob", 42).with_fee(1).with_chain_id("my_dex-demo"),
+            Transaction::new(102, "DemoBob", "DemoCharlie", 84).with_fee(2).with_chain_id("my_dex-demo"),
         ];
+        for tx in demo_transactions.iter_mut() {
+            tx.sign(&demo_keypair)?;
+        }
+        // Über den Mempool (Prüfung, Deckung, Gebührenreihenfolge); der Konsens
+        // nimmt dieselben Transaktionen in seinen nächsten Block auf
+        let demo_transactions = {
+            let mut pool = mempool.lock_recover();
+            for tx in demo_transactions {
+                let id = tx.id;
+                match pool.insert(tx, &demo_ledger) {
+                    Ok(outcome) => debug!("Demo-Tx {} im Mempool: {:?}", id, outcome),
+                    Err(e) => warn!("Demo-Tx {} nicht im Mempool: {}", id, e),
+                }
+            }
+            pool.select_for_block(&crate::block::BlockLimits::default())
+        };
 
-        let demo_timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Zeitfehler")
-            .as_secs();
+        // Blockproduktion nur mit NTP-geprüfter Uhr; Zeitstempel ist die korrigierte Zeit
+        let demo_block = match Block::produce(&clock_guard, 999, "0".to_string(), 0, demo_transactions) {
+            Ok(block) => Some(block),
+            Err(e) => {
+                warn!("Demo-Block nicht produziert: {:?}", e);
+                None
+            }
+        };
 
-        let mut demo_block = Block::new(999, "0".to_string(), demo_timestamp, 0, demo_transactions)
-            .expect("Fehler beim Erstellen des Demo-Blocks");
+        if let Some(mut demo_block) = demo_block {
+            // Block-Signing über den verschlüsselten Keystore (Secret nie im Klartext auf Platte)
+            use crate::identity::keystore::{Keystore, BLOCK_SIGNING_LABEL};
+            let mut keystore = if std::path::Path::new(&config.keystore_path).exists() {
+                Keystore::open(&config.keystore_path)?
+            } else {
+                let mut ks = Keystore::create(&keystore_pass)?;
+                ks.generate_key(BLOCK_SIGNING_LABEL)?;
+                ks.save(&config.keystore_path)?;
+                ks
+            };
+            keystore.unlock(&keystore_pass).context("Keystore konnte nicht entsperrt werden")?;
+            if keystore.public_key(BLOCK_SIGNING_LABEL).is_err() {
+                keystore.generate_key(BLOCK_SIGNING_LABEL)?;
+                keystore.save(&config.keystore_path)?;
+            }
+            let block_signing_key = keystore.public_key(BLOCK_SIGNING_LABEL)?;
 
-        demo_block.sign_block(&demo_keypair);
-        info!("Demo-Block erstellt und signiert: {:#?}", demo_block);
+            demo_block.sign_block_with_keystore(&keystore, BLOCK_SIGNING_LABEL)?;
+            keystore.lock();
+            info!("Demo-Block erstellt und signiert: {:#?}", demo_block);
 
-        let block_valid = demo_block.verify_block(&demo_keypair.public);
-        if block_valid {
-            info!("Demo-Block-Signatur ist gültig.");
-        } else {
-            error!("Demo-Block-Signatur ist ungültig!");
+            match demo_block.accept_checked(&block_signing_key, &demo_ledger, &clock_guard) {
+                Ok(()) => info!("Demo-Block ist gültig (Limits, Deckung, Signaturen)."),
+                Err(e) => error!("Demo-Block abgelehnt: {}", e),
+            }
         }
     }
 
@@ -772,31 +1146,231 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
         });
         info!("Light Client Konsensüberprüfung gestartet.");
     
+        let market_data_hub = MarketDataHub::new();
+        let trade_history = TradeHistory::new(DEFAULT_HISTORY_CAPACITY);
         let api_state = AppState {
             node: Arc::new(node.clone()),
+            shard_manager: (*shard_manager).clone(),
+            market_data: market_data_hub.clone(),
+            trade_history: trade_history.clone(),
         };
         
-        let api_router = build_rest_api(api_state);
+        // Abgelaufene / gefüllte Orders aus den Order-Metadaten entfernen
+        {
+            let node = api_state.node.clone();
+            tokio::spawn(async move {
+                let mut tick = tokio::time::interval(Duration::from_secs(60));
+                loop {
+                    tick.tick().await;
+                    let now = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0);
+                    let removed = node.prune_placed_orders(now);
+                    if removed > 0 {
+                        debug!("{} Order-Einträge abgelaufen oder geschlossen", removed);
+                    }
+                }
+            });
+        }
+
+        let api_auth = RoleAuth::from_config(&config);
+        let api_router = build_rest_api_with_auth(api_state, Some(api_auth));
+        let api_tls = config.tls_paths();
         tokio::spawn(async move {
             let addr = "0.0.0.0:8080".parse::<SocketAddr>().unwrap();
             info!("REST-API läuft auf {}", addr);
-            axum::Server::bind(&addr)
-                .serve(api_router.into_make_service())
-                .await
-                .expect("REST-API konnte nicht gestartet werden");
+            if let Err(e) = serve_router(api_router, addr, api_tls).await {
+                error!("REST-API beendet: {:?}", e);
+            }
     });
 
     // (9) MatchingEngine initialisieren
-    let mut engine = MatchingEngine::new_with_global_security(Some(global_sec_arc.clone()));
-    // Optional: Orders platzieren, etc.
+    {
+        let db = arc_db.clone();
+        health.register("db", true, move |_| {
+            health_report::check_db(
+                db.lock_recover().load_struct::<u8>("health/ping").map(|_| ()).map_err(|e| e.to_string()),
+            )
+        });
+        let queue = crate::settlement::settlement_queue::SettlementQueue::new(arc_db.clone());
+        let t = config.health.clone();
+        health.register("settlement_queue", false, move |_| match queue.pending_jobs() {
+            Ok(jobs) => health_report::check_settlement_queue(jobs.len(), &t),
+            Err(e) => (HealthStatus::Degraded, format!("Queue nicht lesbar: {}", e)),
+        });
+        // "consensus" registriert erst der Ketten-Task (health_report::register_consensus_probe)
+    }
+    // Trade-Historie: gleicher Speicher wie in der REST-API, zusätzlich persistiert
+    let trade_history = trade_history.with_db(arc_db.clone());
+    match trade_history.prune().and_then(|_| trade_history.restore_all()) {
+        Ok(n) => info!("Trade-Historie => {} Trades geladen", n),
+        Err(e) => warn!("Trade-Historie konnte nicht geladen werden: {:?}", e),
+    }
+    trade_history.spawn_feed(&market_data_hub);
+    let time_limited_manager = TimeLimitedOrderManager::new();
+    // Circuit-Breaker: Auslösen/Wiederaufnahme als FaultMessage an die Peers
+    let circuit_breaker = {
+        let (breaker_tx, mut breaker_rx) = tokio::sync::mpsc::unbounded_channel();
+        let node_id = config.node_id.clone();
+        shutdown.spawn("circuit_breaker_events", move |token| async move {
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    Some(ev) = breaker_rx.recv() => {
+                        let ev: crate::dex_logic::circuit_breaker::BreakerEvent = ev;
+                        let msg = crate::gossip::FaultMessage::new(
+                            node_id.clone(),
+                            "circuit_breaker".to_string(),
+                            ev.describe(),
+                            "warning".to_string(),
+                            60,
+                        );
+                        crate::gossip::broadcast_gossip_message(msg).await;
+                    }
+                }
+            }
+        });
+        Arc::new(Mutex::new(
+            crate::dex_logic::circuit_breaker::CircuitBreaker::new(config.circuit_breakers.clone())
+                .with_events(breaker_tx),
+        ))
+    };
+    let mut engine = MatchingEngine::new_with_global_security(Some(global_sec_arc.clone()))
+        .with_market_data("BTC/USDT", market_data_hub.clone())
+        .with_time_limited_manager(time_limited_manager.clone())
+        .with_clock_guard(clock_guard.clone())
+        .with_fee_schedule(config.trading_fees.clone())
+        .with_circuit_breaker(circuit_breaker.clone())
+        .with_order_limits(config.order_limits.clone())
+        .with_book_caps(config.book_caps.clone())
+        .with_admission_clock(crate::utils::hlc::HybridLogicalClock::new(&config.node_id))
+        .with_settlement_queue(crate::settlement::settlement_queue::SettlementQueue::new(arc_db.clone()))
+        .with_dry_run(config.dry_run);
+    if let Some(control) = &halt_control {
+        engine = engine.with_halt_control(control.clone());
+    }
+    if config.sequencer.enabled {
+        // Orders nur noch in der Reihenfolge des per VRF gewählten Sequencers
+        let election = config.sequencer.election().map_err(|e| anyhow::anyhow!("sequencer: {}", e))?;
+        engine = engine.with_sequencing(election);
+    }
+    if config.check_book_invariants {
+        // Invarianten-Verletzungen als FaultMessage an die Peers melden
+        let (fault_tx, mut fault_rx) = tokio::sync::mpsc::unbounded_channel();
+        engine = engine.with_invariant_checks(Some(fault_tx));
+        let node_id = config.node_id.clone();
+        shutdown.spawn("invariant_faults", move |token| async move {
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    Some(fault) = fault_rx.recv() => {
+                        let msg = crate::gossip::FaultMessage::new(
+                            node_id.clone(),
+                            "order_book_invariant".to_string(),
+                            format!("{}: {}", fault.market, fault.reason),
+                            "critical".to_string(),
+                            60,
+                        );
+                        crate::gossip::broadcast_gossip_message(msg).await;
+                    }
+                }
+            }
+        });
+    }
+    // Wallets und Accounts (Anomalie-Auto-Pause, Screening, Demo unten)
+    let btc_cfg = BitcoinRPCConfig {
+        rpc_url: "http://127.0.0.1:8332".into(),
+        rpc_user: "bitcoinrpc".into(),
+        rpc_pass: "pass".into(),
+    };
+    let ltc_cfg = LTCConfig {
+        rpc_url: "http://127.0.0.1:19332".into(),
+        rpc_user: "ltcrpc".into(),
+        rpc_pass: "pass".into(),
+    };
+    let eth_cfg = ETHConfig {
+        rpc_url: "https://mainnet.infura.io/v3/<yourKey>".into(),
+    };
+    let wmgr = WalletManager::new(
+        arc_db.lock_recover().clone(),
+        Some(btc_cfg),
+        Some(ltc_cfg),
+        Some(eth_cfg)
+    );
+    let cold_transfer = Arc::new(crate::fees::fee_pool::WalletColdTransfer::new(wmgr.clone()));
+    let acc_mgr = Arc::new(AccountsManager::new(arc_db.clone(), wmgr));
+    {
+        // Verhaltensanalyse der Trades; signierte Reports gehen per Gossip an die Fullnodes
+        use crate::sanctions::internal_analysis::{AnomalyConfig, AnomalyEngine, AnomalyReport};
+        let (report_tx, mut report_rx) = tokio::sync::mpsc::unbounded_channel::<AnomalyReport>();
+        let keypair = ed25519_dalek::Keypair::from_bytes(&audit_keypair.to_bytes()).expect("Audit-Schlüssel");
+        let anomaly = AnomalyEngine::new(AnomalyConfig::default(), keypair, Box::new(report_tx))
+            .with_pauser(Box::new(acc_mgr.clone()));
+        engine = engine.with_anomaly_engine(Arc::new(Mutex::new(anomaly)));
+        let node_id = config.node_id.clone();
+        shutdown.spawn("anomaly_reports", move |token| async move {
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    Some(report) = report_rx.recv() => {
+                        let msg = crate::gossip::FaultMessage::new(
+                            node_id.clone(),
+                            "anomaly_report".to_string(),
+                            serde_json::to_string(&report).unwrap_or_default(),
+                            "warning".to_string(),
+                            60,
+                        );
+                        crate::gossip::broadcast_gossip_message(msg).await;
+                    }
+                }
+            }
+        });
+    }
+    match engine.restore_book(&arc_db.lock_recover()) {
+        Ok(n) => info!("MatchingEngine => {} Orders aus DexDB wiederhergestellt", n),
+        Err(e) => warn!("MatchingEngine => Order-Book konnte nicht geladen werden: {:?}", e),
+    }
+    let is_follower = config.role.is_follower();
+    // Engine für Config-Reload (Gebühren) und Sequencer-Task
+    let mut matching_engine = None;
+    if is_follower {
+        info!("Node-Rolle follower => kein Matching, kein Settlement, keine Block-Proposals");
+    } else {
+        // Matching-Loops je Markt (oder seriell, siehe matching_concurrency):
+        // beenden beim Shutdown die laufende Runde und sichern das Buch
+        let mut market_shards = MarketShards::new(config.matching_concurrency);
+        matching_engine = Some(market_shards.add_market(MarketPair::new("BTC", "USDT"), engine));
+        market_shards.spawn_matching_loops(&mut shutdown, Duration::from_millis(500), arc_db.clone());
+    }
+
+    if let Some(engine) = matching_engine.clone() {
+        // Config-Reload: neuer Gebührenplan gilt ab dem nächsten Fill
+        let mut live = live_config.clone();
+        shutdown.spawn("config_live/trading_fees", move |token| async move {
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    r = live.changed() => {
+                        if r.is_err() {
+                            break;
+                        }
+                        let fees = live.borrow_and_update().trading_fees.clone();
+                        engine.lock_recover().set_fee_schedule(fees);
+                    }
+                }
+            }
+        });
+    }
 
     // (9.1) Settlement-Workflow optimieren: SecuredSettlementEngine
-    {
+    // Stake-Bonds sperren auf demselben Ledger (nur Leader, siehe (10.0))
+    let mut stake_ledger = None;
+    if !is_follower {
         use crate::settlement::advanced_settlement::{AdvancedSettlementEngine, Asset};
-        use crate::settlement::secured_settlement::{SettlementEngineTrait, SecuredSettlementEngine};
+        use crate::settlement::advanced_settlement::{SettlementEngineTrait, SecuredSettlementEngine};
         use crate::security::security_validator::AdvancedSecurityValidator;
 
-        let arc_db = Arc::new(Mutex::new(db));
         let settlement_fee_pool = Arc::new(FeePool::new(arc_db.clone(), "settlement/fee_pool"));
 
         let standard_fee = config.settlement_fees.standard;
@@ -805,18 +1379,48 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
         let advanced_settlement_engine = AdvancedSettlementEngine::new(
             settlement_fee_pool,
             arc_db.clone(),
-            standard_fee,
-            atomic_fee
+            crate::settlement::fees_config::SettlementFees::new(standard_fee, atomic_fee),
         );
+        stake_ledger = Some(crate::settlement::advanced_settlement::LedgerSettlement::new(
+            advanced_settlement_engine.balances.clone(),
+        ));
+
+        {
+            // Cross-Chain-Swaps über dieselben Backends; offene Swaps nach dem Start fortsetzen
+            use crate::settlement::swap_coordinator::CrossChainSwapCoordinator;
+            let preimage_key = crate::identity::keystore::load_or_create_keypair(
+                &config.keystore_path,
+                &keystore_pass,
+                crate::identity::keystore::SWAP_PREIMAGE_LABEL,
+            )
+            .context("Swap-Preimage-Schlüssel konnte nicht aus dem Keystore geladen werden")?
+            .secret
+            .to_bytes();
+            let mut coordinator = CrossChainSwapCoordinator::new(
+                arc_db.clone(),
+                advanced_settlement_engine.backends.clone(),
+                preimage_key,
+            );
+            // Ohne NTP-Server gäbe es nie eine Messung => dann Systemuhr
+            if !config.ntp_servers.is_empty() {
+                coordinator = coordinator.with_clock_guard(clock_guard.clone());
+            }
+            shutdown.spawn("swap_coordinator", move |token| coordinator.run(Duration::from_secs(10), token));
+        }
 
         let mut secured_engine = SecuredSettlementEngine::new(
             advanced_settlement_engine,
             AdvancedSecurityValidator::new()
         );
 
-        match secured_engine.finalize_trade("buyer1", "seller1", Asset::BTC, Asset::LTC, 1.0, 50000.0) {
-            Ok(_) => info!("Settlement trade finalized successfully."),
-            Err(e) => error!("Settlement trade failed: {:?}", e),
+        // Paper-Trading: die Demo würde echte Ledger-Salden bewegen
+        if config.dry_run {
+            info!("Dry-Run => Demo-Settlement übersprungen");
+        } else {
+            match secured_engine.finalize_trade("buyer1", "seller1", Asset::BTC, Asset::LTC, 1.0, 50000.0) {
+                Ok(_) => info!("Settlement trade finalized successfully."),
+                Err(e) => error!("Settlement trade failed: {:?}", e),
+            }
         }
     }
 
@@ -824,7 +1428,65 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
     let local_node_id = NodeId::random();
     info!("Kademlia => local NodeId = {:?}", &local_node_id);
     let parse_addr = config.listen_addr.parse::<SocketAddr>()?;
-    let p2p_adapter = Arc::new(Mutex::new(TcpP2PAdapter::new(parse_addr)));
+    // Gossip-Parameter werden beim Peering signiert ausgetauscht (FEATURE_GOSSIP_CONFIG)
+    let gossip_key = Arc::new(
+        crate::identity::keystore::load_or_create_keypair(
+            &config.keystore_path,
+            &keystore_pass,
+            crate::identity::keystore::GOSSIP_SIGNING_LABEL,
+        )
+        .context("Gossip-Schlüssel konnte nicht aus dem Keystore geladen werden")?,
+    );
+    let gossip_exchange = crate::network::gossip_config::GossipExchange::new(
+        &crate::network::gossip_config::GossipConfig::new(),
+        gossip_key.clone(),
+        crate::network::gossip_config::GossipBounds::default(),
+    );
+    // Persistentes Adressbuch => Dials/Handshakes (Adapter) und direkt gesehene Peers (Kademlia)
+    let address_book = match crate::network::address_book::AddressBook::load(arc_db.clone()) {
+        Ok(book) => book,
+        Err(e) => {
+            warn!("Adressbuch konnte nicht geladen werden: {:?} => nur im Speicher", e);
+            crate::network::address_book::AddressBook::in_memory()
+        }
+    };
+    let address_book = Arc::new(Mutex::new(address_book));
+    let mut adapter = TcpP2PAdapter::new(parse_addr)
+        .with_handshake_retry(config.noise_handshake)
+        .with_rekey_policy(config.noise_rekey)
+        .with_gossip_config(gossip_exchange)
+        .with_address_book(address_book.clone());
+    // Nicht direkt erreichbare Peers => Noise über das TURN-Relay (aus Schritt 7)
+    if let Some(turn) = p2p_sec.turn_client() {
+        adapter = adapter.with_turn_relay(turn);
+    }
+    if config.handshake_pow.enabled {
+        adapter = adapter.with_pow(&config.handshake_pow);
+    }
+    // Tor => Onion-Peers anwählen; eingehende Streams des eigenen Onion-Service
+    // nimmt der Adapter direkt entgegen (virtuelle Adresse statt 127.0.0.1)
+    let mut onion_bootstrap = Vec::new();
+    if config.tor.enabled {
+        let tor = Arc::new(
+            crate::network::tor::TorTransport::bootstrap(config.tor.state_dir())
+                .await
+                .context("Tor-Client konnte nicht gestartet werden")?,
+        );
+        // Einträge sind in NodeConfig::validate bereits geprüft
+        for entry in &config.tor.bootstrap {
+            if let Ok((id, onion)) = crate::network::tor::parse_onion_bootstrap_peer(entry) {
+                onion_bootstrap.push((id, tor.registry.register(&onion)));
+            }
+        }
+        if !config.tor.onion_service.is_empty() {
+            let onion = tor
+                .publish_onion_service(&config.tor.onion_service, parse_addr.port())
+                .context("Onion-Service konnte nicht veröffentlicht werden")?;
+            info!("Tor => Node erreichbar unter {}", onion);
+        }
+        adapter = adapter.with_tor(tor);
+    }
+    let p2p_adapter = Arc::new(Mutex::new(adapter));
     {
         let p2p_clone = p2p_adapter.clone();
         tokio::spawn(async move {
@@ -833,15 +1495,302 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
             }
         });
     }
-    let kad_service = KademliaService::new(local_node_id, 20, p2p_adapter.clone());
+    // Router-Portweiterleitung nur auf Wunsch; Shutdown entfernt das Mapping wieder
+    if config.upnp_port_mapping {
+        let port = parse_addr.port();
+        shutdown.spawn("port_mapping", move |token| async move {
+            crate::network::port_mapping::PortForwarder::with_defaults(port).run(token).await;
+        });
+    }
+    let mut kad_service = KademliaService::new(local_node_id, 20, p2p_adapter.clone());
+    // Bekannte gute Peers aus dem Adressbuch vor den Bootstrap-Nodes eintragen
+    {
+        // Einträge sind in NodeConfig::validate bereits geprüft
+        let bootstrap: Vec<_> = config
+            .kademlia_bootstrap
+            .iter()
+            .filter_map(|e| crate::network::address_book::parse_bootstrap_peer(e).ok())
+            .chain(onion_bootstrap)
+            .collect();
+        let now = Utc::now().timestamp().max(0) as u64;
+        let n = address_book.lock_recover().seed_routing_table(&mut kad_service.table, &bootstrap, 20, now);
+        info!("Adressbuch => {} Peers in die RoutingTable übernommen ({} Bootstrap)", n, bootstrap.len());
+    }
+    kad_service.set_address_book(address_book.clone());
+    // CRDT-Snapshots von Peers landen in der replizierten CRDT-DB
+    kad_service.set_db(crdt_db.clone());
+    // Neue RoutingTable-Einträge => IP-Filter + GeoIP/ASN-Diversität
+    {
+        let mut pm = crate::network::peer_management::PeerManager::new(
+            crate::network::peer_management::PeerDiscoveryConfig::new(),
+        )
+        .with_address_book(address_book.clone());
+        let div = &config.peer_diversity;
+        if !div.geoip_country_db.is_empty() {
+            let asn_db = Some(div.geoip_asn_db.as_str()).filter(|p| !p.is_empty());
+            match crate::utils::geoip_and_ntp::MaxMindResolver::open(&div.geoip_country_db, asn_db) {
+                Ok(resolver) => pm = pm.with_geo_diversity(Arc::new(resolver), div.policy()),
+                Err(e) => error!("GeoIP-Datenbank nicht ladbar => keine Peer-Diversität: {:?}", e),
+            }
+        }
+        kad_service.set_peer_manager(Arc::new(pm));
+    }
+    // DKG-Nachrichten des Onboarding-Komitees kommen über Kademlia
+    let dkg_inbox = if config.onboarding_dkg.is_enabled() {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        kad_service.set_dkg_inbox(tx);
+        Some(rx)
+    } else {
+        None
+    };
+    // Gegossipte ModeTransitions gehen an den Onboarding-State (10.0)
+    let (transition_in_tx, mut transition_inbox) = tokio::sync::mpsc::unbounded_channel();
+    kad_service.set_transition_inbox(transition_in_tx);
+    // Reliable Gossip (Broadcasts + Anti-Entropy) kommt ebenfalls über Kademlia
+    let (gossip_in_tx, gossip_inbox) = tokio::sync::mpsc::unbounded_channel();
+    kad_service.set_gossip_inbox(gossip_in_tx);
+    // Sequencer-Claims, -Batches und weitergeleitete Orders
+    let (sequencer_in_tx, sequencer_inbox) = tokio::sync::mpsc::unbounded_channel();
+    let sequenced_engine = matching_engine.clone().filter(|_| config.sequencer.enabled);
+    if sequenced_engine.is_some() {
+        kad_service.set_sequencer_inbox(sequencer_in_tx);
+    }
+    // Nakamoto-Blöcke der Peers => ConsensusEngine
+    let (block_in_tx, block_inbox) = tokio::sync::mpsc::unbounded_channel();
+    kad_service.set_block_inbox(block_in_tx);
     let kad_arc = Arc::new(Mutex::new(kad_service));
+    {
+        // ACTIVE_PEERS pflegt der KademliaService bei jeder Tabellenänderung
+        let t = config.health.clone();
+        health.register("dht", true, move |_| {
+            health_report::check_dht_peers(crate::metrics::ACTIVE_PEERS.get().max(0) as usize, &t)
+        });
+    }
     {
         let kad_for_task = kad_arc.clone();
-        tokio::spawn(async move {
-            kad_for_task.lock().unwrap().run_service().await;
+        shutdown.spawn("kademlia", move |token| async move {
+            tokio::select! {
+                _ = kad_for_task.lock_recover().run_service() => {}
+                _ = token.cancelled() => info!("Kademlia => Shutdown"),
+            }
+        });
+    }
+    {
+        // Anti-Entropy: alle 30s Digest an einen zufälligen Peer aus der RoutingTable.
+        // Der Node leitet nur weiter und gleicht ab; lokale Broadcasts gibt es hier nicht.
+        let (gossip_out, _) = tokio::sync::mpsc::channel(1);
+        let (_, gossip_local) = tokio::sync::mpsc::channel(1);
+        let node = crate::network::reliable_gossip::GossipNode::new(gossip_key.clone(), gossip_out, gossip_local)
+            .with_clock_guard(clock_guard.clone());
+        let p2p_for_gossip = p2p_adapter.clone();
+        let kad_for_gossip = kad_arc.clone();
+        shutdown.spawn("reliable_gossip", move |token| async move {
+            let send = move |addr, wire| {
+                p2p_for_gossip.lock_recover().send_kademlia_msg(addr, &KademliaMessage::ReliableGossip(wire));
+            };
+            let peers = move || -> Vec<SocketAddr> {
+                kad_for_gossip.lock_recover().table.all_entries().into_iter().map(|(_, _, addr)| addr).collect()
+            };
+            tokio::select! {
+                _ = node.run_anti_entropy(gossip_inbox, send, peers, Duration::from_secs(30)) => {}
+                _ = token.cancelled() => info!("Reliable Gossip => Shutdown"),
+            }
+        });
+    }
+    // Fee-Pool: Transaktionsgebühren eigener Blöcke, Trading-Fees, Verteilung (16)
+    let mut fee_pool = FeePool::new(arc_db.clone(), "system_accounts/fee_pool")
+        .with_membership(swim_membership.clone())
+        .with_sync_fee(cluster_mgr.sync_fee_rate().unwrap_or(0.0));
+    if let Some(policy) = config.fee_cold_sweep.clone() {
+        let keypair = ed25519_dalek::Keypair::from_bytes(&audit_keypair.to_bytes()).expect("Audit-Schlüssel");
+        // Ohne Audit-Log kein Cold-Sweep: die Fees bleiben dann in den Pools
+        match crate::audit::audit_log::AuditLogger::open("fee_pool_audit.log", keypair) {
+            Ok(audit) => fee_pool = fee_pool.with_cold_sweep(policy, Arc::new(audit), cold_transfer),
+            Err(e) => warn!("Fee-Pool-Audit-Log nicht verfügbar, Cold-Sweep deaktiviert: {:?}", e),
+        }
+    }
+    {
+        // Blockproduktion: Fork-Choice nach Arbeit, Shard-Checkpoints an eigene Blöcke
+        let block_key = Arc::new(
+            crate::identity::keystore::load_or_create_keypair(
+                &config.keystore_path,
+                &keystore_pass,
+                crate::identity::keystore::BLOCK_SIGNING_LABEL,
+            )
+            .context("Block-Schlüssel konnte nicht aus dem Keystore geladen werden")?,
+        );
+        let mut consensus = crate::consensus::engine::ConsensusEngine::new(vec![config.node_id.clone()])
+            .with_mempool(mempool.clone())
+            .with_shard_manager(shard_manager.clone())
+            .with_signing_key(block_key)
+            .with_fee_pool(fee_pool.clone());
+        let p2p_for_blocks = p2p_adapter.clone();
+        let kad_for_blocks = kad_arc.clone();
+        shutdown.spawn("consensus", move |token| async move {
+            let broadcast = move |block: Vec<u8>| {
+                let peers: Vec<SocketAddr> =
+                    kad_for_blocks.lock_recover().table.all_entries().into_iter().map(|(_, _, addr)| addr).collect();
+                let p2p = p2p_for_blocks.lock_recover();
+                for addr in peers {
+                    p2p.send_kademlia_msg(addr, &KademliaMessage::Block(block.clone()));
+                }
+            };
+            tokio::select! {
+                _ = consensus.run(block_inbox, broadcast) => {}
+                _ = token.cancelled() => info!("Konsens => Shutdown"),
+            }
+        });
+    }
+    if let Some(engine) = sequenced_engine {
+        // VRF-Sequencer: Claims je Epoche, signierte Batches, Equivocation als Fault
+        use crate::identity::keystore::{load_or_create_keypair, SEQUENCER_SIGNING_LABEL, SEQUENCER_VRF_LABEL};
+        let vrf_seed = load_or_create_keypair(&config.keystore_path, &keystore_pass, SEQUENCER_VRF_LABEL)
+            .context("Sequencer-VRF-Schlüssel konnte nicht aus dem Keystore geladen werden")?;
+        let vrf = crate::consensus::vrf_committee_async::VrfKeypair::from_seed(&vrf_seed.secret.to_bytes());
+        let batch_key = load_or_create_keypair(&config.keystore_path, &keystore_pass, SEQUENCER_SIGNING_LABEL)
+            .context("Sequencer-Schlüssel konnte nicht aus dem Keystore geladen werden")?;
+        info!(
+            "Sequencer => vrf_pk {} sign_pk {}",
+            hex::encode(vrf.pk),
+            hex::encode(batch_key.public.to_bytes())
+        );
+        let node = crate::consensus::sequencer::SequencerNode::new(
+            config.sequencer.clone(),
+            &config.node_id,
+            vrf,
+            batch_key,
+            engine,
+        );
+        let p2p_for_sequencer = p2p_adapter.clone();
+        let node_id = config.node_id.clone();
+        shutdown.spawn("sequencer", move |token| async move {
+            let send = move |addr, wire| {
+                p2p_for_sequencer.lock_recover().send_kademlia_msg(addr, &KademliaMessage::Sequencer(wire));
+            };
+            let on_equivocation = move |proof: crate::consensus::sequencer::SequencerEquivocation| {
+                let msg = crate::gossip::FaultMessage::new(
+                    node_id.clone(),
+                    "sequencer_equivocation".to_string(),
+                    serde_json::to_string(&proof).unwrap_or_default(),
+                    "critical".to_string(),
+                    60,
+                );
+                tokio::spawn(crate::gossip::broadcast_gossip_message(msg));
+            };
+            tokio::select! {
+                _ = node.run(sequencer_inbox, send, on_equivocation) => {}
+                _ = token.cancelled() => info!("Sequencer => Shutdown"),
+            }
         });
     }
 
+    // (10.0) Onboarding-State (Modus, Fullnodes, ModeTransition-Gossip) und
+    // DKG-Zeremonie für den Komitee-Schlüssel, solange kein Share gespeichert ist
+    {
+        use crate::onboarding::auto_committee::OnboardingGlobalState;
+        use crate::onboarding::dkg::{load_share, node_dkg_keys, persist_share, run_ceremony, DkgSession, KademliaDkgTransport};
+        let dkg_cfg = config.onboarding_dkg.clone();
+        let signer = Arc::new(
+            crate::identity::keystore::load_or_create_keypair(
+                &config.keystore_path,
+                &keystore_pass,
+                crate::identity::keystore::DKG_SIGNING_LABEL,
+            )
+            .context("DKG-Schlüssel konnte nicht aus dem Keystore geladen werden")?,
+        );
+        let dkg_state = load_share(&arc_db.lock_recover(), &config.node_id, &keystore_pass)?;
+        let has_share = dkg_state.is_some();
+        let (transition_out_tx, mut transition_out) = tokio::sync::mpsc::unbounded_channel();
+        let mut onboarding = OnboardingGlobalState::new(dkg_state, config.onboarding.to_config(&dkg_cfg)?)
+            .with_persistence(arc_db.clone())?
+            .with_transition_gossip(signer.clone(), transition_out_tx);
+        // Sybil-Schutz: Zertifikate nur für Identitäten mit Stake-Bond
+        if config.stake_admission.enabled {
+            match stake_ledger.take() {
+                Some(ledger) => {
+                    let registry = crate::sybil::stake_admission::StakeBondRegistry::new(
+                        config.stake_admission.min_bond,
+                        Box::new(ledger),
+                    );
+                    onboarding = onboarding.with_stake_admission(Arc::new(Mutex::new(registry)));
+                }
+                None => warn!("stake_admission => Follower ohne Settlement-Ledger, Bonds werden nicht geprüft"),
+            }
+        }
+        let onboarding = Arc::new(onboarding);
+        // Komitee-Mitglieder sind Fullnodes (node_id = Ed25519-Key)
+        for p in &dkg_cfg.participants {
+            onboarding.add_fullnode(&p.sign_key)?;
+        }
+        onboarding.maybe_switch_to_auto()?;
+
+        let peers = dkg_cfg.peer_addrs(&config.node_id);
+        let p2p_for_transitions = p2p_adapter.clone();
+        shutdown.spawn("onboarding_transition_out", move |token| async move {
+            loop {
+                tokio::select! {
+                    Some(t) = transition_out.recv() => {
+                        let wire = KademliaMessage::ModeTransition(t);
+                        let p2p = p2p_for_transitions.lock_recover();
+                        for addr in &peers {
+                            p2p.send_kademlia_msg(*addr, &wire);
+                        }
+                    }
+                    _ = token.cancelled() => break,
+                }
+            }
+        });
+        let onboarding_in = onboarding.clone();
+        shutdown.spawn("onboarding_transition_in", move |token| async move {
+            loop {
+                tokio::select! {
+                    Some(t) = transition_inbox.recv() => match onboarding_in.on_transition_gossip(&t) {
+                        Ok(true) => info!("Onboarding => ModeTransition epoch {} übernommen", t.epoch),
+                        Ok(false) => debug!("Onboarding => ModeTransition epoch {} wartet auf Signaturen", t.epoch),
+                        Err(e) => warn!("Onboarding => ModeTransition verworfen: {:?}", e),
+                    },
+                    _ = token.cancelled() => break,
+                }
+            }
+        });
+
+        let enc_secret = node_dkg_keys(&signer);
+        info!(
+            "DKG => sign_key={} enc_key={}",
+            hex::encode(signer.public.as_bytes()),
+            hex::encode(enc_secret.public_key().to_bytes())
+        );
+        match dkg_inbox {
+            Some(_) if has_share => info!("DKG => Share bereits gespeichert, keine neue Zeremonie"),
+            Some(mut inbox) => {
+                let mut session = DkgSession::new(&config.node_id, dkg_cfg.threshold, dkg_cfg.participants()?, enc_secret)?;
+                let transport = KademliaDkgTransport {
+                    p2p: p2p_adapter.clone(),
+                    peers: dkg_cfg.peer_addrs(&config.node_id),
+                };
+                let quiet = Duration::from_millis(dkg_cfg.quiet_period_ms);
+                let timeout = Duration::from_millis(dkg_cfg.timeout_ms);
+                let (db, node_id, pass) = (arc_db.clone(), config.node_id.clone(), keystore_pass.clone());
+                shutdown.spawn("onboarding_dkg", move |token| async move {
+                    tokio::select! {
+                        res = run_ceremony(&mut session, &signer, &transport, &mut inbox, quiet, timeout) => match res {
+                            Ok(out) => {
+                                match persist_share(&db.lock_recover(), &node_id, &out, &pass) {
+                                    Ok(()) => info!("DKG => Share gespeichert, qualified={:?}", out.qualified),
+                                    Err(e) => error!("DKG => Share konnte nicht gespeichert werden: {:?}", e),
+                                }
+                                onboarding.install_dkg_output(&node_id, out);
+                            }
+                            Err(e) => error!("DKG-Zeremonie fehlgeschlagen: {:?}", e),
+                        },
+                        _ = token.cancelled() => info!("DKG => Shutdown"),
+                    }
+                });
+            }
+            None => {}
+        }
+    }
+
     // (10.1) Sichere TCP-Operation
     {
         use crate::network::p2p_operations::send_secure_data_to_peer;
@@ -885,8 +1834,8 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
     write_audit_log("Monitoring-Server initialisiert.");
     logger.log_event("system", "Monitoring-Server gestartet.");
 
-    // (12.1) Konsens-Sicherheitsprozess
-    {
+    // (12.1) Konsens-Sicherheitsprozess (Follower schlagen nichts vor)
+    if !is_follower {
         let base_consensus = BaseConsensus;
         let secured_consensus = SecurityDecorator::new(base_consensus);
         let consensus_proposal = secured_consensus.propose("Beispiel-Konsensdaten").await?;
@@ -909,27 +1858,30 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
     logger.log_event("system", "Partial fill Demo durchgeführt.");
 
     // (15) Accounts/Wallet-Demo
-    let arc_db = Arc::new(Mutex::new(db));
-    let btc_cfg = BitcoinRPCConfig {
-        rpc_url: "http://127.0.0.1:8332".into(),
-        rpc_user: "bitcoinrpc".into(),
-        rpc_pass: "pass".into(),
-    };
-    let ltc_cfg = LTCConfig {
-        rpc_url: "http://127.0.0.1:19332".into(),
-        rpc_user: "ltcrpc".into(),
-        rpc_pass: "pass".into(),
-    };
-    let eth_cfg = ETHConfig {
-        rpc_url: "https://mainnet.infura.io/v3/<yourKey>".into(),
-    };
-    let wmgr = WalletManager::new(
-        arc_db.lock().unwrap().clone(),
-        Some(btc_cfg),
-        Some(ltc_cfg),
-        Some(eth_cfg)
-    );
-    let acc_mgr = AccountsManager::new(arc_db.clone(), wmgr);
+    // Orders werden gegen Wallet-Adressen und Land des Accounts gescreent
+    {
+        let accounts = acc_mgr.clone();
+        node.set_screening_lookup(Arc::new(move |user_id: &str| accounts.screening_subject(user_id)));
+    }
+    {
+        // Account-API (Liste nur für Admins), gleiches TLS wie die REST-API
+        let routes: Router = rest_api::accounts_routes(
+            acc_mgr.clone(),
+            Some(RoleAuth::from_config(&config)),
+        )
+        .merge(rest_api::wallet_ledger_routes(
+            acc_mgr.clone(),
+            Some(RoleAuth::from_config(&config)),
+        ));
+        let tls = config.tls_paths();
+        tokio::spawn(async move {
+            let addr: SocketAddr = "0.0.0.0:8082".parse().unwrap();
+            info!("Account-API läuft auf {}", addr);
+            if let Err(e) = serve_router(routes, addr, tls).await {
+                error!("Account-API beendet: {:?}", e);
+            }
+        });
+    }
     acc_mgr.register_fullnode_account("fullnode_1", "topsecret", Some("Germany".into()))?;
     let _fn_acc = acc_mgr.login_fullnode("fullnode_1", "topsecret")?;
     info!("Fullnode-Betreiber eingeloggt => user_id=fullnode_1");
@@ -954,8 +1906,7 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
         logger.log_event("trader", "Account alice gelöscht nach Fund-Spende.");
     }
 
-    // (16) Fee-Pool Distributor Task
-    let fee_pool = FeePool::new(arc_db.clone(), "system_accounts/fee_pool");
+    // (16) Fee-Pool Distributor Task (Fee-Pool selbst siehe Konsens)
     {
         let fp_clone = fee_pool.clone();
         tokio::spawn(async move {
@@ -968,13 +1919,33 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
             }
         });
     }
+    // Sync-Epoche im selben Takt schließen: sync_pool nach bedientem Sync-Traffic
+    {
+        let epochs = cluster_mgr.sync_epochs();
+        let fp_clone = fee_pool.clone();
+        shutdown.spawn("sync_epoch", move |token| async move {
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    _ = tokio::time::sleep(Duration::from_secs(3600)) => {
+                        if let Err(e) = epochs.close(&fp_clone) {
+                            warn!("Sync-Epoche konnte nicht geschlossen werden: {:?}", e);
+                        }
+                    }
+                }
+            }
+        });
+    }
     info!("Fee-Pool Distributor-Task gestartet.");
     write_audit_log("Fee-Pool Distributor-Task gestartet.");
     logger.log_event("system", "Fee-Pool Distributor-Task gestartet.");
 
     // (17) Audit eines Handelsereignisses
     {
-        use crate::audit::audit_log::{TradeAuditEvent, TradeEventType, log_trade_event};
+        use crate::audit::audit_log::{TradeAuditEvent, TradeEventType, log_trade_event, verify_audit_chain};
+        use ed25519_dalek::Keypair;
+        // Derselbe Schlüssel signiert /download_audit_log/signed
+        let event_keypair = Keypair::from_bytes(&audit_keypair.to_bytes()).expect("Audit-Schlüssel");
         let trade_event = TradeAuditEvent::new(
             TradeEventType::Sell,
             "ETH",
@@ -982,11 +1953,15 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
             Some("Charlie".to_string()),
             Some("Dave".to_string()),
         );
-        if let Err(e) = log_trade_event(&trade_event, "trade_audit.log") {
+        if let Err(e) = log_trade_event(&trade_event, "trade_audit.log", event_keypair) {
             eprintln!("Fehler beim Loggen des Handelsereignisses: {:?}", e);
         } else {
             info!("Handelsereignis wurde erfolgreich protokolliert.");
         }
+        match verify_audit_chain("trade_audit.log") {
+            Ok(summary) => info!("Audit-Kette ok: {} Einträge, head={}", summary.entries, summary.head_hash),
+            Err(e) => error!("Audit-Kette beschädigt: {}", e),
+        }
         logger.log_event("system", "Handelsereignis auditiert.");
     }
 
@@ -1016,33 +1991,104 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
         tracing::info!("Layer-2 DEX Integration abgeschlossen.");
     }
 
-    // (18) Time-Limited Orders: Hintergrund-Task
-    {
-        tokio::spawn(async move {
-            loop {
-                tokio::time::sleep(Duration::from_secs(30)).await;
-                if let Err(e) = check_expired_time_limited_orders() {
-                    eprintln!("Fehler beim Prüfen abgelaufener Orders: {:?}", e);
-                }
-            }
-        });
-        info!("Background-Task für Time-Limited-Orders gestartet (alle 30s)...");
-        logger.log_event("system", "Time-Limited Orders Background-Task gestartet.");
-    }
+    // (18) Time-Limited Orders: Ablauf prüft die MatchingEngine vor jedem
+    //      Matching-Durchlauf (siehe (9)) => kein separater Hintergrund-Task
+    info!("Time-Limited Orders => Ablauf-Prüfung im Matching-Loop aktiv");
+    logger.log_event("system", "Time-Limited Orders Ablauf-Prüfung im Matching-Loop aktiv.");
 
     // (19) PriceFeed-Integration und Account-Endpunkt
     // NEU: In echter Produktion => 
     //   1) TLS-gesicherte WebSocket
     //   2) ggf. mehrere Feeds / signierte Oracles, um Manipulationen zu vermeiden
     let price_feed = Arc::new(Mutex::new(PriceFeed::new()));
+    {
+        let price_feed = price_feed.clone();
+        let t = config.health.clone();
+        health.register("price_feed", true, move |now| {
+            let last_fresh = price_feed.lock_recover().last_fresh_update();
+            health_report::check_price_feed(last_fresh, now, &t)
+        });
+    }
     tokio::spawn({
         let price_feed_clone = price_feed.clone();
+        let price_feed_config = config.price_feed.clone();
         async move {
-            if let Err(e) = crate::run_price_feed_system(price_feed_clone).await {
+            // Scraper + Börsen-Ticker aus `price_feed.exchange_apis` (Quorum >= 2)
+            if let Err(e) = crate::run_price_feed_system(price_feed_clone, &price_feed_config).await {
                 error!("PriceFeed system error: {:?}", e);
             }
         }
     });
+    // Binance-Trade-Stream als weitere Quelle im Quorum (Quellname wie der Ticker)
+    {
+        let price_feed = price_feed.clone();
+        shutdown.spawn("price_feed_ws", move |token| async move {
+            let stream = crate::crypto_scraper::price_feed::WsPriceStream::binance_trades("btcusdt", "BTC");
+            crate::crypto_scraper::price_feed::run_ws_price_stream(stream, price_feed, Default::default(), token).await;
+        });
+    }
+    // Referenzpreise an den Circuit-Breaker: Quorum-Median des PriceFeeds; ist er
+    // stale oder fehlt, der letzte lokale Trade-Preis des Intervalls, damit ein
+    // Crash im eigenen Buch auch ohne externe Quellen auslöst.
+    {
+        let price_feed = price_feed.clone();
+        let breaker = circuit_breaker.clone();
+        let trade_history = trade_history.clone();
+        let mut markets: Vec<String> = config.circuit_breakers.markets.keys().cloned().collect();
+        markets.push("BTC/USDT".to_string());
+        markets.sort();
+        markets.dedup();
+        shutdown.spawn("circuit_breaker_feed", move |token| async move {
+            const FEED_TICK_SECS: u64 = 5;
+            let mut tick = tokio::time::interval(Duration::from_secs(FEED_TICK_SECS));
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    _ = tick.tick() => {
+                        let now = Utc::now().timestamp().max(0) as u64;
+                        let prices: Vec<(String, f64)> = {
+                            let pf = price_feed.lock_recover();
+                            markets.iter()
+                                .filter_map(|m| {
+                                    let base = m.split('/').next().unwrap_or(m);
+                                    let reference = pf.aggregated.get(m.as_str()).or_else(|| pf.aggregated.get(base))
+                                        .filter(|a| !a.stale)
+                                        .map(|a| a.median);
+                                    reference
+                                        .or_else(|| {
+                                            trade_history.trades(m, now.saturating_sub(FEED_TICK_SECS), now + 1)
+                                                .last()
+                                                .map(|t| t.price)
+                                        })
+                                        .map(|px| (m.clone(), px))
+                                })
+                                .collect()
+                        };
+                        let mut b = breaker.lock().unwrap();
+                        for (market, px) in prices {
+                            b.observe(&market, px, now);
+                        }
+                    }
+                }
+            }
+        });
+    }
+    // Arbitrage-Monitor über die Orderbücher des Exchange (Routing-Märkte inkl. Dreieck
+    // BTC/ETH/USDT); gefundene Zyklen landen im Log.
+    {
+        use crate::decentralized_order_book::assets::Asset as BookAsset;
+        use crate::decentralized_order_book::exchange::{run_arbitrage_monitor, Exchange};
+        const ARBITRAGE_INTERVAL_SECS: u64 = 10;
+        const ARBITRAGE_MIN_PROFIT: f64 = 0.005;
+        let mut ex = Exchange::new();
+        ex.create_market(BookAsset::BTC, BookAsset::USDT);
+        ex.create_market(BookAsset::ETH, BookAsset::USDT);
+        ex.create_market(BookAsset::BTC, BookAsset::ETH);
+        let exchange = Arc::new(Mutex::new(ex));
+        shutdown.spawn("arbitrage_monitor", move |token| {
+            run_arbitrage_monitor(exchange, Duration::from_secs(ARBITRAGE_INTERVAL_SECS), ARBITRAGE_MIN_PROFIT, token)
+        });
+    }
     #[derive(Clone)]
     struct AppState {
         price_feed: Arc<Mutex<PriceFeed>>,
@@ -1051,30 +2097,41 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
         let pf = state.price_feed.lock().unwrap();
         Json(json!( {
             "prices": pf.prices,
+            "aggregated": pf.aggregated,
             "last_updated": pf.last_updated,
         }))
     }
-    // TIPP: In Produktion => HTTPS/TLS, Auth, 
-    // damit nicht jeder ungeprüft auf 0.0.0.0:3000 zugreifen kann.
+    // /prices ist rein lesend => kein Token, aber TLS wie die REST-API
     let account_routes = Router::new()
         .route("/prices", get(get_current_prices))
         .with_state(AppState { price_feed: price_feed.clone() });
+    let account_tls = config.tls_paths();
     tokio::spawn(async move {
         let addr: SocketAddr = "0.0.0.0:3000".parse().unwrap();
         info!("Account endpoint server gestartet auf {}", addr);
-        axum::Server::bind(&addr)
-            .serve(account_routes.into_make_service())
-            .await
-            .unwrap();
+        if let Err(e) = serve_router(account_routes, addr, account_tls).await {
+            error!("Account endpoint server beendet: {:?}", e);
+        }
     });
 
     // (20) Kritische Daten dezentral über IPFS speichern: Audit-Log
+    //      Ist IPFS down, landet der Inhalt in der lokalen Queue und wird
+    //      vom Flush-Loop nachgeliefert.
+    let ipfs_store = Arc::new(IpfsFallbackStore::default_store());
+    {
+        let flush_store = ipfs_store.clone();
+        shutdown.spawn("ipfs_flush", move |token| flush_store.run_flush_loop(Duration::from_secs(30), token));
+    }
     {
-        match add_file_to_ipfs("trade_audit.log").await {
-            Ok(hash) => {
+        match ipfs_store.put_file("trade_audit.log").await {
+            Ok(StoredRef::Ipfs(hash)) => {
                 info!("Audit Log erfolgreich auf IPFS gespeichert, Hash: {}", hash);
                 logger.log_event("system", &format!("Audit Log auf IPFS gespeichert, Hash: {}", hash));
             },
+            Ok(StoredRef::Queued(path)) => {
+                warn!("IPFS nicht erreichbar => Audit Log lokal eingereiht ({:?})", path);
+                logger.log_event("system", "Audit Log lokal eingereiht (IPFS nicht erreichbar).");
+            },
             Err(e) => {
                 warn!("Fehler beim Speichern des Audit Logs auf IPFS: {:?}", e);
                 logger.log_event("system", "Fehler beim Speichern des Audit Logs auf IPFS.");
@@ -1084,11 +2141,15 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
     
     // (21) Kritische Daten dezentral über IPFS speichern: Konfigurationsdatei
     {
-        match add_file_to_ipfs("config/node_config.yaml").await {
-            Ok(hash) => {
+        match ipfs_store.put_file("config/node_config.yaml").await {
+            Ok(StoredRef::Ipfs(hash)) => {
                 info!("Konfigurationsdatei erfolgreich auf IPFS gespeichert, Hash: {}", hash);
                 logger.log_event("system", &format!("Konfigurationsdatei auf IPFS gespeichert, Hash: {}", hash));
             },
+            Ok(StoredRef::Queued(path)) => {
+                warn!("IPFS nicht erreichbar => Konfigurationsdatei lokal eingereiht ({:?})", path);
+                logger.log_event("system", "Konfigurationsdatei lokal eingereiht (IPFS nicht erreichbar).");
+            },
             Err(e) => {
                 warn!("Fehler beim Speichern der Konfigurationsdatei auf IPFS: {:?}", e);
                 logger.log_event("system", "Fehler beim Speichern der Konfigurationsdatei auf IPFS.");
@@ -1100,7 +2161,8 @@ logger.log_event("system", "ShardManager mit CRDT initialisiert.");
     {
         use crate::gossip::FaultMessage;
         let (rg_tx, rg_rx) = tokio::sync::mpsc::channel(100);
-        let mut reliable_node = ReliableGossipNode::new("node-123".to_string(), rg_tx, rg_rx);
+        let mut reliable_node =
+            ReliableGossipNode::new(gossip_key.clone(), rg_tx, rg_rx).with_clock_guard(clock_guard.clone());
         let fault = FaultMessage {
             node_id: "node-123".to_string(),
             fault_type: "Datenbankfehler".to_string(),
@@ -1134,9 +2196,37 @@ if let Some(wd_config) = load_watchdog_config("config/watchdog.toml") {
             let interval = svc_cfg.interval_sec;
             let svc_cfg = svc_cfg.clone();
             let wl = whitelist.clone();
-
-            tokio::spawn(async move {
-                monitor_and_heal(&svc_name, &node_id, interval, svc_cfg, wl).await;
+            let mut live = live_config.clone();
+
+            // Nur bei geändertem Intervall neu starten, andere Reloads laufen durch
+            shutdown.spawn(&format!("watchdog/{}", svc_name), move |token| async move {
+                let effective = |v: u64| if v == 0 { interval } else { v };
+                let mut iv = effective(live.borrow_and_update().watchdog_interval_sec);
+                'restart: loop {
+                    let heal = monitor_and_heal(&svc_name, &node_id, iv, svc_cfg.clone(), wl.clone());
+                    tokio::pin!(heal);
+                    loop {
+                        tokio::select! {
+                            _ = &mut heal => break 'restart,
+                            _ = token.cancelled() => break 'restart,
+                            r = live.changed() => {
+                                if r.is_err() {
+                                    // Reloader beendet => mit bisherigem Intervall weiter
+                                    tokio::select! {
+                                        _ = &mut heal => {}
+                                        _ = token.cancelled() => {}
+                                    }
+                                    break 'restart;
+                                }
+                                let new_iv = effective(live.borrow_and_update().watchdog_interval_sec);
+                                if new_iv != iv {
+                                    iv = new_iv;
+                                    continue 'restart;
+                                }
+                            }
+                        }
+                    }
+                }
             });
         }
 
@@ -1154,21 +2244,25 @@ if let Some(wd_config) = load_watchdog_config("config/watchdog.toml") {
     tokio::signal::ctrl_c().await?;
     info!("Shutdown-Signal empfangen – Node wird beendet");
     write_audit_log("Shutdown-Signal empfangen.");
+    let report = shutdown.shutdown().await;
+    info!("Shutdown => {} Tasks beendet, {} abgebrochen", report.completed.len(), report.aborted.len());
+    if !report.aborted.is_empty() {
+        warn!("Shutdown => abgebrochen: {:?}", report.aborted);
+    }
+    write_audit_log("Node beendet.");
     shutdown_tracing();
     Ok(())
 }
 
-async fn start_health_server() {
+async fn start_health_server(audit_keypair: Arc<ed25519_dalek::Keypair>, health: HealthRegistry) {
+    // /readyz + /health/detailed aus der HealthRegistry: not ready, solange der
+    // Start läuft oder ein kritisches Subsystem ausfällt; IPFS-Ausfall nur degradiert
     let app = Router::new()
         .route("/healthz", get(|| async { StatusCode::OK }))
-        .route("/readyz", get(|| async {
-            if IS_READY.load(Ordering::Relaxed) {
-                StatusCode::OK
-            } else {
-                StatusCode::SERVICE_UNAVAILABLE
-            }
-        }))
-        .route("/download_audit_log", get(download_audit_log));
+        .route("/download_audit_log", get(download_audit_log))
+        .route("/download_audit_log/signed", get(download_signed_audit_log))
+        .with_state(audit_keypair)
+        .merge(health_report::health_routes(health));
     let addr = HealthSocketAddr::from(([0, 0, 0, 0], 9100));
     tokio::spawn(async move {
         if let Err(e) = axum::Server::bind(&addr)
@@ -1198,3 +2292,18 @@ async fn download_audit_log() -> impl IntoResponse {
         }
     }
 }
+
+/// Log + Manifest (Digest, Einträge, Head-Hash) + Node-Signatur als JSON;
+/// prüfbar mit `audit_log::verify_audit_export`.
+async fn download_signed_audit_log(State(keypair): State<Arc<ed25519_dalek::Keypair>>) -> impl IntoResponse {
+    use crate::audit::audit_log::export_audit_log;
+    let now_ms = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+    match tokio::task::spawn_blocking(move || export_audit_log("trade_audit.log", &keypair, now_ms)).await {
+        Ok(Ok(export)) => (StatusCode::OK, Json(json!(export))),
+        Ok(Err(e)) => {
+            error!("Signierter Audit-Export abgelehnt: {}", e);
+            (StatusCode::CONFLICT, Json(json!({ "error": e.to_string() })))
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
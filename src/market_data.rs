@@ -0,0 +1,281 @@
+///////////////////////////////////////////////////////////
+// my_dex/src/market_data.rs
+///////////////////////////////////////////////////////////
+//
+// Marktdaten-Broadcast für Trading-UIs:
+//  - MarketDataEvent (Trade / BookDelta / OrderCancelled), JSON-serialisierbar
+//  - MarketDataHub => tokio::broadcast, an den die MatchingEngine publiziert
+//  - market_data_routes() => WebSocket-Route `/ws/marketdata`
+//
+// Clients abonnieren Märkte per Query (`?markets=BTC/USDT,ETH/USDT`) oder
+// nachträglich per Text-Nachricht `{"subscribe":["BTC/USDT"]}`. Ohne Filter
+// werden alle Märkte gesendet. Kommt ein Client mit dem Lesen nicht hinterher
+// (Broadcast-Lag oder Send-Timeout), wird die Verbindung geschlossen, damit
+// er den Kanal nicht für alle anderen aufhält.
+///////////////////////////////////////////////////////////
+
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::{Query, State},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+/// Puffergröße des Broadcast-Kanals pro Subscriber.
+pub const MARKET_DATA_CHANNEL_CAPACITY: usize = 1024;
+
+/// Maximale Zeit für das Senden einer Nachricht an einen Client.
+pub const CLIENT_SEND_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TradeEvent {
+    pub market: String,
+    pub buy_order_id: String,
+    pub sell_order_id: String,
+    pub quantity: f64,
+    pub price: f64,
+    pub timestamp: u64,
+}
+
+/// Änderung der offenen Menge einer Order im Buch.
+/// `quantity_change` > 0 => neu eingestellt, < 0 => (teil)gefüllt oder entfernt.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BookDelta {
+    pub market: String,
+    pub order_id: String,
+    pub side: String,
+    pub price: Option<f64>,
+    pub quantity_change: f64,
+}
+
+/// Order wurde ohne (vollständige) Ausführung aus dem Buch genommen.
+/// `reason` z. B. "expired".
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderCancelledEvent {
+    pub market: String,
+    pub order_id: String,
+    /// Eigentümer der Order, damit sein Client die Stornierung zuordnen kann
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    pub reason: String,
+    pub remaining: f64,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketDataEvent {
+    Trade(TradeEvent),
+    BookDelta(BookDelta),
+    OrderCancelled(OrderCancelledEvent),
+}
+
+impl MarketDataEvent {
+    pub fn market(&self) -> &str {
+        match self {
+            MarketDataEvent::Trade(t) => &t.market,
+            MarketDataEvent::BookDelta(d) => &d.market,
+            MarketDataEvent::OrderCancelled(c) => &c.market,
+        }
+    }
+}
+
+/// Verteilt Marktdaten an alle WebSocket-Clients.
+#[derive(Clone)]
+pub struct MarketDataHub {
+    tx: broadcast::Sender<MarketDataEvent>,
+}
+
+impl MarketDataHub {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(MARKET_DATA_CHANNEL_CAPACITY);
+        Self { tx }
+    }
+
+    /// Ohne Subscriber wird das Event verworfen; das ist kein Fehler.
+    pub fn publish(&self, event: MarketDataEvent) {
+        let _ = self.tx.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MarketDataEvent> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for MarketDataHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarketDataQuery {
+    /// Kommagetrennte Liste, z.B. "BTC/USDT,ETH/USDT".
+    pub markets: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscribeRequest {
+    subscribe: Vec<String>,
+}
+
+/// Marktfilter eines Clients; leer => alle Märkte.
+#[derive(Debug, Default, Clone)]
+pub struct SubscriptionFilter {
+    markets: HashSet<String>,
+}
+
+impl SubscriptionFilter {
+    pub fn from_query(q: Option<&str>) -> Self {
+        let markets = q
+            .unwrap_or("")
+            .split(',')
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty())
+            .collect();
+        Self { markets }
+    }
+
+    pub fn matches(&self, event: &MarketDataEvent) -> bool {
+        self.markets.is_empty() || self.markets.contains(event.market())
+    }
+}
+
+/// Router mit der Route `/ws/marketdata`; lässt sich in jeden Router mergen.
+pub fn market_data_routes<S>(hub: MarketDataHub) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/ws/marketdata", get(ws_marketdata))
+        .with_state(hub)
+}
+
+async fn ws_marketdata(
+    ws: WebSocketUpgrade,
+    Query(query): Query<MarketDataQuery>,
+    State(hub): State<MarketDataHub>,
+) -> impl IntoResponse {
+    // Vor dem Upgrade abonnieren => keine Events zwischen Handshake und Task-Start verlieren
+    let rx = hub.subscribe();
+    let filter = SubscriptionFilter::from_query(query.markets.as_deref());
+    ws.on_upgrade(move |socket| client_loop(socket, rx, filter))
+}
+
+async fn client_loop(
+    mut socket: WebSocket,
+    mut rx: broadcast::Receiver<MarketDataEvent>,
+    mut filter: SubscriptionFilter,
+) {
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Ok(event) => {
+                    if !filter.matches(&event) {
+                        continue;
+                    }
+                    let text = match serde_json::to_string(&event) {
+                        Ok(t) => t,
+                        Err(e) => {
+                            warn!("MarketData => Serialisierung fehlgeschlagen: {:?}", e);
+                            continue;
+                        }
+                    };
+                    match tokio::time::timeout(CLIENT_SEND_TIMEOUT, socket.send(Message::Text(text))).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(_)) => break,
+                        Err(_) => {
+                            warn!("MarketData => Client zu langsam (Send-Timeout), Verbindung wird getrennt");
+                            break;
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    warn!("MarketData => Client {} Events im Rückstand, Verbindung wird getrennt", n);
+                    break;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    if let Ok(req) = serde_json::from_str::<SubscribeRequest>(&text) {
+                        debug!("MarketData => neuer Filter {:?}", req.subscribe);
+                        filter = SubscriptionFilter { markets: req.subscribe.into_iter().collect() };
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    let _ = socket.close().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching_engine::{MatchingEngine, OrderData, OrderSide, OrderType};
+    use futures::StreamExt;
+    use std::net::SocketAddr;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+    fn signed_order(id: &str, side: OrderSide, price: f64, qty: f64) -> OrderData {
+        OrderData::new(id, "user", side, OrderType::Limit(price), qty, 0).signed_for_tests()
+    }
+
+    #[test]
+    fn test_filter() {
+        let ev = MarketDataEvent::BookDelta(BookDelta {
+            market: "BTC/USDT".into(),
+            order_id: "o".into(),
+            side: "buy".into(),
+            price: Some(1.0),
+            quantity_change: 1.0,
+        });
+        assert!(SubscriptionFilter::from_query(None).matches(&ev));
+        assert!(SubscriptionFilter::from_query(Some("ETH/USDT, BTC/USDT")).matches(&ev));
+        assert!(!SubscriptionFilter::from_query(Some("ETH/USDT")).matches(&ev));
+    }
+
+    #[tokio::test]
+    async fn test_ws_client_receives_trade_after_match() {
+        let hub = MarketDataHub::new();
+        let app: Router = market_data_routes(hub.clone());
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr: SocketAddr = listener.local_addr().unwrap();
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+
+        let url = format!("ws://{}/ws/marketdata?markets=BTC/USDT", addr);
+        let (mut client, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+        let mut engine = MatchingEngine::new().with_market_data("BTC/USDT", hub.clone());
+        engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        engine.place_order(signed_order("s1", OrderSide::Sell, 100.0, 1.0)).unwrap();
+        let trades = engine.match_orders().unwrap();
+        assert_eq!(trades.len(), 1);
+
+        let trade = tokio::time::timeout(Duration::from_secs(5), async {
+            while let Some(msg) = client.next().await {
+                if let WsMessage::Text(text) = msg.unwrap() {
+                    if let MarketDataEvent::Trade(t) = serde_json::from_str(&text).unwrap() {
+                        return t;
+                    }
+                }
+            }
+            panic!("WebSocket closed before trade event");
+        })
+        .await
+        .expect("no trade event received");
+
+        assert_eq!(trade.market, "BTC/USDT");
+        assert_eq!(trade.buy_order_id, "b1");
+        assert_eq!(trade.sell_order_id, "s1");
+        assert_eq!(trade.quantity, 1.0);
+    }
+}
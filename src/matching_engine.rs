@@ -9,6 +9,8 @@
 //     - Order (id, user, timestamp, side, order_type, quantity, filled, status)
 //
 //  2) Gebührenberechnung (FeeDistribution, FeeOutput, calculate_fee)
+//     - FeeSchedule: Maker/Taker-Raten je Markt; negative Maker-Rate =>
+//       Rebate für ruhende Orders, bezahlt aus der Taker-Fee
 //
 //  3) LimitOrderBook:
 //     - add_order(...)
@@ -21,6 +23,25 @@
 //     - process_trades(...) => SecurityValidate, Settlement, Audit-Log
 //     - ring_sign_demo(...) => Beispielhafte Ring-Signatur mit global_sec
 //     - check_expired_time_limited_orders(...) => Time-Limited Orders
+//     - with_market_data(...) => Trades + Book-Deltas an MarketDataHub (WebSocket)
+//     - halt_market(...) / resume_market(...) => Kill-Switch pro Markt,
+//       nur mit Threshold an Fullnode-Signaturen (MarketHaltControl)
+//     - with_sequencing(...) => Orders nur über den per VRF gewählten
+//       Sequencer, match_orders verarbeitet strikt in Sequenz-Reihenfolge
+//     - with_admission_clock(...) => HLC-SequenceNo bei der Annahme; Zeit-
+//       Priorität und Maker/Taker folgen ihr statt der Ankunftsreihenfolge
+//     - place_replicated_order(...) => Order eines Peers, behält dessen HLC
+//     - submit_commitment(...) / reveal_order(...) => Commit-Reveal gegen MEV
+//     - persist_book(...) / restore_book(...) => Order-Book in DexDB (Shutdown)
+//     - with_circuit_breaker(...) => kein Matching, solange der Breaker
+//       des Marktes nach einem Preissprung ausgelöst ist (Cooldown)
+//     - validate_invariants(...) => kein gekreuztes Buch, keine negativen
+//       Restmengen, keine ruhenden Filled-Orders; optional nach jedem Match
+//       (with_invariant_checks), Verletzungen gehen als InvariantFault raus
+//
+//  4b) MarketShards: eine MatchingEngine pro Markt (MarketPair) hinter
+//      eigenem Lock; Märkte matchen parallel (per_market) oder nacheinander
+//      (serial). Fees aller Märkte laufen in einem gemeinsamen FeeLedger.
 //
 //  5) SecurityValidator & Settlement-Integration
 //
@@ -33,34 +54,50 @@
 ///////////////////////////////////////////////////////////
 
 use anyhow::{Result, anyhow};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::cmp::Ordering;
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use serde::{Deserialize, Serialize};
 
-use tracing::{info, debug, warn, error};
+use tracing::{info, debug, warn, error, info_span, instrument};
 use crate::error::DexError;
 use crate::crdt_logic::Order;
-use crate::metrics::ORDER_COUNT;
+use crate::utils::hlc::{HlcTimestamp, HybridLogicalClock};
+use crate::utils::canonical;
+use crate::utils::geoip_and_ntp::ClockSkewGuard;
+use crate::metrics::{ORDER_COUNT, TRADES_MATCHED, MATCH_LATENCY, MATCH_DURATION_BY_ORDER_TYPE};
+use crate::market_data::{BookDelta, MarketDataEvent, MarketDataHub, OrderCancelledEvent, TradeEvent};
+use crate::dex_logic::commit_reveal::CommitRevealBook;
+use crate::sanctions::internal_analysis::{ActivityEvent, AnomalyEngine};
+use crate::dex_logic::circuit_breaker::CircuitBreaker;
+use crate::storage::db_layer::DexDB;
+use crate::consensus::sequencer::{SequencedBatch, SequencerClaim, SequencerElection, SequencerEquivocation, SequencingState};
 use crate::security::security_validator::{SecurityValidator, AdvancedSecurityValidator};
 use crate::security::global_security_facade::GlobalSecuritySystem; // Neu für global_sec
 use crate::settlement::secured_settlement::{
     SettlementEngineTrait,
     SettlementEngine,
-    SecuredSettlementEngine
+    SecuredSettlementEngine,
+    PendingTrade,
 };
+use crate::settlement::settlement_queue::{KeyedTrade, QueueRunReport, SettlementQueue};
 use crate::logging::enhanced_logging::{log_error, write_audit_log};
+use crate::config_loader::MatchingConcurrency;
+use crate::shutdown::ShutdownCoordinator;
 
 // Falls Sie das Modul time_limited_orders eingebunden haben
 use crate::dex_logic::time_limited_orders::{
-    TimeLimitedOrderManager, TimeLimitedOrderSide, TimeLimitedOrderType,
-    TimeLimitedOrder, TimeLimitedStatus,
+    OrderSide as TimeLimitedOrderSide, TimeLimitedOrder, TimeLimitedOrderManager, TimeLimitedStatus,
 };
+use crate::utils::lock::LockRecover;
 
 // ─────────────────────────────────────────────────────────
 // Order-Typen (Market, Limit, etc.) + Status
 // ─────────────────────────────────────────────────────────
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum OrderType {
     Market,
     Limit(f64),
@@ -68,13 +105,25 @@ pub enum OrderType {
     StopLimit { stop: f64, limit: f64 },
 }
 
-#[derive(Clone, Debug, PartialEq)]
+impl OrderType {
+    /// Kurzname für Metrik-Labels.
+    pub fn label(&self) -> &'static str {
+        match self {
+            OrderType::Market => "market",
+            OrderType::Limit(_) => "limit",
+            OrderType::Stop(_) => "stop",
+            OrderType::StopLimit { .. } => "stop_limit",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum OrderSide {
     Buy,
     Sell,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum OrderStatus {
     Open,
     PartiallyFilled,
@@ -82,7 +131,29 @@ pub enum OrderStatus {
     Cancelled,
 }
 
-#[derive(Clone, Debug)]
+/// Position einer Order in der global vereinbarten Annahme-Reihenfolge.
+/// Alle Replikate sehen für dieselbe Order dieselbe Nummer und sortieren
+/// danach statt nach Ankunft => identische Zeit-Priorität und Maker/Taker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SequenceNo {
+    /// Vom gewählten Sequencer vergeben
+    Sequencer { epoch: u64, seq: u64 },
+    /// HLC des Nodes, der die Order zuerst angenommen hat
+    Hlc(HlcTimestamp),
+}
+
+/// Herkunft einer Order bei der Annahme, bestimmt ihre SequenceNo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Admission {
+    /// Client, Batch, Reveal, Scheduled => eigener Stempel
+    Local,
+    /// Von einem Peer repliziert => HLC des Ursprungs-Nodes
+    Replicated,
+    /// Aus einem geprüften Sequencer-Batch
+    Sequenced { epoch: u64, seq: u64 },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OrderData {
     pub id: String,
     pub user_id: String,
@@ -93,6 +164,15 @@ pub struct OrderData {
     pub filled: f64,
     pub status: OrderStatus,
 
+    // HLC der Ursprungs-Order (aus dem CRDT), bricht Preisgleichstand
+    #[serde(default)]
+    pub hlc: Option<HlcTimestamp>,
+
+    // Global vereinbarte Annahme-Position (Sequencer bzw. HLC), wird bei
+    // der Annahme gesetzt; eingehende Werte zählen nicht (nicht signiert)
+    #[serde(default)]
+    pub sequence: Option<SequenceNo>,
+
     // Neu: Felder für Signatur (Beispiel)
     pub signature: Option<Vec<u8>>,
     pub public_key: Option<Vec<u8>>,
@@ -116,6 +196,8 @@ impl OrderData {
             quantity,
             filled: 0.0,
             status: OrderStatus::Open,
+            hlc: None,
+            sequence: None,
             signature: None,
             public_key: None,
         }
@@ -134,16 +216,62 @@ impl OrderData {
         }
     }
 
-    // Neu: Dummy-Signatur-Prüfung
+    /// Kanonische Bytes der unveränderlichen Order-Felder (ohne `filled`,
+    /// `status`, `hlc` – die ändern sich nach der Platzierung).
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct OrderSigningView<'a> {
+            id: &'a str,
+            user_id: &'a str,
+            timestamp: u64,
+            side: &'a OrderSide,
+            order_type: &'a OrderType,
+            quantity: f64,
+            public_key: &'a Option<Vec<u8>>,
+        }
+        canonical::signing_bytes("my_dex/order/v1", &OrderSigningView {
+            id: &self.id,
+            user_id: &self.user_id,
+            timestamp: self.timestamp,
+            side: &self.side,
+            order_type: &self.order_type,
+            quantity: self.quantity,
+            public_key: &self.public_key,
+        })
+    }
+
+    /// Setzt `public_key` und signiert `signing_bytes`.
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<(), DexError> {
+        self.public_key = Some(keypair.public.to_bytes().to_vec());
+        let bytes = self.signing_bytes()?;
+        self.signature = Some(keypair.sign(&bytes).to_bytes().to_vec());
+        Ok(())
+    }
+
+    /// Ed25519-Prüfung über `signing_bytes`.
     pub fn verify_signature(&self) -> bool {
-        if let (Some(sig), Some(pk)) = (&self.signature, &self.public_key) {
-            // In echter Produktion => ed25519_dalek usw.
-            // Hier nur: Wenn beides nicht leer => "valid"
-            !sig.is_empty() && !pk.is_empty()
-        } else {
-            false
+        let (sig, pk) = match (&self.signature, &self.public_key) {
+            (Some(sig), Some(pk)) => (sig, pk),
+            _ => return false,
+        };
+        let (pk, sig) = match (PublicKey::from_bytes(pk), Signature::from_bytes(sig)) {
+            (Ok(pk), Ok(sig)) => (pk, sig),
+            _ => return false,
+        };
+        match self.signing_bytes() {
+            Ok(bytes) => pk.verify(&bytes, &sig).is_ok(),
+            Err(_) => false,
         }
     }
+
+    /// Signiert mit einem festen Test-Schlüssel.
+    #[cfg(test)]
+    pub(crate) fn signed_for_tests(mut self) -> Self {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        self.sign(&Keypair { secret, public }).unwrap();
+        self
+    }
 }
 
 // ─────────────────────────────────────────────────────────
@@ -164,6 +292,35 @@ impl FeeDistribution {
             node_percent: 0.2,
         }
     }
+
+    /// Anteile in Basispunkten (1/10_000), kaufmännisch gerundet.
+    fn bps(percent: f64) -> u128 {
+        (percent.max(0.0) * FEE_BPS_DENOM as f64).round() as u128
+    }
+}
+
+/// Untereinheiten je Fee-Einheit (1e-8, Satoshi-Auflösung).
+pub const FEE_SUBUNITS_PER_UNIT: u64 = 100_000_000;
+const FEE_BPS_DENOM: u128 = 10_000;
+
+/// Fee-Aufteilung in ganzzahligen Untereinheiten.
+/// Invariante: `founder + dev + node == total`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeSplit {
+    pub total: u64,
+    pub founder: u64,
+    pub dev: u64,
+    pub node: u64,
+}
+
+/// Rundungsregel: Founder- und Dev-Anteil werden abgerundet, der Node-Pool
+/// erhält den Rest inkl. aller Rundungsreste. So geht kein Bruchteil einer
+/// Untereinheit verloren und keiner wird doppelt ausgezahlt.
+pub fn split_fee_subunits(total: u64, distribution: &FeeDistribution) -> FeeSplit {
+    let share = |bps: u128| ((total as u128 * bps) / FEE_BPS_DENOM).min(total as u128) as u64;
+    let founder = share(FeeDistribution::bps(distribution.founder_percent));
+    let dev = share(FeeDistribution::bps(distribution.dev_percent)).min(total - founder);
+    FeeSplit { total, founder, dev, node: total - founder - dev }
 }
 
 #[derive(Clone, Debug)]
@@ -173,11 +330,284 @@ pub struct FeeOutput {
     pub node_fee: f64,
 }
 
+impl FeeOutput {
+    pub fn total(&self) -> f64 {
+        self.founder_fee + self.dev_fee + self.node_fee
+    }
+
+    fn from_split(split: &FeeSplit) -> Self {
+        let unit = FEE_SUBUNITS_PER_UNIT as f64;
+        Self {
+            founder_fee: split.founder as f64 / unit,
+            dev_fee: split.dev as f64 / unit,
+            node_fee: split.node as f64 / unit,
+        }
+    }
+}
+
+/// Settlement-Konto, an das der Pool-Anteil jeder Trade-Fee fließt.
+pub const FEE_POOL_ACCOUNT: &str = "fee_pool";
+
+/// Settlement-Legs eines Fills samt Suffix für den Idempotency-Key.
+/// Die Maker-Fee (negativ = Rebate) verschiebt den Quote-Betrag des
+/// Haupt-Legs, der Taker zahlt den Pool-Anteil in einem eigenen Leg an
+/// `FEE_POOL_ACCOUNT`. Netto zahlt der Taker `notional + taker_fee` bzw.
+/// erhält `notional - taker_fee`, der Maker `notional -/+ maker_fee`.
+fn settlement_legs(
+    buyer: &str,
+    seller: &str,
+    maker: OrderSide,
+    (base_asset, quote_asset): (&str, &str),
+    quantity: f64,
+    notional: f64,
+    fees: &TradeFees,
+) -> Vec<(&'static str, PendingTrade)> {
+    let (quote_amount, taker) = match maker {
+        OrderSide::Buy => (notional + fees.maker_fee, seller),
+        OrderSide::Sell => (notional - fees.maker_fee, buyer),
+    };
+    let leg = |buyer: &str, seller: &str, base_amount: f64, quote_amount: f64| PendingTrade {
+        buyer: buyer.to_string(),
+        seller: seller.to_string(),
+        base_asset: base_asset.to_string(),
+        quote_asset: quote_asset.to_string(),
+        base_amount,
+        quote_amount,
+    };
+    let mut legs = vec![("", leg(buyer, seller, quantity, quote_amount))];
+    if fees.pool_fee > 0.0 {
+        legs.push((":fee", leg(taker, FEE_POOL_ACCOUNT, 0.0, fees.pool_fee)));
+    }
+    legs
+}
+
+/// Summe der Trade-Fees je Quote-Asset, geteilt zwischen allen Märkten.
+/// Reine Buchführung: die Beträge selbst fließen über die Settlement-Legs
+/// (`settlement_legs`).
+/// `totals` ist der Netto-Anteil des Pools (Taker-Fee minus Maker-Rebate).
+#[derive(Clone, Debug, Default)]
+pub struct FeeLedger {
+    pub totals: std::collections::BTreeMap<String, f64>,
+    pub trades: u64,
+    /// Fee-Konto je (User, Asset): negativ = gezahlte Fees, positiv = Rebate-Guthaben
+    pub accounts: std::collections::BTreeMap<(String, String), f64>,
+}
+
+impl FeeLedger {
+    pub fn add(&mut self, quote_asset: &str, fee: &FeeOutput) {
+        *self.totals.entry(quote_asset.to_string()).or_insert(0.0) += fee.total();
+        self.trades += 1;
+    }
+
+    /// Bucht `fee` auf das Konto von `user`; eine negative Fee (Rebate) ist eine Gutschrift.
+    pub fn charge(&mut self, user: &str, asset: &str, fee: f64) {
+        *self.accounts.entry((user.to_string(), asset.to_string())).or_insert(0.0) -= fee;
+    }
+
+    pub fn account(&self, user: &str, asset: &str) -> f64 {
+        self.accounts.get(&(user.to_string(), asset.to_string())).copied().unwrap_or(0.0)
+    }
+}
+
+/// Fee-Buchung eines Trades; wird erst nach erfolgreichem Settlement in den
+/// `FeeLedger` übernommen.
+#[derive(Clone, Debug)]
+struct FeeBooking {
+    quote_asset: String,
+    taker: String,
+    maker: String,
+    fees: TradeFees,
+    pool: FeeOutput,
+}
+
+impl FeeBooking {
+    fn apply(&self, ledger: &mut FeeLedger) {
+        ledger.add(&self.quote_asset, &self.pool);
+        ledger.charge(&self.taker, &self.quote_asset, self.fees.taker_fee);
+        ledger.charge(&self.maker, &self.quote_asset, self.fees.maker_fee);
+    }
+}
+
+/// Maker/Taker-Raten eines Marktes (Anteil am Notional).
+/// `maker_rate < 0` ist ein Rebate und darf die Taker-Fee nicht übersteigen,
+/// damit der Pool netto nie negativ wird.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MarketFees {
+    pub taker_rate: f64,
+    #[serde(default)]
+    pub maker_rate: f64,
+}
+
+impl Default for MarketFees {
+    fn default() -> Self {
+        Self { taker_rate: 0.001, maker_rate: 0.0 }
+    }
+}
+
+/// Fees eines einzelnen Fills.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TradeFees {
+    pub taker_fee: f64,
+    /// negativ => Rebate an den Maker
+    pub maker_fee: f64,
+    /// Anteil, der im Pool verbleibt (>= 0)
+    pub pool_fee: f64,
+}
+
+impl MarketFees {
+    pub fn with_rebate(taker_rate: f64, rebate_rate: f64) -> Self {
+        Self { taker_rate, maker_rate: -rebate_rate }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.taker_rate.is_finite() || !(0.0..=1.0).contains(&self.taker_rate) {
+            return Err(format!("taker_rate {} must be within 0..=1", self.taker_rate));
+        }
+        if !self.maker_rate.is_finite() || self.maker_rate > 1.0 {
+            return Err(format!("maker_rate {} must be <= 1", self.maker_rate));
+        }
+        if self.maker_rate < -self.taker_rate {
+            return Err(format!(
+                "maker rebate {} exceeds taker fee {} => pool would pay out",
+                -self.maker_rate, self.taker_rate
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn split(&self, notional: f64) -> TradeFees {
+        let taker_fee = notional * self.taker_rate;
+        let maker_fee = notional * self.maker_rate;
+        TradeFees { taker_fee, maker_fee, pool_fee: (taker_fee + maker_fee).max(0.0) }
+    }
+}
+
+/// Fee-Raten je Markt ("BASE/QUOTE"), sonst `default`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    #[serde(default)]
+    pub default: MarketFees,
+    #[serde(default)]
+    pub markets: std::collections::BTreeMap<String, MarketFees>,
+}
+
+impl FeeSchedule {
+    /// Normalisierter Schlüssel ("btc/usdt" => "BTC/USDT"), auch für Limits und Breaker.
+    pub(crate) fn market_key(market: &str) -> String {
+        MarketPair::parse(market).map(|p| p.to_string()).unwrap_or_else(|_| market.to_string())
+    }
+
+    pub fn for_market(&self, market: &str) -> MarketFees {
+        self.markets.get(&Self::market_key(market)).copied().unwrap_or(self.default)
+    }
+
+    pub fn set_market(&mut self, market: &str, fees: MarketFees) -> Result<(), DexError> {
+        let key = Self::market_key(market);
+        fees.validate().map_err(|reason| DexError::InvalidConfig {
+            field: format!("trading_fees.markets.{}", key),
+            reason,
+        })?;
+        self.markets.insert(key, fees);
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), DexError> {
+        self.default.validate().map_err(|reason| DexError::InvalidConfig {
+            field: "trading_fees.default".into(),
+            reason,
+        })?;
+        for (market, fees) in &self.markets {
+            fees.validate().map_err(|reason| DexError::InvalidConfig {
+                field: format!("trading_fees.markets.{}", market),
+                reason,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Teilt `total_fee` auf. Der Betrag wird auf ganze Untereinheiten
+/// (`FEE_SUBUNITS_PER_UNIT`) abgerundet und danach per `split_fee_subunits`
+/// verteilt; die Anteile summieren sich exakt zum gerundeten Gesamtbetrag.
+#[instrument(name = "fee_distribution", level = "debug", skip(distribution))]
 pub fn calculate_fee(total_fee: f64, distribution: &FeeDistribution) -> FeeOutput {
-    FeeOutput {
-        founder_fee: total_fee * distribution.founder_percent,
-        dev_fee: total_fee * distribution.dev_percent,
-        node_fee: total_fee * distribution.node_percent,
+    let subunits = (total_fee.max(0.0) * FEE_SUBUNITS_PER_UNIT as f64).floor() as u64;
+    FeeOutput::from_split(&split_fee_subunits(subunits, distribution))
+}
+
+/// Größengrenzen einer einzelnen Order; 0 => keine Grenze.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderSizeLimits {
+    /// Mindestmenge gegen Dust-Orders
+    #[serde(default)]
+    pub min_quantity: f64,
+    /// Höchster Gegenwert (Menge * Preis) gegen Fat-Finger-Orders
+    #[serde(default)]
+    pub max_notional: f64,
+}
+
+impl OrderSizeLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.min_quantity.is_finite() || self.min_quantity < 0.0 {
+            return Err(format!("min_quantity {} must be >= 0", self.min_quantity));
+        }
+        if !self.max_notional.is_finite() || self.max_notional < 0.0 {
+            return Err(format!("max_notional {} must be >= 0", self.max_notional));
+        }
+        Ok(())
+    }
+
+    /// Prüft Menge und Gegenwert einer Order. Ohne Preis (Market-Order auf
+    /// leerer Gegenseite) zählt nur die Mindestmenge.
+    pub fn check(&self, market: &str, quantity: f64, price: Option<f64>) -> Result<(), DexError> {
+        if self.min_quantity > 0.0 && quantity < self.min_quantity {
+            return Err(DexError::OrderTooSmall {
+                market: market.to_string(),
+                quantity,
+                min_quantity: self.min_quantity,
+            });
+        }
+        if self.max_notional > 0.0 {
+            let notional = price.map(|px| quantity * px);
+            let too_large = !quantity.is_finite() || notional.is_some_and(|n| !n.is_finite() || n > self.max_notional);
+            if too_large {
+                return Err(DexError::OrderTooLarge {
+                    market: market.to_string(),
+                    notional: notional.unwrap_or(quantity),
+                    max_notional: self.max_notional,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Order-Größengrenzen je Markt ("BASE/QUOTE"), sonst `default`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderLimits {
+    #[serde(default)]
+    pub default: OrderSizeLimits,
+    #[serde(default)]
+    pub markets: std::collections::BTreeMap<String, OrderSizeLimits>,
+}
+
+impl OrderLimits {
+    pub fn for_market(&self, market: &str) -> OrderSizeLimits {
+        self.markets.get(&FeeSchedule::market_key(market)).copied().unwrap_or(self.default)
+    }
+
+    pub fn validate(&self) -> Result<(), DexError> {
+        self.default.validate().map_err(|reason| DexError::InvalidConfig {
+            field: "order_limits.default".into(),
+            reason,
+        })?;
+        for (market, limits) in &self.markets {
+            limits.validate().map_err(|reason| DexError::InvalidConfig {
+                field: format!("order_limits.markets.{}", market),
+                reason,
+            })?;
+        }
+        Ok(())
     }
 }
 
@@ -189,10 +619,55 @@ pub struct LimitOrder {
     pub order: OrderData,
 }
 
+/// Ein einzelner Fill inkl. der Eigentümer beider Orders.
+/// Gefüllte Orders sind nach dem Matching aus dem Buch entfernt, daher
+/// werden die User-IDs direkt beim Fill mitgeführt.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TradeFill {
+    pub buy_order_id: String,
+    pub sell_order_id: String,
+    pub buyer: String,
+    pub seller: String,
+    pub quantity: f64,
+    pub price: f64,
+    /// Seite der ruhenden Order (Maker); die andere Seite ist Taker
+    pub maker: OrderSide,
+}
+
+impl TradeFill {
+    pub fn as_tuple(&self) -> (String, String, f64, f64) {
+        (self.buy_order_id.clone(), self.sell_order_id.clone(), self.quantity, self.price)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LimitOrderBook {
     pub buy_orders: VecDeque<LimitOrder>,
     pub sell_orders: VecDeque<LimitOrder>,
+    // Einfüge-Reihenfolge je Order-ID (für Maker/Taker)
+    arrivals: HashMap<String, u64>,
+    next_arrival: u64,
+}
+
+/// Maker ist die Order, die zuerst im Buch lag (bei sequenzierten Orders:
+/// kleinere SequenceNo); Market-Orders sind immer Taker. Ohne bekannte
+/// Reihenfolge entscheidet der Zeitstempel, bei Gleichstand gilt die
+/// Sell-Seite als Maker.
+fn maker_side(arrivals: &HashMap<String, u64>, buy: &OrderData, sell: &OrderData) -> OrderSide {
+    match (matches!(buy.order_type, OrderType::Market), matches!(sell.order_type, OrderType::Market)) {
+        (true, false) => return OrderSide::Sell,
+        (false, true) => return OrderSide::Buy,
+        _ => {}
+    }
+    if let (Some(b), Some(s)) = (&buy.sequence, &sell.sequence) {
+        return if b < s { OrderSide::Buy } else { OrderSide::Sell };
+    }
+    let key = |o: &OrderData| (arrivals.get(&o.id).copied().unwrap_or(u64::MAX), o.timestamp);
+    if key(buy) < key(sell) {
+        OrderSide::Buy
+    } else {
+        OrderSide::Sell
+    }
 }
 
 impl LimitOrderBook {
@@ -200,6 +675,8 @@ impl LimitOrderBook {
         Self {
             buy_orders: VecDeque::new(),
             sell_orders: VecDeque::new(),
+            arrivals: HashMap::new(),
+            next_arrival: 0,
         }
     }
     
@@ -207,24 +684,105 @@ impl LimitOrderBook {
     /// falls Signatur oder Menge ungültig.
     pub fn add_order(&mut self, order: OrderData) -> Result<(), DexError> {
         // 1) check quantity
-        if order.quantity <= 0.0 {
+        if !order.quantity.is_finite() || order.quantity <= 0.0 {
             return Err(DexError::Other("Quantity <= 0 => invalid".into()));
         }
         // 2) sign
         if !order.verify_signature() {
             // wir loggen + return Err, damit der aufrufende Code es mitkriegt
             warn!("LimitOrderBook => add_order: Ungültige Signatur => abgelehnt, ID={}", order.id);
-            return Err(DexError::Other("Ungültige Order-Signatur".into()));
+            return Err(DexError::InvalidSignature(format!("order {}", order.id)));
         }
         // => insertion
+        self.arrivals.insert(order.id.clone(), self.next_arrival);
+        self.next_arrival += 1;
         let lo = LimitOrder { order };
-        match lo.order.side {
-            OrderSide::Buy => self.buy_orders.push_back(lo),
-            OrderSide::Sell => self.sell_orders.push_back(lo),
+        let side = match lo.order.side {
+            OrderSide::Buy => &mut self.buy_orders,
+            OrderSide::Sell => &mut self.sell_orders,
+        };
+        // Sequenzierte Orders vor die erste mit größerer Nummer, sonst hinten an
+        let pos = lo.order.sequence.and_then(|seq| {
+            side.iter().position(|other| other.order.sequence.is_some_and(|o| o > seq))
+        });
+        match pos {
+            Some(i) => side.insert(i, lo),
+            None => side.push_back(lo),
         }
         Ok(())
     }
     
+    /// Entfernt die Orders mit den angegebenen IDs (beide Seiten) und gibt sie zurück.
+    pub fn remove_orders(&mut self, ids: &[String]) -> Vec<OrderData> {
+        let mut removed = Vec::new();
+        for side in [&mut self.buy_orders, &mut self.sell_orders] {
+            side.retain(|lo| {
+                if ids.contains(&lo.order.id) {
+                    self.arrivals.remove(&lo.order.id);
+                    removed.push(lo.order.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        removed
+    }
+
+    pub fn len(&self) -> usize {
+        self.buy_orders.len() + self.sell_orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Anzahl ruhender Orders von `user_id`.
+    pub fn count_for_user(&self, user_id: &str) -> usize {
+        self.buy_orders.iter().chain(&self.sell_orders).filter(|lo| lo.order.user_id == user_id).count()
+    }
+
+    /// Höchstes ruhendes Gebot (ohne Market-Orders).
+    pub fn best_bid(&self) -> Option<f64> {
+        self.buy_orders
+            .iter()
+            .filter(|lo| !matches!(lo.order.order_type, OrderType::Market))
+            .map(|lo| order_price(&lo.order, true))
+            .fold(None, |m: Option<f64>, p| Some(m.map_or(p, |m| m.max(p))))
+    }
+
+    /// Niedrigster ruhender Ask (ohne Market-Orders).
+    pub fn best_ask(&self) -> Option<f64> {
+        self.sell_orders
+            .iter()
+            .filter(|lo| !matches!(lo.order.order_type, OrderType::Market))
+            .map(|lo| order_price(&lo.order, false))
+            .fold(None, |m: Option<f64>, p| Some(m.map_or(p, |m| m.min(p))))
+    }
+
+    /// Mitte aus bestem Gebot und bestem Ask; mit nur einer Seite deren bester Preis.
+    pub fn mid_price(&self) -> Option<f64> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some(b), Some(a)) => Some((b + a) / 2.0),
+            (b, a) => b.or(a),
+        }
+    }
+
+    /// Am wenigsten kompetitive ruhende Order (größter Abstand zu `mid`),
+    /// optional nur unter den Orders von `owner`. Bei Gleichstand die jüngste.
+    pub fn farthest_from(&self, mid: f64, owner: Option<&str>) -> Option<(String, f64)> {
+        self.buy_orders
+            .iter()
+            .chain(&self.sell_orders)
+            .filter(|lo| owner.map_or(true, |u| lo.order.user_id == u))
+            .map(|lo| {
+                let arrival = self.arrivals.get(&lo.order.id).copied().unwrap_or(0);
+                (lo.order.id.clone(), distance_from_mid(&lo.order, mid), arrival)
+            })
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal).then(a.2.cmp(&b.2)))
+            .map(|(id, dist, _)| (id, dist))
+    }
+
     pub fn sort_orders(&mut self) {
         self.buy_orders
             .make_contiguous()
@@ -235,8 +793,20 @@ impl LimitOrderBook {
     }
     
     pub fn match_orders(&mut self) -> Vec<(String, String, f64, f64)> {
+        self.match_fills().iter().map(TradeFill::as_tuple).collect()
+    }
+
+    /// Wie match_orders(), liefert aber zusätzlich Käufer/Verkäufer je Fill.
+    ///
+    /// `MATCH_DURATION_BY_ORDER_TYPE` erfasst je beteiligter Order die Dauer ihres
+    /// gesamten Matchings in diesem Lauf: bis sie gefüllt aus dem Buch fällt bzw.
+    /// bis zum Ende des Laufs, falls sie teilgefüllt liegen bleibt.
+    pub fn match_fills(&mut self) -> Vec<TradeFill> {
+        let run_start = std::time::Instant::now();
         self.sort_orders();
         let mut trades = Vec::new();
+        // Beteiligte Orders, deren Matching-Dauer noch nicht erfasst wurde
+        let mut in_match: HashMap<String, &'static str> = HashMap::new();
         
         while let (Some(buy_lo), Some(sell_lo)) = (self.buy_orders.front_mut(), self.sell_orders.front_mut()) {
             let buy_order = &buy_lo.order;
@@ -265,6 +835,8 @@ impl LimitOrderBook {
                 }
             };
 
+            let maker = maker_side(&self.arrivals, buy_order, sell_order);
+
             {
                 let buy_mut = &mut self.buy_orders.front_mut().unwrap().order;
                 let sell_mut = &mut self.sell_orders.front_mut().unwrap().order;
@@ -272,40 +844,118 @@ impl LimitOrderBook {
                 sell_mut.fill(fill_qty);
             }
 
-            trades.push((buy_order.id.clone(), sell_order.id.clone(), fill_qty, trade_price));
+            trades.push(TradeFill {
+                buy_order_id: buy_order.id.clone(),
+                sell_order_id: sell_order.id.clone(),
+                buyer: buy_order.user_id.clone(),
+                seller: sell_order.user_id.clone(),
+                quantity: fill_qty,
+                price: trade_price,
+                maker,
+            });
+
+            in_match.insert(buy_order.id.clone(), buy_order.order_type.label());
+            in_match.insert(sell_order.id.clone(), sell_order.order_type.label());
 
             // ggf. remove front if filled
             if self.buy_orders.front().unwrap().order.status == OrderStatus::Filled {
-                self.buy_orders.pop_front();
+                if let Some(lo) = self.buy_orders.pop_front() {
+                    self.arrivals.remove(&lo.order.id);
+                    observe_match_duration(&mut in_match, &lo.order.id, run_start);
+                }
             }
             if self.sell_orders.front().unwrap().order.status == OrderStatus::Filled {
-                self.sell_orders.pop_front();
+                if let Some(lo) = self.sell_orders.pop_front() {
+                    self.arrivals.remove(&lo.order.id);
+                    observe_match_duration(&mut in_match, &lo.order.id, run_start);
+                }
             }
         }
+        // Teilgefüllt liegen gebliebene Orders: Matching endet mit dem Lauf
+        let elapsed = run_start.elapsed().as_secs_f64();
+        for label in in_match.into_values() {
+            MATCH_DURATION_BY_ORDER_TYPE.with_label_values(&[label]).observe(elapsed);
+        }
+        debug_assert!(
+            self.validate_invariants().is_ok(),
+            "Order-Book nach match_orders inkonsistent: {:?}",
+            self.validate_invariants()
+        );
         trades
     }
-}
-
-fn compare_orders(a: &OrderData, b: &OrderData, is_buy: bool) -> Ordering {
-    let a_market = matches!(a.order_type, OrderType::Market);
-    let b_market = matches!(b.order_type, OrderType::Market);
 
-    // Market-Orders zuerst
-    if a_market && !b_market {
-        return Ordering::Less;
-    }
-    if !a_market && b_market {
-        return Ordering::Greater;
+    /// Prüft die Buch-Invarianten:
+    /// - bestes Gebot liegt strikt unter dem besten Ask (kein gekreuztes Buch)
+    /// - keine negative Restmenge
+    /// - keine Filled-Orders mehr im Buch
+    pub fn validate_invariants(&self) -> Result<(), DexError> {
+        for (side, orders) in [("buy", &self.buy_orders), ("sell", &self.sell_orders)] {
+            for lo in orders {
+                if lo.order.remaining() < -1e-9 {
+                    return Err(DexError::InvariantViolation(format!(
+                        "{} order {} has negative remaining {}",
+                        side,
+                        lo.order.id,
+                        lo.order.remaining()
+                    )));
+                }
+                if lo.order.status == OrderStatus::Filled {
+                    return Err(DexError::InvariantViolation(format!(
+                        "filled {} order {} still resting",
+                        side, lo.order.id
+                    )));
+                }
+            }
+        }
+        let best_buy = self.buy_orders.iter().min_by(|a, b| compare_orders(&a.order, &b.order, true));
+        let best_sell = self.sell_orders.iter().min_by(|a, b| compare_orders(&a.order, &b.order, false));
+        if let (Some(b), Some(s)) = (best_buy, best_sell) {
+            if price_match(&b.order, &s.order) {
+                return Err(DexError::InvariantViolation(format!(
+                    "crossed book: bid {} ({:?}) >= ask {} ({:?})",
+                    b.order.id, b.order.order_type, s.order.id, s.order.order_type
+                )));
+            }
+        }
+        Ok(())
     }
+}
 
+/// Gemeldete Invarianten-Verletzung (wird z. B. als FaultMessage gegossipt).
+#[derive(Clone, Debug)]
+pub struct InvariantFault {
+    pub market: String,
+    pub reason: String,
+    pub timestamp: u64,
+}
+
+/// Priorität im Buch. Verglichen wird ein fester Schlüssel je Order, damit die
+/// Ordnung auch bei teils ungestempelten Orders transitiv bleibt (sonst darf
+/// `sort_by` paniken): Market vor Limit, dann Preis, dann SequenceNo, dann
+/// HLC; Gestempelte vor Ungestempelten, zwei Ungestempelte bleiben in
+/// Einfüge-Reihenfolge.
+fn compare_orders(a: &OrderData, b: &OrderData, is_buy: bool) -> Ordering {
+    let not_market = |o: &OrderData| !matches!(o.order_type, OrderType::Market);
     let price_a = order_price(a, is_buy);
     let price_b = order_price(b, is_buy);
 
-    // Buy => absteigend sortieren, Sell => aufsteigend
-    if is_buy {
-        price_b.partial_cmp(&price_a).unwrap_or(Ordering::Equal)
-    } else {
-        price_a.partial_cmp(&price_b).unwrap_or(Ordering::Equal)
+    not_market(a)
+        .cmp(&not_market(b))
+        // Buy => absteigend sortieren, Sell => aufsteigend
+        .then_with(|| if is_buy { price_b.total_cmp(&price_a) } else { price_a.total_cmp(&price_b) })
+        .then_with(|| stamped_first(&a.sequence).cmp(&stamped_first(&b.sequence)))
+        .then_with(|| stamped_first(&a.hlc).cmp(&stamped_first(&b.hlc)))
+}
+
+fn stamped_first<T: Ord>(stamp: &Option<T>) -> (bool, Option<&T>) {
+    (stamp.is_none(), stamp.as_ref())
+}
+
+/// Preislevel, auf dem die Order im Buch liegt (None für Market-Orders).
+fn resting_price(o: &OrderData) -> Option<f64> {
+    match o.order_type {
+        OrderType::Market => None,
+        _ => Some(order_price(o, matches!(o.side, OrderSide::Buy))),
     }
 }
 
@@ -320,6 +970,23 @@ fn order_price(o: &OrderData, is_buy: bool) -> f64 {
     }
 }
 
+/// Abstand zum Mid; Market-Orders sind immer maximal kompetitiv.
+fn distance_from_mid(o: &OrderData, mid: f64) -> f64 {
+    match o.order_type {
+        OrderType::Market => 0.0,
+        _ => (order_price(o, matches!(o.side, OrderSide::Buy)) - mid).abs(),
+    }
+}
+
+/// Erfasst die Matching-Dauer einer Order, die das Buch gefüllt verlassen hat.
+fn observe_match_duration(in_match: &mut HashMap<String, &'static str>, order_id: &str, run_start: std::time::Instant) {
+    if let Some(label) = in_match.remove(order_id) {
+        MATCH_DURATION_BY_ORDER_TYPE
+            .with_label_values(&[label])
+            .observe(run_start.elapsed().as_secs_f64());
+    }
+}
+
 fn price_match(buy: &OrderData, sell: &OrderData) -> bool {
     match (&buy.order_type, &sell.order_type) {
         (OrderType::Market, _) | (_, OrderType::Market) => true,
@@ -330,6 +997,97 @@ fn price_match(buy: &OrderData, sell: &OrderData) -> bool {
     }
 }
 
+/// Verhalten bei erreichter Obergrenze.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookCapPolicy {
+    /// Neue Order mit `DexError::BookCapExceeded` ablehnen
+    #[default]
+    Reject,
+    /// Die Order mit dem größten Abstand zum Mid verdrängen; ist das die
+    /// neue Order selbst, wird sie abgelehnt
+    EvictFarthest,
+}
+
+/// Obergrenzen gegen Spam im Buch (NodeConfig `book_caps`); 0 => unbegrenzt.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BookCaps {
+    #[serde(default)]
+    pub max_orders_per_user: usize,
+    #[serde(default)]
+    pub max_orders_per_market: usize,
+    #[serde(default)]
+    pub policy: BookCapPolicy,
+}
+
+/// Ergebnis einer Order innerhalb eines Batches.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaceStatus {
+    Accepted,
+    Rejected,
+    /// Selbst gültig, aber wegen einer anderen Order im atomaren Batch nicht übernommen
+    RolledBack,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlaceResult {
+    /// Position im Batch
+    pub index: usize,
+    pub order_id: Option<String>,
+    pub status: PlaceStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// `DexError::code` bei Rejected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+impl PlaceResult {
+    pub fn accepted(index: usize, order_id: &str) -> Self {
+        Self { index, order_id: Some(order_id.to_string()), status: PlaceStatus::Accepted, error: None, code: None }
+    }
+
+    pub fn rejected(index: usize, order_id: Option<&str>, err: &DexError) -> Self {
+        Self {
+            index,
+            order_id: order_id.map(str::to_string),
+            status: PlaceStatus::Rejected,
+            error: Some(err.to_string()),
+            code: Some(err.code().to_string()),
+        }
+    }
+
+    pub fn rolled_back(index: usize, order_id: Option<&str>) -> Self {
+        Self { index, order_id: order_id.map(str::to_string), status: PlaceStatus::RolledBack, error: None, code: None }
+    }
+
+    pub fn is_accepted(&self) -> bool {
+        self.status == PlaceStatus::Accepted
+    }
+}
+
+/// Rücknahme-Information eines laufenden atomaren Batches. Verdrängungen
+/// (Book-Cap) werden erst wirksam, wenn der Batch vollständig übernommen ist;
+/// bis dahin liegt der Buchstand vor der ersten Verdrängung hier.
+#[derive(Default)]
+struct BatchUndo {
+    book_before_eviction: Option<LimitOrderBook>,
+    /// (verdrängte Order, Scope des Caps)
+    evicted: Vec<(OrderData, String)>,
+}
+
+/// Rückruf für verdrängte Orders (Book-Cap), siehe `with_eviction_hook`.
+pub type EvictionHook = Arc<dyn Fn(&OrderData) + Send + Sync>;
+
+/// Atomar: Rejected bleiben, alle Accepted werden zu RolledBack.
+pub fn roll_back_results(results: Vec<PlaceResult>) -> Vec<PlaceResult> {
+    results
+        .into_iter()
+        .map(|r| if r.is_accepted() { PlaceResult::rolled_back(r.index, r.order_id.as_deref()) } else { r })
+        .collect()
+}
+
 // ─────────────────────────────────────────────────────────
 // MatchingEngine
 // ─────────────────────────────────────────────────────────
@@ -350,6 +1108,65 @@ pub struct MatchingEngine {
 
     // NEU: Optionales globales Security-System
     pub global_sec: Option<Arc<Mutex<GlobalSecuritySystem>>>,
+
+    // Marktname + optionaler Broadcast für Marktdaten
+    pub market: String,
+    pub market_data: Option<MarketDataHub>,
+
+    // Kill-Switch (geteilt zwischen allen Engines eines Nodes)
+    pub halt_control: Option<Arc<Mutex<MarketHaltControl>>>,
+
+    // Kanonische Reihenfolge über den VRF-Sequencer (None => lokale Reihenfolge)
+    pub sequencing: Option<SequencingState>,
+
+    // Commit-Reveal (None => Orders direkt über place_order)
+    pub commit_reveal: Option<CommitRevealBook>,
+
+    // NTP-basierte Zeitstempel-Prüfung eingehender Orders (None => keine)
+    pub clock_guard: Option<Arc<ClockSkewGuard>>,
+
+    // Invarianten nach jedem Match prüfen + optionaler Fault-Kanal
+    pub invariant_checks: bool,
+    pub invariant_faults: Option<tokio::sync::mpsc::UnboundedSender<InvariantFault>>,
+
+    // Dauerhafte Settlement-Queue (None => Settlement-Fehler brechen process_trades ab)
+    pub settlement_queue: Option<SettlementQueue>,
+
+    // Paper-Trading: Settlement nur simuliert (siehe with_dry_run)
+    pub dry_run: bool,
+
+    // Gemeinsamer Fee-Zähler (von MarketShards gesetzt)
+    pub fee_ledger: Option<Arc<Mutex<FeeLedger>>>,
+
+    // Maker/Taker-Raten (Default: 0.1% Taker, kein Rebate)
+    pub fee_schedule: FeeSchedule,
+
+    // Mindestmenge / Höchst-Gegenwert je Markt (Default: keine)
+    pub order_limits: OrderLimits,
+
+    // Preissprung-Schutz (None => immer matchen)
+    pub circuit_breaker: Option<Arc<Mutex<CircuitBreaker>>>,
+
+    // Obergrenzen je User / Markt (Default: keine)
+    pub book_caps: BookCaps,
+
+    // HLC für die Annahme-Reihenfolge direkt platzierter Orders (None => Ankunft)
+    pub admission_clock: Option<HybridLogicalClock>,
+
+    // Gibt die Sperren verdrängter Orders frei (None => niemand hält Sperren)
+    pub eviction_hook: Option<EvictionHook>,
+
+    // Verhaltensanalyse je Trade (Wash-Trading, Velocity; None => keine)
+    pub anomaly_engine: Option<Arc<Mutex<AnomalyEngine>>>,
+
+    // Nur während eines atomaren place_orders_batch gesetzt
+    batch_undo: Option<BatchUndo>,
+
+    // Gematchte Trades, deren Einreihen in die Settlement-Queue gescheitert ist
+    unqueued_trades: Vec<KeyedTrade>,
+
+    // Fees je Trade-ID, deren Settlement über die Queue noch aussteht
+    pending_fees: HashMap<String, FeeBooking>,
 }
 
 impl MatchingEngine {
@@ -364,6 +1181,27 @@ impl MatchingEngine {
             advanced_security: Box::new(AdvancedSecurityValidator::new()),
             time_limited_manager: None,
             global_sec: None,
+            market: "BTC/USDT".to_string(),
+            market_data: None,
+            halt_control: None,
+            sequencing: None,
+            commit_reveal: None,
+            clock_guard: None,
+            invariant_checks: false,
+            invariant_faults: None,
+            settlement_queue: None,
+            dry_run: false,
+            fee_ledger: None,
+            fee_schedule: FeeSchedule::default(),
+            order_limits: OrderLimits::default(),
+            circuit_breaker: None,
+            book_caps: BookCaps::default(),
+            admission_clock: None,
+            eviction_hook: None,
+            anomaly_engine: None,
+            batch_undo: None,
+            unqueued_trades: Vec::new(),
+            pending_fees: HashMap::new(),
         }
     }
 
@@ -374,98 +1212,1273 @@ impl MatchingEngine {
         engine
     }
 
-    pub fn with_time_limited_manager(mut self, manager: TimeLimitedOrderManager) -> Self {
-        self.time_limited_manager = Some(manager);
+    /// Settlements laufen über die Queue: gematchte Trades bleiben bis zum
+    /// erfolgreichen (idempotenten) Settlement im Zustand "pending".
+    pub fn with_settlement_queue(mut self, queue: SettlementQueue) -> Self {
+        self.settlement_queue = Some(queue);
         self
     }
 
-    /// Order platzieren (nun mit Checks):
-    /// - Wir prüfen quantity
-    /// - Wir übergeben an LimitOrderBook => signatur => Fehler, wenn invalid
-    pub fn place_order(&mut self, order: OrderData) -> Result<(), DexError> {
-        if order.quantity <= 0.0 {
-            return Err(DexError::Other("Order quantity <= 0 => invalid".into()));
+    /// Paper-Trading: die aktuelle Settlement-Engine wird in eine Dry-Run-
+    /// SecuredSettlementEngine gehüllt, die nur ein simuliertes Ledger bucht.
+    /// Muss nach dem Setzen von `settlement` aufgerufen werden.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        if dry_run {
+            self.enable_dry_run();
         }
-        self.order_book.add_order(order)?;
-        Ok(())
+        self
     }
 
-    /// Vereinte Variante von match_orders():
-    /// - Ruft ggf. Security Audit über global_sec auf
-    /// - Führt das eigentliche Matching (bisheriger Code) durch
-    /// - Liefert Liste an Trades zurück
-    pub fn match_orders(&mut self) -> Result<Vec<(String, String, f64, f64)>, DexError> {
-        // Falls global_sec vorhanden => z.B. Rate Limit / Audit
-        if let Some(ref sec_arc) = self.global_sec {
-            let sec = sec_arc.lock().unwrap();
-            sec.audit_event("MatchingEngine => start match_orders");
+    /// Wie `with_dry_run(true)` für eine bereits geteilte Engine; lässt sich
+    /// nicht mehr abschalten.
+    pub fn enable_dry_run(&mut self) {
+        if self.dry_run {
+            return;
+        }
+        let inner = std::mem::replace(&mut self.settlement, Box::new(SettlementEngine::new()));
+        self.settlement =
+            Box::new(SecuredSettlementEngine::new(inner, AdvancedSecurityValidator::new()).with_dry_run(true));
+        self.dry_run = true;
+        warn!("MatchingEngine {} => DRY-RUN aktiv, keine echten Settlements", self.market);
+    }
+
+    fn audit(&self, event: &str) {
+        if self.dry_run {
+            write_audit_log(&format!("[DRY-RUN] {}", event));
+        } else {
+            write_audit_log(event);
         }
+    }
 
-        // Dann reguläre Matching-Logik
-        let trades = self.order_book.match_orders();
-        Ok(trades)
+    /// Wiederholt fällige Settlement-Jobs (no-op ohne Queue).
+    pub fn retry_pending_settlements(&mut self) -> Result<QueueRunReport, DexError> {
+        let report = match &self.settlement_queue {
+            Some(queue) => queue.process_due(self.settlement.as_mut(), now_secs())?,
+            None => return Ok(QueueRunReport::default()),
+        };
+        // Fees erst buchen, wenn das Haupt-Leg des Trades abgewickelt ist
+        let settled: Vec<FeeBooking> =
+            report.settled_keys.iter().filter_map(|key| self.pending_fees.remove(key)).collect();
+        self.book_fees(&settled);
+        Ok(report)
     }
 
-    /// Prozessiert die Trades => Security-Check, Settlement, Fees, Audit-Log
-    pub fn process_trades(&mut self) -> Result<(), DexError> {
-        // Time-Limited abgelaufene Orders
-        if let Some(ref mut manager) = self.time_limited_manager {
-            if let Err(e) = manager.check_and_handle_expired(&mut self.order_book) {
-                warn!("Fehler bei check_and_handle_expired: {:?}", e);
+    fn book_fees(&self, bookings: &[FeeBooking]) {
+        if let Some(ledger) = &self.fee_ledger {
+            let mut ledger = ledger.lock_recover();
+            for booking in bookings {
+                booking.apply(&mut ledger);
             }
         }
+    }
+
+    pub fn with_time_limited_manager(mut self, manager: TimeLimitedOrderManager) -> Self {
+        self.time_limited_manager = Some(manager);
+        self
+    }
+
+    /// Publiziert Trades und Order-Book-Deltas des Marktes `market` an `hub`.
+    pub fn with_market_data(mut self, market: &str, hub: MarketDataHub) -> Self {
+        self.market = market.to_string();
+        self.market_data = Some(hub);
+        self
+    }
+
+    pub fn with_fee_ledger(mut self, ledger: Arc<Mutex<FeeLedger>>) -> Self {
+        self.fee_ledger = Some(ledger);
+        self
+    }
+
+    pub fn with_order_limits(mut self, limits: OrderLimits) -> Self {
+        self.order_limits = limits;
+        self
+    }
+
+    pub fn with_fee_schedule(mut self, schedule: FeeSchedule) -> Self {
+        self.fee_schedule = schedule;
+        self
+    }
+
+    /// Neuer Gebührenplan zur Laufzeit (Config-Reload), gilt ab dem nächsten Fill.
+    pub fn set_fee_schedule(&mut self, schedule: FeeSchedule) {
+        self.fee_schedule = schedule;
+    }
+
+    /// Obergrenzen je User/Markt; bei `EvictFarthest` gibt `with_eviction_hook`
+    /// die Sperren verdrängter Orders frei.
+    pub fn with_book_caps(mut self, caps: BookCaps) -> Self {
+        self.book_caps = caps;
+        self
+    }
+
+    /// Wird für jede wirksam verdrängte Order aufgerufen; der Halter der
+    /// Balance-Sperren (z.B. DexNode) gibt darüber die Restmenge frei.
+    pub fn with_eviction_hook(mut self, hook: EvictionHook) -> Self {
+        self.eviction_hook = Some(hook);
+        self
+    }
+
+    /// Direkt platzierte Orders bekommen bei der Annahme eine HLC-SequenceNo;
+    /// replizierte Orders (`place_replicated_order`) behalten die des
+    /// Ursprungs-Nodes.
+    pub fn with_admission_clock(mut self, clock: HybridLogicalClock) -> Self {
+        self.admission_clock = Some(clock);
+        self
+    }
+
+    /// Geteilter Breaker (alle Märkte); gefüttert wird er vom PriceFeed.
+    /// Solange er für den Markt ausgelöst ist, wird nicht gematcht.
+    pub fn with_circuit_breaker(mut self, breaker: Arc<Mutex<CircuitBreaker>>) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// true, solange der Circuit-Breaker dieses Marktes ausgelöst ist.
+    pub fn is_circuit_broken(&self) -> bool {
+        self.circuit_breaker
+            .as_ref()
+            .map(|b| b.lock().unwrap().check(&self.market, now_secs()))
+            .unwrap_or(false)
+    }
+
+    pub fn with_halt_control(mut self, control: Arc<Mutex<MarketHaltControl>>) -> Self {
+        self.halt_control = Some(control);
+        self
+    }
+
+    pub fn is_market_halted(&self, market: &str) -> bool {
+        self.halt_control
+            .as_ref()
+            .map(|c| c.lock().unwrap().is_halted(market))
+            .unwrap_or(false)
+    }
+
+    fn ensure_not_halted(&self) -> Result<(), DexError> {
+        if self.is_market_halted(&self.market) {
+            return Err(DexError::MarketHalted(self.market.clone()));
+        }
+        Ok(())
+    }
+
+    /// Hält `market` an. `approvals` müssen von mindestens `threshold`
+    /// verschiedenen Committee-Mitgliedern über `HaltAction::Halt` stammen.
+    pub fn halt_market(&self, market: &str, approvals: &[HaltApproval]) -> Result<(), DexError> {
+        let control = self.halt_control.as_ref()
+            .ok_or_else(|| DexError::Other("No MarketHaltControl set".into()))?;
+        control.lock().unwrap().apply(market, HaltAction::Halt, approvals)
+    }
+
+    pub fn resume_market(&self, market: &str, approvals: &[HaltApproval]) -> Result<(), DexError> {
+        let control = self.halt_control.as_ref()
+            .ok_or_else(|| DexError::Other("No MarketHaltControl set".into()))?;
+        control.lock().unwrap().apply(market, HaltAction::Resume, approvals)
+    }
+
+    /// Ab jetzt werden Orders nur noch über `submit_sequenced` angenommen.
+    pub fn with_sequencing(mut self, election: SequencerElection) -> Self {
+        self.sequencing = Some(SequencingState::new(election));
+        self
+    }
+
+    /// Wählt aus den VRF-Claims den Sequencer der neuen Epoche.
+    pub fn start_sequencer_epoch(&mut self, claims: &[SequencerClaim], epoch: u64) -> Result<SequencerClaim, DexError> {
+        let seq = self.sequencing.as_mut()
+            .ok_or_else(|| DexError::Other("Sequencing not enabled".into()))?;
+        let leader = seq.start_epoch(claims, epoch)?;
+        info!("MatchingEngine {} => Sequencer für Epoche {}: {}", self.market, epoch, leader.node_id);
+        Ok(leader)
+    }
+
+    /// Übernimmt eine Sequenzierung des gewählten Sequencers. Die Orders
+    /// landen erst in `match_orders` im Buch, sobald sie lückenlos sind.
+    pub fn submit_sequenced(&mut self, batch: SequencedBatch) -> Result<(), DexError> {
+        self.ensure_not_halted()?;
+        let seq = self.sequencing.as_mut()
+            .ok_or_else(|| DexError::Other("Sequencing not enabled".into()))?;
+        seq.accept(batch)
+    }
+
+    /// Orders, die beim letzten Epochenwechsel hinter einer Lücke hingen;
+    /// der Aufrufer reicht sie beim neuen Sequencer erneut ein.
+    pub fn take_sequencer_orphans(&mut self) -> Vec<OrderData> {
+        self.sequencing.as_mut().map(|s| s.take_orphaned()).unwrap_or_default()
+    }
+
+    /// Beweis, dass der Sequencer der Epoche eine Position doppelt vergeben hat.
+    pub fn take_sequencer_equivocation(&mut self) -> Option<SequencerEquivocation> {
+        self.sequencing.as_mut().and_then(|s| s.take_equivocation())
+    }
+
+    /// Lehnt Orders ab, deren Zeitstempel mehr als die erlaubte Toleranz
+    /// von der NTP-korrigierten Zeit abweicht.
+    pub fn with_clock_guard(mut self, guard: Arc<ClockSkewGuard>) -> Self {
+        self.clock_guard = Some(guard);
+        self
+    }
+
+    /// Prüft bei jedem Match die Buch-Invarianten: gematcht wird auf einer Kopie,
+    /// die nur bei intakten Invarianten übernommen wird. Bei einer Verletzung
+    /// bleibt das Buch unverändert (keine Fills gehen verloren), sie wird an
+    /// `faults` gemeldet (falls gesetzt) und als Fehler zurückgegeben.
+    pub fn with_invariant_checks(mut self, faults: Option<tokio::sync::mpsc::UnboundedSender<InvariantFault>>) -> Self {
+        self.invariant_checks = true;
+        self.invariant_faults = faults;
+        self
+    }
+
+    pub fn validate_invariants(&self) -> Result<(), DexError> {
+        self.order_book.validate_invariants()
+    }
+
+    fn check_invariants_after_match(&self, candidate: &LimitOrderBook) -> Result<(), DexError> {
+        if let Err(e) = candidate.validate_invariants() {
+            error!("MatchingEngine[{}] => {}", self.market, e);
+            if let Some(tx) = &self.invariant_faults {
+                let _ = tx.send(InvariantFault {
+                    market: self.market.clone(),
+                    reason: e.to_string(),
+                    timestamp: now_secs(),
+                });
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn check_order_timestamp(&self, order: &OrderData) -> Result<(), DexError> {
+        match &self.clock_guard {
+            Some(guard) => guard.check_timestamp_secs(order.timestamp),
+            None => Ok(()),
+        }
+    }
+
+    /// Jeder gematchte Trade geht mit Käufer/Verkäufer an die AnomalyEngine.
+    pub fn with_anomaly_engine(mut self, engine: Arc<Mutex<AnomalyEngine>>) -> Self {
+        self.anomaly_engine = Some(engine);
+        self
+    }
+
+    pub fn with_commit_reveal(mut self, book: CommitRevealBook) -> Self {
+        self.commit_reveal = Some(book);
+        self
+    }
+
+    /// Commit-Phase: nur der Hash der Order wird angenommen, gebunden an
+    /// den (authentifizierten) Nutzer.
+    pub fn submit_commitment(&mut self, user_id: &str, hash: [u8; 32]) -> Result<(), DexError> {
+        self.ensure_not_halted()?;
+        let book = self.commit_reveal.as_mut()
+            .ok_or_else(|| DexError::Other("Commit-reveal not enabled".into()))?;
+        book.submit_commitment_at(user_id, hash, now_secs())
+    }
+
+    /// Reveal-Phase: Order + Nonce müssen zur Commitment der laufenden Epoche
+    /// passen. Danach dieselben Prüfungen wie bei `place_order`, nur ohne
+    /// Höchstalter: der Zeitstempel stammt aus der Commit-Phase.
+    pub fn reveal_order(&mut self, order: OrderData, nonce: &[u8]) -> Result<(), DexError> {
+        self.ensure_not_halted()?;
+        self.validate_order_fields(&order)?;
+        if let Some(guard) = &self.clock_guard {
+            guard.check_not_future_ms(order.timestamp.saturating_mul(1000))?;
+        }
+        let book = self.commit_reveal.as_mut()
+            .ok_or_else(|| DexError::Other("Commit-reveal not enabled".into()))?;
+        let order = book.reveal_order_at(order, nonce, now_secs())?;
+        self.insert_order(order)
+    }
+
+    fn book_key(&self) -> String {
+        format!("order_book/{}", self.market)
+    }
+
+    /// Sichert alle offenen Orders des Buchs (z.B. beim Shutdown).
+    pub fn persist_book(&self, db: &DexDB) -> Result<(), DexError> {
+        let orders: Vec<&OrderData> = self.order_book.buy_orders.iter()
+            .chain(self.order_book.sell_orders.iter())
+            .map(|lo| &lo.order)
+            .collect();
+        db.store_struct(&self.book_key(), &orders)?;
+        info!("MatchingEngine {} => {} offene Orders gesichert", self.market, orders.len());
+        Ok(())
+    }
+
+    /// Lädt ein mit `persist_book` gesichertes Buch; liefert die Anzahl Orders.
+    pub fn restore_book(&mut self, db: &DexDB) -> Result<usize, DexError> {
+        let orders: Vec<OrderData> = db.load_struct(&self.book_key())?.unwrap_or_default();
+        let count = orders.len();
+        for order in orders {
+            self.order_book.add_order(order)?;
+        }
+        Ok(count)
+    }
+
+    fn insert_order(&mut self, order: OrderData) -> Result<(), DexError> {
+        self.insert_order_as(order, Admission::Local)
+    }
+
+    fn insert_order_as(&mut self, order: OrderData, admission: Admission) -> Result<(), DexError> {
+        self.add_to_book_as(order, admission)?;
+        ORDER_COUNT.inc();
+        Ok(())
+    }
+
+    /// Wie `insert_order`, aber ohne `ORDER_COUNT` (zählt der Batch erst nach Abschluss).
+    fn add_to_book(&mut self, order: OrderData) -> Result<(), DexError> {
+        self.add_to_book_as(order, Admission::Local)
+    }
+
+    fn add_to_book_as(&mut self, mut order: OrderData, admission: Admission) -> Result<(), DexError> {
+        if !order.quantity.is_finite() || order.quantity <= 0.0 {
+            return Err(DexError::Other(format!("Order quantity {} => invalid", order.quantity)));
+        }
+        // Signatur vor den Caps: eine gefälschte Order darf keine fremde verdrängen
+        if !order.verify_signature() {
+            return Err(DexError::InvalidSignature(format!("order {}", order.id)));
+        }
+        self.stamp_admission(&mut order, admission);
+        self.enforce_book_caps(&order)?;
+        let delta_order = self.market_data.as_ref().map(|_| order.clone());
+        self.order_book.add_order(order)?;
+        if let Some(o) = delta_order {
+            self.publish_book_delta(&o, o.remaining());
+        }
+        Ok(())
+    }
+
+    /// Vergibt die Annahme-Position einer lokal angenommenen Order: ein Tick
+    /// der Annahme-Uhr, der auch als `hlc` mit der Order repliziert wird.
+    /// Mitgeschickte `sequence`/`hlc` sind nicht signiert und werden verworfen.
+    pub fn assign_sequence(&mut self, order: &mut OrderData) {
+        self.stamp_admission(order, Admission::Local);
+    }
+
+    /// Sequencer-Nummern kommen nur aus geprüften Batches, replizierte Orders
+    /// behalten den HLC ihres Ursprungs-Nodes (ziehen die eigene Uhr nach,
+    /// damit spätere lokale Orders dahinter liegen), alles andere wird lokal
+    /// gestempelt. Ohne Uhr bleiben lokale Orders unsequenziert.
+    fn stamp_admission(&mut self, order: &mut OrderData, admission: Admission) {
+        let origin = match admission {
+            Admission::Sequenced { epoch, seq } => {
+                order.sequence = Some(SequenceNo::Sequencer { epoch, seq });
+                return;
+            }
+            Admission::Replicated => order.hlc,
+            Admission::Local => None,
+        };
+        match (origin, self.admission_clock.as_mut()) {
+            (Some(ts), clock) => {
+                if let Some(clock) = clock {
+                    clock.update(ts);
+                }
+                order.sequence = Some(SequenceNo::Hlc(ts));
+            }
+            (None, Some(clock)) => {
+                let ts = clock.tick();
+                order.hlc = Some(ts);
+                order.sequence = Some(SequenceNo::Hlc(ts));
+            }
+            (None, None) => {
+                order.hlc = None;
+                order.sequence = None;
+            }
+        }
+    }
+
+    /// Prüft die Obergrenzen für `incoming`, erst je User, dann je Markt.
+    fn enforce_book_caps(&mut self, incoming: &OrderData) -> Result<(), DexError> {
+        let caps = self.book_caps.clone();
+        if caps.max_orders_per_user > 0
+            && self.order_book.count_for_user(&incoming.user_id) >= caps.max_orders_per_user
+        {
+            let scope = format!("user {}", incoming.user_id);
+            self.make_room(incoming, Some(&incoming.user_id), &scope, caps.max_orders_per_user)?;
+        }
+        if caps.max_orders_per_market > 0 && self.order_book.len() >= caps.max_orders_per_market {
+            self.make_room(incoming, None, "market", caps.max_orders_per_market)?;
+        }
+        Ok(())
+    }
+
+    /// Verdrängt je nach Policy die am wenigsten kompetitive Order (nur unter
+    /// denen von `owner`, falls gesetzt) oder lehnt `incoming` ab.
+    fn make_room(&mut self, incoming: &OrderData, owner: Option<&str>, scope: &str, limit: usize) -> Result<(), DexError> {
+        let exceeded = DexError::BookCapExceeded { market: self.market.clone(), scope: scope.to_string(), limit };
+        if self.book_caps.policy == BookCapPolicy::Reject {
+            return Err(exceeded);
+        }
+        let mid = match self.order_book.mid_price() {
+            Some(mid) => mid,
+            None => return Err(exceeded),
+        };
+        let victim = match self.order_book.farthest_from(mid, owner) {
+            Some((id, dist)) if dist > distance_from_mid(incoming, mid) => id,
+            _ => return Err(exceeded),
+        };
+        if let Some(undo) = self.batch_undo.as_mut() {
+            if undo.book_before_eviction.is_none() {
+                undo.book_before_eviction = Some(self.order_book.clone());
+            }
+        }
+        for o in self.order_book.remove_orders(&[victim]) {
+            match self.batch_undo.as_mut() {
+                Some(undo) => undo.evicted.push((o, scope.to_string())),
+                None => self.finish_eviction(&o, scope),
+            }
+        }
+        Ok(())
+    }
+
+    /// Folgen einer Verdrängung: Time-Limited-Eintrag, Balance-Sperre und Events.
+    fn finish_eviction(&self, o: &OrderData, scope: &str) {
+        warn!("Book-Cap ({}) => Order {} von {} verdrängt", scope, o.id, o.user_id);
+        if let Some(manager) = &self.time_limited_manager {
+            let _ = manager.cancel(&o.id);
+        }
+        if let Some(hook) = &self.eviction_hook {
+            hook(o);
+        }
+        self.publish_cancellation(o, "evicted");
+        self.publish_book_delta(o, -o.remaining());
+    }
+
+    /// OrderCancelled mit Eigentümer, damit dessen Client die Stornierung sieht.
+    fn publish_cancellation(&self, order: &OrderData, reason: &str) {
+        if let Some(hub) = &self.market_data {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            hub.publish(MarketDataEvent::OrderCancelled(OrderCancelledEvent {
+                market: self.market.clone(),
+                order_id: order.id.clone(),
+                user_id: Some(order.user_id.clone()),
+                reason: reason.into(),
+                remaining: order.remaining(),
+                timestamp: now,
+            }));
+        }
+    }
+
+    /// Änderung an einem Preislevel; Market-Orders liegen auf keinem Level => kein Delta.
+    fn publish_book_delta(&self, order: &OrderData, quantity_change: f64) {
+        if let (Some(hub), Some(price)) = (&self.market_data, resting_price(order)) {
+            let side = match order.side {
+                OrderSide::Buy => "buy",
+                OrderSide::Sell => "sell",
+            };
+            hub.publish(MarketDataEvent::BookDelta(BookDelta {
+                market: self.market.clone(),
+                order_id: order.id.clone(),
+                side: side.to_string(),
+                price: Some(price),
+                quantity_change,
+            }));
+        }
+    }
+
+    /// Order mit Ablaufzeit: wird im TimeLimitedOrderManager registriert und
+    /// regulär ins Buch gelegt. Nach Ablauf (und aufgebrauchten Re-Listings)
+    /// entfernt der nächste Matching-Durchlauf sie wieder.
+    pub fn place_time_limited_order(&mut self, order: OrderData, duration_secs: u64, max_relist: u32) -> Result<(), DexError> {
+        self.place_scheduled_order(order, duration_secs, max_relist, None, None)
+    }
+
+    /// Wie `place_time_limited_order`, zusätzlich mit Good-After-Time und
+    /// Good-Till-Date. Liegt `good_after` in der Zukunft, wird die Order
+    /// zurückgehalten und erst im ersten Matching-Durchlauf danach eingestellt.
+    pub fn place_scheduled_order(
+        &mut self,
+        order: OrderData,
+        duration_secs: u64,
+        max_relist: u32,
+        good_after: Option<u64>,
+        good_till_date: Option<u64>,
+    ) -> Result<(), DexError> {
+        let manager = self
+            .time_limited_manager
+            .clone()
+            .ok_or_else(|| DexError::Other("No TimeLimitedOrderManager configured".into()))?;
+        let price = match order.order_type {
+            OrderType::Limit(px) => px,
+            _ => return Err(DexError::Other("Time-limited orders must be limit orders".into())),
+        };
+        let side = match order.side {
+            OrderSide::Buy => TimeLimitedOrderSide::Buy,
+            OrderSide::Sell => TimeLimitedOrderSide::Sell,
+        };
+        let tl = TimeLimitedOrder::new_at(&order.id, &order.user_id, side, order.quantity, price, duration_secs, max_relist, manager.now())
+            .and_then(|o| o.with_schedule(good_after, good_till_date))
+            .map_err(|e| DexError::Other(format!("Time-limited order rejected: {}", e)))?;
+        if tl.status == TimeLimitedStatus::Scheduled {
+            // Dieselben Checks wie `place_order`; der Zeitstempel zählt bei
+            // Eingang, die Größe wird bei Aktivierung erneut geprüft
+            self.ensure_direct_placement()?;
+            if !order.verify_signature() {
+                return Err(DexError::InvalidSignature(format!("order {}", order.id)));
+            }
+            self.check_order_timestamp(&order)?;
+            self.check_order_size(&order)?;
+            return manager
+                .add_order(tl, Some(order))
+                .map_err(|e| DexError::Other(format!("Time-limited order rejected: {}", e)));
+        }
+        manager
+            .add_order(tl, None)
+            .map_err(|e| DexError::Other(format!("Time-limited order rejected: {}", e)))?;
+        if let Err(e) = self.place_order(order.clone()) {
+            let _ = manager.cancel(&order.id);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Fällige Scheduled-Orders ins Buch legen, abgelaufene Time-Limited
+    /// Orders herausnehmen und als OrderCancelled + BookDelta publizieren.
+    /// Scheitert eine Aktivierung, wird die Order storniert und der Eigentümer
+    /// per OrderCancelled ("activation_failed: ...") benachrichtigt.
+    fn sweep_time_limited_orders(&mut self) -> Result<(), DexError> {
+        let manager = match &self.time_limited_manager {
+            Some(m) => m.clone(),
+            None => return Ok(()),
+        };
+        for order in manager.activate_due_orders() {
+            let activated = self.check_order_size(&order).and_then(|_| self.insert_order(order.clone()));
+            if let Err(e) = activated {
+                warn!("Scheduled Order {} von {} konnte nicht aktiviert werden: {:?}", order.id, order.user_id, e);
+                let _ = manager.cancel(&order.id);
+                self.publish_cancellation(&order, &format!("activation_failed: {}", e));
+            }
+        }
+        let removed = manager.check_and_handle_expired(&mut self.order_book)?;
+        for o in &removed {
+            self.publish_cancellation(o, "expired");
+            self.publish_book_delta(o, -o.remaining());
+        }
+        Ok(())
+    }
+
+    /// Order platzieren (nun mit Checks):
+    /// - Wir prüfen quantity
+    /// - Wir übergeben an LimitOrderBook => signatur => Fehler, wenn invalid
+    #[instrument(name = "place_order", skip(self, order), fields(order_id = %order.id, user_id = %order.user_id))]
+    pub fn place_order(&mut self, order: OrderData) -> Result<(), DexError> {
+        self.ensure_direct_placement()?;
+        if !order.quantity.is_finite() {
+            return Err(DexError::Other(format!("Order quantity {} => invalid", order.quantity)));
+        }
+        self.check_order_timestamp(&order)?;
+        self.check_order_size(&order)?;
+        self.insert_order(order)
+    }
+
+    /// Von einem Peer replizierte Order: dieselben Checks wie `place_order`,
+    /// Zeit-Priorität aber nach dem HLC des Ursprungs-Nodes (`hlc`, aus dem
+    /// CRDT), damit alle Replikate gleich sortieren.
+    #[instrument(name = "place_replicated_order", skip(self, order), fields(order_id = %order.id, user_id = %order.user_id))]
+    pub fn place_replicated_order(&mut self, order: OrderData) -> Result<(), DexError> {
+        self.ensure_direct_placement()?;
+        self.check_order_timestamp(&order)?;
+        self.check_order_size(&order)?;
+        self.insert_order_as(order, Admission::Replicated)
+    }
+
+    /// Mehrere Orders in einem Aufruf (z.B. komplette Quote-Leiter).
+    /// Erst werden alle geprüft (Menge, Signatur, Zeitstempel, doppelte IDs),
+    /// dann eingestellt. Mit `atomic` gilt alles oder nichts: scheitert eine
+    /// Order, landet keine im Buch. Ohne `atomic` wird die gültige Teilmenge
+    /// übernommen. `Err` nur, wenn der Markt gar keine Orders annimmt.
+    pub fn place_orders_batch(&mut self, orders: Vec<OrderData>, atomic: bool) -> Result<Vec<PlaceResult>, DexError> {
+        self.ensure_direct_placement()?;
+
+        let mut seen = BTreeSet::new();
+        let mut results = Vec::with_capacity(orders.len());
+        for (i, order) in orders.iter().enumerate() {
+            let check = if !seen.insert(order.id.as_str())
+                || self.order_book.arrivals.contains_key(&order.id)
+            {
+                Err(DexError::Other(format!("Order-ID {} doppelt", order.id)))
+            } else {
+                self.validate_new_order(order)
+            };
+            results.push(match check {
+                Ok(()) => PlaceResult::accepted(i, &order.id),
+                Err(e) => PlaceResult::rejected(i, Some(&order.id), &e),
+            });
+        }
+        if atomic && !results.iter().all(PlaceResult::is_accepted) {
+            warn!("Batch mit {} Orders abgelehnt (atomar)", orders.len());
+            return Ok(roll_back_results(results));
+        }
+
+        if atomic {
+            self.batch_undo = Some(BatchUndo::default());
+        }
+        let mut inserted: Vec<String> = Vec::new();
+        for (i, order) in orders.into_iter().enumerate() {
+            if !results[i].is_accepted() {
+                continue;
+            }
+            let id = order.id.clone();
+            if let Err(e) = self.add_to_book(order) {
+                results[i] = PlaceResult::rejected(i, Some(&id), &e);
+                if atomic {
+                    self.roll_back_batch(&inserted);
+                    return Ok(roll_back_results(results));
+                }
+                continue;
+            }
+            inserted.push(id);
+        }
+        // Batch steht => zurückgehaltene Verdrängungen wirksam machen
+        if let Some(undo) = self.batch_undo.take() {
+            for (o, scope) in &undo.evicted {
+                self.finish_eviction(o, scope);
+            }
+        }
+        ORDER_COUNT.inc_by(inserted.len() as u64);
+        Ok(results)
+    }
+
+    fn validate_new_order(&self, order: &OrderData) -> Result<(), DexError> {
+        self.check_order_timestamp(order)?;
+        self.validate_order_fields(order)
+    }
+
+    /// Menge, Signatur und Größenlimits – alles außer dem Zeitstempel.
+    fn validate_order_fields(&self, order: &OrderData) -> Result<(), DexError> {
+        if !order.quantity.is_finite() || order.quantity <= 0.0 {
+            return Err(DexError::Other(format!("Order quantity {} => invalid", order.quantity)));
+        }
+        if !order.verify_signature() {
+            return Err(DexError::InvalidSignature(format!("order {}", order.id)));
+        }
+        self.check_order_size(order)
+    }
+
+    /// Mindestmenge und Höchst-Gegenwert laut `order_limits`. Market-Orders
+    /// werden zum besten Preis der Gegenseite bewertet; ohne Gegenseite greift
+    /// nur die Mindestmenge.
+    fn check_order_size(&self, order: &OrderData) -> Result<(), DexError> {
+        let price = match (&order.order_type, &order.side) {
+            (OrderType::Market, OrderSide::Buy) => self.order_book.best_ask(),
+            (OrderType::Market, OrderSide::Sell) => self.order_book.best_bid(),
+            _ => Some(order_price(order, matches!(order.side, OrderSide::Buy))),
+        };
+        self.order_limits.for_market(&self.market).check(&self.market, order.quantity, price)
+    }
+
+    /// Nimmt bereits eingestellte Orders eines atomaren Batches wieder heraus
+    /// und stellt dabei verdrängte Orders mit ihrer alten Priorität wieder her.
+    fn roll_back_batch(&mut self, ids: &[String]) {
+        let undo = self.batch_undo.take().unwrap_or_default();
+        for o in self.order_book.remove_orders(ids) {
+            self.publish_book_delta(&o, -o.remaining());
+        }
+        if let Some(mut book) = undo.book_before_eviction {
+            // Der Stand kann schon frühere Orders dieses Batches enthalten
+            book.remove_orders(ids);
+            self.order_book = book;
+        }
+    }
+
+    fn ensure_direct_placement(&self) -> Result<(), DexError> {
+        self.ensure_not_halted()?;
+        if self.sequencing.is_some() {
+            return Err(DexError::Other("Sequencing enabled => order must be submitted via the sequencer".into()));
+        }
+        if self.commit_reveal.is_some() {
+            return Err(DexError::Other("Commit-reveal enabled => order must be committed first".into()));
+        }
+        Ok(())
+    }
+
+    /// Vereinte Variante von match_orders():
+    /// - Ruft ggf. Security Audit über global_sec auf
+    /// - Führt das eigentliche Matching (bisheriger Code) durch
+    /// - Liefert Liste an Trades zurück
+    pub fn match_orders(&mut self) -> Result<Vec<(String, String, f64, f64)>, DexError> {
+        Ok(self.match_fills()?.iter().map(TradeFill::as_tuple).collect())
+    }
+
+    /// match_orders() mit Käufer/Verkäufer je Fill (für das Settlement).
+    #[instrument(name = "match_orders", skip(self), fields(trades = tracing::field::Empty))]
+    pub fn match_fills(&mut self) -> Result<Vec<TradeFill>, DexError> {
+        self.ensure_not_halted()?;
+
+        // Abgelaufene Orders dürfen nicht mehr matchen
+        self.sweep_time_limited_orders()?;
+
+        // Sequenzierte Orders strikt in (epoch, seq)-Reihenfolge ins Buch.
+        // Die stabile Sortierung im Buch erhält diese Reihenfolge bei Preisgleichheit.
+        let ready = self.sequencing.as_mut().map(|s| s.drain_ready()).unwrap_or_default();
+        for ((epoch, seq), order) in ready {
+            let id = order.id.clone();
+            // Dieselben Checks wie bei direkter Platzierung; der Sequencer legt
+            // nur die Reihenfolge fest, nicht die Gültigkeit
+            let checked = self
+                .check_order_timestamp(&order)
+                .and_then(|_| self.check_order_size(&order))
+                .and_then(|_| self.insert_order_as(order, Admission::Sequenced { epoch, seq }));
+            if let Err(e) = checked {
+                warn!("Sequenzierte Order {} (seq={}) abgelehnt: {:?}", id, seq, e);
+            }
+        }
+
+        // Falls global_sec vorhanden => z.B. Rate Limit / Audit
+        if let Some(ref sec_arc) = self.global_sec {
+            let sec = sec_arc.lock().unwrap();
+            sec.audit_event("MatchingEngine => start match_orders");
+        }
+
+        // Circuit-Breaker ausgelöst => Orders bleiben im Buch, kein Fill
+        // zum Ausreißer-Preis bis zum Ende des Cooldowns
+        if self.is_circuit_broken() {
+            debug!("Circuit-Breaker {} aktiv => Matching pausiert", self.market);
+            return Ok(Vec::new());
+        }
+
+        // Preislevel der Orders vor dem Matching: Fill-Deltas verringern das
+        // Level der jeweiligen Order, nicht den (Maker-)Ausführungspreis
+        let levels: HashMap<String, Option<f64>> = match &self.market_data {
+            Some(_) => self
+                .order_book
+                .buy_orders
+                .iter()
+                .chain(self.order_book.sell_orders.iter())
+                .map(|lo| (lo.order.id.clone(), resting_price(&lo.order)))
+                .collect(),
+            None => HashMap::new(),
+        };
+
+        // Dann reguläre Matching-Logik
+        let timer = MATCH_LATENCY.start_timer();
+        let trades = if self.invariant_checks {
+            // Erst auf der Kopie prüfen, dann übernehmen => Buch und Trades bleiben konsistent
+            let mut candidate = self.order_book.clone();
+            let trades = candidate.match_fills();
+            timer.observe_duration();
+            self.check_invariants_after_match(&candidate)?;
+            self.order_book = candidate;
+            trades
+        } else {
+            let trades = self.order_book.match_fills();
+            timer.observe_duration();
+            trades
+        };
+        TRADES_MATCHED.inc_by(trades.len() as u64);
+        tracing::Span::current().record("trades", trades.len());
+        // Fills an die Time-Limited-Verwaltung => gefüllte Orders fallen dort heraus
+        if let Some(manager) = &self.time_limited_manager {
+            for fill in &trades {
+                manager.record_fill(&fill.buy_order_id, fill.quantity);
+                manager.record_fill(&fill.sell_order_id, fill.quantity);
+            }
+        }
+        if let Some(hub) = &self.market_data {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            for fill in &trades {
+                let (buy_id, sell_id, qty, price) = (&fill.buy_order_id, &fill.sell_order_id, &fill.quantity, &fill.price);
+                hub.publish(MarketDataEvent::Trade(TradeEvent {
+                    market: self.market.clone(),
+                    buy_order_id: buy_id.clone(),
+                    sell_order_id: sell_id.clone(),
+                    quantity: *qty,
+                    price: *price,
+                    timestamp: now,
+                }));
+                for (order_id, side) in [(buy_id, "buy"), (sell_id, "sell")] {
+                    // Market-Orders (None) standen auf keinem Level
+                    let Some(level) = levels.get(order_id).copied().flatten() else { continue };
+                    hub.publish(MarketDataEvent::BookDelta(BookDelta {
+                        market: self.market.clone(),
+                        order_id: order_id.clone(),
+                        side: side.to_string(),
+                        price: Some(level),
+                        quantity_change: -*qty,
+                    }));
+                }
+            }
+        }
+        Ok(trades)
+    }
+
+    /// Prozessiert die Trades => Security-Check, Settlement, Fees, Audit-Log
+    ///
+    /// Jeder Trade läuft in einem eigenen `trade`-Span mit `trade_id` und den
+    /// beiden Order-IDs. Das Settlement erfolgt danach gesammelt und genettet
+    /// über alle Trades des Laufs (`settle_batch`). Fees landen erst im
+    /// `FeeLedger`, wenn das Settlement des Trades gelungen ist.
+    #[instrument(name = "process_trades", skip(self))]
+    pub fn process_trades(&mut self) -> Result<(), DexError> {
+        // Ungültiger Markt => abbrechen, bevor Orders gematcht werden
+        let MarketPair { base: base_asset, quote: quote_asset } = MarketPair::parse(&self.market)?;
+        // Abgelaufene Time-Limited Orders entfernt match_orders() selbst
+        let trades = self.match_fills()?;
+        let mut pending = Vec::with_capacity(trades.len());
+        let mut trade_keys = Vec::with_capacity(trades.len());
+        let mut bookings = Vec::with_capacity(trades.len());
+        for fill in trades {
+            let TradeFill { buy_order_id: buy_id, sell_order_id: sell_id, buyer, seller, quantity: qty, price, maker } = fill;
+            let trade_id = format!("{}:{}", buy_id, sell_id);
+            let trade_span = info_span!(
+                "trade",
+                trade_id = %trade_id,
+                buy_order_id = %buy_id,
+                sell_order_id = %sell_id
+            );
+            let _trade_guard = trade_span.enter();
+            let trade_info = format!("Buy:{}; Sell:{}; Qty:{}; Price:{}", buy_id, sell_id, qty, price);
+
+            debug!("Validiere Trade mit AdvancedSecurityValidator: {}", trade_info);
+            if let Err(e) = self.advanced_security.validate_trade(&trade_info) {
+                log_error(e);
+                return Err(DexError::Other("Trade-Sicherheitsvalidierung fehlgeschlagen".into()));
+            }
+
+            // Taker zahlt die volle Fee, der Maker-Rebate kommt daraus,
+            // nur der Rest geht in den Pool
+            let fees = self.fee_schedule.for_market(&self.market).split(qty * price);
+            let fee_output = calculate_fee(fees.pool_fee, &FeeDistribution::new());
+            debug!("Trade => buy={}, sell={}, px={}, qty={}, maker={:?}, fees={:?}",
+                   buy_id, sell_id, price, qty, maker, fees);
+            let (maker_user, taker_user) = match maker {
+                OrderSide::Buy => (&buyer, &seller),
+                OrderSide::Sell => (&seller, &buyer),
+            };
+            bookings.push((trade_id.clone(), FeeBooking {
+                quote_asset: quote_asset.clone(),
+                taker: taker_user.clone(),
+                maker: maker_user.clone(),
+                fees,
+                pool: fee_output,
+            }));
+
+            if let Some(anomaly) = &self.anomaly_engine {
+                anomaly.lock_recover().observe(ActivityEvent::Trade {
+                    buyer: buyer.clone(),
+                    seller: seller.clone(),
+                    market: self.market.clone(),
+                    quantity: qty,
+                    price,
+                    timestamp: now_secs(),
+                });
+            }
+
+            for (suffix, leg) in settlement_legs(&buyer, &seller, maker, (&base_asset, &quote_asset), qty, qty * price, &fees) {
+                pending.push(leg);
+                trade_keys.push(format!("{}{}", trade_id, suffix));
+            }
+
+            self.audit(&format!(
+                "Trade gematcht: Buy:{}; Sell:{}; Qty:{}; Price:{}",
+                buy_id, sell_id, qty, price
+            ));
+        }
+
+        // Mit Queue: Trades dauerhaft einreihen (Idempotency-Key = trade_id),
+        // dann alle fälligen Jobs versuchen. Scheitert ein Settlement, bleibt
+        // der Match bestehen und der Job wird mit Backoff wiederholt.
+        // Scheitert schon das Einreihen, bleiben die Trades (samt denen früherer
+        // gescheiterter Läufe) im Speicher und werden im nächsten Lauf erneut eingereiht.
+        if let Some(queue) = &self.settlement_queue {
+            // Haupt-Leg-Key == trade_id => Buchung beim Abwickeln des Jobs
+            self.pending_fees.extend(bookings);
+            let mut keyed = std::mem::take(&mut self.unqueued_trades);
+            keyed.extend(
+                std::mem::take(&mut trade_keys)
+                    .into_iter()
+                    .zip(std::mem::take(&mut pending))
+                    .map(|(key, trade)| KeyedTrade { key, trade }),
+            );
+            if !keyed.is_empty() {
+                if let Err(e) = queue.enqueue(keyed.clone(), now_secs()) {
+                    warn!("{} Trades nicht eingereiht, erneuter Versuch im nächsten Lauf: {}", keyed.len(), e);
+                    self.unqueued_trades = keyed;
+                    return Err(e);
+                }
+            }
+        }
+        if self.settlement_queue.is_some() {
+            let report = self.retry_pending_settlements()?;
+            if report.failed_jobs > 0 {
+                warn!("{} Settlement-Jobs pending (Retry mit Backoff)", report.failed_jobs);
+            }
+            return Ok(());
+        }
+
+        // Alle Trades dieses Laufs bilden ein Settlement-Fenster und werden
+        // je Gegenpartei-Paar netto abgewickelt.
+        if !pending.is_empty() {
+            let transfers = match self.settlement.settle_batch(&pending) {
+                Ok(n) => n,
+                Err(e) => {
+                    let reason = e.to_string();
+                    log_error(e);
+                    return Err(DexError::SettlementFailed(reason));
+                }
+            };
+            self.book_fees(&bookings.into_iter().map(|(_, b)| b).collect::<Vec<_>>());
+            self.audit(&format!(
+                "Settlement-Fenster finalisiert: {} Trades => {} Transfers",
+                pending.len(), transfers
+            ));
+        }
+        Ok(())
+    }
+
+    /// Gematchte Trades, die noch nicht in der Settlement-Queue liegen.
+    pub fn unqueued_trades(&self) -> &[KeyedTrade] {
+        &self.unqueued_trades
+    }
+
+    /// Explizit abgelaufene Time-Limited Orders prüfen (optional)
+    pub fn check_expired_time_limited_orders(&mut self) -> Result<(), DexError> {
+        self.sweep_time_limited_orders()
+    }
+
+    /// Ring-Sign-Demo
+    pub fn ring_sign_demo(&self, data: &[u8]) -> Result<Vec<u8>, DexError> {
+        if let Some(ref sec_arc) = self.global_sec {
+            let sec = sec_arc.lock().unwrap();
+            let sig = sec.ring_sign_data(data)?;
+            return Ok(sig);
+        }
+        Err(DexError::Other("No GlobalSecuritySystem set".into()))
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// ─────────────────────────────────────────────────────────
+// MarketShards: eine Engine pro Markt
+// ─────────────────────────────────────────────────────────
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct MarketPair {
+    pub base: String,
+    pub quote: String,
+}
+
+impl MarketPair {
+    pub fn new(base: &str, quote: &str) -> Self {
+        Self { base: base.to_ascii_uppercase(), quote: quote.to_ascii_uppercase() }
+    }
+
+    /// "BTC/USDT" => MarketPair
+    pub fn parse(market: &str) -> Result<Self, DexError> {
+        match market.split_once('/') {
+            Some((b, q)) if !b.is_empty() && !q.is_empty() => Ok(Self::new(b, q)),
+            _ => Err(DexError::Other(format!("invalid market `{}`, expected BASE/QUOTE", market))),
+        }
+    }
+}
+
+impl std::fmt::Display for MarketPair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.base, self.quote)
+    }
+}
 
-        let trades = self.match_orders()?;
-        for (buy_id, sell_id, qty, price) in trades {
-            let trade_info = format!("Buy:{}; Sell:{}; Qty:{}; Price:{}", buy_id, sell_id, qty, price);
+/// Hält je Markt eine eigene MatchingEngine hinter einem eigenen Lock, so dass
+/// ein ausgelasteter Markt das Matching anderer Paare nicht blockiert.
+/// Im Modus `Serial` läuft jede Runde zusätzlich unter einem globalen Lock
+/// (bisheriges Verhalten, z.B. zur Fehlersuche).
+pub struct MarketShards {
+    engines: std::collections::BTreeMap<MarketPair, Arc<Mutex<MatchingEngine>>>,
+    mode: MatchingConcurrency,
+    serial_lock: Arc<Mutex<()>>,
+    pub fees: Arc<Mutex<FeeLedger>>,
+}
 
-            debug!("Validiere Trade mit AdvancedSecurityValidator: {}", trade_info);
-            if let Err(e) = self.advanced_security.validate_trade(&trade_info) {
-                log_error(e);
-                return Err(DexError::Other("Trade-Sicherheitsvalidierung fehlgeschlagen".into()));
-            }
+impl MarketShards {
+    pub fn new(mode: MatchingConcurrency) -> Self {
+        Self {
+            engines: std::collections::BTreeMap::new(),
+            mode,
+            serial_lock: Arc::new(Mutex::new(())),
+            fees: Arc::new(Mutex::new(FeeLedger::default())),
+        }
+    }
 
-            let fee_total = qty * price * 0.001;
-            let fee_output = calculate_fee(fee_total, &FeeDistribution::new());
-            debug!("Trade => buy={}, sell={}, px={}, qty={}, fees={:?}",
-                   buy_id, sell_id, price, qty, fee_output);
+    /// Registriert `engine` für `pair`; Marktname und Fee-Ledger werden gesetzt.
+    pub fn add_market(&mut self, pair: MarketPair, mut engine: MatchingEngine) -> Arc<Mutex<MatchingEngine>> {
+        engine.market = pair.to_string();
+        engine.fee_ledger = Some(self.fees.clone());
+        let arc = Arc::new(Mutex::new(engine));
+        self.engines.insert(pair, arc.clone());
+        arc
+    }
 
-            if let Err(e) = self.settlement.finalize_trade(
-                "buyer_id",
-                "seller_id",
-                "BTC",
-                "USDT",
-                qty,
-                qty * price
-            ) {
-                log_error(e);
-                return Err(DexError::Other("Settlement-Validierung fehlgeschlagen".into()));
-            }
+    pub fn engine(&self, pair: &MarketPair) -> Option<Arc<Mutex<MatchingEngine>>> {
+        self.engines.get(pair).cloned()
+    }
 
-            write_audit_log(&format!(
-                "Trade finalisiert: Buy:{}; Sell:{}; Qty:{}; Price:{}",
-                buy_id, sell_id, qty, price
-            ));
+    pub fn markets(&self) -> Vec<MarketPair> {
+        self.engines.keys().cloned().collect()
+    }
+
+    fn engine_or_err(&self, pair: &MarketPair) -> Result<&Arc<Mutex<MatchingEngine>>, DexError> {
+        self.engines.get(pair).ok_or_else(|| DexError::Other(format!("unknown market {}", pair)))
+    }
+
+    /// Sperrt nur die Engine von `pair`.
+    pub fn place_order(&self, pair: &MarketPair, order: OrderData) -> Result<(), DexError> {
+        let engine = self.engine_or_err(pair)?;
+        let _serial = self.serial_guard();
+        engine.lock().unwrap().place_order(order)
+    }
+
+    pub fn process_market(&self, pair: &MarketPair) -> Result<(), DexError> {
+        let engine = self.engine_or_err(pair)?;
+        let _serial = self.serial_guard();
+        engine.lock().unwrap().process_trades()
+    }
+
+    fn serial_guard(&self) -> Option<std::sync::MutexGuard<'_, ()>> {
+        match self.mode {
+            MatchingConcurrency::Serial => Some(self.serial_lock.lock().unwrap()),
+            MatchingConcurrency::PerMarket => None,
+        }
+    }
+
+    /// Eine Matching-Runde über alle Märkte; `PerMarket` => ein Thread je Markt.
+    pub fn process_all(&self) -> Vec<(MarketPair, Result<(), DexError>)> {
+        match self.mode {
+            MatchingConcurrency::Serial => self
+                .engines
+                .keys()
+                .map(|pair| (pair.clone(), self.process_market(pair)))
+                .collect(),
+            MatchingConcurrency::PerMarket => std::thread::scope(|s| {
+                let handles: Vec<_> = self
+                    .engines
+                    .iter()
+                    .map(|(pair, engine)| (pair.clone(), s.spawn(move || engine.lock().unwrap().process_trades())))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|(pair, h)| {
+                        let res = h
+                            .join()
+                            .unwrap_or_else(|_| Err(DexError::Other(format!("matching thread for {} panicked", pair))));
+                        (pair, res)
+                    })
+                    .collect()
+            }),
+        }
+    }
+
+    pub fn persist_all(&self, db: &DexDB) -> Result<(), DexError> {
+        for engine in self.engines.values() {
+            engine.lock().unwrap().persist_book(db)?;
         }
         Ok(())
     }
 
-    /// Explizit abgelaufene Time-Limited Orders prüfen (optional)
-    pub fn check_expired_time_limited_orders(&mut self) -> Result<(), DexError> {
-        if let Some(ref mut manager) = self.time_limited_manager {
-            manager.check_and_handle_expired(&mut self.order_book)?;
+    /// Startet die Matching-Loops: `PerMarket` => ein Task je Markt,
+    /// `Serial` => ein Task für alle. Beim Shutdown wird das Buch gesichert.
+    pub fn spawn_matching_loops(
+        &self,
+        shutdown: &mut ShutdownCoordinator,
+        interval: std::time::Duration,
+        db: Arc<Mutex<DexDB>>,
+    ) {
+        let groups: Vec<(String, Vec<Arc<Mutex<MatchingEngine>>>)> = match self.mode {
+            MatchingConcurrency::PerMarket => self
+                .engines
+                .iter()
+                .map(|(pair, e)| (format!("matching:{}", pair), vec![e.clone()]))
+                .collect(),
+            MatchingConcurrency::Serial => vec![("matching".to_string(), self.engines.values().cloned().collect())],
+        };
+        for (name, engines) in groups {
+            let db = db.clone();
+            shutdown.spawn(&name, move |token| async move {
+                let mut tick = tokio::time::interval(interval);
+                loop {
+                    tokio::select! {
+                        _ = token.cancelled() => break,
+                        _ = tick.tick() => {
+                            for engine in &engines {
+                                let mut engine = engine.lock().unwrap();
+                                if let Err(e) = engine.process_trades() {
+                                    warn!("MatchingEngine {} => process_trades: {:?}", engine.market, e);
+                                }
+                            }
+                        }
+                    }
+                }
+                for engine in &engines {
+                    let engine = engine.lock().unwrap();
+                    if let Err(e) = engine.persist_book(&db.lock_recover()) {
+                        error!("MatchingEngine {} => Order-Book konnte nicht gesichert werden: {:?}", engine.market, e);
+                    }
+                }
+            });
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────
+// Kill-Switch pro Markt
+// ─────────────────────────────────────────────────────────
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HaltAction {
+    Halt,
+    Resume,
+}
+
+/// Signatur eines Committee-Mitglieds (beides hex) über `halt_signing_bytes`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HaltApproval {
+    pub signer: String,
+    pub signature: String,
+}
+
+/// Domain-Tag der Committee-Signaturen über Halt/Resume.
+pub const MARKET_HALT_DOMAIN: &str = "my_dex/market_halt/v1";
+
+/// Bytes, die für Halt/Resume signiert werden (kanonisches CBOR mit
+/// `MARKET_HALT_DOMAIN`). Die `nonce` ist der aktuelle
+/// Zähler des Marktes in der MarketHaltControl => alte Beschlüsse lassen sich
+/// nicht erneut einspielen, und Beschlüsse für einen Markt verbrauchen nicht
+/// die Nonce eines anderen.
+pub fn halt_signing_bytes(market: &str, action: HaltAction, nonce: u64) -> Result<Vec<u8>, DexError> {
+    #[derive(Serialize)]
+    struct HaltSigningView<'a> {
+        market: &'a str,
+        action: HaltAction,
+        nonce: u64,
+    }
+    canonical::signing_bytes(MARKET_HALT_DOMAIN, &HaltSigningView { market, action, nonce })
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct HaltState {
+    /// Startwert für Märkte ohne eigenen Zähler. Ältere Zustandsdateien hatten
+    /// nur einen Node-weiten Zähler (`nonce`); der bleibt hier erhalten, damit
+    /// unter ihm signierte Beschlüsse nach dem Umstieg nicht wieder gültig werden.
+    #[serde(default, alias = "nonce")]
+    base_nonce: u64,
+    #[serde(default)]
+    nonces: BTreeMap<String, u64>,
+    halted: BTreeSet<String>,
+}
+
+impl HaltState {
+    fn nonce(&self, market: &str) -> u64 {
+        self.nonces.get(market).copied().unwrap_or(self.base_nonce)
+    }
+}
+
+/// Verwaltet angehaltene Märkte. Änderungen nur mit `threshold` gültigen
+/// Signaturen der Committee-Keys (`allowed_node_pubkeys` der Fullnodes).
+/// Der Zustand wird nach jeder Änderung nach `state_path` geschrieben.
+pub struct MarketHaltControl {
+    committee: Vec<PublicKey>,
+    threshold: usize,
+    state_path: Option<PathBuf>,
+    state: HaltState,
+}
+
+impl MarketHaltControl {
+    pub fn new(committee: Vec<PublicKey>, threshold: usize) -> Result<Self, DexError> {
+        if threshold == 0 || threshold > committee.len() {
+            return Err(DexError::Other(format!(
+                "Invalid halt threshold {} for committee of {}", threshold, committee.len()
+            )));
+        }
+        Ok(Self { committee, threshold, state_path: None, state: HaltState::default() })
+    }
+
+    /// Wie `new`, Committee als hex-kodierte Keys (z.B. aus der NodeConfig).
+    pub fn from_hex_keys(keys: &[String], threshold: usize) -> Result<Self, DexError> {
+        let committee = keys
+            .iter()
+            .map(|k| {
+                hex::decode(k)
+                    .ok()
+                    .and_then(|b| PublicKey::from_bytes(&b).ok())
+                    .ok_or_else(|| DexError::Other(format!("Invalid committee key {}", k)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(committee, threshold)
+    }
+
+    /// Lädt einen vorhandenen Zustand von `path`, damit ein Neustart
+    /// angehaltene Märkte respektiert.
+    pub fn with_state_file(mut self, path: impl Into<PathBuf>) -> Result<Self, DexError> {
+        let path = path.into();
+        if path.exists() {
+            let raw = std::fs::read_to_string(&path)
+                .map_err(|e| DexError::Other(format!("Read halt state {:?}: {}", path, e)))?;
+            self.state = serde_json::from_str(&raw)
+                .map_err(|e| DexError::Other(format!("Corrupt halt state {:?}: {}", path, e)))?;
+            if !self.state.halted.is_empty() {
+                warn!("MarketHaltControl => angehaltene Märkte aus {:?}: {:?}", path, self.state.halted);
+            }
+        }
+        self.state_path = Some(path);
+        Ok(self)
+    }
+
+    /// Nonce, über die der nächste Beschluss für `market` signiert werden muss.
+    pub fn nonce(&self, market: &str) -> u64 {
+        self.state.nonce(market)
+    }
+
+    pub fn is_halted(&self, market: &str) -> bool {
+        self.state.halted.contains(market)
+    }
+
+    pub fn halted_markets(&self) -> Vec<String> {
+        self.state.halted.iter().cloned().collect()
+    }
+
+    pub fn apply(&mut self, market: &str, action: HaltAction, approvals: &[HaltApproval]) -> Result<(), DexError> {
+        let nonce = self.state.nonce(market);
+        let msg = halt_signing_bytes(market, action, nonce)?;
+        let mut signers: BTreeSet<[u8; 32]> = BTreeSet::new();
+        for approval in approvals {
+            let pk = match hex::decode(&approval.signer).ok().and_then(|b| PublicKey::from_bytes(&b).ok()) {
+                Some(pk) if self.committee.contains(&pk) => pk,
+                _ => continue,
+            };
+            let sig = match hex::decode(&approval.signature).ok().and_then(|b| Signature::from_bytes(&b).ok()) {
+                Some(sig) => sig,
+                None => continue,
+            };
+            if pk.verify(&msg, &sig).is_ok() {
+                signers.insert(pk.to_bytes());
+            }
         }
+        if signers.len() < self.threshold {
+            return Err(DexError::Other(format!(
+                "Market halt: only {} of {} required committee signatures", signers.len(), self.threshold
+            )));
+        }
+
+        let mut next = self.state.clone();
+        next.nonces.insert(market.to_string(), nonce + 1);
+        match action {
+            HaltAction::Halt => { next.halted.insert(market.to_string()); }
+            HaltAction::Resume => { next.halted.remove(market); }
+        }
+        self.persist(&next)?;
+        self.state = next;
+        warn!("MarketHaltControl => {:?} für Markt {} ({} Signaturen)", action, market, signers.len());
+        write_audit_log(&format!("Market {:?}: {} (nonce={})", action, market, nonce + 1));
         Ok(())
     }
 
-    /// Ring-Sign-Demo
-    pub fn ring_sign_demo(&self, data: &[u8]) -> Result<Vec<u8>, DexError> {
-        if let Some(ref sec_arc) = self.global_sec {
-            let sec = sec_arc.lock().unwrap();
-            let sig = sec.ring_sign_data(data)?;
-            return Ok(sig);
+    fn persist(&self, state: &HaltState) -> Result<(), DexError> {
+        if let Some(path) = &self.state_path {
+            let json = serde_json::to_vec_pretty(state)
+                .map_err(|e| DexError::Other(format!("Serialize halt state: {}", e)))?;
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, json)
+                .and_then(|_| std::fs::rename(&tmp, path))
+                .map_err(|e| DexError::Other(format!("Persist halt state {:?}: {}", path, e)))?;
         }
-        Err(DexError::Other("No GlobalSecuritySystem set".into()))
+        Ok(())
     }
 }
 
@@ -610,6 +2623,8 @@ pub fn demo_matching_engine() -> Result<(), DexError> {
         timestamp: now,
         filled: 0.0,
         status: OrderStatus::Open,
+        hlc: None,
+        sequence: None,
         signature: None,
         public_key: None,
     };
@@ -622,16 +2637,17 @@ pub fn demo_matching_engine() -> Result<(), DexError> {
         timestamp: now,
         filled: 0.0,
         status: OrderStatus::Open,
+        hlc: None,
+        sequence: None,
         signature: None,
         public_key: None,
     };
 
     // (Demo) sign them
-    // In echter Prod => sign with ed25519, etc.
-    order1.signature = Some(vec![1,2,3]);
-    order1.public_key = Some(vec![9,9,9]);
-    order2.signature = Some(vec![1,2,3]);
-    order2.public_key = Some(vec![8,8,8]);
+    let alice_key = Keypair::generate(&mut rand_07::rngs::OsRng);
+    let bob_key = Keypair::generate(&mut rand_07::rngs::OsRng);
+    order1.sign(&alice_key)?;
+    order2.sign(&bob_key)?;
 
     // Insert
     engine.place_order(order1)?;
@@ -658,3 +2674,1390 @@ pub fn demo_matching_engine() -> Result<(), DexError> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap as StdHashMap;
+    use tracing::span::{Attributes, Id};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Registry;
+
+    /// Merkt sich zu jedem neuen Span (Name, Name des Eltern-Spans).
+    #[derive(Clone, Default)]
+    struct SpanRecorder(Arc<Mutex<Vec<(String, Option<String>)>>>);
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SpanRecorder {
+        fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let span = ctx.span(id).expect("span exists");
+            let parent = span.parent().map(|p| p.name().to_string());
+            self.0.lock().unwrap().push((span.name().to_string(), parent));
+        }
+    }
+
+    fn committee_key(seed: u8) -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        ed25519_dalek::Keypair { secret, public }
+    }
+
+    fn approve(kp: &ed25519_dalek::Keypair, market: &str, action: HaltAction, nonce: u64) -> HaltApproval {
+        use ed25519_dalek::Signer;
+        HaltApproval {
+            signer: hex::encode(kp.public.as_bytes()),
+            signature: hex::encode(kp.sign(&halt_signing_bytes(market, action, nonce).unwrap()).to_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_fee_split_sums_to_total_for_random_totals() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(1650);
+        let dists = [
+            FeeDistribution::new(),
+            FeeDistribution { founder_percent: 1.0 / 3.0, dev_percent: 1.0 / 3.0, node_percent: 1.0 / 3.0 },
+            FeeDistribution { founder_percent: 0.7, dev_percent: 0.7, node_percent: 0.0 },
+        ];
+        for _ in 0..10_000 {
+            let total: u64 = match rng.gen_range(0..3) {
+                0 => rng.gen_range(0..100),
+                1 => rng.gen_range(0..1_000_000_000),
+                _ => rng.gen(),
+            };
+            for dist in &dists {
+                let split = split_fee_subunits(total, dist);
+                assert_eq!(split.founder + split.dev + split.node, total, "{:?}", split);
+            }
+        }
+
+        // 7 Untereinheiten 50/30/20 => 3/2/2, Rest geht an den Node-Pool
+        let split = split_fee_subunits(7, &FeeDistribution::new());
+        assert_eq!((split.founder, split.dev, split.node), (3, 2, 2));
+    }
+
+    fn signed_order(id: &str, side: OrderSide, price: f64, qty: f64) -> OrderData {
+        OrderData::new(id, "user", side, OrderType::Limit(price), qty, 0).signed_for_tests()
+    }
+
+    #[test]
+    fn test_order_signature_over_canonical_fields() {
+        let mut o = signed_order("o1", OrderSide::Buy, 100.0, 0.1 + 0.2);
+        assert!(o.verify_signature());
+        // Fills ändern die signierten Felder nicht
+        o.fill(0.1);
+        assert!(o.verify_signature());
+        // gleiche Menge, anders berechnet => gleiche Bytes
+        let mut same = o.clone();
+        same.quantity = "0.30000000000000004".parse().unwrap();
+        assert_eq!(same.signing_bytes().unwrap(), o.signing_bytes().unwrap());
+
+        let mut tampered = o.clone();
+        tampered.quantity = 3.0;
+        assert!(!tampered.verify_signature());
+        let mut unsigned = o;
+        unsigned.signature = Some(vec![1]);
+        assert!(!unsigned.verify_signature());
+    }
+
+    #[test]
+    fn test_invariants_hold_after_regular_match() {
+        let mut book = LimitOrderBook::new();
+        book.add_order(signed_order("b1", OrderSide::Buy, 101.0, 2.0)).unwrap();
+        book.add_order(signed_order("s1", OrderSide::Sell, 100.0, 1.0)).unwrap();
+        book.add_order(signed_order("s2", OrderSide::Sell, 105.0, 1.0)).unwrap();
+        book.match_orders();
+        book.validate_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_corrupted_book_fails_invariants() {
+        // Gekreuzt: Gebot über Ask, ohne dass gematcht wurde
+        let mut crossed = LimitOrderBook::new();
+        crossed.buy_orders.push_back(LimitOrder { order: signed_order("b1", OrderSide::Buy, 110.0, 1.0) });
+        crossed.sell_orders.push_back(LimitOrder { order: signed_order("s1", OrderSide::Sell, 100.0, 1.0) });
+        let err = crossed.validate_invariants().unwrap_err();
+        assert!(err.to_string().contains("crossed"), "{}", err);
+
+        // Negative Restmenge
+        let mut overfilled = LimitOrderBook::new();
+        let mut o = signed_order("b2", OrderSide::Buy, 90.0, 1.0);
+        o.filled = 1.5;
+        o.status = OrderStatus::PartiallyFilled;
+        overfilled.buy_orders.push_back(LimitOrder { order: o });
+        assert!(overfilled.validate_invariants().unwrap_err().to_string().contains("negative"));
+
+        // Filled-Order liegt noch im Buch
+        let mut stale = LimitOrderBook::new();
+        let mut o = signed_order("s2", OrderSide::Sell, 120.0, 1.0);
+        o.fill(1.0);
+        stale.sell_orders.push_back(LimitOrder { order: o });
+        assert!(stale.validate_invariants().unwrap_err().to_string().contains("still resting"));
+    }
+
+    #[test]
+    fn test_engine_reports_invariant_fault() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut engine = MatchingEngine::new().with_invariant_checks(Some(tx));
+        let mut o = signed_order("b1", OrderSide::Buy, 90.0, 1.0);
+        o.filled = 2.0;
+        o.status = OrderStatus::PartiallyFilled;
+        engine.order_book.buy_orders.push_back(LimitOrder { order: o });
+
+        let err = engine.match_orders().unwrap_err();
+        assert!(matches!(err, DexError::InvariantViolation(_)));
+        let fault = rx.try_recv().unwrap();
+        assert_eq!(fault.market, "BTC/USDT");
+        assert!(fault.reason.contains("b1"));
+    }
+
+    #[test]
+    fn test_invariant_violation_leaves_book_unchanged() {
+        let mut engine = MatchingEngine::new().with_invariant_checks(None);
+        engine.order_book.add_order(signed_order("b1", OrderSide::Buy, 101.0, 1.0)).unwrap();
+        engine.order_book.add_order(signed_order("s1", OrderSide::Sell, 100.0, 1.0)).unwrap();
+        // Überfüllte Order abseits des Spreads => Verletzung nach dem Match
+        let mut o = signed_order("b2", OrderSide::Buy, 50.0, 1.0);
+        o.filled = 2.0;
+        o.status = OrderStatus::PartiallyFilled;
+        engine.order_book.buy_orders.push_back(LimitOrder { order: o });
+
+        assert!(engine.match_fills().is_err());
+        // Kein Fill übernommen, der kreuzende Teil liegt noch im Buch
+        assert!(engine.order_book.buy_orders.iter().any(|l| l.order.id == "b1" && l.order.filled == 0.0));
+        assert!(engine.order_book.sell_orders.iter().any(|l| l.order.id == "s1" && l.order.filled == 0.0));
+
+        engine.order_book.buy_orders.retain(|l| l.order.id != "b2");
+        let fills = engine.match_fills().unwrap();
+        assert_eq!(fills.len(), 1);
+        assert!(engine.order_book.sell_orders.is_empty());
+    }
+
+    fn expire_now(manager: &TimeLimitedOrderManager, order_id: &str) {
+        let mut ob = manager.orderbook.lock().unwrap();
+        let o = ob.orders.get_mut(order_id).unwrap();
+        o.start_time -= 3600;
+        o.end_time = o.start_time;
+        o.auto_relist_count = o.max_relist;
+        ob.reindex(order_id);
+    }
+
+    #[test]
+    fn test_expired_order_removed_before_match() {
+        let manager = TimeLimitedOrderManager::new();
+        let hub = MarketDataHub::new();
+        let mut rx = hub.subscribe();
+        let mut engine = MatchingEngine::new()
+            .with_time_limited_manager(manager.clone())
+            .with_market_data("BTC/USDT", hub);
+        engine.place_time_limited_order(signed_order("tl_sell", OrderSide::Sell, 100.0, 1.0), 3600, 1).unwrap();
+        expire_now(&manager, "tl_sell");
+
+        engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        let trades = engine.match_orders().unwrap();
+        assert!(trades.is_empty(), "expired order must not fill: {:?}", trades);
+        assert!(engine.order_book.sell_orders.is_empty());
+        assert_eq!(manager.status("tl_sell"), None);
+        assert_eq!(manager.tracked_orders(), 0);
+
+        let mut cancelled = false;
+        while let Ok(ev) = rx.try_recv() {
+            if let MarketDataEvent::OrderCancelled(c) = ev {
+                assert_eq!(c.order_id, "tl_sell");
+                assert_eq!(c.reason, "expired");
+                cancelled = true;
+            }
+        }
+        assert!(cancelled);
+    }
+
+    #[test]
+    fn test_fill_deltas_use_resting_levels() {
+        let hub = MarketDataHub::new();
+        let mut rx = hub.subscribe();
+        let mut engine = MatchingEngine::new().with_market_data("BTC/USDT", hub);
+        let mut fill_deltas = |engine: &mut MatchingEngine| {
+            assert_eq!(engine.match_orders().unwrap().len(), 1);
+            let mut out = Vec::new();
+            while let Ok(ev) = rx.try_recv() {
+                if let MarketDataEvent::BookDelta(d) = ev {
+                    if d.quantity_change < 0.0 {
+                        out.push((d.order_id, d.price));
+                    }
+                }
+            }
+            out
+        };
+
+        // Taker-Buy @105 gegen Maker-Sell @100: jedes Level verliert an seinem eigenen Preis
+        engine.place_order(signed_order("s1", OrderSide::Sell, 100.0, 1.0)).unwrap();
+        engine.place_order(signed_order("b1", OrderSide::Buy, 105.0, 1.0)).unwrap();
+        assert_eq!(
+            fill_deltas(&mut engine),
+            vec![("b1".to_string(), Some(105.0)), ("s1".to_string(), Some(100.0))]
+        );
+
+        // Market-Order liegt auf keinem Level => nur das Delta des Makers
+        engine.place_order(signed_order("s2", OrderSide::Sell, 101.0, 1.0)).unwrap();
+        let market = OrderData::new("m1", "user", OrderSide::Buy, OrderType::Market, 1.0, 0).signed_for_tests();
+        engine.place_order(market).unwrap();
+        assert_eq!(fill_deltas(&mut engine), vec![("s2".to_string(), Some(101.0))]);
+    }
+
+    #[test]
+    fn test_malformed_market_rejected_before_matching() {
+        let mut engine = MatchingEngine::new().with_market_data("BTCUSDT", MarketDataHub::new());
+        engine.place_order(signed_order("s1", OrderSide::Sell, 100.0, 1.0)).unwrap();
+        engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        let err = engine.process_trades().unwrap_err();
+        assert!(err.to_string().contains("BTCUSDT"), "{}", err);
+        // Nichts gematcht, nichts unter einem falschen Markt gebucht
+        assert!(!engine.order_book.buy_orders.is_empty());
+        assert!(!engine.order_book.sell_orders.is_empty());
+    }
+
+    #[test]
+    fn test_scheduled_order_does_not_match_before_activation() {
+        let manager = TimeLimitedOrderManager::new();
+        let mut engine = MatchingEngine::new().with_time_limited_manager(manager.clone());
+        let good_after = now_secs() + 3600;
+        engine
+            .place_scheduled_order(signed_order("gat_sell", OrderSide::Sell, 100.0, 1.0), 3600, 1, Some(good_after), None)
+            .unwrap();
+        engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        assert!(engine.match_orders().unwrap().is_empty());
+        assert!(engine.order_book.sell_orders.is_empty());
+        assert_eq!(manager.status("gat_sell"), Some(TimeLimitedStatus::Scheduled));
+
+        // Aktivierungszeit erreicht => nächster Durchlauf stellt ein und matcht
+        manager.orderbook.lock().unwrap().orders.get_mut("gat_sell").unwrap().good_after = Some(now_secs());
+        assert_eq!(engine.match_orders().unwrap().len(), 1);
+        assert_eq!(manager.status("gat_sell"), Some(TimeLimitedStatus::Active));
+    }
+
+    #[test]
+    fn test_scheduled_order_validated_like_direct_orders() {
+        let manager = TimeLimitedOrderManager::new();
+        let hub = MarketDataHub::new();
+        let mut rx = hub.subscribe();
+        let limits = OrderLimits {
+            default: OrderSizeLimits { min_quantity: 0.5, max_notional: 0.0 },
+            ..Default::default()
+        };
+        let mut engine = MatchingEngine::new()
+            .with_time_limited_manager(manager.clone())
+            .with_market_data("BTC/USDT", hub)
+            .with_order_limits(limits);
+        let good_after = now_secs() + 3600;
+        // Zu klein => schon bei Eingang abgelehnt
+        let dust = signed_order("gat_dust", OrderSide::Sell, 100.0, 0.1);
+        assert!(engine.place_scheduled_order(dust, 3600, 1, Some(good_after), None).is_err());
+        assert_eq!(manager.status("gat_dust"), None);
+
+        engine
+            .place_scheduled_order(signed_order("gat_sell", OrderSide::Sell, 100.0, 1.0), 3600, 1, Some(good_after), None)
+            .unwrap();
+        // Grenzen bis zur Aktivierung verschärft => Aktivierung scheitert
+        engine.order_limits.default.min_quantity = 2.0;
+        manager.orderbook.lock().unwrap().orders.get_mut("gat_sell").unwrap().good_after = Some(now_secs());
+        engine.match_orders().unwrap();
+        assert!(engine.order_book.sell_orders.is_empty());
+        assert_eq!(manager.status("gat_sell"), Some(TimeLimitedStatus::Cancelled));
+
+        let mut reported = false;
+        while let Ok(ev) = rx.try_recv() {
+            if let MarketDataEvent::OrderCancelled(c) = ev {
+                assert_eq!(c.order_id, "gat_sell");
+                assert_eq!(c.user_id.as_deref(), Some("user"));
+                assert!(c.reason.starts_with("activation_failed"), "{}", c.reason);
+                reported = true;
+            }
+        }
+        assert!(reported);
+    }
+
+    #[test]
+    fn test_equal_price_tie_broken_by_hlc() {
+        let mut clock = crate::utils::hlc::HybridLogicalClock::new("n1");
+        let first = clock.tick_at(1_000);
+        let second = clock.tick_at(1_000);
+        let mut late = signed_order("s_late", OrderSide::Sell, 100.0, 1.0);
+        late.hlc = Some(second);
+        let mut early = signed_order("s_early", OrderSide::Sell, 100.0, 1.0);
+        early.hlc = Some(first);
+
+        // HLC aus dem CRDT zählt nur für replizierte Orders
+        let mut engine = MatchingEngine::new();
+        engine.place_replicated_order(late).unwrap();
+        engine.place_replicated_order(early).unwrap();
+        engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        let trades = engine.match_orders().unwrap();
+        assert_eq!(trades[0].1, "s_early");
+    }
+
+    #[test]
+    fn test_order_timestamp_skew_checked() {
+        let guard = Arc::new(ClockSkewGuard::new(5_000));
+        let mut engine = MatchingEngine::new().with_clock_guard(guard);
+        let now = now_secs();
+        let mut future = signed_order("f1", OrderSide::Buy, 100.0, 1.0);
+        future.timestamp = now + 3600;
+        assert!(matches!(engine.place_order(future), Err(DexError::ClockSkew { .. })));
+        let mut ok = signed_order("ok1", OrderSide::Buy, 100.0, 1.0);
+        ok.timestamp = now + 2;
+        engine.place_order(ok).unwrap();
+        assert_eq!(engine.order_book.buy_orders.len(), 1);
+    }
+
+    #[test]
+    fn test_batch_atomic_rolls_back_and_non_atomic_admits_valid_subset() {
+        let batch = || {
+            let mut forged = signed_order("bad", OrderSide::Buy, 99.0, 1.0);
+            forged.quantity = 5.0; // nach dem Signieren verändert
+            vec![
+                signed_order("q1", OrderSide::Buy, 100.0, 1.0),
+                forged,
+                signed_order("q2", OrderSide::Sell, 101.0, 1.0),
+            ]
+        };
+
+        let mut engine = MatchingEngine::new();
+        let results = engine.place_orders_batch(batch(), true).unwrap();
+        assert_eq!(
+            results.iter().map(|r| r.status.clone()).collect::<Vec<_>>(),
+            vec![PlaceStatus::RolledBack, PlaceStatus::Rejected, PlaceStatus::RolledBack]
+        );
+        assert_eq!(results[1].code.as_deref(), Some("invalid_signature"));
+        assert!(engine.order_book.buy_orders.is_empty());
+        assert!(engine.order_book.sell_orders.is_empty());
+
+        let results = engine.place_orders_batch(batch(), false).unwrap();
+        assert_eq!(
+            results.iter().map(|r| r.status.clone()).collect::<Vec<_>>(),
+            vec![PlaceStatus::Accepted, PlaceStatus::Rejected, PlaceStatus::Accepted]
+        );
+        assert_eq!(engine.order_book.buy_orders.len(), 1);
+        assert_eq!(engine.order_book.sell_orders.len(), 1);
+
+        // Bereits im Buch => doppelte ID, atomar wieder nichts
+        let results = engine.place_orders_batch(vec![signed_order("q3", OrderSide::Buy, 98.0, 1.0), signed_order("q1", OrderSide::Buy, 97.0, 1.0)], true).unwrap();
+        assert!(results.iter().all(|r| !r.is_accepted()));
+        assert_eq!(engine.order_book.buy_orders.len(), 1);
+    }
+
+    #[test]
+    fn test_compare_orders_transitive_with_mixed_stamps() {
+        let hlc = |physical_ms| HlcTimestamp { physical_ms, logical: 0, node: 0 };
+        let mut a = OrderData::new("a", "u", OrderSide::Buy, OrderType::Limit(100.0), 1.0, 0);
+        a.sequence = Some(SequenceNo::Hlc(hlc(2)));
+        let mut b = OrderData::new("b", "u", OrderSide::Buy, OrderType::Limit(100.0), 1.0, 0);
+        b.hlc = Some(hlc(1));
+        let mut c = OrderData::new("c", "u", OrderSide::Buy, OrderType::Limit(100.0), 1.0, 0);
+        c.sequence = Some(SequenceNo::Hlc(hlc(1)));
+        c.hlc = Some(hlc(5));
+        let orders = [a, b, c];
+        for x in &orders {
+            for y in &orders {
+                for z in &orders {
+                    let le = |p: &OrderData, q: &OrderData| compare_orders(p, q, true) != Ordering::Greater;
+                    if le(x, y) && le(y, z) {
+                        assert!(le(x, z), "{} <= {} <= {} but not {} <= {}", x.id, y.id, z.id, x.id, z.id);
+                    }
+                }
+            }
+        }
+        let mut sorted = orders.to_vec();
+        sorted.sort_by(|p, q| compare_orders(p, q, true));
+        let ids: Vec<&str> = sorted.iter().map(|o| o.id.as_str()).collect();
+        assert_eq!(ids, ["c", "a", "b"]);
+    }
+
+    fn user_order(id: &str, user: &str, side: OrderSide, price: f64) -> OrderData {
+        OrderData::new(id, user, side, OrderType::Limit(price), 1.0, 0).signed_for_tests()
+    }
+
+    #[test]
+    fn test_per_user_book_cap_rejects_only_that_user() {
+        let mut engine = MatchingEngine::new().with_book_caps(BookCaps {
+            max_orders_per_user: 2,
+            ..Default::default()
+        });
+        engine.place_order(user_order("a1", "alice", OrderSide::Buy, 99.0)).unwrap();
+        engine.place_order(user_order("a2", "alice", OrderSide::Sell, 101.0)).unwrap();
+
+        let err = engine.place_order(user_order("a3", "alice", OrderSide::Buy, 98.0)).unwrap_err();
+        assert!(matches!(err, DexError::BookCapExceeded { limit: 2, .. }));
+        assert_eq!(err.http_status(), 429);
+        assert_eq!(engine.order_book.count_for_user("alice"), 2);
+
+        engine.place_order(user_order("b1", "bob", OrderSide::Buy, 98.0)).unwrap();
+        assert_eq!(engine.order_book.len(), 3);
+    }
+
+    #[test]
+    fn test_market_cap_evicts_farthest_order_and_notifies_owner() {
+        let hub = MarketDataHub::new();
+        let mut rx = hub.subscribe();
+        let mut engine = MatchingEngine::new()
+            .with_market_data("BTC/USDT", hub)
+            .with_book_caps(BookCaps {
+                max_orders_per_market: 3,
+                policy: BookCapPolicy::EvictFarthest,
+                ..Default::default()
+            });
+        engine.place_order(user_order("near_bid", "alice", OrderSide::Buy, 99.0)).unwrap();
+        engine.place_order(user_order("near_ask", "bob", OrderSide::Sell, 101.0)).unwrap();
+        engine.place_order(user_order("far_bid", "spammer", OrderSide::Buy, 50.0)).unwrap();
+
+        // Mid = 100 => far_bid (Abstand 50) weicht der neuen Order (Abstand 2)
+        engine.place_order(user_order("new_ask", "carol", OrderSide::Sell, 102.0)).unwrap();
+        assert_eq!(engine.order_book.len(), 3);
+        assert!(engine.order_book.buy_orders.iter().all(|lo| lo.order.id != "far_bid"));
+
+        let mut evicted = None;
+        while let Ok(ev) = rx.try_recv() {
+            if let MarketDataEvent::OrderCancelled(c) = ev {
+                evicted = Some(c);
+            }
+        }
+        let evicted = evicted.expect("cancellation event");
+        assert_eq!(evicted.order_id, "far_bid");
+        assert_eq!(evicted.reason, "evicted");
+        assert_eq!(evicted.user_id.as_deref(), Some("spammer"));
+
+        // Eine noch weiter entfernte Order verdrängt niemanden
+        let err = engine.place_order(user_order("farther", "spammer", OrderSide::Sell, 500.0)).unwrap_err();
+        assert!(matches!(err, DexError::BookCapExceeded { .. }));
+        assert_eq!(engine.order_book.len(), 3);
+    }
+
+    #[test]
+    fn test_forged_order_evicts_nobody_and_eviction_releases_locks() {
+        let released = Arc::new(Mutex::new(Vec::new()));
+        let sink = released.clone();
+        let mut engine = MatchingEngine::new()
+            .with_book_caps(BookCaps {
+                max_orders_per_market: 3,
+                policy: BookCapPolicy::EvictFarthest,
+                ..Default::default()
+            })
+            .with_eviction_hook(Arc::new(move |o: &OrderData| sink.lock().unwrap().push(o.id.clone())));
+        engine.place_order(user_order("near_bid", "alice", OrderSide::Buy, 99.0)).unwrap();
+        engine.place_order(user_order("near_ask", "bob", OrderSide::Sell, 101.0)).unwrap();
+        engine.place_order(user_order("far_bid", "dave", OrderSide::Buy, 50.0)).unwrap();
+
+        // Nachträglich veränderte Order => Signatur ungültig, niemand wird verdrängt
+        let mut forged = user_order("forged", "mallory", OrderSide::Sell, 500.0);
+        forged.order_type = OrderType::Limit(102.0);
+        let err = engine.place_order(forged).unwrap_err();
+        assert!(matches!(err, DexError::InvalidSignature(_)));
+        assert_eq!(engine.order_book.len(), 3);
+        assert!(released.lock().unwrap().is_empty());
+
+        engine.place_order(user_order("new_ask", "carol", OrderSide::Sell, 102.0)).unwrap();
+        assert_eq!(*released.lock().unwrap(), vec!["far_bid".to_string()]);
+    }
+
+    #[test]
+    fn test_atomic_batch_rollback_restores_evicted_orders() {
+        let hub = MarketDataHub::new();
+        let mut rx = hub.subscribe();
+        let mut engine = MatchingEngine::new()
+            .with_market_data("BTC/USDT", hub)
+            .with_book_caps(BookCaps {
+                max_orders_per_market: 3,
+                policy: BookCapPolicy::EvictFarthest,
+                ..Default::default()
+            });
+        engine.place_order(user_order("near_bid", "alice", OrderSide::Buy, 99.0)).unwrap();
+        engine.place_order(user_order("far_bid", "spammer", OrderSide::Buy, 50.0)).unwrap();
+        engine.place_order(user_order("near_ask", "bob", OrderSide::Sell, 101.0)).unwrap();
+        let before: Vec<String> = engine.order_book.buy_orders.iter().map(|lo| lo.order.id.clone()).collect();
+        while rx.try_recv().is_ok() {}
+
+        // new_ask verdrängt far_bid, farther scheitert am Cap => alles zurück
+        let results = engine.place_orders_batch(vec![
+            user_order("new_ask", "carol", OrderSide::Sell, 102.0),
+            user_order("farther", "carol", OrderSide::Sell, 500.0),
+        ], true).unwrap();
+        assert_eq!(
+            results.iter().map(|r| r.status.clone()).collect::<Vec<_>>(),
+            vec![PlaceStatus::RolledBack, PlaceStatus::Rejected]
+        );
+        assert_eq!(engine.order_book.len(), 3);
+        let after: Vec<String> = engine.order_book.buy_orders.iter().map(|lo| lo.order.id.clone()).collect();
+        assert_eq!(after, before);
+        assert!(engine.order_book.sell_orders.iter().all(|lo| lo.order.id != "new_ask"));
+        while let Ok(ev) = rx.try_recv() {
+            assert!(!matches!(ev, MarketDataEvent::OrderCancelled(_)), "no eviction after rollback: {:?}", ev);
+        }
+
+        // Ohne die scheiternde Order wird die Verdrängung wirksam
+        engine.place_orders_batch(vec![user_order("new_ask", "carol", OrderSide::Sell, 102.0)], true).unwrap();
+        assert!(engine.order_book.buy_orders.iter().all(|lo| lo.order.id != "far_bid"));
+        let mut evicted = false;
+        while let Ok(ev) = rx.try_recv() {
+            if let MarketDataEvent::OrderCancelled(c) = ev {
+                assert_eq!(c.order_id, "far_bid");
+                evicted = true;
+            }
+        }
+        assert!(evicted);
+    }
+
+    #[test]
+    fn test_order_size_limits_at_boundaries() {
+        let mut limits = OrderLimits {
+            default: OrderSizeLimits { min_quantity: 0.01, max_notional: 10_000.0 },
+            ..Default::default()
+        };
+        limits.markets.insert("ETH/USDT".into(), OrderSizeLimits { min_quantity: 1.0, max_notional: 0.0 });
+        let mut engine = MatchingEngine::new().with_order_limits(limits.clone());
+
+        // Mindestmenge: genau auf der Grenze ok, knapp darunter Dust
+        engine.place_order(signed_order("min_ok", OrderSide::Buy, 90.0, 0.01)).unwrap();
+        let err = engine.place_order(signed_order("dust", OrderSide::Buy, 90.0, 0.0099)).unwrap_err();
+        assert!(matches!(err, DexError::OrderTooSmall { .. }));
+        assert_eq!(err.code(), "order_too_small");
+
+        // Gegenwert: 100 * 100 = Grenze ok, darüber und absurde Mengen abgelehnt
+        engine.place_order(signed_order("max_ok", OrderSide::Sell, 100.0, 100.0)).unwrap();
+        let err = engine.place_order(signed_order("fat", OrderSide::Sell, 100.0, 100.01)).unwrap_err();
+        assert!(matches!(err, DexError::OrderTooLarge { .. }));
+        assert_eq!(err.code(), "order_too_large");
+        assert!(matches!(
+            engine.place_order(signed_order("huge", OrderSide::Sell, 100.0, 1e308)),
+            Err(DexError::OrderTooLarge { .. })
+        ));
+
+        // Market-Buy wird zum besten Ask (100) bewertet
+        let market = |id: &str, qty| OrderData::new(id, "user", OrderSide::Buy, OrderType::Market, qty, 0).signed_for_tests();
+        assert!(matches!(engine.place_order(market("mkt_fat", 100.5)), Err(DexError::OrderTooLarge { .. })));
+        engine.place_order(market("mkt_ok", 100.0)).unwrap();
+
+        // Eigene Grenzen je Markt
+        let mut eth = MatchingEngine::new().with_order_limits(limits);
+        eth.market = "ETH/USDT".into();
+        assert!(matches!(eth.place_order(signed_order("e1", OrderSide::Buy, 2000.0, 0.5)), Err(DexError::OrderTooSmall { .. })));
+        eth.place_order(signed_order("e2", OrderSide::Buy, 2000.0, 1_000.0)).unwrap();
+    }
+
+    #[test]
+    fn test_unexpired_time_limited_order_still_fills() {
+        let manager = TimeLimitedOrderManager::new();
+        let mut engine = MatchingEngine::new().with_time_limited_manager(manager.clone());
+        engine.place_time_limited_order(signed_order("tl_sell", OrderSide::Sell, 100.0, 1.0), 3600, 1).unwrap();
+        engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        assert_eq!(engine.match_orders().unwrap().len(), 1);
+        // Vollständig gefüllt => nicht mehr in der Time-Limited-Verwaltung
+        assert_eq!(manager.status("tl_sell"), None);
+        assert_eq!(manager.tracked_orders(), 0);
+    }
+
+    #[test]
+    fn test_trade_span_hierarchy() {
+        let recorder = SpanRecorder::default();
+        let subscriber = Registry::default().with(recorder.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            let mut engine = MatchingEngine::new();
+            let mut funded = SettlementEngine::new();
+            for user in ["user"] {
+                let mut assets = StdHashMap::new();
+                assets.insert("BTC".to_string(), (1_000.0, 0.0));
+                assets.insert("USDT".to_string(), (1_000_000.0, 0.0));
+                funded.balances.insert(user.to_string(), assets);
+            }
+            engine.settlement = Box::new(funded);
+
+            engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+            engine.place_order(signed_order("s1", OrderSide::Sell, 100.0, 1.0)).unwrap();
+            engine.process_trades().unwrap();
+        });
+
+        let spans = recorder.0.lock().unwrap().clone();
+        let parent_of = |name: &str| {
+            spans
+                .iter()
+                .find(|(n, _)| n == name)
+                .unwrap_or_else(|| panic!("span {} missing in {:?}", name, spans))
+                .1
+                .clone()
+        };
+        assert_eq!(parent_of("place_order"), None);
+        assert_eq!(parent_of("match_orders"), Some("process_trades".to_string()));
+        assert_eq!(parent_of("trade"), Some("process_trades".to_string()));
+        assert_eq!(parent_of("fee_distribution"), Some("trade".to_string()));
+        assert_eq!(parent_of("finalize_trade"), Some("process_trades".to_string()));
+    }
+
+    #[test]
+    fn test_maker_rebate_paid_out_of_taker_fee() {
+        use crate::settlement::secured_settlement::PaperLedger;
+
+        struct Paper(Arc<Mutex<PaperLedger>>);
+        impl SettlementEngineTrait for Paper {
+            fn finalize_trade(&mut self, buyer: &str, seller: &str, base: &str, quote: &str, b: f64, q: f64) -> Result<(), DexError> {
+                self.0.lock().unwrap().apply(buyer, seller, base, quote, b, q);
+                Ok(())
+            }
+        }
+
+        let mut schedule = FeeSchedule::default();
+        schedule.set_market("btc/usdt", MarketFees::with_rebate(0.002, 0.0005)).unwrap();
+        // Rebate > Taker-Fee würde den Pool ins Minus ziehen
+        assert!(schedule.set_market("ETH/USDT", MarketFees::with_rebate(0.001, 0.002)).is_err());
+
+        let ledger = Arc::new(Mutex::new(FeeLedger::default()));
+        let mut engine = MatchingEngine::new()
+            .with_fee_schedule(schedule)
+            .with_fee_ledger(ledger.clone());
+        let paper = Arc::new(Mutex::new(PaperLedger::default()));
+        engine.settlement = Box::new(Paper(paper.clone()));
+        // bob ruht im Buch (Maker), alice kauft hinein (Taker)
+        let sell = OrderData::new("s1", "bob", OrderSide::Sell, OrderType::Limit(100.0), 2.0, 0);
+        let buy = OrderData::new("b1", "alice", OrderSide::Buy, OrderType::Limit(100.0), 2.0, 0);
+        engine.place_order(sell.signed_for_tests()).unwrap();
+        engine.place_order(buy.signed_for_tests()).unwrap();
+        engine.process_trades().unwrap();
+
+        // Notional 200: Taker 0.4, Rebate 0.1, Pool netto 0.3 – in den Settlement-Legs
+        let paper = paper.lock().unwrap();
+        assert!((paper.balance("alice", "USDT") + 200.4).abs() < 1e-9);
+        assert!((paper.balance("bob", "USDT") - 200.1).abs() < 1e-9);
+        assert!((paper.balance(FEE_POOL_ACCOUNT, "USDT") - 0.3).abs() < 1e-9);
+        let ledger = ledger.lock().unwrap();
+        assert!((ledger.account("alice", "USDT") + 0.4).abs() < 1e-9);
+        assert!((ledger.account("bob", "USDT") - 0.1).abs() < 1e-9);
+        assert!((ledger.totals["USDT"] - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_circuit_breaker_pauses_matching_until_cooldown() {
+        use crate::dex_logic::circuit_breaker::{BreakerThresholds, CircuitBreakerConfig};
+        let thresholds = BreakerThresholds { max_move_pct: 0.05, window_secs: 60, cooldown_secs: 300 };
+        let breaker = Arc::new(Mutex::new(CircuitBreaker::new(CircuitBreakerConfig {
+            enabled: true,
+            default: thresholds,
+            markets: Default::default(),
+        })));
+        let mut engine = MatchingEngine::new().with_circuit_breaker(breaker.clone());
+        engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        engine.place_order(signed_order("s1", OrderSide::Sell, 100.0, 1.0)).unwrap();
+
+        let now = now_secs();
+        breaker.lock().unwrap().observe("BTC/USDT", 30_000.0, now);
+        assert!(breaker.lock().unwrap().observe("BTC/USDT", 24_000.0, now).is_some());
+        assert!(engine.match_fills().unwrap().is_empty());
+        assert_eq!(engine.order_book.buy_orders.len(), 1);
+
+        // Nach dem Cooldown gibt check() den Markt frei
+        assert!(!breaker.lock().unwrap().check("BTC/USDT", now + 300));
+        assert_eq!(engine.match_fills().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_process_trades_settles_real_counterparties_netted() {
+        type Calls = Arc<Mutex<Vec<(String, String, String, String, f64, f64)>>>;
+        struct Recorder(Calls);
+        impl SettlementEngineTrait for Recorder {
+            fn finalize_trade(&mut self, buyer: &str, seller: &str, base: &str, quote: &str, b: f64, q: f64) -> Result<(), DexError> {
+                self.0.lock().unwrap().push((buyer.into(), seller.into(), base.into(), quote.into(), b, q));
+                Ok(())
+            }
+        }
+
+        let calls: Calls = Arc::default();
+        let mut engine = MatchingEngine::new();
+        engine.settlement = Box::new(SecuredSettlementEngine::new(Recorder(calls.clone()), AdvancedSecurityValidator::new()));
+        for i in 0..3 {
+            let buy = OrderData::new(&format!("b{}", i), "alice", OrderSide::Buy, OrderType::Limit(100.0), 1.0, 0);
+            let sell = OrderData::new(&format!("s{}", i), "bob", OrderSide::Sell, OrderType::Limit(100.0), 1.0, 0);
+            engine.place_order(buy.signed_for_tests()).unwrap();
+            engine.place_order(sell.signed_for_tests()).unwrap();
+        }
+        engine.process_trades().unwrap();
+
+        // bob ist jeweils Taker (0.1%) und zahlt die Fees netto an den Pool
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0], ("alice".into(), "bob".into(), "BTC".into(), "USDT".into(), 3.0, 300.0));
+        let (buyer, seller, _, _, base, quote) = &calls[1];
+        assert_eq!((buyer.as_str(), seller.as_str(), *base), ("bob", FEE_POOL_ACCOUNT, 0.0));
+        assert!((quote - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_process_trades_feeds_anomaly_engine() {
+        use crate::sanctions::internal_analysis::{AnomalyConfig, AnomalyEngine};
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let config = AnomalyConfig { report_threshold: 10.0, auto_pause_threshold: 1_000.0, ..AnomalyConfig::default() };
+        let anomaly = Arc::new(Mutex::new(AnomalyEngine::new(config, committee_key(9), Box::new(tx))));
+        let mut engine = MatchingEngine::new().with_anomaly_engine(anomaly);
+
+        // alice kauft von bob, dann bob von alice => Round-Trip
+        for (i, (buyer, seller)) in [("alice", "bob"), ("bob", "alice")].into_iter().enumerate() {
+            let buy = OrderData::new(&format!("b{}", i), buyer, OrderSide::Buy, OrderType::Limit(100.0), 1.0, 0);
+            let sell = OrderData::new(&format!("s{}", i), seller, OrderSide::Sell, OrderType::Limit(100.0), 1.0, 0);
+            engine.place_order(buy.signed_for_tests()).unwrap();
+            engine.place_order(sell.signed_for_tests()).unwrap();
+            engine.process_trades().unwrap();
+        }
+
+        let report = rx.try_recv().expect("anomaly report");
+        assert!(report.reasons.iter().any(|r| r.starts_with("round_trip")));
+        assert!(report.verify());
+    }
+
+    #[test]
+    fn test_failed_settlement_stays_pending_and_retries_once() {
+        use crate::settlement::settlement_queue::TradeSettlementState;
+        use crate::storage::db_layer::InMemoryDb;
+
+        struct Flaky(Arc<Mutex<(bool, u32)>>);
+        impl SettlementEngineTrait for Flaky {
+            fn finalize_trade(&mut self, _: &str, _: &str, _: &str, _: &str, _: f64, _: f64) -> Result<(), DexError> {
+                let mut s = self.0.lock().unwrap();
+                if s.0 {
+                    return Err(DexError::Other("rpc down".into()));
+                }
+                s.1 += 1;
+                Ok(())
+            }
+        }
+
+        let state = Arc::new(Mutex::new((true, 0u32)));
+        let db = DexDB { rocks: None, fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))) };
+        let queue = SettlementQueue::new(Arc::new(Mutex::new(db))).with_backoff(0, 0);
+        let mut engine = MatchingEngine::new().with_settlement_queue(queue.clone());
+        engine.settlement = Box::new(Flaky(state.clone()));
+
+        engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        engine.place_order(signed_order("s1", OrderSide::Sell, 100.0, 1.0)).unwrap();
+        // Settlement scheitert, der Match bleibt aber als pending erhalten
+        engine.process_trades().unwrap();
+        assert!(engine.order_book.buy_orders.is_empty());
+        assert_eq!(queue.pending_jobs().unwrap().len(), 1);
+        assert_eq!(state.lock().unwrap().1, 0);
+
+        state.lock().unwrap().0 = false;
+        let report = engine.retry_pending_settlements().unwrap();
+        assert_eq!(report.settled_jobs, 1);
+        assert_eq!(queue.trade_state("b1:s1").unwrap(), Some(TradeSettlementState::Settled));
+
+        // Weitere Läufe wenden nichts doppelt an
+        engine.process_trades().unwrap();
+        engine.retry_pending_settlements().unwrap();
+        assert_eq!(state.lock().unwrap().1, 1);
+    }
+
+    #[test]
+    fn test_failed_settlement_books_no_fees() {
+        use crate::storage::db_layer::InMemoryDb;
+
+        struct Failing(Arc<Mutex<bool>>);
+        impl SettlementEngineTrait for Failing {
+            fn finalize_trade(&mut self, _: &str, _: &str, _: &str, _: &str, _: f64, _: f64) -> Result<(), DexError> {
+                if *self.0.lock().unwrap() {
+                    return Err(DexError::Other("rpc down".into()));
+                }
+                Ok(())
+            }
+        }
+
+        // Direktes Settlement scheitert => keine Fees
+        let ledger = Arc::new(Mutex::new(FeeLedger::default()));
+        let mut engine = MatchingEngine::new().with_fee_ledger(ledger.clone());
+        engine.settlement = Box::new(Failing(Arc::new(Mutex::new(true))));
+        engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        engine.place_order(signed_order("s1", OrderSide::Sell, 100.0, 1.0)).unwrap();
+        assert!(engine.process_trades().is_err());
+        assert!(ledger.lock().unwrap().totals.is_empty());
+        assert_eq!(ledger.lock().unwrap().trades, 0);
+
+        // Über die Queue: erst nach dem erfolgreichen Retry gebucht, genau einmal
+        let failing = Arc::new(Mutex::new(true));
+        let db = DexDB { rocks: None, fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))) };
+        let queue = SettlementQueue::new(Arc::new(Mutex::new(db))).with_backoff(0, 0);
+        let ledger = Arc::new(Mutex::new(FeeLedger::default()));
+        let mut engine = MatchingEngine::new().with_fee_ledger(ledger.clone()).with_settlement_queue(queue);
+        engine.settlement = Box::new(Failing(failing.clone()));
+        engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        engine.place_order(signed_order("s1", OrderSide::Sell, 100.0, 1.0)).unwrap();
+        engine.process_trades().unwrap();
+        assert!(ledger.lock().unwrap().totals.is_empty());
+        assert!(ledger.lock().unwrap().accounts.is_empty());
+
+        *failing.lock().unwrap() = false;
+        assert_eq!(engine.retry_pending_settlements().unwrap().settled_jobs, 1);
+        engine.retry_pending_settlements().unwrap();
+        let ledger = ledger.lock().unwrap();
+        assert_eq!(ledger.trades, 1);
+        assert!((ledger.totals["USDT"] - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_trades_kept_when_enqueue_fails() {
+        use crate::storage::db_layer::InMemoryDb;
+
+        let db = Arc::new(Mutex::new(DexDB { rocks: None, fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))) }));
+        let queue = SettlementQueue::new(db.clone()).with_backoff(0, 0);
+        let mut engine = MatchingEngine::new().with_settlement_queue(queue.clone());
+        // Kaputter Key-Marker => enqueue scheitert beim Lesen
+        db.lock().unwrap().put_raw("settlement_trade/b1:s1", vec![0xff; 4]).unwrap();
+
+        engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        engine.place_order(signed_order("s1", OrderSide::Sell, 100.0, 1.0)).unwrap();
+        assert!(engine.process_trades().is_err());
+        assert!(engine.order_book.buy_orders.is_empty());
+        // Haupt-Leg und Fee-Leg des Trades
+        let keys: Vec<_> = engine.unqueued_trades().iter().map(|t| t.key.as_str()).collect();
+        assert_eq!(keys, vec!["b1:s1", "b1:s1:fee"]);
+
+        // Nächster Lauf reiht den liegengebliebenen Trade ein
+        db.lock().unwrap().delete_key("settlement_trade/b1:s1").unwrap();
+        engine.process_trades().unwrap();
+        assert!(engine.unqueued_trades().is_empty());
+        assert!(queue.trade_state("b1:s1").unwrap().is_some());
+    }
+
+    /// Sammelt die `message`-Felder aller Log-Events.
+    #[derive(Clone, Default)]
+    struct EventRecorder(Arc<Mutex<Vec<String>>>);
+
+    impl<S: Subscriber> Layer<S> for EventRecorder {
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+            struct Msg<'a>(&'a mut String);
+            impl tracing::field::Visit for Msg<'_> {
+                fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+                    if field.name() == "message" {
+                        *self.0 = format!("{:?}", value);
+                    }
+                }
+            }
+            let mut msg = String::new();
+            event.record(&mut Msg(&mut msg));
+            self.0.lock().unwrap().push(msg);
+        }
+    }
+
+    #[test]
+    fn test_dry_run_emits_trades_and_audit_but_no_wallet_calls() {
+        struct WalletRpc(Arc<Mutex<u32>>);
+        impl SettlementEngineTrait for WalletRpc {
+            fn finalize_trade(&mut self, _: &str, _: &str, _: &str, _: &str, _: f64, _: f64) -> Result<(), DexError> {
+                *self.0.lock().unwrap() += 1;
+                Ok(())
+            }
+        }
+
+        let rpc_calls = Arc::new(Mutex::new(0u32));
+        let recorder = EventRecorder::default();
+        let subscriber = Registry::default().with(recorder.clone());
+        let hub = MarketDataHub::new();
+        let mut rx = hub.subscribe();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let mut engine = MatchingEngine::new().with_market_data("BTC/USDT", hub);
+            engine.settlement = Box::new(WalletRpc(rpc_calls.clone()));
+            let mut engine = engine.with_dry_run(true);
+            engine.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+            engine.place_order(signed_order("s1", OrderSide::Sell, 100.0, 1.0)).unwrap();
+            engine.process_trades().unwrap();
+        });
+
+        assert_eq!(*rpc_calls.lock().unwrap(), 0);
+        let mut trade_events = 0;
+        while let Ok(ev) = rx.try_recv() {
+            if let MarketDataEvent::Trade(t) = ev {
+                assert_eq!((t.buy_order_id.as_str(), t.sell_order_id.as_str()), ("b1", "s1"));
+                trade_events += 1;
+            }
+        }
+        assert_eq!(trade_events, 1);
+        let logs = recorder.0.lock().unwrap();
+        assert!(logs.iter().any(|m| m.starts_with("AUDIT: [DRY-RUN] Trade gematcht")), "{:?}", logs);
+        assert!(logs.iter().any(|m| m.starts_with("AUDIT: [DRY-RUN] Settlement-Fenster finalisiert")));
+    }
+
+    #[test]
+    fn test_halt_reject_and_resume_market() {
+        let keys: Vec<_> = (1..=3).map(committee_key).collect();
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("halt_state.json");
+        let control = MarketHaltControl::new(keys.iter().map(|k| k.public).collect(), 2)
+            .unwrap()
+            .with_state_file(&state_path)
+            .unwrap();
+        let control = Arc::new(Mutex::new(control));
+
+        let mut btc = MatchingEngine::new().with_halt_control(control.clone());
+        let mut eth = MatchingEngine::new().with_halt_control(control.clone());
+        eth.market = "ETH/USDT".to_string();
+
+        // Eine Signatur reicht nicht
+        let single = [approve(&keys[0], "BTC/USDT", HaltAction::Halt, 0)];
+        assert!(btc.halt_market("BTC/USDT", &single).is_err());
+        assert!(!btc.is_market_halted("BTC/USDT"));
+
+        let approvals = [
+            approve(&keys[0], "BTC/USDT", HaltAction::Halt, 0),
+            approve(&keys[1], "BTC/USDT", HaltAction::Halt, 0),
+        ];
+        btc.halt_market("BTC/USDT", &approvals).unwrap();
+
+        let err = btc.place_order(signed_order("b1", OrderSide::Buy, 100.0, 1.0)).unwrap_err();
+        assert!(matches!(err, DexError::MarketHalted(ref m) if m == "BTC/USDT"));
+        assert!(matches!(btc.match_orders(), Err(DexError::MarketHalted(_))));
+        // Andere Märkte laufen weiter
+        eth.place_order(signed_order("e1", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        assert!(eth.match_orders().is_ok());
+
+        // Neustart respektiert den Halt
+        let reloaded = MarketHaltControl::new(keys.iter().map(|k| k.public).collect(), 2)
+            .unwrap()
+            .with_state_file(&state_path)
+            .unwrap();
+        assert!(reloaded.is_halted("BTC/USDT"));
+
+        // Alter Beschluss (nonce 0) lässt sich nicht wiederverwenden
+        let stale = [
+            approve(&keys[1], "BTC/USDT", HaltAction::Resume, 0),
+            approve(&keys[2], "BTC/USDT", HaltAction::Resume, 0),
+        ];
+        assert!(btc.resume_market("BTC/USDT", &stale).is_err());
+
+        // Nonces gelten je Markt: ETH ist vom BTC-Halt unberührt
+        assert_eq!(control.lock().unwrap().nonce("ETH/USDT"), 0);
+        let nonce = control.lock().unwrap().nonce("BTC/USDT");
+        assert_eq!(nonce, 1);
+        let resume = [
+            approve(&keys[1], "BTC/USDT", HaltAction::Resume, nonce),
+            approve(&keys[2], "BTC/USDT", HaltAction::Resume, nonce),
+        ];
+        btc.resume_market("BTC/USDT", &resume).unwrap();
+        btc.place_order(signed_order("b2", OrderSide::Buy, 100.0, 1.0)).unwrap();
+        assert!(btc.match_orders().is_ok());
+    }
+
+    #[test]
+    fn test_legacy_halt_state_keeps_global_nonce_as_floor() {
+        let keys: Vec<_> = (1..=2).map(committee_key).collect();
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("halt_state.json");
+        std::fs::write(&state_path, r#"{"nonce":5,"halted":["BTC/USDT"]}"#).unwrap();
+        let control = MarketHaltControl::new(keys.iter().map(|k| k.public).collect(), 2)
+            .unwrap()
+            .with_state_file(&state_path)
+            .unwrap();
+        assert!(control.is_halted("BTC/USDT"));
+        assert_eq!(control.nonce("BTC/USDT"), 5);
+        assert_eq!(control.nonce("ETH/USDT"), 5);
+    }
+
+    #[test]
+    fn test_halt_rejects_foreign_and_duplicate_signers() {
+        let keys: Vec<_> = (1..=3).map(committee_key).collect();
+        let outsider = committee_key(9);
+        let mut control = MarketHaltControl::new(keys.iter().map(|k| k.public).collect(), 2).unwrap();
+
+        let dup = [
+            approve(&keys[0], "BTC/USDT", HaltAction::Halt, 0),
+            approve(&keys[0], "BTC/USDT", HaltAction::Halt, 0),
+        ];
+        assert!(control.apply("BTC/USDT", HaltAction::Halt, &dup).is_err());
+
+        let foreign = [
+            approve(&keys[0], "BTC/USDT", HaltAction::Halt, 0),
+            approve(&outsider, "BTC/USDT", HaltAction::Halt, 0),
+        ];
+        assert!(control.apply("BTC/USDT", HaltAction::Halt, &foreign).is_err());
+        assert!(!control.is_halted("BTC/USDT"));
+
+        // Signaturen über das alte Textformat (ohne Domain-Tag) zählen nicht
+        let legacy = |kp: &ed25519_dalek::Keypair| {
+            use ed25519_dalek::Signer;
+            HaltApproval {
+                signer: hex::encode(kp.public.as_bytes()),
+                signature: hex::encode(kp.sign(b"market_halt|BTC/USDT|halt|0").to_bytes()),
+            }
+        };
+        assert!(control.apply("BTC/USDT", HaltAction::Halt, &[legacy(&keys[0]), legacy(&keys[1])]).is_err());
+        assert!(!control.is_halted("BTC/USDT"));
+    }
+
+    /// ed25519-Key, mit dem `node{i}` seine Batches signiert.
+    fn sequencer_batch_key(i: usize) -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[i as u8 + 1; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        ed25519_dalek::Keypair { secret, public }
+    }
+
+    fn sequencer_nodes() -> (Vec<crate::consensus::vrf_committee_async::VrfKeypair>, SequencerElection) {
+        use crate::consensus::sequencer::SequencerCandidate;
+        use crate::consensus::vrf_committee_async::VrfKeypair;
+        let keys: Vec<VrfKeypair> = (1..=3u8)
+            .map(|i| VrfKeypair::from_seed(&[i; 32]))
+            .collect();
+        let candidates = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| SequencerCandidate {
+                node_id: format!("node{}", i),
+                vrf_pk: k.pk,
+                sign_pk: sequencer_batch_key(i).public.to_bytes(),
+            })
+            .collect();
+        (keys, SequencerElection::new(42, candidates))
+    }
+
+    fn sequencer_index(claim: &SequencerClaim) -> usize {
+        claim.node_id.trim_start_matches("node").parse().unwrap()
+    }
+
+    #[test]
+    fn test_sequenced_nodes_produce_identical_trades() {
+        use crate::consensus::sequencer::{make_claim, OrderSequencer};
+
+        let (keys, election) = sequencer_nodes();
+        let claims: Vec<_> = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| make_claim(k, &format!("node{}", i), election.seed, 1))
+            .collect();
+
+        let mut node_a = MatchingEngine::new().with_sequencing(election.clone());
+        let mut node_b = MatchingEngine::new().with_sequencing(election.clone());
+        let leader_a = node_a.start_sequencer_epoch(&claims, 1).unwrap();
+        // Node B bekommt die Claims in anderer Reihenfolge => gleicher Sequencer
+        let mut reversed = claims.clone();
+        reversed.reverse();
+        let leader_b = node_b.start_sequencer_epoch(&reversed, 1).unwrap();
+        assert_eq!(leader_a, leader_b);
+
+        // Direkter Weg ist gesperrt
+        assert!(node_a.place_order(signed_order("x", OrderSide::Buy, 100.0, 1.0)).is_err());
+
+        let mut orders = vec![
+            signed_order("s1", OrderSide::Sell, 100.0, 1.0),
+            signed_order("b1", OrderSide::Buy, 101.0, 1.0),
+            signed_order("b2", OrderSide::Buy, 101.0, 1.0),
+            signed_order("s2", OrderSide::Sell, 99.0, 1.5),
+            signed_order("b3", OrderSide::Buy, 100.0, 2.0),
+        ];
+        let mut sequencer = OrderSequencer::new(leader_a.clone(), sequencer_batch_key(sequencer_index(&leader_a)));
+        let rest = orders.split_off(2);
+        let early = sequencer.batch(orders).unwrap();
+        let late = sequencer.batch(rest).unwrap();
+
+        // Node A erhält die Batches in Sequenz-Reihenfolge, Node B umgekehrt.
+        node_a.submit_sequenced(early.clone()).unwrap();
+        node_a.submit_sequenced(late.clone()).unwrap();
+        node_b.submit_sequenced(late).unwrap();
+        node_b.submit_sequenced(early).unwrap();
+
+        let trades_a = node_a.match_orders().unwrap();
+        let trades_b = node_b.match_orders().unwrap();
+        assert!(!trades_a.is_empty());
+        assert_eq!(trades_a, trades_b);
+        // Preisgleichheit => b1 vor b2 (Sequenz entscheidet)
+        assert_eq!(trades_a[0].0, "b1");
+    }
+
+    #[test]
+    fn test_batch_from_non_elected_sequencer_rejected() {
+        use crate::consensus::sequencer::{make_claim, OrderSequencer};
+
+        let (keys, election) = sequencer_nodes();
+        let claims: Vec<_> = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| make_claim(k, &format!("node{}", i), election.seed, 1))
+            .collect();
+        let mut engine = MatchingEngine::new().with_sequencing(election);
+        let leader = engine.start_sequencer_epoch(&claims, 1).unwrap();
+
+        let other = claims.into_iter().find(|c| c.node_id != leader.node_id).unwrap();
+        let batch = OrderSequencer::new(other.clone(), sequencer_batch_key(sequencer_index(&other)))
+            .batch(vec![signed_order("b1", OrderSide::Buy, 1.0, 1.0)])
+            .unwrap();
+        assert!(engine.submit_sequenced(batch).is_err());
+
+        // Öffentlicher Leader-Claim, aber Batch mit fremdem Key signiert
+        let forged_batch = OrderSequencer::new(leader.clone(), sequencer_batch_key(sequencer_index(&other)))
+            .batch(vec![signed_order("b1", OrderSide::Buy, 1.0, 1.0)])
+            .unwrap();
+        assert!(engine.submit_sequenced(forged_batch).is_err());
+
+        // Korrekt signierter Batch, nachträglich umsortiert / ergänzt
+        let mut sequencer = OrderSequencer::new(leader.clone(), sequencer_batch_key(sequencer_index(&leader)));
+        let mut batch = sequencer
+            .batch(vec![signed_order("b1", OrderSide::Buy, 1.0, 1.0), signed_order("b2", OrderSide::Buy, 1.0, 1.0)])
+            .unwrap();
+        let mut reordered = batch.clone();
+        reordered.orders.swap(0, 1);
+        assert!(engine.submit_sequenced(reordered).is_err());
+        let mut injected = batch.clone();
+        injected.orders[0].order = signed_order("evil", OrderSide::Buy, 1.0, 1.0);
+        assert!(engine.submit_sequenced(injected).is_err());
+        batch.signature.clear();
+        assert!(engine.submit_sequenced(batch).is_err());
+
+        // Manipulierter VRF-Wert fällt bei der Prüfung durch
+        let mut forged = leader.clone();
+        forged.value = forged.value.wrapping_add(1);
+        assert!(engine.start_sequencer_epoch(&[forged], 2).is_err());
+        // VRF-Proof ist an (seed, epoch) gebunden => kein Wiederverwenden
+        let mut replayed = leader.clone();
+        replayed.epoch = 2;
+        assert!(engine.start_sequencer_epoch(&[replayed], 2).is_err());
+    }
+
+    fn epoch_claims(keys: &[crate::consensus::vrf_committee_async::VrfKeypair], seed: u64, epoch: u64) -> Vec<SequencerClaim> {
+        use crate::consensus::sequencer::make_claim;
+        keys.iter().enumerate().map(|(i, k)| make_claim(k, &format!("node{}", i), seed, epoch)).collect()
+    }
+
+    #[test]
+    fn test_sequencer_equivocation_is_detected() {
+        use crate::consensus::sequencer::OrderSequencer;
+
+        let (keys, election) = sequencer_nodes();
+        let mut engine = MatchingEngine::new().with_sequencing(election.clone());
+        let leader = engine.start_sequencer_epoch(&epoch_claims(&keys, election.seed, 1), 1).unwrap();
+        let key = || sequencer_batch_key(sequencer_index(&leader));
+
+        // Zwei Sequencer-Instanzen mit demselben Claim vergeben beide seq 0
+        let first = OrderSequencer::new(leader.clone(), key())
+            .batch(vec![signed_order("b1", OrderSide::Buy, 100.0, 1.0)])
+            .unwrap();
+        let second = OrderSequencer::new(leader.clone(), key())
+            .batch(vec![signed_order("b2", OrderSide::Buy, 100.0, 1.0)])
+            .unwrap();
+        engine.submit_sequenced(first.clone()).unwrap();
+        // Dieselbe Position mit derselben Order ist nur ein Duplikat
+        engine.submit_sequenced(first).unwrap();
+        assert!(engine.take_sequencer_equivocation().is_none());
+
+        assert!(engine.submit_sequenced(second).is_err());
+        let proof = engine.take_sequencer_equivocation().expect("equivocation proof");
+        assert_eq!(proof.node_id(), leader.node_id);
+        assert!(proof.verify(&election));
+        // Abgesetzt => auch gültige weitere Batches werden abgelehnt
+        let more = OrderSequencer::new(leader.clone(), key())
+            .batch(vec![signed_order("b1", OrderSide::Buy, 100.0, 1.0)])
+            .unwrap();
+        assert!(engine.submit_sequenced(more).is_err());
+
+        // Ein Beweis aus zwei widerspruchsfreien Batches gilt nicht
+        let mut bogus = proof.clone();
+        bogus.second = bogus.first.clone();
+        assert!(!bogus.verify(&election));
+    }
+
+    #[test]
+    fn test_epoch_change_hands_back_gapped_orders() {
+        use crate::consensus::sequencer::OrderSequencer;
+
+        let (keys, election) = sequencer_nodes();
+        let mut engine = MatchingEngine::new().with_sequencing(election.clone());
+        let leader = engine.start_sequencer_epoch(&epoch_claims(&keys, election.seed, 1), 1).unwrap();
+        let mut sequencer = OrderSequencer::new(leader.clone(), sequencer_batch_key(sequencer_index(&leader)));
+        let _lost = sequencer.batch(vec![signed_order("b1", OrderSide::Buy, 100.0, 1.0)]).unwrap();
+        let gapped = sequencer.batch(vec![signed_order("b2", OrderSide::Buy, 100.0, 1.0)]).unwrap();
+        engine.submit_sequenced(gapped).unwrap();
+        assert!(engine.match_orders().unwrap().is_empty());
+        assert!(engine.order_book.buy_orders.is_empty());
+
+        engine.start_sequencer_epoch(&epoch_claims(&keys, election.seed, 2), 2).unwrap();
+        let orphans: Vec<String> = engine.take_sequencer_orphans().into_iter().map(|o| o.id).collect();
+        assert_eq!(orphans, vec!["b2".to_string()]);
+        assert!(engine.take_sequencer_orphans().is_empty());
+    }
+
+    #[test]
+    fn test_sequenced_orders_are_validated_before_the_book() {
+        use crate::consensus::sequencer::OrderSequencer;
+
+        let (keys, election) = sequencer_nodes();
+        let mut engine = MatchingEngine::new()
+            .with_sequencing(election.clone())
+            .with_order_limits(OrderLimits {
+                default: OrderSizeLimits { min_quantity: 0.5, max_notional: 0.0 },
+                ..Default::default()
+            });
+        let leader = engine.start_sequencer_epoch(&epoch_claims(&keys, election.seed, 1), 1).unwrap();
+        let batch = OrderSequencer::new(leader.clone(), sequencer_batch_key(sequencer_index(&leader)))
+            .batch(vec![
+                signed_order("dust", OrderSide::Buy, 100.0, 0.1),
+                signed_order("ok", OrderSide::Buy, 100.0, 1.0),
+            ])
+            .unwrap();
+        engine.submit_sequenced(batch).unwrap();
+        engine.match_orders().unwrap();
+        let ids: Vec<String> = engine.order_book.buy_orders.iter().map(|lo| lo.order.id.clone()).collect();
+        assert_eq!(ids, vec!["ok".to_string()]);
+    }
+
+    #[test]
+    fn test_admission_sequence_makes_matching_independent_of_arrival() {
+        // Ursprungs-Node vergibt die SequenceNo, danach werden die Orders repliziert
+        let mut origin = MatchingEngine::new().with_admission_clock(HybridLogicalClock::new("origin"));
+        let stamped: Vec<OrderData> = [
+            user_order("b1", "alice", OrderSide::Buy, 100.0),
+            user_order("b2", "bob", OrderSide::Buy, 100.0),
+            user_order("s1", "carol", OrderSide::Sell, 100.0),
+        ]
+        .into_iter()
+        .map(|mut o| {
+            origin.assign_sequence(&mut o);
+            o
+        })
+        .collect();
+        assert!(stamped[0].sequence < stamped[1].sequence);
+
+        let run = |node: &str, arrival: [usize; 3]| {
+            let mut engine = MatchingEngine::new().with_admission_clock(HybridLogicalClock::new(node));
+            for i in arrival {
+                engine.place_replicated_order(stamped[i].clone()).unwrap();
+            }
+            let fills = engine.match_fills().unwrap();
+            let resting: Vec<String> = engine.order_book.buy_orders.iter().map(|lo| lo.order.id.clone()).collect();
+            (fills.iter().map(|f| (f.as_tuple(), f.maker.clone())).collect::<Vec<_>>(), resting)
+        };
+        let (trades_a, rest_a) = run("node-a", [0, 1, 2]);
+        let (trades_b, rest_b) = run("node-b", [1, 0, 2]);
+        assert_eq!(trades_a, trades_b);
+        assert_eq!(trades_a, vec![(("b1".to_string(), "s1".to_string(), 1.0, 100.0), OrderSide::Buy)]);
+        assert_eq!(rest_a, vec!["b2".to_string()]);
+        assert_eq!(rest_a, rest_b);
+    }
+
+    #[test]
+    fn test_client_supplied_sequence_is_restamped() {
+        let mut engine = MatchingEngine::new().with_admission_clock(HybridLogicalClock::new("node"));
+        engine.place_order(user_order("b1", "alice", OrderSide::Buy, 100.0)).unwrap();
+        // Unsignierte Sequencer-Nummer bzw. uralter HLC soll vordrängeln
+        let mut forged = user_order("b2", "bob", OrderSide::Buy, 100.0);
+        forged.sequence = Some(SequenceNo::Sequencer { epoch: 0, seq: 0 });
+        forged.hlc = Some(HlcTimestamp { physical_ms: 0, logical: 0, node: 7 });
+        engine.place_order(forged.clone()).unwrap();
+        let seqs: Vec<Option<SequenceNo>> = engine.order_book.buy_orders.iter().map(|lo| lo.order.sequence).collect();
+        assert!(matches!(seqs[1], Some(SequenceNo::Hlc(_))));
+        assert!(seqs[0] < seqs[1]);
+
+        // Auch replizierte Orders bekommen keine Sequencer-Nummer, nur den Ursprungs-HLC
+        let mut replica = MatchingEngine::new().with_admission_clock(HybridLogicalClock::new("replica"));
+        forged.id = "b3".into();
+        replica.place_replicated_order(forged.clone()).unwrap();
+        let seq = replica.order_book.buy_orders[0].order.sequence;
+        assert_eq!(seq, forged.hlc.map(SequenceNo::Hlc));
+    }
+
+    #[test]
+    fn test_engine_admits_revealed_order_only() {
+        use crate::dex_logic::commit_reveal::{order_commitment_hash, CommitRevealWindow};
+
+        let now = now_secs();
+        let window = CommitRevealWindow { epoch: 1, commit_deadline: now, reveal_deadline: now + 60 };
+        let mut book = CommitRevealBook::new(window).unwrap();
+        let order = signed_order("c1", OrderSide::Buy, 100.0, 1.0);
+        // Commit lag vor der Deadline
+        book.submit_commitment_at("user", order_commitment_hash(&order, b"n").unwrap(), now - 10).unwrap();
+        let oversized = signed_order("c2", OrderSide::Buy, 100.0, 50.0);
+        book.submit_commitment_at("user", order_commitment_hash(&oversized, b"n").unwrap(), now - 10).unwrap();
+
+        let mut engine = MatchingEngine::new().with_commit_reveal(book).with_order_limits(OrderLimits {
+            default: OrderSizeLimits { min_quantity: 0.5, max_notional: 1_000.0 },
+            ..Default::default()
+        });
+        assert!(engine.place_order(order.clone()).is_err());
+        assert!(engine.reveal_order(signed_order("c1", OrderSide::Buy, 100.5, 1.0), b"n").is_err());
+        // Größenlimits gelten auch für offengelegte Orders
+        assert!(matches!(engine.reveal_order(oversized, b"n"), Err(DexError::OrderTooLarge { .. })));
+        engine.reveal_order(order, b"n").unwrap();
+        assert_eq!(engine.order_book.buy_orders.len(), 1);
+    }
+
+    #[test]
+    fn test_reveal_skips_max_age_of_commit_timestamp() {
+        use crate::dex_logic::commit_reveal::{order_commitment_hash, CommitRevealWindow};
+
+        let now = now_secs();
+        let window = CommitRevealWindow { epoch: 1, commit_deadline: now, reveal_deadline: now + 60 };
+        let mut book = CommitRevealBook::new(window).unwrap();
+        // Eine Epoche vor dem Reveal erstellt => älter als das Höchstalter des Guards
+        let order = OrderData::new("r1", "user", OrderSide::Buy, OrderType::Limit(100.0), 1.0, now - 600).signed_for_tests();
+        book.submit_commitment_at("user", order_commitment_hash(&order, b"n").unwrap(), now - 600).unwrap();
+
+        let guard = Arc::new(ClockSkewGuard::new(5_000));
+        let mut engine = MatchingEngine::new().with_commit_reveal(book).with_clock_guard(guard);
+        engine.reveal_order(order, b"n").unwrap();
+        assert_eq!(engine.order_book.buy_orders.len(), 1);
+    }
+
+    /// Settlement, das erst zurückkehrt, wenn auch der andere Markt im
+    /// Settlement angekommen ist (oder nach Timeout mit Fehler).
+    struct RendezvousEngine(Arc<(Mutex<usize>, std::sync::Condvar)>);
+
+    impl SettlementEngineTrait for RendezvousEngine {
+        fn finalize_trade(&mut self, _b: &str, _s: &str, _ba: &str, _qa: &str, _x: f64, _y: f64) -> Result<(), DexError> {
+            let (count, cv) = &*self.0;
+            let mut n = count.lock().unwrap();
+            *n += 1;
+            cv.notify_all();
+            let (n, timeout) = cv
+                .wait_timeout_while(n, std::time::Duration::from_secs(2), |n| *n < 2)
+                .unwrap();
+            if timeout.timed_out() && *n < 2 {
+                return Err(DexError::SettlementFailed("other market never ran concurrently".into()));
+            }
+            Ok(())
+        }
+    }
+
+    fn two_market_shards(mode: MatchingConcurrency) -> (MarketShards, MarketPair, MarketPair) {
+        let gate = Arc::new((Mutex::new(0usize), std::sync::Condvar::new()));
+        let mut shards = MarketShards::new(mode);
+        let btc = MarketPair::parse("BTC/USDT").unwrap();
+        let eth = MarketPair::parse("eth/usdc").unwrap();
+        for (pair, px) in [(&btc, 30_000.0), (&eth, 2_000.0)] {
+            let mut engine = MatchingEngine::new();
+            engine.settlement = Box::new(RendezvousEngine(gate.clone()));
+            shards.add_market(pair.clone(), engine);
+            let buy = OrderData::new(&format!("{}-b", pair.base), "alice", OrderSide::Buy, OrderType::Limit(px), 1.0, 0);
+            let sell = OrderData::new(&format!("{}-s", pair.base), "bob", OrderSide::Sell, OrderType::Limit(px), 1.0, 0);
+            shards.place_order(pair, buy.signed_for_tests()).unwrap();
+            shards.place_order(pair, sell.signed_for_tests()).unwrap();
+        }
+        (shards, btc, eth)
+    }
+
+    #[test]
+    fn test_markets_match_concurrently_per_market() {
+        let (shards, btc, eth) = two_market_shards(MatchingConcurrency::PerMarket);
+        let started = std::time::Instant::now();
+        let results = shards.process_all();
+        // Beide Settlements haben sich getroffen => liefen gleichzeitig
+        assert!(results.iter().all(|(_, r)| r.is_ok()), "{:?}", results);
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+
+        assert_eq!(shards.engine(&btc).unwrap().lock().unwrap().market, "BTC/USDT");
+        assert_eq!(shards.engine(&eth).unwrap().lock().unwrap().market, "ETH/USDC");
+        let fees = shards.fees.lock().unwrap();
+        assert_eq!(fees.trades, 2);
+        assert!((fees.totals["USDT"] - 30.0).abs() < 1e-9);
+        assert!((fees.totals["USDC"] - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_serial_mode_blocks_other_markets() {
+        let (shards, btc, eth) = two_market_shards(MatchingConcurrency::Serial);
+        let results: StdHashMap<MarketPair, Result<(), DexError>> = shards.process_all().into_iter().collect();
+        // BTC läuft zuerst allein und wartet vergeblich auf ETH
+        assert!(matches!(results[&btc], Err(DexError::SettlementFailed(_))));
+        assert!(results[&eth].is_ok());
+    }
+}
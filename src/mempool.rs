@@ -0,0 +1,402 @@
+///////////////////////////////////////////////////////////
+// my_dex/src/mempool.rs
+///////////////////////////////////////////////////////////
+//
+// Mempool für ausstehende Transaktionen.
+//
+//  - Aufnahme nur nach `Transaction::check` (Betrag, Signatur, Chain-ID,
+//    unverbrauchte Nonce) und wenn der
+//    Sender alle seine wartenden Transaktionen zusammen decken kann. Damit ist
+//    jede Teilmenge, die der Proposer auswählt, ebenfalls gedeckt.
+//  - Exakte Duplikate werden abgelehnt. Gleicher Sender + gleiche Tx-ID mit
+//    höherer Gebühr ersetzt die alte Transaktion (Replace-by-Fee), sofern die
+//    Gebühr um mindestens `min_rbf_bump_pct` steigt.
+//  - Begrenzt auf `max_txs` Einträge und `max_bytes`; ist der Pool voll, wird
+//    die billigste Transaktion verdrängt, falls die neue mehr zahlt.
+//  - Einträge älter als `ttl` fallen bei `evict_expired` heraus.
+//
+// `select_for_block` liefert die Transaktionen mit den höchsten Gebühren
+// (bei Gleichstand: früher eingetroffen zuerst) innerhalb der Block-Limits,
+// je Sender in Nonce-Reihenfolge und ohne doppelte Nonces.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::block::{BlockLimits, LedgerState, Transaction};
+use crate::error::DexError;
+
+#[derive(Debug, Clone)]
+pub struct MempoolConfig {
+    pub max_txs: usize,
+    pub max_bytes: usize,
+    pub ttl: Duration,
+    pub min_rbf_bump_pct: u64,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            max_txs: 10_000,
+            max_bytes: 8_000_000,
+            ttl: Duration::from_secs(30 * 60),
+            min_rbf_bump_pct: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertOutcome {
+    Added,
+    /// Ersetzt eine Transaktion mit gleicher (Sender, ID) und niedrigerer Gebühr
+    Replaced { old_fee: u64 },
+    /// Pool war voll, die billigste Transaktion wurde verdrängt
+    AddedWithEviction { evicted_id: u32 },
+}
+
+#[derive(Debug, Clone)]
+struct MempoolEntry {
+    tx: Transaction,
+    digest: [u8; 32],
+    size: usize,
+    seq: u64,
+    received: Instant,
+}
+
+type TxKey = (String, u32);
+
+pub struct Mempool {
+    config: MempoolConfig,
+    limits: BlockLimits,
+    entries: HashMap<TxKey, MempoolEntry>,
+    /// Summe aller wartenden Belastungen je Sender
+    pending_spend: HashMap<String, u64>,
+    total_bytes: usize,
+    next_seq: u64,
+}
+
+fn tx_digest(tx: &Transaction) -> Result<[u8; 32], DexError> {
+    let mut h = Sha256::new();
+    h.update(tx.signing_bytes()?);
+    Ok(h.finalize().into())
+}
+
+impl Mempool {
+    pub fn new(config: MempoolConfig) -> Self {
+        Self::with_limits(config, BlockLimits::default())
+    }
+
+    pub fn with_limits(config: MempoolConfig, limits: BlockLimits) -> Self {
+        Self {
+            config,
+            limits,
+            entries: HashMap::new(),
+            pending_spend: HashMap::new(),
+            total_bytes: 0,
+            next_seq: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    pub fn contains(&self, from: &str, id: u32) -> bool {
+        self.entries.contains_key(&(from.to_string(), id))
+    }
+
+    pub fn insert(&mut self, tx: Transaction, state: &dyn LedgerState) -> Result<InsertOutcome, DexError> {
+        self.insert_at(tx, state, Instant::now())
+    }
+
+    pub fn insert_at(
+        &mut self,
+        tx: Transaction,
+        state: &dyn LedgerState,
+        now: Instant,
+    ) -> Result<InsertOutcome, DexError> {
+        let reject = |reason: String| DexError::InvalidTransaction { tx_id: tx.id, reason };
+        tx.check(state, &self.limits)?;
+        let digest = tx_digest(&tx)?;
+        let size = serde_json::to_vec(&tx).map_err(|e| reject(e.to_string()))?.len();
+        if size > self.config.max_bytes {
+            return Err(reject(format!("size {} exceeds mempool capacity", size)));
+        }
+        let key: TxKey = (tx.from.clone(), tx.id);
+
+        // Duplikat / Replace-by-Fee
+        let replaced = match self.entries.get(&key) {
+            Some(old) if old.digest == digest => return Err(reject("duplicate transaction".into())),
+            Some(old) => {
+                let min_fee = old
+                    .tx
+                    .fee
+                    .saturating_add(old.tx.fee.saturating_mul(self.config.min_rbf_bump_pct) / 100)
+                    .max(old.tx.fee + 1);
+                if tx.fee < min_fee {
+                    return Err(reject(format!(
+                        "replacement fee {} below required {} (old fee {})",
+                        tx.fee, min_fee, old.tx.fee
+                    )));
+                }
+                Some(old.tx.clone())
+            }
+            None => None,
+        };
+
+        // Deckung über alle wartenden Transaktionen des Senders
+        let pending = self.pending_spend.get(&tx.from).copied().unwrap_or(0);
+        let pending = pending - replaced.as_ref().map(|t| t.total_spend()).unwrap_or(0);
+        let balance = state.balance(&tx.from);
+        if pending.saturating_add(tx.total_spend()) > balance {
+            return Err(reject(format!(
+                "{} has {} but pending spend would be {}",
+                tx.from,
+                balance,
+                pending.saturating_add(tx.total_spend())
+            )));
+        }
+
+        if let Some(old) = replaced {
+            self.remove(&old.from, old.id);
+            self.push(key, tx, digest, size, now);
+            return Ok(InsertOutcome::Replaced { old_fee: old.fee });
+        }
+
+        // Speicherlimit: billigste verdrängen, falls die neue mehr zahlt
+        let mut evicted_id = None;
+        while self.entries.len() >= self.config.max_txs || self.total_bytes + size > self.config.max_bytes {
+            let cheapest = self.lowest_priority().ok_or_else(|| reject("mempool full".into()))?;
+            let cheapest_fee = self.entries[&cheapest].tx.fee;
+            if cheapest_fee >= tx.fee {
+                return Err(reject(format!("mempool full, fee {} too low (min {})", tx.fee, cheapest_fee + 1)));
+            }
+            debug!("Mempool voll => verdränge {:?} (fee={})", cheapest, cheapest_fee);
+            evicted_id = Some(cheapest.1);
+            self.remove(&cheapest.0, cheapest.1);
+        }
+        self.push(key, tx, digest, size, now);
+        Ok(match evicted_id {
+            Some(id) => InsertOutcome::AddedWithEviction { evicted_id: id },
+            None => InsertOutcome::Added,
+        })
+    }
+
+    fn push(&mut self, key: TxKey, tx: Transaction, digest: [u8; 32], size: usize, now: Instant) {
+        *self.pending_spend.entry(tx.from.clone()).or_insert(0) += tx.total_spend();
+        self.total_bytes += size;
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.entries.insert(key, MempoolEntry { tx, digest, size, seq, received: now });
+    }
+
+    pub fn remove(&mut self, from: &str, id: u32) -> Option<Transaction> {
+        let entry = self.entries.remove(&(from.to_string(), id))?;
+        self.total_bytes -= entry.size;
+        if let Some(p) = self.pending_spend.get_mut(from) {
+            *p -= entry.tx.total_spend();
+            if *p == 0 {
+                self.pending_spend.remove(from);
+            }
+        }
+        Some(entry.tx)
+    }
+
+    /// Niedrigste Gebühr, bei Gleichstand die jüngste.
+    fn lowest_priority(&self) -> Option<TxKey> {
+        self.entries
+            .iter()
+            .min_by(|(_, a), (_, b)| a.tx.fee.cmp(&b.tx.fee).then(b.seq.cmp(&a.seq)))
+            .map(|(k, _)| k.clone())
+    }
+
+    /// Entfernt abgelaufene Transaktionen, gibt deren Anzahl zurück.
+    pub fn evict_expired(&mut self, now: Instant) -> usize {
+        let ttl = self.config.ttl;
+        let expired: Vec<TxKey> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| now.saturating_duration_since(e.received) > ttl)
+            .map(|(k, _)| k.clone())
+            .collect();
+        for (from, id) in &expired {
+            self.remove(from, *id);
+        }
+        expired.len()
+    }
+
+    /// Höchste Gebühren zuerst, innerhalb von Anzahl- und Byte-Limit.
+    /// Transaktionen eines Senders stehen in Nonce-Reihenfolge (sonst lehnt
+    /// `Block::validate` ab); bei doppelter Nonce zählt die teurere.
+    pub fn select_for_block(&self, limits: &BlockLimits) -> Vec<Transaction> {
+        let mut sorted: Vec<&MempoolEntry> = self.entries.values().collect();
+        sorted.sort_by(|a, b| b.tx.fee.cmp(&a.tx.fee).then(a.seq.cmp(&b.seq)));
+        let mut bytes = 0usize;
+        let mut out = Vec::new();
+        let mut nonces = HashSet::new();
+        for e in sorted {
+            if out.len() >= limits.max_transactions {
+                break;
+            }
+            if bytes + e.size > limits.max_bytes || nonces.contains(&(&e.tx.from, e.tx.nonce)) {
+                continue;
+            }
+            nonces.insert((&e.tx.from, e.tx.nonce));
+            bytes += e.size;
+            out.push(e.tx.clone());
+        }
+        // Plätze bleiben in Gebühren-Reihenfolge, je Sender neu nach Nonce belegt
+        let senders: Vec<String> = out.iter().map(|t| t.from.clone()).collect();
+        let mut queues: HashMap<String, VecDeque<Transaction>> = HashMap::new();
+        for tx in out {
+            queues.entry(tx.from.clone()).or_default().push_back(tx);
+        }
+        for queue in queues.values_mut() {
+            queue.make_contiguous().sort_by_key(|t| t.nonce);
+        }
+        senders.iter().filter_map(|s| queues.get_mut(s)?.pop_front()).collect()
+    }
+
+    /// Nach Aufnahme in einen Block aus dem Pool nehmen.
+    pub fn remove_included(&mut self, txs: &[Transaction]) {
+        for tx in txs {
+            self.remove(&tx.from, tx.id);
+        }
+    }
+}
+
+impl Default for Mempool {
+    fn default() -> Self {
+        Self::new(MempoolConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Keypair, PublicKey};
+    use rand::rngs::OsRng;
+
+    struct Ledger {
+        key: PublicKey,
+    }
+
+    impl LedgerState for Ledger {
+        fn balance(&self, _account: &str) -> u64 {
+            1_000
+        }
+        fn public_key(&self, _account: &str) -> Option<PublicKey> {
+            Some(self.key)
+        }
+        fn chain_id(&self) -> &str {
+            "test"
+        }
+        fn next_nonce(&self, _account: &str) -> u64 {
+            1
+        }
+    }
+
+    fn tx(kp: &Keypair, from: &str, id: u32, fee: u64) -> Transaction {
+        let mut t = Transaction::new(id, from, "bob", 10).with_fee(fee).with_nonce(id as u64).with_chain_id("test");
+        t.sign(kp).unwrap();
+        t
+    }
+
+    fn setup() -> (Keypair, Ledger) {
+        let kp = Keypair::generate(&mut OsRng {});
+        let ledger = Ledger { key: kp.public };
+        (kp, ledger)
+    }
+
+    #[test]
+    fn test_fee_priority_ordering() {
+        let (kp, ledger) = setup();
+        let mut pool = Mempool::default();
+        pool.insert(tx(&kp, "a", 1, 5), &ledger).unwrap();
+        pool.insert(tx(&kp, "b", 2, 50), &ledger).unwrap();
+        pool.insert(tx(&kp, "c", 3, 20), &ledger).unwrap();
+        pool.insert(tx(&kp, "d", 4, 20), &ledger).unwrap();
+
+        let limits = BlockLimits { max_transactions: 3, ..Default::default() };
+        let picked: Vec<u32> = pool.select_for_block(&limits).iter().map(|t| t.id).collect();
+        assert_eq!(picked, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_rbf_replaces_lower_fee() {
+        let (kp, ledger) = setup();
+        let mut pool = Mempool::default();
+        pool.insert(tx(&kp, "a", 1, 100), &ledger).unwrap();
+
+        // Exaktes Duplikat und zu kleiner Aufschlag (< 10 %) werden abgelehnt
+        assert!(pool.insert(tx(&kp, "a", 1, 100), &ledger).is_err());
+        assert!(pool.insert(tx(&kp, "a", 1, 105), &ledger).is_err());
+
+        let outcome = pool.insert(tx(&kp, "a", 1, 120), &ledger).unwrap();
+        assert_eq!(outcome, InsertOutcome::Replaced { old_fee: 100 });
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.select_for_block(&BlockLimits::default())[0].fee, 120);
+    }
+
+    #[test]
+    fn test_bounded_pool_evicts_cheapest() {
+        let (kp, ledger) = setup();
+        let cfg = MempoolConfig { max_txs: 2, ..Default::default() };
+        let mut pool = Mempool::new(cfg);
+        pool.insert(tx(&kp, "a", 1, 5), &ledger).unwrap();
+        pool.insert(tx(&kp, "b", 2, 7), &ledger).unwrap();
+        assert!(pool.insert(tx(&kp, "c", 3, 5), &ledger).is_err());
+        let outcome = pool.insert(tx(&kp, "c", 3, 9), &ledger).unwrap();
+        assert_eq!(outcome, InsertOutcome::AddedWithEviction { evicted_id: 1 });
+        assert!(!pool.contains("a", 1));
+    }
+
+    #[test]
+    fn test_expired_and_overspending_rejected() {
+        let (kp, ledger) = setup();
+        let cfg = MempoolConfig { ttl: Duration::from_secs(5), ..Default::default() };
+        let mut pool = Mempool::new(cfg);
+        let t0 = Instant::now();
+        pool.insert_at(tx(&kp, "a", 1, 1), &ledger, t0).unwrap();
+        assert_eq!(pool.evict_expired(t0 + Duration::from_secs(10)), 1);
+        assert!(pool.is_empty());
+
+        // Saldo 1000: Summe der wartenden Belastungen darf nicht darüber liegen
+        let mut big = Transaction::new(2, "a", "bob", 995).with_fee(1).with_nonce(2).with_chain_id("test");
+        big.sign(&kp).unwrap();
+        pool.insert(big, &ledger).unwrap();
+        assert!(pool.insert(tx(&kp, "a", 3, 1), &ledger).is_err());
+    }
+
+    #[test]
+    fn test_sender_nonces_ordered_and_replays_rejected() {
+        let (kp, ledger) = setup();
+        let mut pool = Mempool::default();
+        // Nonce 0 ist laut Ledger verbraucht
+        assert!(pool.insert(tx(&kp, "a", 0, 5), &ledger).is_err());
+
+        pool.insert(tx(&kp, "a", 1, 5), &ledger).unwrap();
+        pool.insert(tx(&kp, "a", 2, 50), &ledger).unwrap();
+        pool.insert(tx(&kp, "b", 3, 20), &ledger).unwrap();
+        let picked: Vec<u32> = pool.select_for_block(&BlockLimits::default()).iter().map(|t| t.id).collect();
+        // Gebühr allein ergäbe 2, 3, 1 => "a" muss aber mit Nonce 1 beginnen
+        assert_eq!(picked, vec![1, 3, 2]);
+
+        // Zweite Transaktion mit derselben Nonce: nur die teurere kommt in den Block
+        let mut twin = Transaction::new(9, "a", "bob", 10).with_fee(60).with_nonce(2).with_chain_id("test");
+        twin.sign(&kp).unwrap();
+        pool.insert(twin, &ledger).unwrap();
+        let picked: Vec<u32> = pool.select_for_block(&BlockLimits::default()).iter().map(|t| t.id).collect();
+        assert_eq!(picked, vec![1, 3, 9]);
+    }
+}
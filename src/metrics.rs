@@ -1,90 +1,264 @@
 // src/metrics.rs
 //
-// Prometheus-Client-Integration. 
-// Startet einen HTTP-Server auf /metrics-Endpunkt.
+// Prometheus-Client-Integration.
+// Alle Metriken hängen an REGISTRY und werden über `/metrics` im
+// Text-Exposition-Format ausgeliefert (hyper: serve_metrics, axum: metrics_router).
 
 use lazy_static::lazy_static;
 use prometheus::{
-    IntCounter, IntGauge, Registry, Encoder, TextEncoder,
-    register_int_counter, register_int_gauge
+    Counter, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    Encoder, TextEncoder,
 };
 use hyper::{Body, Request, Response, Server};
 use hyper::service::{make_service_fn, service_fn};
 use std::net::SocketAddr;
+use std::sync::Once;
 use tracing::{info, error};
 
+/// Gemeinsame Buckets (Sekunden) für die Latenz-Histogramme.
+pub const LATENCY_BUCKETS: &[f64] = &[0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
 lazy_static! {
     pub static ref REGISTRY: Registry = Registry::new();
 
     // Orders
-    pub static ref ORDER_COUNT: IntCounter = register_int_counter!(
+    pub static ref ORDER_COUNT: IntCounter = IntCounter::new(
         "dex_order_total",
         "Total number of Orders created"
     ).unwrap();
 
-    pub static ref ACTIVE_SWAPS: IntGauge = register_int_gauge!(
+    pub static ref TRADES_MATCHED: IntCounter = IntCounter::new(
+        "dex_trades_matched_total",
+        "Anzahl gematchter Trades"
+    ).unwrap();
+
+    pub static ref MATCH_LATENCY: Histogram = Histogram::with_opts(
+        HistogramOpts::new("dex_match_latency_seconds", "Dauer eines match_orders-Durchlaufs")
+            .buckets(vec![0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0])
+    ).unwrap();
+
+    /// Dauer des gesamten Matchings einer Order, nach `OrderType` (market/limit/stop/stop_limit).
+    pub static ref MATCH_DURATION_BY_ORDER_TYPE: HistogramVec = HistogramVec::new(
+        HistogramOpts::new("dex_match_duration_seconds", "Dauer des Matchings einer Order je Order-Typ")
+            .buckets(LATENCY_BUCKETS.to_vec()),
+        &["order_type"]
+    ).unwrap();
+
+    pub static ref ACTIVE_SWAPS: IntGauge = IntGauge::new(
         "dex_active_swaps",
         "Number of active Atomic Swaps"
     ).unwrap();
 
+    // Netzwerk / DHT
+    pub static ref ACTIVE_PEERS: IntGauge = IntGauge::new(
+        "dex_active_peers",
+        "Anzahl bekannter Peers in der Kademlia-Routing-Tabelle"
+    ).unwrap();
+
+    pub static ref DHT_BUCKET_OCCUPANCY: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("dex_dht_bucket_occupancy", "Einträge pro Kademlia-Bucket"),
+        &["bucket"]
+    ).unwrap();
+
+    pub static ref DHT_LOOKUP_DURATION: Histogram = Histogram::with_opts(
+        HistogramOpts::new("dex_dht_lookup_duration_seconds", "Dauer eines Kademlia find_node-Lookups")
+            .buckets(LATENCY_BUCKETS.to_vec())
+    ).unwrap();
+
+    /// Noise-Handshake-Dauer, nach Rolle (initiator/responder).
+    pub static ref NOISE_HANDSHAKE_DURATION: HistogramVec = HistogramVec::new(
+        HistogramOpts::new("dex_noise_handshake_duration_seconds", "Dauer eines Noise-Handshakes")
+            .buckets(LATENCY_BUCKETS.to_vec()),
+        &["role"]
+    ).unwrap();
+
+    /// Durchgeführte Noise-Rekeys, nach Richtung (outgoing/incoming).
+    pub static ref NOISE_REKEYS: IntCounterVec = IntCounterVec::new(
+        Opts::new("dex_noise_rekeys_total", "Rotierte Noise-Transportschlüssel"),
+        &["direction"]
+    ).unwrap();
+
+    /// Verworfene Gossip-Nachrichten (queue_drop_oldest|queue_reject|cache_evicted).
+    pub static ref GOSSIP_DROPPED: IntCounterVec = IntCounterVec::new(
+        Opts::new("dex_gossip_dropped_total", "Verworfene Gossip-Nachrichten"),
+        &["reason"]
+    ).unwrap();
+
+    /// 1 = IPFS-Daemon zuletzt erreichbar, 0 = Fallback auf lokale Queue.
+    pub static ref IPFS_AVAILABLE: IntGauge = IntGauge::new(
+        "dex_ipfs_available",
+        "IPFS-Daemon erreichbar (1) oder im lokalen Fallback (0)"
+    ).unwrap();
+
+    pub static ref IPFS_PENDING_UPLOADS: IntGauge = IntGauge::new(
+        "dex_ipfs_pending_uploads",
+        "Lokal eingereihte, noch nicht nach IPFS hochgeladene Inhalte"
+    ).unwrap();
+
+    /// Höhe der eigenen Kette (Spitze nach Fork-Choice).
+    pub static ref CONSENSUS_HEIGHT: IntGauge = IntGauge::new(
+        "dex_consensus_height",
+        "Höhe der lokalen Kettenspitze"
+    ).unwrap();
+
+    /// Höchster von Peers gesehener Block-Index; Differenz zu CONSENSUS_HEIGHT = Rückstand.
+    pub static ref CONSENSUS_PEER_HEIGHT: IntGauge = IntGauge::new(
+        "dex_consensus_peer_height",
+        "Höchster von Peers gemeldeter Block-Index"
+    ).unwrap();
+
+    /// Abgelehnte P2P-Nachrichten (too_large|limit_exceeded|malformed|quarantined).
+    pub static ref P2P_MESSAGES_REJECTED: IntCounterVec = IntCounterVec::new(
+        Opts::new("dex_p2p_messages_rejected_total", "Abgelehnte P2P-Nachrichten"),
+        &["reason"]
+    ).unwrap();
+
+    /// Vom Rate-Limit verworfene Nachrichten, nach Ebene (peer/subnet).
+    pub static ref RATE_LIMIT_DROPS: IntCounterVec = IntCounterVec::new(
+        Opts::new("dex_rate_limit_drops_total", "Vom Rate-Limit verworfene Nachrichten"),
+        &["scope"]
+    ).unwrap();
+
+    pub static ref RATE_LIMIT_ADAPTIVE_ACTIVE: IntGauge = IntGauge::new(
+        "dex_rate_limit_adaptive_active",
+        "1, solange der adaptive DDoS-Modus aktiv ist"
+    ).unwrap();
+
+    /// Peers je Land / ASN in der Peer-Tabelle (kind = country|asn).
+    pub static ref PEER_DIVERSITY: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("dex_peer_diversity", "Peers je Land bzw. ASN"),
+        &["kind", "value"]
+    ).unwrap();
+
+    // Fees
+    pub static ref FEE_PAYOUTS_TOTAL: Counter = Counter::new(
+        "dex_fee_payouts_total",
+        "Summe aller aus dem FeePool ausgezahlten Gebühren"
+    ).unwrap();
+
     // Node-Starts (wenn Node re-launched etc.)
-    pub static ref DEX_NODE_STARTS: IntCounter = register_int_counter!(
+    pub static ref DEX_NODE_STARTS: IntCounter = IntCounter::new(
         "dex_node_starts_total",
         "Wie oft ein Node-Prozess startete"
     ).unwrap();
 
     // CRDT Merges
-    pub static ref CRDT_MERGE_COUNT: IntCounter = register_int_counter!(
+    pub static ref CRDT_MERGE_COUNT: IntCounter = IntCounter::new(
         "dex_crdt_merge_total",
         "Wie oft CRDT-merge_remote aufgerufen wurde"
     ).unwrap();
 
     // HTLC / AtomicSwap Kennzahlen
-    pub static ref HTLC_REDEEM_COUNT: IntCounter = register_int_counter!(
+    pub static ref HTLC_REDEEM_COUNT: IntCounter = IntCounter::new(
         "dex_htlc_redeem_total",
         "Anzahl Redeems in HTLC"
     ).unwrap();
 
-    pub static ref HTLC_REFUND_COUNT: IntCounter = register_int_counter!(
+    pub static ref HTLC_REFUND_COUNT: IntCounter = IntCounter::new(
         "dex_htlc_refund_total",
         "Anzahl Refunds in HTLC"
     ).unwrap();
 
-    pub static ref SWAP_SELLER_REDEEM_COUNT: IntCounter = register_int_counter!(
+    pub static ref SWAP_SELLER_REDEEM_COUNT: IntCounter = IntCounter::new(
         "dex_swap_seller_redeem_total",
         "Wie oft Seller redeem auf AtomicSwap"
     ).unwrap();
 
-    pub static ref SWAP_BUYER_REDEEM_COUNT: IntCounter = register_int_counter!(
+    pub static ref SWAP_BUYER_REDEEM_COUNT: IntCounter = IntCounter::new(
         "dex_swap_buyer_redeem_total",
         "Wie oft Buyer redeem auf AtomicSwap"
     ).unwrap();
 
-    pub static ref SWAP_REFUND_COUNT: IntCounter = register_int_counter!(
+    pub static ref SWAP_REFUND_COUNT: IntCounter = IntCounter::new(
         "dex_swap_refund_total",
         "Wie oft AtomicSwap refund ausgeführt"
     ).unwrap();
 
     // partial fill
-    pub static ref PARTIAL_FILL_COUNT: IntCounter = register_int_counter!(
+    pub static ref PARTIAL_FILL_COUNT: IntCounter = IntCounter::new(
         "dex_partial_fill_total",
         "Wie oft eine Partial-Fill Operation ausgeführt wurde"
     ).unwrap();
+
+    // WebSocket-Preisfeeds
+    pub static ref PRICE_FEED_CONNECTED: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("dex_price_feed_connected", "1 = WebSocket-Preisfeed verbunden"),
+        &["feed"]
+    ).unwrap();
+    pub static ref PRICE_FEED_STALE: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("dex_price_feed_stale", "1 = kein Update innerhalb des Staleness-Fensters"),
+        &["feed"]
+    ).unwrap();
+    pub static ref PRICE_FEED_RECONNECTS: IntCounterVec = IntCounterVec::new(
+        Opts::new("dex_price_feed_reconnects_total", "Reconnect-Versuche pro Preisfeed"),
+        &["feed"]
+    ).unwrap();
 }
 
+static REGISTER: Once = Once::new();
+
+/// Registriert alle Metriken in REGISTRY. Mehrfache Aufrufe sind harmlos.
 pub fn register_metrics() {
-    REGISTRY.register(Box::new(ORDER_COUNT.clone())).unwrap();
-    REGISTRY.register(Box::new(ACTIVE_SWAPS.clone())).unwrap();
-    REGISTRY.register(Box::new(DEX_NODE_STARTS.clone())).unwrap();
-    REGISTRY.register(Box::new(CRDT_MERGE_COUNT.clone())).unwrap();
-
-    REGISTRY.register(Box::new(HTLC_REDEEM_COUNT.clone())).unwrap();
-    REGISTRY.register(Box::new(HTLC_REFUND_COUNT.clone())).unwrap();
-    REGISTRY.register(Box::new(SWAP_SELLER_REDEEM_COUNT.clone())).unwrap();
-    REGISTRY.register(Box::new(SWAP_BUYER_REDEEM_COUNT.clone())).unwrap();
-    REGISTRY.register(Box::new(SWAP_REFUND_COUNT.clone())).unwrap();
-
-    REGISTRY.register(Box::new(PARTIAL_FILL_COUNT.clone())).unwrap();
+    REGISTER.call_once(|| {
+        REGISTRY.register(Box::new(ORDER_COUNT.clone())).unwrap();
+        REGISTRY.register(Box::new(TRADES_MATCHED.clone())).unwrap();
+        REGISTRY.register(Box::new(MATCH_LATENCY.clone())).unwrap();
+        REGISTRY.register(Box::new(MATCH_DURATION_BY_ORDER_TYPE.clone())).unwrap();
+        REGISTRY.register(Box::new(ACTIVE_SWAPS.clone())).unwrap();
+        REGISTRY.register(Box::new(ACTIVE_PEERS.clone())).unwrap();
+        REGISTRY.register(Box::new(DHT_BUCKET_OCCUPANCY.clone())).unwrap();
+        REGISTRY.register(Box::new(DHT_LOOKUP_DURATION.clone())).unwrap();
+        REGISTRY.register(Box::new(NOISE_HANDSHAKE_DURATION.clone())).unwrap();
+        REGISTRY.register(Box::new(RATE_LIMIT_DROPS.clone())).unwrap();
+        REGISTRY.register(Box::new(RATE_LIMIT_ADAPTIVE_ACTIVE.clone())).unwrap();
+        REGISTRY.register(Box::new(PEER_DIVERSITY.clone())).unwrap();
+        REGISTRY.register(Box::new(FEE_PAYOUTS_TOTAL.clone())).unwrap();
+        REGISTRY.register(Box::new(DEX_NODE_STARTS.clone())).unwrap();
+        REGISTRY.register(Box::new(CRDT_MERGE_COUNT.clone())).unwrap();
+
+        REGISTRY.register(Box::new(HTLC_REDEEM_COUNT.clone())).unwrap();
+        REGISTRY.register(Box::new(HTLC_REFUND_COUNT.clone())).unwrap();
+        REGISTRY.register(Box::new(SWAP_SELLER_REDEEM_COUNT.clone())).unwrap();
+        REGISTRY.register(Box::new(SWAP_BUYER_REDEEM_COUNT.clone())).unwrap();
+        REGISTRY.register(Box::new(SWAP_REFUND_COUNT.clone())).unwrap();
+
+        REGISTRY.register(Box::new(PARTIAL_FILL_COUNT.clone())).unwrap();
+
+        REGISTRY.register(Box::new(PRICE_FEED_CONNECTED.clone())).unwrap();
+        REGISTRY.register(Box::new(PRICE_FEED_STALE.clone())).unwrap();
+        REGISTRY.register(Box::new(PRICE_FEED_RECONNECTS.clone())).unwrap();
+        REGISTRY.register(Box::new(NOISE_REKEYS.clone())).unwrap();
+        REGISTRY.register(Box::new(GOSSIP_DROPPED.clone())).unwrap();
+        REGISTRY.register(Box::new(P2P_MESSAGES_REJECTED.clone())).unwrap();
+        REGISTRY.register(Box::new(IPFS_AVAILABLE.clone())).unwrap();
+        REGISTRY.register(Box::new(IPFS_PENDING_UPLOADS.clone())).unwrap();
+        REGISTRY.register(Box::new(CONSENSUS_HEIGHT.clone())).unwrap();
+        REGISTRY.register(Box::new(CONSENSUS_PEER_HEIGHT.clone())).unwrap();
+    });
+}
+
+/// Alle Metriken im Prometheus-Textformat.
+pub fn gather_metrics_text() -> String {
+    register_metrics();
+    let metric_families = REGISTRY.gather();
+    let mut buf = Vec::new();
+    let encoder = TextEncoder::new();
+    if let Err(e) = encoder.encode(&metric_families, &mut buf) {
+        error!("Metrics encode error: {:?}", e);
+    }
+    String::from_utf8(buf).unwrap_or_default()
+}
+
+async fn metrics_handler() -> impl axum::response::IntoResponse {
+    (
+        [(axum::http::header::CONTENT_TYPE, TextEncoder::new().format_type().to_string())],
+        gather_metrics_text(),
+    )
+}
+
+/// Router mit `/metrics`, zum Einhängen in einen axum-Server.
+pub fn metrics_router() -> axum::Router {
+    axum::Router::new().route("/metrics", axum::routing::get(metrics_handler))
 }
 
 pub async fn serve_metrics(addr: SocketAddr) {
@@ -93,11 +267,7 @@ pub async fn serve_metrics(addr: SocketAddr) {
     let svc = make_service_fn(|_conn| async {
         Ok::<_, hyper::Error>(service_fn(|req: Request<Body>| async move {
             if req.uri().path() == "/metrics" {
-                let metric_families = REGISTRY.gather();
-                let mut buf = Vec::new();
-                let encoder = TextEncoder::new();
-                encoder.encode(&metric_families, &mut buf).unwrap();
-                Ok::<_, hyper::Error>(Response::new(Body::from(buf)))
+                Ok::<_, hyper::Error>(Response::new(Body::from(gather_metrics_text())))
             } else {
                 Ok::<_, hyper::Error>(
                     Response::builder().status(404).body(Body::from("Not Found")).unwrap()
@@ -111,3 +281,95 @@ pub async fn serve_metrics(addr: SocketAddr) {
         error!("Metrics server error: {:?}", e);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body as AxumBody;
+    use axum::http::{Request as AxumRequest, StatusCode};
+    use tower::ServiceExt;
+
+    /// Minimaler Parser für das Textformat: jede Nicht-Kommentarzeile muss
+    /// `name{labels} value` sein. Liefert (name, value)-Paare.
+    fn parse_exposition(text: &str) -> Vec<(String, f64)> {
+        text.lines()
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|l| {
+                let (series, value) = l.rsplit_once(' ').expect("sample line has a value");
+                let name = series.split('{').next().unwrap().to_string();
+                let value: f64 = value.parse().expect("sample value is numeric");
+                (name, value)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_exports_order_counter() {
+        ORDER_COUNT.inc();
+        MATCH_LATENCY.observe(0.002);
+
+        let response = metrics_router()
+            .oneshot(AxumRequest::builder().uri("/metrics").body(AxumBody::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let samples = parse_exposition(std::str::from_utf8(&bytes).unwrap());
+        let orders = samples.iter().find(|(n, _)| n == "dex_order_total").expect("order counter exported");
+        assert!(orders.1 >= 1.0);
+        assert!(samples.iter().any(|(n, _)| n == "dex_match_latency_seconds_bucket"));
+    }
+
+    /// Kumulierte Bucket-Zähler eines (gelabelten) Histogramms aus REGISTRY.
+    fn bucket_counts(name: &str, label: Option<(&str, &str)>) -> Vec<(f64, u64)> {
+        register_metrics();
+        let family = REGISTRY
+            .gather()
+            .into_iter()
+            .find(|mf| mf.get_name() == name)
+            .unwrap_or_else(|| panic!("{} not registered", name));
+        let metric = family
+            .get_metric()
+            .iter()
+            .find(|m| match label {
+                Some((k, v)) => m.get_label().iter().any(|l| l.get_name() == k && l.get_value() == v),
+                None => true,
+            })
+            .expect("metric with label")
+            .clone();
+        metric
+            .get_histogram()
+            .get_bucket()
+            .iter()
+            .map(|b| (b.get_upper_bound(), b.get_cumulative_count()))
+            .collect()
+    }
+
+    fn count_at(buckets: &[(f64, u64)], upper: f64) -> u64 {
+        buckets.iter().find(|(u, _)| (*u - upper).abs() < 1e-12).map(|(_, c)| *c).unwrap()
+    }
+
+    #[test]
+    fn test_latency_histogram_buckets() {
+        let stop = MATCH_DURATION_BY_ORDER_TYPE.with_label_values(&["stop_test"]);
+        stop.observe(0.0002);
+        stop.observe(0.003);
+        stop.observe(0.2);
+        let b = bucket_counts("dex_match_duration_seconds", Some(("order_type", "stop_test")));
+        assert_eq!(count_at(&b, 0.0005), 1);
+        assert_eq!(count_at(&b, 0.005), 2);
+        assert_eq!(count_at(&b, 0.5), 3);
+
+        let initiator = NOISE_HANDSHAKE_DURATION.with_label_values(&["initiator_test"]);
+        initiator.observe(0.04);
+        let b = bucket_counts("dex_noise_handshake_duration_seconds", Some(("role", "initiator_test")));
+        assert_eq!(count_at(&b, 0.01), 0);
+        assert_eq!(count_at(&b, 0.05), 1);
+
+        let before = DHT_LOOKUP_DURATION.get_sample_count();
+        DHT_LOOKUP_DURATION.observe(0.3);
+        DHT_LOOKUP_DURATION.observe(0.7);
+        assert_eq!(DHT_LOOKUP_DURATION.get_sample_count() - before, 2);
+    }
+}
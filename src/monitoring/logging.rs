@@ -3,8 +3,14 @@
 ///////////////////////////////////////////
 
 use chrono::{DateTime, Utc};
+use once_cell::sync::OnceCell;
+use std::collections::VecDeque;
 use std::sync::{Mutex, Arc};
 
+/// Standardgröße des Ringpuffers; ältere Einträge fallen heraus
+/// (dauerhaft bleiben sie im tracing-Backend).
+pub const DEFAULT_LOG_CAPACITY: usize = 10_000;
+
 /// Struktur, die einen Log-Eintrag repr�sentiert.
 #[derive(Debug, Clone)]
 pub struct LogEntry {
@@ -15,26 +21,41 @@ pub struct LogEntry {
 
 /// Ein einfacher, thread-sicherer Logger, der Log-Eintr�ge sammelt.
 pub struct Logger {
-    logs: Mutex<Vec<LogEntry>>,
+    logs: Mutex<VecDeque<LogEntry>>,
+    capacity: usize,
 }
 
 impl Logger {
     /// Erzeugt einen neuen Logger.
     pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_LOG_CAPACITY)
+    }
+
+    /// Logger mit höchstens `capacity` Einträgen (mindestens 1).
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
         Logger {
-            logs: Mutex::new(Vec::new()),
+            logs: Mutex::new(VecDeque::with_capacity(capacity.min(1024))),
+            capacity,
         }
     }
 
     /// F�gt einen neuen Log-Eintrag hinzu.
+    /// Der Eintrag wird zus�tzlich als `tracing`-Event (Target `dex_event_log`)
+    /// ausgegeben, damit der In-Memory-Puffer und das tracing-Backend dieselben
+    /// Ereignisse sehen.
     pub fn log_event(&self, user_type: &str, event: &str) {
+        tracing::info!(target: "dex_event_log", user_type = %user_type, "{}", event);
         let log_entry = LogEntry {
             timestamp: Utc::now(),
             user_type: user_type.to_string(),
             event: event.to_string(),
         };
         let mut logs = self.logs.lock().unwrap();
-        logs.push(log_entry);
+        if logs.len() >= self.capacity {
+            logs.pop_front();
+        }
+        logs.push_back(log_entry);
     }
 
     /// Gibt alle Log-Eintr�ge f�r einen bestimmten Nutzer-Typ zur�ck.
@@ -49,11 +70,41 @@ impl Logger {
     /// Gibt alle Log-Eintr�ge zur�ck (f�r Admins etc.).
     pub fn get_all_logs(&self) -> Vec<LogEntry> {
         let logs = self.logs.lock().unwrap();
-        logs.clone()
+        logs.iter().cloned().collect()
     }
 }
 
-/// Erzeuge einen globalen Logger als Arc, damit er in verschiedenen Modulen genutzt werden kann.
+static GLOBAL_LOGGER: OnceCell<Arc<Logger>> = OnceCell::new();
+
+/// Liefert den prozessweiten Logger. Alle Aufrufe teilen sich dieselbe Instanz,
+/// sodass Eintr�ge aus verschiedenen Modulen zusammenlaufen.
 pub fn get_global_logger() -> Arc<Logger> {
-    Arc::new(Logger::new())
+    GLOBAL_LOGGER.get_or_init(|| Arc::new(Logger::new())).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_global_logger_is_shared() {
+        let a = get_global_logger();
+        let b = get_global_logger();
+        assert!(Arc::ptr_eq(&a, &b));
+
+        a.log_event("singleton_test", "von a geschrieben");
+        let seen_by_b = b.get_logs_for_user("singleton_test");
+        assert_eq!(seen_by_b.len(), 1);
+        assert_eq!(seen_by_b[0].event, "von a geschrieben");
+    }
+
+    #[test]
+    fn test_ring_buffer_drops_oldest() {
+        let logger = Logger::with_capacity(3);
+        for i in 0..5 {
+            logger.log_event("trader", &format!("event {}", i));
+        }
+        let events: Vec<String> = logger.get_all_logs().into_iter().map(|e| e.event).collect();
+        assert_eq!(events, ["event 2", "event 3", "event 4"]);
+    }
 }
@@ -0,0 +1,292 @@
+///////////////////////////////////////////////////////////
+// my_dex/src/network/address_book.rs
+///////////////////////////////////////////////////////////
+//
+// Persistentes Adressbuch für die Peer-Verwaltung.
+//
+// Die Kademlia-RoutingTable ist flüchtig; gute Peers gehen bei jedem
+// Neustart verloren. Das Adressbuch merkt sich je NodeId die zuletzt
+// bekannte Adresse und Verbindungsqualität:
+//   - RTT (EWMA über Handshake-Dauern)
+//   - Erfolge/Fehlschläge beim Dial => Zuverlässigkeit
+//   - aufsummierte Verbindungsdauer (Uptime)
+//   - last_seen
+// Daraus ergibt sich ein Score, nach dem gewählt wird, wen wir zuerst
+// anwählen und womit die DHT beim Start befüllt wird (vor Bootstrap-Nodes).
+//
+// Layout (DexDB):
+//   address_book/<hex(node_id)> => PeerRecord
+///////////////////////////////////////////////////////////
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::error::DexError;
+use crate::kademlia::kademlia_service::{NodeId, RoutingTable};
+use crate::storage::db_layer::DexDB;
+use crate::utils::lock::LockRecover;
+
+const BOOK_PREFIX: &str = "address_book/";
+
+/// Gewicht eines neuen RTT-Messwerts im gleitenden Mittel.
+const RTT_EWMA_ALPHA: f64 = 0.2;
+/// Nach dieser Zeit ohne Kontakt halbiert sich der Score.
+const FRESHNESS_HALF_LIFE_SECS: f64 = 7.0 * 86_400.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerRecord {
+    pub node_id: NodeId,
+    pub address: SocketAddr,
+    /// Gleitendes Mittel der Handshake-RTT in ms (None => nie gemessen)
+    pub rtt_ms: Option<f64>,
+    pub successes: u64,
+    pub failures: u64,
+    /// Summe aller Verbindungsdauern
+    pub uptime_secs: u64,
+    pub last_seen: u64,
+}
+
+impl PeerRecord {
+    fn new(node_id: NodeId, address: SocketAddr, now: u64) -> Self {
+        Self { node_id, address, rtt_ms: None, successes: 0, failures: 0, uptime_secs: 0, last_seen: now }
+    }
+
+    /// Erfolgsquote mit Laplace-Glättung (unbekannt => 0.5).
+    pub fn success_rate(&self) -> f64 {
+        (self.successes as f64 + 1.0) / ((self.successes + self.failures) as f64 + 2.0)
+    }
+
+    /// Höher = zuerst anwählen.
+    pub fn score(&self, now: u64) -> f64 {
+        let latency = match self.rtt_ms {
+            Some(rtt) => 1.0 / (1.0 + rtt / 200.0),
+            None => 0.5,
+        };
+        let uptime = 1.0 + (self.uptime_secs as f64 / 86_400.0).min(1.0) * 0.5;
+        let age = now.saturating_sub(self.last_seen) as f64;
+        let freshness = 0.5f64.powf(age / FRESHNESS_HALF_LIFE_SECS);
+        self.success_rate() * latency * uptime * freshness
+    }
+}
+
+pub type SharedAddressBook = Arc<Mutex<AddressBook>>;
+
+/// Bootstrap-Eintrag aus der Config: `hex-node-id@ip:port`.
+pub fn parse_bootstrap_peer(entry: &str) -> Result<(NodeId, SocketAddr), String> {
+    let (id_hex, addr) = entry
+        .split_once('@')
+        .ok_or_else(|| format!("`{}` is not of the form node_id@ip:port", entry))?;
+    let bytes = hex::decode(id_hex.trim()).map_err(|e| format!("`{}`: node_id is not hex: {}", entry, e))?;
+    let id: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| format!("`{}`: node_id must be 32 bytes", entry))?;
+    let addr = addr
+        .trim()
+        .parse::<SocketAddr>()
+        .map_err(|_| format!("`{}`: `{}` is not a socket address", entry, addr))?;
+    Ok((NodeId(id), addr))
+}
+
+pub struct AddressBook {
+    db: Option<Arc<Mutex<DexDB>>>,
+    peers: HashMap<NodeId, PeerRecord>,
+}
+
+impl AddressBook {
+    /// Nur im Speicher (Tests).
+    pub fn in_memory() -> Self {
+        Self { db: None, peers: HashMap::new() }
+    }
+
+    /// Lädt alle gespeicherten Peers aus `db`; Änderungen werden sofort persistiert.
+    pub fn load(db: Arc<Mutex<DexDB>>) -> Result<Self, DexError> {
+        let mut peers = HashMap::new();
+        for (key, bytes) in db.lock_recover().list_entries_with_prefix(BOOK_PREFIX)? {
+            match bincode::deserialize::<PeerRecord>(&bytes) {
+                Ok(rec) => {
+                    peers.insert(rec.node_id.clone(), rec);
+                }
+                Err(e) => warn!("Adressbuch => {} nicht lesbar, übersprungen: {:?}", key, e),
+            }
+        }
+        Ok(Self { db: Some(db), peers })
+    }
+
+    fn persist(&self, rec: &PeerRecord) {
+        if let Some(db) = &self.db {
+            let key = format!("{}{}", BOOK_PREFIX, hex::encode(rec.node_id.0));
+            if let Err(e) = db.lock_recover().store_struct(&key, rec) {
+                warn!("Adressbuch => Peer {} nicht gespeichert: {:?}", rec.address, e);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    pub fn get(&self, node_id: &NodeId) -> Option<&PeerRecord> {
+        self.peers.get(node_id)
+    }
+
+    /// Peer gesehen (z. B. via Kademlia/mDNS); aktualisiert Adresse und last_seen.
+    pub fn observe(&mut self, node_id: NodeId, address: SocketAddr, now: u64) {
+        let rec = self
+            .peers
+            .entry(node_id.clone())
+            .or_insert_with(|| PeerRecord::new(node_id, address, now));
+        rec.address = address;
+        rec.last_seen = now;
+        let rec = rec.clone();
+        self.persist(&rec);
+    }
+
+    fn by_addr_mut(&mut self, address: &SocketAddr) -> Option<&mut PeerRecord> {
+        self.peers.values_mut().find(|r| &r.address == address)
+    }
+
+    /// Ergebnis eines Dials an `address`: `Some(rtt)` bei Erfolg, `None` bei Fehlschlag.
+    /// Liefert false, wenn keine NodeId zu dieser Adresse bekannt ist.
+    pub fn record_dial(&mut self, address: &SocketAddr, rtt: Option<Duration>, now: u64) -> bool {
+        let rec = match self.by_addr_mut(address) {
+            Some(r) => r,
+            None => return false,
+        };
+        match rtt {
+            Some(rtt) => {
+                let sample = rtt.as_secs_f64() * 1000.0;
+                rec.rtt_ms = Some(match rec.rtt_ms {
+                    Some(prev) => prev * (1.0 - RTT_EWMA_ALPHA) + sample * RTT_EWMA_ALPHA,
+                    None => sample,
+                });
+                rec.successes += 1;
+                rec.last_seen = now;
+            }
+            None => rec.failures += 1,
+        }
+        let rec = rec.clone();
+        self.persist(&rec);
+        true
+    }
+
+    /// Eine Verbindung zu `address` war `connected` lang offen.
+    pub fn record_uptime(&mut self, address: &SocketAddr, connected: Duration, now: u64) {
+        if let Some(rec) = self.by_addr_mut(address) {
+            rec.uptime_secs += connected.as_secs();
+            rec.last_seen = now;
+            let rec = rec.clone();
+            self.persist(&rec);
+        }
+    }
+
+    /// Alle Peers nach Score absteigend (bei Gleichstand nach Adresse, deterministisch).
+    pub fn dial_order(&self, now: u64) -> Vec<(NodeId, SocketAddr)> {
+        let mut recs: Vec<&PeerRecord> = self.peers.values().collect();
+        recs.sort_by(|a, b| {
+            b.score(now)
+                .partial_cmp(&a.score(now))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.address.cmp(&b.address))
+        });
+        recs.into_iter().map(|r| (r.node_id.clone(), r.address)).collect()
+    }
+
+    /// Befüllt die RoutingTable: zuerst die `limit` besten bekannten Peers,
+    /// danach die Bootstrap-Nodes. Liefert die Anzahl eingetragener Peers.
+    pub fn seed_routing_table(
+        &self,
+        table: &mut RoutingTable,
+        bootstrap: &[(NodeId, SocketAddr)],
+        limit: usize,
+        now: u64,
+    ) -> usize {
+        let mut n = 0;
+        for (id, addr) in self.dial_order(now).into_iter().take(limit) {
+            table.update_node(id, addr);
+            n += 1;
+        }
+        for (id, addr) in bootstrap {
+            table.update_node(id.clone(), *addr);
+            n += 1;
+        }
+        n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(b: u8) -> NodeId {
+        NodeId([b; 32])
+    }
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([10, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn test_stats_update_and_survive_restart() {
+        let db = Arc::new(Mutex::new(DexDB::in_memory()));
+        let mut book = AddressBook::load(db.clone()).unwrap();
+        book.observe(id(1), addr(9000), 100);
+        assert!(book.record_dial(&addr(9000), Some(Duration::from_millis(100)), 110));
+        book.record_dial(&addr(9000), Some(Duration::from_millis(200)), 120);
+        book.record_dial(&addr(9000), None, 130);
+        book.record_uptime(&addr(9000), Duration::from_secs(3_600), 140);
+        assert!(!book.record_dial(&addr(9999), None, 140), "unbekannte Adresse");
+
+        let rec = book.get(&id(1)).unwrap();
+        assert!((rec.rtt_ms.unwrap() - 120.0).abs() < 1e-9); // 100 * 0.8 + 200 * 0.2
+        assert_eq!((rec.successes, rec.failures, rec.uptime_secs, rec.last_seen), (2, 1, 3_600, 140));
+        assert!((rec.success_rate() - 0.6).abs() < 1e-9);
+
+        // Neustart: Statistik und last_seen kommen aus der DB
+        let reloaded = AddressBook::load(db).unwrap();
+        assert_eq!(reloaded.get(&id(1)), Some(rec));
+    }
+
+    #[test]
+    fn test_dial_order_prefers_reliable_fast_recent_peers() {
+        let mut book = AddressBook::in_memory();
+        let now = 1_000_000;
+        for (b, port) in [(1, 9001), (2, 9002), (3, 9003), (4, 9004)] {
+            book.observe(id(b), addr(port), now);
+        }
+        // 1: schnell + zuverlässig, 2: langsam, 3: fällt ständig aus, 4: lange nicht gesehen
+        for _ in 0..5 {
+            book.record_dial(&addr(9001), Some(Duration::from_millis(20)), now);
+            book.record_dial(&addr(9002), Some(Duration::from_millis(800)), now);
+            book.record_dial(&addr(9003), None, now);
+            book.record_dial(&addr(9004), Some(Duration::from_millis(20)), now - 30 * 86_400);
+        }
+        book.peers.get_mut(&id(4)).unwrap().last_seen = now - 30 * 86_400;
+
+        let order: Vec<SocketAddr> = book.dial_order(now).into_iter().map(|(_, a)| a).collect();
+        assert_eq!(order, vec![addr(9001), addr(9002), addr(9004), addr(9003)]);
+
+        let mut table = RoutingTable::new(id(0), 20);
+        assert_eq!(book.seed_routing_table(&mut table, &[(id(9), addr(7000))], 2, now), 3);
+        let seeded: Vec<SocketAddr> = table.all_entries().into_iter().map(|(_, _, a)| a).collect();
+        assert!(seeded.contains(&addr(9001)) && seeded.contains(&addr(7000)));
+        assert!(!seeded.contains(&addr(9003)));
+    }
+
+    #[test]
+    fn test_parse_bootstrap_peer() {
+        let entry = format!("{}@10.0.0.1:9000", hex::encode([7u8; 32]));
+        assert_eq!(parse_bootstrap_peer(&entry), Ok((id(7), addr(9000))));
+        assert!(parse_bootstrap_peer("10.0.0.1:9000").is_err());
+        assert!(parse_bootstrap_peer("abcd@10.0.0.1:9000").is_err());
+        assert!(parse_bootstrap_peer(&format!("{}@localhost", hex::encode([7u8; 32]))).is_err());
+    }
+}
@@ -3,8 +3,13 @@
 ///////////////////////////////////////////////////////////
 
 use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use rand::seq::SliceRandom;
+use serde::{Serialize, Deserialize};
+use tokio::net::UdpSocket;
 use tokio::sync::mpsc::{self, Sender, Receiver};
 use tokio::task::JoinHandle;
 use tokio::time::{sleep, Instant};
@@ -18,6 +23,9 @@ use crate::kademlia::kademlia_service::{
     KademliaService, NodeId, SimpleStorage, KademliaP2PAdapter, KademliaMessage,
 };
 use crate::storage::replicated_db_layer::{DexDB, CrdtSnapshot};
+use crate::error::DexError;
+use crate::fees::fee_pool::FeePool;
+use crate::utils::lock::LockRecover;
 
 /// ClusterManagerConfig => Konfiguration für mDNS, Kademlia, etc.
 #[derive(Debug, Clone)]
@@ -32,6 +40,8 @@ pub struct ClusterManagerConfig {
     pub kademlia_bucket_size: usize,
     /// Gossip-Intervall zum Snapshots-Sync
     pub snapshot_sync_interval: Duration,
+    /// SWIM-Membership (Failure-Detection)
+    pub swim: SwimConfig,
 }
 
 /// ClusterManager => verwaltet mDNS + Kademlia + Snapshot-Sync
@@ -43,6 +53,11 @@ pub struct ClusterManager {
     stop_flag: Arc<Mutex<bool>>,
     snapshot_task: Option<JoinHandle<()>>,
     failover_task: Option<JoinHandle<()>>,
+    membership: Arc<Mutex<Membership>>,
+    swim_task: Option<JoinHandle<()>>,
+    /// Anteil der Fees für Sync-Knoten (None = Sync-Fee aus)
+    sync_fee_rate: Option<f64>,
+    sync_work: Arc<Mutex<SyncWorkLedger>>,
 }
 
 impl ClusterManager {
@@ -76,6 +91,11 @@ impl ClusterManager {
             kad_service.table.update_node(seed_id.clone(), *seed_addr, |_nid,_addr| true);
         }
 
+        let mut membership = Membership::new(&member_key(&local_id), config.swim.bind_addr, config.swim.clone());
+        for seed in &config.swim.seeds {
+            membership.add_seed(*seed);
+        }
+
         ClusterManager {
             config,
             db,
@@ -84,7 +104,77 @@ impl ClusterManager {
             stop_flag: Arc::new(Mutex::new(false)),
             snapshot_task: None,
             failover_task: None,
+            membership: Arc::new(Mutex::new(membership)),
+            swim_task: None,
+            sync_fee_rate: None,
+            sync_work: Arc::new(Mutex::new(SyncWorkLedger::default())),
+        }
+    }
+
+    /// Aktiviert die Sync-Fee: `rate` jeder Fee (z. B. 0.01) geht in den
+    /// sync_pool, der pro Epoche nach bedientem Sync-Traffic verteilt wird.
+    pub fn enable_sync_fee(&mut self, rate: f64) {
+        self.sync_fee_rate = Some(rate);
+        // Laufende Epoche fortsetzen, falls persistiert
+        match SyncWorkLedger::load_latest(&self.db) {
+            Ok(Some(ledger)) => *self.sync_work.lock().unwrap() = ledger,
+            Ok(None) => {}
+            Err(e) => warn!("sync_work => load failed: {:?}", e),
+        }
+        info!("Sync-Fee aktiv => rate={}", rate);
+    }
+
+    pub fn sync_fee_rate(&self) -> Option<f64> {
+        self.sync_fee_rate
+    }
+
+    /// `node` hat uns `bytes` in `snapshots` Snapshots geliefert. Wir zählen
+    /// auf Empfängerseite, damit sich niemand selbst Arbeit gutschreiben kann.
+    pub fn record_sync_served(&self, node: &str, bytes: u64, snapshots: u64) {
+        if self.sync_fee_rate.is_none() {
+            return;
         }
+        let mut ledger = self.sync_work.lock().unwrap();
+        ledger.record(node, bytes, snapshots);
+        if let Err(e) = ledger.persist(&self.db) {
+            warn!("sync_work => persist failed: {:?}", e);
+        }
+    }
+
+    /// Schließt die Epoche: verteilt den sync_pool nach den Zählern und
+    /// beginnt eine neue Epoche. Liefert die abgeschlossene Epoche zurück.
+    pub fn close_sync_epoch(&self, fee_pool: &FeePool) -> Result<SyncWorkLedger, Box<dyn std::error::Error>> {
+        Ok(self.sync_epochs().close(fee_pool)?)
+    }
+
+    /// Handle zum Schließen der Sync-Epochen aus einem eigenen Task
+    /// (der ClusterManager selbst bleibt in main).
+    pub fn sync_epochs(&self) -> SyncEpochs {
+        SyncEpochs {
+            db: Arc::clone(&self.db),
+            ledger: Arc::clone(&self.sync_work),
+            enabled: self.sync_fee_rate.is_some(),
+        }
+    }
+
+    /// Schlüssel, mit dem SWIM-Nachrichten signiert werden. Die Member-ID ist
+    /// daraus abgeleitet (`NodeId::from_public_key`), `local_id` muss passen.
+    pub fn with_swim_key(self, keypair: Arc<Keypair>) -> Self {
+        if member_key(&NodeId::from_public_key(&keypair.public)) != member_key(&self.config.local_id) {
+            warn!("SWIM => local_id passt nicht zum Signierschlüssel, Peers verwerfen unsere Nachrichten");
+        }
+        self.membership.lock_recover().set_signing_key(keypair);
+        self
+    }
+
+    /// Geteilte Membership-Sicht => für ShardManager / FeePool
+    pub fn membership(&self) -> Arc<Mutex<Membership>> {
+        Arc::clone(&self.membership)
+    }
+
+    /// Lebende Mitglieder (inkl. Suspects), ohne uns selbst
+    pub fn alive_members(&self) -> Vec<String> {
+        self.membership.lock().unwrap().alive_members()
     }
 
     /// Startet mDNS + Kademlia + Snapshot-Sync
@@ -97,7 +187,7 @@ impl ClusterManager {
             let kad_cloned = Arc::clone(&self.kademlia);
             let sf = Arc::clone(&self.stop_flag);
             tokio::spawn(async move {
-                let k = kad_cloned.lock().unwrap();
+                let k = kad_cloned.lock_recover();
                 k.run_service().await; 
                 // blockiert => wenn stop_flag => ...
                 info!("KademliaService => beendet");
@@ -134,23 +224,15 @@ impl ClusterManager {
             }));
         }
 
-        // (D) Failover => Node-Fail => wir könnten ein Mechanismus haben, 
-        //  der periodisch pingt / Node anruft => if dead => re-shard ...
+        // (D) Failover => SWIM-Probes über UDP; tote Nodes landen in der
+        //  Membership-Sicht, ShardManager/FeePool lesen sie dort aus.
         {
+            let socket = UdpSocket::bind(self.config.swim.bind_addr).await?;
+            info!("SWIM => listening on {}", socket.local_addr()?);
             let sf = Arc::clone(&self.stop_flag);
-            let dbclone = Arc::clone(&self.db);
-            self.failover_task = Some(tokio::spawn(async move {
-                loop {
-                    if *sf.lock().unwrap() {
-                        break;
-                    }
-                    // Stub: wir prüfen "Nodes" in Kademlia => if not responding => 
-                    //  => re-shard or replicate 
-                    // Hier nur Dummy
-                    debug!("Failover Check => no real logic here (demo) ...");
-                    sleep(Duration::from_secs(120)).await;
-                }
-            }));
+            let membership = Arc::clone(&self.membership);
+            let cfg = self.config.swim.clone();
+            self.swim_task = Some(tokio::spawn(run_swim(socket, membership, cfg, sf)));
         }
 
         // **NEU**: Ruft perform_initial_sync_for_new_node, 
@@ -174,6 +256,9 @@ impl ClusterManager {
         if let Some(h) = self.failover_task.take() {
             let _ = h.await;
         }
+        if let Some(h) = self.swim_task.take() {
+            let _ = h.await;
+        }
         // Kademlia => da run_service blockiert => i. d. R. abort
         info!("ClusterManager => all tasks ended");
     }
@@ -194,45 +279,6 @@ impl ClusterManager {
         Ok(())
     }
 
-    /// Ein einfacher Stub-Adapter, der KademliaMessage per 
-    /// Stub an handle_message(...) leitet.
-    pub struct MockKademliaAdapter {
-        pub inbound_tx: Sender<KademliaMessage>,
-    }
-
-    impl MockKademliaAdapter {
-        pub fn new() -> Self {
-            let (tx, mut rx) = mpsc::channel(100);
-
-            // Worker => loop
-            tokio::spawn(async move {
-                while let Some(msg) = rx.recv().await {
-                    debug!("(MockKademliaAdapter) => got msg: {:?}", msg);
-                    // In real => wir würdest handle
-                }
-            });
-
-            MockKademliaAdapter {
-                inbound_tx: tx,
-            }
-        }
-    }
-
-    impl KademliaP2PAdapter for MockKademliaAdapter {
-        fn send_kademlia_msg(&self, _addr: std::net::SocketAddr, msg: &KademliaMessage) {
-            let msg_cloned = msg.clone();
-            let _ = self.inbound_tx.try_send(msg_cloned);
-        }
-
-        fn local_address(&self) -> std::net::SocketAddr {
-            "0.0.0.0:9999".parse().unwrap()
-        }
-
-        fn ping_node(&self, _node_id: &NodeId, _addr: std::net::SocketAddr) -> bool {
-            true
-        }
-    }
-
     // --------------------------------------------------------
     // **NEU**: Logik, um "Initial Sync" von random Node zu bekommen.
     // --------------------------------------------------------
@@ -254,7 +300,7 @@ impl ClusterManager {
         info!("Node {:?} => no local snapshots => performing initial sync from random peer...", new_node_id);
 
         // Step 2: Aus Kademlia => wähle random Peer
-        let kad = self.kademlia.lock().unwrap();
+        let kad = self.kademlia.lock_recover();
         let peers = kad.table.all_entries();
         if peers.is_empty() {
             warn!("No peers known => can't do initial sync => maybe we are alone?");
@@ -279,14 +325,1026 @@ impl ClusterManager {
         // Hier machen wir minimal:
         tokio::time::sleep(std::time::Duration::from_secs(2)).await;
         // stub => "simulate got remote 3 snapshots"
-        let fake_remote = vec![
-            CrdtSnapshot { version: 100, data: vec![11,22,33] },
-            CrdtSnapshot { version: 101, data: vec![44,55,66] },
-            CrdtSnapshot { version: 102, data: vec![77,88,99] },
-        ];
+        let origin = format!("{:?}", peer_nodeid);
+        let mut vector = crate::storage::replicated_db_layer::VersionVector::default();
+        let fake_remote: Vec<CrdtSnapshot> = [vec![11,22,33], vec![44,55,66], vec![77,88,99]]
+            .into_iter()
+            .map(|data| {
+                vector.increment(&origin);
+                CrdtSnapshot::new(&origin, vector.clone(), data)
+            })
+            .collect();
+        let bytes: u64 = fake_remote.iter().map(|s| s.data.len() as u64).sum();
+        let count = fake_remote.len() as u64;
         self.db.sync_with_remote(fake_remote)?;
+        self.record_sync_served(&member_key(peer_nodeid), bytes, count);
         info!("Initial sync done => Node {:?} now has snapshots from peer={:?}.", new_node_id, peer_nodeid);
 
         Ok(())
     }
 }
+
+/// Ein einfacher Stub-Adapter, der KademliaMessage per 
+/// Stub an handle_message(...) leitet.
+pub struct MockKademliaAdapter {
+    pub inbound_tx: Sender<KademliaMessage>,
+}
+
+impl MockKademliaAdapter {
+    pub fn new() -> Self {
+        let (tx, mut rx) = mpsc::channel(100);
+
+        // Worker => loop
+        tokio::spawn(async move {
+            while let Some(msg) = rx.recv().await {
+                debug!("(MockKademliaAdapter) => got msg: {:?}", msg);
+                // In real => wir würdest handle
+            }
+        });
+
+        MockKademliaAdapter {
+            inbound_tx: tx,
+        }
+    }
+}
+
+impl KademliaP2PAdapter for MockKademliaAdapter {
+    fn send_kademlia_msg(&self, _addr: std::net::SocketAddr, msg: &KademliaMessage) {
+        let msg_cloned = msg.clone();
+        let _ = self.inbound_tx.try_send(msg_cloned);
+    }
+
+    fn local_address(&self) -> std::net::SocketAddr {
+        "0.0.0.0:9999".parse().unwrap()
+    }
+
+    fn ping_node(&self, _node_id: &NodeId, _addr: std::net::SocketAddr) -> bool {
+        true
+    }
+}
+
+// --------------------------------------------------------
+// Sync-Arbeit pro Epoche (Basis der Sync-Fee)
+// --------------------------------------------------------
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncWork {
+    pub bytes_served: u64,
+    pub snapshots_served: u64,
+}
+
+/// Schließt Sync-Epochen eines ClusterManagers, siehe `ClusterManager::sync_epochs`.
+#[derive(Clone)]
+pub struct SyncEpochs {
+    db: Arc<DexDB>,
+    ledger: Arc<Mutex<SyncWorkLedger>>,
+    enabled: bool,
+}
+
+impl SyncEpochs {
+    pub fn close(&self, fee_pool: &FeePool) -> anyhow::Result<SyncWorkLedger> {
+        let mut ledger = self.ledger.lock_recover();
+        ledger.persist(&self.db)?;
+        if self.enabled {
+            fee_pool.distribute_sync_pool(&ledger.weights())?;
+        }
+        let closed = ledger.clone();
+        *ledger = SyncWorkLedger { epoch: closed.epoch + 1, served: HashMap::new() };
+        ledger.persist(&self.db)?;
+        info!("Sync epoch {} closed => {} nodes", closed.epoch, closed.served.len());
+        Ok(closed)
+    }
+}
+
+/// Zähler einer Epoche: Node-ID (Member-ID) -> geleistete Sync-Arbeit.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncWorkLedger {
+    pub epoch: u64,
+    pub served: HashMap<String, SyncWork>,
+}
+
+const SYNC_WORK_PREFIX: &str = "sync_work/epoch_";
+
+impl SyncWorkLedger {
+    pub fn record(&mut self, node: &str, bytes: u64, snapshots: u64) {
+        let w = self.served.entry(node.to_string()).or_default();
+        w.bytes_served += bytes;
+        w.snapshots_served += snapshots;
+    }
+
+    /// Gewicht für die Fee-Verteilung: bediente Bytes.
+    pub fn weights(&self) -> HashMap<String, u64> {
+        self.served.iter().map(|(n, w)| (n.clone(), w.bytes_served)).collect()
+    }
+
+    fn key(epoch: u64) -> String {
+        format!("{}{:010}", SYNC_WORK_PREFIX, epoch)
+    }
+
+    pub fn persist(&self, db: &DexDB) -> anyhow::Result<()> {
+        db.put_raw(&Self::key(self.epoch), bincode::serialize(self)?)?;
+        db.put_raw("sync_work/current", self.epoch.to_be_bytes().to_vec())
+    }
+
+    pub fn load(db: &DexDB, epoch: u64) -> anyhow::Result<Option<Self>> {
+        match db.get_raw(&Self::key(epoch))? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn load_latest(db: &DexDB) -> anyhow::Result<Option<Self>> {
+        let epoch = match db.get_raw("sync_work/current")? {
+            Some(b) if b.len() == 8 => u64::from_be_bytes(b[..8].try_into()?),
+            _ => return Ok(None),
+        };
+        Self::load(db, epoch)
+    }
+}
+
+// --------------------------------------------------------
+// SWIM-Membership: Failure-Detection + Dissemination
+// --------------------------------------------------------
+//
+// Jede Probe-Periode pingt ein Node genau ein Mitglied (Round-Robin, nach
+// jeder Runde neu gemischt). Kommt kein Ack innerhalb `probe_timeout`,
+// bitten wir `indirect_probes` andere Mitglieder per PingReq, es für uns zu
+// versuchen. Bleibt auch das aus, gilt das Ziel als Suspect; nach
+// `suspicion_timeout` ohne Widerlegung als Dead. Ein Suspect widerlegt den
+// Verdacht, indem es seine Incarnation erhöht. Zustandsänderungen werden an
+// Pings/Acks angehängt (Piggyback) und ~λ·log(n) mal weiterverbreitet.
+//
+// Jede Nachricht ist Ed25519-signiert; die Member-ID ist aus dem Public Key
+// abgeleitet (`NodeId::from_public_key`), ein Absender kann sich also nicht
+// als anderes Mitglied ausgeben. Alive mit höherer Incarnation (Widerlegung,
+// Wiederbeitritt) braucht zusätzlich die Signatur des Mitglieds selbst, damit
+// niemand einen toten Node künstlich am Leben hält. Dead ist nicht endgültig:
+// meldet sich ein totgesagter Node, bekommt er seinen Dead-Eintrag zurück und
+// tritt mit höherer Incarnation wieder bei.
+//
+// `Membership` ist eine reine Zustandsmaschine (Zeit kommt von außen,
+// ausgehende Nachrichten werden zurückgegeben); `run_swim` treibt sie über UDP.
+
+/// Membership-ID eines Kademlia-Nodes (hex).
+pub fn member_key(id: &NodeId) -> String {
+    hex::encode(id.0)
+}
+
+#[derive(Debug, Clone)]
+pub struct SwimConfig {
+    pub bind_addr: SocketAddr,
+    /// Bekannte SWIM-Adressen zum Beitreten
+    pub seeds: Vec<SocketAddr>,
+    pub probe_interval: Duration,
+    pub probe_timeout: Duration,
+    pub indirect_probes: usize,
+    pub suspicion_timeout: Duration,
+    pub max_piggyback: usize,
+    /// Zugelassene Mitglieder (leer => jeder Key mit passender Member-ID)
+    pub allowed_keys: Vec<PublicKey>,
+}
+
+impl Default for SwimConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: "0.0.0.0:7946".parse().unwrap(),
+            seeds: Vec::new(),
+            probe_interval: Duration::from_secs(1),
+            probe_timeout: Duration::from_millis(300),
+            indirect_probes: 3,
+            suspicion_timeout: Duration::from_secs(5),
+            max_piggyback: 8,
+            allowed_keys: Vec::new(),
+        }
+    }
+}
+
+/// `swim` in der Node-Config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SwimSettings {
+    pub bind_addr: String,
+    pub seeds: Vec<String>,
+    /// Zugelassene Ed25519-Keys (hex); leer => offene Membership
+    pub allowed_keys: Vec<String>,
+    pub probe_interval_ms: u64,
+    pub suspicion_timeout_ms: u64,
+}
+
+impl Default for SwimSettings {
+    fn default() -> Self {
+        let d = SwimConfig::default();
+        Self {
+            bind_addr: d.bind_addr.to_string(),
+            seeds: Vec::new(),
+            allowed_keys: Vec::new(),
+            probe_interval_ms: d.probe_interval.as_millis() as u64,
+            suspicion_timeout_ms: d.suspicion_timeout.as_millis() as u64,
+        }
+    }
+}
+
+impl SwimSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        self.to_config().map(|_| ())
+    }
+
+    pub fn to_config(&self) -> Result<SwimConfig, String> {
+        if self.probe_interval_ms == 0 || self.suspicion_timeout_ms == 0 {
+            return Err("probe_interval_ms and suspicion_timeout_ms must be > 0".into());
+        }
+        let bind_addr = self.bind_addr.parse().map_err(|e| format!("bind_addr `{}`: {}", self.bind_addr, e))?;
+        let seeds = self
+            .seeds
+            .iter()
+            .map(|s| s.parse().map_err(|e| format!("seed `{}`: {}", s, e)))
+            .collect::<Result<_, String>>()?;
+        let allowed_keys = self
+            .allowed_keys
+            .iter()
+            .map(|h| {
+                hex::decode(h)
+                    .ok()
+                    .and_then(|b| key_from_bytes(&b))
+                    .ok_or_else(|| format!("allowed_keys: `{}` is not an Ed25519 key", h))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(SwimConfig {
+            bind_addr,
+            seeds,
+            allowed_keys,
+            probe_interval: Duration::from_millis(self.probe_interval_ms),
+            suspicion_timeout: Duration::from_millis(self.suspicion_timeout_ms),
+            ..SwimConfig::default()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MemberState {
+    Alive,
+    Suspect,
+    Dead,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemberUpdate {
+    pub id: String,
+    pub addr: SocketAddr,
+    pub state: MemberState,
+    pub incarnation: u64,
+    /// Signatur des Mitglieds über (id, addr, incarnation), nur bei Alive
+    #[serde(default)]
+    pub proof: Option<AliveProof>,
+}
+
+/// Selbst signierte Alive-Meldung eines Mitglieds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AliveProof {
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+fn alive_signing_bytes(id: &str, addr: SocketAddr, incarnation: u64) -> Result<Vec<u8>, DexError> {
+    crate::utils::canonical::signing_bytes("my_dex/swim/alive/v1", &(id, addr.to_string(), incarnation))
+}
+
+fn key_from_bytes(bytes: &[u8]) -> Option<PublicKey> {
+    PublicKey::from_bytes(bytes).ok()
+}
+
+fn signature_valid(pk: &PublicKey, msg: &[u8], sig: &[u8]) -> bool {
+    Signature::from_bytes(sig).map(|sig| pk.verify(msg, &sig).is_ok()).unwrap_or(false)
+}
+
+impl MemberUpdate {
+    /// Key aus dem Proof, falls er zur ID passt und die Signatur gültig ist.
+    fn proven_key(&self) -> Option<PublicKey> {
+        let proof = self.proof.as_ref()?;
+        let pk = key_from_bytes(&proof.public_key)?;
+        if member_key(&NodeId::from_public_key(&pk)) != self.id {
+            return None;
+        }
+        let bytes = alive_signing_bytes(&self.id, self.addr, self.incarnation).ok()?;
+        signature_valid(&pk, &bytes, &proof.signature).then_some(pk)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SwimMessage {
+    Ping { from: String, seq: u64, updates: Vec<MemberUpdate> },
+    PingReq { from: String, seq: u64, target: String, target_addr: SocketAddr, updates: Vec<MemberUpdate> },
+    Ack { from: String, seq: u64, updates: Vec<MemberUpdate> },
+}
+
+impl SwimMessage {
+    fn parts(&self) -> (&str, &[MemberUpdate]) {
+        match self {
+            SwimMessage::Ping { from, updates, .. }
+            | SwimMessage::PingReq { from, updates, .. }
+            | SwimMessage::Ack { from, updates, .. } => (from.as_str(), updates.as_slice()),
+        }
+    }
+}
+
+/// Signierte SWIM-Nachricht (Wire-Format von `run_swim`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedSwimMessage {
+    pub msg: SwimMessage,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl SignedSwimMessage {
+    fn signing_bytes(msg: &SwimMessage) -> Result<Vec<u8>, DexError> {
+        crate::utils::canonical::signing_bytes("my_dex/swim/message/v1", msg)
+    }
+
+    pub fn sign(msg: SwimMessage, kp: &Keypair) -> Result<Self, DexError> {
+        let signature = kp.sign(&Self::signing_bytes(&msg)?).to_bytes().to_vec();
+        Ok(Self { msg, public_key: kp.public.as_bytes().to_vec(), signature })
+    }
+
+    /// Key des Absenders, wenn die Signatur gültig ist und `from` die aus
+    /// diesem Key abgeleitete Member-ID ist.
+    pub fn verify(&self) -> Option<PublicKey> {
+        let pk = key_from_bytes(&self.public_key)?;
+        if member_key(&NodeId::from_public_key(&pk)) != self.msg.parts().0 {
+            return None;
+        }
+        let bytes = Self::signing_bytes(&self.msg).ok()?;
+        signature_valid(&pk, &bytes, &self.signature).then_some(pk)
+    }
+}
+
+/// Zu versendende Nachrichten.
+pub type SwimOutgoing = Vec<(SocketAddr, SwimMessage)>;
+
+#[derive(Debug, Clone)]
+struct Member {
+    addr: SocketAddr,
+    state: MemberState,
+    incarnation: u64,
+    since: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProbePhase {
+    Direct,
+    Indirect,
+}
+
+#[derive(Debug)]
+struct PendingProbe {
+    target: String,
+    sent_at: Instant,
+    phase: ProbePhase,
+}
+
+/// PingReq, den wir für einen anderen Node ausführen.
+#[derive(Debug)]
+struct Relay {
+    requester: SocketAddr,
+    requester_seq: u64,
+    sent_at: Instant,
+}
+
+#[derive(Debug)]
+pub struct Membership {
+    local_id: String,
+    local_addr: SocketAddr,
+    incarnation: u64,
+    config: SwimConfig,
+    members: HashMap<String, Member>,
+    probe_order: Vec<String>,
+    pending: HashMap<u64, PendingProbe>,
+    relays: HashMap<u64, Relay>,
+    next_seq: u64,
+    /// Update -> verbleibende Weitergaben
+    gossip: Vec<(MemberUpdate, u32)>,
+    seeds: Vec<SocketAddr>,
+    keypair: Option<Arc<Keypair>>,
+}
+
+impl Membership {
+    pub fn new(local_id: &str, local_addr: SocketAddr, config: SwimConfig) -> Self {
+        Self {
+            local_id: local_id.to_string(),
+            local_addr,
+            incarnation: 0,
+            config,
+            members: HashMap::new(),
+            probe_order: Vec::new(),
+            pending: HashMap::new(),
+            relays: HashMap::new(),
+            next_seq: 1,
+            gossip: Vec::new(),
+            seeds: Vec::new(),
+            keypair: None,
+        }
+    }
+
+    /// Signierschlüssel für ausgehende Nachrichten und eigene Alive-Meldungen.
+    pub fn set_signing_key(&mut self, keypair: Arc<Keypair>) {
+        self.keypair = Some(keypair);
+    }
+
+    fn key_allowed(&self, pk: &PublicKey) -> bool {
+        self.config.allowed_keys.is_empty() || self.config.allowed_keys.contains(pk)
+    }
+
+    /// Signierte Nachricht prüfen und verarbeiten; ungültige oder nicht
+    /// zugelassene Absender werden verworfen.
+    pub fn handle_signed(&mut self, src: SocketAddr, env: SignedSwimMessage, now: Instant) -> SwimOutgoing {
+        match env.verify() {
+            Some(pk) if self.key_allowed(&pk) => self.handle(src, env.msg, now),
+            Some(_) => {
+                debug!("SWIM => member {} not allowed", env.msg.parts().0);
+                Vec::new()
+            }
+            None => {
+                warn!("SWIM => invalid signature from {}", src);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Signiert ausgehende Nachrichten; ohne Schlüssel wird nichts gesendet.
+    pub fn sign_outgoing(&self, out: SwimOutgoing) -> Vec<(SocketAddr, SignedSwimMessage)> {
+        let kp = match &self.keypair {
+            Some(kp) => kp,
+            None => {
+                if !out.is_empty() {
+                    warn!("SWIM => no signing key, dropping {} messages", out.len());
+                }
+                return Vec::new();
+            }
+        };
+        out.into_iter()
+            .filter_map(|(addr, msg)| match SignedSwimMessage::sign(msg, kp) {
+                Ok(env) => Some((addr, env)),
+                Err(e) => {
+                    error!("SWIM => sign: {:?}", e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Eigene, selbst signierte Alive-Meldung mit der aktuellen Incarnation.
+    fn self_alive(&self) -> MemberUpdate {
+        let proof = self.keypair.as_ref().and_then(|kp| {
+            let bytes = alive_signing_bytes(&self.local_id, self.local_addr, self.incarnation).ok()?;
+            Some(AliveProof {
+                public_key: kp.public.as_bytes().to_vec(),
+                signature: kp.sign(&bytes).to_bytes().to_vec(),
+            })
+        });
+        MemberUpdate {
+            id: self.local_id.clone(),
+            addr: self.local_addr,
+            state: MemberState::Alive,
+            incarnation: self.incarnation,
+            proof,
+        }
+    }
+
+    pub fn local_id(&self) -> &str {
+        &self.local_id
+    }
+
+    pub fn incarnation(&self) -> u64 {
+        self.incarnation
+    }
+
+    /// Seed ohne bekannte ID => wird beim nächsten `probe` angepingt.
+    pub fn add_seed(&mut self, addr: SocketAddr) {
+        self.seeds.push(addr);
+    }
+
+    pub fn add_member(&mut self, id: &str, addr: SocketAddr, now: Instant) {
+        if id == self.local_id || self.members.contains_key(id) {
+            return;
+        }
+        self.members.insert(
+            id.to_string(),
+            Member { addr, state: MemberState::Alive, incarnation: 0, since: now },
+        );
+        self.enqueue(MemberUpdate { id: id.to_string(), addr, state: MemberState::Alive, incarnation: 0, proof: None });
+    }
+
+    pub fn state_of(&self, id: &str) -> Option<MemberState> {
+        self.members.get(id).map(|m| m.state)
+    }
+
+    /// Suspects zählen bis zur Bestätigung weiter als Mitglied.
+    pub fn is_alive(&self, id: &str) -> bool {
+        id == self.local_id || matches!(self.state_of(id), Some(MemberState::Alive | MemberState::Suspect))
+    }
+
+    /// Alle nicht-toten Mitglieder (ohne uns selbst), sortiert.
+    pub fn alive_members(&self) -> Vec<String> {
+        let mut out: Vec<String> = self
+            .members
+            .iter()
+            .filter(|(_, m)| m.state != MemberState::Dead)
+            .map(|(id, _)| id.clone())
+            .collect();
+        out.sort();
+        out
+    }
+
+    /// Startet die Probe dieser Periode.
+    pub fn probe(&mut self, now: Instant) -> SwimOutgoing {
+        let mut out = Vec::new();
+        for addr in std::mem::take(&mut self.seeds) {
+            let seq = self.take_seq();
+            out.push((addr, SwimMessage::Ping { from: self.local_id.clone(), seq, updates: self.piggyback() }));
+        }
+        if let Some(target) = self.next_target() {
+            out.extend(self.probe_target(&target, now));
+        }
+        out
+    }
+
+    /// Direkte Probe auf `target`.
+    pub fn probe_target(&mut self, target: &str, now: Instant) -> SwimOutgoing {
+        let addr = match self.members.get(target) {
+            Some(m) if m.state != MemberState::Dead => m.addr,
+            _ => return Vec::new(),
+        };
+        let seq = self.take_seq();
+        self.pending.insert(seq, PendingProbe { target: target.to_string(), sent_at: now, phase: ProbePhase::Direct });
+        vec![(addr, SwimMessage::Ping { from: self.local_id.clone(), seq, updates: self.piggyback() })]
+    }
+
+    /// Timeouts auswerten: indirekte Probes, Suspect, Dead.
+    pub fn tick(&mut self, now: Instant) -> SwimOutgoing {
+        let mut out = Vec::new();
+        let timeout = self.config.probe_timeout;
+
+        let expired: Vec<u64> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| now.duration_since(p.sent_at) >= timeout)
+            .map(|(seq, _)| *seq)
+            .collect();
+        for seq in expired {
+            let probe = self.pending.remove(&seq).unwrap();
+            match probe.phase {
+                ProbePhase::Direct => {
+                    let target_addr = match self.members.get(&probe.target) {
+                        Some(m) => m.addr,
+                        None => continue,
+                    };
+                    let mut helpers: Vec<SocketAddr> = self
+                        .members
+                        .iter()
+                        .filter(|(id, m)| **id != probe.target && m.state == MemberState::Alive)
+                        .map(|(_, m)| m.addr)
+                        .collect();
+                    helpers.shuffle(&mut rand::thread_rng());
+                    helpers.truncate(self.config.indirect_probes);
+                    debug!("SWIM => no ack from {} => {} indirect probes", probe.target, helpers.len());
+                    for h in helpers {
+                        out.push((h, SwimMessage::PingReq {
+                            from: self.local_id.clone(),
+                            seq,
+                            target: probe.target.clone(),
+                            target_addr,
+                            updates: self.piggyback(),
+                        }));
+                    }
+                    self.pending.insert(seq, PendingProbe { phase: ProbePhase::Indirect, sent_at: now, ..probe });
+                }
+                ProbePhase::Indirect => self.suspect(&probe.target, now),
+            }
+        }
+
+        let suspicion = self.config.suspicion_timeout;
+        let dead: Vec<String> = self
+            .members
+            .iter()
+            .filter(|(_, m)| m.state == MemberState::Suspect && now.duration_since(m.since) >= suspicion)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in dead {
+            let m = self.members.get_mut(&id).unwrap();
+            m.state = MemberState::Dead;
+            m.since = now;
+            warn!("SWIM => member {} declared dead", id);
+            let u = MemberUpdate { id, addr: m.addr, state: MemberState::Dead, incarnation: m.incarnation, proof: None };
+            self.enqueue(u);
+        }
+
+        self.relays.retain(|_, r| now.duration_since(r.sent_at) < timeout * 2);
+        out
+    }
+
+    /// Eingehende Nachricht von `src` verarbeiten.
+    pub fn handle(&mut self, src: SocketAddr, msg: SwimMessage, now: Instant) -> SwimOutgoing {
+        let (from, updates) = msg.parts();
+        for u in updates.to_vec() {
+            self.apply_update(u, now);
+        }
+        let from = from.to_string();
+        // direkter Kontakt => unbekannter Absender tritt bei
+        if !self.members.contains_key(&from) {
+            self.add_member(&from, src, now);
+        }
+        // Totgesagter meldet sich => Dead-Eintrag zurückspielen, damit er
+        // mit höherer Incarnation widerlegt und wieder beitritt
+        if let Some(m) = self.members.get(&from).filter(|m| m.state == MemberState::Dead) {
+            let u = MemberUpdate { id: from.clone(), addr: m.addr, state: MemberState::Dead, incarnation: m.incarnation, proof: None };
+            self.enqueue(u);
+        }
+
+        let mut out = Vec::new();
+        match msg {
+            SwimMessage::Ping { seq, .. } => {
+                out.push((src, SwimMessage::Ack { from: self.local_id.clone(), seq, updates: self.piggyback() }));
+            }
+            SwimMessage::PingReq { seq, target_addr, .. } => {
+                let relay_seq = self.take_seq();
+                self.relays.insert(relay_seq, Relay { requester: src, requester_seq: seq, sent_at: now });
+                out.push((target_addr, SwimMessage::Ping {
+                    from: self.local_id.clone(),
+                    seq: relay_seq,
+                    updates: self.piggyback(),
+                }));
+            }
+            SwimMessage::Ack { from, seq, .. } => {
+                if let Some(relay) = self.relays.remove(&seq) {
+                    out.push((relay.requester, SwimMessage::Ack {
+                        from,
+                        seq: relay.requester_seq,
+                        updates: self.piggyback(),
+                    }));
+                } else if let Some(probe) = self.pending.remove(&seq) {
+                    debug!("SWIM => ack for {} ({:?})", probe.target, probe.phase);
+                }
+            }
+        }
+        out
+    }
+
+    fn suspect(&mut self, id: &str, now: Instant) {
+        if let Some(m) = self.members.get_mut(id) {
+            if m.state != MemberState::Alive {
+                return;
+            }
+            m.state = MemberState::Suspect;
+            m.since = now;
+            info!("SWIM => member {} suspect", id);
+            let u = MemberUpdate { id: id.to_string(), addr: m.addr, state: MemberState::Suspect, incarnation: m.incarnation, proof: None };
+            self.enqueue(u);
+        }
+    }
+
+    /// SWIM-Präzedenz: höhere Incarnation gewinnt, bei gleicher schlägt
+    /// Suspect Alive und Dead beides. Alive über eine bekannte Incarnation
+    /// hinaus (Widerlegung, Wiederbeitritt nach Dead) nur mit Proof des
+    /// Mitglieds selbst.
+    fn apply_update(&mut self, u: MemberUpdate, now: Instant) {
+        if u.id == self.local_id {
+            if u.state != MemberState::Alive && u.incarnation >= self.incarnation {
+                self.incarnation = u.incarnation + 1;
+                info!("SWIM => refuting {:?} about us => incarnation {}", u.state, self.incarnation);
+                let refute = self.self_alive();
+                self.enqueue(refute);
+            }
+            return;
+        }
+        if u.state == MemberState::Alive && u.proof.is_some() {
+            match u.proven_key() {
+                Some(pk) if self.key_allowed(&pk) => {}
+                _ => {
+                    debug!("SWIM => invalid alive proof for {}", u.id);
+                    return;
+                }
+            }
+        }
+        let proven = u.proof.is_some();
+        let accept = match self.members.get(&u.id) {
+            None => u.state != MemberState::Dead,
+            Some(m) => match (m.state, u.state) {
+                (MemberState::Dead, MemberState::Dead) => false,
+                (_, MemberState::Dead) => u.incarnation >= m.incarnation,
+                (MemberState::Dead, MemberState::Suspect) => false,
+                (MemberState::Alive, MemberState::Suspect) => u.incarnation >= m.incarnation,
+                (_, MemberState::Alive) => u.incarnation > m.incarnation && proven,
+                _ => u.incarnation > m.incarnation,
+            },
+        };
+        if !accept {
+            return;
+        }
+        if self.state_of(&u.id) == Some(MemberState::Dead) {
+            info!("SWIM => member {} rejoined (inc {})", u.id, u.incarnation);
+        }
+        debug!("SWIM => member {} => {:?} (inc {})", u.id, u.state, u.incarnation);
+        self.members.insert(
+            u.id.clone(),
+            Member { addr: u.addr, state: u.state, incarnation: u.incarnation, since: now },
+        );
+        self.enqueue(u);
+    }
+
+    fn enqueue(&mut self, u: MemberUpdate) {
+        self.gossip.retain(|(g, _)| g.id != u.id);
+        let n = self.members.len() as f64 + 1.0;
+        let retransmits = (3.0 * n.log2().ceil()).max(1.0) as u32;
+        self.gossip.push((u, retransmits));
+    }
+
+    fn piggyback(&mut self) -> Vec<MemberUpdate> {
+        self.gossip.sort_by(|a, b| b.1.cmp(&a.1));
+        let take = self.gossip.len().min(self.config.max_piggyback);
+        let mut out = Vec::with_capacity(take);
+        for (u, left) in self.gossip.iter_mut().take(take) {
+            out.push(u.clone());
+            *left -= 1;
+        }
+        self.gossip.retain(|(_, left)| *left > 0);
+        out
+    }
+
+    fn next_target(&mut self) -> Option<String> {
+        while let Some(id) = self.probe_order.pop() {
+            if matches!(self.state_of(&id), Some(MemberState::Alive | MemberState::Suspect)) {
+                return Some(id);
+            }
+        }
+        self.probe_order = self.alive_members();
+        self.probe_order.shuffle(&mut rand::thread_rng());
+        self.probe_order.pop()
+    }
+
+    fn take_seq(&mut self) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        seq
+    }
+}
+
+/// Treibt `Membership` über UDP (bincode, signiert), bis `stop_flag` gesetzt ist.
+async fn run_swim(
+    socket: UdpSocket,
+    membership: Arc<Mutex<Membership>>,
+    cfg: SwimConfig,
+    stop_flag: Arc<Mutex<bool>>,
+) {
+    let mut probe_iv = tokio::time::interval(cfg.probe_interval);
+    let mut tick_iv = tokio::time::interval(cfg.probe_timeout / 2);
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        if *stop_flag.lock().unwrap() {
+            break;
+        }
+        let out = tokio::select! {
+            _ = probe_iv.tick() => membership.lock().unwrap().probe(Instant::now()),
+            _ = tick_iv.tick() => membership.lock().unwrap().tick(Instant::now()),
+            r = socket.recv_from(&mut buf) => match r {
+                Ok((n, src)) => match bincode::deserialize::<SignedSwimMessage>(&buf[..n]) {
+                    Ok(env) => membership.lock().unwrap().handle_signed(src, env, Instant::now()),
+                    Err(e) => {
+                        debug!("SWIM => invalid packet from {}: {:?}", src, e);
+                        Vec::new()
+                    }
+                },
+                Err(e) => {
+                    warn!("SWIM => recv error: {:?}", e);
+                    Vec::new()
+                }
+            },
+        };
+        let out = membership.lock().unwrap().sign_outgoing(out);
+        for (addr, msg) in out {
+            match bincode::serialize(&msg) {
+                Ok(bytes) => {
+                    if let Err(e) = socket.send_to(&bytes, addr).await {
+                        debug!("SWIM => send to {} failed: {:?}", addr, e);
+                    }
+                }
+                Err(e) => error!("SWIM => serialize: {:?}", e),
+            }
+        }
+    }
+    info!("SWIM => beendet");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    fn cfg() -> SwimConfig {
+        SwimConfig {
+            probe_timeout: Duration::from_millis(100),
+            suspicion_timeout: Duration::from_millis(500),
+            ..SwimConfig::default()
+        }
+    }
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    fn key(i: u8) -> Arc<Keypair> {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[i; 32]).unwrap();
+        let public = (&secret).into();
+        Arc::new(Keypair { secret, public })
+    }
+
+    /// Member-ID von Node `i` (aus dessen Key abgeleitet)
+    fn id(i: u8) -> String {
+        member_key(&NodeId::from_public_key(&key(i).public))
+    }
+
+    /// Drei Nodes 1/2/3, die sich gegenseitig kennen.
+    fn cluster(now: Instant) -> HashMap<SocketAddr, Membership> {
+        let mut nodes = HashMap::new();
+        for i in 1..=3u8 {
+            let mut m = Membership::new(&id(i), addr(i as u16), cfg());
+            m.set_signing_key(key(i));
+            for j in 1..=3u8 {
+                m.add_member(&id(j), addr(j as u16), now);
+            }
+            nodes.insert(addr(i as u16), m);
+        }
+        nodes
+    }
+
+    /// Stellt Nachrichten zu, bis das Netz ruhig ist; `blocked(src, dst)` verwirft.
+    fn deliver(
+        nodes: &mut HashMap<SocketAddr, Membership>,
+        src: SocketAddr,
+        out: SwimOutgoing,
+        now: Instant,
+        blocked: &dyn Fn(SocketAddr, SocketAddr) -> bool,
+    ) {
+        let signed = nodes[&src].sign_outgoing(out);
+        let mut queue: VecDeque<(SocketAddr, SocketAddr, SignedSwimMessage)> =
+            signed.into_iter().map(|(dst, m)| (src, dst, m)).collect();
+        while let Some((from, to, msg)) = queue.pop_front() {
+            if blocked(from, to) {
+                continue;
+            }
+            if let Some(node) = nodes.get_mut(&to) {
+                let replies = node.handle_signed(from, msg, now);
+                for (dst, m) in node.sign_outgoing(replies) {
+                    queue.push_back((to, dst, m));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_missed_probes_mark_suspect_then_dead() {
+        let t0 = Instant::now();
+        let mut nodes = cluster(t0);
+        let (a, c) = (addr(1), addr(3));
+        let c_down = |_src: SocketAddr, dst: SocketAddr| dst == addr(3);
+
+        let out = nodes.get_mut(&a).unwrap().probe_target(&id(3), t0);
+        deliver(&mut nodes, a, out, t0, &c_down);
+        assert_eq!(nodes[&a].state_of(&id(3)), Some(MemberState::Alive));
+
+        // direkter Timeout => PingReq an B, B erreicht C ebenfalls nicht
+        let t1 = t0 + Duration::from_millis(100);
+        let out = nodes.get_mut(&a).unwrap().tick(t1);
+        assert!(out.iter().any(|(dst, m)| *dst == addr(2) && matches!(m, SwimMessage::PingReq { .. })));
+        deliver(&mut nodes, a, out, t1, &c_down);
+        assert_eq!(nodes[&a].state_of(&id(3)), Some(MemberState::Alive));
+
+        let t2 = t1 + Duration::from_millis(100);
+        let out = nodes.get_mut(&a).unwrap().tick(t2);
+        deliver(&mut nodes, a, out, t2, &c_down);
+        assert_eq!(nodes[&a].state_of(&id(3)), Some(MemberState::Suspect));
+        assert!(nodes[&a].is_alive(&id(3)));
+
+        let t3 = t2 + Duration::from_millis(500);
+        let out = nodes.get_mut(&a).unwrap().tick(t3);
+        deliver(&mut nodes, a, out, t3, &c_down);
+        assert_eq!(nodes[&a].state_of(&id(3)), Some(MemberState::Dead));
+        assert_eq!(nodes[&a].alive_members(), vec![id(2)]);
+
+        // Dissemination: B erfährt per Piggyback vom Tod von C
+        let out = nodes.get_mut(&a).unwrap().probe_target(&id(2), t3);
+        deliver(&mut nodes, a, out, t3, &c_down);
+        assert_eq!(nodes[&addr(2)].state_of(&id(3)), Some(MemberState::Dead));
+    }
+
+    #[test]
+    fn test_indirect_ack_refutes_failed_direct_probe() {
+        let t0 = Instant::now();
+        let mut nodes = cluster(t0);
+        let a = addr(1);
+        // nur die Verbindung A <-> C ist gestört
+        let a_c_cut = |src: SocketAddr, dst: SocketAddr| {
+            (src == addr(1) && dst == addr(3)) || (src == addr(3) && dst == addr(1))
+        };
+
+        let out = nodes.get_mut(&a).unwrap().probe_target(&id(3), t0);
+        deliver(&mut nodes, a, out, t0, &a_c_cut);
+
+        let t1 = t0 + Duration::from_millis(100);
+        let out = nodes.get_mut(&a).unwrap().tick(t1);
+        deliver(&mut nodes, a, out, t1, &a_c_cut);
+
+        // Ack kam über B zurück => kein Verdacht
+        for t in [t1 + Duration::from_millis(100), t1 + Duration::from_secs(2)] {
+            let out = nodes.get_mut(&a).unwrap().tick(t);
+            deliver(&mut nodes, a, out, t, &a_c_cut);
+            assert_eq!(nodes[&a].state_of(&id(3)), Some(MemberState::Alive));
+        }
+    }
+
+    #[test]
+    fn test_suspect_refutes_with_higher_incarnation() {
+        let t0 = Instant::now();
+        let mut nodes = cluster(t0);
+        let (a, b) = (addr(1), addr(2));
+        let none = |_: SocketAddr, _: SocketAddr| false;
+
+        // A hält B fälschlich für verdächtig und sagt es B
+        let suspicion = MemberUpdate { id: id(2), addr: b, state: MemberState::Suspect, incarnation: 0, proof: None };
+        nodes.get_mut(&a).unwrap().apply_update(suspicion.clone(), t0);
+        assert_eq!(nodes[&a].state_of(&id(2)), Some(MemberState::Suspect));
+
+        let out = nodes.get_mut(&a).unwrap().probe_target(&id(2), t0);
+        deliver(&mut nodes, a, out, t0, &none);
+        assert_eq!(nodes[&b].incarnation(), 1);
+        // Ack von B trägt Alive(inc 1) => überschreibt Suspect(inc 0)
+        assert_eq!(nodes[&a].state_of(&id(2)), Some(MemberState::Alive));
+
+        let t1 = t0 + Duration::from_secs(1);
+        let out = nodes.get_mut(&a).unwrap().tick(t1);
+        deliver(&mut nodes, a, out, t1, &none);
+        assert_eq!(nodes[&a].state_of(&id(2)), Some(MemberState::Alive));
+    }
+
+    #[test]
+    fn test_forged_or_unsigned_messages_rejected() {
+        let t0 = Instant::now();
+        let mut nodes = cluster(t0);
+        let a = addr(1);
+        let ping = SwimMessage::Ping { from: id(2), seq: 1, updates: vec![] };
+
+        // Node 3 gibt sich als Node 2 aus
+        let forged = SignedSwimMessage::sign(ping.clone(), &key(3)).unwrap();
+        assert!(forged.verify().is_none());
+        assert!(nodes.get_mut(&a).unwrap().handle_signed(addr(3), forged, t0).is_empty());
+
+        // Nachträglich veränderte Nachricht
+        let mut tampered = SignedSwimMessage::sign(ping.clone(), &key(2)).unwrap();
+        tampered.msg = SwimMessage::Ping { from: id(2), seq: 2, updates: vec![] };
+        assert!(nodes.get_mut(&a).unwrap().handle_signed(addr(2), tampered, t0).is_empty());
+
+        // Nicht zugelassener Key
+        let mut closed = Membership::new(&id(1), a, SwimConfig { allowed_keys: vec![key(2).public], ..cfg() });
+        let stranger = SignedSwimMessage::sign(SwimMessage::Ping { from: id(4), seq: 1, updates: vec![] }, &key(4)).unwrap();
+        assert!(closed.handle_signed(addr(4), stranger, t0).is_empty());
+        assert_eq!(closed.state_of(&id(4)), None);
+        let ok = SignedSwimMessage::sign(ping, &key(2)).unwrap();
+        assert_eq!(closed.handle_signed(addr(2), ok, t0).len(), 1);
+
+        // Alive mit höherer Incarnation ohne gültigen Proof hält niemanden am Leben
+        let node = nodes.get_mut(&a).unwrap();
+        node.apply_update(MemberUpdate { id: id(3), addr: addr(3), state: MemberState::Dead, incarnation: 0, proof: None }, t0);
+        let mut fake = MemberUpdate { id: id(3), addr: addr(3), state: MemberState::Alive, incarnation: 5, proof: None };
+        node.apply_update(fake.clone(), t0);
+        assert_eq!(node.state_of(&id(3)), Some(MemberState::Dead));
+        let bytes = alive_signing_bytes(&id(3), addr(3), 5).unwrap();
+        fake.proof = Some(AliveProof {
+            public_key: key(2).public.as_bytes().to_vec(),
+            signature: key(2).sign(&bytes).to_bytes().to_vec(),
+        });
+        node.apply_update(fake, t0);
+        assert_eq!(node.state_of(&id(3)), Some(MemberState::Dead));
+    }
+
+    #[test]
+    fn test_dead_member_rejoins_with_higher_incarnation() {
+        let t0 = Instant::now();
+        let mut nodes = cluster(t0);
+        let (a, c) = (addr(1), addr(3));
+        let none = |_: SocketAddr, _: SocketAddr| false;
+
+        // A hat C (fälschlich) für tot erklärt
+        let dead = MemberUpdate { id: id(3), addr: c, state: MemberState::Dead, incarnation: 0, proof: None };
+        nodes.get_mut(&a).unwrap().apply_update(dead, t0);
+        assert_eq!(nodes[&a].state_of(&id(3)), Some(MemberState::Dead));
+        assert!(!nodes[&a].alive_members().contains(&id(3)));
+
+        // C meldet sich bei A => bekommt den Dead-Eintrag zurück, widerlegt
+        // mit Incarnation 1 und ist bei A wieder Alive
+        let out = nodes.get_mut(&c).unwrap().probe_target(&id(1), t0);
+        deliver(&mut nodes, c, out, t0, &none);
+        assert_eq!(nodes[&c].incarnation(), 1);
+        let out = nodes.get_mut(&c).unwrap().probe_target(&id(1), t0);
+        deliver(&mut nodes, c, out, t0, &none);
+        assert_eq!(nodes[&a].state_of(&id(3)), Some(MemberState::Alive));
+        assert!(nodes[&a].alive_members().contains(&id(3)));
+    }
+}
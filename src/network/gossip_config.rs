@@ -6,11 +6,26 @@
 // die es erm�glicht, zwischen Push- und Pull-Mechanismen zu w�hlen,
 // Zeitintervalle f�r den State-Austausch anzupassen und zu entscheiden,
 // ob Deltas oder der vollst�ndige State gesendet wird.
+//
+// Beim Peering tauschen beide Seiten ihre Gossip-Parameter signiert aus
+// (siehe `GossipExchange`, Feature-Flag FEATURE_GOSSIP_CONFIG). Jeder
+// Parameter hat eine Unter- und Obergrenze (`GossipBounds`): die eigene
+// Konfiguration wird darauf geklemmt, ein Peer außerhalb der Grenzen wird
+// abgelehnt. Weicht ein Peer stark von uns ab, wird gewarnt.
+//
+// Die Signatur deckt auch den statischen Noise-Schlüssel des Absenders ab:
+// Eine Konfiguration gilt nur auf der Verbindung, deren Handshake genau
+// diesen Schlüssel authentisiert hat, und lässt sich nicht über eine andere
+// Verbindung wieder einspielen. Übernommen wird je Peer eine gemeinsame
+// Konfiguration (`GossipExchange::effective`).
 ///////////////////////////////////////////////////////////
 
+use std::sync::Arc;
 use std::time::Duration;
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
 use serde::{Serialize, Deserialize};
-use tracing::{info, debug};
+use thiserror::Error;
+use tracing::{info, debug, warn};
 
 /// Enum zur Auswahl des Gossip-Modus
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +43,10 @@ pub struct GossipConfig {
     pub interval: Duration,
     /// Senden wir nur Deltas oder den vollst�ndigen State
     pub use_deltas: bool,
+    /// An wie viele Peers eine Nachricht je Runde weitergereicht wird
+    pub fanout: usize,
+    /// Lebensdauer einer Gossip-Nachricht, danach wird sie verworfen
+    pub message_ttl: Duration,
 }
 
 impl GossipConfig {
@@ -36,6 +55,188 @@ impl GossipConfig {
             mode: GossipMode::Push,
             interval: Duration::from_secs(5),
             use_deltas: true,
+            fanout: 6,
+            message_ttl: Duration::from_secs(120),
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum GossipConfigError {
+    #[error("gossip parameter {field} out of bounds: {value}")]
+    OutOfBounds { field: &'static str, value: String },
+
+    #[error("invalid signature on gossip config")]
+    InvalidSignature,
+
+    #[error("gossip config signed for a different noise peer key")]
+    PeerMismatch,
+
+    #[error("malformed gossip config: {0}")]
+    Malformed(String),
+}
+
+/// Erlaubter Bereich je Parameter (jeweils inklusive).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GossipBounds {
+    pub interval: (Duration, Duration),
+    pub fanout: (usize, usize),
+    pub message_ttl: (Duration, Duration),
+    /// Ab diesem Faktor Abweichung zur eigenen Konfiguration wird gewarnt
+    pub divergence_factor: f64,
+}
+
+impl Default for GossipBounds {
+    fn default() -> Self {
+        Self {
+            interval: (Duration::from_secs(1), Duration::from_secs(60)),
+            fanout: (2, 16),
+            message_ttl: (Duration::from_secs(10), Duration::from_secs(3600)),
+            divergence_factor: 2.0,
+        }
+    }
+}
+
+impl GossipBounds {
+    /// Erster Parameter außerhalb der Grenzen => Fehler.
+    pub fn check(&self, config: &GossipConfig) -> Result<(), GossipConfigError> {
+        fn within<T: PartialOrd + std::fmt::Debug>(field: &'static str, v: T, (lo, hi): (T, T)) -> Result<(), GossipConfigError> {
+            if v < lo || v > hi {
+                return Err(GossipConfigError::OutOfBounds { field, value: format!("{:?}", v) });
+            }
+            Ok(())
+        }
+        within("interval", config.interval, self.interval)?;
+        within("fanout", config.fanout, self.fanout)?;
+        within("message_ttl", config.message_ttl, self.message_ttl)
+    }
+
+    /// Klemmt jeden Parameter in seinen Bereich.
+    pub fn clamp(&self, config: &GossipConfig) -> GossipConfig {
+        let mut c = config.clone();
+        c.interval = c.interval.clamp(self.interval.0, self.interval.1);
+        c.fanout = c.fanout.clamp(self.fanout.0, self.fanout.1);
+        c.message_ttl = c.message_ttl.clamp(self.message_ttl.0, self.message_ttl.1);
+        c
+    }
+
+    /// Parameter, bei denen `remote` um mehr als `divergence_factor` von `local` abweicht.
+    pub fn divergent_fields(&self, local: &GossipConfig, remote: &GossipConfig) -> Vec<&'static str> {
+        let off = |a: f64, b: f64| a.max(b) > a.min(b) * self.divergence_factor;
+        let mut fields = Vec::new();
+        if off(local.interval.as_secs_f64(), remote.interval.as_secs_f64()) {
+            fields.push("interval");
+        }
+        if off(local.fanout as f64, remote.fanout as f64) {
+            fields.push("fanout");
+        }
+        if off(local.message_ttl.as_secs_f64(), remote.message_ttl.as_secs_f64()) {
+            fields.push("message_ttl");
+        }
+        fields
+    }
+}
+
+const GOSSIP_CONFIG_DOMAIN: &str = "my_dex/gossip_config/v1";
+
+/// Gossip-Konfiguration mit ed25519-Signatur des Absenders, gebunden an
+/// dessen statischen Noise-Schlüssel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedGossipConfig {
+    pub config: GossipConfig,
+    /// Statischer Noise-Schlüssel, über den der Absender verbunden ist
+    pub noise_static: Vec<u8>,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct GossipConfigSigningView<'a> {
+    config: &'a GossipConfig,
+    noise_static: &'a [u8],
+}
+
+impl SignedGossipConfig {
+    fn signing_bytes(config: &GossipConfig, noise_static: &[u8]) -> Result<Vec<u8>, GossipConfigError> {
+        crate::utils::canonical::signing_bytes(GOSSIP_CONFIG_DOMAIN, &GossipConfigSigningView { config, noise_static })
+            .map_err(|e| GossipConfigError::Malformed(e.to_string()))
+    }
+
+    pub fn sign(config: GossipConfig, noise_static: &[u8], keypair: &Keypair) -> Self {
+        let bytes = Self::signing_bytes(&config, noise_static).expect("GossipConfig enthält keine Floats");
+        let signature = keypair.sign(&bytes).to_bytes().to_vec();
+        Self { config, noise_static: noise_static.to_vec(), public_key: keypair.public.to_bytes().to_vec(), signature }
+    }
+
+    /// Prüft die Signatur und dass sie für `peer_noise_static` ausgestellt
+    /// wurde, den im Handshake authentisierten Schlüssel der Gegenseite.
+    pub fn verify(&self, peer_noise_static: &[u8]) -> Result<(), GossipConfigError> {
+        if self.noise_static != peer_noise_static {
+            return Err(GossipConfigError::PeerMismatch);
+        }
+        let pubkey = PublicKey::from_bytes(&self.public_key).map_err(|_| GossipConfigError::InvalidSignature)?;
+        let signature = Signature::from_bytes(&self.signature).map_err(|_| GossipConfigError::InvalidSignature)?;
+        pubkey
+            .verify(&Self::signing_bytes(&self.config, &self.noise_static)?, &signature)
+            .map_err(|_| GossipConfigError::InvalidSignature)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap_or_default()
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, GossipConfigError> {
+        bincode::deserialize(data).map_err(|e| GossipConfigError::Malformed(e.to_string()))
+    }
+}
+
+/// Eigene (geklemmte) Konfiguration, Signierschlüssel und Grenzen für Peers.
+#[derive(Debug, Clone)]
+pub struct GossipExchange {
+    pub local: GossipConfig,
+    keypair: Arc<Keypair>,
+    pub bounds: GossipBounds,
+}
+
+impl GossipExchange {
+    pub fn new(config: &GossipConfig, keypair: Arc<Keypair>, bounds: GossipBounds) -> Self {
+        let clamped = bounds.clamp(config);
+        if bounds.check(config).is_err() {
+            warn!("Gossip-Konfiguration außerhalb der Grenzen => geklemmt auf {:?}", clamped);
+        }
+        Self { local: clamped, keypair, bounds }
+    }
+
+    /// Eigene Konfiguration, signiert für die Verbindung mit dem eigenen
+    /// statischen Noise-Schlüssel `local_noise_static`.
+    pub fn signed_for(&self, local_noise_static: &[u8]) -> SignedGossipConfig {
+        SignedGossipConfig::sign(self.local.clone(), local_noise_static, &self.keypair)
+    }
+
+    /// Prüft die Konfiguration eines Peers: Signatur, Bindung an dessen
+    /// Noise-Schlüssel und Grenzen müssen stimmen, starke Abweichungen werden
+    /// nur geloggt. Liefert die für diesen Peer geltende Konfiguration.
+    pub fn accept_peer(&self, remote: &SignedGossipConfig, peer_noise_static: &[u8]) -> Result<GossipConfig, GossipConfigError> {
+        remote.verify(peer_noise_static)?;
+        self.bounds.check(&remote.config)?;
+        let divergent = self.bounds.divergent_fields(&self.local, &remote.config);
+        if !divergent.is_empty() {
+            warn!("Gossip-Konfiguration des Peers weicht stark ab: {:?} (lokal {:?}, remote {:?})",
+                  divergent, self.local, remote.config);
+        }
+        Ok(self.effective(&remote.config))
+    }
+
+    /// Gemeinsame Konfiguration mit einem Peer: nie schneller pushen und
+    /// Nachrichten nie länger halten, als der Peer es will; Deltas nur, wenn
+    /// beide sie nutzen. Modus und Fanout bleiben lokal.
+    pub fn effective(&self, remote: &GossipConfig) -> GossipConfig {
+        GossipConfig {
+            mode: self.local.mode.clone(),
+            interval: self.local.interval.max(remote.interval),
+            use_deltas: self.local.use_deltas && remote.use_deltas,
+            fanout: self.local.fanout,
+            message_ttl: self.local.message_ttl.min(remote.message_ttl),
         }
     }
 }
@@ -54,6 +255,65 @@ pub fn next_gossip_interval(config: &GossipConfig) -> Duration {
 
 /// Loggt die aktuelle Konfiguration des Gossip-Protokolls.
 pub fn log_gossip_config(config: &GossipConfig) {
-    info!("Gossip-Konfiguration: Modus: {:?}, Interval: {:?}, Deltas: {}",
-          config.mode, config.interval, config.use_deltas);
+    info!("Gossip-Konfiguration: Modus: {:?}, Interval: {:?}, Deltas: {}, Fanout: {}, TTL: {:?}",
+          config.mode, config.interval, config.use_deltas, config.fanout, config.message_ttl);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair(seed: u8) -> Arc<Keypair> {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Arc::new(Keypair { secret, public })
+    }
+
+    const NOISE_A: [u8; 32] = [0xA1; 32];
+    const NOISE_B: [u8; 32] = [0xB2; 32];
+
+    #[test]
+    fn test_local_config_clamped_and_peer_out_of_bounds_rejected() {
+        let mut cfg = GossipConfig::new();
+        cfg.fanout = 100;
+        cfg.interval = Duration::from_millis(10);
+        let ex = GossipExchange::new(&cfg, keypair(1), GossipBounds::default());
+        assert_eq!(ex.local.fanout, 16);
+        assert_eq!(ex.local.interval, Duration::from_secs(1));
+        assert!(ex.signed_for(&NOISE_A).verify(&NOISE_A).is_ok());
+
+        let mut bad = GossipConfig::new();
+        bad.message_ttl = Duration::from_secs(86_400);
+        let remote = SignedGossipConfig::sign(bad, &NOISE_B, &keypair(2));
+        assert_eq!(
+            ex.accept_peer(&remote, &NOISE_B),
+            Err(GossipConfigError::OutOfBounds { field: "message_ttl", value: "86400s".into() })
+        );
+    }
+
+    #[test]
+    fn test_compatible_peer_accepted_and_tampering_detected() {
+        let ex = GossipExchange::new(&GossipConfig::new(), keypair(1), GossipBounds::default());
+
+        let mut slow = GossipConfig::new();
+        slow.interval = Duration::from_secs(30);
+        slow.message_ttl = Duration::from_secs(60);
+        let remote = SignedGossipConfig::sign(slow.clone(), &NOISE_B, &keypair(2));
+        let decoded = SignedGossipConfig::decode(&remote.encode()).unwrap();
+        let effective = ex.accept_peer(&decoded, &NOISE_B).unwrap();
+        assert_eq!(effective.interval, Duration::from_secs(30));
+        assert_eq!(effective.message_ttl, Duration::from_secs(60));
+        assert_eq!(effective.fanout, ex.local.fanout);
+        assert_eq!(ex.bounds.divergent_fields(&ex.local, &slow), vec!["interval"]);
+
+        let mut tampered = remote.clone();
+        tampered.config.fanout = 3;
+        assert_eq!(ex.accept_peer(&tampered, &NOISE_B), Err(GossipConfigError::InvalidSignature));
+
+        // Auf einer anderen Verbindung wieder eingespielt => abgelehnt
+        assert_eq!(ex.accept_peer(&remote, &NOISE_A), Err(GossipConfigError::PeerMismatch));
+        let mut rebound = remote;
+        rebound.noise_static = NOISE_A.to_vec();
+        assert_eq!(ex.accept_peer(&rebound, &NOISE_A), Err(GossipConfigError::InvalidSignature));
+    }
 }
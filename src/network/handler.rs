@@ -1,10 +1,34 @@
 /////////////////////////////////////////////////////
 /// my_DEX/src/network/handler.rs
 /////////////////////////////////////////////////////
+//
+// Neben dem einfachen `P2PNetwork` enthält dieses Modul die Eingangsprüfung
+// für KademliaMessages:
+//  - `decode_kademlia` liest zuerst nur den Varianten-Tag und dekodiert dann
+//    mit einem Byte-Budget je Nachrichtentyp. bincode prüft deklarierte Längen
+//    gegen dieses Budget, *bevor* es Speicher reserviert => ein `Store` mit
+//    1 TiB angekündigtem `data` kostet keine Allokation.
+//  - Nach dem Dekodieren werden die Anzahl-Limits (z. B. `closer_nodes`) geprüft.
+//  - `MessageGuard` vergibt Strafpunkte je abgelehnter Nachricht und stellt
+//    Wiederholungstäter für eine Weile unter Quarantäne. Schlüssel ist die im
+//    Noise-Handshake authentifizierte Peer-Identität (statischer Schlüssel),
+//    nicht die IP: Tor-Peers (alle 127.0.0.1) oder Peers hinter einem NAT
+//    teilen sich sonst die Strafe. Alte Einträge verfallen (`prune`).
+/////////////////////////////////////////////////////
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
 
+use bincode::Options;
+use thiserror::Error;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::kademlia::kademlia_service::{KademliaMessage, ID_LENGTH};
+use crate::metrics::P2P_MESSAGES_REJECTED;
 
 pub struct P2PNetwork {
     pub peers: Vec<String>,
@@ -39,3 +63,341 @@ impl P2PNetwork {
         Ok(())
     }
 }
+
+/// Obergrenzen je Nachrichtentyp.
+#[derive(Clone, Debug)]
+pub struct MessageLimits {
+    /// Harte Obergrenze für jede Nachricht (entspricht dem Transport-Frame)
+    pub max_message_bytes: usize,
+    pub max_key_bytes: usize,
+    pub max_value_bytes: usize,
+    pub max_closer_nodes: usize,
+    pub max_crdt_snapshots: usize,
+}
+
+impl Default for MessageLimits {
+    fn default() -> Self {
+        Self {
+            max_message_bytes: 65535,
+            max_key_bytes: 256,
+            max_value_bytes: 32 * 1024,
+            max_closer_nodes: 20,
+            max_crdt_snapshots: 64,
+        }
+    }
+}
+
+/// Großzügige Obergrenze für (NodeId, SocketAddr) in bincode.
+const NODE_ENTRY_BYTES: usize = ID_LENGTH + 32;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MessageRejection {
+    #[error("message of {len} bytes exceeds {limit}")]
+    TooLarge { len: usize, limit: usize },
+
+    #[error("{kind}: {field} exceeds limit {limit}")]
+    LimitExceeded { kind: &'static str, field: &'static str, limit: usize },
+
+    #[error("malformed message: {0}")]
+    Malformed(String),
+}
+
+impl MessageRejection {
+    fn reason(&self) -> &'static str {
+        match self {
+            MessageRejection::TooLarge { .. } => "too_large",
+            MessageRejection::LimitExceeded { .. } => "limit_exceeded",
+            MessageRejection::Malformed(_) => "malformed",
+        }
+    }
+
+    /// Strafpunkte: Übergröße wiegt schwerer als ein einfacher Parse-Fehler.
+    fn penalty(&self) -> u32 {
+        match self {
+            MessageRejection::Malformed(_) => 10,
+            MessageRejection::TooLarge { .. } | MessageRejection::LimitExceeded { .. } => 25,
+        }
+    }
+}
+
+/// Byte-Budget je Varianten-Tag (Reihenfolge wie in `KademliaMessage`).
+fn variant_budget(tag: u32, limits: &MessageLimits) -> Result<(&'static str, usize), MessageRejection> {
+    let id = ID_LENGTH;
+    let len = 8; // u64-Längenpräfix
+    let budget = match tag {
+        0 => ("Ping", 4 + id),
+        1 => ("Pong", 4 + id),
+        2 => ("FindNode", 4 + 2 * id),
+        3 => ("FindNodeResult", 4 + id + len + limits.max_closer_nodes * NODE_ENTRY_BYTES),
+        4 => ("Store", 4 + id + len + limits.max_key_bytes + len + limits.max_value_bytes),
+        5 => ("StoreResult", 4 + id + 1),
+        6 => ("FindValue", 4 + id + len + limits.max_key_bytes),
+        7 => (
+            "FindValueResult",
+            4 + id + len + limits.max_key_bytes + 1 + len + limits.max_value_bytes
+                + len + limits.max_closer_nodes * NODE_ENTRY_BYTES,
+        ),
+        8 => ("CrdtSnapshots", limits.max_message_bytes),
+        9 => ("Dkg", limits.max_message_bytes),
+        10 => ("ModeTransition", limits.max_message_bytes),
+        other => return Err(MessageRejection::Malformed(format!("unknown variant {}", other))),
+    };
+    Ok((budget.0, budget.1.min(limits.max_message_bytes)))
+}
+
+/// Dekodiert eine KademliaMessage unter Einhaltung von `limits`.
+/// Kompatibel zu `bincode::serialize` (fixint, little endian).
+pub fn decode_kademlia(bytes: &[u8], limits: &MessageLimits) -> Result<KademliaMessage, MessageRejection> {
+    if bytes.len() > limits.max_message_bytes {
+        return Err(MessageRejection::TooLarge { len: bytes.len(), limit: limits.max_message_bytes });
+    }
+    let tag = bytes
+        .get(..4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| MessageRejection::Malformed("truncated variant tag".into()))?;
+    let (kind, budget) = variant_budget(tag, limits)?;
+
+    let msg: KademliaMessage = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(budget as u64)
+        .deserialize(bytes)
+        .map_err(|e| match *e {
+            bincode::ErrorKind::SizeLimit => MessageRejection::LimitExceeded { kind, field: "bytes", limit: budget },
+            other => MessageRejection::Malformed(other.to_string()),
+        })?;
+
+    let exceeded = |field: &'static str, limit: usize| Err(MessageRejection::LimitExceeded { kind, field, limit });
+    match &msg {
+        KademliaMessage::FindNodeResult { closer_nodes, .. } | KademliaMessage::FindValueResult { closer_nodes, .. }
+            if closer_nodes.len() > limits.max_closer_nodes =>
+        {
+            exceeded("closer_nodes", limits.max_closer_nodes)
+        }
+        KademliaMessage::Store { key, .. } | KademliaMessage::FindValue { key, .. } | KademliaMessage::FindValueResult { key, .. }
+            if key.len() > limits.max_key_bytes =>
+        {
+            exceeded("key", limits.max_key_bytes)
+        }
+        KademliaMessage::Store { data, .. } if data.len() > limits.max_value_bytes => {
+            exceeded("data", limits.max_value_bytes)
+        }
+        KademliaMessage::FindValueResult { data: Some(data), .. } if data.len() > limits.max_value_bytes => {
+            exceeded("data", limits.max_value_bytes)
+        }
+        KademliaMessage::CrdtSnapshots(snaps) if snaps.len() > limits.max_crdt_snapshots => {
+            exceeded("snapshots", limits.max_crdt_snapshots)
+        }
+        _ => Ok(msg),
+    }
+}
+
+#[derive(Clone, Debug)]
+struct PeerStrikes {
+    score: u32,
+    last_strike: Instant,
+    quarantined_until: Option<Instant>,
+}
+
+/// Höchstens so viele Peers werden verfolgt, bevor `prune` aufräumt.
+pub const MAX_TRACKED_PEERS: usize = 10_000;
+
+/// Strafpunkte und Quarantäne je authentifizierter Peer-Identität (statischer
+/// Noise-Schlüssel). Punkte verfallen nach `quarantine_duration` ohne neuen
+/// Verstoß.
+#[derive(Debug)]
+pub struct MessageGuard {
+    pub limits: MessageLimits,
+    /// Ab dieser Punktzahl => Quarantäne
+    pub quarantine_threshold: u32,
+    pub quarantine_duration: Duration,
+    pub max_tracked_peers: usize,
+    peers: HashMap<Vec<u8>, PeerStrikes>,
+}
+
+impl Default for MessageGuard {
+    fn default() -> Self {
+        Self::new(MessageLimits::default())
+    }
+}
+
+impl MessageGuard {
+    pub fn new(limits: MessageLimits) -> Self {
+        Self {
+            limits,
+            quarantine_threshold: 50,
+            quarantine_duration: Duration::from_secs(600),
+            max_tracked_peers: MAX_TRACKED_PEERS,
+            peers: HashMap::new(),
+        }
+    }
+
+    pub fn is_quarantined(&self, peer: &[u8], now: Instant) -> bool {
+        self.peers
+            .get(peer)
+            .and_then(|p| p.quarantined_until)
+            .map(|until| now < until)
+            .unwrap_or(false)
+    }
+
+    pub fn score(&self, peer: &[u8]) -> u32 {
+        self.peers.get(peer).map(|p| p.score).unwrap_or(0)
+    }
+
+    pub fn tracked_peers(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Entfernt Peers ohne laufende Quarantäne, deren letzter Verstoß
+    /// länger als `quarantine_duration` zurückliegt.
+    pub fn prune(&mut self, now: Instant) {
+        let window = self.quarantine_duration;
+        self.peers.retain(|_, p| {
+            p.quarantined_until.map(|until| now < until).unwrap_or(false)
+                || (p.score > 0 && now.saturating_duration_since(p.last_strike) < window)
+        });
+    }
+
+    /// Prüft eine eingehende Nachricht des Peers `peer` (statischer
+    /// Noise-Schlüssel); `addr` dient nur dem Logging. Nachrichten von Peers in
+    /// Quarantäne werden ohne Dekodierung verworfen.
+    pub fn check(&mut self, peer: &[u8], addr: SocketAddr, bytes: &[u8], now: Instant) -> Result<KademliaMessage, MessageRejection> {
+        if self.is_quarantined(peer, now) {
+            P2P_MESSAGES_REJECTED.with_label_values(&["quarantined"]).inc();
+            return Err(MessageRejection::Malformed(format!("{} is quarantined", addr)));
+        }
+        decode_kademlia(bytes, &self.limits).map_err(|rej| {
+            self.penalize(peer, addr, &rej, now);
+            rej
+        })
+    }
+
+    fn penalize(&mut self, peer: &[u8], addr: SocketAddr, rej: &MessageRejection, now: Instant) {
+        P2P_MESSAGES_REJECTED.with_label_values(&[rej.reason()]).inc();
+        if !self.peers.contains_key(peer) && self.peers.len() >= self.max_tracked_peers {
+            self.prune(now);
+        }
+        let window = self.quarantine_duration;
+        let entry = self.peers.entry(peer.to_vec()).or_insert(PeerStrikes {
+            score: 0,
+            last_strike: now,
+            quarantined_until: None,
+        });
+        // Verfall: lange kein Verstoß => von vorn zählen
+        if now.saturating_duration_since(entry.last_strike) >= window {
+            entry.score = 0;
+        }
+        entry.last_strike = now;
+        entry.score = entry.score.saturating_add(rej.penalty());
+        warn!("Nachricht von {} verworfen ({}) => score={}", addr, rej, entry.score);
+        if entry.score >= self.quarantine_threshold {
+            entry.quarantined_until = Some(now + self.quarantine_duration);
+            entry.score = 0;
+            warn!("Peer {} => Quarantäne für {:?}", addr, self.quarantine_duration);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kademlia::kademlia_service::NodeId;
+
+    fn addr() -> SocketAddr {
+        "10.0.0.7:4000".parse().unwrap()
+    }
+
+    const PEER: &[u8] = &[7; 32];
+
+    /// Store-Frame mit angekündigter, aber nicht mitgesendeter `data`-Länge.
+    fn store_header(data_len: u64) -> Vec<u8> {
+        let mut b = Vec::new();
+        b.extend_from_slice(&4u32.to_le_bytes());
+        b.extend_from_slice(&[7u8; ID_LENGTH]);
+        b.extend_from_slice(&1u64.to_le_bytes());
+        b.push(b'k');
+        b.extend_from_slice(&data_len.to_le_bytes());
+        b
+    }
+
+    #[test]
+    fn test_oversized_store_rejected_before_allocation() {
+        let limits = MessageLimits::default();
+        // 1 TiB angekündigt: eine Allokation würde den Prozess abbrechen
+        let res = decode_kademlia(&store_header(1 << 40), &limits);
+        assert_eq!(res.unwrap_err(), MessageRejection::LimitExceeded { kind: "Store", field: "bytes", limit: variant_budget(4, &limits).unwrap().1 });
+
+        // Gültiger Store innerhalb der Limits geht durch (kompatibel zu bincode::serialize)
+        let ok = KademliaMessage::Store { source: NodeId([1; ID_LENGTH]), key: b"k".to_vec(), data: vec![0; 1024] };
+        let decoded = decode_kademlia(&bincode::serialize(&ok).unwrap(), &limits).unwrap();
+        assert!(matches!(decoded, KademliaMessage::Store { data, .. } if data.len() == 1024));
+
+        // Zu viele closer_nodes, obwohl der Frame selbst klein genug ist
+        let tight = MessageLimits { max_closer_nodes: 2, ..MessageLimits::default() };
+        let many = KademliaMessage::FindNodeResult {
+            source: NodeId([1; ID_LENGTH]),
+            closer_nodes: (0..3).map(|i| (NodeId([i; ID_LENGTH]), addr())).collect(),
+        };
+        assert!(matches!(
+            decode_kademlia(&bincode::serialize(&many).unwrap(), &tight),
+            Err(MessageRejection::LimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_repeat_offender_is_quarantined() {
+        let mut guard = MessageGuard::default();
+        let now = Instant::now();
+
+        assert!(matches!(guard.check(PEER, addr(), b"\x63\x00", now), Err(MessageRejection::Malformed(_))));
+        assert_eq!(guard.score(PEER), 10);
+        guard.check(PEER, addr(), &store_header(1 << 40), now).unwrap_err();
+        assert_eq!(guard.score(PEER), 35);
+        assert!(!guard.is_quarantined(PEER, now));
+        guard.check(PEER, addr(), &store_header(1 << 40), now).unwrap_err();
+        assert!(guard.is_quarantined(PEER, now));
+
+        // Auch gültige Nachrichten werden während der Quarantäne verworfen,
+        // auch nach Wechsel von IP/Port
+        let ping = bincode::serialize(&KademliaMessage::Ping(NodeId([2; ID_LENGTH]))).unwrap();
+        assert!(guard.check(PEER, addr(), &ping, now).is_err());
+        assert!(guard.check(PEER, "10.9.9.9:5000".parse().unwrap(), &ping, now).is_err());
+        assert!(guard.check(PEER, addr(), &ping, now + guard.quarantine_duration).is_ok());
+    }
+
+    #[test]
+    fn test_peers_behind_one_ip_are_judged_separately() {
+        let mut guard = MessageGuard::default();
+        let now = Instant::now();
+        // Zwei Tor-Peers, beide über den lokalen SOCKS-Proxy
+        let tor: SocketAddr = "127.0.0.1:9050".parse().unwrap();
+        let honest: &[u8] = &[8; 32];
+        for _ in 0..3 {
+            let _ = guard.check(PEER, tor, &store_header(1 << 40), now);
+        }
+        assert!(guard.is_quarantined(PEER, now));
+
+        let ping = bincode::serialize(&KademliaMessage::Ping(NodeId([2; ID_LENGTH]))).unwrap();
+        assert!(guard.check(honest, tor, &ping, now).is_ok());
+        assert!(!guard.is_quarantined(honest, now));
+    }
+
+    #[test]
+    fn test_stale_strikes_are_pruned() {
+        let mut guard = MessageGuard { max_tracked_peers: 2, ..MessageGuard::default() };
+        let now = Instant::now();
+        let later = now + guard.quarantine_duration;
+        guard.check(&[1; 32], addr(), b"\x63\x00", now).unwrap_err();
+        guard.check(&[2; 32], addr(), b"\x63\x00", now).unwrap_err();
+        assert_eq!(guard.tracked_peers(), 2);
+
+        // Voll => alte Einträge fallen beim nächsten neuen Peer heraus
+        guard.check(&[3; 32], addr(), b"\x63\x00", later).unwrap_err();
+        assert_eq!(guard.tracked_peers(), 1);
+        assert_eq!(guard.score(&[1; 32]), 0);
+
+        // Punkte verfallen auch ohne Aufräumen
+        guard.check(&[3; 32], addr(), b"\x63\x00", later + guard.quarantine_duration).unwrap_err();
+        assert_eq!(guard.score(&[3; 32]), 10);
+    }
+}
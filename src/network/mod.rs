@@ -2,6 +2,7 @@
 /// my_DEX/src/network/mod.rs
 //////////////////////////////////////////////////
 
+pub mod address_book;
 pub mod cluster_management;
 pub mod gossip_config;
 pub mod handler;
@@ -11,6 +12,11 @@ pub mod p2p_adapter;
 pub mod p2p_operations;
 pub mod p2p_security;
 pub mod peer_management;
+pub mod port_mapping;
+pub mod reliable_gossip;
 pub mod secure_channel;
 pub mod security_monitor;
+pub mod stun;
 pub mod tcp;
+pub mod tor;
+pub mod turn;
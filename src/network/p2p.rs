@@ -2,10 +2,10 @@
 /// my_DEX/src/network/p2p.rs
 ////////////////////////////////////////////////
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
 use std::io::{Write, Read};
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -17,6 +17,13 @@ use tokio::task::{JoinHandle};
 use tokio::time::{sleep, timeout};
 use tracing::{info, warn, debug, error};
 
+use crate::metrics::RATE_LIMIT_DROPS;
+use crate::network::port_mapping::PortForwarder;
+use crate::network::tor::TorTransport;
+use tokio_util::sync::CancellationToken;
+use crate::rate_limiting::subnet_limiter::{SubnetLimiterConfig, SubnetRateLimiter};
+use crate::utils::lock::LockRecover;
+
 //////////////////////////////////////////////////////////////////////////////////////
 // NodeId: 256-Bit, Distanzberechnungen, Hilfsmethoden
 //////////////////////////////////////////////////////////////////////////////////////
@@ -95,6 +102,64 @@ pub enum KademliaMessage {
         data: Option<Vec<u8>>,
         closer_nodes: Vec<(NodeId, SocketAddr)>,
     },
+
+    /// Eigene, von außen erreichbare Adresse (STUN-IP + TCP-Listen-Port)
+    Announce {
+        source: NodeId,
+        addr: SocketAddr,
+    },
+}
+
+//////////////////////////////////////////////////////////////////////////////////////
+// Eclipse-Schutz
+// - Teil jedes Buckets ist für langlebige, IP-diverse Peers reserviert
+// - neue Nodes müssen sich über Zeit bewähren, bevor sie etablierte ersetzen;
+//   bewährte Kandidaten werden beim Refresh angepingt, stumme verfallen
+// - Limits je IP (ganze Tabelle) und je Subnetz (pro Bucket)
+//////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct EclipseConfig {
+    /// Anteil der Bucket-Plätze für etablierte, subnetz-diverse Nodes
+    pub reserved_fraction: f64,
+    /// Ab diesem Alter gilt ein Eintrag als etabliert
+    pub min_established_age: Duration,
+    /// So lange muss ein neuer Node im Kandidaten-Cache bekannt sein,
+    /// bevor er einen bestehenden Eintrag ersetzen darf
+    pub min_candidate_age: Duration,
+    /// Kandidaten ohne Kontakt (Nachricht oder Ping-Antwort) verfallen danach
+    pub candidate_ttl: Duration,
+    /// Max. Einträge je IP über alle Buckets
+    pub max_per_ip: usize,
+    /// Max. Einträge je /24 (IPv4) bzw. /48 (IPv6) pro Bucket
+    pub max_per_subnet: usize,
+}
+
+impl Default for EclipseConfig {
+    fn default() -> Self {
+        Self {
+            reserved_fraction: 0.5,
+            min_established_age: Duration::from_secs(600),
+            min_candidate_age: Duration::from_secs(120),
+            candidate_ttl: Duration::from_secs(1800),
+            max_per_ip: 2,
+            max_per_subnet: 2,
+        }
+    }
+}
+
+/// Subnetz-Schlüssel: /24 für IPv4, /48 für IPv6.
+pub fn subnet_of(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(v4) => {
+            let o = v4.octets();
+            IpAddr::from([o[0], o[1], o[2], 0])
+        }
+        IpAddr::V6(v6) => {
+            let s = v6.segments();
+            IpAddr::from([s[0], s[1], s[2], 0, 0, 0, 0, 0])
+        }
+    }
 }
 
 //////////////////////////////////////////////////////////////////////////////////////
@@ -107,12 +172,16 @@ pub struct BucketEntry {
     pub node_id: NodeId,
     pub address: SocketAddr,
     pub last_seen: Instant,
+    /// Erster Kontakt => Grundlage für "etabliert" und Kandidaten-Alter
+    pub first_seen: Instant,
 }
 
 #[derive(Debug)]
 pub struct KBucket {
     pub entries: VecDeque<BucketEntry>,
     pub capacity: usize,
+    /// Neue Nodes, die (noch) keinen Platz bekommen haben
+    pub candidates: HashMap<NodeId, BucketEntry>,
 }
 
 impl KBucket {
@@ -120,56 +189,173 @@ impl KBucket {
         KBucket {
             entries: VecDeque::with_capacity(capacity),
             capacity,
+            candidates: HashMap::new(),
         }
     }
 
     /// Upsert:
-    /// 1) Falls Node existiert, nach vorne (aktualisiere last_seen).
-    /// 2) Falls neu und Bucket ist voll => LRU-Node anpingen. Wenn er nicht antwortet, werfe ihn raus.
+    /// 1) Falls Node existiert, nach vorne (aktualisiere last_seen, first_seen bleibt).
+    /// 2) Falls neu und Bucket nicht voll => einfügen (sofern Subnetz-Limit ok).
+    /// 3) Falls neu und Bucket voll => Node landet im Kandidaten-Cache (je Subnetz
+    ///    höchstens `max_per_subnet`, verfallene Kandidaten machen vorher Platz).
+    ///    Erst wenn er dort mindestens `min_candidate_age` bekannt ist, darf er
+    ///    den LRU-Node ersetzen - und auch nur, wenn dieser nicht antwortet und
+    ///    nicht zu den reservierten, langlebigen Einträgen gehört.
     pub fn upsert<F>(
         &mut self,
         node_id: NodeId,
         address: SocketAddr,
         do_ping: F,
+        cfg: &EclipseConfig,
     ) where
         F: Fn(NodeId, SocketAddr) -> bool,
     {
+        let now = Instant::now();
         if let Some(pos) = self.entries.iter().position(|e| e.node_id == node_id) {
             // Move nach vorne
             let mut entry = self.entries.remove(pos).unwrap();
-            entry.last_seen = Instant::now();
+            entry.last_seen = now;
             entry.address = address;
             self.entries.push_front(entry);
-        } else {
-            // Neu
-            if self.entries.len() >= self.capacity {
-                // LRU-Knoten = entries.back()
-                if let Some(lru) = self.entries.back() {
-                    // Pingen
-                    let ok = do_ping(lru.node_id.clone(), lru.address);
-                    if !ok {
-                        // LRU ist tot => remove
-                        self.entries.pop_back();
-                        // Füge den neuen Node ein
-                        let entry = BucketEntry {
-                            node_id,
-                            address,
-                            last_seen: Instant::now(),
-                        };
-                        self.entries.push_front(entry);
-                        return;
-                    }
+            return;
+        }
+
+        // Neu => max. `max_per_subnet` Einträge aus demselben Subnetz je Bucket
+        let subnet = subnet_of(address.ip());
+        let same_subnet = self.entries.iter().filter(|e| subnet_of(e.address.ip()) == subnet).count();
+        if same_subnet >= cfg.max_per_subnet {
+            debug!("KBucket: {} verworfen, Subnetz-Limit erreicht", address);
+            return;
+        }
+
+        if self.entries.len() < self.capacity {
+            let first_seen = self.candidates.remove(&node_id).map(|c| c.first_seen).unwrap_or(now);
+            self.entries.push_front(BucketEntry {
+                node_id,
+                address,
+                last_seen: now,
+                first_seen,
+            });
+            return;
+        }
+
+        // Bucket voll => Kandidat vormerken bzw. auffrischen
+        self.prune_candidates(now, cfg);
+        let qualified = {
+            if !self.candidates.contains_key(&node_id) {
+                let same_subnet = self
+                    .candidates
+                    .values()
+                    .filter(|c| subnet_of(c.address.ip()) == subnet)
+                    .count();
+                if same_subnet >= cfg.max_per_subnet || self.candidates.len() >= self.capacity {
+                    // Cache voll => bereits bekannte Kandidaten behalten
+                    return;
                 }
-                // Andernfalls verwerfen wir den neuen Node, da Bucket voll und LRU ist aktiv
-            } else {
-                let entry = BucketEntry {
-                    node_id,
-                    address,
-                    last_seen: Instant::now(),
-                };
-                self.entries.push_front(entry);
+            }
+            let cand = self.candidates.entry(node_id.clone()).or_insert(BucketEntry {
+                node_id: node_id.clone(),
+                address,
+                last_seen: now,
+                first_seen: now,
+            });
+            cand.last_seen = now;
+            cand.address = address;
+            now.duration_since(cand.first_seen) >= cfg.min_candidate_age
+        };
+        if qualified {
+            self.replace_lru_with(&node_id, &do_ping, cfg, now);
+        }
+    }
+
+    /// Ersetzt den ältesten nicht reservierten Eintrag durch den Kandidaten
+    /// `node_id`, sofern dieser Eintrag nicht auf den Ping antwortet.
+    /// Gibt false zurück, wenn der Bucket unverändert bleibt.
+    fn replace_lru_with<F>(&mut self, node_id: &NodeId, do_ping: &F, cfg: &EclipseConfig, now: Instant) -> bool
+    where
+        F: Fn(NodeId, SocketAddr) -> bool,
+    {
+        let Some(cand) = self.candidates.get(node_id) else {
+            return false;
+        };
+        let subnet = subnet_of(cand.address.ip());
+        if self.entries.iter().filter(|e| subnet_of(e.address.ip()) == subnet).count() >= cfg.max_per_subnet {
+            return false;
+        }
+        let protected = self.protected_ids(cfg);
+        let Some(pos) = self.entries.iter().rposition(|e| !protected.contains(&e.node_id)) else {
+            return false;
+        };
+        let lru = self.entries[pos].clone();
+        if do_ping(lru.node_id.clone(), lru.address) {
+            return false;
+        }
+        // LRU ist tot => ersetzen
+        self.entries.remove(pos);
+        let cand = self.candidates.remove(node_id).unwrap();
+        self.entries.push_front(BucketEntry { last_seen: now, ..cand });
+        true
+    }
+
+    /// Entfernt Kandidaten, die länger als `candidate_ttl` nicht gesehen wurden.
+    pub fn prune_candidates(&mut self, now: Instant, cfg: &EclipseConfig) {
+        self.candidates
+            .retain(|_, c| now.saturating_duration_since(c.last_seen) < cfg.candidate_ttl);
+    }
+
+    /// Beim Bucket-Refresh: bewährte Kandidaten (älteste zuerst) anpingen.
+    /// Wer nicht antwortet, fliegt aus dem Cache; wer antwortet, ersetzt einen
+    /// toten, nicht reservierten Eintrag - auch ohne sich erneut zu melden.
+    pub fn maintain_candidates<F>(&mut self, do_ping: F, cfg: &EclipseConfig)
+    where
+        F: Fn(NodeId, SocketAddr) -> bool,
+    {
+        let now = Instant::now();
+        self.prune_candidates(now, cfg);
+        let mut qualified: Vec<BucketEntry> = self
+            .candidates
+            .values()
+            .filter(|c| now.saturating_duration_since(c.first_seen) >= cfg.min_candidate_age)
+            .cloned()
+            .collect();
+        qualified.sort_by_key(|c| c.first_seen);
+        for cand in qualified {
+            if !do_ping(cand.node_id.clone(), cand.address) {
+                self.candidates.remove(&cand.node_id);
+                continue;
+            }
+            if let Some(c) = self.candidates.get_mut(&cand.node_id) {
+                c.last_seen = now;
+            }
+            if !self.replace_lru_with(&cand.node_id, &do_ping, cfg, now) {
+                // Ältester ersetzbarer Eintrag lebt => weitere Versuche sinnlos
+                break;
+            }
+        }
+    }
+
+    /// Reservierte Einträge: die ältesten etablierten Nodes mit paarweise
+    /// verschiedenen Subnetzen, höchstens `reserved_fraction * capacity` Stück.
+    pub fn protected_ids(&self, cfg: &EclipseConfig) -> HashSet<NodeId> {
+        let reserved = ((self.capacity as f64) * cfg.reserved_fraction).ceil() as usize;
+        let mut established: Vec<&BucketEntry> = self
+            .entries
+            .iter()
+            .filter(|e| e.first_seen.elapsed() >= cfg.min_established_age)
+            .collect();
+        established.sort_by_key(|e| e.first_seen);
+
+        let mut subnets = HashSet::new();
+        let mut out = HashSet::new();
+        for e in established {
+            if out.len() >= reserved {
+                break;
+            }
+            if subnets.insert(subnet_of(e.address.ip())) {
+                out.insert(e.node_id.clone());
             }
         }
+        out
     }
 
     pub fn remove(&mut self, node_id: &NodeId) {
@@ -202,6 +388,9 @@ pub struct RoutingTable {
     pub local_id: NodeId,
     pub buckets: Vec<KBucket>,
     pub bucket_size: usize,
+    pub eclipse: EclipseConfig,
+    /// Externe Adresse, die wir Peers per `Announce` mitteilen
+    pub advertised_addr: Option<SocketAddr>,
 }
 
 impl RoutingTable {
@@ -214,9 +403,59 @@ impl RoutingTable {
             local_id,
             buckets,
             bucket_size,
+            eclipse: EclipseConfig::default(),
+            advertised_addr: None,
+        }
+    }
+
+    pub fn set_advertised_addr(&mut self, addr: SocketAddr) {
+        if self.advertised_addr != Some(addr) {
+            info!("Advertised address => {}", addr);
+            self.advertised_addr = Some(addr);
         }
     }
 
+    /// Externe Adresse aus dem STUN-Ergebnis. STUN läuft über UDP, dessen
+    /// NAT-Mapping gilt nicht für den TCP-Listener; UPnP leitet den
+    /// Listen-Port 1:1 weiter => IP aus STUN, Port = eigener TCP-Port.
+    pub fn set_advertised_from_stun(&mut self, stun_mapped: SocketAddr, tcp_listen_port: u16) {
+        self.set_advertised_addr(SocketAddr::new(stun_mapped.ip(), tcp_listen_port));
+    }
+
+    /// `Announce` mit der eigenen externen Adresse (None, solange keine bekannt ist).
+    pub fn announcement(&self) -> Option<KademliaMessage> {
+        self.advertised_addr.map(|addr| KademliaMessage::Announce { source: self.local_id.clone(), addr })
+    }
+
+    /// Übernimmt die angekündigte Adresse eines Peers. Nur Adressen mit der
+    /// IP der Verbindung werden akzeptiert, sonst könnte ein Peer fremde
+    /// Hosts in die Tabellen anderer Nodes eintragen.
+    pub fn apply_announcement<F>(&mut self, source: NodeId, addr: SocketAddr, sender_addr: SocketAddr, do_ping: F) -> bool
+    where
+        F: Fn(NodeId, SocketAddr) -> bool,
+    {
+        if addr.ip() != sender_addr.ip() {
+            debug!("Announce {} von {} verworfen: IP passt nicht zur Verbindung", addr, sender_addr);
+            return false;
+        }
+        self.update_node(source, addr, do_ping);
+        true
+    }
+
+    pub fn with_eclipse_config(mut self, cfg: EclipseConfig) -> Self {
+        self.eclipse = cfg;
+        self
+    }
+
+    /// Anzahl Einträge mit dieser IP über alle Buckets.
+    fn entries_with_ip(&self, ip: IpAddr) -> usize {
+        self.buckets
+            .iter()
+            .flat_map(|b| b.entries.iter())
+            .filter(|e| e.address.ip() == ip)
+            .count()
+    }
+
     /// Berechnet Bucket-Index
     fn bucket_index(&self, node_id: &NodeId) -> usize {
         let x = self.local_id.xor(node_id);
@@ -243,7 +482,24 @@ impl RoutingTable {
             return;
         }
         let idx = self.bucket_index(&node_id);
-        self.buckets[idx].upsert(node_id, address, do_ping);
+        let known = self.buckets[idx].entries.iter().any(|e| e.node_id == node_id);
+        if !known && self.entries_with_ip(address.ip()) >= self.eclipse.max_per_ip {
+            debug!("RoutingTable: {} verworfen, IP-Limit erreicht", address);
+            return;
+        }
+        let cfg = self.eclipse.clone();
+        self.buckets[idx].upsert(node_id, address, do_ping, &cfg);
+    }
+
+    /// Kandidaten-Pflege über alle Buckets (siehe `KBucket::maintain_candidates`).
+    pub fn maintain_candidates<F>(&mut self, do_ping: F)
+    where
+        F: Fn(NodeId, SocketAddr) -> bool,
+    {
+        let cfg = self.eclipse.clone();
+        for bucket in self.buckets.iter_mut().filter(|b| !b.candidates.is_empty()) {
+            bucket.maintain_candidates(&do_ping, &cfg);
+        }
     }
 
     pub fn remove_node(&mut self, node_id: &NodeId) {
@@ -322,15 +578,14 @@ impl RoutingTable {
 // (Optional) NatTraversal => hier exemplarisch
 //////////////////////////////////////////////////////////////////////////////////////
 
-#[allow(unused)]
-pub fn try_upnp_port_forwarding(port: u16) {
-    // In echter Produktion könnte man crates wie igd (UPnP)
-    // oder nat_upnp nutzen.
-    // Hier nur ein Platzhalter:
-    info!("Versuche NAT-Portweiterleitung via UPnP für Port={}", port);
-    // ...
-    // => z. B. igd::aio::search_and_get_list().await, ...
-    // => je nach Erfolg => info oder warn
+/// Startet die Portweiterleitung (UPnP, Fallback NAT-PMP) im Hintergrund.
+/// Abbruch des Tokens entfernt das Mapping wieder; der Handle endet danach.
+pub fn try_upnp_port_forwarding(port: u16) -> (CancellationToken, JoinHandle<()>) {
+    info!("Versuche NAT-Portweiterleitung (UPnP/NAT-PMP) für Port={}", port);
+    let token = CancellationToken::new();
+    let forwarder = PortForwarder::with_defaults(port);
+    let handle = tokio::spawn(forwarder.run(token.clone()));
+    (token, handle)
 }
 
 //////////////////////////////////////////////////////////////////////////////////////
@@ -472,35 +727,94 @@ impl TokenBucket {
 pub struct P2PSecurity {
     // Rate-Limit => IP -> TokenBucket
     pub rate_limiters: Arc<Mutex<HashMap<SocketAddr, TokenBucket>>>,
+    // Darunter: /24- bzw. /64-Aggregation + adaptiver DDoS-Modus
+    pub subnet_limiter: Arc<Mutex<SubnetRateLimiter>>,
     pub use_tor: bool,
     pub stun_servers: Vec<String>,
+    /// Eingebetteter Tor-Client, nach `init_tor` gesetzt
+    pub tor: Mutex<Option<Arc<TorTransport>>>,
+    /// Nickname des eigenen Onion-Service; None => keiner veröffentlicht
+    pub onion_service_nickname: Option<String>,
+    pub tor_state_dir: Option<std::path::PathBuf>,
 }
 
 impl P2PSecurity {
     pub fn new(use_tor: bool, stun_servers: Vec<String>) -> Self {
         P2PSecurity {
             rate_limiters: Arc::new(Mutex::new(HashMap::new())),
+            subnet_limiter: Arc::new(Mutex::new(SubnetRateLimiter::new(SubnetLimiterConfig::default()))),
             use_tor,
             stun_servers,
+            tor: Mutex::new(None),
+            onion_service_nickname: None,
+            tor_state_dir: None,
         }
     }
+
+    /// Limits des Subnetz-Limiters, z. B. `(&config.rate_limits).into()`.
+    pub fn with_subnet_limits(self, limits: SubnetLimiterConfig) -> Self {
+        self.subnet_limiter.lock_recover().reconfigure(limits);
+        self
+    }
+
+    /// Eigenen Onion-Service veröffentlichen (nur mit `use_tor`).
+    pub fn with_onion_service(mut self, nickname: &str, state_dir: Option<std::path::PathBuf>) -> Self {
+        self.onion_service_nickname = Some(nickname.to_string());
+        self.tor_state_dir = state_dir;
+        self
+    }
+
+    pub fn tor(&self) -> Option<Arc<TorTransport>> {
+        self.tor.lock_recover().clone()
+    }
     pub fn check_rate_limit(&self, addr: SocketAddr) -> bool {
-        let mut lock = self.rate_limiters.lock().unwrap();
+        if !self.subnet_limiter.lock_recover().check(&addr.ip()) {
+            return false;
+        }
+        let mut lock = self.rate_limiters.lock_recover();
         let bucket = lock.entry(addr).or_insert_with(|| TokenBucket::new(200, 50));
         if !bucket.try_consume() {
+            RATE_LIMIT_DROPS.with_label_values(&["peer"]).inc();
             warn!("Rate limit => dropping traffic from {}", addr);
             return false;
         }
         true
     }
-    pub async fn init_tor(&self) {
-        if self.use_tor {
-            info!("(Stub) Tor init => e.g. arti-client usage");
+    /// Bootstrapt den Tor-Client; optional wird ein Onion-Service auf dem
+    /// Port von `local_addr` veröffentlicht. Dessen eingehende Streams nimmt
+    /// ein `TcpP2PAdapter::with_tor` mit demselben Transport entgegen.
+    pub async fn init_tor(&self, local_addr: SocketAddr) {
+        if !self.use_tor || self.tor().is_some() {
+            return;
+        }
+        let transport = match TorTransport::bootstrap(self.tor_state_dir.clone()).await {
+            Ok(t) => Arc::new(t),
+            Err(e) => {
+                error!("Tor init fehlgeschlagen => {:?}", e);
+                return;
+            }
+        };
+        if let Some(nick) = &self.onion_service_nickname {
+            match transport.publish_onion_service(nick, local_addr.port()) {
+                Ok(onion) => info!("Node erreichbar unter {}", onion),
+                Err(e) => warn!("Onion-Service konnte nicht veröffentlicht werden => {:?}", e),
+            }
         }
+        *self.tor.lock_recover() = Some(transport);
     }
-    pub async fn perform_stun(&self) {
-        for s in &self.stun_servers {
-            debug!("(Stub) STUN => contacting server={}", s);
+    /// Externe Adresse per STUN (Failover über alle Server). Der UDP-Socket
+    /// nutzt nach Möglichkeit `local_port`, damit port-erhaltende NATs den
+    /// gleichen externen Port wie für den P2P-Listener liefern.
+    pub async fn perform_stun(&self, local_port: u16) -> Option<SocketAddr> {
+        if self.stun_servers.is_empty() {
+            return None;
+        }
+        match crate::network::stun::discover_external_addr(&self.stun_servers, local_port, Duration::from_secs(2)).await {
+            Ok(addr) => Some(addr),
+            Err(e) => {
+                warn!("STUN => keine externe Adresse ermittelt: {:?}", e);
+                None
+            }
         }
     }
     pub fn ring_sign(&self, data: &[u8]) -> Vec<u8> {
@@ -556,6 +870,10 @@ pub struct KademliaService {
     pub refresh_interval: Duration,
     pub rePublishHandle: Option<JoinHandle<()>>,
     pub concurrency_handle: Option<JoinHandle<()>>,
+    /// Router-Portweiterleitung anfordern? (`upnp_port_mapping`, Standard: aus)
+    pub port_mapping_enabled: bool,
+    /// Router-Portweiterleitung; wird in `stop` wieder entfernt
+    pub port_mapping: Option<(CancellationToken, JoinHandle<()>)>,
 }
 
 impl KademliaService {
@@ -585,9 +903,17 @@ impl KademliaService {
             refresh_interval,
             rePublishHandle: None,
             concurrency_handle: None,
+            port_mapping_enabled: false,
+            port_mapping: None,
         }
     }
 
+    /// Schaltet die UPnP/NAT-PMP-Portweiterleitung in `start` ein.
+    pub fn with_port_mapping(mut self, enabled: bool) -> Self {
+        self.port_mapping_enabled = enabled;
+        self
+    }
+
     /// Startet Hintergrund-Tasks:
     ///  1) Bucket-Refresh
     ///  2) Re-Publish & Expire
@@ -605,23 +931,43 @@ impl KademliaService {
 
         let local_id_copy = self.local_id.clone();
 
+        if self.port_mapping_enabled {
+            let listen_port = self.p2p.lock_recover().local_address().port();
+            self.port_mapping = Some(try_upnp_port_forwarding(listen_port));
+        }
+
         // Task 1: Bucket-Refresh + NAT-Traversal
         self.concurrency_handle = Some(tokio::spawn(async move {
             info!("KademliaService {} => concurrency task started", hex::encode(&local_id_copy.0));
-            let local_p = p2p.lock().unwrap().local_address().port();
-            try_upnp_port_forwarding(local_p);
+            let local_p = p2p.lock_recover().local_address().port();
 
             // Falls wir STUN/Tor etc. => wir holen P2PSecurity
-            if let Some(sec) = p2p.lock().unwrap().security() {
-                sec.perform_stun().await;
-                sec.init_tor().await;
+            if let Some(sec) = p2p.lock_recover().security() {
+                if let Some(mapped) = sec.perform_stun(local_p).await {
+                    table_arc.lock_recover().set_advertised_from_stun(mapped, local_p);
+                }
+                let local_addr = p2p.lock_recover().local_address();
+                sec.init_tor(local_addr).await;
             }
 
-            while !*sf.lock().unwrap() {
+            while !*sf.lock_recover() {
+                table_arc
+                    .lock_recover()
+                    .maintain_candidates(|nid, addr| p2p.lock_recover().ping_node(&nid, addr));
+                // Externe Adresse an alle bekannten Peers, damit sie uns zurückrufen können
+                let announce = {
+                    let table = table_arc.lock_recover();
+                    table.announcement().map(|msg| (msg, table.find_closest(&local_id_copy, usize::MAX)))
+                };
+                if let Some((msg, peers)) = announce {
+                    for (_, addr) in peers {
+                        p2p.lock_recover().send_kademlia_msg(addr, &msg);
+                    }
+                }
                 debug!("Kademlia => refreshing all buckets...");
                 let buckets_count = ID_LENGTH * 8;
                 for i in 0..buckets_count {
-                    if *sf.lock().unwrap() {
+                    if *sf.lock_recover() {
                         break;
                     }
                     let mut target = local_id_copy.clone();
@@ -640,14 +986,14 @@ impl KademliaService {
         self.rePublishHandle = Some(tokio::spawn(async move {
             info!("KademliaService => RePublish/Expire Task started");
             loop {
-                if *sf.lock().unwrap() {
+                if *sf.lock_recover() {
                     break;
                 }
                 {
-                    let mut st_l = st_arc2.lock().unwrap();
+                    let mut st_l = st_arc2.lock_recover();
                     st_l.expire_data();
                     let mut do_republish = |key: &[u8], data: &[u8]| {
-                        let table_locked = table_arc2.lock().unwrap();
+                        let table_locked = table_arc2.lock_recover();
                         let nodes = table_locked.find_closest(&local_id_copy, table_locked.bucket_size);
                         for (nid, addr) in nodes {
                             let msg = KademliaMessage::Store {
@@ -655,7 +1001,7 @@ impl KademliaService {
                                 key: key.to_vec(),
                                 data: data.to_vec(),
                             };
-                            p2p.lock().unwrap().send_kademlia_msg(addr, &msg);
+                            p2p.lock_recover().send_kademlia_msg(addr, &msg);
                         }
                     };
                     st_l.republish(&mut do_republish);
@@ -668,7 +1014,7 @@ impl KademliaService {
 
     /// Beendet die Tasks
     pub async fn stop(&mut self) {
-        let mut sf = self.stop_flag.lock().unwrap();
+        let mut sf = self.stop_flag.lock_recover();
         *sf = true;
         drop(sf);
         if let Some(h) = self.concurrency_handle.take() {
@@ -677,6 +1023,10 @@ impl KademliaService {
         if let Some(h) = self.rePublishHandle.take() {
             let _ = h.await;
         }
+        if let Some((token, h)) = self.port_mapping.take() {
+            token.cancel();
+            let _ = h.await;
+        }
         info!("KademliaService => all tasks ended");
     }
 
@@ -697,7 +1047,7 @@ impl KademliaService {
     }
 
     fn do_ping(&self, node_id: NodeId, addr: SocketAddr) -> bool {
-        self.p2p.lock().unwrap().ping_node(&node_id, addr)
+        self.p2p.lock_recover().ping_node(&node_id, addr)
     }
 }
 
@@ -709,7 +1059,7 @@ impl KademliaServiceInterface for KademliaService {
                 let ok = self.do_ping(node_id.clone(), sender_addr);
                 if ok {
                     let pong = KademliaMessage::Pong(self.local_id.clone());
-                    self.p2p.lock().unwrap().send_kademlia_msg(sender_addr, &pong);
+                    self.p2p.lock_recover().send_kademlia_msg(sender_addr, &pong);
                 }
                 self.table.update_node(node_id, sender_addr, |nid, addr| {
                     self.do_ping(nid, addr)
@@ -732,7 +1082,7 @@ impl KademliaServiceInterface for KademliaService {
                     source: self.local_id.clone(),
                     closer_nodes: closer,
                 };
-                self.p2p.lock().unwrap().send_kademlia_msg(sender_addr, &result);
+                self.p2p.lock_recover().send_kademlia_msg(sender_addr, &result);
             }
             KademliaMessage::FindNodeResult { source, closer_nodes } => {
                 debug!("Kademlia => Received FindNodeResult from {}, {} nodes", short_id(&source), closer_nodes.len());
@@ -755,7 +1105,7 @@ impl KademliaServiceInterface for KademliaService {
                     source: self.local_id.clone(),
                     stored: true,
                 };
-                self.p2p.lock().unwrap().send_kademlia_msg(sender_addr, &ack);
+                self.p2p.lock_recover().send_kademlia_msg(sender_addr, &ack);
             }
             KademliaMessage::StoreResult { source, stored } => {
                 debug!("Kademlia => Received StoreResult => stored={}, from {}", stored, short_id(&source));
@@ -779,7 +1129,7 @@ impl KademliaServiceInterface for KademliaService {
                     data: data_opt.clone(),
                     closer_nodes,
                 };
-                self.p2p.lock().unwrap().send_kademlia_msg(sender_addr, &resp);
+                self.p2p.lock_recover().send_kademlia_msg(sender_addr, &resp);
             }
             KademliaMessage::FindValueResult { source, key, data, closer_nodes } => {
                 debug!("Kademlia => Received FIND_VALUE_RESULT from {}, data={:?}, #closer={}",
@@ -796,6 +1146,12 @@ impl KademliaServiceInterface for KademliaService {
                     // wir könnten nun die closer_nodes weiter abfragen
                 }
             }
+            KademliaMessage::Announce { source, addr } => {
+                debug!("Kademlia => Received ANNOUNCE from {}, addr={}", short_id(&source), addr);
+                self.table.apply_announcement(source, addr, sender_addr, |nid, addr| {
+                    self.do_ping(nid, addr)
+                });
+            }
         }
     }
 }
@@ -803,3 +1159,205 @@ impl KademliaServiceInterface for KademliaService {
 fn short_id(id: &NodeId) -> String {
     format!("{}", hex::encode(&id.0[..2]))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ago(secs: u64) -> Instant {
+        Instant::now().checked_sub(Duration::from_secs(secs)).unwrap_or_else(Instant::now)
+    }
+
+    /// NodeId, die im Bucket 0 relativ zu `local` (nur MSB gesetzt) landet.
+    fn id_in_bucket0(local: &NodeId, salt: u8) -> NodeId {
+        let mut id = local.0;
+        id[0] ^= 0x80;
+        id[ID_LENGTH - 1] ^= salt;
+        NodeId(id)
+    }
+
+    #[test]
+    fn test_subnet_limits_from_config() {
+        let limits = crate::config_loader::RateLimitConfig { subnet_capacity: 2, subnet_refill_per_sec: 0, ..Default::default() };
+        let sec = P2PSecurity::new(false, Vec::new()).with_subnet_limits((&limits).into());
+        let addr: SocketAddr = "203.0.113.7:9000".parse().unwrap();
+        assert!(sec.check_rate_limit(addr));
+        assert!(sec.check_rate_limit(addr));
+        // Default-Kapazität wäre 1000 => hier greift die konfigurierte
+        assert!(!sec.check_rate_limit(addr));
+    }
+
+    #[test]
+    fn test_attacker_subnet_cannot_evict_established_peers() {
+        let local = NodeId([0u8; ID_LENGTH]);
+        let cfg = EclipseConfig {
+            reserved_fraction: 0.5,
+            min_established_age: Duration::from_secs(5),
+            min_candidate_age: Duration::from_secs(3600),
+            candidate_ttl: Duration::from_secs(7200),
+            max_per_ip: 2,
+            max_per_subnet: 2,
+        };
+        let mut table = RoutingTable::new(local.clone(), 4).with_eclipse_config(cfg);
+
+        // 4 etablierte Peers aus verschiedenen Subnetzen
+        let mut honest = Vec::new();
+        for i in 1..=4u8 {
+            let nid = id_in_bucket0(&local, i);
+            let addr: SocketAddr = format!("10.{}.0.1:9000", i).parse().unwrap();
+            table.update_node(nid.clone(), addr, |_, _| true);
+            honest.push(nid);
+        }
+        for e in table.buckets[0].entries.iter_mut() {
+            e.first_seen = ago(10);
+        }
+
+        // Angreifer: viele nahe IDs aus 6.6.6.0/24, alle "lebendig";
+        // die ehrlichen Peers antworten (z. B. wegen DoS) nicht mehr auf Pings.
+        for i in 0..100u8 {
+            let nid = id_in_bucket0(&local, 100 + i);
+            let addr: SocketAddr = format!("6.6.6.{}:9000", i).parse().unwrap();
+            table.update_node(nid, addr, |_, _| false);
+        }
+
+        let ids: Vec<NodeId> = table.buckets[0].entries.iter().map(|e| e.node_id.clone()).collect();
+        for nid in &honest {
+            assert!(ids.contains(nid), "etablierter Peer wurde verdrängt");
+        }
+        assert!(table.find_closest(&local, 10).iter().all(|(_, a)| !a.ip().to_string().starts_with("6.6.6.")));
+    }
+
+    #[test]
+    fn test_aged_candidate_replaces_dead_unprotected_entry() {
+        let cfg = EclipseConfig {
+            reserved_fraction: 0.5,
+            min_established_age: Duration::from_secs(3600),
+            min_candidate_age: Duration::from_secs(5),
+            candidate_ttl: Duration::from_secs(3600),
+            max_per_ip: 2,
+            max_per_subnet: 2,
+        };
+        let mut bucket = KBucket::new(2);
+        let a = NodeId([1u8; ID_LENGTH]);
+        let b = NodeId([2u8; ID_LENGTH]);
+        let c = NodeId([3u8; ID_LENGTH]);
+        bucket.upsert(a.clone(), "10.0.0.1:1".parse().unwrap(), |_, _| true, &cfg);
+        bucket.upsert(b.clone(), "10.1.0.1:1".parse().unwrap(), |_, _| true, &cfg);
+
+        // Erster Kontakt => nur Kandidat
+        let c_addr: SocketAddr = "10.2.0.1:1".parse().unwrap();
+        bucket.upsert(c.clone(), c_addr, |_, _| false, &cfg);
+        assert!(bucket.candidates.contains_key(&c));
+        assert_eq!(bucket.entries.len(), 2);
+        assert!(!bucket.entries.iter().any(|e| e.node_id == c));
+
+        // Nach Bewährungszeit ersetzt er den toten LRU-Eintrag (a)
+        bucket.candidates.get_mut(&c).unwrap().first_seen = ago(10);
+        bucket.upsert(c.clone(), c_addr, |_, _| false, &cfg);
+        assert!(bucket.entries.iter().any(|e| e.node_id == c));
+        assert!(!bucket.entries.iter().any(|e| e.node_id == a));
+    }
+
+    fn full_bucket(cfg: &EclipseConfig) -> KBucket {
+        let mut bucket = KBucket::new(2);
+        bucket.upsert(NodeId([1u8; ID_LENGTH]), "10.0.0.1:1".parse().unwrap(), |_, _| true, cfg);
+        bucket.upsert(NodeId([2u8; ID_LENGTH]), "10.1.0.1:1".parse().unwrap(), |_, _| true, cfg);
+        bucket
+    }
+
+    #[test]
+    fn test_stale_candidates_expire_and_make_room() {
+        let cfg = EclipseConfig { candidate_ttl: Duration::from_secs(60), ..EclipseConfig::default() };
+        let mut bucket = full_bucket(&cfg);
+        bucket.upsert(NodeId([3u8; ID_LENGTH]), "10.2.0.1:1".parse().unwrap(), |_, _| true, &cfg);
+        bucket.upsert(NodeId([4u8; ID_LENGTH]), "10.3.0.1:1".parse().unwrap(), |_, _| true, &cfg);
+        assert_eq!(bucket.candidates.len(), 2);
+
+        // Cache voll => Newcomer wartet
+        let newcomer = NodeId([5u8; ID_LENGTH]);
+        bucket.upsert(newcomer.clone(), "10.4.0.1:1".parse().unwrap(), |_, _| true, &cfg);
+        assert!(!bucket.candidates.contains_key(&newcomer));
+
+        // Die alten Kandidaten melden sich nicht mehr => verfallen
+        for c in bucket.candidates.values_mut() {
+            c.last_seen = ago(120);
+        }
+        bucket.upsert(newcomer.clone(), "10.4.0.1:1".parse().unwrap(), |_, _| true, &cfg);
+        assert!(bucket.candidates.contains_key(&newcomer));
+        assert_eq!(bucket.candidates.len(), 1);
+    }
+
+    #[test]
+    fn test_one_subnet_cannot_fill_the_candidate_cache() {
+        let cfg = EclipseConfig { max_per_subnet: 1, ..EclipseConfig::default() };
+        let mut bucket = full_bucket(&cfg);
+        for i in 0..10u8 {
+            bucket.upsert(NodeId([100 + i; ID_LENGTH]), format!("6.6.6.{}:1", i).parse().unwrap(), |_, _| true, &cfg);
+        }
+        assert_eq!(bucket.candidates.len(), 1);
+        let honest = NodeId([9u8; ID_LENGTH]);
+        bucket.upsert(honest.clone(), "10.9.0.1:1".parse().unwrap(), |_, _| true, &cfg);
+        assert!(bucket.candidates.contains_key(&honest));
+    }
+
+    #[test]
+    fn test_maintenance_pings_candidates_and_promotes_live_ones() {
+        let cfg = EclipseConfig {
+            min_established_age: Duration::from_secs(3600),
+            min_candidate_age: Duration::from_secs(5),
+            ..EclipseConfig::default()
+        };
+        let mut bucket = full_bucket(&cfg);
+        let live: SocketAddr = "10.2.0.1:1".parse().unwrap();
+        let dead: SocketAddr = "10.3.0.1:1".parse().unwrap();
+        bucket.upsert(NodeId([3u8; ID_LENGTH]), live, |_, _| true, &cfg);
+        bucket.upsert(NodeId([4u8; ID_LENGTH]), dead, |_, _| true, &cfg);
+        for c in bucket.candidates.values_mut() {
+            c.first_seen = ago(10);
+        }
+
+        // Kein erneuter Kontakt nötig: der Refresh pingt, nur `live` antwortet.
+        // Der stumme Kandidat fliegt raus, `live` ersetzt den toten LRU-Eintrag.
+        bucket.maintain_candidates(|_, addr| addr == live, &cfg);
+        assert!(bucket.candidates.is_empty());
+        assert!(bucket.entries.iter().any(|e| e.address == live));
+        assert!(!bucket.entries.iter().any(|e| e.node_id == NodeId([1u8; ID_LENGTH])));
+        assert!(!bucket.entries.iter().any(|e| e.address == dead));
+    }
+
+    #[test]
+    fn test_announcement_uses_tcp_port_and_connection_ip() {
+        let mut table = RoutingTable::new(NodeId([0u8; ID_LENGTH]), 8);
+        assert!(table.announcement().is_none());
+        // UDP-Mapping 40000 darf nicht als TCP-Port erscheinen
+        table.set_advertised_from_stun("203.0.113.5:40000".parse().unwrap(), 9000);
+        match table.announcement() {
+            Some(KademliaMessage::Announce { addr, .. }) => assert_eq!(addr, "203.0.113.5:9000".parse().unwrap()),
+            other => panic!("expected announce, got {:?}", other),
+        }
+
+        let mut peer = NodeId([0u8; ID_LENGTH]);
+        peer.0[0] = 0x80;
+        let announced: SocketAddr = "198.51.100.7:9000".parse().unwrap();
+        // Ephemerer Quellport der Verbindung, gleiche IP => übernommen
+        assert!(table.apply_announcement(peer.clone(), announced, "198.51.100.7:51234".parse().unwrap(), |_, _| true));
+        assert_eq!(table.find_closest(&peer, 1), vec![(peer.clone(), announced)]);
+        // Fremde IP => verworfen
+        let foreign: SocketAddr = "192.0.2.1:9000".parse().unwrap();
+        assert!(!table.apply_announcement(peer.clone(), foreign, "198.51.100.7:51234".parse().unwrap(), |_, _| true));
+        assert_eq!(table.find_closest(&peer, 1), vec![(peer, announced)]);
+    }
+
+    #[test]
+    fn test_per_ip_cap_across_table() {
+        let local = NodeId([0u8; ID_LENGTH]);
+        let mut table = RoutingTable::new(local, 8);
+        let addr: SocketAddr = "7.7.7.7:9000".parse().unwrap();
+        for i in 0..5u8 {
+            let mut id = [0u8; ID_LENGTH];
+            id[(i as usize) % 4] = 0x80 >> i;
+            table.update_node(NodeId(id), addr, |_, _| true);
+        }
+        assert_eq!(table.entries_with_ip(addr.ip()), 2);
+    }
+}
@@ -6,30 +6,378 @@ use std::{
     collections::HashMap,
     net::{SocketAddr, ToSocketAddrs},
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use tokio::{
     net::{TcpListener, TcpStream},
-    io::{AsyncReadExt, AsyncWriteExt},
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    sync::{mpsc, Mutex as AsyncMutex},
     time::sleep,
     task::JoinHandle,
 };
 use tracing::{debug, info, warn, error};
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::kademlia::kademlia_service::{KademliaP2PAdapter, KademliaMessage};
-use snow::{Builder, params::NoiseParams, Session};
+use crate::network::address_book::SharedAddressBook;
+use crate::network::tor::{is_onion_virtual, OnionInbound, TorTransport};
+use crate::network::turn::TurnClient;
+use crate::network::handler::MessageGuard;
+use crate::network::noise::{NoiseTransport, RekeyPolicy};
+use crate::network::gossip_config::{GossipConfig, GossipExchange, SignedGossipConfig};
+use crate::sybil::pow::{PowChallenge, PowConfig, PowGate};
+use crate::protocol::version::{negotiate, required_features, Hello, NegotiatedProtocol, FEATURE_GOSSIP_CONFIG, HELLO_TIMEOUT};
+use crate::utils::lock::LockRecover;
+use snow::{Builder, Keypair as NoiseKeypair, params::NoiseParams};
 use bincode;
 
 /// Dieses Struct hält die Sitzung für einen Peer:
 /// - Der Schreib-Halbzugriff (write_half), um asynchron Daten zu senden.
-/// - Ein Noise-Session-Objekt, um sowohl verschlüsselt zu senden als auch
-///   in der Gegenrichtung zu entschlüsseln.
-///   (Im Lese-Loop haben wir ebenfalls Zugriff auf die Session.)
+/// - Den Noise-Transport (nach dem Handshake), um sowohl verschlüsselt zu
+///   senden als auch in der Gegenrichtung zu entschlüsseln. Er rotiert die
+///   Schlüssel gemäß `RekeyPolicy`, ohne die Verbindung neu aufzubauen.
+///   (Im Lese-Loop haben wir ebenfalls Zugriff auf den Transport.)
+///
+/// Der Transport ist entweder TCP oder ein Tor-Stream (Onion-Peers).
+type BoxedRead = Box<dyn AsyncRead + Send + Unpin>;
+type BoxedWrite = Box<dyn AsyncWrite + Send + Unpin>;
+
 struct PeerConnection {
-    write_half: tokio::net::OwnedWriteHalf,
-    noise_session: Session,  // beidseitig => hier z. B. Responder- oder Initiator-Side
+    write_half: BoxedWrite,
+    transport: NoiseTransport,
+    /// Ausgehandelte Version/Features (siehe protocol::version)
+    protocol: NegotiatedProtocol,
+    /// Geprüfte Gossip-Parameter des Peers (nur mit FEATURE_GOSSIP_CONFIG)
+    gossip: Option<GossipConfig>,
+}
+
+type ConnectionMap = Arc<AsyncMutex<HashMap<SocketAddr, PeerConnection>>>;
+
+/// Größen-Limits, Strafpunkte und Quarantäne (siehe network::handler).
+type SharedGuard = Arc<Mutex<MessageGuard>>;
+
+/// Empfangene Nachrichten (Absender-Adresse, Nachricht) für den Aufrufer,
+/// z. B. `kad_service.handle_message(addr, msg)`.
+pub type InboundSender = mpsc::UnboundedSender<(SocketAddr, KademliaMessage)>;
+
+/// Größte Noise-Nachricht; längere Frames werden abgelehnt.
+const MAX_FRAME_LEN: usize = 65535;
+
+const NOISE_PATTERN: &str = "Noise_XX_25519_ChaChaPoly_SHA256";
+
+/// Statischer Noise-Schlüssel des Adapters; XX authentisiert ihn gegenüber
+/// der Gegenseite, Gossip-Configs sind an ihn gebunden.
+fn generate_noise_key() -> NoiseKeypair {
+    let params: NoiseParams = NOISE_PATTERN.parse().expect("gültiges Noise-Pattern");
+    Builder::new(params).generate_keypair().expect("X25519-Schlüsselerzeugung")
+}
+
+/// Wiederholungen des Initiator-Handshakes (`noise_handshake` in der Node-Config).
+/// Nur vorübergehende Fehler (Timeout, Reset, Abbruch mitten im Handshake)
+/// werden wiederholt; Auth-/Protokollfehler brechen sofort ab.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HandshakeRetryPolicy {
+    /// Versuche insgesamt; 1 => keine Wiederholung
+    pub max_attempts: u32,
+    /// Wartezeit vor dem 2. Versuch, verdoppelt sich je Versuch
+    pub base_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Zeitlimit je Versuch (Connect + Handshake + Versions-Austausch)
+    pub attempt_timeout_ms: u64,
+}
+
+impl Default for HandshakeRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_backoff_ms: 200,
+            max_backoff_ms: 2_000,
+            attempt_timeout_ms: 10_000,
+        }
+    }
+}
+
+impl HandshakeRetryPolicy {
+    /// Wartezeit nach dem fehlgeschlagenen Versuch `attempt` (ab 1):
+    /// exponentiell bis `max_backoff_ms`, davon zufällig 50–100 %, damit
+    /// sich viele Nodes nach einem Netzausfall nicht im Gleichtakt melden.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_backoff_ms
+            .saturating_mul(1u64 << attempt.saturating_sub(1).min(16))
+            .min(self.max_backoff_ms);
+        Duration::from_millis(rand::thread_rng().gen_range(exp / 2..=exp))
+    }
+}
+
+/// Client-Puzzle vor dem Noise-Handshake (`handshake_pow` in der
+/// Node-Config, siehe sybil::pow). Gilt für TCP- und Onion-Verbindungen, nicht fürs Relay.
+#[derive(Debug, Clone)]
+struct PowPolicy {
+    gate: Arc<PowGate>,
+    max_solve_difficulty: u8,
+    /// Wartezeit auf Challenge bzw. Nonce
+    timeout: Duration,
+}
+
+/// Responder: Challenge schicken und die Nonce mit Zeitlimit prüfen. Ein
+/// stummer Initiator hält so nur seinen eigenen Task, nie den Accept-Loop.
+async fn require_pow<S: AsyncRead + AsyncWrite + Unpin>(
+    socket: &mut S,
+    remote_addr: SocketAddr,
+    pow: &PowPolicy,
+) -> Result<()> {
+    let challenge = pow.gate.issue();
+    socket.write_all(&challenge.to_bytes()).await?;
+    let mut nonce = [0u8; 8];
+    tokio::time::timeout(pow.timeout, socket.read_exact(&mut nonce))
+        .await
+        .with_context(|| format!("Keine PoW-Lösung von {} innerhalb {:?}", remote_addr, pow.timeout))??;
+    if !challenge.verify(u64::from_be_bytes(nonce)) {
+        return Err(anyhow!("Ungültiger Proof-of-Work von {} (difficulty={})", remote_addr, challenge.difficulty));
+    }
+    Ok(())
+}
+
+/// Initiator: Challenge lesen, bis `max_solve_difficulty` lösen, Nonce schicken.
+async fn answer_pow(
+    read_half: &mut BoxedRead,
+    write_half: &mut BoxedWrite,
+    addr: SocketAddr,
+    pow: &PowPolicy,
+) -> Result<()> {
+    let mut buf = [0u8; PowChallenge::WIRE_LEN];
+    tokio::time::timeout(pow.timeout, read_half.read_exact(&mut buf))
+        .await
+        .with_context(|| format!("Keine PoW-Challenge von {} innerhalb {:?}", addr, pow.timeout))??;
+    let challenge = PowChallenge::from_bytes(&buf);
+    let max = pow.max_solve_difficulty;
+    // Brute-Force blockiert => nicht auf dem Runtime-Thread
+    let nonce = tokio::task::spawn_blocking(move || challenge.solve_capped(max))
+        .await?
+        .ok_or_else(|| anyhow!("PoW-Difficulty {} von {} über Limit {}", challenge.difficulty, addr, max))?;
+    write_half.write_all(&nonce.to_be_bytes()).await?;
+    Ok(())
+}
+
+/// Lohnt ein neuer Versuch? Timeout, Reset/Abbruch und vorzeitiges EOF ja;
+/// Noise-Fehler (falscher Schlüssel, manipulierte Nachricht) und
+/// inkompatible Versionen nein.
+fn is_transient_handshake_error(err: &anyhow::Error) -> bool {
+    use std::io::ErrorKind;
+    err.chain().any(|cause| {
+        if cause.is::<tokio::time::error::Elapsed>() {
+            return true;
+        }
+        cause.downcast_ref::<std::io::Error>().is_some_and(|io| {
+            matches!(
+                io.kind(),
+                ErrorKind::TimedOut
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::UnexpectedEof
+                    | ErrorKind::Interrupted
+            )
+        })
+    })
+}
+
+/// Jede Nachricht (Handshake und Transport) geht als Frame mit 4-Byte-
+/// Längenpräfix (big endian) über den Stream. Ohne Framing können TCP-Reads
+/// zwei Nachrichten zusammenfassen oder eine zerteilen.
+async fn write_frame<W: AsyncWrite + Unpin + ?Sized>(w: &mut W, payload: &[u8]) -> Result<()> {
+    if payload.len() > MAX_FRAME_LEN {
+        return Err(anyhow!("Frame zu groß: {} > {} bytes", payload.len(), MAX_FRAME_LEN));
+    }
+    w.write_all(&(payload.len() as u32).to_be_bytes()).await?;
+    w.write_all(payload).await?;
+    w.flush().await?;
+    Ok(())
+}
+
+/// Liest einen Frame; `None` bei sauberem EOF vor dem Längenpräfix.
+async fn read_frame<R: AsyncRead + Unpin + ?Sized>(r: &mut R) -> Result<Option<Vec<u8>>> {
+    let mut len_buf = [0u8; 4];
+    match r.read_exact(&mut len_buf).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_be_bytes(len_buf) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(anyhow!("Frame-Länge {} überschreitet {} bytes", len, MAX_FRAME_LEN));
+    }
+    let mut buf = vec![0u8; len];
+    r.read_exact(&mut buf).await?;
+    Ok(Some(buf))
+}
+
+/// Versions-/Capability-Austausch über den frischen Noise-Transport, vor jeder
+/// RPC. Der Initiator sendet zuerst. Beide Seiten berechnen dasselbe Ergebnis;
+/// bei Inkompatibilität bricht jede Seite mit dem Grund ab, ebenso wenn das
+/// Hello des Peers nicht innerhalb von `timeout` eintrifft.
+async fn exchange_hello(
+    read_half: &mut (dyn AsyncRead + Send + Unpin),
+    write_half: &mut (dyn AsyncWrite + Send + Unpin),
+    transport: &mut NoiseTransport,
+    local: &Hello,
+    initiator: bool,
+    timeout: Duration,
+) -> Result<NegotiatedProtocol> {
+    let ours = transport.encrypt(&local.encode())?;
+    if initiator {
+        write_frame(write_half, &ours).await?;
+    }
+    let frame = tokio::time::timeout(timeout, read_frame(read_half))
+        .await
+        .with_context(|| format!("Kein Hello innerhalb {:?}", timeout))??
+        .ok_or_else(|| anyhow!("Remote schloss vor dem Versions-Austausch"))?;
+    let remote = Hello::decode(&transport.decrypt(&frame)?)?;
+    if !initiator {
+        write_frame(write_half, &ours).await?;
+    }
+    let negotiated = negotiate(local, &remote)?;
+    debug!("Protokoll ausgehandelt => v{} features={:#x} ({})", negotiated.version, negotiated.features, negotiated.remote_agent);
+    Ok(negotiated)
+}
+
+/// Signierte Gossip-Parameter austauschen (gleiche Reihenfolge wie beim
+/// Hello). Nur wenn FEATURE_GOSSIP_CONFIG ausgehandelt wurde; ein Peer mit
+/// ungültiger Signatur, einer für einen anderen Noise-Schlüssel als
+/// `keys.1` ausgestellten Config oder Werten außerhalb der Grenzen wird
+/// abgelehnt. `keys` = (eigener, im Handshake authentisierter fremder
+/// statischer Noise-Schlüssel). Liefert die für den Peer geltende Config.
+async fn exchange_gossip_config(
+    read_half: &mut (dyn AsyncRead + Send + Unpin),
+    write_half: &mut (dyn AsyncWrite + Send + Unpin),
+    transport: &mut NoiseTransport,
+    gossip: Option<&GossipExchange>,
+    protocol: &NegotiatedProtocol,
+    keys: (&[u8], &[u8]),
+    initiator: bool,
+) -> Result<Option<GossipConfig>> {
+    let gossip = match gossip {
+        Some(g) if protocol.supports(FEATURE_GOSSIP_CONFIG) => g,
+        _ => return Ok(None),
+    };
+    let (local_static, remote_static) = keys;
+    let ours = transport.encrypt(&gossip.signed_for(local_static).encode())?;
+    if initiator {
+        write_frame(write_half, &ours).await?;
+    }
+    let frame = read_frame(read_half).await?
+        .ok_or_else(|| anyhow!("Remote schloss vor dem Gossip-Config-Austausch"))?;
+    let remote = SignedGossipConfig::decode(&transport.decrypt(&frame)?)?;
+    if !initiator {
+        write_frame(write_half, &ours).await?;
+    }
+    Ok(Some(gossip.accept_peer(&remote, remote_static)?))
+}
+
+/// Bytes je Relay-Datagramm; bleibt unter üblichen Pfad-MTUs.
+const RELAY_CHUNK: usize = 1200;
+
+/// Noise-Streams über ein TURN-Relay. Je Peer gibt es einen In-Memory-Duplex:
+/// Was die Noise-Seite schreibt, geht in Datagrammen über `TurnClient::send_to`,
+/// relayed Datagramme des Peers landen im Lese-Ende. Auf dem Relay sind also
+/// nur Handshake- und verschlüsselte Transport-Frames zu sehen. Verlorene
+/// oder umsortierte Datagramme lassen die Noise-Entschlüsselung scheitern,
+/// die Verbindung wird dann geschlossen und beim nächsten Senden neu aufgebaut.
+#[derive(Clone)]
+struct TurnRelay {
+    client: Arc<TurnClient>,
+    streams: Arc<Mutex<HashMap<SocketAddr, mpsc::UnboundedSender<Vec<u8>>>>>,
+}
+
+impl TurnRelay {
+    fn new(client: Arc<TurnClient>) -> Self {
+        Self { client, streams: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    /// Öffnet (oder ersetzt) den Stream zu `peer`.
+    fn open(&self, peer: SocketAddr) -> (BoxedRead, BoxedWrite) {
+        let (ours, theirs) = tokio::io::duplex(2 * MAX_FRAME_LEN);
+        let (mut pump_read, mut pump_write) = tokio::io::split(theirs);
+        let (tx, mut rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        self.streams.lock_recover().insert(peer, tx.clone());
+
+        // Relay => Stream
+        tokio::spawn(async move {
+            while let Some(data) = rx.recv().await {
+                if pump_write.write_all(&data).await.is_err() {
+                    break;
+                }
+            }
+        });
+        // Stream => Relay; endet, sobald die Noise-Seite beide Hälften fallen lässt
+        let client = self.client.clone();
+        let streams = self.streams.clone();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; RELAY_CHUNK];
+            loop {
+                match pump_read.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        if let Err(e) = client.send_to(peer, &buf[..n]).await {
+                            warn!("TURN-Relay zu {} fehlgeschlagen => {:?}", peer, e);
+                            break;
+                        }
+                    }
+                }
+            }
+            let mut streams = streams.lock_recover();
+            if streams.get(&peer).is_some_and(|s| s.same_channel(&tx)) {
+                streams.remove(&peer);
+            }
+        });
+
+        let (r, w) = tokio::io::split(ours);
+        (Box::new(r), Box::new(w))
+    }
+
+    /// Verteilt relayed Datagramme; ein unbekannter Peer startet einen
+    /// Noise-Responder wie eine eingehende TCP-Verbindung.
+    async fn accept_loop(
+        self,
+        connections: ConnectionMap,
+        rekey_policy: RekeyPolicy,
+        inbound: Option<InboundSender>,
+        hello: Hello,
+        gossip: Option<GossipExchange>,
+        guard: SharedGuard,
+        noise_key: Arc<NoiseKeypair>,
+    ) {
+        while let Some((peer, data)) = self.client.recv().await {
+            let known = self.streams.lock_recover().get(&peer).cloned();
+            if let Some(tx) = known {
+                if tx.send(data).is_ok() {
+                    continue;
+                }
+            }
+            info!("Eingehende Relay-Verbindung von {}", peer);
+            let (read_half, write_half) = self.open(peer);
+            if let Some(tx) = self.streams.lock_recover().get(&peer) {
+                let _ = tx.send(data);
+            }
+            let connections = connections.clone();
+            let inbound = inbound.clone();
+            let hello = hello.clone();
+            let gossip = gossip.clone();
+            let guard = guard.clone();
+            let noise_key = noise_key.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_incoming_stream(read_half, write_half, peer, connections, rekey_policy, inbound, hello, gossip, guard, noise_key).await {
+                    warn!("Fehler in Relay-Verbindung({}): {:?}", peer, e);
+                }
+            });
+        }
+        debug!("TURN-Relay => Empfang beendet");
+    }
 }
 
 /// TCP + Noise-XX-Adapter für Kademlia.
@@ -37,11 +385,34 @@ struct PeerConnection {
 /// - Verwaltet eine HashMap an aktiven Verbindungen (SocketAddr -> PeerConnection).
 /// - Jede eingehende Verbindung durchläuft den Noise-Handshake (Responder).
 /// - Jede ausgehende Verbindung durchläuft den Noise-Handshake (Initiator).
+/// - Danach tauschen beide Seiten `Hello` aus (Version + Features); inkompatible
+///   Peers werden getrennt.
 /// - Danach werden KademliaMessage binär kodiert (bincode) und via Noise verschlüsselt.
 pub struct TcpP2PAdapter {
     local_addr: SocketAddr,
-    connections: Arc<Mutex<HashMap<SocketAddr, PeerConnection>>>,
+    connections: ConnectionMap,
     listener_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// Ziel für entschlüsselte eingehende Nachrichten (None => nur loggen)
+    inbound: Option<InboundSender>,
+    /// Für Ziele im virtuellen Onion-Bereich (siehe network::tor)
+    tor: Option<Arc<TorTransport>>,
+    /// Relay für Peers, die direkt nicht erreichbar sind (symmetrisches NAT)
+    turn: Option<TurnRelay>,
+    /// Wann Transport-Schlüssel je Verbindung rotiert werden
+    rekey_policy: RekeyPolicy,
+    /// Eigenes Hello für den Versions-Austausch
+    hello: Hello,
+    /// Signierte Gossip-Parameter + Grenzen für Peers (None => kein Austausch)
+    gossip: Option<GossipExchange>,
+    guard: SharedGuard,
+    /// Wiederholungen beim ausgehenden Handshake
+    handshake_retry: HandshakeRetryPolicy,
+    /// Statischer Noise-Schlüssel (XX)
+    noise_key: Arc<NoiseKeypair>,
+    /// Dial-Ergebnisse, Handshake-RTT und Verbindungsdauer ausgehender Peers
+    address_book: Option<SharedAddressBook>,
+    /// Client-Puzzle vor dem Handshake (None => aus)
+    pow: Option<PowPolicy>,
 }
 
 impl TcpP2PAdapter {
@@ -50,11 +421,113 @@ impl TcpP2PAdapter {
     pub fn new(local_addr: SocketAddr) -> Self {
         Self {
             local_addr,
-            connections: Arc::new(Mutex::new(HashMap::new())),
+            connections: Arc::new(AsyncMutex::new(HashMap::new())),
             listener_handle: Arc::new(Mutex::new(None)),
+            inbound: None,
+            tor: None,
+            turn: None,
+            rekey_policy: RekeyPolicy::default(),
+            hello: Hello::local(),
+            gossip: None,
+            guard: Arc::new(Mutex::new(MessageGuard::default())),
+            handshake_retry: HandshakeRetryPolicy::default(),
+            noise_key: Arc::new(generate_noise_key()),
+            address_book: None,
+            pow: None,
         }
     }
 
+    /// Ausgehende Dials (Erfolg + Handshake-Dauer oder Fehlschlag) und die
+    /// Dauer der Verbindung landen im Adressbuch.
+    pub fn with_address_book(mut self, book: SharedAddressBook) -> Self {
+        self.address_book = Some(book);
+        self
+    }
+
+    /// Eingehende TCP-Verbindungen müssen vor dem Handshake ein Puzzle lösen,
+    /// ausgehende lösen das der Gegenseite. Alle Nodes brauchen dieselbe
+    /// Einstellung, sonst scheitert der Handshake.
+    pub fn with_pow(mut self, config: &PowConfig) -> Self {
+        self.pow = Some(PowPolicy {
+            gate: Arc::new(config.gate()),
+            max_solve_difficulty: config.max_solve_difficulty,
+            timeout: config.timeout(),
+        });
+        self
+    }
+
+    /// Tauscht beim Peering die signierten Gossip-Parameter aus und bietet
+    /// dafür FEATURE_GOSSIP_CONFIG an.
+    pub fn with_gossip_config(mut self, gossip: GossipExchange) -> Self {
+        self.hello.features |= FEATURE_GOSSIP_CONFIG;
+        self.gossip = Some(gossip);
+        self
+    }
+
+    pub fn with_handshake_retry(mut self, policy: HandshakeRetryPolicy) -> Self {
+        self.handshake_retry = policy;
+        self
+    }
+
+    /// Eigene Limits / Quarantäne-Regeln für eingehende Nachrichten.
+    pub fn with_message_guard(mut self, guard: MessageGuard) -> Self {
+        self.guard = Arc::new(Mutex::new(guard));
+        self
+    }
+
+    /// Überschreibt das angekündigte Hello (z. B. Features während eines
+    /// Rolling Upgrades noch nicht anbieten).
+    pub fn with_protocol(mut self, hello: Hello) -> Self {
+        self.hello = hello;
+        self
+    }
+
+    /// Mit `addr` ausgehandeltes Protokoll, falls verbunden.
+    pub async fn peer_protocol(&self, addr: &SocketAddr) -> Option<NegotiatedProtocol> {
+        self.connections.lock().await.get(addr).map(|c| c.protocol.clone())
+    }
+
+    /// Eigener statischer Noise-Schlüssel (public), wie ihn Peers im Handshake sehen.
+    pub fn noise_public_key(&self) -> &[u8] {
+        &self.noise_key.public
+    }
+
+    /// Mit dem Peer an `addr` geltende Gossip-Parameter (aus dessen
+    /// geprüfter Config und der eigenen, siehe `GossipExchange::effective`).
+    pub async fn peer_gossip_config(&self, addr: &SocketAddr) -> Option<GossipConfig> {
+        self.connections.lock().await.get(addr).and_then(|c| c.gossip.clone())
+    }
+
+    /// Beide Seiten müssen dieselbe Policy nutzen, sonst laufen die
+    /// Schlüssel auseinander.
+    pub fn with_rekey_policy(mut self, policy: RekeyPolicy) -> Self {
+        self.rekey_policy = policy;
+        self
+    }
+
+    /// Eingehende Nachrichten aller Verbindungen (ein- und ausgehend
+    /// aufgebaut) werden an `tx` weitergereicht.
+    pub fn with_inbound(mut self, tx: InboundSender) -> Self {
+        self.inbound = Some(tx);
+        self
+    }
+
+    /// Schlägt der direkte Verbindungsaufbau fehl, läuft die Verbindung über
+    /// das TURN-Relay, mit demselben Noise-Handshake wie über TCP.
+    /// Relayed Datagramme (auch Antworten auf eigene Handshakes) verteilt
+    /// erst `start_listener`.
+    pub fn with_turn_relay(mut self, turn: Arc<TurnClient>) -> Self {
+        self.turn = Some(TurnRelay::new(turn));
+        self
+    }
+
+    /// Onion-Adressen werden über diesen Tor-Client angewählt; läuft darauf ein
+    /// Onion-Service, nimmt `start_listener` dessen Streams entgegen.
+    pub fn with_tor(mut self, tor: Arc<TorTransport>) -> Self {
+        self.tor = Some(tor);
+        self
+    }
+
     /// Startet den TCP-Listener (Noise-Responder für eingehende) asynchron in einem Tokio-Task.
     /// Jede eingehende Verbindung durchläuft den Noise-Handshake (Responder).
     /// Anschließend wird in einer Endlosschleife in `handle_incoming_loop` 
@@ -67,13 +540,44 @@ impl TcpP2PAdapter {
     pub fn start_listener(&self) -> Result<()> {
         let local_addr = self.local_addr;
         let connections_clone = self.connections.clone();
+        let rekey_policy = self.rekey_policy;
+        let inbound = self.inbound.clone();
+        let hello = self.hello.clone();
+        let gossip = self.gossip.clone();
+        let msg_guard = self.guard.clone();
+        let noise_key = self.noise_key.clone();
+        let pow = self.pow.clone();
 
-        let mut guard = self.listener_handle.lock().unwrap();
+        let mut guard = self.listener_handle.lock_recover();
         if guard.is_some() {
             warn!("Listener bereits gestartet, ignoriere zweiten Aufruf.");
             return Ok(());
         }
 
+        if let Some(relay) = self.turn.clone() {
+            let connections = self.connections.clone();
+            let inbound = self.inbound.clone();
+            let hello = self.hello.clone();
+            let gossip = self.gossip.clone();
+            let msg_guard = self.guard.clone();
+            let noise_key = self.noise_key.clone();
+            tokio::spawn(relay.accept_loop(connections, rekey_policy, inbound, hello, gossip, msg_guard, noise_key));
+        }
+
+        if let Some(onion_inbound) = self.tor.as_ref().and_then(|t| t.take_inbound()) {
+            tokio::spawn(onion_accept_loop(
+                onion_inbound,
+                self.connections.clone(),
+                rekey_policy,
+                self.inbound.clone(),
+                self.hello.clone(),
+                self.gossip.clone(),
+                self.guard.clone(),
+                self.noise_key.clone(),
+                self.pow.clone(),
+            ));
+        }
+
         let handle = tokio::spawn(async move {
             let listener = match TcpListener::bind(local_addr).await {
                 Ok(l) => {
@@ -97,9 +601,22 @@ impl TcpP2PAdapter {
                 info!("Eingehende Verbindung von {}", remote_addr);
 
                 let connections_arc = connections_clone.clone();
-                // Spawn Task => Noise-Handshake + Lese-Loop
+                let inbound = inbound.clone();
+                let hello = hello.clone();
+                let gossip = gossip.clone();
+                let msg_guard = msg_guard.clone();
+                let noise_key = noise_key.clone();
+                let pow = pow.clone();
+                // Spawn Task => PoW + Noise-Handshake + Lese-Loop
                 tokio::spawn(async move {
-                    if let Err(e) = handle_incoming_connection(socket, remote_addr, connections_arc).await {
+                    let mut socket = socket;
+                    if let Some(pow) = &pow {
+                        if let Err(e) = require_pow(&mut socket, remote_addr, pow).await {
+                            warn!("PoW von {} => {:?}; Verbindung geschlossen", remote_addr, e);
+                            return;
+                        }
+                    }
+                    if let Err(e) = handle_incoming_connection(socket, remote_addr, connections_arc, rekey_policy, inbound, hello, gossip, msg_guard, noise_key).await {
                         warn!("Fehler in handle_incoming_connection({}): {:?}", remote_addr, e);
                     }
                 });
@@ -110,137 +627,241 @@ impl TcpP2PAdapter {
     }
 }
 
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Streams des eigenen Onion-Service: wie eingehendes TCP (PoW + Responder),
+/// aber mit der virtuellen Adresse aus `network::tor` statt 127.0.0.1.
+#[allow(clippy::too_many_arguments)]
+async fn onion_accept_loop(
+    mut streams: OnionInbound,
+    connections: ConnectionMap,
+    rekey_policy: RekeyPolicy,
+    inbound: Option<InboundSender>,
+    hello: Hello,
+    gossip: Option<GossipExchange>,
+    guard: SharedGuard,
+    noise_key: Arc<NoiseKeypair>,
+    pow: Option<PowPolicy>,
+) {
+    while let Some((remote_addr, mut stream)) = streams.recv().await {
+        info!("Eingehende Onion-Verbindung {}", remote_addr);
+        let connections = connections.clone();
+        let inbound = inbound.clone();
+        let hello = hello.clone();
+        let gossip = gossip.clone();
+        let guard = guard.clone();
+        let noise_key = noise_key.clone();
+        let pow = pow.clone();
+        tokio::spawn(async move {
+            if let Some(pow) = &pow {
+                if let Err(e) = require_pow(&mut stream, remote_addr, pow).await {
+                    warn!("PoW von {} => {:?}; Verbindung geschlossen", remote_addr, e);
+                    return;
+                }
+            }
+            let (r, w) = tokio::io::split(stream);
+            if let Err(e) = handle_incoming_stream(Box::new(r), Box::new(w), remote_addr, connections, rekey_policy, inbound, hello, gossip, guard, noise_key).await {
+                warn!("Fehler in Onion-Verbindung({}): {:?}", remote_addr, e);
+            }
+        });
+    }
+    debug!("Onion-Service => Empfang beendet");
+}
+
 /// Asynchrones Hilfsfunktion: Noise-Handshake (Responder).
 /// Anschließend read-loop -> bincode -> KademliaMessage. 
+#[allow(clippy::too_many_arguments)]
 async fn handle_incoming_connection(
     socket: TcpStream,
     remote_addr: SocketAddr,
-    connections_arc: Arc<Mutex<HashMap<SocketAddr, PeerConnection>>>
+    connections_arc: ConnectionMap,
+    rekey_policy: RekeyPolicy,
+    inbound: Option<InboundSender>,
+    hello: Hello,
+    gossip: Option<GossipExchange>,
+    guard: SharedGuard,
+    noise_key: Arc<NoiseKeypair>,
+) -> Result<()> {
+    let (read_half, write_half) = socket.into_split();
+    handle_incoming_stream(Box::new(read_half), Box::new(write_half), remote_addr, connections_arc, rekey_policy, inbound, hello, gossip, guard, noise_key).await
+}
+
+/// Responder-Seite für einen beliebigen Byte-Stream (TCP oder TURN-Relay).
+#[allow(clippy::too_many_arguments)]
+async fn handle_incoming_stream(
+    mut read_half: BoxedRead,
+    mut write_half: BoxedWrite,
+    remote_addr: SocketAddr,
+    connections_arc: ConnectionMap,
+    rekey_policy: RekeyPolicy,
+    inbound: Option<InboundSender>,
+    hello: Hello,
+    gossip: Option<GossipExchange>,
+    guard: SharedGuard,
+    noise_key: Arc<NoiseKeypair>,
 ) -> Result<()> {
     // 1) Noise-Params: wir machen "Noise_XX_25519_ChaChaPoly_SHA256"
-    let noise_params: NoiseParams = "Noise_XX_25519_ChaChaPoly_SHA256".parse()
+    let noise_params: NoiseParams = NOISE_PATTERN.parse()
         .map_err(|e| anyhow!("Noise Params parse error: {:?}", e))?;
 
     let builder = Builder::new(noise_params);
-    // Da wir ephemeral (keine statischen Keys) nutzen: 
-    // => build_responder
+    // XX überträgt die statischen Schlüssel beider Seiten verschlüsselt
     let mut noise_session = builder
+        .local_private_key(&noise_key.private)
         .build_responder()
         .map_err(|e| anyhow!("build_responder: {:?}", e))?;
 
-    // 2) Socket -> split
-    let (mut read_half, write_half) = socket.into_split();
-
-    // 3) Handshake-Phase:
+    // 2) Handshake-Phase:
     //    => "Noise_XX" erfordert 3 messages.
     //    => wir (Responder) warten zuerst auf msg von Initiator
-    let mut buf = [0u8; 1024];
-    let n1 = read_half.read(&mut buf).await?;
-    if n1 == 0 {
-        return Err(anyhow!("Handshake-Fehler => Remote closed immediately"));
-    }
+    let msg1 = read_frame(&mut read_half).await?
+        .ok_or_else(|| anyhow!("Handshake-Fehler => Remote closed immediately"))?;
     let mut tmp_out = vec![0u8; 1024];
-    let len1 = noise_session.read_message(&buf[..n1], &mut tmp_out)
+    noise_session.read_message(&msg1, &mut tmp_out)
         .map_err(|e| anyhow!("noise read_message(1): {:?}", e))?;
-    debug!("Responder => erstes Handshake-Fragment gelesen ({} bytes).", n1);
+    debug!("Responder => erstes Handshake-Fragment gelesen ({} bytes).", msg1.len());
 
     // => Sende 2. msg
     let mut msg2 = vec![0u8; 1024];
     let l2 = noise_session.write_message(&[], &mut msg2)
         .map_err(|e| anyhow!("noise write_message(2): {:?}", e))?;
     // => an remote
-    let mut wh = write_half.clone();
-    wh.write_all(&msg2[..l2]).await?;
+    write_frame(&mut write_half, &msg2[..l2]).await?;
     debug!("Responder => zweites Handshake-Fragment gesendet ({} bytes).", l2);
 
     // => warte drittes
-    let n3 = read_half.read(&mut buf).await?;
-    if n3 == 0 {
-        return Err(anyhow!("Handshake-Fehler => Remote closed on 3rd msg"));
-    }
-    let len3 = noise_session.read_message(&buf[..n3], &mut tmp_out)
+    let msg3 = read_frame(&mut read_half).await?
+        .ok_or_else(|| anyhow!("Handshake-Fehler => Remote closed on 3rd msg"))?;
+    noise_session.read_message(&msg3, &mut tmp_out)
         .map_err(|e| anyhow!("noise read_message(3): {:?}", e))?;
-    debug!("Responder => drittes Handshake-Fragment gelesen ({} bytes).", n3);
+    debug!("Responder => drittes Handshake-Fragment gelesen ({} bytes).", msg3.len());
 
     if !noise_session.is_handshake_complete() {
         return Err(anyhow!("Noise-Handshake (XX) nicht komplett => Abbruch."));
     }
+    let remote_static = noise_session
+        .get_remote_static()
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| anyhow!("Noise-Handshake ohne statischen Schlüssel des Peers"))?;
+    reject_quarantined(&guard, &remote_static, remote_addr)?;
     info!("Noise-Responder Handshake erfolgreich => remote={}", remote_addr);
 
-    // 4) Noise-Sitzung => wir packen es in `PeerConnection`.
+    // 3) Noise-Sitzung => Transport-Modus, Versions-Austausch, dann in `PeerConnection`.
+    let mut transport = NoiseTransport::from_handshake(noise_session, rekey_policy)?;
+    let protocol = match exchange_hello(&mut *read_half, &mut *write_half, &mut transport, &hello, false, HELLO_TIMEOUT).await {
+        Ok(p) => p,
+        Err(e) => {
+            warn!("Peer {} abgelehnt => {}", remote_addr, e);
+            return Err(e);
+        }
+    };
+    let keys = (noise_key.public.as_slice(), remote_static.as_slice());
+    let peer_gossip = match exchange_gossip_config(&mut *read_half, &mut *write_half, &mut transport, gossip.as_ref(), &protocol, keys, false).await {
+        Ok(g) => g,
+        Err(e) => {
+            warn!("Peer {} abgelehnt (Gossip-Config) => {}", remote_addr, e);
+            return Err(e);
+        }
+    };
     let peer_conn = PeerConnection {
         write_half,
-        noise_session,
+        transport,
+        protocol,
+        gossip: peer_gossip,
     };
 
-    // 5) in connections-Map packen
-    {
-        let mut lock = connections_arc.lock().unwrap();
-        lock.insert(remote_addr, peer_conn);
-    }
+    // 4) in connections-Map packen
+    connections_arc.lock().await.insert(remote_addr, peer_conn);
 
-    // 6) Lese-Loop => 
+    // 5) Lese-Loop => 
     //    - wir warten auf verschlüsselte KademliaMessages
     //    - wir decrypten + bincode-deserialize
-    //    - in echtem code: kad_svc.handle_message(remote_addr, msg)
-    read_loop_incoming(remote_addr, connections_arc, read_half).await?;
+    //    - weiter an `inbound` (z.B. kad_svc.handle_message(remote_addr, msg))
+    read_loop_incoming(remote_addr, remote_static, connections_arc, read_half, inbound, guard).await?;
 
     Ok(())
 }
 
+/// Quarantäne gilt für die authentifizierte Identität (statischer
+/// Noise-Schlüssel) und wird daher erst nach dem Handshake geprüft.
+fn reject_quarantined(guard: &SharedGuard, remote_static: &[u8], addr: SocketAddr) -> Result<()> {
+    if guard.lock_recover().is_quarantined(remote_static, Instant::now()) {
+        warn!("{} steht unter Quarantäne => Verbindung abgelehnt", addr);
+        return Err(anyhow!("Peer {} steht unter Quarantäne", addr));
+    }
+    Ok(())
+}
+
 /// Ständiger Lese-Loop nach abgeschlossenem Handshake.
 /// Wir holen uns unser PeerConnection aus der Map, um 
-/// an die `noise_session` zu gelangen (die wir im Responder init. haben).
+/// an den Noise-Transport zu gelangen.
 async fn read_loop_incoming(
     remote_addr: SocketAddr,
-    connections_arc: Arc<Mutex<HashMap<SocketAddr, PeerConnection>>>,
-    mut read_half: tokio::net::OwnedReadHalf,
+    remote_static: Vec<u8>,
+    connections_arc: ConnectionMap,
+    mut read_half: BoxedRead,
+    inbound: Option<InboundSender>,
+    guard: SharedGuard,
 ) -> Result<()> {
-    let mut buf = [0u8; 4096];
     loop {
-        let n = match read_half.read(&mut buf).await {
-            Ok(0) => {
+        let frame = match read_frame(&mut read_half).await {
+            Ok(Some(f)) => f,
+            Ok(None) => {
                 info!("Remote {} => EOF => Closing read_loop", remote_addr);
                 break;
             }
-            Ok(n) => n,
             Err(e) => {
                 warn!("Read-Error bei {} => {:?}", remote_addr, e);
                 break;
             }
         };
-        // => Aus der Map => noise_session
-        let mut guard = connections_arc.lock().unwrap();
-        let conn = match guard.get_mut(&remote_addr) {
-            Some(c) => c,
-            None => {
-                warn!("ConnectionState für {} verschwunden => Abbruch read_loop", remote_addr);
+        // => Aus der Map => transport (+ ausgehandeltes Protokoll)
+        let (decrypted, protocol) = {
+            let mut guard = connections_arc.lock().await;
+            match guard.get_mut(&remote_addr) {
+                Some(conn) => (conn.transport.decrypt(&frame), conn.protocol.clone()),
+                None => {
+                    warn!("ConnectionState für {} verschwunden => Abbruch read_loop", remote_addr);
+                    break;
+                }
+            }
+        };
+        // Replay/Umsortierung => Stream ist kompromittiert => Verbindung schließen
+        let decrypted_msg = match decrypted {
+            Ok(m) => m,
+            Err(e) => {
+                warn!("Noise decrypt von {} => {:?}", remote_addr, e);
                 break;
             }
         };
-        let mut decrypted_msg = vec![0u8; 4096];
-        let len = conn.noise_session.read_message(&buf[..n], &mut decrypted_msg)
-            .map_err(|e| anyhow!("Noise decrypt read_message => {:?}", e))?;
-        decrypted_msg.truncate(len);
 
-        // => bincode deserialize
-        let msg: KademliaMessage = match bincode::deserialize(&decrypted_msg) {
+        // => Limits prüfen + bincode deserialize. Abgelehnt => Verbindung
+        //    schließen; Wiederholungstäter landen in Quarantäne.
+        let checked = guard.lock_recover().check(&remote_static, remote_addr, &decrypted_msg, Instant::now());
+        let msg: KademliaMessage = match checked {
             Ok(m) => m,
             Err(e) => {
-                warn!("bincode deserialize => Fehler: {:?}", e);
+                warn!("Nachricht von {} abgelehnt => {}", remote_addr, e);
                 break;
             }
         };
-        info!("Empfangen (verschlüsselt) von {} => {:?}", remote_addr, msg);
+        // Nachrichten eines nicht ausgehandelten Features => Protokollverstoß
+        if !protocol.supports(required_features(&msg)) {
+            warn!("{} sendet Nachricht ohne ausgehandeltes Feature {:#x} => Verbindung geschlossen", remote_addr, required_features(&msg));
+            break;
+        }
+        debug!("Empfangen (verschlüsselt) von {} => {:?}", remote_addr, msg);
 
-        // => In echtem System: kad_svc.handle_message(remote_addr, msg);
-        // => hier: no-op
+        if let Some(tx) = &inbound {
+            if tx.send((remote_addr, msg)).is_err() {
+                debug!("Inbound-Empfänger geschlossen => verwerfe Nachricht von {}", remote_addr);
+            }
+        }
     }
     // => wir entfernen die Connection:
-    {
-        let mut guard2 = connections_arc.lock().unwrap();
-        guard2.remove(&remote_addr);
-    }
+    connections_arc.lock().await.remove(&remote_addr);
     info!("Beende read_loop_incoming for {}", remote_addr);
     Ok(())
 }
@@ -248,71 +869,198 @@ async fn read_loop_incoming(
 impl TcpP2PAdapter {
     /// Initiator-Verbindungsaufbau (wenn wir `send_kademlia_msg` an 
     /// unbekannten Peer aufrufen) => Machen den Noise-XX-Handshake als Initiator.
+    /// Vorübergehende Fehler werden gemäß `handshake_retry` mit Jitter-Backoff
+    /// wiederholt, jeder Versuch mit frischer Verbindung und Noise-Session.
     async fn connect_and_handshake_initiator(
         &self,
         addr: SocketAddr
     ) -> Result<()> {
-        // DNS-Auflösung
-        let resolved = match addr.to_string().to_socket_addrs() {
-            Ok(mut i) => i.next().unwrap_or(addr),
-            Err(e) => {
-                return Err(anyhow!("DNS-Auflösung fehlgeschlagen => {}", e));
+        let policy = self.handshake_retry;
+        let max_attempts = policy.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            let result = tokio::time::timeout(
+                Duration::from_millis(policy.attempt_timeout_ms),
+                self.handshake_initiator_once(addr),
+            )
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|r| r);
+            let err = match result {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            if !is_transient_handshake_error(&err) {
+                return Err(err.context(format!("Handshake mit {} endgültig fehlgeschlagen", addr)));
+            }
+            if attempt >= max_attempts {
+                return Err(err.context(format!("Handshake mit {} nach {} Versuchen aufgegeben", addr, attempt)));
             }
+            let wait = policy.backoff(attempt);
+            debug!("Handshake mit {} (Versuch {}/{}) => {:?}; neuer Versuch in {:?}", addr, attempt, max_attempts, err, wait);
+            sleep(wait).await;
+            attempt += 1;
+        }
+    }
+
+    /// Ein einzelner Versuch: Verbindung, Noise-XX, Hello, Gossip-Config.
+    async fn handshake_initiator_once(
+        &self,
+        addr: SocketAddr
+    ) -> Result<()> {
+        let (mut read_half, mut write_half) = self.dial(addr).await?;
+        if let Some(pow) = &self.pow {
+            answer_pow(&mut read_half, &mut write_half, addr, pow).await?;
+        }
+        self.handshake_initiator_over(addr, read_half, write_half).await
+    }
+
+    /// Baut die Verbindung zu `addr` auf (direkt, sonst über TURN) und
+    /// verbucht das Ergebnis samt Handshake-Dauer im Adressbuch.
+    async fn ensure_connected(&self, addr: SocketAddr) -> Result<()> {
+        if self.connections.lock().await.contains_key(&addr) {
+            return Ok(());
+        }
+        let started = Instant::now();
+        let mut result = self.connect_and_handshake_initiator(addr).await;
+        if let Err(e) = &result {
+            warn!("connect_and_handshake_initiator({}) => {:?}", addr, e);
+            // Fallback nur mit Noise; ohne Handshake geht nichts über das Relay
+            if self.turn.is_some() && !is_onion_virtual(&addr) {
+                result = self.connect_via_relay(addr).await;
+                match &result {
+                    Ok(()) => debug!("send_kademlia_msg({}) => Noise über TURN-Relay", addr),
+                    Err(e) => warn!("connect_via_relay({}) => {:?}", addr, e),
+                }
+            }
+        }
+        if let Some(book) = &self.address_book {
+            let rtt = result.as_ref().ok().map(|_| started.elapsed());
+            book.lock_recover().record_dial(&addr, rtt, unix_now());
+        }
+        result
+    }
+
+    /// Direkt nicht erreichbar => derselbe Noise-XX-Handshake über das
+    /// TURN-Relay. Danach laufen nur verschlüsselte Frames über das Relay.
+    async fn connect_via_relay(&self, addr: SocketAddr) -> Result<()> {
+        let relay = self
+            .turn
+            .as_ref()
+            .ok_or_else(|| anyhow!("Kein TURN-Relay konfiguriert"))?;
+        let (read_half, write_half) = relay.open(addr);
+        tokio::time::timeout(
+            Duration::from_millis(self.handshake_retry.attempt_timeout_ms),
+            self.handshake_initiator_over(addr, read_half, write_half),
+        )
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|r| r)
+        .with_context(|| format!("Handshake mit {} über TURN-Relay", addr))
+    }
+
+    /// Stream zu `addr`: Tor für Onion-Adressen, sonst TCP.
+    async fn dial(&self, addr: SocketAddr) -> Result<(BoxedRead, BoxedWrite)> {
+        let (read_half, write_half): (BoxedRead, BoxedWrite) = if is_onion_virtual(&addr) {
+            // Onion-Peer => über den eingebetteten Tor-Client
+            let tor = self
+                .tor
+                .as_ref()
+                .ok_or_else(|| anyhow!("{} ist eine Onion-Adresse, aber Tor ist nicht aktiv", addr))?;
+            let stream = tor.dial_virtual(&addr).await?;
+            let (r, w) = tokio::io::split(stream);
+            (Box::new(r), Box::new(w))
+        } else {
+            // DNS-Auflösung
+            let resolved = match addr.to_string().to_socket_addrs() {
+                Ok(mut i) => i.next().unwrap_or(addr),
+                Err(e) => {
+                    return Err(anyhow!("DNS-Auflösung fehlgeschlagen => {}", e));
+                }
+            };
+            let stream = TcpStream::connect(resolved).await
+                .with_context(|| format!("connect() zu {}", resolved))?;
+            let (r, w) = stream.into_split();
+            (Box::new(r), Box::new(w))
         };
-        let stream = TcpStream::connect(resolved).await
-            .map_err(|e| anyhow!("connect() zu {} => {:?}", resolved, e))?;
+        Ok((read_half, write_half))
+    }
 
-        let (mut read_half, write_half) = stream.into_split();
-        let noise_params: NoiseParams = "Noise_XX_25519_ChaChaPoly_SHA256".parse()?;
+    /// Initiator-Handshake über einen bereits geöffneten Stream.
+    async fn handshake_initiator_over(
+        &self,
+        addr: SocketAddr,
+        mut read_half: BoxedRead,
+        mut write_half: BoxedWrite,
+    ) -> Result<()> {
+        let noise_params: NoiseParams = NOISE_PATTERN.parse()?;
         let builder = Builder::new(noise_params);
-        let mut noise_session = builder.build_initiator()?;
+        let mut noise_session = builder.local_private_key(&self.noise_key.private).build_initiator()?;
 
         // Handshake Initiator: 3 Msg
         // 1) Schicke msg1
         let mut msg1 = vec![0u8; 1024];
         let l1 = noise_session.write_message(&[], &mut msg1)
             .map_err(|e| anyhow!("noise write_message(1): {:?}", e))?;
-        let mut wh_clone = write_half.clone();
-        wh_clone.write_all(&msg1[..l1]).await?;
+        write_frame(&mut write_half, &msg1[..l1]).await?;
 
         // 2) Lese msg2
-        let mut buf = [0u8; 1024];
-        let n2 = read_half.read(&mut buf).await?;
-        if n2 == 0 {
-            return Err(anyhow!("Handshake abgebrochen => remote schloss (2)"));
-        }
+        let msg2 = read_frame(&mut read_half).await?.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Handshake abgebrochen => remote schloss (2)")
+        })?;
         let mut tmp_out = vec![0u8; 1024];
-        noise_session.read_message(&buf[..n2], &mut tmp_out)
+        noise_session.read_message(&msg2, &mut tmp_out)
             .map_err(|e| anyhow!("noise read_message(2): {:?}", e))?;
 
         // 3) Schicke msg3
         let mut msg3 = vec![0u8; 1024];
         let l3 = noise_session.write_message(&[], &mut msg3)
             .map_err(|e| anyhow!("noise write_message(3): {:?}", e))?;
-        let mut wh_clone2 = write_half.clone();
-        wh_clone2.write_all(&msg3[..l3]).await?;
+        write_frame(&mut write_half, &msg3[..l3]).await?;
 
         if !noise_session.is_handshake_complete() {
             return Err(anyhow!("Handshake unvollständig (Initiator) => Abbruch."));
         }
+        let remote_static = noise_session
+            .get_remote_static()
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| anyhow!("Noise-Handshake ohne statischen Schlüssel von {}", addr))?;
+        reject_quarantined(&self.guard, &remote_static, addr)?;
         info!("Noise-Initiator Handshake erfolgreich => remote={}", addr);
 
+        let mut transport = NoiseTransport::from_handshake(noise_session, self.rekey_policy)?;
+        let protocol = exchange_hello(&mut *read_half, &mut *write_half, &mut transport, &self.hello, true, HELLO_TIMEOUT)
+            .await
+            .map_err(|e| anyhow!("Peer {} abgelehnt => {}", addr, e))?;
+        let keys = (self.noise_key.public.as_slice(), remote_static.as_slice());
+        let peer_gossip = exchange_gossip_config(&mut *read_half, &mut *write_half, &mut transport, self.gossip.as_ref(), &protocol, keys, true)
+            .await
+            .map_err(|e| anyhow!("Peer {} abgelehnt (Gossip-Config) => {}", addr, e))?;
+
         // => Speichere in connections
         let peer_conn = PeerConnection {
             write_half,
-            noise_session,
+            transport,
+            protocol,
+            gossip: peer_gossip,
         };
-        let mut lock = self.connections.lock().unwrap();
-        lock.insert(addr, peer_conn);
+        self.connections.lock().await.insert(addr, peer_conn);
 
         // => Asynchroner read-Loop
         // Wir spawnen analog handle_incoming => 
         //   aber wir haben hier => wir "sind" der Initiator =>  read_loop_incoming
         let connections_clone = self.connections.clone();
+        let inbound = self.inbound.clone();
+        let guard = self.guard.clone();
+        let book = self.address_book.clone();
+        let connected_at = Instant::now();
         tokio::spawn(async move {
-            if let Err(e) = read_loop_incoming(addr, connections_clone, read_half).await {
+            if let Err(e) = read_loop_incoming(addr, remote_static, connections_clone, read_half, inbound, guard).await {
                 warn!("read_loop_incoming error initiator => {:?}", e);
             }
+            if let Some(book) = book {
+                book.lock_recover().record_uptime(&addr, connected_at.elapsed(), unix_now());
+            }
         });
 
         Ok(())
@@ -331,16 +1079,8 @@ impl KademliaP2PAdapter for TcpP2PAdapter {
         // Wir spawnen asynchron, weil Connect + Write blocken könnte.
         tokio::spawn(async move {
             // 1) Falls wir in connections NICHT haben => connect + handshake (Initiator)
-            let exists = {
-                let lock = connections.lock().unwrap();
-                lock.contains_key(&addr)
-            };
-            if !exists {
-                // => connect & handshake
-                if let Err(e) = adapter_ref.connect_and_handshake_initiator(addr).await {
-                    warn!("connect_and_handshake_initiator({}) => {:?}", addr, e);
-                    return;
-                }
+            if adapter_ref.ensure_connected(addr).await.is_err() {
+                return;
             }
             // 2) Nun bincode + Noise
             let bin = match bincode::serialize(&msg_cloned) {
@@ -350,8 +1090,8 @@ impl KademliaP2PAdapter for TcpP2PAdapter {
                     return;
                 }
             };
-            // 3) Hole PeerConnection => noise_session => write_message => => .write_all
-            let mut lock = connections.lock().unwrap();
+            // 3) Hole PeerConnection => transport.encrypt => .write_all
+            let mut lock = connections.lock().await;
             let pc = match lock.get_mut(&addr) {
                 Some(p) => p,
                 None => {
@@ -359,18 +1099,23 @@ impl KademliaP2PAdapter for TcpP2PAdapter {
                     return;
                 }
             };
-            let mut enc_buf = vec![0u8; bin.len() + 128];
-            let len = match pc.noise_session.write_message(&bin, &mut enc_buf) {
-                Ok(l) => l,
+            // Peer hat das nötige Feature nicht ausgehandelt => nicht senden
+            let needed = required_features(&msg_cloned);
+            if !pc.protocol.supports(needed) {
+                debug!("send_kademlia_msg({}) => Feature {:#x} nicht ausgehandelt, verworfen", addr, needed);
+                return;
+            }
+            let enc_buf = match pc.transport.encrypt(&bin) {
+                Ok(b) => b,
                 Err(e) => {
-                    warn!("noise_session.write_message => {:?}", e);
+                    warn!("transport.encrypt => {:?}", e);
                     // => drop connection
                     lock.remove(&addr);
                     return;
                 }
             };
             // => Senden
-            if let Err(e) = pc.write_half.write_all(&enc_buf[..len]).await {
+            if let Err(e) = write_frame(&mut pc.write_half, &enc_buf).await {
                 warn!("send_kademlia_msg => write_all error => {:?}", e);
                 lock.remove(&addr);
             }
@@ -389,6 +1134,417 @@ impl Clone for TcpP2PAdapter {
             local_addr: self.local_addr,
             connections: self.connections.clone(),
             listener_handle: self.listener_handle.clone(),
+            inbound: self.inbound.clone(),
+            tor: self.tor.clone(),
+            turn: self.turn.clone(),
+            rekey_policy: self.rekey_policy,
+            hello: self.hello.clone(),
+            gossip: self.gossip.clone(),
+            guard: self.guard.clone(),
+            handshake_retry: self.handshake_retry,
+            noise_key: self.noise_key.clone(),
+            address_book: self.address_book.clone(),
+            pow: self.pow.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn fast_retry(max_attempts: u32) -> HandshakeRetryPolicy {
+        HandshakeRetryPolicy { max_attempts, base_backoff_ms: 10, max_backoff_ms: 50, attempt_timeout_ms: 2_000 }
+    }
+
+    /// Responder, der die erste Verbindung sofort kappt und ab der zweiten
+    /// normal antwortet. Liefert Adresse und Zähler der Verbindungen.
+    async fn flaky_responder() -> (SocketAddr, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            let connections: ConnectionMap = Arc::new(AsyncMutex::new(HashMap::new()));
+            loop {
+                let (socket, remote) = listener.accept().await.unwrap();
+                if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                    drop(socket);
+                    continue;
+                }
+                let guard: SharedGuard = Arc::new(Mutex::new(MessageGuard::default()));
+                let connections = connections.clone();
+                tokio::spawn(async move {
+                    let _ = handle_incoming_connection(
+                        socket, remote, connections, RekeyPolicy::default(), None, Hello::local(), None, guard,
+                        Arc::new(generate_noise_key()),
+                    )
+                    .await;
+                });
+            }
+        });
+        (addr, accepted)
+    }
+
+    #[tokio::test]
+    async fn test_transient_handshake_failure_is_retried() {
+        let (addr, accepted) = flaky_responder().await;
+        let adapter = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap()).with_handshake_retry(fast_retry(3));
+
+        adapter.connect_and_handshake_initiator(addr).await.unwrap();
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+        assert!(adapter.peer_protocol(&addr).await.is_some());
+
+        // Ohne Wiederholung scheitert derselbe Ablauf
+        let (addr, accepted) = flaky_responder().await;
+        let once = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap()).with_handshake_retry(fast_retry(1));
+        let err = once.connect_and_handshake_initiator(addr).await.unwrap_err();
+        assert!(is_transient_handshake_error(&err), "{:?}", err);
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_handshake_failure_is_not_retried() {
+        // Antwortet auf msg1 mit Müll => Noise kann msg2 nicht lesen
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = read_frame(&mut socket).await;
+                let _ = write_frame(&mut socket, &[0xAB; 96]).await;
+            }
+        });
+
+        let adapter = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap()).with_handshake_retry(fast_retry(5));
+        let err = adapter.connect_and_handshake_initiator(addr).await.unwrap_err();
+        assert!(!is_transient_handshake_error(&err), "{:?}", err);
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_turn_fallback_runs_noise_over_relay() {
+        use crate::kademlia::kademlia_service::NodeId;
+        use crate::network::turn::{test_server::spawn_mock_turn, TurnConfig};
+
+        async fn relayed_client(server: SocketAddr) -> Arc<TurnClient> {
+            let client = TurnClient::connect(TurnConfig {
+                server: server.to_string(),
+                username: "alice".into(),
+                password: "secret".into(),
+                lifetime: Duration::from_secs(600),
+            })
+            .await
+            .unwrap();
+            client.allocate().await.unwrap();
+            client
+        }
+
+        let (server_a, log_a) = spawn_mock_turn().await;
+        let (server_b, log_b) = spawn_mock_turn().await;
+        let turn_a = relayed_client(server_a).await;
+        let turn_b = relayed_client(server_b).await;
+        let (relay_a, relay_b) = (turn_a.relayed_addr().unwrap(), turn_b.relayed_addr().unwrap());
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let b = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap()).with_inbound(tx).with_turn_relay(turn_b);
+        b.start_listener().unwrap();
+        // Direkt nicht erreichbar: unter der Relay-Adresse lauscht kein TCP
+        let a = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap())
+            .with_handshake_retry(fast_retry(1))
+            .with_turn_relay(turn_a);
+        a.start_listener().unwrap();
+
+        let id = NodeId::random();
+        a.send_kademlia_msg(relay_b, &KademliaMessage::Ping(id.clone()));
+        let (from, msg) = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+        assert_eq!(from, relay_a);
+        assert!(matches!(msg, KademliaMessage::Ping(ref got) if got.0 == id.0));
+        assert!(a.peer_protocol(&relay_b).await.is_some());
+
+        // Über die Relays liefen Handshake und Noise-Frames, nie die Nachricht im Klartext
+        let logged: Vec<Vec<u8>> = log_a.lock().unwrap().iter().chain(log_b.lock().unwrap().iter()).cloned().collect();
+        assert!(!logged.is_empty());
+        let plain = bincode::serialize(&KademliaMessage::Ping(id.clone())).unwrap();
+        for payload in &logged {
+            assert!(!payload.windows(id.0.len()).any(|w| w == id.0), "Klartext-NodeId auf dem Relay");
+            assert!(!payload.windows(plain.len()).any(|w| w == plain.as_slice()));
+        }
+    }
+
+    fn gossip_exchange(seed: u8, interval_secs: u64) -> GossipExchange {
+        use crate::network::gossip_config::GossipBounds;
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        let mut config = GossipConfig::new();
+        config.interval = Duration::from_secs(interval_secs);
+        GossipExchange::new(&config, Arc::new(ed25519_dalek::Keypair { secret, public }), GossipBounds::default())
+    }
+
+    #[tokio::test]
+    async fn test_gossip_config_bound_to_noise_peer_in_handshake() {
+        let b = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap()).with_gossip_config(gossip_exchange(2, 20));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let b_addr = listener.local_addr().unwrap();
+        let b_side = b.clone();
+        tokio::spawn(async move {
+            let (socket, remote) = listener.accept().await.unwrap();
+            let _ = handle_incoming_connection(
+                socket, remote, b_side.connections.clone(), b_side.rekey_policy, None, b_side.hello.clone(),
+                b_side.gossip.clone(), b_side.guard.clone(), b_side.noise_key.clone(),
+            )
+            .await;
+        });
+
+        let a = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap())
+            .with_handshake_retry(fast_retry(1))
+            .with_gossip_config(gossip_exchange(1, 5));
+        a.connect_and_handshake_initiator(b_addr).await.unwrap();
+        // Beide Seiten übernehmen die gemeinsame Config (langsameres Intervall)
+        assert_eq!(a.peer_gossip_config(&b_addr).await.unwrap().interval, Duration::from_secs(20));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let got = b.connections.lock().await.values().find_map(|c| c.gossip.clone());
+                if let Some(cfg) = got {
+                    break cfg;
+                }
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .map(|cfg| assert_eq!(cfg.interval, Duration::from_secs(20)))
+        .expect("responder hat keine Gossip-Config übernommen");
+
+        // Peer spielt eine für einen anderen Noise-Schlüssel signierte Config ein
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let evil_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (mut r, mut w) = socket.into_split();
+            let key = generate_noise_key();
+            let mut hs = Builder::new(NOISE_PATTERN.parse().unwrap())
+                .local_private_key(&key.private)
+                .build_responder()
+                .unwrap();
+            let mut buf = vec![0u8; 1024];
+            let m1 = read_frame(&mut r).await.unwrap().unwrap();
+            hs.read_message(&m1, &mut buf).unwrap();
+            let len = hs.write_message(&[], &mut buf).unwrap();
+            write_frame(&mut w, &buf[..len]).await.unwrap();
+            let m3 = read_frame(&mut r).await.unwrap().unwrap();
+            hs.read_message(&m3, &mut buf).unwrap();
+            let mut transport = NoiseTransport::from_handshake(hs, RekeyPolicy::default()).unwrap();
+            let mut hello = Hello::local();
+            hello.features |= FEATURE_GOSSIP_CONFIG;
+            exchange_hello(&mut r, &mut w, &mut transport, &hello, false, HELLO_TIMEOUT).await.unwrap();
+            let _ = read_frame(&mut r).await;
+            let replayed = gossip_exchange(3, 5).signed_for(&[7u8; 32]);
+            let frame = transport.encrypt(&replayed.encode()).unwrap();
+            write_frame(&mut w, &frame).await.unwrap();
+            let _ = read_frame(&mut r).await;
+        });
+        let err = a.connect_and_handshake_initiator(evil_addr).await.unwrap_err();
+        assert!(format!("{:?}", err).contains("different noise peer key"), "{:?}", err);
+        assert!(a.peer_gossip_config(&evil_addr).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dials_and_uptime_recorded_in_address_book() {
+        use crate::kademlia::kademlia_service::NodeId;
+        use crate::network::address_book::AddressBook;
+
+        let (up, _) = flaky_responder().await;
+        let down = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let mut book = AddressBook::in_memory();
+        book.observe(NodeId([1; 32]), up, 0);
+        book.observe(NodeId([2; 32]), down, 0);
+        let book = Arc::new(Mutex::new(book));
+
+        let adapter = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap())
+            .with_handshake_retry(fast_retry(2))
+            .with_address_book(book.clone());
+        adapter.ensure_connected(up).await.unwrap();
+        assert!(adapter.ensure_connected(down).await.is_err());
+
+        {
+            let book = book.lock().unwrap();
+            let ok = book.get(&NodeId([1; 32])).unwrap();
+            assert_eq!((ok.successes, ok.failures), (1, 0));
+            assert!(ok.rtt_ms.is_some());
+            let failed = book.get(&NodeId([2; 32])).unwrap();
+            assert_eq!((failed.successes, failed.failures), (0, 1));
+        }
+
+        // Verbindung endet => Dauer wird verbucht (Sekunden, hier 0) und last_seen aktualisiert
+        book.lock().unwrap().observe(NodeId([1; 32]), up, 0);
+        adapter.connections.lock().await.remove(&up);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while book.lock().unwrap().get(&NodeId([1; 32])).unwrap().last_seen == 0 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Verbindungsende nicht verbucht");
+    }
+
+    #[tokio::test]
+    async fn test_panic_while_holding_connections_does_not_brick_them() {
+        let (up, _) = flaky_responder().await;
+        let adapter = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap()).with_handshake_retry(fast_retry(2));
+        adapter.ensure_connected(up).await.unwrap();
+
+        let connections = adapter.connections.clone();
+        let crashed = tokio::spawn(async move {
+            let mut map = connections.lock().await;
+            map.remove(&up);
+            panic!("Task stirbt mit gehaltener Connection-Map");
+        })
+        .await;
+        assert!(crashed.is_err());
+
+        // Map bleibt benutzbar, inkl. Neuaufbau der Verbindung
+        assert!(!adapter.connections.lock().await.contains_key(&up));
+        adapter.ensure_connected(up).await.unwrap();
+        assert!(adapter.connections.lock().await.contains_key(&up));
+    }
+
+    fn pow_config(base_difficulty: u8, max_solve_difficulty: u8) -> PowConfig {
+        PowConfig {
+            enabled: true,
+            base_difficulty,
+            max_difficulty: base_difficulty,
+            max_solve_difficulty,
+            timeout_ms: 300,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pow_handshake_between_adapters() {
+        // Freien Port finden, dann dort lauschen
+        let b_addr = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let b = TcpP2PAdapter::new(b_addr).with_pow(&pow_config(8, 24));
+        b.start_listener().unwrap();
+        sleep(Duration::from_millis(50)).await;
+
+        let a = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap())
+            .with_handshake_retry(fast_retry(1))
+            .with_pow(&pow_config(8, 24));
+        a.connect_and_handshake_initiator(b_addr).await.unwrap();
+        assert!(a.peer_protocol(&b_addr).await.is_some());
+
+        // Ohne PoW liest der Initiator die Challenge als Noise-Frame => kein Handshake
+        let plain = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap()).with_handshake_retry(fast_retry(1));
+        assert!(plain.connect_and_handshake_initiator(b_addr).await.is_err());
+
+        // Difficulty über dem eigenen Limit wird nicht gelöst
+        let capped = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap())
+            .with_handshake_retry(fast_retry(1))
+            .with_pow(&pow_config(8, 4));
+        let err = capped.connect_and_handshake_initiator(b_addr).await.unwrap_err();
+        assert!(format!("{:?}", err).contains("über Limit"), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_silent_initiator_times_out_without_blocking_accepts() {
+        let b_addr = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let b = TcpP2PAdapter::new(b_addr).with_pow(&pow_config(4, 24));
+        b.start_listener().unwrap();
+        sleep(Duration::from_millis(50)).await;
+
+        // Verbindet sich, liest die Challenge und schweigt
+        let mut silent = TcpStream::connect(b_addr).await.unwrap();
+        let mut challenge = [0u8; PowChallenge::WIRE_LEN];
+        silent.read_exact(&mut challenge).await.unwrap();
+
+        // Ein zweiter Peer kommt trotzdem sofort durch
+        let a = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap())
+            .with_handshake_retry(fast_retry(1))
+            .with_pow(&pow_config(4, 24));
+        tokio::time::timeout(Duration::from_secs(2), a.connect_and_handshake_initiator(b_addr))
+            .await
+            .expect("Accept-Loop blockiert")
+            .unwrap();
+
+        // Nach dem Zeitlimit schließt der Responder die stumme Verbindung
+        let mut rest = [0u8; 1];
+        let n = tokio::time::timeout(Duration::from_secs(2), silent.read(&mut rest))
+            .await
+            .expect("stumme Verbindung nicht geschlossen")
+            .unwrap_or(0);
+        assert_eq!(n, 0);
+    }
+
+    /// Fertige Noise-Transports beider Seiten, Handshake im Speicher.
+    fn noise_pair() -> (NoiseTransport, NoiseTransport) {
+        let (ka, kb) = (generate_noise_key(), generate_noise_key());
+        let mut i = Builder::new(NOISE_PATTERN.parse().unwrap()).local_private_key(&ka.private).build_initiator().unwrap();
+        let mut r = Builder::new(NOISE_PATTERN.parse().unwrap()).local_private_key(&kb.private).build_responder().unwrap();
+        let (mut buf, mut out) = (vec![0u8; 1024], vec![0u8; 1024]);
+        let n = i.write_message(&[], &mut buf).unwrap();
+        r.read_message(&buf[..n], &mut out).unwrap();
+        let n = r.write_message(&[], &mut buf).unwrap();
+        i.read_message(&buf[..n], &mut out).unwrap();
+        let n = i.write_message(&[], &mut buf).unwrap();
+        r.read_message(&buf[..n], &mut out).unwrap();
+        (
+            NoiseTransport::from_handshake(i, RekeyPolicy::default()).unwrap(),
+            NoiseTransport::from_handshake(r, RekeyPolicy::default()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_missing_hello_times_out() {
+        let (_initiator, mut transport) = noise_pair();
+        // Initiator schweigt nach dem Noise-Handshake
+        let (_silent, ours) = tokio::io::duplex(MAX_FRAME_LEN);
+        let (mut r, mut w) = tokio::io::split(ours);
+        let started = Instant::now();
+        let err = exchange_hello(&mut r, &mut w, &mut transport, &Hello::local(), false, Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Kein Hello"), "{:?}", err);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_messages_need_negotiated_feature() {
+        use crate::kademlia::kademlia_service::NodeId;
+        use crate::protocol::version::FEATURE_SHARD_SNAPSHOT;
+
+        // B bietet keine Shard-Snapshots an
+        let mut hello = Hello::local();
+        hello.features &= !FEATURE_SHARD_SNAPSHOT;
+        let b_addr = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let b = TcpP2PAdapter::new(b_addr).with_inbound(tx).with_protocol(hello);
+        b.start_listener().unwrap();
+        sleep(Duration::from_millis(50)).await;
+
+        let a = TcpP2PAdapter::new("127.0.0.1:0".parse().unwrap()).with_handshake_retry(fast_retry(1));
+        a.connect_and_handshake_initiator(b_addr).await.unwrap();
+        assert!(!a.peer_protocol(&b_addr).await.unwrap().supports(FEATURE_SHARD_SNAPSHOT));
+
+        // Snapshot wird nicht gesendet, der Ping schon
+        a.send_kademlia_msg(b_addr, &KademliaMessage::CrdtSnapshots(Vec::new()));
+        a.send_kademlia_msg(b_addr, &KademliaMessage::Ping(NodeId::random()));
+        let (_, msg) = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+        assert!(matches!(msg, KademliaMessage::Ping(_)));
+        assert!(tokio::time::timeout(Duration::from_millis(200), rx.recv()).await.is_err());
+    }
+
+    #[test]
+    fn test_backoff_is_jittered_and_capped() {
+        let policy = HandshakeRetryPolicy { base_backoff_ms: 100, max_backoff_ms: 300, ..Default::default() };
+        for _ in 0..50 {
+            let first = policy.backoff(1).as_millis();
+            assert!((50..=100).contains(&first), "{}", first);
+            let late = policy.backoff(10).as_millis();
+            assert!((150..=300).contains(&late), "{}", late);
         }
     }
 }
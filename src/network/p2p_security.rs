@@ -32,18 +32,18 @@ use anyhow::{Result, anyhow};
 
 use crate::error::DexError;
 use crate::rate_limiting::token_bucket::TokenBucket;
+use crate::rate_limiting::subnet_limiter::{SubnetLimiterConfig, SubnetRateLimiter};
+use crate::metrics::RATE_LIMIT_DROPS;
 
 // Für NAT-Traversal via UPnP. 
 // crates.io: igd, nat_upnp
 // cargo add igd
 use igd::aio::search_gateway;
 
-// Für STUN: (wie bisher)
-use stun::{
-    client::{Client as StunClient, Runner, TransactionId},
-    message::{Message, BINDING_REQUEST},
-    xoraddr::XorMappedAddress,
-};
+// Für STUN: eigener RFC-5389-Client
+use crate::network::stun::discover_external_addr;
+use crate::network::turn::{TurnClient, TurnConfig};
+use std::net::SocketAddr;
 
 // Falls du TLS-Authentifizierung (Client-Certs) brauchst:
 use rustls::{Certificate};
@@ -63,9 +63,14 @@ pub struct P2PSecurityConfig {
     /// Ob wir NAT-Traversal per UPnP/TURN/… versuchen
     pub nat_traversal_enabled: bool,
 
-    /// STUN-Server, falls wir STUN wollen
+    /// STUN-Server, falls wir STUN wollen; mehrere kommagetrennt (Failover)
     pub stun_server: String,
 
+    /// TURN-Relay (leer => kein Relay)
+    pub turn_server: String,
+    pub turn_username: String,
+    pub turn_password: String,
+
     /// TLS-Client-Zertifikate => optional
     pub trusted_client_certs: Vec<Vec<u8>>,
 
@@ -104,6 +109,9 @@ pub trait P2PSecurity: Send + Sync {
 pub struct AdvancedP2PSecurity {
     pub config: P2PSecurityConfig,
     pub buckets: Arc<Mutex<HashMap<IpAddr, TokenBucket>>>,
+    pub subnet_limiter: Arc<Mutex<SubnetRateLimiter>>,
+    /// Nach `turn_allocate_channel` gesetzt
+    pub turn: Mutex<Option<Arc<TurnClient>>>,
 }
 
 impl AdvancedP2PSecurity {
@@ -112,8 +120,16 @@ impl AdvancedP2PSecurity {
         AdvancedP2PSecurity {
             config,
             buckets: Arc::new(Mutex::new(map)),
+            subnet_limiter: Arc::new(Mutex::new(SubnetRateLimiter::new(SubnetLimiterConfig::default()))),
+            turn: Mutex::new(None),
         }
     }
+
+    /// Limits des Subnetz-Limiters, z. B. `(&config.rate_limits).into()`.
+    pub fn with_subnet_limits(self, limits: SubnetLimiterConfig) -> Self {
+        self.subnet_limiter.lock().unwrap().reconfigure(limits);
+        self
+    }
 }
 
 #[allow(unused)]
@@ -136,43 +152,60 @@ impl AdvancedP2PSecurity {
         }
     }
 
+    /// Konfigurierte STUN-Server (kommagetrennt) in Failover-Reihenfolge.
+    pub fn stun_servers(&self) -> Vec<String> {
+        self.config
+            .stun_server
+            .split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    /// Externe Adresse (IP + gemappter Port) per STUN-Binding-Request.
+    pub async fn stun_probe_external_ip(&self) -> Result<SocketAddr, DexError> {
+        let servers = self.stun_servers();
+        if servers.is_empty() {
+            return Err(DexError::Other("stun_probe_external_ip => no STUN server".into()));
+        }
+        let addr = discover_external_addr(&servers, 0, Duration::from_secs(2))
+            .await
+            .map_err(|e| DexError::Other(format!("STUN failed: {:?}", e)))?;
+        debug!("STUN => external address = {}", addr);
+        Ok(addr)
+    }
+
+    /// TURN-Allocation anlegen; die relayed Adresse wird Peers gemeldet, die
+    /// uns direkt nicht erreichen. Der Client bleibt über `turn_client()` für
+    /// den P2P-Adapter verfügbar, `TurnClient::run_refresh` hält ihn am Leben.
+    pub async fn turn_allocate_channel(&self) -> Result<SocketAddr, DexError> {
+        if self.config.turn_server.is_empty() {
+            return Err(DexError::Other("turn_allocate_channel => no TURN server".into()));
+        }
+        let client = TurnClient::connect(TurnConfig {
+            server: self.config.turn_server.clone(),
+            username: self.config.turn_username.clone(),
+            password: self.config.turn_password.clone(),
+            lifetime: Duration::from_secs(600),
+        })
+        .await
+        .map_err(|e| DexError::Other(format!("TURN connect: {:?}", e)))?;
+        let alloc = client
+            .allocate()
+            .await
+            .map_err(|e| DexError::Other(format!("TURN allocate: {:?}", e)))?;
+        *self.turn.lock().unwrap() = Some(client);
+        Ok(alloc.relayed)
+    }
+
+    pub fn turn_client(&self) -> Option<Arc<TurnClient>> {
+        self.turn.lock().unwrap().clone()
+    }
+
     /// Interne Hilfsfunktion => STUN
     async fn stun_external_ip(&self) -> Result<IpAddr, DexError> {
-        if self.config.stun_server.is_empty() {
-            return Err(DexError::Other("stun_external_ip => no STUN server".into()));
-        }
-        let server_addr = &self.config.stun_server;
-        let local_addr = "0.0.0.0:0".parse().unwrap();
-        let socket = tokio::net::UdpSocket::bind(local_addr).await
-            .map_err(|e| DexError::Other(format!("stun bind: {:?}", e)))?;
-        socket.connect(server_addr).await
-            .map_err(|e| DexError::Other(format!("stun connect: {:?}", e)))?;
-
-        let mut msg = Message::new();
-        msg.initialize_header(BINDING_REQUEST, &TransactionId::new())
-           .map_err(|e| DexError::Other(format!("stun init: {:?}", e)))?;
-        let raw = msg.to_bytes();
-        socket.send(&raw).await
-            .map_err(|e| DexError::Other(format!("stun send: {:?}", e)))?;
-
-        let mut buf = vec![0u8; 1024];
-        let n = socket.recv(&mut buf).await
-            .map_err(|e| DexError::Other(format!("stun recv: {:?}", e)))?;
-        
-        let mut resp = Message::new();
-        resp.raw_attributes(&buf[..n]);
-        resp.decode_header().map_err(|e| {
-            DexError::Other(format!("stun decode: {:?}", e))
-        })?;
-
-        let xor_addr = XorMappedAddress::default();
-        let mut extractor = resp.attribute_reader();
-        let mapped: XorMappedAddress = extractor.read::<XorMappedAddress>(xor_addr)
-            .map_err(|_e| DexError::Other("No XorMappedAddress from STUN".into()))?;
-
-        let ip = mapped.ip();
-        debug!("STUN => external IP = {}", ip);
-        Ok(ip)
+        Ok(self.stun_probe_external_ip().await?.ip())
     }
 }
 
@@ -250,6 +283,9 @@ impl P2PSecurity for AdvancedP2PSecurity {
 
     /// 5) DDoS => Token-Bucket
     fn rate_limit(&self, ip: &IpAddr) -> bool {
+        if !self.subnet_limiter.lock().unwrap().check(ip) {
+            return false;
+        }
         let mut lock = self.buckets.lock().unwrap();
         let capacity = self.config.ddos_rate_limit_per_min;
         let tokens_per_sec = capacity / 60 + 1;
@@ -257,6 +293,7 @@ impl P2PSecurity for AdvancedP2PSecurity {
             TokenBucket::new(capacity as u64, tokens_per_sec as u64)
         });
         if !tb.try_consume() {
+            RATE_LIMIT_DROPS.with_label_values(&["peer"]).inc();
             warn!("rate_limit => IP={} => blocked", ip);
             return false;
         }
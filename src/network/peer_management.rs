@@ -8,18 +8,28 @@
 //  - NAT Traversal mit STUN/TURN (Integration mit passenden Libraries)
 //  - IP-Blocklisten (Whitelist/Blacklist zur Filterung b�swilliger IPs)
 //  - DDoS-Schutz (z.B. durch Proxys oder Rate Limiting)
+//  - GeoIP-Diversit�t (max. Peers je Land/ASN, Refresh bevorzugt
+//    unterrepr�sentierte Regionen)
 //  
 // Diese Funktionen werden �ber eine Konfigurationsstruktur gesteuert,
 // sodass der Benutzer entscheiden kann, welche Funktionen aktiv sein sollen.
 ///////////////////////////////////////////////////////////
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
 use tracing::{info, warn, debug};
 
+use crate::metrics::PEER_DIVERSITY;
+use crate::network::address_book::SharedAddressBook;
+use crate::network::tor::is_onion_virtual;
+use crate::utils::geoip_and_ntp::{GeoInfo, GeoIpResolver};
+use crate::utils::lock::LockRecover;
+
 /// Konfigurationsparameter f�r die Peer-Verwaltung
 #[derive(Debug, Clone)]
 pub struct PeerDiscoveryConfig {
@@ -50,11 +60,142 @@ impl PeerDiscoveryConfig {
     }
 }
 
+/// Obergrenzen für Peers aus derselben Region.
+/// Ein Land zählt nur, wenn GeoIP es kennt. Für das ASN-Limit zählt ohne
+/// ASN-Treffer das /16 (IPv4) bzw. /32 (IPv6); Peers ohne GeoIP-Daten teilen
+/// sich also kein gemeinsames "??"/ASN 0. Onion-Peers haben kein Netz und
+/// bilden eine eigene Gruppe mit `max_onion`.
+#[derive(Debug, Clone)]
+pub struct DiversityPolicy {
+    pub max_per_country: usize,
+    pub max_per_asn: usize,
+    pub max_onion: usize,
+}
+
+impl Default for DiversityPolicy {
+    fn default() -> Self {
+        Self {
+            max_per_country: 8,
+            max_per_asn: 4,
+            max_onion: 8,
+        }
+    }
+}
+
+/// `peer_diversity` in der Node-Config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeerDiversitySettings {
+    /// MaxMind GeoLite2-Country (mmdb); leer => keine Diversitäts-Limits
+    pub geoip_country_db: String,
+    /// MaxMind GeoLite2-ASN (mmdb); leer => Gruppierung nur nach Präfix
+    pub geoip_asn_db: String,
+    pub max_per_country: usize,
+    pub max_per_asn: usize,
+    pub max_onion: usize,
+}
+
+impl Default for PeerDiversitySettings {
+    fn default() -> Self {
+        let d = DiversityPolicy::default();
+        Self {
+            geoip_country_db: String::new(),
+            geoip_asn_db: String::new(),
+            max_per_country: d.max_per_country,
+            max_per_asn: d.max_per_asn,
+            max_onion: d.max_onion,
+        }
+    }
+}
+
+impl PeerDiversitySettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_per_country == 0 || self.max_per_asn == 0 || self.max_onion == 0 {
+            return Err("max_per_country, max_per_asn and max_onion must be > 0".into());
+        }
+        if self.geoip_country_db.is_empty() && !self.geoip_asn_db.is_empty() {
+            return Err("geoip_asn_db needs geoip_country_db".into());
+        }
+        for path in [&self.geoip_country_db, &self.geoip_asn_db] {
+            if !path.is_empty() && !std::path::Path::new(path).exists() {
+                return Err(format!("{} does not exist", path));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn policy(&self) -> DiversityPolicy {
+        DiversityPolicy {
+            max_per_country: self.max_per_country,
+            max_per_asn: self.max_per_asn,
+            max_onion: self.max_onion,
+        }
+    }
+}
+
+/// Gruppe für das ASN-Limit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NetGroup {
+    Asn(u32),
+    /// /16 bzw. /32 ohne ASN-Treffer
+    Prefix(IpAddr),
+    Onion,
+}
+
+impl NetGroup {
+    pub fn of(ip: IpAddr, asn: u32) -> Self {
+        if is_onion_virtual(&SocketAddr::new(ip, 0)) {
+            return NetGroup::Onion;
+        }
+        if asn != 0 {
+            return NetGroup::Asn(asn);
+        }
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(IpAddr::V6(v6)),
+            v4 => v4,
+        };
+        match ip {
+            IpAddr::V4(v4) => {
+                let o = v4.octets();
+                NetGroup::Prefix(IpAddr::from([o[0], o[1], 0, 0]))
+            }
+            IpAddr::V6(v6) => {
+                let s = v6.segments();
+                NetGroup::Prefix(IpAddr::from([s[0], s[1], 0, 0, 0, 0, 0, 0]))
+            }
+        }
+    }
+}
+
+impl fmt::Display for NetGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetGroup::Asn(asn) => write!(f, "AS{}", asn),
+            NetGroup::Prefix(ip @ IpAddr::V4(_)) => write!(f, "{}/16", ip),
+            NetGroup::Prefix(ip) => write!(f, "{}/32", ip),
+            NetGroup::Onion => write!(f, "onion"),
+        }
+    }
+}
+
+/// Land (falls bekannt) und Netz-Gruppe eines aufgenommenen Peers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PeerGroup {
+    country: Option<String>,
+    net: NetGroup,
+}
+
 /// Verwalter f�r Peers im Netzwerk
 pub struct PeerManager {
     pub config: PeerDiscoveryConfig,
     // Aktuell bekannte Peers (IP:Port)
     pub peers: Arc<Mutex<HashSet<SocketAddr>>>,
+    pub diversity: DiversityPolicy,
+    geo_resolver: Option<Arc<dyn GeoIpResolver>>,
+    // Land/Netz-Gruppe je aufgenommenem Peer
+    peer_geo: Arc<Mutex<HashMap<SocketAddr, PeerGroup>>>,
+    // Persistente Verbindungsqualität je NodeId (optional)
+    address_book: Option<SharedAddressBook>,
 }
 
 impl PeerManager {
@@ -62,7 +203,163 @@ impl PeerManager {
         Self {
             config,
             peers: Arc::new(Mutex::new(HashSet::new())),
+            diversity: DiversityPolicy::default(),
+            geo_resolver: None,
+            peer_geo: Arc::new(Mutex::new(HashMap::new())),
+            address_book: None,
+        }
+    }
+
+    /// Hängt das persistente Adressbuch an; `dial_order` sortiert danach.
+    pub fn with_address_book(mut self, book: SharedAddressBook) -> Self {
+        self.address_book = Some(book);
+        self
+    }
+
+    /// Bekannte Peers in Wählreihenfolge: zuerst nach Adressbuch-Score,
+    /// Peers ohne Eintrag danach (sortiert nach Adresse).
+    pub fn dial_order(&self, now: u64) -> Vec<SocketAddr> {
+        let mut rest: Vec<SocketAddr> = self.peers.lock_recover().iter().copied().collect();
+        rest.sort();
+        let mut ordered = Vec::with_capacity(rest.len());
+        if let Some(book) = &self.address_book {
+            for (_, addr) in book.lock_recover().dial_order(now) {
+                if let Some(pos) = rest.iter().position(|a| *a == addr) {
+                    ordered.push(rest.remove(pos));
+                }
+            }
+        }
+        ordered.extend(rest);
+        ordered
+    }
+
+    /// Aktiviert die GeoIP-Diversit�tspr�fung. Ohne Resolver gilt nur die IP-Filterung.
+    pub fn with_geo_diversity(mut self, resolver: Arc<dyn GeoIpResolver>, policy: DiversityPolicy) -> Self {
+        self.geo_resolver = Some(resolver);
+        self.diversity = policy;
+        self
+    }
+
+    fn group_of(&self, ip: IpAddr) -> PeerGroup {
+        let geo = self
+            .geo_resolver
+            .as_ref()
+            .and_then(|r| r.lookup(ip))
+            .unwrap_or_else(GeoInfo::unknown);
+        PeerGroup {
+            country: Some(geo.country).filter(|c| c != &GeoInfo::unknown().country),
+            net: NetGroup::of(ip, geo.asn),
+        }
+    }
+
+    /// Ist die Gruppe eines neuen Peers schon voll? Liefert den Grund.
+    fn over_limit(&self, group: &PeerGroup, geo_map: &HashMap<SocketAddr, PeerGroup>) -> Option<String> {
+        if let Some(country) = &group.country {
+            let same_country = geo_map.values().filter(|g| g.country.as_ref() == Some(country)).count();
+            if same_country >= self.diversity.max_per_country {
+                return Some(format!("Land {} hat bereits {} Peers", country, same_country));
+            }
+        }
+        let same_net = geo_map.values().filter(|g| g.net == group.net).count();
+        let net_cap = match group.net {
+            NetGroup::Onion => self.diversity.max_onion,
+            _ => self.diversity.max_per_asn,
+        };
+        if same_net >= net_cap {
+            return Some(format!("Netz {} hat bereits {} Peers", group.net, same_net));
         }
+        None
+    }
+
+    /// Nimmt einen Peer in die Tabelle auf, sofern IP-Filter und Diversitäts-
+    /// Limits es erlauben. Gibt true zurück, wenn der Peer (jetzt) bekannt ist.
+    pub fn admit_peer(&self, addr: SocketAddr) -> bool {
+        if !self.is_ip_allowed(&addr.ip()) {
+            return false;
+        }
+        let mut peers = self.peers.lock_recover();
+        if peers.contains(&addr) {
+            return true;
+        }
+        if self.geo_resolver.is_some() {
+            let group = self.group_of(addr.ip());
+            let mut geo_map = self.peer_geo.lock_recover();
+            if let Some(reason) = self.over_limit(&group, &geo_map) {
+                warn!("Peer {} abgelehnt: {}", addr, reason);
+                return false;
+            }
+            geo_map.insert(addr, group);
+            drop(geo_map);
+        }
+        peers.insert(addr);
+        drop(peers);
+        self.update_diversity_metrics();
+        true
+    }
+
+    /// Übernimmt einen bereits gesetzten Peer (z. B. Bootstrap) ohne Limit-Prüfung,
+    /// damit er in der Verteilung mitzählt.
+    pub fn track_peer(&self, addr: SocketAddr) {
+        if self.peers.lock_recover().insert(addr) && self.geo_resolver.is_some() {
+            let group = self.group_of(addr.ip());
+            self.peer_geo.lock_recover().insert(addr, group);
+        }
+        self.update_diversity_metrics();
+    }
+
+    pub fn remove_peer(&self, addr: &SocketAddr) {
+        self.peers.lock_recover().remove(addr);
+        self.peer_geo.lock_recover().remove(addr);
+        self.update_diversity_metrics();
+    }
+
+    /// Aktuelle Verteilung: (Peers je bekanntem Land, Peers je Netz-Gruppe).
+    pub fn diversity_distribution(&self) -> (HashMap<String, usize>, HashMap<NetGroup, usize>) {
+        let geo_map = self.peer_geo.lock_recover();
+        let mut by_country = HashMap::new();
+        let mut by_net = HashMap::new();
+        for g in geo_map.values() {
+            if let Some(c) = &g.country {
+                *by_country.entry(c.clone()).or_insert(0) += 1;
+            }
+            *by_net.entry(g.net.clone()).or_insert(0) += 1;
+        }
+        (by_country, by_net)
+    }
+
+    fn update_diversity_metrics(&self) {
+        let (by_country, by_net) = self.diversity_distribution();
+        PEER_DIVERSITY.reset();
+        for (c, n) in by_country {
+            PEER_DIVERSITY.with_label_values(&["country", &c]).set(n as i64);
+        }
+        for (net, n) in by_net {
+            PEER_DIVERSITY.with_label_values(&["net", &net.to_string()]).set(n as i64);
+        }
+    }
+
+    /// Sortiert Kandidaten f�r den Bucket-Refresh: Peers aus Regionen mit den
+    /// wenigsten bekannten Peers zuerst, Kandidaten �ber dem Limit fallen weg.
+    pub fn refresh_priority(&self, candidates: &[SocketAddr]) -> Vec<SocketAddr> {
+        if self.geo_resolver.is_none() {
+            return candidates.to_vec();
+        }
+        let (by_country, by_net) = self.diversity_distribution();
+        let geo_map = self.peer_geo.lock_recover();
+        let mut scored: Vec<(usize, usize, SocketAddr)> = candidates
+            .iter()
+            .filter_map(|addr| {
+                let group = self.group_of(addr.ip());
+                if self.over_limit(&group, &geo_map).is_some() {
+                    return None;
+                }
+                let c = group.country.as_ref().and_then(|c| by_country.get(c)).copied().unwrap_or(0);
+                let a = by_net.get(&group.net).copied().unwrap_or(0);
+                Some((c, a, *addr))
+            })
+            .collect();
+        scored.sort();
+        scored.into_iter().map(|(_, _, addr)| addr).collect()
     }
 
     /// Automatische Peer Discovery: Diese Funktion simuliert
@@ -76,12 +373,9 @@ impl PeerManager {
             // Beispiel: neue Peers werden alle 30 Sekunden entdeckt.
             // Wir simulieren hier die Entdeckung:
             let simulated_peer: SocketAddr = "192.168.1.100:9000".parse()?;
-            {
-                let mut peers = self.peers.lock().unwrap();
-                if !peers.contains(&simulated_peer) {
-                    peers.insert(simulated_peer);
-                    info!("Neuer Peer entdeckt: {}", simulated_peer);
-                }
+            let known = self.peers.lock_recover().contains(&simulated_peer);
+            if !known && self.admit_peer(simulated_peer) {
+                info!("Neuer Peer entdeckt: {}", simulated_peer);
             }
         }
         Ok(())
@@ -109,3 +403,136 @@ impl PeerManager {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::geoip_and_ntp::StaticGeoResolver;
+
+    fn manager(policy: DiversityPolicy) -> PeerManager {
+        let mut res = StaticGeoResolver::default();
+        for i in 1..=5u8 {
+            res.insert(IpAddr::from([10, 0, 0, i]), "DE", 3320 + i as u32);
+        }
+        res.insert(IpAddr::from([10, 0, 1, 1]), "FR", 5410);
+        res.insert(IpAddr::from([10, 0, 2, 1]), "US", 7922);
+        PeerManager::new(PeerDiscoveryConfig::new()).with_geo_diversity(Arc::new(res), policy)
+    }
+
+    fn addr(ip: [u8; 4]) -> SocketAddr {
+        SocketAddr::from((ip, 9000))
+    }
+
+    #[test]
+    fn test_admission_capped_for_overrepresented_country() {
+        let pm = manager(DiversityPolicy { max_per_country: 3, max_per_asn: 4, max_onion: 8 });
+        for i in 1..=3u8 {
+            assert!(pm.admit_peer(addr([10, 0, 0, i])));
+        }
+        assert!(!pm.admit_peer(addr([10, 0, 0, 4])), "4. Peer aus DE muss abgelehnt werden");
+        assert!(pm.admit_peer(addr([10, 0, 1, 1])));
+
+        let (by_country, _) = pm.diversity_distribution();
+        assert_eq!(by_country.get("DE"), Some(&3));
+        assert_eq!(by_country.get("FR"), Some(&1));
+
+        // Nach dem Entfernen ist wieder Platz
+        pm.remove_peer(&addr([10, 0, 0, 1]));
+        assert!(pm.admit_peer(addr([10, 0, 0, 4])));
+    }
+
+    #[test]
+    fn test_refresh_prefers_underrepresented_regions() {
+        let pm = manager(DiversityPolicy { max_per_country: 2, max_per_asn: 4, max_onion: 8 });
+        pm.admit_peer(addr([10, 0, 0, 1]));
+        pm.admit_peer(addr([10, 0, 1, 1]));
+
+        let order = pm.refresh_priority(&[addr([10, 0, 1, 1]), addr([10, 0, 0, 2]), addr([10, 0, 2, 1])]);
+        assert_eq!(order.first(), Some(&addr([10, 0, 2, 1])));
+
+        pm.admit_peer(addr([10, 0, 0, 2]));
+        let order = pm.refresh_priority(&[addr([10, 0, 0, 3]), addr([10, 0, 2, 1])]);
+        assert_eq!(order, vec![addr([10, 0, 2, 1])]);
+    }
+
+    #[test]
+    fn test_unknown_geo_peers_grouped_by_prefix() {
+        let pm = manager(DiversityPolicy { max_per_country: 2, max_per_asn: 2, max_onion: 8 });
+        // Ohne GeoIP-Treffer: verschiedene /16 blockieren sich nicht
+        for i in 1..=5u8 {
+            assert!(pm.admit_peer(addr([20 + i, 1, 0, 1])));
+        }
+        // Dasselbe /16 unterliegt dem ASN-Limit
+        assert!(pm.admit_peer(addr([21, 1, 0, 2])));
+        assert!(!pm.admit_peer(addr([21, 1, 0, 3])));
+
+        let (by_country, by_net) = pm.diversity_distribution();
+        assert!(by_country.is_empty(), "unbekanntes Land zählt nicht");
+        assert_eq!(by_net.get(&NetGroup::Prefix(IpAddr::from([21, 1, 0, 0]))), Some(&2));
+    }
+
+    #[test]
+    fn test_onion_peers_have_their_own_cap() {
+        let pm = manager(DiversityPolicy { max_per_country: 2, max_per_asn: 2, max_onion: 3 });
+        let onion = |n: u16| SocketAddr::new(IpAddr::from([0xfd87, 0xd87e, 0xeb43, 0, 0, 0, 0, n]), 9000);
+        for n in 1..=3 {
+            assert!(pm.admit_peer(onion(n)));
+        }
+        assert!(!pm.admit_peer(onion(4)));
+        assert_eq!(pm.diversity_distribution().1.get(&NetGroup::Onion), Some(&3));
+    }
+
+    #[test]
+    fn test_peer_diversity_settings_validation() {
+        let s = PeerDiversitySettings::default();
+        assert!(s.validate().is_ok());
+        assert_eq!(s.policy().max_per_asn, DiversityPolicy::default().max_per_asn);
+        assert!(PeerDiversitySettings { max_onion: 0, ..s.clone() }.validate().is_err());
+        assert!(PeerDiversitySettings { geoip_asn_db: "asn.mmdb".into(), ..s }.validate().is_err());
+    }
+
+    #[test]
+    fn test_dial_order_follows_address_book_score() {
+        use crate::kademlia::kademlia_service::NodeId;
+        use crate::network::address_book::AddressBook;
+
+        let mut book = AddressBook::in_memory();
+        book.observe(NodeId([1; 32]), addr([10, 0, 0, 1]), 1_000);
+        book.observe(NodeId([2; 32]), addr([10, 0, 0, 2]), 1_000);
+        book.record_dial(&addr([10, 0, 0, 1]), None, 1_000);
+        book.record_dial(&addr([10, 0, 0, 2]), Some(Duration::from_millis(30)), 1_000);
+
+        let pm = PeerManager::new(PeerDiscoveryConfig::new())
+            .with_address_book(Arc::new(Mutex::new(book)));
+        for i in [1u8, 2, 3] {
+            pm.admit_peer(addr([10, 0, 0, i]));
+        }
+        // 3 ist nicht im Adressbuch => ans Ende
+        assert_eq!(
+            pm.dial_order(1_000),
+            vec![addr([10, 0, 0, 2]), addr([10, 0, 0, 1]), addr([10, 0, 0, 3])]
+        );
+    }
+
+    #[test]
+    fn test_panic_while_holding_peer_map_does_not_brick_it() {
+        let pm = Arc::new(PeerManager::new(PeerDiscoveryConfig::new()));
+        assert!(pm.admit_peer(addr([10, 0, 0, 1])));
+
+        let pm2 = pm.clone();
+        let crashed = std::thread::spawn(move || {
+            let mut peers = pm2.peers.lock().unwrap();
+            peers.insert(addr([10, 0, 0, 2]));
+            panic!("Task stirbt mit gehaltenem Lock");
+        })
+        .join();
+        assert!(crashed.is_err());
+        assert!(pm.peers.is_poisoned());
+
+        // Weiterer Zugriff funktioniert und sieht den letzten Zustand
+        assert!(pm.admit_peer(addr([10, 0, 0, 3])));
+        pm.remove_peer(&addr([10, 0, 0, 1]));
+        assert_eq!(pm.dial_order(0), vec![addr([10, 0, 0, 2]), addr([10, 0, 0, 3])]);
+        assert!(!pm.peers.is_poisoned());
+    }
+}
@@ -0,0 +1,299 @@
+//////////////////////////////////////////////////
+/// my_DEX/src/network/port_mapping.rs
+//////////////////////////////////////////////////
+//
+// Portweiterleitung am Heim-Router, damit Nodes ohne manuelle
+// Router-Konfiguration eingehend erreichbar sind.
+//
+//  - UPnP-IGD (igd-Crate): AddPortMapping/DeletePortMapping am Gateway
+//  - NAT-PMP (RFC 6886): eigener kleiner Client, UDP an Gateway:5351
+//
+// `PortForwarder::run` probiert die Mapper der Reihe nach, erneuert die
+// Lease nach der halben Laufzeit und löscht das Mapping beim Abbruch des
+// Tokens. Ist kein Gateway erreichbar, wird nur gewarnt – der Node läuft
+// dann eben ohne eingehende Verbindungen von außen (oder über TURN/Tor).
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket as StdUdpSocket};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use tokio::net::UdpSocket;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+pub const NAT_PMP_PORT: u16 = 5351;
+const MAPPING_DESCRIPTION: &str = "my_dex p2p";
+
+#[async_trait::async_trait]
+pub trait PortMapper: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// Mappt `port` (TCP) extern auf denselben Port; liefert die externe Adresse.
+    async fn add_mapping(&self, port: u16, lease: Duration) -> Result<SocketAddr>;
+    async fn remove_mapping(&self, port: u16) -> Result<()>;
+}
+
+/// Lokale IPv4, über die `gateway` erreicht wird (ohne Pakete zu senden).
+fn local_ipv4_towards(gateway: Ipv4Addr) -> Result<Ipv4Addr> {
+    let sock = StdUdpSocket::bind("0.0.0.0:0")?;
+    sock.connect((gateway, 9))?;
+    match sock.local_addr()?.ip() {
+        IpAddr::V4(v4) => Ok(v4),
+        IpAddr::V6(_) => Err(anyhow!("No IPv4 route to gateway {}", gateway)),
+    }
+}
+
+// ----------------------------------------------------------------------
+// UPnP-IGD
+// ----------------------------------------------------------------------
+
+pub struct UpnpMapper;
+
+#[async_trait::async_trait]
+impl PortMapper for UpnpMapper {
+    fn name(&self) -> &'static str {
+        "upnp"
+    }
+
+    async fn add_mapping(&self, port: u16, lease: Duration) -> Result<SocketAddr> {
+        let gateway = igd::aio::search_gateway(Default::default())
+            .await
+            .map_err(|e| anyhow!("UPnP search_gateway: {:?}", e))?;
+        let gw_ip = match gateway.addr.ip() {
+            IpAddr::V4(v4) => *v4,
+            other => return Err(anyhow!("UPnP gateway {} is not IPv4", other)),
+        };
+        let local = SocketAddrV4::new(local_ipv4_towards(gw_ip)?, port);
+        gateway
+            .add_port(igd::PortMappingProtocol::TCP, port, local, lease.as_secs() as u32, MAPPING_DESCRIPTION)
+            .await
+            .map_err(|e| anyhow!("UPnP add_port: {:?}", e))?;
+        let ext_ip = gateway
+            .get_external_ip()
+            .await
+            .map_err(|e| anyhow!("UPnP get_external_ip: {:?}", e))?;
+        Ok(SocketAddr::new(IpAddr::V4(ext_ip), port))
+    }
+
+    async fn remove_mapping(&self, port: u16) -> Result<()> {
+        let gateway = igd::aio::search_gateway(Default::default())
+            .await
+            .map_err(|e| anyhow!("UPnP search_gateway: {:?}", e))?;
+        gateway
+            .remove_port(igd::PortMappingProtocol::TCP, port)
+            .await
+            .map_err(|e| anyhow!("UPnP remove_port: {:?}", e))
+    }
+}
+
+// ----------------------------------------------------------------------
+// NAT-PMP (RFC 6886)
+// ----------------------------------------------------------------------
+
+pub struct NatPmpMapper {
+    pub gateway: SocketAddr,
+    pub timeout: Duration,
+}
+
+impl NatPmpMapper {
+    pub fn new(gateway: Ipv4Addr) -> Self {
+        Self {
+            gateway: SocketAddr::new(IpAddr::V4(gateway), NAT_PMP_PORT),
+            timeout: Duration::from_millis(750),
+        }
+    }
+
+    /// Ohne bekanntes Gateway: übliche Heimnetz-Annahme x.y.z.1.
+    pub fn guess_gateway() -> Option<Self> {
+        let sock = StdUdpSocket::bind("0.0.0.0:0").ok()?;
+        sock.connect("192.0.2.1:9").ok()?;
+        match sock.local_addr().ok()?.ip() {
+            IpAddr::V4(v4) if v4.is_private() => {
+                let o = v4.octets();
+                Some(Self::new(Ipv4Addr::new(o[0], o[1], o[2], 1)))
+            }
+            _ => None,
+        }
+    }
+
+    async fn request(&self, req: &[u8], expect_op: u8, min_len: usize) -> Result<Vec<u8>> {
+        let sock = UdpSocket::bind("0.0.0.0:0").await?;
+        sock.connect(self.gateway).await?;
+        let mut buf = [0u8; 16];
+        // RFC 6886: Retransmit mit Verdopplung des Timeouts
+        let mut wait = self.timeout;
+        for _ in 0..3 {
+            sock.send(req).await?;
+            if let Ok(Ok(n)) = tokio::time::timeout(wait, sock.recv(&mut buf)).await {
+                if n >= min_len && buf[0] == 0 && buf[1] == expect_op {
+                    let code = u16::from_be_bytes([buf[2], buf[3]]);
+                    if code != 0 {
+                        return Err(anyhow!("NAT-PMP result code {}", code));
+                    }
+                    return Ok(buf[..n].to_vec());
+                }
+            }
+            wait *= 2;
+        }
+        Err(anyhow!("NAT-PMP gateway {} did not answer", self.gateway))
+    }
+
+    async fn external_ip(&self) -> Result<Ipv4Addr> {
+        let resp = self.request(&[0, 0], 128, 12).await?;
+        Ok(Ipv4Addr::new(resp[8], resp[9], resp[10], resp[11]))
+    }
+
+    async fn map(&self, port: u16, lifetime_secs: u32) -> Result<u16> {
+        // Opcode 2 = TCP
+        let mut req = vec![0u8, 2, 0, 0];
+        req.extend_from_slice(&port.to_be_bytes());
+        req.extend_from_slice(&port.to_be_bytes());
+        req.extend_from_slice(&lifetime_secs.to_be_bytes());
+        let resp = self.request(&req, 130, 16).await?;
+        Ok(u16::from_be_bytes([resp[10], resp[11]]))
+    }
+}
+
+#[async_trait::async_trait]
+impl PortMapper for NatPmpMapper {
+    fn name(&self) -> &'static str {
+        "nat-pmp"
+    }
+
+    async fn add_mapping(&self, port: u16, lease: Duration) -> Result<SocketAddr> {
+        let external_port = self.map(port, lease.as_secs() as u32).await?;
+        let ip = self.external_ip().await?;
+        Ok(SocketAddr::new(IpAddr::V4(ip), external_port))
+    }
+
+    async fn remove_mapping(&self, port: u16) -> Result<()> {
+        // Lifetime 0 löscht das Mapping
+        self.map(port, 0).await.map(|_| ())
+    }
+}
+
+// ----------------------------------------------------------------------
+// PortForwarder
+// ----------------------------------------------------------------------
+
+pub struct PortForwarder {
+    pub port: u16,
+    pub lease: Duration,
+    pub mappers: Vec<Box<dyn PortMapper>>,
+}
+
+impl PortForwarder {
+    /// UPnP zuerst, dann NAT-PMP am vermuteten Gateway.
+    pub fn with_defaults(port: u16) -> Self {
+        let mut mappers: Vec<Box<dyn PortMapper>> = vec![Box::new(UpnpMapper)];
+        if let Some(pmp) = NatPmpMapper::guess_gateway() {
+            mappers.push(Box::new(pmp));
+        }
+        Self { port, lease: Duration::from_secs(3600), mappers }
+    }
+
+    /// Erster Mapper, der erfolgreich mappt (Index, externe Adresse).
+    async fn establish(&self) -> Option<(usize, SocketAddr)> {
+        for (i, m) in self.mappers.iter().enumerate() {
+            match m.add_mapping(self.port, self.lease).await {
+                Ok(ext) => return Some((i, ext)),
+                Err(e) => debug!("Portweiterleitung via {} fehlgeschlagen: {:?}", m.name(), e),
+            }
+        }
+        None
+    }
+
+    /// Mapping anlegen, Lease erneuern, beim Abbruch wieder entfernen.
+    pub async fn run(self, token: CancellationToken) {
+        let Some((idx, ext)) = self.establish().await else {
+            warn!("Kein UPnP/NAT-PMP-Gateway gefunden => Port {} nicht weitergeleitet", self.port);
+            return;
+        };
+        let mapper = &self.mappers[idx];
+        info!("Portweiterleitung via {} => {} -> lokal :{}", mapper.name(), ext, self.port);
+        let refresh = self.lease / 2;
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = tokio::time::sleep(refresh) => {
+                    if let Err(e) = mapper.add_mapping(self.port, self.lease).await {
+                        warn!("Lease-Erneuerung via {} fehlgeschlagen: {:?}", mapper.name(), e);
+                    }
+                }
+            }
+        }
+        match mapper.remove_mapping(self.port).await {
+            Ok(()) => info!("Portweiterleitung für {} entfernt", self.port),
+            Err(e) => warn!("Portweiterleitung für {} nicht entfernt: {:?}", self.port, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// NAT-PMP-Gateway auf localhost, protokolliert (Port, Lifetime).
+    async fn spawn_pmp_gateway() -> (SocketAddr, Arc<Mutex<Vec<(u16, u32)>>>) {
+        let sock = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = sock.local_addr().unwrap();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let log2 = log.clone();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 16];
+            while let Ok((n, from)) = sock.recv_from(&mut buf).await {
+                let mut resp = vec![0u8, 128 + buf[1], 0, 0, 0, 0, 0, 1];
+                match (buf[1], n) {
+                    (0, _) => resp.extend_from_slice(&[203, 0, 113, 9]),
+                    (2, 12) => {
+                        let port = u16::from_be_bytes([buf[4], buf[5]]);
+                        let life = u32::from_be_bytes([buf[8], buf[9], buf[10], buf[11]]);
+                        log2.lock().unwrap().push((port, life));
+                        resp.extend_from_slice(&buf[4..6]);
+                        resp.extend_from_slice(&buf[6..8]);
+                        resp.extend_from_slice(&buf[8..12]);
+                    }
+                    _ => continue,
+                }
+                let _ = sock.send_to(&resp, from).await;
+            }
+        });
+        (addr, log)
+    }
+
+    #[tokio::test]
+    async fn test_nat_pmp_mapping_requested_and_released() {
+        let (gw, log) = spawn_pmp_gateway().await;
+        let mapper = NatPmpMapper { gateway: gw, timeout: Duration::from_millis(200) };
+        let forwarder = PortForwarder { port: 40404, lease: Duration::from_secs(120), mappers: vec![Box::new(mapper)] };
+
+        let token = CancellationToken::new();
+        let handle = tokio::spawn(forwarder.run(token.clone()));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        token.cancel();
+        handle.await.unwrap();
+
+        // Anlegen mit Lease, Entfernen mit Lifetime 0
+        assert_eq!(*log.lock().unwrap(), vec![(40404, 120), (40404, 0)]);
+    }
+
+    #[tokio::test]
+    async fn test_no_gateway_falls_back_gracefully() {
+        let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mapper = NatPmpMapper { gateway: silent.local_addr().unwrap(), timeout: Duration::from_millis(20) };
+        let forwarder = PortForwarder { port: 40405, lease: Duration::from_secs(60), mappers: vec![Box::new(mapper)] };
+        // Kehrt ohne Mapping sofort zurück statt zu hängen
+        tokio::time::timeout(Duration::from_secs(2), forwarder.run(CancellationToken::new()))
+            .await
+            .unwrap();
+    }
+
+    /// Gegen das echte Gateway im LAN: `cargo test --features nat-gateway-tests`.
+    #[cfg(feature = "nat-gateway-tests")]
+    #[tokio::test]
+    async fn test_upnp_mapping_on_local_gateway() {
+        let ext = UpnpMapper.add_mapping(40406, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(ext.port(), 40406);
+        UpnpMapper.remove_mapping(40406).await.unwrap();
+    }
+}
@@ -4,29 +4,172 @@
 // fehlende Nachrichten erkennt und gezielt Re-Requests an betroffene Peers sendet.
 // Er nutzt Tokio f�r asynchrone Operationen und log/Env_logger f�r strukturiertes Logging.
 
-use std::collections::HashMap;
+//
+// Anti-Entropy: Broadcasts allein reichen nicht, wenn ein Node kurz offline
+// war – die verpassten Nachrichten kommen nie wieder. Daher hält jeder Node
+// einen begrenzten Speicher der zuletzt gesehenen Nachrichten (per Hash) und
+// schickt periodisch einem zufälligen Peer seinen Digest
+// (KademliaMessage::ReliableGossip); der Empfänger zieht per Pull nach, was
+// ihm fehlt. Jede Nachricht ist vom Absender signiert (sender = Ed25519-Key),
+// nachgeladene Nachrichten werden nur angenommen, wenn wir sie angefragt
+// haben und die Signatur stimmt.
+//
+// Jede Nachricht trägt ihren signierten Sendezeitpunkt (`sent_ms`). Mit
+// ClockSkewGuard werden Live-Broadcasts außerhalb des Fensters (zu weit in
+// der Zukunft oder älter als das Höchstalter) verworfen; per Anti-Entropy
+// nachgeladene Nachrichten dürfen alt sein, aber nicht aus der Zukunft kommen.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc::{Sender, Receiver};
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc::{Sender, Receiver, UnboundedReceiver};
 use tokio::time::sleep;
 use log::{info, warn, error, debug};
 
+use crate::error::DexError;
+use crate::utils::geoip_and_ntp::ClockSkewGuard;
+use crate::utils::hlc::local_physical_ms;
+
+/// Obergrenze des Speichers zuletzt gesehener Nachrichten.
+pub const DEFAULT_RECENT_CAPACITY: usize = 4096;
+
+/// Höchstens so viele Nachrichten pro Pull bzw. Push.
+pub const MAX_PULL: usize = 256;
+
+const GOSSIP_MESSAGE_DOMAIN: &str = "my_dex/reliable_gossip/message/v2";
+
+pub type GossipResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
 /// Repr�sentiert eine Gossip-Nachricht, die vom Knoten im Netzwerk versendet wird.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GossipMessage {
-    /// Identifikation des sendenden Knotens.
+    /// Ed25519-Schlüssel (hex) des sendenden Knotens.
     pub sender: String,
     /// Eindeutige, fortlaufende Sequenznummer zur Erkennung von Nachrichtenl�cken.
     pub seq: u64,
+    /// Sendezeitpunkt (UNIX-ms) laut Absender.
+    pub sent_ms: u64,
     /// Der Nachrichteninhalt (Payload) als Byte-Array.
     pub payload: Vec<u8>,
+    /// Signatur des Absenders über (sender, seq, sent_ms, payload).
+    pub signature: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct GossipMessageSigningView<'a> {
+    sender: &'a str,
+    seq: u64,
+    sent_ms: u64,
+    payload: &'a [u8],
+}
+
+/// Anti-Entropy und Broadcast über das Netz (KademliaMessage::ReliableGossip).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GossipWire {
+    Broadcast(GossipMessage),
+    /// Hashes, die der Absender kennt => Empfänger antwortet mit Pull
+    Digest(Vec<String>),
+    /// Angefragte Hashes => Empfänger antwortet mit Push
+    Pull(Vec<String>),
+    Push(Vec<GossipMessage>),
+}
+
+impl GossipMessage {
+    fn signing_bytes(sender: &str, seq: u64, sent_ms: u64, payload: &[u8]) -> Vec<u8> {
+        crate::utils::canonical::signing_bytes(
+            GOSSIP_MESSAGE_DOMAIN,
+            &GossipMessageSigningView { sender, seq, sent_ms, payload },
+        )
+        .expect("GossipMessage enthält keine Floats")
+    }
+
+    pub fn signed(keypair: &Keypair, seq: u64, sent_ms: u64, payload: Vec<u8>) -> Self {
+        let sender = hex::encode(keypair.public.as_bytes());
+        let signature = keypair.sign(&Self::signing_bytes(&sender, seq, sent_ms, &payload)).to_bytes().to_vec();
+        GossipMessage { sender, seq, sent_ms, payload, signature }
+    }
+
+    /// Prüft die Signatur gegen den Schlüssel in `sender`.
+    pub fn verify(&self) -> Result<(), DexError> {
+        let invalid = || DexError::InvalidSignature(format!("gossip {}#{}", self.sender, self.seq));
+        let key = hex::decode(&self.sender)
+            .ok()
+            .and_then(|b| PublicKey::from_bytes(&b).ok())
+            .ok_or_else(invalid)?;
+        let signature = Signature::from_bytes(&self.signature).map_err(|_| invalid())?;
+        key.verify(&Self::signing_bytes(&self.sender, self.seq, self.sent_ms, &self.payload), &signature)
+            .map_err(|_| invalid())
+    }
+
+    /// Inhalts-Hash (hex) für Digest und Deduplizierung.
+    pub fn hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.sender.as_bytes());
+        hasher.update([0u8]);
+        hasher.update(self.seq.to_be_bytes());
+        hasher.update(&self.payload);
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Begrenzter Speicher der zuletzt gesehenen Nachrichten (FIFO-Verdrängung).
+#[derive(Debug)]
+pub struct RecentMessages {
+    by_hash: HashMap<String, GossipMessage>,
+    order: VecDeque<String>,
+    capacity: usize,
+}
+
+impl RecentMessages {
+    pub fn new(capacity: usize) -> Self {
+        RecentMessages { by_hash: HashMap::new(), order: VecDeque::new(), capacity: capacity.max(1) }
+    }
+
+    /// Liefert false, wenn die Nachricht schon bekannt war.
+    pub fn insert(&mut self, msg: GossipMessage) -> bool {
+        let hash = msg.hash();
+        if self.by_hash.contains_key(&hash) {
+            return false;
+        }
+        while self.order.len() >= self.capacity {
+            if let Some(old) = self.order.pop_front() {
+                self.by_hash.remove(&old);
+            }
+        }
+        self.order.push_back(hash.clone());
+        self.by_hash.insert(hash, msg);
+        true
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.by_hash.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&GossipMessage> {
+        self.by_hash.get(hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_hash.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_hash.is_empty()
+    }
 }
 
 /// Repr�sentiert einen Knoten im Gossip-Netzwerk.
 /// Jeder Knoten speichert seinen lokalen Zustand und verfolgt die zuletzt empfangenen
 /// Sequenznummern von seinen Peers, um fehlende Nachrichten zu erkennen.
 pub struct GossipNode {
-    /// Eindeutige Kennung des Knotens.
+    /// Eindeutige Kennung des Knotens (hex des Ed25519-Schlüssels).
     pub id: String,
+    keypair: Arc<Keypair>,
     /// Lokaler Sequenzz�hler f�r von diesem Knoten gesendete Nachrichten.
     pub local_seq: u64,
     /// HashMap, die f�r jeden Peer die zuletzt empfangene Sequenznummer speichert.
@@ -35,30 +178,124 @@ pub struct GossipNode {
     pub gossip_tx: Sender<GossipMessage>,
     /// Receiver-Kanal, �ber den dieser Knoten Nachrichten aus dem Netzwerk empf�ngt.
     pub gossip_rx: Receiver<GossipMessage>,
+    /// Zuletzt gesehene (eigene und empfangene) Nachrichten für Anti-Entropy.
+    pub recent: RecentMessages,
+    /// Per Pull angefragte Hashes; nur diese nimmt ein Push an.
+    requested: HashSet<String>,
+    /// Zeitfenster für `sent_ms` (None => keine Prüfung).
+    clock_guard: Option<Arc<ClockSkewGuard>>,
 }
 
 impl GossipNode {
     /// Erzeugt einen neuen GossipNode mit gegebener ID und den �bergebenen Kan�len.
-    pub fn new(id: String, gossip_tx: Sender<GossipMessage>, gossip_rx: Receiver<GossipMessage>) -> Self {
+    pub fn new(keypair: Arc<Keypair>, gossip_tx: Sender<GossipMessage>, gossip_rx: Receiver<GossipMessage>) -> Self {
         GossipNode {
-            id,
+            id: hex::encode(keypair.public.as_bytes()),
+            keypair,
             local_seq: 0,
             last_seen: HashMap::new(),
             gossip_tx,
             gossip_rx,
+            recent: RecentMessages::new(DEFAULT_RECENT_CAPACITY),
+            requested: HashSet::new(),
+            clock_guard: None,
+        }
+    }
+
+    /// Prüft `sent_ms` eingehender Nachrichten und stempelt eigene mit der
+    /// NTP-korrigierten Zeit.
+    pub fn with_clock_guard(mut self, guard: Arc<ClockSkewGuard>) -> Self {
+        self.clock_guard = Some(guard);
+        self
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.clock_guard.as_ref().map(|g| g.corrected_now_ms()).unwrap_or_else(local_physical_ms)
+    }
+
+    /// Digest: Hashes aller zuletzt gesehenen Nachrichten.
+    pub fn digest(&self) -> HashSet<String> {
+        self.recent.order.iter().cloned().collect()
+    }
+
+    /// Welche Hashes aus dem Digest eines Peers fehlen lokal? (höchstens MAX_PULL)
+    pub fn missing_from(&self, peer_digest: &HashSet<String>) -> Vec<String> {
+        let mut missing: Vec<String> = peer_digest.iter().filter(|h| !self.recent.contains(h)).cloned().collect();
+        missing.sort();
+        missing.truncate(MAX_PULL);
+        missing
+    }
+
+    /// Beantwortet einen Pull: liefert die angefragten Nachrichten, soweit noch vorhanden.
+    pub fn serve_pull(&self, hashes: &[String]) -> Vec<GossipMessage> {
+        hashes.iter().take(MAX_PULL).filter_map(|h| self.recent.get(h).cloned()).collect()
+    }
+
+    /// Verarbeitet eine Nachricht vom Netz; liefert ggf. die Antwort an den Absender.
+    pub async fn on_wire(&mut self, wire: GossipWire) -> GossipResult<Option<GossipWire>> {
+        match wire {
+            GossipWire::Broadcast(msg) => {
+                self.process_message(msg).await?;
+                Ok(None)
+            }
+            GossipWire::Digest(hashes) => {
+                let wanted = self.missing_from(&hashes.into_iter().collect());
+                if wanted.is_empty() {
+                    return Ok(None);
+                }
+                self.requested.extend(wanted.iter().cloned());
+                Ok(Some(GossipWire::Pull(wanted)))
+            }
+            GossipWire::Pull(hashes) => Ok(Some(GossipWire::Push(self.serve_pull(&hashes)))),
+            GossipWire::Push(msgs) => {
+                let n = self.ingest_recovered(msgs).await?;
+                if n > 0 {
+                    debug!("Anti-Entropy: {} Nachrichten nachgeladen", n);
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Übernimmt per Anti-Entropy nachgeladene Nachrichten. Sequenz-Lücken-
+    /// Erkennung greift hier nicht, die Nachrichten sind ja gerade die Lücke.
+    /// Nicht angefragte oder falsch signierte Nachrichten werden verworfen.
+    async fn ingest_recovered(&mut self, msgs: Vec<GossipMessage>) -> GossipResult<usize> {
+        let mut recovered = 0;
+        for msg in msgs.into_iter().take(MAX_PULL) {
+            if !self.requested.remove(&msg.hash()) {
+                warn!("Node {} => nicht angefragte Nachricht {}#{} verworfen", self.id, msg.sender, msg.seq);
+                continue;
+            }
+            let checked = msg.verify().and_then(|_| match &self.clock_guard {
+                Some(guard) => guard.check_not_future_ms(msg.sent_ms),
+                None => Ok(()),
+            });
+            if let Err(e) = checked {
+                warn!("Node {} => nachgeladene Nachricht verworfen: {}", self.id, e);
+                continue;
+            }
+            if msg.sender == self.id || !self.recent.insert(msg.clone()) {
+                continue;
+            }
+            let last_seq = self.last_seen.entry(msg.sender.clone()).or_insert(0);
+            if msg.seq > *last_seq {
+                *last_seq = msg.seq;
+            }
+            info!("Node {} holt per Anti-Entropy Nachricht {} von {} nach", self.id, msg.seq, msg.sender);
+            self.process_payload(msg.payload).await?;
+            recovered += 1;
         }
+        Ok(recovered)
     }
 
     /// Sendet eine neue Nachricht (Broadcast) ins Netzwerk.
     /// Erh�ht den lokalen Sequenzz�hler und erstellt eine neue GossipMessage.
-    pub async fn broadcast(&mut self, payload: Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn broadcast(&mut self, payload: Vec<u8>) -> GossipResult<()> {
         self.local_seq += 1;
-        let msg = GossipMessage {
-            sender: self.id.clone(),
-            seq: self.local_seq,
-            payload,
-        };
+        let msg = GossipMessage::signed(&self.keypair, self.local_seq, self.now_ms(), payload);
 
+        self.recent.insert(msg.clone());
         debug!("Node {} broadcastet Nachricht mit seq {}", self.id, self.local_seq);
         // Sende die Nachricht �ber den asynchronen Kanal.
         self.gossip_tx.send(msg).await?;
@@ -78,11 +315,20 @@ impl GossipNode {
     /// Verarbeitet eine empfangene Gossip-Nachricht.
     /// - �berpr�ft, ob die Sequenznummern l�ckenhaft sind.
 /// - Fordert bei L�cken gezielt fehlende Nachrichten an.
-    async fn process_message(&mut self, msg: GossipMessage) -> Result<(), Box<dyn std::error::Error>> {
+    async fn process_message(&mut self, msg: GossipMessage) -> GossipResult<()> {
         // Ignoriere Nachrichten, die von diesem Knoten selbst gesendet wurden.
         if msg.sender == self.id {
             return Ok(());
         }
+        msg.verify()?;
+        if let Some(guard) = &self.clock_guard {
+            guard.check_timestamp_ms(msg.sent_ms)?;
+        }
+
+        // Schon per Anti-Entropy (oder doppelt) erhalten
+        if !self.recent.insert(msg.clone()) {
+            return Ok(());
+        }
 
         // Hole die zuletzt empfangene Sequenznummer des Absenders oder initialisiere sie mit 0.
         let last_seq = self.last_seen.entry(msg.sender.clone()).or_insert(0);
@@ -113,7 +359,7 @@ impl GossipNode {
 
     /// Fordert eine fehlende Nachricht von einem Peer an.
     /// In einer echten Produktion w�rden hier Netzwerkprotokolle eingesetzt, um den Peer direkt zu kontaktieren.
-    async fn request_missing(&self, sender: &String, missing_seq: u64) -> Result<(), Box<dyn std::error::Error>> {
+    async fn request_missing(&self, sender: &String, missing_seq: u64) -> GossipResult<()> {
         // Produktion: Hier k�nnte ein Netzwerkrequest (z. B. �ber TCP/UDP) implementiert werden.
         info!("Node {} fordert fehlende Nachricht {} von {}", self.id, missing_seq, sender);
         // Simuliere eine Netzwerkverz�gerung f�r den Re-Request.
@@ -124,7 +370,7 @@ impl GossipNode {
 
     /// Verarbeitet die Payload der empfangenen Nachricht.
     /// Hier wird der globale Zustand (z. B. Orderbuch) aktualisiert.
-    async fn process_payload(&self, payload: Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
+    async fn process_payload(&self, payload: Vec<u8>) -> GossipResult<()> {
         let payload_str = String::from_utf8(payload.clone()).unwrap_or_else(|_| "<ung�ltige UTF-8>".to_string());
         info!("Node {} verarbeitet Payload: {}", self.id, payload_str);
         // Hier wird die Logik zur Zustandsaktualisierung implementiert.
@@ -132,6 +378,51 @@ impl GossipNode {
     }
 }
 
+impl GossipNode {
+    /// Digest als Wire-Nachricht (sortiert, deterministisch).
+    pub fn digest_wire(&self) -> GossipWire {
+        let mut hashes: Vec<String> = self.recent.order.iter().cloned().collect();
+        hashes.sort();
+        GossipWire::Digest(hashes)
+    }
+
+    /// Hauptschleife am Netz: verarbeitet eingehende Gossip-Nachrichten aus
+    /// `inbox` (Antworten gehen über `send` an den Absender zurück) und
+    /// schickt alle `interval` einem zufälligen Peer aus `peers()` den Digest.
+    pub async fn run_anti_entropy<S, P>(
+        mut self,
+        mut inbox: UnboundedReceiver<(SocketAddr, GossipWire)>,
+        send: S,
+        peers: P,
+        interval: Duration,
+    ) where
+        S: Fn(SocketAddr, GossipWire),
+        P: Fn() -> Vec<SocketAddr>,
+    {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                Some(msg) = self.gossip_rx.recv() => {
+                    if let Err(e) = self.process_message(msg).await {
+                        error!("Fehler bei der Verarbeitung der Nachricht: {}", e);
+                    }
+                }
+                Some((from, wire)) = inbox.recv() => match self.on_wire(wire).await {
+                    Ok(Some(reply)) => send(from, reply),
+                    Ok(None) => {}
+                    Err(e) => warn!("Gossip von {} verworfen: {}", from, e),
+                },
+                _ = ticker.tick() => {
+                    let peer = peers().choose(&mut rand::thread_rng()).copied();
+                    if let Some(peer) = peer {
+                        send(peer, self.digest_wire());
+                    }
+                }
+            }
+        }
+    }
+}
+
 //
 // Beispiel: Simulation eines Gossip-Netzwerks mit zwei Nodes.
 //
@@ -143,6 +434,10 @@ mod tests {
     use super::*;
     use tokio::sync::mpsc;
 
+    fn key() -> Arc<Keypair> {
+        Arc::new(Keypair::generate(&mut rand_07::rngs::OsRng))
+    }
+
     #[tokio::test]
     async fn test_reliable_gossip() {
         // Initialisiere das Logging (f�r Tests wird env_logger so konfiguriert, dass die Ausgabe in den Test-Logs erscheint).
@@ -154,8 +449,8 @@ mod tests {
 
         // In dieser Simulation sind die Kan�le kreuzverkn�pft:
         // NodeA sendet �ber tx_a, empf�ngt �ber rx_b, und NodeB sendet �ber tx_b, empf�ngt �ber rx_a.
-        let mut node_a = GossipNode::new("NodeA".to_string(), tx_a.clone(), rx_b);
-        let mut node_b = GossipNode::new("NodeB".to_string(), tx_b.clone(), rx_a);
+        let mut node_a = GossipNode::new(key(), tx_a.clone(), rx_b);
+        let mut node_b = GossipNode::new(key(), tx_b.clone(), rx_a);
 
         // Starte asynchrone Tasks f�r beide Nodes.
         let handle_a = tokio::spawn(async move {
@@ -175,4 +470,88 @@ mod tests {
         // Warte, bis beide Tasks abgeschlossen sind.
         let _ = tokio::join!(handle_a, handle_b);
     }
+
+    /// Node ohne Netz; der zurückgegebene Receiver hält den Broadcast-Kanal offen.
+    fn node() -> (GossipNode, mpsc::Receiver<GossipMessage>) {
+        let (tx, out) = mpsc::channel(100);
+        let (_, rx) = mpsc::channel(1);
+        (GossipNode::new(key(), tx, rx), out)
+    }
+
+    #[tokio::test]
+    async fn test_missed_broadcast_recovered_via_anti_entropy() {
+        let (mut a, _a_out) = node();
+        let (mut b, _b_out) = node();
+        a.broadcast(b"m1".to_vec()).await.unwrap();
+        a.broadcast(b"m2".to_vec()).await.unwrap();
+        a.broadcast(b"m3".to_vec()).await.unwrap();
+
+        // NodeB war bei m2 offline: bekommt m1 und m3, m2 fehlt endgültig
+        let sent: Vec<GossipMessage> = a.recent.order.iter().map(|h| a.recent.get(h).unwrap().clone()).collect();
+        b.on_wire(GossipWire::Broadcast(sent[0].clone())).await.unwrap();
+        b.on_wire(GossipWire::Broadcast(sent[2].clone())).await.unwrap();
+        assert_eq!(b.recent.len(), 2);
+
+        // Digest (A => B) => Pull (B => A) => Push (A => B), alles als Wire-Nachricht
+        let wire = |w: &GossipWire| bincode::deserialize::<GossipWire>(&bincode::serialize(w).unwrap()).unwrap();
+        let pull = b.on_wire(wire(&a.digest_wire())).await.unwrap().expect("Pull");
+        assert!(matches!(&pull, GossipWire::Pull(h) if h == &vec![sent[1].hash()]));
+        let push = a.on_wire(wire(&pull)).await.unwrap().expect("Push");
+        assert!(b.on_wire(wire(&push)).await.unwrap().is_none());
+        assert!(b.recent.contains(&sent[1].hash()));
+        assert_eq!(b.digest(), a.digest());
+
+        // Zweite Runde: nichts mehr zu holen, kein Duplikat
+        assert!(b.on_wire(a.digest_wire()).await.unwrap().is_none());
+        assert!(b.on_wire(push).await.unwrap().is_none());
+        assert_eq!(b.recent.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_recovered_messages_are_verified() {
+        let (mut a, _a_out) = node();
+        let (mut b, _b_out) = node();
+        a.broadcast(b"m1".to_vec()).await.unwrap();
+        let genuine = a.recent.get(&a.recent.order[0]).unwrap().clone();
+
+        // Gefälschter Inhalt unter A's Signatur: angefragt, aber Signatur passt nicht
+        let mut forged = genuine.clone();
+        forged.payload = b"evil".to_vec();
+        b.on_wire(GossipWire::Digest(vec![forged.hash()])).await.unwrap().expect("Pull");
+        b.on_wire(GossipWire::Push(vec![forged.clone()])).await.unwrap();
+        assert!(!b.recent.contains(&forged.hash()));
+
+        // Korrekt signiert, aber nie angefragt
+        b.on_wire(GossipWire::Push(vec![genuine.clone()])).await.unwrap();
+        assert!(b.recent.is_empty());
+
+        // Live-Broadcast mit falscher Signatur wird abgelehnt
+        assert!(b.on_wire(GossipWire::Broadcast(forged)).await.is_err());
+        assert!(b.recent.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sent_time_window_for_broadcast_and_recovery() {
+        let (mut b, _b_out) = node();
+        let guard = Arc::new(ClockSkewGuard::new(5_000).with_max_age_ms(60_000));
+        b = b.with_clock_guard(guard.clone());
+        let kp = key();
+        let now = guard.corrected_now_ms();
+
+        // Verzögert, aber im Fenster => angenommen
+        let delayed = GossipMessage::signed(&kp, 1, now - 30_000, b"late".to_vec());
+        b.on_wire(GossipWire::Broadcast(delayed)).await.unwrap();
+        // Aus der Zukunft oder zu alt => abgelehnt
+        let future = GossipMessage::signed(&kp, 2, now + 3_600_000, b"future".to_vec());
+        assert!(b.on_wire(GossipWire::Broadcast(future.clone())).await.is_err());
+        let stale = GossipMessage::signed(&kp, 3, now - 3_600_000, b"stale".to_vec());
+        assert!(b.on_wire(GossipWire::Broadcast(stale.clone())).await.is_err());
+        assert_eq!(b.recent.len(), 1);
+
+        // Per Anti-Entropy: alt ist ok, Zukunft nicht
+        b.on_wire(GossipWire::Digest(vec![stale.hash(), future.hash()])).await.unwrap().expect("Pull");
+        b.on_wire(GossipWire::Push(vec![stale.clone(), future.clone()])).await.unwrap();
+        assert!(b.recent.contains(&stale.hash()));
+        assert!(!b.recent.contains(&future.hash()));
+    }
 }
@@ -4,13 +4,15 @@
 
 use tokio::net::TcpStream;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use snow::{Builder, params::NoiseParams, Session};
+use snow::{Builder, params::NoiseParams};
 use bytes::{Bytes, BytesMut};
 use std::sync::{Arc, Mutex};
+use crate::network::noise::{NoiseTransport, RekeyPolicy};
 use crate::network::security_monitor::SecurityMonitor; // ? Verbindung mit Security Monitor
 
 pub struct SecureChannel {
-    session: Arc<Mutex<Session>>,
+    /// Rotiert die Schlüssel selbst (siehe `RekeyPolicy`)
+    session: Arc<Mutex<NoiseTransport>>,
     stream: TcpStream,
     monitor: Arc<SecurityMonitor>, // ? Security Monitoring direkt integriert
 }
@@ -33,7 +35,7 @@ impl SecureChannel {
 
         println!("?? Secure Channel aufgebaut mit {}", addr);
         Ok(SecureChannel {
-            session: Arc::new(Mutex::new(session)),
+            session: Arc::new(Mutex::new(NoiseTransport::from_handshake(session, RekeyPolicy::default())?)),
             stream,
             monitor,
         })
@@ -57,7 +59,7 @@ impl SecureChannel {
 
         println!("? Secure Channel akzeptiert Verbindung.");
         Ok(SecureChannel {
-            session: Arc::new(Mutex::new(session)),
+            session: Arc::new(Mutex::new(NoiseTransport::from_handshake(session, RekeyPolicy::default())?)),
             stream,
             monitor,
         })
@@ -66,16 +68,17 @@ impl SecureChannel {
     pub async fn send(&mut self, plaintext: &[u8]) -> Result<()> {
         let nonce = SecurityMonitor::generate_nonce();
         let mut buf = BytesMut::with_capacity(plaintext.len() + 16 + 8);
-        buf[..8].copy_from_slice(&nonce.to_le_bytes());
+        buf.extend_from_slice(&nonce.to_le_bytes());
 
-        let len = self.session.lock().unwrap()
-            .write_message(plaintext, &mut buf[8..])
+        let ciphertext = self.session.lock().unwrap()
+            .encrypt(plaintext)
             .map_err(|e| {
                 self.monitor.log_event("? Fehler bei der Verschl�sselung!");
                 anyhow!("?? Verschl�sselung fehlgeschlagen: {:?}", e)
             })?;
+        buf.extend_from_slice(&ciphertext);
 
-        self.stream.write_all(&buf[..len + 8]).await?;
+        self.stream.write_all(&buf).await?;
         self.monitor.log_event(&format!("?? Nachricht gesendet mit Nonce: {}", nonce));
         Ok(())
     }
@@ -95,15 +98,14 @@ impl SecureChannel {
             return Err(anyhow!("? Replay-Angriff erkannt! Nachricht blockiert."));
         }
 
-        let mut out = BytesMut::with_capacity(1024);
-        let len = self.session.lock().unwrap()
-            .read_message(&buf[8..n], &mut out)
+        let plaintext = self.session.lock().unwrap()
+            .decrypt(&buf[8..n])
             .map_err(|e| {
                 self.monitor.log_event("? Fehler bei der Entschl�sselung!");
                 anyhow!("?? Entschl�sselung fehlgeschlagen: {:?}", e)
             })?;
 
-        out.truncate(len);
+        let out = BytesMut::from(&plaintext[..]);
         self.monitor.log_event(&format!("?? Empfangene Nachricht (Nonce {}): {:?}", nonce, out));
         Ok(out.freeze())
     }
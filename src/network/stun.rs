@@ -0,0 +1,304 @@
+//////////////////////////////////////////////////
+/// my_DEX/src/network/stun.rs
+//////////////////////////////////////////////////
+//
+// Minimaler STUN-Client (RFC 5389) zur Ermittlung der externen Adresse.
+//
+// Ablauf: Binding-Request per UDP an den Server, Antwort mit passender
+// Transaction-ID abwarten, XOR-MAPPED-ADDRESS (Fallback: MAPPED-ADDRESS)
+// auslesen. Bei mehreren Servern wird der Reihe nach probiert, jeder mit
+// einigen Retransmits, bis einer antwortet.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use rand::RngCore;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+use tracing::{debug, warn};
+
+pub const MAGIC_COOKIE: u32 = 0x2112_A442;
+pub const BINDING_REQUEST: u16 = 0x0001;
+pub const BINDING_SUCCESS: u16 = 0x0101;
+pub const ATTR_MAPPED_ADDRESS: u16 = 0x0001;
+pub const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+const HEADER_LEN: usize = 20;
+/// Retransmits pro Server (RFC 5389 empfiehlt mehr, für Peer-Discovery reicht das)
+const ATTEMPTS_PER_SERVER: u32 = 3;
+
+pub type TransactionId = [u8; 12];
+
+pub fn new_transaction_id() -> TransactionId {
+    let mut id = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut id);
+    id
+}
+
+/// STUN-Header + Attribute (bereits kodiert) zusammensetzen.
+pub fn encode_message(msg_type: u16, txid: &TransactionId, attrs: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + attrs.len());
+    out.extend_from_slice(&msg_type.to_be_bytes());
+    out.extend_from_slice(&(attrs.len() as u16).to_be_bytes());
+    out.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+    out.extend_from_slice(txid);
+    out.extend_from_slice(attrs);
+    out
+}
+
+/// Ein Attribut (Typ, Länge, Wert, auf 4 Byte aufgefüllt) anhängen.
+pub fn push_attr(buf: &mut Vec<u8>, attr_type: u16, value: &[u8]) {
+    buf.extend_from_slice(&attr_type.to_be_bytes());
+    buf.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buf.extend_from_slice(value);
+    while buf.len() % 4 != 0 {
+        buf.push(0);
+    }
+}
+
+pub fn binding_request(txid: &TransactionId) -> Vec<u8> {
+    encode_message(BINDING_REQUEST, txid, &[])
+}
+
+/// Dekodierte Nachricht: Typ, Transaction-ID, Attribute (Typ, Wert).
+#[derive(Debug, Clone)]
+pub struct StunMessage {
+    pub msg_type: u16,
+    pub txid: TransactionId,
+    pub attrs: Vec<(u16, Vec<u8>)>,
+}
+
+impl StunMessage {
+    pub fn attr(&self, attr_type: u16) -> Option<&[u8]> {
+        self.attrs.iter().find(|(t, _)| *t == attr_type).map(|(_, v)| v.as_slice())
+    }
+}
+
+pub fn decode_message(buf: &[u8]) -> Result<StunMessage> {
+    if buf.len() < HEADER_LEN {
+        return Err(anyhow!("STUN message too short ({} bytes)", buf.len()));
+    }
+    let msg_type = u16::from_be_bytes([buf[0], buf[1]]);
+    let len = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+    let cookie = u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]);
+    if cookie != MAGIC_COOKIE {
+        return Err(anyhow!("Bad STUN magic cookie {:#x}", cookie));
+    }
+    if buf.len() < HEADER_LEN + len {
+        return Err(anyhow!("Truncated STUN message"));
+    }
+    let mut txid = [0u8; 12];
+    txid.copy_from_slice(&buf[8..20]);
+
+    let mut attrs = Vec::new();
+    let body = &buf[HEADER_LEN..HEADER_LEN + len];
+    let mut pos = 0;
+    while pos + 4 <= body.len() {
+        let t = u16::from_be_bytes([body[pos], body[pos + 1]]);
+        let l = u16::from_be_bytes([body[pos + 2], body[pos + 3]]) as usize;
+        let start = pos + 4;
+        if start + l > body.len() {
+            return Err(anyhow!("Truncated STUN attribute {:#x}", t));
+        }
+        attrs.push((t, body[start..start + l].to_vec()));
+        pos = start + ((l + 3) & !3);
+    }
+    Ok(StunMessage { msg_type, txid, attrs })
+}
+
+/// XOR-MAPPED-ADDRESS-Wert für `addr` (Port/IP mit Cookie bzw. Cookie+TxID verknüpft).
+pub fn encode_xor_address(addr: &SocketAddr, txid: &TransactionId) -> Vec<u8> {
+    let cookie = MAGIC_COOKIE.to_be_bytes();
+    let mut out = vec![0u8];
+    let xport = addr.port() ^ (MAGIC_COOKIE >> 16) as u16;
+    match addr.ip() {
+        IpAddr::V4(v4) => {
+            out.push(0x01);
+            out.extend_from_slice(&xport.to_be_bytes());
+            for (i, b) in v4.octets().iter().enumerate() {
+                out.push(b ^ cookie[i]);
+            }
+        }
+        IpAddr::V6(v6) => {
+            out.push(0x02);
+            out.extend_from_slice(&xport.to_be_bytes());
+            let mut key = [0u8; 16];
+            key[..4].copy_from_slice(&cookie);
+            key[4..].copy_from_slice(txid);
+            for (i, b) in v6.octets().iter().enumerate() {
+                out.push(b ^ key[i]);
+            }
+        }
+    }
+    out
+}
+
+pub fn decode_xor_address(value: &[u8], txid: &TransactionId) -> Result<SocketAddr> {
+    if value.len() < 4 {
+        return Err(anyhow!("XOR-MAPPED-ADDRESS too short"));
+    }
+    let cookie = MAGIC_COOKIE.to_be_bytes();
+    let port = u16::from_be_bytes([value[2], value[3]]) ^ (MAGIC_COOKIE >> 16) as u16;
+    match value[1] {
+        0x01 if value.len() >= 8 => {
+            let o: Vec<u8> = (0..4).map(|i| value[4 + i] ^ cookie[i]).collect();
+            Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(o[0], o[1], o[2], o[3])), port))
+        }
+        0x02 if value.len() >= 20 => {
+            let mut key = [0u8; 16];
+            key[..4].copy_from_slice(&cookie);
+            key[4..].copy_from_slice(txid);
+            let mut o = [0u8; 16];
+            for i in 0..16 {
+                o[i] = value[4 + i] ^ key[i];
+            }
+            Ok(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(o)), port))
+        }
+        fam => Err(anyhow!("Unknown address family {:#x}", fam)),
+    }
+}
+
+fn decode_plain_address(value: &[u8]) -> Result<SocketAddr> {
+    if value.len() < 8 {
+        return Err(anyhow!("MAPPED-ADDRESS too short"));
+    }
+    let port = u16::from_be_bytes([value[2], value[3]]);
+    match value[1] {
+        0x01 => Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(value[4], value[5], value[6], value[7])), port)),
+        0x02 if value.len() >= 20 => {
+            let mut o = [0u8; 16];
+            o.copy_from_slice(&value[4..20]);
+            Ok(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(o)), port))
+        }
+        fam => Err(anyhow!("Unknown address family {:#x}", fam)),
+    }
+}
+
+/// Gemappte Adresse aus einer Binding-Success-Antwort.
+pub fn mapped_address(msg: &StunMessage) -> Result<SocketAddr> {
+    if msg.msg_type != BINDING_SUCCESS {
+        return Err(anyhow!("Unexpected STUN response type {:#06x}", msg.msg_type));
+    }
+    if let Some(v) = msg.attr(ATTR_XOR_MAPPED_ADDRESS) {
+        return decode_xor_address(v, &msg.txid);
+    }
+    if let Some(v) = msg.attr(ATTR_MAPPED_ADDRESS) {
+        return decode_plain_address(v);
+    }
+    Err(anyhow!("STUN response without mapped address"))
+}
+
+/// Ein Binding-Request über `socket` an `server`.
+pub async fn binding_on(socket: &UdpSocket, server: &str, per_try: Duration) -> Result<SocketAddr> {
+    let server_addr = tokio::net::lookup_host(server)
+        .await?
+        .next()
+        .ok_or_else(|| anyhow!("STUN server {} did not resolve", server))?;
+    let txid = new_transaction_id();
+    let req = binding_request(&txid);
+    let mut buf = [0u8; 1024];
+    for attempt in 0..ATTEMPTS_PER_SERVER {
+        socket.send_to(&req, server_addr).await?;
+        let deadline = tokio::time::Instant::now() + per_try;
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            match timeout(remaining, socket.recv_from(&mut buf)).await {
+                Ok(Ok((n, from))) => {
+                    if from != server_addr {
+                        continue;
+                    }
+                    match decode_message(&buf[..n]) {
+                        Ok(msg) if msg.txid == txid => return mapped_address(&msg),
+                        Ok(_) => continue, // alte/fremde Transaktion
+                        Err(e) => debug!("STUN {} => ungültige Antwort: {:?}", server, e),
+                    }
+                }
+                Ok(Err(e)) => return Err(anyhow!("STUN recv from {}: {:?}", server, e)),
+                Err(_) => {
+                    debug!("STUN {} => Timeout (Versuch {})", server, attempt + 1);
+                    break;
+                }
+            }
+        }
+    }
+    Err(anyhow!("STUN server {} did not answer", server))
+}
+
+/// Externe Adresse über den ersten antwortenden Server. `local_port` = 0
+/// wählt einen beliebigen Port; mit dem Port des P2P-Listeners bleibt bei
+/// port-erhaltenden NATs auch der externe Port gleich.
+pub async fn discover_external_addr(servers: &[String], local_port: u16, per_try: Duration) -> Result<SocketAddr> {
+    if servers.is_empty() {
+        return Err(anyhow!("No STUN servers configured"));
+    }
+    let socket = match UdpSocket::bind(("0.0.0.0", local_port)).await {
+        Ok(s) => s,
+        Err(_) => UdpSocket::bind("0.0.0.0:0").await?,
+    };
+    let mut last_err = None;
+    for server in servers {
+        match binding_on(&socket, server, per_try).await {
+            Ok(addr) => {
+                debug!("STUN {} => external {}", server, addr);
+                return Ok(addr);
+            }
+            Err(e) => {
+                warn!("STUN {} fehlgeschlagen => {:?}, nächster Server", server, e);
+                last_err = Some(e);
+            }
+        }
+    }
+    Err(last_err.unwrap_or_else(|| anyhow!("All STUN servers failed")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lokaler Responder: beantwortet jeden Binding-Request mit der
+    /// Absenderadresse als XOR-MAPPED-ADDRESS.
+    async fn spawn_responder() -> SocketAddr {
+        let sock = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = sock.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            while let Ok((n, from)) = sock.recv_from(&mut buf).await {
+                let Ok(req) = decode_message(&buf[..n]) else { continue };
+                if req.msg_type != BINDING_REQUEST {
+                    continue;
+                }
+                let mut attrs = Vec::new();
+                push_attr(&mut attrs, ATTR_XOR_MAPPED_ADDRESS, &encode_xor_address(&from, &req.txid));
+                let _ = sock.send_to(&encode_message(BINDING_SUCCESS, &req.txid, &attrs), from).await;
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn test_xor_address_roundtrip() {
+        let txid = new_transaction_id();
+        for a in ["198.51.100.4:40000", "[2001:db8::17]:9000"] {
+            let addr: SocketAddr = a.parse().unwrap();
+            assert_eq!(decode_xor_address(&encode_xor_address(&addr, &txid), &txid).unwrap(), addr);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_binding_against_local_responder() {
+        let server = spawn_responder().await;
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mapped = binding_on(&socket, &server.to_string(), Duration::from_millis(500)).await.unwrap();
+        assert_eq!(mapped, socket.local_addr().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_failover_to_second_server() {
+        // Erster Server schweigt
+        let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let good = spawn_responder().await;
+        let servers = vec![silent.local_addr().unwrap().to_string(), good.to_string()];
+        let addr = discover_external_addr(&servers, 0, Duration::from_millis(100)).await.unwrap();
+        assert_eq!(addr.ip(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+    }
+}
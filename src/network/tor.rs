@@ -0,0 +1,413 @@
+//////////////////////////////////////////////////
+/// my_DEX/src/network/tor.rs
+//////////////////////////////////////////////////
+//
+// Tor-Transport über den eingebetteten arti-Client.
+//
+//  - Ausgehend: Peers mit `.onion`-Adresse werden über Tor angewählt.
+//  - Eingehend (optional): der Node veröffentlicht einen eigenen Onion-Service
+//    und leitet jeden Stream an seinen lokalen P2P-Listener weiter; damit ist
+//    er ohne öffentliche IP erreichbar.
+//
+// Routing-Tabelle und `send_kademlia_msg` arbeiten mit `SocketAddr`. Eine
+// v3-Onion-Adresse passt da nicht hinein, daher bekommt jede bekannte Onion
+// eine virtuelle IPv6 aus fd87:d87e:eb43::/48 (dem OnionCat-Präfix, wie bei
+// Bitcoin). Der `OnionRegistry` löst diese wieder zur Onion auf; Adapter
+// prüfen mit `is_onion_virtual`, ob ein Ziel über Tor geroutet werden muss.
+//
+// Eingehende Onion-Streams sind anonym. Sie gehen nicht über den lokalen
+// TCP-Listener (dort sähen alle wie 127.0.0.1 aus), sondern direkt an den
+// Adapter, je Stream mit eigener Adresse aus fd87:d87e:eb43:ffff::/64. Das
+// Subnetz-Limit fasst so alle eingehenden Onion-Peers zu einer Gruppe
+// zusammen, getrennt von Localhost und von ausgehenden Onions; einzelne
+// Peers unterscheidet danach die Noise-Identität (MessageGuard).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use arti_client::config::TorClientConfigBuilder;
+use arti_client::{DataStream, TorClient, TorClientConfig};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+use tor_cell::relaycell::msg::Connected;
+use tor_hsservice::config::OnionServiceConfigBuilder;
+use tor_hsservice::{HsNickname, RunningOnionService};
+use tor_rtcompat::PreferredRuntime;
+use tracing::{debug, info, warn};
+
+use crate::kademlia::kademlia_service::NodeId;
+
+/// OnionCat-Präfix für virtuelle Adressen.
+const ONION_PREFIX: [u16; 3] = [0xfd87, 0xd87e, 0xeb43];
+/// Viertes Segment eingehender Onion-Streams; ausgehende Onions nutzen es nie.
+const INBOUND_SEGMENT: u16 = 0xffff;
+/// Noch nicht vom Adapter abgeholte eingehende Streams; darüber wird verworfen.
+const ONION_INBOUND_BACKLOG: usize = 64;
+
+/// Eingehender Onion-Stream samt virtueller Absenderadresse.
+pub type OnionInbound = mpsc::Receiver<(SocketAddr, DataStream)>;
+
+/// `tor` in der Node-Config.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TorSettings {
+    pub enabled: bool,
+    /// Schlüssel + Cache des Tor-Clients; leer => flüchtig (neue Onion je Start)
+    pub state_dir: String,
+    /// Nickname des eigenen Onion-Service; leer => keiner
+    pub onion_service: String,
+    /// Onion-Peers: `hex-node-id@host.onion:port`
+    pub bootstrap: Vec<String>,
+}
+
+impl TorSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled && (!self.onion_service.is_empty() || !self.bootstrap.is_empty()) {
+            return Err("onion_service and bootstrap need enabled = true".into());
+        }
+        if !self.onion_service.is_empty() {
+            self.onion_service
+                .parse::<HsNickname>()
+                .map_err(|e| format!("onion_service `{}`: {}", self.onion_service, e))?;
+        }
+        for entry in &self.bootstrap {
+            parse_onion_bootstrap_peer(entry)?;
+        }
+        Ok(())
+    }
+
+    pub fn state_dir(&self) -> Option<PathBuf> {
+        (!self.state_dir.is_empty()).then(|| PathBuf::from(&self.state_dir))
+    }
+}
+
+/// Wie `address_book::parse_bootstrap_peer`, nur mit Onion-Adresse.
+pub fn parse_onion_bootstrap_peer(entry: &str) -> Result<(NodeId, OnionAddr), String> {
+    let (id_hex, addr) = entry
+        .split_once('@')
+        .ok_or_else(|| format!("`{}` is not of the form node_id@host.onion:port", entry))?;
+    let bytes = hex::decode(id_hex.trim()).map_err(|e| format!("`{}`: node_id is not hex: {}", entry, e))?;
+    let id: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| format!("`{}`: node_id must be 32 bytes", entry))?;
+    let onion = OnionAddr::parse(addr.trim()).map_err(|e| format!("`{}`: {}", entry, e))?;
+    Ok((NodeId(id), onion))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OnionAddr {
+    /// z. B. "abcd...xyz.onion"
+    pub host: String,
+    pub port: u16,
+}
+
+impl OnionAddr {
+    /// Parst "host.onion:port".
+    pub fn parse(s: &str) -> Result<Self> {
+        let (host, port) = s
+            .rsplit_once(':')
+            .ok_or_else(|| anyhow!("Onion address without port: {}", s))?;
+        if !host.ends_with(".onion") {
+            return Err(anyhow!("Not an onion address: {}", s));
+        }
+        let port = port.parse().map_err(|_| anyhow!("Invalid port in {}", s))?;
+        Ok(Self { host: host.to_ascii_lowercase(), port })
+    }
+
+    /// Virtuelle SocketAddr für Routing-Tabelle / Adapter.
+    pub fn virtual_addr(&self) -> SocketAddr {
+        let digest = Sha256::digest(self.host.as_bytes());
+        let mut seg = [0u16; 8];
+        seg[..3].copy_from_slice(&ONION_PREFIX);
+        for i in 0..5 {
+            seg[3 + i] = u16::from_be_bytes([digest[2 * i], digest[2 * i + 1]]);
+        }
+        seg[3] = seg[3].min(INBOUND_SEGMENT - 1);
+        SocketAddr::new(
+            IpAddr::V6(Ipv6Addr::new(seg[0], seg[1], seg[2], seg[3], seg[4], seg[5], seg[6], seg[7])),
+            self.port,
+        )
+    }
+}
+
+impl fmt::Display for OnionAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+/// Liegt `addr` im virtuellen Onion-Bereich?
+pub fn is_onion_virtual(addr: &SocketAddr) -> bool {
+    match addr.ip() {
+        IpAddr::V6(v6) => v6.segments()[..3] == ONION_PREFIX,
+        IpAddr::V4(_) => false,
+    }
+}
+
+/// Eingehender Onion-Stream (anonym, nicht zurück anwählbar)?
+pub fn is_onion_inbound(addr: &SocketAddr) -> bool {
+    match addr.ip() {
+        IpAddr::V6(v6) => is_onion_virtual(addr) && v6.segments()[3] == INBOUND_SEGMENT,
+        IpAddr::V4(_) => false,
+    }
+}
+
+/// Adresse des `n`-ten eingehenden Onion-Streams.
+fn inbound_virtual_addr(n: u64, port: u16) -> SocketAddr {
+    let [a, b, c, d] = [(n >> 48) as u16, (n >> 32) as u16, (n >> 16) as u16, n as u16];
+    let [p0, p1, p2] = ONION_PREFIX;
+    SocketAddr::new(IpAddr::V6(Ipv6Addr::new(p0, p1, p2, INBOUND_SEGMENT, a, b, c, d)), port)
+}
+
+/// Virtuelle Adresse (ohne Port) -> Onion-Host.
+#[derive(Debug, Default)]
+pub struct OnionRegistry {
+    hosts: Mutex<HashMap<IpAddr, String>>,
+}
+
+impl OnionRegistry {
+    pub fn register(&self, onion: &OnionAddr) -> SocketAddr {
+        let addr = onion.virtual_addr();
+        self.hosts.lock().unwrap().insert(addr.ip(), onion.host.clone());
+        addr
+    }
+
+    pub fn resolve(&self, addr: &SocketAddr) -> Option<OnionAddr> {
+        if !is_onion_virtual(addr) {
+            return None;
+        }
+        self.hosts
+            .lock()
+            .unwrap()
+            .get(&addr.ip())
+            .map(|host| OnionAddr { host: host.clone(), port: addr.port() })
+    }
+}
+
+pub struct TorTransport {
+    client: TorClient<PreferredRuntime>,
+    pub registry: OnionRegistry,
+    onion_service: Mutex<Option<Arc<RunningOnionService>>>,
+    /// Eingehende Streams des Onion-Service, bis der Adapter sie abholt
+    inbound: Mutex<Option<OnionInbound>>,
+    next_inbound: Arc<AtomicU64>,
+}
+
+impl fmt::Debug for TorTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TorTransport")
+            .field("registry", &self.registry)
+            .field("onion_service", &self.onion_service.lock().unwrap().is_some())
+            .finish()
+    }
+}
+
+impl TorTransport {
+    /// Startet den eingebetteten Tor-Client und wartet auf den Bootstrap.
+    /// Mit `state_dir` bleiben Schlüssel (und damit die eigene Onion-Adresse)
+    /// über Neustarts erhalten.
+    pub async fn bootstrap(state_dir: Option<PathBuf>) -> Result<Self> {
+        let config = match state_dir {
+            Some(dir) => TorClientConfigBuilder::from_directories(dir.join("state"), dir.join("cache"))
+                .build()
+                .map_err(|e| anyhow!("Tor config: {:?}", e))?,
+            None => TorClientConfig::default(),
+        };
+        let client = TorClient::create_bootstrapped(config)
+            .await
+            .map_err(|e| anyhow!("Tor bootstrap failed: {:?}", e))?;
+        info!("Tor-Client gebootstrapped");
+        Ok(Self {
+            client,
+            registry: OnionRegistry::default(),
+            onion_service: Mutex::new(None),
+            inbound: Mutex::new(None),
+            next_inbound: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Baut einen Stream zu `onion` über Tor auf.
+    pub async fn dial(&self, onion: &OnionAddr) -> Result<DataStream> {
+        debug!("Tor => dial {}", onion);
+        self.client
+            .connect((onion.host.as_str(), onion.port))
+            .await
+            .map_err(|e| anyhow!("Tor connect to {} failed: {:?}", onion, e))
+    }
+
+    /// Wie `dial`, aber für eine virtuelle Adresse aus der Routing-Tabelle.
+    pub async fn dial_virtual(&self, addr: &SocketAddr) -> Result<DataStream> {
+        let onion = self
+            .registry
+            .resolve(addr)
+            .ok_or_else(|| anyhow!("No onion address registered for {}", addr))?;
+        self.dial(&onion).await
+    }
+
+    /// Veröffentlicht einen Onion-Service `nickname` auf `port`. Eingehende
+    /// Streams holt der P2P-Adapter über `take_inbound` ab.
+    pub fn publish_onion_service(&self, nickname: &str, port: u16) -> Result<OnionAddr> {
+        let nick: HsNickname = nickname
+            .parse()
+            .map_err(|e| anyhow!("Invalid onion service nickname {}: {:?}", nickname, e))?;
+        let cfg = OnionServiceConfigBuilder::default()
+            .nickname(nick)
+            .build()
+            .map_err(|e| anyhow!("Onion service config: {:?}", e))?;
+        let (service, rend_requests) = self
+            .client
+            .launch_onion_service(cfg)
+            .map_err(|e| anyhow!("launch_onion_service: {:?}", e))?;
+        let host = service
+            .onion_name()
+            .ok_or_else(|| anyhow!("Onion service has no address yet"))?
+            .to_string();
+        let onion = OnionAddr { host, port };
+        *self.onion_service.lock().unwrap() = Some(service);
+        let (tx, rx) = mpsc::channel(ONION_INBOUND_BACKLOG);
+        *self.inbound.lock().unwrap() = Some(rx);
+
+        let next_inbound = self.next_inbound.clone();
+        tokio::spawn(async move {
+            let mut streams = tor_hsservice::handle_rend_requests(rend_requests);
+            while let Some(req) = streams.next().await {
+                let tx = tx.clone();
+                let addr = inbound_virtual_addr(next_inbound.fetch_add(1, Ordering::Relaxed), port);
+                tokio::spawn(async move {
+                    let stream = match req.accept(Connected::new_empty()).await {
+                        Ok(s) => s,
+                        Err(e) => {
+                            warn!("Onion-Service => accept fehlgeschlagen: {:?}", e);
+                            return;
+                        }
+                    };
+                    if tx.try_send((addr, stream)).is_err() {
+                        warn!("Onion-Service => Backlog voll oder Adapter weg, Stream {} verworfen", addr);
+                    }
+                });
+            }
+            info!("Onion-Service => Rendezvous-Stream beendet");
+        });
+        info!("Onion-Service veröffentlicht => {}", onion);
+        Ok(onion)
+    }
+
+    /// Eingehende Streams des Onion-Service (einmalig; None ohne Service).
+    pub fn take_inbound(&self) -> Option<OnionInbound> {
+        self.inbound.lock().unwrap().take()
+    }
+
+    /// Eigene Onion-Adresse, falls ein Service läuft.
+    pub fn own_onion_host(&self) -> Option<String> {
+        self.onion_service
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|s| s.onion_name())
+            .map(|id| id.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOST: &str = "pg6mmjiyjmcrsslvykfwnntlaru7p5svn6y2ymmju6nubxndf4pscryd.onion";
+
+    #[test]
+    fn test_onion_virtual_addr_roundtrip() {
+        let onion = OnionAddr::parse(&format!("{}:9000", HOST)).unwrap();
+        let reg = OnionRegistry::default();
+        let addr = reg.register(&onion);
+        assert!(is_onion_virtual(&addr));
+        assert_eq!(addr.port(), 9000);
+        assert_eq!(reg.resolve(&addr), Some(onion));
+
+        let clearnet: SocketAddr = "203.0.113.7:9000".parse().unwrap();
+        assert!(!is_onion_virtual(&clearnet));
+        assert!(reg.resolve(&clearnet).is_none());
+        assert!(OnionAddr::parse("example.com:80").is_err());
+    }
+
+    #[test]
+    fn test_inbound_onion_addrs_are_distinct_and_not_loopback() {
+        let a = inbound_virtual_addr(0, 9000);
+        let b = inbound_virtual_addr(1, 9000);
+        assert_ne!(a, b);
+        assert!(!a.ip().is_loopback());
+        assert!(is_onion_virtual(&a) && is_onion_inbound(&a));
+        // Alle eingehenden teilen ein /64, getrennt von Localhost und ausgehenden Onions
+        use crate::rate_limiting::subnet_limiter::SubnetKey;
+        assert_eq!(SubnetKey::of(&a.ip()), SubnetKey::of(&b.ip()));
+        let outbound = OnionAddr::parse(&format!("{}:9000", HOST)).unwrap().virtual_addr();
+        assert!(!is_onion_inbound(&outbound));
+        assert_ne!(SubnetKey::of(&a.ip()), SubnetKey::of(&outbound.ip()));
+        assert!(OnionRegistry::default().resolve(&a).is_none());
+    }
+
+    #[test]
+    fn test_tor_settings_validation() {
+        let peer = format!("{}@{}:9000", "ab".repeat(32), HOST);
+        assert!(TorSettings::default().validate().is_ok());
+        let ok = TorSettings { enabled: true, onion_service: "mydex".into(), bootstrap: vec![peer.clone()], ..Default::default() };
+        assert!(ok.validate().is_ok());
+        let disabled = TorSettings { bootstrap: vec![peer], ..Default::default() };
+        assert!(disabled.validate().is_err());
+        let clearnet = TorSettings { enabled: true, bootstrap: vec![format!("{}@203.0.113.7:9000", "ab".repeat(32))], ..Default::default() };
+        assert!(clearnet.validate().is_err());
+    }
+
+    /// Braucht Zugang zum Tor-Netz: `cargo test --features tor-network-tests`.
+    #[cfg(feature = "tor-network-tests")]
+    #[tokio::test]
+    async fn test_two_nodes_connect_over_tor() -> Result<()> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let dir_a = tempfile::tempdir()?;
+        let dir_b = tempfile::tempdir()?;
+
+        // Node B: Onion-Service, eingehende Streams direkt aus `take_inbound`
+        let node_b = TorTransport::bootstrap(Some(dir_b.path().to_path_buf())).await?;
+        let onion_b = node_b.publish_onion_service("mydextest", 9000)?;
+        let mut inbound_b = node_b.take_inbound().unwrap();
+        let responder = tokio::spawn(async move {
+            let (addr, mut sock) = inbound_b.recv().await.expect("no inbound stream");
+            assert!(is_onion_inbound(&addr));
+            let mut buf = [0u8; 4];
+            if sock.read_exact(&mut buf).await.is_ok() {
+                let _ = sock.write_all(b"pong").await;
+                let _ = sock.flush().await;
+            }
+        });
+
+        // Node A: wählt B über die virtuelle Adresse an
+        let node_a = TorTransport::bootstrap(Some(dir_a.path().to_path_buf())).await?;
+        let virt = node_a.registry.register(&onion_b);
+        let mut stream = None;
+        // Der Service-Deskriptor braucht einige Zeit bis zur Veröffentlichung
+        for _ in 0..30 {
+            match node_a.dial_virtual(&virt).await {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(_) => tokio::time::sleep(std::time::Duration::from_secs(10)).await,
+            }
+        }
+        let mut stream = stream.ok_or_else(|| anyhow!("onion service never became reachable"))?;
+        stream.write_all(b"ping").await?;
+        stream.flush().await?;
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await?;
+        assert_eq!(&reply, b"pong");
+        responder.await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,490 @@
+//////////////////////////////////////////////////
+/// my_DEX/src/network/turn.rs
+//////////////////////////////////////////////////
+//
+// TURN-Client (RFC 5766, UDP) für Peers hinter symmetrischem NAT.
+//
+//  - Allocate mit Long-Term-Credentials: die erste Anfrage geht ohne Auth
+//    raus, der Server antwortet 401 mit REALM/NONCE, danach signieren wir mit
+//    MESSAGE-INTEGRITY (HMAC-SHA1, Key = MD5(user:realm:pass)).
+//  - CreatePermission pro Peer-IP, danach Send-Indications an den Peer.
+//    Eingehende Data-Indications landen in `recv()`.
+//  - `run_refresh` erneuert Allocation (vor Ablauf der LIFETIME) und
+//    Permissions (laufen nach 5 Minuten aus).
+//
+// Die Nachrichtenkodierung teilen wir mit network::stun.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use md5::{Digest as _, Md5};
+use sha1::Sha1;
+use tokio::net::UdpSocket;
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::network::stun::{
+    decode_message, decode_xor_address, encode_message, encode_xor_address, new_transaction_id, push_attr,
+    StunMessage, TransactionId,
+};
+
+pub const ALLOCATE_REQUEST: u16 = 0x0003;
+pub const ALLOCATE_SUCCESS: u16 = 0x0103;
+pub const ALLOCATE_ERROR: u16 = 0x0113;
+pub const REFRESH_REQUEST: u16 = 0x0004;
+pub const REFRESH_SUCCESS: u16 = 0x0104;
+pub const CREATE_PERMISSION_REQUEST: u16 = 0x0008;
+pub const CREATE_PERMISSION_SUCCESS: u16 = 0x0108;
+pub const SEND_INDICATION: u16 = 0x0016;
+pub const DATA_INDICATION: u16 = 0x0017;
+
+pub const ATTR_USERNAME: u16 = 0x0006;
+pub const ATTR_MESSAGE_INTEGRITY: u16 = 0x0008;
+pub const ATTR_ERROR_CODE: u16 = 0x0009;
+pub const ATTR_LIFETIME: u16 = 0x000D;
+pub const ATTR_XOR_PEER_ADDRESS: u16 = 0x0012;
+pub const ATTR_DATA: u16 = 0x0013;
+pub const ATTR_REALM: u16 = 0x0014;
+pub const ATTR_NONCE: u16 = 0x0015;
+pub const ATTR_XOR_RELAYED_ADDRESS: u16 = 0x0016;
+pub const ATTR_REQUESTED_TRANSPORT: u16 = 0x0019;
+
+/// Permissions gelten laut RFC 5766 fünf Minuten.
+const PERMISSION_LIFETIME: Duration = Duration::from_secs(300);
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(800);
+const REQUEST_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone)]
+pub struct TurnConfig {
+    pub server: String,
+    pub username: String,
+    pub password: String,
+    /// Gewünschte Lebensdauer der Allocation
+    pub lifetime: Duration,
+}
+
+#[derive(Debug, Clone)]
+struct AuthState {
+    realm: String,
+    nonce: Vec<u8>,
+    key: [u8; 16],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Allocation {
+    pub relayed: SocketAddr,
+    pub mapped: Option<SocketAddr>,
+    pub expires: Instant,
+}
+
+pub struct TurnClient {
+    socket: Arc<UdpSocket>,
+    server: SocketAddr,
+    config: TurnConfig,
+    auth: Mutex<Option<AuthState>>,
+    allocation: Mutex<Option<Allocation>>,
+    permissions: Mutex<HashMap<IpAddr, Instant>>,
+    pending: Mutex<HashMap<TransactionId, oneshot::Sender<StunMessage>>>,
+    incoming_rx: tokio::sync::Mutex<mpsc::UnboundedReceiver<(SocketAddr, Vec<u8>)>>,
+}
+
+fn long_term_key(user: &str, realm: &str, pass: &str) -> [u8; 16] {
+    let mut h = Md5::new();
+    h.update(format!("{}:{}:{}", user, realm, pass).as_bytes());
+    h.finalize().into()
+}
+
+/// Nachricht kodieren und MESSAGE-INTEGRITY als letztes Attribut anhängen.
+fn encode_with_integrity(msg_type: u16, txid: &TransactionId, attrs: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut msg = encode_message(msg_type, txid, attrs);
+    // Länge inkl. MI-Attribut (4 + 20), bevor der HMAC berechnet wird
+    let len = (attrs.len() + 24) as u16;
+    msg[2..4].copy_from_slice(&len.to_be_bytes());
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(&msg);
+    let tag = mac.finalize().into_bytes();
+    msg.extend_from_slice(&ATTR_MESSAGE_INTEGRITY.to_be_bytes());
+    msg.extend_from_slice(&20u16.to_be_bytes());
+    msg.extend_from_slice(&tag);
+    msg
+}
+
+fn error_code(msg: &StunMessage) -> Option<u16> {
+    msg.attr(ATTR_ERROR_CODE)
+        .filter(|v| v.len() >= 4)
+        .map(|v| (v[2] & 0x07) as u16 * 100 + v[3] as u16)
+}
+
+impl TurnClient {
+    /// Bindet einen UDP-Socket und startet den Empfangs-Loop.
+    pub async fn connect(config: TurnConfig) -> Result<Arc<Self>> {
+        let server = tokio::net::lookup_host(&config.server)
+            .await?
+            .next()
+            .ok_or_else(|| anyhow!("TURN server {} did not resolve", config.server))?;
+        let bind = if server.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket = Arc::new(UdpSocket::bind(bind).await?);
+        let (tx, rx) = mpsc::unbounded_channel();
+        let client = Arc::new(Self {
+            socket,
+            server,
+            config,
+            auth: Mutex::new(None),
+            allocation: Mutex::new(None),
+            permissions: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+            incoming_rx: tokio::sync::Mutex::new(rx),
+        });
+        let weak = Arc::downgrade(&client);
+        let socket = client.socket.clone();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 65_536];
+            loop {
+                let (n, from) = match socket.recv_from(&mut buf).await {
+                    Ok(r) => r,
+                    Err(e) => {
+                        warn!("TURN recv => {:?}", e);
+                        break;
+                    }
+                };
+                let Some(client) = weak.upgrade() else { break };
+                if from != client.server {
+                    continue;
+                }
+                let msg = match decode_message(&buf[..n]) {
+                    Ok(m) => m,
+                    Err(e) => {
+                        debug!("TURN => ungültige Nachricht: {:?}", e);
+                        continue;
+                    }
+                };
+                if msg.msg_type == DATA_INDICATION {
+                    let peer = msg.attr(ATTR_XOR_PEER_ADDRESS).and_then(|v| decode_xor_address(v, &msg.txid).ok());
+                    if let (Some(peer), Some(data)) = (peer, msg.attr(ATTR_DATA)) {
+                        let _ = tx.send((peer, data.to_vec()));
+                    }
+                    continue;
+                }
+                if let Some(waiter) = client.pending.lock().unwrap().remove(&msg.txid) {
+                    let _ = waiter.send(msg);
+                }
+            }
+        });
+        Ok(client)
+    }
+
+    pub fn allocation(&self) -> Option<Allocation> {
+        *self.allocation.lock().unwrap()
+    }
+
+    pub fn relayed_addr(&self) -> Option<SocketAddr> {
+        self.allocation().map(|a| a.relayed)
+    }
+
+    /// Nächstes relayed Datagramm (Peer, Daten).
+    pub async fn recv(&self) -> Option<(SocketAddr, Vec<u8>)> {
+        self.incoming_rx.lock().await.recv().await
+    }
+
+    /// Request senden und Antwort abwarten; bei 401/438 einmal mit frischen
+    /// Credentials wiederholen. `build` liefert die Attribute zur TxID
+    /// (XOR-PEER-ADDRESS hängt bei IPv6 davon ab); USERNAME/REALM/NONCE
+    /// werden angehängt.
+    async fn transact<F>(&self, msg_type: u16, build: F) -> Result<StunMessage>
+    where
+        F: Fn(&TransactionId) -> Vec<u8>,
+    {
+        for _ in 0..2 {
+            let txid = new_transaction_id();
+            let mut attrs = build(&txid);
+            let auth = self.auth.lock().unwrap().clone();
+            let raw = match &auth {
+                Some(a) => {
+                    push_attr(&mut attrs, ATTR_USERNAME, self.config.username.as_bytes());
+                    push_attr(&mut attrs, ATTR_REALM, a.realm.as_bytes());
+                    push_attr(&mut attrs, ATTR_NONCE, &a.nonce);
+                    encode_with_integrity(msg_type, &txid, &attrs, &a.key)
+                }
+                None => encode_message(msg_type, &txid, &attrs),
+            };
+            let resp = self.roundtrip(&txid, &raw).await?;
+            match error_code(&resp) {
+                Some(401) | Some(438) => {
+                    let realm = resp
+                        .attr(ATTR_REALM)
+                        .map(|r| String::from_utf8_lossy(r).to_string())
+                        .or_else(|| auth.as_ref().map(|a| a.realm.clone()))
+                        .ok_or_else(|| anyhow!("TURN 401 without REALM"))?;
+                    let nonce = resp.attr(ATTR_NONCE).ok_or_else(|| anyhow!("TURN 401 without NONCE"))?.to_vec();
+                    let key = long_term_key(&self.config.username, &realm, &self.config.password);
+                    *self.auth.lock().unwrap() = Some(AuthState { realm, nonce, key });
+                }
+                Some(code) => return Err(anyhow!("TURN request {:#06x} failed with error {}", msg_type, code)),
+                None => return Ok(resp),
+            }
+        }
+        Err(anyhow!("TURN authentication failed"))
+    }
+
+    async fn roundtrip(&self, txid: &TransactionId, raw: &[u8]) -> Result<StunMessage> {
+        for attempt in 0..REQUEST_ATTEMPTS {
+            let (tx, rx) = oneshot::channel();
+            self.pending.lock().unwrap().insert(*txid, tx);
+            self.socket.send_to(raw, self.server).await?;
+            match tokio::time::timeout(REQUEST_TIMEOUT, rx).await {
+                Ok(Ok(msg)) => return Ok(msg),
+                _ => {
+                    self.pending.lock().unwrap().remove(txid);
+                    debug!("TURN => Timeout (Versuch {})", attempt + 1);
+                }
+            }
+        }
+        Err(anyhow!("TURN server {} did not answer", self.server))
+    }
+
+    /// Allocate => relayed Adresse auf dem TURN-Server.
+    pub async fn allocate(&self) -> Result<Allocation> {
+        let mut attrs = Vec::new();
+        // UDP (17) in den oberen 8 Bit
+        push_attr(&mut attrs, ATTR_REQUESTED_TRANSPORT, &[17, 0, 0, 0]);
+        push_attr(&mut attrs, ATTR_LIFETIME, &(self.config.lifetime.as_secs() as u32).to_be_bytes());
+        let resp = self.transact(ALLOCATE_REQUEST, |_| attrs.clone()).await?;
+        if resp.msg_type != ALLOCATE_SUCCESS {
+            return Err(anyhow!("Unexpected Allocate response {:#06x}", resp.msg_type));
+        }
+        let relayed = decode_xor_address(
+            resp.attr(ATTR_XOR_RELAYED_ADDRESS)
+                .ok_or_else(|| anyhow!("Allocate response without XOR-RELAYED-ADDRESS"))?,
+            &resp.txid,
+        )?;
+        let mapped = resp
+            .attr(crate::network::stun::ATTR_XOR_MAPPED_ADDRESS)
+            .and_then(|v| decode_xor_address(v, &resp.txid).ok());
+        let lifetime = resp
+            .attr(ATTR_LIFETIME)
+            .filter(|v| v.len() >= 4)
+            .map(|v| u32::from_be_bytes([v[0], v[1], v[2], v[3]]) as u64)
+            .unwrap_or(self.config.lifetime.as_secs());
+        let alloc = Allocation { relayed, mapped, expires: Instant::now() + Duration::from_secs(lifetime) };
+        *self.allocation.lock().unwrap() = Some(alloc);
+        info!("TURN => relayed address {} (lifetime {}s)", relayed, lifetime);
+        Ok(alloc)
+    }
+
+    /// Allocation verlängern.
+    pub async fn refresh(&self) -> Result<()> {
+        let mut attrs = Vec::new();
+        push_attr(&mut attrs, ATTR_LIFETIME, &(self.config.lifetime.as_secs() as u32).to_be_bytes());
+        let resp = self.transact(REFRESH_REQUEST, |_| attrs.clone()).await?;
+        if resp.msg_type != REFRESH_SUCCESS {
+            return Err(anyhow!("Unexpected Refresh response {:#06x}", resp.msg_type));
+        }
+        if let Some(a) = self.allocation.lock().unwrap().as_mut() {
+            a.expires = Instant::now() + self.config.lifetime;
+        }
+        Ok(())
+    }
+
+    pub async fn create_permission(&self, peer: SocketAddr) -> Result<()> {
+        let resp = self
+            .transact(CREATE_PERMISSION_REQUEST, |txid| {
+                let mut attrs = Vec::new();
+                push_attr(&mut attrs, ATTR_XOR_PEER_ADDRESS, &encode_xor_address(&peer, txid));
+                attrs
+            })
+            .await?;
+        if resp.msg_type != CREATE_PERMISSION_SUCCESS {
+            return Err(anyhow!("Unexpected CreatePermission response {:#06x}", resp.msg_type));
+        }
+        self.permissions.lock().unwrap().insert(peer.ip(), Instant::now());
+        Ok(())
+    }
+
+    /// Datagramm über das Relay an `peer` (legt die Permission bei Bedarf an).
+    pub async fn send_to(&self, peer: SocketAddr, data: &[u8]) -> Result<()> {
+        if self.allocation().is_none() {
+            self.allocate().await?;
+        }
+        let fresh = self
+            .permissions
+            .lock()
+            .unwrap()
+            .get(&peer.ip())
+            .map(|t| t.elapsed() < PERMISSION_LIFETIME)
+            .unwrap_or(false);
+        if !fresh {
+            self.create_permission(peer).await?;
+        }
+        let txid = new_transaction_id();
+        let mut attrs = Vec::new();
+        push_attr(&mut attrs, ATTR_XOR_PEER_ADDRESS, &encode_xor_address(&peer, &txid));
+        push_attr(&mut attrs, ATTR_DATA, data);
+        // Indications werden nicht authentifiziert (RFC 5766, Abschnitt 10)
+        self.socket.send_to(&encode_message(SEND_INDICATION, &txid, &attrs), self.server).await?;
+        Ok(())
+    }
+
+    /// Hält Allocation und Permissions am Leben, bis `token` abbricht.
+    pub async fn run_refresh(self: Arc<Self>, token: CancellationToken) {
+        let mut tick = tokio::time::interval(Duration::from_secs(30));
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = tick.tick() => {
+                    let due = self
+                        .allocation()
+                        .map(|a| a.expires.saturating_duration_since(Instant::now()) < Duration::from_secs(120))
+                        .unwrap_or(false);
+                    if due {
+                        if let Err(e) = self.refresh().await {
+                            warn!("TURN refresh fehlgeschlagen => neue Allocation: {:?}", e);
+                            let _ = self.allocate().await;
+                        }
+                    }
+                    let stale: Vec<IpAddr> = self
+                        .permissions
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .filter(|(_, t)| t.elapsed() > PERMISSION_LIFETIME - Duration::from_secs(60))
+                        .map(|(ip, _)| *ip)
+                        .collect();
+                    for ip in stale {
+                        if let Err(e) = self.create_permission(SocketAddr::new(ip, 0)).await {
+                            warn!("TURN permission refresh {} fehlgeschlagen: {:?}", ip, e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Mock-TURN-Server für Tests hier und im P2P-Adapter.
+#[cfg(test)]
+pub(crate) mod test_server {
+    use super::*;
+    use crate::network::stun::ATTR_XOR_MAPPED_ADDRESS;
+
+    fn reply(msg_type: u16, txid: &TransactionId, attrs: &[u8]) -> Vec<u8> {
+        encode_message(msg_type, txid, attrs)
+    }
+
+    /// Minimaler TURN-Server: verlangt einmal Auth (401), vergibt eine
+    /// Allocation und relayed Send-Indications an Peers mit Permission.
+    /// Datagramme von Peers an die Relay-Adresse gehen als Data-Indication
+    /// an den Client. `relayed` protokolliert alle Nutzdaten in beide Richtungen.
+    pub(crate) async fn spawn_mock_turn() -> (SocketAddr, Arc<Mutex<Vec<Vec<u8>>>>) {
+        let relayed = Arc::new(Mutex::new(Vec::new()));
+        let log = relayed.clone();
+        let sock = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let addr = sock.local_addr().unwrap();
+        let relay = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let client: Arc<Mutex<Option<SocketAddr>>> = Arc::new(Mutex::new(None));
+        {
+            let (sock, relay, client, log) = (sock.clone(), relay.clone(), client.clone(), log.clone());
+            tokio::spawn(async move {
+                let mut buf = vec![0u8; 65_536];
+                while let Ok((n, from)) = relay.recv_from(&mut buf).await {
+                    let Some(to) = *client.lock().unwrap() else { continue };
+                    log.lock().unwrap().push(buf[..n].to_vec());
+                    let txid = new_transaction_id();
+                    let mut attrs = Vec::new();
+                    push_attr(&mut attrs, ATTR_XOR_PEER_ADDRESS, &encode_xor_address(&from, &txid));
+                    push_attr(&mut attrs, ATTR_DATA, &buf[..n]);
+                    let _ = sock.send_to(&encode_message(DATA_INDICATION, &txid, &attrs), to).await;
+                }
+            });
+        }
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 65_536];
+            let mut perms: Vec<IpAddr> = Vec::new();
+            while let Ok((n, from)) = sock.recv_from(&mut buf).await {
+                let Ok(req) = decode_message(&buf[..n]) else { continue };
+                let authed = req.attr(ATTR_MESSAGE_INTEGRITY).is_some();
+                let mut attrs = Vec::new();
+                match req.msg_type {
+                    ALLOCATE_REQUEST | CREATE_PERMISSION_REQUEST | REFRESH_REQUEST if !authed => {
+                        push_attr(&mut attrs, ATTR_ERROR_CODE, &[0, 0, 4, 1]);
+                        push_attr(&mut attrs, ATTR_REALM, b"mydex");
+                        push_attr(&mut attrs, ATTR_NONCE, b"n0nce");
+                        let _ = sock.send_to(&reply(ALLOCATE_ERROR, &req.txid, &attrs), from).await;
+                    }
+                    ALLOCATE_REQUEST => {
+                        *client.lock().unwrap() = Some(from);
+                        push_attr(&mut attrs, ATTR_XOR_RELAYED_ADDRESS, &encode_xor_address(&relay.local_addr().unwrap(), &req.txid));
+                        push_attr(&mut attrs, ATTR_XOR_MAPPED_ADDRESS, &encode_xor_address(&from, &req.txid));
+                        push_attr(&mut attrs, ATTR_LIFETIME, &600u32.to_be_bytes());
+                        let _ = sock.send_to(&reply(ALLOCATE_SUCCESS, &req.txid, &attrs), from).await;
+                    }
+                    CREATE_PERMISSION_REQUEST => {
+                        if let Some(p) = req.attr(ATTR_XOR_PEER_ADDRESS).and_then(|v| decode_xor_address(v, &req.txid).ok()) {
+                            perms.push(p.ip());
+                        }
+                        let _ = sock.send_to(&reply(CREATE_PERMISSION_SUCCESS, &req.txid, &attrs), from).await;
+                    }
+                    REFRESH_REQUEST => {
+                        let _ = sock.send_to(&reply(REFRESH_SUCCESS, &req.txid, &attrs), from).await;
+                    }
+                    SEND_INDICATION if Some(from) == *client.lock().unwrap() => {
+                        let peer = req.attr(ATTR_XOR_PEER_ADDRESS).and_then(|v| decode_xor_address(v, &req.txid).ok());
+                        if let (Some(peer), Some(data)) = (peer, req.attr(ATTR_DATA)) {
+                            if perms.contains(&peer.ip()) {
+                                log.lock().unwrap().push(data.to_vec());
+                                let _ = relay.send_to(data, peer).await;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        });
+        (addr, relayed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_server::spawn_mock_turn;
+
+    fn config(server: SocketAddr) -> TurnConfig {
+        TurnConfig {
+            server: server.to_string(),
+            username: "alice".into(),
+            password: "secret".into(),
+            lifetime: Duration::from_secs(600),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allocate_with_auth_challenge() {
+        let (server, _) = spawn_mock_turn().await;
+        let client = TurnClient::connect(config(server)).await.unwrap();
+        let alloc = client.allocate().await.unwrap();
+        assert_eq!(alloc.relayed.ip(), IpAddr::from([127, 0, 0, 1]));
+        assert!(client.auth.lock().unwrap().is_some());
+        client.refresh().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_relayed_datagram_reaches_peer() {
+        let (server, _) = spawn_mock_turn().await;
+        let peer = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = TurnClient::connect(config(server)).await.unwrap();
+        client.send_to(peer.local_addr().unwrap(), b"hello via relay").await.unwrap();
+
+        let mut buf = [0u8; 64];
+        let (n, from) = tokio::time::timeout(Duration::from_secs(2), peer.recv_from(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&buf[..n], b"hello via relay");
+        assert_eq!(Some(from), client.relayed_addr());
+    }
+}
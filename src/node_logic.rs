@@ -6,13 +6,18 @@
 //    - new(config: NodeConfig): Konstruktor
 //    - start() (async): Start-Logik (NTP-Sync, NAT-Traversal)
 //    - calc_fee_preview(amount: f64)
-//    - place_order(req: OrderRequest)
+//    - place_order(req: OrderRequest) -> Order-ID
+//    - cancel_order(user_id, order_id)
+//    - order_status(order_id), order_book(market)
+//    - state_digest(shards): Merkle-Root je ShardManager-Shard für den Abgleich zwischen Nodes
 //    - list_open_orders()
 //    - execute_matching()
 //    - user_get_free_balance(user_id, coin)
 //    - user_deposit(user_id, coin, amount)
 //    - partial_fill_order(order_id, fill_amount)
 //    - get_time_offset()
+//    - Rolle `follower` (NodeConfig.role): nur Lesen/Sync, Trading-Operationen
+//      liefern DexError::ReadOnlyNode
 //
 //  2) DexNodeSnippet (aus Snippet, um GlobalSecurity einzubinden)
 //    - new(config: NodeConfig, Option<Arc<Mutex<GlobalSecuritySystem>>>)
@@ -27,16 +32,24 @@
 //    - setup_nat_traversal(): Versucht UPnP-Port-Mapping via IGD
 //
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::net::SocketAddr;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH, Duration};
 use tokio::task;
 use tracing::{info, debug, instrument, warn, error};
 
 use crate::config_loader::NodeConfig;
-use crate::crdt_logic::CrdtState;
+use crate::decentralized_order_book::conflict_resolution::ConflictPolicyRegistry;
+use crate::crdt_logic::{orders_root, CrdtState};
+use crate::shard_logic::ShardManager;
+use crate::decentralized_order_book::nonce_registry::NonceRegistry;
+use crate::utils::lock::LockRecover;
+use crate::utils::geoip_and_ntp::ClockSkewGuard;
 use crate::metrics::ORDER_COUNT;
 use crate::error::DexError;
+use crate::sanctions::sanctions_list::{global_sanctions, SanctionsList, ScreeningSubject};
 
 // Ursprüngliches Security-System:
 use crate::security::advanced_security::AdvancedSecuritySystem;
@@ -47,7 +60,10 @@ use crate::security::global_security_facade::GlobalSecuritySystem;
 use crate::logging::enhanced_logging::{log_error, write_audit_log};
 
 // Falls Sie eine Matching-Engine haben
-use crate::matching_engine::{MatchingEngine, TradeResult};
+use crate::matching_engine::{
+    roll_back_results, EvictionHook, HaltAction, HaltApproval, MarketHaltControl, MatchingEngine, OrderData,
+    PlaceResult, TradeResult,
+};
 // Falls Sie Settlement/Balance-Funktionen haben
 use crate::settlement::advanced_settlement::SettlementEngineTrait;
 // Falls Sie Fees berechnen wollen
@@ -65,13 +81,14 @@ use igd::PortMappingProtocol;
 // Zusätzliche Strukturen: z. B. OrderSide, OrderRequest
 ////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
 pub enum OrderSide {
     Buy,
     Sell,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OrderRequest {
     pub user_id: String,
     pub coin_to_sell: String,
@@ -79,8 +96,80 @@ pub struct OrderRequest {
     pub amount: f64,
     pub price: f64,
     pub side: OrderSide,
+    // Client-Nonce, je User streng monoton (Pflicht, sobald der Node eine NonceRegistry hat)
+    #[serde(default)]
+    pub nonce: Option<u64>,
 }
 
+impl OrderRequest {
+    /// Marktname "BASE/QUOTE": bei Sell wird BASE verkauft, bei Buy gekauft.
+    pub fn market(&self) -> String {
+        match self.side {
+            OrderSide::Sell => format!("{}/{}", self.coin_to_sell, self.coin_to_buy),
+            OrderSide::Buy => format!("{}/{}", self.coin_to_buy, self.coin_to_sell),
+        }
+    }
+}
+
+/// Seite einer CRDT-Order in `market` ("BASE/QUOTE") anhand ihrer ID
+/// `{coin_to_sell}_{coin_to_buy}_{nanoid}` (siehe `place_order`);
+/// `None`, wenn die Order zu einem anderen Markt gehört.
+fn order_side_in_market(order_id: &str, market: &str) -> Option<OrderSide> {
+    let (base, quote) = market.split_once('/')?;
+    if order_id.starts_with(&format!("{}_{}_", base, quote)) {
+        Some(OrderSide::Sell)
+    } else if order_id.starts_with(&format!("{}_{}_", quote, base)) {
+        Some(OrderSide::Buy)
+    } else {
+        None
+    }
+}
+
+/// Zustand einer Order, wie ihn REST-Clients (z.B. dex-cli) sehen.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderStatusInfo {
+    pub order_id: String,
+    pub user_id: String,
+    pub market: String,
+    pub side: OrderSide,
+    pub amount: f64,
+    pub filled: f64,
+    pub price: f64,
+    /// "open", "filled" oder "cancelled"
+    pub status: String,
+}
+
+/// State-Roots eines Nodes (hex): `shards` je Shard-ID aus dem ShardManager
+/// (`compute_merkle_root`), `root` über das Orderbuch des Nodes.
+/// Zwei Nodes sind synchron, wenn alle Shard-Roots übereinstimmen.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct StateDigest {
+    pub node_id: String,
+    pub shards: BTreeMap<u32, String>,
+    pub root: String,
+}
+
+impl StateDigest {
+    /// Shards, deren Root abweicht oder die nur ein Node hält, aufsteigend.
+    pub fn diverging_shards(&self, other: &StateDigest) -> Vec<u32> {
+        let ids: BTreeSet<u32> = self.shards.keys().chain(other.shards.keys()).copied().collect();
+        ids.into_iter()
+            .filter(|id| self.shards.get(id) != other.shards.get(id))
+            .collect()
+    }
+}
+
+/// Aggregiertes Orderbuch eines Marktes: (Preis, Menge), beste Preise zuerst.
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct OrderBookSnapshot {
+    pub market: String,
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+}
+
+/// Wallet-Adressen und Land eines Users (für das Sanktions-Screening beim Platzieren).
+pub type ScreeningLookup = Arc<dyn Fn(&str) -> Result<ScreeningSubject, DexError> + Send + Sync>;
+
 ////////////////////////////////////////////////////////////////////////////////
 // DexNode — Zusammenführung aus Original-Code + Snippet
 ////////////////////////////////////////////////////////////////////////////////
@@ -108,6 +197,18 @@ pub struct DexNode {
     
     // Zeit-Offset aus NTP
     pub ntp_time_offset: Arc<Mutex<Option<i64>>>,
+
+    // Order-ID => ursprünglicher Request (Markt, Seite, gesperrte Coins)
+    pub placed_orders: Arc<Mutex<HashMap<String, OrderRequest>>>,
+
+    // Wallet-Adressen und Land je User (AccountsManager); wird nach dem Start gesetzt
+    pub screening_lookup: Arc<RwLock<Option<ScreeningLookup>>>,
+
+    // Kill-Switch je Markt, geteilt mit den MatchingEngines
+    pub halt_control: Option<Arc<Mutex<MarketHaltControl>>>,
+
+    // Replay-Schutz über (user_id, nonce); None => keine Nonce-Pflicht
+    pub nonce_registry: Option<Arc<Mutex<NonceRegistry>>>,
 }
 
 impl DexNode {
@@ -120,7 +221,8 @@ impl DexNode {
         info!("Creating DexNode with config: {:?}", config);
 
         // CRDT
-        let st = CrdtState::default();
+        let policy = ConflictPolicyRegistry::new().from_config(&config.crdt_conflict_policy);
+        let st = CrdtState::new(&config.node_id).with_conflict_policy(policy);
 
         // Altes Security-System
         let advanced_sec = AdvancedSecuritySystem::new()
@@ -135,19 +237,136 @@ impl DexNode {
             settlement_engine: None,
             balances: Arc::new(Mutex::new(std::collections::HashMap::new())),
             ntp_time_offset: Arc::new(Mutex::new(None)),
+            placed_orders: Arc::new(Mutex::new(HashMap::new())),
+            screening_lookup: Arc::new(RwLock::new(None)),
+            halt_control: None,
+            nonce_registry: None,
         }
     }
 
-    /// Setze eine MatchingEngine
+    /// Setze eine MatchingEngine (Follower ignorieren sie); mit `dry_run`
+    /// settlet sie nur noch simuliert.
     pub fn set_matching_engine(&mut self, me: Arc<Mutex<MatchingEngine>>) {
+        if self.is_follower() {
+            warn!("Follower-Node {} => MatchingEngine wird nicht gesetzt", self.config.node_id);
+            return;
+        }
+        {
+            let mut engine = me.lock().unwrap();
+            if self.config.dry_run {
+                engine.enable_dry_run();
+            }
+            engine.eviction_hook = Some(self.eviction_release_hook());
+        }
         self.matching_engine = Some(me);
     }
 
-    /// Setze eine SettlementEngine
+    /// Gibt die Restmenge einer vom Book-Cap verdrängten Order frei und
+    /// vergisst ihre Metadaten (wie `close_order`, ohne CRDT-Remove).
+    fn eviction_release_hook(&self) -> EvictionHook {
+        let balances = self.balances.clone();
+        let placed_orders = self.placed_orders.clone();
+        Arc::new(move |o: &OrderData| {
+            let Some(req) = placed_orders.lock().unwrap().remove(&o.id) else {
+                return;
+            };
+            let mut bals = balances.lock().unwrap();
+            let (free, locked) = bals.entry((req.user_id.clone(), req.coin_to_sell.clone())).or_insert((0.0, 0.0));
+            let release = o.remaining().max(0.0).min(*locked);
+            *locked -= release;
+            *free += release;
+            write_audit_log(&format!("Order {} von {} verdrängt => released {}", o.id, req.user_id, release));
+        })
+    }
+
+    /// Setze eine SettlementEngine (Follower und `dry_run` ignorieren sie:
+    /// ohne Engine werden Trades nicht auf Wallets/Chains gebucht)
     pub fn set_settlement_engine(&mut self, se: Arc<Mutex<dyn SettlementEngineTrait + Send>>) {
+        if self.is_follower() {
+            warn!("Follower-Node {} => SettlementEngine wird nicht gesetzt", self.config.node_id);
+            return;
+        }
+        if self.config.dry_run {
+            warn!("Dry-Run auf Node {} => SettlementEngine wird nicht gesetzt", self.config.node_id);
+            return;
+        }
         self.settlement_engine = Some(se);
     }
 
+    /// Ab jetzt braucht jede Order eine frische Nonce; verbraucht wird sie erst,
+    /// wenn die Order angenommen ist.
+    pub fn set_nonce_registry(&mut self, registry: Arc<Mutex<NonceRegistry>>) {
+        self.nonce_registry = Some(registry);
+    }
+
+    /// Remote-Orders und Merges mit HLC aus der Zukunft lehnt der CRDT-State ab.
+    pub fn set_clock_guard(&mut self, guard: Arc<ClockSkewGuard>) {
+        self.state.lock_recover().clock_guard = Some(guard);
+    }
+
+    /// Dieselbe MarketHaltControl wie die MatchingEngines (`with_halt_control`).
+    pub fn set_halt_control(&mut self, control: Arc<Mutex<MarketHaltControl>>) {
+        self.halt_control = Some(control);
+    }
+
+    pub fn is_market_halted(&self, market: &str) -> bool {
+        self.halt_control
+            .as_ref()
+            .map(|c| c.lock().unwrap().is_halted(market))
+            .unwrap_or(false)
+    }
+
+    /// Hält `market` an bzw. setzt ihn fort; `approvals` wie bei
+    /// `MarketHaltControl::apply` (Threshold an Committee-Signaturen über die
+    /// aktuelle Nonce des Marktes).
+    #[instrument(name="node_halt_action", skip(self, approvals))]
+    pub fn apply_halt_action(&self, market: &str, action: HaltAction, approvals: &[HaltApproval]) -> Result<(), DexError> {
+        self.ensure_writable("halt_market")?;
+        let control = self.halt_control.as_ref()
+            .ok_or_else(|| DexError::Other("No MarketHaltControl set".into()))?;
+        control.lock().unwrap().apply(market, action, approvals)
+    }
+
+    /// Ob `market` angehalten ist und welche Nonce der nächste Beschluss braucht.
+    pub fn halt_status(&self, market: &str) -> Option<(bool, u64)> {
+        let control = self.halt_control.as_ref()?.lock().unwrap();
+        Some((control.is_halted(market), control.nonce(market)))
+    }
+
+    /// Quelle von Wallet-Adressen und Land für das Sanktions-Screening. Über
+    /// `&self`, weil die Accounts erst nach dem Node (und der REST-API) entstehen.
+    pub fn set_screening_lookup(&self, lookup: ScreeningLookup) {
+        *self.screening_lookup.write().unwrap_or_else(|e| e.into_inner()) = Some(lookup);
+    }
+
+    /// Screent Wallet-Adressen und Jurisdiktion des Order-Users gegen `list`.
+    /// Ohne Lookup (Node ohne Accounts) gibt es nichts zu prüfen.
+    fn screen_order_party(&self, req: &OrderRequest, list: &SanctionsList) -> Result<(), DexError> {
+        let lookup = self.screening_lookup.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let Some(lookup) = lookup else {
+            debug!("Kein Screening-Lookup => Sanktions-Screening für {} übersprungen", req.user_id);
+            return Ok(());
+        };
+        let subject = lookup(&req.user_id)?;
+        let result = list.screen_subject(&subject);
+        if let Err(ref e) = result {
+            warn!("Order von {} abgelehnt: {}", req.user_id, e);
+        }
+        result
+    }
+
+    pub fn is_follower(&self) -> bool {
+        self.config.role.is_follower()
+    }
+
+    /// Follower dürfen keinen Trading-State verändern.
+    fn ensure_writable(&self, op: &str) -> Result<(), DexError> {
+        if self.is_follower() {
+            return Err(DexError::ReadOnlyNode(op.to_string()));
+        }
+        Ok(())
+    }
+
     /// Start-Logik (async) => zusammengeführt mit Snippet-Code
     #[instrument(name="node_start", skip(self))]
     pub async fn start(&mut self) -> Result<()> {
@@ -183,15 +402,38 @@ impl DexNode {
         amount * fee_percent
     }
 
+    /// Sperrt die Coins, legt die Order im CRDT an und liefert die neue Order-ID.
     #[instrument(name="node_place_order", skip(self, req))]
-    pub fn place_order(&self, req: OrderRequest) -> Result<(), DexError> {
+    pub fn place_order(&self, req: OrderRequest) -> Result<String, DexError> {
+        self.ensure_writable("place_order")?;
+        let market = req.market();
+        if self.is_market_halted(&market) {
+            return Err(DexError::MarketHalted(market));
+        }
+        // NaN/inf würden die Balance-Vergleiche unten aushebeln => vor allem anderen ablehnen
+        if !req.amount.is_finite() || req.amount <= 0.0 || !req.price.is_finite() || req.price <= 0.0 {
+            return Err(DexError::Other(format!(
+                "Ungültige Order: amount={} price={}", req.amount, req.price
+            )));
+        }
+        // Dieselben Mindestmengen/Höchst-Gegenwerte wie in der MatchingEngine
+        self.config.order_limits.for_market(&market).check(&market, req.amount, Some(req.price))?;
         // 🚫 Banned-Prüfung (Watchtower)
         if let Some(global_sec) = &self.global_security {
-            if global_sec.lock().unwrap().is_banned(&req.user_id) {
-                return Err(DexError::Other(format!(
-                    "User {} ist vom Netzwerk gesperrt", req.user_id
-                )));
+            let sec = global_sec.lock().unwrap();
+            if sec.is_banned(&req.user_id) {
+                return Err(DexError::UserBanned(req.user_id.clone()));
             }
+            sec.enforce_rate_limit(&req.user_id)?;
+        }
+
+        // Sanktions-Screening: Wallet-Adressen, über die die Order abgewickelt
+        // wird, und Land/Jurisdiktion des Accounts.
+        self.screen_order_party(&req, &global_sanctions())?;
+
+        // Replay/veraltete Nonce => ablehnen, bevor etwas gesperrt wird
+        if let Some(registry) = &self.nonce_registry {
+            registry.lock_recover().check(&req.user_id, req.nonce.unwrap_or(0))?;
         }
 
         // 1) check free
@@ -199,10 +441,10 @@ impl DexNode {
         let bal_key = (req.user_id.clone(), req.coin_to_sell.clone());
         let (free, locked) = bals.entry(bal_key.clone()).or_insert((0.0, 0.0));
         if *free < req.amount {
-            return Err(DexError::Other(format!(
-                "Not enough free balance for user={} coin={}",
-                req.user_id, req.coin_to_sell
-            )));
+            return Err(DexError::InsufficientBalance {
+                user: req.user_id.clone(),
+                asset: req.coin_to_sell.clone(),
+            });
         }
 
         // 2) lock
@@ -212,15 +454,31 @@ impl DexNode {
 
         // 3) CRDT => addLocalOrder
         let mut st = self.state.lock().unwrap();
-        let local_order_id = format!("{}_{}", req.coin_to_sell, req.coin_to_buy);
+        let local_order_id = format!("{}_{}_{}", req.coin_to_sell, req.coin_to_buy, nanoid::nanoid!(12));
 
-        st.add_local_order(
+        if let Err(e) = st.add_local_order_with_side(
             &self.config.node_id,
             &local_order_id,
             &req.user_id,
+            req.side.clone(),
             req.amount,
             req.price,
-        )?;
+        ) {
+            drop(st);
+            self.unlock_balance(&bal_key, req.amount);
+            return Err(e);
+        }
+
+        // 4) Nonce erst nach der Annahme verbrauchen; verliert die Order den
+        //    Wettlauf gegen ein Replay (oder scheitert die DB), wird sie zurückgenommen
+        if let Some(registry) = &self.nonce_registry {
+            if let Err(e) = registry.lock_recover().check_and_record(&req.user_id, req.nonce.unwrap_or(0)) {
+                let _ = st.remove_local_order(&self.config.node_id, &local_order_id);
+                drop(st);
+                self.unlock_balance(&bal_key, req.amount);
+                return Err(e);
+            }
+        }
 
         ORDER_COUNT.inc();
         info!(
@@ -231,9 +489,263 @@ impl DexNode {
             "User {} placed order => side={:?}, amt={}",
             req.user_id, req.side, req.amount
         ));
+        drop(st);
+        self.placed_orders.lock().unwrap().insert(local_order_id.clone(), req);
+        Ok(local_order_id)
+    }
+
+    /// Gibt eine gerade gesperrte Menge wieder frei (abgelehnte Order).
+    fn unlock_balance(&self, key: &(String, String), amount: f64) {
+        let mut bals = self.balances.lock().unwrap();
+        let (free, locked) = bals.entry(key.clone()).or_insert((0.0, 0.0));
+        let release = amount.min(*locked);
+        *locked -= release;
+        *free += release;
+    }
+
+    /// Mehrere Orders auf einmal. Mit `atomic` wird vorab geprüft, ob die
+    /// Summe je (User, Coin) durch die freie Balance gedeckt ist; scheitert
+    /// danach trotzdem eine Order, werden die bereits platzierten wieder
+    /// storniert und ihre Sperren freigegeben. Ohne `atomic` wird jede Order
+    /// für sich platziert.
+    #[instrument(name="node_place_orders_batch", skip(self, reqs), fields(count = reqs.len()))]
+    pub fn place_orders_batch(&self, reqs: Vec<OrderRequest>, atomic: bool) -> Result<Vec<PlaceResult>, DexError> {
+        self.ensure_writable("place_orders_batch")?;
+
+        if atomic {
+            let shortfalls = self.batch_shortfalls(&reqs);
+            if !shortfalls.is_empty() {
+                let results = reqs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, req)| {
+                        if shortfalls.contains(&i) {
+                            PlaceResult::rejected(i, None, &DexError::InsufficientBalance {
+                                user: req.user_id.clone(),
+                                asset: req.coin_to_sell.clone(),
+                            })
+                        } else {
+                            PlaceResult::rolled_back(i, None)
+                        }
+                    })
+                    .collect();
+                return Ok(results);
+            }
+        }
+
+        let mut results = Vec::with_capacity(reqs.len());
+        let mut placed: Vec<(String, String)> = Vec::new();
+        for (i, req) in reqs.into_iter().enumerate() {
+            let user_id = req.user_id.clone();
+            match self.place_order(req) {
+                Ok(order_id) => {
+                    results.push(PlaceResult::accepted(i, &order_id));
+                    placed.push((user_id, order_id));
+                }
+                Err(e) if atomic => {
+                    warn!("Batch => Order {} abgelehnt ({}), {} Orders werden zurückgerollt", i, e, placed.len());
+                    for (user, order_id) in &placed {
+                        self.rollback_order(user, order_id);
+                    }
+                    results.push(PlaceResult::rejected(i, None, &e));
+                    return Ok(roll_back_results(results));
+                }
+                Err(e) => results.push(PlaceResult::rejected(i, None, &e)),
+            }
+        }
+        Ok(results)
+    }
+
+    /// Indizes der Orders, bei denen die kumulierte Menge je (User, Coin)
+    /// die freie Balance übersteigt.
+    fn batch_shortfalls(&self, reqs: &[OrderRequest]) -> Vec<usize> {
+        let bals = self.balances.lock().unwrap();
+        let mut needed: HashMap<(&str, &str), f64> = HashMap::new();
+        let mut out = Vec::new();
+        for (i, req) in reqs.iter().enumerate() {
+            let sum = needed.entry((req.user_id.as_str(), req.coin_to_sell.as_str())).or_insert(0.0);
+            *sum += req.amount;
+            let free = bals
+                .get(&(req.user_id.clone(), req.coin_to_sell.clone()))
+                .map(|(free, _)| *free)
+                .unwrap_or(0.0);
+            if *sum > free {
+                out.push(i);
+            }
+        }
+        out
+    }
+
+    /// Nimmt eine Batch-Order vollständig zurück: Order entfernen, Sperre lösen, Metadaten löschen.
+    fn rollback_order(&self, user_id: &str, order_id: &str) {
+        if let Err(e) = self.cancel_order(user_id, order_id) {
+            error!("Rollback von Order {} fehlgeschlagen: {:?}", order_id, e);
+        }
+        self.placed_orders.lock().unwrap().remove(order_id);
+    }
+
+    /// Eigentümer einer über diesen Node platzierten Order.
+    pub fn order_owner(&self, order_id: &str) -> Option<String> {
+        self.placed_orders.lock().unwrap().get(order_id).map(|r| r.user_id.clone())
+    }
+
+    /// Storniert eine offene Order des Users und gibt die noch gesperrte Restmenge frei.
+    /// `user_id` muss der beim Platzieren gespeicherte Eigentümer sein.
+    #[instrument(name="node_cancel_order", skip(self))]
+    pub fn cancel_order(&self, user_id: &str, order_id: &str) -> Result<(), DexError> {
+        self.ensure_writable("cancel_order")?;
+        let req = self
+            .placed_orders
+            .lock()
+            .unwrap()
+            .get(order_id)
+            .cloned()
+            .ok_or_else(|| DexError::OrderNotFound { order_id: order_id.to_string() })?;
+        if req.user_id != user_id {
+            return Err(DexError::Forbidden(format!("Order {} gehört nicht zu User {}", order_id, user_id)));
+        }
+
+        let release = self.close_order(order_id, &req)?;
+        write_audit_log(&format!("User {} cancelled order {} => released {}", user_id, order_id, release));
         Ok(())
     }
 
+    /// Entfernt eine offene Order aus dem CRDT, gibt die Restmenge frei und
+    /// vergisst die Metadaten. Liefert die freigegebene Menge.
+    fn close_order(&self, order_id: &str, req: &OrderRequest) -> Result<f64, DexError> {
+        let remaining = {
+            let mut st = self.state.lock().unwrap();
+            let ord = st
+                .visible_orders()
+                .into_iter()
+                .find(|o| o.id == order_id)
+                .ok_or_else(|| DexError::OrderNotFound { order_id: order_id.to_string() })?;
+            let remaining = ord.quantity - st.partial_filled_sum(&ord);
+            st.remove_local_order(&self.config.node_id, order_id)?;
+            remaining
+        };
+
+        let mut bals = self.balances.lock().unwrap();
+        let (free, locked) = bals.entry((req.user_id.clone(), req.coin_to_sell.clone())).or_insert((0.0, 0.0));
+        let release = remaining.max(0.0).min(*locked);
+        *locked -= release;
+        *free += release;
+        drop(bals);
+        self.placed_orders.lock().unwrap().remove(order_id);
+        Ok(release)
+    }
+
+    /// Räumt `placed_orders` auf: Orders, die älter als `order_timeout_sec` sind,
+    /// laufen ab (Restmenge wird freigegeben); Einträge, deren Order nicht mehr
+    /// sichtbar ist (gefüllt oder per Merge entfernt), werden verworfen.
+    /// Liefert die Anzahl entfernter Einträge.
+    #[instrument(name="node_prune_placed_orders", skip(self))]
+    pub fn prune_placed_orders(&self, now_secs: u64) -> usize {
+        if self.is_follower() {
+            return 0;
+        }
+        let visible: HashMap<String, u64> = self
+            .state
+            .lock()
+            .unwrap()
+            .visible_orders()
+            .into_iter()
+            .map(|o| (o.id, o.timestamp))
+            .collect();
+        let entries: Vec<(String, OrderRequest)> = self
+            .placed_orders
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, req)| (id.clone(), req.clone()))
+            .collect();
+
+        let mut removed = 0;
+        for (order_id, req) in entries {
+            match visible.get(&order_id) {
+                None => {
+                    self.placed_orders.lock().unwrap().remove(&order_id);
+                    removed += 1;
+                }
+                Some(ts) if ts.saturating_add(self.config.order_timeout_sec) <= now_secs => {
+                    match self.close_order(&order_id, &req) {
+                        Ok(release) => {
+                            write_audit_log(&format!("Order {} of {} expired => released {}", order_id, req.user_id, release));
+                            removed += 1;
+                        }
+                        Err(e) => warn!("Ablauf von Order {} fehlgeschlagen: {:?}", order_id, e),
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        removed
+    }
+
+    /// Status einer über diesen Node platzierten Order.
+    pub fn order_status(&self, order_id: &str) -> Option<OrderStatusInfo> {
+        let req = self.placed_orders.lock().unwrap().get(order_id).cloned()?;
+        let st = self.state.lock().unwrap();
+        let visible = st.visible_orders().into_iter().find(|o| o.id == order_id);
+        let (filled, status) = match &visible {
+            Some(o) => (st.partial_filled_sum(o), "open"),
+            None => {
+                // Nicht mehr sichtbar => entweder voll gefüllt oder storniert
+                let removed = st.orset.adds.keys().find(|o| o.id == order_id);
+                match removed {
+                    Some(o) if st.partial_filled_sum(o) >= o.quantity => (st.partial_filled_sum(o), "filled"),
+                    Some(o) => (st.partial_filled_sum(o), "cancelled"),
+                    None => (0.0, "cancelled"),
+                }
+            }
+        };
+        Some(OrderStatusInfo {
+            order_id: order_id.to_string(),
+            user_id: req.user_id.clone(),
+            market: req.market(),
+            side: req.side.clone(),
+            amount: req.amount,
+            filled,
+            price: req.price,
+            status: status.to_string(),
+        })
+    }
+
+    /// Offene Restmengen eines Marktes, pro Preisstufe zusammengefasst.
+    /// Gebaut aus den replizierten CRDT-Orders, damit auch Follower und andere
+    /// Full-Nodes das Buch sehen; Markt und Seite stecken in der Order-ID.
+    pub fn order_book(&self, market: &str) -> OrderBookSnapshot {
+        let st = self.state.lock().unwrap();
+        let mut bids: HashMap<u64, (f64, f64)> = HashMap::new();
+        let mut asks: HashMap<u64, (f64, f64)> = HashMap::new();
+        for o in st.visible_orders() {
+            let Some(side) = order_side_in_market(&o.id, market) else {
+                continue;
+            };
+            let remaining = o.quantity - st.partial_filled_sum(&o);
+            if remaining <= 0.0 {
+                continue;
+            }
+            let levels = if side == OrderSide::Buy { &mut bids } else { &mut asks };
+            levels.entry(o.price.to_bits()).or_insert((o.price, 0.0)).1 += remaining;
+        }
+        let mut bids: Vec<(f64, f64)> = bids.into_values().collect();
+        let mut asks: Vec<(f64, f64)> = asks.into_values().collect();
+        bids.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        asks.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        OrderBookSnapshot { market: market.to_string(), bids, asks }
+    }
+
+    /// Roots der Shards, die `shards` lokal hält, plus Root des Orderbuchs.
+    pub fn state_digest(&self, shards: &ShardManager) -> StateDigest {
+        let visible = self.state.lock().unwrap().visible_orders();
+        StateDigest {
+            node_id: self.config.node_id.clone(),
+            shards: shards.shard_roots(),
+            root: hex::encode(orders_root(&visible)),
+        }
+    }
+
     #[instrument(name="node_list_orders", skip(self))]
     pub fn list_open_orders(&self) -> Vec<String> {
         let st = self.state.lock().unwrap();
@@ -243,6 +755,7 @@ impl DexNode {
 
     #[instrument(name="node_execute_matching", skip(self))]
     pub fn execute_matching(&self) -> Result<(), DexError> {
+        self.ensure_writable("execute_matching")?;
         if let Some(me) = &self.matching_engine {
             let trades: Vec<TradeResult> = me.lock().unwrap().match_orders();
             if let Some(se) = &self.settlement_engine {
@@ -256,6 +769,24 @@ impl DexNode {
         Ok(())
     }
 
+    fn commit_reveal_engine(&self) -> Result<&Arc<Mutex<MatchingEngine>>, DexError> {
+        self.matching_engine
+            .as_ref()
+            .ok_or_else(|| DexError::Other("No matching engine => commit-reveal unavailable".into()))
+    }
+
+    /// Commit-Phase (REST `/api/commit_order`): nur der Hash, gebunden an `user_id`.
+    pub fn submit_order_commitment(&self, user_id: &str, hash: [u8; 32]) -> Result<(), DexError> {
+        self.ensure_writable("submit_order_commitment")?;
+        self.commit_reveal_engine()?.lock_or_err("matching_engine")?.submit_commitment(user_id, hash)
+    }
+
+    /// Reveal-Phase (REST `/api/reveal_order`): Order + Nonce zur Commitment.
+    pub fn reveal_order(&self, order: OrderData, nonce: &[u8]) -> Result<(), DexError> {
+        self.ensure_writable("reveal_order")?;
+        self.commit_reveal_engine()?.lock_or_err("matching_engine")?.reveal_order(order, nonce)
+    }
+
     pub fn user_get_free_balance(&self, user_id: &str, coin: &str) -> f64 {
         let bals = self.balances.lock().unwrap();
         let key = (user_id.to_string(), coin.to_string());
@@ -273,9 +804,17 @@ impl DexNode {
 
     #[instrument(name="node_partial_fill", skip(self))]
     pub fn partial_fill_order(&self, order_id: &str, fill_amount: f64) -> Result<(), DexError> {
+        self.ensure_writable("partial_fill_order")?;
         let min_fill = self.config.partial_fill_min_amount;
-        let mut st = self.state.lock().unwrap();
-        st.partial_fill(&self.config.node_id, order_id, fill_amount, min_fill)
+        let fully_filled = {
+            let mut st = self.state.lock().unwrap();
+            st.partial_fill(&self.config.node_id, order_id, fill_amount, min_fill)?;
+            !st.visible_orders().iter().any(|o| o.id == order_id)
+        };
+        if fully_filled {
+            self.placed_orders.lock().unwrap().remove(order_id);
+        }
+        Ok(())
     }
 
     // ================  NAT + NTP  ================
@@ -403,3 +942,358 @@ fn example_time_limited() {
 
     manager.cancel_order("orderABC").unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_loader::{load_config, NodeRole};
+
+    fn node(id: &str, role: NodeRole) -> DexNode {
+        let mut cfg = load_config("config/node_config.yaml").unwrap();
+        cfg.node_id = id.to_string();
+        cfg.role = role;
+        DexNode::new(cfg, None)
+    }
+
+    #[test]
+    fn test_dry_run_node_never_settles_for_real() {
+        let mut full = node("full-1", NodeRole::Full);
+        full.config.dry_run = true;
+        let me = Arc::new(Mutex::new(MatchingEngine::new()));
+        full.set_matching_engine(me.clone());
+        assert!(me.lock().unwrap().dry_run);
+
+        use crate::settlement::advanced_settlement::AdvancedSettlementEngine;
+        use crate::storage::db_layer::{DexDB, InMemoryDb};
+        let db = Arc::new(Mutex::new(DexDB {
+            rocks: None,
+            fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))),
+        }));
+        let fee_pool = Arc::new(crate::fees::fee_pool::FeePool::new(db.clone(), "settlement/fee_pool"));
+        let fees = crate::settlement::fees_config::SettlementFees::new(0.001, 0.002);
+        let se: Arc<Mutex<dyn SettlementEngineTrait + Send>> =
+            Arc::new(Mutex::new(AdvancedSettlementEngine::new(fee_pool, db, fees)));
+        full.set_settlement_engine(se);
+        assert!(full.settlement_engine.is_none());
+    }
+
+    #[test]
+    fn test_nonce_consumed_only_for_accepted_orders() {
+        let mut full = node("full-1", NodeRole::Full);
+        full.set_nonce_registry(Arc::new(Mutex::new(NonceRegistry::new())));
+        let req = |nonce: Option<u64>| OrderRequest {
+            user_id: "alice".into(),
+            coin_to_sell: "BTC".into(),
+            coin_to_buy: "USDT".into(),
+            amount: 1.0,
+            price: 30_000.0,
+            side: OrderSide::Sell,
+            nonce,
+        };
+        assert!(matches!(full.place_order(req(None)), Err(DexError::StaleNonce { .. })));
+
+        // Ungedeckt => abgelehnt, die Nonce bleibt frei
+        assert!(matches!(full.place_order(req(Some(5))), Err(DexError::InsufficientBalance { .. })));
+        full.user_deposit("alice", "BTC", 2.0);
+        full.place_order(req(Some(5))).unwrap();
+
+        // Replay derselben Nonce sperrt nichts
+        assert!(matches!(full.place_order(req(Some(5))), Err(DexError::DuplicateOrder { nonce: 5, .. })));
+        assert_eq!(full.user_get_free_balance("alice", "BTC"), 1.0);
+        assert_eq!(full.list_open_orders().len(), 1);
+    }
+
+    #[test]
+    fn test_place_order_enforces_order_limits_and_finite_amounts() {
+        use crate::matching_engine::OrderSizeLimits;
+        let mut full = node("full-1", NodeRole::Full);
+        full.config.order_limits.markets.insert(
+            "BTC/USDT".into(),
+            OrderSizeLimits { min_quantity: 0.01, max_notional: 50_000.0 },
+        );
+        full.user_deposit("alice", "BTC", 5.0);
+        let req = |amount: f64, price: f64| OrderRequest {
+            user_id: "alice".into(),
+            coin_to_sell: "BTC".into(),
+            coin_to_buy: "USDT".into(),
+            amount,
+            price,
+            side: OrderSide::Sell,
+            nonce: None,
+        };
+        assert!(matches!(full.place_order(req(0.001, 30_000.0)), Err(DexError::OrderTooSmall { .. })));
+        assert!(matches!(full.place_order(req(2.0, 30_000.0)), Err(DexError::OrderTooLarge { .. })));
+        assert!(full.place_order(req(f64::NAN, 30_000.0)).is_err());
+        assert!(full.place_order(req(1.0, f64::INFINITY)).is_err());
+        assert_eq!(full.user_get_free_balance("alice", "BTC"), 5.0);
+        full.place_order(req(1.0, 30_000.0)).unwrap();
+        assert_eq!(full.user_get_free_balance("alice", "BTC"), 4.0);
+    }
+
+    #[test]
+    fn test_follower_rejects_place_order_but_serves_book() {
+        let full = node("full-1", NodeRole::Full);
+        full.user_deposit("alice", "BTC", 2.0);
+        let req = OrderRequest {
+            user_id: "alice".into(),
+            coin_to_sell: "BTC".into(),
+            coin_to_buy: "USDT".into(),
+            amount: 1.5,
+            price: 30_000.0,
+            side: OrderSide::Sell,
+            nonce: None,
+        };
+        let order_id = full.place_order(req.clone()).unwrap();
+        full.user_deposit("bob", "USDT", 50_000.0);
+        let bid = OrderRequest {
+            user_id: "bob".into(),
+            coin_to_sell: "USDT".into(),
+            coin_to_buy: "BTC".into(),
+            amount: 0.5,
+            price: 29_000.0,
+            side: OrderSide::Buy,
+            nonce: None,
+        };
+        full.place_order(bid).unwrap();
+
+        // Follower synchronisiert nur den CRDT-State; Order-Metadaten bleiben lokal beim Full-Node
+        let follower = node("follower-1", NodeRole::Follower);
+        follower.user_deposit("alice", "BTC", 2.0);
+        follower.state.lock().unwrap().merge_remote("full-1", &full.state.lock().unwrap()).unwrap();
+        assert!(follower.placed_orders.lock().unwrap().is_empty());
+
+        match follower.place_order(req) {
+            Err(DexError::ReadOnlyNode(op)) => assert_eq!(op, "place_order"),
+            other => panic!("expected ReadOnlyNode, got {:?}", other),
+        }
+        assert!(matches!(follower.cancel_order("alice", &order_id), Err(DexError::ReadOnlyNode(_))));
+        assert!(matches!(follower.execute_matching(), Err(DexError::ReadOnlyNode(_))));
+        assert_eq!(follower.user_get_free_balance("alice", "BTC"), 2.0);
+
+        let book = follower.order_book("BTC/USDT");
+        assert_eq!(book.asks, vec![(30_000.0, 1.5)]);
+        assert_eq!(book.bids, vec![(29_000.0, 0.5)]);
+        assert!(follower.order_book("ETH/USDT").asks.is_empty());
+    }
+
+    #[test]
+    fn test_place_orders_batch_atomic_vs_partial() {
+        use crate::matching_engine::PlaceStatus;
+        let full = node("full-1", NodeRole::Full);
+        full.user_deposit("mm", "BTC", 2.0);
+        let sell = |amount: f64, price: f64| OrderRequest {
+            user_id: "mm".into(),
+            coin_to_sell: "BTC".into(),
+            coin_to_buy: "USDT".into(),
+            amount,
+            price,
+            side: OrderSide::Sell,
+            nonce: None,
+        };
+        let statuses = |r: &[PlaceResult]| r.iter().map(|x| x.status.clone()).collect::<Vec<_>>();
+
+        // Summe 2.5 > 2.0 => atomar nichts
+        let r = full.place_orders_batch(vec![sell(1.0, 100.0), sell(1.0, 101.0), sell(0.5, 102.0)], true).unwrap();
+        assert_eq!(statuses(&r), vec![PlaceStatus::RolledBack, PlaceStatus::RolledBack, PlaceStatus::Rejected]);
+        assert_eq!(full.user_get_free_balance("mm", "BTC"), 2.0);
+        assert!(full.order_book("BTC/USDT").asks.is_empty());
+
+        // Scheitert erst beim Platzieren (Menge 0) => bereits platzierte werden zurückgerollt
+        let r = full.place_orders_batch(vec![sell(1.0, 100.0), sell(0.0, 101.0)], true).unwrap();
+        assert_eq!(statuses(&r), vec![PlaceStatus::RolledBack, PlaceStatus::Rejected]);
+        assert_eq!(full.user_get_free_balance("mm", "BTC"), 2.0);
+        assert!(full.order_book("BTC/USDT").asks.is_empty());
+        assert!(full.placed_orders.lock().unwrap().is_empty());
+
+        // Nicht atomar => gültige Teilmenge
+        let r = full.place_orders_batch(vec![sell(1.0, 100.0), sell(1.0, 101.0), sell(0.5, 102.0)], false).unwrap();
+        assert_eq!(statuses(&r), vec![PlaceStatus::Accepted, PlaceStatus::Accepted, PlaceStatus::Rejected]);
+        assert_eq!(full.user_get_free_balance("mm", "BTC"), 0.0);
+        assert_eq!(full.order_book("BTC/USDT").asks.len(), 2);
+    }
+
+    #[test]
+    fn test_cancel_checks_owner_and_prune_evicts_filled_and_expired() {
+        let full = node("full-1", NodeRole::Full);
+        full.user_deposit("alice", "BTC", 10.0);
+        let sell = |amount: f64| OrderRequest {
+            user_id: "alice".into(),
+            coin_to_sell: "BTC".into(),
+            coin_to_buy: "USDT".into(),
+            amount,
+            price: 30_000.0,
+            side: OrderSide::Sell,
+            nonce: None,
+        };
+
+        let a = full.place_order(sell(1.0)).unwrap();
+        assert!(matches!(full.cancel_order("mallory", &a), Err(DexError::Forbidden(_))));
+        assert_eq!(full.order_owner(&a).as_deref(), Some("alice"));
+
+        // Voll gefüllt => Eintrag sofort weg
+        let b = full.place_order(sell(2.0)).unwrap();
+        full.partial_fill_order(&b, 2.0).unwrap();
+        assert!(full.order_owner(&b).is_none());
+
+        // Abgelaufen => Restmenge frei, Eintrag weg
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert_eq!(full.prune_placed_orders(now), 0);
+        assert_eq!(full.prune_placed_orders(now + full.config.order_timeout_sec + 1), 1);
+        assert!(full.placed_orders.lock().unwrap().is_empty());
+        assert!(full.list_open_orders().is_empty());
+        assert_eq!(full.user_get_free_balance("alice", "BTC"), 8.0);
+    }
+
+    #[test]
+    fn test_order_screening_checks_wallet_addresses_not_user_id() {
+        let full = node("full-1", NodeRole::Full);
+        let mut list = SanctionsList::new();
+        list.add_address("bc1q-sanctioned");
+        let req = |user: &str| OrderRequest {
+            user_id: user.into(),
+            coin_to_sell: "BTC".into(),
+            coin_to_buy: "USDT".into(),
+            amount: 1.0,
+            price: 30_000.0,
+            side: OrderSide::Sell,
+            nonce: None,
+        };
+
+        full.set_screening_lookup(Arc::new(|user: &str| {
+            Ok(match user {
+                "mallory" => ScreeningSubject {
+                    addresses: vec!["bc1q-clean".to_string(), "bc1q-sanctioned".to_string()],
+                    country: Some("DE".into()),
+                },
+                _ => ScreeningSubject { addresses: vec!["bc1q-clean".to_string()], country: None },
+            })
+        }));
+        assert!(matches!(
+            full.screen_order_party(&req("mallory"), &list),
+            Err(DexError::SanctionedParty(_))
+        ));
+        // user_id selbst ist keine Adresse
+        assert!(full.screen_order_party(&req("bc1q-sanctioned"), &list).is_ok());
+    }
+
+    #[test]
+    fn test_order_screening_checks_account_jurisdiction() {
+        let full = node("full-1", NodeRole::Full);
+        let list = SanctionsList::with_default_jurisdictions();
+        let country = crate::sanctions::sanctions_list::DEFAULT_SANCTIONED_JURISDICTIONS[0];
+        full.set_screening_lookup(Arc::new(move |user: &str| {
+            Ok(ScreeningSubject {
+                addresses: vec!["bc1q-clean".to_string()],
+                country: Some(if user == "eve" { country.to_string() } else { "DE".to_string() }),
+            })
+        }));
+        let req = |user: &str| OrderRequest {
+            user_id: user.into(),
+            coin_to_sell: "BTC".into(),
+            coin_to_buy: "USDT".into(),
+            amount: 1.0,
+            price: 30_000.0,
+            side: OrderSide::Sell,
+            nonce: None,
+        };
+        assert!(matches!(
+            full.screen_order_party(&req("eve"), &list),
+            Err(DexError::SanctionedParty(_))
+        ));
+        assert!(full.screen_order_party(&req("alice"), &list).is_ok());
+    }
+
+    #[test]
+    fn test_halted_market_rejects_node_orders() {
+        use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+        use crate::matching_engine::halt_signing_bytes;
+        let keys: Vec<Keypair> = (1..=2u8)
+            .map(|seed| {
+                let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+                let public = PublicKey::from(&secret);
+                Keypair { secret, public }
+            })
+            .collect();
+        let control = MarketHaltControl::new(keys.iter().map(|k| k.public).collect(), 2).unwrap();
+        let mut full = node("full-1", NodeRole::Full);
+        full.set_halt_control(Arc::new(Mutex::new(control)));
+        full.user_deposit("alice", "BTC", 1.0);
+
+        let (halted, nonce) = full.halt_status("BTC/USDT").unwrap();
+        assert!(!halted);
+        let approvals: Vec<HaltApproval> = keys
+            .iter()
+            .map(|k| HaltApproval {
+                signer: hex::encode(k.public.as_bytes()),
+                signature: hex::encode(k.sign(&halt_signing_bytes("BTC/USDT", HaltAction::Halt, nonce).unwrap()).to_bytes()),
+            })
+            .collect();
+        full.apply_halt_action("BTC/USDT", HaltAction::Halt, &approvals).unwrap();
+        // Derselbe Beschluss lässt sich nicht noch einmal einspielen
+        assert!(full.apply_halt_action("BTC/USDT", HaltAction::Halt, &approvals).is_err());
+
+        let err = full.place_order(OrderRequest {
+            user_id: "alice".into(),
+            coin_to_sell: "BTC".into(),
+            coin_to_buy: "USDT".into(),
+            amount: 1.0,
+            price: 30_000.0,
+            side: OrderSide::Sell,
+            nonce: None,
+        }).unwrap_err();
+        assert!(matches!(err, DexError::MarketHalted(ref m) if m == "BTC/USDT"));
+        assert_eq!(full.user_get_free_balance("alice", "BTC"), 1.0);
+    }
+
+    /// ShardManager mit Shards 0..3 unter `dir`, jeder Shard mit derselben signierten Order.
+    fn shard_manager(dir: &std::path::Path, kp: &ed25519_dalek::Keypair) -> ShardManager {
+        let sm = ShardManager::new(3, None);
+        for sid in 0..3u32 {
+            let path = dir.join(format!("shard_{}", sid));
+            sm.create_shard(sid, path.to_str().unwrap(), crate::watchtower::Watchtower::new("n")).unwrap();
+            sm.apply_delta(sid, &signed_delta(&format!("s{}-o1", sid), kp)).unwrap();
+        }
+        sm
+    }
+
+    fn signed_delta(order_id: &str, kp: &ed25519_dalek::Keypair) -> crate::dex_logic::advanced_crdt_sharding::CrdtDelta {
+        let mut clock = crate::utils::hlc::HybridLogicalClock::new("n");
+        let mut o = crate::crdt_logic::Order {
+            id: order_id.to_string(),
+            user_id: "alice".to_string(),
+            timestamp: 1,
+            quantity: 1.0,
+            price: 100.0,
+            side: None,
+            hlc: clock.tick(),
+            signature: None,
+            public_key: None,
+        };
+        o.sign(kp).unwrap();
+        crate::dex_logic::advanced_crdt_sharding::CrdtDelta { updated_orders: vec![o], removed_orders: vec![], hlc: clock.tick() }
+    }
+
+    #[test]
+    fn test_state_digest_matches_when_synced_and_names_diverging_shard() {
+        let kp = ed25519_dalek::Keypair::generate(&mut rand_07::rngs::OsRng);
+        let (dir_a, dir_b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let (sm_a, sm_b) = (shard_manager(dir_a.path(), &kp), shard_manager(dir_b.path(), &kp));
+        let a = node("node-a", NodeRole::Full);
+        let b = node("node-b", NodeRole::Follower);
+
+        let (da, db) = (a.state_digest(&sm_a), b.state_digest(&sm_b));
+        // Shard-IDs und Roots kommen aus dem ShardManager
+        assert_eq!(da.shards.keys().copied().collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(da.shards[&1], hex::encode(sm_a.shards.lock().unwrap()[&1].compute_merkle_root()));
+        assert!(da.diverging_shards(&db).is_empty());
+
+        // b bekommt eine weitere Order in Shard 2 => genau dieser Shard weicht ab
+        sm_b.apply_delta(2, &signed_delta("s2-o2", &kp)).unwrap();
+        let db = b.state_digest(&sm_b);
+        assert_eq!(da.diverging_shards(&db), vec![2]);
+
+        // Shard, den nur ein Node hält, zählt ebenfalls
+        sm_b.shards.lock().unwrap().remove(&0);
+        assert_eq!(da.diverging_shards(&b.state_digest(&sm_b)), vec![0, 2]);
+    }
+}
@@ -8,12 +8,26 @@ use tracing::{info, debug, instrument, warn};
 use crate::identity::access_control::{AccessPolicy, is_allowed};
 use crate::identity::{verify_message};
 use crate::utils::aesgcm_utils::SimpleResolver;
+use crate::metrics::NOISE_HANDSHAKE_DURATION;
+use crate::sybil::pow::{PowChallenge, PowGate};
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct NoiseConfig {
     pub pattern: String, // z. B. "Noise_XX_25519_ChaChaPoly_SHA256"
     pub static_private: Option<Vec<u8>>,
     pub access_policy: Option<AccessPolicy>,
+    /// Responder: PoW-Challenge vor dem Handshake (None => aus)
+    pub pow_gate: Option<Arc<PowGate>>,
+    /// Initiator: erwartet eine PoW-Challenge des Responders
+    pub solve_pow: bool,
+    /// Initiator: höhere Difficulty wird abgelehnt statt gelöst
+    /// (Standard `pow::DEFAULT_MAX_SOLVE_DIFFICULTY`)
+    pub max_pow_difficulty: u8,
+    /// So lange wird höchstens auf die Challenge (Initiator) bzw. die
+    /// Nonce (Responder) gewartet
+    pub pow_challenge_timeout: Duration,
 }
 
 /// Minimale Session => Noise
@@ -36,6 +50,27 @@ pub async fn initiator_connect(cfg: &NoiseConfig, addr: &str) -> Result<NoiseSes
     let mut stream = TcpStream::connect(addr).await?;
     info!("Initiator => connected to {}", addr);
 
+    // Client-Puzzle vor dem (teuren) Noise-Handshake
+    if cfg.solve_pow {
+        let mut raw = [0u8; PowChallenge::WIRE_LEN];
+        tokio::time::timeout(cfg.pow_challenge_timeout, stream.read_exact(&mut raw))
+            .await
+            .map_err(|_| anyhow!("No PoW challenge from {} within {:?}", addr, cfg.pow_challenge_timeout))??;
+        let challenge = PowChallenge::from_bytes(&raw);
+        let max = cfg.max_pow_difficulty;
+        let nonce = match tokio::task::spawn_blocking(move || challenge.solve_capped(max)).await? {
+            Some(n) => n,
+            None => {
+                warn!("Initiator => PoW-Difficulty {} von {} über Limit {} => close", challenge.difficulty, addr, max);
+                return Err(anyhow!("PoW difficulty {} from {} exceeds {}", challenge.difficulty, addr, max));
+            }
+        };
+        debug!("Initiator => PoW gelöst, difficulty={}", challenge.difficulty);
+        stream.write_all(&nonce.to_be_bytes()).await?;
+    }
+
+    let handshake_timer = NOISE_HANDSHAKE_DURATION.with_label_values(&["initiator"]).start_timer();
+
     // handshake
     let mut buf = vec![0u8; 1024];
     let len = session.write_message(&[], &mut buf)?;
@@ -57,6 +92,7 @@ pub async fn initiator_connect(cfg: &NoiseConfig, addr: &str) -> Result<NoiseSes
         }
     }
 
+    handshake_timer.observe_duration();
     info!("Noise handshake done (initiator), remote_static={:?}", remote_static);
 
     Ok(NoiseSession{ session, remote_static })
@@ -67,6 +103,21 @@ pub async fn responder_accept(cfg: &NoiseConfig, listener: &TcpListener) -> Resu
     let (mut stream, addr) = listener.accept().await?;
     info!("Responder => accepted from {}", addr);
 
+    if let Some(gate) = &cfg.pow_gate {
+        let challenge = gate.issue();
+        stream.write_all(&challenge.to_bytes()).await?;
+        let mut nonce = [0u8; 8];
+        tokio::time::timeout(cfg.pow_challenge_timeout, stream.read_exact(&mut nonce))
+            .await
+            .map_err(|_| anyhow!("No PoW solution from {} within {:?}", addr, cfg.pow_challenge_timeout))??;
+        if !challenge.verify(u64::from_be_bytes(nonce)) {
+            warn!("Responder => PoW von {} ungültig (difficulty={}) => close", addr, challenge.difficulty);
+            return Err(anyhow!("Insufficient proof-of-work from {}", addr));
+        }
+    }
+
+    let handshake_timer = NOISE_HANDSHAKE_DURATION.with_label_values(&["responder"]).start_timer();
+
     let noise_params: NoiseParams = cfg.pattern.parse()?;
     let builder = Builder::new(noise_params);
 
@@ -96,6 +147,7 @@ pub async fn responder_accept(cfg: &NoiseConfig, listener: &TcpListener) -> Resu
         }
     }
 
+    handshake_timer.observe_duration();
     info!("Noise handshake done (responder), remote_static={:?}", remote_static);
 
     Ok((NoiseSession{ session, remote_static }, stream))
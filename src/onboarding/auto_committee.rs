@@ -10,6 +10,7 @@
 //   3) Pr�fung von Software-Hashes (Whitelist) und DB/CRDT-Hash
 //   4) M-of-K Threshold-Signaturen der Pr�fdienste
 //   5) Phasen-Umschaltung von "admin" auf "auto"
+//      (persistiert in DexDB + signierte ModeTransition per Gossip)
 // 
 // Ohne Platzhalter/Demo-Stub, sondern als echter (wenn auch beispielhafter)
 // Produktionscode, der die ben�tigten Strukturen, Datenfluss und Logik abbildet.
@@ -24,25 +25,29 @@ use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 use anyhow::{Result, anyhow};
-use tracing::{info, debug, warn, error};
-use rand::Rng;
+use tracing::{debug, info, warn};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 use sha2::{Sha256, Digest};
 
-// Nehmen wir an, du hast bereits ein CRDT-Hash oder Chain-Hash im System:
-use crate::dex_logic::crdt_orderbook::OrderBookCRDT; // z.B. als "CRDT" placeholder
-use crate::noise::secure_channel::verify_software_image_checksum; // fiktive Funktion, s.u. 
 use crate::error::DexError;
+use crate::storage::db_layer::DexDB;
+use crate::sybil::stake_admission::StakeBondRegistry;
+use crate::utils::lock::LockRecover;
 
-// 1) DKG-Bibliothek (Beispiel: threshold_crypto), wir tun so als ob du es h�ttest
-// Hier nur ein exemplarischer Import:
-// use threshold_crypto::{SecretKeyShare, PublicKeySet, SignatureShare};
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+
+// 1) Threshold-Signaturen: threshold_crypto (BLS12-381)
+use threshold_crypto::{Signature as ThresholdSignature, SignatureShare};
 
 // ------------------------------------------------------------
 // Enums, Structs
 // ------------------------------------------------------------
 
 /// OnboardingMode => "admin" oder "auto"
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OnboardingMode {
     Admin,
     Auto,
@@ -59,9 +64,60 @@ pub struct OnboardingConfig {
     pub required_count_for_auto: usize,
     pub k: usize,
     pub m: usize,
+    /// Admin-Keys, deren Signatur im Admin-Modus ein Onboarding erlaubt
+    pub admin_public_keys: Vec<PublicKey>,
     // evtl. Pfade zur Software-Whitelist etc.
 }
 
+/// `onboarding` in der Node-Config. K und M kommen aus `onboarding_dkg`
+/// (Teilnehmer bzw. Schwelle + 1), der Modus aus dem gespeicherten Zustand.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OnboardingSettings {
+    pub required_count_for_auto: usize,
+    /// Admin-Keys (hex, Ed25519) für Phase A und die Software-Whitelist
+    pub admin_public_keys: Vec<String>,
+}
+
+impl Default for OnboardingSettings {
+    fn default() -> Self {
+        Self { required_count_for_auto: 5, admin_public_keys: Vec::new() }
+    }
+}
+
+impl OnboardingSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.required_count_for_auto == 0 {
+            return Err("required_count_for_auto must be > 0".into());
+        }
+        self.admin_keys().map(|_| ())
+    }
+
+    fn admin_keys(&self) -> Result<Vec<PublicKey>, String> {
+        self.admin_public_keys
+            .iter()
+            .map(|h| {
+                hex::decode(h)
+                    .ok()
+                    .and_then(|b| PublicKey::from_bytes(&b).ok())
+                    .ok_or_else(|| format!("admin_public_keys: `{}` is not an Ed25519 key", h))
+            })
+            .collect()
+    }
+
+    /// Start-Konfiguration im Admin-Modus.
+    pub fn to_config(&self, dkg: &crate::onboarding::dkg::DkgCommitteeConfig) -> Result<OnboardingConfig, DexError> {
+        let admin_public_keys = self.admin_keys().map_err(DexError::Other)?;
+        Ok(OnboardingConfig {
+            mode: OnboardingMode::Admin,
+            required_count_for_auto: self.required_count_for_auto,
+            k: dkg.participants.len(),
+            m: dkg.threshold + 1,
+            admin_public_keys,
+        })
+    }
+}
+
 /// Repr�sentiert eine Teil-Signatur (Partial Signature), 
 /// ausgestellt von einem Pr�fdienst (Validator).
 #[derive(Clone, Debug)]
@@ -77,12 +133,14 @@ pub struct OnboardingCertificate {
     pub node_id: String,            // PublicKey der Node
     pub software_hash: String,      // Hash der SW
     pub db_hash: String,            // DB-Hash
-    pub threshold_signature: Vec<u8>,  // Aggregierte Sig
+    pub timestamp: u64,             // aus dem Request, Teil der signierten Nachricht
+    pub threshold_signature: Vec<u8>,  // Aggregierte BLS-Sig (96 Bytes)
     pub signers_list: Vec<String>,  // Wer hat signiert
+    pub admin_signature: Option<Vec<u8>>, // Ed25519-Sig eines Admins (Phase A)
 }
 
 /// OnboardingRequest => Das Paket, das der Newcomer broadcastet
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct OnboardingRequest {
     pub node_id: String,        // z.B. public key / Node-Identit�t
     pub software_hash: String,  // docker-image-hash
@@ -91,20 +149,85 @@ pub struct OnboardingRequest {
     // weitere Felder => Versionsinfo, NodeName
 }
 
+impl OnboardingRequest {
+    /// Request mit dem SHA-256 des laufenden Binaries (std::env::current_exe).
+    pub fn for_running_binary(node_id: &str, db_hash: &str) -> Result<Self> {
+        let exe = std::env::current_exe()?;
+        let software_hash = compute_binary_hash(&exe.to_string_lossy())?;
+        let timestamp = SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        Ok(Self {
+            node_id: node_id.to_string(),
+            software_hash,
+            db_hash: db_hash.to_string(),
+            timestamp,
+        })
+    }
+}
+
 // ------------------------------------------------------------
-// "ProofOfClean" => wir definieren einfache Whitelist
-//   (In einer echten DEX: Docker oder TEE Attestation).
+// "ProofOfClean" => Whitelist erlaubter Binary-Hashes
+//   Kommt als signierte Konfiguration (Admin-Key aus OnboardingConfig),
+//   nicht mehr als fest einkompilierte Liste.
+//   (In einer echten DEX zusätzlich: Docker oder TEE Attestation).
 // ------------------------------------------------------------
 
-/// Einfache Whitelist: Docker-Image => sha256 => OK
-static SOFTWARE_WHITELIST: &[&str] = &[
-    "sha256:official-dex-image-abc123",
-    "sha256:official-dex-image-def456",
-    "sha256:official-dex-image-latest",
-];
+/// Signierte Whitelist, Hashes im Format "sha256:<hex>".
+/// `signer`/`signature` hex-kodiert (Ed25519).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedSoftwareWhitelist {
+    pub hashes: Vec<String>,
+    pub version: u64,
+    pub signer: String,
+    pub signature: String,
+}
+
+impl SignedSoftwareWhitelist {
+    /// Kanonische Bytes über Version + Hashes (utils::canonical).
+    fn signing_bytes(hashes: &[String], version: u64) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct WhitelistSigningView<'a> {
+            hashes: &'a [String],
+            version: u64,
+        }
+        crate::utils::canonical::signing_bytes(
+            "my_dex/onboarding/software_whitelist/v1",
+            &WhitelistSigningView { hashes, version },
+        )
+    }
+
+    pub fn sign(hashes: Vec<String>, version: u64, admin_key: &Keypair) -> Self {
+        let bytes = Self::signing_bytes(&hashes, version).expect("Whitelist enthält nur Strings/Zahlen");
+        let sig = admin_key.sign(&bytes);
+        Self {
+            hashes,
+            version,
+            signer: hex::encode(admin_key.public.as_bytes()),
+            signature: hex::encode(sig.to_bytes()),
+        }
+    }
+
+    /// Signatur gültig und Signer ist einer der `trusted` Admin-Keys?
+    pub fn verify(&self, trusted: &[PublicKey]) -> bool {
+        let pk = hex::decode(&self.signer).ok().and_then(|b| PublicKey::from_bytes(&b).ok());
+        let sig = hex::decode(&self.signature).ok().and_then(|b| Signature::from_bytes(&b).ok());
+        match (pk, sig, Self::signing_bytes(&self.hashes, self.version)) {
+            (Some(pk), Some(sig), Ok(bytes)) => trusted.contains(&pk) && pk.verify(&bytes, &sig).is_ok(),
+            _ => false,
+        }
+    }
+
+    /// Lädt die Whitelist aus einer JSON-Datei (z. B. config/software_whitelist.json).
+    pub fn load_from_file(path: &str) -> Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read software whitelist {}: {:?}", path, e))?;
+        Ok(serde_json::from_str(&data)?)
+    }
+}
 
-fn is_software_hash_whitelisted(hash: &str) -> bool {
-    SOFTWARE_WHITELIST.contains(&hash)
+/// SHA-256 einer Datei als "sha256:<hex>".
+pub fn compute_binary_hash(path: &str) -> Result<String> {
+    let data = std::fs::read(path).map_err(|e| anyhow!("Cannot read binary {}: {:?}", path, e))?;
+    Ok(format!("sha256:{}", hex::encode(Sha256::digest(&data))))
 }
 
 // ------------------------------------------------------------
@@ -116,6 +239,12 @@ pub struct GlobalBeacon {
     pub latest_random: u64,
 }
 
+impl Default for GlobalBeacon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GlobalBeacon {
     pub fn new() -> Self {
         // Start => random
@@ -139,8 +268,6 @@ impl GlobalBeacon {
 // Komitee-Auswahl => w�hle k Nodes zuf�llig aus N
 // ------------------------------------------------------------
 pub fn select_k_validators(fullnode_ids: &[String], k: usize, random_seed: u64) -> Vec<String> {
-    // Wir bilden Pseudocode => shuffle
-    let mut rng = rand::thread_rng();
     let mut indices: Vec<usize> = (0..fullnode_ids.len()).collect();
     // Mischen deterministisch via random_seed
     let mut derived = rand::rngs::StdRng::seed_from_u64(random_seed);
@@ -155,19 +282,37 @@ pub fn select_k_validators(fullnode_ids: &[String], k: usize, random_seed: u64)
 }
 
 // ------------------------------------------------------------
-// DKG / Threshold-Sig -> Pseudocode
-// Wir tun so, als w�rden wir "public_key_set" + "secret_key_share" 
-// in DB haben. 
-// In echt => threshold_crypto::SecretKeyShare
+// DKG / Threshold-Sig => threshold_crypto (BLS)
+// PublicKeySet / SecretKeyShare kapseln die threshold_crypto-Typen,
+// `index` ist der Share-Index im PublicKeySet.
+// Ein PublicKeySet mit Grad t braucht t+1 = M Shares.
+// Erzeugt werden beide in der DKG-Zeremonie (onboarding::dkg).
 // ------------------------------------------------------------
 #[derive(Clone, Debug)]
 pub struct PublicKeySet {
-    pub group_key_bytes: Vec<u8>,
+    pub inner: threshold_crypto::PublicKeySet,
 }
+
+impl PublicKeySet {
+    pub fn new(inner: threshold_crypto::PublicKeySet) -> Self {
+        Self { inner }
+    }
+
+    /// Gruppen-Public-Key (48 Bytes)
+    pub fn group_key_bytes(&self) -> Vec<u8> {
+        self.inner.public_key().to_bytes().to_vec()
+    }
+
+    /// Benötigte Anzahl Shares (M)
+    pub fn required_shares(&self) -> usize {
+        self.inner.threshold() + 1
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SecretKeyShare {
     pub index: usize,
-    pub share_bytes: Vec<u8>,
+    pub share: threshold_crypto::SecretKeyShare,
 }
 
 #[derive(Clone, Debug)]
@@ -185,55 +330,156 @@ impl DKGState {
     }
 }
 
-// Exemplarisch => partial_sign
+/// Teil-Signatur eines Komitee-Mitglieds
 pub fn partial_sign(
     sec_share: &SecretKeyShare,
     message: &[u8]
-) -> Result<Vec<u8>> {
-    // in echtem Code => sec_share.sign(message)
-    // hier => pseudo
-    let mut hasher = Sha256::new();
-    hasher.update(&sec_share.share_bytes);
-    hasher.update(message);
-    let digest = hasher.finalize();
-    Ok(digest[..].to_vec())
+) -> Result<SignatureShare> {
+    Ok(sec_share.share.sign(message))
 }
 
-// Exemplarisch => combine partial signatures
+/// Kombiniert mindestens M gültige Teil-Signaturen zur Gruppen-Signatur.
+/// Ungültige Shares (falscher Index/Key) werden verworfen, bevor gezählt wird.
 pub fn combine_partial_signatures(
     pk_set: &PublicKeySet,
-    partial_sigs: &[(usize, Vec<u8>)],
+    partial_sigs: &[(usize, SignatureShare)],
     m: usize,
     message: &[u8]
 ) -> Result<Vec<u8>> {
-    // in echtem Code => threshold_crypto::combine_signatures
-    // hier => pseudo: wir hashen alles
-    if partial_sigs.len() < m {
-        return Err(anyhow!("Not enough partial sigs: have={}, need={}", partial_sigs.len(), m));
+    let m = m.max(pk_set.required_shares());
+    let valid: Vec<&(usize, SignatureShare)> = partial_sigs
+        .iter()
+        .filter(|(idx, share)| {
+            let ok = pk_set.inner.public_key_share(*idx).verify(share, message);
+            if !ok {
+                warn!("combine_partial_signatures => ungültiger Share von Index {}", idx);
+            }
+            ok
+        })
+        .collect();
+    if valid.len() < m {
+        return Err(anyhow!("Not enough partial sigs: have={}, need={}", valid.len(), m));
     }
-    let mut hasher = Sha256::new();
-    hasher.update(&pk_set.group_key_bytes);
-    hasher.update(message);
-    for (idx, sig) in partial_sigs {
-        hasher.update(&sig);
-        hasher.update(&idx.to_le_bytes());
+    let combined = pk_set
+        .inner
+        .combine_signatures(valid.iter().map(|(idx, share)| (*idx, share)))
+        .map_err(|e| anyhow!("combine_signatures: {:?}", e))?;
+    if !pk_set.inner.public_key().verify(&combined, message) {
+        return Err(anyhow!("Combined signature does not verify against group key"));
     }
-    let digest = hasher.finalize();
-    Ok(digest[..].to_vec())
+    Ok(combined.to_bytes().to_vec())
 }
 
-// Exemplarisch => verify aggregated signature
+/// Prüft die aggregierte Signatur gegen den Gruppen-Key.
 pub fn verify_threshold_sig(
     pk_set: &PublicKeySet,
     message: &[u8],
     aggregated_sig: &[u8]
 ) -> bool {
-    // pseudo => we do a hash check
-    let mut hasher = Sha256::new();
-    hasher.update(&pk_set.group_key_bytes);
-    hasher.update(message);
-    let expected = hasher.finalize();
-    &expected[..] == aggregated_sig
+    let bytes: [u8; 96] = match aggregated_sig.try_into() {
+        Ok(b) => b,
+        Err(_) => return false,
+    };
+    match ThresholdSignature::from_bytes(bytes) {
+        Ok(sig) => pk_set.inner.public_key().verify(&sig, message),
+        Err(_) => false,
+    }
+}
+
+// ------------------------------------------------------------
+// Persistenz + ModeTransition
+// ------------------------------------------------------------
+
+/// DB-Key für Modus, Fullnode-Set und Software-Whitelist
+pub const ONBOARDING_STATE_KEY: &str = "onboarding/state";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct PersistedOnboardingState {
+    mode: Option<OnboardingMode>,
+    fullnodes: Vec<String>,
+    #[serde(default)]
+    transition_epoch: u64,
+    // Zuletzt geladene Whitelist => nach Neustart kein Rollback auf eine ältere Version
+    #[serde(default)]
+    software_whitelist_version: Option<u64>,
+    #[serde(default)]
+    software_whitelist: Vec<String>,
+}
+
+/// Signatur eines Fullnodes über `ModeTransition::signing_bytes`.
+/// `signer` ist der hex-kodierte Ed25519-Key (= node_id des Fullnodes).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TransitionApproval {
+    pub signer: String,
+    pub signature: String,
+}
+
+/// Ankündigung eines Moduswechsels, wird an alle Peers gegossipt.
+/// Wirksam erst mit `transition_threshold` verschiedenen Fullnode-Signaturen;
+/// `epoch` muss größer als die zuletzt übernommene sein (kein Replay).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModeTransition {
+    pub from: OnboardingMode,
+    pub to: OnboardingMode,
+    pub fullnodes: Vec<String>,
+    pub epoch: u64,
+    pub timestamp: u64,
+    pub approvals: Vec<TransitionApproval>,
+}
+
+impl ModeTransition {
+    /// Kanonische Bytes ohne `approvals` (utils::canonical).
+    fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct TransitionSigningView<'a> {
+            from: &'a OnboardingMode,
+            to: &'a OnboardingMode,
+            fullnodes: &'a [String],
+            epoch: u64,
+            timestamp: u64,
+        }
+        crate::utils::canonical::signing_bytes("my_dex/onboarding/mode_transition/v1", &TransitionSigningView {
+            from: &self.from,
+            to: &self.to,
+            fullnodes: &self.fullnodes,
+            epoch: self.epoch,
+            timestamp: self.timestamp,
+        })
+    }
+
+    /// Fügt die Signatur von `kp` hinzu (einmal pro Key).
+    pub fn approve(&mut self, kp: &Keypair) {
+        let signer = hex::encode(kp.public.as_bytes());
+        if self.approvals.iter().any(|a| a.signer == signer) {
+            return;
+        }
+        let bytes = self.signing_bytes().expect("ModeTransition enthält nur Strings/Zahlen");
+        let signature = hex::encode(kp.sign(&bytes).to_bytes());
+        self.approvals.push(TransitionApproval { signer, signature });
+    }
+
+    fn approval_valid(&self, a: &TransitionApproval) -> bool {
+        let pk = hex::decode(&a.signer).ok().and_then(|b| PublicKey::from_bytes(&b).ok());
+        let sig = hex::decode(&a.signature).ok().and_then(|b| Signature::from_bytes(&b).ok());
+        match (pk, sig, self.signing_bytes()) {
+            (Some(pk), Some(sig), Ok(bytes)) => pk.verify(&bytes, &sig).is_ok(),
+            _ => false,
+        }
+    }
+
+    /// Mindestens eine Signatur und alle Signaturen gültig.
+    pub fn verify(&self) -> bool {
+        !self.approvals.is_empty() && self.approvals.iter().all(|a| self.approval_valid(a))
+    }
+
+    /// Verschiedene Signer mit gültiger Signatur.
+    pub fn valid_signers(&self) -> HashSet<String> {
+        self.approvals
+            .iter()
+            .filter(|a| self.approval_valid(a))
+            .map(|a| a.signer.clone())
+            .collect()
+    }
 }
 
 // ------------------------------------------------------------
@@ -246,30 +492,319 @@ pub fn verify_threshold_sig(
 #[derive(Clone)]
 pub struct OnboardingGlobalState {
     pub config: Arc<Mutex<OnboardingConfig>>,
-    pub dkg_state: Arc<Mutex<DKGState>>,
+    /// None, bis die DKG-Zeremonie einen Schlüssel geliefert hat
+    pub dkg_state: Arc<Mutex<Option<DKGState>>>,
     pub fullnode_list: Arc<Mutex<HashSet<String>>>, // node_id strings
     pub global_beacon: Arc<Mutex<GlobalBeacon>>,
+    // Persistenz (None => nur im Speicher)
+    pub db: Option<Arc<Mutex<DexDB>>>,
+    // Signatur-Key + Gossip-Kanal für ModeTransition
+    pub node_keypair: Option<Arc<Keypair>>,
+    pub transition_tx: Option<UnboundedSender<ModeTransition>>,
+    // Erlaubte Binary-Hashes (aus SignedSoftwareWhitelist); leer => nichts erlaubt
+    pub software_whitelist: Arc<Mutex<HashSet<String>>>,
+    // Epoche der zuletzt übernommenen ModeTransition
+    pub transition_epoch: Arc<Mutex<u64>>,
+    // Version der geladenen Whitelist (None => noch keine)
+    pub software_whitelist_version: Arc<Mutex<Option<u64>>>,
+    // Stake-Bonds (None => Zulassung ohne Bond)
+    pub stake_admission: Option<Arc<Mutex<StakeBondRegistry>>>,
 }
 
 impl OnboardingGlobalState {
-    pub fn new(dkg: DKGState, conf: OnboardingConfig) -> Self {
+    pub fn new(dkg: Option<DKGState>, conf: OnboardingConfig) -> Self {
         let beacon = GlobalBeacon::new();
         Self {
             config: Arc::new(Mutex::new(conf)),
             dkg_state: Arc::new(Mutex::new(dkg)),
             fullnode_list: Arc::new(Mutex::new(HashSet::new())),
             global_beacon: Arc::new(Mutex::new(beacon)),
+            db: None,
+            node_keypair: None,
+            transition_tx: None,
+            software_whitelist: Arc::new(Mutex::new(HashSet::new())),
+            transition_epoch: Arc::new(Mutex::new(0)),
+            software_whitelist_version: Arc::new(Mutex::new(None)),
+            stake_admission: None,
+        }
+    }
+
+    /// Übernimmt eine signierte Whitelist; Versionen <= der geladenen werden
+    /// abgelehnt (kein Rollback auf eine alte, gültig signierte Liste).
+    pub fn load_software_whitelist(&self, wl: &SignedSoftwareWhitelist) -> Result<(), DexError> {
+        let trusted = self.config.lock().unwrap().admin_public_keys.clone();
+        if !wl.verify(&trusted) {
+            return Err(DexError::Other("Software whitelist signature invalid or signer unknown".into()));
+        }
+        {
+            let mut version = self.software_whitelist_version.lock().unwrap();
+            if let Some(current) = *version {
+                if wl.version <= current {
+                    warn!("Software-Whitelist v{} abgelehnt, v{} ist bereits geladen", wl.version, current);
+                    return Err(DexError::Other(format!(
+                        "Software whitelist version {} is not newer than {}", wl.version, current
+                    )));
+                }
+            }
+            *version = Some(wl.version);
+            *self.software_whitelist.lock().unwrap() = wl.hashes.iter().cloned().collect();
+        }
+        self.persist().map_err(|e| DexError::Other(format!("persist onboarding state: {:?}", e)))?;
+        info!("Software-Whitelist v{} geladen => {} Hashes", wl.version, wl.hashes.len());
+        Ok(())
+    }
+
+    pub fn is_software_hash_whitelisted(&self, hash: &str) -> bool {
+        self.software_whitelist.lock().unwrap().contains(hash)
+    }
+
+    /// Lädt Modus + Fullnode-Set aus `db` (falls vorhanden) und speichert
+    /// künftige Änderungen dort => ein Neustart fällt nicht auf Admin zurück.
+    pub fn with_persistence(mut self, db: Arc<Mutex<DexDB>>) -> Result<Self> {
+        let stored: Option<PersistedOnboardingState> = db.lock_recover().load_struct(ONBOARDING_STATE_KEY)?;
+        if let Some(st) = stored {
+            if let Some(mode) = st.mode {
+                self.config.lock().unwrap().mode = mode;
+            }
+            self.fullnode_list.lock().unwrap().extend(st.fullnodes);
+            *self.transition_epoch.lock().unwrap() = st.transition_epoch;
+            if st.software_whitelist_version.is_some() {
+                *self.software_whitelist_version.lock().unwrap() = st.software_whitelist_version;
+                *self.software_whitelist.lock().unwrap() = st.software_whitelist.into_iter().collect();
+            }
+            info!("Onboarding-State geladen => mode={:?}, fullnodes={}",
+                  self.config.lock().unwrap().mode, self.fullnode_list.lock().unwrap().len());
+        }
+        self.db = Some(db);
+        Ok(self)
+    }
+
+    /// Übernimmt das Ergebnis einer abgeschlossenen DKG-Runde.
+    pub fn install_dkg_output(&self, node_id: &str, out: crate::onboarding::dkg::DkgOutput) {
+        *self.dkg_state.lock().unwrap() = Some(out.into_dkg_state(node_id));
+    }
+
+    /// PublicKeySet des Komitees; vor Abschluss der DKG gibt es keinen.
+    fn committee_key(&self) -> Result<PublicKeySet, DexError> {
+        self.dkg_state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|d| d.pk_set.clone())
+            .ok_or_else(|| DexError::Other("No committee key yet (DKG not completed)".into()))
+    }
+
+    pub fn with_transition_gossip(mut self, keypair: Arc<Keypair>, tx: UnboundedSender<ModeTransition>) -> Self {
+        self.node_keypair = Some(keypair);
+        self.transition_tx = Some(tx);
+        self
+    }
+
+    /// Zertifikate werden nur für Identitäten mit gültigem Stake-Bond
+    /// angenommen (siehe sybil::stake_admission).
+    pub fn with_stake_admission(mut self, registry: Arc<Mutex<StakeBondRegistry>>) -> Self {
+        self.stake_admission = Some(registry);
+        self
+    }
+
+    fn check_stake(&self, node_id: &str) -> Result<(), DexError> {
+        match &self.stake_admission {
+            Some(registry) => registry.lock_recover().check_admission(node_id).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// Verbrennt den Bond einer Fullnode und nimmt sie aus der Liste.
+    pub fn slash_fullnode(&self, node_id: &str, reason: &str) -> Result<u64, DexError> {
+        let registry = self
+            .stake_admission
+            .as_ref()
+            .ok_or_else(|| DexError::Other("Stake admission is not enabled".into()))?;
+        // Erst aus der Liste, auch wenn das Verbrennen scheitert
+        let removed = self.fullnode_list.lock().unwrap().remove(node_id);
+        if removed {
+            self.persist().map_err(|e| DexError::Other(format!("persist onboarding state: {:?}", e)))?;
+        }
+        registry.lock_recover().slash(node_id, reason)
+    }
+
+    fn sorted_fullnodes(&self) -> Vec<String> {
+        let mut v: Vec<String> = self.fullnode_list.lock().unwrap().iter().cloned().collect();
+        v.sort();
+        v
+    }
+
+    fn persist(&self) -> Result<()> {
+        if let Some(db) = &self.db {
+            let st = PersistedOnboardingState {
+                mode: Some(self.config.lock().unwrap().mode.clone()),
+                fullnodes: self.sorted_fullnodes(),
+                transition_epoch: *self.transition_epoch.lock().unwrap(),
+                software_whitelist_version: *self.software_whitelist_version.lock().unwrap(),
+                software_whitelist: {
+                    let mut v: Vec<String> = self.software_whitelist.lock().unwrap().iter().cloned().collect();
+                    v.sort();
+                    v
+                },
+            };
+            db.lock_recover().store_struct(ONBOARDING_STATE_KEY, &st)?;
         }
+        Ok(())
+    }
+
+    /// Fügt einen Fullnode hinzu und speichert das Set.
+    pub fn add_fullnode(&self, node_id: &str) -> Result<()> {
+        self.fullnode_list.lock().unwrap().insert(node_id.to_string());
+        self.persist()
     }
 
     /// Z�hlt Fullnodes => falls >= config.required_count_for_auto => switch => auto
     pub fn maybe_switch_to_auto(&self) -> Result<()> {
         let cnt = self.fullnode_list.lock().unwrap().len();
-        let mut c = self.config.lock().unwrap();
-        if c.mode == OnboardingMode::Admin && cnt >= c.required_count_for_auto {
-            c.mode = OnboardingMode::Auto;
-            info!("Onboarding mode switched => 'auto' (? {} Fullnodes)", c.required_count_for_auto);
+        let switched = {
+            let mut c = self.config.lock().unwrap();
+            if c.mode == OnboardingMode::Admin && cnt >= c.required_count_for_auto {
+                c.mode = OnboardingMode::Auto;
+                *self.transition_epoch.lock().unwrap() += 1;
+                info!("Onboarding mode switched => 'auto' (? {} Fullnodes)", c.required_count_for_auto);
+                true
+            } else {
+                false
+            }
+        };
+        if switched {
+            self.persist()?;
+            self.announce_transition(OnboardingMode::Admin, OnboardingMode::Auto);
+        }
+        Ok(())
+    }
+
+    fn announce_transition(&self, from: OnboardingMode, to: OnboardingMode) {
+        let (kp, tx) = match (&self.node_keypair, &self.transition_tx) {
+            (Some(kp), Some(tx)) => (kp, tx),
+            _ => return,
+        };
+        let ts = SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let mut t = ModeTransition {
+            from,
+            to,
+            fullnodes: self.sorted_fullnodes(),
+            epoch: *self.transition_epoch.lock().unwrap(),
+            timestamp: ts,
+            approvals: Vec::new(),
+        };
+        t.approve(kp);
+        if tx.send(t).is_err() {
+            warn!("ModeTransition => Gossip-Kanal geschlossen");
+        }
+    }
+
+    /// Anzahl verschiedener Fullnode-Signaturen, die eine ModeTransition
+    /// braucht: Mehrheit der lokal bekannten Fullnodes, mindestens `m`.
+    pub fn transition_threshold(&self) -> usize {
+        let known = self.fullnode_list.lock().unwrap().len();
+        let m = self.config.lock().unwrap().m;
+        (known / 2 + 1).max(m)
+    }
+
+    /// Zeichnet eine gegossipte admin => auto Transition mit, wenn sie der
+    /// eigenen Sicht entspricht (alle gelisteten Fullnodes bekannt, Schwelle
+    /// erreicht, Epoche neu). Die ergänzte Transition geht wieder in den Gossip.
+    /// Eine Rückkehr zu Admin wird nie automatisch mitgezeichnet.
+    pub fn co_sign_transition(&self, t: &ModeTransition) -> Result<ModeTransition, DexError> {
+        let kp = self.node_keypair.as_ref()
+            .ok_or_else(|| DexError::Other("No node key for ModeTransition".into()))?;
+        if !t.verify() {
+            return Err(DexError::Other("ModeTransition signature invalid".into()));
+        }
+        if t.from != OnboardingMode::Admin || t.to != OnboardingMode::Auto {
+            return Err(DexError::Other(format!("Refusing to co-sign {:?} -> {:?}", t.from, t.to)));
+        }
+        if t.epoch <= *self.transition_epoch.lock().unwrap() {
+            return Err(DexError::Other(format!("ModeTransition epoch {} is stale", t.epoch)));
+        }
+        let required = self.config.lock().unwrap().required_count_for_auto;
+        {
+            let known = self.fullnode_list.lock().unwrap();
+            if known.len() < required || !t.fullnodes.iter().all(|f| known.contains(f)) {
+                return Err(DexError::Other("ModeTransition does not match local fullnode set".into()));
+            }
+        }
+        let mut signed = t.clone();
+        signed.approve(kp);
+        if let Some(tx) = &self.transition_tx {
+            if tx.send(signed.clone()).is_err() {
+                warn!("ModeTransition => Gossip-Kanal geschlossen");
+            }
+        }
+        Ok(signed)
+    }
+
+    /// Eingang aus dem Gossip: passt die Transition zur eigenen Sicht, wird sie
+    /// mitgezeichnet (und weitergegossipt); danach wird sie übernommen, sobald
+    /// genug Fullnodes signiert haben. Fehlende Signaturen sind kein Fehler,
+    /// die Transition kommt mit weiteren Signaturen erneut an.
+    pub fn on_transition_gossip(&self, t: &ModeTransition) -> Result<bool, DexError> {
+        let own = self.node_keypair.as_ref().map(|kp| hex::encode(kp.public.as_bytes()));
+        let signed_by_us = own.as_ref().is_some_and(|me| t.approvals.iter().any(|a| &a.signer == me));
+        let current = if !signed_by_us && own.is_some() {
+            match self.co_sign_transition(t) {
+                Ok(signed) => signed,
+                Err(e) => {
+                    debug!("ModeTransition epoch {} nicht mitgezeichnet => {}", t.epoch, e);
+                    t.clone()
+                }
+            }
+        } else {
+            t.clone()
+        };
+        let known = self.fullnode_list.lock().unwrap().clone();
+        let signers = current.valid_signers().into_iter().filter(|s| known.contains(s)).count();
+        if signers < self.transition_threshold() {
+            return Ok(false);
+        }
+        self.apply_remote_transition(&current)?;
+        Ok(true)
+    }
+
+    /// Übernimmt eine gegossipte ModeTransition. Sie braucht
+    /// `transition_threshold` verschiedene Signaturen bekannter Fullnodes
+    /// (node_id = hex Public Key), eine neuere Epoche als die zuletzt
+    /// übernommene und muss vom aktuellen Modus ausgehen.
+    pub fn apply_remote_transition(&self, t: &ModeTransition) -> Result<(), DexError> {
+        if !t.verify() {
+            return Err(DexError::Other("ModeTransition signature invalid".into()));
+        }
+        let current_epoch = *self.transition_epoch.lock().unwrap();
+        if t.epoch <= current_epoch {
+            return Err(DexError::Other(format!(
+                "ModeTransition epoch {} is stale or already applied (current {})", t.epoch, current_epoch
+            )));
         }
+        if self.config.lock().unwrap().mode != t.from {
+            return Err(DexError::Other(format!("ModeTransition from {:?} does not match current mode", t.from)));
+        }
+        let signers: Vec<String> = {
+            let known = self.fullnode_list.lock().unwrap();
+            t.valid_signers().into_iter().filter(|s| known.contains(s)).collect()
+        };
+        let threshold = self.transition_threshold();
+        if signers.len() < threshold {
+            return Err(DexError::Other(format!(
+                "ModeTransition has {} known fullnode signatures, need {}", signers.len(), threshold
+            )));
+        }
+        let required = self.config.lock().unwrap().required_count_for_auto;
+        if t.to == OnboardingMode::Auto && t.fullnodes.len() < required {
+            return Err(DexError::Other(format!(
+                "ModeTransition lists {} fullnodes, need {}", t.fullnodes.len(), required
+            )));
+        }
+        self.fullnode_list.lock().unwrap().extend(t.fullnodes.iter().cloned());
+        self.config.lock().unwrap().mode = t.to.clone();
+        *self.transition_epoch.lock().unwrap() = t.epoch;
+        self.persist().map_err(|e| DexError::Other(format!("persist onboarding state: {:?}", e)))?;
+        info!("Onboarding => ModeTransition {:?} -> {:?} (epoch {}) von {:?} übernommen", t.from, t.to, t.epoch, signers);
         Ok(())
     }
 }
@@ -278,23 +813,44 @@ impl OnboardingGlobalState {
 // PHASE A: Admin / Gatekeeper => sign_onboarding_certificate
 // ------------------------------------------------------------
 impl OnboardingGlobalState {
+    /// Admin signiert (Ed25519) node_id, software_hash und db_hash des Requests.
+    /// Das Ergebnis gehört in `OnboardingCertificate::admin_signature`.
     pub fn admin_sign_onboarding_certificate(
         &self,
-        admin_secret: &[u8],   // Admin Private Key
+        admin_key: &Keypair,
         request: &OnboardingRequest
     ) -> Result<Vec<u8>> {
-        // In echtem Code => ECDSA oder Ed25519
-        // Wir machen ein pseudo-hash:
-        let mut hasher = Sha256::new();
-        hasher.update(admin_secret);
-        hasher.update(request.node_id.as_bytes());
-        hasher.update(request.software_hash.as_bytes());
-        hasher.update(request.db_hash.as_bytes());
-        let digest = hasher.finalize();
-        let sig = digest[..].to_vec();
+        let msg = form_admin_message(&request.node_id, &request.software_hash, &request.db_hash)?;
+        Ok(admin_key.sign(&msg).to_bytes().to_vec())
+    }
 
-        Ok(sig)
+    /// true => `cert.admin_signature` stammt von einem konfigurierten Admin-Key.
+    fn verify_admin_signature(&self, conf: &OnboardingConfig, cert: &OnboardingCertificate) -> bool {
+        let sig = match cert.admin_signature.as_ref().and_then(|b| Signature::from_bytes(b).ok()) {
+            Some(sig) => sig,
+            None => return false,
+        };
+        let msg = match form_admin_message(&cert.node_id, &cert.software_hash, &cert.db_hash) {
+            Ok(msg) => msg,
+            Err(_) => return false,
+        };
+        conf.admin_public_keys.iter().any(|pk| pk.verify(&msg, &sig).is_ok())
+    }
+}
+
+/// Kanonische Bytes der Admin-Signatur (utils::canonical); die Felder sind
+/// einzeln kodiert, ein `|` in der node_id verschiebt keine Grenzen.
+fn form_admin_message(node_id: &str, software_hash: &str, db_hash: &str) -> Result<Vec<u8>, DexError> {
+    #[derive(Serialize)]
+    struct AdminSigningView<'a> {
+        node_id: &'a str,
+        software_hash: &'a str,
+        db_hash: &'a str,
     }
+    crate::utils::canonical::signing_bytes(
+        "my_dex/onboarding/admin/v1",
+        &AdminSigningView { node_id, software_hash, db_hash },
+    )
 }
 
 // ------------------------------------------------------------
@@ -329,7 +885,7 @@ impl OnboardingGlobalState {
         let mut partials = Vec::new();
         for validator_id in &selected {
             // check => software_hash in whitelist ?
-            if !is_software_hash_whitelisted(&request.software_hash) {
+            if !self.is_software_hash_whitelisted(&request.software_hash) {
                 warn!("Validator {} => software_hash not whitelisted => NO partial sig", validator_id);
                 continue;
             }
@@ -340,7 +896,7 @@ impl OnboardingGlobalState {
             }
             // => partial_sign
             let dkg_locked = self.dkg_state.lock().unwrap();
-            let share_opt = dkg_locked.shares.get(validator_id);
+            let share_opt = dkg_locked.as_ref().and_then(|d| d.shares.get(validator_id));
             let share = match share_opt {
                 Some(s) => s,
                 None => {
@@ -348,7 +904,7 @@ impl OnboardingGlobalState {
                     continue;
                 }
             };
-            let message = form_onboarding_message(request);
+            let message = form_onboarding_message(request)?;
             let psig = partial_sign(share, &message)
                 .map_err(|e| DexError::Other(format!("partial_sign error: {:?}", e)))?;
             // index = share.index
@@ -362,12 +918,12 @@ impl OnboardingGlobalState {
         }
 
         // aggregator => combine
-        let pk_set = self.dkg_state.lock().unwrap().pk_set.clone();
+        let pk_set = self.committee_key()?;
         let aggregated_sig = combine_partial_signatures(
             &pk_set,
             &partials,
             conf.m,
-            &form_onboarding_message(request),
+            &form_onboarding_message(request)?,
         ).map_err(|e| DexError::Other(format!("combine_partial_signatures: {:?}", e)))?;
 
         // => fertiges OnboardingCertificate
@@ -376,21 +932,18 @@ impl OnboardingGlobalState {
             node_id: request.node_id.clone(),
             software_hash: request.software_hash.clone(),
             db_hash: request.db_hash.clone(),
+            timestamp: request.timestamp,
             threshold_signature: aggregated_sig,
             signers_list: signers,
+            admin_signature: None,
         };
         Ok(cert)
     }
 }
 
-// Hilfsfunktion => generiert Bytes aus request
-fn form_onboarding_message(req: &OnboardingRequest) -> Vec<u8> {
-    let mut out = Vec::new();
-    out.extend_from_slice(req.node_id.as_bytes());
-    out.extend_from_slice(req.software_hash.as_bytes());
-    out.extend_from_slice(req.db_hash.as_bytes());
-    out.extend_from_slice(&req.timestamp.to_le_bytes());
-    out
+// Hilfsfunktion => kanonische Bytes des Requests (utils::canonical)
+fn form_onboarding_message(req: &OnboardingRequest) -> Result<Vec<u8>, DexError> {
+    crate::utils::canonical::signing_bytes("my_dex/onboarding/auto/v1", req)
 }
 
 // ------------------------------------------------------------
@@ -402,31 +955,41 @@ impl OnboardingGlobalState {
         cert: &OnboardingCertificate
     ) -> Result<(), DexError> {
         let conf = self.config.lock().unwrap().clone();
+        // Ohne Bond keine Zulassung, egal wer signiert hat
+        if let Err(e) = self.check_stake(&cert.node_id) {
+            warn!("Onboarding => {} ohne gültigen Stake-Bond => abgelehnt", cert.node_id);
+            return Err(e);
+        }
         match conf.mode {
             OnboardingMode::Admin => {
-                // => check Admin Sig 
-                //   => ABER: wir sind im Code, haben hier threshold...
-                //   => In real => check "admin signature" ...
-                warn!("Currently in Admin mode => ignoring threshold signature? (Should check adminSig).");
-                // In real => if valid => add to fullnode_list
-                self.fullnode_list.lock().unwrap().insert(cert.node_id.clone());
-                self.maybe_switch_to_auto()?;
+                // => check Admin Sig
+                if !self.verify_admin_signature(&conf, cert) {
+                    warn!("Admin mode => Zertifikat für {} ohne gültige Admin-Signatur => abgelehnt", cert.node_id);
+                    return Err(DexError::Other(format!(
+                        "Onboarding certificate for {} lacks a valid admin signature", cert.node_id
+                    )));
+                }
+                self.add_fullnode(&cert.node_id)
+                    .map_err(|e| DexError::Other(format!("persist onboarding state: {:?}", e)))?;
+                self.maybe_switch_to_auto()
+                    .map_err(|e| DexError::Other(format!("switch to auto: {:?}", e)))?;
             },
             OnboardingMode::Auto => {
                 // => check threshold sig
-                let pk_set = self.dkg_state.lock().unwrap().pk_set.clone();
+                let pk_set = self.committee_key()?;
                 let msg = form_onboarding_message(&OnboardingRequest {
                     node_id: cert.node_id.clone(),
                     software_hash: cert.software_hash.clone(),
                     db_hash: cert.db_hash.clone(),
-                    timestamp: 0, // we don't have the original
-                });
+                    timestamp: cert.timestamp,
+                })?;
                 let ok = verify_threshold_sig(&pk_set, &msg, &cert.threshold_signature);
                 if !ok {
                     return Err(DexError::Other("Threshold signature invalid".into()));
                 }
                 // => if ok => add to fullnode_list
-                self.fullnode_list.lock().unwrap().insert(cert.node_id.clone());
+                self.add_fullnode(&cert.node_id)
+                    .map_err(|e| DexError::Other(format!("persist onboarding state: {:?}", e)))?;
                 info!("Node {} accepted as Fullnode => 'auto' mode => signers={:?}",
                       cert.node_id, cert.signers_list);
             }
@@ -436,16 +999,404 @@ impl OnboardingGlobalState {
 }
 
 // ------------------------------------------------------------
-// Minimales "verify_software_image_checksum"
-// Wir hatten es in noise::secure_channel::??? Hier nun
-// eine m�gliche Funktion, wir referenzieren es.
+// verify_software_image_checksum
+// Liest Image/Binary, SHA-256 => Vergleich mit `expected_hash`
+// ("sha256:<hex>" oder nur "<hex>", Groß/Klein egal).
 // ------------------------------------------------------------
 
-/// In real => du w�rdest z. B. Docker-Image entpacken und hashen, 
-/// oder RemoteAttestation-Hash etc.
-pub fn verify_software_image_checksum(_image_path: &str, _expected_hash: &str) -> bool {
-    // "no placeholder" => wir tun so, als w�rden wir 
-    // datei=read => sha256 => compare
-    // In real => implement it.
-    true
+pub fn verify_software_image_checksum(image_path: &str, expected_hash: &str) -> bool {
+    let actual = match compute_binary_hash(image_path) {
+        Ok(h) => h,
+        Err(e) => {
+            warn!("verify_software_image_checksum => {:?}", e);
+            return false;
+        }
+    };
+    let expected = expected_hash.trim().to_lowercase();
+    let expected = expected.strip_prefix("sha256:").unwrap_or(&expected);
+    actual.strip_prefix("sha256:") == Some(expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SecretKey;
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    fn admin() -> Keypair {
+        keypair(42)
+    }
+
+    fn key_set(m: usize, n: usize) -> (DKGState, threshold_crypto::SecretKeySet) {
+        let sk_set = threshold_crypto::SecretKeySet::random(m - 1, &mut rand_07::thread_rng());
+        let mut dkg = DKGState::new(PublicKeySet::new(sk_set.public_keys()));
+        for i in 0..n {
+            dkg.shares.insert(format!("v{}", i), SecretKeyShare { index: i, share: sk_set.secret_key_share(i) });
+        }
+        (dkg, sk_set)
+    }
+
+    #[test]
+    fn test_fewer_than_m_shares_fail() {
+        let (dkg, _) = key_set(3, 5);
+        let msg = b"onboard fn_new";
+        let partials: Vec<(usize, SignatureShare)> = ["v0", "v1"]
+            .iter()
+            .map(|v| {
+                let s = &dkg.shares[*v];
+                (s.index, partial_sign(s, msg).unwrap())
+            })
+            .collect();
+        assert!(combine_partial_signatures(&dkg.pk_set, &partials, 3, msg).is_err());
+        // Auch mit zu kleinem m-Parameter gilt die Schwelle des Key-Sets
+        assert!(combine_partial_signatures(&dkg.pk_set, &partials, 1, msg).is_err());
+    }
+
+    #[test]
+    fn test_m_shares_verify_against_group_key() {
+        let (dkg, _) = key_set(3, 5);
+        let msg = b"onboard fn_new";
+        let partials: Vec<(usize, SignatureShare)> = ["v4", "v1", "v3"]
+            .iter()
+            .map(|v| {
+                let s = &dkg.shares[*v];
+                (s.index, partial_sign(s, msg).unwrap())
+            })
+            .collect();
+        let sig = combine_partial_signatures(&dkg.pk_set, &partials, 3, msg).unwrap();
+        assert!(verify_threshold_sig(&dkg.pk_set, msg, &sig));
+        assert!(!verify_threshold_sig(&dkg.pk_set, b"other message", &sig));
+
+        // Fremder Share zählt nicht
+        let (foreign, _) = key_set(3, 5);
+        let mut mixed = partials[..2].to_vec();
+        mixed.push((2, partial_sign(&foreign.shares["v2"], msg).unwrap()));
+        assert!(combine_partial_signatures(&dkg.pk_set, &mixed, 3, msg).is_err());
+    }
+
+    fn state() -> OnboardingGlobalState {
+        let (dkg, _) = key_set(1, 2);
+        let conf = OnboardingConfig {
+            mode: OnboardingMode::Admin,
+            required_count_for_auto: 2,
+            k: 2,
+            m: 1,
+            admin_public_keys: vec![admin().public],
+        };
+        OnboardingGlobalState::new(Some(dkg), conf)
+    }
+
+    fn request(node_id: &str) -> OnboardingRequest {
+        OnboardingRequest {
+            node_id: node_id.into(),
+            software_hash: "sha256:official-dex-image-latest".into(),
+            db_hash: "000000".into(),
+            timestamp: 0,
+        }
+    }
+
+    fn unsigned_cert(node_id: &str) -> OnboardingCertificate {
+        let req = request(node_id);
+        OnboardingCertificate {
+            node_id: req.node_id,
+            software_hash: req.software_hash,
+            db_hash: req.db_hash,
+            timestamp: req.timestamp,
+            threshold_signature: vec![],
+            signers_list: vec![],
+            admin_signature: None,
+        }
+    }
+
+    fn cert(node_id: &str) -> OnboardingCertificate {
+        let sig = state().admin_sign_onboarding_certificate(&admin(), &request(node_id)).unwrap();
+        OnboardingCertificate { admin_signature: Some(sig), ..unsigned_cert(node_id) }
+    }
+
+    fn temp_binary(name: &str, content: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("dex_sw_{}_{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_tampered_binary_fails_attestation() {
+        let path = temp_binary("tamper", b"official dex build");
+        let hash = compute_binary_hash(&path).unwrap();
+        assert!(verify_software_image_checksum(&path, &hash));
+        assert!(verify_software_image_checksum(&path, &hash.to_uppercase().replace("SHA256:", "")));
+
+        std::fs::write(&path, b"official dex build + backdoor").unwrap();
+        assert!(!verify_software_image_checksum(&path, &hash));
+        assert!(!verify_software_image_checksum("/nonexistent/dex-binary", &hash));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_signed_whitelist_loaded_and_enforced() {
+        let path = temp_binary("wl", b"official dex build");
+        let good = compute_binary_hash(&path).unwrap();
+        let node = state();
+        assert!(!node.is_software_hash_whitelisted(&good));
+
+        let forged = SignedSoftwareWhitelist::sign(vec![good.clone()], 1, &keypair(7));
+        assert!(node.load_software_whitelist(&forged).is_err());
+
+        let mut wl = SignedSoftwareWhitelist::sign(vec![good.clone()], 1, &admin());
+        node.load_software_whitelist(&wl).unwrap();
+        assert!(node.is_software_hash_whitelisted(&good));
+
+        std::fs::write(&path, b"tampered").unwrap();
+        assert!(!node.is_software_hash_whitelisted(&compute_binary_hash(&path).unwrap()));
+
+        // Nachträglich ergänzter Hash => Signatur ungültig
+        wl.hashes.push("sha256:deadbeef".into());
+        assert!(node.load_software_whitelist(&wl).is_err());
+
+        // Neuere Version ersetzt, ältere oder gleiche Version => kein Rollback
+        let v2 = SignedSoftwareWhitelist::sign(vec!["sha256:v2".into()], 2, &admin());
+        node.load_software_whitelist(&v2).unwrap();
+        assert!(!node.is_software_hash_whitelisted(&good));
+        let old = SignedSoftwareWhitelist::sign(vec![good.clone()], 1, &admin());
+        assert!(node.load_software_whitelist(&old).is_err());
+        let same = SignedSoftwareWhitelist::sign(vec![good.clone()], 2, &admin());
+        assert!(node.load_software_whitelist(&same).is_err());
+        assert!(node.is_software_hash_whitelisted("sha256:v2"));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_whitelist_version_survives_restart() {
+        let db = Arc::new(Mutex::new(DexDB::in_memory()));
+        let node = state().with_persistence(db.clone()).unwrap();
+        let v1 = SignedSoftwareWhitelist::sign(vec!["sha256:v1".into()], 1, &admin());
+        let v2 = SignedSoftwareWhitelist::sign(vec!["sha256:v2".into()], 2, &admin());
+        node.load_software_whitelist(&v2).unwrap();
+
+        // Neustart => v2 aktiv, die ältere, gültig signierte v1 wird abgelehnt
+        let restarted = state().with_persistence(db).unwrap();
+        assert_eq!(*restarted.software_whitelist_version.lock().unwrap(), Some(2));
+        assert!(restarted.is_software_hash_whitelisted("sha256:v2"));
+        assert!(restarted.load_software_whitelist(&v1).is_err());
+        assert!(!restarted.is_software_hash_whitelisted("sha256:v1"));
+    }
+
+    #[test]
+    fn test_request_hashes_running_binary() {
+        let req = OnboardingRequest::for_running_binary("fn_self", "000000").unwrap();
+        let exe = std::env::current_exe().unwrap();
+        assert!(verify_software_image_checksum(&exe.to_string_lossy(), &req.software_hash));
+    }
+
+    #[test]
+    fn test_admin_signed_onboarding_accepted() {
+        let node = state();
+        node.accept_onboarding_certificate(&cert("fn_ok")).unwrap();
+        assert!(node.fullnode_list.lock().unwrap().contains("fn_ok"));
+    }
+
+    #[test]
+    fn test_onboarding_requires_stake_bond() {
+        use crate::settlement::secured_settlement::SettlementEngine;
+        use crate::sybil::stake_admission::{bond_signing_bytes, STAKE_ASSET};
+
+        let op = keypair(7);
+        let mut ledger = SettlementEngine::new();
+        ledger
+            .balances
+            .entry(hex::encode(op.public.as_bytes()))
+            .or_default()
+            .insert(STAKE_ASSET.to_string(), (1_000.0, 0.0));
+        let registry = Arc::new(Mutex::new(StakeBondRegistry::new(1_000, Box::new(ledger))));
+        let node = state().with_stake_admission(registry.clone());
+
+        assert!(node.accept_onboarding_certificate(&cert("fn_sybil")).is_err());
+        assert!(node.fullnode_list.lock().unwrap().is_empty());
+
+        let sig = op.sign(&bond_signing_bytes("fn_ok", 1_000, 0).unwrap());
+        registry.lock().unwrap().bond("fn_ok", op.public, 1_000, 0, &sig).unwrap();
+        node.accept_onboarding_certificate(&cert("fn_ok")).unwrap();
+        assert!(node.fullnode_list.lock().unwrap().contains("fn_ok"));
+
+        assert_eq!(node.slash_fullnode("fn_ok", "equivocation").unwrap(), 1_000);
+        assert!(!node.fullnode_list.lock().unwrap().contains("fn_ok"));
+        assert!(node.accept_onboarding_certificate(&cert("fn_ok")).is_err());
+    }
+
+    #[test]
+    fn test_forged_or_missing_admin_signature_rejected() {
+        let node = state();
+        assert!(node.accept_onboarding_certificate(&unsigned_cert("fn_self")).is_err());
+
+        // Selbst signiert statt vom Admin
+        let self_sig = node.admin_sign_onboarding_certificate(&keypair(9), &request("fn_self")).unwrap();
+        let forged = OnboardingCertificate { admin_signature: Some(self_sig), ..unsigned_cert("fn_self") };
+        assert!(node.accept_onboarding_certificate(&forged).is_err());
+
+        // Gültige Admin-Signatur, aber für eine andere Node
+        let mut other = cert("fn_real");
+        other.node_id = "fn_self".into();
+        assert!(node.accept_onboarding_certificate(&other).is_err());
+        assert!(node.fullnode_list.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_admin_signature_binds_field_boundaries() {
+        let node = state();
+        // "a|b" + "c" und "a" + "b|c" ergaben früher dieselben Bytes
+        let mut req = request("fn|x");
+        req.software_hash = "sha256:1".into();
+        let sig = node.admin_sign_onboarding_certificate(&admin(), &req).unwrap();
+        let shifted = OnboardingCertificate {
+            node_id: "fn".into(),
+            software_hash: "x|sha256:1".into(),
+            db_hash: req.db_hash.clone(),
+            admin_signature: Some(sig),
+            ..unsigned_cert("fn")
+        };
+        let conf = node.config.lock().unwrap().clone();
+        assert!(!node.verify_admin_signature(&conf, &shifted));
+    }
+
+    #[test]
+    fn test_auto_mode_survives_restart() {
+        let db = Arc::new(Mutex::new(DexDB::in_memory()));
+        let kp = Arc::new(keypair(1));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let node = state()
+            .with_persistence(db.clone())
+            .unwrap()
+            .with_transition_gossip(kp.clone(), tx);
+        node.accept_onboarding_certificate(&cert("fn_a")).unwrap();
+        assert_eq!(node.config.lock().unwrap().mode, OnboardingMode::Admin);
+        node.accept_onboarding_certificate(&cert("fn_b")).unwrap();
+        assert_eq!(node.config.lock().unwrap().mode, OnboardingMode::Auto);
+
+        let transition = rx.try_recv().expect("transition gossiped");
+        assert!(transition.verify());
+        assert_eq!(transition.to, OnboardingMode::Auto);
+        assert_eq!(transition.fullnodes, vec!["fn_a".to_string(), "fn_b".to_string()]);
+
+        // "Neustart": frischer State aus derselben DB
+        let restarted = state().with_persistence(db).unwrap();
+        assert_eq!(restarted.config.lock().unwrap().mode, OnboardingMode::Auto);
+        assert_eq!(restarted.fullnode_list.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_remote_transition_requires_threshold_and_fresh_epoch() {
+        let keys: Vec<Keypair> = (2..=4).map(keypair).collect();
+        let ids: Vec<String> = keys.iter().map(|k| hex::encode(k.public.as_bytes())).collect();
+        let with_fullnodes = |s: OnboardingGlobalState| {
+            for id in &ids {
+                s.fullnode_list.lock().unwrap().insert(id.clone());
+            }
+            s
+        };
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let origin = with_fullnodes(state().with_transition_gossip(Arc::new(keypair(2)), tx));
+        origin.maybe_switch_to_auto().unwrap();
+        let t = rx.try_recv().unwrap();
+        assert_eq!(t.epoch, 1);
+
+        // Eine Signatur reicht nicht (Mehrheit von 3 => 2)
+        let peer = with_fullnodes(state());
+        assert_eq!(peer.transition_threshold(), 2);
+        assert!(peer.apply_remote_transition(&t).is_err());
+        // Dieselbe Signatur doppelt zählt nur einmal
+        let mut dup = t.clone();
+        dup.approvals.push(dup.approvals[0].clone());
+        assert!(peer.apply_remote_transition(&dup).is_err());
+        assert_eq!(peer.config.lock().unwrap().mode, OnboardingMode::Admin);
+
+        let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+        let cosigner = with_fullnodes(state().with_transition_gossip(Arc::new(keypair(3)), tx2));
+        let t2 = cosigner.co_sign_transition(&t).unwrap();
+        assert_eq!(t2.valid_signers().len(), 2);
+
+        // Unbekannte Signer zählen nicht
+        assert!(state().apply_remote_transition(&t2).is_err());
+        // Nachträglich ergänztes Fullnode-Set => Signaturen ungültig
+        let mut forged = t2.clone();
+        forged.fullnodes.push("sybil".into());
+        assert!(peer.apply_remote_transition(&forged).is_err());
+
+        peer.apply_remote_transition(&t2).unwrap();
+        assert_eq!(peer.config.lock().unwrap().mode, OnboardingMode::Auto);
+        // Replay derselben Transition
+        assert!(peer.apply_remote_transition(&t2).is_err());
+
+        // Rückkehr zu Admin mit nur einem Fullnode => abgelehnt
+        let mut revert = ModeTransition {
+            from: OnboardingMode::Auto,
+            to: OnboardingMode::Admin,
+            fullnodes: vec![],
+            epoch: 2,
+            timestamp: 0,
+            approvals: vec![],
+        };
+        revert.approve(&keys[0]);
+        assert!(peer.apply_remote_transition(&revert).is_err());
+        assert!(cosigner.co_sign_transition(&revert).is_err());
+        assert_eq!(peer.config.lock().unwrap().mode, OnboardingMode::Auto);
+    }
+    #[test]
+    fn test_gossiped_transition_is_cosigned_and_applied() {
+        let ids: Vec<String> = (2..=4).map(|i| hex::encode(keypair(i).public.as_bytes())).collect();
+        let with_fullnodes = |s: OnboardingGlobalState| {
+            s.fullnode_list.lock().unwrap().extend(ids.iter().cloned());
+            s
+        };
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let origin = with_fullnodes(state().with_transition_gossip(Arc::new(keypair(2)), tx));
+        origin.maybe_switch_to_auto().unwrap();
+        let t = rx.try_recv().unwrap();
+
+        // Ohne eigenen Key: eine Signatur reicht nicht, kein Fehler
+        let observer = with_fullnodes(state());
+        assert!(!observer.on_transition_gossip(&t).unwrap());
+
+        // Mit Key: mitzeichnen, weitergossipen und übernehmen
+        let (tx2, mut rx2) = tokio::sync::mpsc::unbounded_channel();
+        let cosigner = with_fullnodes(state().with_transition_gossip(Arc::new(keypair(3)), tx2));
+        assert!(cosigner.on_transition_gossip(&t).unwrap());
+        assert_eq!(cosigner.config.lock().unwrap().mode, OnboardingMode::Auto);
+        let t2 = rx2.try_recv().unwrap();
+        assert_eq!(t2.valid_signers().len(), 2);
+
+        assert!(observer.on_transition_gossip(&t2).unwrap());
+        assert_eq!(observer.config.lock().unwrap().mode, OnboardingMode::Auto);
+    }
+
+    #[test]
+    fn test_settings_map_to_dkg_committee() {
+        let dkg: crate::onboarding::dkg::DkgCommitteeConfig = serde_json::from_value(serde_json::json!({
+            "threshold": 1,
+            "participants": [
+                {"node_id": "a", "addr": "127.0.0.1:1", "sign_key": "00", "enc_key": "00"},
+                {"node_id": "b", "addr": "127.0.0.1:2", "sign_key": "00", "enc_key": "00"},
+                {"node_id": "c", "addr": "127.0.0.1:3", "sign_key": "00", "enc_key": "00"}
+            ]
+        }))
+        .unwrap();
+        let settings = OnboardingSettings {
+            required_count_for_auto: 3,
+            admin_public_keys: vec![hex::encode(admin().public.as_bytes())],
+        };
+        settings.validate().unwrap();
+        let conf = settings.to_config(&dkg).unwrap();
+        assert_eq!((conf.k, conf.m), (3, 2));
+        assert_eq!(conf.mode, OnboardingMode::Admin);
+        assert_eq!(conf.admin_public_keys, vec![admin().public]);
+
+        let bad = OnboardingSettings { admin_public_keys: vec!["zz".into()], ..settings };
+        assert!(bad.validate().is_err());
+    }
 }
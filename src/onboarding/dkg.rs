@@ -0,0 +1,825 @@
+///////////////////////////////////////////////////////////
+// my_dex/src/onboarding/dkg.rs
+///////////////////////////////////////////////////////////
+//
+// Distributed Key Generation (Feldman-VSS) für das Onboarding-Komitee.
+//
+// Ersetzt die vorher zentral erzeugten Shares in auto_committee: jedes
+// Komitee-Mitglied ist Dealer eines eigenen Zufallspolynoms vom Grad t und
+// verteilt dessen Auswertungen verschlüsselt an alle anderen. Das gemeinsame
+// PublicKeySet ist die Summe aller Commitments, der eigene SecretKeyShare die
+// Summe aller empfangenen Werte. Kein Knoten kennt je den Gruppen-Secret-Key.
+//
+// Runden:
+//   1) Deal          => Commitment + pro Empfänger verschlüsselter Wert
+//   2) Complaint     => Empfänger meldet ungültigen/fehlenden Wert
+//   3) Justification => Dealer legt den beanstandeten Wert offen
+//   4) finalize()    => Dealer mit offener oder falscher Rechtfertigung
+//                       werden disqualifiziert, Rest bildet das Ergebnis
+//
+// Deal/Complaint/Justification werden an alle Teilnehmer gebroadcastet, damit
+// alle ehrlichen Knoten dieselbe qualifizierte Dealer-Menge bestimmen.
+// Der P2P-Kanal authentisiert keine Peers; jede Nachricht trägt daher die
+// Ed25519-Signatur ihres Absenders (`SignedDkgMessage`).
+//
+// Im Netz treibt `run_ceremony` eine Session über Kademlia
+// (`KademliaMessage::Dkg`); main startet sie, wenn `onboarding_dkg` in der
+// Node-Config Teilnehmer hat und noch kein Share gespeichert ist.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use rand_07::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tracing::{debug, info, warn};
+
+use threshold_crypto::ff::Field;
+use threshold_crypto::group::CurveAffine;
+use threshold_crypto::poly::{Commitment, Poly};
+use threshold_crypto::serde_impl::FieldWrap;
+use threshold_crypto::{Ciphertext, Fr, G1Affine};
+
+use crate::identity::keystore::{self, KdfParams, SealedBlob};
+use crate::kademlia::kademlia_service::{KademliaMessage, KademliaP2PAdapter};
+use crate::onboarding::auto_committee::{DKGState, PublicKeySet, SecretKeyShare};
+use crate::storage::db_layer::DexDB;
+use crate::utils::lock::LockRecover;
+
+/// Ein Komitee-Mitglied. `index` ist der Share-Index im späteren PublicKeySet,
+/// `enc_key` der BLS-Key, mit dem die Deal-Werte für diesen Knoten verschlüsselt werden,
+/// `sign_key` der Ed25519-Key, mit dem er seine DKG-Nachrichten signiert.
+#[derive(Clone, Debug)]
+pub struct DkgParticipant {
+    pub node_id: String,
+    pub index: usize,
+    pub enc_key: threshold_crypto::PublicKey,
+    pub sign_key: PublicKey,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DkgMessage {
+    Deal {
+        dealer: String,
+        commitment: Commitment,
+        /// node_id -> verschlüsselter Wert f_dealer(index+1)
+        encrypted_values: BTreeMap<String, Ciphertext>,
+    },
+    Complaint {
+        accuser: String,
+        dealer: String,
+    },
+    Justification {
+        dealer: String,
+        accuser: String,
+        /// bincode(FieldWrap<Fr>) im Klartext
+        value: Vec<u8>,
+    },
+}
+
+impl DkgMessage {
+    /// Knoten, der diese Nachricht senden darf.
+    pub fn sender(&self) -> &str {
+        match self {
+            DkgMessage::Deal { dealer, .. } => dealer,
+            DkgMessage::Complaint { accuser, .. } => accuser,
+            DkgMessage::Justification { dealer, .. } => dealer,
+        }
+    }
+}
+
+/// DKG-Nachricht mit Signatur des Absenders (`DkgMessage::sender`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedDkgMessage {
+    pub msg: DkgMessage,
+    pub signature: Vec<u8>,
+}
+
+impl SignedDkgMessage {
+    pub fn sign(msg: DkgMessage, kp: &Keypair) -> Result<Self> {
+        let signature = kp.sign(&dkg_signing_bytes(&msg)?).to_bytes().to_vec();
+        Ok(Self { msg, signature })
+    }
+
+    pub fn verify(&self, key: &PublicKey) -> bool {
+        let sig = match Signature::from_bytes(&self.signature) {
+            Ok(sig) => sig,
+            Err(_) => return false,
+        };
+        dkg_signing_bytes(&self.msg).is_ok_and(|bytes| key.verify(&bytes, &sig).is_ok())
+    }
+}
+
+fn dkg_signing_bytes(msg: &DkgMessage) -> Result<Vec<u8>> {
+    crate::utils::canonical::signing_bytes("my_dex/onboarding/dkg/v1", msg)
+        .map_err(|e| anyhow!("DKG signing bytes: {:?}", e))
+}
+
+/// Ausgang für DKG-Nachrichten (Broadcast an alle Komitee-Mitglieder).
+pub trait DkgTransport {
+    fn broadcast(&self, msg: &SignedDkgMessage) -> Result<()>;
+}
+
+impl DkgTransport for UnboundedSender<SignedDkgMessage> {
+    fn broadcast(&self, msg: &SignedDkgMessage) -> Result<()> {
+        self.send(msg.clone()).map_err(|e| anyhow!("DKG broadcast failed: {:?}", e))
+    }
+}
+
+/// Broadcast über Kademlia an die übrigen Komitee-Mitglieder.
+pub struct KademliaDkgTransport {
+    pub p2p: Arc<Mutex<dyn KademliaP2PAdapter + Send>>,
+    pub peers: Vec<SocketAddr>,
+}
+
+impl DkgTransport for KademliaDkgTransport {
+    fn broadcast(&self, msg: &SignedDkgMessage) -> Result<()> {
+        let wire = KademliaMessage::Dkg(msg.clone());
+        let p2p = self.p2p.lock_recover();
+        for addr in &self.peers {
+            p2p.send_kademlia_msg(*addr, &wire);
+        }
+        Ok(())
+    }
+}
+
+/// Ergebnis einer abgeschlossenen DKG-Runde für diesen Knoten.
+#[derive(Clone)]
+pub struct DkgOutput {
+    pub pk_set: PublicKeySet,
+    pub share: SecretKeyShare,
+    /// Dealer, deren Polynome in das Ergebnis eingeflossen sind
+    pub qualified: Vec<String>,
+    secret: Fr,
+}
+
+/// Von Hand, damit der rohe Share (`secret`) nie in Logs landet.
+impl std::fmt::Debug for DkgOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DkgOutput")
+            .field("pk_set", &self.pk_set)
+            .field("share_index", &self.share.index)
+            .field("qualified", &self.qualified)
+            .field("secret", &"<redacted>")
+            .finish()
+    }
+}
+
+impl DkgOutput {
+    /// Befüllt einen DKGState mit dem gemeinsamen PublicKeySet und dem eigenen Share.
+    pub fn into_dkg_state(self, node_id: &str) -> DKGState {
+        let mut st = DKGState::new(self.pk_set);
+        st.shares.insert(node_id.to_string(), self.share);
+        st
+    }
+}
+
+pub struct DkgSession {
+    node_id: String,
+    threshold: usize,
+    participants: BTreeMap<String, DkgParticipant>,
+    enc_secret: threshold_crypto::SecretKey,
+    poly: Option<Poly>,
+    commitments: BTreeMap<String, Commitment>,
+    /// dealer -> für mich bestimmter, verifizierter Wert
+    values: BTreeMap<String, Fr>,
+    /// (dealer, accuser) ohne gültige Rechtfertigung
+    open_complaints: BTreeSet<(String, String)>,
+    disqualified: BTreeSet<String>,
+}
+
+impl DkgSession {
+    /// `threshold` ist der Polynomgrad t => t+1 Shares signieren.
+    pub fn new(
+        node_id: &str,
+        threshold: usize,
+        participants: Vec<DkgParticipant>,
+        enc_secret: threshold_crypto::SecretKey,
+    ) -> Result<Self> {
+        if participants.len() <= threshold {
+            return Err(anyhow!("DKG needs more than {} participants, got {}", threshold, participants.len()));
+        }
+        let participants: BTreeMap<String, DkgParticipant> =
+            participants.into_iter().map(|p| (p.node_id.clone(), p)).collect();
+        if !participants.contains_key(node_id) {
+            return Err(anyhow!("Node {} is not part of the DKG committee", node_id));
+        }
+        Ok(Self {
+            node_id: node_id.to_string(),
+            threshold,
+            participants,
+            enc_secret,
+            poly: None,
+            commitments: BTreeMap::new(),
+            values: BTreeMap::new(),
+            open_complaints: BTreeSet::new(),
+            disqualified: BTreeSet::new(),
+        })
+    }
+
+    /// Runde 1: eigenes Polynom ziehen und Deal erzeugen.
+    pub fn deal(&mut self) -> Result<DkgMessage> {
+        let poly = Poly::random(self.threshold, &mut rand_07::thread_rng());
+        let commitment = poly.commitment();
+        let mut encrypted_values = BTreeMap::new();
+        for p in self.participants.values() {
+            let value = poly.evaluate(p.index + 1);
+            encrypted_values.insert(p.node_id.clone(), p.enc_key.encrypt(encode_fr(&value)?));
+        }
+        self.poly = Some(poly);
+        Ok(DkgMessage::Deal { dealer: self.node_id.clone(), commitment, encrypted_values })
+    }
+
+    /// Prüft die Signatur gegen den `sign_key` des Absenders und verarbeitet
+    /// die Nachricht dann wie `handle_message`.
+    pub fn handle_signed(&mut self, env: &SignedDkgMessage) -> Result<Vec<DkgMessage>> {
+        let from = env.msg.sender().to_string();
+        let key = self
+            .participants
+            .get(&from)
+            .map(|p| p.sign_key)
+            .ok_or_else(|| anyhow!("DKG message from unknown participant {}", from))?;
+        if !env.verify(&key) {
+            return Err(anyhow!("DKG message from {} has an invalid signature", from));
+        }
+        self.handle_message(&from, &env.msg)
+    }
+
+    /// Verarbeitet eine Nachricht. `from` muss bereits authentisiert sein
+    /// (siehe `handle_signed`). Rückgabe: Nachrichten, die zu broadcasten sind.
+    pub fn handle_message(&mut self, from: &str, msg: &DkgMessage) -> Result<Vec<DkgMessage>> {
+        if msg.sender() != from || !self.participants.contains_key(from) {
+            return Err(anyhow!("DKG message from {} not accepted (claims {})", from, msg.sender()));
+        }
+        match msg {
+            DkgMessage::Deal { dealer, commitment, encrypted_values } => {
+                Ok(self.on_deal(dealer, commitment, encrypted_values))
+            }
+            DkgMessage::Complaint { accuser, dealer } => self.on_complaint(accuser, dealer),
+            DkgMessage::Justification { dealer, accuser, value } => {
+                self.on_justification(dealer, accuser, value);
+                Ok(vec![])
+            }
+        }
+    }
+
+    fn on_deal(
+        &mut self,
+        dealer: &str,
+        commitment: &Commitment,
+        encrypted_values: &BTreeMap<String, Ciphertext>,
+    ) -> Vec<DkgMessage> {
+        if self.commitments.contains_key(dealer) {
+            debug!("DKG: doppelter Deal von {} ignoriert", dealer);
+            return vec![];
+        }
+        if commitment.degree() != self.threshold {
+            warn!("DKG: Dealer {} hat falschen Grad {} => disqualifiziert", dealer, commitment.degree());
+            self.disqualified.insert(dealer.to_string());
+            return vec![];
+        }
+        self.commitments.insert(dealer.to_string(), commitment.clone());
+
+        let my_index = self.participants[&self.node_id].index;
+        let value = encrypted_values
+            .get(&self.node_id)
+            .filter(|ct| ct.verify())
+            .and_then(|ct| self.enc_secret.decrypt(ct))
+            .and_then(|bytes| decode_fr(&bytes).ok())
+            .filter(|v| value_matches(commitment, my_index, v));
+        match value {
+            Some(v) => {
+                self.values.insert(dealer.to_string(), v);
+                vec![]
+            }
+            None => {
+                warn!("DKG: ungültiger Wert von Dealer {} => Complaint", dealer);
+                self.open_complaints.insert((dealer.to_string(), self.node_id.clone()));
+                vec![DkgMessage::Complaint { accuser: self.node_id.clone(), dealer: dealer.to_string() }]
+            }
+        }
+    }
+
+    fn on_complaint(&mut self, accuser: &str, dealer: &str) -> Result<Vec<DkgMessage>> {
+        self.open_complaints.insert((dealer.to_string(), accuser.to_string()));
+        if dealer != self.node_id {
+            return Ok(vec![]);
+        }
+        // Wir sind der Dealer => Wert öffentlich offenlegen
+        let poly = self.poly.as_ref().ok_or_else(|| anyhow!("Complaint before own deal"))?;
+        let idx = self
+            .participants
+            .get(accuser)
+            .map(|p| p.index)
+            .ok_or_else(|| anyhow!("Unknown accuser {}", accuser))?;
+        let value = encode_fr(&poly.evaluate(idx + 1))?;
+        Ok(vec![DkgMessage::Justification {
+            dealer: self.node_id.clone(),
+            accuser: accuser.to_string(),
+            value,
+        }])
+    }
+
+    fn on_justification(&mut self, dealer: &str, accuser: &str, value: &[u8]) {
+        let (commitment, idx) = match (self.commitments.get(dealer), self.participants.get(accuser)) {
+            (Some(c), Some(p)) => (c, p.index),
+            _ => return,
+        };
+        match decode_fr(value) {
+            Ok(v) if value_matches(commitment, idx, &v) => {
+                self.open_complaints.remove(&(dealer.to_string(), accuser.to_string()));
+                if accuser == self.node_id {
+                    self.values.insert(dealer.to_string(), v);
+                }
+            }
+            _ => {
+                warn!("DKG: Rechtfertigung von {} für {} ungültig => disqualifiziert", dealer, accuser);
+                self.disqualified.insert(dealer.to_string());
+            }
+        }
+    }
+
+    /// Teilnehmer, von denen weder ein Deal kam noch die disqualifiziert sind.
+    pub fn missing_deals(&self) -> usize {
+        self.participants
+            .keys()
+            .filter(|id| !self.commitments.contains_key(*id) && !self.disqualified.contains(*id))
+            .count()
+    }
+
+    /// Dealer, deren Beitrag gezählt wird (Deal erhalten, keine offene Beschwerde).
+    pub fn qualified_dealers(&self) -> Vec<String> {
+        self.commitments
+            .keys()
+            .filter(|d| !self.disqualified.contains(*d))
+            .filter(|d| !self.open_complaints.iter().any(|(dealer, _)| dealer == *d))
+            .cloned()
+            .collect()
+    }
+
+    /// Runde 4: PublicKeySet und eigenen Share aus den qualifizierten Dealern bilden.
+    pub fn finalize(&self) -> Result<DkgOutput> {
+        let qualified = self.qualified_dealers();
+        if qualified.len() <= self.threshold {
+            return Err(anyhow!(
+                "DKG failed: only {} qualified dealers, need {}",
+                qualified.len(),
+                self.threshold + 1
+            ));
+        }
+        let mut commitment: Option<Commitment> = None;
+        let mut secret = Fr::zero();
+        for dealer in &qualified {
+            let value = self
+                .values
+                .get(dealer)
+                .ok_or_else(|| anyhow!("Missing value from qualified dealer {}", dealer))?;
+            secret.add_assign(value);
+            match commitment.as_mut() {
+                Some(c) => *c += &self.commitments[dealer],
+                None => commitment = Some(self.commitments[dealer].clone()),
+            }
+        }
+        let pk_set = threshold_crypto::PublicKeySet::from(commitment.expect("qualified is not empty"));
+        let index = self.participants[&self.node_id].index;
+        let share = threshold_crypto::SecretKeyShare::from_mut(&mut secret.clone());
+        if pk_set.public_key_share(index) != share.public_key_share() {
+            return Err(anyhow!("DKG share does not match the combined commitment"));
+        }
+        info!("DKG abgeschlossen => node={}, qualified={:?}", self.node_id, qualified);
+        Ok(DkgOutput {
+            pk_set: PublicKeySet::new(pk_set),
+            share: SecretKeyShare { index, share },
+            qualified,
+            secret,
+        })
+    }
+}
+
+fn value_matches(commitment: &Commitment, index: usize, value: &Fr) -> bool {
+    commitment.evaluate(index + 1) == G1Affine::one().mul(*value)
+}
+
+fn encode_fr(value: &Fr) -> Result<Vec<u8>> {
+    Ok(bincode::serialize(&FieldWrap(value))?)
+}
+
+fn decode_fr(bytes: &[u8]) -> Result<Fr> {
+    let w: FieldWrap<Fr> = bincode::deserialize(bytes)?;
+    Ok(w.0)
+}
+
+// ------------------------------------------------------------
+// Persistenz des eigenen Shares (verschlüsselt)
+// ------------------------------------------------------------
+
+pub fn dkg_share_key(node_id: &str) -> String {
+    format!("onboarding/dkg_share/{}", node_id)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PersistedDkgShare {
+    index: usize,
+    pk_set: threshold_crypto::PublicKeySet,
+    kdf: KdfParams,
+    sealed: SealedBlob,
+}
+
+/// Speichert PublicKeySet (Klartext) und Share (AES-GCM, Schlüssel per
+/// Argon2id aus `pass` wie im Keystore).
+pub fn persist_share(db: &DexDB, node_id: &str, out: &DkgOutput, pass: &str) -> Result<()> {
+    let kdf = KdfParams::recommended();
+    let key = kdf.derive(pass)?;
+    let sealed = keystore::seal(&key, &encode_fr(&out.secret)?, dkg_share_key(node_id).as_bytes())?;
+    let rec = PersistedDkgShare {
+        index: out.share.index,
+        pk_set: out.pk_set.inner.clone(),
+        kdf,
+        sealed,
+    };
+    db.store_struct(&dkg_share_key(node_id), &rec)?;
+    Ok(())
+}
+
+/// Lädt einen gespeicherten Share; `None`, wenn noch keine DKG lief.
+pub fn load_share(db: &DexDB, node_id: &str, pass: &str) -> Result<Option<DKGState>> {
+    let rec: Option<PersistedDkgShare> = db.load_struct(&dkg_share_key(node_id))?;
+    let rec = match rec {
+        Some(r) => r,
+        None => return Ok(None),
+    };
+    let key = rec.kdf.derive(pass)?;
+    let plain = keystore::open(&key, &rec.sealed, dkg_share_key(node_id).as_bytes())
+        .map_err(|e| anyhow!("Cannot decrypt DKG share: {:?}", e))?;
+    let mut secret = decode_fr(&plain)?;
+    let share = threshold_crypto::SecretKeyShare::from_mut(&mut secret);
+    if rec.pk_set.public_key_share(rec.index) != share.public_key_share() {
+        return Err(anyhow!("Stored DKG share does not match its PublicKeySet"));
+    }
+    let mut st = DKGState::new(PublicKeySet::new(rec.pk_set));
+    st.shares.insert(node_id.to_string(), SecretKeyShare { index: rec.index, share });
+    Ok(Some(st))
+}
+
+/// Treibt die eigene Session im Netz: Deal senden, empfangene Nachrichten
+/// (nur mit gültiger Signatur) verarbeiten und Antworten broadcasten. Endet,
+/// sobald alle Deals da sind und `quiet` lang nichts mehr kam (Complaints,
+/// Justifications), spätestens nach `timeout`. Wer später dazukommt, bekommt
+/// den eigenen Deal erneut, sobald sein erster Deal eintrifft.
+pub async fn run_ceremony<T: DkgTransport + ?Sized>(
+    session: &mut DkgSession,
+    signer: &Keypair,
+    transport: &T,
+    inbound: &mut UnboundedReceiver<SignedDkgMessage>,
+    quiet: Duration,
+    timeout: Duration,
+) -> Result<DkgOutput> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let own_id = session.node_id.clone();
+    let own_deal = SignedDkgMessage::sign(session.deal()?, signer)?;
+    let mut outgoing = session.handle_message(&own_id, &own_deal.msg)?;
+    transport.broadcast(&own_deal)?;
+    loop {
+        // Eigene Nachrichten verarbeitet die Session selbst, der Bus liefert nur fremde
+        while let Some(msg) = outgoing.pop() {
+            outgoing.extend(session.handle_message(&own_id, &msg)?);
+            transport.broadcast(&SignedDkgMessage::sign(msg, signer)?)?;
+        }
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        let wait = if session.missing_deals() == 0 { quiet.min(remaining) } else { remaining };
+        let env = match tokio::time::timeout(wait, inbound.recv()).await {
+            Ok(Some(env)) => env,
+            Ok(None) | Err(_) => break,
+        };
+        let first_deal = matches!(&env.msg, DkgMessage::Deal { dealer, .. } if !session.commitments.contains_key(dealer));
+        match session.handle_signed(&env) {
+            Ok(replies) => {
+                outgoing.extend(replies);
+                if first_deal {
+                    transport.broadcast(&own_deal)?;
+                }
+            }
+            Err(e) => warn!("DKG: Nachricht verworfen => {}", e),
+        }
+    }
+    session.finalize()
+}
+
+/// `onboarding_dkg.participants` in der Node-Config; der Share-Index ergibt
+/// sich aus der Reihenfolge.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DkgPeerConfig {
+    pub node_id: String,
+    pub addr: String,
+    /// Ed25519-Key (hex), siehe `node_dkg_keys`
+    pub sign_key: String,
+    /// BLS-Key (hex, 48 Byte) für die verschlüsselten Deal-Werte
+    pub enc_key: String,
+}
+
+/// `onboarding_dkg` in der Node-Config. Ohne Teilnehmer läuft keine Zeremonie.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DkgCommitteeConfig {
+    /// Polynomgrad t => t+1 Shares signieren
+    pub threshold: usize,
+    pub participants: Vec<DkgPeerConfig>,
+    pub quiet_period_ms: u64,
+    pub timeout_ms: u64,
+}
+
+impl Default for DkgCommitteeConfig {
+    fn default() -> Self {
+        Self { threshold: 1, participants: Vec::new(), quiet_period_ms: 5_000, timeout_ms: 120_000 }
+    }
+}
+
+impl DkgCommitteeConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.participants.is_empty()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        if self.participants.len() <= self.threshold {
+            return Err(format!(
+                "{} participants cannot reach threshold {} (need > threshold)",
+                self.participants.len(),
+                self.threshold
+            ));
+        }
+        if self.quiet_period_ms == 0 || self.timeout_ms < self.quiet_period_ms {
+            return Err("need 0 < quiet_period_ms <= timeout_ms".into());
+        }
+        let mut ids = BTreeSet::new();
+        for p in &self.participants {
+            if p.node_id.trim().is_empty() || !ids.insert(p.node_id.as_str()) {
+                return Err(format!("participant node_id `{}` empty or duplicate", p.node_id));
+            }
+            p.addr
+                .parse::<SocketAddr>()
+                .map_err(|e| format!("participant {}: addr `{}`: {}", p.node_id, p.addr, e))?;
+        }
+        self.participants().map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    pub fn participants(&self) -> Result<Vec<DkgParticipant>> {
+        self.participants
+            .iter()
+            .enumerate()
+            .map(|(index, p)| {
+                let enc: [u8; 48] = hex::decode(&p.enc_key)
+                    .ok()
+                    .and_then(|b| b.try_into().ok())
+                    .ok_or_else(|| anyhow!("participant {}: enc_key must be 48 hex bytes", p.node_id))?;
+                let enc_key = threshold_crypto::PublicKey::from_bytes(enc)
+                    .map_err(|e| anyhow!("participant {}: enc_key invalid: {:?}", p.node_id, e))?;
+                let sign_key = hex::decode(&p.sign_key)
+                    .ok()
+                    .and_then(|b| PublicKey::from_bytes(&b).ok())
+                    .ok_or_else(|| anyhow!("participant {}: sign_key invalid", p.node_id))?;
+                Ok(DkgParticipant { node_id: p.node_id.clone(), index, enc_key, sign_key })
+            })
+            .collect()
+    }
+
+    /// Adressen aller Teilnehmer außer `own_id`.
+    pub fn peer_addrs(&self, own_id: &str) -> Vec<SocketAddr> {
+        self.participants
+            .iter()
+            .filter(|p| p.node_id != own_id)
+            .filter_map(|p| p.addr.parse().ok())
+            .collect()
+    }
+}
+
+/// Signier- und BLS-Schlüssel eines Nodes für die Zeremonie. Der BLS-Key wird
+/// deterministisch aus dem Ed25519-Secret abgeleitet, muss also nicht
+/// zusätzlich gespeichert werden; beide Public Keys gehören in die
+/// `participants`-Einträge der anderen Nodes.
+pub fn node_dkg_keys(signer: &Keypair) -> threshold_crypto::SecretKey {
+    rand_07::rngs::StdRng::from_seed(signer.secret.to_bytes()).gen()
+}
+
+/// Treibt mehrere lokale Sessions über einen gemeinsamen Bus bis zum Ende
+/// (für Tests/Simulation; im Netz kommen die Nachrichten über P2P).
+pub fn run_local_rounds(sessions: &mut HashMap<String, DkgSession>, initial: Vec<DkgMessage>) -> Result<()> {
+    let mut queue = initial;
+    while !queue.is_empty() {
+        let mut next = Vec::new();
+        for msg in &queue {
+            for session in sessions.values_mut() {
+                next.extend(session.handle_message(msg.sender(), msg)?);
+            }
+        }
+        queue = next;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::onboarding::auto_committee::{combine_partial_signatures, partial_sign, verify_threshold_sig};
+    use ed25519_dalek::SecretKey as EdSecretKey;
+
+    fn signer(i: usize) -> Keypair {
+        let secret = EdSecretKey::from_bytes(&[i as u8 + 1; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    fn participants(n: usize) -> Vec<DkgParticipant> {
+        (0..n)
+            .map(|i| DkgParticipant {
+                node_id: format!("v{}", i),
+                index: i,
+                enc_key: node_dkg_keys(&signer(i)).public_key(),
+                sign_key: signer(i).public,
+            })
+            .collect()
+    }
+
+    fn committee(n: usize, t: usize) -> HashMap<String, DkgSession> {
+        (0..n)
+            .map(|i| {
+                let id = format!("v{}", i);
+                let s = DkgSession::new(&id, t, participants(n), node_dkg_keys(&signer(i))).unwrap();
+                (id, s)
+            })
+            .collect()
+    }
+
+    /// Broadcast an die Eingänge aller anderen Sessions
+    struct Bus(Vec<UnboundedSender<SignedDkgMessage>>);
+
+    impl DkgTransport for Bus {
+        fn broadcast(&self, msg: &SignedDkgMessage) -> Result<()> {
+            for tx in &self.0 {
+                let _ = tx.send(msg.clone());
+            }
+            Ok(())
+        }
+    }
+
+    fn sign_with(outputs: &[(String, DkgOutput)], signers: &[&str], msg: &[u8]) -> Vec<u8> {
+        let pk_set = &outputs[0].1.pk_set;
+        let partials: Vec<_> = outputs
+            .iter()
+            .filter(|(id, _)| signers.contains(&id.as_str()))
+            .map(|(_, o)| (o.share.index, partial_sign(&o.share, msg).unwrap()))
+            .collect();
+        combine_partial_signatures(pk_set, &partials, signers.len(), msg).unwrap()
+    }
+
+    #[test]
+    fn test_dkg_produces_working_threshold_key() {
+        let mut sessions = committee(4, 1);
+        let deals: Vec<DkgMessage> = sessions.values_mut().map(|s| s.deal().unwrap()).collect();
+        run_local_rounds(&mut sessions, deals).unwrap();
+
+        let mut outputs: Vec<(String, DkgOutput)> =
+            sessions.iter().map(|(id, s)| (id.clone(), s.finalize().unwrap())).collect();
+        outputs.sort_by(|a, b| a.0.cmp(&b.0));
+        let group = outputs[0].1.pk_set.group_key_bytes();
+        assert!(outputs.iter().all(|(_, o)| o.pk_set.group_key_bytes() == group));
+
+        let msg = b"onboard fn_new";
+        let sig = sign_with(&outputs, &["v1", "v3"], msg);
+        assert!(verify_threshold_sig(&outputs[0].1.pk_set, msg, &sig));
+        // Andere Teilmenge => gleiche Gruppen-Signatur
+        assert_eq!(sign_with(&outputs, &["v0", "v2"], msg), sig);
+
+        // Debug-Ausgabe enthält den Share nicht
+        let out = &outputs[0].1;
+        let dbg = format!("{:?}", out);
+        assert!(dbg.contains("<redacted>"));
+        assert!(!dbg.contains(&format!("{:?}", out.secret)));
+    }
+
+    #[test]
+    fn test_faulty_dealer_is_disqualified() {
+        let mut sessions = committee(4, 1);
+        let mut deals: Vec<DkgMessage> = sessions.values_mut().map(|s| s.deal().unwrap()).collect();
+        // v2 verteilt für v0 einen Wert, der nicht zum Commitment passt
+        let v0_key = sessions["v0"].participants["v0"].enc_key.clone();
+        for d in deals.iter_mut() {
+            if let DkgMessage::Deal { dealer, encrypted_values, .. } = d {
+                if dealer == "v2" {
+                    encrypted_values.insert("v0".into(), v0_key.encrypt(encode_fr(&Fr::one()).unwrap()));
+                }
+            }
+        }
+        // v2 rechtfertigt sich nicht => eigene Session aus dem Bus nehmen
+        let mut faulty = sessions.remove("v2").unwrap();
+        run_local_rounds(&mut sessions, deals.clone()).unwrap();
+        for d in &deals {
+            faulty.handle_message(d.sender(), d).unwrap();
+        }
+
+        let outputs: Vec<(String, DkgOutput)> =
+            sessions.iter().map(|(id, s)| (id.clone(), s.finalize().unwrap())).collect();
+        for (_, o) in &outputs {
+            assert!(!o.qualified.contains(&"v2".to_string()));
+            assert_eq!(o.pk_set.group_key_bytes(), outputs[0].1.pk_set.group_key_bytes());
+        }
+        let msg = b"mode switch";
+        let sig = sign_with(&outputs, &["v0", "v1"], msg);
+        assert!(verify_threshold_sig(&outputs[0].1.pk_set, msg, &sig));
+    }
+
+    #[test]
+    fn test_justified_complaint_keeps_dealer() {
+        let mut sessions = committee(3, 1);
+        let deals: Vec<DkgMessage> = sessions.values_mut().map(|s| s.deal().unwrap()).collect();
+        // Unbegründete Beschwerde von v1 gegen v0 => v0 legt Wert offen
+        let mut queue = deals;
+        queue.push(DkgMessage::Complaint { accuser: "v1".into(), dealer: "v0".into() });
+        run_local_rounds(&mut sessions, queue).unwrap();
+        for s in sessions.values() {
+            assert!(s.qualified_dealers().contains(&"v0".to_string()));
+        }
+    }
+
+    #[test]
+    fn test_persisted_share_roundtrip() {
+        let mut sessions = committee(3, 1);
+        let deals: Vec<DkgMessage> = sessions.values_mut().map(|s| s.deal().unwrap()).collect();
+        run_local_rounds(&mut sessions, deals).unwrap();
+        let out = sessions["v1"].finalize().unwrap();
+
+        let db = DexDB::in_memory();
+        persist_share(&db, "v1", &out, "secret-pass").unwrap();
+        let st = load_share(&db, "v1", "secret-pass").unwrap().unwrap();
+        assert_eq!(st.pk_set.group_key_bytes(), out.pk_set.group_key_bytes());
+        assert_eq!(st.shares["v1"].share.public_key_share(), out.share.share.public_key_share());
+        assert!(load_share(&db, "v1", "wrong-pass").is_err());
+        assert!(load_share(&db, "v0", "secret-pass").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_networked_ceremony_checks_signatures() {
+        let n = 3;
+        let (txs, rxs): (Vec<_>, Vec<_>) = (0..n).map(|_| tokio::sync::mpsc::unbounded_channel()).unzip();
+        let mut sessions = committee(n, 1);
+
+        // Deal mit fremder Signatur => verworfen, kein Commitment von v2
+        let mut forger = DkgSession::new("v2", 1, participants(n), node_dkg_keys(&signer(2))).unwrap();
+        let forged = SignedDkgMessage::sign(forger.deal().unwrap(), &signer(0)).unwrap();
+        assert!(sessions.get_mut("v0").unwrap().handle_signed(&forged).is_err());
+        assert_eq!(sessions["v0"].missing_deals(), n);
+
+        let mut handles = Vec::new();
+        for (i, mut rx) in rxs.into_iter().enumerate() {
+            let mut session = sessions.remove(&format!("v{}", i)).unwrap();
+            let others: Vec<_> = txs.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, tx)| tx.clone()).collect();
+            handles.push(tokio::spawn(async move {
+                let bus = Bus(others);
+                run_ceremony(&mut session, &signer(i), &bus, &mut rx, Duration::from_millis(200), Duration::from_secs(10))
+                    .await
+                    .unwrap()
+            }));
+        }
+        let mut outputs = Vec::new();
+        for (i, h) in handles.into_iter().enumerate() {
+            outputs.push((format!("v{}", i), h.await.unwrap()));
+        }
+        for (_, o) in &outputs {
+            assert_eq!(o.qualified.len(), n);
+            assert_eq!(o.pk_set.group_key_bytes(), outputs[0].1.pk_set.group_key_bytes());
+        }
+        let msg = b"onboard fn_net";
+        let sig = sign_with(&outputs, &["v0", "v2"], msg);
+        assert!(verify_threshold_sig(&outputs[0].1.pk_set, msg, &sig));
+    }
+
+    #[test]
+    fn test_committee_config_parses_participants() {
+        let peer = |i: usize| DkgPeerConfig {
+            node_id: format!("v{}", i),
+            addr: format!("127.0.0.1:90{:02}", i),
+            sign_key: hex::encode(signer(i).public.as_bytes()),
+            enc_key: hex::encode(node_dkg_keys(&signer(i)).public_key().to_bytes()),
+        };
+        let mut cfg = DkgCommitteeConfig { participants: (0..3).map(peer).collect(), ..Default::default() };
+        cfg.validate().unwrap();
+        let parts = cfg.participants().unwrap();
+        assert_eq!(parts[2].index, 2);
+        assert_eq!(parts[1].sign_key, signer(1).public);
+        assert_eq!(cfg.peer_addrs("v0").len(), 2);
+
+        cfg.participants[1].enc_key = "abcd".into();
+        assert!(cfg.validate().is_err());
+        cfg.participants[1] = peer(0);
+        assert!(cfg.validate().is_err());
+        assert!(DkgCommitteeConfig::default().validate().is_ok());
+        assert!(!DkgCommitteeConfig::default().is_enabled());
+    }
+}
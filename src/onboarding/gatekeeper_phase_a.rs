@@ -155,7 +155,7 @@ impl GatekeeperPhaseA {
             &req.software_hash,
             &req.db_hash,
             now
-        );
+        )?;
         let signature = self.admin_keypair.sign(&message);
         let cert = OnboardingCertificate {
             node_id: req.node_id.clone(),
@@ -182,16 +182,30 @@ fn build_onboarding_message(
     software_hash: &str,
     db_hash: &str,
     ts: u64,
-) -> Vec<u8> {
-    // Kein Demo, sondern reale Concatenation
-    let mut msg = Vec::new();
-    msg.extend_from_slice(b"PhaseA-Onboarding:");
-    msg.extend_from_slice(node_id.as_bytes());
-    msg.extend_from_slice(node_pubkey);
-    msg.extend_from_slice(software_hash.as_bytes());
-    msg.extend_from_slice(db_hash.as_bytes());
-    msg.extend_from_slice(&ts.to_le_bytes());
-    msg
+) -> Result<Vec<u8>, DexError> {
+    #[derive(Serialize)]
+    struct PhaseAMessage<'a> {
+        node_id: &'a str,
+        #[serde(with = "serde_bytes_array")]
+        node_pubkey: &'a [u8; 32],
+        software_hash: &'a str,
+        db_hash: &'a str,
+        issued_at: u64,
+    }
+    crate::utils::canonical::signing_bytes("my_dex/onboarding/phase_a/v1", &PhaseAMessage {
+        node_id,
+        node_pubkey,
+        software_hash,
+        db_hash,
+        issued_at: ts,
+    })
+}
+
+/// Pubkey als CBOR-Bytestring statt als Array aus 32 Integern.
+mod serde_bytes_array {
+    pub fn serialize<S: serde::Serializer>(bytes: &&[u8; 32], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_bytes(&bytes[..])
+    }
 }
 
 ////////////////////////////////////////////////////////////
@@ -211,7 +225,7 @@ pub fn verify_phase_a_onboarding_cert(
         software_hash,
         db_hash,
         cert.issued_at
-    );
+    )?;
     let sig = Signature::from_bytes(&cert.signature)
         .map_err(|_| DexError::Other("Ung�ltige Signatur im Zertifikat (Format)".into()))?;
 
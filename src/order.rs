@@ -8,11 +8,17 @@
 // Schlüssel des Users geprüft. Dadurch wird sichergestellt, dass nur authentische
 // Orders ins Orderbuch gelangen und auch Löschungen (Cancel-Orders) nur von den
 // Eigentümern initiiert werden können.
+//
+// Signiert wird über kanonisches CBOR (utils::canonical) mit eigener Domain je
+// Nachrichtentyp, eine Order-Signatur passt also nie auf ein Cancel.
 use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
 use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
 use serde::{Serialize, Deserialize};
 
+use crate::error::DexError;
+use crate::utils::canonical;
+
 /// Definiert den Ordertyp (Buy oder Sell).
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum OrderType {
@@ -47,41 +53,47 @@ impl Order {
         }
     }
 
-    /// Signiert die Order mit dem gegebenen Keypair. Dabei wird eine
-    /// Signatur über einen konsistenten Nachrichten-String (Order-ID, User-ID, OrderType,
-    /// Amount, Price und Timestamp) erzeugt.
+    /// Signiert die Order mit dem gegebenen Keypair über `signing_bytes`
+    /// (Order-ID, User-ID, OrderType, Amount, Price und Timestamp).
     pub fn sign(&mut self, keypair: &Keypair) -> Result<()> {
         // In der Produktion sollte sichergestellt werden, dass der User (user_id)
         // dem Schlüssel des Keypairs entspricht.
-        let message = self.message_to_sign();
-        let signature = keypair.sign(message.as_bytes());
-        self.signature = Some(signature);
+        let bytes = self.signing_bytes()?;
+        self.signature = Some(keypair.sign(&bytes));
         Ok(())
     }
 
     /// Überprüft die digitale Signatur der Order anhand des bekannten öffentlichen Schlüssels.
     pub fn verify(&self, public_key: &PublicKey) -> Result<()> {
         if let Some(signature) = &self.signature {
-            let message = self.message_to_sign();
-            public_key.verify(message.as_bytes(), signature)
+            let bytes = self.signing_bytes()?;
+            public_key.verify(&bytes, signature)
                 .map_err(|e| anyhow!("Order-Signatur-Überprüfung fehlgeschlagen: {:?}", e))
         } else {
             Err(anyhow!("Order besitzt keine Signatur"))
         }
     }
 
-    /// Erzeugt den Nachrichtentext, der signiert bzw. überprüft wird.
-    fn message_to_sign(&self) -> String {
-        // Der String enthält alle relevanten Felder. Änderungen an einem dieser Felder
-        // führen zu einer ungültigen Signatur.
-        format!("{}|{}|{:?}|{}|{}|{}", 
-            self.order_id, 
-            self.user_id, 
-            self.order_type, 
-            self.amount, 
-            self.price, 
-            self.timestamp.timestamp()
-        )
+    /// Kanonische Bytes, die signiert bzw. überprüft werden. Änderungen an
+    /// einem dieser Felder führen zu einer ungültigen Signatur.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct OrderSigningView<'a> {
+            order_id: u64,
+            user_id: &'a str,
+            order_type: &'a OrderType,
+            amount: f64,
+            price: f64,
+            timestamp: i64,
+        }
+        canonical::signing_bytes("my_dex/order/v1", &OrderSigningView {
+            order_id: self.order_id,
+            user_id: &self.user_id,
+            order_type: &self.order_type,
+            amount: self.amount,
+            price: self.price,
+            timestamp: self.timestamp.timestamp(),
+        })
     }
 }
 
@@ -108,25 +120,65 @@ impl CancelOrder {
 
     /// Signiert die CancelOrder-Nachricht mit dem gegebenen Keypair.
     pub fn sign(&mut self, keypair: &Keypair) -> Result<()> {
-        let message = self.message_to_sign();
-        let signature = keypair.sign(message.as_bytes());
-        self.signature = Some(signature);
+        let bytes = self.signing_bytes()?;
+        self.signature = Some(keypair.sign(&bytes));
         Ok(())
     }
 
     /// Überprüft die Signatur der CancelOrder anhand des öffentlichen Schlüssels.
     pub fn verify(&self, public_key: &PublicKey) -> Result<()> {
         if let Some(signature) = &self.signature {
-            let message = self.message_to_sign();
-            public_key.verify(message.as_bytes(), signature)
+            let bytes = self.signing_bytes()?;
+            public_key.verify(&bytes, signature)
                 .map_err(|e| anyhow!("CancelOrder-Signatur-Überprüfung fehlgeschlagen: {:?}", e))
         } else {
             Err(anyhow!("CancelOrder besitzt keine Signatur"))
         }
     }
 
-    /// Erzeugt den Nachrichtentext für die Signatur der CancelOrder.
-    fn message_to_sign(&self) -> String {
-        format!("{}|{}|{}", self.order_id, self.user_id, self.timestamp.timestamp())
+    /// Kanonische Bytes für die Signatur der CancelOrder.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        #[derive(Serialize)]
+        struct CancelOrderSigningView<'a> {
+            order_id: u64,
+            user_id: &'a str,
+            timestamp: i64,
+        }
+        canonical::signing_bytes("my_dex/order/cancel/v1", &CancelOrderSigningView {
+            order_id: self.order_id,
+            user_id: &self.user_id,
+            timestamp: self.timestamp.timestamp(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair() -> Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[9; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    #[test]
+    fn test_order_signature_does_not_verify_as_cancel() {
+        let kp = keypair();
+        let mut order = Order::new(1, "alice".into(), OrderType::Buy, 0.5, 100.0);
+        order.sign(&kp).unwrap();
+        assert!(order.verify(&kp.public).is_ok());
+
+        let mut tampered = order.clone();
+        tampered.order_type = OrderType::Sell;
+        assert!(tampered.verify(&kp.public).is_err());
+
+        // Gleiche ID/User/Zeit, aber andere Domain => Signatur nicht übertragbar
+        let mut cancel = CancelOrder::new(1, "alice".into());
+        cancel.timestamp = order.timestamp;
+        cancel.signature = order.signature;
+        assert!(cancel.verify(&kp.public).is_err());
+        cancel.sign(&kp).unwrap();
+        assert!(cancel.verify(&kp.public).is_ok());
     }
 }
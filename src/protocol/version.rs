@@ -0,0 +1,212 @@
+////////////////////////////////////////////////////
+/// my_dex/src/protocol/version.rs
+////////////////////////////////////////////////////
+//
+// Versions- und Capability-Austausch direkt nach dem Noise-Handshake.
+//
+// Bevor irgendeine RPC (KademliaMessage, Gossip, ...) fließt, schicken beide
+// Seiten ein verschlüsseltes `Hello` mit ihrem unterstützten Versionsbereich
+// und ihren Feature-Flags. Beide berechnen daraus deterministisch dasselbe
+// Ergebnis:
+//   - Version = höchste Version, die beide sprechen (min der Maxima),
+//     sofern sie über beiden Minima liegt.
+//   - Features = Schnittmenge; Flags aus `REQUIRED_FEATURES` müssen beide haben.
+// Passt das nicht, wird die Verbindung mit einem klaren Grund abgelehnt.
+//
+// Rolling Upgrade: eine neue Version erhöht `PROTOCOL_VERSION`, lässt
+// `MIN_PROTOCOL_VERSION` aber so lange stehen, bis alle Nodes aktualisiert sind.
+// Neue Formate werden nur genutzt, wenn das Feature-Flag ausgehandelt wurde:
+// `required_features` ordnet jeder Nachricht ihr Flag zu, der Adapter sendet
+// sie nur bei ausgehandeltem Flag und trennt Peers, die es trotzdem tun.
+//
+// Das Hello muss innerhalb von `HELLO_TIMEOUT` eintreffen, sonst wird die
+// Verbindung geschlossen (stumme Peers binden keine Tasks).
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::kademlia::kademlia_service::KademliaMessage;
+
+/// Höchste Protokollversion dieses Builds.
+pub const PROTOCOL_VERSION: u16 = 2;
+/// Älteste Version, mit der dieser Build noch spricht. Builds vor Version 2
+/// kennen kein Hello und können den Austausch gar nicht führen.
+pub const MIN_PROTOCOL_VERSION: u16 = 2;
+
+/// So lange wartet jede Seite nach dem Noise-Handshake auf das Hello.
+pub const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Feature-Flags (Bitmaske).
+pub const FEATURE_SIGNED_DELTAS: u32 = 1 << 0;
+pub const FEATURE_ANTI_ENTROPY: u32 = 1 << 1;
+pub const FEATURE_SHARD_SNAPSHOT: u32 = 1 << 2;
+pub const FEATURE_TURN_RELAY: u32 = 1 << 3;
+/// Signierter Austausch der Gossip-Parameter (siehe network::gossip_config).
+/// Wird nur angeboten, wenn der Adapter eine Gossip-Konfiguration hat.
+pub const FEATURE_GOSSIP_CONFIG: u32 = 1 << 4;
+
+/// Ohne diese Features ist keine sichere Kommunikation möglich.
+pub const REQUIRED_FEATURES: u32 = FEATURE_SIGNED_DELTAS;
+
+/// Erstes Paket nach dem Noise-Handshake.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hello {
+    pub min_version: u16,
+    pub max_version: u16,
+    pub features: u32,
+    /// Nur zur Diagnose, z. B. "my_dex/0.1.0"
+    pub agent: String,
+}
+
+impl Hello {
+    /// Hello für diesen Build.
+    pub fn local() -> Self {
+        Self {
+            min_version: MIN_PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+            features: FEATURE_SIGNED_DELTAS | FEATURE_ANTI_ENTROPY | FEATURE_SHARD_SNAPSHOT | FEATURE_TURN_RELAY,
+            agent: format!("my_dex/{}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap_or_default()
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, HandshakeRejection> {
+        bincode::deserialize(data).map_err(|e| HandshakeRejection::Malformed(e.to_string()))
+    }
+}
+
+/// Flags, die ein Peer für `msg` ausgehandelt haben muss (0 => immer erlaubt).
+pub fn required_features(msg: &KademliaMessage) -> u32 {
+    match msg {
+        KademliaMessage::ReliableGossip(_) => FEATURE_ANTI_ENTROPY,
+        KademliaMessage::CrdtSnapshots(_) | KademliaMessage::ShardTransfer(_) => FEATURE_SHARD_SNAPSHOT,
+        _ => 0,
+    }
+}
+
+/// Ergebnis der Aushandlung; für beide Seiten identisch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NegotiatedProtocol {
+    pub version: u16,
+    pub features: u32,
+    pub remote_agent: String,
+}
+
+impl NegotiatedProtocol {
+    pub fn supports(&self, feature: u32) -> bool {
+        self.features & feature == feature
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum HandshakeRejection {
+    #[error("incompatible protocol version: local {local_min}..={local_max}, remote {remote_min}..={remote_max}")]
+    IncompatibleVersion { local_min: u16, local_max: u16, remote_min: u16, remote_max: u16 },
+
+    #[error("peer lacks required features: {missing:#x}")]
+    MissingFeatures { missing: u32 },
+
+    #[error("malformed hello: {0}")]
+    Malformed(String),
+}
+
+/// Aushandlung nach den oben beschriebenen Regeln.
+pub fn negotiate(local: &Hello, remote: &Hello) -> Result<NegotiatedProtocol, HandshakeRejection> {
+    if remote.min_version > remote.max_version {
+        return Err(HandshakeRejection::Malformed(format!(
+            "min_version {} > max_version {}",
+            remote.min_version, remote.max_version
+        )));
+    }
+    let version = local.max_version.min(remote.max_version);
+    if version < local.min_version.max(remote.min_version) {
+        return Err(HandshakeRejection::IncompatibleVersion {
+            local_min: local.min_version,
+            local_max: local.max_version,
+            remote_min: remote.min_version,
+            remote_max: remote.max_version,
+        });
+    }
+    let missing = REQUIRED_FEATURES & !(local.features & remote.features);
+    if missing != 0 {
+        return Err(HandshakeRejection::MissingFeatures { missing });
+    }
+    Ok(NegotiatedProtocol {
+        version,
+        features: local.features & remote.features,
+        remote_agent: remote.agent.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello(min: u16, max: u16, features: u32) -> Hello {
+        Hello { min_version: min, max_version: max, features, agent: "test".into() }
+    }
+
+    #[test]
+    fn test_compatible_peers_agree_on_common_subset() {
+        let new = hello(1, 2, FEATURE_SIGNED_DELTAS | FEATURE_ANTI_ENTROPY | FEATURE_TURN_RELAY);
+        let old = hello(1, 1, FEATURE_SIGNED_DELTAS | FEATURE_SHARD_SNAPSHOT);
+
+        let a = negotiate(&new, &old).unwrap();
+        let b = negotiate(&old, &new).unwrap();
+        assert_eq!((a.version, a.features), (1, FEATURE_SIGNED_DELTAS));
+        assert_eq!((a.version, a.features), (b.version, b.features));
+        assert!(a.supports(FEATURE_SIGNED_DELTAS));
+        assert!(!a.supports(FEATURE_ANTI_ENTROPY));
+
+        // Zwei aktuelle Builds sprechen die neueste Version
+        let local = Hello::local();
+        let p = negotiate(&local, &Hello::decode(&local.encode()).unwrap()).unwrap();
+        assert_eq!((p.version, p.features), (PROTOCOL_VERSION, local.features));
+    }
+
+    #[test]
+    fn test_incompatible_version_is_rejected_with_reason() {
+        let local = hello(1, 2, FEATURE_SIGNED_DELTAS);
+        let future = hello(3, 4, FEATURE_SIGNED_DELTAS);
+        let err = negotiate(&local, &future).unwrap_err();
+        assert_eq!(
+            err,
+            HandshakeRejection::IncompatibleVersion { local_min: 1, local_max: 2, remote_min: 3, remote_max: 4 }
+        );
+        assert!(err.to_string().contains("remote 3..=4"));
+        // Symmetrisch: die Gegenseite lehnt ebenfalls ab
+        assert!(negotiate(&future, &local).is_err());
+
+        let unsigned = hello(1, 2, FEATURE_ANTI_ENTROPY);
+        assert_eq!(
+            negotiate(&local, &unsigned).unwrap_err(),
+            HandshakeRejection::MissingFeatures { missing: FEATURE_SIGNED_DELTAS }
+        );
+        assert!(matches!(Hello::decode(b"\x01"), Err(HandshakeRejection::Malformed(_))));
+
+        // Builds ohne Hello (Version 1) werden vom aktuellen Build abgelehnt
+        let pre_hello = hello(1, 1, FEATURE_SIGNED_DELTAS);
+        assert!(matches!(
+            negotiate(&Hello::local(), &pre_hello),
+            Err(HandshakeRejection::IncompatibleVersion { remote_max: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_message_features_follow_negotiation() {
+        let snapshots = KademliaMessage::CrdtSnapshots(Vec::new());
+        assert_eq!(required_features(&snapshots), FEATURE_SHARD_SNAPSHOT);
+        assert_eq!(required_features(&KademliaMessage::Block(Vec::new())), 0);
+
+        let with = negotiate(&Hello::local(), &Hello::local()).unwrap();
+        assert!(with.supports(required_features(&snapshots)));
+        let without = negotiate(&Hello::local(), &hello(MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, FEATURE_SIGNED_DELTAS)).unwrap();
+        assert!(!without.supports(required_features(&snapshots)));
+        assert!(without.supports(required_features(&KademliaMessage::Block(Vec::new()))));
+    }
+}
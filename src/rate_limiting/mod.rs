@@ -2,6 +2,5 @@
 /// my_DEX/src/rate_limiting/mod.rs
 //////////////////////////////////////////////////// 
 
-pub mod message;
-
 pub mod token_bucket;
+pub mod subnet_limiter;
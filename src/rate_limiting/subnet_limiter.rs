@@ -0,0 +1,265 @@
+////////////////////////////////////////////////////
+/// my_DEX/src/rate_limiting/subnet_limiter.rs
+////////////////////////////////////////////////////
+//
+// Subnetz-Aggregation + adaptiver DDoS-Modus für das P2P-Rate-Limit.
+//  - Alle Adressen eines /24 (IPv4) bzw. /64 (IPv6) teilen sich einen Bucket,
+//    Port- oder IP-Rotation innerhalb des Subnetzes hilft also nicht.
+//  - Steigt die Gesamtrate eingehender Nachrichten über `spike_threshold_per_sec`,
+//    kostet jede Nachricht `tightened_cost` Tokens => Limits werden global enger,
+//    bis die Rate wieder unter die Schwelle fällt.
+//  - Die Per-Peer-Buckets (P2PSecurity / AdvancedP2PSecurity) bleiben darüber
+//    bestehen.
+//  - Buckets, die länger als `idle_ttl` nichts gesehen haben, werden entfernt
+//    (ein neuer Bucket ist ohnehin voll). Zusätzlich höchstens `max_subnets`
+//    Einträge; bei Überlauf fliegt der am längsten ungenutzte raus.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+use crate::metrics::{RATE_LIMIT_ADAPTIVE_ACTIVE, RATE_LIMIT_DROPS};
+use crate::rate_limiting::token_bucket::TokenBucket;
+
+/// /24 bzw. /64 einer Adresse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubnetKey {
+    V4([u8; 3]),
+    V6([u16; 4]),
+}
+
+impl SubnetKey {
+    pub fn of(ip: &IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(v4) => {
+                let o = v4.octets();
+                SubnetKey::V4([o[0], o[1], o[2]])
+            }
+            IpAddr::V6(v6) => {
+                // IPv4-mapped => wie IPv4 behandeln
+                if let Some(v4) = v6.to_ipv4_mapped() {
+                    let o = v4.octets();
+                    return SubnetKey::V4([o[0], o[1], o[2]]);
+                }
+                let s = v6.segments();
+                SubnetKey::V6([s[0], s[1], s[2], s[3]])
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubnetLimiterConfig {
+    pub subnet_capacity: u64,
+    pub subnet_refill_per_sec: u64,
+    /// Gesamtrate (alle Peers), ab der der adaptive Modus greift.
+    pub spike_threshold_per_sec: u64,
+    /// Token-Kosten pro Nachricht im adaptiven Modus.
+    pub tightened_cost: u64,
+    /// Ungenutzte Subnetz-Buckets verfallen nach dieser Zeit.
+    pub idle_ttl: Duration,
+    /// Obergrenze für gleichzeitig gehaltene Subnetz-Buckets.
+    pub max_subnets: usize,
+}
+
+impl Default for SubnetLimiterConfig {
+    fn default() -> Self {
+        Self {
+            subnet_capacity: 1_000,
+            subnet_refill_per_sec: 250,
+            spike_threshold_per_sec: 5_000,
+            tightened_cost: 4,
+            idle_ttl: Duration::from_secs(300),
+            max_subnets: 100_000,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SubnetRateLimiter {
+    config: SubnetLimiterConfig,
+    /// Subnetz => (Bucket, letzte Nachricht)
+    subnets: HashMap<SubnetKey, (TokenBucket, Instant)>,
+    last_sweep: Instant,
+    window_start: Instant,
+    window_count: u64,
+    tightened: bool,
+}
+
+impl SubnetRateLimiter {
+    pub fn new(config: SubnetLimiterConfig) -> Self {
+        Self {
+            config,
+            subnets: HashMap::new(),
+            last_sweep: Instant::now(),
+            window_start: Instant::now(),
+            window_count: 0,
+            tightened: false,
+        }
+    }
+
+    /// Neue Grenzen (Config-Reload); bestehende Buckets starten neu.
+    pub fn reconfigure(&mut self, config: SubnetLimiterConfig) {
+        self.config = config;
+        self.subnets.clear();
+    }
+
+    pub fn is_tightened(&self) -> bool {
+        self.tightened
+    }
+
+    pub fn tracked_subnets(&self) -> usize {
+        self.subnets.len()
+    }
+
+    /// Entfernt Buckets ohne Nachricht seit `idle_ttl`.
+    pub fn evict_idle(&mut self, now: Instant) {
+        let ttl = self.config.idle_ttl;
+        self.subnets.retain(|_, (_, last)| now.duration_since(*last) < ttl);
+        self.last_sweep = now;
+    }
+
+    /// Platz für ein neues Subnetz: erst Idle-Sweep, dann LRU-Eintrag.
+    fn make_room(&mut self, now: Instant) {
+        if self.subnets.len() < self.config.max_subnets {
+            return;
+        }
+        self.evict_idle(now);
+        while self.subnets.len() >= self.config.max_subnets.max(1) {
+            let oldest = self.subnets.iter().min_by_key(|(_, (_, last))| *last).map(|(k, _)| *k);
+            match oldest {
+                Some(k) => {
+                    self.subnets.remove(&k);
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Zählt die Nachricht für die Gesamtrate und schaltet ggf. den
+    /// adaptiven Modus (1-Sekunden-Fenster).
+    fn observe_global(&mut self) {
+        if self.window_start.elapsed() >= Duration::from_secs(1) {
+            let was = self.tightened;
+            self.tightened = self.window_count > self.config.spike_threshold_per_sec;
+            if was && !self.tightened {
+                warn!("RateLimit => Gesamtrate normalisiert, adaptiver Modus aus");
+            }
+            self.window_start = Instant::now();
+            self.window_count = 0;
+        }
+        self.window_count += 1;
+        if !self.tightened && self.window_count > self.config.spike_threshold_per_sec {
+            warn!("RateLimit => Gesamtrate > {}/s, adaptiver Modus an", self.config.spike_threshold_per_sec);
+            self.tightened = true;
+        }
+        RATE_LIMIT_ADAPTIVE_ACTIVE.set(self.tightened as i64);
+    }
+
+    /// true => Nachricht von `ip` darf passieren (Subnetz-Ebene).
+    pub fn check(&mut self, ip: &IpAddr) -> bool {
+        self.observe_global();
+        let cost = if self.tightened { self.config.tightened_cost.max(1) } else { 1 };
+        let key = SubnetKey::of(ip);
+        let now = Instant::now();
+        if now.duration_since(self.last_sweep) >= self.config.idle_ttl {
+            self.evict_idle(now);
+        }
+        if !self.subnets.contains_key(&key) {
+            self.make_room(now);
+        }
+        let cfg = &self.config;
+        let (bucket, last) = self
+            .subnets
+            .entry(key)
+            .or_insert_with(|| (TokenBucket::new(cfg.subnet_capacity, cfg.subnet_refill_per_sec), now));
+        *last = now;
+        if !bucket.try_consume_n(cost) {
+            RATE_LIMIT_DROPS.with_label_values(&["subnet"]).inc();
+            warn!("Rate limit => Subnetz {:?} gedrosselt (ip={})", key, ip);
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    fn cfg() -> SubnetLimiterConfig {
+        SubnetLimiterConfig {
+            subnet_capacity: 10,
+            subnet_refill_per_sec: 1,
+            spike_threshold_per_sec: 10_000,
+            tightened_cost: 4,
+            idle_ttl: Duration::from_secs(300),
+            max_subnets: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_ports_and_hosts_in_one_slash24_share_bucket() {
+        let mut limiter = SubnetRateLimiter::new(cfg());
+        let mut allowed = 0;
+        for host in 1..=20u8 {
+            if limiter.check(&IpAddr::V4(Ipv4Addr::new(203, 0, 113, host))) {
+                allowed += 1;
+            }
+        }
+        assert_eq!(allowed, 10);
+        // Anderes /24 hat eigenen Bucket
+        assert!(limiter.check(&IpAddr::V4(Ipv4Addr::new(203, 0, 114, 1))));
+    }
+
+    #[test]
+    fn test_ipv6_slash64_aggregation() {
+        let a: Ipv6Addr = "2001:db8:1:2::1".parse().unwrap();
+        let b: Ipv6Addr = "2001:db8:1:2:ffff::9".parse().unwrap();
+        let c: Ipv6Addr = "2001:db8:1:3::1".parse().unwrap();
+        assert_eq!(SubnetKey::of(&IpAddr::V6(a)), SubnetKey::of(&IpAddr::V6(b)));
+        assert_ne!(SubnetKey::of(&IpAddr::V6(a)), SubnetKey::of(&IpAddr::V6(c)));
+    }
+
+    #[test]
+    fn test_adaptive_mode_tightens_on_spike() {
+        let mut config = cfg();
+        config.spike_threshold_per_sec = 5;
+        config.subnet_capacity = 100;
+        let mut limiter = SubnetRateLimiter::new(config);
+        for host in 0..6u8 {
+            assert!(limiter.check(&IpAddr::V4(Ipv4Addr::new(10, host, 0, 1))));
+        }
+        assert!(limiter.is_tightened());
+        // Neues Subnetz: 100 Tokens / 4 pro Nachricht => 25 Nachrichten
+        let ip = IpAddr::V4(Ipv4Addr::new(198, 51, 100, 7));
+        let allowed = (0..40).filter(|_| limiter.check(&ip)).count();
+        assert_eq!(allowed, 25);
+    }
+
+    #[test]
+    fn test_rotating_subnets_stay_bounded() {
+        let mut config = cfg();
+        config.max_subnets = 100;
+        let mut limiter = SubnetRateLimiter::new(config);
+        let home = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        for i in 0..5_000u32 {
+            let [_, a, b, _] = i.to_be_bytes();
+            limiter.check(&IpAddr::V4(Ipv4Addr::new(10, a, b, 1)));
+            if i % 10 == 0 {
+                limiter.check(&home);
+            }
+        }
+        assert!(limiter.tracked_subnets() <= 100);
+        // Aktives Subnetz wird nicht verdrängt => Bucket ist leer
+        assert!(!limiter.check(&home));
+
+        // Nach der Idle-Zeit ist alles weg
+        let later = Instant::now() + Duration::from_secs(301);
+        limiter.evict_idle(later);
+        assert_eq!(limiter.tracked_subnets(), 0);
+    }
+}
@@ -2,26 +2,57 @@
 /// my_DEX/src/rate_limiting/token_bucket.rs
 //////////////////////////////////////////////////// 
 
-pub mod message;
+use std::time::Duration;
 
-pub mod token_bucket;
-
-use std::time::Instant;
+use crate::utils::clock::{system_clock, SharedClock};
 
+#[derive(Debug)]
 pub struct TokenBucket {
     capacity: u64,
     tokens: u64,
     refill_rate: u64,
-    last_refill: Instant,
+    // Monotone Zeit des letzten Refills laut `clock`
+    last_refill: Duration,
+    clock: SharedClock,
 }
 
 impl TokenBucket {
     pub fn new(capacity: u64, refill_rate: u64) -> Self {
+        Self::with_clock(capacity, refill_rate, system_clock())
+    }
+
+    pub fn with_clock(capacity: u64, refill_rate: u64, clock: SharedClock) -> Self {
         TokenBucket {
             capacity,
             tokens: capacity,
             refill_rate,
-            last_refill: Instant::now(),
+            last_refill: clock.monotonic(),
+            clock,
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = self.clock.monotonic();
+        let elapsed = now.saturating_sub(self.last_refill).as_secs();
+        if elapsed > 0 {
+            let refill = elapsed.saturating_mul(self.refill_rate);
+            self.tokens = std::cmp::min(self.capacity, self.tokens.saturating_add(refill));
+            self.last_refill = now;
+        }
+    }
+
+    pub fn try_consume(&mut self) -> bool {
+        self.try_consume_n(1)
+    }
+
+    /// Verbraucht `n` Tokens auf einmal (alles oder nichts).
+    pub fn try_consume_n(&mut self, n: u64) -> bool {
+        self.refill();
+        if self.tokens >= n {
+            self.tokens -= n;
+            true
+        } else {
+            false
         }
     }
 }
@@ -4,29 +4,47 @@
 
 use axum::{
     routing::{get, post},
-    extract::{Path, State, Json},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{ConnectInfo, Extension, Path, State, Json},
+    http::{header, HeaderMap, Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     Router,
 };
+use axum_server::tls_rustls::RustlsConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use tracing::{info, warn};
 
 use crate::node_logic::{DexNode, OrderRequest};
+use crate::matching_engine::{HaltAction, HaltApproval, OrderData, PlaceResult};
+use axum::extract::Query;
 use crate::error::DexError;
-use crate::shard_logic::shard_manager::ShardManager;
+use crate::shard_logic::ShardManager;
+use crate::market_data::{market_data_routes, MarketDataHub};
+use crate::trade_history::{trade_history_routes, TradeHistory};
+use crate::jsonrpc::jsonrpc_routes;
+use crate::identity::accounts::AccountsManager;
+use crate::identity::balance_ledger::{LedgerEntry, Reconciliation};
+use crate::identity::access_control::{Permission, Role};
+use crate::config_loader::{is_placeholder_token, NodeConfig};
 
 #[derive(Clone)]
 pub struct AppState {
     pub node: Arc<DexNode>,
     pub shard_manager: ShardManager,
+    pub market_data: MarketDataHub,
+    pub trade_history: TradeHistory,
 }
 
 #[derive(Serialize)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub message: Option<String>,
+    /// Maschinenlesbarer Fehlercode (siehe `DexError::code`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
     pub data: Option<T>,
 }
 
@@ -35,6 +53,7 @@ impl<T: Serialize> ApiResponse<T> {
         Self {
             success: true,
             message: None,
+            code: None,
             data: Some(data),
         }
     }
@@ -43,9 +62,25 @@ impl<T: Serialize> ApiResponse<T> {
         Self {
             success: false,
             message: Some(msg.to_string()),
+            code: None,
             data: None,
         }
     }
+
+    pub fn from_error(err: &DexError) -> Self {
+        Self {
+            success: false,
+            message: Some(err.to_string()),
+            code: Some(err.code().to_string()),
+            data: None,
+        }
+    }
+}
+
+/// Antwort für einen DexError: Status aus `http_status`, Body mit `code`.
+fn dex_error_response(err: &DexError) -> axum::response::Response {
+    let status = StatusCode::from_u16(err.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    (status, Json(ApiResponse::<()>::from_error(err))).into_response()
 }
 
 // ==== Request/Response Models ====
@@ -56,6 +91,75 @@ pub struct BalanceQuery {
     pub coin: String,
 }
 
+#[derive(Deserialize)]
+pub struct CancelOrderRequest {
+    /// Nur ohne Auth ausgewertet; sonst bestimmt der Token den Eigentümer.
+    #[serde(default)]
+    pub user_id: String,
+    pub order_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct BookQuery {
+    /// z.B. "BTC/USDT"
+    pub market: String,
+}
+
+#[derive(Serialize)]
+pub struct PlacedOrder {
+    pub order_id: String,
+    pub status: String,
+}
+
+/// Obergrenze für `POST /orders/batch`.
+pub const MAX_BATCH_ORDERS: usize = 100;
+
+#[derive(Deserialize)]
+pub struct BatchOrderRequest {
+    pub orders: Vec<OrderRequest>,
+    /// true => alle oder keine
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+#[derive(Serialize)]
+pub struct BatchPlaced {
+    pub atomic: bool,
+    pub accepted: usize,
+    pub results: Vec<PlaceResult>,
+}
+
+/// Body für `POST /api/commit_order`: `order_commitment_hash` als Hex.
+#[derive(Deserialize)]
+pub struct CommitOrderRequest {
+    pub user_id: String,
+    pub hash: String,
+}
+
+/// Body für `POST /api/reveal_order`: signierte Order + Nonce (Hex) der Commitment.
+#[derive(Deserialize)]
+pub struct RevealOrderRequest {
+    pub order: OrderData,
+    pub nonce: String,
+}
+
+/// Body für `POST /api/market/halt`: Committee-Beschluss über die aktuelle
+/// Nonce des Marktes (siehe `halt_signing_bytes`).
+#[derive(Deserialize)]
+pub struct HaltRequest {
+    pub market: String,
+    pub action: HaltAction,
+    pub approvals: Vec<HaltApproval>,
+}
+
+#[derive(Serialize)]
+pub struct HaltStatusView {
+    pub market: String,
+    pub halted: bool,
+    /// Nonce, über die der nächste Beschluss signiert werden muss
+    pub nonce: u64,
+}
+
 #[derive(Serialize)]
 pub struct ShardInfoEntry {
     pub shard_id: u32,
@@ -68,43 +172,219 @@ pub async fn ping() -> impl IntoResponse {
     (StatusCode::OK, Json(ApiResponse::success("pong")))
 }
 
+/// Mit Auth nur im Namen des eigenen Accounts (`Principal::acts_for`), sonst
+/// könnte jeder Trader-Token das Guthaben eines fremden `user_id` sperren.
 pub async fn place_order(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Json(req): Json<OrderRequest>,
 ) -> impl IntoResponse {
+    if let Some(Extension(principal)) = principal {
+        if !principal.acts_for(&req.user_id) {
+            let resp = dex_error_response(&DexError::Forbidden(format!("Account {}", req.user_id)));
+            return (resp.status(), resp);
+        }
+    }
     if state.node.watchtower.is_banned(&req.user_id) {
         warn!("Gebannter Nutzer {} versucht Order zu platzieren", req.user_id);
-        return (
-            StatusCode::FORBIDDEN,
-            Json(ApiResponse::<()> ::error("Zugriff verweigert: gesperrter Nutzer")),
-        );
+        let resp = dex_error_response(&DexError::UserBanned(req.user_id.clone()));
+        return (resp.status(), resp);
     }
 
     match state.node.place_order(req) {
-        Ok(_) => (StatusCode::OK, Json(ApiResponse::success("Order akzeptiert"))),
+        Ok(order_id) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(PlacedOrder { order_id, status: "open".into() })).into_response(),
+        ),
         Err(e) => {
             warn!("Fehler bei Order: {:?}", e);
-            (
-                StatusCode::BAD_REQUEST,
-                Json(ApiResponse::<()> ::error(&format!("{:?}", e))),
-            )
+            let resp = dex_error_response(&e);
+            (resp.status(), resp)
+        }
+    }
+}
+
+/// Prüft Principal und Bann für Commit/Reveal; `Some(response)` => ablehnen.
+fn reject_commit_reveal(state: &AppState, principal: Option<Extension<Principal>>, user_id: &str) -> Option<Response> {
+    if let Some(Extension(principal)) = principal {
+        if !principal.acts_for(user_id) {
+            return Some(dex_error_response(&DexError::Forbidden(format!("Account {}", user_id))));
+        }
+    }
+    if state.node.watchtower.is_banned(user_id) {
+        warn!("Gebannter Nutzer {} versucht Commit/Reveal", user_id);
+        return Some(dex_error_response(&DexError::UserBanned(user_id.to_string())));
+    }
+    None
+}
+
+/// `POST /api/commit_order`: Commit-Phase, nur im Namen des eigenen Accounts.
+pub async fn commit_order(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Json(req): Json<CommitOrderRequest>,
+) -> Response {
+    if let Some(resp) = reject_commit_reveal(&state, principal, &req.user_id) {
+        return resp;
+    }
+    let hash: [u8; 32] = match hex::decode(&req.hash).ok().and_then(|h| h.try_into().ok()) {
+        Some(h) => h,
+        None => {
+            return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("hash muss 32 Byte Hex sein"))).into_response();
         }
+    };
+    match state.node.submit_order_commitment(&req.user_id, hash) {
+        Ok(()) => (StatusCode::OK, Json(ApiResponse::success("committed"))).into_response(),
+        Err(e) => dex_error_response(&e),
+    }
+}
+
+/// `POST /api/reveal_order`: Reveal-Phase; die Order muss dem Principal gehören.
+pub async fn reveal_order(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Json(req): Json<RevealOrderRequest>,
+) -> Response {
+    if let Some(resp) = reject_commit_reveal(&state, principal, &req.order.user_id) {
+        return resp;
+    }
+    let nonce = match hex::decode(&req.nonce) {
+        Ok(n) => n,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error("nonce muss Hex sein"))).into_response();
+        }
+    };
+    let order_id = req.order.id.clone();
+    match state.node.reveal_order(req.order, &nonce) {
+        Ok(()) => (StatusCode::OK, Json(ApiResponse::success(PlacedOrder { order_id, status: "open".into() }))).into_response(),
+        Err(e) => {
+            warn!("Fehler bei Reveal: {:?}", e);
+            dex_error_response(&e)
+        }
+    }
+}
+
+/// `POST /orders/batch`: 200 mit Einzelergebnissen; ein atomarer Batch,
+/// der nicht vollständig übernommen wurde, liefert 422 und dieselben Ergebnisse.
+/// Mit Auth muss der Principal für jede `user_id` im Batch handeln dürfen.
+pub async fn place_orders_batch(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Json(req): Json<BatchOrderRequest>,
+) -> Response {
+    if req.orders.is_empty() || req.orders.len() > MAX_BATCH_ORDERS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error(&format!("Batch muss 1..={} Orders enthalten", MAX_BATCH_ORDERS))),
+        )
+            .into_response();
+    }
+    if let Some(Extension(principal)) = principal {
+        if let Some(o) = req.orders.iter().find(|o| !principal.acts_for(&o.user_id)) {
+            return dex_error_response(&DexError::Forbidden(format!("Account {}", o.user_id)));
+        }
+    }
+    if let Some(o) = req.orders.iter().find(|o| state.node.watchtower.is_banned(&o.user_id)) {
+        warn!("Gebannter Nutzer {} versucht Batch zu platzieren", o.user_id);
+        return dex_error_response(&DexError::UserBanned(o.user_id.clone()));
+    }
+
+    let atomic = req.atomic;
+    let results = match state.node.place_orders_batch(req.orders, atomic) {
+        Ok(r) => r,
+        Err(e) => return dex_error_response(&e),
+    };
+    let accepted = results.iter().filter(|r| r.is_accepted()).count();
+    let body = BatchPlaced { atomic, accepted, results };
+    if atomic && accepted < body.results.len() {
+        let code = body.results.iter().find_map(|r| r.code.clone());
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ApiResponse {
+                success: false,
+                message: Some("Batch abgelehnt, keine Order übernommen".into()),
+                code,
+                data: Some(body),
+            }),
+        )
+            .into_response();
     }
+    (StatusCode::OK, Json(ApiResponse::success(body))).into_response()
 }
 
+/// Storniert nur im Namen des gespeicherten Order-Eigentümers: mit Auth muss der
+/// Principal für ihn handeln dürfen (`Principal::acts_for`), die `user_id` im Body
+/// zählt dann nicht. Ohne Auth (lokale Tests) prüft der Node gegen den Body.
+pub async fn cancel_order(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Json(req): Json<CancelOrderRequest>,
+) -> impl IntoResponse {
+    let acting_user = match principal {
+        Some(Extension(principal)) => match state.node.order_owner(&req.order_id) {
+            Some(owner) if principal.acts_for(&owner) => owner,
+            Some(_) => {
+                let resp = dex_error_response(&DexError::Forbidden(format!("Order {}", req.order_id)));
+                return (resp.status(), resp);
+            }
+            None => {
+                let resp = dex_error_response(&DexError::OrderNotFound { order_id: req.order_id.clone() });
+                return (resp.status(), resp);
+            }
+        },
+        None => req.user_id.clone(),
+    };
+    match state.node.cancel_order(&acting_user, &req.order_id) {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(PlacedOrder { order_id: req.order_id, status: "cancelled".into() })).into_response(),
+        ),
+        Err(e) => {
+            let resp = dex_error_response(&e);
+            (resp.status(), resp)
+        }
+    }
+}
+
+pub async fn get_order_status(
+    Path(order_id): Path<String>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match state.node.order_status(&order_id) {
+        Some(info) => (StatusCode::OK, Json(ApiResponse::success(info)).into_response()),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error(&format!("Order {} nicht gefunden", order_id))).into_response(),
+        ),
+    }
+}
+
+pub async fn get_order_book(
+    Query(q): Query<BookQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    (StatusCode::OK, Json(ApiResponse::success(state.node.order_book(&q.market))))
+}
+
+/// Mit Auth nur für den eigenen Account (`Principal::acts_for`).
 pub async fn get_balance(
     State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
     Json(req): Json<BalanceQuery>,
 ) -> impl IntoResponse {
+    if let Some(Extension(principal)) = principal {
+        if !principal.acts_for(&req.user_id) {
+            let resp = dex_error_response(&DexError::Forbidden(format!("Account {}", req.user_id)));
+            return (resp.status(), resp);
+        }
+    }
     if state.node.watchtower.is_banned(&req.user_id) {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(ApiResponse::<()> ::error("Zugriff verweigert: gesperrter Nutzer")),
-        );
+        let resp = dex_error_response(&DexError::UserBanned(req.user_id.clone()));
+        return (resp.status(), resp);
     }
 
     let bal = state.node.user_get_free_balance(&req.user_id, &req.coin);
-    (StatusCode::OK, Json(ApiResponse::success(bal)))
+    (StatusCode::OK, Json(ApiResponse::success(bal)).into_response())
 }
 
 pub async fn get_all_shards(State(state): State<AppState>) -> impl IntoResponse {
@@ -121,6 +401,11 @@ pub async fn get_all_shards(State(state): State<AppState>) -> impl IntoResponse
     (StatusCode::OK, Json(ApiResponse::success(entries)))
 }
 
+/// State-Root je Shard; `dex-cli compare-state` vergleicht zwei Nodes damit.
+pub async fn get_state_hash(State(state): State<AppState>) -> impl IntoResponse {
+    (StatusCode::OK, Json(ApiResponse::success(state.node.state_digest(&state.shard_manager))))
+}
+
 pub async fn get_single_shard(
     Path(shard_id): Path<u32>,
     State(state): State<AppState>,
@@ -138,31 +423,1157 @@ pub async fn get_single_shard(
     })))
 }
 
+/// `POST /api/market/halt` => hält den Markt an bzw. setzt ihn fort, sofern
+/// die Approvals das Committee-Threshold erreichen.
+pub async fn halt_market(
+    State(state): State<AppState>,
+    Json(req): Json<HaltRequest>,
+) -> Response {
+    if let Err(e) = state.node.apply_halt_action(&req.market, req.action, &req.approvals) {
+        warn!("{:?} für {} abgelehnt: {}", req.action, req.market, e);
+        return dex_error_response(&e);
+    }
+    info!("Markt {} => {:?}", req.market, req.action);
+    halt_status_response(&state, req.market)
+}
+
+/// `GET /api/market/halt_status?market=...` => Zustand und nächste Nonce.
+pub async fn get_halt_status(
+    Query(q): Query<BookQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    halt_status_response(&state, q.market)
+}
+
+fn halt_status_response(state: &AppState, market: String) -> Response {
+    match state.node.halt_status(&market) {
+        Some((halted, nonce)) => {
+            (StatusCode::OK, Json(ApiResponse::success(HaltStatusView { market, halted, nonce }))).into_response()
+        }
+        None => dex_error_response(&DexError::Other("No MarketHaltControl set".into())),
+    }
+}
+
 pub async fn force_replicate_shard(
     Path(shard_id): Path<u32>,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
-    match state.shard_manager.replicate_shard_to_new_node(shard_id) {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(ApiResponse::<()> ::success("Replikation angestoßen")),
+    let moves = state.shard_manager.rebalance_shard(shard_id);
+    (StatusCode::OK, Json(ApiResponse::success(moves)))
+}
+
+// ==== Authentifizierung ====
+
+/// Erlaubte API-Tokens. Akzeptiert `Authorization: Bearer <token>` oder `X-API-Key: <token>`.
+#[derive(Clone, Default)]
+pub struct ApiAuth {
+    tokens: Arc<HashSet<String>>,
+}
+
+impl ApiAuth {
+    pub fn new<I: IntoIterator<Item = String>>(tokens: I) -> Self {
+        Self {
+            tokens: Arc::new(tokens.into_iter().filter(|t| !t.is_empty()).collect()),
+        }
+    }
+
+    fn is_valid(&self, presented: &str) -> bool {
+        // Vergleich ohne frühen Abbruch, damit die Laufzeit nichts über den Token verrät
+        self.tokens.iter().fold(false, |ok, t| ok | constant_time_eq(t.as_bytes(), presented.as_bytes()))
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn presented_token<B>(req: &Request<B>) -> Option<&str> {
+    token_from_headers(req.headers())
+}
+
+/// Token aus `Authorization: Bearer` bzw. `X-API-Key`; auch für `/rpc`.
+pub(crate) fn token_from_headers(headers: &HeaderMap) -> Option<&str> {
+    if let Some(v) = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()) {
+        return v.strip_prefix("Bearer ").map(str::trim);
+    }
+    headers.get("x-api-key").and_then(|v| v.to_str().ok()).map(str::trim)
+}
+
+/// Middleware für zustandsändernde Routen => 401 ohne gültigen Token.
+pub async fn require_api_token<B>(
+    State(auth): State<ApiAuth>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    match presented_token(&req) {
+        Some(token) if auth.is_valid(token) => next.run(req).await,
+        _ => {
+            warn!("Unautorisierter Zugriff auf {}", req.uri().path());
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ApiResponse::<()>::error("Fehlender oder ungültiger API-Token")),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Authentifizierter Aufrufer: Rolle und, falls der Token an einen Account
+/// gebunden ist, dessen `user_id`. Liegt nach `require_permission` in den
+/// Request-Extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub role: Role,
+    pub user_id: Option<String>,
+}
+
+impl Principal {
+    /// Darf der Aufrufer im Namen von `user_id` handeln? Admin immer,
+    /// sonst nur mit einem an genau diesen Account gebundenen Token.
+    pub fn acts_for(&self, user_id: &str) -> bool {
+        self.role == Role::Admin || self.user_id.as_deref() == Some(user_id)
+    }
+}
+
+/// Token => Rolle (+ optional gebundener Account). Der Token identifiziert den
+/// Principal, die Rolle bestimmt über `Role::allows`, welche Routen er nutzen darf.
+#[derive(Clone, Default)]
+pub struct RoleAuth {
+    tokens: Arc<Vec<(String, Principal)>>,
+}
+
+impl RoleAuth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tokens<I: IntoIterator<Item = String>>(mut self, role: Role, tokens: I) -> Self {
+        Arc::make_mut(&mut self.tokens).extend(
+            tokens
+                .into_iter()
+                .filter(|t| !t.is_empty())
+                .map(|t| (t, Principal { role, user_id: None })),
+        );
+        self
+    }
+
+    /// Trader-Tokens, die an einen Account gebunden sind (`user_id => token`).
+    pub fn with_user_tokens<I: IntoIterator<Item = (String, String)>>(mut self, tokens: I) -> Self {
+        Arc::make_mut(&mut self.tokens).extend(
+            tokens
+                .into_iter()
+                .filter(|(_, t)| !t.is_empty())
+                .map(|(user_id, t)| (t, Principal { role: Role::Trader, user_id: Some(user_id) })),
+        );
+        self
+    }
+
+    /// Tokens aus `api_tokens` (Trader), `admin_api_tokens`, `fullnode_api_tokens`
+    /// und `readonly_api_tokens`.
+    /// Platzhalter-Tokens (`CHANGE_ME...`, leer) werden nie übernommen, auch wenn
+    /// `NodeConfig::check_rest_security` übersprungen wurde.
+    pub fn from_config(cfg: &NodeConfig) -> Self {
+        let usable = |tokens: &[String]| -> Vec<String> {
+            tokens
+                .iter()
+                .filter(|t| {
+                    let placeholder = is_placeholder_token(t);
+                    if placeholder {
+                        warn!("Platzhalter-API-Token in der Config ignoriert");
+                    }
+                    !placeholder
+                })
+                .cloned()
+                .collect()
+        };
+        Self::new()
+            .with_tokens(Role::Admin, usable(&cfg.admin_api_tokens))
+            .with_tokens(Role::Fullnode, usable(&cfg.fullnode_api_tokens))
+            .with_tokens(Role::Trader, usable(&cfg.api_tokens))
+            .with_tokens(Role::ReadOnly, usable(&cfg.readonly_api_tokens))
+            .with_user_tokens(
+                cfg.user_api_tokens
+                    .iter()
+                    .filter(|(_, t)| !is_placeholder_token(t))
+                    .map(|(user, t)| (user.clone(), t.clone())),
+            )
+    }
+
+    pub(crate) fn principal_of(&self, presented: &str) -> Option<Principal> {
+        // Wie ApiAuth::is_valid: alle Einträge vergleichen, kein früher Abbruch
+        self.tokens.iter().fold(None, |found, (t, principal)| {
+            if constant_time_eq(t.as_bytes(), presented.as_bytes()) && found.is_none() {
+                Some(principal.clone())
+            } else {
+                found
+            }
+        })
+    }
+}
+
+/// State für `require_permission`: wer authentifiziert und was die Route verlangt.
+#[derive(Clone)]
+pub struct RouteGuard {
+    pub auth: RoleAuth,
+    pub permission: Permission,
+}
+
+/// Middleware => 401 ohne gültigen Token, 403 wenn die Rolle die Permission
+/// nicht hat. Bei Erfolg liegen `Role` und `Principal` in den Request-Extensions.
+pub async fn require_permission<B>(
+    State(guard): State<RouteGuard>,
+    mut req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let principal = match presented_token(&req).and_then(|t| guard.auth.principal_of(t)) {
+        Some(principal) => principal,
+        None => {
+            warn!("Unautorisierter Zugriff auf {}", req.uri().path());
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(ApiResponse::<()>::error("Fehlender oder ungültiger API-Token")),
+            )
+                .into_response();
+        }
+    };
+    let role = principal.role;
+    if !role.allows(guard.permission) {
+        warn!("{:?} ohne {:?} => {} verweigert", role, guard.permission, req.uri().path());
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::<()>::error("Keine Berechtigung für diese Route")),
+        )
+            .into_response();
+    }
+    req.extensions_mut().insert(role);
+    req.extensions_mut().insert(principal);
+    next.run(req).await
+}
+
+/// Hängt `require_permission` an alle Routen von `router`; ohne Auth bleibt
+/// er offen (nur lokale Tests).
+pub fn guarded<S>(router: Router<S>, auth: &Option<RoleAuth>, permission: Permission) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    match auth {
+        Some(auth) => router.route_layer(middleware::from_fn_with_state(
+            RouteGuard { auth: auth.clone(), permission },
+            require_permission,
+        )),
+        None => router,
+    }
+}
+
+// ==== Accounts ====
+
+#[derive(Deserialize)]
+pub struct AccountListQuery {
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+pub async fn list_accounts(
+    Query(q): Query<AccountListQuery>,
+    State(accounts): State<Arc<AccountsManager>>,
+) -> impl IntoResponse {
+    match accounts.list_accounts(q.cursor.as_deref(), q.limit.unwrap_or(50)) {
+        Ok(page) => (StatusCode::OK, Json(ApiResponse::success(page)).into_response()),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(&format!("{:?}", e))).into_response(),
+        ),
+    }
+}
+
+/// Mit Auth nur für den eigenen Account (`Principal::acts_for`).
+pub async fn get_account_info(
+    Path(user_id): Path<String>,
+    State(accounts): State<Arc<AccountsManager>>,
+    principal: Option<Extension<Principal>>,
+) -> impl IntoResponse {
+    if let Some(Extension(principal)) = principal {
+        if !principal.acts_for(&user_id) {
+            let resp = dex_error_response(&DexError::Forbidden(format!("Account {}", user_id)));
+            return (resp.status(), resp);
+        }
+    }
+    match accounts.account_info(&user_id) {
+        Ok(Some(info)) => (StatusCode::OK, Json(ApiResponse::success(info)).into_response()),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error("Account nicht gefunden")).into_response(),
         ),
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse::<()> ::error(&format!("Fehler: {:?}", e))),
+            Json(ApiResponse::<()>::error(&format!("{:?}", e))).into_response(),
         ),
     }
 }
 
+/// Body für die 2FA-Routen. `code` ist der aktuelle TOTP-Code; beim
+/// Einrichten (`enroll`) reicht das Passwort.
+#[derive(Deserialize)]
+pub struct TwoFactorRequest {
+    pub password: String,
+    pub code: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TwoFactorSecretView {
+    /// base32, einmalig für die Authenticator-App
+    pub secret: String,
+}
+
+#[derive(Serialize)]
+pub struct RecoveryCodesView {
+    /// Klartext, wird nur in dieser Antwort ausgeliefert
+    pub codes: Vec<String>,
+}
+
+fn required_code(req: &TwoFactorRequest) -> Result<&str, DexError> {
+    req.code.as_deref().ok_or_else(|| DexError::InvalidCredentials("2FA code required".into()))
+}
+
+/// Mit Auth nur für den eigenen Account. Liefert den Client-Schlüssel für das
+/// Fehlversuch-Limit: die IP des Aufrufers, ohne ConnectInfo der Principal.
+fn two_fa_client(
+    user_id: &str,
+    principal: &Option<Extension<Principal>>,
+    addr: &Option<ConnectInfo<SocketAddr>>,
+) -> Result<String, DexError> {
+    if let Some(Extension(principal)) = principal {
+        if !principal.acts_for(user_id) {
+            return Err(DexError::Forbidden(format!("Account {}", user_id)));
+        }
+    }
+    Ok(match (addr, principal) {
+        (Some(ConnectInfo(addr)), _) => addr.ip().to_string(),
+        (None, Some(Extension(p))) => format!("{:?}:{}", p.role, p.user_id.as_deref().unwrap_or("-")),
+        (None, None) => "local".to_string(),
+    })
+}
+
+pub async fn enroll_2fa(
+    Path(user_id): Path<String>,
+    State(accounts): State<Arc<AccountsManager>>,
+    principal: Option<Extension<Principal>>,
+    addr: Option<ConnectInfo<SocketAddr>>,
+    Json(req): Json<TwoFactorRequest>,
+) -> Response {
+    let client = match two_fa_client(&user_id, &principal, &addr) {
+        Ok(client) => client,
+        Err(e) => return dex_error_response(&e),
+    };
+    match accounts.enroll_2fa(&user_id, &req.password, &client) {
+        Ok(secret) => (StatusCode::OK, Json(ApiResponse::success(TwoFactorSecretView { secret }))).into_response(),
+        Err(e) => dex_error_response(&e),
+    }
+}
+
+/// Aktiviert das per Enroll ausgegebene Secret mit dem ersten gültigen Code.
+pub async fn confirm_2fa(
+    Path(user_id): Path<String>,
+    State(accounts): State<Arc<AccountsManager>>,
+    principal: Option<Extension<Principal>>,
+    addr: Option<ConnectInfo<SocketAddr>>,
+    Json(req): Json<TwoFactorRequest>,
+) -> Response {
+    let result = two_fa_client(&user_id, &principal, &addr).and_then(|client| {
+        accounts.confirm_2fa(&user_id, &req.password, required_code(&req)?, &client)
+    });
+    match result {
+        Ok(()) => (StatusCode::OK, Json(ApiResponse::success(()))).into_response(),
+        Err(e) => dex_error_response(&e),
+    }
+}
+
+pub async fn disable_2fa(
+    Path(user_id): Path<String>,
+    State(accounts): State<Arc<AccountsManager>>,
+    principal: Option<Extension<Principal>>,
+    addr: Option<ConnectInfo<SocketAddr>>,
+    Json(req): Json<TwoFactorRequest>,
+) -> Response {
+    let result = two_fa_client(&user_id, &principal, &addr).and_then(|client| {
+        accounts.disable_2fa(&user_id, &req.password, required_code(&req)?, &client)
+    });
+    match result {
+        Ok(()) => (StatusCode::OK, Json(ApiResponse::success(()))).into_response(),
+        Err(e) => dex_error_response(&e),
+    }
+}
+
+pub async fn rotate_2fa_secret(
+    Path(user_id): Path<String>,
+    State(accounts): State<Arc<AccountsManager>>,
+    principal: Option<Extension<Principal>>,
+    addr: Option<ConnectInfo<SocketAddr>>,
+    Json(req): Json<TwoFactorRequest>,
+) -> Response {
+    let result = two_fa_client(&user_id, &principal, &addr).and_then(|client| {
+        accounts.rotate_2fa_secret(&user_id, &req.password, required_code(&req)?, &client)
+    });
+    match result {
+        Ok(secret) => (StatusCode::OK, Json(ApiResponse::success(TwoFactorSecretView { secret }))).into_response(),
+        Err(e) => dex_error_response(&e),
+    }
+}
+
+pub async fn generate_recovery_codes(
+    Path(user_id): Path<String>,
+    State(accounts): State<Arc<AccountsManager>>,
+    principal: Option<Extension<Principal>>,
+    addr: Option<ConnectInfo<SocketAddr>>,
+    Json(req): Json<TwoFactorRequest>,
+) -> Response {
+    let result = two_fa_client(&user_id, &principal, &addr).and_then(|client| {
+        accounts.generate_recovery_codes(&user_id, &req.password, required_code(&req)?, &client)
+    });
+    match result {
+        Ok(codes) => (StatusCode::OK, Json(ApiResponse::success(RecoveryCodesView { codes }))).into_response(),
+        Err(e) => dex_error_response(&e),
+    }
+}
+
+/// `GET /accounts` (`ListAccounts`, nur Admin), `GET /accounts/:user_id` (`ReadAccount`)
+/// und `POST /accounts/:user_id/2fa/{enroll,confirm,disable,rotate,recovery_codes}` (`ManageTwoFactor`,
+/// nur für den eigenen Account; Fehlversuche zählen zusätzlich je Client-IP).
+/// Ohne gesetzte Auth bleiben die Routen offen, nur für lokale Tests.
+pub fn accounts_routes<S>(
+    accounts: Arc<AccountsManager>,
+    auth: Option<RoleAuth>,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let list = guarded(Router::new().route("/accounts", get(list_accounts)), &auth, Permission::ListAccounts);
+    let info = guarded(Router::new().route("/accounts/:user_id", get(get_account_info)), &auth, Permission::ReadAccount);
+    let two_fa = guarded(
+        Router::new()
+            .route("/accounts/:user_id/2fa/enroll", post(enroll_2fa))
+            .route("/accounts/:user_id/2fa/confirm", post(confirm_2fa))
+            .route("/accounts/:user_id/2fa/disable", post(disable_2fa))
+            .route("/accounts/:user_id/2fa/rotate", post(rotate_2fa_secret))
+            .route("/accounts/:user_id/2fa/recovery_codes", post(generate_recovery_codes)),
+        &auth,
+        Permission::ManageTwoFactor,
+    );
+    list.merge(info).merge(two_fa).with_state(accounts)
+}
+
+/// Journal eines Wallets plus Abgleich gegen die gespeicherte Balance.
+#[derive(Serialize)]
+pub struct WalletLedgerView {
+    pub wallet_id: String,
+    pub entries: Vec<LedgerEntry>,
+    pub reconciliation: Option<Reconciliation>,
+    /// Gesetzt, wenn Journal und Wallet auseinanderlaufen
+    pub mismatch: Option<String>,
+}
+
+/// Mit Auth nur für Wallets des eigenen Accounts; Admin sieht alle.
+pub async fn get_wallet_ledger(
+    Path(wallet_id): Path<String>,
+    State(accounts): State<Arc<AccountsManager>>,
+    principal: Option<Extension<Principal>>,
+) -> Response {
+    if let Some(Extension(principal)) = principal {
+        let owned = match (principal.role, principal.user_id.as_deref()) {
+            (Role::Admin, _) => true,
+            (_, Some(user_id)) => match accounts.owns_wallet(user_id, &wallet_id) {
+                Ok(owned) => owned,
+                Err(e) => return dex_error_response(&e),
+            },
+            (_, None) => false,
+        };
+        if !owned {
+            return dex_error_response(&DexError::Forbidden(format!("Wallet {}", wallet_id)));
+        }
+    }
+    let wallets = &accounts.wallet_manager;
+    let (reconciliation, mismatch) = match wallets.reconcile(&wallet_id) {
+        Ok(rec) => (Some(rec), None),
+        Err(e @ DexError::LedgerMismatch { .. }) => {
+            warn!("Wallet {} => {}", wallet_id, e);
+            (None, Some(e.to_string()))
+        }
+        Err(e) => return dex_error_response(&e),
+    };
+    match wallets.ledger(&wallet_id) {
+        Ok(entries) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(WalletLedgerView { wallet_id, entries, reconciliation, mismatch })),
+        )
+            .into_response(),
+        Err(e) => dex_error_response(&e),
+    }
+}
+
+/// `GET /wallets/:id/ledger` (`ReadAccount`).
+pub fn wallet_ledger_routes<S>(accounts: Arc<AccountsManager>, auth: Option<RoleAuth>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    guarded(Router::new().route("/wallets/:id/ledger", get(get_wallet_ledger)), &auth, Permission::ReadAccount)
+        .with_state(accounts)
+}
+
 // ==== Router aufbauen ====
 
+/// Router ohne Token-Pflicht, nur für lokale Tests.
 pub fn build_rest_api(state: AppState) -> Router {
+    build_rest_api_with_auth(state, None)
+}
+
+/// Öffentlich bleiben Ping, Order-Book, Candles und `/ws/marketdata`. Alle anderen Routen
+/// verlangen, sobald `auth` gesetzt ist, einen Token, dessen Rolle die Permission
+/// der Route hat (siehe `Role::allows`). `/rpc` prüft dieselben Permissions pro Methode.
+/// Account-Routen (`ReadAccount`) liefern darüber hinaus nur den eigenen Account,
+/// `/api/market/halt` verlangt `HaltMarket` und die Committee-Approvals.
+pub fn build_rest_api_with_auth(state: AppState, auth: Option<RoleAuth>) -> Router {
+    let trade = guarded(
+        Router::new()
+            .route("/api/place_order", post(place_order))
+            .route("/api/cancel_order", post(cancel_order))
+            .route("/api/commit_order", post(commit_order))
+            .route("/api/reveal_order", post(reveal_order))
+            .route("/orders/batch", post(place_orders_batch)),
+        &auth,
+        Permission::Trade,
+    );
+    let replicate = guarded(
+        Router::new().route("/api/replicate_shard/:id", post(force_replicate_shard)),
+        &auth,
+        Permission::ReplicateShards,
+    );
+    let read_state = guarded(
+        Router::new()
+            .route("/api/shards", get(get_all_shards))
+            .route("/api/shard/:id", get(get_single_shard))
+            .route("/api/state_hash", get(get_state_hash))
+            .route("/api/order/:id", get(get_order_status)),
+        &auth,
+        Permission::ReadState,
+    );
+    let read_account = guarded(
+        Router::new().route("/api/get_balance", post(get_balance)),
+        &auth,
+        Permission::ReadAccount,
+    );
+    let halt = guarded(
+        Router::new().route("/api/market/halt", post(halt_market)),
+        &auth,
+        Permission::HaltMarket,
+    );
+    let halt_status = guarded(
+        Router::new().route("/api/market/halt_status", get(get_halt_status)),
+        &auth,
+        Permission::ReadState,
+    );
+
+    let market_data = market_data_routes(state.market_data.clone());
+    let candles = trade_history_routes(state.trade_history.clone());
+    let rpc = jsonrpc_routes(state.clone(), auth.clone());
+
     Router::new()
         .route("/api/ping", get(ping))
-        .route("/api/place_order", post(place_order))
-        .route("/api/get_balance", post(get_balance))
-        .route("/api/shards", get(get_all_shards))
-        .route("/api/shard/:id", get(get_single_shard))
-        .route("/api/replicate_shard/:id", post(force_replicate_shard))
+        .route("/api/book", get(get_order_book))
+        .merge(trade)
+        .merge(replicate)
+        .merge(read_state)
+        .merge(read_account)
+        .merge(halt)
+        .merge(halt_status)
+        .merge(market_data)
+        .merge(candles)
+        .merge(rpc)
         .with_state(state)
 }
+
+/// Startet `router` auf `addr`; mit `tls = Some((cert, key))` über rustls, sonst im Klartext.
+pub async fn serve_router(router: Router, addr: SocketAddr, tls: Option<(String, String)>) -> anyhow::Result<()> {
+    match tls {
+        Some((cert, key)) => {
+            let tls_config = RustlsConfig::from_pem_file(&cert, &key).await?;
+            info!("HTTPS-Server auf {}", addr);
+            axum_server::bind_rustls(addr, tls_config)
+                .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+                .await?;
+        }
+        None => {
+            warn!("Kein TLS-Zertifikat konfiguriert => {} läuft im Klartext", addr);
+            axum::Server::bind(&addr)
+                .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    /// Der echte REST-Router aus `build_rest_api_with_auth`, Trader-Token `secret-token`.
+    fn protected_router() -> Router {
+        use crate::config_loader::load_config;
+        let state = AppState {
+            node: Arc::new(DexNode::new(load_config("config/node_config.yaml").unwrap(), None)),
+            shard_manager: ShardManager::new(3, None),
+            market_data: MarketDataHub::new(),
+            trade_history: TradeHistory::new(16),
+        };
+        let auth = RoleAuth::new().with_tokens(Role::Trader, vec!["secret-token".to_string()]);
+        build_rest_api_with_auth(state, Some(auth))
+    }
+
+    #[tokio::test]
+    async fn test_unauthenticated_mutating_request_rejected() {
+        let resp = protected_router()
+            .oneshot(Request::post("/api/place_order").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = protected_router()
+            .oneshot(
+                Request::post("/api/place_order")
+                    .header(header::AUTHORIZATION, "Bearer wrong")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_list_accounts_requires_admin_token() {
+        use crate::identity::wallet::WalletManager;
+        use crate::storage::db_layer::{DexDB, InMemoryDb};
+        let mem = || DexDB { rocks: None, fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))) };
+        let accounts = Arc::new(AccountsManager::new(
+            Arc::new(Mutex::new(mem())),
+            WalletManager::new(mem(), None, None, None),
+        ));
+        let app = || -> Router {
+            accounts_routes(
+                accounts.clone(),
+                Some(
+                    RoleAuth::new()
+                        .with_tokens(Role::Trader, vec!["user-token".to_string()])
+                        .with_tokens(Role::Admin, vec!["admin-token".to_string()]),
+                ),
+            )
+        };
+
+        let list = |token: &'static str| {
+            Request::get("/accounts?limit=10")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+        assert_eq!(app().oneshot(list("user-token")).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(app().oneshot(list("wrong-token")).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(app().oneshot(list("admin-token")).await.unwrap().status(), StatusCode::OK);
+
+        // Einzelabfrage nur für den eigenen Account
+        let app = || -> Router {
+            accounts_routes(
+                accounts.clone(),
+                Some(
+                    RoleAuth::new()
+                        .with_tokens(Role::Trader, vec!["shared".to_string()])
+                        .with_user_tokens(vec![("alice".to_string(), "alice-token".to_string())]),
+                ),
+            )
+        };
+        let info = |user: &str, token: &str| {
+            Request::get(format!("/accounts/{}", user))
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+        assert_eq!(app().oneshot(info("bob", "alice-token")).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(app().oneshot(info("alice", "shared")).await.unwrap().status(), StatusCode::FORBIDDEN);
+        // Eigener Account => Auth ok, existiert hier nur nicht
+        assert_eq!(app().oneshot(info("alice", "alice-token")).await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_balance_only_for_own_account() {
+        use crate::config_loader::load_config;
+        let state = AppState {
+            node: Arc::new(DexNode::new(load_config("config/node_config.yaml").unwrap(), None)),
+            shard_manager: ShardManager::new(3, None),
+            market_data: MarketDataHub::new(),
+            trade_history: TradeHistory::new(16),
+        };
+        state.node.user_deposit("alice", "BTC", 1.0);
+        let auth = RoleAuth::new()
+            .with_tokens(Role::Admin, vec!["admin".to_string()])
+            .with_user_tokens(vec![
+                ("alice".to_string(), "alice-token".to_string()),
+                ("bob".to_string(), "bob-token".to_string()),
+            ]);
+        let balance = |token: &str| {
+            Request::post("/api/get_balance")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::json!({ "user_id": "alice", "coin": "BTC" }).to_string()))
+                .unwrap()
+        };
+        let app = || build_rest_api_with_auth(state.clone(), Some(auth.clone()));
+        assert_eq!(app().oneshot(balance("bob-token")).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(app().oneshot(balance("alice-token")).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(app().oneshot(balance("admin")).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_2fa_routes_require_token_and_password() {
+        use crate::identity::accounts::{Account, AccountType};
+        use crate::identity::wallet::WalletManager;
+        use crate::storage::db_layer::DexDB;
+        use sha2::{Digest, Sha256};
+        let db = Arc::new(Mutex::new(DexDB::in_memory()));
+        db.lock().unwrap().store_struct("accounts/alice", &Account {
+            user_id: "alice".into(),
+            account_type: AccountType::NormalUser,
+            is_fee_pool_recipient: false,
+            fee_share_percent: 0.0,
+            wallet_ids: vec![],
+            paused: false,
+            country: None,
+            two_fa_secret: None,
+            hashed_password: Some(format!("sha256:{}", hex::encode(Sha256::digest(b"pw")))),
+            recovery_code_hashes: vec![],
+            last_totp_step: None,
+            pending_two_fa_secret: None,
+            active: true,
+        }).unwrap();
+        let accounts = Arc::new(AccountsManager::new(db, WalletManager::new(DexDB::in_memory(), None, None, None)));
+        let app = || -> Router {
+            accounts_routes(
+                accounts.clone(),
+                Some(
+                    RoleAuth::new()
+                        .with_tokens(Role::Trader, vec!["user-token".to_string()])
+                        .with_tokens(Role::ReadOnly, vec!["ro-token".to_string()])
+                        .with_user_tokens(vec![
+                            ("alice".to_string(), "alice-token".to_string()),
+                            ("bob".to_string(), "bob-token".to_string()),
+                        ]),
+                ),
+            )
+        };
+        let enroll = |token: &'static str, password: &str| {
+            Request::post("/accounts/alice/2fa/enroll")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::json!({ "password": password }).to_string()))
+                .unwrap()
+        };
+        assert_eq!(app().oneshot(enroll("ro-token", "pw")).await.unwrap().status(), StatusCode::FORBIDDEN);
+        // Nur der eigene Account: fremde und ungebundene Tokens kommen nicht bis zur Passwortprüfung
+        for _ in 0..crate::identity::accounts::MAX_2FA_FAILURES {
+            assert_eq!(app().oneshot(enroll("bob-token", "falsch")).await.unwrap().status(), StatusCode::FORBIDDEN);
+            assert_eq!(app().oneshot(enroll("user-token", "falsch")).await.unwrap().status(), StatusCode::FORBIDDEN);
+        }
+        assert_eq!(app().oneshot(enroll("alice-token", "falsch")).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+        let resp = app().oneshot(enroll("alice-token", "pw")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(json["data"]["secret"].as_str().is_some_and(|s| !s.is_empty()));
+        // Unbestätigt: ein erneutes Enroll ersetzt das Secret, Bestätigen braucht einen Code
+        assert_eq!(app().oneshot(enroll("alice-token", "pw")).await.unwrap().status(), StatusCode::OK);
+        let confirm = Request::post("/accounts/alice/2fa/confirm")
+            .header(header::AUTHORIZATION, "Bearer alice-token")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::json!({ "password": "pw", "code": "000000" }).to_string()))
+            .unwrap();
+        assert_eq!(app().oneshot(confirm).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_ledger_route_reports_reconciliation() {
+        use crate::identity::balance_ledger::Posting;
+        use crate::identity::wallet::{BlockchainType, WalletInfo};
+        use crate::storage::db_layer::DexDB;
+        let accounts = Arc::new(AccountsManager::new(
+            Arc::new(Mutex::new(DexDB::in_memory())),
+            WalletManager::new(DexDB::in_memory(), None, None, None),
+        ));
+        let wallets = &accounts.wallet_manager;
+        wallets.store_wallet(&WalletInfo {
+            wallet_id: "w1".into(),
+            blockchain: BlockchainType::Bitcoin,
+            public_info: "xpub".into(),
+            address: "bc1q".into(),
+            onchain_balance: 0.0,
+            dex_balance: 0.0,
+        }).unwrap();
+        wallets.add_dex_balance("w1", 2.5, Posting::new("fee_pool", "fee_payout", "t1")).unwrap();
+
+        let get = |path: &str| Request::get(path).body(Body::empty()).unwrap();
+        let app = || -> Router { wallet_ledger_routes(accounts.clone(), None) };
+        let resp = app().oneshot(get("/wallets/w1/ledger")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"]["entries"][0]["reason"], "fee_payout");
+        assert_eq!(json["data"]["reconciliation"]["dex_balance"], 2.5);
+        assert!(json["data"]["mismatch"].is_null());
+
+        assert_eq!(app().oneshot(get("/wallets/nope/ledger")).await.unwrap().status(), StatusCode::NOT_FOUND);
+
+        // Mit Auth nur das eigene Wallet
+        let auth = RoleAuth::new()
+            .with_tokens(Role::Admin, vec!["admin".to_string()])
+            .with_user_tokens(vec![("alice".to_string(), "alice-token".to_string())]);
+        let ledger = |token: &str| {
+            Request::get("/wallets/w1/ledger")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let app = || -> Router { wallet_ledger_routes(accounts.clone(), Some(auth.clone())) };
+        assert_eq!(app().oneshot(ledger("alice-token")).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(app().oneshot(ledger("admin")).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_orders_batch_route_atomic_flag() {
+        use crate::config_loader::load_config;
+        let state = AppState {
+            node: Arc::new(DexNode::new(load_config("config/node_config.yaml").unwrap(), None)),
+            shard_manager: ShardManager::new(3, None),
+            market_data: MarketDataHub::new(),
+            trade_history: TradeHistory::new(16),
+        };
+        state.node.user_deposit("mm", "BTC", 1.0);
+        let batch = |atomic: bool| {
+            let order = |amount: f64| serde_json::json!({
+                "user_id": "mm", "coin_to_sell": "BTC", "coin_to_buy": "USDT",
+                "amount": amount, "price": 100.0, "side": "sell"
+            });
+            Request::post("/orders/batch")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::json!({ "orders": [order(0.6), order(0.6)], "atomic": atomic }).to_string()))
+                .unwrap()
+        };
+
+        let resp = build_rest_api(state.clone()).oneshot(batch(true)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let json: serde_json::Value = serde_json::from_slice(&hyper::body::to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(json["code"], "insufficient_balance");
+        assert_eq!(json["data"]["accepted"], 0);
+        assert_eq!(state.node.user_get_free_balance("mm", "BTC"), 1.0);
+
+        let resp = build_rest_api(state.clone()).oneshot(batch(false)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let json: serde_json::Value = serde_json::from_slice(&hyper::body::to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(json["data"]["accepted"], 1);
+        assert_eq!(json["data"]["results"][1]["status"], "rejected");
+    }
+
+    #[tokio::test]
+    async fn test_place_order_only_for_own_account() {
+        use crate::config_loader::load_config;
+        let state = AppState {
+            node: Arc::new(DexNode::new(load_config("config/node_config.yaml").unwrap(), None)),
+            shard_manager: ShardManager::new(3, None),
+            market_data: MarketDataHub::new(),
+            trade_history: TradeHistory::new(16),
+        };
+        state.node.user_deposit("alice", "BTC", 1.0);
+        let auth = RoleAuth::new()
+            .with_tokens(Role::Trader, vec!["shared".to_string()])
+            .with_user_tokens(vec![
+                ("alice".to_string(), "alice-token".to_string()),
+                ("bob".to_string(), "bob-token".to_string()),
+            ]);
+        let order = serde_json::json!({
+            "user_id": "alice", "coin_to_sell": "BTC", "coin_to_buy": "USDT",
+            "amount": 0.5, "price": 100.0, "side": "sell"
+        });
+        let post = |path: &str, token: &str, body: &serde_json::Value| {
+            Request::post(path)
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let batch = serde_json::json!({ "orders": [order.clone()], "atomic": false });
+        let app = || build_rest_api_with_auth(state.clone(), Some(auth.clone()));
+
+        for token in ["bob-token", "shared"] {
+            assert_eq!(app().oneshot(post("/api/place_order", token, &order)).await.unwrap().status(), StatusCode::FORBIDDEN);
+            assert_eq!(app().oneshot(post("/orders/batch", token, &batch)).await.unwrap().status(), StatusCode::FORBIDDEN);
+        }
+        assert_eq!(state.node.user_get_free_balance("alice", "BTC"), 1.0);
+
+        assert_eq!(app().oneshot(post("/api/place_order", "alice-token", &order)).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(app().oneshot(post("/orders/batch", "alice-token", &batch)).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(state.node.user_get_free_balance("alice", "BTC"), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_commit_and_reveal_only_for_own_account() {
+        use crate::config_loader::load_config;
+        use crate::dex_logic::commit_reveal::{order_commitment_hash, CommitRevealBook, CommitRevealWindow};
+        use crate::matching_engine::{MatchingEngine, OrderSide, OrderType};
+
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+        let book = CommitRevealBook::new(CommitRevealWindow { epoch: 1, commit_deadline: now + 600, reveal_deadline: now + 660 }).unwrap();
+        let mut node = DexNode::new(load_config("config/node_config.yaml").unwrap(), None);
+        node.set_matching_engine(Arc::new(Mutex::new(MatchingEngine::new().with_commit_reveal(book))));
+        let state = AppState {
+            node: Arc::new(node),
+            shard_manager: ShardManager::new(3, None),
+            market_data: MarketDataHub::new(),
+            trade_history: TradeHistory::new(16),
+        };
+        let auth = RoleAuth::new().with_user_tokens(vec![
+            ("alice".to_string(), "alice-token".to_string()),
+            ("bob".to_string(), "bob-token".to_string()),
+        ]);
+        let post = |path: &str, token: &str, body: &serde_json::Value| {
+            Request::post(path)
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let app = || build_rest_api_with_auth(state.clone(), Some(auth.clone()));
+
+        let order = OrderData::new("cr1", "alice", OrderSide::Buy, OrderType::Limit(100.0), 1.0, now).signed_for_tests();
+        let commit = serde_json::json!({
+            "user_id": "alice",
+            "hash": hex::encode(order_commitment_hash(&order, b"salt").unwrap()),
+        });
+        assert_eq!(app().oneshot(post("/api/commit_order", "bob-token", &commit)).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(app().oneshot(post("/api/commit_order", "alice-token", &commit)).await.unwrap().status(), StatusCode::OK);
+        let bad = serde_json::json!({ "user_id": "alice", "hash": "abcd" });
+        assert_eq!(app().oneshot(post("/api/commit_order", "alice-token", &bad)).await.unwrap().status(), StatusCode::BAD_REQUEST);
+
+        let reveal = serde_json::json!({ "order": order, "nonce": hex::encode(b"salt") });
+        assert_eq!(app().oneshot(post("/api/reveal_order", "bob-token", &reveal)).await.unwrap().status(), StatusCode::FORBIDDEN);
+        // Commit-Phase läuft noch => Reveal abgelehnt
+        assert!(!app().oneshot(post("/api/reveal_order", "alice-token", &reveal)).await.unwrap().status().is_success());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_order_only_by_bound_owner() {
+        use crate::config_loader::load_config;
+        use crate::node_logic::OrderSide;
+        let state = AppState {
+            node: Arc::new(DexNode::new(load_config("config/node_config.yaml").unwrap(), None)),
+            shard_manager: ShardManager::new(3, None),
+            market_data: MarketDataHub::new(),
+            trade_history: TradeHistory::new(16),
+        };
+        state.node.user_deposit("alice", "BTC", 1.0);
+        let order_id = state.node.place_order(OrderRequest {
+            user_id: "alice".into(),
+            coin_to_sell: "BTC".into(),
+            coin_to_buy: "USDT".into(),
+            amount: 1.0,
+            price: 100.0,
+            side: OrderSide::Sell,
+            nonce: None,
+        }).unwrap();
+        let auth = RoleAuth::new()
+            .with_tokens(Role::Trader, vec!["shared".to_string()])
+            .with_user_tokens(vec![
+                ("alice".to_string(), "alice-token".to_string()),
+                ("bob".to_string(), "bob-token".to_string()),
+            ]);
+        let cancel = |token: &str| {
+            Request::post("/api/cancel_order")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .header(header::CONTENT_TYPE, "application/json")
+                // user_id im Body wird mit Auth ignoriert
+                .body(Body::from(serde_json::json!({ "user_id": "alice", "order_id": order_id }).to_string()))
+                .unwrap()
+        };
+        let app = || build_rest_api_with_auth(state.clone(), Some(auth.clone()));
+
+        assert_eq!(app().oneshot(cancel("bob-token")).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(app().oneshot(cancel("shared")).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(state.node.user_get_free_balance("alice", "BTC"), 0.0);
+        assert_eq!(app().oneshot(cancel("alice-token")).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(state.node.user_get_free_balance("alice", "BTC"), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_role_route_matrix() {
+        let auth = Some(
+            RoleAuth::new()
+                .with_tokens(Role::Admin, vec!["admin".to_string()])
+                .with_tokens(Role::Fullnode, vec!["fullnode".to_string()])
+                .with_tokens(Role::Trader, vec!["trader".to_string()])
+                .with_tokens(Role::ReadOnly, vec!["readonly".to_string()]),
+        );
+        let app = || -> Router {
+            let ok = || async { "ok" };
+            Router::new()
+                .merge(guarded(Router::new().route("/trade", post(ok)), &auth, Permission::Trade))
+                .merge(guarded(Router::new().route("/halt", post(ok)), &auth, Permission::HaltMarket))
+                .merge(guarded(Router::new().route("/replicate", post(ok)), &auth, Permission::ReplicateShards))
+                .merge(guarded(Router::new().route("/shards", get(ok)), &auth, Permission::ReadState))
+        };
+        let call = |path: &str, token: &str| {
+            let builder = if path == "/shards" { Request::get(path) } else { Request::post(path) };
+            builder
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let cases = [
+            ("/trade", "trader", StatusCode::OK),
+            ("/trade", "admin", StatusCode::OK),
+            ("/trade", "readonly", StatusCode::FORBIDDEN),
+            ("/trade", "fullnode", StatusCode::FORBIDDEN),
+            ("/halt", "admin", StatusCode::OK),
+            ("/halt", "trader", StatusCode::FORBIDDEN),
+            ("/halt", "fullnode", StatusCode::FORBIDDEN),
+            ("/replicate", "fullnode", StatusCode::OK),
+            ("/replicate", "trader", StatusCode::FORBIDDEN),
+            ("/shards", "readonly", StatusCode::OK),
+            ("/shards", "nobody", StatusCode::UNAUTHORIZED),
+        ];
+        for (path, token, expected) in cases {
+            let status = app().oneshot(call(path, token)).await.unwrap().status();
+            assert_eq!(status, expected, "{} mit {}", path, token);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_halt_route_admin_only_and_applies_approvals() {
+        use crate::config_loader::load_config;
+        use crate::matching_engine::{halt_signing_bytes, MarketHaltControl};
+        use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+        let secret = SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        let key = Keypair { secret, public };
+        let mut node = DexNode::new(load_config("config/node_config.yaml").unwrap(), None);
+        node.set_halt_control(Arc::new(Mutex::new(MarketHaltControl::new(vec![key.public], 1).unwrap())));
+        let state = AppState {
+            node: Arc::new(node),
+            shard_manager: ShardManager::new(3, None),
+            market_data: MarketDataHub::new(),
+            trade_history: TradeHistory::new(16),
+        };
+        let auth = RoleAuth::new()
+            .with_tokens(Role::Admin, vec!["admin".to_string()])
+            .with_tokens(Role::Trader, vec!["trader".to_string()]);
+        let app = || build_rest_api_with_auth(state.clone(), Some(auth.clone()));
+
+        let approval = serde_json::json!({
+            "signer": hex::encode(key.public.as_bytes()),
+            "signature": hex::encode(key.sign(&halt_signing_bytes("BTC/USDT", HaltAction::Halt, 0).unwrap()).to_bytes()),
+        });
+        let halt = |token: &str| {
+            Request::post("/api/market/halt")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    serde_json::json!({ "market": "BTC/USDT", "action": "Halt", "approvals": [approval] }).to_string(),
+                ))
+                .unwrap()
+        };
+        assert_eq!(app().oneshot(halt("trader")).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert!(!state.node.is_market_halted("BTC/USDT"));
+
+        let resp = app().oneshot(halt("admin")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let json: serde_json::Value = serde_json::from_slice(&hyper::body::to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(json["data"]["halted"], true);
+        assert_eq!(json["data"]["nonce"], 1);
+        assert!(state.node.is_market_halted("BTC/USDT"));
+        // Beschluss verbraucht => kein zweites Mal
+        assert_ne!(app().oneshot(halt("admin")).await.unwrap().status(), StatusCode::OK);
+
+        let status = Request::get("/api/market/halt_status?market=BTC/USDT")
+            .header(header::AUTHORIZATION, "Bearer trader")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(app().oneshot(status).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_valid_token_and_open_probe() {
+        use crate::config_loader::load_config;
+        // Token akzeptiert => der Handler selbst lehnt den leeren Body ab, nicht die Auth
+        let resp = protected_router()
+            .oneshot(
+                Request::post("/api/place_order")
+                    .header("x-api-key", "secret-token")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_ne!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_ne!(resp.status(), StatusCode::FORBIDDEN);
+
+        // Demo-Tokens aus der Beispiel-Config authentifizieren nie
+        let cfg = load_config("config/node_config.yaml").unwrap();
+        assert!(RoleAuth::from_config(&cfg).principal_of("CHANGE_ME_API_TOKEN").is_none());
+
+        let resp = protected_router()
+            .oneshot(Request::get("/api/ping").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_state_hash_of_two_nodes_names_diverging_shard() {
+        use crate::config_loader::load_config;
+        use crate::node_logic::StateDigest;
+        let (dir_a, dir_b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let state = |dir: &std::path::Path| {
+            let shard_manager = ShardManager::new(3, None);
+            for sid in [0u32, 5] {
+                let path = dir.join(format!("shard_{}", sid));
+                shard_manager
+                    .create_shard(sid, path.to_str().unwrap(), crate::watchtower::Watchtower::new("n"))
+                    .unwrap();
+            }
+            AppState {
+                node: Arc::new(DexNode::new(load_config("config/node_config.yaml").unwrap(), None)),
+                shard_manager,
+                market_data: MarketDataHub::new(),
+                trade_history: TradeHistory::new(16),
+            }
+        };
+        let (a, b) = (state(dir_a.path()), state(dir_b.path()));
+        b.shard_manager.shards.lock().unwrap().get_mut(&5).unwrap().crdt_state
+            .add_local_order("nb", "o1", "alice", 1.0, 100.0)
+            .unwrap();
+
+        let digest = |st: &AppState| {
+            let app = build_rest_api_with_auth(st.clone(), None);
+            async move {
+                let resp = app.oneshot(Request::get("/api/state_hash").body(Body::empty()).unwrap()).await.unwrap();
+                assert_eq!(resp.status(), StatusCode::OK);
+                let json: serde_json::Value =
+                    serde_json::from_slice(&hyper::body::to_bytes(resp.into_body()).await.unwrap()).unwrap();
+                serde_json::from_value::<StateDigest>(json["data"].clone()).unwrap()
+            }
+        };
+        let (da, db) = (digest(&a).await, digest(&b).await);
+        assert_eq!(da.shards.keys().copied().collect::<Vec<_>>(), vec![0, 5]);
+        assert_eq!(da.diverging_shards(&db), vec![5]);
+    }
+}
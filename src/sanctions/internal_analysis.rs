@@ -4,7 +4,29 @@
 
 
 // Dieses Modul implementiert einen einfachen Ansatz zur internen Analyse von Transaktionen.
-// Es wird anhand des Transaktionsbetrags ermittelt, ob eine Transaktion ungew�hnlich (verd�chtig) ist.
+// Es wird anhand des Transaktionsbetrags ermittelt, ob eine Transaktion ungewöhnlich (verdächtig) ist.
+//
+// Zusätzlich: `AnomalyEngine` bewertet Accounts verhaltensbasiert über einen
+// Strom von `ActivityEvent`s (Trades, Ein- und Auszahlungen) innerhalb eines
+// gleitenden Zeitfensters:
+//  - Self-Trades (Käufer == Verkäufer)
+//  - Round-Tripping / Wash-Trading (A kauft von B und B von A im Fenster)
+//  - Velocity (zu viele Trades pro Fenster)
+//  - Deposit -> Trade -> Withdraw in kurzer Folge
+// Überschreitet der Score `report_threshold`, wird ein signierter
+// `AnomalyReport` an den `AnomalySink` (Gossip zu Fullnodes) gegeben. Ab
+// `auto_pause_threshold` wird der Account bis zur manuellen Prüfung pausiert.
+
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::warn;
+
+use crate::error::DexError;
+use crate::identity::accounts::AccountsManager;
+use crate::market_data::TradeEvent;
 
 /// Struktur zur Darstellung einer Transaktion.
 #[derive(Debug, Clone)]
@@ -15,8 +37,8 @@ pub struct Transaction {
     pub amount: f64,
 }
 
-/// Analysiert eine Liste von Transaktionen und gibt jene zur�ck, die als verd�chtig gelten.
-/// Eine Transaktion gilt als verd�chtig, wenn ihr Betrag mehr als 2 Standardabweichungen �ber dem Durchschnitt liegt.
+/// Analysiert eine Liste von Transaktionen und gibt jene zurück, die als verdächtig gelten.
+/// Eine Transaktion gilt als verdächtig, wenn ihr Betrag mehr als 2 Standardabweichungen über dem Durchschnitt liegt.
 pub fn analyze_transactions(transactions: &[Transaction]) -> Vec<&Transaction> {
     // Berechnung des Durchschnitts
     let sum: f64 = transactions.iter().map(|tx| tx.amount).sum();
@@ -28,13 +50,451 @@ pub fn analyze_transactions(transactions: &[Transaction]) -> Vec<&Transaction> {
     let std_dev = variance.sqrt();
     let threshold = average + 2.0 * std_dev;
 
-    // Filter: Transaktionen mit einem Betrag oberhalb des Schwellenwerts gelten als verd�chtig
+    // Filter: Transaktionen mit einem Betrag oberhalb des Schwellenwerts gelten als verdächtig
     transactions.iter().filter(|tx| tx.amount > threshold).collect()
 }
 
+/// Aktivität eines Accounts, wie sie die AnomalyEngine sieht.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ActivityEvent {
+    Trade { buyer: String, seller: String, market: String, quantity: f64, price: f64, timestamp: u64 },
+    Deposit { user_id: String, amount: f64, timestamp: u64 },
+    Withdrawal { user_id: String, amount: f64, timestamp: u64 },
+}
+
+impl ActivityEvent {
+    /// Brücke vom Marktdaten-Stream: `TradeEvent` enthält nur Order-IDs,
+    /// die User-IDs liefert die Settlement-Seite.
+    pub fn from_trade(trade: &TradeEvent, buyer: &str, seller: &str) -> Self {
+        ActivityEvent::Trade {
+            buyer: buyer.to_string(),
+            seller: seller.to_string(),
+            market: trade.market.clone(),
+            quantity: trade.quantity,
+            price: trade.price,
+            timestamp: trade.timestamp,
+        }
+    }
+
+    fn timestamp(&self) -> u64 {
+        match self {
+            ActivityEvent::Trade { timestamp, .. }
+            | ActivityEvent::Deposit { timestamp, .. }
+            | ActivityEvent::Withdrawal { timestamp, .. } => *timestamp,
+        }
+    }
+}
+
+/// Gewichte und Schwellen der Heuristiken.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyConfig {
+    /// Gleitendes Fenster in Sekunden.
+    pub window_secs: u64,
+    pub self_trade_weight: f64,
+    pub round_trip_weight: f64,
+    /// Trades pro Fenster, ab denen `velocity_weight` pro weiterem Trade greift.
+    pub max_trades_per_window: usize,
+    pub velocity_weight: f64,
+    pub deposit_trade_withdraw_weight: f64,
+    pub report_threshold: f64,
+    pub auto_pause_threshold: f64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: 3600,
+            self_trade_weight: 40.0,
+            round_trip_weight: 20.0,
+            max_trades_per_window: 50,
+            velocity_weight: 2.0,
+            deposit_trade_withdraw_weight: 30.0,
+            report_threshold: 50.0,
+            auto_pause_threshold: 80.0,
+        }
+    }
+}
+
+const ANOMALY_REPORT_DOMAIN: &str = "my_dex/anomaly_report/v1";
+
+/// Signierter Befund eines Nodes über einen Account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyReport {
+    pub user_id: String,
+    pub score: f64,
+    pub reasons: Vec<String>,
+    pub window_start: u64,
+    pub window_end: u64,
+    pub auto_paused: bool,
+    /// Öffentlicher Schlüssel des meldenden Nodes (hex).
+    pub reporter: String,
+    pub signature: String,
+}
+
+impl AnomalyReport {
+    fn signing_bytes(&self) -> Result<Vec<u8>, DexError> {
+        crate::utils::canonical::signing_bytes(
+            ANOMALY_REPORT_DOMAIN,
+            &AnomalyReportSigningView {
+                user_id: &self.user_id,
+                score: self.score,
+                reasons: &self.reasons,
+                window_start: self.window_start,
+                window_end: self.window_end,
+                auto_paused: self.auto_paused,
+                reporter: &self.reporter,
+            },
+        )
+    }
+
+    pub fn verify(&self) -> bool {
+        let pk = hex::decode(&self.reporter).ok().and_then(|b| PublicKey::from_bytes(&b).ok());
+        let sig = hex::decode(&self.signature).ok().and_then(|b| Signature::from_bytes(&b).ok());
+        match (pk, sig) {
+            (Some(pk), Some(sig)) => match self.signing_bytes() {
+                Ok(bytes) => pk.verify(&bytes, &sig).is_ok(),
+                Err(_) => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AnomalyReportSigningView<'a> {
+    user_id: &'a str,
+    score: f64,
+    reasons: &'a [String],
+    window_start: u64,
+    window_end: u64,
+    auto_paused: bool,
+    reporter: &'a str,
+}
+
+/// Ziel für Reports, z.B. der Gossip-Kanal zu den Fullnodes.
+pub trait AnomalySink: Send + Sync {
+    fn publish(&self, report: &AnomalyReport);
+}
+
+impl AnomalySink for UnboundedSender<AnomalyReport> {
+    fn publish(&self, report: &AnomalyReport) {
+        if self.send(report.clone()).is_err() {
+            warn!("AnomalySink => Gossip-Kanal geschlossen, Report für {} verworfen", report.user_id);
+        }
+    }
+}
+
+/// Pausiert Accounts bei hohem Score.
+pub trait AccountPauser: Send + Sync {
+    fn pause(&self, user_id: &str) -> Result<(), DexError>;
+}
+
+impl AccountPauser for AccountsManager {
+    fn pause(&self, user_id: &str) -> Result<(), DexError> {
+        self.pause_account(user_id)
+    }
+}
+
+impl<P: AccountPauser + ?Sized> AccountPauser for Arc<P> {
+    fn pause(&self, user_id: &str) -> Result<(), DexError> {
+        (**self).pause(user_id)
+    }
+}
+
+pub struct AnomalyEngine {
+    config: AnomalyConfig,
+    keypair: Keypair,
+    sink: Box<dyn AnomalySink>,
+    pauser: Option<Box<dyn AccountPauser>>,
+    /// Events pro User im aktuellen Fenster.
+    recent: HashMap<String, VecDeque<ActivityEvent>>,
+    /// Bereits gemeldete User (bis der Score wieder unter die Schwelle fällt).
+    reported: HashSet<String>,
+    /// Zeitpunkt des letzten `prune`-Laufs.
+    last_prune: u64,
+}
+
+impl AnomalyEngine {
+    pub fn new(config: AnomalyConfig, keypair: Keypair, sink: Box<dyn AnomalySink>) -> Self {
+        Self {
+            config,
+            keypair,
+            sink,
+            pauser: None,
+            recent: HashMap::new(),
+            reported: HashSet::new(),
+            last_prune: 0,
+        }
+    }
+
+    pub fn with_pauser(mut self, pauser: Box<dyn AccountPauser>) -> Self {
+        self.pauser = Some(pauser);
+        self
+    }
+
+    /// Verarbeitet ein Event und liefert ggf. die neu erzeugten Reports.
+    pub fn observe(&mut self, event: ActivityEvent) -> Vec<AnomalyReport> {
+        let users: Vec<String> = match &event {
+            ActivityEvent::Trade { buyer, seller, .. } if buyer == seller => vec![buyer.clone()],
+            ActivityEvent::Trade { buyer, seller, .. } => vec![buyer.clone(), seller.clone()],
+            ActivityEvent::Deposit { user_id, .. } | ActivityEvent::Withdrawal { user_id, .. } => vec![user_id.clone()],
+        };
+        let now = event.timestamp();
+        // Einmal pro Fenster: User ohne Events im Fenster vergessen
+        if now.saturating_sub(self.last_prune) >= self.config.window_secs {
+            self.prune(now);
+        }
+        let mut reports = Vec::new();
+        for user in users {
+            let events = self.recent.entry(user.clone()).or_default();
+            events.push_back(event.clone());
+            while events.front().map_or(false, |e| e.timestamp() + self.config.window_secs < now) {
+                events.pop_front();
+            }
+            let (score, reasons) = score_events(&user, events, &self.config);
+            if score < self.config.report_threshold {
+                self.reported.remove(&user);
+                continue;
+            }
+            if !self.reported.insert(user.clone()) {
+                continue;
+            }
+            let window_start = events.front().map(|e| e.timestamp()).unwrap_or(now);
+            reports.push(self.raise(&user, score, reasons, window_start, now));
+        }
+        reports
+    }
+
+    /// Entfernt Events ausserhalb des Fensters und User, die keine mehr haben.
+    pub fn prune(&mut self, now: u64) {
+        let window = self.config.window_secs;
+        self.recent.retain(|_, events| {
+            while events.front().map_or(false, |e| e.timestamp() + window < now) {
+                events.pop_front();
+            }
+            !events.is_empty()
+        });
+        let recent = &self.recent;
+        self.reported.retain(|user| recent.contains_key(user));
+        self.last_prune = now;
+    }
+
+    /// Anzahl der User mit Events im Fenster.
+    pub fn tracked_users(&self) -> usize {
+        self.recent.len()
+    }
+
+    fn raise(&self, user: &str, score: f64, reasons: Vec<String>, window_start: u64, window_end: u64) -> AnomalyReport {
+        let mut auto_paused = false;
+        if score >= self.config.auto_pause_threshold {
+            if let Some(p) = &self.pauser {
+                match p.pause(user) {
+                    Ok(()) => auto_paused = true,
+                    Err(e) => warn!("AnomalyEngine => Auto-Pause für {} fehlgeschlagen: {:?}", user, e),
+                }
+            }
+        }
+        let mut report = AnomalyReport {
+            user_id: user.to_string(),
+            score,
+            reasons,
+            window_start,
+            window_end,
+            auto_paused,
+            reporter: hex::encode(self.keypair.public.as_bytes()),
+            signature: String::new(),
+        };
+        match report.signing_bytes() {
+            Ok(bytes) => report.signature = hex::encode(self.keypair.sign(&bytes).to_bytes()),
+            // NaN-Score (fehlerhafte Gewichte) => Report bleibt unsigniert und fällt bei verify() durch
+            Err(e) => warn!("AnomalyEngine => Report für {} nicht signierbar: {:?}", user, e),
+        }
+        warn!("Anomalie => user={} score={:.1} reasons={:?} paused={}", user, score, report.reasons, auto_paused);
+        self.sink.publish(&report);
+        report
+    }
+}
+
+/// Bewertet die Events eines Users im Fenster.
+fn score_events(user: &str, events: &VecDeque<ActivityEvent>, cfg: &AnomalyConfig) -> (f64, Vec<String>) {
+    let mut score = 0.0;
+    let mut reasons = Vec::new();
+
+    let mut self_trades = 0usize;
+    let mut trades = 0usize;
+    let mut bought_from: HashSet<&str> = HashSet::new();
+    let mut sold_to: HashSet<&str> = HashSet::new();
+    let mut deposit_seen = false;
+    let mut traded_after_deposit = false;
+    let mut dtw = 0usize;
+
+    for e in events {
+        match e {
+            ActivityEvent::Trade { buyer, seller, .. } => {
+                trades += 1;
+                if buyer == seller {
+                    self_trades += 1;
+                } else if buyer == user {
+                    bought_from.insert(seller.as_str());
+                } else {
+                    sold_to.insert(buyer.as_str());
+                }
+                if deposit_seen {
+                    traded_after_deposit = true;
+                }
+            }
+            ActivityEvent::Deposit { .. } => deposit_seen = true,
+            ActivityEvent::Withdrawal { .. } => {
+                if traded_after_deposit {
+                    dtw += 1;
+                    deposit_seen = false;
+                    traded_after_deposit = false;
+                }
+            }
+        }
+    }
+
+    if self_trades > 0 {
+        score += cfg.self_trade_weight * self_trades as f64;
+        reasons.push(format!("self_trade x{}", self_trades));
+    }
+    let round_trips = bought_from.intersection(&sold_to).count();
+    if round_trips > 0 {
+        score += cfg.round_trip_weight * round_trips as f64;
+        reasons.push(format!("round_trip with {} counterparties", round_trips));
+    }
+    if trades > cfg.max_trades_per_window {
+        score += cfg.velocity_weight * (trades - cfg.max_trades_per_window) as f64;
+        reasons.push(format!("velocity {} trades/window", trades));
+    }
+    if dtw > 0 {
+        score += cfg.deposit_trade_withdraw_weight * dtw as f64;
+        reasons.push(format!("deposit_trade_withdraw x{}", dtw));
+    }
+    (score, reasons)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ed25519_dalek::SecretKey;
+    use std::sync::Mutex;
+
+    fn node_key() -> Keypair {
+        let secret = SecretKey::from_bytes(&[3u8; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    #[derive(Clone, Default)]
+    struct Collect(Arc<Mutex<Vec<AnomalyReport>>>);
+    impl AnomalySink for Collect {
+        fn publish(&self, report: &AnomalyReport) {
+            self.0.lock().unwrap().push(report.clone());
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordPause(Arc<Mutex<Vec<String>>>);
+    impl AccountPauser for RecordPause {
+        fn pause(&self, user_id: &str) -> Result<(), DexError> {
+            self.0.lock().unwrap().push(user_id.to_string());
+            Ok(())
+        }
+    }
+
+    fn trade(buyer: &str, seller: &str, ts: u64) -> ActivityEvent {
+        ActivityEvent::Trade {
+            buyer: buyer.into(),
+            seller: seller.into(),
+            market: "BTC/USDT".into(),
+            quantity: 1.0,
+            price: 100.0,
+            timestamp: ts,
+        }
+    }
+
+    #[test]
+    fn test_wash_trading_triggers_report() {
+        let sink = Collect::default();
+        let paused = RecordPause::default();
+        let mut engine = AnomalyEngine::new(AnomalyConfig::default(), node_key(), Box::new(sink.clone()))
+            .with_pauser(Box::new(paused.clone()));
+
+        // Hin- und Herhandeln zwischen zwei Accounts plus Self-Trades
+        for i in 0..3 {
+            engine.observe(trade("mallory", "trent", i * 10));
+            engine.observe(trade("trent", "mallory", i * 10 + 5));
+        }
+        engine.observe(trade("mallory", "mallory", 40));
+        engine.observe(trade("mallory", "mallory", 41));
+
+        let reports = sink.0.lock().unwrap().clone();
+        let mallory = reports.iter().find(|r| r.user_id == "mallory").expect("report for mallory");
+        assert!(mallory.score >= AnomalyConfig::default().report_threshold);
+        assert!(mallory.reasons.iter().any(|r| r.starts_with("self_trade")));
+        assert!(mallory.verify());
+        assert!(mallory.auto_paused);
+        assert_eq!(paused.0.lock().unwrap().as_slice(), ["mallory".to_string()]);
+        // Nur ein Report pro Episode
+        assert_eq!(reports.iter().filter(|r| r.user_id == "mallory").count(), 1);
+    }
+
+    #[test]
+    fn test_report_signature_covers_fields() {
+        let sink = Collect::default();
+        let paused = Arc::new(RecordPause::default());
+        let mut engine = AnomalyEngine::new(AnomalyConfig::default(), node_key(), Box::new(sink.clone()))
+            .with_pauser(Box::new(paused.clone()));
+        for ts in 0..6 {
+            engine.observe(trade("mallory", "mallory", ts));
+        }
+        let report = sink.0.lock().unwrap()[0].clone();
+        assert!(report.verify());
+        assert_eq!(paused.0.lock().unwrap().as_slice(), ["mallory".to_string()]);
+
+        // Jede Änderung an einem signierten Feld bricht die Signatur
+        let mut tampered = report.clone();
+        tampered.reasons.push("manual_review".into());
+        assert!(!tampered.verify());
+        let mut tampered = report.clone();
+        tampered.auto_paused = !tampered.auto_paused;
+        assert!(!tampered.verify());
+        let mut tampered = report;
+        tampered.score += 1.0;
+        assert!(!tampered.verify());
+    }
+
+    #[test]
+    fn test_normal_trading_no_report() {
+        let sink = Collect::default();
+        let mut engine = AnomalyEngine::new(AnomalyConfig::default(), node_key(), Box::new(sink.clone()));
+
+        engine.observe(ActivityEvent::Deposit { user_id: "alice".into(), amount: 100.0, timestamp: 0 });
+        let counterparties = ["bob", "carol", "dave", "erin", "frank"];
+        for (i, cp) in counterparties.iter().enumerate() {
+            engine.observe(trade("alice", cp, 100 + i as u64 * 60));
+        }
+        // Auszahlung erst lange nach dem Fenster
+        engine.observe(ActivityEvent::Withdrawal { user_id: "alice".into(), amount: 50.0, timestamp: 10_000 });
+
+        assert!(sink.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_idle_users_are_pruned() {
+        let sink = Collect::default();
+        let mut engine = AnomalyEngine::new(AnomalyConfig::default(), node_key(), Box::new(sink.clone()));
+        for i in 0..100 {
+            engine.observe(trade(&format!("buyer{}", i), &format!("seller{}", i), i));
+        }
+        assert_eq!(engine.tracked_users(), 200);
+
+        // Nach Ablauf des Fensters bleiben nur die User des neuen Events
+        let window = AnomalyConfig::default().window_secs;
+        engine.observe(trade("alice", "bob", 100 + 2 * window));
+        assert_eq!(engine.tracked_users(), 2);
+    }
 
     #[test]
     fn test_analyze_transactions() {
@@ -42,7 +502,7 @@ mod tests {
             Transaction { address: "A".to_string(), amount: 100.0 },
             Transaction { address: "B".to_string(), amount: 110.0 },
             Transaction { address: "C".to_string(), amount: 105.0 },
-            // Diese Transaktion sollte als verd�chtig erkannt werden
+            // Diese Transaktion sollte als verdächtig erkannt werden
             Transaction { address: "D".to_string(), amount: 1000.0 },
         ];
         let suspicious = analyze_transactions(&transactions);
@@ -4,17 +4,76 @@
 
 // Dieses Modul ruft offizielle Sanktionslisten ab (beispielhaft OFAC und EU),
 // parst die CSV-Daten und konsolidiert die gefundenen Adressen in einer einheitlichen Liste.
+//
+// Screening: `is_blocked` prüft sowohl Krypto-Adressen (OFAC SDN "Digital Currency
+// Address") als auch Jurisdiktionen (Land des Accounts). Die aktive Liste liegt
+// prozessweit in `global_sanctions()` und wird von `update_manager` ersetzt.
 
 use reqwest::blocking::get;
 use csv::ReaderBuilder;
+use once_cell::sync::Lazy;
 use std::error::Error;
 use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+use crate::error::DexError;
+
+/// Umfassend sanktionierte Jurisdiktionen (Standardwerte, per `countries` erweiterbar).
+pub const DEFAULT_SANCTIONED_JURISDICTIONS: &[&str] = &[
+    "Cuba", "Iran", "North Korea", "Syria", "Crimea", "Donetsk", "Luhansk",
+];
 
 /// Struktur zur Speicherung der konsolidierten Sanktionsliste.
 #[derive(Debug, Clone)]
 pub struct SanctionsList {
     /// Eine Menge eindeutiger Adressen, die als sanktioniert gelten.
     pub addresses: HashSet<String>,
+    /// Gesperrte Länder/Regionen (normalisiert, lowercase).
+    pub countries: HashSet<String>,
+}
+
+/// Adressen mit Prüfsummen-Schreibweise (0x..., bech32) sind case-insensitiv;
+/// Base58-Adressen bleiben unverändert.
+fn normalize_address(addr: &str) -> String {
+    let a = addr.trim();
+    let lower = a.to_ascii_lowercase();
+    if lower.starts_with("0x") || lower.starts_with("bc1") || lower.starts_with("ltc1") || lower.starts_with("tb1") {
+        lower
+    } else {
+        a.to_string()
+    }
+}
+
+fn normalize_country(c: &str) -> String {
+    c.trim().to_lowercase()
+}
+
+/// Was über eine Partei gescreent wird: Wallet-Adressen und Land (falls bekannt).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScreeningSubject {
+    pub addresses: Vec<String>,
+    pub country: Option<String>,
+}
+
+/// Extrahiert Krypto-Adressen aus einem SDN-Remarks-Feld, z.B.
+/// "Digital Currency Address - XBT 1AbC...; Digital Currency Address - ETH 0x12...".
+pub fn extract_digital_currency_addresses(remarks: &str) -> Vec<String> {
+    const MARKER: &str = "Digital Currency Address - ";
+    let mut out = Vec::new();
+    let mut rest = remarks;
+    while let Some(pos) = rest.find(MARKER) {
+        rest = &rest[pos + MARKER.len()..];
+        // Format: "<TICKER> <ADRESSE>"
+        let mut parts = rest.split_whitespace();
+        let _ticker = parts.next();
+        if let Some(addr) = parts.next() {
+            let addr = addr.trim_end_matches(|c: char| c == ';' || c == '.' || c == ',');
+            if !addr.is_empty() {
+                out.push(addr.to_string());
+            }
+        }
+    }
+    out
 }
 
 impl SanctionsList {
@@ -22,7 +81,73 @@ impl SanctionsList {
     pub fn new() -> Self {
         SanctionsList {
             addresses: HashSet::new(),
+            countries: HashSet::new(),
+        }
+    }
+
+    /// Liste mit den Standard-Jurisdiktionen und ohne Adressen.
+    pub fn with_default_jurisdictions() -> Self {
+        let mut list = Self::new();
+        for c in DEFAULT_SANCTIONED_JURISDICTIONS {
+            list.add_country(c);
+        }
+        list
+    }
+
+    /// Normalisierte Schreibweise, wie sie in `addresses` gespeichert wird.
+    pub fn normalized_address(addr: &str) -> String {
+        normalize_address(addr)
+    }
+
+    pub fn add_address(&mut self, addr: &str) {
+        self.addresses.insert(normalize_address(addr));
+    }
+
+    pub fn add_country(&mut self, country: &str) {
+        self.countries.insert(normalize_country(country));
+    }
+
+    /// true, wenn `address_or_country` eine gelistete Adresse oder Jurisdiktion ist.
+    pub fn is_blocked(&self, address_or_country: &str) -> bool {
+        let candidate = address_or_country.trim();
+        if candidate.is_empty() {
+            return false;
+        }
+        self.addresses.contains(&normalize_address(candidate))
+            || self.countries.contains(&normalize_country(candidate))
+    }
+
+    /// Prüft Adresse(n) und optional das Land; Fehler beim ersten Treffer.
+    pub fn screen<'a, I>(&self, addresses: I, country: Option<&str>) -> Result<(), DexError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        for addr in addresses {
+            if self.is_blocked(addr) {
+                return Err(DexError::SanctionedParty(format!("address {}", addr)));
+            }
         }
+        if let Some(c) = country {
+            if self.countries.contains(&normalize_country(c)) {
+                return Err(DexError::SanctionedParty(format!("jurisdiction {}", c)));
+            }
+        }
+        Ok(())
+    }
+
+    /// `screen` für Adressen und Land eines Accounts.
+    pub fn screen_subject(&self, subject: &ScreeningSubject) -> Result<(), DexError> {
+        self.screen(subject.addresses.iter().map(String::as_str), subject.country.as_deref())
+    }
+
+    /// Lädt eine von `update_manager` gespeicherte Liste (kommagetrennte Adressen).
+    pub fn load_from_file(path: &str) -> Result<SanctionsList, Box<dyn Error>> {
+        let data = std::fs::read_to_string(path)?;
+        let mut list = Self::with_default_jurisdictions();
+        for addr in data.split(',').filter(|a| !a.trim().is_empty()) {
+            list.add_address(addr);
+        }
+        Ok(list)
     }
     
     /// Ruft die OFAC-Sanktionsliste ab und extrahiert die Adressen.
@@ -36,9 +161,9 @@ impl SanctionsList {
         let mut addresses = Vec::new();
         for result in rdr.records() {
             let record = result?;
-            // Annahme: Die zweite Spalte enth�lt eine relevante Adresse.
-            if let Some(addr) = record.get(1) {
-                addresses.push(addr.to_string());
+            // Krypto-Adressen stehen in den Remarks als "Digital Currency Address - ..."
+            for field in record.iter() {
+                addresses.extend(extract_digital_currency_addresses(field));
             }
         }
         Ok(addresses)
@@ -65,19 +190,75 @@ impl SanctionsList {
         let ofac_addresses = Self::fetch_ofac_list()?;
         let eu_addresses = Self::fetch_eu_list()?;
         
-        let mut consolidated = SanctionsList::new();
+        let mut consolidated = SanctionsList::with_default_jurisdictions();
         // Alle Adressen aus beiden Listen zusammenf�hren
         for addr in ofac_addresses.into_iter().chain(eu_addresses.into_iter()) {
-            consolidated.addresses.insert(addr);
+            consolidated.add_address(&addr);
         }
         Ok(consolidated)
     }
 }
 
+static GLOBAL_SANCTIONS: Lazy<RwLock<Arc<SanctionsList>>> =
+    Lazy::new(|| RwLock::new(Arc::new(SanctionsList::with_default_jurisdictions())));
+
+/// Aktuell gültige Sanktionsliste des Nodes.
+pub fn global_sanctions() -> Arc<SanctionsList> {
+    GLOBAL_SANCTIONS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Ersetzt die aktive Liste (nach einem akzeptierten Update).
+pub fn set_global_sanctions(list: SanctionsList) {
+    *GLOBAL_SANCTIONS.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(list);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
+    fn sample_list() -> SanctionsList {
+        let mut list = SanctionsList::with_default_jurisdictions();
+        list.add_address("0x8589427373D6D84E98730D7795D8f6f8731FDA16");
+        list.add_address("12QtD5BFwRsdNsAZY76UVE1xyCGNTojH9h");
+        list
+    }
+
+    #[test]
+    fn test_sanctioned_address_blocked() {
+        let list = sample_list();
+        // ETH-Adressen unabhängig von der Groß-/Kleinschreibung
+        assert!(list.is_blocked("0x8589427373d6d84e98730d7795d8f6f8731fda16"));
+        assert!(list.is_blocked(" 12QtD5BFwRsdNsAZY76UVE1xyCGNTojH9h "));
+        let err = list.screen(["bc1qclean", "12QtD5BFwRsdNsAZY76UVE1xyCGNTojH9h"], None).unwrap_err();
+        assert!(matches!(err, DexError::SanctionedParty(_)));
+    }
+
+    #[test]
+    fn test_clean_address_passes() {
+        let list = sample_list();
+        // Base58 ist case-sensitiv => andere Schreibweise ist eine andere Adresse
+        assert!(!list.is_blocked("12qtd5bfwrsdnsazy76uve1xycgntojh9h"));
+        assert!(!list.is_blocked("0x0000000000000000000000000000000000000001"));
+        assert!(list.screen(["0x0000000000000000000000000000000000000001"], Some("Germany")).is_ok());
+    }
+
+    #[test]
+    fn test_jurisdiction_screening() {
+        let list = sample_list();
+        assert!(list.is_blocked("north korea"));
+        assert!(matches!(list.screen(std::iter::empty(), Some("Iran")), Err(DexError::SanctionedParty(_))));
+    }
+
+    #[test]
+    fn test_extract_sdn_remarks() {
+        let remarks = "Digital Currency Address - XBT 12QtD5BFwRsdNsAZY76UVE1xyCGNTojH9h; \
+                       Digital Currency Address - ETH 0x8589427373D6D84E98730D7795D8f6f8731FDA16; alt. Secondary sanctions risk.";
+        assert_eq!(
+            extract_digital_currency_addresses(remarks),
+            vec!["12QtD5BFwRsdNsAZY76UVE1xyCGNTojH9h", "0x8589427373D6D84E98730D7795D8f6f8731FDA16"]
+        );
+    }
+
     #[test]
     fn test_consolidate_lists() {
         let result = SanctionsList::consolidate_lists();
@@ -8,10 +8,21 @@
 // Die konsolidierte Liste wird abgerufen, digital signiert (SHA256-Hash) und
 // anhand eines simplen Kriteriums (Hash beginnt mit "00") validiert.
 // Bei erfolgreicher Validierung wird die Liste lokal in der Datei "sanctions_list_update.txt" gespeichert.
+//
+// Aktiviert wird eine Liste auf dem Node NUR über `SanctionsUpdater::apply`:
+// inkrementelle Updates (Adds/Removes) mit streng steigender Version und
+// Ed25519-Signatur eines vertrauenswürdigen Publishers. Unsignierte Updates,
+// fremde Publisher, Downgrades und Versionslücken werden abgelehnt; die zuletzt
+// gültige Liste bleibt aktiv.
 
-use crate::sanctions::sanctions_list::SanctionsList;
+use crate::sanctions::sanctions_list::{set_global_sanctions, SanctionsList};
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use serde::{Deserialize, Serialize};
 use std::error::Error;
+use std::path::{Path, PathBuf};
 use sha2::{Sha256, Digest};
+use thiserror::Error as ThisError;
+use tracing::{info, warn};
 
 /// F�hrt das Update der Sanktionsliste durch.
 /// - Ruft die offiziellen Sanktionslisten ab und konsolidiert sie.
@@ -33,24 +44,322 @@ pub fn update_sanctions_list() -> Result<(), Box<dyn Error>> {
     hasher.update(data.as_bytes());
     let result_hash = hasher.finalize();
     let hash_string = format!("{:x}", result_hash);
-    println!("Neuer Hash der Sanktionsliste: {}", hash_string);
+    info!("Neuer Hash der Sanktionsliste: {}", hash_string);
     
     // Simulierter Konsensmechanismus:
     // Akzeptiere das Update, wenn der Hash mit "00" beginnt.
     if hash_string.starts_with("00") {
         // Speichere die aktualisierte Liste in der Datei "sanctions_list_update.txt"
+        // Nur Rohdaten für den Publisher; aktiv wird die Liste erst als signiertes Update.
         std::fs::write("sanctions_list_update.txt", &data)?;
-        println!("Sanktionslisten-Update wurde akzeptiert und gespeichert.");
+        info!("Sanktionslisten-Update wurde akzeptiert und gespeichert.");
         Ok(())
     } else {
-        println!("Sanktionslisten-Update wurde vom Konsensmechanismus abgelehnt.");
+        warn!("Sanktionslisten-Update wurde vom Konsensmechanismus abgelehnt (Hash {}).", hash_string);
         Err("Konsensvalidierung fehlgeschlagen".into())
     }
 }
 
+/// Domain-Tag der Publisher-Signatur über ein Update.
+pub const SANCTIONS_UPDATE_DOMAIN: &str = "my_dex/sanctions_update/v1";
+
+/// Inhalt eines inkrementellen Updates (das, was signiert wird).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SanctionsUpdatePayload {
+    /// Version nach Anwendung dieses Updates.
+    pub version: u64,
+    /// Version, auf der das Update aufsetzt (muss der aktiven Version entsprechen).
+    pub base_version: u64,
+    pub added_addresses: Vec<String>,
+    pub removed_addresses: Vec<String>,
+    pub added_countries: Vec<String>,
+    pub removed_countries: Vec<String>,
+}
+
+impl SanctionsUpdatePayload {
+    /// Kanonische Bytes (CBOR mit `SANCTIONS_UPDATE_DOMAIN`) mit sortierten Listen.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut p = self.clone();
+        p.added_addresses.sort();
+        p.removed_addresses.sort();
+        p.added_countries.sort();
+        p.removed_countries.sort();
+        // Nur Strings und Integer => kanonische Kodierung kann nicht scheitern
+        crate::utils::canonical::signing_bytes(SANCTIONS_UPDATE_DOMAIN, &p).expect("payload serialisierbar")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedSanctionsUpdate {
+    pub payload: SanctionsUpdatePayload,
+    /// Öffentlicher Schlüssel des Publishers (hex).
+    pub publisher: String,
+    /// Ed25519-Signatur über `payload.signing_bytes()` (hex).
+    pub signature: Option<String>,
+}
+
+impl SignedSanctionsUpdate {
+    /// Publisher-Seite: signiert ein Payload.
+    pub fn sign(payload: SanctionsUpdatePayload, keypair: &Keypair) -> Self {
+        let signature = keypair.sign(&payload.signing_bytes());
+        Self {
+            payload,
+            publisher: hex::encode(keypair.public.as_bytes()),
+            signature: Some(hex::encode(signature.to_bytes())),
+        }
+    }
+
+    /// Differenz zwischen zwei Listen als Payload.
+    pub fn diff(previous: &SanctionsList, next: &SanctionsList, base_version: u64) -> SanctionsUpdatePayload {
+        SanctionsUpdatePayload {
+            version: base_version + 1,
+            base_version,
+            added_addresses: next.addresses.difference(&previous.addresses).cloned().collect(),
+            removed_addresses: previous.addresses.difference(&next.addresses).cloned().collect(),
+            added_countries: next.countries.difference(&previous.countries).cloned().collect(),
+            removed_countries: previous.countries.difference(&next.countries).cloned().collect(),
+        }
+    }
+}
+
+#[derive(ThisError, Debug)]
+pub enum SanctionsUpdateError {
+    #[error("update v{0} is not signed")]
+    Unsigned(u64),
+    #[error("update v{version} signed by untrusted publisher {publisher}")]
+    UntrustedPublisher { version: u64, publisher: String },
+    #[error("update v{0} has an invalid signature")]
+    BadSignature(u64),
+    #[error("update v{offered} is not newer than active v{active}")]
+    Downgrade { active: u64, offered: u64 },
+    #[error("update v{version} expects base v{base}, active is v{active}")]
+    VersionGap { version: u64, base: u64, active: u64 },
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid update/state file: {0}")]
+    Parse(#[from] serde_json::Error),
+}
+
+/// Persistierter Zustand: aktive Version + Liste.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct SanctionsState {
+    version: u64,
+    addresses: Vec<String>,
+    countries: Vec<String>,
+}
+
+/// Verwaltet die aktive, versionierte Sanktionsliste des Nodes.
+pub struct SanctionsUpdater {
+    trusted_publishers: Vec<PublicKey>,
+    version: u64,
+    list: SanctionsList,
+    state_path: Option<PathBuf>,
+}
+
+impl SanctionsUpdater {
+    /// Startet mit Version 0 und `initial` (z.B. nur Standard-Jurisdiktionen).
+    pub fn new(trusted_publishers: Vec<PublicKey>, initial: SanctionsList) -> Self {
+        Self { trusted_publishers, version: 0, list: initial, state_path: None }
+    }
+
+    /// Lädt den zuletzt akzeptierten Zustand aus `path` (falls vorhanden) und
+    /// persistiert künftige Updates dorthin.
+    pub fn load<P: AsRef<Path>>(path: P, trusted_publishers: Vec<PublicKey>) -> Result<Self, SanctionsUpdateError> {
+        let path = path.as_ref().to_path_buf();
+        let mut updater = Self::new(trusted_publishers, SanctionsList::with_default_jurisdictions());
+        if path.exists() {
+            let state: SanctionsState = serde_json::from_slice(&std::fs::read(&path)?)?;
+            let mut list = SanctionsList::new();
+            state.addresses.iter().for_each(|a| list.add_address(a));
+            state.countries.iter().for_each(|c| list.add_country(c));
+            updater.version = state.version;
+            updater.list = list;
+        }
+        updater.state_path = Some(path);
+        Ok(updater)
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn list(&self) -> &SanctionsList {
+        &self.list
+    }
+
+    fn verify(&self, update: &SignedSanctionsUpdate) -> Result<(), SanctionsUpdateError> {
+        let version = update.payload.version;
+        let sig_hex = update.signature.as_ref().ok_or(SanctionsUpdateError::Unsigned(version))?;
+        let untrusted = || SanctionsUpdateError::UntrustedPublisher { version, publisher: update.publisher.clone() };
+        let pk_bytes = hex::decode(&update.publisher).map_err(|_| untrusted())?;
+        let publisher = self
+            .trusted_publishers
+            .iter()
+            .find(|k| k.as_bytes()[..] == pk_bytes[..])
+            .ok_or_else(untrusted)?;
+        let sig = hex::decode(sig_hex)
+            .ok()
+            .and_then(|b| Signature::from_bytes(&b).ok())
+            .ok_or(SanctionsUpdateError::BadSignature(version))?;
+        publisher
+            .verify(&update.payload.signing_bytes(), &sig)
+            .map_err(|_| SanctionsUpdateError::BadSignature(version))
+    }
+
+    /// Prüft und wendet ein Update an. Bei jedem Fehler bleibt die aktive Liste unverändert.
+    pub fn apply(&mut self, update: &SignedSanctionsUpdate) -> Result<(), SanctionsUpdateError> {
+        self.verify(update)?;
+        let p = &update.payload;
+        if p.version <= self.version {
+            return Err(SanctionsUpdateError::Downgrade { active: self.version, offered: p.version });
+        }
+        if p.base_version != self.version {
+            return Err(SanctionsUpdateError::VersionGap { version: p.version, base: p.base_version, active: self.version });
+        }
+
+        let mut next = self.list.clone();
+        for a in &p.removed_addresses {
+            next.addresses.remove(&SanctionsList::normalized_address(a));
+        }
+        for c in &p.removed_countries {
+            next.countries.remove(&c.trim().to_lowercase());
+        }
+        p.added_addresses.iter().for_each(|a| next.add_address(a));
+        p.added_countries.iter().for_each(|c| next.add_country(c));
+
+        if let Some(path) = &self.state_path {
+            let mut state = SanctionsState {
+                version: p.version,
+                addresses: next.addresses.iter().cloned().collect(),
+                countries: next.countries.iter().cloned().collect(),
+            };
+            state.addresses.sort();
+            state.countries.sort();
+            // Atomar ersetzen => nach einem Absturz bleibt der alte Stand lesbar
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, serde_json::to_vec_pretty(&state)?)?;
+            std::fs::rename(&tmp, path)?;
+        }
+
+        self.version = p.version;
+        self.list = next;
+        Ok(())
+    }
+
+    /// Macht die aktive Liste für das Screening (`global_sanctions`) verfügbar.
+    pub fn publish_global(&self) {
+        set_global_sanctions(self.list.clone());
+    }
+
+    /// Wendet alle `*.json`-Updates aus `dir` in Versionsreihenfolge an.
+    /// Abgelehnte Updates werden übersprungen; liefert die Anzahl angewandter Updates.
+    pub fn apply_updates_from_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<usize, SanctionsUpdateError> {
+        let mut updates = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match serde_json::from_slice::<SignedSanctionsUpdate>(&std::fs::read(&path)?) {
+                Ok(u) => updates.push(u),
+                Err(e) => warn!("Ungültige Update-Datei {:?}: {}", path, e),
+            }
+        }
+        updates.sort_by_key(|u| u.payload.version);
+        let mut applied = 0;
+        for u in &updates {
+            match self.apply(u) {
+                Ok(()) => applied += 1,
+                Err(e) => warn!("Sanktions-Update abgelehnt: {}", e),
+            }
+        }
+        Ok(applied)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ed25519_dalek::SecretKey;
+
+    fn publisher(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    fn payload(version: u64, add: &[&str], remove: &[&str]) -> SanctionsUpdatePayload {
+        SanctionsUpdatePayload {
+            version,
+            base_version: version - 1,
+            added_addresses: add.iter().map(|s| s.to_string()).collect(),
+            removed_addresses: remove.iter().map(|s| s.to_string()).collect(),
+            added_countries: vec![],
+            removed_countries: vec![],
+        }
+    }
+
+    fn updater_at_v1() -> SanctionsUpdater {
+        let key = publisher(1);
+        let mut up = SanctionsUpdater::new(vec![key.public], SanctionsList::new());
+        up.apply(&SignedSanctionsUpdate::sign(payload(1, &["addrA", "addrB"], &[]), &key)).unwrap();
+        up
+    }
+
+    #[test]
+    fn test_unsigned_update_rejected_prior_list_kept() {
+        let mut up = updater_at_v1();
+        let mut unsigned = SignedSanctionsUpdate::sign(payload(2, &[], &["addrA", "addrB"]), &publisher(1));
+        unsigned.signature = None;
+
+        assert!(matches!(up.apply(&unsigned), Err(SanctionsUpdateError::Unsigned(2))));
+        assert_eq!(up.version(), 1);
+        assert!(up.list().is_blocked("addrA"));
+        assert!(up.list().is_blocked("addrB"));
+    }
+
+    #[test]
+    fn test_rejects_foreign_tampered_and_downgrade() {
+        let mut up = updater_at_v1();
+
+        let foreign = SignedSanctionsUpdate::sign(payload(2, &[], &["addrA"]), &publisher(9));
+        assert!(matches!(up.apply(&foreign), Err(SanctionsUpdateError::UntrustedPublisher { .. })));
+
+        let mut tampered = SignedSanctionsUpdate::sign(payload(2, &["addrC"], &[]), &publisher(1));
+        tampered.payload.removed_addresses.push("addrA".into());
+        assert!(matches!(up.apply(&tampered), Err(SanctionsUpdateError::BadSignature(2))));
+
+        let replay = SignedSanctionsUpdate::sign(payload(1, &[], &["addrA"]), &publisher(1));
+        assert!(matches!(up.apply(&replay), Err(SanctionsUpdateError::Downgrade { active: 1, offered: 1 })));
+
+        // Signatur über dieselben Daten ohne Domain-Tag gilt nicht
+        let key = publisher(1);
+        let p = payload(2, &["addrC"], &[]);
+        let untagged = SignedSanctionsUpdate {
+            signature: Some(hex::encode(key.sign(&serde_json::to_vec(&p).unwrap()).to_bytes())),
+            publisher: hex::encode(key.public.as_bytes()),
+            payload: p,
+        };
+        assert!(matches!(up.apply(&untagged), Err(SanctionsUpdateError::BadSignature(2))));
+
+        assert!(up.list().is_blocked("addrA"));
+    }
+
+    #[test]
+    fn test_incremental_update_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join("sanctions_state.json");
+        let key = publisher(1);
+        {
+            let mut up = SanctionsUpdater::load(&state, vec![key.public]).unwrap();
+            up.apply(&SignedSanctionsUpdate::sign(payload(1, &["addrA", "addrB"], &[]), &key)).unwrap();
+            up.apply(&SignedSanctionsUpdate::sign(payload(2, &["addrC"], &["addrB"]), &key)).unwrap();
+        }
+        let up = SanctionsUpdater::load(&state, vec![key.public]).unwrap();
+        assert_eq!(up.version(), 2);
+        assert!(up.list().is_blocked("addrA") && up.list().is_blocked("addrC"));
+        assert!(!up.list().is_blocked("addrB"));
+    }
     
     #[test]
     fn test_update_sanctions_list() {
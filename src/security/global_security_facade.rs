@@ -31,6 +31,7 @@ use monero::{util::ringct::prove_ring_ct, consensus::encode::serialize}; // Beis
 use ark_ec::PairingEngine;
 use ark_groth16::{Groth16, Proof, VerifyingKey}; // für zk-SNARK
 use ark_std::test_rng; // rng
+use crate::error::DexError;
 use crate::watchtower::Watchtower;
 use crate::logging::enhanced_logging::{log_error, write_audit_log};
 use crate::security::async_security_tasks;
@@ -115,6 +116,15 @@ impl GlobalSecuritySystem {
         ok
     }
 
+    /// Wie check_rate_limit, aber als typisierter Fehler für Result-Pfade.
+    pub fn enforce_rate_limit(&self, key: &str) -> Result<(), DexError> {
+        if self.check_rate_limit(key) {
+            Ok(())
+        } else {
+            Err(DexError::RateLimited(key.to_string()))
+        }
+    }
+
     // 2) Multi-Sig – echtes M-of-N ED25519, Partial-Sigs
     pub fn create_multisig_wallet(&mut self, threshold: u8, owners: Vec<ed25519_dalek::PublicKey>) -> Result<()> {
         if threshold as usize > owners.len() || threshold == 0 {
@@ -7,7 +7,6 @@ use std::collections::HashSet;
 use std::time::Duration;
 use tokio::time::{sleep, interval};
 use tracing::{info, warn, error};
-use chrono::Utc;
 use base64::{engine::general_purpose, Engine as _};
 
 use crate::dex_logic::sign_utils::KeyPair;
@@ -17,6 +16,7 @@ use crate::self_healing::config::{HealthCheckType, ServiceConfig};
 use crate::self_healing::health_checks::{check_tcp_port, check_http_ok, dummy_health_check};
 use crate::self_healing::escalation::{send_webhook, build_default_payload};
 use crate::self_healing::custom_checks::check_orderbook_state;
+use crate::utils::clock::{system_clock, SharedClock};
 
 /// Sichere Neustartlogik mit dynamischer Whitelist
 pub async fn restart_service(service_name: &str, whitelist: &HashSet<String>) -> Result<(), String> {
@@ -61,6 +61,18 @@ pub async fn monitor_and_heal(
     interval_sec: u64,
     config: ServiceConfig,
     whitelist: HashSet<String>
+) {
+    monitor_and_heal_with_clock(service_name, node_id, interval_sec, config, whitelist, system_clock()).await
+}
+
+/// Wie `monitor_and_heal`; der Zeitstempel im signierten Fehlerbericht kommt aus `clock`.
+pub async fn monitor_and_heal_with_clock(
+    service_name: &str,
+    node_id: &str,
+    interval_sec: u64,
+    config: ServiceConfig,
+    whitelist: HashSet<String>,
+    clock: SharedClock,
 ) {
     let mut ticker = interval(Duration::from_secs(interval_sec));
     let keypair = get_or_create_keypair().expect("Keypair konnte nicht geladen werden");
@@ -84,7 +96,7 @@ pub async fn monitor_and_heal(
         if !healthy {
             warn!("Dienst '{}' ungesund – starte Self-Healing", service_name);
 
-            let timestamp = Utc::now().timestamp();
+            let timestamp = clock.unix_secs();
             let body = format!("{}:{}:{}", node_id, service_name, timestamp);
             let signature = keypair.sign_message(body.as_bytes());
             let sig_b64 = general_purpose::STANDARD.encode(signature.serialize_compact());
@@ -12,12 +12,18 @@
 //
 // Damit beseitigen wir triviale Schwachstellen ohne das Grunddesign zu ändern.
 //
+// Backends: Die eigentliche Bewegung der Mittel übernimmt je Asset ein
+// `ChainSettlement` aus der `SettlementRegistry`. Standard ist das
+// Off-Chain-Ledger (`LedgerSettlement`) für BTC/LTC/ETH; ein neues Paar (z. B.
+// ein ERC-20-Token) braucht nur `register_backend`, keine Änderung der Engine.
+//
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tracing::{debug, info, warn, error};
 use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
 
 use crate::error::DexError;
 use crate::security::security_validator::SecurityValidator;
@@ -29,6 +35,7 @@ use crate::storage::db_layer::DexDB;
 
 // **NEU**: FeeConfig
 use crate::settlement::fees_config::SettlementFees;
+use crate::utils::lock::LockRecover;
 
 // NEU: Globaler Mutex => wir sperren finalize-Methoden
 use lazy_static::lazy_static;
@@ -37,14 +44,237 @@ lazy_static! {
     static ref ENGINE_MUTEX: Mutex<()> = Mutex::new(());
 }
 
-/// Repräsentiert ein einfaches "Asset" – in einer echten Umsetzung
-/// könntest du Asset::BTC, Asset::ETH, Asset::LTC, ERC20, usw. haben.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// Repräsentiert ein einfaches "Asset".
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Asset {
     BTC,
     LTC,
     ETH,
-    // ggf. mehr
+    /// ERC-20-Token auf Ethereum, per Symbol (z. B. "USDC")
+    Erc20(String),
+}
+
+/// Kette, auf der ein Asset abgewickelt wird.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Chain {
+    Bitcoin,
+    Litecoin,
+    Ethereum,
+}
+
+impl Asset {
+    pub fn chain(&self) -> Chain {
+        match self {
+            Asset::BTC => Chain::Bitcoin,
+            Asset::LTC => Chain::Litecoin,
+            Asset::ETH | Asset::Erc20(_) => Chain::Ethereum,
+        }
+    }
+
+    /// Kürzel, z. B. für den Fee-Pool je Asset.
+    pub fn symbol(&self) -> String {
+        match self {
+            Asset::BTC => "BTC".into(),
+            Asset::LTC => "LTC".into(),
+            Asset::ETH => "ETH".into(),
+            Asset::Erc20(token) => token.clone(),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////
+// ChainSettlement => ein Backend pro Asset
+///////////////////////////////////////////////////////////
+
+/// Eine Backend-Operation, die über `ChainSettlement::execute_once`
+/// höchstens einmal je Operations-ID ausgeführt wird.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChainOp {
+    Lock { user: String, amount: f64 },
+    Unlock { user: String, amount: f64 },
+    Settle { from: String, to: String, amount: f64 },
+}
+
+/// Abwicklung eines Assets. Ein Trade läuft in zwei Phasen: erst `lock` auf
+/// beiden Seiten (bei Fehler `unlock` der bereits gesperrten Seite), dann
+/// `settle` der gesperrten Beträge an die Gegenpartei.
+pub trait ChainSettlement: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Sperrt `amount` beim Nutzer (Reservierung / HTLC-Funding).
+    fn lock(&self, user: &str, asset: &Asset, amount: f64) -> Result<(), DexError>;
+
+    /// Gibt eine Sperre wieder frei (Rollback).
+    fn unlock(&self, user: &str, asset: &Asset, amount: f64) -> Result<(), DexError>;
+
+    /// Überträgt zuvor gesperrte `amount` von `from` an `to`.
+    fn settle(&self, from: &str, to: &str, asset: &Asset, amount: f64) -> Result<(), DexError>;
+
+    /// Nimmt ein `settle` zurück: `to` gibt `amount` ab, bei `from` ist es
+    /// wieder gesperrt. Die Engine braucht das, wenn nach dem ersten Leg der
+    /// zweite scheitert. Standard: nicht möglich (z. B. bestätigte On-Chain-Tx).
+    fn unsettle(&self, from: &str, to: &str, asset: &Asset, amount: f64) -> Result<(), DexError> {
+        let _ = (from, to, amount);
+        Err(DexError::Other(format!("{}: cannot reverse a settled {:?} leg", self.name(), asset)))
+    }
+
+    /// Bucht die Fee eines Legs. Standard: in den Teil-Pool des Assets
+    /// (`FeePool::for_asset`), Beträge verschiedener Assets landen also nie
+    /// in derselben Summe. Backends mit eigenen Netzgebühren (Gas,
+    /// Miner-Fee) können das überschreiben.
+    fn account_fee(&self, fee_pool: &FeePool, user: &str, asset: &Asset, fee: f64) -> Result<(), DexError> {
+        debug!("{} => fee user={} asset={:?} amount={:.8}", self.name(), user, asset, fee);
+        fee_pool.for_asset(&asset.symbol()).add_fees(fee)
+    }
+
+    /// Führt `op` aus, außer `op_id` wurde schon ausgeführt (dann Ok ohne
+    /// Wirkung). Der Swap-Koordinator wiederholt nach einem Absturz damit
+    /// gefahrlos den zuletzt begonnenen Schritt. Backends ohne eigenes
+    /// Gedächtnis (z. B. HTLC per Hashlock) müssen das selbst umsetzen.
+    fn execute_once(&self, op_id: &str, asset: &Asset, op: &ChainOp) -> Result<(), DexError> {
+        let _ = (op_id, asset, op);
+        Err(DexError::Other(format!("{}: idempotent operations not supported", self.name())))
+    }
+}
+
+/// Off-Chain-Ledger: user_id => (Asset => (free, locked)).
+pub type LedgerBalances = Arc<Mutex<HashMap<String, HashMap<Asset, (f64, f64)>>>>;
+
+/// Standard-Backend: bucht nur im internen Ledger der Engine.
+#[derive(Clone, Debug)]
+pub struct LedgerSettlement {
+    balances: LedgerBalances,
+    /// Bereits ausgeführte Operations-IDs (`execute_once`)
+    applied_ops: Arc<Mutex<HashSet<String>>>,
+}
+
+impl LedgerSettlement {
+    pub fn new(balances: LedgerBalances) -> Self {
+        Self { balances, applied_ops: Arc::new(Mutex::new(HashSet::new())) }
+    }
+
+    fn with_entry<R>(&self, user: &str, asset: &Asset, f: impl FnOnce(&mut (f64, f64)) -> Result<R, DexError>) -> Result<R, DexError> {
+        let mut guard = self.balances.lock_or_err("balances")?;
+        let entry = guard
+            .entry(user.to_string())
+            .or_insert_with(HashMap::new)
+            .entry(asset.clone())
+            .or_insert((0.0, 0.0));
+        f(entry)
+    }
+
+    /// Vernichtet gesperrtes Guthaben (z. B. Slashing eines Stake-Bonds).
+    pub fn burn_locked(&self, user: &str, asset: &Asset, amount: f64) -> Result<(), DexError> {
+        self.with_entry(user, asset, |bal| {
+            if bal.1 < amount {
+                return Err(DexError::InsufficientBalance { user: user.to_string(), asset: format!("locked {:?}", asset) });
+            }
+            bal.1 -= amount;
+            Ok(())
+        })
+    }
+}
+
+impl ChainSettlement for LedgerSettlement {
+    fn name(&self) -> &str {
+        "ledger"
+    }
+
+    fn lock(&self, user: &str, asset: &Asset, amount: f64) -> Result<(), DexError> {
+        self.with_entry(user, asset, |bal| {
+            if bal.0 < amount {
+                return Err(DexError::InsufficientBalance { user: user.to_string(), asset: format!("{:?}", asset) });
+            }
+            bal.0 -= amount;
+            bal.1 += amount;
+            Ok(())
+        })
+    }
+
+    fn unlock(&self, user: &str, asset: &Asset, amount: f64) -> Result<(), DexError> {
+        self.with_entry(user, asset, |bal| {
+            if bal.1 < amount {
+                return Err(DexError::Other(format!("Mismatch locked {:?} for {}", asset, user)));
+            }
+            bal.1 -= amount;
+            bal.0 += amount;
+            Ok(())
+        })
+    }
+
+    fn settle(&self, from: &str, to: &str, asset: &Asset, amount: f64) -> Result<(), DexError> {
+        self.with_entry(from, asset, |bal| {
+            if bal.1 < amount {
+                return Err(DexError::Other(format!("Mismatch locked {:?} for {}", asset, from)));
+            }
+            bal.1 -= amount;
+            Ok(())
+        })?;
+        self.with_entry(to, asset, |bal| {
+            bal.0 += amount;
+            Ok(())
+        })
+    }
+
+    fn unsettle(&self, from: &str, to: &str, asset: &Asset, amount: f64) -> Result<(), DexError> {
+        self.with_entry(to, asset, |bal| {
+            if bal.0 < amount {
+                return Err(DexError::InsufficientBalance { user: to.to_string(), asset: format!("{:?}", asset) });
+            }
+            bal.0 -= amount;
+            Ok(())
+        })?;
+        self.with_entry(from, asset, |bal| {
+            bal.1 += amount;
+            Ok(())
+        })
+    }
+
+    fn execute_once(&self, op_id: &str, asset: &Asset, op: &ChainOp) -> Result<(), DexError> {
+        // Lock über die ganze Operation => kein zweiter Aufruf mit derselben ID dazwischen
+        let mut applied = self.applied_ops.lock_or_err("applied ops")?;
+        if applied.contains(op_id) {
+            debug!("ledger => op {} schon ausgeführt, übersprungen", op_id);
+            return Ok(());
+        }
+        match op {
+            ChainOp::Lock { user, amount } => self.lock(user, asset, *amount)?,
+            ChainOp::Unlock { user, amount } => self.unlock(user, asset, *amount)?,
+            ChainOp::Settle { from, to, amount } => self.settle(from, to, asset, *amount)?,
+        }
+        applied.insert(op_id.to_string());
+        Ok(())
+    }
+}
+
+/// Asset => Backend.
+#[derive(Clone, Default)]
+pub struct SettlementRegistry {
+    backends: HashMap<Asset, Arc<dyn ChainSettlement>>,
+}
+
+impl std::fmt::Debug for SettlementRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut m = f.debug_map();
+        for (asset, backend) in &self.backends {
+            m.entry(asset, &backend.name());
+        }
+        m.finish()
+    }
+}
+
+impl SettlementRegistry {
+    pub fn register(&mut self, asset: Asset, backend: Arc<dyn ChainSettlement>) {
+        info!("Settlement-Backend {} für {:?} ({:?}) registriert", backend.name(), asset, asset.chain());
+        self.backends.insert(asset, backend);
+    }
+
+    pub fn backend(&self, asset: &Asset) -> Result<Arc<dyn ChainSettlement>, DexError> {
+        self.backends
+            .get(asset)
+            .cloned()
+            .ok_or_else(|| DexError::Other(format!("No settlement backend for {:?}", asset)))
+    }
 }
 
 ///////////////////////////////////////////////////////////
@@ -86,6 +316,9 @@ pub struct AdvancedSettlementEngine {
 
     pub db: Arc<Mutex<DexDB>>,
     pub fees_config: SettlementFees,
+
+    /// Backends je Asset (Standard: Ledger für BTC/LTC/ETH)
+    pub backends: SettlementRegistry,
 }
 
 impl AdvancedSettlementEngine {
@@ -94,23 +327,38 @@ impl AdvancedSettlementEngine {
         db: Arc<Mutex<DexDB>>,
         fees_config: SettlementFees,
     ) -> Self {
+        let balances: LedgerBalances = Arc::new(Mutex::new(HashMap::new()));
+        let ledger: Arc<dyn ChainSettlement> = Arc::new(LedgerSettlement::new(balances.clone()));
+        let mut backends = SettlementRegistry::default();
+        for asset in [Asset::BTC, Asset::LTC, Asset::ETH] {
+            backends.register(asset, ledger.clone());
+        }
         Self {
-            balances: Arc::new(Mutex::new(HashMap::new())),
+            balances,
             fee_pool,
             max_retries: 3,
             retry_backoff: Duration::from_millis(200),
             db,
             fees_config,
+            backends,
         }
     }
 
-    /// Hilfsfunktion => Fees
+    /// Registriert (oder ersetzt) das Backend für `asset`.
+    pub fn register_backend(&mut self, asset: Asset, backend: Arc<dyn ChainSettlement>) {
+        self.backends.register(asset, backend);
+    }
+
+    /// Hilfsfunktion => Fees über das Backend des Assets
     fn apply_fees(&self, user: &str, asset: &Asset, amount: f64, fee_percent: f64) {
         let fee_amt = amount * fee_percent;
         if fee_amt <= 0.0 {
             return;
         }
-        let res = self.fee_pool.add_fees_in_asset(*asset, fee_amt);
+        let res = self
+            .backends
+            .backend(asset)
+            .and_then(|b| b.account_fee(&self.fee_pool, user, asset, fee_amt));
         if let Err(e) = res {
             warn!("apply_fees => user={} => failed to add fee => err={:?}, ignoring", user, e);
         } else {
@@ -130,7 +378,7 @@ impl SettlementEngineTrait for AdvancedSettlementEngine {
         quote_amount: f64,
     ) -> Result<(), DexError> {
         // (A) => globaler Lock
-        let _lock = ENGINE_MUTEX.lock().map_err(|_| DexError::Other("engine mutex poisoned".into()))?;
+        let _lock = ENGINE_MUTEX.lock_or_err("settlement engine")?;
 
         // (1) Negative-/Nullwert-Prüfung
         if base_amount <= 0.0 {
@@ -144,62 +392,55 @@ impl SettlementEngineTrait for AdvancedSettlementEngine {
             buyer, seller, base_asset, quote_asset, base_amount, quote_amount
         );
 
-        // (B) => Wir sperren balances => Race Condition in-memory fix
-        let mut guard = self.balances.lock().map_err(|_| DexError::Other("balances mutex poisoned".into()))?;
+        // (B) => Backends je Asset
+        let quote_backend = self.backends.backend(&quote_asset)?;
+        let base_backend = self.backends.backend(&base_asset)?;
 
-        // Buyer => locked quote
-        {
-            let buyer_map = guard.entry(buyer.to_string()).or_insert_with(HashMap::new);
-            let bal_quote = buyer_map.entry(quote_asset.clone()).or_insert((0.0, 0.0));
-            if bal_quote.0 < quote_amount {
-                return Err(DexError::Other(format!("Not enough free quote for buyer={}", buyer)));
+        // Buyer => locked quote, Seller => locked base
+        quote_backend.lock(buyer, &quote_asset, quote_amount)?;
+        if let Err(e) = base_backend.lock(seller, &base_asset, base_amount) {
+            if let Err(re) = quote_backend.unlock(buyer, &quote_asset, quote_amount) {
+                error!("finalize_trade => Rollback der Quote-Sperre fehlgeschlagen: {:?}", re);
             }
-            bal_quote.0 -= quote_amount;
-            bal_quote.1 += quote_amount;
+            return Err(e);
         }
 
-        // Seller => locked base
-        {
-            let seller_map = guard.entry(seller.to_string()).or_insert_with(HashMap::new);
-            let bal_base = seller_map.entry(base_asset.clone()).or_insert((0.0, 0.0));
-            if bal_base.0 < base_amount {
-                return Err(DexError::Other(format!("Not enough free base for seller={}", seller)));
+        let release_locks = || {
+            if let Err(re) = base_backend.unlock(seller, &base_asset, base_amount) {
+                error!("finalize_trade => Rollback der Base-Sperre fehlgeschlagen: {:?}", re);
             }
-            bal_base.0 -= base_amount;
-            bal_base.1 += base_amount;
-        }
-
-        // Fees => standard_fee_rate
-        let fee_percent = self.fees_config.standard_fee_rate;
-        self.apply_fees(buyer, &quote_asset, quote_amount, fee_percent);
-        self.apply_fees(seller, &base_asset, base_amount, fee_percent);
-
-        // Release => buyer kriegt base, seller kriegt quote
-        {
-            let buyer_map = guard.entry(buyer.to_string()).or_insert_with(HashMap::new);
-            let bal_base = buyer_map.entry(base_asset.clone()).or_insert((0.0, 0.0));
-            if bal_base.1 < base_amount {
-                return Err(DexError::Other(format!("Mismatch locked base for buyer={}", buyer)));
+            if let Err(re) = quote_backend.unlock(buyer, &quote_asset, quote_amount) {
+                error!("finalize_trade => Rollback der Quote-Sperre fehlgeschlagen: {:?}", re);
             }
-            bal_base.1 -= base_amount;
-            bal_base.0 += base_amount;
+        };
 
-            let seller_map = guard.entry(seller.to_string()).or_insert_with(HashMap::new);
-            let bal_quote = seller_map.entry(quote_asset.clone()).or_insert((0.0, 0.0));
-            if bal_quote.1 < quote_amount {
-                return Err(DexError::Other(format!("Mismatch locked quote for seller={}", seller)));
+        // Release => buyer kriegt base, seller kriegt quote. Scheitert der
+        // zweite Leg, wird der erste zurückgebucht => kein halber Trade.
+        if let Err(e) = base_backend.settle(seller, buyer, &base_asset, base_amount) {
+            release_locks();
+            return Err(e);
+        }
+        if let Err(e) = quote_backend.settle(buyer, seller, &quote_asset, quote_amount) {
+            match base_backend.unsettle(seller, buyer, &base_asset, base_amount) {
+                Ok(()) => release_locks(),
+                Err(re) => error!(
+                    "finalize_trade => Base-Leg {} {:?} an {} nicht zurückgebucht, Trade halb abgewickelt: {:?}",
+                    base_amount, base_asset, buyer, re
+                ),
             }
-            bal_quote.1 -= quote_amount;
-            bal_quote.0 += quote_amount;
+            return Err(e);
         }
 
-        drop(guard); // balances-Lock freigeben
+        // Fees => standard_fee_rate, erst nach beiden Legs
+        let fee_percent = self.fees_config.standard_fee_rate;
+        self.apply_fees(buyer, &quote_asset, quote_amount, fee_percent);
+        self.apply_fees(seller, &base_asset, base_amount, fee_percent);
 
         // DB => Retry
         let mut attempt = 0;
         while attempt < self.max_retries {
             attempt += 1;
-            let locked_db = self.db.lock().map_err(|_| DexError::Other("DB lock broken".into()))?;
+            let locked_db = self.db.lock_recover();
             let store_res = locked_db.store_struct("settlement/balances", &*(self.balances.lock().unwrap()));
             drop(locked_db);
             if let Err(e) = store_res {
@@ -214,7 +455,7 @@ impl SettlementEngineTrait for AdvancedSettlementEngine {
     }
 
     fn finalize_atomic_swap(&mut self, swap_id: &str, swap: &mut AtomicSwap) -> Result<(), DexError> {
-        let _lock = ENGINE_MUTEX.lock().map_err(|_| DexError::Other("engine mutex poisoned".into()))?;
+        let _lock = ENGINE_MUTEX.lock_or_err("settlement engine")?;
 
         info!("finalize_atomic_swap => swap_id={}", swap_id);
 
@@ -255,7 +496,7 @@ impl SettlementEngineTrait for AdvancedSettlementEngine {
     }
 
     fn finalize_onchain_htlc(&mut self, htlc_id: &str, htlc: &mut OnchainHtlc) -> Result<(), DexError> {
-        let _lock = ENGINE_MUTEX.lock().map_err(|_| DexError::Other("engine mutex poisoned".into()))?;
+        let _lock = ENGINE_MUTEX.lock_or_err("settlement engine")?;
 
         info!("finalize_onchain_htlc => htlc_id={}", htlc_id);
         // => hier kein negativity check, da OnchainHtlc nicht storage of amounts?
@@ -313,3 +554,125 @@ impl<E: SettlementEngineTrait, S: SecurityValidator> SettlementEngineTrait for S
         self.inner.finalize_onchain_htlc(htlc_id, htlc)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::db_layer::InMemoryDb;
+
+    /// Backend, das nur mitschreibt, was die Engine von ihm will.
+    #[derive(Default)]
+    struct MockChain {
+        calls: Mutex<Vec<String>>,
+        /// `settle` schlägt fehl (z. B. RPC down)
+        fail_settle: bool,
+    }
+
+    impl ChainSettlement for MockChain {
+        fn name(&self) -> &str {
+            "mock-erc20"
+        }
+        fn lock(&self, user: &str, asset: &Asset, amount: f64) -> Result<(), DexError> {
+            self.calls.lock().unwrap().push(format!("lock {} {:?} {}", user, asset, amount));
+            Ok(())
+        }
+        fn unlock(&self, user: &str, asset: &Asset, amount: f64) -> Result<(), DexError> {
+            self.calls.lock().unwrap().push(format!("unlock {} {:?} {}", user, asset, amount));
+            Ok(())
+        }
+        fn settle(&self, from: &str, to: &str, asset: &Asset, amount: f64) -> Result<(), DexError> {
+            if self.fail_settle {
+                return Err(DexError::Other("mock settle failed".into()));
+            }
+            self.calls.lock().unwrap().push(format!("settle {}->{} {:?} {}", from, to, asset, amount));
+            Ok(())
+        }
+    }
+
+    fn engine() -> AdvancedSettlementEngine {
+        let db = Arc::new(Mutex::new(DexDB { rocks: None, fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))) }));
+        let fee_pool = Arc::new(FeePool::new(db.clone(), "test/fee_pool"));
+        AdvancedSettlementEngine::new(fee_pool, db, SettlementFees::new(0.0, 0.0))
+    }
+
+    #[test]
+    fn test_trade_dispatches_to_registered_backend() {
+        let usdc = Asset::Erc20("USDC".into());
+        let mock = Arc::new(MockChain::default());
+        let mut eng = engine();
+        eng.register_backend(usdc.clone(), mock.clone());
+        eng.balances.lock().unwrap()
+            .entry("seller".into()).or_default()
+            .insert(Asset::BTC, (2.0, 0.0));
+
+        eng.finalize_trade("buyer", "seller", Asset::BTC, usdc.clone(), 1.0, 30000.0).unwrap();
+
+        // Quote-Leg lief über das Mock-Backend ...
+        assert_eq!(
+            *mock.calls.lock().unwrap(),
+            vec![
+                format!("lock buyer {:?} 30000", usdc),
+                format!("settle buyer->seller {:?} 30000", usdc),
+            ]
+        );
+        // ... der Base-Leg über das Ledger
+        let balances = eng.balances.lock().unwrap();
+        assert_eq!(balances["seller"][&Asset::BTC], (1.0, 0.0));
+        assert_eq!(balances["buyer"][&Asset::BTC], (1.0, 0.0));
+        assert!(!balances.get("buyer").map_or(false, |m| m.contains_key(&usdc)));
+    }
+
+    #[test]
+    fn test_failed_leg_rolls_back_and_unknown_asset_rejected() {
+        let usdc = Asset::Erc20("USDC".into());
+        let mock = Arc::new(MockChain::default());
+        let mut eng = engine();
+        eng.register_backend(usdc.clone(), mock.clone());
+
+        // Seller hat kein BTC => Quote-Sperre wird zurückgenommen
+        assert!(eng.finalize_trade("buyer", "seller", Asset::BTC, usdc.clone(), 1.0, 30000.0).is_err());
+        assert_eq!(mock.calls.lock().unwrap().last().unwrap(), &format!("unlock buyer {:?} 30000", usdc));
+
+        let dai = Asset::Erc20("DAI".into());
+        assert!(eng.finalize_trade("buyer", "seller", Asset::BTC, dai, 1.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_second_leg_failure_reverts_first_leg() {
+        let usdc = Asset::Erc20("USDC".into());
+        let mock = Arc::new(MockChain { fail_settle: true, ..Default::default() });
+        let mut eng = engine();
+        eng.fees_config = SettlementFees::new(0.01, 0.0);
+        eng.register_backend(usdc.clone(), mock.clone());
+        eng.balances.lock().unwrap().entry("seller".into()).or_default().insert(Asset::BTC, (2.0, 0.0));
+
+        assert!(eng.finalize_trade("buyer", "seller", Asset::BTC, usdc.clone(), 1.0, 30000.0).is_err());
+
+        // Base-Leg zurückgebucht, beide Sperren gelöst, keine Fee verbucht
+        let balances = eng.balances.lock().unwrap();
+        assert_eq!(balances["seller"][&Asset::BTC], (2.0, 0.0));
+        assert_eq!(balances["buyer"][&Asset::BTC], (0.0, 0.0));
+        assert_eq!(mock.calls.lock().unwrap().last().unwrap(), &format!("unlock buyer {:?} 30000", usdc));
+        assert_eq!(eng.fee_pool.for_asset("BTC").current_dev_pool().unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_fees_are_pooled_per_asset() {
+        let usdc = Asset::Erc20("USDC".into());
+        let mut eng = engine();
+        eng.fees_config = SettlementFees::new(0.01, 0.0);
+        eng.register_backend(usdc.clone(), Arc::new(MockChain::default()));
+        eng.balances.lock().unwrap().entry("seller".into()).or_default().insert(Asset::BTC, (2.0, 0.0));
+
+        eng.finalize_trade("buyer", "seller", Asset::BTC, usdc, 1.0, 30000.0).unwrap();
+
+        // 0.01 BTC und 300 USDC, nicht 300.01 "irgendwas"
+        let total = |symbol: &str| {
+            let pool = eng.fee_pool.for_asset(symbol);
+            pool.current_dev_pool().unwrap() + pool.current_nodes_pool().unwrap()
+        };
+        assert!((total("BTC") - 0.01).abs() < 1e-9);
+        assert!((total("USDC") - 300.0).abs() < 1e-6);
+        assert_eq!(eng.fee_pool.current_dev_pool().unwrap(), 0.0);
+    }
+}
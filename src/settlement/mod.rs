@@ -4,4 +4,6 @@ pub mod advanced_settlement;
 pub mod async_security_tasks;
 pub mod secured_settlement;
 pub mod settlement;
+pub mod settlement_queue;
+pub mod swap_coordinator;
 pub mod fees_config;
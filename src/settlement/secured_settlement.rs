@@ -17,7 +17,11 @@
 //     noch nicht fertig ist (oder immer scheitert).
 ///////////////////////////////////////////////////////////
 
+use std::collections::{BTreeMap, HashMap};
+
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, instrument};
 use crate::error::DexError;
 use crate::security::security_validator::{SecurityValidator, AdvancedSecurityValidator};
 
@@ -34,6 +38,169 @@ pub trait SettlementEngineTrait: Send + Sync {
         base_amount: f64,
         quote_amount: f64,
     ) -> Result<(), DexError>;
+
+    /// Settlet mehrere Trades. Standard: jeder Trade einzeln.
+    /// Liefert die Anzahl tatsächlich ausgeführter Transfers.
+    fn settle_batch(&mut self, trades: &[PendingTrade]) -> Result<usize, DexError> {
+        for t in trades {
+            self.finalize_trade(&t.buyer, &t.seller, &t.base_asset, &t.quote_asset, t.base_amount, t.quote_amount)?;
+        }
+        Ok(trades.len())
+    }
+}
+
+/// Erlaubt, eine bereits geboxte Engine erneut zu umschließen (z.B. für Dry-Run).
+impl SettlementEngineTrait for Box<dyn SettlementEngineTrait> {
+    fn finalize_trade(
+        &mut self,
+        buyer: &str,
+        seller: &str,
+        base_asset: &str,
+        quote_asset: &str,
+        base_amount: f64,
+        quote_amount: f64,
+    ) -> Result<(), DexError> {
+        (**self).finalize_trade(buyer, seller, base_asset, quote_asset, base_amount, quote_amount)
+    }
+
+    fn settle_batch(&mut self, trades: &[PendingTrade]) -> Result<usize, DexError> {
+        (**self).settle_batch(trades)
+    }
+}
+
+/// Simuliertes Ledger für den Dry-Run: Netto-Deltas je (User, Asset).
+#[derive(Clone, Debug, Default)]
+pub struct PaperLedger {
+    pub balances: HashMap<String, HashMap<String, f64>>,
+    pub transfers: usize,
+}
+
+impl PaperLedger {
+    pub fn apply(&mut self, buyer: &str, seller: &str, base_asset: &str, quote_asset: &str, base_amount: f64, quote_amount: f64) {
+        let mut credit = |user: &str, asset: &str, delta: f64| {
+            *self
+                .balances
+                .entry(user.to_string())
+                .or_default()
+                .entry(asset.to_string())
+                .or_insert(0.0) += delta;
+        };
+        credit(buyer, base_asset, base_amount);
+        credit(buyer, quote_asset, -quote_amount);
+        credit(seller, base_asset, -base_amount);
+        credit(seller, quote_asset, quote_amount);
+        self.transfers += 1;
+    }
+
+    pub fn balance(&self, user: &str, asset: &str) -> f64 {
+        self.balances.get(user).and_then(|m| m.get(asset)).copied().unwrap_or(0.0)
+    }
+}
+
+/// Beträge unterhalb dieser Schwelle gelten beim Netting als ausgeglichen.
+const NET_EPSILON: f64 = 1e-9;
+
+/// Ein noch nicht abgewickelter Trade innerhalb eines Settlement-Fensters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingTrade {
+    pub buyer: String,
+    pub seller: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub base_amount: f64,
+    pub quote_amount: f64,
+}
+
+/// Sammelt Trades eines Settlement-Fensters und verrechnet gegenläufige
+/// Beträge je Gegenpartei-Paar und Marktpaar.
+///
+/// Schlüssel ist (a, b, base, quote) mit a < b; gespeichert wird, was a
+/// netto an Base erhält und netto an Quote zahlt (negativ => umgekehrt).
+#[derive(Clone, Debug, Default)]
+pub struct NettingBatch {
+    positions: BTreeMap<(String, String, String, String), (f64, f64)>,
+    trades: usize,
+}
+
+impl NettingBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, t: &PendingTrade) {
+        self.trades += 1;
+        let (a, b, sign) = if t.buyer <= t.seller {
+            (t.buyer.clone(), t.seller.clone(), 1.0)
+        } else {
+            (t.seller.clone(), t.buyer.clone(), -1.0)
+        };
+        let pos = self
+            .positions
+            .entry((a, b, t.base_asset.clone(), t.quote_asset.clone()))
+            .or_insert((0.0, 0.0));
+        pos.0 += sign * t.base_amount;
+        pos.1 += sign * t.quote_amount;
+    }
+
+    pub fn trade_count(&self) -> usize {
+        self.trades
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades == 0
+    }
+
+    /// Netto-Transfers des Fensters. Pro Paar genau einer, außer Base und
+    /// Quote fließen netto zur selben Partei (dann zwei einseitige Legs).
+    pub fn net_transfers(&self) -> Vec<PendingTrade> {
+        let mut out = Vec::new();
+        for ((a, b, base, quote), (base_net, quote_net)) in &self.positions {
+            let leg = |buyer: &str, seller: &str, base_amount: f64, quote_amount: f64| PendingTrade {
+                buyer: buyer.to_string(),
+                seller: seller.to_string(),
+                base_asset: base.clone(),
+                quote_asset: quote.clone(),
+                base_amount,
+                quote_amount,
+            };
+            let base_zero = base_net.abs() < NET_EPSILON;
+            let quote_zero = quote_net.abs() < NET_EPSILON;
+            match (base_zero, quote_zero) {
+                (true, true) => {}
+                (false, _) if quote_zero || base_net.signum() == quote_net.signum() => {
+                    // Käufer erhält Base und zahlt Quote => ein regulärer Transfer
+                    if *base_net > 0.0 {
+                        out.push(leg(a, b, *base_net, quote_net.max(0.0)));
+                    } else {
+                        out.push(leg(b, a, -base_net, (-quote_net).max(0.0)));
+                    }
+                }
+                (true, false) => {
+                    if *quote_net > 0.0 {
+                        out.push(leg(a, b, 0.0, *quote_net));
+                    } else {
+                        out.push(leg(b, a, 0.0, -quote_net));
+                    }
+                }
+                (false, false) => {
+                    // Base und Quote fließen netto zur gleichen Partei
+                    if *base_net > 0.0 {
+                        out.push(leg(a, b, *base_net, 0.0));
+                        out.push(leg(b, a, 0.0, -quote_net));
+                    } else {
+                        out.push(leg(b, a, -base_net, 0.0));
+                        out.push(leg(a, b, 0.0, *quote_net));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.trades = 0;
+    }
 }
 
 /// Basiseinfach implementierte Settlement-Engine (z.B. aus matching_engine.rs)
@@ -55,7 +222,7 @@ impl SettlementEngine {
         let user_balance = self.balances.entry(user_id.to_string()).or_insert_with(std::collections::HashMap::new);
         let entry = user_balance.entry(asset.to_string()).or_insert((0.0, 0.0));
         if entry.0 < amount {
-            return Err(DexError::Other(format!("Nicht genügend Guthaben bei {}", user_id)));
+            return Err(DexError::InsufficientBalance { user: user_id.to_string(), asset: asset.to_string() });
         }
         entry.0 -= amount;
         entry.1 += amount;
@@ -66,7 +233,7 @@ impl SettlementEngine {
         let user_balance = self.balances.entry(user_id.to_string()).or_insert_with(std::collections::HashMap::new);
         let entry = user_balance.entry(asset.to_string()).or_insert((0.0, 0.0));
         if entry.1 < amount {
-            return Err(DexError::Other(format!("Nicht genügend gesperrte Mittel bei {}", user_id)));
+            return Err(DexError::InsufficientBalance { user: user_id.to_string(), asset: format!("locked {}", asset) });
         }
         entry.1 -= amount;
         entry.0 += amount;
@@ -75,6 +242,7 @@ impl SettlementEngine {
 }
 
 impl SettlementEngineTrait for SettlementEngine {
+    #[instrument(name = "finalize_trade", skip(self))]
     fn finalize_trade(
         &mut self,
         buyer: &str,
@@ -98,18 +266,65 @@ impl SettlementEngineTrait for SettlementEngine {
 /// SecuredSettlementEngine umschließt eine bestehende SettlementEngine (inner)
 /// und einen Sicherheitsvalidator. Vor dem finalen Abschluss eines Settlements
 /// wird der Validator aufgerufen, um die Sicherheitsbedingungen zu prüfen.
+///
+/// Über `queue_trade` gesammelte Trades bilden ein Settlement-Fenster; erst
+/// `flush_window` verrechnet sie und settlet je Gegenpartei-Paar netto.
+///
+/// Mit `dry_run` wird weiterhin validiert, gebucht wird aber nur ins
+/// `paper`-Ledger; `inner` (Wallets/Chains) wird nie aufgerufen.
 pub struct SecuredSettlementEngine<E: SettlementEngineTrait, S: SecurityValidator> {
     pub inner: E,
     pub validator: S,
+    pub window: NettingBatch,
+    pub dry_run: bool,
+    pub paper: PaperLedger,
 }
 
 impl<E: SettlementEngineTrait, S: SecurityValidator> SecuredSettlementEngine<E, S> {
     pub fn new(inner: E, validator: S) -> Self {
-        Self { inner, validator }
+        Self { inner, validator, window: NettingBatch::new(), dry_run: false, paper: PaperLedger::default() }
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Merkt einen Trade für das laufende Settlement-Fenster vor.
+    pub fn queue_trade(&mut self, trade: PendingTrade) -> Result<(), DexError> {
+        if trade.base_amount < 0.0 || trade.quote_amount < 0.0 {
+            return Err(DexError::Other(format!("Negativer Settlement-Betrag: {:?}", trade)));
+        }
+        self.window.push(&trade);
+        Ok(())
+    }
+
+    /// Schließt das Fenster: verrechnet alle vorgemerkten Trades und führt
+    /// nur die Netto-Transfers (validiert) aus. Liefert deren Anzahl.
+    ///
+    /// Scheitert ein Transfer, enthält das Fenster danach genau die noch
+    /// offenen Transfers: ein erneuter Flush wendet die bereits
+    /// abgewickelten nicht doppelt an und verliert keine Trades.
+    #[instrument(name = "flush_settlement_window", skip(self), fields(trades = self.window.trade_count()))]
+    pub fn flush_window(&mut self) -> Result<usize, DexError> {
+        let transfers = self.window.net_transfers();
+        debug!("Netting: {} Trades => {} Transfers", self.window.trade_count(), transfers.len());
+        for (i, t) in transfers.iter().enumerate() {
+            if let Err(e) = self.finalize_trade(&t.buyer, &t.seller, &t.base_asset, &t.quote_asset, t.base_amount, t.quote_amount) {
+                self.window.clear();
+                for open in &transfers[i..] {
+                    self.window.push(open);
+                }
+                return Err(e);
+            }
+        }
+        self.window.clear();
+        Ok(transfers.len())
     }
 }
 
 impl<E: SettlementEngineTrait, S: SecurityValidator> SettlementEngineTrait for SecuredSettlementEngine<E, S> {
+    #[instrument(name = "secured_finalize_trade", skip(self))]
     fn finalize_trade(
         &mut self,
         buyer: &str,
@@ -126,9 +341,21 @@ impl<E: SettlementEngineTrait, S: SecurityValidator> SettlementEngineTrait for S
         // NEU: Wenn validator.validate_settlement(...) in einem Stub immer Err(...) wirft, 
         // blockierst du dein System. => Ggf. optional config: use_zk_snarks => wenn false => skip
         self.validator.validate_settlement(&settlement_info)?;
+        if self.dry_run {
+            debug!("Dry-Run => simuliertes Settlement: {}", settlement_info);
+            self.paper.apply(buyer, seller, base_asset, quote_asset, base_amount, quote_amount);
+            return Ok(());
+        }
         // Wenn die Validierung erfolgreich ist, delegieren wir an die innere Engine.
         self.inner.finalize_trade(buyer, seller, base_asset, quote_asset, base_amount, quote_amount)
     }
+
+    fn settle_batch(&mut self, trades: &[PendingTrade]) -> Result<usize, DexError> {
+        for t in trades {
+            self.queue_trade(t.clone())?;
+        }
+        self.flush_window()
+    }
 }
 
 #[cfg(test)]
@@ -144,4 +371,114 @@ mod tests {
         let result = secured_engine.finalize_trade("buyer", "seller", "BTC", "USDT", 1.0, 50000.0);
         assert!(result.is_ok());
     }
+
+    /// Zählt nur die Aufrufe, damit die Anzahl Transfers prüfbar ist.
+    #[derive(Default)]
+    struct CountingEngine {
+        calls: Vec<(String, String, f64, f64)>,
+    }
+
+    impl SettlementEngineTrait for CountingEngine {
+        fn finalize_trade(
+            &mut self,
+            buyer: &str,
+            seller: &str,
+            _base_asset: &str,
+            _quote_asset: &str,
+            base_amount: f64,
+            quote_amount: f64,
+        ) -> Result<(), DexError> {
+            self.calls.push((buyer.to_string(), seller.to_string(), base_amount, quote_amount));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_ten_trades_between_two_users_net_to_one_transfer() {
+        let mut engine = SecuredSettlementEngine::new(CountingEngine::default(), AdvancedSecurityValidator::new());
+        // 7x alice kauft 1 BTC @100, 3x bob kauft 1 BTC @100 => netto alice +4 BTC / -400 USDT
+        let trades: Vec<PendingTrade> = (0..10)
+            .map(|i| {
+                let (buyer, seller) = if i < 7 { ("alice", "bob") } else { ("bob", "alice") };
+                PendingTrade {
+                    buyer: buyer.into(),
+                    seller: seller.into(),
+                    base_asset: "BTC".into(),
+                    quote_asset: "USDT".into(),
+                    base_amount: 1.0,
+                    quote_amount: 100.0,
+                }
+            })
+            .collect();
+
+        let transfers = engine.settle_batch(&trades).unwrap();
+        assert_eq!(transfers, 1);
+        assert_eq!(engine.inner.calls, vec![("alice".to_string(), "bob".to_string(), 4.0, 400.0)]);
+        assert!(engine.window.is_empty());
+    }
+
+    #[test]
+    fn test_failed_flush_keeps_only_open_transfers() {
+        /// Scheitert beim ersten Transfer an `fail_for`.
+        struct FailFor {
+            fail_for: Option<String>,
+            calls: Vec<String>,
+        }
+        impl SettlementEngineTrait for FailFor {
+            fn finalize_trade(&mut self, buyer: &str, _: &str, _: &str, _: &str, _: f64, _: f64) -> Result<(), DexError> {
+                if self.fail_for.as_deref() == Some(buyer) {
+                    self.fail_for = None;
+                    return Err(DexError::Other("rpc down".into()));
+                }
+                self.calls.push(buyer.to_string());
+                Ok(())
+            }
+        }
+        let mut engine = SecuredSettlementEngine::new(
+            FailFor { fail_for: Some("carol".into()), calls: Vec::new() },
+            AdvancedSecurityValidator::new(),
+        );
+        let t = PendingTrade {
+            buyer: "alice".into(),
+            seller: "bob".into(),
+            base_asset: "BTC".into(),
+            quote_asset: "USDT".into(),
+            base_amount: 1.0,
+            quote_amount: 100.0,
+        };
+        engine.queue_trade(t.clone()).unwrap();
+        engine.queue_trade(PendingTrade { buyer: "carol".into(), seller: "dave".into(), ..t }).unwrap();
+
+        assert!(engine.flush_window().is_err());
+        assert_eq!(engine.window.net_transfers().len(), 1);
+        assert_eq!(engine.flush_window().unwrap(), 1);
+        assert_eq!(engine.inner.calls, vec!["alice".to_string(), "carol".to_string()]);
+        assert!(engine.window.is_empty());
+    }
+
+    #[test]
+    fn test_fully_offsetting_trades_settle_nothing() {
+        let mut batch = NettingBatch::new();
+        let t = PendingTrade {
+            buyer: "alice".into(),
+            seller: "bob".into(),
+            base_asset: "BTC".into(),
+            quote_asset: "USDT".into(),
+            base_amount: 2.0,
+            quote_amount: 200.0,
+        };
+        batch.push(&t);
+        batch.push(&PendingTrade { buyer: "bob".into(), seller: "alice".into(), ..t.clone() });
+        assert_eq!(batch.trade_count(), 2);
+        assert!(batch.net_transfers().is_empty());
+
+        // Gleiche Base-Menge, aber anderer Preis => nur die Quote-Differenz fließt
+        batch.clear();
+        batch.push(&t);
+        batch.push(&PendingTrade { buyer: "bob".into(), seller: "alice".into(), quote_amount: 210.0, ..t });
+        let net = batch.net_transfers();
+        assert_eq!(net.len(), 1);
+        assert_eq!((net[0].buyer.as_str(), net[0].seller.as_str()), ("bob", "alice"));
+        assert_eq!((net[0].base_amount, net[0].quote_amount), (0.0, 10.0));
+    }
 }
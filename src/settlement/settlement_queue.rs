@@ -0,0 +1,354 @@
+///////////////////////////////////////////////////////////
+// my_dex/src/settlement/settlement_queue.rs
+///////////////////////////////////////////////////////////
+//
+// Dauerhafte Settlement-Queue in DexDB.
+//
+// Ein Match verändert das Buch sofort, das Settlement kann aber scheitern
+// (z.B. RPC einer Chain nicht erreichbar). Statt den Fehler nur zu loggen,
+// landen die Trades eines Laufs als Job in der Queue und bleiben dort im
+// Zustand "pending settlement", bis ein Versuch gelingt.
+//
+//  - Jeder Trade hat einen Idempotency-Key (Buy-ID:Sell-ID). Ein Key, der
+//    schon eingereiht oder abgewickelt ist, wird nicht erneut aufgenommen.
+//  - Ein Job wird je Gegenpartei-Paar genettet (NettingBatch, deterministisch
+//    aus den Trades des Jobs). Jeder Netto-Transfer wird direkt nach seinem
+//    Erfolg markiert; ein Retry nach Teilausfall wendet nur die offenen an.
+//  - Fehlgeschlagene Jobs werden mit exponentiellem Backoff wiederholt.
+//  - Nach Erfolg werden alle Keys des Jobs als "settled" markiert; ein
+//    erneutes Einreihen oder ein doppelter Retry ist damit ein No-Op.
+//
+// Layout:
+//   settlement_queue/<job_id>          => SettlementJob
+//   settlement_trade/<key>             => TradeSettlementState
+//   settlement_transfer/<job_id>/<i>   => Marker: Transfer i des Jobs angewendet
+///////////////////////////////////////////////////////////
+
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::error::DexError;
+use crate::settlement::secured_settlement::{NettingBatch, PendingTrade, SettlementEngineTrait};
+use crate::storage::db_layer::DexDB;
+use crate::utils::lock::LockRecover;
+
+const JOB_PREFIX: &str = "settlement_queue/";
+const TRADE_PREFIX: &str = "settlement_trade/";
+const TRANSFER_PREFIX: &str = "settlement_transfer/";
+
+/// Zustand eines einzelnen Trades (per Idempotency-Key).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TradeSettlementState {
+    Pending { job_id: String },
+    Settled,
+}
+
+/// Ein Trade mit seinem Idempotency-Key.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyedTrade {
+    pub key: String,
+    pub trade: PendingTrade,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SettlementJob {
+    pub id: String,
+    pub trades: Vec<KeyedTrade>,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+}
+
+/// Ergebnis eines Queue-Durchlaufs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueueRunReport {
+    pub settled_jobs: usize,
+    pub failed_jobs: usize,
+    pub transfers: usize,
+    /// Keys der Trades, die in diesem Lauf vollständig abgewickelt wurden.
+    pub settled_keys: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct SettlementQueue {
+    db: Arc<Mutex<DexDB>>,
+    pub base_backoff_secs: u64,
+    pub max_backoff_secs: u64,
+}
+
+impl SettlementQueue {
+    pub fn new(db: Arc<Mutex<DexDB>>) -> Self {
+        Self { db, base_backoff_secs: 2, max_backoff_secs: 300 }
+    }
+
+    pub fn with_backoff(mut self, base_secs: u64, max_secs: u64) -> Self {
+        self.base_backoff_secs = base_secs;
+        self.max_backoff_secs = max_secs.max(base_secs);
+        self
+    }
+
+    pub fn trade_state(&self, key: &str) -> Result<Option<TradeSettlementState>, DexError> {
+        self.db.lock_recover().load_struct(&format!("{}{}", TRADE_PREFIX, key))
+    }
+
+    /// Reiht die noch unbekannten Trades als einen Job ein.
+    /// Liefert die Job-ID oder None, wenn alle Keys schon bekannt sind.
+    pub fn enqueue(&self, trades: Vec<KeyedTrade>, now: u64) -> Result<Option<String>, DexError> {
+        let mut fresh = Vec::with_capacity(trades.len());
+        for t in trades {
+            if self.trade_state(&t.key)?.is_none() && !fresh.iter().any(|f: &KeyedTrade| f.key == t.key) {
+                fresh.push(t);
+            }
+        }
+        if fresh.is_empty() {
+            return Ok(None);
+        }
+
+        let mut hasher = Sha256::new();
+        for t in &fresh {
+            hasher.update(t.key.as_bytes());
+            hasher.update([0u8]);
+        }
+        let job_id = hex::encode(&hasher.finalize()[..16]);
+        let job = SettlementJob {
+            id: job_id.clone(),
+            trades: fresh,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+        };
+
+        let db = self.db.lock_recover();
+        // Job zuerst schreiben: stürzt der Node dazwischen ab, bleibt höchstens
+        // ein Job ohne Key-Marker übrig, den der nächste Lauf normal abwickelt.
+        db.store_struct(&format!("{}{}", JOB_PREFIX, job_id), &job)?;
+        for t in &job.trades {
+            db.store_struct(
+                &format!("{}{}", TRADE_PREFIX, t.key),
+                &TradeSettlementState::Pending { job_id: job_id.clone() },
+            )?;
+        }
+        Ok(Some(job_id))
+    }
+
+    /// Alle noch offenen Jobs (nach Job-ID sortiert).
+    pub fn pending_jobs(&self) -> Result<Vec<SettlementJob>, DexError> {
+        let entries = self.db.lock_recover().list_entries_with_prefix(JOB_PREFIX)?;
+        entries
+            .into_iter()
+            .map(|(k, v)| {
+                bincode::deserialize(&v).map_err(|e| DexError::Other(format!("settlement job {}: {:?}", k, e)))
+            })
+            .collect()
+    }
+
+    fn backoff_secs(&self, attempts: u32) -> u64 {
+        let exp = attempts.saturating_sub(1).min(32);
+        self.base_backoff_secs.saturating_mul(1u64 << exp).min(self.max_backoff_secs)
+    }
+
+    /// Netto-Transfers eines Jobs; gleiche Trades ergeben immer dieselbe Liste
+    /// in derselben Reihenfolge, die Indizes sind damit über Retries stabil.
+    fn job_transfers(job: &SettlementJob) -> Vec<PendingTrade> {
+        let mut batch = NettingBatch::new();
+        for t in &job.trades {
+            batch.push(&t.trade);
+        }
+        batch.net_transfers()
+    }
+
+    fn transfer_key(job_id: &str, index: usize) -> String {
+        format!("{}{}/{}", TRANSFER_PREFIX, job_id, index)
+    }
+
+    /// Wendet die noch nicht markierten Transfers an und markiert jeden
+    /// sofort nach Erfolg. Liefert die Anzahl in diesem Lauf angewendeter.
+    fn apply_open_transfers(
+        &self,
+        engine: &mut dyn SettlementEngineTrait,
+        job_id: &str,
+        transfers: &[PendingTrade],
+    ) -> Result<usize, DexError> {
+        let mut applied = 0;
+        for (i, t) in transfers.iter().enumerate() {
+            let key = Self::transfer_key(job_id, i);
+            if self.db.lock_recover().load_struct::<bool>(&key)?.is_some() {
+                continue;
+            }
+            engine.finalize_trade(&t.buyer, &t.seller, &t.base_asset, &t.quote_asset, t.base_amount, t.quote_amount)?;
+            self.db.lock_recover().store_struct(&key, &true)?;
+            applied += 1;
+        }
+        Ok(applied)
+    }
+
+    /// Versucht alle fälligen Jobs abzuwickeln. Fehler einzelner Jobs
+    /// werden nicht propagiert, sondern mit Backoff neu eingeplant.
+    pub fn process_due(&self, engine: &mut dyn SettlementEngineTrait, now: u64) -> Result<QueueRunReport, DexError> {
+        let mut report = QueueRunReport::default();
+        for mut job in self.pending_jobs()? {
+            if job.next_attempt_at > now {
+                continue;
+            }
+            let transfers = Self::job_transfers(&job);
+            let outcome = self.apply_open_transfers(engine, &job.id, &transfers);
+            let db = self.db.lock_recover();
+            match outcome {
+                Ok(applied) => {
+                    for t in &job.trades {
+                        db.store_struct(&format!("{}{}", TRADE_PREFIX, t.key), &TradeSettlementState::Settled)?;
+                    }
+                    db.delete_key(&format!("{}{}", JOB_PREFIX, job.id))?;
+                    for i in 0..transfers.len() {
+                        db.delete_key(&Self::transfer_key(&job.id, i))?;
+                    }
+                    report.settled_jobs += 1;
+                    report.transfers += applied;
+                    report.settled_keys.extend(job.trades.iter().map(|t| t.key.clone()));
+                    info!("Settlement-Job {} abgewickelt ({} Trades, Versuch {})", job.id, job.trades.len(), job.attempts + 1);
+                }
+                Err(e) => {
+                    job.attempts += 1;
+                    job.next_attempt_at = now + self.backoff_secs(job.attempts);
+                    job.last_error = Some(e.to_string());
+                    warn!(
+                        "Settlement-Job {} fehlgeschlagen (Versuch {}): {} => Retry ab {}",
+                        job.id, job.attempts, e, job.next_attempt_at
+                    );
+                    db.store_struct(&format!("{}{}", JOB_PREFIX, job.id), &job)?;
+                    report.failed_jobs += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::db_layer::InMemoryDb;
+
+    /// Scheitert die ersten `fail_first` Aufrufe, zählt danach jede Anwendung.
+    struct FlakyEngine {
+        fail_first: u32,
+        calls: u32,
+        applied: Vec<(String, String, f64, f64)>,
+    }
+
+    impl SettlementEngineTrait for FlakyEngine {
+        fn finalize_trade(
+            &mut self,
+            buyer: &str,
+            seller: &str,
+            _base_asset: &str,
+            _quote_asset: &str,
+            base_amount: f64,
+            quote_amount: f64,
+        ) -> Result<(), DexError> {
+            self.calls += 1;
+            if self.calls <= self.fail_first {
+                return Err(DexError::Other("rpc down".into()));
+            }
+            self.applied.push((buyer.to_string(), seller.to_string(), base_amount, quote_amount));
+            Ok(())
+        }
+    }
+
+    fn mem_queue() -> SettlementQueue {
+        let db = DexDB { rocks: None, fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))) };
+        SettlementQueue::new(Arc::new(Mutex::new(db))).with_backoff(10, 60)
+    }
+
+    fn keyed(key: &str) -> KeyedTrade {
+        KeyedTrade {
+            key: key.to_string(),
+            trade: PendingTrade {
+                buyer: "alice".into(),
+                seller: "bob".into(),
+                base_asset: "BTC".into(),
+                quote_asset: "USDT".into(),
+                base_amount: 1.0,
+                quote_amount: 100.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_failed_then_retried_settlement_applies_exactly_once() {
+        let queue = mem_queue();
+        let mut engine = FlakyEngine { fail_first: 1, calls: 0, applied: Vec::new() };
+        let job_id = queue.enqueue(vec![keyed("b1:s1")], 1_000).unwrap().unwrap();
+
+        // Erster Versuch scheitert => Job bleibt pending, Backoff 10s
+        let r = queue.process_due(&mut engine, 1_000).unwrap();
+        assert_eq!((r.settled_jobs, r.failed_jobs), (0, 1));
+        let jobs = queue.pending_jobs().unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].next_attempt_at, 1_010);
+        assert_eq!(queue.trade_state("b1:s1").unwrap(), Some(TradeSettlementState::Pending { job_id }));
+
+        // Vor Ablauf des Backoffs passiert nichts
+        let r = queue.process_due(&mut engine, 1_005).unwrap();
+        assert_eq!(r, QueueRunReport::default());
+
+        // Retry gelingt
+        let r = queue.process_due(&mut engine, 1_010).unwrap();
+        assert_eq!((r.settled_jobs, r.transfers), (1, 1));
+        assert!(queue.pending_jobs().unwrap().is_empty());
+        assert_eq!(queue.trade_state("b1:s1").unwrap(), Some(TradeSettlementState::Settled));
+
+        // Erneutes Einreihen und weitere Läufe wenden nichts doppelt an
+        assert_eq!(queue.enqueue(vec![keyed("b1:s1")], 2_000).unwrap(), None);
+        queue.process_due(&mut engine, 5_000).unwrap();
+        assert_eq!(engine.applied, vec![("alice".to_string(), "bob".to_string(), 1.0, 100.0)]);
+    }
+
+    #[test]
+    fn test_partial_failure_retries_only_open_transfers() {
+        let queue = mem_queue();
+        // Zwei Paare => zwei Netto-Transfers; der zweite scheitert einmal
+        let mut carol = keyed("b2:s2");
+        carol.trade.buyer = "carol".into();
+        carol.trade.seller = "dave".into();
+        queue.enqueue(vec![keyed("b1:s1"), carol], 1_000).unwrap().unwrap();
+        struct SecondFailsOnce {
+            calls: u32,
+            applied: Vec<String>,
+        }
+        impl SettlementEngineTrait for SecondFailsOnce {
+            fn finalize_trade(&mut self, buyer: &str, _: &str, _: &str, _: &str, _: f64, _: f64) -> Result<(), DexError> {
+                self.calls += 1;
+                if self.calls == 2 {
+                    return Err(DexError::Other("rpc down".into()));
+                }
+                self.applied.push(buyer.to_string());
+                Ok(())
+            }
+        }
+        let mut engine = SecondFailsOnce { calls: 0, applied: Vec::new() };
+
+        let r = queue.process_due(&mut engine, 1_000).unwrap();
+        assert_eq!(r.failed_jobs, 1);
+        assert_eq!(engine.applied, vec!["alice".to_string()]);
+
+        let r = queue.process_due(&mut engine, 1_010).unwrap();
+        assert_eq!((r.settled_jobs, r.transfers), (1, 1));
+        // alice/bob wurde nicht doppelt abgewickelt
+        assert_eq!(engine.applied, vec!["alice".to_string(), "carol".to_string()]);
+        assert!(queue.db.lock_recover().list_entries_with_prefix(TRANSFER_PREFIX).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let queue = mem_queue();
+        assert_eq!(queue.backoff_secs(1), 10);
+        assert_eq!(queue.backoff_secs(2), 20);
+        assert_eq!(queue.backoff_secs(3), 40);
+        assert_eq!(queue.backoff_secs(4), 60);
+        assert_eq!(queue.backoff_secs(40), 60);
+    }
+}
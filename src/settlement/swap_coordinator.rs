@@ -0,0 +1,519 @@
+///////////////////////////////////////////////////////////
+// my_dex/src/settlement/swap_coordinator.rs
+///////////////////////////////////////////////////////////
+//
+// Generischer Cross-Chain-Swap-Koordinator (HTLC-Lebenszyklus).
+//
+// `cross_chain_demo` zeigt einen einmaligen Ablauf im Speicher. Hier wird
+// derselbe Ablauf als Zustandsmaschine in DexDB geführt, damit ein Neustart
+// laufende Swaps nicht verliert:
+//
+//   Negotiated -> InitiatorLocked -> BothLocked -> Revealed -> Completed
+//        \              \                \
+//         +--------------+----------------+--> CounterpartyRefunded -> Refunded
+//
+//  - Der Initiator sperrt zuerst (lange Timelock), die Gegenpartei danach
+//    (kurze Timelock). Der Initiator löst die Gegenseite mit dem Preimage ein
+//    und macht es damit öffentlich; die Gegenpartei löst damit ein.
+//  - Läuft die kurze Timelock ab, bevor das Preimage offen ist, wird die
+//    Gegenseite erstattet; nach der langen Timelock die Initiator-Seite.
+//  - Die Mittel bewegt das `ChainSettlement`-Backend des jeweiligen Assets
+//    aus der `SettlementRegistry` (lock = HTLC-Funding, settle = Claim,
+//    unlock = Refund), immer über `execute_once` mit der ID
+//    "<swap_id>:<Zielzustand>".
+//  - Write-ahead: vor dem Backend-Aufruf wird der Zielzustand als
+//    `pending_step` gespeichert. Stürzt der Node zwischen Backend und
+//    Speichern ab, setzt `drive` genau diesen Schritt fort; das Backend
+//    erkennt die Operations-ID und bewegt nichts doppelt.
+//  - Das Preimage wird nicht gespeichert, sondern per HMAC aus dem
+//    Node-Geheimnis und dem Swap-Salt abgeleitet. Vor dem Einlösen wird es
+//    gegen den Hashlock geprüft; öffentlich (`revealed_preimage`) ist es erst
+//    ab `Revealed`.
+//  - Jeder Übergang wird gespeichert, in `history` festgehalten und als
+//    Audit-Event geloggt.
+//
+// Ein fehlgeschlagener Backend-Aufruf ändert den Zustand nicht; der nächste
+// `drive` plant den Schritt neu (inkl. Timelock-Prüfung).
+//
+// Mit ClockSkewGuard laufen die Timelocks gegen die NTP-korrigierte Zeit, und
+// solange die lokale Uhr nicht geprüft oder außerhalb der Toleranz ist, wird
+// nichts weitergetrieben (ein vorgehender Node würde sonst zu früh erstatten).
+//
+// Layout:
+//   cross_chain_swap/<swap_id> => SwapRecord
+///////////////////////////////////////////////////////////
+
+use std::sync::{Arc, Mutex};
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::error::DexError;
+use crate::logging::enhanced_logging::write_audit_log;
+use crate::settlement::advanced_settlement::{Asset, ChainOp, SettlementRegistry};
+use crate::storage::db_layer::DexDB;
+use crate::utils::geoip_and_ntp::ClockSkewGuard;
+use crate::utils::lock::LockRecover;
+
+const SWAP_PREFIX: &str = "cross_chain_swap/";
+
+/// Eine Seite des Swaps: `owner` sperrt `amount` von `asset` für die Gegenpartei.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SwapLeg {
+    pub owner: String,
+    pub asset: Asset,
+    pub amount: f64,
+}
+
+/// Ausgehandelte Bedingungen.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SwapTerms {
+    pub initiator: SwapLeg,
+    pub counterparty: SwapLeg,
+    /// Dauer der kurzen Timelock (Gegenseite) in Sekunden; die Initiator-Seite
+    /// erhält das Doppelte.
+    pub timelock_secs: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrossChainSwapState {
+    Negotiated,
+    InitiatorLocked,
+    BothLocked,
+    /// Initiator hat die Gegenseite eingelöst, Preimage ist öffentlich.
+    Revealed,
+    Completed,
+    /// Gegenseite erstattet, Initiator-Seite wartet auf ihre Timelock.
+    CounterpartyRefunded,
+    Refunded,
+}
+
+impl CrossChainSwapState {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, CrossChainSwapState::Completed | CrossChainSwapState::Refunded)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SwapTransition {
+    pub from: CrossChainSwapState,
+    pub to: CrossChainSwapState,
+    pub at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SwapRecord {
+    pub id: String,
+    pub terms: SwapTerms,
+    /// sha256(preimage), hex
+    pub hashlock: String,
+    /// Zufälliger Salt, aus dem der Initiator das Preimage ableitet (hex);
+    /// ohne das Node-Geheimnis wertlos.
+    pub preimage_salt: String,
+    /// Ab `Revealed` öffentlich (hex), vorher None.
+    pub revealed_preimage: Option<String>,
+    pub state: CrossChainSwapState,
+    /// Begonnener, noch nicht bestätigter Übergang (Write-ahead).
+    pub pending_step: Option<CrossChainSwapState>,
+    pub initiator_timelock: u64,
+    pub counterparty_timelock: u64,
+    pub history: Vec<SwapTransition>,
+    pub last_error: Option<String>,
+}
+
+pub struct CrossChainSwapCoordinator {
+    db: Arc<Mutex<DexDB>>,
+    registry: SettlementRegistry,
+    /// HMAC-Schlüssel für die Preimage-Ableitung (aus dem Keystore)
+    preimage_key: [u8; 32],
+    /// NTP-korrigierte Zeit für `run` (None => Systemuhr)
+    clock_guard: Option<Arc<ClockSkewGuard>>,
+}
+
+impl CrossChainSwapCoordinator {
+    pub fn new(db: Arc<Mutex<DexDB>>, registry: SettlementRegistry, preimage_key: [u8; 32]) -> Self {
+        Self { db, registry, preimage_key, clock_guard: None }
+    }
+
+    pub fn with_clock_guard(mut self, guard: Arc<ClockSkewGuard>) -> Self {
+        self.clock_guard = Some(guard);
+        self
+    }
+
+    /// Zeit für Timelock-Entscheidungen; Fehler, solange die Uhr nicht stimmt.
+    fn now_secs(&self) -> Result<u64, DexError> {
+        match &self.clock_guard {
+            Some(guard) => {
+                guard.ensure_local_clock_ok()?;
+                Ok(guard.corrected_now_ms() / 1000)
+            }
+            None => Ok(std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)),
+        }
+    }
+
+    fn derive_preimage(&self, salt_hex: &str) -> Result<Vec<u8>, DexError> {
+        let salt = hex::decode(salt_hex).map_err(|e| DexError::Other(format!("swap salt: {}", e)))?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.preimage_key).expect("HMAC accepts any key length");
+        mac.update(b"my_dex/swap_preimage");
+        mac.update(&salt);
+        Ok(mac.finalize().into_bytes().to_vec())
+    }
+
+    /// Prüft das Preimage gegen den Hashlock des Swaps.
+    fn checked_preimage(rec: &SwapRecord, preimage: &[u8]) -> Result<(), DexError> {
+        if hex::encode(Sha256::digest(preimage)) != rec.hashlock {
+            return Err(DexError::Other(format!("Swap {}: preimage does not match hashlock", rec.id)));
+        }
+        Ok(())
+    }
+
+    fn key(id: &str) -> String {
+        format!("{}{}", SWAP_PREFIX, id)
+    }
+
+    fn store(&self, rec: &SwapRecord) -> Result<(), DexError> {
+        self.db.lock_recover().store_struct(&Self::key(&rec.id), rec)
+    }
+
+    pub fn swap(&self, id: &str) -> Result<Option<SwapRecord>, DexError> {
+        self.db.lock_recover().load_struct(&Self::key(id))
+    }
+
+    /// Legt einen neuen Swap an; das Preimage wird aus einem frischen Salt abgeleitet.
+    pub fn negotiate(&self, terms: SwapTerms, now: u64) -> Result<String, DexError> {
+        if terms.initiator.amount <= 0.0 || terms.counterparty.amount <= 0.0 {
+            return Err(DexError::Other("Swap amounts must be positive".into()));
+        }
+        if terms.timelock_secs == 0 {
+            return Err(DexError::Other("Swap timelock must be > 0".into()));
+        }
+        // Backends müssen vorhanden sein, bevor etwas gesperrt wird
+        self.registry.backend(&terms.initiator.asset)?;
+        self.registry.backend(&terms.counterparty.asset)?;
+
+        let mut salt = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let preimage_salt = hex::encode(salt);
+        let preimage = self.derive_preimage(&preimage_salt)?;
+        let hashlock = hex::encode(Sha256::digest(&preimage));
+        let mut hasher = Sha256::new();
+        hasher.update(hashlock.as_bytes());
+        hasher.update(terms.initiator.owner.as_bytes());
+        hasher.update(terms.counterparty.owner.as_bytes());
+        hasher.update(now.to_be_bytes());
+        let id = hex::encode(&hasher.finalize()[..16]);
+
+        let rec = SwapRecord {
+            id: id.clone(),
+            counterparty_timelock: now + terms.timelock_secs,
+            initiator_timelock: now + 2 * terms.timelock_secs,
+            terms,
+            hashlock,
+            preimage_salt,
+            revealed_preimage: None,
+            state: CrossChainSwapState::Negotiated,
+            pending_step: None,
+            history: Vec::new(),
+            last_error: None,
+        };
+        self.store(&rec)?;
+        write_audit_log(&format!(
+            "CrossChainSwap {} negotiated: {} {:?} {:.8} <-> {} {:?} {:.8}",
+            id,
+            rec.terms.initiator.owner, rec.terms.initiator.asset, rec.terms.initiator.amount,
+            rec.terms.counterparty.owner, rec.terms.counterparty.asset, rec.terms.counterparty.amount,
+        ));
+        Ok(id)
+    }
+
+    /// Nächster Zielzustand (None = kein Schritt fällig). Ein begonnener
+    /// Schritt (`pending_step`) hat Vorrang, unabhängig von den Timelocks.
+    fn plan_step(rec: &SwapRecord, now: u64) -> Option<CrossChainSwapState> {
+        use CrossChainSwapState::*;
+        if let Some(pending) = rec.pending_step {
+            return Some(pending);
+        }
+        let cp_expired = now >= rec.counterparty_timelock;
+        let init_expired = now >= rec.initiator_timelock;
+        Some(match rec.state {
+            Negotiated if cp_expired => Refunded,
+            Negotiated => InitiatorLocked,
+            InitiatorLocked if cp_expired => CounterpartyRefunded,
+            InitiatorLocked => BothLocked,
+            BothLocked if cp_expired => CounterpartyRefunded,
+            BothLocked => Revealed,
+            Revealed => Completed,
+            CounterpartyRefunded if init_expired => Refunded,
+            CounterpartyRefunded | Completed | Refunded => return None,
+        })
+    }
+
+    /// Backend-Operation für den Übergang `from -> to` (None = nichts zu bewegen).
+    fn step_op(&self, rec: &SwapRecord, from: CrossChainSwapState, to: CrossChainSwapState) -> Result<Option<(Asset, ChainOp)>, DexError> {
+        use CrossChainSwapState::*;
+        let init = &rec.terms.initiator;
+        let cp = &rec.terms.counterparty;
+        Ok(match (from, to) {
+            (Negotiated, InitiatorLocked) => Some((init.asset.clone(), ChainOp::Lock { user: init.owner.clone(), amount: init.amount })),
+            (InitiatorLocked, BothLocked) => Some((cp.asset.clone(), ChainOp::Lock { user: cp.owner.clone(), amount: cp.amount })),
+            (BothLocked, CounterpartyRefunded) => Some((cp.asset.clone(), ChainOp::Unlock { user: cp.owner.clone(), amount: cp.amount })),
+            (BothLocked, Revealed) => {
+                // Initiator löst die Gegenseite mit dem Preimage ein
+                Self::checked_preimage(rec, &self.derive_preimage(&rec.preimage_salt)?)?;
+                Some((cp.asset.clone(), ChainOp::Settle { from: cp.owner.clone(), to: init.owner.clone(), amount: cp.amount }))
+            }
+            (Revealed, Completed) => {
+                // Gegenpartei löst mit dem nun öffentlichen Preimage ein
+                let revealed = rec
+                    .revealed_preimage
+                    .as_deref()
+                    .ok_or_else(|| DexError::Other(format!("Swap {}: preimage not revealed", rec.id)))?;
+                let preimage = hex::decode(revealed).map_err(|e| DexError::Other(format!("revealed preimage: {}", e)))?;
+                Self::checked_preimage(rec, &preimage)?;
+                Some((init.asset.clone(), ChainOp::Settle { from: init.owner.clone(), to: cp.owner.clone(), amount: init.amount }))
+            }
+            (CounterpartyRefunded, Refunded) => Some((init.asset.clone(), ChainOp::Unlock { user: init.owner.clone(), amount: init.amount })),
+            // Abgelaufen, bevor etwas gesperrt war / nur die Initiator-Seite gesperrt
+            (Negotiated, Refunded) | (InitiatorLocked, CounterpartyRefunded) => None,
+            (from, to) => return Err(DexError::Other(format!("Swap {}: invalid transition {:?} -> {:?}", rec.id, from, to))),
+        })
+    }
+
+    /// Führt alle fälligen Schritte eines Swaps aus und liefert den Endzustand.
+    pub fn drive(&self, id: &str, now: u64) -> Result<CrossChainSwapState, DexError> {
+        let mut rec = self
+            .swap(id)?
+            .ok_or_else(|| DexError::Other(format!("Unknown swap {}", id)))?;
+        while let Some(to) = Self::plan_step(&rec, now) {
+            let from = rec.state;
+            let op = match self.step_op(&rec, from, to) {
+                Ok(op) => op,
+                Err(e) => {
+                    warn!("CrossChainSwap {} bleibt in {:?}: {}", rec.id, from, e);
+                    rec.last_error = Some(e.to_string());
+                    self.store(&rec)?;
+                    return Ok(rec.state);
+                }
+            };
+            // Absicht zuerst speichern, dann das Backend
+            if rec.pending_step != Some(to) {
+                rec.pending_step = Some(to);
+                self.store(&rec)?;
+            }
+            if let Some((asset, op)) = op {
+                let op_id = format!("{}:{:?}", rec.id, to);
+                let res = self.registry.backend(&asset).and_then(|b| b.execute_once(&op_id, &asset, &op));
+                if let Err(e) = res {
+                    // Operation nicht ausgeführt => Schritt beim nächsten drive neu planen
+                    warn!("CrossChainSwap {} bleibt in {:?}: {}", rec.id, from, e);
+                    rec.pending_step = None;
+                    rec.last_error = Some(e.to_string());
+                    self.store(&rec)?;
+                    return Ok(rec.state);
+                }
+            }
+            if to == CrossChainSwapState::Revealed {
+                rec.revealed_preimage = Some(hex::encode(self.derive_preimage(&rec.preimage_salt)?));
+            }
+            rec.state = to;
+            rec.pending_step = None;
+            rec.last_error = None;
+            rec.history.push(SwapTransition { from, to, at: now });
+            self.store(&rec)?;
+            write_audit_log(&format!("CrossChainSwap {}: {:?} -> {:?} (t={})", rec.id, from, to, now));
+        }
+        Ok(rec.state)
+    }
+
+    /// Alle nicht abgeschlossenen Swaps (nach ID sortiert).
+    pub fn in_flight(&self) -> Result<Vec<SwapRecord>, DexError> {
+        let entries = self.db.lock_recover().list_entries_with_prefix(SWAP_PREFIX)?;
+        let mut out = Vec::new();
+        for (k, v) in entries {
+            let rec: SwapRecord =
+                bincode::deserialize(&v).map_err(|e| DexError::Other(format!("swap record {}: {:?}", k, e)))?;
+            if !rec.state.is_terminal() {
+                out.push(rec);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Nach einem Neustart: alle offenen Swaps weitertreiben.
+    pub fn recover(&self, now: u64) -> Result<Vec<(String, CrossChainSwapState)>, DexError> {
+        let pending = self.in_flight()?;
+        info!("CrossChainSwap-Recovery: {} offene Swaps", pending.len());
+        pending
+            .into_iter()
+            .map(|rec| self.drive(&rec.id, now).map(|s| (rec.id, s)))
+            .collect()
+    }
+
+    /// Treibt alle offenen Swaps periodisch weiter (Timelocks, Retries),
+    /// beginnend mit einer Recovery direkt nach dem Start.
+    pub async fn run(self, interval: std::time::Duration, token: CancellationToken) {
+        loop {
+            match self.now_secs() {
+                Ok(now) => {
+                    if let Err(e) = self.recover(now) {
+                        warn!("CrossChainSwap-Lauf fehlgeschlagen: {}", e);
+                    }
+                }
+                Err(e) => warn!("CrossChainSwap-Lauf ausgesetzt: {}", e),
+            }
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settlement::advanced_settlement::{ChainSettlement, LedgerBalances, LedgerSettlement};
+
+    const KEY: [u8; 32] = [9u8; 32];
+    use std::collections::HashMap;
+    use CrossChainSwapState::*;
+
+    fn setup() -> (Arc<Mutex<DexDB>>, SettlementRegistry, LedgerBalances) {
+        let db = DexDB::in_memory();
+        let balances: LedgerBalances = Arc::new(Mutex::new(HashMap::new()));
+        {
+            let mut b = balances.lock().unwrap();
+            b.entry("alice".into()).or_insert_with(HashMap::new).insert(Asset::BTC, (1.0, 0.0));
+            b.entry("bob".into()).or_insert_with(HashMap::new).insert(Asset::ETH, (20.0, 0.0));
+        }
+        let ledger: Arc<dyn ChainSettlement> = Arc::new(LedgerSettlement::new(balances.clone()));
+        let mut registry = SettlementRegistry::default();
+        registry.register(Asset::BTC, ledger.clone());
+        registry.register(Asset::ETH, ledger);
+        (Arc::new(Mutex::new(db)), registry, balances)
+    }
+
+    fn terms() -> SwapTerms {
+        SwapTerms {
+            initiator: SwapLeg { owner: "alice".into(), asset: Asset::BTC, amount: 0.5 },
+            counterparty: SwapLeg { owner: "bob".into(), asset: Asset::ETH, amount: 10.0 },
+            timelock_secs: 100,
+        }
+    }
+
+    fn bal(b: &LedgerBalances, user: &str, asset: Asset) -> (f64, f64) {
+        b.lock().unwrap().get(user).and_then(|m| m.get(&asset).copied()).unwrap_or((0.0, 0.0))
+    }
+
+    fn states(rec: &SwapRecord) -> Vec<CrossChainSwapState> {
+        rec.history.iter().map(|t| t.to).collect()
+    }
+
+    #[test]
+    fn test_happy_path_completes_swap() {
+        let (db, registry, balances) = setup();
+        let coord = CrossChainSwapCoordinator::new(db, registry, KEY);
+        let id = coord.negotiate(terms(), 1_000).unwrap();
+
+        assert_eq!(coord.drive(&id, 1_010).unwrap(), Completed);
+        let rec = coord.swap(&id).unwrap().unwrap();
+        assert_eq!(states(&rec), vec![InitiatorLocked, BothLocked, Revealed, Completed]);
+        let revealed = hex::decode(rec.revealed_preimage.unwrap()).unwrap();
+        assert_eq!(rec.hashlock, hex::encode(Sha256::digest(&revealed)));
+
+        assert_eq!(bal(&balances, "alice", Asset::BTC), (0.5, 0.0));
+        assert_eq!(bal(&balances, "alice", Asset::ETH), (10.0, 0.0));
+        assert_eq!(bal(&balances, "bob", Asset::BTC), (0.5, 0.0));
+        assert_eq!(bal(&balances, "bob", Asset::ETH), (10.0, 0.0));
+        assert!(coord.in_flight().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_timeout_refund_survives_restart() {
+        let (db, registry, balances) = setup();
+        // Bob hat zu wenig ETH => Gegenseite kann nicht sperren
+        balances.lock().unwrap().get_mut("bob").unwrap().insert(Asset::ETH, (1.0, 0.0));
+
+        let coord = CrossChainSwapCoordinator::new(db.clone(), registry.clone(), KEY);
+        let id = coord.negotiate(terms(), 1_000).unwrap();
+        assert_eq!(coord.drive(&id, 1_010).unwrap(), InitiatorLocked);
+        assert!(coord.swap(&id).unwrap().unwrap().last_error.is_some());
+        assert_eq!(bal(&balances, "alice", Asset::BTC), (0.5, 0.5));
+        drop(coord);
+
+        // Neustart nach Ablauf der kurzen Timelock
+        let coord = CrossChainSwapCoordinator::new(db.clone(), registry.clone(), KEY);
+        assert_eq!(coord.recover(1_100).unwrap(), vec![(id.clone(), CounterpartyRefunded)]);
+        // Initiator-Seite bleibt bis zur langen Timelock gesperrt
+        assert_eq!(bal(&balances, "alice", Asset::BTC), (0.5, 0.5));
+        drop(coord);
+
+        let coord = CrossChainSwapCoordinator::new(db, registry, KEY);
+        assert_eq!(coord.recover(1_200).unwrap(), vec![(id.clone(), Refunded)]);
+        let rec = coord.swap(&id).unwrap().unwrap();
+        assert_eq!(states(&rec), vec![InitiatorLocked, CounterpartyRefunded, Refunded]);
+        assert_eq!(bal(&balances, "alice", Asset::BTC), (1.0, 0.0));
+        assert_eq!(bal(&balances, "bob", Asset::ETH), (1.0, 0.0));
+        assert!(coord.recover(1_300).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unverified_clock_suspends_timelock_decisions() {
+        let (db, registry, _) = setup();
+        let guard = Arc::new(ClockSkewGuard::new(1_000));
+        let coord = CrossChainSwapCoordinator::new(db, registry, KEY).with_clock_guard(guard.clone());
+        assert!(coord.now_secs().is_err(), "nie gemessen");
+        guard.record_offset(-60_000);
+        assert!(matches!(coord.now_secs(), Err(DexError::ClockSkew { .. })));
+        guard.record_offset(500);
+        let now = coord.now_secs().unwrap();
+        assert!(now.abs_diff(guard.corrected_now_ms() / 1000) <= 1);
+    }
+
+    #[test]
+    fn test_crash_after_backend_step_does_not_move_funds_twice() {
+        let (db, registry, balances) = setup();
+        let coord = CrossChainSwapCoordinator::new(db.clone(), registry.clone(), KEY);
+        let id = coord.negotiate(terms(), 1_000).unwrap();
+
+        // Absturz simulieren: Absicht gespeichert, Backend hat gesperrt, Zustand nicht mehr gespeichert
+        let mut rec = coord.swap(&id).unwrap().unwrap();
+        rec.pending_step = Some(InitiatorLocked);
+        coord.store(&rec).unwrap();
+        let lock = ChainOp::Lock { user: "alice".into(), amount: 0.5 };
+        registry.backend(&Asset::BTC).unwrap().execute_once(&format!("{}:InitiatorLocked", id), &Asset::BTC, &lock).unwrap();
+        assert_eq!(bal(&balances, "alice", Asset::BTC), (0.5, 0.5));
+
+        let coord = CrossChainSwapCoordinator::new(db, registry, KEY);
+        assert_eq!(coord.recover(1_010).unwrap(), vec![(id.clone(), Completed)]);
+        // Nur einmal gesperrt und übertragen
+        assert_eq!(bal(&balances, "alice", Asset::BTC), (0.5, 0.0));
+        assert_eq!(bal(&balances, "bob", Asset::BTC), (0.5, 0.0));
+        assert!(coord.swap(&id).unwrap().unwrap().pending_step.is_none());
+    }
+
+    #[test]
+    fn test_preimage_not_stored_and_checked_against_hashlock() {
+        let (db, registry, balances) = setup();
+        let coord = CrossChainSwapCoordinator::new(db.clone(), registry.clone(), KEY);
+        let id = coord.negotiate(terms(), 1_000).unwrap();
+        let preimage = coord.derive_preimage(&coord.swap(&id).unwrap().unwrap().preimage_salt).unwrap();
+        let raw = db.lock().unwrap().list_entries_with_prefix(SWAP_PREFIX).unwrap().remove(0).1;
+        assert!(!raw.windows(preimage.len()).any(|w| w == preimage.as_slice()));
+        assert!(coord.swap(&id).unwrap().unwrap().revealed_preimage.is_none());
+
+        // Anderes Node-Geheimnis => Preimage passt nicht, nichts wird eingelöst
+        let wrong = CrossChainSwapCoordinator::new(db, registry, [1u8; 32]);
+        assert_eq!(wrong.drive(&id, 1_010).unwrap(), BothLocked);
+        assert!(wrong.swap(&id).unwrap().unwrap().last_error.unwrap().contains("hashlock"));
+        assert_eq!(bal(&balances, "bob", Asset::ETH), (10.0, 10.0));
+        assert_eq!(bal(&balances, "alice", Asset::ETH), (0.0, 0.0));
+    }
+}
@@ -5,8 +5,12 @@
 // Erweiterter ShardManager mit parted storing (Sharding) + Self-Healing:
 //  - Wir verwalten für jeden Shard einen AdvancedShardState
 //  - Wir halten fest, welche NodeIds Replikate eines Shards besitzen (ShardReplicaInfo)
-//  - Beim Node-Ausfall (on_node_failed) verteilen wir den Shard an eine neue Node
-//  - Periodisches maintain_shards() prüft, ob unser Replication-Factor erfüllt ist.
+//  - Beim Node-Ausfall (on_node_failed) und periodisch in maintain_shards()
+//    gleichen wir die tatsächlichen Replikate per plan_from_replicas() an die
+//    HRW-Platzierung der lebenden Member an (siehe rebalance.rs).
+//  - Ist der lokale Node Quelle eines Moves, schickt er das Transfer-Log
+//    (Snapshot seit dem letzten Anker + Deltas) per Kademlia ans Ziel; das
+//    Ziel prüft Signaturen und den verankerten Checkpoint vor der Übernahme.
 //
 // Voraussetzung:
 //  - advanced_crdt_sharding.rs (AdvancedShardState) ist vorhanden
@@ -16,7 +20,7 @@
 // (c) Ihr DEX-Projekt
 //
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use anyhow::Result;
 use tracing::{info, debug, warn};
@@ -35,7 +39,12 @@ use crate::dex_logic::advanced_crdt_sharding::{
 use crate::watchtower::Watchtower;
 
 // Falls du Node-Failure-Detection via Kademlia willst:
-use crate::kademlia::kademlia_service::{KademliaService, NodeId};
+use crate::kademlia::kademlia_service::{KademliaMessage, KademliaService, NodeId};
+use crate::network::cluster_management::{member_key, MemberState, Membership};
+use crate::utils::lock::LockRecover;
+
+pub mod rebalance;
+use rebalance::{checkpoint_hash, ShardMove, ShardPlacement, ShardTransfer};
 
 ////////////////////////////////////////////////////////////
 // Hilfsstruct: ShardReplicaInfo => speichert Replikate pro Shard
@@ -143,6 +152,22 @@ pub struct ShardManager {
 
     /// Optional: Kademlia => um Node-Failure-Detection & Peer-Find durchzuführen
     pub kademlia: Option<Arc<Mutex<KademliaService>>>,
+
+    /// Optional: SWIM-Membership => tote Replikate erkennen, nur lebende Nodes wählen
+    pub membership: Option<Arc<Mutex<Membership>>>,
+
+    /// Transfer-Log je lokalem Shard: Snapshot zum letzten Anker + Deltas seitdem
+    pub transfers: Arc<Mutex<HashMap<u32, ShardTransfer>>>,
+
+    /// Verankerte Checkpoint-Roots je Shard => Referenz für eingehende Transfers
+    pub anchors: Arc<Mutex<HashMap<u32, [u8; 32]>>>,
+
+    /// Eigene Kademlia-ID (beim Erzeugen gemerkt, damit Kademlia-Callbacks
+    /// den Service-Lock nicht erneut nehmen müssen)
+    pub local_id: Option<NodeId>,
+
+    /// Verzeichnis für per Transfer übernommene Shards
+    pub storage_dir: String,
 }
 
 impl ShardManager {
@@ -150,14 +175,30 @@ impl ShardManager {
     ///  - replication_factor => z. B. 3
     ///  - optional kademlia, wenn Sie Node-Failure-Detection und Peer-Suche wollen
     pub fn new(replication_factor: usize, kademlia: Option<Arc<Mutex<KademliaService>>>) -> Self {
+        let local_id = kademlia.as_ref().map(|k| k.lock_recover().local_id.clone());
         Self {
             shards: Arc::new(Mutex::new(HashMap::new())),
             subscriptions: Arc::new(Mutex::new(ShardSubscription::new())),
             shard_info: Arc::new(Mutex::new(ShardReplicaInfo::new(replication_factor))),
             kademlia,
+            membership: None,
+            transfers: Arc::new(Mutex::new(HashMap::new())),
+            anchors: Arc::new(Mutex::new(HashMap::new())),
+            local_id,
+            storage_dir: "shards".to_string(),
         }
     }
 
+    pub fn with_storage_dir(mut self, dir: &str) -> Self {
+        self.storage_dir = dir.to_string();
+        self
+    }
+
+    pub fn with_membership(mut self, membership: Arc<Mutex<Membership>>) -> Self {
+        self.membership = Some(membership);
+        self
+    }
+
     /// Erzeugt einen neuen Shard
     ///  - Pfad => RocksDB
     ///  - watchtower => Falls Sie es brauchen
@@ -167,18 +208,18 @@ impl ShardManager {
         path: &str,
         watchtower: Watchtower
     ) -> Result<()> {
-        let mut lock = self.shards.lock().unwrap();
+        let mut lock = self.shards.lock_recover();
         if lock.contains_key(&shard_id) {
             warn!("Shard {} already exists", shard_id);
             return Ok(());
         }
         let st = AdvancedShardState::new(shard_id, path, watchtower)?;
+        self.transfers.lock_recover().insert(shard_id, ShardTransfer::begin(shard_id, &st.crdt_state));
         lock.insert(shard_id, st);
 
         // Wir selbst sind (lokaler Node) => fügen wir uns als Replica hinzu
-        if let Some(kad) = &self.kademlia {
-            let local_id = kad.lock().unwrap().local_id.clone();
-            self.shard_info.lock().unwrap().add_replica(shard_id, local_id);
+        if let Some(local_id) = &self.local_id {
+            self.shard_info.lock_recover().add_replica(shard_id, local_id.clone());
         }
 
         info!("Shard {} created => path={}", shard_id, path);
@@ -188,20 +229,23 @@ impl ShardManager {
     /// Node abonniert Shard => speichert in subscriptions
     /// (unverändert)
     pub fn subscribe_node_to_shard(&self, node_id: &str, shard_id: u32) {
-        let mut lock = self.subscriptions.lock().unwrap();
+        let mut lock = self.subscriptions.lock_recover();
         lock.subscribe(node_id, shard_id);
     }
 
     pub fn unsubscribe_node_from_shard(&self, node_id: &str, shard_id: u32) {
-        let mut lock = self.subscriptions.lock().unwrap();
+        let mut lock = self.subscriptions.lock_recover();
         lock.unsubscribe(node_id, shard_id);
     }
 
     /// Wendet Delta auf einen Shard an
     pub fn apply_delta(&self, shard_id: u32, delta: &CrdtDelta) -> Result<()> {
-        let mut lock = self.shards.lock().unwrap();
+        let mut lock = self.shards.lock_recover();
         if let Some(sh) = lock.get_mut(&shard_id) {
             sh.apply_delta(delta)?;
+            if let Some(t) = self.transfers.lock_recover().get_mut(&shard_id) {
+                t.push_delta(delta.clone());
+            }
         } else {
             warn!("Shard {} not found => ignoring delta", shard_id);
         }
@@ -210,7 +254,7 @@ impl ShardManager {
 
     /// Shard => Full Snapshot & store
     pub fn store_shard_snapshot(&self, shard_id: u32) -> Result<()> {
-        let mut lock = self.shards.lock().unwrap();
+        let mut lock = self.shards.lock_recover();
         if let Some(sh) = lock.get_mut(&shard_id) {
             sh.store_shard_snapshot()?;
         } else {
@@ -221,7 +265,7 @@ impl ShardManager {
 
     /// Shard => Load snapshot from DB
     pub fn load_shard_snapshot(&self, shard_id: u32) -> Result<()> {
-        let mut lock = self.shards.lock().unwrap();
+        let mut lock = self.shards.lock_recover();
         if let Some(sh) = lock.get_mut(&shard_id) {
             sh.load_shard_snapshot()?;
         } else {
@@ -232,7 +276,7 @@ impl ShardManager {
 
     /// Shard => Erzeugt CrdtShardSnapshot
     pub fn create_shard_snapshot(&self, shard_id: u32) -> Option<CrdtShardSnapshot> {
-        let lock = self.shards.lock().unwrap();
+        let lock = self.shards.lock_recover();
         lock.get(&shard_id).map(|sh| sh.create_shard_snapshot())
     }
 
@@ -240,17 +284,28 @@ impl ShardManager {
     /// und senden an diese Knoten => in einer realen Implementation
     /// bräuchte man p2p-Aufrufe, z. B. p2p.send_message(nodeId, deltaMsg).
     pub fn broadcast_delta(&self, shard_id: u32, delta: CrdtDelta) {
-        let subs = self.subscriptions.lock().unwrap();
+        let subs = self.subscriptions.lock_recover();
         let subscribers = subs.get_subscribers(shard_id);
         debug!("Broadcasting delta to {} subscribers for shard={}", subscribers.len(), shard_id);
         // In echt => p2p.sendDelta(...) 
     }
 
-    /// Checkpoint => MerkleRoot verankern
+    /// Merkle-Root je lokalem Shard (hex, `compute_merkle_root`), nach Shard-ID sortiert.
+    pub fn shard_roots(&self) -> BTreeMap<u32, String> {
+        self.shards
+            .lock_recover()
+            .iter()
+            .map(|(sid, sh)| (*sid, hex::encode(sh.compute_merkle_root())))
+            .collect()
+    }
+
+    /// Checkpoint => MerkleRoot verankern; das Transfer-Log beginnt neu ab diesem Stand.
     pub fn checkpoint_and_store(&self, shard_id: u32, block_height: u64, txid: Option<String>) -> Result<()> {
-        let mut lock = self.shards.lock().unwrap();
+        let mut lock = self.shards.lock_recover();
         if let Some(sh) = lock.get_mut(&shard_id) {
             sh.checkpoint_and_store(block_height, txid)?;
+            self.anchors.lock_recover().insert(shard_id, checkpoint_hash(&sh.crdt_state));
+            self.transfers.lock_recover().insert(shard_id, ShardTransfer::begin(shard_id, &sh.crdt_state));
         } else {
             warn!("Shard {} not found => cannot checkpoint", shard_id);
         }
@@ -263,99 +318,206 @@ impl ShardManager {
     // ----------------------------------------------------------------
 
     /// Wird aufgerufen, wenn Kademlia oder P2P feststellt, dass node_id tot ist.
-    /// => Wir entfernen node_id als Replica, ersetzen ggf. via replicate_shard_to_new_node
+    /// => Moves von den tatsächlichen Replikaten zur HRW-Platzierung der
+    ///    lebenden Member; node_id fällt danach überall als Replica weg.
     pub fn on_node_failed(&self, dead_node: &NodeId) {
-        let mut replica_info = self.shard_info.lock().unwrap();
+        self.rebalance(Some(dead_node));
+        let mut info = self.shard_info.lock_recover();
+        for set in info.shard_replicas.values_mut() {
+            set.remove(dead_node);
+        }
+    }
 
-        let shard_ids: Vec<u32> = replica_info
-            .shard_replicas
-            .keys()
-            .cloned()
-            .collect();
+    /// Gleicht Replikate mit der SWIM-Sicht ab: als Dead gemeldete Nodes
+    /// werden wie `on_node_failed` behandelt.
+    pub fn reconcile_with_membership(&self) {
+        let membership = match &self.membership {
+            Some(m) => m,
+            None => return,
+        };
+        let dead: HashSet<NodeId> = {
+            let m = membership.lock_recover();
+            let info = self.shard_info.lock_recover();
+            info.shard_replicas
+                .values()
+                .flatten()
+                .filter(|nid| m.state_of(&member_key(nid)) == Some(MemberState::Dead))
+                .cloned()
+                .collect()
+        };
+        for nid in dead {
+            info!("Membership => replica {:?} dead => re-replicating", nid);
+            self.on_node_failed(&nid);
+        }
+    }
 
-        for sid in shard_ids {
-            let had_it = replica_info.get_replicas(sid).contains(dead_node);
-            if had_it {
-                replica_info.remove_replica(sid, dead_node);
-                if replica_info.needs_new_replica(sid) {
-                    if let Err(e) = self.replicate_shard_to_new_node(sid) {
-                        warn!("Error replicate shard {} => {:?}", sid, e);
-                    }
-                }
-            }
+    fn is_live_candidate(&self, nid: &NodeId) -> bool {
+        match &self.membership {
+            Some(m) => m.lock_recover().is_alive(&member_key(nid)),
+            None => true,
         }
     }
 
-    /// Falls wir local Shard 'shard_id' haben => wir suchen via Kademlia
-    /// einen neuen Node, der nicht in shard_info, und replicaten Snapshot
-    fn replicate_shard_to_new_node(&self, shard_id: u32) -> Result<()> {
-        let local_map = self.shards.lock().unwrap();
-        let local_shard = match local_map.get(&shard_id) {
-            Some(s) => s,
+    /// Lebende Member (inkl. uns), sortiert. Mit SWIM dessen Sicht, die auf
+    /// allen Nodes konvergiert; sonst Replikat-Halter + Kademlia-Nachbarn.
+    fn live_members(&self, exclude: Option<&NodeId>) -> Vec<NodeId> {
+        let mut members: Vec<NodeId> = match &self.membership {
+            Some(m) => m.lock_recover().alive_members().iter().filter_map(|k| node_id_from_member(k)).collect(),
             None => {
-                warn!("We do not hold shard {}, can't replicate", shard_id);
-                return Ok(());
+                let mut out: Vec<NodeId> =
+                    self.shard_info.lock_recover().shard_replicas.values().flatten().cloned().collect();
+                if let (Some(kad), Some(local)) = (&self.kademlia, &self.local_id) {
+                    out.extend(kad.lock_recover().table.find_closest(local, 20).into_iter().map(|(nid, _)| nid));
+                }
+                out
             }
         };
-        drop(local_map);
+        members.extend(self.local_id.iter().cloned());
+        members.retain(|nid| Some(nid) != exclude && self.is_live_candidate(nid));
+        members.sort_by(|a, b| a.0.cmp(&b.0));
+        members.dedup();
+        members
+    }
 
-        let mut rep_info = self.shard_info.lock().unwrap();
-        let existing = rep_info.get_replicas(shard_id);
+    /// Plant die Moves von den aktuellen Replikaten zur Platzierung unter den
+    /// lebenden Membern (ohne `exclude`) und führt sie aus.
+    fn rebalance(&self, exclude: Option<&NodeId>) {
+        let current = self.shard_info.lock_recover().shard_replicas.clone();
+        let members = self.live_members(exclude);
+        let plan = self.placement().plan_from_replicas(&current, &members);
+        if !plan.is_empty() {
+            info!("Rebalance => {} Moves bei {} lebenden Membern", plan.len(), members.len());
+        }
+        self.execute_plan(&plan);
+    }
 
-        let kad_opt = match &self.kademlia {
-            Some(k) => k.clone(),
-            None => {
-                warn!("No Kademlia => can't replicate automatically!");
-                return Ok(());
-            }
-        };
-        let kad = kad_opt.lock().unwrap();
-        let candidates = kad.table.find_closest(&kad.local_id, 20);
-        drop(kad);
-
-        let mut chosen: Option<NodeId> = None;
-        for (nid, _addr) in candidates {
-            if !existing.contains(&nid) && nid != kad_opt.lock().unwrap().local_id {
-                chosen = Some(nid);
-                break;
+    /// Wie `maintain_shards`, aber nur für `shard_id`; liefert die Anzahl Moves.
+    pub fn rebalance_shard(&self, shard_id: u32) -> usize {
+        let current = self.shard_info.lock_recover().shard_replicas.clone();
+        let members = self.live_members(None);
+        let plan: Vec<ShardMove> = self
+            .placement()
+            .plan_from_replicas(&current, &members)
+            .into_iter()
+            .filter(|mv| mv.shard_id == shard_id)
+            .collect();
+        self.execute_plan(&plan);
+        plan.len()
+    }
+
+    /// Alle Nodes führen denselben Plan aus: die Quelle verschickt ihr
+    /// Transfer-Log, die Replikat-Info zieht überall nach.
+    fn execute_plan(&self, plan: &[ShardMove]) {
+        for mv in plan {
+            if self.local_id.as_ref() == Some(&mv.from) {
+                match self.export_transfer(mv.shard_id) {
+                    Some(transfer) => self.send_transfer(&mv.to, transfer),
+                    None => warn!("Rebalance => Shard {} nicht lokal, kein Transfer an {:?}", mv.shard_id, mv.to),
+                }
             }
+            self.apply_move(mv);
         }
+    }
 
-        let new_node = match chosen {
-            Some(n) => n,
-            None => {
-                warn!("No suitable node found to replicate shard {}", shard_id);
-                return Ok(());
-            }
+    fn send_transfer(&self, to: &NodeId, transfer: ShardTransfer) {
+        let Some(kad) = &self.kademlia else {
+            warn!("No Kademlia => Shard {} kann nicht übertragen werden", transfer.shard_id);
+            return;
         };
+        let shard_id = transfer.shard_id;
+        if kad.lock_recover().send_to_node(to, &KademliaMessage::ShardTransfer(transfer)) {
+            info!("Rebalance => Shard {} an {:?} übertragen", shard_id, to);
+        } else {
+            warn!("Rebalance => Ziel {:?} für Shard {} nicht in der Routing-Tabelle", to, shard_id);
+        }
+    }
+
+    // ----------------------------------------------------------------
+    // Rebalancing => deterministischer Plan + Snapshot/Delta-Transfer
+    // ----------------------------------------------------------------
+
+    /// Alle bekannten Shards (lokal oder mit Replikat-Info) mit unserem Replication-Factor.
+    pub fn placement(&self) -> ShardPlacement {
+        let info = self.shard_info.lock_recover();
+        let local = self.shards.lock_recover();
+        ShardPlacement::new(
+            info.shard_replicas.keys().chain(local.keys()).copied(),
+            info.replication_factor,
+        )
+    }
+
+    /// Minimale Moves, wenn sich die Cluster-Mitglieder von `old_members` zu `new_members` ändern.
+    pub fn compute_rebalance_plan(&self, old_members: &[NodeId], new_members: &[NodeId]) -> Vec<ShardMove> {
+        self.placement().compute_rebalance_plan(old_members, new_members)
+    }
+
+    /// Quelle: aktuelles Transfer-Log des Shards (Snapshot zum letzten Anker + Deltas).
+    pub fn export_transfer(&self, shard_id: u32) -> Option<ShardTransfer> {
+        self.transfers.lock_recover().get(&shard_id).cloned()
+    }
 
-        // => wir versenden Snapshot => p2p call
-        let snap = local_shard.create_shard_snapshot();
-        info!("Replicate shard {} => new node: {:?}, sending snapshot", shard_id, new_node);
+    /// Verankerter Root eines Shards, z. B. aus einem Checkpoint auf der Chain.
+    pub fn record_anchored_checkpoint(&self, shard_id: u32, root: [u8; 32]) {
+        self.anchors.lock_recover().insert(shard_id, root);
+    }
 
-        // TODO => p2p_send_shard_snapshot(new_node, snap) => in echt real Code
-        // Evtl. wir fügen in "subscribe_node_to_shard" => ...
-        // Nach dem Versenden:
-        rep_info.add_replica(shard_id, new_node);
+    /// Ziel: per Kademlia eingetroffenen Transfer prüfen und übernehmen.
+    /// Ohne bekannten Anker muss der Snapshot leer sein (Stand bei Erzeugung).
+    pub fn receive_transfer(&self, transfer: &ShardTransfer) -> Result<()> {
+        let anchored = self
+            .anchors
+            .lock_recover()
+            .get(&transfer.shard_id)
+            .copied()
+            .unwrap_or_else(|| CrdtState::default().state_root());
+        let node = self.local_id.as_ref().map(member_key).unwrap_or_default();
+        let path = format!("{}/shard_{}", self.storage_dir, transfer.shard_id);
+        self.install_transfer(transfer, &anchored, &path, Watchtower::new(&node))
+    }
 
+    /// Ziel: State aus dem Transfer aufbauen, Signaturen und den Anker prüfen
+    /// und erst dann als lokalen Shard übernehmen.
+    pub fn install_transfer(
+        &self,
+        transfer: &ShardTransfer,
+        anchored: &[u8; 32],
+        path: &str,
+        watchtower: Watchtower,
+    ) -> Result<()> {
+        let state = transfer.restore(anchored)?;
+        let mut st = AdvancedShardState::new(transfer.shard_id, path, watchtower)?;
+        st.crdt_state = state;
+        st.store_shard_snapshot()?;
+        self.shards.lock_recover().insert(transfer.shard_id, st);
+        // Log weiterführen => wir können den Shard selbst wieder abgeben
+        self.transfers.lock_recover().insert(transfer.shard_id, transfer.clone());
+        if let Some(local_id) = &self.local_id {
+            self.shard_info.lock_recover().add_replica(transfer.shard_id, local_id.clone());
+        }
+        info!("Rebalance => shard {} übernommen, Checkpoint ok", transfer.shard_id);
         Ok(())
     }
 
-    /// Manuell periodisch aufrufen => check if needs new replica
-    pub fn maintain_shards(&self) {
-        let shard_ids: Vec<u32> = {
-            let s = self.shards.lock().unwrap();
-            s.keys().cloned().collect()
-        };
-        for sid in shard_ids {
-            let mut rep_info = self.shard_info.lock().unwrap();
-            if rep_info.needs_new_replica(sid) {
-                if let Err(e) = self.replicate_shard_to_new_node(sid) {
-                    warn!("Error replicate shard {} => {:?}", sid, e);
-                }
-            }
+    /// Replikat-Info gemäß Move nachziehen.
+    pub fn apply_move(&self, mv: &ShardMove) {
+        let mut info = self.shard_info.lock_recover();
+        info.add_replica(mv.shard_id, mv.to.clone());
+        if let Some(released) = &mv.release {
+            info.remove_replica(mv.shard_id, released);
         }
     }
+
+    /// Manuell periodisch aufrufen => tote Replikate ersetzen, Platzierung angleichen
+    pub fn maintain_shards(&self) {
+        self.reconcile_with_membership();
+        self.rebalance(None);
+    }
+}
+
+/// Membership-Key (hex) => NodeId
+fn node_id_from_member(key: &str) -> Option<NodeId> {
+    let bytes = hex::decode(key).ok()?;
+    Some(NodeId(bytes.try_into().ok()?))
 }
 
 ////////////////////////////////////////////////////////////
@@ -374,6 +536,7 @@ pub fn demo_shard_manager_advanced() -> Result<()> {
     sm.subscribe_node_to_shard("AliceNode", 0);
 
     // 3) wende Delta an:
+    let mut clock = crate::utils::hlc::HybridLogicalClock::new("AliceNode");
     let deltaA = CrdtDelta {
         updated_orders: vec![
             Order {
@@ -382,9 +545,14 @@ pub fn demo_shard_manager_advanced() -> Result<()> {
                 timestamp: 0,
                 quantity: 3.0,
                 price: 99.0,
+                side: None,
+                hlc: clock.tick(),
+                signature: None,
+                public_key: None,
             }
         ],
-        removed_orders: vec![]
+        removed_orders: vec![],
+        hlc: clock.tick(),
     };
     sm.apply_delta(0, &deltaA)?;
 
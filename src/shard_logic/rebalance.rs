@@ -0,0 +1,387 @@
+////////////////////////////////////////////////////////////
+// my_dex/src/shard_logic/rebalance.rs
+////////////////////////////////////////////////////////////
+//
+// Deterministisches Rebalancing der Shard-Replikate:
+//  - Platzierung per Rendezvous-Hashing (HRW): für jeden Shard gewinnen die
+//    `replication_factor` Nodes mit dem höchsten SHA-256(shard_id || node_id).
+//    Kommt ein Node hinzu, wechseln nur die Shards, bei denen er gewinnt
+//    (bei n -> n+1 Nodes im Mittel 1/(n+1)); fällt einer weg, nur seine.
+//  - compute_rebalance_plan(old, new) => minimale Liste von ShardMoves
+//    (Quelle -> Ziel), auf allen Nodes identisch. plan_from_replicas() geht
+//    statt von einer alten Member-Liste von den tatsächlichen Replikaten aus,
+//    damit die Replikat-Info nach den Moves wieder der HRW-Platzierung folgt.
+//  - ShardTransfer => OR-Set-Snapshot (Tags + Tombstones) zum letzten
+//    verankerten Checkpoint + alle Deltas seitdem. Das Ziel prüft jede
+//    Order-Signatur und vergleicht den Snapshot mit dem Checkpoint-Root, den
+//    es selbst kennt (Anker), nicht mit einem Wert der Quelle.
+//
+// Der Checkpoint-Hash ist der State-Root des CrdtState (sichtbare Orders
+// sortiert nach id, unabhängig von der HashMap-Reihenfolge im ORSet).
+////////////////////////////////////////////////////////////
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::crdt_logic::{CrdtSnapshot, CrdtState};
+use crate::dex_logic::advanced_crdt_sharding::CrdtDelta;
+use crate::error::DexError;
+use crate::kademlia::kademlia_service::NodeId;
+
+/// Node-Kennung, unter der übertragene Orders im ORSet landen.
+const TRANSFER_NODE: &str = "rebalance";
+
+/// Eine Replica von `shard_id` wandert zu `to`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardMove {
+    pub shard_id: u32,
+    /// Node, der Snapshot + Deltas liefert (lebende Replica)
+    pub from: NodeId,
+    pub to: NodeId,
+    /// Replica, die nach erfolgreicher Übernahme entfällt; `None`, wenn sie
+    /// schon weg ist (ausgefallener Node) und `from` nur als Quelle dient.
+    pub release: Option<NodeId>,
+}
+
+/// Welche Shards es gibt und wie oft jeder repliziert wird.
+#[derive(Debug, Clone)]
+pub struct ShardPlacement {
+    pub shard_ids: Vec<u32>,
+    pub replication_factor: usize,
+}
+
+impl ShardPlacement {
+    pub fn new(shard_ids: impl IntoIterator<Item = u32>, replication_factor: usize) -> Self {
+        let mut shard_ids: Vec<u32> = shard_ids.into_iter().collect();
+        shard_ids.sort_unstable();
+        shard_ids.dedup();
+        Self { shard_ids, replication_factor }
+    }
+
+    /// Replikate von `shard_id` unter `members`, bester Score zuerst.
+    pub fn replicas_for(&self, shard_id: u32, members: &[NodeId]) -> Vec<NodeId> {
+        let mut scored: Vec<([u8; 32], &NodeId)> = members
+            .iter()
+            .map(|nid| (rendezvous_score(shard_id, nid), nid))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1 .0.cmp(&b.1 .0)));
+        scored.dedup_by(|a, b| a.1 == b.1);
+        scored
+            .into_iter()
+            .take(self.replication_factor)
+            .map(|(_, nid)| nid.clone())
+            .collect()
+    }
+
+    /// Minimale Moves von der Zuordnung unter `old_members` zu der unter `new_members`.
+    /// Nur Shards, deren Replikat-Menge sich ändert, tauchen auf; sortiert nach
+    /// (shard_id, to), damit alle Nodes denselben Plan ausführen.
+    pub fn compute_rebalance_plan(&self, old_members: &[NodeId], new_members: &[NodeId]) -> Vec<ShardMove> {
+        let alive: BTreeSet<&[u8; 32]> = new_members.iter().map(|n| &n.0).collect();
+        let mut plan = Vec::new();
+        for &shard_id in &self.shard_ids {
+            let old = self.replicas_for(shard_id, old_members);
+            let new = self.replicas_for(shard_id, new_members);
+            plan_shard(shard_id, &old, &new, &alive, &mut plan);
+        }
+        sort_plan(&mut plan);
+        plan
+    }
+
+    /// Wie `compute_rebalance_plan`, aber ausgehend von den tatsächlichen
+    /// Replikaten (`current`, z. B. `ShardReplicaInfo::shard_replicas`) statt
+    /// einer HRW-Zuordnung unter alten Membern.
+    pub fn plan_from_replicas(
+        &self,
+        current: &HashMap<u32, HashSet<NodeId>>,
+        new_members: &[NodeId],
+    ) -> Vec<ShardMove> {
+        let alive: BTreeSet<&[u8; 32]> = new_members.iter().map(|n| &n.0).collect();
+        let mut plan = Vec::new();
+        for &shard_id in &self.shard_ids {
+            let old: Vec<NodeId> = current.get(&shard_id).map(|s| s.iter().cloned().collect()).unwrap_or_default();
+            let new = self.replicas_for(shard_id, new_members);
+            plan_shard(shard_id, &old, &new, &alive, &mut plan);
+        }
+        sort_plan(&mut plan);
+        plan
+    }
+}
+
+fn plan_shard(shard_id: u32, old: &[NodeId], new: &[NodeId], alive: &BTreeSet<&[u8; 32]>, plan: &mut Vec<ShardMove>) {
+    let mut added: Vec<&NodeId> = new.iter().filter(|n| !old.contains(n)).collect();
+    let mut removed: Vec<&NodeId> = old.iter().filter(|n| !new.contains(n)).collect();
+    if added.is_empty() {
+        return;
+    }
+    added.sort_by(|a, b| a.0.cmp(&b.0));
+    removed.sort_by(|a, b| a.0.cmp(&b.0));
+    // Ist die abgegebene Replica tot, liefert eine bleibende den Stand,
+    // notfalls eine andere lebende, die selbst abgegeben wird
+    let survivor = old
+        .iter()
+        .filter(|n| new.contains(n))
+        .min_by(|a, b| a.0.cmp(&b.0))
+        .or_else(|| old.iter().filter(|n| alive.contains(&n.0)).min_by(|a, b| a.0.cmp(&b.0)));
+
+    for (i, to) in added.into_iter().enumerate() {
+        let released = removed.get(i).copied();
+        let from = match released {
+            Some(r) if alive.contains(&r.0) => Some(r),
+            _ => survivor,
+        };
+        match from {
+            Some(from) => plan.push(ShardMove {
+                shard_id,
+                from: from.clone(),
+                to: to.clone(),
+                release: released.cloned(),
+            }),
+            None => warn!("Rebalance => Shard {} hat keine lebende Replica als Quelle", shard_id),
+        }
+    }
+}
+
+fn sort_plan(plan: &mut [ShardMove]) {
+    plan.sort_by(|a, b| a.shard_id.cmp(&b.shard_id).then_with(|| a.to.0.cmp(&b.to.0)));
+}
+
+fn rendezvous_score(shard_id: u32, node: &NodeId) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(shard_id.to_be_bytes());
+    hasher.update(node.0);
+    hasher.finalize().into()
+}
+
+/// Hash über die sichtbaren Orders, sortiert nach id (= `CrdtState::state_root`).
+pub fn checkpoint_hash(state: &CrdtState) -> [u8; 32] {
+    state.state_root()
+}
+
+/// Wendet ein Delta auf einen CrdtState an, mit derselben Signaturprüfung
+/// wie `AdvancedShardState::apply_delta`, aber ohne DB.
+pub fn apply_delta_to_state(state: &mut CrdtState, delta: &CrdtDelta) -> Result<(), DexError> {
+    state.clock.update(delta.hlc);
+    for o in &delta.updated_orders {
+        if !o.verify_signature() {
+            warn!("Order {} hat ungültige Signatur => im Transfer übersprungen", o.id);
+            continue;
+        }
+        state.add_remote_order(TRANSFER_NODE, o.clone())?;
+    }
+    for rid in &delta.removed_orders {
+        state.remove_local_order(TRANSFER_NODE, rid)?;
+    }
+    Ok(())
+}
+
+/// Übertragung eines Shards: OR-Set-Snapshot zum letzten verankerten
+/// Checkpoint (bzw. zur Shard-Erzeugung) und alle Deltas seitdem. Die Quelle
+/// führt das Log laufend mit, damit jeder Transfer gegen den Anker prüfbar ist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardTransfer {
+    pub shard_id: u32,
+    pub snapshot: CrdtSnapshot,
+    pub deltas: Vec<CrdtDelta>,
+}
+
+impl ShardTransfer {
+    pub fn begin(shard_id: u32, source: &CrdtState) -> Self {
+        Self { shard_id, snapshot: source.snapshot(), deltas: Vec::new() }
+    }
+
+    /// Delta, das die Quelle nach dem Snapshot angewendet hat.
+    pub fn push_delta(&mut self, delta: CrdtDelta) {
+        self.deltas.push(delta);
+    }
+
+    /// Baut den State auf dem Ziel auf. Jede Order im Snapshot muss gültig
+    /// signiert sein, und der Snapshot muss `anchored` ergeben – den Root des
+    /// Checkpoints, den das Ziel unabhängig von der Quelle kennt.
+    pub fn restore(&self, anchored: &[u8; 32]) -> Result<CrdtState, DexError> {
+        let orders = self
+            .snapshot
+            .adds
+            .iter()
+            .chain(&self.snapshot.removes)
+            .map(|(o, _)| o)
+            .chain(self.snapshot.fill_counters.iter().map(|(o, _)| o));
+        for o in orders {
+            if !o.verify_signature() {
+                return Err(DexError::InvalidSignature(format!(
+                    "Order {} im Transfer von Shard {}",
+                    o.id, self.shard_id
+                )));
+            }
+        }
+
+        let mut state = CrdtState::default();
+        state.merge_snapshot(TRANSFER_NODE, &self.snapshot)?;
+        let actual = checkpoint_hash(&state);
+        if &actual != anchored {
+            return Err(DexError::CheckpointMismatch {
+                shard_id: self.shard_id,
+                expected: hex::encode(anchored),
+                actual: hex::encode(actual),
+            });
+        }
+        for d in &self.deltas {
+            apply_delta_to_state(&mut state, d)?;
+        }
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crdt_logic::Order;
+    use crate::utils::hlc::HybridLogicalClock;
+    use ed25519_dalek::Keypair;
+
+    fn node(i: u8) -> NodeId {
+        NodeId([i; 32])
+    }
+
+    fn signed_order(id: &str, quantity: f64, kp: &Keypair, clock: &mut HybridLogicalClock) -> Order {
+        let mut o = Order {
+            id: id.to_string(),
+            user_id: "alice".to_string(),
+            timestamp: 1,
+            quantity,
+            price: 100.0,
+            side: None,
+            hlc: clock.tick(),
+            signature: None,
+            public_key: None,
+        };
+        o.sign(kp).unwrap();
+        o
+    }
+
+    fn shard_state(shard_id: u32, kp: &Keypair) -> CrdtState {
+        let mut clock = HybridLogicalClock::new("src");
+        let mut st = CrdtState::default();
+        for k in 0..3 {
+            let o = signed_order(&format!("s{}-o{}", shard_id, k), 1.0 + k as f64, kp, &mut clock);
+            st.add_remote_order("src", o).unwrap();
+        }
+        st
+    }
+
+    fn keypair() -> Keypair {
+        Keypair::generate(&mut rand_07::rngs::OsRng)
+    }
+
+    #[test]
+    fn test_adding_fourth_node_moves_about_a_quarter_and_transfers_verify() {
+        let placement = ShardPlacement::new(0..256, 1);
+        let old = vec![node(1), node(2), node(3)];
+        let new = vec![node(1), node(2), node(3), node(4)];
+
+        let plan = placement.compute_rebalance_plan(&old, &new);
+        let moved = plan.len() as f64 / 256.0;
+        assert!((0.18..=0.32).contains(&moved), "{} Shards bewegt", plan.len());
+        // Nur der neue Node gewinnt Shards, jede Quelle gibt ihren ab
+        for mv in &plan {
+            assert_eq!(mv.to, node(4));
+            assert_eq!(mv.release.as_ref(), Some(&mv.from));
+            assert_eq!(placement.replicas_for(mv.shard_id, &old), vec![mv.from.clone()]);
+        }
+        // Deterministisch, unabhängig von der Reihenfolge der Member
+        let shuffled = vec![node(4), node(2), node(1), node(3)];
+        assert_eq!(placement.compute_rebalance_plan(&old, &shuffled), plan);
+
+        let kp = keypair();
+        let mut clock = HybridLogicalClock::new("src");
+        for mv in &plan {
+            let mut source = shard_state(mv.shard_id, &kp);
+            // Vor dem Anker entfernt => Tombstone muss mitwandern
+            source.remove_local_order("src", &format!("s{}-o2", mv.shard_id)).unwrap();
+            let anchored = checkpoint_hash(&source);
+            let mut transfer = ShardTransfer::begin(mv.shard_id, &source);
+            // Nach dem Anker entfernt die Quelle eine weitere Order
+            let delta = CrdtDelta {
+                updated_orders: vec![],
+                removed_orders: vec![format!("s{}-o0", mv.shard_id)],
+                hlc: clock.tick(),
+            };
+            apply_delta_to_state(&mut source, &delta).unwrap();
+            transfer.push_delta(delta);
+
+            let mut restored = transfer.restore(&anchored).unwrap();
+            assert_eq!(checkpoint_hash(&restored), checkpoint_hash(&source));
+            assert_eq!(restored.visible_orders().len(), 1);
+            // Alte Adds der Quelle erwecken die entfernte Order nicht wieder
+            restored.merge_snapshot("src", &shard_state(mv.shard_id, &kp).snapshot()).unwrap();
+            assert!(!restored.visible_orders().iter().any(|o| o.id.ends_with("-o2")));
+        }
+    }
+
+    #[test]
+    fn test_plan_from_replicas_converges_to_hrw_placement() {
+        let placement = ShardPlacement::new(0..32, 2);
+        // Tatsächliche Replikate ad hoc verteilt: überall node(1) + node(3)
+        let current: HashMap<u32, HashSet<NodeId>> =
+            (0..32).map(|sid| (sid, [node(1), node(3)].into_iter().collect())).collect();
+        let live = vec![node(1), node(2), node(4)];
+
+        let plan = placement.plan_from_replicas(&current, &live);
+        let mut after = current.clone();
+        for mv in &plan {
+            assert_ne!(mv.from, node(3), "tote Replica darf keine Quelle sein");
+            let set = after.get_mut(&mv.shard_id).unwrap();
+            set.insert(mv.to.clone());
+            if let Some(r) = &mv.release {
+                set.remove(r);
+            }
+        }
+        for sid in 0..32 {
+            let expected: HashSet<NodeId> = placement.replicas_for(sid, &live).into_iter().collect();
+            assert_eq!(after[&sid], expected, "Shard {}", sid);
+        }
+    }
+
+    #[test]
+    fn test_failed_node_and_tampered_transfer() {
+        let placement = ShardPlacement::new(0..64, 2);
+        let old = vec![node(1), node(2), node(3), node(4)];
+        let new = vec![node(1), node(2), node(4)];
+
+        let plan = placement.compute_rebalance_plan(&old, &new);
+        assert!(!plan.is_empty());
+        for mv in &plan {
+            // node(3) ist tot => Quelle ist die verbleibende Replica
+            assert_eq!(mv.release, Some(node(3)));
+            assert_ne!(mv.from, node(3));
+            assert!(placement.replicas_for(mv.shard_id, &new).contains(&mv.to));
+        }
+
+        let kp = keypair();
+        let source = shard_state(7, &kp);
+        let anchored = checkpoint_hash(&source);
+        let transfer = ShardTransfer::begin(7, &source);
+        assert!(transfer.restore(&anchored).is_ok());
+
+        // Veränderte Menge => Signatur passt nicht mehr
+        let mut tampered = transfer.clone();
+        tampered.snapshot.adds[0].0.quantity = 42.0;
+        assert!(matches!(tampered.restore(&anchored), Err(DexError::InvalidSignature(_))));
+
+        // Unsignierte Order untergeschoben
+        let mut unsigned = transfer.clone();
+        let mut extra = unsigned.snapshot.adds[0].clone();
+        extra.0.id = "forged".into();
+        extra.0.signature = None;
+        unsigned.snapshot.adds.push(extra);
+        assert!(matches!(unsigned.restore(&anchored), Err(DexError::InvalidSignature(_))));
+
+        // Gültig signierte Order weggelassen => Anker passt nicht
+        let mut truncated = transfer.clone();
+        truncated.snapshot.adds.pop();
+        assert!(matches!(truncated.restore(&anchored), Err(DexError::CheckpointMismatch { shard_id: 7, .. })));
+    }
+}
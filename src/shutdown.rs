@@ -0,0 +1,128 @@
+// my_dex/src/shutdown.rs
+//
+// Geordneter Shutdown:
+//  - Ein gemeinsamer `CancellationToken` wird an die großen Subsysteme
+//    (Kademlia, Matching, Replikation, Watchdog) gereicht.
+//  - Nach `cancel()` beenden die Tasks ihre aktuelle Arbeit, sichern ihren
+//    Zustand (z.B. Order-Book in die DexDB) und schließen Verbindungen.
+//  - Was nach `timeout` noch läuft, wird abgebrochen.
+
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio::time::{timeout_at, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ShutdownReport {
+    pub completed: Vec<String>,
+    pub aborted: Vec<String>,
+}
+
+pub struct ShutdownCoordinator {
+    token: CancellationToken,
+    timeout: Duration,
+    tasks: Vec<(String, JoinHandle<()>)>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(timeout: Duration) -> Self {
+        Self { token: CancellationToken::new(), timeout, tasks: Vec::new() }
+    }
+
+    /// Token für Subsysteme, die ihre Tasks selbst spawnen.
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Spawnt einen Task, der den Token bekommt und beim Shutdown abgewartet wird.
+    pub fn spawn<F, Fut>(&mut self, name: &str, f: F)
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handle = tokio::spawn(f(self.token.clone()));
+        self.tasks.push((name.to_string(), handle));
+    }
+
+    /// Übernimmt einen bereits gespawnten Task.
+    pub fn track(&mut self, name: &str, handle: JoinHandle<()>) {
+        self.tasks.push((name.to_string(), handle));
+    }
+
+    /// Signalisiert den Shutdown und wartet höchstens `timeout` auf alle Tasks.
+    pub async fn shutdown(self) -> ShutdownReport {
+        info!("Shutdown => signalisiere {} Tasks (Timeout {:?})", self.tasks.len(), self.timeout);
+        self.token.cancel();
+        let deadline = Instant::now() + self.timeout;
+        let mut report = ShutdownReport::default();
+        for (name, mut handle) in self.tasks {
+            match timeout_at(deadline, &mut handle).await {
+                Ok(Ok(())) => report.completed.push(name),
+                Ok(Err(e)) => {
+                    warn!("Shutdown => Task {} endete mit Fehler: {:?}", name, e);
+                    report.completed.push(name);
+                }
+                Err(_) => {
+                    warn!("Shutdown => Task {} nach Timeout abgebrochen", name);
+                    handle.abort();
+                    report.aborted.push(name);
+                }
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching_engine::{MatchingEngine, OrderData, OrderSide, OrderType};
+    use crate::storage::db_layer::{DexDB, InMemoryDb};
+    use std::sync::{Arc, Mutex};
+
+    fn mem_db() -> DexDB {
+        DexDB { rocks: None, fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))) }
+    }
+
+    #[tokio::test]
+    async fn test_pending_book_persist_completes_during_shutdown() {
+        let db = Arc::new(mem_db());
+        let mut engine = MatchingEngine::new();
+        let o = OrderData::new("b1", "alice", OrderSide::Buy, OrderType::Limit(100.0), 1.0, 0).signed_for_tests();
+        engine.place_order(o).unwrap();
+
+        let mut coordinator = ShutdownCoordinator::new(Duration::from_secs(5));
+        let task_db = db.clone();
+        coordinator.spawn("matching", move |token| async move {
+            token.cancelled().await;
+            // Persistieren dauert etwas => muss trotzdem abgeschlossen werden
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            engine.persist_book(&task_db).unwrap();
+        });
+
+        let report = coordinator.shutdown().await;
+        assert_eq!(report.completed, vec!["matching".to_string()]);
+        assert!(report.aborted.is_empty());
+
+        let mut restored = MatchingEngine::new();
+        assert_eq!(restored.restore_book(&db).unwrap(), 1);
+        assert_eq!(restored.order_book.buy_orders[0].order.id, "b1");
+    }
+
+    #[tokio::test]
+    async fn test_stuck_task_aborted_after_timeout() {
+        let mut coordinator = ShutdownCoordinator::new(Duration::from_millis(50));
+        coordinator.spawn("stuck", |_token| async move {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+        });
+        coordinator.spawn("polite", |token| async move {
+            token.cancelled().await;
+        });
+        let report = coordinator.shutdown().await;
+        assert_eq!(report.aborted, vec!["stuck".to_string()]);
+        assert_eq!(report.completed, vec!["polite".to_string()]);
+    }
+}
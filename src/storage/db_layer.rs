@@ -5,12 +5,13 @@
 use anyhow::{Result, anyhow};
 use rocksdb::{DB, Options, Direction, IteratorMode};
 use serde::{Serialize, de::DeserializeOwned};
-use tracing::{info, debug, warn, instrument};
+use tracing::{info, debug, warn, error, instrument};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
 use crate::error::DexError;
+use crate::storage::migrations::{dex_db_migrations, MigrationRunner, MigrationStore};
 
 #[derive(Default, Debug)]
 pub struct InMemoryDb {
@@ -38,6 +39,41 @@ impl InMemoryDb {
     }
 }
 
+/// Obergrenze für den exponentiellen Backoff beim Öffnen.
+pub const MAX_OPEN_BACKOFF_SECS: u64 = 60;
+
+/// Einordnung eines Fehlers beim Öffnen der RocksDB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenErrorKind {
+    /// Lohnt einen Retry (LOCK-Datei noch von altem Prozess gehalten, Busy, Timeout)
+    Transient,
+    /// Retry sinnlos (Korruption, fehlende Rechte, ungültige Optionen, Platte voll)
+    Fatal,
+}
+
+/// Klassifiziert anhand der RocksDB-Fehlermeldung: fatal ist nur, was eines
+/// der Fatal-Muster enthält ("Corruption: bad block …" ebenso wie ein LOCK ohne
+/// Rechte). Alles andere – Lock-Konflikte ("While lock file … Resource
+/// temporarily unavailable"), Busy, Timeouts, Unbekanntes – gilt als transient.
+pub fn classify_open_error(e: &anyhow::Error) -> OpenErrorKind {
+    let msg = format!("{:?}", e).to_ascii_lowercase();
+    const FATAL: &[&str] = &[
+        "corruption",
+        "permission denied",
+        "invalid argument",
+        "not supported",
+        "notsupported",
+        "no space left",
+        "read-only file system",
+        "not a directory",
+    ];
+    if FATAL.iter().any(|p| msg.contains(p)) {
+        OpenErrorKind::Fatal
+    } else {
+        OpenErrorKind::Transient
+    }
+}
+
 #[derive(Debug)]
 pub struct DexDB {
     pub rocks: Option<DB>,
@@ -55,38 +91,68 @@ impl DexDB {
 
         info!("DexDB: RocksDB open/created at path={}", path);
 
-        Ok(DexDB {
+        let dex_db = DexDB {
             rocks: Some(db),
             fallback_mem: None,
-        })
+        };
+        let version = MigrationRunner::new(dex_db_migrations())?.run(&dex_db)?;
+        debug!("DexDB schema version {}", version);
+        Ok(dex_db)
     }
 
+    /// Öffnet mit Retries für transiente Fehler; Backoff verdoppelt sich ab
+    /// `backoff_sec` bis MAX_OPEN_BACKOFF_SECS. Fatale Fehler und erschöpfte
+    /// Retries fallen auf die In-Memory-DB zurück.
     #[instrument(name="db_open_with_retries", skip(path, max_tries, backoff_sec))]
     pub fn open_with_retries(path: &str, max_tries: u32, backoff_sec: u64) -> Result<Self> {
+        Self::open_with_retries_using(path, max_tries, backoff_sec, Self::open)
+    }
+
+    fn open_with_retries_using<F>(path: &str, max_tries: u32, backoff_sec: u64, mut open: F) -> Result<Self>
+    where
+        F: FnMut(&str) -> Result<Self>,
+    {
         let mut attempt = 0;
         loop {
             attempt += 1;
-            match Self::open(path) {
+            match open(path) {
                 Ok(db) => {
                     return Ok(db);
                 }
                 Err(e) => {
+                    // Zu neue Schema-Version => kein Retry, kein Fallback.
+                    if let Some(DexError::SchemaVersionTooNew { .. }) = e.downcast_ref::<DexError>() {
+                        return Err(e);
+                    }
+                    if classify_open_error(&e) == OpenErrorKind::Fatal {
+                        error!(
+                            "DB open at {} failed with FATAL error, no retry => fallback to in-memory DB, data will NOT be persisted: {:?}",
+                            path, e
+                        );
+                        return Ok(Self::in_memory());
+                    }
                     warn!("DB open failed (attempt {}/{}): {:?}", attempt, max_tries, e);
                     if attempt >= max_tries {
-                        warn!("Max DB attempts reached => fallback to in-memory DB!");
-                        let mem = InMemoryDb::default();
-                        return Ok(DexDB {
-                            rocks: None,
-                            fallback_mem: Some(Arc::new(Mutex::new(mem))),
-                        });
+                        error!("Max DB attempts reached => fallback to in-memory DB!");
+                        return Ok(Self::in_memory());
                     } else {
-                        thread::sleep(Duration::from_secs(backoff_sec));
+                        let exp = (attempt - 1).min(16);
+                        let wait = backoff_sec.saturating_mul(1u64 << exp).min(MAX_OPEN_BACKOFF_SECS.max(backoff_sec));
+                        thread::sleep(Duration::from_secs(wait));
                     }
                 }
             }
         }
     }
 
+    /// Reine In-Memory-DB ohne RocksDB (Fallback, Tests, Demos).
+    pub fn in_memory() -> Self {
+        DexDB {
+            rocks: None,
+            fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))),
+        }
+    }
+
     /// Lesevorgang (generisch)
     pub fn load_struct<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, DexError> {
         if let Some(rdb) = &self.rocks {
@@ -113,6 +179,42 @@ impl DexDB {
         }
     }
 
+    /// Batch-Lesevorgang: lädt alle `keys` mit einem RocksDB-Multi-Get statt
+    /// einem `get` pro Key. Die Werte werden direkt aus den gepinnten Slices
+    /// deserialisiert, ohne Zwischenkopie. Reihenfolge entspricht `keys`.
+    pub fn load_structs_batch<T: DeserializeOwned>(&self, keys: &[String]) -> Result<Vec<Option<T>>, DexError> {
+        if let Some(rdb) = &self.rocks {
+            let cf = rdb.cf_handle("default")
+                .ok_or_else(|| DexError::DatabaseError("default CF missing".into()))?;
+            let results = rdb.batched_multi_get_cf(cf, keys.iter().map(|k| k.as_bytes()), false);
+            let mut out = Vec::with_capacity(keys.len());
+            for (key, res) in keys.iter().zip(results) {
+                match res {
+                    Ok(Some(slice)) => {
+                        let val: T = bincode::deserialize(&slice)
+                            .map_err(|e| DexError::Other(format!("deserialize error for {}: {:?}", key, e)))?;
+                        out.push(Some(val));
+                    }
+                    Ok(None) => out.push(None),
+                    Err(e) => return Err(DexError::Other(format!("rocksdb multi_get error: {:?}", e))),
+                }
+            }
+            Ok(out)
+        } else if let Some(mem) = &self.fallback_mem {
+            let lock = mem.lock().unwrap();
+            keys.iter()
+                .map(|k| match lock.get(k) {
+                    Some(bytes) => bincode::deserialize(bytes)
+                        .map(Some)
+                        .map_err(|e| DexError::Other(format!("deserialize mem error: {:?}", e))),
+                    None => Ok(None),
+                })
+                .collect()
+        } else {
+            Ok(keys.iter().map(|_| None).collect())
+        }
+    }
+
     /// Schreibvorgang (generisch)
     pub fn store_struct<T: Serialize>(&self, key: &str, val: &T) -> Result<(), DexError> {
         let encoded = bincode::serialize(val)
@@ -127,6 +229,108 @@ impl DexDB {
         Ok(())
     }
 
+    /// Schreibt bereits serialisierte Bytes (z.B. beim Restore eines Snapshots).
+    pub fn put_raw(&self, key: &str, bytes: Vec<u8>) -> Result<(), DexError> {
+        if let Some(rdb) = &self.rocks {
+            rdb.put(key.as_bytes(), bytes)
+                .map_err(|e| DexError::Other(format!("rocksdb put: {:?}", e)))?;
+        } else if let Some(mem) = &self.fallback_mem {
+            let mut lock = mem.lock().unwrap();
+            lock.put(key, bytes);
+        }
+        Ok(())
+    }
+
+    /// Schreibt mehrere Keys atomar (RocksDB-WriteBatch), z. B. Wallet + Journal-Eintrag.
+    pub fn put_raw_batch(&self, items: Vec<(String, Vec<u8>)>) -> Result<(), DexError> {
+        if let Some(rdb) = &self.rocks {
+            let mut batch = rocksdb::WriteBatch::default();
+            for (k, v) in items {
+                batch.put(k.as_bytes(), v);
+            }
+            rdb.write(batch)
+                .map_err(|e| DexError::Other(format!("rocksdb write batch: {:?}", e)))?;
+        } else if let Some(mem) = &self.fallback_mem {
+            let mut lock = mem.lock().unwrap();
+            for (k, v) in items {
+                lock.put(&k, v);
+            }
+        }
+        Ok(())
+    }
+
+    /// Entfernt einen Key (fehlender Key ist kein Fehler).
+    pub fn delete_key(&self, key: &str) -> Result<(), DexError> {
+        if let Some(rdb) = &self.rocks {
+            rdb.delete(key.as_bytes())
+                .map_err(|e| DexError::Other(format!("rocksdb delete: {:?}", e)))?;
+        } else if let Some(mem) = &self.fallback_mem {
+            let mut lock = mem.lock().unwrap();
+            lock.store.remove(key);
+        }
+        Ok(())
+    }
+
+    /// Alle Key/Value-Paare mit Prefix, nach Key sortiert.
+    pub fn list_entries_with_prefix(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, DexError> {
+        let mut out = Vec::new();
+        if let Some(rdb) = &self.rocks {
+            let mode = IteratorMode::From(prefix.as_bytes(), Direction::Forward);
+            for item in rdb.iterator(mode) {
+                let (k, v) = item.map_err(|e| DexError::Other(format!("iterator error: {:?}", e)))?;
+                if !k.starts_with(prefix.as_bytes()) {
+                    break;
+                }
+                out.push((String::from_utf8_lossy(&k).to_string(), v.to_vec()));
+            }
+        } else if let Some(mem) = &self.fallback_mem {
+            let lock = mem.lock().unwrap();
+            out = lock.list_prefix(prefix);
+            out.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        Ok(out)
+    }
+
+    /// Eine Seite von Einträgen mit Prefix, beginnend direkt nach dem Key `start_after`
+    /// (exklusiv). Liefert zusätzlich den Key des letzten Eintrags als Cursor für die
+    /// nächste Seite, falls es weitere Einträge gibt.
+    pub fn list_entries_page(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        limit: usize,
+    ) -> Result<(Vec<(String, Vec<u8>)>, Option<String>), DexError> {
+        let mut out = Vec::new();
+        let mut has_more = false;
+        if let Some(rdb) = &self.rocks {
+            let start = start_after.unwrap_or(prefix);
+            let mode = IteratorMode::From(start.as_bytes(), Direction::Forward);
+            for item in rdb.iterator(mode) {
+                let (k, v) = item.map_err(|e| DexError::Other(format!("iterator error: {:?}", e)))?;
+                if !k.starts_with(prefix.as_bytes()) {
+                    break;
+                }
+                if Some(k.as_ref()) == start_after.map(|s| s.as_bytes()) {
+                    continue;
+                }
+                if out.len() == limit {
+                    has_more = true;
+                    break;
+                }
+                out.push((String::from_utf8_lossy(&k).to_string(), v.to_vec()));
+            }
+        } else if let Some(mem) = &self.fallback_mem {
+            let lock = mem.lock().unwrap();
+            let mut all = lock.list_prefix(prefix);
+            all.sort_by(|a, b| a.0.cmp(&b.0));
+            let mut rest = all.into_iter().filter(|(k, _)| start_after.map_or(true, |s| k.as_str() > s));
+            out = rest.by_ref().take(limit).collect();
+            has_more = rest.next().is_some();
+        }
+        let cursor = if has_more { out.last().map(|(k, _)| k.clone()) } else { None };
+        Ok((out, cursor))
+    }
+
     /// Key-Liste mit Prefix
     pub fn list_keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>, DexError> {
         let mut out = Vec::new();
@@ -150,3 +354,111 @@ impl DexDB {
         Ok(out)
     }
 }
+
+/// DexDB hat keine Column Families; der CF-Name wird ignoriert.
+impl MigrationStore for DexDB {
+    fn read(&self, _cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if let Some(rdb) = &self.rocks {
+            Ok(rdb.get(key)?.map(|v| v.to_vec()))
+        } else if let Some(mem) = &self.fallback_mem {
+            let lock = mem.lock().unwrap();
+            Ok(lock.get(&String::from_utf8_lossy(key)).map(|v| v.to_vec()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn write(&self, _cf: &str, key: &[u8], val: &[u8]) -> Result<()> {
+        if let Some(rdb) = &self.rocks {
+            rdb.put(key, val)?;
+        } else if let Some(mem) = &self.fallback_mem {
+            let mut lock = mem.lock().unwrap();
+            lock.put(&String::from_utf8_lossy(key), val.to_vec());
+        }
+        Ok(())
+    }
+
+    fn remove(&self, _cf: &str, key: &[u8]) -> Result<()> {
+        if let Some(rdb) = &self.rocks {
+            rdb.delete(key)?;
+        } else if let Some(mem) = &self.fallback_mem {
+            let mut lock = mem.lock().unwrap();
+            lock.store.remove(&*String::from_utf8_lossy(key));
+        }
+        Ok(())
+    }
+
+    fn scan_prefix(&self, _cf: &str, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let entries = self
+            .list_entries_with_prefix(&String::from_utf8_lossy(prefix))
+            .map_err(|e| anyhow!("{}", e))?;
+        Ok(entries.into_iter().map(|(k, v)| (k.into_bytes(), v)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_structs_batch_matches_sequential() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let db = DexDB::open(dir.path().to_str().unwrap())?;
+        let mut keys = Vec::new();
+        for i in 0..100u64 {
+            let key = format!("wallets/w{}", i);
+            if i % 10 != 0 {
+                db.store_struct(&key, &(i, i as f64 * 0.5))?;
+            }
+            keys.push(key);
+        }
+
+        let batched: Vec<Option<(u64, f64)>> = db.load_structs_batch(&keys)?;
+        let sequential = keys
+            .iter()
+            .map(|k| db.load_struct::<(u64, f64)>(k))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(batched, sequential);
+        assert_eq!(batched.iter().filter(|v| v.is_none()).count(), 10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_classify_open_errors() {
+        let lock = anyhow!("RocksDB open error: Error {{ message: \"IO error: While lock file: /data/LOCK: Resource temporarily unavailable\" }}");
+        let corrupt = anyhow!("RocksDB open error: Error {{ message: \"Corruption: bad block contents in MANIFEST-000005\" }}");
+        let perms = anyhow!("RocksDB open error: Error {{ message: \"IO error: /data/CURRENT: Permission denied\" }}");
+        let lock_perms = anyhow!("RocksDB open error: Error {{ message: \"IO error: While lock file: /data/LOCK: Permission denied\" }}");
+        assert_eq!(classify_open_error(&lock), OpenErrorKind::Transient);
+        assert_eq!(classify_open_error(&lock_perms), OpenErrorKind::Fatal);
+        assert_eq!(classify_open_error(&corrupt), OpenErrorKind::Fatal);
+        assert_eq!(classify_open_error(&perms), OpenErrorKind::Fatal);
+    }
+
+    #[test]
+    fn test_corruption_fails_fast_to_memory_fallback() -> Result<()> {
+        let mut calls = 0;
+        let started = std::time::Instant::now();
+        // Backoff von 30s: ein Retry würde den Test sichtbar aufhalten
+        let db = DexDB::open_with_retries_using("/unused", 5, 30, |_| {
+            calls += 1;
+            Err(anyhow!("RocksDB open error: Error {{ message: \"Corruption: checksum mismatch\" }}"))
+        })?;
+        assert_eq!(calls, 1);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(db.rocks.is_none() && db.fallback_mem.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_transient_errors_exhaust_retries_then_fall_back() -> Result<()> {
+        let mut calls = 0;
+        let db = DexDB::open_with_retries_using("/unused", 3, 0, |_| {
+            calls += 1;
+            Err(anyhow!("RocksDB open error: Error {{ message: \"IO error: lock hold by current process\" }}"))
+        })?;
+        assert_eq!(calls, 3);
+        assert!(db.fallback_mem.is_some());
+        Ok(())
+    }
+}
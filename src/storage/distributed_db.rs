@@ -2,17 +2,36 @@
 /// my_DEX/src/storage/distributed_db.rs
 /////////////////////////////////////////////////////
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use rocksdb::{DB, Options};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{BufRead, Write};
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::mpsc::{self, Receiver, Sender};
-use tokio::time::{sleep, Duration};
-use tracing::{error, info};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tokio::time::{sleep, timeout, Duration};
+use tracing::{error, info, warn};
+use crate::utils::lock::LockRecover;
+
+/// Maximale Wartezeit auf ein Prepare-Ack eines Replikats.
+const PREPARE_ACK_TIMEOUT: Duration = Duration::from_secs(2);
+/// Maximale Wartezeit auf die Entscheidung des Koordinators bei der Recovery.
+const DECISION_QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+/// Abstand, in dem offene fremde Prepares erneut beim Koordinator erfragt werden.
+const DECISION_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+/// Nach so vielen abgeschlossenen Transaktionen wird das WAL kompaktiert.
+const WAL_COMPACT_EVERY: u64 = 1_000;
+/// So viele Commit-Marker bleiben nach der Kompaktierung für Entscheidungsanfragen erhalten.
+const WAL_KEEP_DECISIONS: usize = 10_000;
 
 /// Trait, das grundlegende Datenbankoperationen sowie Replikation und Synchronisation definiert.
 #[async_trait]
@@ -39,15 +58,16 @@ impl RocksDBInstance {
     }
 }
 
+#[async_trait]
 impl DistributedDB for RocksDBInstance {
     fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
-        let db = self.db.lock().unwrap();
+        let db = self.db.lock_recover();
         db.put(key, value)?;
         Ok(())
     }
 
     fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
-        let db = self.db.lock().unwrap();
+        let db = self.db.lock_recover();
         let value = db.get(key)?;
         Ok(value)
     }
@@ -73,36 +93,348 @@ impl DistributedDB for RocksDBInstance {
 }
 
 /// Repr�sentiert eine Replikationsnachricht, die �ber das Netzwerk ausgetauscht wird.
-#[derive(Serialize, Deserialize, Debug)]
+///
+/// `Put` ist die alte Fire-and-forget-Replikation. `Prepare`/`Commit`/`Abort`
+/// bilden das Two-Phase-Commit-Protokoll, auf das ein Replikat mit einem
+/// `ReplicationAck` antwortet. Mit `Decision` fragt ein Replikat nach einem
+/// Neustart beim Koordinator nach, ob eine offene Transaktion committed wurde.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ReplicationOp {
     Put { key: Vec<u8>, value: Vec<u8> },
+    Prepare { txid: String, key: Vec<u8>, value: Vec<u8> },
+    Commit { txid: String },
+    Abort { txid: String },
+    Decision { txid: String },
+}
+
+/// Antwort eines Replikats auf Prepare/Commit/Abort. Auf `Decision` bedeutet
+/// `ok == true`, dass der Koordinator einen Commit-Eintrag für die Transaktion hat;
+/// `decision` unterscheidet dann Abort von "noch nicht entschieden" (`None`).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplicationAck {
+    pub txid: String,
+    pub ok: bool,
+    #[serde(default)]
+    pub decision: Option<WalState>,
+}
+
+impl ReplicationAck {
+    fn new(txid: String, ok: bool) -> Self {
+        Self { txid, ok, decision: None }
+    }
+}
+
+/// Wie viele Knoten (inkl. lokalem Node) einen Schreibvorgang vorbereitet
+/// haben müssen, bevor er bestätigt wird.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsistencyLevel {
+    /// Nur der lokale Node.
+    One,
+    /// Mehrheit aller Knoten (lokal + Peers).
+    Quorum,
+    /// Alle Knoten.
+    All,
+}
+
+impl ConsistencyLevel {
+    /// Anzahl benötigter Acks bei `cluster_size` Knoten (lokaler Node eingeschlossen).
+    pub fn required_acks(&self, cluster_size: usize) -> usize {
+        match self {
+            ConsistencyLevel::One => 1,
+            ConsistencyLevel::Quorum => cluster_size / 2 + 1,
+            ConsistencyLevel::All => cluster_size,
+        }
+    }
+}
+
+/// Zustand eines WAL-Eintrags.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalState {
+    Prepared,
+    Committed,
+    Aborted,
+}
+
+/// Eine Zeile im Write-Ahead-Log. Prepared-Einträge tragen Key und Value,
+/// Committed/Aborted-Einträge nur die Transaktions-ID.
+///
+/// Transaktions-IDs haben die Form `<listen_addr des Koordinators>-<n>`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WalEntry {
+    pub txid: String,
+    pub state: WalState,
+    #[serde(default)]
+    pub key: Vec<u8>,
+    #[serde(default)]
+    pub value: Vec<u8>,
+}
+
+/// Append-only Write-Ahead-Log (eine JSON-Zeile pro Eintrag).
+/// Jeder Eintrag wird vor der Rückkehr per fsync auf die Platte geschrieben.
+pub struct WriteAheadLog {
+    path: PathBuf,
+    lock: Mutex<()>,
+    decided_since_compaction: AtomicU64,
+}
+
+impl WriteAheadLog {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+            decided_since_compaction: AtomicU64::new(0),
+        }
+    }
+
+    /// Hängt einen Eintrag an und synchronisiert die Datei.
+    pub fn append(&self, entry: &WalEntry) -> Result<()> {
+        let _guard = self.lock.lock_recover();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Liest alle Einträge. Eine fehlende Datei entspricht einem leeren Log,
+    /// eine abgeschnittene letzte Zeile (Crash während des Schreibens) wird ignoriert.
+    pub fn read_all(&self) -> Result<Vec<WalEntry>> {
+        let _guard = self.lock.lock_recover();
+        self.read_unlocked()
+    }
+
+    fn read_unlocked(&self) -> Result<Vec<WalEntry>> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut entries = Vec::new();
+        for line in std::io::BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<WalEntry>(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!("Skipping unreadable WAL line in {:?}: {:?}", self.path, e),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Liefert alle Prepared-Einträge ohne abschließendes Commit/Abort,
+    /// in der Reihenfolge, in der sie vorbereitet wurden.
+    pub fn pending(&self) -> Result<Vec<WalEntry>> {
+        let mut order = Vec::new();
+        let mut open: HashMap<String, WalEntry> = HashMap::new();
+        for entry in self.read_all()? {
+            match entry.state {
+                WalState::Prepared => {
+                    order.push(entry.txid.clone());
+                    open.insert(entry.txid.clone(), entry);
+                }
+                WalState::Committed | WalState::Aborted => {
+                    open.remove(&entry.txid);
+                }
+            }
+        }
+        Ok(order.into_iter().filter_map(|txid| open.remove(&txid)).collect())
+    }
+
+    /// Sucht einen offenen Prepared-Eintrag anhand seiner Transaktions-ID.
+    pub fn find_pending(&self, txid: &str) -> Result<Option<WalEntry>> {
+        Ok(self.pending()?.into_iter().find(|e| e.txid == txid))
+    }
+
+    /// Liefert die im WAL protokollierte Entscheidung für `txid`, falls vorhanden.
+    pub fn decision(&self, txid: &str) -> Result<Option<WalState>> {
+        Ok(self
+            .read_all()?
+            .into_iter()
+            .filter(|e| e.txid == txid && e.state != WalState::Prepared)
+            .map(|e| e.state)
+            .last())
+    }
+
+    /// Committed-Transaktionen, deren Prepared-Eintrag (Key/Value) noch im WAL
+    /// steht, in Commit-Reihenfolge. Sie werden bei der Recovery erneut angewendet,
+    /// falls der Node zwischen Commit-Eintrag und DB-Schreibvorgang abgestürzt ist.
+    pub fn committed_since_compaction(&self) -> Result<Vec<WalEntry>> {
+        let mut prepared: HashMap<String, WalEntry> = HashMap::new();
+        let mut committed = Vec::new();
+        for entry in self.read_all()? {
+            match entry.state {
+                WalState::Prepared => {
+                    prepared.insert(entry.txid.clone(), entry);
+                }
+                WalState::Committed => {
+                    if let Some(p) = prepared.remove(&entry.txid) {
+                        committed.push(p);
+                    }
+                }
+                WalState::Aborted => {
+                    prepared.remove(&entry.txid);
+                }
+            }
+        }
+        Ok(committed)
+    }
+
+    /// Schreibt das WAL neu: offene Prepared-Einträge bleiben vollständig erhalten,
+    /// von abgeschlossenen Transaktionen bleiben nur die letzten
+    /// `WAL_KEEP_DECISIONS` Commit-Marker (ohne Key/Value) für Entscheidungsanfragen.
+    /// Die neue Datei wird erst nach fsync per Rename übernommen.
+    pub fn compact(&self) -> Result<()> {
+        let _guard = self.lock.lock_recover();
+        let entries = self.read_unlocked()?;
+        let mut decided: HashMap<String, WalState> = HashMap::new();
+        for e in &entries {
+            if e.state != WalState::Prepared {
+                decided.insert(e.txid.clone(), e.state);
+            }
+        }
+        let mut kept: Vec<WalEntry> = Vec::new();
+        let mut markers: Vec<WalEntry> = Vec::new();
+        for e in entries {
+            match (e.state, decided.get(&e.txid)) {
+                (WalState::Prepared, None) => kept.push(e),
+                (WalState::Committed, Some(WalState::Committed)) => markers.push(WalEntry {
+                    txid: e.txid,
+                    state: WalState::Committed,
+                    key: Vec::new(),
+                    value: Vec::new(),
+                }),
+                _ => {}
+            }
+        }
+        let skip = markers.len().saturating_sub(WAL_KEEP_DECISIONS);
+        let tmp = self.path.with_extension("compact");
+        {
+            let mut file = std::fs::File::create(&tmp)?;
+            for e in markers.into_iter().skip(skip).chain(kept) {
+                let mut line = serde_json::to_string(&e)?;
+                line.push('\n');
+                file.write_all(line.as_bytes())?;
+            }
+            file.sync_data()?;
+        }
+        std::fs::rename(&tmp, &self.path)?;
+        self.decided_since_compaction.store(0, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Zählt eine abgeschlossene Transaktion und kompaktiert alle `WAL_COMPACT_EVERY`.
+    /// Bei Commits erst nach dem DB-Schreibvorgang aufrufen: die Kompaktierung
+    /// verwirft Key/Value des Prepared-Eintrags.
+    fn note_decided(&self) {
+        if self.decided_since_compaction.fetch_add(1, Ordering::SeqCst) + 1 >= WAL_COMPACT_EVERY {
+            if let Err(e) = self.compact() {
+                warn!("WAL compaction of {:?} failed: {:?}", self.path, e);
+            }
+        }
+    }
+
+    pub fn log_prepared(&self, txid: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        self.append(&WalEntry {
+            txid: txid.to_string(),
+            state: WalState::Prepared,
+            key: key.to_vec(),
+            value: value.to_vec(),
+        })
+    }
+
+    /// Protokolliert die Commit-Entscheidung. Kompaktiert wird erst über
+    /// `note_decided`, nachdem der Wert in der DB steht.
+    pub fn log_committed(&self, txid: &str) -> Result<()> {
+        self.append(&WalEntry {
+            txid: txid.to_string(),
+            state: WalState::Committed,
+            key: Vec::new(),
+            value: Vec::new(),
+        })
+    }
+
+    pub fn log_aborted(&self, txid: &str) -> Result<()> {
+        self.append(&WalEntry {
+            txid: txid.to_string(),
+            state: WalState::Aborted,
+            key: Vec::new(),
+            value: Vec::new(),
+        })?;
+        self.note_decided();
+        Ok(())
+    }
+}
+
+/// Koordinator-Adresse aus einer Transaktions-ID (`<addr>-<n>`).
+fn coordinator_of(txid: &str) -> Option<&str> {
+    txid.rsplit_once('-').map(|(addr, _)| addr)
 }
 
 /// DistributedDexDB verwaltet die lokale DB?Instanz, sendet Schreibvorg�nge an Peers
 /// und bietet einen Synchronisationsmechanismus bei Recovery.
 pub struct DistributedDexDB {
-    pub local_db: Box<dyn DistributedDB>,
+    pub local_db: Arc<dyn DistributedDB>,
     pub peers: Vec<String>,
     // Sender, um eingehende Replikationsbefehle an den lokalen Server weiterzuleiten
     pub replication_sender: Sender<ReplicationOp>,
     pub replication_receiver: Receiver<ReplicationOp>,
     /// Die TCP-Adresse, unter der dieser Node Replikationsbefehle empf�ngt.
     pub listen_addr: SocketAddr,
+    /// Write-Ahead-Log für Two-Phase-Commit (Koordinator- und Replikat-Seite).
+    pub wal: Arc<WriteAheadLog>,
+    tx_counter: AtomicU64,
 }
 
 impl DistributedDexDB {
+    /// Legt das WAL unter `replication_wal_<port>.log` im Arbeitsverzeichnis an.
     pub fn new(
         local_db: Box<dyn DistributedDB>,
         peers: Vec<String>,
         listen_addr: SocketAddr,
+    ) -> Self {
+        let wal_path = format!("replication_wal_{}.log", listen_addr.port());
+        Self::with_wal(local_db, peers, listen_addr, wal_path)
+    }
+
+    /// Wie `new`, aber mit explizitem Pfad für das Write-Ahead-Log.
+    pub fn with_wal<P: Into<PathBuf>>(
+        local_db: Box<dyn DistributedDB>,
+        peers: Vec<String>,
+        listen_addr: SocketAddr,
+        wal_path: P,
     ) -> Self {
         let (tx, rx) = mpsc::channel(100);
+        let wal = WriteAheadLog::new(wal_path);
+        // Nach einer Kompaktierung sagt die Länge des WAL nichts mehr über vergebene
+        // IDs aus; der Zähler startet daher mindestens bei der aktuellen Zeit in µs.
+        let prefix = format!("{}-", listen_addr);
+        let highest = wal
+            .read_all()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|e| e.txid.strip_prefix(&prefix).and_then(|n| n.parse::<u64>().ok()))
+            .max()
+            .map(|n| n + 1)
+            .unwrap_or(0);
+        let now_micros = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or(0);
+        let next_tx = highest.max(now_micros);
         Self {
-            local_db,
+            local_db: Arc::from(local_db),
             peers,
             replication_sender: tx,
             replication_receiver: rx,
             listen_addr,
+            wal: Arc::new(wal),
+            tx_counter: AtomicU64::new(next_tx),
         }
     }
 
@@ -132,6 +464,108 @@ impl DistributedDexDB {
         Ok(())
     }
 
+    /// Schreibt per Two-Phase-Commit: Prepare lokal (WAL) und auf allen Peers,
+    /// bei ausreichend Acks gemäß `consistency_level` lokal anwenden und
+    /// Commit an die Peers senden. Erst danach gilt der Schreibvorgang als bestätigt.
+    /// Wird das geforderte Level nicht erreicht, wird die Transaktion abgebrochen.
+    pub async fn put_with_consistency(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        consistency_level: ConsistencyLevel,
+    ) -> Result<()> {
+        let txid = self.next_txid();
+        let required = consistency_level.required_acks(self.peers.len() + 1);
+
+        // Phase 1: Prepare
+        self.wal.log_prepared(&txid, key, value)?;
+        let prepare = ReplicationOp::Prepare {
+            txid: txid.clone(),
+            key: key.to_vec(),
+            value: value.to_vec(),
+        };
+        let mut handles = Vec::new();
+        for peer in self.peers.clone() {
+            let msg = prepare.clone();
+            handles.push(tokio::spawn(async move {
+                let ok = matches!(
+                    timeout(PREPARE_ACK_TIMEOUT, send_with_ack(&peer, &msg)).await,
+                    Ok(Ok(true))
+                );
+                (peer, ok)
+            }));
+        }
+        let mut prepared_peers = Vec::new();
+        for h in handles {
+            if let Ok((peer, true)) = h.await {
+                prepared_peers.push(peer);
+            }
+        }
+        let acks = prepared_peers.len() + 1;
+
+        if acks < required {
+            warn!(
+                "Tx {} aborted: {} of {} required acks ({:?})",
+                txid, acks, required, consistency_level
+            );
+            self.wal.log_aborted(&txid)?;
+            let abort = ReplicationOp::Abort { txid: txid.clone() };
+            for peer in &prepared_peers {
+                if let Err(e) = send_with_ack(peer, &abort).await {
+                    error!("Failed to send abort to {}: {:?}", peer, e);
+                }
+            }
+            return Err(anyhow!(
+                "Consistency level {:?} not reached: {} of {} acks",
+                consistency_level, acks, required
+            ));
+        }
+
+        // Phase 2: Commit. Die Entscheidung steht im WAL, bevor sie angewendet wird.
+        self.wal.log_committed(&txid)?;
+        self.local_db.put(key, value)?;
+        self.wal.note_decided();
+        let commit = ReplicationOp::Commit { txid: txid.clone() };
+        for peer in &prepared_peers {
+            // Ein verpasstes Commit holt das Replikat bei seiner Recovery per
+            // `Decision`-Anfrage nach.
+            if let Err(e) = send_with_ack(peer, &commit).await {
+                warn!("Commit for tx {} not delivered to {}: {:?}", txid, peer, e);
+            }
+        }
+        info!("Tx {} committed with {} acks ({:?})", txid, acks, consistency_level);
+        Ok(())
+    }
+
+    /// Stellt den WAL-Zustand nach einem Neustart her und gibt die Anzahl der
+    /// angewendeten Transaktionen zurück:
+    /// - Committed-Transaktionen seit der letzten Kompaktierung werden erneut angewendet.
+    /// - Offene Prepares eigener Transaktionen gelten als abgebrochen (es gab keine
+    ///   Commit-Entscheidung).
+    /// - Offene Prepares fremder Transaktionen folgen der Entscheidung des
+    ///   Koordinators. Ist er nicht erreichbar oder noch unentschieden, bleiben
+    ///   sie offen (ein Abort könnte einem bereits bestätigten Commit
+    ///   widersprechen); der Replikationsserver fragt sie periodisch nach
+    ///   (`resolve_in_doubt`).
+    ///
+    /// Danach wird das WAL kompaktiert.
+    pub async fn recover(&self) -> Result<usize> {
+        let own = self.listen_addr.to_string();
+        recover_from_wal(&self.wal, self.local_db.as_ref(), &own).await
+    }
+
+    /// Fragt offene fremde Prepares beim jeweiligen Koordinator nach und
+    /// wendet bestätigte Commits an. Gibt die Anzahl angewendeter zurück.
+    pub async fn resolve_in_doubt(&self) -> Result<usize> {
+        let own = self.listen_addr.to_string();
+        resolve_foreign_prepares(&self.wal, self.local_db.as_ref(), &own).await
+    }
+
+    fn next_txid(&self) -> String {
+        let n = self.tx_counter.fetch_add(1, Ordering::SeqCst);
+        format!("{}-{}", self.listen_addr, n)
+    }
+
     pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
         self.local_db.get(key)
     }
@@ -142,18 +576,68 @@ impl DistributedDexDB {
     }
 
     /// Startet den Replikationsserver, der �ber TCP eingehende Replikationsbefehle empf�ngt.
-    pub async fn start_replication_server(&self) -> Result<()> {
+    /// Vor dem Binden werden offene WAL-Einträge wiederhergestellt.
+    pub async fn start_replication_server(&self) -> Result<JoinHandle<()>> {
+        self.start_replication_server_until(CancellationToken::new()).await
+    }
+
+    /// Wie `start_replication_server`, nimmt aber nach `token.cancel()` keine
+    /// neuen Verbindungen mehr an. Laufende Verbindungen werden noch abgearbeitet.
+    pub async fn start_replication_server_until(&self, token: CancellationToken) -> Result<JoinHandle<()>> {
         let listener = TcpListener::bind(self.listen_addr).await?;
-        info!("Replication server listening on {}", self.listen_addr);
+        self.start_replication_server_on(listener, token).await
+    }
+
+    /// Wie `start_replication_server_until`, aber auf einem bereits gebundenen Listener.
+    pub async fn start_replication_server_on(
+        &self,
+        listener: TcpListener,
+        token: CancellationToken,
+    ) -> Result<JoinHandle<()>> {
+        let recovered = self.recover().await?;
+        if recovered > 0 {
+            info!("Recovered {} committed WAL entries", recovered);
+        }
+        info!("Replication server listening on {}", listener.local_addr()?);
+        let own = self.listen_addr.to_string();
+        if has_foreign_pending(&self.wal, &own)? {
+            // Koordinator war bei der Recovery nicht erreichbar => nachfragen, bis entschieden
+            let (wal, db, token) = (self.wal.clone(), self.local_db.clone(), token.clone());
+            tokio::spawn(async move {
+                loop {
+                    tokio::select! {
+                        _ = token.cancelled() => break,
+                        _ = sleep(DECISION_RETRY_INTERVAL) => {}
+                    }
+                    if let Err(e) = resolve_foreign_prepares(&wal, db.as_ref(), &own).await {
+                        warn!("Resolving in-doubt WAL entries failed: {:?}", e);
+                    }
+                    match has_foreign_pending(&wal, &own) {
+                        Ok(false) => break,
+                        Ok(true) => {}
+                        Err(e) => warn!("Reading WAL failed: {:?}", e),
+                    }
+                }
+            });
+        }
         let local_db = self.local_db.clone();
-        tokio::spawn(async move {
+        let wal = self.wal.clone();
+        let handle = tokio::spawn(async move {
             loop {
-                match listener.accept().await {
+                let accepted = tokio::select! {
+                    _ = token.cancelled() => {
+                        info!("Replication server => Shutdown, keine neuen Verbindungen");
+                        break;
+                    }
+                    res = listener.accept() => res,
+                };
+                match accepted {
                     Ok((socket, addr)) => {
                         info!("Received replication connection from {}", addr);
                         let db_clone = local_db.clone();
+                        let wal_clone = wal.clone();
                         tokio::spawn(async move {
-                            if let Err(e) = handle_replication_connection(socket, db_clone).await {
+                            if let Err(e) = handle_replication_connection(socket, db_clone, wal_clone).await {
                                 error!("Error handling replication connection: {:?}", e);
                             }
                         });
@@ -164,7 +648,79 @@ impl DistributedDexDB {
                 }
             }
         });
-        Ok(())
+        Ok(handle)
+    }
+}
+
+/// Siehe `DistributedDexDB::recover`.
+async fn recover_from_wal(wal: &WriteAheadLog, db: &dyn DistributedDB, own_addr: &str) -> Result<usize> {
+    let mut applied = 0;
+    for entry in wal.committed_since_compaction()? {
+        db.put(&entry.key, &entry.value)?;
+        applied += 1;
+    }
+    // Eigene Prepares ohne Entscheidung wurden nie bestätigt (Presumed Abort)
+    for entry in wal.pending()? {
+        if coordinator_of(&entry.txid).unwrap_or(own_addr) == own_addr {
+            info!("Aborting undecided own WAL tx {}", entry.txid);
+            wal.log_aborted(&entry.txid)?;
+        }
+    }
+    applied += resolve_foreign_prepares(wal, db, own_addr).await?;
+    wal.compact()?;
+    Ok(applied)
+}
+
+fn has_foreign_pending(wal: &WriteAheadLog, own_addr: &str) -> Result<bool> {
+    Ok(wal
+        .pending()?
+        .iter()
+        .any(|e| coordinator_of(&e.txid).is_some_and(|c| c != own_addr)))
+}
+
+/// Siehe `DistributedDexDB::resolve_in_doubt`. Eigene Transaktionen bleiben
+/// unberührt (sie können gerade im Flug sein).
+async fn resolve_foreign_prepares(wal: &WriteAheadLog, db: &dyn DistributedDB, own_addr: &str) -> Result<usize> {
+    let mut applied = 0;
+    for entry in wal.pending()? {
+        let coordinator = match coordinator_of(&entry.txid) {
+            Some(c) if c != own_addr => c,
+            _ => continue,
+        };
+        match query_decision(coordinator, &entry.txid).await {
+            Some(WalState::Committed) => {
+                info!("Applying WAL tx {} confirmed by {}", entry.txid, coordinator);
+                // Erst schreiben, dann protokollieren: ein Crash dazwischen wiederholt nur den Put
+                db.put(&entry.key, &entry.value)?;
+                wal.log_committed(&entry.txid)?;
+                wal.note_decided();
+                applied += 1;
+            }
+            Some(_) => {
+                info!("WAL tx {} aborted by {}", entry.txid, coordinator);
+                wal.log_aborted(&entry.txid)?;
+            }
+            None => warn!("WAL tx {} stays in doubt, no decision from {}", entry.txid, coordinator),
+        }
+    }
+    Ok(applied)
+}
+
+/// Entscheidung des Koordinators; `None`, wenn er nicht antwortet oder noch
+/// nicht entschieden hat.
+async fn query_decision(coordinator: &str, txid: &str) -> Option<WalState> {
+    let query = ReplicationOp::Decision { txid: txid.to_string() };
+    match timeout(DECISION_QUERY_TIMEOUT, request_ack(coordinator, &query)).await {
+        Ok(Ok(ack)) if ack.ok => Some(WalState::Committed),
+        Ok(Ok(ack)) => ack.decision,
+        Ok(Err(e)) => {
+            warn!("Decision for tx {} unavailable from {}: {:?}", txid, coordinator, e);
+            None
+        }
+        Err(_) => {
+            warn!("Decision query for tx {} to {} timed out", txid, coordinator);
+            None
+        }
     }
 }
 
@@ -172,24 +728,86 @@ impl DistributedDexDB {
 async fn send_replication_message(peer_addr: &str, msg: &ReplicationOp) -> Result<()> {
     let addr: SocketAddr = peer_addr.parse()?;
     let mut stream = TcpStream::connect(addr).await?;
-    let serialized = serde_json::to_string(msg)?;
+    let mut serialized = serde_json::to_string(msg)?;
+    serialized.push('\n');
     stream.write_all(serialized.as_bytes()).await?;
     stream.flush().await?;
     info!("Sent replication message to {}", peer_addr);
     Ok(())
 }
 
+/// Sendet eine 2PC-Nachricht und wartet auf das `ReplicationAck` des Peers.
+async fn send_with_ack(peer_addr: &str, msg: &ReplicationOp) -> Result<bool> {
+    Ok(request_ack(peer_addr, msg).await?.ok)
+}
+
+async fn request_ack(peer_addr: &str, msg: &ReplicationOp) -> Result<ReplicationAck> {
+    let addr: SocketAddr = peer_addr.parse()?;
+    let mut stream = TcpStream::connect(addr).await?;
+    let mut serialized = serde_json::to_string(msg)?;
+    serialized.push('\n');
+    stream.write_all(serialized.as_bytes()).await?;
+    stream.flush().await?;
+    let mut lines = BufReader::new(stream).lines();
+    match lines.next_line().await? {
+        Some(line) => Ok(serde_json::from_str(&line)?),
+        None => Err(anyhow!("Peer {} closed connection without ack", peer_addr)),
+    }
+}
+
 /// Behandelt eine eingehende Replikationsverbindung.
-async fn handle_replication_connection(mut stream: TcpStream, db: Box<dyn DistributedDB>) -> Result<()> {
-    let reader = BufReader::new(&mut stream);
-    let mut lines = reader.lines();
+async fn handle_replication_connection(
+    stream: TcpStream,
+    db: Arc<dyn DistributedDB>,
+    wal: Arc<WriteAheadLog>,
+) -> Result<()> {
+    let (read_half, mut write_half) = stream.into_split();
+    let mut lines = BufReader::new(read_half).lines();
     while let Some(line) = lines.next_line().await? {
         let op: ReplicationOp = serde_json::from_str(&line)?;
-        match op {
+        let ack = match op {
             ReplicationOp::Put { key, value } => {
                 info!("Applying replicated put for key: {:?}", key);
                 db.put(&key, &value)?;
+                None
+            }
+            ReplicationOp::Prepare { txid, key, value } => {
+                let ok = wal.log_prepared(&txid, &key, &value).is_ok();
+                Some(ReplicationAck::new(txid, ok))
+            }
+            ReplicationOp::Commit { txid } => {
+                let ok = match wal.find_pending(&txid)? {
+                    Some(entry) => {
+                        db.put(&entry.key, &entry.value)?;
+                        wal.log_committed(&txid)?;
+                        wal.note_decided();
+                        true
+                    }
+                    None => false,
+                };
+                Some(ReplicationAck::new(txid, ok))
             }
+            ReplicationOp::Abort { txid } => {
+                let ok = wal.log_aborted(&txid).is_ok();
+                Some(ReplicationAck::new(txid, ok))
+            }
+            ReplicationOp::Decision { txid } => {
+                let decision = match wal.decision(&txid)? {
+                    Some(state) => Some(state),
+                    // Noch vorbereitet => der Koordinator entscheidet gerade
+                    None if wal.find_pending(&txid)?.is_some() => None,
+                    // Unbekannt (bzw. wegkompaktierter Abort) => nie committed
+                    None => Some(WalState::Aborted),
+                };
+                let ok = decision == Some(WalState::Committed);
+                Some(ReplicationAck { txid, ok, decision })
+            }
+        };
+        if let Some(ack) = ack {
+            let mut out = serde_json::to_string(&ack)?;
+            out.push('\n');
+            write_half.write_all(out.as_bytes()).await?;
+            write_half.flush().await?;
         }
     }
     Ok(())
@@ -200,21 +818,150 @@ mod tests {
     use super::*;
     use std::net::SocketAddr;
 
+    async fn listener() -> Result<(TcpListener, SocketAddr)> {
+        let l = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = l.local_addr()?;
+        Ok((l, addr))
+    }
+
+    fn node(dir: &std::path::Path, addr: SocketAddr, peers: Vec<String>) -> Result<DistributedDexDB> {
+        let db = RocksDBInstance::new(dir.join("db").to_str().unwrap())?;
+        Ok(DistributedDexDB::with_wal(Box::new(db), peers, addr, dir.join("wal.log")))
+    }
+
     #[tokio::test]
     async fn test_distributed_db_put_get() -> Result<()> {
-        // Erstelle eine lokale RocksDB-Instanz in einem tempor�ren Verzeichnis.
         let tmp_dir = tempfile::tempdir()?;
-        let db_instance = RocksDBInstance::new(tmp_dir.path().to_str().unwrap())?;
-        let local_db: Box<dyn DistributedDB> = Box::new(db_instance);
-        let listen_addr: SocketAddr = "127.0.0.1:4000".parse()?;
-        let distributed_db = DistributedDexDB::new(local_db, vec!["127.0.0.1:4001".to_string()], listen_addr);
+        let (l, addr) = listener().await?;
+        let (_, peer) = listener().await?;
+        let distributed_db = node(tmp_dir.path(), addr, vec![peer.to_string()])?;
         // Starte den Replikationsserver
-        distributed_db.start_replication_server().await?;
-        // F�hre einen put aus
+        distributed_db.start_replication_server_on(l, CancellationToken::new()).await?;
         distributed_db.put(b"key1", b"value1")?;
-        // �berpr�fe den get-Aufruf
         let value = distributed_db.get(b"key1")?;
         assert_eq!(value, Some(b"value1".to_vec()));
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_quorum_write_survives_replica_kill() -> Result<()> {
+        let dir_a = tempfile::tempdir()?;
+        let dir_b = tempfile::tempdir()?;
+        let dir_c = tempfile::tempdir()?;
+        let (l_b, addr_b) = listener().await?;
+        let (l_c, addr_c) = listener().await?;
+        let (_, addr_a) = listener().await?;
+        let replica_b = node(dir_b.path(), addr_b, vec![])?;
+        let replica_c = node(dir_c.path(), addr_c, vec![])?;
+        let handle_b = replica_b.start_replication_server_on(l_b, CancellationToken::new()).await?;
+        let _handle_c = replica_c.start_replication_server_on(l_c, CancellationToken::new()).await?;
+
+        let coordinator = node(dir_a.path(), addr_a, vec![addr_b.to_string(), addr_c.to_string()])?;
+        coordinator
+            .put_with_consistency(b"acked", b"v1", ConsistencyLevel::Quorum)
+            .await?;
+
+        // Replikat B "stirbt" nach dem Ack.
+        handle_b.abort();
+        drop(replica_b);
+
+        assert_eq!(coordinator.get(b"acked")?, Some(b"v1".to_vec()));
+        assert_eq!(replica_c.get(b"acked")?, Some(b"v1".to_vec()));
+
+        // Mit nur noch 2 von 3 Knoten ist Quorum weiterhin erreichbar, All nicht.
+        coordinator
+            .put_with_consistency(b"second", b"v2", ConsistencyLevel::Quorum)
+            .await?;
+        assert!(coordinator
+            .put_with_consistency(b"third", b"v3", ConsistencyLevel::All)
+            .await
+            .is_err());
+        assert_eq!(coordinator.get(b"third")?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_recovery_aborts_undecided_own_prepares() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (_, addr) = listener().await?;
+        {
+            // tx-1: Commit-Entscheidung protokolliert, Crash vor dem DB-Schreibvorgang.
+            // tx-2: Prepare ohne Entscheidung => darf nicht angewendet werden.
+            let wal = WriteAheadLog::new(dir.path().join("wal.log"));
+            wal.log_prepared(&format!("{}-1", addr), b"k1", b"v1")?;
+            wal.log_committed(&format!("{}-1", addr))?;
+            wal.log_prepared(&format!("{}-2", addr), b"k2", b"v2")?;
+        }
+        let restarted = node(dir.path(), addr, vec![])?;
+        assert_eq!(restarted.recover().await?, 1);
+        assert_eq!(restarted.get(b"k1")?, Some(b"v1".to_vec()));
+        assert_eq!(restarted.get(b"k2")?, None);
+        // Kompaktiert: keine Payloads mehr, nur der Commit-Marker von tx-1.
+        let entries = restarted.wal.read_all()?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].state, WalState::Committed);
+        assert!(entries[0].value.is_empty());
+        assert_eq!(restarted.recover().await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replica_recovery_follows_coordinator_decision() -> Result<()> {
+        let dir_coord = tempfile::tempdir()?;
+        let dir_replica = tempfile::tempdir()?;
+        let (l_coord, addr_coord) = listener().await?;
+        let (_, addr_replica) = listener().await?;
+        let committed_tx = format!("{}-7", addr_coord);
+        let aborted_tx = format!("{}-8", addr_coord);
+
+        let coordinator = node(dir_coord.path(), addr_coord, vec![])?;
+        coordinator.wal.log_prepared(&committed_tx, b"k7", b"v7")?;
+        coordinator.wal.log_committed(&committed_tx)?;
+        coordinator.wal.log_prepared(&aborted_tx, b"k8", b"v8")?;
+        coordinator.wal.log_aborted(&aborted_tx)?;
+        let _h = coordinator.start_replication_server_on(l_coord, CancellationToken::new()).await?;
+
+        {
+            // Replikat hat beide Prepares, aber keine Entscheidung erhalten
+            // (Abort wurde nur an Peers mit Ack geschickt).
+            let wal = WriteAheadLog::new(dir_replica.path().join("wal.log"));
+            wal.log_prepared(&committed_tx, b"k7", b"v7")?;
+            wal.log_prepared(&aborted_tx, b"k8", b"v8")?;
+        }
+        let replica = node(dir_replica.path(), addr_replica, vec![])?;
+        assert_eq!(replica.recover().await?, 1);
+        assert_eq!(replica.get(b"k7")?, Some(b"v7".to_vec()));
+        assert_eq!(replica.get(b"k8")?, None);
+        assert!(replica.wal.pending()?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_coordinator_leaves_prepare_in_doubt() -> Result<()> {
+        let dir_coord = tempfile::tempdir()?;
+        let dir_replica = tempfile::tempdir()?;
+        let (l_coord, addr_coord) = listener().await?;
+        let (_, addr_replica) = listener().await?;
+        let txid = format!("{}-9", addr_coord);
+        let coordinator = node(dir_coord.path(), addr_coord, vec![])?;
+        coordinator.wal.log_prepared(&txid, b"k9", b"v9")?;
+        coordinator.wal.log_committed(&txid)?;
+        {
+            let wal = WriteAheadLog::new(dir_replica.path().join("wal.log"));
+            wal.log_prepared(&txid, b"k9", b"v9")?;
+        }
+
+        // Koordinator läuft noch nicht => kein Abort, der Prepare bleibt offen
+        let replica = node(dir_replica.path(), addr_replica, vec![])?;
+        assert_eq!(replica.recover().await?, 0);
+        assert_eq!(replica.get(b"k9")?, None);
+        assert_eq!(replica.wal.pending()?.len(), 1);
+        assert_eq!(replica.wal.decision(&txid)?, None);
+
+        let _h = coordinator.start_replication_server_on(l_coord, CancellationToken::new()).await?;
+        assert_eq!(replica.resolve_in_doubt().await?, 1);
+        assert_eq!(replica.get(b"k9")?, Some(b"v9".to_vec()));
+        assert!(replica.wal.pending()?.is_empty());
+        Ok(())
+    }
 }
@@ -0,0 +1,277 @@
+////////////////////////////////////////////////////
+/// my_DEX/src/storage/ipfs_fallback.rs
+////////////////////////////////////////////////////
+//
+// Lokaler Fallback, wenn der IPFS-Daemon nicht erreichbar ist.
+//
+// `put` versucht zuerst IPFS. Schlägt das fehl, landet der Inhalt in einer
+// Upload-Queue auf der lokalen Platte (`<dir>/pending/<name>`), statt verloren
+// zu gehen. `flush` schiebt die Queue nach IPFS, sobald der Daemon wieder da
+// ist, und merkt sich name => CID in `<dir>/index.json`.
+//
+// `get` liest zuerst die lokale Queue (neuester, noch nicht hochgeladener
+// Stand), danach per Index-CID bzw. direkt per Hash aus IPFS.
+//
+// Die Erreichbarkeit steht als `dex_ipfs_available` / `dex_ipfs_pending_uploads`
+// in den Metriken und wird von /readyz gemeldet.
+////////////////////////////////////////////////////
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::metrics::{IPFS_AVAILABLE, IPFS_PENDING_UPLOADS};
+use crate::storage::ipfs_storage::{IpfsSnapshotStore, SnapshotStore};
+
+/// Standard-Verzeichnis neben der DB.
+pub const DEFAULT_FALLBACK_DIR: &str = "ipfs_fallback";
+
+/// Wo ein Inhalt gelandet ist.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoredRef {
+    Ipfs(String),
+    /// Lokal eingereiht, Upload folgt
+    Queued(PathBuf),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FlushReport {
+    pub uploaded: Vec<(String, String)>,
+    pub remaining: usize,
+}
+
+pub struct IpfsFallbackStore {
+    dir: PathBuf,
+    remote: Arc<dyn SnapshotStore>,
+}
+
+fn safe_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect()
+}
+
+impl IpfsFallbackStore {
+    pub fn new(dir: impl Into<PathBuf>, remote: Arc<dyn SnapshotStore>) -> Self {
+        Self { dir: dir.into(), remote }
+    }
+
+    /// Lokaler Daemon + `DEFAULT_FALLBACK_DIR`.
+    pub fn default_store() -> Self {
+        Self::new(DEFAULT_FALLBACK_DIR, Arc::new(IpfsSnapshotStore))
+    }
+
+    fn pending_dir(&self) -> PathBuf {
+        self.dir.join("pending")
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join("index.json")
+    }
+
+    fn load_index(&self) -> BTreeMap<String, String> {
+        fs::read(self.index_path())
+            .ok()
+            .and_then(|b| serde_json::from_slice(&b).ok())
+            .unwrap_or_default()
+    }
+
+    fn store_index(&self, index: &BTreeMap<String, String>) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let tmp = self.dir.join("index.json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(index)?)?;
+        fs::rename(&tmp, self.index_path())?;
+        Ok(())
+    }
+
+    /// Logische Namen der noch nicht hochgeladenen Inhalte (sortiert).
+    pub fn pending(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(self.pending_dir())
+            .map(|rd| rd.filter_map(|e| e.ok()).filter_map(|e| e.file_name().into_string().ok()).collect())
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    fn set_available(&self, up: bool) {
+        IPFS_AVAILABLE.set(up as i64);
+        IPFS_PENDING_UPLOADS.set(self.pending().len() as i64);
+    }
+
+    /// Speichert `data` unter `name`: IPFS, sonst lokale Queue.
+    pub async fn put(&self, name: &str, data: Vec<u8>) -> Result<StoredRef> {
+        let name = safe_name(name);
+        match self.remote.put_block(data.clone()).await {
+            Ok(cid) => {
+                let mut index = self.load_index();
+                index.insert(name.clone(), cid.clone());
+                self.store_index(&index)?;
+                // Ältere, noch eingereihte Version ist damit überholt
+                let _ = fs::remove_file(self.pending_dir().join(&name));
+                self.set_available(true);
+                Ok(StoredRef::Ipfs(cid))
+            }
+            Err(e) => {
+                warn!("IPFS nicht erreichbar ({}) => {} lokal eingereiht", e, name);
+                fs::create_dir_all(self.pending_dir())?;
+                let path = self.pending_dir().join(&name);
+                fs::write(&path, &data).with_context(|| format!("write {:?}", path))?;
+                self.set_available(false);
+                Ok(StoredRef::Queued(path))
+            }
+        }
+    }
+
+    /// Wie `put`, liest den Inhalt aus einer Datei.
+    pub async fn put_file(&self, path: impl AsRef<Path>) -> Result<StoredRef> {
+        let path = path.as_ref();
+        let data = fs::read(path).with_context(|| format!("read {:?}", path))?;
+        self.put(&path.to_string_lossy(), data).await
+    }
+
+    /// Nur die lokale Queue (kein Netzwerkzugriff).
+    pub fn get_queued(&self, name: &str) -> Option<Vec<u8>> {
+        fs::read(self.pending_dir().join(safe_name(name))).ok()
+    }
+
+    /// Lokale Queue zuerst, dann IPFS (per Index-CID oder `name` als Hash).
+    pub async fn get(&self, name: &str) -> Result<Vec<u8>> {
+        if let Some(data) = self.get_queued(name) {
+            return Ok(data);
+        }
+        let safe = safe_name(name);
+        let cid = self.load_index().get(&safe).cloned().unwrap_or_else(|| name.to_string());
+        match self.remote.get_block(&cid).await {
+            Ok(data) => {
+                self.set_available(true);
+                Ok(data)
+            }
+            Err(e) => {
+                self.set_available(false);
+                Err(e.context(format!("{} weder lokal eingereiht noch aus IPFS lesbar", name)))
+            }
+        }
+    }
+
+    /// Lädt die Queue nach IPFS hoch. Bricht beim ersten Fehler ab (Daemon
+    /// vermutlich weiterhin down); der Rest bleibt eingereiht.
+    pub async fn flush(&self) -> Result<FlushReport> {
+        let mut report = FlushReport::default();
+        let pending = self.pending();
+        let mut index = self.load_index();
+        for (i, name) in pending.iter().enumerate() {
+            let path = self.pending_dir().join(name);
+            let data = fs::read(&path)?;
+            match self.remote.put_block(data).await {
+                Ok(cid) => {
+                    index.insert(name.clone(), cid.clone());
+                    self.store_index(&index)?;
+                    fs::remove_file(&path)?;
+                    report.uploaded.push((name.clone(), cid));
+                }
+                Err(e) => {
+                    warn!("IPFS-Flush bei {} gescheitert: {}", name, e);
+                    report.remaining = pending.len() - i;
+                    self.set_available(false);
+                    return Ok(report);
+                }
+            }
+        }
+        if !report.uploaded.is_empty() {
+            info!("IPFS wieder erreichbar => {} eingereihte Uploads nachgeholt", report.uploaded.len());
+        }
+        self.set_available(true);
+        Ok(report)
+    }
+
+    /// Periodischer Flush bis zum Shutdown.
+    pub async fn run_flush_loop(self: Arc<Self>, interval: Duration, token: CancellationToken) {
+        let mut tick = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = tick.tick() => {
+                    if self.pending().is_empty() {
+                        continue;
+                    }
+                    if let Err(e) = self.flush().await {
+                        warn!("IPFS-Flush fehlgeschlagen: {:?}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    /// IPFS-Attrappe, die sich per Flag an- und abschalten lässt.
+    #[derive(Default)]
+    struct FlakyIpfs {
+        up: AtomicBool,
+        blocks: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl SnapshotStore for FlakyIpfs {
+        async fn put_block(&self, data: Vec<u8>) -> Result<String> {
+            if !self.up.load(Ordering::SeqCst) {
+                anyhow::bail!("connection refused");
+            }
+            let cid = crate::storage::ipfs_storage::compute_cid_v0(&data);
+            self.blocks.lock().unwrap().insert(cid.clone(), data);
+            Ok(cid)
+        }
+
+        async fn get_block(&self, cid: &str) -> Result<Vec<u8>> {
+            if !self.up.load(Ordering::SeqCst) {
+                anyhow::bail!("connection refused");
+            }
+            self.blocks.lock().unwrap().get(cid).cloned().ok_or_else(|| anyhow::anyhow!("not found"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backups_queue_locally_while_down_and_flush_on_recovery() {
+        let dir = tempfile::tempdir().unwrap();
+        let ipfs = Arc::new(FlakyIpfs::default());
+        let store = IpfsFallbackStore::new(dir.path(), ipfs.clone());
+
+        // IPFS down => lokal eingereiht, aber weiterhin lesbar
+        let r = store.put("config/node_config.yaml", b"role: full\n".to_vec()).await.unwrap();
+        assert!(matches!(r, StoredRef::Queued(_)));
+        store.put("trade_audit.log", b"audit\n".to_vec()).await.unwrap();
+        assert_eq!(store.pending(), vec!["config_node_config.yaml", "trade_audit.log"]);
+        assert_eq!(store.get("config/node_config.yaml").await.unwrap(), b"role: full\n");
+        assert_eq!(IPFS_AVAILABLE.get(), 0);
+
+        // Flush während des Ausfalls ändert nichts
+        let report = store.flush().await.unwrap();
+        assert_eq!((report.uploaded.len(), report.remaining), (0, 2));
+
+        // Recovery => Queue geht nach IPFS, Index zeigt auf die CIDs
+        ipfs.up.store(true, Ordering::SeqCst);
+        let report = store.flush().await.unwrap();
+        assert_eq!((report.uploaded.len(), report.remaining), (2, 0));
+        assert!(store.pending().is_empty());
+        assert_eq!(IPFS_AVAILABLE.get(), 1);
+        assert_eq!(ipfs.blocks.lock().unwrap().len(), 2);
+        assert_eq!(store.get("config/node_config.yaml").await.unwrap(), b"role: full\n");
+
+        // Direkter Upload bei laufendem Daemon
+        let r = store.put("backup_config_hash", b"cfg".to_vec()).await.unwrap();
+        assert_eq!(r, StoredRef::Ipfs(crate::storage::ipfs_storage::compute_cid_v0(b"cfg")));
+    }
+}
@@ -0,0 +1,241 @@
+///////////////////////////////////////////////////////////
+// my_DEX/src/storage/migrations.rs
+///////////////////////////////////////////////////////////
+//
+// Schema-Versionierung und Migrationen für die RocksDB-Speicher.
+//
+// Jede DB speichert unter `SCHEMA_VERSION_KEY` (in `META_CF`) die Version ihres
+// On-Disk-Formats. Beim Öffnen führt der `MigrationRunner` alle Schritte aus,
+// deren Zielversion größer als die gespeicherte ist, in aufsteigender Reihenfolge.
+// Ist die gespeicherte Version neuer als die des Binaries, wird das Öffnen
+// abgebrochen, statt Daten mit falschem Layout zu lesen.
+
+use anyhow::{anyhow, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::info;
+
+use crate::error::DexError;
+
+/// Key, unter dem die Schema-Version (u32, big-endian) liegt.
+pub const SCHEMA_VERSION_KEY: &[u8] = b"__schema_version";
+
+/// Column Family für Metadaten. Bei Stores ohne CFs wird der Name ignoriert.
+pub const META_CF: &str = "default";
+
+/// Minimaler Zugriff, den eine Migration auf den Store braucht.
+pub trait MigrationStore {
+    fn read(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>>;
+    fn write(&self, cf: &str, key: &[u8], val: &[u8]) -> Result<()>;
+    fn remove(&self, cf: &str, key: &[u8]) -> Result<()>;
+    /// Alle Einträge, deren Key mit `prefix` beginnt.
+    fn scan_prefix(&self, cf: &str, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+}
+
+/// Ein Migrationsschritt, der den Store auf `version` anhebt.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub apply: fn(&dyn MigrationStore) -> Result<()>,
+}
+
+/// Führt geordnete Migrationen aus und pflegt die Schema-Version.
+pub struct MigrationRunner {
+    steps: Vec<Migration>,
+}
+
+impl MigrationRunner {
+    /// Die Schritte müssen streng aufsteigende Versionen ab 1 haben.
+    pub fn new(steps: Vec<Migration>) -> Result<Self> {
+        for (i, step) in steps.iter().enumerate() {
+            if step.version != i as u32 + 1 {
+                return Err(anyhow!(
+                    "Migration '{}' has version {}, expected {}",
+                    step.description,
+                    step.version,
+                    i + 1
+                ));
+            }
+        }
+        Ok(Self { steps })
+    }
+
+    /// Version, die dieses Binary schreibt.
+    pub fn latest_version(&self) -> u32 {
+        self.steps.last().map(|s| s.version).unwrap_or(0)
+    }
+
+    /// Bringt den Store auf `latest_version()` und gibt die neue Version zurück.
+    pub fn run(&self, store: &dyn MigrationStore) -> Result<u32> {
+        let on_disk = read_schema_version(store)?;
+        let supported = self.latest_version();
+        if on_disk > supported {
+            return Err(DexError::SchemaVersionTooNew { on_disk, supported }.into());
+        }
+        for step in self.steps.iter().filter(|s| s.version > on_disk) {
+            info!("Applying DB migration v{}: {}", step.version, step.description);
+            (step.apply)(store)
+                .map_err(|e| anyhow!("Migration v{} ('{}') failed: {:?}", step.version, step.description, e))?;
+            // Nach jedem Schritt sichern => ein Abbruch setzt beim nächsten Schritt fort.
+            write_schema_version(store, step.version)?;
+        }
+        Ok(supported.max(on_disk))
+    }
+}
+
+/// Gespeicherte Version; eine DB ohne Eintrag gilt als Version 0.
+pub fn read_schema_version(store: &dyn MigrationStore) -> Result<u32> {
+    match store.read(META_CF, SCHEMA_VERSION_KEY)? {
+        Some(bytes) => {
+            let arr: [u8; 4] = bytes
+                .as_slice()
+                .try_into()
+                .map_err(|_| anyhow!("Corrupt schema version entry ({} bytes)", bytes.len()))?;
+            Ok(u32::from_be_bytes(arr))
+        }
+        None => Ok(0),
+    }
+}
+
+pub fn write_schema_version(store: &dyn MigrationStore, version: u32) -> Result<()> {
+    store.write(META_CF, SCHEMA_VERSION_KEY, &version.to_be_bytes())
+}
+
+/// Hilfsfunktion für Layout-Änderungen: liest alle Einträge unter `prefix` als
+/// `Old`, wandelt sie mit `convert` um und schreibt sie als `New` zurück.
+pub fn reserialize<Old, New, F>(store: &dyn MigrationStore, cf: &str, prefix: &[u8], convert: F) -> Result<usize>
+where
+    Old: DeserializeOwned,
+    New: Serialize,
+    F: Fn(Old) -> New,
+{
+    let entries = store.scan_prefix(cf, prefix)?;
+    for (key, bytes) in &entries {
+        let old: Old = bincode::deserialize(bytes)
+            .map_err(|e| anyhow!("Cannot decode {:?} as old layout: {:?}", String::from_utf8_lossy(key), e))?;
+        store.write(cf, key, &bincode::serialize(&convert(old))?)?;
+    }
+    Ok(entries.len())
+}
+
+fn noop(_store: &dyn MigrationStore) -> Result<()> {
+    Ok(())
+}
+
+/// Migrationen für `storage::db_layer::DexDB` (Accounts, Wallets, ...).
+/// Neue Schritte hinten anhängen, nie bestehende ändern.
+pub fn dex_db_migrations() -> Vec<Migration> {
+    vec![Migration {
+        version: 1,
+        description: "initial schema marker",
+        apply: noop,
+    }]
+}
+
+/// Migrationen für `AdvancedShardDB`.
+pub fn shard_db_migrations() -> Vec<Migration> {
+    vec![Migration {
+        version: 1,
+        description: "initial schema marker",
+        apply: noop,
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+
+    #[derive(Default)]
+    struct MemStore {
+        data: RefCell<BTreeMap<(String, Vec<u8>), Vec<u8>>>,
+    }
+
+    impl MigrationStore for MemStore {
+        fn read(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+            Ok(self.data.borrow().get(&(cf.to_string(), key.to_vec())).cloned())
+        }
+        fn write(&self, cf: &str, key: &[u8], val: &[u8]) -> Result<()> {
+            self.data.borrow_mut().insert((cf.to_string(), key.to_vec()), val.to_vec());
+            Ok(())
+        }
+        fn remove(&self, cf: &str, key: &[u8]) -> Result<()> {
+            self.data.borrow_mut().remove(&(cf.to_string(), key.to_vec()));
+            Ok(())
+        }
+        fn scan_prefix(&self, cf: &str, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+            Ok(self
+                .data
+                .borrow()
+                .iter()
+                .filter(|((c, k), _)| c == cf && k.starts_with(prefix))
+                .map(|((_, k), v)| (k.clone(), v.clone()))
+                .collect())
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct AccountV1 {
+        user_id: String,
+        balance: u64,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct AccountV2 {
+        user_id: String,
+        balance: u64,
+        is_paused: bool,
+    }
+
+    fn add_paused_flag(store: &dyn MigrationStore) -> Result<()> {
+        reserialize::<AccountV1, AccountV2, _>(store, META_CF, b"account_", |old| AccountV2 {
+            user_id: old.user_id,
+            balance: old.balance,
+            is_paused: false,
+        })?;
+        Ok(())
+    }
+
+    fn steps() -> Vec<Migration> {
+        vec![
+            Migration { version: 1, description: "initial", apply: noop },
+            Migration { version: 2, description: "add Account.is_paused", apply: add_paused_flag },
+        ]
+    }
+
+    #[test]
+    fn test_up_migration_v1_to_v2() -> Result<()> {
+        let store = MemStore::default();
+        let v1 = AccountV1 { user_id: "alice".into(), balance: 42 };
+        store.write(META_CF, b"account_alice", &bincode::serialize(&v1)?)?;
+        write_schema_version(&store, 1)?;
+
+        let runner = MigrationRunner::new(steps())?;
+        assert_eq!(runner.run(&store)?, 2);
+        assert_eq!(read_schema_version(&store)?, 2);
+
+        let bytes = store.read(META_CF, b"account_alice")?.unwrap();
+        let v2: AccountV2 = bincode::deserialize(&bytes)?;
+        assert_eq!(v2, AccountV2 { user_id: "alice".into(), balance: 42, is_paused: false });
+
+        // Zweiter Lauf ist ein No-op.
+        assert_eq!(runner.run(&store)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_newer_on_disk_version_fails_fast() -> Result<()> {
+        let store = MemStore::default();
+        write_schema_version(&store, 7)?;
+        let err = MigrationRunner::new(steps())?.run(&store).unwrap_err();
+        assert!(err.to_string().contains("newer"), "unexpected error: {}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn test_out_of_order_steps_rejected() {
+        let bad = vec![Migration { version: 2, description: "skip", apply: noop }];
+        assert!(MigrationRunner::new(bad).is_err());
+    }
+}
@@ -8,10 +8,14 @@
 //! - dex_db.rs: Persistente Speicherung via RocksDB mit Column Families
 //! - distributed_db.rs: Erweiterte, verteilte DB-Logik (Replikation & Synchronisation)
 //! - ipfs_storage.rs: Funktionen zur Integration von IPFS
+//! - ipfs_fallback.rs: Lokale Upload-Queue, wenn der IPFS-Daemon fehlt
 //! - replicated_db_layer.rs: Erweiterter DB-Layer mit Replikationsmechanismen
+//! - migrations.rs: Schema-Version und geordnete Migrationen beim Öffnen
 
 pub mod db_layer;
 pub mod dex_db;
 pub mod distributed_db;
+pub mod ipfs_fallback;
 pub mod ipfs_storage;
+pub mod migrations;
 pub mod replicated_db_layer;
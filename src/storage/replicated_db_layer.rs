@@ -17,20 +17,120 @@ use anyhow::Result;
 use rocksdb::{DB, Options, Direction, IteratorMode};
 use serde::{Serialize, Deserialize};
 use bincode;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 use tokio::time::sleep;
 use tracing::{info, debug, warn, error};
 
+use crate::utils::lock::LockRecover;
+
+/// Versionsvektor: Node-ID -> Anzahl der lokalen Updates dieses Nodes.
+/// Anders als eine einzelne Versionsnummer unterscheidet er lineare
+/// Historie von nebenläufigen Änderungen auf verschiedenen Nodes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionVector(pub BTreeMap<String, u64>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VvOrder {
+    Equal,
+    /// self ist Vorgänger von other
+    Before,
+    /// self hat other vollständig gesehen
+    After,
+    Concurrent,
+}
+
+impl VersionVector {
+    pub fn get(&self, node: &str) -> u64 {
+        *self.0.get(node).unwrap_or(&0)
+    }
+
+    pub fn increment(&mut self, node: &str) -> u64 {
+        let e = self.0.entry(node.to_string()).or_insert(0);
+        *e += 1;
+        *e
+    }
+
+    /// Komponentenweises Maximum.
+    pub fn merge(&mut self, other: &VersionVector) {
+        for (node, &v) in &other.0 {
+            let e = self.0.entry(node.clone()).or_insert(0);
+            *e = (*e).max(v);
+        }
+    }
+
+    pub fn compare(&self, other: &VersionVector) -> VvOrder {
+        let mut less = false;
+        let mut greater = false;
+        for node in self.0.keys().chain(other.0.keys()) {
+            let (a, b) = (self.get(node), other.get(node));
+            less |= a < b;
+            greater |= a > b;
+        }
+        match (less, greater) {
+            (false, false) => VvOrder::Equal,
+            (true, false) => VvOrder::Before,
+            (false, true) => VvOrder::After,
+            (true, true) => VvOrder::Concurrent,
+        }
+    }
+}
+
 /// CRDT-Snapshot repräsentiert den Zustand der Datenbank.
+/// `version` ist der Zähler des Ursprungs-Nodes (`vector.get(origin)`),
+/// der Versionsvektor beschreibt, welche Updates der Snapshot enthält.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CrdtSnapshot {
     pub version: u64,
+    pub origin: String,
+    pub vector: VersionVector,
     pub data: Vec<u8>,
 }
 
+impl CrdtSnapshot {
+    pub fn new(origin: &str, vector: VersionVector, data: Vec<u8>) -> Self {
+        Self { version: vector.get(origin), origin: origin.to_string(), vector, data }
+    }
+
+    fn key(&self) -> String {
+        snapshot_key(&self.origin, self.version)
+    }
+}
+
+/// Altes Format (nur `version` + `data`) vor Einführung der Versionsvektoren.
+#[derive(Deserialize)]
+struct LegacyCrdtSnapshot {
+    version: u64,
+    data: Vec<u8>,
+}
+
+const SNAPSHOT_PREFIX: &str = "crdt_snapshot_";
+const HEAD_KEY: &str = "crdt_head";
+
+fn snapshot_key(origin: &str, version: u64) -> String {
+    format!("{}{}_v{:020}", SNAPSHOT_PREFIX, origin, version)
+}
+
+fn decode_snapshot(bytes: &[u8]) -> Result<CrdtSnapshot> {
+    match bincode::deserialize::<CrdtSnapshot>(bytes) {
+        Ok(s) => Ok(s),
+        Err(_) => {
+            let old: LegacyCrdtSnapshot = bincode::deserialize(bytes)?;
+            let mut vector = VersionVector::default();
+            vector.0.insert("legacy".into(), old.version);
+            Ok(CrdtSnapshot::new("legacy", vector, old.data))
+        }
+    }
+}
+
+/// Führt zwei nebenläufige Snapshot-Daten über die CRDT-Merge-Logik zusammen.
+pub type SnapshotMergeFn = Arc<dyn Fn(&[u8], &[u8]) -> Result<Vec<u8>> + Send + Sync>;
+
+/// Prüft die Daten eines empfangenen Snapshots vor der Übernahme (z. B. Zeitstempel).
+pub type SnapshotCheckFn = Arc<dyn Fn(&[u8]) -> Result<()> + Send + Sync>;
+
 /// Eine einfache In-Memory-Datenbank als Fallback.
 #[derive(Default, Debug)]
 pub struct InMemoryDb {
@@ -59,6 +159,15 @@ pub struct DexDB {
 
     // NEU => optional KademliaService, um beidseitig Snapshots zu verschicken
     pub kademlia: Option<Arc<Mutex<crate::kademlia::kademlia_service::KademliaService>>>,
+
+    /// Merge für nebenläufige Snapshots; ohne => beide Seiten bleiben getrennt gespeichert
+    pub snapshot_merge: Option<SnapshotMergeFn>,
+
+    /// Prüfung empfangener Snapshots; abgelehnte werden verworfen
+    pub snapshot_check: Option<SnapshotCheckFn>,
+
+    /// Serialisiert Lesen-Ändern-Schreiben des Heads (`commit_local`, `integrate`)
+    pub head_lock: Mutex<()>,
 }
 
 impl DexDB {
@@ -72,6 +181,9 @@ impl DexDB {
             rocks: Some(db),
             fallback_mem: None,
             kademlia: None,
+            snapshot_merge: None,
+            snapshot_check: None,
+            head_lock: Mutex::new(()),
         })
     }
 
@@ -91,6 +203,9 @@ impl DexDB {
                             rocks: None,
                             fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))),
                             kademlia: None,
+                            snapshot_merge: None,
+                            snapshot_check: None,
+                            head_lock: Mutex::new(()),
                         });
                     }
                     thread::sleep(Duration::from_secs(backoff_sec));
@@ -99,91 +214,133 @@ impl DexDB {
         }
     }
 
-    /// Speichert einen CRDT-Snapshot in der Datenbank.
-    pub fn store_crdt_snapshot(&self, snapshot: &CrdtSnapshot) -> Result<()> {
-        let key = format!("crdt_snapshot_v{}", snapshot.version);
-        let encoded = bincode::serialize(snapshot)?;
-        if let Some(rdb) = &self.rocks {
-            rdb.put(key.as_bytes(), &encoded)?;
-            debug!("Snapshot in RocksDB gespeichert: {}", key);
-        } else if let Some(mem) = &self.fallback_mem {
-            let mut lock = mem.lock().unwrap();
-            lock.put(&key, encoded);
-            debug!("Snapshot im InMemoryDB gespeichert: {}", key);
-        }
-        Ok(())
+    pub fn with_snapshot_merge(mut self, merge: SnapshotMergeFn) -> Self {
+        self.snapshot_merge = Some(merge);
+        self
+    }
+
+    pub fn with_snapshot_check(mut self, check: SnapshotCheckFn) -> Self {
+        self.snapshot_check = Some(check);
+        self
     }
 
-    /// Lädt einen CRDT-Snapshot anhand der Versionsnummer.
-    pub fn load_crdt_snapshot(&self, version: u64) -> Result<Option<CrdtSnapshot>> {
-        let key = format!("crdt_snapshot_v{}", version);
+    pub fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>> {
         if let Some(rdb) = &self.rocks {
-            match rdb.get(key.as_bytes())? {
-                Some(bytes) => {
-                    let snap: CrdtSnapshot = bincode::deserialize(&bytes)?;
-                    Ok(Some(snap))
-                },
-                None => Ok(None),
-            }
+            Ok(rdb.get(key.as_bytes())?)
         } else if let Some(mem) = &self.fallback_mem {
-            let lock = mem.lock().unwrap();
-            if let Some(bytes) = lock.get(&key) {
-                let snap: CrdtSnapshot = bincode::deserialize(bytes)?;
-                Ok(Some(snap))
-            } else {
-                Ok(None)
-            }
+            Ok(mem.lock_recover().get(key).map(|b| b.to_vec()))
         } else {
             Ok(None)
         }
     }
 
-    /// Listet alle gespeicherten CRDT-Snapshots auf.
-    pub fn list_crdt_snapshots(&self) -> Result<Vec<CrdtSnapshot>> {
-        let mut out = Vec::new();
-        let prefix = "crdt_snapshot_v";
+    pub fn put_raw(&self, key: &str, val: Vec<u8>) -> Result<()> {
         if let Some(rdb) = &self.rocks {
-            let mode = IteratorMode::From(prefix.as_bytes(), Direction::Forward);
-            for item in rdb.iterator(mode) {
-                let (k, v) = item?;
-                if !k.starts_with(prefix.as_bytes()) {
-                    break;
-                }
-                let snap: CrdtSnapshot = bincode::deserialize(&v)?;
-                out.push(snap);
-            }
+            rdb.put(key.as_bytes(), &val)?;
         } else if let Some(mem) = &self.fallback_mem {
-            let lock = mem.lock().unwrap();
-            for (k, v) in lock.list_prefix(prefix) {
-                let snap: CrdtSnapshot = bincode::deserialize(&v)?;
-                out.push(snap);
+            mem.lock_recover().put(key, val);
+        }
+        Ok(())
+    }
+
+    /// Speichert einen CRDT-Snapshot in der Datenbank und führt ihn in den
+    /// aktuellen Stand (Head) ein.
+    pub fn store_crdt_snapshot(&self, snapshot: &CrdtSnapshot) -> Result<()> {
+        let _head = self.head_lock.lock_recover();
+        self.store_and_integrate(snapshot)
+    }
+
+    /// Aufrufer hält `head_lock`.
+    fn store_and_integrate(&self, snapshot: &CrdtSnapshot) -> Result<()> {
+        let key = snapshot.key();
+        self.put_raw(&key, bincode::serialize(snapshot)?)?;
+        debug!("Snapshot gespeichert: {}", key);
+        self.integrate(snapshot)
+    }
+
+    /// Lädt einen CRDT-Snapshot anhand von Ursprungs-Node und Versionsnummer.
+    pub fn load_crdt_snapshot(&self, origin: &str, version: u64) -> Result<Option<CrdtSnapshot>> {
+        match self.get_raw(&snapshot_key(origin, version))? {
+            Some(bytes) => Ok(Some(decode_snapshot(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Aktueller, zusammengeführter Stand.
+    pub fn load_head(&self) -> Result<Option<CrdtSnapshot>> {
+        match self.get_raw(HEAD_KEY)? {
+            Some(bytes) => Ok(Some(decode_snapshot(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Neuer lokaler Stand von `node_id`: zählt dessen Eintrag im Vektor hoch.
+    pub fn commit_local(&self, node_id: &str, data: Vec<u8>) -> Result<CrdtSnapshot> {
+        let _head = self.head_lock.lock_recover();
+        let mut vector = self.load_head()?.map(|h| h.vector).unwrap_or_default();
+        vector.increment(node_id);
+        let snap = CrdtSnapshot::new(node_id, vector, data);
+        self.store_and_integrate(&snap)?;
+        Ok(snap)
+    }
+
+    /// Head mit `snap` zusammenführen (Aufrufer hält `head_lock`):
+    ///  - schon enthalten => nichts
+    ///  - Nachfolger => übernehmen
+    ///  - nebenläufig => CRDT-Merge beider Seiten, Vektoren vereinigt
+    fn integrate(&self, snap: &CrdtSnapshot) -> Result<()> {
+        let head = match self.load_head()? {
+            Some(h) => h,
+            None => return self.put_raw(HEAD_KEY, bincode::serialize(snap)?),
+        };
+        match head.vector.compare(&snap.vector) {
+            VvOrder::Equal | VvOrder::After => Ok(()),
+            VvOrder::Before => self.put_raw(HEAD_KEY, bincode::serialize(snap)?),
+            VvOrder::Concurrent => {
+                let Some(merge) = &self.snapshot_merge else {
+                    warn!(
+                        "Nebenläufiger Snapshot {} ohne Merge-Funktion => bleibt separat gespeichert",
+                        snap.key()
+                    );
+                    return Ok(());
+                };
+                let data = merge(&head.data, &snap.data)?;
+                let mut vector = head.vector.clone();
+                vector.merge(&snap.vector);
+                let merged = CrdtSnapshot::new(&head.origin, vector, data);
+                debug!("Nebenläufige Snapshots zusammengeführt => {:?}", merged.vector);
+                self.put_raw(HEAD_KEY, bincode::serialize(&merged)?)
             }
         }
+    }
+
+    /// Listet alle gespeicherten CRDT-Snapshots auf.
+    pub fn list_crdt_snapshots(&self) -> Result<Vec<CrdtSnapshot>> {
+        let out = self.replicate_state()?;
         debug!("Anzahl gefundener Snapshots: {}", out.len());
         Ok(out)
     }
 
     /// Synchronisiert den lokalen Zustand mit den Snapshots eines entfernten Nodes.
+    /// Unbekannte Snapshots werden gespeichert und über den Versionsvektor in den
+    /// Head eingeführt – nebenläufige Stände werden gemergt statt überschrieben.
     pub fn sync_with_remote(&self, remote_snapshots: Vec<CrdtSnapshot>) -> Result<()> {
-        if let Some(rdb) = &self.rocks {
-            for snap in remote_snapshots {
-                let key = format!("crdt_snapshot_v{}", snap.version);
-                if rdb.get(key.as_bytes())?.is_none() {
-                    rdb.put(key.as_bytes(), &bincode::serialize(&snap)?)?;
-                    debug!("Remote Snapshot hinzugefügt: {}", key);
-                } else {
-                    debug!("Remote Snapshot existiert bereits: {}", key);
+        let _head = self.head_lock.lock_recover();
+        for snap in remote_snapshots {
+            if let Some(check) = &self.snapshot_check {
+                if let Err(e) = check(&snap.data) {
+                    warn!("Remote Snapshot {} v{} verworfen: {}", snap.origin, snap.version, e);
+                    continue;
                 }
             }
-        } else if let Some(mem) = &self.fallback_mem {
-            let mut lock = mem.lock().unwrap();
-            for snap in remote_snapshots {
-                let key = format!("crdt_snapshot_v{}", snap.version);
-                if lock.get(&key).is_none() {
-                    lock.put(&key, bincode::serialize(&snap)?);
-                    debug!("Remote Snapshot hinzugefügt (Fallback): {}", key);
-                }
+            let key = snap.key();
+            if self.get_raw(&key)?.is_none() {
+                self.put_raw(&key, bincode::serialize(&snap)?)?;
+                debug!("Remote Snapshot hinzugefügt: {}", key);
+            } else {
+                debug!("Remote Snapshot existiert bereits: {}", key);
             }
+            self.integrate(&snap)?;
         }
         Ok(())
     }
@@ -191,7 +348,7 @@ impl DexDB {
     /// Extrahiert alle lokalen Snapshots, um sie an andere Nodes zu replizieren.
     pub fn replicate_state(&self) -> Result<Vec<CrdtSnapshot>> {
         let mut snapshots = Vec::new();
-        let prefix = "crdt_snapshot_v";
+        let prefix = SNAPSHOT_PREFIX;
         if let Some(rdb) = &self.rocks {
             let mode = IteratorMode::From(prefix.as_bytes(), Direction::Forward);
             for item in rdb.iterator(mode) {
@@ -199,14 +356,12 @@ impl DexDB {
                 if !k.starts_with(prefix.as_bytes()) {
                     break;
                 }
-                let snap: CrdtSnapshot = bincode::deserialize(&v)?;
-                snapshots.push(snap);
+                snapshots.push(decode_snapshot(&v)?);
             }
         } else if let Some(mem) = &self.fallback_mem {
-            let lock = mem.lock().unwrap();
-            for (k, v) in lock.list_prefix(prefix) {
-                let snap: CrdtSnapshot = bincode::deserialize(&v)?;
-                snapshots.push(snap);
+            let lock = mem.lock_recover();
+            for (_k, v) in lock.list_prefix(prefix) {
+                snapshots.push(decode_snapshot(&v)?);
             }
         }
         Ok(snapshots)
@@ -226,7 +381,7 @@ impl DexDB {
             // NEU => beidseitige Synchronisierung:
             //    Wir schicken hier unsere Snapshots an Peers (über KademliaMessage::CrdtSnapshots)
             if let Some(ref kad_service) = self.kademlia {
-                let kad = kad_service.lock().unwrap();
+                let kad = kad_service.lock_recover();
                 // Wir holen z.B. die 20 nächsten Peers
                 let peers = kad.table.find_closest(&kad.local_id, 20);
                 for (_, addr) in peers {
@@ -261,24 +416,99 @@ mod tests {
         assert_eq!(value.unwrap(), &[1, 2, 3]);
     }
     
-    #[test]
-    fn test_sync_with_remote() {
-        let snap = CrdtSnapshot {
-            version: 1,
-            data: vec![1, 2, 3, 4],
-        };
-        let mem_db = Arc::new(Mutex::new(InMemoryDb::default()));
-        let dex_db = DexDB {
+    fn mem_dex_db() -> DexDB {
+        DexDB {
             rocks: None,
-            fallback_mem: Some(mem_db.clone()),
+            fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))),
             kademlia: None,
-        };
+            snapshot_merge: None,
+            snapshot_check: None,
+            head_lock: Mutex::new(()),
+        }
+    }
+
+    #[test]
+    fn test_sync_with_remote() {
+        let mut vector = VersionVector::default();
+        vector.increment("n1");
+        let snap = CrdtSnapshot::new("n1", vector, vec![1, 2, 3, 4]);
+        let dex_db = mem_dex_db();
         let remote_snapshots = vec![snap.clone()];
         assert!(dex_db.sync_with_remote(remote_snapshots).is_ok());
-        let loaded = dex_db.load_crdt_snapshot(1).unwrap();
+        let loaded = dex_db.load_crdt_snapshot("n1", 1).unwrap();
         assert!(loaded.is_some());
     }
 
+    #[test]
+    fn test_version_vector_ordering() {
+        let mut a = VersionVector::default();
+        let mut b = VersionVector::default();
+        assert_eq!(a.compare(&b), VvOrder::Equal);
+        a.increment("A");
+        assert_eq!(a.compare(&b), VvOrder::After);
+        assert_eq!(b.compare(&a), VvOrder::Before);
+        b.increment("B");
+        assert_eq!(a.compare(&b), VvOrder::Concurrent);
+        a.merge(&b);
+        assert_eq!(a.compare(&b), VvOrder::After);
+    }
+
+    #[test]
+    fn test_concurrent_snapshots_merge_without_data_loss() {
+        // Daten = Menge von Bytes (G-Set), Merge = Vereinigung
+        let union: SnapshotMergeFn = Arc::new(|a: &[u8], b: &[u8]| {
+            let mut v: Vec<u8> = a.iter().chain(b.iter()).copied().collect();
+            v.sort_unstable();
+            v.dedup();
+            Ok(v)
+        });
+        let node_a = mem_dex_db().with_snapshot_merge(union.clone());
+        let node_b = mem_dex_db().with_snapshot_merge(union);
+
+        // Beide Nodes erreichen unabhängig "Version 1" bzw. A sogar 2
+        node_a.commit_local("A", vec![1]).unwrap();
+        node_a.commit_local("A", vec![1, 2]).unwrap();
+        node_b.commit_local("B", vec![3]).unwrap();
+
+        node_a.sync_with_remote(node_b.replicate_state().unwrap()).unwrap();
+        node_b.sync_with_remote(node_a.replicate_state().unwrap()).unwrap();
+
+        let head_a = node_a.load_head().unwrap().unwrap();
+        let head_b = node_b.load_head().unwrap().unwrap();
+        assert_eq!(head_a.data, vec![1, 2, 3]);
+        assert_eq!(head_b.data, vec![1, 2, 3]);
+        assert_eq!(head_a.vector, head_b.vector);
+        assert_eq!(head_a.vector.get("A"), 2);
+        assert_eq!(head_a.vector.get("B"), 1);
+
+        // Nächster lokaler Commit baut auf dem gemergten Vektor auf
+        let next = node_b.commit_local("B", vec![1, 2, 3, 4]).unwrap();
+        assert_eq!(next.vector.compare(&head_a.vector), VvOrder::After);
+    }
+
+    #[test]
+    fn test_concurrent_commits_do_not_lose_head_updates() {
+        let db = Arc::new(mem_dex_db());
+        let workers: Vec<_> = (0..4)
+            .map(|i| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..25 {
+                        db.commit_local(&format!("n{}", i), vec![i]).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for w in workers {
+            w.join().unwrap();
+        }
+        // Jeder Commit baut auf dem vorigen Head auf => kein Zähler geht verloren
+        let head = db.load_head().unwrap().unwrap();
+        for i in 0..4 {
+            assert_eq!(head.vector.get(&format!("n{}", i)), 25);
+        }
+    }
+
     #[tokio::test]
     async fn test_run_gossip_sync() {
         let mem_db = Arc::new(Mutex::new(InMemoryDb::default()));
@@ -286,13 +516,12 @@ mod tests {
             rocks: None,
             fallback_mem: Some(mem_db.clone()),
             kademlia: None, // im Test kein Kademlia
+            snapshot_merge: None,
+            snapshot_check: None,
+            head_lock: Mutex::new(()),
         };
         // Füge einen Snapshot hinzu, damit etwas synchronisiert wird.
-        let snap = CrdtSnapshot {
-            version: 1,
-            data: vec![10, 20, 30],
-        };
-        dex_db.store_crdt_snapshot(&snap).unwrap();
+        dex_db.commit_local("n1", vec![10, 20, 30]).unwrap();
         // Starte die Gossip-Synchronisation für eine kurze Zeit.
         tokio::spawn(async move {
             let _ = dex_db.run_gossip_sync().await;
@@ -3,3 +3,4 @@
 ///////////////////////////////////////
 
 pub mod pow;
+pub mod stake_admission;
@@ -1,16 +1,36 @@
 ///////////////////////////////////////
 /// my_DEX/src/sybil/pow.rs
 ///////////////////////////////////////
+//
+// Proof-of-Work:
+//  - validate_pow => statischer Check einer Node-ID
+//  - Client-Puzzle für die Verbindungsannahme (PowGate): der Responder
+//    schickt vor dem Noise-Handshake eine Challenge + Difficulty, der
+//    Initiator muss eine Nonce liefern, deren Hash genügend führende
+//    Null-Bits hat. Die Difficulty steigt mit der eingehenden
+//    Verbindungsrate, so wird ein Handshake-Flood teuer für den Angreifer.
+//  - Der Initiator löst nur bis `max_difficulty` (siehe `solve_capped`),
+//    sonst könnte ein feindlicher Responder ihn beliebig lange rechnen lassen.
+//  - Aktiviert über `handshake_pow` in der Node-Config; der TcpP2PAdapter
+//    stellt die Challenge vor jedem eingehenden Noise-Handshake und löst sie
+//    vor jedem ausgehenden. Alle Nodes eines Netzes müssen es gleich setzen.
 
 use super::*;
 use sha2::{Sha256, Digest};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
 
-pub fn validate_pow(node_id: &str, difficulty: usize) -> bool {
-    let mut hasher = Sha256::new();
-    hasher.update(node_id.as_bytes());
-    let result = hasher.finalize();
+/// Höchste Difficulty, die ein Initiator standardmäßig noch löst
+/// (2^24 Hashes im Mittel, Bruchteile einer Sekunde).
+pub const DEFAULT_MAX_SOLVE_DIFFICULTY: u8 = 24;
+
+fn leading_zero_bits(hash: &[u8]) -> usize {
     let mut count = 0;
-    for byte in result.iter() {
+    for byte in hash.iter() {
         if *byte == 0 {
             count += 8;
         } else {
@@ -18,5 +38,220 @@ pub fn validate_pow(node_id: &str, difficulty: usize) -> bool {
             break;
         }
     }
-    count >= difficulty
+    count
+}
+
+pub fn validate_pow(node_id: &str, difficulty: usize) -> bool {
+    let mut hasher = Sha256::new();
+    hasher.update(node_id.as_bytes());
+    let result = hasher.finalize();
+    leading_zero_bits(&result) >= difficulty
+}
+
+/// Challenge des Responders. Wire-Format: [difficulty, challenge(16)].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowChallenge {
+    pub difficulty: u8,
+    pub challenge: [u8; 16],
+}
+
+impl PowChallenge {
+    pub const WIRE_LEN: usize = 17;
+
+    pub fn to_bytes(&self) -> [u8; Self::WIRE_LEN] {
+        let mut out = [0u8; Self::WIRE_LEN];
+        out[0] = self.difficulty;
+        out[1..].copy_from_slice(&self.challenge);
+        out
+    }
+
+    pub fn from_bytes(b: &[u8; Self::WIRE_LEN]) -> Self {
+        let mut challenge = [0u8; 16];
+        challenge.copy_from_slice(&b[1..]);
+        Self { difficulty: b[0], challenge }
+    }
+
+    fn hash(&self, nonce: u64) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"dex_pow");
+        hasher.update(self.challenge);
+        hasher.update(nonce.to_be_bytes());
+        hasher.finalize().into()
+    }
+
+    pub fn verify(&self, nonce: u64) -> bool {
+        leading_zero_bits(&self.hash(nonce)) >= self.difficulty as usize
+    }
+
+    /// Wie `solve`, aber `None`, wenn die Difficulty über `max_difficulty`
+    /// liegt. Der Initiator sollte nur diese Variante verwenden.
+    pub fn solve_capped(&self, max_difficulty: u8) -> Option<u64> {
+        if self.difficulty > max_difficulty {
+            return None;
+        }
+        Some(self.solve())
+    }
+
+    /// Brute-Force auf Initiator-Seite (ohne Obergrenze).
+    pub fn solve(&self) -> u64 {
+        let mut nonce = 0u64;
+        while !self.verify(nonce) {
+            nonce = nonce.wrapping_add(1);
+        }
+        nonce
+    }
+}
+
+/// Config-Abschnitt `handshake_pow`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowConfig {
+    pub enabled: bool,
+    pub base_difficulty: u8,
+    /// Obergrenze unter Last (Responder)
+    pub max_difficulty: u8,
+    pub free_rate_per_sec: usize,
+    /// Höchste Difficulty, die wir als Initiator noch lösen
+    pub max_solve_difficulty: u8,
+    /// Wartezeit auf Challenge (Initiator) bzw. Nonce (Responder)
+    pub timeout_ms: u64,
+}
+
+impl Default for PowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_difficulty: 8,
+            max_difficulty: 20,
+            free_rate_per_sec: 10,
+            max_solve_difficulty: DEFAULT_MAX_SOLVE_DIFFICULTY,
+            timeout_ms: 5_000,
+        }
+    }
+}
+
+impl PowConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_difficulty < self.base_difficulty {
+            return Err("max_difficulty must be >= base_difficulty".into());
+        }
+        // Sonst lehnen sich eigene Nodes unter Last gegenseitig ab
+        if self.max_difficulty > self.max_solve_difficulty {
+            return Err("max_difficulty must not exceed max_solve_difficulty".into());
+        }
+        if self.free_rate_per_sec == 0 || self.timeout_ms == 0 {
+            return Err("free_rate_per_sec and timeout_ms must be > 0".into());
+        }
+        Ok(())
+    }
+
+    pub fn gate(&self) -> PowGate {
+        PowGate::new(self.base_difficulty, self.max_difficulty, self.free_rate_per_sec)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+/// Difficulty-Steuerung des Responders.
+#[derive(Debug)]
+pub struct PowGate {
+    /// Difficulty bei normaler Last (0 => Puzzle ist trivial)
+    pub base_difficulty: u8,
+    pub max_difficulty: u8,
+    /// Verbindungen pro Sekunde, bis zu denen `base_difficulty` gilt
+    pub free_rate_per_sec: usize,
+    recent: Mutex<VecDeque<Instant>>,
+}
+
+impl PowGate {
+    pub fn new(base_difficulty: u8, max_difficulty: u8, free_rate_per_sec: usize) -> Self {
+        Self {
+            base_difficulty,
+            max_difficulty: max_difficulty.max(base_difficulty),
+            free_rate_per_sec: free_rate_per_sec.max(1),
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Aktuelle Difficulty: je Verdopplung der Rate über `free_rate_per_sec`
+    /// zwei Bit mehr (=> vierfacher Aufwand), gedeckelt bei `max_difficulty`.
+    pub fn current_difficulty(&self) -> u8 {
+        let rate = {
+            let mut recent = self.recent.lock().unwrap();
+            Self::prune(&mut recent);
+            recent.len()
+        };
+        if rate <= self.free_rate_per_sec {
+            return self.base_difficulty;
+        }
+        let ratio = rate as f64 / self.free_rate_per_sec as f64;
+        let extra = (ratio.log2().ceil() as u32).saturating_mul(2);
+        (self.base_difficulty as u32 + extra).min(self.max_difficulty as u32) as u8
+    }
+
+    fn prune(recent: &mut VecDeque<Instant>) {
+        while recent.front().map_or(false, |t| t.elapsed() > Duration::from_secs(1)) {
+            recent.pop_front();
+        }
+    }
+
+    /// Neue Challenge für eine eingehende Verbindung (zählt zur Rate).
+    pub fn issue(&self) -> PowChallenge {
+        let difficulty = self.current_difficulty();
+        self.recent.lock().unwrap().push_back(Instant::now());
+        let mut challenge = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut challenge);
+        debug!("PowGate => challenge difficulty={}", difficulty);
+        PowChallenge { difficulty, challenge }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_solution_accepted_insufficient_rejected() {
+        let ch = PowChallenge { difficulty: 10, challenge: [7u8; 16] };
+        let nonce = ch.solve();
+        assert!(ch.verify(nonce));
+
+        // Eine Nonce, die die Difficulty verfehlt
+        let bad = (0..).find(|n| !ch.verify(*n)).unwrap();
+        assert!(!ch.verify(bad));
+
+        let roundtrip = PowChallenge::from_bytes(&ch.to_bytes());
+        assert_eq!(roundtrip, ch);
+    }
+
+    #[test]
+    fn test_solve_capped_refuses_excessive_difficulty() {
+        let hostile = PowChallenge { difficulty: 255, challenge: [1u8; 16] };
+        assert_eq!(hostile.solve_capped(DEFAULT_MAX_SOLVE_DIFFICULTY), None);
+
+        let ok = PowChallenge { difficulty: 8, challenge: [1u8; 16] };
+        let nonce = ok.solve_capped(8).unwrap();
+        assert!(ok.verify(nonce));
+    }
+
+    #[test]
+    fn test_difficulty_rises_under_load() {
+        let gate = PowGate::new(4, 20, 10);
+        assert_eq!(gate.current_difficulty(), 4);
+        for _ in 0..10 {
+            gate.issue();
+        }
+        assert_eq!(gate.current_difficulty(), 4);
+        for _ in 0..30 {
+            gate.issue();
+        }
+        // 40 / 10 => 2 Verdopplungen => +4
+        assert_eq!(gate.current_difficulty(), 8);
+        for _ in 0..10_000 {
+            gate.issue();
+        }
+        assert_eq!(gate.current_difficulty(), 20);
+    }
 }
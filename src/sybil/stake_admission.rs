@@ -0,0 +1,523 @@
+///////////////////////////////////////
+/// my_DEX/src/sybil/stake_admission.rs
+///////////////////////////////////////
+//
+// Sybil-Schutz für das Fullnode-/Komitee-Set über Stake-Bonds:
+//  - Jede Identität braucht einen eigenen, vom Operator signierten Bond
+//    >= `min_bond`. Die Signatur deckt die laufende Bond-Nonce des Operators
+//    ab, ein abgefangener Bond lässt sich also nicht erneut einspielen. Der Betrag wird beim Bonden auf dem `StakeLedger` vom
+//    Konto des Operators gesperrt; ohne gedeckte Sperre kein Bond.
+//    N Identitäten binden also tatsächlich N * min_bond.
+//  - `unbond` (vom Operator signiert) gibt die Sperre wieder frei.
+//  - Nur gebondete, nicht geslashte Identitäten kommen ins Komitee; die
+//    Auswahl ist nach Stake gewichtet (siehe `proof_of_stake::Validator`).
+//  - `slash` verbrennt den gesamten Bond und entfernt die Identität. Scheitert
+//    das Verbrennen, bleibt der Betrag offen und `slash` kann wiederholt werden.
+//  - Aktiv über `stake_admission` in der Node-Config: Onboarding-Zertifikate
+//    (auto_committee) werden nur für gebondete Identitäten angenommen.
+
+use std::collections::HashMap;
+
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use tracing::{info, warn};
+
+use crate::consensus::proof_of_stake::Validator;
+use crate::consensus::vrf_committee_async::Node;
+use crate::error::DexError;
+use crate::settlement::advanced_settlement::{Asset, ChainSettlement, LedgerSettlement};
+use crate::settlement::secured_settlement::SettlementEngine;
+use crate::utils::canonical;
+
+/// Asset, in dem Stake auf dem Settlement-Ledger gesperrt wird.
+pub const STAKE_ASSET: &str = "DEX";
+
+/// Config-Abschnitt `stake_admission`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StakeAdmissionConfig {
+    pub enabled: bool,
+    pub min_bond: u64,
+}
+
+impl Default for StakeAdmissionConfig {
+    fn default() -> Self {
+        Self { enabled: false, min_bond: 1_000 }
+    }
+}
+
+impl StakeAdmissionConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.enabled && self.min_bond == 0 {
+            return Err("min_bond must be > 0".into());
+        }
+        Ok(())
+    }
+}
+
+/// Ledger, auf dem die Bonds tatsächlich gedeckt sind. Konten sind die
+/// hex-kodierten Operator-Keys.
+pub trait StakeLedger: Send + Sync {
+    /// Sperrt `amount` vom freien Guthaben; Fehler bei fehlender Deckung.
+    fn lock_stake(&mut self, account: &str, amount: u64) -> Result<(), DexError>;
+    /// Gibt gesperrten Stake wieder frei.
+    fn release_stake(&mut self, account: &str, amount: u64) -> Result<(), DexError>;
+    /// Vernichtet gesperrten Stake (Slashing).
+    fn burn_stake(&mut self, account: &str, amount: u64) -> Result<(), DexError>;
+}
+
+impl StakeLedger for SettlementEngine {
+    fn lock_stake(&mut self, account: &str, amount: u64) -> Result<(), DexError> {
+        self.lock_funds(account, STAKE_ASSET, amount as f64)
+    }
+
+    fn release_stake(&mut self, account: &str, amount: u64) -> Result<(), DexError> {
+        self.release_funds(account, STAKE_ASSET, amount as f64)
+    }
+
+    fn burn_stake(&mut self, account: &str, amount: u64) -> Result<(), DexError> {
+        let locked = self
+            .balances
+            .get_mut(account)
+            .and_then(|assets| assets.get_mut(STAKE_ASSET))
+            .filter(|entry| entry.1 >= amount as f64)
+            .ok_or_else(|| DexError::InsufficientBalance {
+                user: account.to_string(),
+                asset: format!("locked {}", STAKE_ASSET),
+            })?;
+        locked.1 -= amount as f64;
+        Ok(())
+    }
+}
+
+/// Bonds auf dem Ledger der AdvancedSettlementEngine (Stake als Token
+/// `STAKE_ASSET`).
+impl StakeLedger for LedgerSettlement {
+    fn lock_stake(&mut self, account: &str, amount: u64) -> Result<(), DexError> {
+        self.lock(account, &stake_asset(), amount as f64)
+    }
+
+    fn release_stake(&mut self, account: &str, amount: u64) -> Result<(), DexError> {
+        self.unlock(account, &stake_asset(), amount as f64)
+    }
+
+    fn burn_stake(&mut self, account: &str, amount: u64) -> Result<(), DexError> {
+        self.burn_locked(account, &stake_asset(), amount as f64)
+    }
+}
+
+fn stake_asset() -> Asset {
+    Asset::Erc20(STAKE_ASSET.to_string())
+}
+
+/// Nachricht, die der Operator für einen Bond signiert. `nonce` ist die
+/// aktuelle Bond-Nonce des Operators (`StakeBondRegistry::bond_nonce`).
+pub fn bond_signing_bytes(node_id: &str, amount: u64, nonce: u64) -> Result<Vec<u8>, DexError> {
+    canonical::signing_bytes("my_dex/stake/bond/v1", &(node_id, amount, nonce))
+}
+
+/// Nachricht, die der Operator zum Auflösen des Bonds Nr. `bond_seq` signiert.
+/// Die Sequenz verhindert, dass ein altes Unbond einen neuen Bond auflöst.
+pub fn unbond_signing_bytes(node_id: &str, bond_seq: u64) -> Result<Vec<u8>, DexError> {
+    canonical::signing_bytes("my_dex/stake/unbond/v1", &(node_id, bond_seq))
+}
+
+#[derive(Clone, Debug)]
+pub struct StakeBond {
+    pub node_id: String,
+    pub operator: PublicKey,
+    pub amount: u64,
+    pub slashed: bool,
+    /// Laufende Nummer dieses Bonds (für `unbond_signing_bytes`).
+    pub seq: u64,
+}
+
+impl StakeBond {
+    fn account(&self) -> String {
+        hex::encode(self.operator.as_bytes())
+    }
+}
+
+pub struct StakeBondRegistry {
+    pub min_bond: u64,
+    ledger: Box<dyn StakeLedger>,
+    bonds: HashMap<String, StakeBond>,
+    /// Operator-Konto => nächste erwartete Bond-Nonce
+    nonces: HashMap<String, u64>,
+    next_seq: u64,
+    burned: u64,
+}
+
+impl StakeBondRegistry {
+    pub fn new(min_bond: u64, ledger: Box<dyn StakeLedger>) -> Self {
+        Self { min_bond, ledger, bonds: HashMap::new(), nonces: HashMap::new(), next_seq: 0, burned: 0 }
+    }
+
+    /// Nonce, die der nächste Bond von `operator` signieren muss.
+    pub fn bond_nonce(&self, operator: &PublicKey) -> u64 {
+        self.nonces.get(&hex::encode(operator.as_bytes())).copied().unwrap_or(0)
+    }
+
+    /// Hinterlegt einen Bond. Die Signatur muss vom Operator über
+    /// `bond_signing_bytes(node_id, amount, nonce)` mit `nonce ==
+    /// bond_nonce(operator)` stammen, und der Betrag muss auf dem
+    /// Ledger-Konto des Operators gesperrt werden können. Ein erneuter Bond
+    /// desselben Operators passt die Sperre an. Liefert die Bond-Nummer.
+    pub fn bond(
+        &mut self,
+        node_id: &str,
+        operator: PublicKey,
+        amount: u64,
+        nonce: u64,
+        signature: &Signature,
+    ) -> Result<u64, DexError> {
+        if amount < self.min_bond {
+            return Err(DexError::Other(format!("Bond {} below minimum {}", amount, self.min_bond)));
+        }
+        let expected = self.bond_nonce(&operator);
+        if nonce != expected {
+            return Err(DexError::Other(format!("Stale bond nonce {} for {} (expected {})", nonce, node_id, expected)));
+        }
+        if operator.verify(&bond_signing_bytes(node_id, amount, nonce)?, signature).is_err() {
+            return Err(DexError::Other(format!("Invalid bond signature for {}", node_id)));
+        }
+        let account = hex::encode(operator.as_bytes());
+        let previous = match self.bonds.get(node_id) {
+            Some(existing) if existing.slashed => {
+                return Err(DexError::Other(format!("{} was slashed and cannot rebond", node_id)));
+            }
+            Some(existing) if existing.operator != operator => {
+                return Err(DexError::Other(format!("{} is bonded by another operator", node_id)));
+            }
+            Some(existing) => existing.amount,
+            None => 0,
+        };
+        if amount > previous {
+            self.ledger.lock_stake(&account, amount - previous)?;
+        } else if amount < previous {
+            self.ledger.release_stake(&account, previous - amount)?;
+        }
+        self.nonces.insert(account.clone(), nonce + 1);
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.bonds.insert(
+            node_id.to_string(),
+            StakeBond { node_id: node_id.to_string(), operator, amount, slashed: false, seq },
+        );
+        info!("StakeBond => {} gebondet mit {} (gesperrt auf {})", node_id, amount, account);
+        Ok(seq)
+    }
+
+    /// Löst einen Bond auf und gibt die Sperre frei; liefert den Betrag.
+    /// Geslashte Bonds bleiben als Sperrvermerk stehen.
+    pub fn unbond(&mut self, node_id: &str, signature: &Signature) -> Result<u64, DexError> {
+        let bond = self.bonds.get(node_id)
+            .ok_or_else(|| DexError::Other(format!("{} has no stake bond", node_id)))?;
+        if bond.slashed {
+            return Err(DexError::Other(format!("{} is slashed", node_id)));
+        }
+        if bond.operator.verify(&unbond_signing_bytes(node_id, bond.seq)?, signature).is_err() {
+            return Err(DexError::Other(format!("Invalid unbond signature for {}", node_id)));
+        }
+        let (account, amount) = (bond.account(), bond.amount);
+        self.ledger.release_stake(&account, amount)?;
+        self.bonds.remove(node_id);
+        info!("StakeBond => {} aufgelöst, {} freigegeben", node_id, amount);
+        Ok(amount)
+    }
+
+    /// Zulassungsprüfung beim Beitritt zum Fullnode-Set.
+    pub fn check_admission(&self, node_id: &str) -> Result<u64, DexError> {
+        match self.bonds.get(node_id) {
+            Some(b) if !b.slashed && b.amount >= self.min_bond => Ok(b.amount),
+            Some(b) if b.slashed => Err(DexError::Other(format!("{} is slashed", node_id))),
+            _ => Err(DexError::Other(format!("{} has no valid stake bond", node_id))),
+        }
+    }
+
+    /// Verbrennt den Bond bei Fehlverhalten; liefert den verbrannten Betrag.
+    /// Die Identität ist sofort gesperrt. Scheitert das Verbrennen auf dem
+    /// Ledger, bleibt der Betrag am Bond stehen (nicht in `total_burned`) und
+    /// ein erneuter Aufruf versucht es wieder.
+    pub fn slash(&mut self, node_id: &str, reason: &str) -> Result<u64, DexError> {
+        let bond = self.bonds.get_mut(node_id)
+            .ok_or_else(|| DexError::Other(format!("{} has no stake bond", node_id)))?;
+        bond.slashed = true;
+        if bond.amount == 0 {
+            return Ok(0);
+        }
+        let burned = bond.amount;
+        if let Err(e) = self.ledger.burn_stake(&bond.account(), burned) {
+            warn!("StakeBond => Stake von {} konnte nicht verbrannt werden: {}", node_id, e);
+            return Err(e);
+        }
+        bond.amount = 0;
+        self.burned += burned;
+        warn!("StakeBond => {} geslasht ({}), {} verbrannt", node_id, reason, burned);
+        Ok(burned)
+    }
+
+    pub fn total_burned(&self) -> u64 {
+        self.burned
+    }
+
+    /// Alle zugelassenen Identitäten mit ihrem Bond als Stake.
+    pub fn validators(&self) -> Vec<Validator> {
+        let mut out: Vec<Validator> = self
+            .bonds
+            .values()
+            .filter(|b| self.check_admission(&b.node_id).is_ok())
+            .map(|b| Validator { id: b.node_id.clone(), stake: b.amount })
+            .collect();
+        out.sort_by(|a, b| a.id.cmp(&b.id));
+        out
+    }
+
+    /// Anteil einer Identität am gesamten Komitee-Gewicht.
+    pub fn committee_weight(&self, node_id: &str) -> f64 {
+        let validators = self.validators();
+        let total: u64 = validators.iter().map(|v| v.stake).sum();
+        match validators.iter().find(|v| v.id == node_id) {
+            Some(v) if total > 0 => v.stake as f64 / total as f64,
+            _ => 0.0,
+        }
+    }
+
+    /// Deterministische, stake-gewichtete Auswahl von `size` Mitgliedern ohne
+    /// Zurücklegen (Efraimidis-Spirakis mit Hash statt Zufall).
+    pub fn select_committee(&self, seed: u64, round: u64, size: usize) -> Vec<Validator> {
+        let mut keyed: Vec<(f64, Validator)> = self
+            .validators()
+            .into_iter()
+            .map(|v| {
+                let mut hasher = Sha256::new();
+                hasher.update(seed.to_be_bytes());
+                hasher.update(round.to_be_bytes());
+                hasher.update(v.id.as_bytes());
+                let h = hasher.finalize();
+                let x = u64::from_be_bytes(h[..8].try_into().unwrap());
+                // u in (0,1]
+                let u = (x as f64 + 1.0) / (u64::MAX as f64 + 1.0);
+                (u.ln() / v.stake as f64, v)
+            })
+            .collect();
+        // Größter Key gewinnt (ln(u)/w ist <= 0)
+        keyed.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        keyed.into_iter().take(size).map(|(_, v)| v).collect()
+    }
+
+    /// Übernimmt die Bonds als Stake für den VRF-Komitee-Consensus und
+    /// entfernt Nodes ohne gültigen Bond.
+    pub fn apply_to_nodes(&self, nodes: Vec<Node>) -> Vec<Node> {
+        nodes
+            .into_iter()
+            .filter_map(|mut n| match self.check_admission(&n.node_id.to_string()) {
+                Ok(stake) => {
+                    n.stake = stake;
+                    Some(n)
+                }
+                Err(e) => {
+                    warn!("Komitee => Node {} abgelehnt: {}", n.node_id, e);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+
+    fn operator(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = PublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    fn account(kp: &Keypair) -> String {
+        hex::encode(kp.public.as_bytes())
+    }
+
+    /// Ledger, auf dem jeder Operator aus `funded` 10_000 freien Stake hat.
+    fn registry(min_bond: u64, funded: &[&Keypair]) -> StakeBondRegistry {
+        let mut ledger = SettlementEngine::new();
+        for kp in funded {
+            ledger.balances.entry(account(kp)).or_default().insert(STAKE_ASSET.to_string(), (10_000.0, 0.0));
+        }
+        StakeBondRegistry::new(min_bond, Box::new(ledger))
+    }
+
+    fn bond(reg: &mut StakeBondRegistry, kp: &Keypair, node_id: &str, amount: u64) -> Result<u64, DexError> {
+        let nonce = reg.bond_nonce(&kp.public);
+        let sig = kp.sign(&bond_signing_bytes(node_id, amount, nonce).unwrap());
+        reg.bond(node_id, kp.public, amount, nonce, &sig)
+    }
+
+    fn unbond_sig(kp: &Keypair, node_id: &str, seq: u64) -> Signature {
+        kp.sign(&unbond_signing_bytes(node_id, seq).unwrap())
+    }
+
+    #[test]
+    fn test_unbonded_identities_cannot_join() {
+        let op = operator(1);
+        let mut reg = registry(1_000, &[&op]);
+        bond(&mut reg, &op, "node_a", 1_000).unwrap();
+
+        // Zu wenig Stake
+        assert!(bond(&mut reg, &op, "sybil_1", 10).is_err());
+        // Signatur über anderen Betrag
+        let nonce = reg.bond_nonce(&op.public);
+        let sig = op.sign(&bond_signing_bytes("sybil_2", 5_000, nonce).unwrap());
+        assert!(reg.bond("sybil_2", op.public, 1_000, nonce, &sig).is_err());
+
+        assert!(reg.check_admission("sybil_1").is_err());
+        assert!(reg.check_admission("sybil_3").is_err());
+        let committee = reg.select_committee(7, 1, 10);
+        assert_eq!(committee.len(), 1);
+        assert_eq!(committee[0].id, "node_a");
+
+        let nodes = vec![Node::new(1, 0), Node::new(2, 0)];
+        bond(&mut reg, &op, "1", 2_000).unwrap();
+        let admitted = reg.apply_to_nodes(nodes);
+        assert_eq!(admitted.len(), 1);
+        assert_eq!(admitted[0].stake, 2_000);
+    }
+
+    #[test]
+    fn test_committee_weight_scales_with_stake() {
+        let (big, small) = (operator(1), operator(2));
+        let mut reg = registry(100, &[&big, &small]);
+        bond(&mut reg, &big, "big", 9_000).unwrap();
+        bond(&mut reg, &small, "small", 1_000).unwrap();
+        assert!((reg.committee_weight("big") - 0.9).abs() < 1e-9);
+
+        let mut big_first = 0;
+        for round in 0..500 {
+            if reg.select_committee(99, round, 1)[0].id == "big" {
+                big_first += 1;
+            }
+        }
+        // Erwartet ~90%
+        assert!(big_first > 400, "big selected {} / 500", big_first);
+    }
+
+    #[test]
+    fn test_slashing_burns_bond() {
+        let op = operator(3);
+        let mut reg = registry(100, &[&op]);
+        bond(&mut reg, &op, "evil", 500).unwrap();
+        assert_eq!(reg.slash("evil", "double vote").unwrap(), 500);
+        assert_eq!(reg.total_burned(), 500);
+        assert!(reg.check_admission("evil").is_err());
+        assert!(bond(&mut reg, &op, "evil", 500).is_err());
+        assert!(reg.validators().is_empty());
+    }
+
+    #[test]
+    fn test_bond_requires_locked_stake_and_unbond_releases_it() {
+        let (op, broke) = (operator(4), operator(5));
+        let mut reg = registry(1_000, &[&op]);
+
+        // Ohne Guthaben auf dem Ledger kein Bond, egal wie gut signiert
+        assert!(bond(&mut reg, &broke, "sybil", 1_000).is_err());
+        assert!(reg.check_admission("sybil").is_err());
+
+        // 10_000 Guthaben => genau zehn Identitäten à 1_000
+        for i in 0..10 {
+            bond(&mut reg, &op, &format!("n{}", i), 1_000).unwrap();
+        }
+        assert!(bond(&mut reg, &op, "n10", 1_000).is_err());
+        assert_eq!(reg.validators().len(), 10);
+
+        // Unbond nur mit Signatur über die aktuelle Bond-Nummer
+        let seq = bond(&mut reg, &op, "n0", 1_000).unwrap();
+        assert!(reg.unbond("n0", &unbond_sig(&op, "n0", seq + 1)).is_err());
+        assert!(reg.unbond("n0", &unbond_sig(&broke, "n0", seq)).is_err());
+        let old = unbond_sig(&op, "n0", seq);
+        assert_eq!(reg.unbond("n0", &old).unwrap(), 1_000);
+        assert!(reg.check_admission("n0").is_err());
+
+        // Freigegebener Stake reicht wieder für eine Identität; das alte
+        // Unbond gilt für den neuen Bond nicht
+        bond(&mut reg, &op, "n10", 1_000).unwrap();
+        assert!(bond(&mut reg, &op, "n0", 1_000).is_err());
+        reg.unbond("n9", &unbond_sig(&op, "n9", reg.bonds["n9"].seq)).unwrap();
+        let seq = bond(&mut reg, &op, "n0", 1_000).unwrap();
+        assert!(reg.unbond("n0", &old).is_err());
+        assert!(reg.unbond("n0", &unbond_sig(&op, "n0", seq)).is_ok());
+    }
+
+    #[test]
+    fn test_bond_signature_cannot_be_replayed() {
+        let op = operator(6);
+        let mut reg = registry(1_000, &[&op]);
+        let nonce = reg.bond_nonce(&op.public);
+        let sig = op.sign(&bond_signing_bytes("n0", 1_000, nonce).unwrap());
+        let seq = reg.bond("n0", op.public, 1_000, nonce, &sig).unwrap();
+        reg.unbond("n0", &unbond_sig(&op, "n0", seq)).unwrap();
+
+        // Abgefangener Bond erneut eingespielt => abgelehnt, Stake bleibt frei
+        assert!(reg.bond("n0", op.public, 1_000, nonce, &sig).is_err());
+        assert!(reg.check_admission("n0").is_err());
+        // Mit der neuen Nonce signiert geht es
+        bond(&mut reg, &op, "n0", 1_000).unwrap();
+        assert_eq!(reg.bond_nonce(&op.public), nonce + 2);
+    }
+
+    /// Ledger, dessen Burn immer scheitert (bis `burn_ok` gesetzt ist).
+    struct FailingBurn {
+        burn_ok: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl StakeLedger for FailingBurn {
+        fn lock_stake(&mut self, _: &str, _: u64) -> Result<(), DexError> {
+            Ok(())
+        }
+        fn release_stake(&mut self, _: &str, _: u64) -> Result<(), DexError> {
+            Ok(())
+        }
+        fn burn_stake(&mut self, account: &str, _: u64) -> Result<(), DexError> {
+            if self.burn_ok.load(std::sync::atomic::Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err(DexError::InsufficientBalance { user: account.to_string(), asset: STAKE_ASSET.into() })
+            }
+        }
+    }
+
+    #[test]
+    fn test_failed_burn_is_not_counted_and_can_be_retried() {
+        let op = operator(7);
+        let burn_ok = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let mut reg = StakeBondRegistry::new(100, Box::new(FailingBurn { burn_ok: burn_ok.clone() }));
+        bond(&mut reg, &op, "evil", 500).unwrap();
+
+        assert!(reg.slash("evil", "double vote").is_err());
+        assert_eq!(reg.total_burned(), 0);
+        // Trotzdem sofort aus dem Komitee
+        assert!(reg.check_admission("evil").is_err());
+
+        burn_ok.store(true, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(reg.slash("evil", "double vote").unwrap(), 500);
+        assert_eq!(reg.total_burned(), 500);
+        assert_eq!(reg.slash("evil", "double vote").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_ledger_settlement_backs_bonds() {
+        use crate::settlement::advanced_settlement::LedgerBalances;
+        let op = operator(8);
+        let balances: LedgerBalances = Default::default();
+        balances.lock().unwrap().entry(account(&op)).or_default().insert(stake_asset(), (1_500.0, 0.0));
+        let mut reg = StakeBondRegistry::new(1_000, Box::new(LedgerSettlement::new(balances.clone())));
+
+        bond(&mut reg, &op, "n0", 1_000).unwrap();
+        assert!(bond(&mut reg, &op, "n1", 1_000).is_err());
+        assert_eq!(balances.lock().unwrap()[&account(&op)][&stake_asset()], (500.0, 1_000.0));
+        reg.slash("n0", "equivocation").unwrap();
+        assert_eq!(balances.lock().unwrap()[&account(&op)][&stake_asset()], (500.0, 0.0));
+    }
+}
@@ -0,0 +1,445 @@
+///////////////////////////////////////////////////////////
+// my_dex/src/trade_history.rs
+///////////////////////////////////////////////////////////
+//
+// Trade-Historie und Candlesticks (OHLCV) pro Markt:
+//  - TradeHistory => Ringpuffer je Markt, gespeist aus dem MarketDataHub
+//    (`MarketDataEvent::Trade`), optional in DexDB persistiert
+//  - restore_all() => lädt beim Start die Trades aller persistierten Märkte
+//  - prune() => DexDB hält je Markt höchstens `capacity` Trades (wie der
+//    Puffer); record() räumt alle PRUNE_EVERY Trades den Markt auf
+//  - aggregate_candles() => OHLCV-Buckets für ein Intervall
+//  - trade_history_routes() => `GET /markets/:market/candles?interval=1m&from=&to=`
+//
+// Märkte enthalten "/" (BTC/USDT); im Pfad daher entweder URL-kodiert
+// (`BTC%2FUSDT`) oder mit "-" bzw. "_" als Trenner (`BTC-USDT`).
+// Zeitangaben in Unix-Sekunden, wie im TradeEvent.
+///////////////////////////////////////////////////////////
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+use crate::error::DexError;
+use crate::market_data::{MarketDataEvent, MarketDataHub, TradeEvent};
+use crate::storage::db_layer::DexDB;
+use crate::utils::lock::LockRecover;
+
+/// Trades pro Markt im Speicher.
+pub const DEFAULT_HISTORY_CAPACITY: usize = 100_000;
+
+/// Maximale Anzahl Candles pro Antwort; längere Bereiche werden gekappt.
+pub const MAX_CANDLES_PER_REQUEST: u64 = 1000;
+
+const DB_PREFIX: &str = "trade_history/";
+
+/// Nach so vielen persistierten Trades wird der betroffene Markt in der DB gekürzt.
+const PRUNE_EVERY: u64 = 1000;
+
+/// Zerlegt `trade_history/<market>/<ts>/<seq>` in (market, seq). Der Markt
+/// enthält selbst "/", daher von hinten.
+fn key_parts(key: &str) -> Option<(&str, u64)> {
+    let mut parts = key.strip_prefix(DB_PREFIX)?.rsplitn(3, '/');
+    let seq = parts.next()?.parse().ok()?;
+    let _ts = parts.next()?;
+    Some((parts.next()?, seq))
+}
+
+/// Unterstützte Intervalle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandleInterval {
+    M1,
+    M5,
+    M15,
+    H1,
+    H4,
+    D1,
+}
+
+impl CandleInterval {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "1m" => Some(Self::M1),
+            "5m" => Some(Self::M5),
+            "15m" => Some(Self::M15),
+            "1h" => Some(Self::H1),
+            "4h" => Some(Self::H4),
+            "1d" => Some(Self::D1),
+            _ => None,
+        }
+    }
+
+    pub fn secs(self) -> u64 {
+        match self {
+            Self::M1 => 60,
+            Self::M5 => 300,
+            Self::M15 => 900,
+            Self::H1 => 3600,
+            Self::H4 => 14_400,
+            Self::D1 => 86_400,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Candle {
+    /// Beginn des Buckets (Unix-Sekunden, auf das Intervall ausgerichtet)
+    pub open_time: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Basis-Volumen
+    pub volume: f64,
+    pub trades: u64,
+}
+
+/// Aggregiert Trades (nach Zeit sortiert) im Bereich [from, to) zu Candles.
+/// Buckets ohne Trades werden ausgelassen.
+pub fn aggregate_candles(trades: &[TradeEvent], interval_secs: u64, from: u64, to: u64) -> Vec<Candle> {
+    let mut out: Vec<Candle> = Vec::new();
+    for t in trades.iter().filter(|t| t.timestamp >= from && t.timestamp < to) {
+        let open_time = t.timestamp - t.timestamp % interval_secs;
+        match out.last_mut() {
+            Some(c) if c.open_time == open_time => {
+                c.high = c.high.max(t.price);
+                c.low = c.low.min(t.price);
+                c.close = t.price;
+                c.volume += t.quantity;
+                c.trades += 1;
+            }
+            _ => out.push(Candle {
+                open_time,
+                open: t.price,
+                high: t.price,
+                low: t.price,
+                close: t.price,
+                volume: t.quantity,
+                trades: 1,
+            }),
+        }
+    }
+    out
+}
+
+/// Normalisiert "BTC-USDT" / "BTC_USDT" zu "BTC/USDT".
+pub fn normalize_market(raw: &str) -> String {
+    if raw.contains('/') {
+        return raw.to_ascii_uppercase();
+    }
+    raw.replacen(|c| c == '-' || c == '_', "/", 1).to_ascii_uppercase()
+}
+
+/// Ringpuffer der letzten Trades je Markt. Klone teilen denselben Speicher.
+#[derive(Clone)]
+pub struct TradeHistory {
+    markets: Arc<Mutex<HashMap<String, VecDeque<TradeEvent>>>>,
+    capacity: usize,
+    db: Option<Arc<Mutex<DexDB>>>,
+    seq: Arc<Mutex<u64>>,
+}
+
+impl TradeHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            markets: Arc::new(Mutex::new(HashMap::new())),
+            capacity: capacity.max(1),
+            db: None,
+            seq: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// Persistiert jeden Trade zusätzlich in DexDB.
+    pub fn with_db(mut self, db: Arc<Mutex<DexDB>>) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    fn push_mem(&self, trade: TradeEvent) {
+        let mut markets = self.markets.lock().unwrap();
+        let buf = markets.entry(trade.market.clone()).or_default();
+        // Trades kommen praktisch immer in Zeitreihenfolge; sonst einsortieren
+        let pos = buf.iter().rposition(|t| t.timestamp <= trade.timestamp).map(|i| i + 1).unwrap_or(0);
+        buf.insert(pos, trade);
+        while buf.len() > self.capacity {
+            buf.pop_front();
+        }
+    }
+
+    pub fn record(&self, trade: TradeEvent) -> Result<(), DexError> {
+        if let Some(db) = &self.db {
+            let seq = {
+                let mut s = self.seq.lock().unwrap();
+                *s += 1;
+                *s
+            };
+            let key = format!("{}{}/{:020}/{:010}", DB_PREFIX, trade.market, trade.timestamp, seq);
+            db.lock_recover().store_struct(&key, &trade)?;
+            if seq % PRUNE_EVERY == 0 {
+                self.prune_market(&trade.market)?;
+            }
+        }
+        self.push_mem(trade);
+        Ok(())
+    }
+
+    /// Löscht in der DB die ältesten Trades von `market` über `capacity` hinaus.
+    fn prune_market(&self, market: &str) -> Result<usize, DexError> {
+        let Some(db) = &self.db else { return Ok(0) };
+        let db = db.lock_recover();
+        let mut keys = db.list_keys_with_prefix(&format!("{}{}/", DB_PREFIX, market))?;
+        // Zeitstempel und Sequenz sind nullgefüllt => lexikografisch = zeitlich
+        keys.sort();
+        let excess = keys.len().saturating_sub(self.capacity);
+        for key in &keys[..excess] {
+            db.delete_key(key)?;
+        }
+        if excess > 0 {
+            debug!("TradeHistory => {} alte Trades von {} gelöscht", excess, market);
+        }
+        Ok(excess)
+    }
+
+    /// Persistierte Märkte (aus den DB-Keys).
+    fn persisted_markets(&self) -> Result<BTreeSet<String>, DexError> {
+        let Some(db) = &self.db else { return Ok(BTreeSet::new()) };
+        let keys = db.lock_recover().list_keys_with_prefix(DB_PREFIX)?;
+        Ok(keys.iter().filter_map(|k| key_parts(k)).map(|(m, _)| m.to_string()).collect())
+    }
+
+    /// Kürzt alle persistierten Märkte auf `capacity` Trades.
+    pub fn prune(&self) -> Result<usize, DexError> {
+        let mut n = 0;
+        for market in self.persisted_markets()? {
+            n += self.prune_market(&market)?;
+        }
+        Ok(n)
+    }
+
+    /// `restore` für jeden persistierten Markt; liefert die Anzahl geladener Trades.
+    pub fn restore_all(&self) -> Result<usize, DexError> {
+        let mut n = 0;
+        for market in self.persisted_markets()? {
+            n += self.restore(&market)?;
+        }
+        Ok(n)
+    }
+
+    /// Lädt persistierte Trades von `market` (die letzten `capacity`) in den Puffer.
+    pub fn restore(&self, market: &str) -> Result<usize, DexError> {
+        let Some(db) = &self.db else { return Ok(0) };
+        let entries = db.lock_recover().list_entries_with_prefix(&format!("{}{}/", DB_PREFIX, market))?;
+        // Neue Keys müssen hinter allen vorhandenen liegen, auch nach prune()
+        if let Some(max_seq) = entries.iter().filter_map(|(k, _)| key_parts(k)).map(|(_, seq)| seq).max() {
+            let mut seq = self.seq.lock().unwrap();
+            *seq = (*seq).max(max_seq);
+        }
+        let skip = entries.len().saturating_sub(self.capacity);
+        let mut n = 0;
+        for (k, v) in entries.into_iter().skip(skip) {
+            let trade: TradeEvent = bincode::deserialize(&v)
+                .map_err(|e| DexError::Other(format!("trade history {}: {:?}", k, e)))?;
+            self.push_mem(trade);
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    pub fn trades(&self, market: &str, from: u64, to: u64) -> Vec<TradeEvent> {
+        let markets = self.markets.lock().unwrap();
+        markets
+            .get(market)
+            .map(|buf| buf.iter().filter(|t| t.timestamp >= from && t.timestamp < to).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn candles(&self, market: &str, interval: CandleInterval, from: u64, to: u64) -> Vec<Candle> {
+        aggregate_candles(&self.trades(market, from, to), interval.secs(), from, to)
+    }
+
+    /// Abonniert den Hub und schreibt jeden Trade mit.
+    pub fn spawn_feed(&self, hub: &MarketDataHub) -> tokio::task::JoinHandle<()> {
+        let mut rx = hub.subscribe();
+        let history = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(MarketDataEvent::Trade(t)) => {
+                        if let Err(e) = history.record(t) {
+                            warn!("TradeHistory => Persistenz fehlgeschlagen: {:?}", e);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("TradeHistory => {} Marktdaten-Events verpasst", n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            debug!("TradeHistory => Feed beendet");
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CandleQuery {
+    pub interval: Option<String>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+/// Router mit `/markets/:market/candles`; lässt sich in jeden Router mergen.
+pub fn trade_history_routes<S>(history: TradeHistory) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/markets/:market/candles", get(get_candles))
+        .with_state(history)
+}
+
+/// Bereich normalisieren: `to` default jetzt, `from` default `to` minus
+/// MAX_CANDLES_PER_REQUEST Intervalle; zu lange Bereiche werden auf die
+/// jüngsten MAX_CANDLES_PER_REQUEST Intervalle gekappt.
+pub fn clamp_range(interval: CandleInterval, from: Option<u64>, to: Option<u64>, now: u64) -> (u64, u64) {
+    let max_span = interval.secs() * MAX_CANDLES_PER_REQUEST;
+    let to = to.unwrap_or(now);
+    let from = from.unwrap_or_else(|| to.saturating_sub(max_span));
+    (from.max(to.saturating_sub(max_span)), to)
+}
+
+async fn get_candles(
+    Path(market): Path<String>,
+    Query(q): Query<CandleQuery>,
+    State(history): State<TradeHistory>,
+) -> impl IntoResponse {
+    let raw = q.interval.as_deref().unwrap_or("1m");
+    let Some(interval) = CandleInterval::parse(raw) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "success": false, "message": format!("unsupported interval {}", raw) })),
+        );
+    };
+    if let (Some(from), Some(to)) = (q.from, q.to) {
+        if from >= to {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "success": false, "message": "from must be < to" })),
+            );
+        }
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (from, to) = clamp_range(interval, q.from, q.to, now);
+    let market = normalize_market(&market);
+    let candles = history.candles(&market, interval, from, to);
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "success": true,
+            "data": { "market": market, "interval": raw, "from": from, "to": to, "candles": candles },
+        })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::db_layer::InMemoryDb;
+
+    fn trade(ts: u64, price: f64, qty: f64) -> TradeEvent {
+        TradeEvent {
+            market: "BTC/USDT".into(),
+            buy_order_id: format!("b{}", ts),
+            sell_order_id: format!("s{}", ts),
+            quantity: qty,
+            price,
+            timestamp: ts,
+        }
+    }
+
+    #[test]
+    fn test_ohlcv_aggregation() {
+        let trades = vec![
+            trade(60, 100.0, 1.0),
+            trade(75, 105.0, 0.5),
+            trade(90, 98.0, 2.0),
+            trade(119, 101.0, 1.5),
+            trade(120, 110.0, 1.0),
+            // Lücke 180..240 => kein Candle
+            trade(250, 90.0, 3.0),
+        ];
+        let c = aggregate_candles(&trades, 60, 0, 300);
+        assert_eq!(c.len(), 3);
+        assert_eq!(
+            c[0],
+            Candle { open_time: 60, open: 100.0, high: 105.0, low: 98.0, close: 101.0, volume: 5.0, trades: 4 }
+        );
+        assert_eq!((c[1].open_time, c[1].open, c[1].close, c[1].volume), (120, 110.0, 110.0, 1.0));
+        assert_eq!((c[2].open_time, c[2].low, c[2].trades), (240, 90.0, 1));
+
+        // 5m-Intervall fasst alles zusammen, `to` ist exklusiv
+        let c = aggregate_candles(&trades, 300, 0, 250);
+        assert_eq!(c.len(), 1);
+        assert_eq!((c[0].open, c[0].high, c[0].low, c[0].close), (100.0, 110.0, 98.0, 110.0));
+        assert_eq!(c[0].volume, 6.0);
+    }
+
+    #[test]
+    fn test_history_ring_buffer_persistence_and_range_cap() {
+        let db = DexDB { rocks: None, fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))) };
+        let db = Arc::new(Mutex::new(db));
+        let history = TradeHistory::new(3).with_db(db.clone());
+        for (i, ts) in [10u64, 70, 20, 130, 200].iter().enumerate() {
+            history.record(trade(*ts, 100.0 + i as f64, 1.0)).unwrap();
+        }
+        // Nur die letzten drei (zeitlich sortiert) bleiben im Speicher
+        let ts: Vec<u64> = history.trades("BTC/USDT", 0, u64::MAX).iter().map(|t| t.timestamp).collect();
+        assert_eq!(ts, vec![70, 130, 200]);
+
+        let restored = TradeHistory::new(10).with_db(db);
+        assert_eq!(restored.restore("BTC/USDT").unwrap(), 5);
+        assert_eq!(restored.candles("BTC/USDT", CandleInterval::M1, 0, 300).len(), 4);
+
+        assert_eq!(normalize_market("btc-usdt"), "BTC/USDT");
+        let (from, to) = clamp_range(CandleInterval::M1, Some(0), Some(1_000_000), 0);
+        assert_eq!((from, to), (1_000_000 - 60 * MAX_CANDLES_PER_REQUEST, 1_000_000));
+        assert!(CandleInterval::parse("7m").is_none());
+    }
+
+    #[test]
+    fn test_restore_all_markets_and_prune() {
+        let db = DexDB { rocks: None, fallback_mem: Some(Arc::new(Mutex::new(InMemoryDb::default()))) };
+        let db = Arc::new(Mutex::new(db));
+        let history = TradeHistory::new(2).with_db(db.clone());
+        for ts in [10u64, 20, 30] {
+            history.record(trade(ts, 100.0, 1.0)).unwrap();
+        }
+        history.record(TradeEvent { market: "ETH/USDT".into(), ..trade(40, 5.0, 2.0) }).unwrap();
+
+        // Ältester BTC-Trade über der Kapazität fliegt aus der DB
+        assert_eq!(history.prune().unwrap(), 1);
+        assert_eq!(history.prune().unwrap(), 0);
+
+        let restored = TradeHistory::new(10).with_db(db.clone());
+        assert_eq!(restored.restore_all().unwrap(), 3);
+        let ts: Vec<u64> = restored.trades("BTC/USDT", 0, u64::MAX).iter().map(|t| t.timestamp).collect();
+        assert_eq!(ts, vec![20, 30]);
+        assert_eq!(restored.trades("ETH/USDT", 0, u64::MAX).len(), 1);
+
+        // Neue Keys überschreiben keine vorhandenen (Sequenz aus den Keys)
+        restored.record(trade(30, 101.0, 1.0)).unwrap();
+        let keys = db.lock().unwrap().list_keys_with_prefix("trade_history/BTC/USDT/").unwrap();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(key_parts("trade_history/BTC/USDT/00000000000000000030/0000000004"), Some(("BTC/USDT", 4)));
+    }
+}
@@ -99,15 +99,23 @@ impl TradingService {
 
     /// 1) Order-Placement (Lock Dex-Funds, dann ins Orderbuch).
     pub fn place_order(&self, req: &TradeOrderRequest) -> Result<(), DexError> {
+        // Sanktions-Screening (Land + Wallet-Adressen des Accounts)
+        self.accounts_mgr.screen_sanctions(&req.user_id)?;
+        self.accounts_mgr.ensure_can_trade(&req.user_id)?;
+
         // Dex-Balance-Check
         let free_bal = self
             .accounts_mgr
             .check_free_balance(&req.user_id, &req.coin_to_sell)?;
         if free_bal < req.amount {
-            return Err(DexError::Other(format!(
+            warn!(
                 "Nicht genug Guthaben: user={}, coin={}, free={}, needed={}",
                 req.user_id, req.coin_to_sell, free_bal, req.amount
-            )));
+            );
+            return Err(DexError::InsufficientBalance {
+                user: req.user_id.clone(),
+                asset: req.coin_to_sell.clone(),
+            });
         }
 
         // Lock Dex Funds
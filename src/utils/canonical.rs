@@ -0,0 +1,117 @@
+////////////////////////////////////////////////////////
+// my_DEX/src/utils/canonical.rs
+////////////////////////////////////////////////////////
+//
+// Kanonische Serialisierung für alles, was signiert oder verifiziert wird.
+//
+// Deterministisches CBOR nach RFC 8949, Abschnitt 4.2.1:
+//  - Integer/Längen immer in kürzester Form
+//  - Map-Keys bytweise sortiert nach ihrer eigenen Kodierung (Struct-Felder
+//    werden zu Text-Keys => Feldreihenfolge im Code spielt keine Rolle,
+//    HashMap-Reihenfolge ebenso wenig)
+//  - Floats als IEEE-754-Wert in kürzester verlustfreier Breite, -0.0 => 0.0,
+//    NaN wird abgelehnt. Kein Umweg über `Display`, also unabhängig von
+//    Formatierung und Locale.
+//
+// Vor die Nutzlast kommt ein Domain-Tag (z. B. "my_dex/order/v1"), damit eine
+// Signatur nie für einen anderen Nachrichtentyp gültig ist.
+
+use ciborium::value::Value;
+use serde::Serialize;
+
+use crate::error::DexError;
+
+fn encoding_error(e: impl std::fmt::Debug) -> DexError {
+    DexError::Other(format!("Canonical encoding failed: {:?}", e))
+}
+
+fn encode(value: &Value) -> Result<Vec<u8>, DexError> {
+    let mut out = Vec::new();
+    ciborium::ser::into_writer(value, &mut out).map_err(encoding_error)?;
+    Ok(out)
+}
+
+fn canonicalize(value: Value) -> Result<Value, DexError> {
+    Ok(match value {
+        Value::Float(f) if f.is_nan() => return Err(DexError::Other("Canonical encoding: NaN is not allowed".into())),
+        Value::Float(f) if f == 0.0 => Value::Float(0.0),
+        Value::Array(items) => Value::Array(items.into_iter().map(canonicalize).collect::<Result<_, _>>()?),
+        Value::Tag(tag, inner) => Value::Tag(tag, Box::new(canonicalize(*inner)?)),
+        Value::Map(entries) => {
+            let mut keyed = entries
+                .into_iter()
+                .map(|(k, v)| {
+                    let k = canonicalize(k)?;
+                    Ok((encode(&k)?, k, canonicalize(v)?))
+                })
+                .collect::<Result<Vec<_>, DexError>>()?;
+            keyed.sort_by(|a, b| a.0.cmp(&b.0));
+            if keyed.windows(2).any(|w| w[0].0 == w[1].0) {
+                return Err(DexError::Other("Canonical encoding: duplicate map key".into()));
+            }
+            Value::Map(keyed.into_iter().map(|(_, k, v)| (k, v)).collect())
+        }
+        other => other,
+    })
+}
+
+/// Deterministische CBOR-Bytes von `value`.
+pub fn canonical_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, DexError> {
+    let value = Value::serialized(value).map_err(encoding_error)?;
+    encode(&canonicalize(value)?)
+}
+
+/// Zu signierende Bytes: `[domain, value]` kanonisch kodiert.
+pub fn signing_bytes<T: Serialize + ?Sized>(domain: &str, value: &T) -> Result<Vec<u8>, DexError> {
+    canonical_bytes(&(domain, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Serialize)]
+    struct Fields {
+        b: Vec<Option<bool>>,
+        a: u64,
+    }
+
+    #[test]
+    fn test_stable_bytes_independent_of_field_and_insertion_order() {
+        // Felder sortiert (a vor b), Integer in kürzester Form
+        let bytes = canonical_bytes(&Fields { b: vec![Some(true), None], a: 1 }).unwrap();
+        assert_eq!(hex::encode(&bytes), "a2616101616282f5f6");
+
+        let mut forward = HashMap::new();
+        let mut backward = HashMap::new();
+        for i in 0..64u32 {
+            forward.insert(format!("k{}", i), i);
+            backward.insert(format!("k{}", 63 - i), 63 - i);
+        }
+        let first = canonical_bytes(&forward).unwrap();
+        for _ in 0..10 {
+            assert_eq!(canonical_bytes(&forward).unwrap(), first);
+        }
+        assert_eq!(canonical_bytes(&backward).unwrap(), first);
+    }
+
+    #[test]
+    fn test_float_quantities_do_not_shift_bytes() {
+        let computed = 0.1_f64 + 0.2;
+        let literal: f64 = "0.30000000000000004".parse().unwrap();
+        assert_eq!(canonical_bytes(&computed).unwrap(), canonical_bytes(&literal).unwrap());
+        assert_eq!(canonical_bytes(&-0.0_f64).unwrap(), canonical_bytes(&0.0_f64).unwrap());
+        // Binärwert statt Dezimalstring: 1.5 => Half-Float 0x3e00
+        assert_eq!(canonical_bytes(&1.5_f64).unwrap(), vec![0xf9, 0x3e, 0x00]);
+        assert_eq!(canonical_bytes(&1.5_f32).unwrap(), canonical_bytes(&1.5_f64).unwrap());
+        assert!(canonical_bytes(&f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_domain_separation() {
+        let a = signing_bytes("my_dex/order/v1", &42u64).unwrap();
+        let b = signing_bytes("my_dex/fault/v1", &42u64).unwrap();
+        assert_ne!(a, b);
+    }
+}
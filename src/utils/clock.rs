@@ -0,0 +1,114 @@
+//////////////////////////////////////////
+// my_DEX/src/utils/clock.rs
+//////////////////////////////////////////
+//
+// Austauschbare Zeitquelle für zeitabhängige Logik (Order-Ablauf,
+// HTLC-Timelocks, Token-Bucket-Refill, Watchdog-Zeitstempel).
+//
+//  - SystemClock: echte Uhr (SystemTime + Instant), Default überall.
+//  - MockClock:   wird nur per `advance`/`set_unix_secs` bewegt, damit Tests
+//                 Ablauf und Timeouts ohne sleep deterministisch prüfen.
+//
+// Komponenten halten ein `SharedClock` (Arc<dyn Clock>) und bekommen es
+// über `with_clock(..)` injiziert.
+//////////////////////////////////////////
+
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub trait Clock: Send + Sync + Debug {
+    /// Wanduhr in UNIX-Millisekunden.
+    fn unix_millis(&self) -> u64;
+
+    /// Monotone Zeit seit einem festen Ursprung (für Intervalle/Refill).
+    fn monotonic(&self) -> Duration;
+
+    fn unix_secs(&self) -> u64 {
+        self.unix_millis() / 1000
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Die echte Uhr.
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn unix_millis(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+    }
+
+    fn monotonic(&self) -> Duration {
+        static ORIGIN: OnceLock<Instant> = OnceLock::new();
+        ORIGIN.get_or_init(Instant::now).elapsed()
+    }
+}
+
+/// Manuell vorgestellte Uhr für Tests. Klone teilen dieselbe Zeit.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    unix_millis: Arc<AtomicU64>,
+    monotonic_nanos: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    pub fn new(start_unix_secs: u64) -> Self {
+        Self {
+            unix_millis: Arc::new(AtomicU64::new(start_unix_secs * 1000)),
+            monotonic_nanos: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Stellt Wanduhr und monotone Zeit um `by` vor.
+    pub fn advance(&self, by: Duration) {
+        self.unix_millis.fetch_add(by.as_millis() as u64, Ordering::SeqCst);
+        self.monotonic_nanos.fetch_add(by.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    /// Setzt nur die Wanduhr (z.B. Sprung durch NTP); monoton bleibt unberührt.
+    pub fn set_unix_secs(&self, secs: u64) {
+        self.unix_millis.store(secs * 1000, Ordering::SeqCst);
+    }
+
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(self.clone())
+    }
+}
+
+impl Clock for MockClock {
+    fn unix_millis(&self) -> u64 {
+        self.unix_millis.load(Ordering::SeqCst)
+    }
+
+    fn monotonic(&self) -> Duration {
+        Duration::from_nanos(self.monotonic_nanos.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_only_moves_when_advanced() {
+        let clock = MockClock::new(1_700_000_000);
+        let shared = clock.shared();
+        assert_eq!(shared.unix_secs(), 1_700_000_000);
+        assert_eq!(shared.monotonic(), Duration::ZERO);
+
+        clock.advance(Duration::from_millis(1_500));
+        assert_eq!(shared.unix_millis(), 1_700_000_001_500);
+        assert_eq!(shared.monotonic(), Duration::from_millis(1_500));
+
+        clock.set_unix_secs(1_600_000_000);
+        assert_eq!(shared.unix_secs(), 1_600_000_000);
+        assert_eq!(shared.monotonic(), Duration::from_millis(1_500));
+    }
+}
@@ -0,0 +1,319 @@
+//////////////////////////////////////////////////////
+// my_DEX/src/utils/geoip_and_ntp.rs
+//////////////////////////////////////////////////////
+
+// NTP-Offset-Messung + Clock-Skew-Schutz, GeoIP-Lookup (Land + ASN).
+//
+// Der ClockSkewGuard hält den zuletzt gemessenen Offset (NTP-Median minus
+// lokale Uhr). Damit werden
+//   - eingehende Orders/Nachrichten abgelehnt, deren Zeitstempel mehr als
+//     `max_skew_ms` in der Zukunft oder mehr als `max_age_ms` in der
+//     Vergangenheit liegt (verzögerte Zustellung ist legitim, daher das
+//     größere Fenster nach hinten),
+//   - Blockproduktion und HTLC-Refunds verweigert, solange die lokale Uhr
+//     selbst zu weit daneben liegt.
+//
+// GeoIpResolver liefert Land/ASN einer IP, z. B. aus den MaxMind-Datenbanken
+// (GeoLite2-Country + GeoLite2-ASN). Genutzt für die Peer-Diversität in
+// network::peer_management.
+//
+// (c) Ihr DEX-Projekt
+
+use std::collections::HashMap;
+use std::net::{IpAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::error::DexError;
+use crate::utils::hlc::{set_time_offset_ms, system_ms};
+
+/// Standard-Toleranz, falls nichts konfiguriert ist.
+pub const DEFAULT_MAX_CLOCK_SKEW_MS: u64 = 5_000;
+
+/// Standard-Höchstalter eingehender Zeitstempel.
+pub const DEFAULT_MAX_MESSAGE_AGE_MS: u64 = 300_000;
+
+#[derive(Debug)]
+pub struct ClockSkewGuard {
+    max_skew_ms: u64,
+    max_age_ms: u64,
+    offset_ms: AtomicI64,
+    measured: AtomicBool,
+}
+
+impl ClockSkewGuard {
+    pub fn new(max_skew_ms: u64) -> Self {
+        Self {
+            max_skew_ms,
+            max_age_ms: DEFAULT_MAX_MESSAGE_AGE_MS.max(max_skew_ms),
+            offset_ms: AtomicI64::new(0),
+            measured: AtomicBool::new(false),
+        }
+    }
+
+    /// Höchstalter nach hinten; nie kleiner als die Toleranz nach vorn.
+    pub fn with_max_age_ms(mut self, max_age_ms: u64) -> Self {
+        self.max_age_ms = max_age_ms.max(self.max_skew_ms);
+        self
+    }
+
+    pub fn max_skew_ms(&self) -> u64 {
+        self.max_skew_ms
+    }
+
+    /// Neuer Messwert: NTP-Zeit minus lokale Zeit.
+    pub fn record_offset(&self, offset_ms: i64) {
+        self.offset_ms.store(offset_ms, Ordering::Relaxed);
+        self.measured.store(true, Ordering::Relaxed);
+    }
+
+    /// Abweichung der lokalen Uhr; None, solange noch nie gemessen wurde.
+    pub fn local_skew_ms(&self) -> Option<i64> {
+        if self.measured.load(Ordering::Relaxed) {
+            Some(self.offset_ms.load(Ordering::Relaxed))
+        } else {
+            None
+        }
+    }
+
+    /// Systemuhr + gemessener Offset.
+    pub fn corrected_now_ms(&self) -> u64 {
+        let now = system_ms() as i64 + self.offset_ms.load(Ordering::Relaxed);
+        now.max(0) as u64
+    }
+
+    /// Prüft einen fremden Zeitstempel (ms) gegen die korrigierte Zeit.
+    pub fn check_timestamp_ms(&self, ts_ms: u64) -> Result<(), DexError> {
+        self.check_timestamp_ms_at(ts_ms, self.corrected_now_ms())
+    }
+
+    pub fn check_timestamp_ms_at(&self, ts_ms: u64, now_ms: u64) -> Result<(), DexError> {
+        let skew = ts_ms as i64 - now_ms as i64;
+        if skew < 0 && skew.unsigned_abs() > self.max_age_ms {
+            return Err(DexError::ClockSkew { skew_ms: skew, max_ms: self.max_age_ms });
+        }
+        self.check_not_future_ms_at(ts_ms, now_ms)
+    }
+
+    /// Nur die Grenze nach vorn, z. B. für nachgeladene oder gemergte Daten,
+    /// die beliebig alt sein dürfen.
+    pub fn check_not_future_ms(&self, ts_ms: u64) -> Result<(), DexError> {
+        self.check_not_future_ms_at(ts_ms, self.corrected_now_ms())
+    }
+
+    pub fn check_not_future_ms_at(&self, ts_ms: u64, now_ms: u64) -> Result<(), DexError> {
+        let skew = ts_ms as i64 - now_ms as i64;
+        if skew > self.max_skew_ms as i64 {
+            return Err(DexError::ClockSkew { skew_ms: skew, max_ms: self.max_skew_ms });
+        }
+        Ok(())
+    }
+
+    /// Zeitstempel in Sekunden (Orders, Blöcke).
+    pub fn check_timestamp_secs(&self, ts_secs: u64) -> Result<(), DexError> {
+        self.check_timestamp_ms(ts_secs.saturating_mul(1000))
+    }
+
+    /// Lokale Uhr innerhalb der Toleranz? Ohne Messung => nicht ok.
+    pub fn ensure_local_clock_ok(&self) -> Result<(), DexError> {
+        match self.local_skew_ms() {
+            Some(off) if off.unsigned_abs() <= self.max_skew_ms => Ok(()),
+            Some(off) => Err(DexError::ClockSkew { skew_ms: off, max_ms: self.max_skew_ms }),
+            None => Err(DexError::Other("Local clock not yet verified against NTP".into())),
+        }
+    }
+}
+
+impl Default for ClockSkewGuard {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CLOCK_SKEW_MS)
+    }
+}
+
+/// Offset (ms) gegen einen einzelnen NTP-Server.
+pub fn query_ntp_offset_ms(server: &str) -> Result<i64> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_read_timeout(Some(Duration::from_secs(3)))?;
+    let res = sntpc::simple_get_time(server, socket)
+        .map_err(|e| anyhow!("NTP query {} failed: {:?}", server, e))?;
+    Ok(res.offset() / 1000)
+}
+
+/// Median-Offset über alle erreichbaren Server.
+pub async fn measure_ntp_offset_ms(servers: &[String]) -> Result<i64> {
+    let mut offsets = Vec::new();
+    for srv in servers {
+        let s = srv.clone();
+        match tokio::task::spawn_blocking(move || query_ntp_offset_ms(&s)).await? {
+            Ok(off) => {
+                debug!("NTP server={} => offset={}ms", srv, off);
+                offsets.push(off);
+            }
+            Err(e) => warn!("NTP server={} => {:?}", srv, e),
+        }
+    }
+    if offsets.is_empty() {
+        return Err(anyhow!("All NTP queries failed"));
+    }
+    offsets.sort_unstable();
+    Ok(offsets[offsets.len() / 2])
+}
+
+/// Misst periodisch den Offset und aktualisiert `guard`, bis `token` abbricht.
+pub async fn run_skew_monitor(
+    guard: Arc<ClockSkewGuard>,
+    servers: Vec<String>,
+    interval: Duration,
+    token: CancellationToken,
+) {
+    if servers.is_empty() {
+        warn!("Clock-Skew-Monitor => keine NTP-Server konfiguriert");
+        return;
+    }
+    let mut tick = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = tick.tick() => match measure_ntp_offset_ms(&servers).await {
+                Ok(off) => {
+                    guard.record_offset(off);
+                    // HLC-Zeitstempel folgen derselben korrigierten Uhr
+                    set_time_offset_ms(off);
+                    if off.unsigned_abs() > guard.max_skew_ms() {
+                        warn!("Lokale Uhr weicht {}ms von NTP ab (max {}ms)", off, guard.max_skew_ms());
+                    } else {
+                        info!("NTP-Offset => {}ms", off);
+                    }
+                }
+                Err(e) => warn!("NTP-Messung fehlgeschlagen: {:?}", e),
+            },
+        }
+    }
+}
+
+// ----------------------------------------------------------------------
+// GeoIP
+// ----------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GeoInfo {
+    /// ISO-3166 Alpha-2, "??" falls unbekannt
+    pub country: String,
+    /// Autonomous System Number, 0 falls unbekannt
+    pub asn: u32,
+}
+
+impl GeoInfo {
+    pub fn unknown() -> Self {
+        Self { country: "??".into(), asn: 0 }
+    }
+}
+
+pub trait GeoIpResolver: Send + Sync {
+    fn lookup(&self, ip: IpAddr) -> Option<GeoInfo>;
+}
+
+/// Lookup über MaxMind-DBs (mmdb).
+pub struct MaxMindResolver {
+    country_db: maxminddb::Reader<Vec<u8>>,
+    asn_db: Option<maxminddb::Reader<Vec<u8>>>,
+}
+
+impl MaxMindResolver {
+    pub fn open(country_db_path: &str, asn_db_path: Option<&str>) -> Result<Self> {
+        let country_db = maxminddb::Reader::open_readfile(country_db_path)
+            .map_err(|e| anyhow!("Cannot open GeoIP DB {}: {:?}", country_db_path, e))?;
+        let asn_db = match asn_db_path {
+            Some(p) => Some(
+                maxminddb::Reader::open_readfile(p).map_err(|e| anyhow!("Cannot open ASN DB {}: {:?}", p, e))?,
+            ),
+            None => None,
+        };
+        Ok(Self { country_db, asn_db })
+    }
+}
+
+impl GeoIpResolver for MaxMindResolver {
+    fn lookup(&self, ip: IpAddr) -> Option<GeoInfo> {
+        let country = self
+            .country_db
+            .lookup::<maxminddb::geoip2::Country>(ip)
+            .ok()
+            .and_then(|c| c.country)
+            .and_then(|c| c.iso_code)
+            .map(|s| s.to_string())?;
+        let asn = self
+            .asn_db
+            .as_ref()
+            .and_then(|db| db.lookup::<maxminddb::geoip2::Asn>(ip).ok())
+            .and_then(|a| a.autonomous_system_number)
+            .unwrap_or(0);
+        Some(GeoInfo { country, asn })
+    }
+}
+
+/// Feste Zuordnung (Tests, statische Seed-Listen).
+#[derive(Default)]
+pub struct StaticGeoResolver {
+    pub entries: HashMap<IpAddr, GeoInfo>,
+}
+
+impl StaticGeoResolver {
+    pub fn insert(&mut self, ip: IpAddr, country: &str, asn: u32) {
+        self.entries.insert(ip, GeoInfo { country: country.into(), asn });
+    }
+}
+
+impl GeoIpResolver for StaticGeoResolver {
+    fn lookup(&self, ip: IpAddr) -> Option<GeoInfo> {
+        self.entries.get(&ip).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_far_future_timestamp_rejected() {
+        let guard = ClockSkewGuard::new(2_000).with_max_age_ms(5_000);
+        let now = 1_700_000_000_000;
+        let err = guard.check_timestamp_ms_at(now + 3_600_000, now).unwrap_err();
+        assert!(matches!(err, DexError::ClockSkew { .. }));
+        assert!(guard.check_timestamp_ms_at(now - 10_000, now).is_err());
+    }
+
+    #[test]
+    fn test_delayed_timestamp_accepted_within_max_age() {
+        let guard = ClockSkewGuard::new(2_000).with_max_age_ms(60_000);
+        let now = 1_700_000_000_000;
+        guard.check_timestamp_ms_at(now - 30_000, now).unwrap();
+        assert!(guard.check_timestamp_ms_at(now + 3_000, now).is_err());
+        // Nur-Zukunft-Prüfung lässt beliebig alte Zeitstempel durch
+        guard.check_not_future_ms_at(now - 3_600_000, now).unwrap();
+        assert!(guard.check_not_future_ms_at(now + 3_000, now).is_err());
+    }
+
+    #[test]
+    fn test_within_tolerance_accepted() {
+        let guard = ClockSkewGuard::new(2_000);
+        let now = 1_700_000_000_000;
+        guard.check_timestamp_ms_at(now + 1_500, now).unwrap();
+        guard.check_timestamp_ms_at(now - 2_000, now).unwrap();
+    }
+
+    #[test]
+    fn test_local_skew_blocks_production() {
+        let guard = ClockSkewGuard::new(1_000);
+        assert!(guard.ensure_local_clock_ok().is_err());
+        guard.record_offset(400);
+        guard.ensure_local_clock_ok().unwrap();
+        guard.record_offset(-5_000);
+        assert!(guard.ensure_local_clock_ok().is_err());
+    }
+}
@@ -8,9 +8,11 @@
 //
 // (c) Ihr DEX-Projekt
 
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tokio::task;
 use tokio::time::{timeout, Duration};
 use anyhow::{Result, anyhow};
@@ -18,8 +20,10 @@ use sntpc::{self, NtpTimestampGenerator};
 use tracing::{info, debug, warn};
 
 // ----------------------------------------------------------------------
-// GLOBAL_TIME_CACHE => z. B. aggregated NTP time in ms
-pub static GLOBAL_TIME_CACHE: OnceCell<u64> = OnceCell::new();
+// GLOBAL_TIME_OFFSET_MS => aggregierter NTP-Offset (NTP-Zeit minus Systemuhr).
+// Gespeichert wird der Offset, nicht ein Zeitpunkt: die Uhr läuft weiter und
+// wird bei jeder neuen Messung nachgeführt.
+pub static GLOBAL_TIME_OFFSET_MS: AtomicI64 = AtomicI64::new(0);
 
 #[derive(Debug, Clone)]
 pub struct HlcState {
@@ -36,6 +40,113 @@ impl Default for HlcState {
     }
 }
 
+// ----------------------------------------------------------------------
+// HLC-Zeitstempel + Uhr (Kulkarni et al., "Logical Physical Clocks")
+//
+// Ordnung: (physical_ms, logical, node) lexikographisch => total und auf
+// allen Nodes identisch. `node` ist ein stabiler Hash der Node-ID und
+// bricht nur echte Gleichstände. Was kausal nach einem Event passiert
+// (lokal oder nach Empfang), bekommt immer einen größeren Zeitstempel,
+// auch wenn die Wanduhr des Empfängers nachgeht.
+// ----------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct HlcTimestamp {
+    pub physical_ms: u64,
+    pub logical: u64,
+    pub node: u64,
+}
+
+/// Stabiler 64-Bit-Tag einer Node-ID (erste 8 Bytes von SHA-256).
+pub fn node_tag(node_id: &str) -> u64 {
+    let digest = Sha256::digest(node_id.as_bytes());
+    let mut b = [0u8; 8];
+    b.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(b)
+}
+
+/// Unkorrigierte Systemuhr in ms.
+pub fn system_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH).unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Lokale physische Zeit in ms (Systemuhr + NTP-Offset).
+pub fn local_physical_ms() -> u64 {
+    (system_ms() as i64 + GLOBAL_TIME_OFFSET_MS.load(Ordering::Relaxed)).max(0) as u64
+}
+
+/// Neuer NTP-Offset (ms) für `local_physical_ms`.
+pub fn set_time_offset_ms(offset_ms: i64) {
+    GLOBAL_TIME_OFFSET_MS.store(offset_ms, Ordering::Relaxed);
+    debug!("GLOBAL_TIME_OFFSET_MS updated => {}", offset_ms);
+}
+
+#[derive(Debug, Clone)]
+pub struct HybridLogicalClock {
+    pub state: HlcState,
+    pub node: u64,
+}
+
+impl HybridLogicalClock {
+    pub fn new(node_id: &str) -> Self {
+        Self { state: HlcState::default(), node: node_tag(node_id) }
+    }
+
+    /// Lokales Event / Senden.
+    pub fn tick(&mut self) -> HlcTimestamp {
+        self.tick_at(local_physical_ms())
+    }
+
+    pub fn tick_at(&mut self, physical_ms: u64) -> HlcTimestamp {
+        let last = self.state.last_physical_ms;
+        self.state.last_physical_ms = last.max(physical_ms);
+        if self.state.last_physical_ms == last {
+            self.state.logical_clock += 1;
+        } else {
+            self.state.logical_clock = 0;
+        }
+        self.current()
+    }
+
+    /// Empfang eines Zeitstempels => Uhr vorziehen, Rückgabe ist der Empfangs-Zeitstempel.
+    pub fn update(&mut self, remote: HlcTimestamp) -> HlcTimestamp {
+        self.update_at(remote, local_physical_ms())
+    }
+
+    pub fn update_at(&mut self, remote: HlcTimestamp, physical_ms: u64) -> HlcTimestamp {
+        let last = self.state.last_physical_ms;
+        let l = last.max(remote.physical_ms).max(physical_ms);
+        self.state.logical_clock = if l == last && l == remote.physical_ms {
+            self.state.logical_clock.max(remote.logical) + 1
+        } else if l == last {
+            self.state.logical_clock + 1
+        } else if l == remote.physical_ms {
+            remote.logical + 1
+        } else {
+            0
+        };
+        self.state.last_physical_ms = l;
+        self.current()
+    }
+
+    /// Letzter vergebener Zeitstempel (ohne die Uhr weiterzustellen).
+    pub fn current(&self) -> HlcTimestamp {
+        HlcTimestamp {
+            physical_ms: self.state.last_physical_ms,
+            logical: self.state.logical_clock,
+            node: self.node,
+        }
+    }
+}
+
+impl Default for HybridLogicalClock {
+    fn default() -> Self {
+        Self::new("local")
+    }
+}
+
 // ----------------------------------------------------------------------
 // Weltweite NTP-Pools nach Kontinent, plus globale Pools.
 // Alle "pub static" => extern zugreifbar
@@ -184,14 +295,7 @@ pub fn aggregate_time(timestamps: &[u64]) -> u64 {
 
 /// HLC-Update
 pub fn update_hlc(logical_clock: &mut u64, last_physical_ms: &mut u64, remote_time: u64) {
-    let local_ms = if let Some(ntp_ms) = GLOBAL_TIME_CACHE.get() {
-        *ntp_ms
-    } else {
-        let st = SystemTime::now()
-            .duration_since(UNIX_EPOCH).unwrap_or_default()
-            .as_millis() as u64;
-        st
-    };
+    let local_ms = local_physical_ms();
 
     let physical_now = std::cmp::max(local_ms, remote_time);
 
@@ -204,11 +308,46 @@ pub fn update_hlc(logical_clock: &mut u64, last_physical_ms: &mut u64, remote_ti
     *last_physical_ms = new_ts;
 }
 
-/// Schreibt new_time in die GLOBAL_TIME_CACHE
+/// Übernimmt eine aggregierte NTP-Zeit (ms) als Offset zur Systemuhr.
 pub fn update_cache(new_time_ms: u64) {
-    // ignore error if already set
-    GLOBAL_TIME_CACHE.set(new_time_ms).ok();
-    debug!("GLOBAL_TIME_CACHE updated => {}", new_time_ms);
+    set_time_offset_ms(new_time_ms as i64 - system_ms() as i64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ntp_time_keeps_running() {
+        // Offset ~0, damit parallele Tests nicht verschoben werden
+        update_cache(system_ms());
+        let first = local_physical_ms();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert!(local_physical_ms() > first);
+    }
+
+    #[test]
+    fn test_receive_orders_after_send_despite_slow_clock() {
+        let mut a = HybridLogicalClock::new("A");
+        let mut b = HybridLogicalClock::new("B");
+        let sent = a.tick_at(10_000);
+        // B's Wanduhr geht 5s nach
+        let recv = b.update_at(sent, 5_000);
+        assert!(recv > sent);
+        let next = b.tick_at(5_001);
+        assert!(next > recv);
+        assert_eq!(next.physical_ms, 10_000);
+    }
+
+    #[test]
+    fn test_tick_monotonic_without_physical_progress() {
+        let mut c = HybridLogicalClock::new("A");
+        let t1 = c.tick_at(1_000);
+        let t2 = c.tick_at(1_000);
+        let t3 = c.tick_at(999);
+        assert!(t1 < t2 && t2 < t3);
+        assert_eq!(c.tick_at(2_000).logical, 0);
+    }
 }
 
 // Ende hlc.rs
@@ -0,0 +1,69 @@
+//////////////////////////////////////////
+// my_DEX/src/utils/lock.rs
+//////////////////////////////////////////
+//
+// Mutex-Zugriff, der Poisoning übersteht.
+//
+// Panict ein Task, während er einen std-Mutex hält, ist der Mutex danach
+// "poisoned" und jedes weitere `.lock().unwrap()` panict ebenfalls – ein
+// lokaler Fehler legt so den ganzen Node lahm. Für geteilten Zustand, der
+// auch nach einem abgebrochenen Zugriff konsistent genug ist (Peer-Map,
+// Routing-Tabelle, DB-Handle), übernehmen wir den Inhalt und heben das
+// Poisoning auf. Wo ein halb geschriebener Zustand nicht weiterverwendet
+// werden darf, liefert `lock_or_err` stattdessen `DexError::LockPoisoned`.
+//////////////////////////////////////////
+
+use std::sync::{Mutex, MutexGuard};
+use tracing::warn;
+
+use crate::error::DexError;
+
+pub trait LockRecover<T> {
+    /// Sperrt den Mutex; ist er poisoned, wird der Inhalt übernommen und
+    /// das Poisoning aufgehoben.
+    fn lock_recover(&self) -> MutexGuard<'_, T>;
+
+    /// Sperrt den Mutex; Poisoning => `DexError::LockPoisoned(what)`.
+    fn lock_or_err(&self, what: &str) -> Result<MutexGuard<'_, T>, DexError>;
+}
+
+impl<T> LockRecover<T> for Mutex<T> {
+    fn lock_recover(&self) -> MutexGuard<'_, T> {
+        match self.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned (Panic in anderem Task) => Zustand wird übernommen");
+                let guard = poisoned.into_inner();
+                self.clear_poison();
+                guard
+            }
+        }
+    }
+
+    fn lock_or_err(&self, what: &str) -> Result<MutexGuard<'_, T>, DexError> {
+        self.lock().map_err(|_| DexError::LockPoisoned(what.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_recover_clears_poison_but_lock_or_err_reports_it() {
+        let m = Arc::new(Mutex::new(1u32));
+        let m2 = m.clone();
+        let _ = std::thread::spawn(move || {
+            let _g = m2.lock().unwrap();
+            panic!("Test-Panic mit gehaltenem Lock");
+        })
+        .join();
+        assert!(m.is_poisoned());
+        assert!(matches!(m.lock_or_err("counter"), Err(DexError::LockPoisoned(w)) if w == "counter"));
+
+        *m.lock_recover() += 1;
+        assert!(!m.is_poisoned());
+        assert_eq!(*m.lock_or_err("counter").unwrap(), 2);
+    }
+}
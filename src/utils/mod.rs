@@ -8,4 +8,8 @@
 // (c) Ihr DEX-Projekt
 
 pub mod hlc;
+pub mod geoip_and_ntp;
 pub mod aesgcm_utils;
+pub mod canonical;
+pub mod clock;
+pub mod lock;